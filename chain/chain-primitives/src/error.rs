@@ -1,5 +1,6 @@
 use std::io;
 
+use borsh::{BorshDeserialize, BorshSerialize};
 use chrono::DateTime;
 use near_primitives::time::Utc;
 
@@ -10,7 +11,7 @@ use near_primitives::shard_layout::ShardLayoutError;
 use near_primitives::sharding::{ChunkHash, ShardChunkHeader};
 use near_primitives::types::{BlockHeight, EpochId, ShardId};
 
-#[derive(thiserror::Error, Debug)]
+#[derive(thiserror::Error, BorshSerialize, BorshDeserialize, Debug, Clone, PartialEq, Eq)]
 pub enum QueryError {
     #[error("Account ID {requested_account_id} is invalid")]
     InvalidAccount {
@@ -143,6 +144,12 @@ pub enum Error {
     /// Invalid epoch hash
     #[error("Invalid Epoch Hash")]
     InvalidEpochHash,
+    /// Epoch sync data doesn't match `epoch_sync_data_hash` of the block header.
+    #[error("Invalid Epoch Sync Data")]
+    InvalidEpochSyncData,
+    /// Epoch sync proof doesn't follow the previously verified epoch.
+    #[error("Invalid Epoch Sync Proof")]
+    InvalidEpochSyncProof,
     /// `next_bps_hash` doens't correspond to the actual next block producers set
     #[error("Invalid Next BP Hash")]
     InvalidNextBPHash,
@@ -274,6 +281,8 @@ impl Error {
             | Error::MaliciousChallenge
             | Error::IncorrectNumberOfChunkHeaders
             | Error::InvalidEpochHash
+            | Error::InvalidEpochSyncData
+            | Error::InvalidEpochSyncProof
             | Error::InvalidNextBPHash
             | Error::NotEnoughApprovals
             | Error::InvalidFinalityInfo
@@ -0,0 +1,148 @@
+//! Blocks which finished applying chunks and wait to be postprocessed.
+//!
+//! Chunks of blocks are applied in the rayon thread pool, which sends the
+//! results back to the chain.  Results can arrive in bursts, for example when
+//! a batch of blocks is received after a network hiccup.  Postprocessing all
+//! of them at once then holds the client up, so the chain postprocesses a
+//! bounded number of blocks at a time and picks them in the following order:
+//!
+//! * Blocks extending the current head come first, since accepting them is
+//!   what moves the head.
+//! * Other blocks, such as blocks on forks, follow in the order their chunks
+//!   finished being applied.  So that they aren't starved by a steady stream
+//!   of blocks extending the head, for example while the node catches up with
+//!   the network, one of them is picked after every `MAX_HEAD_BLOCKS_IN_A_ROW`
+//!   blocks extending the head.
+use std::time::Instant;
+
+use crossbeam_channel::{unbounded, Receiver, Sender};
+use near_primitives::hash::CryptoHash;
+use near_primitives::time::Clock;
+
+use crate::chain::ApplyChunkResult;
+use crate::Error;
+
+/// Maximum number of blocks extending the head postprocessed in a row while
+/// other blocks are waiting.
+const MAX_HEAD_BLOCKS_IN_A_ROW: usize = 4;
+
+pub(crate) struct AppliedBlock {
+    pub(crate) block_hash: CryptoHash,
+    pub(crate) apply_results: Vec<Result<ApplyChunkResult, Error>>,
+    /// When applying the chunks finished.
+    pub(crate) applied_at: Instant,
+}
+
+impl AppliedBlock {
+    pub(crate) fn new(
+        block_hash: CryptoHash,
+        apply_results: Vec<Result<ApplyChunkResult, Error>>,
+    ) -> Self {
+        Self { block_hash, apply_results, applied_at: Clock::instant() }
+    }
+}
+
+pub(crate) struct ApplyChunksQueue {
+    /// Used by the threads applying chunks to send the results back.
+    sender: Sender<AppliedBlock>,
+    receiver: Receiver<AppliedBlock>,
+    /// Blocks received from the threads applying chunks, in the order they
+    /// were received.
+    ready: Vec<AppliedBlock>,
+    /// Number of blocks extending the head picked in a row while other blocks
+    /// were waiting.
+    head_blocks_in_a_row: usize,
+}
+
+impl ApplyChunksQueue {
+    pub(crate) fn new() -> Self {
+        // Even though the channel is unbounded, its size is practically bounded
+        // by the number of blocks in processing.
+        let (sender, receiver) = unbounded();
+        Self { sender, receiver, ready: vec![], head_blocks_in_a_row: 0 }
+    }
+
+    pub(crate) fn sender(&self) -> Sender<AppliedBlock> {
+        self.sender.clone()
+    }
+
+    /// Number of blocks ready to be postprocessed.
+    pub(crate) fn len(&mut self) -> usize {
+        self.receive();
+        self.ready.len()
+    }
+
+    /// Returns the block to postprocess next and whether it extends the head,
+    /// as told by `extends_head`.
+    pub(crate) fn pop(
+        &mut self,
+        extends_head: impl Fn(&CryptoHash) -> bool,
+    ) -> Option<(AppliedBlock, bool)> {
+        self.receive();
+        let head_block = self.ready.iter().position(|block| extends_head(&block.block_hash));
+        let other_block = self.ready.iter().position(|block| !extends_head(&block.block_hash));
+        let (index, is_head_block) = match (head_block, other_block) {
+            (None, None) => return None,
+            (Some(index), None) => {
+                self.head_blocks_in_a_row = 0;
+                (index, true)
+            }
+            (Some(index), Some(_)) if self.head_blocks_in_a_row < MAX_HEAD_BLOCKS_IN_A_ROW => {
+                self.head_blocks_in_a_row += 1;
+                (index, true)
+            }
+            (_, Some(index)) => {
+                self.head_blocks_in_a_row = 0;
+                (index, false)
+            }
+        };
+        Some((self.ready.remove(index), is_head_block))
+    }
+
+    fn receive(&mut self) {
+        self.ready.extend(self.receiver.try_iter());
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{AppliedBlock, ApplyChunksQueue, MAX_HEAD_BLOCKS_IN_A_ROW};
+    use near_primitives::hash::CryptoHash;
+
+    #[test]
+    fn test_apply_chunks_queue_order() {
+        let hash = |i: u8| CryptoHash::hash_bytes(&[i]);
+        let mut queue = ApplyChunksQueue::new();
+        let sender = queue.sender();
+        assert!(queue.pop(|_| true).is_none());
+
+        // Blocks extending the head are picked first, the others in the order
+        // they were applied.
+        for i in 0..3 {
+            sender.send(AppliedBlock::new(hash(i), vec![])).unwrap();
+        }
+        assert_eq!(queue.len(), 3);
+        let head = hash(2);
+        let mut order = vec![];
+        while let Some((block, is_head_block)) = queue.pop(|block_hash| *block_hash == head) {
+            assert_eq!(is_head_block, block.block_hash == head);
+            order.push(block.block_hash);
+        }
+        assert_eq!(order, vec![hash(2), hash(0), hash(1)]);
+
+        // Another block gets its turn after enough blocks extending the head.
+        sender.send(AppliedBlock::new(hash(0), vec![])).unwrap();
+        for i in 1..=MAX_HEAD_BLOCKS_IN_A_ROW + 1 {
+            sender.send(AppliedBlock::new(hash(i as u8), vec![])).unwrap();
+        }
+        let mut order = vec![];
+        while let Some((block, _)) = queue.pop(|block_hash| *block_hash != hash(0)) {
+            order.push(block.block_hash);
+        }
+        let mut expected: Vec<_> = (1..=MAX_HEAD_BLOCKS_IN_A_ROW).map(|i| hash(i as u8)).collect();
+        expected.push(hash(0));
+        expected.push(hash(MAX_HEAD_BLOCKS_IN_A_ROW as u8 + 1));
+        assert_eq!(order, expected);
+        assert_eq!(queue.len(), 0);
+    }
+}
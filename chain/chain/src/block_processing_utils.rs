@@ -29,6 +29,9 @@ pub(crate) struct BlockPreprocessInfo {
     pub(crate) apply_chunks_done: Arc<OnceCell<()>>,
     /// This is used to calculate block processing time metric
     pub(crate) block_start_processing_time: Instant,
+    /// When the preparation of applying the chunks started, which is when the
+    /// `ApplyScheduling` stage starts.
+    pub(crate) apply_scheduling_start: Instant,
 }
 
 /// Blocks which finished pre-processing and are now being applied asynchronously
@@ -124,6 +127,14 @@ impl BlocksInProcessing {
         }
     }
 
+    /// Returns true if the block is in the pool and its previous block is
+    /// `prev_hash`.
+    pub(crate) fn has_prev_hash(&self, block_hash: &CryptoHash, prev_hash: &CryptoHash) -> bool {
+        self.preprocessed_blocks
+            .get(block_hash)
+            .map_or(false, |(block, _)| block.header().prev_hash() == prev_hash)
+    }
+
     pub(crate) fn has_blocks_to_catch_up(&self, prev_hash: &CryptoHash) -> bool {
         self.preprocessed_blocks
             .iter()
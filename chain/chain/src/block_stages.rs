@@ -0,0 +1,113 @@
+//! Durations of the stages of block processing.
+//!
+//! The total time it takes to process a block doesn't tell which part of the
+//! processing got slower.  Every stage a block goes through is therefore timed
+//! separately and reported under the `stage` label of
+//! `near_block_processing_stage_time`.  Each stage has a soft budget: a stage
+//! taking longer than its budget logs a warning with the block and the time it
+//! took, but the processing carries on as usual.
+use std::time::Duration;
+
+use near_chain_configs::BlockStageBudgets;
+use near_primitives::hash::CryptoHash;
+use near_primitives::types::BlockHeight;
+use tracing::warn;
+
+use crate::metrics::ChainMetrics;
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, strum::IntoStaticStr)]
+#[strum(serialize_all = "snake_case")]
+pub enum BlockStage {
+    /// Checks of the block header and of the block itself against its
+    /// previous block.
+    HeaderVerification,
+    /// Checks that the chunks of the block are known and valid.
+    ChunksCheck,
+    /// Preparation of applying the chunks and waiting for a thread to apply
+    /// them.
+    ApplyScheduling,
+    /// Applying the chunks.
+    Apply,
+    /// Storing the results of applying the chunks and updating the head.
+    Postprocess,
+    /// Garbage collection run after the block is accepted.
+    GarbageCollection,
+    /// Sending the new chain info to the network.
+    NetworkInfoUpdate,
+}
+
+impl BlockStage {
+    fn budget(self, budgets: &BlockStageBudgets) -> Option<Duration> {
+        match self {
+            BlockStage::HeaderVerification => budgets.header_verification,
+            BlockStage::ChunksCheck => budgets.chunks_check,
+            BlockStage::ApplyScheduling => budgets.apply_scheduling,
+            BlockStage::Apply => budgets.apply,
+            BlockStage::Postprocess => budgets.postprocess,
+            BlockStage::GarbageCollection => budgets.garbage_collection,
+            BlockStage::NetworkInfoUpdate => budgets.network_info_update,
+        }
+    }
+}
+
+/// Records that `stage` of processing the block took `elapsed`.
+pub(crate) fn record_block_stage(
+    metrics: &ChainMetrics,
+    budgets: &BlockStageBudgets,
+    stage: BlockStage,
+    block_hash: &CryptoHash,
+    block_height: BlockHeight,
+    elapsed: Duration,
+) {
+    let label: &'static str = stage.into();
+    metrics.block_stage_time.with_label_values(&[label]).observe(elapsed.as_secs_f64());
+    if let Some(budget) = stage.budget(budgets) {
+        if elapsed > budget {
+            metrics.block_stage_over_budget_total.with_label_values(&[label]).inc();
+            warn!(
+                target: "chain",
+                stage = label,
+                ?elapsed,
+                ?budget,
+                %block_hash,
+                block_height,
+                "Block processing stage exceeded its budget"
+            );
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{record_block_stage, BlockStage};
+    use crate::metrics::ChainMetrics;
+    use near_chain_configs::BlockStageBudgets;
+    use near_o11y::metrics::MetricsRegistry;
+    use near_primitives::hash::CryptoHash;
+    use std::time::Duration;
+
+    #[test]
+    fn test_record_block_stage() {
+        let metrics = MetricsRegistry::instance("test_record_block_stage").get::<ChainMetrics>();
+        let budgets = BlockStageBudgets {
+            apply: Some(Duration::from_millis(100)),
+            garbage_collection: None,
+            ..BlockStageBudgets::default()
+        };
+        let record = |stage, elapsed| {
+            record_block_stage(&metrics, &budgets, stage, &CryptoHash::default(), 1, elapsed)
+        };
+        record(BlockStage::Apply, Duration::from_millis(50));
+        record(BlockStage::Apply, Duration::from_millis(150));
+        record(BlockStage::GarbageCollection, Duration::from_secs(100));
+
+        let over_budget =
+            |label| metrics.block_stage_over_budget_total.with_label_values(&[label]).get();
+        assert_eq!(over_budget("apply"), 1);
+        assert_eq!(over_budget("garbage_collection"), 0);
+        let count = |label| metrics.block_stage_time.with_label_values(&[label]).get_sample_count();
+        assert_eq!(count("apply"), 2);
+        assert_eq!(count("garbage_collection"), 1);
+        assert_eq!(count("header_verification"), 0);
+    }
+}
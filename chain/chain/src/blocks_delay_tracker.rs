@@ -16,7 +16,8 @@ use std::mem;
 use std::time::Instant;
 use tracing::error;
 
-use crate::{metrics, Chain, ChainStoreAccess, RuntimeAdapter};
+use crate::metrics::ChainMetrics;
+use crate::{Chain, ChainStoreAccess, RuntimeAdapter};
 
 const BLOCK_DELAY_TRACKING_COUNT: u64 = 50;
 
@@ -298,18 +299,23 @@ impl BlocksDelayTracker {
         }
     }
 
-    pub fn finish_block_processing(&mut self, block_hash: &CryptoHash, new_head: Option<Tip>) {
+    pub fn finish_block_processing(
+        &mut self,
+        block_hash: &CryptoHash,
+        new_head: Option<Tip>,
+        metrics: &ChainMetrics,
+    ) {
         if let Some(processed_block) = self.blocks.get_mut(&block_hash) {
             processed_block.processed_timestamp = Some(Clock::instant());
         }
         // To get around the rust reference scope check
         if let Some(processed_block) = self.blocks.get(&block_hash) {
             let chunks = processed_block.chunks.clone();
-            self.update_block_metrics(processed_block);
+            self.update_block_metrics(processed_block, metrics);
             for (shard_id, chunk_hash) in chunks.into_iter().enumerate() {
                 if let Some(chunk_hash) = chunk_hash {
                     if let Some(processed_chunk) = self.chunks.get(&chunk_hash) {
-                        self.update_chunk_metrics(processed_chunk, shard_id as ShardId);
+                        self.update_chunk_metrics(processed_chunk, shard_id as ShardId, metrics);
                     }
                 }
             }
@@ -319,31 +325,39 @@ impl BlocksDelayTracker {
         }
     }
 
-    fn update_block_metrics(&self, block: &BlockTrackingStats) {
+    fn update_block_metrics(&self, block: &BlockTrackingStats, metrics: &ChainMetrics) {
         if let Some(start) = block.orphaned_timestamp {
             if let Some(end) = block.removed_from_orphan_timestamp {
-                metrics::BLOCK_ORPHANED_DELAY
+                metrics
+                    .block_orphaned_delay
                     .observe(end.saturating_duration_since(start).as_secs_f64());
             }
         } else {
-            metrics::BLOCK_ORPHANED_DELAY.observe(0.);
+            metrics.block_orphaned_delay.observe(0.);
         }
         if let Some(start) = block.missing_chunks_timestamp {
             if let Some(end) = block.removed_from_missing_chunks_timestamp {
-                metrics::BLOCK_MISSING_CHUNKS_DELAY
+                metrics
+                    .block_missing_chunks_delay
                     .observe(end.saturating_duration_since(start).as_secs_f64());
             }
         } else {
-            metrics::BLOCK_MISSING_CHUNKS_DELAY.observe(0.);
+            metrics.block_missing_chunks_delay.observe(0.);
         }
     }
 
-    fn update_chunk_metrics(&self, chunk: &ChunkTrackingStats, shard_id: ShardId) {
+    fn update_chunk_metrics(
+        &self,
+        chunk: &ChunkTrackingStats,
+        shard_id: ShardId,
+        metrics: &ChainMetrics,
+    ) {
         if let Some(chunk_requested) = chunk.requested_timestamp {
             // Theoretically chunk_received should have been set here because a block being processed
             // requires all chunks to be received
             if let Some(chunk_received) = chunk.completed_timestamp {
-                metrics::CHUNK_RECEIVED_DELAY
+                metrics
+                    .chunk_received_delay
                     .with_label_values(&[&shard_id.to_string()])
                     .observe((chunk_received - chunk_requested).num_milliseconds() as f64 / 1000.);
             }
@@ -407,6 +421,7 @@ impl BlocksDelayTracker {
                 block_status,
                 missing_chunks_ms,
                 chunks_info,
+                missing_ancestor: None,
             }
         })
     }
@@ -480,9 +495,39 @@ impl Chain {
             num_blocks_missing_chunks: self.blocks_with_missing_chunks_len(),
             blocks_info,
             floating_chunks_info,
+            orphans_info: self.get_orphans_info(),
         }
     }
 
+    fn get_orphans_info(&self) -> Vec<BlockProcessingInfo> {
+        let mut orphans_info = vec![];
+        self.orphans().map(&mut |hash, block, added| {
+            let height = block.header().height();
+            let orphaned = Clock::instant().saturating_duration_since(*added);
+            // Stats of old blocks may have been pruned from the tracker already.
+            let mut info = self
+                .blocks_delay_tracker
+                .get_block_processing_info(height, hash, self, &*self.runtime_adapter)
+                .unwrap_or_else(|| BlockProcessingInfo {
+                    height,
+                    hash: *hash,
+                    received_timestamp: Clock::utc()
+                        - chrono::Duration::from_std(orphaned)
+                            .unwrap_or_else(|_| chrono::Duration::zero()),
+                    in_progress_ms: orphaned.as_millis(),
+                    orphaned_ms: Some(orphaned.as_millis()),
+                    missing_chunks_ms: None,
+                    block_status: BlockProcessingStatus::Orphan,
+                    chunks_info: vec![],
+                    missing_ancestor: None,
+                });
+            info.missing_ancestor = self.orphans().missing_ancestor(hash);
+            orphans_info.push(info);
+        });
+        orphans_info.sort_by(|a, b| b.height.cmp(&a.height));
+        orphans_info
+    }
+
     pub fn print_chain_processing_info_to_string(
         &self,
         log_summary_style: LogSummaryStyle,
@@ -1,5 +1,6 @@
 use std::collections::{HashMap, HashSet};
 
+use std::path::PathBuf;
 use std::sync::Arc;
 use std::time::{Duration as TimeDuration, Instant};
 
@@ -7,6 +8,7 @@ use borsh::BorshSerialize;
 use chrono::Duration;
 use itertools::Itertools;
 use near_o11y::log_assert;
+use near_o11y::metrics::MetricsRegistry;
 use near_primitives::sandbox::state_patch::SandboxStatePatch;
 use near_primitives::time::Clock;
 use rand::seq::SliceRandom;
@@ -14,13 +16,17 @@ use rand::SeedableRng;
 use rand_chacha::ChaCha20Rng;
 use tracing::{debug, error, info, warn, Span};
 
+use near_chain_configs::{BlockStageBudgets, MIN_GC_NUM_EPOCHS_TO_KEEP};
 use near_chain_primitives::error::{BlockKnownError, Error, LogTransientStorageError};
 use near_primitives::block::{genesis_chunks, Tip};
+use near_primitives::block_header::{Approval, ApprovalInner};
 use near_primitives::challenge::{
     BlockDoubleSign, Challenge, ChallengeBody, ChallengesResult, ChunkProofs, ChunkState,
     MaybeEncodedShardChunk, PartialState, SlashedValidator,
 };
 use near_primitives::checked_feature;
+use near_primitives::epoch_manager::block_info::BlockInfo;
+use near_primitives::epoch_manager::epoch_info::EpochInfo;
 use near_primitives::hash::{hash, CryptoHash};
 use near_primitives::merkle::{
     combine_hash, merklize, verify_path, Direction, MerklePath, MerklePathItem, PartialMerkleTree,
@@ -32,37 +38,50 @@ use near_primitives::sharding::{
 };
 use near_primitives::state_part::PartId;
 use near_primitives::syncing::{
-    get_num_state_parts, ReceiptProofResponse, RootProof, ShardStateSyncResponseHeader,
-    ShardStateSyncResponseHeaderV1, ShardStateSyncResponseHeaderV2, StateHeaderKey, StatePartKey,
+    get_num_state_parts, EpochSyncData, EpochSyncProof, ReceiptProofResponse, RootProof,
+    ShardStateSyncResponseHeader, ShardStateSyncResponseHeaderV1, ShardStateSyncResponseHeaderV2,
+    StateHeaderKey, StatePartKey,
 };
 use near_primitives::transaction::{
     ExecutionOutcomeWithId, ExecutionOutcomeWithIdAndProof, SignedTransaction,
 };
 use near_primitives::types::chunk_extra::ChunkExtra;
+use near_primitives::types::validator_stake::ValidatorStake;
 use near_primitives::types::{
-    AccountId, Balance, BlockExtra, BlockHeight, BlockHeightDelta, EpochId, Gas, MerkleHash,
-    NumBlocks, NumShards, ShardId, StateChangesForSplitStates, StateRoot,
+    AccountId, Balance, BlockExtra, BlockHeight, BlockHeightDelta, EpochId, Finality, Gas,
+    MerkleHash, NumBlocks, NumShards, ShardId, StateChangesForSplitStates, StateRoot,
 };
 use near_primitives::unwrap_or_return;
 use near_primitives::utils::MaybeValidated;
 use near_primitives::views::{
-    BlockStatusView, DroppedReason, ExecutionOutcomeWithIdView, ExecutionStatusView,
-    FinalExecutionOutcomeView, FinalExecutionOutcomeWithReceiptView, FinalExecutionStatus,
-    LightClientBlockView, SignedTransactionView,
+    BlockStatusView, DroppedReason, ExecutionOutcomeProofView, ExecutionOutcomeWithIdView,
+    ExecutionStatusView, FinalExecutionOutcomeView, FinalExecutionOutcomeWithReceiptView,
+    FinalExecutionStatus, LightClientBlockView, ReceiptExecutionProofView,
+    ReceiptInclusionProofView, SignedTransactionView,
 };
 #[cfg(feature = "protocol_feature_flat_state")]
 use near_store::{flat_state, StorageError};
-use near_store::{DBCol, ShardTries, StoreUpdate, WrappedTrieChanges};
+use near_store::{DBCol, ShardTries, Store, StoreUpdate, WrappedTrieChanges};
 
+use crate::apply_chunks_queue::{AppliedBlock, ApplyChunksQueue};
 use crate::block_processing_utils::{
     BlockPreprocessInfo, BlockProcessingArtifact, BlocksInProcessing, DoneApplyChunkCallback,
 };
+use crate::block_stages::{record_block_stage, BlockStage};
 use crate::blocks_delay_tracker::BlocksDelayTracker;
+use crate::contract_code_index::ContractCodeIndex;
+use crate::contract_events::ContractEventsIndex;
 use crate::crypto_hash_timer::CryptoHashTimer;
 use crate::flat_storage_creator::FlatStorageCreator;
 use crate::lightclient::get_epoch_block_producers_view;
+use crate::metrics::{self, ChainMetrics};
 use crate::migrations::check_if_block_is_first_with_chunk_of_version;
 use crate::missing_chunks::{BlockLike, MissingChunksPool};
+use crate::outcome_compaction;
+use crate::persisted_orphans::{self, PERSISTED_ORPHANS_HORIZON};
+use crate::recently_processed::RecentlyProcessed;
+use crate::shard_readiness::ShardReadiness;
+use crate::state_mismatch::StateMismatchBundles;
 use crate::store::{ChainStore, ChainStoreAccess, ChainStoreUpdate, GCMode};
 use crate::types::{
     AcceptedBlock, ApplySplitStateResult, ApplySplitStateResultOrStateChanges,
@@ -73,16 +92,15 @@ use crate::validate::{
     validate_challenge, validate_chunk_proofs, validate_chunk_with_chunk_extra,
     validate_transactions_order,
 };
+use crate::DoomslugThresholdMode;
 use crate::{byzantine_assert, create_light_client_block_view, Doomslug};
-use crate::{metrics, DoomslugThresholdMode};
 use actix::Message;
-use crossbeam_channel::{unbounded, Receiver, Sender};
 use delay_detector::DelayDetector;
-use near_client_primitives::types::StateSplitApplyingStatus;
+use near_client_primitives::types::{StatePartsApplyingStatus, StateSplitApplyingStatus};
 use near_primitives::shard_layout::{
     account_id_to_shard_id, account_id_to_shard_uid, ShardLayout, ShardUId,
 };
-use near_primitives::version::PROTOCOL_VERSION;
+use near_primitives::version::{ProtocolVersion, Version, PROTOCOL_VERSION};
 use near_store::flat_state::FlatStorageError;
 #[cfg(feature = "protocol_feature_flat_state")]
 use near_store::flat_state::{store_helper, FlatStateDelta};
@@ -121,6 +139,14 @@ const ACCEPTABLE_TIME_DIFFERENCE: i64 = 12 * 10;
 /// Over this block height delta in advance if we are not chunk producer - route tx to upcoming validators.
 pub const TX_ROUTING_HEIGHT_HORIZON: BlockHeightDelta = 4;
 
+/// Maximum number of epoch proofs sent in response to a single request.
+pub const MAX_EPOCH_SYNC_PROOFS: usize = 16;
+
+/// Maximum number of blocks, ending with the block a receipt was executed in,
+/// whose incoming receipts are searched for the receipt.  Receipts are
+/// executed in the block they are received in, unless delayed by congestion.
+const MAX_RECEIPT_PROOF_SEARCH_BLOCKS: usize = 1000;
+
 /// Private constant for 1 NEAR (copy from near/config.rs) used for reporting.
 const NEAR_BASE: Balance = 1_000_000_000_000_000_000_000_000;
 
@@ -167,8 +193,9 @@ impl Orphan {
 /// A block is removed from the pool if
 /// 1) it is ready to be processed
 /// or
-/// 2) size of the pool exceeds MAX_ORPHAN_SIZE and the orphan was added a long time ago
-///    or the height is high
+/// 2) size of the pool exceeds MAX_ORPHAN_SIZE and the orphan was added more than
+///    MAX_ORPHAN_AGE_SECS ago, or the pool is still full and the orphan is the furthest
+///    from the head, or the oldest of the orphans furthest from the head
 pub struct OrphanBlockPool {
     /// A map from block hash to a orphan block
     orphans: HashMap<CryptoHash, Orphan>,
@@ -185,16 +212,18 @@ pub struct OrphanBlockPool {
     prev_hash_idx: HashMap<CryptoHash, Vec<CryptoHash>>,
     /// number of orphans that were evicted
     evicted: usize,
+    metrics: Arc<ChainMetrics>,
 }
 
 impl OrphanBlockPool {
-    pub fn new() -> OrphanBlockPool {
+    pub fn new(metrics: Arc<ChainMetrics>) -> OrphanBlockPool {
         OrphanBlockPool {
             orphans: HashMap::default(),
             orphans_requested_missing_chunks: HashSet::default(),
             height_idx: HashMap::default(),
             prev_hash_idx: HashMap::default(),
             evicted: 0,
+            metrics,
         }
     }
 
@@ -208,7 +237,14 @@ impl OrphanBlockPool {
 
     /// Add a block to the orphan pool
     /// `requested_missing_chunks`: whether missing chunks has been requested for the orphan
-    fn add(&mut self, orphan: Orphan, requested_missing_chunks: bool) {
+    /// Returns the hashes of the orphans evicted to make room for the block, which can include
+    /// the block itself.
+    fn add(
+        &mut self,
+        orphan: Orphan,
+        requested_missing_chunks: bool,
+        head_height: BlockHeight,
+    ) -> Vec<CryptoHash> {
         let block_hash = *orphan.block.hash();
         let height_hashes = self.height_idx.entry(orphan.block.header().height()).or_default();
         height_hashes.push(*orphan.block.hash());
@@ -220,38 +256,60 @@ impl OrphanBlockPool {
             self.orphans_requested_missing_chunks.insert(block_hash);
         }
 
+        let mut evicted = vec![];
         if self.orphans.len() > MAX_ORPHAN_SIZE {
-            let old_len = self.orphans.len();
-
-            let mut removed_hashes: HashSet<CryptoHash> = HashSet::default();
-            self.orphans.retain(|_, ref mut x| {
-                let keep = x.added.elapsed() < TimeDuration::from_secs(MAX_ORPHAN_AGE_SECS);
-                if !keep {
-                    removed_hashes.insert(*x.block.hash());
-                }
-                keep
-            });
-            let mut heights = self.height_idx.keys().cloned().collect::<Vec<u64>>();
-            heights.sort_unstable();
-            for h in heights.iter().rev() {
-                if let Some(hash) = self.height_idx.remove(h) {
-                    for h in hash {
-                        let _ = self.orphans.remove(&h);
-                        removed_hashes.insert(h);
-                    }
-                }
-                if self.orphans.len() < MAX_ORPHAN_SIZE {
-                    break;
-                }
+            let max_age = TimeDuration::from_secs(MAX_ORPHAN_AGE_SECS);
+            evicted = self
+                .orphans
+                .values()
+                .filter(|orphan| orphan.added.elapsed() >= max_age)
+                .map(|orphan| orphan.hash())
+                .collect();
+            for hash in &evicted {
+                self.remove(hash);
             }
-            self.height_idx.retain(|_, ref mut xs| xs.iter().any(|x| !removed_hashes.contains(x)));
-            self.prev_hash_idx
-                .retain(|_, ref mut xs| xs.iter().any(|x| !removed_hashes.contains(x)));
-            self.orphans_requested_missing_chunks.retain(|x| !removed_hashes.contains(x));
+        }
+        if self.orphans.len() > MAX_ORPHAN_SIZE {
+            // Orphans far from the head are the least likely to be adopted soon.
+            let mut candidates = self
+                .orphans
+                .values()
+                .map(|orphan| (orphan.height().abs_diff(head_height), orphan.added, orphan.hash()))
+                .collect::<Vec<_>>();
+            candidates.sort_by(|a, b| b.0.cmp(&a.0).then(a.1.cmp(&b.1)));
+            let furthest = candidates
+                .into_iter()
+                .take(self.orphans.len() - MAX_ORPHAN_SIZE)
+                .map(|(_, _, hash)| hash)
+                .collect::<Vec<_>>();
+            for hash in &furthest {
+                self.remove(hash);
+            }
+            evicted.extend(furthest);
+        }
+        self.evicted += evicted.len();
+        self.metrics.num_orphans.set(self.orphans.len() as i64);
+        evicted
+    }
 
-            self.evicted += old_len - self.orphans.len();
+    fn remove(&mut self, hash: &CryptoHash) {
+        let orphan = match self.orphans.remove(hash) {
+            Some(orphan) => orphan,
+            None => return,
+        };
+        self.orphans_requested_missing_chunks.remove(hash);
+        if let Some(hashes) = self.height_idx.get_mut(&orphan.height()) {
+            hashes.retain(|h| h != hash);
+            if hashes.is_empty() {
+                self.height_idx.remove(&orphan.height());
+            }
+        }
+        if let Some(hashes) = self.prev_hash_idx.get_mut(orphan.prev_hash()) {
+            hashes.retain(|h| h != hash);
+            if hashes.is_empty() {
+                self.prev_hash_idx.remove(orphan.prev_hash());
+            }
         }
-        metrics::NUM_ORPHANS.set(self.orphans.len() as i64);
     }
 
     pub fn contains(&self, hash: &CryptoHash) -> bool {
@@ -262,6 +320,20 @@ impl OrphanBlockPool {
         self.orphans.get(hash)
     }
 
+    /// Returns the hash of the block the orphan is waiting for, that is the
+    /// parent of its oldest ancestor in the pool.
+    pub fn missing_ancestor(&self, hash: &CryptoHash) -> Option<CryptoHash> {
+        let mut prev_hash = *self.orphans.get(hash)?.block.header().prev_hash();
+        // Orphans can't form a cycle, the bound is only a safeguard.
+        for _ in 0..self.orphans.len() {
+            match self.orphans.get(&prev_hash) {
+                Some(orphan) => prev_hash = *orphan.block.header().prev_hash(),
+                None => break,
+            }
+        }
+        Some(prev_hash)
+    }
+
     // Iterates over existing orphans.
     pub fn map(&self, orphan_fn: &mut dyn FnMut(&CryptoHash, &Block, &Instant)) {
         self.orphans
@@ -288,7 +360,7 @@ impl OrphanBlockPool {
 
         self.height_idx.retain(|_, ref mut xs| xs.iter().any(|x| !removed_hashes.contains(x)));
 
-        metrics::NUM_ORPHANS.set(self.orphans.len() as i64);
+        self.metrics.num_orphans.set(self.orphans.len() as i64);
         ret
     }
 
@@ -416,7 +488,27 @@ pub fn check_known(
     check_known_store(chain, block_hash)
 }
 
-type BlockApplyChunksResult = (CryptoHash, Vec<Result<ApplyChunkResult, Error>>);
+/// Reason why [`Chain::clear_archive_data`] stopped before the GC stop height.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ArchiveGCBlocker {
+    /// Cold storage is in use but no block has been migrated to it yet.
+    ColdHeadMissing,
+    /// Heights above the cold head haven’t been migrated to cold storage yet.
+    ColdHeadBehind { cold_head_height: BlockHeight, gc_stop_height: BlockHeight },
+}
+
+impl std::fmt::Display for ArchiveGCBlocker {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::ColdHeadMissing => write!(f, "no blocks have been migrated to cold storage"),
+            Self::ColdHeadBehind { cold_head_height, gc_stop_height } => write!(
+                f,
+                "cold head at #{} is behind gc stop height #{}",
+                cold_head_height, gc_stop_height
+            ),
+        }
+    }
+}
 
 /// Facade to the blockchain block processing and storage.
 /// Provides current view on the state according to the chain state.
@@ -437,14 +529,32 @@ pub struct Chain {
     /// `blocks_in_processing` keeps track of all the blocks that have been preprocessed but are
     /// waiting for chunks being applied.
     pub(crate) blocks_in_processing: BlocksInProcessing,
-    /// Used by async_apply_chunks to send apply chunks results back to chain
-    apply_chunks_sender: Sender<BlockApplyChunksResult>,
-    /// Used to receive apply chunks results
-    apply_chunks_receiver: Receiver<BlockApplyChunksResult>,
+    /// Blocks which finished applying chunks, sent back by async_apply_chunks.
+    apply_chunks_queue: ApplyChunksQueue,
+    /// Maximum number of blocks postprocessed by a single call to
+    /// `postprocess_ready_blocks`.
+    max_postprocessed_blocks_per_step: usize,
     /// Time when head was updated most recently.
     last_time_head_updated: Instant,
+    /// Exponential moving average of the time it takes to process a block.
+    average_block_processing_time: Option<TimeDuration>,
+    /// Shards whose state for the next epoch is being caught up.  Blocks are
+    /// applied as caught up only once all of them are ready.
+    pub(crate) shard_readiness: ShardReadiness,
+    /// Blocks processed most recently, which survive restarts.
+    recently_processed: RecentlyProcessed,
+    /// Whether orphans close to the head are saved, see `persisted_orphans`.
+    persist_orphans: bool,
+    /// Index of events emitted by contracts, if enabled.
+    contract_events: Option<Arc<ContractEventsIndex>>,
+    /// Index of the accounts contracts are deployed to, if enabled.
+    contract_code_index: Option<ContractCodeIndex>,
+    /// Soft budgets of the stages of block processing.
+    block_stage_budgets: BlockStageBudgets,
     /// Used when it is needed to create flat storage in background for some shards.
     flat_storage_creator: Option<FlatStorageCreator>,
+    /// Saves forensic bundles of state root mismatches, if enabled.
+    state_mismatch_bundles: Option<StateMismatchBundles>,
 
     /// Support for sandbox's patch_state requests.
     ///
@@ -459,6 +569,7 @@ pub struct Chain {
     /// was empty and could not hold any records (which it cannot).  It’s
     /// impossible to have non-empty state patch on non-sandbox builds.
     pending_state_patch: SandboxStatePatch,
+    metrics: Arc<ChainMetrics>,
 }
 
 impl Drop for Chain {
@@ -495,20 +606,25 @@ impl Chain {
         ))
     }
 
+    /// Creates the chain of a view client, which reads the chain data from
+    /// `store`.  With split storage that is the store which falls back to cold
+    /// storage, so that the view client serves data garbage collected in hot
+    /// storage.
     pub fn new_for_view_client(
         runtime_adapter: Arc<dyn RuntimeAdapter>,
+        store: Store,
         chain_genesis: &ChainGenesis,
         doomslug_threshold_mode: DoomslugThresholdMode,
         save_trie_changes: bool,
+        metrics: &MetricsRegistry,
     ) -> Result<Chain, Error> {
-        let (store, _) = runtime_adapter.genesis_state();
+        let metrics = metrics.get::<ChainMetrics>();
         let store = ChainStore::new(store, chain_genesis.height, save_trie_changes);
         let genesis = Self::make_genesis_block(&*runtime_adapter, chain_genesis)?;
-        let (sc, rc) = unbounded();
         Ok(Chain {
             store,
             runtime_adapter,
-            orphans: OrphanBlockPool::new(),
+            orphans: OrphanBlockPool::new(metrics.clone()),
             blocks_with_missing_chunks: MissingChunksPool::new(),
             blocks_in_processing: BlocksInProcessing::new(),
             genesis,
@@ -517,11 +633,20 @@ impl Chain {
             block_economics_config: BlockEconomicsConfig::from(chain_genesis),
             doomslug_threshold_mode,
             blocks_delay_tracker: BlocksDelayTracker::default(),
-            apply_chunks_sender: sc,
-            apply_chunks_receiver: rc,
+            apply_chunks_queue: ApplyChunksQueue::new(),
+            max_postprocessed_blocks_per_step: usize::MAX,
             last_time_head_updated: Clock::instant(),
+            average_block_processing_time: None,
+            shard_readiness: ShardReadiness::default(),
+            recently_processed: RecentlyProcessed::default(),
+            persist_orphans: false,
+            contract_events: None,
+            contract_code_index: None,
+            state_mismatch_bundles: None,
+            block_stage_budgets: BlockStageBudgets::default(),
             flat_storage_creator: None,
             pending_state_patch: Default::default(),
+            metrics,
         })
     }
 
@@ -530,7 +655,9 @@ impl Chain {
         chain_genesis: &ChainGenesis,
         doomslug_threshold_mode: DoomslugThresholdMode,
         save_trie_changes: bool,
+        metrics: &MetricsRegistry,
     ) -> Result<Chain, Error> {
+        let metrics = metrics.get::<ChainMetrics>();
         // Get runtime initial state and create genesis block out of it.
         let (store, state_roots) = runtime_adapter.genesis_state();
         let mut store = ChainStore::new(store, chain_genesis.height, save_trie_changes);
@@ -642,23 +769,32 @@ impl Chain {
         info!(target: "chain", "Init: header head @ #{} {}; block head @ #{} {}",
               header_head.height, header_head.last_block_hash,
               block_head.height, block_head.last_block_hash);
-        metrics::BLOCK_HEIGHT_HEAD.set(block_head.height as i64);
+        metrics.block_height_head.set(block_head.height as i64);
         let block_header = store.get_block_header(&block_head.last_block_hash)?;
-        metrics::BLOCK_ORDINAL_HEAD.set(block_header.block_ordinal() as i64);
-        metrics::HEADER_HEAD_HEIGHT.set(header_head.height as i64);
-        metrics::BOOT_TIME_SECONDS.set(Clock::utc().timestamp());
+        metrics.block_ordinal_head.set(block_header.block_ordinal() as i64);
+        metrics.header_head_height.set(header_head.height as i64);
+        metrics.boot_time_seconds.set(Clock::utc().timestamp());
+
+        metrics.tail_height.set(store.tail()? as i64);
+        metrics.chunk_tail_height.set(store.chunk_tail()? as i64);
+        metrics.fork_tail_height.set(store.fork_tail()? as i64);
+
+        // Catchups interrupted by a restart are resumed, so their shards are still pending.
+        let mut shard_readiness = ShardReadiness::default();
+        for (sync_hash, state_sync_info) in store.iterate_state_sync_infos()? {
+            let epoch_id = store.get_block_header(&sync_hash)?.epoch_id().clone();
+            shard_readiness.mark_pending(
+                epoch_id,
+                state_sync_info.shards.iter().map(|ShardInfo(shard_id, _)| *shard_id),
+            );
+        }
 
-        metrics::TAIL_HEIGHT.set(store.tail()? as i64);
-        metrics::CHUNK_TAIL_HEIGHT.set(store.chunk_tail()? as i64);
-        metrics::FORK_TAIL_HEIGHT.set(store.fork_tail()? as i64);
+        let recently_processed = RecentlyProcessed::load(store.store())?;
 
-        // Even though the channel is unbounded, the channel size is practically bounded by the size
-        // of blocks_in_processing, which is set to 5 now.
-        let (sc, rc) = unbounded();
         Ok(Chain {
             store,
             runtime_adapter,
-            orphans: OrphanBlockPool::new(),
+            orphans: OrphanBlockPool::new(metrics.clone()),
             blocks_with_missing_chunks: MissingChunksPool::new(),
             blocks_in_processing: BlocksInProcessing::new(),
             genesis: genesis.clone(),
@@ -667,14 +803,170 @@ impl Chain {
             block_economics_config: BlockEconomicsConfig::from(chain_genesis),
             doomslug_threshold_mode,
             blocks_delay_tracker: BlocksDelayTracker::default(),
-            apply_chunks_sender: sc,
-            apply_chunks_receiver: rc,
+            apply_chunks_queue: ApplyChunksQueue::new(),
+            max_postprocessed_blocks_per_step: usize::MAX,
             last_time_head_updated: Clock::instant(),
+            average_block_processing_time: None,
+            shard_readiness,
+            recently_processed,
+            persist_orphans: false,
+            contract_events: None,
+            contract_code_index: None,
+            state_mismatch_bundles: None,
+            block_stage_budgets: BlockStageBudgets::default(),
             flat_storage_creator,
             pending_state_patch: Default::default(),
+            metrics,
         })
     }
 
+    /// Blocks processed most recently, including before the node was
+    /// restarted.
+    pub fn recently_processed(&self) -> &RecentlyProcessed {
+        &self.recently_processed
+    }
+
+    /// Starts saving orphans close to the head, and puts the ones saved
+    /// before the node was restarted back into the orphan pool.  Saved
+    /// orphans which were processed or fell too far behind the head in the
+    /// meantime are dropped.  The ones whose previous block was processed
+    /// wouldn't be adopted from the pool anymore, so they are returned to be
+    /// processed as any other block instead.
+    pub fn enable_orphan_persistence(&mut self) -> Result<Vec<Block>, Error> {
+        self.persist_orphans = true;
+        let head_height = self.head()?.height;
+        let tail_height = self.store.tail()?;
+        let mut store_update = self.store.store().store_update();
+        store_update.set_tag("orphans");
+        let mut num_restored = 0;
+        let mut ready = vec![];
+        for block in persisted_orphans::load(self.store.store())? {
+            let height = block.header().height();
+            if height < tail_height
+                || height.abs_diff(head_height) > PERSISTED_ORPHANS_HORIZON
+                || self.block_exists(block.hash())?
+            {
+                persisted_orphans::remove(&mut store_update, block.hash());
+                continue;
+            }
+            if self.block_exists(block.header().prev_hash())? {
+                // Saved again by `add_orphan` if it turns out to be an orphan
+                // after all.
+                persisted_orphans::remove(&mut store_update, block.hash());
+                ready.push(block);
+                continue;
+            }
+            let orphan = Orphan {
+                block: block.into(),
+                provenance: Provenance::NONE,
+                added: Clock::instant(),
+            };
+            for hash in self.orphans.add(orphan, false, head_height) {
+                persisted_orphans::remove(&mut store_update, &hash);
+            }
+            num_restored += 1;
+        }
+        store_update.commit()?;
+        info!(
+            target: "chain",
+            num_restored,
+            num_ready = ready.len(),
+            "Restored orphans saved before the restart"
+        );
+        Ok(ready)
+    }
+
+    /// Adds the block to the orphan pool, and saves it if it's close to the
+    /// head and orphans are persisted.
+    fn add_orphan(&mut self, orphan: Orphan, requested_missing_chunks: bool) -> Result<(), Error> {
+        let head_height = self.head()?.height;
+        if !self.persist_orphans {
+            self.orphans.add(orphan, requested_missing_chunks, head_height);
+            return Ok(());
+        }
+        let mut store_update = self.store.store().store_update();
+        store_update.set_tag("orphans");
+        if orphan.height().abs_diff(head_height) <= PERSISTED_ORPHANS_HORIZON {
+            persisted_orphans::save(&mut store_update, orphan.block.get_inner())?;
+        }
+        for hash in self.orphans.add(orphan, requested_missing_chunks, head_height) {
+            persisted_orphans::remove(&mut store_update, &hash);
+        }
+        store_update.commit()?;
+        Ok(())
+    }
+
+    /// Starts indexing events emitted by contracts in processed blocks.
+    pub fn enable_contract_events_index(&mut self) {
+        let index = ContractEventsIndex::new(self.store.store().clone());
+        self.contract_events = Some(Arc::new(index));
+    }
+
+    /// Index of events emitted by contracts, if enabled.
+    pub fn contract_events_index(&self) -> Option<&Arc<ContractEventsIndex>> {
+        self.contract_events.as_ref()
+    }
+
+    /// Starts indexing the accounts contracts are deployed to in processed
+    /// blocks.
+    pub fn enable_contract_code_index(&mut self) {
+        self.contract_code_index = Some(ContractCodeIndex::new(self.store.store().clone()));
+    }
+
+    /// Saves a forensic bundle into `dir` whenever a chunk disagrees with the
+    /// state root computed for its shard, see `state_mismatch`.
+    pub fn enable_state_mismatch_bundles(&mut self, dir: PathBuf, node_version: Version) {
+        self.state_mismatch_bundles = Some(StateMismatchBundles::new(dir, node_version));
+    }
+
+    pub fn set_block_stage_budgets(&mut self, budgets: BlockStageBudgets) {
+        self.block_stage_budgets = budgets;
+    }
+
+    /// Limits the number of blocks postprocessed by a single call to
+    /// `postprocess_ready_blocks`, so that blocks finishing applying chunks
+    /// at once are postprocessed in between other work.  The caller has to
+    /// keep calling it until no blocks are left, for example once for every
+    /// call of the `DoneApplyChunkCallback`.
+    pub fn set_max_postprocessed_blocks_per_step(&mut self, max_blocks: usize) {
+        self.max_postprocessed_blocks_per_step = max_blocks.max(1);
+    }
+
+    /// Records that `stage` of processing the block took `elapsed`, warning
+    /// if it went over its budget.
+    pub fn record_block_stage(
+        &self,
+        stage: BlockStage,
+        block_hash: &CryptoHash,
+        block_height: BlockHeight,
+        elapsed: TimeDuration,
+    ) {
+        record_block_stage(
+            &self.metrics,
+            &self.block_stage_budgets,
+            stage,
+            block_hash,
+            block_height,
+            elapsed,
+        );
+    }
+
+    /// Records a processed block, persisting the record right away.
+    fn record_processed_block(&mut self, block: &Block) -> Result<(), Error> {
+        let height = block.header().height();
+        let chunk_hashes = block
+            .chunks()
+            .iter()
+            .filter(|chunk_header| chunk_header.height_included() == height)
+            .map(|chunk_header| chunk_header.chunk_hash())
+            .collect();
+        self.recently_processed.add_block(*block.hash(), chunk_hashes);
+        let mut store_update = self.store.store().store_update();
+        self.recently_processed.save(&mut store_update)?;
+        store_update.commit()?;
+        Ok(())
+    }
+
     #[cfg(feature = "test_features")]
     pub fn adv_disable_doomslug(&mut self) {
         self.doomslug_threshold_mode = DoomslugThresholdMode::NoApprovals
@@ -688,12 +980,19 @@ impl Chain {
     ) -> Result<CryptoHash, Error> {
         let bps = runtime_adapter.get_epoch_block_producers_ordered(&epoch_id, last_known_hash)?;
         let protocol_version = runtime_adapter.get_epoch_protocol_version(&prev_epoch_id)?;
+        Ok(Self::compute_bp_hash_from_stakes(bps.into_iter().map(|(bp, _)| bp), protocol_version))
+    }
+
+    /// Hashes the block producers set the way it's stored in `next_bp_hash`
+    /// of headers of blocks produced at `protocol_version`.
+    fn compute_bp_hash_from_stakes(
+        bps: impl Iterator<Item = ValidatorStake>,
+        protocol_version: ProtocolVersion,
+    ) -> CryptoHash {
         if checked_feature!("stable", BlockHeaderV3, protocol_version) {
-            let validator_stakes = bps.into_iter().map(|(bp, _)| bp);
-            Ok(CryptoHash::hash_borsh_iter(validator_stakes))
+            CryptoHash::hash_borsh_iter(bps)
         } else {
-            let validator_stakes = bps.into_iter().map(|(bp, _)| bp.into_v1());
-            Ok(CryptoHash::hash_borsh_iter(validator_stakes))
+            CryptoHash::hash_borsh_iter(bps.map(|bp| bp.into_v1()))
         }
     }
 
@@ -701,6 +1000,13 @@ impl Chain {
         self.last_time_head_updated
     }
 
+    /// Returns the moving average of the time it takes to process a block,
+    /// weighing recent blocks the most, or None if no block has been processed
+    /// since the start.
+    pub fn average_block_processing_time(&self) -> Option<TimeDuration> {
+        self.average_block_processing_time
+    }
+
     /// Creates a light client block for the last final block from perspective of some other block
     ///
     /// # Arguments
@@ -768,11 +1074,10 @@ impl Chain {
             byzantine_assert!(false);
             return Err(e);
         }
-        self.orphans.add(
+        self.add_orphan(
             Orphan { block, provenance: Provenance::NONE, added: Clock::instant() },
             requested_missing_chunks,
-        );
-        Ok(())
+        )
     }
 
     fn save_block_height_processed(&mut self, block_height: BlockHeight) -> Result<(), Error> {
@@ -865,20 +1170,29 @@ impl Chain {
         gc_config: &near_chain_configs::GCConfig,
     ) -> Result<(), Error> {
         let _d = DelayDetector::new(|| "GC".into());
+        let _gc_lock = outcome_compaction::lock_gc();
 
         let head = self.store.head()?;
         let tail = self.store.tail()?;
-        let gc_stop_height = self.runtime_adapter.get_gc_stop_height(&head.last_block_hash);
+        let mut gc_stop_height = self.runtime_adapter.get_gc_stop_height(&head.last_block_hash);
+        // Close to the disk budget fewer epochs are kept than configured.
+        let gc_num_epochs_to_keep = near_store::disk_budget()
+            .gc_num_epochs_to_keep(gc_config.gc_num_epochs_to_keep(), MIN_GC_NUM_EPOCHS_TO_KEEP);
+        if gc_num_epochs_to_keep < gc_config.gc_num_epochs_to_keep() {
+            gc_stop_height = gc_stop_height.max(
+                self.get_epoch_start_height_back(&head.last_block_hash, gc_num_epochs_to_keep)?,
+            );
+        }
         if gc_stop_height > head.height {
             return Err(Error::GCError("gc_stop_height cannot be larger than head.height".into()));
         }
         let prev_epoch_id = self.get_block_header(&head.prev_block_hash)?.epoch_id().clone();
         let epoch_change = prev_epoch_id != head.epoch_id;
         let mut fork_tail = self.store.fork_tail()?;
-        metrics::TAIL_HEIGHT.set(tail as i64);
-        metrics::FORK_TAIL_HEIGHT.set(fork_tail as i64);
-        metrics::CHUNK_TAIL_HEIGHT.set(self.store.chunk_tail()? as i64);
-        metrics::GC_STOP_HEIGHT.set(gc_stop_height as i64);
+        self.metrics.tail_height.set(tail as i64);
+        self.metrics.fork_tail_height.set(fork_tail as i64);
+        self.metrics.chunk_tail_height.set(self.store.chunk_tail()? as i64);
+        self.metrics.gc_stop_height.set(gc_stop_height as i64);
         if epoch_change && fork_tail < gc_stop_height {
             // if head doesn't change on the epoch boundary, we may update fork tail several times
             // but that is fine since it doesn't affect correctness and also we limit the number of
@@ -949,20 +1263,43 @@ impl Chain {
     /// storage, archival nodes do garbage collect that data.
     ///
     /// `gc_height_limit` limits how many heights will the function process.
-    pub fn clear_archive_data(&mut self, gc_height_limit: BlockHeightDelta) -> Result<(), Error> {
+    ///
+    /// If `cold_store` is set, the node keeps its archive in cold storage and
+    /// only heights at or below the cold head (see [`ChainStore::cold_head`])
+    /// are collected.  In that case the returned [`ArchiveGCBlocker`] tells
+    /// why garbage collection didn’t reach the GC stop height.
+    pub fn clear_archive_data(
+        &mut self,
+        gc_height_limit: BlockHeightDelta,
+        cold_store: bool,
+    ) -> Result<Option<ArchiveGCBlocker>, Error> {
         let _d = DelayDetector::new(|| "GC".into());
 
         let head = self.store.head()?;
-        let gc_stop_height = self.runtime_adapter.get_gc_stop_height(&head.last_block_hash);
+        let mut gc_stop_height = self.runtime_adapter.get_gc_stop_height(&head.last_block_hash);
         if gc_stop_height > head.height {
             return Err(Error::GCError("gc_stop_height cannot be larger than head.height".into()));
         }
+        self.metrics.gc_stop_height.set(gc_stop_height as i64);
+
+        let mut blocker = None;
+        if cold_store {
+            match self.store.cold_head()? {
+                None => return Ok(Some(ArchiveGCBlocker::ColdHeadMissing)),
+                Some(cold_head_height) if cold_head_height + 1 < gc_stop_height => {
+                    blocker =
+                        Some(ArchiveGCBlocker::ColdHeadBehind { cold_head_height, gc_stop_height });
+                    gc_stop_height = cold_head_height + 1;
+                }
+                Some(_) => {}
+            }
+        }
 
         let mut chain_store_update = self.store.store_update();
         chain_store_update.clear_redundant_chunk_data(gc_stop_height, gc_height_limit)?;
-        metrics::CHUNK_TAIL_HEIGHT.set(chain_store_update.chunk_tail()? as i64);
-        metrics::GC_STOP_HEIGHT.set(gc_stop_height as i64);
-        chain_store_update.commit()
+        self.metrics.chunk_tail_height.set(chain_store_update.chunk_tail()? as i64);
+        chain_store_update.commit()?;
+        Ok(blocker)
     }
 
     pub fn clear_forks_data(
@@ -1153,6 +1490,34 @@ impl Chain {
         Ok(())
     }
 
+    /// Registers BLS keys carried by the approvals aggregate of the header.  The approvers may
+    /// be validators of either this or the next epoch.
+    #[cfg(feature = "protocol_feature_bls_approvals")]
+    fn register_bls_keys(&self, header: &BlockHeader) -> Result<(), Error> {
+        let approvals_aggregate = match header.approvals_aggregate() {
+            Some(approvals_aggregate) => approvals_aggregate,
+            None => return Ok(()),
+        };
+        for registration in &approvals_aggregate.registrations {
+            let is_valid =
+                [header.epoch_id(), header.next_epoch_id()].into_iter().any(|epoch_id| {
+                    matches!(
+                        self.runtime_adapter.verify_bls_key_registration(
+                            epoch_id,
+                            header.prev_hash(),
+                            registration
+                        ),
+                        Ok(true)
+                    )
+                });
+            if !is_valid {
+                return Err(Error::InvalidApprovals);
+            }
+            self.runtime_adapter.register_bls_key(registration)?;
+        }
+        Ok(())
+    }
+
     /// Validate header. Returns error if the header is invalid.
     /// `challenges`: the function will add new challenges generated from validating this header
     ///               to the vector. You can pass an empty vector here, or a vector with existing
@@ -1264,6 +1629,9 @@ impl Chain {
         if header.raw_timestamp() <= prev_header.raw_timestamp() {
             return Err(Error::InvalidBlockPastTime(prev_header.timestamp(), header.timestamp()));
         }
+        #[cfg(feature = "protocol_feature_bls_approvals")]
+        self.register_bls_keys(header)?;
+
         // If this is not the block we produced (hence trust in it) - validates block
         // producer, confirmation signatures and finality info.
         if *provenance != Provenance::PRODUCED {
@@ -1276,6 +1644,18 @@ impl Chain {
             )? {
                 return Err(Error::InvalidApprovals);
             };
+            #[cfg(feature = "protocol_feature_bls_approvals")]
+            if let Some(approvals_aggregate) = header.approvals_aggregate() {
+                if !self.runtime_adapter.verify_approvals_aggregate(
+                    prev_header.hash(),
+                    prev_header.height(),
+                    header.height(),
+                    header.approvals(),
+                    approvals_aggregate,
+                )? {
+                    return Err(Error::InvalidApprovals);
+                }
+            }
 
             let stakes = self
                 .runtime_adapter
@@ -1283,9 +1663,9 @@ impl Chain {
                 .iter()
                 .map(|(x, is_slashed)| (x.stake_this_epoch, x.stake_next_epoch, *is_slashed))
                 .collect::<Vec<_>>();
-            if !Doomslug::can_approved_block_be_produced(
+            if !Doomslug::can_block_with_approvers_be_produced(
                 self.doomslug_threshold_mode,
-                header.approvals(),
+                &header.approvers_mask(),
                 &stakes,
             ) {
                 return Err(Error::NotEnoughApprovals);
@@ -1344,6 +1724,45 @@ impl Chain {
         Ok(())
     }
 
+    /// Validates a block produced by this node the same way a block received
+    /// from the network is validated, without storing or applying it.  Block
+    /// production reads state which can change concurrently, so this is the
+    /// last check before the block is published.
+    pub fn validate_produced_block(&self, block: &Block) -> Result<(), Error> {
+        let header = block.header();
+        if block.chunks().len() != self.runtime_adapter.num_shards(header.epoch_id())? as usize {
+            return Err(Error::IncorrectNumberOfChunkHeaders);
+        }
+        // Challenges are only generated for double signing, which can't happen
+        // since the block isn't stored yet.
+        self.validate_header(header, &Provenance::NONE, &mut vec![])?;
+
+        let prev_block = self.get_block(header.prev_hash())?;
+        self.runtime_adapter.verify_block_vrf(
+            header.epoch_id(),
+            header.height(),
+            prev_block.header().random_value(),
+            block.vrf_value(),
+            block.vrf_proof(),
+        )?;
+        if header.random_value() != &hash(block.vrf_value().0.as_ref()) {
+            return Err(Error::InvalidRandomnessBeaconOutput);
+        }
+        Chain::validate_block_impl(self.runtime_adapter.as_ref(), &self.genesis, block)?;
+
+        let protocol_version =
+            self.runtime_adapter.get_epoch_protocol_version(header.epoch_id())?;
+        if !block.verify_gas_price(
+            prev_block.header().gas_price(),
+            self.block_economics_config.min_gas_price(protocol_version),
+            self.block_economics_config.max_gas_price(protocol_version),
+            self.block_economics_config.gas_price_adjustment_rate(protocol_version),
+        ) {
+            return Err(Error::InvalidGasPrice);
+        }
+        self.validate_chunk_headers(block, &prev_block)
+    }
+
     /// Verify that `challenges` are valid
     /// If all challenges are valid, returns ChallengesResult, which comprises of the list of
     /// validators that need to be slashed and the list of blocks that are challenged.
@@ -1622,7 +2041,7 @@ impl Chain {
         apply_chunks_done_callback: DoneApplyChunkCallback,
     ) -> Result<(), Error> {
         let block_received_time = Clock::instant();
-        metrics::BLOCK_PROCESSING_ATTEMPTS_TOTAL.inc();
+        self.metrics.block_processing_attempts_total.inc();
 
         let block_height = block.header().height();
         let hash = *block.hash();
@@ -1655,6 +2074,8 @@ impl Chain {
     /// If there are no blocks that are ready to be postprocessed, it returns immediately
     /// with an empty list. Even if there are blocks being processed, it does not wait
     /// for these blocks to be ready.
+    /// At most `max_postprocessed_blocks_per_step` blocks are postprocessed, blocks extending
+    /// the head first, see `ApplyChunksQueue`.
     pub fn postprocess_ready_blocks(
         &mut self,
         me: &Option<AccountId>,
@@ -1663,11 +2084,26 @@ impl Chain {
     ) -> (Vec<AcceptedBlock>, HashMap<CryptoHash, Error>) {
         let mut accepted_blocks = vec![];
         let mut errors = HashMap::new();
-        while let Ok((block_hash, apply_result)) = self.apply_chunks_receiver.try_recv() {
+        for _ in 0..self.max_postprocessed_blocks_per_step {
+            // The head moves as blocks are postprocessed.
+            let head = self.head().map(|tip| tip.last_block_hash).ok();
+            let blocks_in_processing = &self.blocks_in_processing;
+            let next = self.apply_chunks_queue.pop(|block_hash| {
+                head.map_or(false, |head| blocks_in_processing.has_prev_hash(block_hash, &head))
+            });
+            let (applied_block, is_head_block) = match next {
+                Some(next) => next,
+                None => break,
+            };
+            let AppliedBlock { block_hash, apply_results, applied_at } = applied_block;
+            self.metrics
+                .apply_chunks_completion_delay
+                .with_label_values(&[if is_head_block { "head" } else { "other" }])
+                .observe(Clock::instant().saturating_duration_since(applied_at).as_secs_f64());
             match self.postprocess_block(
                 me,
                 block_hash,
-                apply_result,
+                apply_results,
                 block_processing_artifacts,
                 apply_chunks_done_callback.clone(),
             ) {
@@ -1679,6 +2115,7 @@ impl Chain {
                 }
             }
         }
+        self.metrics.apply_chunks_ready_blocks.set(self.apply_chunks_queue.len() as i64);
         (accepted_blocks, errors)
     }
 
@@ -1763,7 +2200,7 @@ impl Chain {
     ///
     /// This is done by fetching header by height and checking that it’s the
     /// same one as provided.
-    fn is_on_current_chain(&self, header: &BlockHeader) -> Result<bool, Error> {
+    pub fn is_on_current_chain(&self, header: &BlockHeader) -> Result<bool, Error> {
         let chain_header = self.get_block_header_by_height(header.height())?;
         Ok(chain_header.hash() == header.hash())
     }
@@ -1806,6 +2243,7 @@ impl Chain {
 
     pub fn reset_data_pre_state_sync(&mut self, sync_hash: CryptoHash) -> Result<(), Error> {
         let _span = tracing::debug_span!(target: "sync", "reset_data_pre_state_sync").entered();
+        let _gc_lock = outcome_compaction::lock_gc();
         let head = self.head()?;
         // Get header we were syncing into.
         let header = self.get_block_header(&sync_hash)?;
@@ -1924,7 +2362,7 @@ impl Chain {
         // 1) preprocess the block where we verify that the block is valid and ready to be processed
         //    No chain updates are applied at this step.
         let state_patch = self.pending_state_patch.take();
-        let preprocess_timer = metrics::BLOCK_PREPROCESSING_TIME.start_timer();
+        let preprocess_timer = self.metrics.block_preprocessing_time.start_timer();
         let preprocess_res = self.preprocess_block(
             me,
             &block,
@@ -1960,7 +2398,7 @@ impl Chain {
                             let time = Clock::instant();
                             self.blocks_delay_tracker.mark_block_orphaned(block.hash(), time);
                             let orphan = Orphan { block, provenance, added: time };
-                            self.orphans.add(orphan, requested_missing_chunks);
+                            self.add_orphan(orphan, requested_missing_chunks)?;
 
                             debug!(
                                 target: "chain",
@@ -2017,6 +2455,7 @@ impl Chain {
         let block_hash = *block.hash();
         let block_height = block.header().height();
         let apply_chunks_done_marker = block_preprocess_info.apply_chunks_done.clone();
+        let apply_scheduling_start = block_preprocess_info.apply_scheduling_start;
         self.blocks_in_processing.add(block, block_preprocess_info)?;
 
         // 2) schedule apply chunks, which will be executed in the rayon thread pool.
@@ -2024,6 +2463,7 @@ impl Chain {
             block_hash,
             block_height,
             apply_chunk_work,
+            apply_scheduling_start,
             apply_chunks_done_marker,
             apply_chunks_done_callback.clone(),
         );
@@ -2032,6 +2472,7 @@ impl Chain {
     }
 
     /// Applying chunks async by starting the work at the rayon thread pool
+    /// `apply_scheduling_start`: when the preparation of the work started
     /// `apply_chunks_done_marker`: a marker that will be set to true once applying chunks is finished
     /// `apply_chunks_done_callback`: a callback that will be called once applying chunks is finished
     fn schedule_apply_chunks(
@@ -2039,16 +2480,28 @@ impl Chain {
         block_hash: CryptoHash,
         block_height: BlockHeight,
         work: Vec<Box<dyn FnOnce(&Span) -> Result<ApplyChunkResult, Error> + Send>>,
+        apply_scheduling_start: Instant,
         apply_chunks_done_marker: Arc<OnceCell<()>>,
         apply_chunks_done_callback: DoneApplyChunkCallback,
     ) {
-        let sc = self.apply_chunks_sender.clone();
+        let sc = self.apply_chunks_queue.sender();
+        let metrics = self.metrics.clone();
+        let budgets = self.block_stage_budgets.clone();
         spawn(move || {
+            let record_stage = |stage, elapsed| {
+                record_block_stage(&metrics, &budgets, stage, &block_hash, block_height, elapsed)
+            };
+            let apply_start = Clock::instant();
+            record_stage(
+                BlockStage::ApplyScheduling,
+                apply_start.saturating_duration_since(apply_scheduling_start),
+            );
             // do_apply_chunks runs `work` parallelly, but still waits for all of them to finish
             let res = do_apply_chunks(block_hash, block_height, work);
+            record_stage(BlockStage::Apply, apply_start.elapsed());
             // If we encounter error here, that means the receiver is deallocated and the client
             // thread is already shut down. The node is already crashed, so we can unwrap here
-            sc.send((block_hash.clone(), res)).unwrap();
+            sc.send(AppliedBlock::new(block_hash, res)).unwrap();
             if let Err(_) = apply_chunks_done_marker.set(()) {
                 // This should never happen, if it does, it means there is a bug in our code.
                 log_assert!(false, "apply chunks are called twice for block {block_hash:?}");
@@ -2071,10 +2524,16 @@ impl Chain {
         block_preprocess_info: BlockPreprocessInfo,
         apply_results: Vec<Result<ApplyChunkResult, Error>>,
     ) -> Result<Option<Tip>, Error> {
+        let pending_shards = block_preprocess_info.state_dl_info.as_ref().map(|state_dl_info| {
+            state_dl_info.shards.iter().map(|ShardInfo(shard_id, _)| *shard_id).collect::<Vec<_>>()
+        });
         let mut chain_update = self.chain_update();
         let new_head =
             chain_update.postprocess_block(me, &block, block_preprocess_info, apply_results)?;
         chain_update.commit()?;
+        if let Some(shards) = pending_shards {
+            self.shard_readiness.mark_pending(block.header().epoch_id().clone(), shards);
+        }
         Ok(new_head)
     }
 
@@ -2089,7 +2548,8 @@ impl Chain {
         block_processing_artifacts: &mut BlockProcessingArtifact,
         apply_chunks_done_callback: DoneApplyChunkCallback,
     ) -> Result<AcceptedBlock, Error> {
-        let timer = metrics::BLOCK_POSTPROCESSING_TIME.start_timer();
+        let postprocess_start = Clock::instant();
+        let timer = self.metrics.block_postprocessing_time.start_timer();
         let (block, block_preprocess_info) =
             self.blocks_in_processing.remove(&block_hash).expect(&format!(
                 "block {:?} finished applying chunks but not in blocks_in_processing pool",
@@ -2172,6 +2632,29 @@ impl Chain {
 
         self.pending_state_patch.clear();
 
+        if let Err(err) = self.record_processed_block(&block) {
+            warn!(target: "chain", ?err, "Failed to record processed block");
+        }
+
+        if let Some(contract_events) = &self.contract_events {
+            let result = self
+                .get_block_execution_outcomes(block.hash())
+                .and_then(|outcomes| contract_events.index_block(&block, &outcomes));
+            if let Err(err) = result {
+                warn!(target: "chain", ?err, "Failed to index contract events");
+            }
+        }
+
+        if let Some(contract_code_index) = &self.contract_code_index {
+            let result = self
+                .store
+                .get_state_changes_with_cause_in_block(block.hash())
+                .and_then(|changes| contract_code_index.index_block(&block, &changes));
+            if let Err(err) = result {
+                warn!(target: "chain", ?err, "Failed to index deployed contracts");
+            }
+        }
+
         if let Some(tip) = &new_head {
             // TODO: move this logic of tracking validators metrics to EpochManager
             if let Ok(producers) = self
@@ -2187,22 +2670,34 @@ impl Chain {
                     }
                 }
                 stake /= NEAR_BASE;
-                metrics::VALIDATOR_AMOUNT_STAKED.set(i64::try_from(stake).unwrap_or(i64::MAX));
-                metrics::VALIDATOR_ACTIVE_TOTAL.set(count);
+                self.metrics.validator_amount_staked.set(i64::try_from(stake).unwrap_or(i64::MAX));
+                self.metrics.validator_active_total.set(count);
             }
 
             self.last_time_head_updated = Clock::instant();
         };
 
-        metrics::BLOCK_PROCESSED_TOTAL.inc();
-        metrics::BLOCK_PROCESSING_TIME.observe(
-            Clock::instant()
-                .saturating_duration_since(block_start_processing_time.clone())
-                .as_secs_f64(),
+        self.metrics.block_processed_total.inc();
+        let block_processing_time =
+            Clock::instant().saturating_duration_since(block_start_processing_time.clone());
+        self.metrics.block_processing_time.observe(block_processing_time.as_secs_f64());
+        self.average_block_processing_time = Some(match self.average_block_processing_time {
+            Some(average) => average.mul_f64(0.9) + block_processing_time.mul_f64(0.1),
+            None => block_processing_time,
+        });
+        self.blocks_delay_tracker.finish_block_processing(
+            &block_hash,
+            new_head.clone(),
+            &self.metrics,
         );
-        self.blocks_delay_tracker.finish_block_processing(&block_hash, new_head.clone());
 
         timer.observe_duration();
+        self.record_block_stage(
+            BlockStage::Postprocess,
+            &block_hash,
+            block.header().height(),
+            postprocess_start.elapsed(),
+        );
         let _timer = CryptoHashTimer::new_with_start(*block.hash(), block_start_processing_time);
 
         self.check_orphans(
@@ -2237,6 +2732,7 @@ impl Chain {
         ),
         Error,
     > {
+        let header_verification_start = Clock::instant();
         // see if the block is already in processing or if there are too many blocks being processed
         self.blocks_in_processing.add_dry_run(block.hash())?;
 
@@ -2323,7 +2819,12 @@ impl Chain {
                 let state_dl_info = self.get_state_dl_info(me, block)?;
                 (state_dl_info.is_none(), state_dl_info)
             } else {
-                (self.prev_block_is_caught_up(&prev_prev_hash, &prev_hash)?, None)
+                // Also consult the barrier: the catchup may already be done with the previous
+                // block without having finished yet, in which case the entries checked by
+                // `prev_block_is_caught_up` are about to be removed.
+                let is_caught_up = self.prev_block_is_caught_up(&prev_prev_hash, &prev_hash)?
+                    && self.shard_readiness.is_epoch_ready(block.header().epoch_id());
+                (is_caught_up, None)
             };
 
         self.check_if_challenged_block_on_chain(block.header())?;
@@ -2372,6 +2873,14 @@ impl Chain {
             block.header().prev_hash(),
         )?;
 
+        let chunks_check_start = Clock::instant();
+        self.record_block_stage(
+            BlockStage::HeaderVerification,
+            block.hash(),
+            block.header().height(),
+            chunks_check_start.saturating_duration_since(header_verification_start),
+        );
+
         let prev_block = self.get_block(&prev_hash)?;
 
         self.validate_chunk_headers(&block, &prev_block)?;
@@ -2382,6 +2891,14 @@ impl Chain {
         // Check if block can be finalized and drop it otherwise.
         self.check_if_finalizable(block.header())?;
 
+        let apply_scheduling_start = Clock::instant();
+        self.record_block_stage(
+            BlockStage::ChunksCheck,
+            block.hash(),
+            block.header().height(),
+            apply_scheduling_start.saturating_duration_since(chunks_check_start),
+        );
+
         let apply_chunk_work = self.apply_chunks_preprocessing(
             me,
             block,
@@ -2405,6 +2922,7 @@ impl Chain {
                 provenance: provenance.clone(),
                 apply_chunks_done: Arc::new(OnceCell::new()),
                 block_start_processing_time: block_received_time,
+                apply_scheduling_start,
             },
         ))
     }
@@ -2590,6 +3108,16 @@ impl Chain {
         }
         if let Some(orphans) = self.orphans.remove_by_prev_hash(prev_hash) {
             debug!(target: "chain", found_orphans = orphans.len(), "Check orphans");
+            if self.persist_orphans {
+                let mut store_update = self.store.store().store_update();
+                store_update.set_tag("orphans");
+                for orphan in &orphans {
+                    persisted_orphans::remove(&mut store_update, orphan.block.hash());
+                }
+                if let Err(err) = store_update.commit() {
+                    warn!(target: "chain", ?err, "Failed to remove adopted orphans from the store");
+                }
+            }
             for orphan in orphans.into_iter() {
                 let block_hash = orphan.hash();
                 self.blocks_delay_tracker.mark_block_unorphaned(&block_hash, Clock::instant());
@@ -2626,6 +3154,179 @@ impl Chain {
         )
     }
 
+    /// Builds the epoch sync data committed to by the header of the block
+    /// with hash `block_hash`, which has to be the first block of an epoch.
+    pub fn get_epoch_sync_data(&self, block_hash: &CryptoHash) -> Result<EpochSyncData, Error> {
+        let header = self.get_block_header(block_hash)?;
+        if header.epoch_sync_data_hash().is_none() {
+            return Err(Error::Other(format!(
+                "block {} does not carry epoch sync data hash",
+                block_hash
+            )));
+        }
+        let (
+            prev_epoch_first_block_info,
+            prev_epoch_prev_last_block_info,
+            prev_epoch_last_block_info,
+            prev_epoch_info,
+            cur_epoch_info,
+            next_epoch_info,
+        ) = self.runtime_adapter.get_epoch_sync_data(
+            header.prev_hash(),
+            header.epoch_id(),
+            header.next_epoch_id(),
+        )?;
+        let data = EpochSyncData {
+            prev_epoch_first_block_info: BlockInfo::clone(&prev_epoch_first_block_info),
+            prev_epoch_prev_last_block_info: BlockInfo::clone(&prev_epoch_prev_last_block_info),
+            prev_epoch_last_block_info: BlockInfo::clone(&prev_epoch_last_block_info),
+            prev_epoch_info: EpochInfo::clone(&prev_epoch_info),
+            cur_epoch_info: EpochInfo::clone(&cur_epoch_info),
+            next_epoch_info: EpochInfo::clone(&next_epoch_info),
+        };
+        Self::validate_epoch_sync_data(&header, &data)?;
+        Ok(data)
+    }
+
+    /// Checks that `data` hashes to `epoch_sync_data_hash` of `header`.
+    pub fn validate_epoch_sync_data(
+        header: &BlockHeader,
+        data: &EpochSyncData,
+    ) -> Result<(), Error> {
+        match header.epoch_sync_data_hash() {
+            Some(hash) if hash == data.hash() => Ok(()),
+            _ => Err(Error::InvalidEpochSyncData),
+        }
+    }
+
+    /// Returns proofs of the epochs following the epoch of the block with
+    /// hash `block_hash`, which has to be on the canonical chain and be either
+    /// the genesis or the first block of an epoch.  Only epochs whose first
+    /// block is final are proven, at most `MAX_EPOCH_SYNC_PROOFS` of them.
+    pub fn get_epoch_sync_proofs(
+        &self,
+        block_hash: &CryptoHash,
+    ) -> Result<Vec<EpochSyncProof>, Error> {
+        let header = self.get_block_header(block_hash)?;
+        if self.store.get_block_hash_by_height(header.height())? != *block_hash {
+            return Err(Error::Other(format!("block {} is not canonical", block_hash)));
+        }
+        if header.height() != self.genesis.header().height()
+            && header.epoch_sync_data_hash().is_none()
+        {
+            return Err(Error::Other(format!("block {} does not start an epoch", block_hash)));
+        }
+
+        // Walk epochs back from the final head, as only the epoch of a block
+        // knows where it starts.
+        let mut epoch_start_hashes = vec![];
+        let mut last_hash = self.final_head()?.last_block_hash;
+        while self.get_block_header(&last_hash)?.height() > header.height() {
+            let epoch_start_height = self.runtime_adapter.get_epoch_start_height(&last_hash)?;
+            if epoch_start_height <= header.height() {
+                break;
+            }
+            let epoch_start_hash = self.store.get_block_hash_by_height(epoch_start_height)?;
+            last_hash = *self.get_block_header(&epoch_start_hash)?.prev_hash();
+            epoch_start_hashes.push(epoch_start_hash);
+        }
+
+        let mut proofs = vec![];
+        for epoch_start_hash in epoch_start_hashes.iter().rev() {
+            if proofs.len() >= MAX_EPOCH_SYNC_PROOFS {
+                break;
+            }
+            let header = self.get_block_header(epoch_start_hash)?;
+            if header.epoch_sync_data_hash().is_none() {
+                continue;
+            }
+            let data = self.get_epoch_sync_data(epoch_start_hash)?;
+            proofs.push(EpochSyncProof { header, data });
+        }
+        Ok(proofs)
+    }
+
+    /// Verifies the proof of an epoch whose block producers are known to be
+    /// `block_producers`.  The header has to be signed by one of them, carry
+    /// approvals of more than 2/3 of their stake, and commit to the epoch sync
+    /// data and to the block producers of the next epoch, which are returned.
+    /// Only the known set is used to check the signatures since the sampler in
+    /// the data isn't trusted yet.
+    pub fn verify_epoch_sync_proof(
+        proof: &EpochSyncProof,
+        block_producers: &[ValidatorStake],
+    ) -> Result<Vec<ValidatorStake>, Error> {
+        let EpochSyncProof { header, data } = proof;
+        Self::validate_epoch_sync_data(header, data)?;
+        if Self::epoch_info_block_producers(&data.cur_epoch_info) != block_producers {
+            return Err(Error::InvalidEpochSyncProof);
+        }
+        if !block_producers.iter().any(|bp| header.verify_block_producer(bp.public_key())) {
+            return Err(Error::InvalidSignature);
+        }
+        Self::verify_epoch_sync_approvals(header, block_producers)?;
+        let next_block_producers = Self::epoch_info_block_producers(&data.next_epoch_info);
+        let next_bp_hash = Self::compute_bp_hash_from_stakes(
+            next_block_producers.iter().cloned(),
+            data.cur_epoch_info.protocol_version(),
+        );
+        if header.next_bp_hash() != &next_bp_hash {
+            return Err(Error::InvalidNextBPHash);
+        }
+        Ok(next_block_producers)
+    }
+
+    /// Checks that the approvals in the header of the first block of an epoch
+    /// carry more than 2/3 of the stake of the epoch's block producers, the
+    /// same threshold light clients require.  The first block of an epoch is
+    /// approved by the block producers of its own epoch only, in the order of
+    /// `block_producers`.  Approvals which don't verify count as missing.
+    fn verify_epoch_sync_approvals(
+        header: &BlockHeader,
+        block_producers: &[ValidatorStake],
+    ) -> Result<(), Error> {
+        let prev_height = header.prev_height().ok_or(Error::InvalidEpochSyncProof)?;
+        let approvals = header.approvals();
+        if approvals.len() > block_producers.len() {
+            return Err(Error::InvalidApprovals);
+        }
+        let inner = if prev_height + 1 == header.height() {
+            ApprovalInner::Endorsement(*header.prev_hash())
+        } else {
+            ApprovalInner::Skip(prev_height)
+        };
+        let message = Approval::get_data_for_sig(&inner, header.height());
+        let total_stake: Balance = block_producers.iter().map(|bp| bp.stake()).sum();
+        let approved_stake: Balance = approvals
+            .iter()
+            .zip(block_producers.iter())
+            .filter(|(approval, bp)| match approval {
+                Some(signature) => signature.verify(&message, bp.public_key()),
+                None => false,
+            })
+            .map(|(_, bp)| bp.stake())
+            .sum();
+        if approved_stake <= total_stake * 2 / 3 {
+            return Err(Error::NotEnoughApprovals);
+        }
+        Ok(())
+    }
+
+    /// Block producers of an epoch in the order of their first appearance in
+    /// the settlement, the same as `get_epoch_block_producers_ordered`.
+    /// Settlement entries which don't refer to a validator are skipped, as
+    /// epoch info received from peers may be malformed.
+    pub fn epoch_info_block_producers(epoch_info: &EpochInfo) -> Vec<ValidatorStake> {
+        let mut seen = HashSet::new();
+        epoch_info
+            .block_producers_settlement()
+            .iter()
+            .filter(|validator_id| (**validator_id as usize) < epoch_info.validators_len())
+            .filter(|validator_id| seen.insert(**validator_id))
+            .map(|validator_id| epoch_info.get_validator(*validator_id))
+            .collect()
+    }
+
     pub fn get_state_response_header(
         &self,
         shard_id: ShardId,
@@ -2864,14 +3565,35 @@ impl Chain {
         // Before saving State Part data, we need to make sure we can calculate and save State Header
         self.get_state_response_header(shard_id, sync_hash)?;
 
+        // Parts are cached only to serve further requests faster, which is
+        // not worth the space when the database is close to its disk budget.
+        if near_store::disk_budget().is_disabling_recorders() {
+            near_store::disk_budget().record_skipped("state_part");
+            return Ok(state_part);
+        }
+
         // Saving the part data
         let mut store_update = self.store.store().store_update();
         store_update.set(DBCol::StateParts, &key, &state_part);
         store_update.commit()?;
+        metrics::STATE_SYNC_PARTS_STORED.inc();
+        metrics::STATE_SYNC_PARTS_STORED_BYTES.add(state_part.len() as i64);
 
         Ok(state_part)
     }
 
+    /// Returns state part previously computed by `get_state_response_part`
+    /// without computing it if it is missing.
+    pub fn get_cached_state_response_part(
+        &self,
+        shard_id: ShardId,
+        part_id: u64,
+        sync_hash: CryptoHash,
+    ) -> Result<Option<Vec<u8>>, Error> {
+        let key = StatePartKey(sync_hash, shard_id, part_id).try_to_vec()?;
+        Ok(self.store.store().get(DBCol::StateParts, &key)?.map(|part| part.into()))
+    }
+
     pub fn set_state_header(
         &mut self,
         shard_id: ShardId,
@@ -3055,6 +3777,8 @@ impl Chain {
         let key = StatePartKey(sync_hash, shard_id, part_id.idx).try_to_vec()?;
         store_update.set(DBCol::StateParts, &key, data);
         store_update.commit()?;
+        metrics::STATE_SYNC_PARTS_STORED.inc();
+        metrics::STATE_SYNC_PARTS_STORED_BYTES.add(data.len() as i64);
         Ok(())
     }
 
@@ -3064,6 +3788,7 @@ impl Chain {
         sync_hash: CryptoHash,
         num_parts: u64,
         state_parts_task_scheduler: &dyn Fn(ApplyStatePartsRequest),
+        status: Arc<StatePartsApplyingStatus>,
     ) -> Result<(), Error> {
         let shard_state_header = self.get_state_header(shard_id, sync_hash)?;
         let state_root = shard_state_header.chunk_prev_state_root();
@@ -3076,6 +3801,7 @@ impl Chain {
             num_parts,
             epoch_id,
             sync_hash,
+            status,
         });
 
         Ok(())
@@ -3217,6 +3943,29 @@ impl Chain {
         }
         blocks_catch_up_state.processed_blocks = processed_blocks;
 
+        // A block built on top of a block which is already done may have been accepted only
+        // afterwards.  It's recorded as a block to catch up when it's accepted, so look for such
+        // blocks until the catchup is finished, otherwise they would be dropped from the queue
+        // together with their parent.
+        let mut late_blocks = vec![];
+        for done_block in &blocks_catch_up_state.done_blocks {
+            for next_block_hash in self.store.get_blocks_to_catchup(done_block)? {
+                if !blocks_catch_up_state.contains(&next_block_hash)
+                    && !late_blocks.contains(&next_block_hash)
+                {
+                    late_blocks.push(next_block_hash);
+                }
+            }
+        }
+        if !late_blocks.is_empty() {
+            debug!(
+                target: "catchup",
+                ?late_blocks,
+                "Found blocks accepted after their parent was caught up"
+            );
+        }
+        blocks_catch_up_state.pending_blocks.extend(late_blocks);
+
         for pending_block in blocks_catch_up_state.pending_blocks.drain(..) {
             let block = self.store.get_block(&pending_block)?.clone();
             let prev_block = self.store.get_block(block.header().prev_hash())?.clone();
@@ -3261,6 +4010,16 @@ impl Chain {
         Ok(())
     }
 
+    /// Returns true if all blocks of the catchup are done and no block built on top of them is
+    /// still being processed, i.e. the catchup can be finished with `finish_catchup_blocks`.
+    pub fn is_catchup_finished(&self, blocks_catch_up_state: &BlocksCatchUpState) -> bool {
+        blocks_catch_up_state.is_finished()
+            && !blocks_catch_up_state
+                .done_blocks
+                .iter()
+                .any(|block_hash| self.blocks_in_processing.has_blocks_to_catch_up(block_hash))
+    }
+
     /// Apply transactions in chunks for the next epoch in blocks that were blocked on the state sync
     pub fn finish_catchup_blocks(
         &mut self,
@@ -3293,6 +4052,9 @@ impl Chain {
         chain_store_update.remove_state_dl_info(*epoch_first_block);
 
         chain_store_update.commit()?;
+        // Nothing can run between the commit and this, so blocks see the catchup either as not
+        // finished with the shards pending or as finished with the shards ready.
+        self.shard_readiness.mark_ready(first_block.header().epoch_id());
 
         for hash in affected_blocks.iter() {
             self.check_orphans(
@@ -3364,7 +4126,16 @@ impl Chain {
         })?;
         let transaction: SignedTransactionView = SignedTransaction::clone(&transaction).into();
         let transaction_outcome = outcomes.pop().unwrap();
-        Ok(FinalExecutionOutcomeView { status, transaction, transaction_outcome, receipts_outcome })
+        let mut outcome = FinalExecutionOutcomeView {
+            status,
+            transaction,
+            transaction_outcome,
+            receipts_outcome,
+            finality: None,
+            refunds: None,
+        };
+        outcome.annotate_refunds(|receipt_id| self.store.get_receipt(receipt_id))?;
+        Ok(outcome)
     }
 
     pub fn get_final_transaction_result_with_receipt(
@@ -3392,7 +4163,33 @@ impl Chain {
             })
             .collect::<Result<Vec<_>, _>>()?;
 
-        Ok(FinalExecutionOutcomeWithReceiptView { final_outcome, receipts })
+        Ok(FinalExecutionOutcomeWithReceiptView { final_outcome, receipts, outcome_proofs: None })
+    }
+
+    /// Returns finality of the block with respect to the current head.
+    ///
+    /// Blocks which are unknown or not on the canonical chain are tentative
+    /// regardless of their height, since a block on a fork never becomes final.
+    pub fn get_block_finality(&self, block_hash: &CryptoHash) -> Result<Finality, Error> {
+        let header = match self.get_block_header(block_hash) {
+            Ok(header) => header,
+            Err(Error::DBNotFoundErr(_)) => return Ok(Finality::None),
+            Err(err) => return Err(err),
+        };
+        match self.is_on_current_chain(&header) {
+            Ok(true) => {}
+            Ok(false) | Err(Error::DBNotFoundErr(_)) => return Ok(Finality::None),
+            Err(err) => return Err(err),
+        }
+        if header.height() <= self.final_head()?.height {
+            return Ok(Finality::Final);
+        }
+        let head_header = self.head_header()?;
+        match self.get_block_header(head_header.last_ds_final_block()) {
+            Ok(ds_final) if header.height() <= ds_final.height() => Ok(Finality::DoomSlug),
+            Ok(_) | Err(Error::DBNotFoundErr(_)) => Ok(Finality::None),
+            Err(err) => Err(err),
+        }
     }
 
     /// Find a validator to forward transactions to
@@ -3597,6 +4394,15 @@ impl Chain {
                 ApplyChunksMode::NotCaughtUp => cares_about_shard_this_epoch,
                 // update both this epoch and next epoch
                 ApplyChunksMode::IsCaughtUp => {
+                    if !cares_about_shard_this_epoch
+                        && cares_about_shard_next_epoch
+                        && !self.shard_readiness.is_ready(block.header().epoch_id(), shard_id)
+                    {
+                        return Err(Error::Other(format!(
+                            "state of shard {} for the next epoch is not ready yet",
+                            shard_id
+                        )));
+                    }
                     cares_about_shard_this_epoch || cares_about_shard_next_epoch
                 }
                 // catching up next epoch's shard states, do not update this epoch's shard state
@@ -3660,6 +4466,18 @@ impl Chain {
                                                 prev_chunk_extra: {:#?}\n\
                                                 chunk_header: {:#?}", e,block.header().prev_hash(),block.header().hash(),shard_id,prev_chunk_height_included,prev_chunk_extra,chunk_header);
                         byzantine_assert!(false);
+                        if let (Error::InvalidStateRoot, Some(bundles)) =
+                            (&e, &self.state_mismatch_bundles)
+                        {
+                            bundles.save_in_background(
+                                &self.store,
+                                self.runtime_adapter.clone(),
+                                prev_block,
+                                block,
+                                chunk_header,
+                                prev_chunk_extra.as_ref().clone(),
+                            );
+                        }
                         match self.create_chunk_state_challenge(prev_block, block, chunk_header) {
                             Ok(chunk_state) => {
                                 Error::InvalidChunkState(Box::new(chunk_state))
@@ -3905,6 +4723,7 @@ impl Chain {
             self.runtime_adapter.clone(),
             self.doomslug_threshold_mode,
             self.transaction_validity_period,
+            self.metrics.clone(),
         )
     }
 
@@ -4294,6 +5113,11 @@ impl Chain {
         self.orphans.len_evicted()
     }
 
+    #[inline]
+    pub(crate) fn orphans(&self) -> &OrphanBlockPool {
+        &self.orphans
+    }
+
     /// Check if hash is for a known orphan.
     #[inline]
     pub fn is_orphan(&self, hash: &CryptoHash) -> bool {
@@ -4317,16 +5141,26 @@ impl Chain {
         self.store.is_height_processed(height)
     }
 
-    /// Check if can sync with sync_hash
-    pub fn check_sync_hash_validity(&self, sync_hash: &CryptoHash) -> Result<bool, Error> {
+    /// Checks that `sync_hash` is the first block of one of the last
+    /// `num_epochs` epochs, for which the node serves state sync data.
+    pub fn check_sync_hash_validity(
+        &self,
+        sync_hash: &CryptoHash,
+        num_epochs: u64,
+    ) -> Result<bool, Error> {
         let head = self.head()?;
         // It's important to check that Block exists because we will sync with it.
         // Do not replace with `get_block_header`.
         let sync_block = self.get_block(sync_hash)?;
-        // The Epoch of sync_hash may be either the current one or the previous one
-        if head.epoch_id == *sync_block.header().epoch_id()
-            || head.epoch_id == *sync_block.header().next_epoch_id()
-        {
+        let sync_epoch_id = sync_block.header().epoch_id();
+        let is_recent_epoch = if head.epoch_id == *sync_epoch_id {
+            num_epochs >= 1
+        } else if head.epoch_id == *sync_block.header().next_epoch_id() {
+            num_epochs >= 2
+        } else {
+            self.is_recent_epoch(&head.last_block_hash, sync_epoch_id, num_epochs)?
+        };
+        if is_recent_epoch {
             let prev_hash = *sync_block.header().prev_hash();
             // If sync_hash is not on the Epoch boundary, it's malicious behavior
             self.runtime_adapter.is_next_block_epoch_start(&prev_hash)
@@ -4335,11 +5169,71 @@ impl Chain {
         }
     }
 
+    /// Returns the height of the first block of the epoch `num_epochs - 1`
+    /// epochs before the epoch of the block, or of the first epoch if there
+    /// are fewer epochs.
+    fn get_epoch_start_height_back(
+        &self,
+        block_hash: &CryptoHash,
+        num_epochs: u64,
+    ) -> Result<BlockHeight, Error> {
+        let mut epoch_start_height = self.runtime_adapter.get_epoch_start_height(block_hash)?;
+        for _ in 1..num_epochs {
+            if epoch_start_height <= self.genesis.header().height() {
+                break;
+            }
+            let epoch_start_hash = self.store.get_block_hash_by_height(epoch_start_height)?;
+            let prev_hash = *self.get_block_header(&epoch_start_hash)?.prev_hash();
+            epoch_start_height = self.runtime_adapter.get_epoch_start_height(&prev_hash)?;
+        }
+        Ok(epoch_start_height)
+    }
+
+    /// Checks whether `epoch_id` is the epoch of the block or one of the
+    /// `num_epochs - 1` epochs before it on the canonical chain.
+    fn is_recent_epoch(
+        &self,
+        block_hash: &CryptoHash,
+        epoch_id: &EpochId,
+        num_epochs: u64,
+    ) -> Result<bool, Error> {
+        let mut block_hash = *block_hash;
+        for _ in 0..num_epochs {
+            let header = self.get_block_header(&block_hash)?;
+            if header.epoch_id() == epoch_id {
+                return Ok(true);
+            }
+            let epoch_start_height = self.runtime_adapter.get_epoch_start_height(&block_hash)?;
+            if epoch_start_height <= self.genesis.header().height() {
+                break;
+            }
+            let epoch_start_hash = self.store.get_block_hash_by_height(epoch_start_height)?;
+            block_hash = *self.get_block_header(&epoch_start_hash)?.prev_hash();
+        }
+        Ok(false)
+    }
+
     /// Get transaction result for given hash of transaction or receipt id on the canonical chain
     pub fn get_execution_outcome(
         &self,
         id: &CryptoHash,
     ) -> Result<ExecutionOutcomeWithIdAndProof, Error> {
+        // Outcomes in final blocks are indexed by the outcome compaction, so
+        // that the outcomes on forks don't have to be checked.
+        if let Some(block_hash) = self.store.get_canonical_outcome_block(id)? {
+            if let Some(outcome_with_proof) =
+                self.store.get_outcome_by_id_and_block_hash(id, &block_hash)?
+            {
+                return Ok(ExecutionOutcomeWithIdAndProof {
+                    proof: outcome_with_proof.proof,
+                    block_hash,
+                    outcome_with_id: ExecutionOutcomeWithId {
+                        id: *id,
+                        outcome: outcome_with_proof.outcome,
+                    },
+                });
+            }
+        }
         let outcomes = self.store.get_outcomes_by_id(id)?;
         outcomes
             .into_iter()
@@ -4350,6 +5244,166 @@ impl Chain {
             .ok_or_else(|| Error::DBNotFoundErr(format!("EXECUTION OUTCOME: {}", id)))
     }
 
+    /// Finds the chunk which sent the receipt and proves that the receipt is
+    /// among the outgoing receipts of the chunk.  The chunk is found by
+    /// looking for the receipt among the incoming receipts of the receiving
+    /// shard in the blocks up to the one the receipt was executed in, so the
+    /// receipt has to be executed on the canonical chain already.  Returns
+    /// `None` if the receipt isn't found, which is also the case of receipts
+    /// converted from transactions and executed in the same chunk, which no
+    /// chunk sends.
+    pub fn get_receipt_inclusion_proof(
+        &self,
+        receipt_id: &CryptoHash,
+    ) -> Result<Option<ReceiptInclusionProofView>, Error> {
+        let mut block_hash = match self.get_execution_outcome(receipt_id) {
+            Ok(outcome) => outcome.block_hash,
+            Err(Error::DBNotFoundErr(_)) => return Ok(None),
+            Err(err) => return Err(err),
+        };
+        let to_shard_id = match self.store.get_shard_id_for_receipt_id(receipt_id) {
+            Ok(shard_id) => shard_id,
+            Err(Error::DBNotFoundErr(_)) => return Ok(None),
+            Err(err) => return Err(err),
+        };
+        for _ in 0..MAX_RECEIPT_PROOF_SEARCH_BLOCKS {
+            let receipt_proofs = match self.store.get_incoming_receipts(&block_hash, to_shard_id) {
+                Ok(receipt_proofs) => receipt_proofs,
+                // Nothing is stored for blocks sending no receipts to the shard.
+                Err(Error::DBNotFoundErr(_)) => Arc::new(vec![]),
+                Err(err) => return Err(err),
+            };
+            let receipt_proof = receipt_proofs.iter().find(|ReceiptProof(receipts, _)| {
+                receipts.iter().any(|receipt| &receipt.receipt_id == receipt_id)
+            });
+            if let Some(receipt_proof) = receipt_proof {
+                // Incoming receipts of a block come from the chunks included
+                // in the block.
+                let block = self.get_block(&block_hash)?;
+                let from_shard_id = receipt_proof.1.from_shard_id;
+                let chunk_header =
+                    block.chunks().get(from_shard_id as usize).cloned().ok_or_else(|| {
+                        Error::Other(format!(
+                            "Block {} has no chunk of shard {}",
+                            block_hash, from_shard_id
+                        ))
+                    })?;
+                let (_, chunk_proofs) = merklize(
+                    &block
+                        .chunks()
+                        .iter()
+                        .map(|chunk| chunk.outgoing_receipts_root())
+                        .collect::<Vec<CryptoHash>>(),
+                );
+                return Ok(Some(ReceiptInclusionProofView {
+                    receipt_id: *receipt_id,
+                    block_hash,
+                    block_height: block.header().height(),
+                    chunk_hash: chunk_header.chunk_hash().0,
+                    from_shard_id,
+                    outgoing_receipts_root: chunk_header.outgoing_receipts_root(),
+                    receipt_proof: receipt_proof.clone().into(),
+                    chunk_proof: chunk_proofs[from_shard_id as usize].clone(),
+                }));
+            }
+            let header = self.get_block_header(&block_hash)?;
+            if header.height() == self.genesis.header().height() {
+                break;
+            }
+            block_hash = *header.prev_hash();
+        }
+        Ok(None)
+    }
+
+    /// Proves that the receipt was executed with its outcome to a light client
+    /// which knows the block merkle root of `light_client_head`.  The outcome
+    /// root of the chunk which executed the receipt is included in the next
+    /// block with a chunk of the same shard, which the proof goes through.
+    /// Returns `None` if there is no such block yet, or if it or the light
+    /// client head isn't final and on the canonical chain.
+    pub fn get_receipt_execution_proof(
+        &self,
+        receipt_id: &CryptoHash,
+        light_client_head: &CryptoHash,
+    ) -> Result<Option<ReceiptExecutionProofView>, Error> {
+        let outcome = self.get_execution_outcome(receipt_id)?;
+        let (block, outcome_root_proof) = match self.get_outcome_root_proof(
+            &outcome.block_hash,
+            &outcome.outcome_with_id.outcome.executor_id,
+        )? {
+            Some(it) => it,
+            None => return Ok(None),
+        };
+        let head_header = self.get_block_header(light_client_head)?;
+        match self.check_blocks_final_and_canonical(&[block.header(), &head_header]) {
+            Ok(()) => {}
+            Err(Error::Other(_)) => return Ok(None),
+            Err(err) => return Err(err),
+        }
+        let mut outcome_proof = ExecutionOutcomeWithIdView::from(outcome);
+        outcome_proof.block_hash = *block.hash();
+        Ok(Some(ReceiptExecutionProofView {
+            outcome_proof,
+            outcome_root_proof,
+            block_header_lite: block.header().clone().into(),
+            block_proof: self.get_block_proof(block.hash(), light_client_head)?,
+        }))
+    }
+
+    /// Returns the proofs of the outcomes of the transaction and its receipts
+    /// to the outcome roots of the blocks including them.  Outcomes whose
+    /// outcome root isn't included in a block yet are skipped.
+    pub fn get_execution_outcome_proofs(
+        &self,
+        outcome: &FinalExecutionOutcomeView,
+    ) -> Result<Vec<ExecutionOutcomeProofView>, Error> {
+        let mut proofs = vec![];
+        for outcome in
+            std::iter::once(&outcome.transaction_outcome).chain(outcome.receipts_outcome.iter())
+        {
+            let (block, outcome_root_proof) = match self
+                .get_outcome_root_proof(&outcome.block_hash, &outcome.outcome.executor_id)?
+            {
+                Some(it) => it,
+                None => continue,
+            };
+            proofs.push(ExecutionOutcomeProofView {
+                id: outcome.id,
+                outcome_root_proof,
+                block_header_lite: block.header().clone().into(),
+            });
+        }
+        Ok(proofs)
+    }
+
+    /// Returns the block including the outcome root of the chunk of
+    /// `executor_id`'s shard applied in the given block, which is the next
+    /// block with a chunk of the same shard, along with the path from that
+    /// outcome root to the outcome root of the block.  Returns `None` if there
+    /// is no such block yet.
+    fn get_outcome_root_proof(
+        &self,
+        block_hash: &CryptoHash,
+        executor_id: &AccountId,
+    ) -> Result<Option<(Block, MerklePath)>, Error> {
+        let epoch_id = self.get_block_header(block_hash)?.epoch_id().clone();
+        let shard_id = self.runtime_adapter.account_id_to_shard_id(executor_id, &epoch_id)?;
+        let (block_hash, shard_id) =
+            match self.get_next_block_hash_with_new_chunk(block_hash, shard_id)? {
+                Some(it) => it,
+                None => return Ok(None),
+            };
+        let block = self.get_block(&block_hash)?;
+        let (_, outcome_root_proofs) = merklize(
+            &block.chunks().iter().map(|chunk| chunk.outcome_root()).collect::<Vec<CryptoHash>>(),
+        );
+        let outcome_root_proof =
+            outcome_root_proofs.get(shard_id as usize).cloned().ok_or_else(|| {
+                Error::Other(format!("Block {} has no chunk of shard {}", block_hash, shard_id))
+            })?;
+        Ok(Some((block, outcome_root_proof)))
+    }
+
     /// Retrieve the up to `max_headers_returned` headers on the main chain
     /// `hashes`: a list of block "locators". `hashes` should be ordered from older blocks to
     ///           more recent blocks. This function will find the first block in `hashes`
@@ -4484,6 +5538,7 @@ pub struct ChainUpdate<'a> {
     doomslug_threshold_mode: DoomslugThresholdMode,
     #[allow(unused)]
     transaction_validity_period: BlockHeightDelta,
+    metrics: Arc<ChainMetrics>,
 }
 
 pub struct SameHeightResult {
@@ -4517,6 +5572,7 @@ impl<'a> ChainUpdate<'a> {
         runtime_adapter: Arc<dyn RuntimeAdapter>,
         doomslug_threshold_mode: DoomslugThresholdMode,
         transaction_validity_period: BlockHeightDelta,
+        metrics: Arc<ChainMetrics>,
     ) -> Self {
         let chain_store_update: ChainStoreUpdate<'_> = store.store_update();
         Self::new_impl(
@@ -4524,6 +5580,7 @@ impl<'a> ChainUpdate<'a> {
             doomslug_threshold_mode,
             transaction_validity_period,
             chain_store_update,
+            metrics,
         )
     }
 
@@ -4532,12 +5589,14 @@ impl<'a> ChainUpdate<'a> {
         doomslug_threshold_mode: DoomslugThresholdMode,
         transaction_validity_period: BlockHeightDelta,
         chain_store_update: ChainStoreUpdate<'a>,
+        metrics: Arc<ChainMetrics>,
     ) -> Self {
         ChainUpdate {
             runtime_adapter,
             chain_store_update,
             doomslug_threshold_mode,
             transaction_validity_period,
+            metrics,
         }
     }
 
@@ -4769,6 +5828,7 @@ impl<'a> ChainUpdate<'a> {
             } else {
                 info!(target: "chain", %shard_id, "Add delta for flat storage creation");
                 let mut store_update = self.chain_store_update.store().store_update();
+                store_update.set_tag("flat_state_delta");
                 store_helper::set_delta(&mut store_update, shard_id, block_hash.clone(), &delta)
                     .map_err(|e| StorageError::from(e))?;
                 self.chain_store_update.merge(store_update);
@@ -5044,7 +6104,7 @@ impl<'a> ChainUpdate<'a> {
             let tip = Tip::from_header(header);
             self.chain_store_update.save_header_head_if_not_challenged(&tip)?;
             debug!(target: "chain", "Header head updated to {} at {}", tip.last_block_hash, tip.height);
-            metrics::HEADER_HEAD_HEIGHT.set(tip.height as i64);
+            self.metrics.header_head_height.set(tip.height as i64);
 
             Ok(Some(tip))
         } else {
@@ -5082,8 +6142,8 @@ impl<'a> ChainUpdate<'a> {
             let tip = Tip::from_header(header);
 
             self.chain_store_update.save_body_head(&tip)?;
-            metrics::BLOCK_HEIGHT_HEAD.set(tip.height as i64);
-            metrics::BLOCK_ORDINAL_HEAD.set(header.block_ordinal() as i64);
+            self.metrics.block_height_head.set(tip.height as i64);
+            self.metrics.block_ordinal_head.set(header.block_ordinal() as i64);
             debug!(target: "chain", "Head updated to {} at {}", tip.last_block_hash, tip.height);
             Ok(Some(tip))
         } else {
@@ -5355,6 +6415,7 @@ pub struct ApplyStatePartsRequest {
     pub num_parts: u64,
     pub epoch_id: EpochId,
     pub sync_hash: CryptoHash,
+    pub status: Arc<StatePartsApplyingStatus>,
 }
 
 #[derive(Message)]
@@ -5446,6 +6507,14 @@ impl BlocksCatchUpState {
             && self.scheduled_blocks.is_empty()
             && self.processed_blocks.is_empty()
     }
+
+    /// Returns true if the block is in any stage of the catchup.
+    pub fn contains(&self, block_hash: &CryptoHash) -> bool {
+        self.pending_blocks.contains(block_hash)
+            || self.scheduled_blocks.contains(block_hash)
+            || self.processed_blocks.contains_key(block_hash)
+            || self.done_blocks.contains(block_hash)
+    }
 }
 
 impl Chain {
@@ -5472,8 +6541,17 @@ impl Chain {
 
 #[cfg(test)]
 mod tests {
+    use std::time::{Duration, Instant};
+
+    use near_primitives::block::Block;
     use near_primitives::hash::CryptoHash;
 
+    use super::{Orphan, OrphanBlockPool, MAX_ORPHAN_AGE_SECS, MAX_ORPHAN_SIZE};
+    use crate::metrics::ChainMetrics;
+    use crate::missing_chunks::BlockLike;
+    use crate::test_utils::setup;
+    use crate::Provenance;
+
     #[test]
     pub fn receipt_randomness_reproducibility() {
         // Sanity check that the receipt shuffling implementation does not change.
@@ -5484,4 +6562,51 @@ mod tests {
         );
         assert_eq!(receipt_proofs, vec![2, 3, 1, 4, 0, 5, 6],);
     }
+
+    /// Checks that a full orphan pool first evicts orphans older than
+    /// `MAX_ORPHAN_AGE_SECS`, then the ones furthest from the head and among
+    /// equally distant ones the oldest.
+    #[test]
+    fn orphan_pool_eviction() {
+        let (chain, _, signer) = setup();
+        let genesis = chain.get_block(&chain.genesis().hash().clone()).unwrap();
+        let metrics = near_o11y::metrics::MetricsRegistry::global().get::<ChainMetrics>();
+        let mut pool = OrphanBlockPool::new(metrics);
+        let now = Instant::now();
+        let orphan = |height, age_secs| Orphan {
+            block: Block::empty_with_height(&genesis, height, &*signer).into(),
+            provenance: Provenance::NONE,
+            added: now - Duration::from_secs(age_secs),
+        };
+        let head_height = MAX_ORPHAN_SIZE as u64;
+
+        // Heights 1 and 2 * head_height - 1 are equally far from the head, the
+        // former was added earlier.
+        let oldest_furthest = orphan(1, 20);
+        let furthest = orphan(2 * head_height - 1, 10);
+        let (oldest_furthest_hash, furthest_hash) = (oldest_furthest.hash(), furthest.hash());
+        assert!(pool.add(oldest_furthest, false, head_height).is_empty());
+        assert!(pool.add(furthest, false, head_height).is_empty());
+        for height in 2..MAX_ORPHAN_SIZE as u64 {
+            assert!(pool.add(orphan(height, 0), false, head_height).is_empty());
+        }
+        assert_eq!(pool.len(), MAX_ORPHAN_SIZE);
+
+        let next = orphan(head_height, 0);
+        assert_eq!(pool.add(next, false, head_height), vec![oldest_furthest_hash]);
+        assert!(!pool.contains(&oldest_furthest_hash));
+        let next = orphan(head_height + 1, 0);
+        assert_eq!(pool.add(next, false, head_height), vec![furthest_hash]);
+        assert_eq!(pool.len(), MAX_ORPHAN_SIZE);
+
+        // An expired orphan goes first even though it's at the head.
+        let expired = orphan(head_height, MAX_ORPHAN_AGE_SECS + 1);
+        let expired_hash = expired.hash();
+        assert_eq!(pool.add(expired, false, head_height), vec![expired_hash]);
+        let next = orphan(head_height + 2, 0);
+        let next_hash = next.hash();
+        assert_eq!(pool.add(next, false, head_height).len(), 1);
+        assert!(pool.contains(&next_hash));
+        assert_eq!(pool.len_evicted(), 4);
+    }
 }
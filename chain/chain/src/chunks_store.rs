@@ -3,7 +3,8 @@ use std::sync::Arc;
 use borsh::BorshDeserialize;
 use near_cache::CellLruCache;
 use near_chain_primitives::Error;
-use near_primitives::sharding::{ChunkHash, PartialEncodedChunk, ShardChunk};
+use near_primitives::hash::CryptoHash;
+use near_primitives::sharding::{ChunkHash, PartialEncodedChunk, ReceiptProof, ShardChunk};
 use near_store::{DBCol, Store};
 
 #[cfg(not(feature = "no_cache"))]
@@ -11,6 +12,14 @@ const CHUNK_CACHE_SIZE: usize = 1024;
 #[cfg(feature = "no_cache")]
 const CHUNK_CACHE_SIZE: usize = 1;
 
+/// Returns the key under which a verified receipt proof of the chunk is marked
+/// in `DBCol::VerifiedReceiptProofs`.
+pub fn verified_receipt_proof_key(chunk_hash: &ChunkHash, receipt_proof: &ReceiptProof) -> Vec<u8> {
+    let mut key = chunk_hash.as_bytes().to_vec();
+    key.extend_from_slice(CryptoHash::hash_borsh(receipt_proof).as_bytes());
+    key
+}
+
 pub struct ReadOnlyChunksStore {
     store: Store,
     partial_chunks: CellLruCache<Vec<u8>, Arc<PartialEncodedChunk>>,
@@ -51,6 +60,16 @@ impl ReadOnlyChunksStore {
             _ => Err(Error::ChunkMissing(chunk_hash.clone())),
         }
     }
+    /// Whether the receipt proof was verified against the outgoing receipts
+    /// root of the chunk and marked as such when the chunk was saved.
+    pub fn is_receipt_proof_verified(
+        &self,
+        chunk_hash: &ChunkHash,
+        receipt_proof: &ReceiptProof,
+    ) -> bool {
+        let key = verified_receipt_proof_key(chunk_hash, receipt_proof);
+        self.store.exists(DBCol::VerifiedReceiptProofs, &key).unwrap_or(false)
+    }
     pub fn get_chunk(&self, chunk_hash: &ChunkHash) -> Result<Arc<ShardChunk>, Error> {
         match self.read_with_cache(DBCol::Chunks, &self.chunks, chunk_hash.as_ref()) {
             Ok(Some(shard_chunk)) => Ok(shard_chunk),
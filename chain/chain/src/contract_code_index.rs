@@ -0,0 +1,184 @@
+//! Index of the accounts contracts are deployed to, by hash of their code.
+//!
+//! Without an index, finding every deployment of a contract, for example of
+//! one with a known vulnerability, requires scanning the whole state.  When
+//! enabled, the deployments in the state changes of every processed block are
+//! written to [`DBCol::ContractCodeAccounts`], keyed by the hash of the code
+//! and the account, so that the accounts can be listed page by page.
+//!
+//! Entries are only ever added: blocks which end up off the canonical chain
+//! are indexed as well, and an account keeps its entry after it deploys other
+//! code or is deleted.  Queries therefore check every candidate against the
+//! current state of the account.  Contracts deployed before the index was
+//! enabled, including the ones in genesis, aren't indexed.
+use borsh::{BorshDeserialize, BorshSerialize};
+use near_primitives::block::Block;
+use near_primitives::hash::{hash, CryptoHash};
+use near_primitives::types::{AccountId, BlockHeight, StateChangeValue, StateChanges};
+use near_primitives::views::{ContractAccountView, ContractAccountsView};
+use near_store::{DBCol, Store};
+
+use crate::Error;
+
+/// Maximum number of accounts returned by a single query.
+pub const MAX_QUERY_LIMIT: usize = 1000;
+
+/// Block in which the code was last seen deployed to the account.
+#[derive(BorshSerialize, BorshDeserialize)]
+struct Record {
+    block_height: BlockHeight,
+    block_hash: CryptoHash,
+}
+
+fn encode_key(code_hash: &CryptoHash, account_id: &AccountId) -> Vec<u8> {
+    let mut key = code_hash.0.to_vec();
+    key.extend_from_slice(account_id.as_bytes());
+    key
+}
+
+fn decode_account(key: &[u8], value: &[u8]) -> Result<ContractAccountView, Error> {
+    let invalid = || Error::Other(format!("invalid contract code index key {:?}", key));
+    let account_id = key.get(32..).ok_or_else(invalid)?;
+    let account_id = std::str::from_utf8(account_id).map_err(|_| invalid())?;
+    let record = Record::try_from_slice(value)?;
+    Ok(ContractAccountView {
+        account_id: account_id.parse().map_err(|_| invalid())?,
+        block_height: record.block_height,
+        block_hash: record.block_hash,
+    })
+}
+
+pub struct ContractCodeIndex {
+    store: Store,
+}
+
+impl ContractCodeIndex {
+    pub fn new(store: Store) -> Self {
+        Self { store }
+    }
+
+    /// Indexes the contracts deployed in the state changes of a processed
+    /// block.
+    pub fn index_block(&self, block: &Block, changes: &StateChanges) -> Result<(), Error> {
+        let record = Record { block_height: block.header().height(), block_hash: *block.hash() };
+        let mut store_update = self.store.store_update();
+        let mut num_deployments = 0;
+        for change in changes {
+            if let StateChangeValue::ContractCodeUpdate { account_id, code } = &change.value {
+                let key = encode_key(&hash(code), account_id);
+                store_update.set_ser(DBCol::ContractCodeAccounts, &key, &record)?;
+                num_deployments += 1;
+            }
+        }
+        if num_deployments > 0 {
+            store_update.commit()?;
+        }
+        Ok(())
+    }
+}
+
+/// Returns up to `limit` accounts the code with the hash is deployed to, in
+/// order of account id and starting after `cursor`, the `next_cursor` of the
+/// previous query.  Candidates for which `is_deployed` returns false, because
+/// the account no longer has the code, are skipped.
+///
+/// The index is read from the cursor on and `is_deployed` is called only for
+/// candidates up to the last account returned, so that paging through all
+/// accounts checks each candidate once.  A page ending right before
+/// candidates which are all skipped still has a `next_cursor`.
+pub fn query_contract_accounts(
+    store: &Store,
+    code_hash: &CryptoHash,
+    cursor: Option<&AccountId>,
+    limit: usize,
+    mut is_deployed: impl FnMut(&AccountId) -> Result<bool, Error>,
+) -> Result<ContractAccountsView, Error> {
+    // The smallest key after the cursor.
+    let start = match cursor {
+        Some(cursor) => {
+            let mut start = encode_key(code_hash, cursor);
+            start.push(0);
+            start
+        }
+        None => vec![],
+    };
+    let limit = limit.min(MAX_QUERY_LIMIT);
+    let mut accounts: Vec<ContractAccountView> = vec![];
+    let mut next_cursor = None;
+    for item in store.iter_prefix_from(DBCol::ContractCodeAccounts, &code_hash.0, &start) {
+        let (key, value) = item?;
+        if accounts.len() == limit {
+            next_cursor = Some(accounts.last().unwrap().account_id.clone());
+            break;
+        }
+        let account = decode_account(&key, &value)?;
+        if is_deployed(&account.account_id)? {
+            accounts.push(account);
+        }
+    }
+    Ok(ContractAccountsView { accounts, next_cursor })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{query_contract_accounts, ContractCodeIndex};
+    use crate::test_utils::setup;
+    use crate::{Block, ChainStoreAccess};
+    use near_primitives::hash::hash;
+    use near_primitives::types::{
+        AccountId, StateChangeCause, StateChangeValue, StateChangeWithCause,
+    };
+    use std::cell::Cell;
+
+    fn deploy(account_id: &str, code: &[u8]) -> StateChangeWithCause {
+        StateChangeWithCause {
+            cause: StateChangeCause::InitialState,
+            value: StateChangeValue::ContractCodeUpdate {
+                account_id: account_id.parse().unwrap(),
+                code: code.to_vec(),
+            },
+        }
+    }
+
+    #[test]
+    fn test_index_and_query() {
+        let (chain, _, signer) = setup();
+        let store = chain.store().store().clone();
+        let index = ContractCodeIndex::new(store.clone());
+        let genesis = chain.get_block(chain.genesis().hash()).unwrap();
+        let block = Block::empty(&genesis, &*signer);
+        let changes = vec![
+            deploy("c.near", b"vulnerable"),
+            deploy("a.near", b"vulnerable"),
+            deploy("b.near", b"other"),
+            deploy("d.near", b"vulnerable"),
+            deploy("e.near", b"vulnerable"),
+        ];
+        index.index_block(&block, &changes).unwrap();
+
+        // `d.near` has deployed other code since.
+        let code_hash = hash(b"vulnerable");
+        let checked = Cell::new(0);
+        let is_deployed = |account_id: &AccountId| {
+            checked.set(checked.get() + 1);
+            Ok(account_id.as_str() != "d.near")
+        };
+        let page = query_contract_accounts(&store, &code_hash, None, 2, is_deployed).unwrap();
+        let accounts: Vec<_> = page.accounts.iter().map(|a| a.account_id.as_str()).collect();
+        assert_eq!(accounts, vec!["a.near", "c.near"]);
+        assert_eq!(page.accounts[0].block_hash, *block.hash());
+        let cursor = page.next_cursor.unwrap();
+        assert_eq!(cursor.as_str(), "c.near");
+
+        let page =
+            query_contract_accounts(&store, &code_hash, Some(&cursor), 2, is_deployed).unwrap();
+        let accounts: Vec<_> = page.accounts.iter().map(|a| a.account_id.as_str()).collect();
+        assert_eq!(accounts, vec!["e.near"]);
+        assert_eq!(page.next_cursor, None);
+        // Every candidate was checked once.
+        assert_eq!(checked.get(), 4);
+
+        let page = query_contract_accounts(&store, &hash(b"unknown"), None, 10, is_deployed);
+        assert!(page.unwrap().accounts.is_empty());
+    }
+}
@@ -0,0 +1,440 @@
+//! Index of events emitted by contracts.
+//!
+//! Contracts emit events following the event standard (NEP-297) as logs of
+//! the form `EVENT_JSON:{"standard": ..., "version": ..., "event": ...,
+//! "data": ...}`.  Without an index the only way to find the events of a
+//! contract is to scan the execution outcomes of every block.
+//!
+//! When enabled, events in the outcomes of every processed block are written
+//! to [`DBCol::ContractEvents`], keyed by the account of the contract, the
+//! standard, the event name and the height, so that the events of a contract
+//! can be queried page by page, optionally of a single standard or event.
+//! Only successful executions are indexed.
+//!
+//! Blocks are indexed as they are processed, including blocks which end up
+//! off the canonical chain.  Queries skip events of such blocks, while
+//! subscribers get the events of every processed block and have to check
+//! canonicity themselves if it matters to them.
+use std::collections::HashMap;
+use std::sync::Mutex;
+
+use borsh::{BorshDeserialize, BorshSerialize};
+use crossbeam_channel::{Receiver, Sender, TrySendError};
+use near_primitives::block::Block;
+use near_primitives::hash::CryptoHash;
+use near_primitives::serialize::{from_base64, to_base64};
+use near_primitives::transaction::{ExecutionOutcomeWithIdAndProof, ExecutionStatus};
+use near_primitives::types::{AccountId, BlockHeight, ShardId};
+use near_primitives::views::{ContractEventView, ContractEventsView};
+use near_store::{DBCol, Store};
+use tracing::debug;
+
+use crate::{ChainStore, ChainStoreAccess, Error};
+
+/// Prefix of logs which are events.
+pub const EVENT_LOG_PREFIX: &str = "EVENT_JSON:";
+/// Maximum number of events returned by a single query.
+pub const MAX_QUERY_LIMIT: usize = 1000;
+/// Number of events a subscriber can lag behind.  Events which don't fit are
+/// dropped for that subscriber.
+const SUBSCRIPTION_CAPACITY: usize = 10_000;
+
+/// Separates the variable length parts of keys.  Account ids can't contain
+/// it and events which have it in the standard or the name aren't indexed.
+const KEY_SEPARATOR: u8 = 0;
+
+/// Event parsed from a log.
+#[derive(serde::Deserialize, Debug, PartialEq)]
+pub struct EventLog {
+    pub standard: String,
+    pub version: String,
+    pub event: String,
+    #[serde(default)]
+    pub data: Option<serde_json::Value>,
+}
+
+/// Parses a log following the event standard.  Returns `None` for any other
+/// log.
+pub fn parse_event_log(log: &str) -> Option<EventLog> {
+    let event: EventLog = serde_json::from_str(log.strip_prefix(EVENT_LOG_PREFIX)?).ok()?;
+    let is_valid = |s: &str| !s.is_empty() && !s.as_bytes().contains(&KEY_SEPARATOR);
+    if !is_valid(&event.standard) || !is_valid(&event.event) {
+        return None;
+    }
+    Some(event)
+}
+
+#[derive(BorshSerialize, BorshDeserialize)]
+struct Record {
+    block_hash: CryptoHash,
+    version: String,
+    /// Data of the event as JSON, if it has any.
+    data: Option<String>,
+}
+
+/// Prefix of the keys of the events of an account, optionally of a single
+/// standard and event.
+fn key_prefix(account_id: &AccountId, standard: Option<&str>, event: Option<&str>) -> Vec<u8> {
+    let mut key = account_id.as_bytes().to_vec();
+    key.push(KEY_SEPARATOR);
+    for part in [standard, event].into_iter().flatten() {
+        key.extend_from_slice(part.as_bytes());
+        key.push(KEY_SEPARATOR);
+    }
+    key
+}
+
+fn encode_key(event: &ContractEventView) -> Vec<u8> {
+    let mut key = key_prefix(&event.account_id, Some(&event.standard), Some(&event.event));
+    key.extend_from_slice(&event.block_height.to_be_bytes());
+    key.extend_from_slice(&event.block_hash.0);
+    key.extend_from_slice(&event.outcome_id.0);
+    key.extend_from_slice(&event.log_index.to_be_bytes());
+    key
+}
+
+fn decode_event(key: &[u8], value: &[u8]) -> Result<ContractEventView, Error> {
+    let invalid = || Error::Other(format!("invalid contract event key {:?}", key));
+    let mut parts = key.splitn(4, |byte| *byte == KEY_SEPARATOR);
+    let mut next_str = || -> Result<String, Error> {
+        let part = parts.next().ok_or_else(invalid)?;
+        String::from_utf8(part.to_vec()).map_err(|_| invalid())
+    };
+    let account_id = next_str()?.parse().map_err(|_| invalid())?;
+    let standard = next_str()?;
+    let event = next_str()?;
+    let rest = parts.next().ok_or_else(invalid)?;
+    if rest.len() != 8 + 32 + 32 + 4 {
+        return Err(invalid());
+    }
+    let block_height = BlockHeight::from_be_bytes(rest[..8].try_into().unwrap());
+    let outcome_id = CryptoHash::try_from(&rest[40..72]).map_err(|_| invalid())?;
+    let log_index = u32::from_be_bytes(rest[72..].try_into().unwrap());
+    let record = Record::try_from_slice(value)?;
+    let data = match record.data {
+        Some(data) => Some(serde_json::from_str(&data).map_err(|_| invalid())?),
+        None => None,
+    };
+    Ok(ContractEventView {
+        account_id,
+        standard,
+        version: record.version,
+        event,
+        data,
+        block_height,
+        block_hash: record.block_hash,
+        outcome_id,
+        log_index,
+    })
+}
+
+/// Events a subscriber is interested in.  `None` matches anything.
+#[derive(Clone, Debug, Default)]
+pub struct ContractEventsFilter {
+    pub account_id: Option<AccountId>,
+    pub standard: Option<String>,
+    pub event: Option<String>,
+}
+
+impl ContractEventsFilter {
+    pub fn matches(&self, event: &ContractEventView) -> bool {
+        self.account_id.as_ref().map_or(true, |account_id| *account_id == event.account_id)
+            && self.standard.as_ref().map_or(true, |standard| *standard == event.standard)
+            && self.event.as_ref().map_or(true, |name| *name == event.event)
+    }
+}
+
+pub struct ContractEventsIndex {
+    store: Store,
+    subscribers: Mutex<Vec<(ContractEventsFilter, Sender<ContractEventView>)>>,
+}
+
+impl ContractEventsIndex {
+    pub fn new(store: Store) -> Self {
+        Self { store, subscribers: Mutex::new(vec![]) }
+    }
+
+    /// Subscribes to events matching the filter in processed blocks.  The
+    /// subscription ends once the receiver is dropped.
+    pub fn subscribe(&self, filter: ContractEventsFilter) -> Receiver<ContractEventView> {
+        let (sender, receiver) = crossbeam_channel::bounded(SUBSCRIPTION_CAPACITY);
+        self.subscribers.lock().unwrap().push((filter, sender));
+        receiver
+    }
+
+    /// Indexes the events in the outcomes of a processed block and passes
+    /// them on to the subscribers.
+    pub fn index_block(
+        &self,
+        block: &Block,
+        outcomes: &HashMap<ShardId, Vec<ExecutionOutcomeWithIdAndProof>>,
+    ) -> Result<(), Error> {
+        let mut events = vec![];
+        for outcome in outcomes.values().flatten() {
+            let outcome_id = outcome.outcome_with_id.id;
+            let outcome = &outcome.outcome_with_id.outcome;
+            if let ExecutionStatus::Failure(_) | ExecutionStatus::Unknown = outcome.status {
+                continue;
+            }
+            for (log_index, log) in outcome.logs.iter().enumerate() {
+                let EventLog { standard, version, event, data } = match parse_event_log(log) {
+                    Some(event) => event,
+                    None => continue,
+                };
+                events.push(ContractEventView {
+                    account_id: outcome.executor_id.clone(),
+                    standard,
+                    version,
+                    event,
+                    data,
+                    block_height: block.header().height(),
+                    block_hash: *block.hash(),
+                    outcome_id,
+                    log_index: log_index as u32,
+                });
+            }
+        }
+        if events.is_empty() {
+            return Ok(());
+        }
+        let mut store_update = self.store.store_update();
+        for event in &events {
+            let record = Record {
+                block_hash: event.block_hash,
+                version: event.version.clone(),
+                data: event.data.as_ref().map(|data| data.to_string()),
+            };
+            store_update.set_ser(DBCol::ContractEvents, &encode_key(event), &record)?;
+        }
+        store_update.commit()?;
+        self.notify(events);
+        Ok(())
+    }
+
+    fn notify(&self, events: Vec<ContractEventView>) {
+        let mut subscribers = self.subscribers.lock().unwrap();
+        subscribers.retain(|(filter, sender)| {
+            for event in events.iter().filter(|event| filter.matches(event)) {
+                match sender.try_send(event.clone()) {
+                    Ok(()) => {}
+                    Err(TrySendError::Full(_)) => {
+                        debug!(target: "chain", "Contract events subscriber lags behind");
+                        return true;
+                    }
+                    Err(TrySendError::Disconnected(_)) => return false,
+                }
+            }
+            true
+        });
+    }
+}
+
+#[derive(thiserror::Error, Debug)]
+pub enum QueryError {
+    #[error("Invalid query: {0}")]
+    InvalidQuery(String),
+    #[error(transparent)]
+    Chain(#[from] Error),
+}
+
+/// Returns up to `limit` events of the account on the canonical chain,
+/// optionally of a single standard and event, starting after the `cursor`
+/// returned by the previous query.  The index is read from the cursor on, so
+/// paging through all events reads each of them once.
+pub fn query_contract_events(
+    chain_store: &ChainStore,
+    account_id: &AccountId,
+    standard: Option<&str>,
+    event: Option<&str>,
+    cursor: Option<&str>,
+    limit: usize,
+) -> Result<ContractEventsView, QueryError> {
+    if event.is_some() && standard.is_none() {
+        return Err(QueryError::InvalidQuery("event name given without a standard".to_string()));
+    }
+    let prefix = key_prefix(account_id, standard, event);
+    // The smallest key after the cursor, the index is read from there on.
+    let start = match cursor {
+        Some(cursor) => {
+            let mut start = from_base64(cursor)
+                .map_err(|err| QueryError::InvalidQuery(format!("invalid cursor: {}", err)))?;
+            if !start.starts_with(&prefix) {
+                return Err(QueryError::InvalidQuery(
+                    "cursor belongs to a different query".to_string(),
+                ));
+            }
+            start.push(0);
+            start
+        }
+        None => vec![],
+    };
+    let limit = limit.min(MAX_QUERY_LIMIT);
+    let mut events = vec![];
+    let mut next_cursor = None;
+    for item in chain_store.store().iter_prefix_from(DBCol::ContractEvents, &prefix, &start) {
+        let (key, value) = item.map_err(Error::from)?;
+        if events.len() == limit {
+            next_cursor = Some(to_base64(&encode_key(events.last().unwrap())));
+            break;
+        }
+        let event = decode_event(&key, &value)?;
+        match chain_store.get_block_hash_by_height(event.block_height) {
+            Ok(block_hash) if block_hash == event.block_hash => {}
+            Ok(_) | Err(Error::DBNotFoundErr(_)) => continue,
+            Err(err) => return Err(err.into()),
+        }
+        events.push(event);
+    }
+    Ok(ContractEventsView { events, next_cursor })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{
+        parse_event_log, query_contract_events, ContractEventsFilter, ContractEventsIndex,
+        EventLog, QueryError,
+    };
+    use crate::test_utils::setup;
+    use crate::{Block, ChainStoreAccess};
+    use assert_matches::assert_matches;
+    use near_primitives::hash::hash;
+    use near_primitives::transaction::{
+        ExecutionOutcome, ExecutionOutcomeWithId, ExecutionOutcomeWithIdAndProof, ExecutionStatus,
+    };
+    use near_primitives::types::AccountId;
+    use std::collections::HashMap;
+
+    #[test]
+    fn test_parse_event_log() {
+        let log =
+            r#"EVENT_JSON:{"standard":"nep171","version":"1.0.0","event":"nft_mint","data":[1]}"#;
+        assert_eq!(
+            parse_event_log(log),
+            Some(EventLog {
+                standard: "nep171".to_string(),
+                version: "1.0.0".to_string(),
+                event: "nft_mint".to_string(),
+                data: Some(serde_json::json!([1])),
+            })
+        );
+        assert_eq!(parse_event_log(r#"{"standard":"nep171"}"#), None);
+        assert_eq!(parse_event_log("EVENT_JSON:not json"), None);
+        assert_eq!(
+            parse_event_log(r#"EVENT_JSON:{"standard":"","version":"1","event":"a"}"#),
+            None
+        );
+    }
+
+    fn outcome(
+        block: &Block,
+        executor_id: &AccountId,
+        logs: &[&str],
+    ) -> ExecutionOutcomeWithIdAndProof {
+        ExecutionOutcomeWithIdAndProof {
+            proof: vec![],
+            block_hash: *block.hash(),
+            outcome_with_id: ExecutionOutcomeWithId {
+                id: hash(logs.join("").as_bytes()),
+                outcome: ExecutionOutcome {
+                    logs: logs.iter().map(|log| log.to_string()).collect(),
+                    executor_id: executor_id.clone(),
+                    status: ExecutionStatus::SuccessValue(vec![]),
+                    ..ExecutionOutcome::default()
+                },
+            },
+        }
+    }
+
+    fn event_log(event: &str, height: u64) -> String {
+        format!(
+            r#"EVENT_JSON:{{"standard":"nep141","version":"1.0.0","event":"{}","data":{}}}"#,
+            event, height
+        )
+    }
+
+    #[test]
+    fn test_index_and_query() {
+        let (mut chain, _, signer) = setup();
+        let index = ContractEventsIndex::new(chain.store().store().clone());
+        let token: AccountId = "token.near".parse().unwrap();
+        let other: AccountId = "other.near".parse().unwrap();
+        let transfers = index.subscribe(ContractEventsFilter {
+            event: Some("ft_transfer".to_string()),
+            ..ContractEventsFilter::default()
+        });
+        let mut blocks = vec![chain.get_block(chain.genesis().hash()).unwrap()];
+        for height in 1..4 {
+            let block = Block::empty(blocks.last().unwrap(), &*signer);
+            blocks.push(block.clone());
+            chain.process_block_test(&None, block.clone()).unwrap();
+            let mint = event_log("ft_mint", height);
+            let transfer = event_log("ft_transfer", height);
+            let outcomes = HashMap::from([(
+                0,
+                vec![
+                    outcome(&block, &token, &[&mint, "not an event", &transfer]),
+                    outcome(&block, &other, &[&mint]),
+                ],
+            )]);
+            index.index_block(&block, &outcomes).unwrap();
+        }
+        // An event of a block which isn't on the canonical chain.
+        let fork = Block::empty_with_height(&blocks[1], 3, &*signer);
+        let outcomes =
+            HashMap::from([(0, vec![outcome(&fork, &token, &[&event_log("ft_mint", 100)])])]);
+        index.index_block(&fork, &outcomes).unwrap();
+
+        // Events are ordered by name and then by height.
+        let mut events = vec![];
+        let mut cursor = None;
+        loop {
+            let page = query_contract_events(
+                chain.store(),
+                &token,
+                Some("nep141"),
+                None,
+                cursor.as_deref(),
+                2,
+            )
+            .unwrap();
+            events.extend(page.events);
+            cursor = match page.next_cursor {
+                Some(cursor) => Some(cursor),
+                None => break,
+            };
+        }
+        let events: Vec<_> = events
+            .iter()
+            .map(|event| (event.event.as_str(), event.block_height, event.data.clone()))
+            .collect();
+        assert_eq!(
+            events,
+            [
+                ("ft_mint", 1, Some(1.into())),
+                ("ft_mint", 2, Some(2.into())),
+                ("ft_mint", 3, Some(3.into())),
+                ("ft_transfer", 1, Some(1.into())),
+                ("ft_transfer", 2, Some(2.into())),
+                ("ft_transfer", 3, Some(3.into())),
+            ]
+        );
+
+        let page = query_contract_events(
+            chain.store(),
+            &other,
+            Some("nep141"),
+            Some("ft_transfer"),
+            None,
+            10,
+        )
+        .unwrap();
+        assert!(page.events.is_empty());
+        assert_matches!(
+            query_contract_events(chain.store(), &token, None, Some("ft_mint"), None, 10),
+            Err(QueryError::InvalidQuery(_))
+        );
+
+        let received: Vec<_> = transfers.try_iter().map(|event| event.block_height).collect();
+        assert_eq!(received, [1, 2, 3]);
+    }
+}
@@ -9,7 +9,7 @@ use near_primitives::hash::CryptoHash;
 use near_primitives::time::Clock;
 use near_primitives::types::{AccountId, ApprovalStake, Balance, BlockHeight, BlockHeightDelta};
 use near_primitives::validator_signer::ValidatorSigner;
-use tracing::info;
+use tracing::{error, info};
 
 /// Have that many iterations in the timer instead of `loop` to prevent potential bugs from blocking
 /// the node
@@ -46,6 +46,17 @@ pub enum DoomslugBlockProductionReadiness {
     ReadySince(Instant),
 }
 
+/// Reason to refuse signing an approval which, together with an approval signed before, would
+/// violate the slashing rules.
+#[derive(PartialEq, Eq, Debug, Clone, Copy)]
+pub enum ApprovalConflict {
+    /// An approval with the same or a larger target height was already signed.
+    TargetHeight { largest_target_height: BlockHeight },
+    /// The skip would jump over a block endorsed before, i.e. its parent is below the endorsed
+    /// block and its target above it.
+    SkipOverEndorsement { endorsed_height: BlockHeight },
+}
+
 struct DoomslugTimer {
     started: Instant,
     last_endorsement_sent: Instant,
@@ -98,6 +109,11 @@ pub struct Doomslug {
     approval_tracking: HashMap<BlockHeight, DoomslugApprovalsTrackersAtHeight>,
     /// Largest target height for which we issued an approval
     largest_target_height: BlockHeight,
+    /// Largest height of a block which we endorsed
+    largest_endorsed_height: BlockHeight,
+    /// Number of approvals not signed because they would conflict with the signed ones, since the
+    /// last call to `take_num_refused_approvals`
+    num_refused_approvals: u64,
     /// Largest height for which we saw a block containing 1/2 endorsements in it
     largest_final_height: BlockHeight,
     /// Largest height for which we saw threshold approvals (and thus can potentially create a block)
@@ -330,6 +346,7 @@ impl DoomslugApprovalsTrackersAtHeight {
 impl Doomslug {
     pub fn new(
         largest_target_height: BlockHeight,
+        largest_endorsed_height: BlockHeight,
         endorsement_delay: Duration,
         min_delay: Duration,
         delay_step: Duration,
@@ -340,6 +357,8 @@ impl Doomslug {
         Doomslug {
             approval_tracking: HashMap::new(),
             largest_target_height,
+            largest_endorsed_height,
+            num_refused_approvals: 0,
             largest_approval_height: 0,
             largest_final_height: 0,
             largest_threshold_height: 0,
@@ -390,6 +409,15 @@ impl Doomslug {
         self.largest_target_height
     }
 
+    pub fn get_largest_endorsed_height(&self) -> BlockHeight {
+        self.largest_endorsed_height
+    }
+
+    /// Returns the number of approvals refused since the previous call.
+    pub fn take_num_refused_approvals(&mut self) -> u64 {
+        std::mem::take(&mut self.num_refused_approvals)
+    }
+
     pub fn get_timer_height(&self) -> BlockHeight {
         self.timer.height
     }
@@ -444,11 +472,10 @@ impl Doomslug {
                 && cur_time >= self.timer.last_endorsement_sent + self.timer.endorsement_delay
             {
                 if tip_height >= self.largest_target_height {
-                    self.largest_target_height = tip_height + 1;
-
                     if let Some(approval) = self.create_approval(tip_height + 1) {
                         ret.push(approval);
                     }
+                    self.largest_target_height = tip_height + 1;
                     self.update_history(ApprovalHistoryEntry {
                         parent_height: tip_height,
                         target_height: tip_height + 1,
@@ -469,19 +496,19 @@ impl Doomslug {
             if cur_time >= self.timer.started + skip_delay {
                 debug_assert!(!self.endorsement_pending);
 
-                self.largest_target_height =
-                    std::cmp::max(self.timer.height + 1, self.largest_target_height);
-
-                if let Some(approval) = self.create_approval(self.timer.height + 1) {
-                    ret.push(approval);
+                if self.timer.height >= self.largest_target_height {
+                    if let Some(approval) = self.create_approval(self.timer.height + 1) {
+                        ret.push(approval);
+                    }
+                    self.largest_target_height = self.timer.height + 1;
+                    self.update_history(ApprovalHistoryEntry {
+                        parent_height: tip_height,
+                        target_height: self.timer.height + 1,
+                        timer_started_ago_millis: self.timer.started.elapsed().as_millis() as u64,
+                        expected_delay_millis: skip_delay.as_millis() as u64,
+                        approval_creation_time: chrono::Utc::now(),
+                    });
                 }
-                self.update_history(ApprovalHistoryEntry {
-                    parent_height: tip_height,
-                    target_height: self.timer.height + 1,
-                    timer_started_ago_millis: self.timer.started.elapsed().as_millis() as u64,
-                    expected_delay_millis: skip_delay.as_millis() as u64,
-                    approval_creation_time: chrono::Utc::now(),
-                });
 
                 // Restart the timer
                 self.timer.started += skip_delay;
@@ -494,10 +521,52 @@ impl Doomslug {
         ret
     }
 
-    fn create_approval(&self, target_height: BlockHeight) -> Option<Approval> {
-        self.signer.as_ref().map(|signer| {
-            Approval::new(self.tip.block_hash, self.tip.height, target_height, &**signer)
-        })
+    /// Checks that an approval from the current tip to `target_height` can't be slashed together
+    /// with any approval signed before, including the ones signed before a restart or on another
+    /// fork.  Every approval must target a height above all previously signed approvals, and a
+    /// skip must not jump over a block endorsed before.
+    pub fn check_approval(&self, target_height: BlockHeight) -> Result<(), ApprovalConflict> {
+        let parent_height = self.tip.height;
+        if target_height <= self.largest_target_height {
+            return Err(ApprovalConflict::TargetHeight {
+                largest_target_height: self.largest_target_height,
+            });
+        }
+        if target_height != parent_height + 1
+            && parent_height < self.largest_endorsed_height
+            && target_height > self.largest_endorsed_height
+        {
+            return Err(ApprovalConflict::SkipOverEndorsement {
+                endorsed_height: self.largest_endorsed_height,
+            });
+        }
+        Ok(())
+    }
+
+    /// Sets the signer of the approvals, e.g. when the key of the validator
+    /// staked in the epoch of the next block differs from the current one.
+    pub fn set_signer(&mut self, signer: Option<Arc<dyn ValidatorSigner>>) {
+        self.signer = signer;
+    }
+
+    fn create_approval(&mut self, target_height: BlockHeight) -> Option<Approval> {
+        let signer = self.signer.clone()?;
+        if let Err(conflict) = self.check_approval(target_height) {
+            error!(
+                target: "doomslug",
+                ?conflict,
+                parent_height = self.tip.height,
+                target_height,
+                "Refusing to sign an approval conflicting with an approval signed before"
+            );
+            self.num_refused_approvals += 1;
+            return None;
+        }
+        if target_height == self.tip.height + 1 {
+            self.largest_endorsed_height =
+                std::cmp::max(self.tip.height, self.largest_endorsed_height);
+        }
+        Some(Approval::new(self.tip.block_hash, self.tip.height, target_height, &*signer))
     }
 
     /// Determines whether a block has enough approvals to be produced.
@@ -513,6 +582,17 @@ impl Doomslug {
         mode: DoomslugThresholdMode,
         approvals: &[Option<Signature>],
         stakes: &[(Balance, Balance, bool)],
+    ) -> bool {
+        let approvers = approvals.iter().map(Option::is_some).collect::<Vec<_>>();
+        Self::can_block_with_approvers_be_produced(mode, &approvers, stakes)
+    }
+
+    /// Same as `can_approved_block_be_produced`, but takes which of the validators approved the
+    /// block instead of their signatures.
+    pub fn can_block_with_approvers_be_produced(
+        mode: DoomslugThresholdMode,
+        approvers: &[bool],
+        stakes: &[(Balance, Balance, bool)],
     ) -> bool {
         if mode == DoomslugThresholdMode::NoApprovals {
             return true;
@@ -521,18 +601,18 @@ impl Doomslug {
         let threshold1 = stakes.iter().map(|(x, _, _)| x).sum::<Balance>() * 2 / 3;
         let threshold2 = stakes.iter().map(|(_, x, _)| x).sum::<Balance>() * 2 / 3;
 
-        let approved_stake1 = approvals
+        let approved_stake1 = approvers
             .iter()
             .zip(stakes.iter())
             .filter(|(_, (_, _, is_slashed))| !*is_slashed)
-            .map(|(approval, (stake, _, _))| if approval.is_some() { *stake } else { 0 })
+            .map(|(approved, (stake, _, _))| if *approved { *stake } else { 0 })
             .sum::<Balance>();
 
-        let approved_stake2 = approvals
+        let approved_stake2 = approvers
             .iter()
             .zip(stakes.iter())
             .filter(|(_, (_, _, is_slashed))| !*is_slashed)
-            .map(|(approval, (_, stake, _))| if approval.is_some() { *stake } else { 0 })
+            .map(|(approved, (_, stake, _))| if *approved { *stake } else { 0 })
             .sum::<Balance>();
 
         (approved_stake1 > threshold1 || threshold1 == 0)
@@ -617,20 +697,22 @@ impl Doomslug {
         ret
     }
 
-    /// Processes single approval
+    /// Processes single approval.  Returns false if the approval was ignored
+    /// because its target height is too far from the tip.
     pub fn on_approval_message(
         &mut self,
         now: Instant,
         approval: &Approval,
         stakes: &[(ApprovalStake, bool)],
-    ) {
+    ) -> bool {
         if approval.target_height < self.tip.height
             || approval.target_height > self.tip.height + MAX_HEIGHTS_AHEAD_TO_STORE_APPROVALS
         {
-            return;
+            return false;
         }
 
         let _ = self.on_approval_message_internal(now, approval, stakes);
+        true
     }
 
     /// Gets the current status of approvals for a given height.
@@ -715,16 +797,18 @@ mod tests {
     use near_primitives::hash::hash;
     use near_primitives::time::Clock;
     use near_primitives::types::ApprovalStake;
-    use near_primitives::validator_signer::InMemoryValidatorSigner;
+    use near_primitives::validator_signer::{InMemoryValidatorSigner, ValidatorSigner};
 
     use crate::doomslug::{
-        DoomslugApprovalsTrackersAtHeight, DoomslugBlockProductionReadiness, DoomslugThresholdMode,
+        ApprovalConflict, DoomslugApprovalsTrackersAtHeight, DoomslugBlockProductionReadiness,
+        DoomslugThresholdMode,
     };
     use crate::Doomslug;
 
     #[test]
     fn test_endorsements_and_skips_basic() {
         let mut ds = Doomslug::new(
+            0,
             0,
             Duration::from_millis(400),
             Duration::from_millis(1000),
@@ -842,16 +926,14 @@ mod tests {
         assert_eq!(ds.process_timer(now + Duration::from_millis(400)), vec![]);
 
         // The block height was less than the timer height, and thus the timer was reset.
-        // The wait time for height 7 with last ds final block at 5 is 1100
-        assert_eq!(ds.process_timer(now + Duration::from_millis(1099)), vec![]);
-
-        match ds.process_timer(now + Duration::from_millis(1100)) {
-            approvals if approvals.len() == 0 => assert!(false),
-            approvals => {
-                assert_eq!(approvals[0].inner, ApprovalInner::Skip(6));
-                assert_eq!(approvals[0].target_height, 8);
-            }
-        }
+        // The wait time for height 7 with last ds final block at 5 is 1100, but height 8 was
+        // already targeted by a skip from 5, so skipping to it from 6 would conflict
+        assert_eq!(ds.process_timer(now + Duration::from_millis(1100)), vec![]);
+        assert_eq!(
+            ds.check_approval(8),
+            Err(ApprovalConflict::TargetHeight { largest_target_height: ds.largest_target_height })
+        );
+        assert_eq!(ds.take_num_refused_approvals(), 0);
     }
 
     #[test]
@@ -885,6 +967,7 @@ mod tests {
             "test",
         ));
         let mut ds = Doomslug::new(
+            0,
             0,
             Duration::from_millis(400),
             Duration::from_millis(1000),
@@ -1216,4 +1299,93 @@ mod tests {
             5
         );
     }
+
+    #[test]
+    fn test_skip_below_endorsement_refused() {
+        let signer: Arc<dyn ValidatorSigner> = Arc::new(InMemoryValidatorSigner::from_seed(
+            "test".parse().unwrap(),
+            KeyType::ED25519,
+            "test",
+        ));
+        let new_doomslug = |largest_target_height, largest_endorsed_height| {
+            Doomslug::new(
+                largest_target_height,
+                largest_endorsed_height,
+                Duration::from_millis(400),
+                Duration::from_millis(1000),
+                Duration::from_millis(100),
+                Duration::from_millis(3000),
+                Some(signer.clone()),
+                DoomslugThresholdMode::TwoThirds,
+            )
+        };
+        // The node endorses the block at height 10.
+        let mut ds = new_doomslug(0, 0);
+        let now = Clock::instant();
+        ds.set_tip(now, hash(&[10]), 10, 10);
+        let approvals = ds.process_timer(now + Duration::from_millis(400));
+        assert_eq!(approvals.len(), 1);
+        assert_eq!(approvals[0].inner, ApprovalInner::Endorsement(hash(&[10])));
+        assert_eq!(approvals[0].target_height, 11);
+
+        // After a restart on a fork whose tip is at 8, the skip to 10 doesn't jump over the
+        // endorsed block, but targets a lower height than the endorsement.
+        let mut ds = new_doomslug(ds.get_largest_target_height(), ds.get_largest_endorsed_height());
+        ds.set_tip(now, hash(&[8]), 8, 8);
+        assert_eq!(
+            ds.check_approval(10),
+            Err(ApprovalConflict::TargetHeight { largest_target_height: 11 })
+        );
+        assert_eq!(ds.process_timer(now + Duration::from_millis(1000)).len(), 0);
+        assert_eq!(ds.take_num_refused_approvals(), 0);
+    }
+
+    #[test]
+    fn test_approvals_conflicting_with_signed_ones_refused() {
+        // The node endorsed a block at height 10 and restarted on a fork whose tip is at 8.
+        let mut ds = Doomslug::new(
+            11,
+            10,
+            Duration::from_millis(400),
+            Duration::from_millis(1000),
+            Duration::from_millis(100),
+            Duration::from_millis(3000),
+            Some(Arc::new(InMemoryValidatorSigner::from_seed(
+                "test".parse().unwrap(),
+                KeyType::ED25519,
+                "test",
+            ))),
+            DoomslugThresholdMode::TwoThirds,
+        );
+        let now = Clock::instant();
+        ds.set_tip(now, hash(&[8]), 8, 8);
+        assert_eq!(
+            ds.check_approval(9),
+            Err(ApprovalConflict::TargetHeight { largest_target_height: 11 })
+        );
+        assert_eq!(
+            ds.check_approval(11),
+            Err(ApprovalConflict::TargetHeight { largest_target_height: 11 })
+        );
+        assert_eq!(
+            ds.check_approval(12),
+            Err(ApprovalConflict::SkipOverEndorsement { endorsed_height: 10 })
+        );
+
+        // Neither the skip to 10 nor the one to 11 is created.
+        assert_eq!(ds.process_timer(now + Duration::from_millis(1000)).len(), 0);
+        assert_eq!(ds.process_timer(now + Duration::from_millis(2000)).len(), 0);
+        assert_eq!(ds.take_num_refused_approvals(), 0);
+
+        // The skip to 12 would jump over the endorsed block, so it isn't signed.
+        assert_eq!(ds.process_timer(now + Duration::from_millis(3100)).len(), 0);
+        assert_eq!(ds.take_num_refused_approvals(), 1);
+
+        // Once the tip reaches the endorsed block, approvals are signed again.
+        ds.set_tip(now + Duration::from_millis(3100), hash(&[12]), 12, 12);
+        let approvals = ds.process_timer(now + Duration::from_millis(3500));
+        assert_eq!(approvals.len(), 1);
+        assert_eq!(approvals[0].inner, ApprovalInner::Endorsement(hash(&[12])));
+        assert_eq!(ds.get_largest_endorsed_height(), 12);
+    }
 }
@@ -0,0 +1,1191 @@
+//! Support for running the transaction runtime outside of the node process.
+//!
+//! [`ExternalRuntimeAdapter`] implements [`RuntimeAdapter`] by forwarding the
+//! execution entry points (`validate_tx`, `prepare_transactions`, chunk
+//! application and `query`) to an [`ExecutionEngine`], which may live behind
+//! IPC, gRPC or any other transport able to carry opaque bytes.  Epoch
+//! management, storage and state sync are still served by a local runtime.
+//!
+//! Requests and responses are borsh-encoded [`ExternalRuntimeRequest`] and
+//! [`ExternalRuntimeResponse`] values.  An engine process answers them with
+//! [`serve_request`] on top of its own `RuntimeAdapter`.
+use std::cmp::Ordering;
+use std::collections::HashMap;
+use std::sync::Arc;
+use std::time::Instant;
+
+use borsh::{BorshDeserialize, BorshSerialize};
+use near_chain_configs::ProtocolConfig;
+use near_chain_primitives::error::QueryError;
+use near_chain_primitives::Error;
+use near_client_primitives::types::StateSplitApplyingStatus;
+use near_crypto::Signature;
+use near_epoch_manager::EpochManagerAdapter;
+use near_pool::types::{PoolIterator, TransactionGroup};
+use near_primitives::challenge::{ChallengesResult, PartialState};
+use near_primitives::epoch_manager::block_info::BlockInfo;
+use near_primitives::epoch_manager::epoch_info::EpochInfo;
+use near_primitives::epoch_manager::ShardConfig;
+use near_primitives::errors::{EpochError, InvalidTxError, StorageError};
+use near_primitives::hash::CryptoHash;
+use near_primitives::receipt::Receipt;
+use near_primitives::sandbox::state_patch::SandboxStatePatch;
+use near_primitives::shard_layout::{ShardLayout, ShardUId};
+use near_primitives::sharding::ChunkHash;
+use near_primitives::state_part::PartId;
+use near_primitives::time::Clock;
+use near_primitives::transaction::{ExecutionOutcomeWithId, SignedTransaction};
+use near_primitives::types::validator_stake::{ValidatorStake, ValidatorStakeIter};
+use near_primitives::types::{
+    AccountId, ApprovalStake, Balance, BlockHeight, EpochHeight, EpochId, Gas,
+    RawStateChangesWithTrieKey, ShardId, StateChangesForSplitStates, StateRoot, StateRootNode,
+    ValidatorInfoIdentifier,
+};
+use near_primitives::version::ProtocolVersion;
+use near_primitives::views::{
+    BlockProducerProofView, EpochRandomnessView, EpochValidatorInfo, QueryRequest, QueryResponse,
+};
+use near_store::flat_state::{ChainAccessForFlatStorage, FlatStorageState, FlatStorageStateStatus};
+use near_store::{
+    PartialStorage, ShardTries, Store, StoreUpdate, Trie, TrieChanges, WrappedTrieChanges,
+};
+
+use crate::types::{ApplySplitStateResult, ApplyTransactionResult, BlockHeader, BlockHeaderInfo};
+use crate::RuntimeAdapter;
+
+/// Default number of pool transactions offered to the engine by the first
+/// request of a `prepare_transactions` call.
+pub const DEFAULT_PREPARE_BATCH_SIZE: usize = 100;
+
+/// Transport to an out-of-process execution engine.
+///
+/// Implementations carry a borsh-encoded [`ExternalRuntimeRequest`] to the
+/// engine and return its borsh-encoded [`ExternalRuntimeResponse`].  Transport
+/// failures should be reported as `Error::Other`.
+pub trait ExecutionEngine: Send + Sync {
+    fn call(&self, request: Vec<u8>) -> Result<Vec<u8>, Error>;
+}
+
+/// Error returned by the engine for a runtime call.
+///
+/// Storage errors are preserved so that callers can still tell them apart,
+/// everything else is flattened into a message.
+#[derive(BorshSerialize, BorshDeserialize, Debug, Clone, PartialEq, Eq)]
+pub enum ExternalRuntimeError {
+    StorageError(StorageError),
+    Other(String),
+}
+
+impl From<Error> for ExternalRuntimeError {
+    fn from(error: Error) -> Self {
+        match error {
+            Error::StorageError(err) => ExternalRuntimeError::StorageError(err),
+            err => ExternalRuntimeError::Other(err.to_string()),
+        }
+    }
+}
+
+impl From<ExternalRuntimeError> for Error {
+    fn from(error: ExternalRuntimeError) -> Self {
+        match error {
+            ExternalRuntimeError::StorageError(err) => Error::StorageError(err),
+            ExternalRuntimeError::Other(msg) => Error::Other(msg),
+        }
+    }
+}
+
+#[derive(BorshSerialize, BorshDeserialize, Debug, Clone)]
+pub struct ValidateTxRequest {
+    pub gas_price: Balance,
+    pub state_root: Option<StateRoot>,
+    pub transaction: SignedTransaction,
+    pub verify_signature: bool,
+    pub epoch_id: EpochId,
+    pub current_protocol_version: ProtocolVersion,
+}
+
+/// Pool iterators and the chain validation closure cannot cross a process
+/// boundary, so the adapter pulls a batch of candidates out of the pool,
+/// filters them with the chain validation and sends the survivors in pull
+/// order.  The engine offers its runtime the transactions selected from the
+/// previous batches, then the candidates, in that same order.
+#[derive(BorshSerialize, BorshDeserialize, Debug, Clone)]
+pub struct PrepareTransactionsRequest {
+    pub gas_price: Balance,
+    pub gas_limit: Gas,
+    pub max_transactions: Option<u64>,
+    pub epoch_id: EpochId,
+    pub shard_id: ShardId,
+    pub state_root: StateRoot,
+    pub next_block_height: BlockHeight,
+    pub selected: Vec<SignedTransaction>,
+    pub candidates: Vec<SignedTransaction>,
+    pub current_protocol_version: ProtocolVersion,
+}
+
+#[derive(BorshSerialize, BorshDeserialize, Debug, Clone)]
+pub struct PrepareTransactionsResponse {
+    /// Transactions selected from the previous batches and the candidates.
+    pub transactions: Vec<SignedTransaction>,
+    /// Number of candidates the runtime pulled.  It stops pulling once the
+    /// limits are reached, in which case the rest of them weren't considered.
+    pub num_pulled_candidates: u64,
+}
+
+#[derive(BorshSerialize, BorshDeserialize, Debug, Clone)]
+pub struct ApplyTransactionsRequest {
+    pub shard_id: ShardId,
+    pub state_root: StateRoot,
+    pub height: BlockHeight,
+    pub block_timestamp: u64,
+    pub prev_block_hash: CryptoHash,
+    pub block_hash: CryptoHash,
+    pub receipts: Vec<Receipt>,
+    pub transactions: Vec<SignedTransaction>,
+    pub last_validator_proposals: Vec<ValidatorStake>,
+    pub gas_price: Balance,
+    pub gas_limit: Gas,
+    pub challenges_result: ChallengesResult,
+    pub random_seed: CryptoHash,
+    pub generate_storage_proof: bool,
+    pub is_new_chunk: bool,
+    pub is_first_block_with_chunk_of_version: bool,
+    pub use_flat_storage: bool,
+}
+
+/// Serializable form of [`ApplyTransactionResult`].
+#[derive(BorshSerialize, BorshDeserialize, Debug, Clone)]
+pub struct ApplyTransactionsResponse {
+    pub shard_uid: ShardUId,
+    pub trie_changes: TrieChanges,
+    pub state_changes: Vec<RawStateChangesWithTrieKey>,
+    pub new_root: StateRoot,
+    pub outcomes: Vec<ExecutionOutcomeWithId>,
+    pub outgoing_receipts: Vec<Receipt>,
+    pub validator_proposals: Vec<ValidatorStake>,
+    pub total_gas_burnt: Gas,
+    pub total_balance_burnt: Balance,
+    pub proof: Option<PartialState>,
+    pub processed_delayed_receipts: Vec<Receipt>,
+}
+
+impl ApplyTransactionsResponse {
+    fn from_result(result: ApplyTransactionResult) -> Self {
+        Self {
+            shard_uid: result.trie_changes.shard_uid(),
+            trie_changes: result.trie_changes.trie_changes().clone(),
+            state_changes: result.trie_changes.state_changes().to_vec(),
+            new_root: result.new_root,
+            outcomes: result.outcomes,
+            outgoing_receipts: result.outgoing_receipts,
+            validator_proposals: result.validator_proposals,
+            total_gas_burnt: result.total_gas_burnt,
+            total_balance_burnt: result.total_balance_burnt,
+            proof: result.proof.map(|proof| proof.nodes),
+            processed_delayed_receipts: result.processed_delayed_receipts,
+        }
+    }
+
+    /// Rebuilds the result on the node side.  Trie changes are bound to the
+    /// node's own `ShardTries` so that they are written to the local store.
+    fn into_result(self, tries: ShardTries, block_hash: CryptoHash) -> ApplyTransactionResult {
+        ApplyTransactionResult {
+            trie_changes: WrappedTrieChanges::new(
+                tries,
+                self.shard_uid,
+                self.trie_changes,
+                self.state_changes,
+                block_hash,
+            ),
+            new_root: self.new_root,
+            outcomes: self.outcomes,
+            outgoing_receipts: self.outgoing_receipts,
+            validator_proposals: self.validator_proposals,
+            total_gas_burnt: self.total_gas_burnt,
+            total_balance_burnt: self.total_balance_burnt,
+            proof: self.proof.map(|nodes| PartialStorage { nodes }),
+            processed_delayed_receipts: self.processed_delayed_receipts,
+        }
+    }
+}
+
+#[derive(BorshSerialize, BorshDeserialize, Debug, Clone)]
+pub struct QueryRuntimeRequest {
+    pub shard_uid: ShardUId,
+    pub state_root: StateRoot,
+    pub block_height: BlockHeight,
+    pub block_timestamp: u64,
+    pub prev_block_hash: CryptoHash,
+    pub block_hash: CryptoHash,
+    pub epoch_id: EpochId,
+    pub request: QueryRequest,
+}
+
+#[derive(BorshSerialize, BorshDeserialize, Debug, Clone)]
+pub enum ExternalRuntimeRequest {
+    ValidateTx(ValidateTxRequest),
+    PrepareTransactions(PrepareTransactionsRequest),
+    ApplyTransactions(ApplyTransactionsRequest),
+    Query(QueryRuntimeRequest),
+}
+
+#[derive(BorshSerialize, BorshDeserialize, Debug, Clone)]
+pub enum ExternalRuntimeResponse {
+    ValidateTx(Result<Option<InvalidTxError>, ExternalRuntimeError>),
+    PrepareTransactions(Result<PrepareTransactionsResponse, ExternalRuntimeError>),
+    ApplyTransactions(Result<ApplyTransactionsResponse, ExternalRuntimeError>),
+    Query(Result<QueryResponse, QueryError>),
+}
+
+/// Handles a single borsh-encoded [`ExternalRuntimeRequest`] with `runtime`.
+///
+/// This is the engine side of the protocol: a server only has to read
+/// requests off its transport and write back whatever this returns.
+pub fn serve_request(runtime: &dyn RuntimeAdapter, request: &[u8]) -> Result<Vec<u8>, Error> {
+    let response = match ExternalRuntimeRequest::try_from_slice(request)? {
+        ExternalRuntimeRequest::ValidateTx(req) => ExternalRuntimeResponse::ValidateTx(
+            runtime
+                .validate_tx(
+                    req.gas_price,
+                    req.state_root,
+                    &req.transaction,
+                    req.verify_signature,
+                    &req.epoch_id,
+                    req.current_protocol_version,
+                )
+                .map_err(Into::into),
+        ),
+        ExternalRuntimeRequest::PrepareTransactions(req) => {
+            let num_selected = req.selected.len();
+            let mut iter = CandidatesIterator::new(req.selected.into_iter().chain(req.candidates));
+            ExternalRuntimeResponse::PrepareTransactions(
+                runtime
+                    .prepare_transactions(
+                        req.gas_price,
+                        req.gas_limit,
+                        req.max_transactions,
+                        &req.epoch_id,
+                        req.shard_id,
+                        req.state_root,
+                        req.next_block_height,
+                        &mut iter,
+                        // Candidates were already checked by the chain on the node side.
+                        &mut |_| true,
+                        req.current_protocol_version,
+                        // The node already collected the candidates before its deadline.
+                        None,
+                    )
+                    .map(|transactions| PrepareTransactionsResponse {
+                        transactions,
+                        num_pulled_candidates: iter.pulled.saturating_sub(num_selected) as u64,
+                    })
+                    .map_err(Into::into),
+            )
+        }
+        ExternalRuntimeRequest::ApplyTransactions(req) => {
+            ExternalRuntimeResponse::ApplyTransactions(
+                runtime
+                    .apply_transactions_with_optional_storage_proof(
+                        req.shard_id,
+                        &req.state_root,
+                        req.height,
+                        req.block_timestamp,
+                        &req.prev_block_hash,
+                        &req.block_hash,
+                        &req.receipts,
+                        &req.transactions,
+                        ValidatorStakeIter::new(&req.last_validator_proposals),
+                        req.gas_price,
+                        req.gas_limit,
+                        &req.challenges_result,
+                        req.random_seed,
+                        req.generate_storage_proof,
+                        req.is_new_chunk,
+                        req.is_first_block_with_chunk_of_version,
+                        SandboxStatePatch::default(),
+                        req.use_flat_storage,
+                    )
+                    .map(ApplyTransactionsResponse::from_result)
+                    .map_err(Into::into),
+            )
+        }
+        ExternalRuntimeRequest::Query(req) => ExternalRuntimeResponse::Query(runtime.query(
+            req.shard_uid,
+            &req.state_root,
+            req.block_height,
+            req.block_timestamp,
+            &req.prev_block_hash,
+            &req.block_hash,
+            &req.epoch_id,
+            &req.request,
+        )),
+    };
+    Ok(response.try_to_vec()?)
+}
+
+/// Replays candidates of a [`PrepareTransactionsRequest`] one transaction per
+/// group, so that each of them is considered exactly once and in order.
+struct CandidatesIterator {
+    groups: std::vec::IntoIter<TransactionGroup>,
+    current: Option<TransactionGroup>,
+    /// Number of candidates pulled and not put back.
+    pulled: usize,
+}
+
+impl CandidatesIterator {
+    fn new(candidates: impl Iterator<Item = SignedTransaction>) -> Self {
+        let groups: Vec<_> = candidates.map(TransactionGroup::from_transaction).collect();
+        Self { groups: groups.into_iter(), current: None, pulled: 0 }
+    }
+}
+
+impl PoolIterator for CandidatesIterator {
+    fn next(&mut self) -> Option<&mut TransactionGroup> {
+        self.current = self.groups.next();
+        if self.current.is_some() {
+            self.pulled += 1;
+        }
+        self.current.as_mut()
+    }
+
+    /// Runtimes only put back the candidates they pulled last, so the node
+    /// puts them back into its pool as if they weren't pulled at all.
+    fn put_back(&mut self, _transaction: SignedTransaction) {
+        self.pulled -= 1;
+    }
+}
+
+/// Engine which serves requests in-process with a local runtime.
+///
+/// Every call still goes through the wire encoding, which makes it suitable
+/// for checking that a runtime behaves the same behind the protocol.
+pub struct LocalExecutionEngine {
+    runtime: Arc<dyn RuntimeAdapter>,
+}
+
+impl LocalExecutionEngine {
+    pub fn new(runtime: Arc<dyn RuntimeAdapter>) -> Self {
+        Self { runtime }
+    }
+}
+
+impl ExecutionEngine for LocalExecutionEngine {
+    fn call(&self, request: Vec<u8>) -> Result<Vec<u8>, Error> {
+        serve_request(self.runtime.as_ref(), &request)
+    }
+}
+
+/// [`RuntimeAdapter`] that executes transactions and queries in an external
+/// engine while delegating everything else to `inner`.
+///
+/// `inner` must share the node's store: chunk application results are written
+/// to it through `inner.get_tries()`.
+pub struct ExternalRuntimeAdapter {
+    inner: Arc<dyn RuntimeAdapter>,
+    engine: Arc<dyn ExecutionEngine>,
+    prepare_batch_size: usize,
+}
+
+impl ExternalRuntimeAdapter {
+    pub fn new(inner: Arc<dyn RuntimeAdapter>, engine: Arc<dyn ExecutionEngine>) -> Self {
+        Self { inner, engine, prepare_batch_size: DEFAULT_PREPARE_BATCH_SIZE }
+    }
+
+    pub fn with_prepare_batch_size(mut self, prepare_batch_size: usize) -> Self {
+        self.prepare_batch_size = prepare_batch_size.max(1);
+        self
+    }
+
+    fn execute(&self, request: ExternalRuntimeRequest) -> Result<ExternalRuntimeResponse, Error> {
+        let response = self.engine.call(request.try_to_vec()?)?;
+        Ok(ExternalRuntimeResponse::try_from_slice(&response)?)
+    }
+}
+
+fn unexpected_response(response: ExternalRuntimeResponse) -> Error {
+    Error::Other(format!("external runtime: unexpected response {:?}", response))
+}
+
+impl EpochManagerAdapter for ExternalRuntimeAdapter {
+    fn epoch_exists(&self, epoch_id: &EpochId) -> bool {
+        self.inner.epoch_exists(epoch_id)
+    }
+
+    fn num_shards(&self, epoch_id: &EpochId) -> Result<ShardId, Error> {
+        self.inner.num_shards(epoch_id)
+    }
+
+    fn num_total_parts(&self) -> usize {
+        self.inner.num_total_parts()
+    }
+
+    fn num_data_parts(&self) -> usize {
+        self.inner.num_data_parts()
+    }
+
+    fn get_part_owner(&self, epoch_id: &EpochId, part_id: u64) -> Result<AccountId, Error> {
+        self.inner.get_part_owner(epoch_id, part_id)
+    }
+
+    fn account_id_to_shard_id(
+        &self,
+        account_id: &AccountId,
+        epoch_id: &EpochId,
+    ) -> Result<ShardId, Error> {
+        self.inner.account_id_to_shard_id(account_id, epoch_id)
+    }
+
+    fn shard_id_to_uid(&self, shard_id: ShardId, epoch_id: &EpochId) -> Result<ShardUId, Error> {
+        self.inner.shard_id_to_uid(shard_id, epoch_id)
+    }
+
+    fn get_shard_layout(&self, epoch_id: &EpochId) -> Result<ShardLayout, Error> {
+        self.inner.get_shard_layout(epoch_id)
+    }
+
+    fn get_shard_config(&self, epoch_id: &EpochId) -> Result<ShardConfig, Error> {
+        self.inner.get_shard_config(epoch_id)
+    }
+
+    fn is_next_block_epoch_start(&self, parent_hash: &CryptoHash) -> Result<bool, Error> {
+        self.inner.is_next_block_epoch_start(parent_hash)
+    }
+
+    fn get_epoch_id_from_prev_block(&self, parent_hash: &CryptoHash) -> Result<EpochId, Error> {
+        self.inner.get_epoch_id_from_prev_block(parent_hash)
+    }
+
+    fn get_epoch_height_from_prev_block(
+        &self,
+        parent_hash: &CryptoHash,
+    ) -> Result<EpochHeight, Error> {
+        self.inner.get_epoch_height_from_prev_block(parent_hash)
+    }
+
+    fn get_next_epoch_id_from_prev_block(
+        &self,
+        parent_hash: &CryptoHash,
+    ) -> Result<EpochId, Error> {
+        self.inner.get_next_epoch_id_from_prev_block(parent_hash)
+    }
+
+    fn get_prev_shard_ids(
+        &self,
+        prev_hash: &CryptoHash,
+        shard_ids: Vec<ShardId>,
+    ) -> Result<Vec<ShardId>, Error> {
+        self.inner.get_prev_shard_ids(prev_hash, shard_ids)
+    }
+
+    fn get_shard_layout_from_prev_block(
+        &self,
+        parent_hash: &CryptoHash,
+    ) -> Result<ShardLayout, Error> {
+        self.inner.get_shard_layout_from_prev_block(parent_hash)
+    }
+
+    fn get_epoch_id(&self, block_hash: &CryptoHash) -> Result<EpochId, Error> {
+        self.inner.get_epoch_id(block_hash)
+    }
+
+    fn get_epoch_start_height(&self, block_hash: &CryptoHash) -> Result<BlockHeight, Error> {
+        self.inner.get_epoch_start_height(block_hash)
+    }
+
+    fn get_epoch_block_producers_ordered(
+        &self,
+        epoch_id: &EpochId,
+        last_known_block_hash: &CryptoHash,
+    ) -> Result<Vec<(ValidatorStake, bool)>, Error> {
+        self.inner.get_epoch_block_producers_ordered(epoch_id, last_known_block_hash)
+    }
+
+    fn get_epoch_block_approvers_ordered(
+        &self,
+        parent_hash: &CryptoHash,
+    ) -> Result<Vec<(ApprovalStake, bool)>, Error> {
+        self.inner.get_epoch_block_approvers_ordered(parent_hash)
+    }
+
+    fn get_epoch_chunk_producers(&self, epoch_id: &EpochId) -> Result<Vec<ValidatorStake>, Error> {
+        self.inner.get_epoch_chunk_producers(epoch_id)
+    }
+
+    fn get_block_producer(
+        &self,
+        epoch_id: &EpochId,
+        height: BlockHeight,
+    ) -> Result<AccountId, Error> {
+        self.inner.get_block_producer(epoch_id, height)
+    }
+
+    fn get_block_producer_proof(
+        &self,
+        epoch_id: &EpochId,
+        height: BlockHeight,
+    ) -> Result<BlockProducerProofView, Error> {
+        self.inner.get_block_producer_proof(epoch_id, height)
+    }
+
+    fn get_epoch_randomness(&self, block_hash: &CryptoHash) -> Result<EpochRandomnessView, Error> {
+        self.inner.get_epoch_randomness(block_hash)
+    }
+
+    fn get_chunk_producer(
+        &self,
+        epoch_id: &EpochId,
+        height: BlockHeight,
+        shard_id: ShardId,
+    ) -> Result<AccountId, Error> {
+        self.inner.get_chunk_producer(epoch_id, height, shard_id)
+    }
+
+    fn get_validator_by_account_id(
+        &self,
+        epoch_id: &EpochId,
+        last_known_block_hash: &CryptoHash,
+        account_id: &AccountId,
+    ) -> Result<(ValidatorStake, bool), Error> {
+        self.inner.get_validator_by_account_id(epoch_id, last_known_block_hash, account_id)
+    }
+
+    fn get_fisherman_by_account_id(
+        &self,
+        epoch_id: &EpochId,
+        last_known_block_hash: &CryptoHash,
+        account_id: &AccountId,
+    ) -> Result<(ValidatorStake, bool), Error> {
+        self.inner.get_fisherman_by_account_id(epoch_id, last_known_block_hash, account_id)
+    }
+
+    fn get_validator_info(
+        &self,
+        epoch_id: ValidatorInfoIdentifier,
+    ) -> Result<EpochValidatorInfo, Error> {
+        self.inner.get_validator_info(epoch_id)
+    }
+
+    fn verify_block_vrf(
+        &self,
+        epoch_id: &EpochId,
+        block_height: BlockHeight,
+        prev_random_value: &CryptoHash,
+        vrf_value: &near_crypto::vrf::Value,
+        vrf_proof: &near_crypto::vrf::Proof,
+    ) -> Result<(), Error> {
+        self.inner.verify_block_vrf(epoch_id, block_height, prev_random_value, vrf_value, vrf_proof)
+    }
+
+    fn verify_validator_signature(
+        &self,
+        epoch_id: &EpochId,
+        last_known_block_hash: &CryptoHash,
+        account_id: &AccountId,
+        data: &[u8],
+        signature: &Signature,
+    ) -> Result<bool, Error> {
+        self.inner.verify_validator_signature(
+            epoch_id,
+            last_known_block_hash,
+            account_id,
+            data,
+            signature,
+        )
+    }
+
+    fn verify_validator_or_fisherman_signature(
+        &self,
+        epoch_id: &EpochId,
+        last_known_block_hash: &CryptoHash,
+        account_id: &AccountId,
+        data: &[u8],
+        signature: &Signature,
+    ) -> Result<bool, Error> {
+        self.inner.verify_validator_or_fisherman_signature(
+            epoch_id,
+            last_known_block_hash,
+            account_id,
+            data,
+            signature,
+        )
+    }
+
+    fn verify_header_signature(&self, header: &BlockHeader) -> Result<bool, Error> {
+        self.inner.verify_header_signature(header)
+    }
+
+    fn verify_chunk_signature_with_header_parts(
+        &self,
+        chunk_hash: &ChunkHash,
+        signature: &Signature,
+        epoch_id: &EpochId,
+        last_known_hash: &CryptoHash,
+        height_created: BlockHeight,
+        shard_id: ShardId,
+    ) -> Result<bool, Error> {
+        self.inner.verify_chunk_signature_with_header_parts(
+            chunk_hash,
+            signature,
+            epoch_id,
+            last_known_hash,
+            height_created,
+            shard_id,
+        )
+    }
+
+    fn verify_approval(
+        &self,
+        prev_block_hash: &CryptoHash,
+        prev_block_height: BlockHeight,
+        block_height: BlockHeight,
+        approvals: &[Option<Signature>],
+    ) -> Result<bool, Error> {
+        self.inner.verify_approval(prev_block_hash, prev_block_height, block_height, approvals)
+    }
+
+    fn verify_approvals_and_threshold_orphan(
+        &self,
+        epoch_id: &EpochId,
+        can_approved_block_be_produced: &dyn Fn(
+            &[Option<Signature>],
+            // (stake this in epoch, stake in next epoch, is_slashed)
+            &[(Balance, Balance, bool)],
+        ) -> bool,
+        prev_block_hash: &CryptoHash,
+        prev_block_height: BlockHeight,
+        block_height: BlockHeight,
+        approvals: &[Option<Signature>],
+    ) -> Result<(), Error> {
+        self.inner.verify_approvals_and_threshold_orphan(
+            epoch_id,
+            can_approved_block_be_produced,
+            prev_block_hash,
+            prev_block_height,
+            block_height,
+            approvals,
+        )
+    }
+
+    #[cfg(feature = "protocol_feature_bls_approvals")]
+    fn verify_bls_key_registration(
+        &self,
+        epoch_id: &EpochId,
+        last_known_block_hash: &CryptoHash,
+        registration: &near_primitives::block_header::BlsKeyRegistration,
+    ) -> Result<bool, Error> {
+        self.inner.verify_bls_key_registration(epoch_id, last_known_block_hash, registration)
+    }
+
+    #[cfg(feature = "protocol_feature_bls_approvals")]
+    fn register_bls_key(
+        &self,
+        registration: &near_primitives::block_header::BlsKeyRegistration,
+    ) -> Result<(), Error> {
+        self.inner.register_bls_key(registration)
+    }
+
+    #[cfg(feature = "protocol_feature_bls_approvals")]
+    fn get_bls_key(&self, account_id: &AccountId) -> Option<near_crypto::bls::PublicKey> {
+        self.inner.get_bls_key(account_id)
+    }
+
+    #[cfg(feature = "protocol_feature_bls_approvals")]
+    fn verify_approvals_aggregate(
+        &self,
+        prev_block_hash: &CryptoHash,
+        prev_block_height: BlockHeight,
+        block_height: BlockHeight,
+        approvals: &[Option<Signature>],
+        approvals_aggregate: &near_primitives::block_header::ApprovalsAggregate,
+    ) -> Result<bool, Error> {
+        self.inner.verify_approvals_aggregate(
+            prev_block_hash,
+            prev_block_height,
+            block_height,
+            approvals,
+            approvals_aggregate,
+        )
+    }
+}
+
+impl RuntimeAdapter for ExternalRuntimeAdapter {
+    fn genesis_state(&self) -> (Store, Vec<StateRoot>) {
+        self.inner.genesis_state()
+    }
+
+    fn get_tries(&self) -> ShardTries {
+        self.inner.get_tries()
+    }
+
+    fn store(&self) -> &Store {
+        self.inner.store()
+    }
+
+    fn get_trie_for_shard(
+        &self,
+        shard_id: ShardId,
+        prev_hash: &CryptoHash,
+        state_root: StateRoot,
+        use_flat_storage: bool,
+    ) -> Result<Trie, Error> {
+        self.inner.get_trie_for_shard(shard_id, prev_hash, state_root, use_flat_storage)
+    }
+
+    fn get_view_trie_for_shard(
+        &self,
+        shard_id: ShardId,
+        prev_hash: &CryptoHash,
+        state_root: StateRoot,
+    ) -> Result<Trie, Error> {
+        self.inner.get_view_trie_for_shard(shard_id, prev_hash, state_root)
+    }
+
+    fn get_flat_storage_state_for_shard(&self, shard_id: ShardId) -> Option<FlatStorageState> {
+        self.inner.get_flat_storage_state_for_shard(shard_id)
+    }
+
+    fn try_create_flat_storage_state_for_shard(
+        &self,
+        shard_id: ShardId,
+        latest_block_height: BlockHeight,
+        chain_access: &dyn ChainAccessForFlatStorage,
+    ) -> FlatStorageStateStatus {
+        self.inner.try_create_flat_storage_state_for_shard(
+            shard_id,
+            latest_block_height,
+            chain_access,
+        )
+    }
+
+    fn set_flat_storage_state_for_genesis(
+        &self,
+        genesis_block: &CryptoHash,
+        genesis_epoch_id: &EpochId,
+    ) -> Result<StoreUpdate, Error> {
+        self.inner.set_flat_storage_state_for_genesis(genesis_block, genesis_epoch_id)
+    }
+
+    fn will_shard_layout_change_next_epoch(&self, parent_hash: &CryptoHash) -> Result<bool, Error> {
+        self.inner.will_shard_layout_change_next_epoch(parent_hash)
+    }
+
+    fn cares_about_shard(
+        &self,
+        account_id: Option<&AccountId>,
+        parent_hash: &CryptoHash,
+        shard_id: ShardId,
+        is_me: bool,
+    ) -> bool {
+        self.inner.cares_about_shard(account_id, parent_hash, shard_id, is_me)
+    }
+
+    fn will_care_about_shard(
+        &self,
+        account_id: Option<&AccountId>,
+        parent_hash: &CryptoHash,
+        shard_id: ShardId,
+        is_me: bool,
+    ) -> bool {
+        self.inner.will_care_about_shard(account_id, parent_hash, shard_id, is_me)
+    }
+
+    fn update_tracked_shards(&self, shard_ids: Vec<ShardId>) -> Result<(), Error> {
+        self.inner.update_tracked_shards(shard_ids)
+    }
+
+    fn get_gc_stop_height(&self, block_hash: &CryptoHash) -> BlockHeight {
+        self.inner.get_gc_stop_height(block_hash)
+    }
+
+    fn get_epoch_minted_amount(&self, epoch_id: &EpochId) -> Result<Balance, Error> {
+        self.inner.get_epoch_minted_amount(epoch_id)
+    }
+
+    fn get_epoch_sync_data(
+        &self,
+        prev_epoch_last_block_hash: &CryptoHash,
+        epoch_id: &EpochId,
+        next_epoch_id: &EpochId,
+    ) -> Result<
+        (
+            Arc<BlockInfo>,
+            Arc<BlockInfo>,
+            Arc<BlockInfo>,
+            Arc<EpochInfo>,
+            Arc<EpochInfo>,
+            Arc<EpochInfo>,
+        ),
+        Error,
+    > {
+        self.inner.get_epoch_sync_data(prev_epoch_last_block_hash, epoch_id, next_epoch_id)
+    }
+
+    fn get_epoch_protocol_version(&self, epoch_id: &EpochId) -> Result<ProtocolVersion, Error> {
+        self.inner.get_epoch_protocol_version(epoch_id)
+    }
+
+    fn epoch_sync_init_epoch_manager(
+        &self,
+        prev_epoch_first_block_info: BlockInfo,
+        prev_epoch_prev_last_block_info: BlockInfo,
+        prev_epoch_last_block_info: BlockInfo,
+        prev_epoch_id: &EpochId,
+        prev_epoch_info: EpochInfo,
+        epoch_id: &EpochId,
+        epoch_info: EpochInfo,
+        next_epoch_id: &EpochId,
+        next_epoch_info: EpochInfo,
+    ) -> Result<(), Error> {
+        self.inner.epoch_sync_init_epoch_manager(
+            prev_epoch_first_block_info,
+            prev_epoch_prev_last_block_info,
+            prev_epoch_last_block_info,
+            prev_epoch_id,
+            prev_epoch_info,
+            epoch_id,
+            epoch_info,
+            next_epoch_id,
+            next_epoch_info,
+        )
+    }
+
+    fn add_validator_proposals(
+        &self,
+        block_header_info: BlockHeaderInfo,
+    ) -> Result<StoreUpdate, Error> {
+        self.inner.add_validator_proposals(block_header_info)
+    }
+
+    fn check_state_transition(
+        &self,
+        partial_storage: PartialStorage,
+        shard_id: ShardId,
+        state_root: &StateRoot,
+        height: BlockHeight,
+        block_timestamp: u64,
+        prev_block_hash: &CryptoHash,
+        block_hash: &CryptoHash,
+        receipts: &[Receipt],
+        transactions: &[SignedTransaction],
+        last_validator_proposals: ValidatorStakeIter,
+        gas_price: Balance,
+        gas_limit: Gas,
+        challenges_result: &ChallengesResult,
+        random_value: CryptoHash,
+        is_new_chunk: bool,
+        is_first_block_with_chunk_of_version: bool,
+    ) -> Result<ApplyTransactionResult, Error> {
+        self.inner.check_state_transition(
+            partial_storage,
+            shard_id,
+            state_root,
+            height,
+            block_timestamp,
+            prev_block_hash,
+            block_hash,
+            receipts,
+            transactions,
+            last_validator_proposals,
+            gas_price,
+            gas_limit,
+            challenges_result,
+            random_value,
+            is_new_chunk,
+            is_first_block_with_chunk_of_version,
+        )
+    }
+
+    fn obtain_state_part(
+        &self,
+        shard_id: ShardId,
+        block_hash: &CryptoHash,
+        state_root: &StateRoot,
+        part_id: PartId,
+    ) -> Result<Vec<u8>, Error> {
+        self.inner.obtain_state_part(shard_id, block_hash, state_root, part_id)
+    }
+
+    fn validate_state_part(&self, state_root: &StateRoot, part_id: PartId, data: &[u8]) -> bool {
+        self.inner.validate_state_part(state_root, part_id, data)
+    }
+
+    fn apply_update_to_split_states(
+        &self,
+        block_hash: &CryptoHash,
+        state_roots: HashMap<ShardUId, StateRoot>,
+        next_shard_layout: &ShardLayout,
+        state_changes: StateChangesForSplitStates,
+    ) -> Result<Vec<ApplySplitStateResult>, Error> {
+        self.inner.apply_update_to_split_states(
+            block_hash,
+            state_roots,
+            next_shard_layout,
+            state_changes,
+        )
+    }
+
+    fn build_state_for_split_shards(
+        &self,
+        shard_uid: ShardUId,
+        state_root: &StateRoot,
+        next_epoch_shard_layout: &ShardLayout,
+        state_split_status: Arc<StateSplitApplyingStatus>,
+    ) -> Result<HashMap<ShardUId, StateRoot>, Error> {
+        self.inner.build_state_for_split_shards(
+            shard_uid,
+            state_root,
+            next_epoch_shard_layout,
+            state_split_status,
+        )
+    }
+
+    fn apply_state_part(
+        &self,
+        shard_id: ShardId,
+        state_root: &StateRoot,
+        part_id: PartId,
+        part: &[u8],
+        epoch_id: &EpochId,
+    ) -> Result<(), Error> {
+        self.inner.apply_state_part(shard_id, state_root, part_id, part, epoch_id)
+    }
+
+    fn get_state_root_node(
+        &self,
+        shard_id: ShardId,
+        block_hash: &CryptoHash,
+        state_root: &StateRoot,
+    ) -> Result<StateRootNode, Error> {
+        self.inner.get_state_root_node(shard_id, block_hash, state_root)
+    }
+
+    fn validate_state_root_node(
+        &self,
+        state_root_node: &StateRootNode,
+        state_root: &StateRoot,
+    ) -> bool {
+        self.inner.validate_state_root_node(state_root_node, state_root)
+    }
+
+    fn compare_epoch_id(
+        &self,
+        epoch_id: &EpochId,
+        other_epoch_id: &EpochId,
+    ) -> Result<Ordering, Error> {
+        self.inner.compare_epoch_id(epoch_id, other_epoch_id)
+    }
+
+    fn chunk_needs_to_be_fetched_from_archival(
+        &self,
+        chunk_prev_block_hash: &CryptoHash,
+        header_head: &CryptoHash,
+    ) -> Result<bool, Error> {
+        self.inner.chunk_needs_to_be_fetched_from_archival(chunk_prev_block_hash, header_head)
+    }
+
+    fn get_protocol_config(&self, epoch_id: &EpochId) -> Result<ProtocolConfig, Error> {
+        self.inner.get_protocol_config(epoch_id)
+    }
+
+    fn get_prev_epoch_id_from_prev_block(
+        &self,
+        prev_block_hash: &CryptoHash,
+    ) -> Result<EpochId, Error> {
+        self.inner.get_prev_epoch_id_from_prev_block(prev_block_hash)
+    }
+
+    fn get_protocol_upgrade_block_height(
+        &self,
+        block_hash: CryptoHash,
+    ) -> Result<Option<BlockHeight>, EpochError> {
+        self.inner.get_protocol_upgrade_block_height(block_hash)
+    }
+
+    fn validate_tx(
+        &self,
+        gas_price: Balance,
+        state_root: Option<StateRoot>,
+        transaction: &SignedTransaction,
+        verify_signature: bool,
+        epoch_id: &EpochId,
+        current_protocol_version: ProtocolVersion,
+    ) -> Result<Option<InvalidTxError>, Error> {
+        let request = ExternalRuntimeRequest::ValidateTx(ValidateTxRequest {
+            gas_price,
+            state_root,
+            transaction: transaction.clone(),
+            verify_signature,
+            epoch_id: epoch_id.clone(),
+            current_protocol_version,
+        });
+        match self.execute(request)? {
+            ExternalRuntimeResponse::ValidateTx(result) => result.map_err(Into::into),
+            response => Err(unexpected_response(response)),
+        }
+    }
+
+    /// The pool is pulled lazily, in batches: each batch is offered to the
+    /// engine along with the transactions selected from the previous ones,
+    /// until the engine stops pulling before the end of a batch, as the limits
+    /// were reached, or the pool runs out.  The candidates the engine didn't
+    /// pull are put back into the pool, so only the transactions its runtime
+    /// considered leave it, same as with a local runtime.  Batches grow with
+    /// the selected transactions, which bounds the cost of replaying them.
+    /// `deadline` only bounds pulling the candidates on the node side.
+    fn prepare_transactions(
+        &self,
+        gas_price: Balance,
+        gas_limit: Gas,
+        max_transactions: Option<u64>,
+        epoch_id: &EpochId,
+        shard_id: ShardId,
+        state_root: StateRoot,
+        next_block_height: BlockHeight,
+        pool_iterator: &mut dyn PoolIterator,
+        chain_validate: &mut dyn FnMut(&SignedTransaction) -> bool,
+        current_protocol_version: ProtocolVersion,
+        deadline: Option<Instant>,
+    ) -> Result<Vec<SignedTransaction>, Error> {
+        let out_of_time = || deadline.map_or(false, |deadline| Clock::instant() >= deadline);
+        let mut selected = vec![];
+        loop {
+            let batch_size = self.prepare_batch_size.max(selected.len());
+            let mut candidates = vec![];
+            let mut last_batch = false;
+            while candidates.len() < batch_size {
+                if out_of_time() {
+                    last_batch = true;
+                    break;
+                }
+                let iter = match pool_iterator.next() {
+                    Some(iter) => iter,
+                    None => {
+                        last_batch = true;
+                        break;
+                    }
+                };
+                if let Some(transaction) = iter.next() {
+                    if chain_validate(&transaction) {
+                        candidates.push(transaction);
+                    }
+                }
+            }
+            if candidates.is_empty() {
+                return Ok(selected);
+            }
+            let request = ExternalRuntimeRequest::PrepareTransactions(PrepareTransactionsRequest {
+                gas_price,
+                gas_limit,
+                max_transactions,
+                epoch_id: epoch_id.clone(),
+                shard_id,
+                state_root,
+                next_block_height,
+                selected,
+                candidates: candidates.clone(),
+                current_protocol_version,
+            });
+            let response = match self.execute(request)? {
+                ExternalRuntimeResponse::PrepareTransactions(result) => result?,
+                response => return Err(unexpected_response(response)),
+            };
+            selected = response.transactions;
+            let num_pulled = response.num_pulled_candidates as usize;
+            if num_pulled < candidates.len() {
+                for transaction in candidates.drain(num_pulled..) {
+                    pool_iterator.put_back(transaction);
+                }
+                return Ok(selected);
+            }
+            if last_batch {
+                return Ok(selected);
+            }
+        }
+    }
+
+    fn apply_transactions_with_optional_storage_proof(
+        &self,
+        shard_id: ShardId,
+        state_root: &StateRoot,
+        height: BlockHeight,
+        block_timestamp: u64,
+        prev_block_hash: &CryptoHash,
+        block_hash: &CryptoHash,
+        receipts: &[Receipt],
+        transactions: &[SignedTransaction],
+        last_validator_proposals: ValidatorStakeIter,
+        gas_price: Balance,
+        gas_limit: Gas,
+        challenges_result: &ChallengesResult,
+        random_seed: CryptoHash,
+        generate_storage_proof: bool,
+        is_new_chunk: bool,
+        is_first_block_with_chunk_of_version: bool,
+        state_patch: SandboxStatePatch,
+        use_flat_storage: bool,
+    ) -> Result<ApplyTransactionResult, Error> {
+        if !state_patch.is_empty() {
+            return Err(Error::Other(
+                "external runtime does not support sandbox state patches".to_string(),
+            ));
+        }
+        let request = ExternalRuntimeRequest::ApplyTransactions(ApplyTransactionsRequest {
+            shard_id,
+            state_root: *state_root,
+            height,
+            block_timestamp,
+            prev_block_hash: *prev_block_hash,
+            block_hash: *block_hash,
+            receipts: receipts.to_vec(),
+            transactions: transactions.to_vec(),
+            last_validator_proposals: last_validator_proposals.collect(),
+            gas_price,
+            gas_limit,
+            challenges_result: challenges_result.clone(),
+            random_seed,
+            generate_storage_proof,
+            is_new_chunk,
+            is_first_block_with_chunk_of_version,
+            use_flat_storage,
+        });
+        match self.execute(request)? {
+            ExternalRuntimeResponse::ApplyTransactions(result) => {
+                Ok(result?.into_result(self.inner.get_tries(), *block_hash))
+            }
+            response => Err(unexpected_response(response)),
+        }
+    }
+
+    fn query(
+        &self,
+        shard_uid: ShardUId,
+        state_root: &StateRoot,
+        block_height: BlockHeight,
+        block_timestamp: u64,
+        prev_block_hash: &CryptoHash,
+        block_hash: &CryptoHash,
+        epoch_id: &EpochId,
+        request: &QueryRequest,
+    ) -> Result<QueryResponse, QueryError> {
+        let request = ExternalRuntimeRequest::Query(QueryRuntimeRequest {
+            shard_uid,
+            state_root: *state_root,
+            block_height,
+            block_timestamp,
+            prev_block_hash: *prev_block_hash,
+            block_hash: *block_hash,
+            epoch_id: epoch_id.clone(),
+            request: request.clone(),
+        });
+        let internal_error = |error_message| QueryError::InternalError {
+            error_message,
+            block_height,
+            block_hash: *block_hash,
+        };
+        match self.execute(request) {
+            Ok(ExternalRuntimeResponse::Query(result)) => result,
+            Ok(response) => Err(internal_error(unexpected_response(response).to_string())),
+            Err(err) => Err(internal_error(err.to_string())),
+        }
+    }
+}
@@ -1,6 +1,8 @@
 pub use block_processing_utils::{BlockProcessingArtifact, DoneApplyChunkCallback};
-pub use chain::{check_known, collect_receipts, Chain, MAX_ORPHAN_SIZE};
-pub use doomslug::{Doomslug, DoomslugBlockProductionReadiness, DoomslugThresholdMode};
+pub use chain::{check_known, collect_receipts, ArchiveGCBlocker, Chain, MAX_ORPHAN_SIZE};
+pub use doomslug::{
+    ApprovalConflict, Doomslug, DoomslugBlockProductionReadiness, DoomslugThresholdMode,
+};
 pub use lightclient::{create_light_client_block_view, get_epoch_block_producers_view};
 pub use near_chain_primitives::{self, Error};
 pub use near_primitives::receipt::ReceiptResult;
@@ -8,22 +10,34 @@ pub use store::{ChainStore, ChainStoreAccess, ChainStoreUpdate};
 pub use store_validator::{ErrorMessage, StoreValidator};
 pub use types::{Block, BlockHeader, BlockStatus, ChainGenesis, Provenance, RuntimeAdapter};
 
+mod apply_chunks_queue;
 mod block_processing_utils;
+pub mod block_stages;
 pub mod blocks_delay_tracker;
 pub mod chain;
 pub mod chunks_store;
+pub mod contract_code_index;
+pub mod contract_events;
 pub mod crypto_hash_timer;
 mod doomslug;
+pub mod external_runtime;
 mod flat_storage_creator;
 mod lightclient;
 mod metrics;
 pub mod migrations;
 pub mod missing_chunks;
+pub mod outcome_compaction;
+mod persisted_orphans;
+pub mod recently_processed;
+mod shard_readiness;
+pub mod state_mismatch;
+pub mod state_parts_apply;
 mod store;
 pub mod store_validator;
 pub mod test_utils;
 #[cfg(test)]
 mod tests;
+pub mod trie_diff;
 pub mod types;
 pub mod validate;
 
@@ -1,24 +1,11 @@
 use near_o11y::metrics::{
-    exponential_buckets, try_create_histogram, try_create_histogram_vec, try_create_int_counter,
-    try_create_int_gauge, Histogram, HistogramVec, IntCounter, IntGauge,
+    exponential_buckets, try_create_histogram_vec, try_create_int_counter, try_create_int_gauge,
+    Histogram, HistogramVec, IntCounter, IntCounterVec, IntGauge, MetricSet, MetricsRegistry,
 };
 use once_cell::sync::Lazy;
 
-pub static BLOCK_PROCESSING_ATTEMPTS_TOTAL: Lazy<IntCounter> = Lazy::new(|| {
-    try_create_int_counter(
-        "near_block_processing_attempts_total",
-        "Total number of block processing attempts. The most common reason for aborting block processing is missing chunks",
-    )
-    .unwrap()
-});
-pub static BLOCK_PROCESSED_TOTAL: Lazy<IntCounter> = Lazy::new(|| {
-    try_create_int_counter("near_block_processed_total", "Total number of blocks processed")
-        .unwrap()
-});
-pub static BLOCK_PROCESSING_TIME: Lazy<Histogram> = Lazy::new(|| {
-    try_create_histogram("near_block_processing_time", "Time taken to process blocks successfully, from when a block is ready to be processed till when the processing is finished. Measures only the time taken by the successful attempts of block processing")
-        .unwrap()
-});
+/// Chunks are applied by the runtime adapter, which doesn't know which chain it applies them for,
+/// so this metric stays in the global registry.
 pub static APPLYING_CHUNKS_TIME: Lazy<HistogramVec> = Lazy::new(|| {
     try_create_histogram_vec(
         "near_applying_chunks_time",
@@ -28,73 +15,205 @@ pub static APPLYING_CHUNKS_TIME: Lazy<HistogramVec> = Lazy::new(|| {
     )
     .unwrap()
 });
-pub static BLOCK_PREPROCESSING_TIME: Lazy<Histogram> = Lazy::new(|| {
-    try_create_histogram("near_block_preprocessing_time", "Time taken to preprocess blocks, only include the time when the preprocessing is successful")
-        .unwrap()
-});
-pub static BLOCK_POSTPROCESSING_TIME: Lazy<Histogram> = Lazy::new(|| {
-    try_create_histogram("near_block_postprocessing_time", "Time taken to postprocess blocks")
-        .unwrap()
-});
-pub static BLOCK_HEIGHT_HEAD: Lazy<IntGauge> = Lazy::new(|| {
-    try_create_int_gauge("near_block_height_head", "Height of the current head of the blockchain")
-        .unwrap()
-});
-pub static BLOCK_ORDINAL_HEAD: Lazy<IntGauge> = Lazy::new(|| {
-    try_create_int_gauge("near_block_ordinal_head", "Ordinal of the current head of the blockchain")
-        .unwrap()
-});
-pub static VALIDATOR_AMOUNT_STAKED: Lazy<IntGauge> = Lazy::new(|| {
+
+/// Outcome compaction runs in a background thread of the node, next to the chain.
+pub static OUTCOME_COMPACTION_HEIGHT: Lazy<IntGauge> = Lazy::new(|| {
     try_create_int_gauge(
-        "near_validators_stake_total",
-        "The total stake of all active validators during the last block",
+        "near_outcome_compaction_height",
+        "Height up to which outcome proofs of blocks off the canonical chain were removed",
     )
     .unwrap()
 });
-pub static VALIDATOR_ACTIVE_TOTAL: Lazy<IntGauge> = Lazy::new(|| {
-    try_create_int_gauge(
-        "near_validator_active_total",
-        "The total number of validators active after last block",
+
+pub static OUTCOME_COMPACTION_REMOVED_TOTAL: Lazy<IntCounter> = Lazy::new(|| {
+    try_create_int_counter(
+        "near_outcome_compaction_removed_total",
+        "Number of outcome proofs of blocks off the canonical chain removed by compaction",
     )
     .unwrap()
 });
-pub static NUM_ORPHANS: Lazy<IntGauge> =
-    Lazy::new(|| try_create_int_gauge("near_num_orphans", "Number of orphan blocks.").unwrap());
-pub static HEADER_HEAD_HEIGHT: Lazy<IntGauge> = Lazy::new(|| {
-    try_create_int_gauge("near_header_head_height", "Height of the header head").unwrap()
-});
-pub static BOOT_TIME_SECONDS: Lazy<IntGauge> = Lazy::new(|| {
+
+/// State parts are stored by the view client's chain and removed by the garbage collection of the
+/// client's chain, so these metrics stay in the global registry.
+pub static STATE_SYNC_PARTS_STORED: Lazy<IntGauge> = Lazy::new(|| {
     try_create_int_gauge(
-        "near_boot_time_seconds",
-        "Unix timestamp in seconds of the moment the client was started",
+        "near_state_sync_parts_stored",
+        "Number of state parts stored to serve state sync requests, either computed or \
+         downloaded.  They are kept until the block they belong to is garbage collected",
     )
     .unwrap()
 });
-pub static TAIL_HEIGHT: Lazy<IntGauge> =
-    Lazy::new(|| try_create_int_gauge("near_tail_height", "Height of tail").unwrap());
-pub static CHUNK_TAIL_HEIGHT: Lazy<IntGauge> =
-    Lazy::new(|| try_create_int_gauge("near_chunk_tail_height", "Height of chunk tail").unwrap());
-pub static FORK_TAIL_HEIGHT: Lazy<IntGauge> =
-    Lazy::new(|| try_create_int_gauge("near_fork_tail_height", "Height of fork tail").unwrap());
-pub static GC_STOP_HEIGHT: Lazy<IntGauge> =
-    Lazy::new(|| try_create_int_gauge("near_gc_stop_height", "Target height of gc").unwrap());
-pub static CHUNK_RECEIVED_DELAY: Lazy<HistogramVec> = Lazy::new(|| {
-    try_create_histogram_vec(
-        "near_chunk_receive_delay_seconds",
-        "Delay between requesting and receiving a chunk.",
-        &["shard_id"],
-        Some(exponential_buckets(0.001, 1.6, 20).unwrap()),
-    )
-    .unwrap()
-});
-pub static BLOCK_ORPHANED_DELAY: Lazy<Histogram> = Lazy::new(|| {
-    try_create_histogram("near_block_orphaned_delay", "How long blocks stay in the orphan pool")
-        .unwrap()
-});
-pub static BLOCK_MISSING_CHUNKS_DELAY: Lazy<Histogram> = Lazy::new(|| {
-    try_create_histogram(
-        "near_block_missing_chunks_delay",
-        "How long blocks stay in the missing chunks pool",
+
+pub static STATE_SYNC_PARTS_STORED_BYTES: Lazy<IntGauge> = Lazy::new(|| {
+    try_create_int_gauge(
+        "near_state_sync_parts_stored_bytes",
+        "Total size of state parts stored to serve state sync requests",
     )
     .unwrap()
 });
+
+/// Metrics of a single chain.
+pub struct ChainMetrics {
+    pub block_processing_attempts_total: IntCounter,
+    pub block_processed_total: IntCounter,
+    pub block_processing_time: Histogram,
+    pub block_preprocessing_time: Histogram,
+    pub block_postprocessing_time: Histogram,
+    pub block_stage_time: HistogramVec,
+    pub block_stage_over_budget_total: IntCounterVec,
+    pub block_height_head: IntGauge,
+    pub block_ordinal_head: IntGauge,
+    pub validator_amount_staked: IntGauge,
+    pub validator_active_total: IntGauge,
+    pub num_orphans: IntGauge,
+    pub header_head_height: IntGauge,
+    pub boot_time_seconds: IntGauge,
+    pub tail_height: IntGauge,
+    pub chunk_tail_height: IntGauge,
+    pub fork_tail_height: IntGauge,
+    pub gc_stop_height: IntGauge,
+    pub chunk_received_delay: HistogramVec,
+    pub block_orphaned_delay: Histogram,
+    pub block_missing_chunks_delay: Histogram,
+    pub apply_chunks_completion_delay: HistogramVec,
+    pub apply_chunks_ready_blocks: IntGauge,
+}
+
+impl MetricSet for ChainMetrics {
+    fn new(registry: &MetricsRegistry) -> Self {
+        Self {
+            block_processing_attempts_total: registry
+                .try_create_int_counter(
+                    "near_block_processing_attempts_total",
+                    "Total number of block processing attempts. The most common reason for \
+                     aborting block processing is missing chunks",
+                )
+                .unwrap(),
+            block_processed_total: registry
+                .try_create_int_counter(
+                    "near_block_processed_total",
+                    "Total number of blocks processed",
+                )
+                .unwrap(),
+            block_processing_time: registry
+                .try_create_histogram(
+                    "near_block_processing_time",
+                    "Time taken to process blocks successfully, from when a block is ready \
+                     to be processed till when the processing is finished. Measures only \
+                     the time taken by the successful attempts of block processing",
+                )
+                .unwrap(),
+            block_preprocessing_time: registry
+                .try_create_histogram(
+                    "near_block_preprocessing_time",
+                    "Time taken to preprocess blocks, only include the time when the \
+                     preprocessing is successful",
+                )
+                .unwrap(),
+            block_postprocessing_time: registry
+                .try_create_histogram(
+                    "near_block_postprocessing_time",
+                    "Time taken to postprocess blocks",
+                )
+                .unwrap(),
+            block_stage_time: registry
+                .try_create_histogram_vec(
+                    "near_block_processing_stage_time",
+                    "Time taken by each stage of block processing",
+                    &["stage"],
+                    Some(exponential_buckets(0.001, 1.6, 20).unwrap()),
+                )
+                .unwrap(),
+            block_stage_over_budget_total: registry
+                .try_create_int_counter_vec(
+                    "near_block_processing_stage_over_budget_total",
+                    "Number of times a stage of block processing took longer than its budget",
+                    &["stage"],
+                )
+                .unwrap(),
+            block_height_head: registry
+                .try_create_int_gauge(
+                    "near_block_height_head",
+                    "Height of the current head of the blockchain",
+                )
+                .unwrap(),
+            block_ordinal_head: registry
+                .try_create_int_gauge(
+                    "near_block_ordinal_head",
+                    "Ordinal of the current head of the blockchain",
+                )
+                .unwrap(),
+            validator_amount_staked: registry
+                .try_create_int_gauge(
+                    "near_validators_stake_total",
+                    "The total stake of all active validators during the last block",
+                )
+                .unwrap(),
+            validator_active_total: registry
+                .try_create_int_gauge(
+                    "near_validator_active_total",
+                    "The total number of validators active after last block",
+                )
+                .unwrap(),
+            num_orphans: registry
+                .try_create_int_gauge("near_num_orphans", "Number of orphan blocks.")
+                .unwrap(),
+            header_head_height: registry
+                .try_create_int_gauge("near_header_head_height", "Height of the header head")
+                .unwrap(),
+            boot_time_seconds: registry
+                .try_create_int_gauge(
+                    "near_boot_time_seconds",
+                    "Unix timestamp in seconds of the moment the client was started",
+                )
+                .unwrap(),
+            tail_height: registry
+                .try_create_int_gauge("near_tail_height", "Height of tail")
+                .unwrap(),
+            chunk_tail_height: registry
+                .try_create_int_gauge("near_chunk_tail_height", "Height of chunk tail")
+                .unwrap(),
+            fork_tail_height: registry
+                .try_create_int_gauge("near_fork_tail_height", "Height of fork tail")
+                .unwrap(),
+            gc_stop_height: registry
+                .try_create_int_gauge("near_gc_stop_height", "Target height of gc")
+                .unwrap(),
+            chunk_received_delay: registry
+                .try_create_histogram_vec(
+                    "near_chunk_receive_delay_seconds",
+                    "Delay between requesting and receiving a chunk.",
+                    &["shard_id"],
+                    Some(exponential_buckets(0.001, 1.6, 20).unwrap()),
+                )
+                .unwrap(),
+            block_orphaned_delay: registry
+                .try_create_histogram(
+                    "near_block_orphaned_delay",
+                    "How long blocks stay in the orphan pool",
+                )
+                .unwrap(),
+            block_missing_chunks_delay: registry
+                .try_create_histogram(
+                    "near_block_missing_chunks_delay",
+                    "How long blocks stay in the missing chunks pool",
+                )
+                .unwrap(),
+            apply_chunks_completion_delay: registry
+                .try_create_histogram_vec(
+                    "near_apply_chunks_completion_delay",
+                    "Time between applying the chunks of a block finishing and the block being \
+                     postprocessed, by whether the block extends the head",
+                    &["path"],
+                    Some(exponential_buckets(0.001, 1.6, 20).unwrap()),
+                )
+                .unwrap(),
+            apply_chunks_ready_blocks: registry
+                .try_create_int_gauge(
+                    "near_apply_chunks_ready_blocks",
+                    "Number of blocks which finished applying chunks and wait to be postprocessed",
+                )
+                .unwrap(),
+        }
+    }
+}
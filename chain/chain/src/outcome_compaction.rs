@@ -0,0 +1,274 @@
+//! Background compaction of the outcomes of transactions and receipts.
+//!
+//! Outcomes are stored in [`DBCol::TransactionResultForBlock`] for every block
+//! which produced them, including blocks on forks, and the entries of fork
+//! blocks stay until garbage collection reaches their height, which on
+//! archival nodes is never.  Looking up the outcome on the canonical chain
+//! therefore scans the outcomes on all forks and checks each block.
+//!
+//! The compaction runs in a background thread.  It goes through the heights
+//! up to the final head, which can no longer change, one by one.  Outcomes of
+//! the blocks off the canonical chain are removed, and the outcomes of the
+//! canonical block are recorded in [`DBCol::CanonicalOutcomeBlock`], which
+//! lets lookups go straight to the right entry.  The progress is recorded
+//! under [`OUTCOME_COMPACTION_KEY`], so an interrupted compaction resumes
+//! where it stopped.
+//!
+//! Garbage collection removes the outcomes and blocks of the heights it
+//! reaches as well, so a height is never compacted while garbage collection
+//! runs, see [`lock_gc`].
+use std::collections::{HashMap, HashSet};
+use std::io;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Arc, Mutex, MutexGuard};
+use std::time::Duration;
+
+use near_primitives::block::Tip;
+use near_primitives::hash::CryptoHash;
+use near_primitives::types::{BlockHeight, EpochId};
+use near_primitives::utils::{get_outcome_id_block_hash, index_to_bytes};
+use near_store::{DBCol, Store, FINAL_HEAD_KEY, OUTCOME_COMPACTION_KEY, TAIL_KEY};
+
+use crate::metrics;
+
+/// Held by garbage collection and while compacting a height.  It's shared by
+/// all the chains of the process, which only matters in tests running several
+/// nodes at once.
+static GC_LOCK: Mutex<()> = Mutex::new(());
+
+/// Serializes garbage collection with the compaction until the guard is
+/// dropped.
+pub(crate) fn lock_gc() -> MutexGuard<'static, ()> {
+    // The lock guards no data, so a panic while holding it leaves nothing broken.
+    GC_LOCK.lock().unwrap_or_else(|err| err.into_inner())
+}
+
+#[derive(Clone, Debug, PartialEq, Eq, serde::Serialize, serde::Deserialize)]
+#[serde(default)]
+pub struct OutcomeCompactionConfig {
+    /// Whether outcomes of blocks off the canonical chain are removed once
+    /// the chain is final at their height.
+    pub enabled: bool,
+    /// How long to wait before checking for new final heights once the
+    /// compaction has caught up with the final head.
+    pub poll_interval: Duration,
+    /// Maximum number of heights compacted in a single step, after which the
+    /// thread checks whether it should stop.
+    pub max_heights_per_step: u64,
+}
+
+impl Default for OutcomeCompactionConfig {
+    fn default() -> Self {
+        Self { enabled: false, poll_interval: Duration::from_secs(1), max_heights_per_step: 100 }
+    }
+}
+
+pub struct OutcomeCompaction {
+    store: Store,
+    config: OutcomeCompactionConfig,
+}
+
+impl OutcomeCompaction {
+    pub fn new(store: Store, config: OutcomeCompactionConfig) -> Self {
+        Self { store, config }
+    }
+
+    /// Returns the height up to which all heights have been compacted.
+    pub fn compacted_height(&self) -> io::Result<Option<BlockHeight>> {
+        self.store.get_ser(DBCol::BlockMisc, OUTCOME_COMPACTION_KEY)
+    }
+
+    /// Compacts up to `max_heights_per_step` final heights which haven't been
+    /// compacted yet.  Returns the number of heights compacted.
+    pub fn run_step(&self) -> io::Result<u64> {
+        let final_head: Tip = match self.store.get_ser(DBCol::BlockMisc, FINAL_HEAD_KEY)? {
+            Some(final_head) => final_head,
+            None => return Ok(0),
+        };
+        let compacted_height = match self.compacted_height()? {
+            Some(height) => height,
+            // Everything below the tail has been garbage collected already.
+            None => self
+                .store
+                .get_ser::<BlockHeight>(DBCol::BlockMisc, TAIL_KEY)?
+                .unwrap_or(0)
+                .saturating_sub(1),
+        };
+        let last_height =
+            std::cmp::min(final_head.height, compacted_height + self.config.max_heights_per_step);
+        for height in compacted_height + 1..=last_height {
+            self.compact_height(height)?;
+        }
+        Ok(last_height.saturating_sub(compacted_height))
+    }
+
+    fn compact_height(&self, height: BlockHeight) -> io::Result<()> {
+        let _gc_lock = lock_gc();
+        let key = index_to_bytes(height);
+        let canonical_hash: Option<CryptoHash> = self.store.get_ser(DBCol::BlockHeight, &key)?;
+        let block_hashes: HashMap<EpochId, HashSet<CryptoHash>> =
+            self.store.get_ser(DBCol::BlockPerHeight, &key)?.unwrap_or_default();
+        let mut store_update = self.store.store_update();
+        let mut num_removed = 0;
+        for block_hash in block_hashes.values().flatten() {
+            let is_canonical = canonical_hash.as_ref() == Some(block_hash);
+            for item in self
+                .store
+                .iter_prefix_ser::<Vec<CryptoHash>>(DBCol::OutcomeIds, block_hash.as_ref())
+            {
+                let (key, outcome_ids) = item?;
+                for outcome_id in outcome_ids {
+                    if is_canonical {
+                        store_update.set_ser(
+                            DBCol::CanonicalOutcomeBlock,
+                            outcome_id.as_ref(),
+                            block_hash,
+                        )?;
+                    } else {
+                        store_update.delete(
+                            DBCol::TransactionResultForBlock,
+                            &get_outcome_id_block_hash(&outcome_id, block_hash),
+                        );
+                        num_removed += 1;
+                    }
+                }
+                if !is_canonical {
+                    store_update.delete(DBCol::OutcomeIds, &key);
+                }
+            }
+        }
+        store_update.set_ser(DBCol::BlockMisc, OUTCOME_COMPACTION_KEY, &height)?;
+        store_update.commit()?;
+        metrics::OUTCOME_COMPACTION_HEIGHT.set(height as i64);
+        metrics::OUTCOME_COMPACTION_REMOVED_TOTAL.inc_by(num_removed);
+        Ok(())
+    }
+
+    /// Starts the compaction in a background thread, unless it's disabled in
+    /// the config.  The thread stops once the returned handle is dropped.
+    pub fn spawn(self) -> io::Result<Option<OutcomeCompactionHandle>> {
+        if !self.config.enabled {
+            return Ok(None);
+        }
+        let stop = Arc::new(AtomicBool::new(false));
+        let thread_stop = stop.clone();
+        std::thread::Builder::new().name("outcome_compaction".to_string()).spawn(move || {
+            while !thread_stop.load(Ordering::Relaxed) {
+                match self.run_step() {
+                    Ok(0) => std::thread::sleep(self.config.poll_interval),
+                    Ok(_) => {}
+                    Err(err) => {
+                        tracing::error!(target: "chain", ?err, "Outcome compaction failed");
+                        std::thread::sleep(self.config.poll_interval);
+                    }
+                }
+            }
+        })?;
+        Ok(Some(OutcomeCompactionHandle { stop }))
+    }
+}
+
+/// Stops the compaction thread when dropped.  The thread finishes the step
+/// it's in the middle of first.
+pub struct OutcomeCompactionHandle {
+    stop: Arc<AtomicBool>,
+}
+
+impl Drop for OutcomeCompactionHandle {
+    fn drop(&mut self) {
+        self.stop.store(true, Ordering::Relaxed);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{OutcomeCompaction, OutcomeCompactionConfig};
+    use crate::{ChainStore, ChainStoreAccess};
+    use near_primitives::block::Tip;
+    use near_primitives::hash::{hash, CryptoHash};
+    use near_primitives::transaction::ExecutionOutcomeWithProof;
+    use near_primitives::types::{BlockHeight, EpochId};
+    use near_primitives::utils::{get_block_shard_id, get_outcome_id_block_hash, index_to_bytes};
+    use near_store::test_utils::create_test_store;
+    use near_store::{DBCol, Store, FINAL_HEAD_KEY};
+    use std::collections::{HashMap, HashSet};
+
+    /// Saves blocks at `height`, the first one canonical, each of which
+    /// produced an outcome with id `outcome_id`.
+    fn save_blocks(
+        store: &Store,
+        height: BlockHeight,
+        block_hashes: &[CryptoHash],
+        outcome_id: &CryptoHash,
+    ) {
+        let mut store_update = store.store_update();
+        let key = index_to_bytes(height);
+        store_update.set_ser(DBCol::BlockHeight, &key, &block_hashes[0]).unwrap();
+        let per_height: HashMap<EpochId, HashSet<CryptoHash>> =
+            [(EpochId::default(), block_hashes.iter().copied().collect())].into_iter().collect();
+        store_update.set_ser(DBCol::BlockPerHeight, &key, &per_height).unwrap();
+        for block_hash in block_hashes {
+            store_update
+                .set_ser(DBCol::OutcomeIds, &get_block_shard_id(block_hash, 0), &vec![*outcome_id])
+                .unwrap();
+            store_update
+                .set_ser(
+                    DBCol::TransactionResultForBlock,
+                    &get_outcome_id_block_hash(outcome_id, block_hash),
+                    &ExecutionOutcomeWithProof { proof: vec![], outcome: Default::default() },
+                )
+                .unwrap();
+        }
+        store_update.commit().unwrap();
+    }
+
+    fn set_final_height(store: &Store, height: BlockHeight) {
+        let tip = Tip {
+            height,
+            last_block_hash: CryptoHash::default(),
+            prev_block_hash: CryptoHash::default(),
+            epoch_id: EpochId::default(),
+            next_epoch_id: EpochId::default(),
+        };
+        let mut store_update = store.store_update();
+        store_update.set_ser(DBCol::BlockMisc, FINAL_HEAD_KEY, &tip).unwrap();
+        store_update.commit().unwrap();
+    }
+
+    #[test]
+    fn test_compaction() {
+        let store = create_test_store();
+        let chain_store = ChainStore::new(store.clone(), 0, true);
+        let compaction = OutcomeCompaction::new(
+            store.clone(),
+            OutcomeCompactionConfig { max_heights_per_step: 2, ..Default::default() },
+        );
+        let canonical = hash(b"canonical");
+        let fork = hash(b"fork");
+        let outcome_id = hash(b"tx");
+        save_blocks(&store, 3, &[canonical, fork], &outcome_id);
+        let other_outcome_id = hash(b"other tx");
+        save_blocks(&store, 5, &[hash(b"later"), hash(b"later fork")], &other_outcome_id);
+
+        // Nothing to do without final head.
+        assert_eq!(compaction.run_step().unwrap(), 0);
+        assert_eq!(chain_store.get_outcomes_by_id(&outcome_id).unwrap().len(), 2);
+
+        set_final_height(&store, 4);
+        assert_eq!(compaction.run_step().unwrap(), 2);
+        assert_eq!(compaction.compacted_height().unwrap(), Some(2));
+        assert_eq!(compaction.run_step().unwrap(), 2);
+        assert_eq!(compaction.run_step().unwrap(), 0);
+        assert_eq!(compaction.compacted_height().unwrap(), Some(4));
+
+        let outcomes = chain_store.get_outcomes_by_id(&outcome_id).unwrap();
+        assert_eq!(outcomes.len(), 1);
+        assert_eq!(outcomes[0].block_hash, canonical);
+        assert!(chain_store.get_outcomes_by_block_hash_and_shard_id(&fork, 0).unwrap().is_empty());
+        assert_eq!(chain_store.get_canonical_outcome_block(&outcome_id).unwrap(), Some(canonical));
+
+        // Heights above the final head are left alone.
+        assert_eq!(chain_store.get_outcomes_by_id(&other_outcome_id).unwrap().len(), 2);
+        assert_eq!(chain_store.get_canonical_outcome_block(&other_outcome_id).unwrap(), None);
+    }
+}
@@ -0,0 +1,39 @@
+//! Orphans close to the head which survive restarts.
+//!
+//! Orphans are blocks whose previous block isn't processed yet, typically
+//! because the node learned about a fork late and is still fetching its
+//! ancestors.  The orphan pool lives in memory, so a node restarted during a
+//! fork forgets the orphans it collected and downloads the same recent blocks
+//! again once it's back.  When enabled, orphans within
+//! `PERSISTED_ORPHANS_HORIZON` heights of the head are saved as they enter
+//! the pool and removed as they leave it, and the saved ones are put back
+//! into the pool when the node starts.
+use std::io;
+
+use borsh::BorshDeserialize;
+use near_primitives::block::Block;
+use near_primitives::hash::CryptoHash;
+use near_primitives::types::BlockHeightDelta;
+use near_store::{DBCol, Store, StoreUpdate};
+
+/// Orphans further than this from the head aren't saved.
+pub(crate) const PERSISTED_ORPHANS_HORIZON: BlockHeightDelta = 64;
+
+pub(crate) fn save(store_update: &mut StoreUpdate, block: &Block) -> io::Result<()> {
+    store_update.set_ser(DBCol::Orphans, block.hash().as_ref(), block)
+}
+
+pub(crate) fn remove(store_update: &mut StoreUpdate, block_hash: &CryptoHash) {
+    store_update.delete(DBCol::Orphans, block_hash.as_ref());
+}
+
+/// Loads the orphans saved before the node was restarted.
+pub(crate) fn load(store: &Store) -> io::Result<Vec<Block>> {
+    store
+        .iter(DBCol::Orphans)
+        .map(|item| {
+            let (_, value) = item?;
+            Block::try_from_slice(&value)
+        })
+        .collect()
+}
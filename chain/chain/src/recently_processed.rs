@@ -0,0 +1,127 @@
+//! Record of the blocks processed most recently.
+//!
+//! After a restart the node doesn't remember which blocks and chunks it saw
+//! shortly before, so blocks peers send again are verified and rebroadcast
+//! once more, and parts of chunks which were completed before the restart get
+//! processed and requested all over again.  During incident recovery, when
+//! nodes restart repeatedly, this adds load just when there's the least of it
+//! to spare.
+//!
+//! The record holds hashes of the last [`MAX_BLOCKS`] fully processed blocks
+//! together with the hashes of the chunks included in them.  It's persisted
+//! after every processed block, so a restarted node can drop such blocks and
+//! chunks early.
+use std::collections::{HashSet, VecDeque};
+use std::io;
+
+use borsh::{BorshDeserialize, BorshSerialize};
+use near_primitives::hash::CryptoHash;
+use near_primitives::sharding::ChunkHash;
+use near_store::{DBCol, Store, StoreUpdate, RECENTLY_PROCESSED_KEY};
+
+/// Number of most recently processed blocks kept in the record.
+pub const MAX_BLOCKS: usize = 64;
+
+#[derive(BorshSerialize, BorshDeserialize)]
+struct Record {
+    /// Processed blocks, oldest first, with the chunks included in them.
+    blocks: Vec<(CryptoHash, Vec<ChunkHash>)>,
+}
+
+#[derive(Default)]
+pub struct RecentlyProcessed {
+    blocks: VecDeque<(CryptoHash, Vec<ChunkHash>)>,
+    block_hashes: HashSet<CryptoHash>,
+    chunk_hashes: HashSet<ChunkHash>,
+}
+
+impl RecentlyProcessed {
+    /// Loads the record persisted before the node was restarted.
+    pub fn load(store: &Store) -> io::Result<Self> {
+        let mut this = Self::default();
+        let record: Option<Record> = store.get_ser(DBCol::BlockMisc, RECENTLY_PROCESSED_KEY)?;
+        for (block_hash, chunk_hashes) in record.map_or(vec![], |record| record.blocks) {
+            this.add_block(block_hash, chunk_hashes);
+        }
+        Ok(this)
+    }
+
+    /// Saves the record so that it survives a restart.
+    pub fn save(&self, store_update: &mut StoreUpdate) -> io::Result<()> {
+        let record = Record { blocks: self.blocks.iter().cloned().collect() };
+        store_update.set_ser(DBCol::BlockMisc, RECENTLY_PROCESSED_KEY, &record)
+    }
+
+    /// Records a fully processed block and the chunks included in it.
+    pub fn add_block(&mut self, block_hash: CryptoHash, chunk_hashes: Vec<ChunkHash>) {
+        if !self.block_hashes.insert(block_hash) {
+            return;
+        }
+        self.chunk_hashes.extend(chunk_hashes.iter().cloned());
+        self.blocks.push_back((block_hash, chunk_hashes));
+        if self.blocks.len() > MAX_BLOCKS {
+            let (block_hash, chunk_hashes) = self.blocks.pop_front().unwrap();
+            self.block_hashes.remove(&block_hash);
+            for chunk_hash in chunk_hashes {
+                self.chunk_hashes.remove(&chunk_hash);
+            }
+        }
+    }
+
+    pub fn contains_block(&self, block_hash: &CryptoHash) -> bool {
+        self.block_hashes.contains(block_hash)
+    }
+
+    /// Hashes of the chunks included in the recorded blocks.
+    pub fn chunk_hashes(&self) -> &HashSet<ChunkHash> {
+        &self.chunk_hashes
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{RecentlyProcessed, MAX_BLOCKS};
+    use near_primitives::hash::{hash, CryptoHash};
+    use near_primitives::sharding::ChunkHash;
+    use near_store::test_utils::create_test_store;
+    use near_store::Store;
+
+    fn block(height: u64) -> (CryptoHash, Vec<ChunkHash>) {
+        let block_hash = hash(&height.to_le_bytes());
+        (block_hash, vec![ChunkHash(hash(block_hash.as_ref()))])
+    }
+
+    fn restart(store: &Store, recently_processed: &RecentlyProcessed) -> RecentlyProcessed {
+        let mut store_update = store.store_update();
+        recently_processed.save(&mut store_update).unwrap();
+        store_update.commit().unwrap();
+        RecentlyProcessed::load(store).unwrap()
+    }
+
+    #[test]
+    fn test_repeated_restarts() {
+        let store = create_test_store();
+        let mut recently_processed = RecentlyProcessed::load(&store).unwrap();
+        assert!(!recently_processed.contains_block(&block(0).0));
+
+        // Restart after every few blocks, with more blocks processed in total
+        // than the record holds.
+        let num_blocks = 3 * MAX_BLOCKS as u64;
+        for height in 0..num_blocks {
+            let (block_hash, chunk_hashes) = block(height);
+            recently_processed.add_block(block_hash, chunk_hashes);
+            if height % 5 == 0 {
+                recently_processed = restart(&store, &recently_processed);
+            }
+        }
+        recently_processed = restart(&store, &recently_processed);
+
+        for height in 0..num_blocks {
+            let (block_hash, chunk_hashes) = block(height);
+            let recorded = height >= num_blocks - MAX_BLOCKS as u64;
+            assert_eq!(recently_processed.contains_block(&block_hash), recorded);
+            assert_eq!(recently_processed.chunk_hashes().contains(&chunk_hashes[0]), recorded);
+        }
+        assert_eq!(recently_processed.chunk_hashes().len(), MAX_BLOCKS);
+    }
+}
@@ -0,0 +1,70 @@
+//! Readiness of the state of shards for the next epoch.
+//!
+//! A node which will track a shard in the next epoch, but doesn't track it in
+//! the current one, downloads the state of the shard at the first block of the
+//! current epoch and then catches up by applying chunks of the blocks accepted
+//! in the meantime.  Until the catchup is finished, chunks of the shard may be
+//! applied for the next epoch only by the catchup itself.
+//!
+//! The barrier makes that explicit.  The shards are marked pending when the
+//! first block of the epoch is accepted and marked ready in the same step in
+//! which the catchup is finished.  Blocks of the epoch are applied as caught up
+//! only once all its shards are ready, so a block accepted in between is always
+//! left to the catchup, however its processing interleaves with it.
+use std::collections::{HashMap, HashSet};
+
+use near_primitives::types::{EpochId, ShardId};
+
+#[derive(Default)]
+pub struct ShardReadiness {
+    /// Shards whose state for the next epoch isn't ready yet, by the epoch in
+    /// which they are caught up.
+    pending: HashMap<EpochId, HashSet<ShardId>>,
+}
+
+impl ShardReadiness {
+    pub fn mark_pending(&mut self, epoch_id: EpochId, shards: impl IntoIterator<Item = ShardId>) {
+        self.pending.entry(epoch_id).or_default().extend(shards);
+    }
+
+    /// Marks all shards caught up in the epoch as ready.
+    pub fn mark_ready(&mut self, epoch_id: &EpochId) {
+        self.pending.remove(epoch_id);
+    }
+
+    pub fn is_ready(&self, epoch_id: &EpochId, shard_id: ShardId) -> bool {
+        self.pending.get(epoch_id).map_or(true, |shards| !shards.contains(&shard_id))
+    }
+
+    /// Returns true if no shard caught up in the epoch is pending.
+    pub fn is_epoch_ready(&self, epoch_id: &EpochId) -> bool {
+        !self.pending.contains_key(epoch_id)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::ShardReadiness;
+    use near_primitives::hash::CryptoHash;
+    use near_primitives::types::EpochId;
+
+    #[test]
+    fn test_shard_readiness() {
+        let epoch_id = EpochId(CryptoHash::hash_bytes(b"epoch"));
+        let other_epoch_id = EpochId::default();
+        let mut readiness = ShardReadiness::default();
+        assert!(readiness.is_epoch_ready(&epoch_id));
+
+        readiness.mark_pending(epoch_id.clone(), [1, 3]);
+        assert!(!readiness.is_epoch_ready(&epoch_id));
+        assert!(readiness.is_ready(&epoch_id, 0));
+        assert!(!readiness.is_ready(&epoch_id, 1));
+        assert!(!readiness.is_ready(&epoch_id, 3));
+        assert!(readiness.is_ready(&other_epoch_id, 1));
+        assert!(readiness.is_epoch_ready(&other_epoch_id));
+
+        readiness.mark_ready(&epoch_id);
+        assert!(readiness.is_epoch_ready(&epoch_id));
+        assert!(readiness.is_ready(&epoch_id, 1));
+    }
+}
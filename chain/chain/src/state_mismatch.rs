@@ -0,0 +1,315 @@
+//! Forensic bundles of state root mismatches.
+//!
+//! A chunk whose header disagrees with the state root this node computed for
+//! the shard in the previous block fails with `InvalidChunkState`.  Either
+//! this node or the chunk producer applied the shard differently, which
+//! usually means a consensus bug.  Investigating it requires replaying the
+//! application, so the node captures a bundle with everything the replay
+//! needs: the headers, the applied chunk, its incoming receipts, the trie
+//! nodes read while applying it (the witness), the resulting outcomes and the
+//! versions of the node and of the protocol.  The application is replayed
+//! right away to record the witness, which also makes the bundle
+//! self-contained: it doesn't depend on the database of the node.
+//!
+//! Bundles are borsh serialized into `<dir>/<block_hash>_<shard_id>.bin`,
+//! where the block is the one with the chunk which disagrees.
+//!
+//! The replay takes about as long as applying the chunk, so bundles are
+//! captured in the background, one at a time, rather than while the block is
+//! being processed.  A node which disagrees with every chunk would otherwise
+//! fill its disk, so at most `MAX_BUNDLES` bundles of at most
+//! `MAX_BUNDLE_SIZE` bytes each are kept; further mismatches are only logged.
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Arc;
+
+use borsh::{BorshDeserialize, BorshSerialize};
+use near_primitives::block::{Block, BlockHeader};
+use near_primitives::challenge::PartialState;
+use near_primitives::hash::CryptoHash;
+use near_primitives::receipt::Receipt;
+use near_primitives::sandbox::state_patch::SandboxStatePatch;
+use near_primitives::sharding::{ShardChunk, ShardChunkHeader};
+use near_primitives::transaction::ExecutionOutcomeWithId;
+use near_primitives::types::chunk_extra::ChunkExtra;
+use near_primitives::types::{ShardId, StateRoot};
+use near_primitives::version::{ProtocolVersion, Version};
+use tracing::{error, warn};
+
+use crate::chain::collect_receipts_from_response;
+use crate::migrations::check_if_block_is_first_with_chunk_of_version;
+use crate::types::RuntimeAdapter;
+use crate::{ChainStore, ChainStoreAccess, Error};
+
+/// Number of bundles in the directory at most.
+const MAX_BUNDLES: usize = 16;
+/// Size of a bundle in bytes at most.
+const MAX_BUNDLE_SIZE: usize = 256 * 1024 * 1024;
+
+/// Saves bundles of state root mismatches into a directory.
+pub(crate) struct StateMismatchBundles {
+    dir: PathBuf,
+    /// Version of the node recorded in the bundles.
+    node_version: Version,
+    /// Whether a bundle is being captured.
+    capturing: Arc<AtomicBool>,
+}
+
+impl StateMismatchBundles {
+    pub fn new(dir: PathBuf, node_version: Version) -> Self {
+        Self { dir, node_version, capturing: Arc::new(AtomicBool::new(false)) }
+    }
+
+    /// Captures the bundle of the chunk disagreeing with the state root
+    /// computed for its shard in `prev_block` and saves it in the background,
+    /// unless it's saved already, another bundle is being captured or there
+    /// are `MAX_BUNDLES` bundles already.  Failures are only logged.
+    pub fn save_in_background(
+        &self,
+        store: &ChainStore,
+        runtime_adapter: Arc<dyn RuntimeAdapter>,
+        prev_block: &Block,
+        block: &Block,
+        chunk_header: &ShardChunkHeader,
+        prev_chunk_extra: ChunkExtra,
+    ) {
+        let block_hash = *block.hash();
+        let shard_id = chunk_header.shard_id();
+        if StateMismatchBundle::path(&self.dir, &block_hash, shard_id).exists() {
+            return;
+        }
+        let num_bundles = count_bundles(&self.dir);
+        if num_bundles >= MAX_BUNDLES {
+            warn!(
+                target: "chain",
+                %block_hash,
+                shard_id,
+                num_bundles,
+                "State root mismatch, not saving forensic bundle as there are too many already");
+            return;
+        }
+        if self.capturing.swap(true, Ordering::AcqRel) {
+            warn!(
+                target: "chain",
+                %block_hash,
+                shard_id,
+                "State root mismatch, not saving forensic bundle as another one is being captured");
+            return;
+        }
+        let store = ChainStore::new(store.store().clone(), store.get_genesis_height(), false);
+        let dir = self.dir.clone();
+        let node_version = self.node_version.clone();
+        let capturing = self.capturing.clone();
+        let prev_block = prev_block.clone();
+        let block = block.clone();
+        let chunk_header = chunk_header.clone();
+        let spawned =
+            std::thread::Builder::new().name("state_mismatch_bundle".into()).spawn(move || {
+                let result = StateMismatchBundle::capture(
+                    &store,
+                    &*runtime_adapter,
+                    &node_version,
+                    &prev_block,
+                    &block,
+                    &chunk_header,
+                    prev_chunk_extra,
+                )
+                .map_err(|err| err.to_string())
+                .and_then(|bundle| bundle.save(&dir).map_err(|err| err.to_string()));
+                capturing.store(false, Ordering::Release);
+                match result {
+                    Ok(path) => error!(
+                        target: "chain",
+                        %block_hash,
+                        shard_id,
+                        path = %path.display(),
+                        "State root mismatch, saved forensic bundle"),
+                    Err(err) => warn!(
+                        target: "chain",
+                        %block_hash,
+                        shard_id,
+                        %err,
+                        "Failed to save forensic bundle of state root mismatch"),
+                }
+            });
+        if let Err(err) = spawned {
+            self.capturing.store(false, Ordering::Release);
+            warn!(target: "chain", %err, "Failed to start capturing forensic bundle");
+        }
+    }
+}
+
+/// Returns the number of bundles in `dir`, which may not exist yet.
+fn count_bundles(dir: &Path) -> usize {
+    match std::fs::read_dir(dir) {
+        Ok(entries) => entries
+            .filter_map(Result::ok)
+            .filter(|entry| entry.path().extension().map_or(false, |ext| ext == "bin"))
+            .count(),
+        Err(_) => 0,
+    }
+}
+
+#[derive(BorshSerialize, BorshDeserialize, Debug)]
+pub struct StateMismatchBundle {
+    /// Version and build of the node which wrote the bundle, and the version
+    /// of rustc it was built with.
+    pub node_version: String,
+    pub node_build: String,
+    pub rustc_version: String,
+    /// Protocol version of the epoch of the block.
+    pub protocol_version: ProtocolVersion,
+    pub shard_id: ShardId,
+    /// Block with the chunk which disagrees and the header of that chunk.
+    pub block_header: BlockHeader,
+    pub chunk_header: ShardChunkHeader,
+    /// The previous block, the application of the shard in which is replayed.
+    pub prev_block_header: BlockHeader,
+    /// Chunk extra this node computed for the shard in the previous block.
+    pub prev_chunk_extra: ChunkExtra,
+    /// Chunk applied in the previous block, None if the previous block has
+    /// no new chunk for the shard, in which case only the validator
+    /// proposals were applied.
+    pub prev_chunk: Option<ShardChunk>,
+    /// State root the replay started from.
+    pub prev_state_root: StateRoot,
+    pub incoming_receipts: Vec<Receipt>,
+    /// Trie nodes read by the replay.
+    pub partial_state: PartialState,
+    pub outcomes: Vec<ExecutionOutcomeWithId>,
+    /// State root computed by the replay.
+    pub new_state_root: StateRoot,
+}
+
+impl StateMismatchBundle {
+    /// Replays the application of the shard of `chunk_header` in `prev_block`,
+    /// whose result disagrees with the header.
+    pub(crate) fn capture(
+        store: &ChainStore,
+        runtime_adapter: &dyn RuntimeAdapter,
+        node_version: &Version,
+        prev_block: &Block,
+        block: &Block,
+        chunk_header: &ShardChunkHeader,
+        prev_chunk_extra: ChunkExtra,
+    ) -> Result<Self, Error> {
+        let shard_id = chunk_header.shard_id();
+        let prev_prev_block = store.get_block(prev_block.header().prev_hash())?;
+        let prev_chunk_header = &prev_block.chunks()[shard_id as usize];
+        let header = prev_block.header();
+        let challenges_result = header.challenges_result();
+        let (prev_chunk, prev_state_root, incoming_receipts, apply_result) =
+            if prev_chunk_header.height_included() == header.height() {
+                let prev_chunk = store.get_chunk_clone_from_header(prev_chunk_header)?;
+                let last_height_included =
+                    prev_prev_block.chunks()[shard_id as usize].height_included();
+                let receipts =
+                    collect_receipts_from_response(&store.get_incoming_receipts_for_shard(
+                        shard_id,
+                        *prev_block.hash(),
+                        last_height_included,
+                    )?);
+                let is_first_block_with_chunk_of_version =
+                    check_if_block_is_first_with_chunk_of_version(
+                        store,
+                        runtime_adapter,
+                        prev_prev_block.hash(),
+                        shard_id,
+                    )?;
+                let prev_state_root = prev_chunk_header.prev_state_root();
+                let apply_result = runtime_adapter.apply_transactions_with_optional_storage_proof(
+                    shard_id,
+                    &prev_state_root,
+                    prev_chunk_header.height_included(),
+                    header.raw_timestamp(),
+                    prev_chunk_header.prev_block_hash(),
+                    prev_block.hash(),
+                    &receipts,
+                    prev_chunk.transactions(),
+                    prev_chunk_header.validator_proposals(),
+                    prev_prev_block.header().gas_price(),
+                    prev_chunk_header.gas_limit(),
+                    challenges_result,
+                    *header.random_value(),
+                    true,
+                    true,
+                    is_first_block_with_chunk_of_version,
+                    SandboxStatePatch::default(),
+                    // Reads through flat storage don't touch trie nodes, so
+                    // they wouldn't make it into the witness.
+                    false,
+                )?;
+                (Some(prev_chunk), prev_state_root, receipts, apply_result)
+            } else {
+                let shard_uid = runtime_adapter.shard_id_to_uid(shard_id, header.epoch_id())?;
+                let extra = store.get_chunk_extra(prev_prev_block.hash(), &shard_uid)?;
+                let apply_result = runtime_adapter.apply_transactions_with_optional_storage_proof(
+                    shard_id,
+                    extra.state_root(),
+                    header.height(),
+                    header.raw_timestamp(),
+                    prev_prev_block.hash(),
+                    prev_block.hash(),
+                    &[],
+                    &[],
+                    extra.validator_proposals(),
+                    header.gas_price(),
+                    extra.gas_limit(),
+                    challenges_result,
+                    *header.random_value(),
+                    true,
+                    false,
+                    false,
+                    SandboxStatePatch::default(),
+                    false,
+                )?;
+                (None, *extra.state_root(), vec![], apply_result)
+            };
+        Ok(Self {
+            node_version: node_version.version.clone(),
+            node_build: node_version.build.clone(),
+            rustc_version: node_version.rustc_version.clone(),
+            protocol_version: runtime_adapter
+                .get_epoch_protocol_version(block.header().epoch_id())?,
+            shard_id,
+            block_header: block.header().clone(),
+            chunk_header: chunk_header.clone(),
+            prev_block_header: header.clone(),
+            prev_chunk_extra,
+            prev_chunk,
+            prev_state_root,
+            incoming_receipts,
+            partial_state: apply_result.proof.map_or(PartialState(vec![]), |proof| proof.nodes),
+            outcomes: apply_result.outcomes,
+            new_state_root: apply_result.new_root,
+        })
+    }
+
+    /// Path of the bundle of the chunk of the shard in the block.
+    pub fn path(dir: &Path, block_hash: &CryptoHash, shard_id: ShardId) -> PathBuf {
+        dir.join(format!("{}_{}.bin", block_hash, shard_id))
+    }
+
+    /// Writes the bundle into `dir`, returning the path of the file.  Fails
+    /// if the bundle is bigger than `MAX_BUNDLE_SIZE`.  The file is written
+    /// under a temporary name first, so that the bundle appears complete.
+    pub fn save(&self, dir: &Path) -> std::io::Result<PathBuf> {
+        let bytes = self.try_to_vec()?;
+        if bytes.len() > MAX_BUNDLE_SIZE {
+            return Err(std::io::Error::new(
+                std::io::ErrorKind::Other,
+                format!("bundle has {} bytes, limit is {}", bytes.len(), MAX_BUNDLE_SIZE),
+            ));
+        }
+        std::fs::create_dir_all(dir)?;
+        let path = Self::path(dir, self.block_header.hash(), self.shard_id);
+        let tmp_path = path.with_extension("tmp");
+        std::fs::write(&tmp_path, bytes)?;
+        std::fs::rename(&tmp_path, &path)?;
+        Ok(path)
+    }
+
+    pub fn load(path: &Path) -> std::io::Result<Self> {
+        Self::try_from_slice(&std::fs::read(path)?)
+    }
+}
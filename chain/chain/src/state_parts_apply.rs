@@ -0,0 +1,166 @@
+//! Application of downloaded state parts.
+//!
+//! Parts of a shard are independent of each other, so they are validated and
+//! applied concurrently on the rayon pool.  Every part being applied holds its
+//! serialized data, the deserialized trie nodes and the resulting trie changes
+//! in memory at once, so the number of parts in flight is bounded by a memory
+//! budget rather than by the size of the pool.  Parts are read from the store
+//! only once there's room for them in the budget.
+use std::sync::atomic::Ordering;
+use std::sync::{Condvar, Mutex};
+
+use borsh::BorshSerialize;
+use near_primitives::state_part::PartId;
+use near_primitives::syncing::StatePartKey;
+use near_store::DBCol;
+use tracing::debug;
+
+use crate::chain::ApplyStatePartsRequest;
+use crate::Error;
+
+/// Memory taken by a part being applied, relative to its serialized size.
+const MEMORY_PER_PART_BYTE: u64 = 3;
+
+/// Bounds the total memory of the parts in flight.
+struct MemoryBudget {
+    limit: u64,
+    used: Mutex<u64>,
+    released: Condvar,
+}
+
+impl MemoryBudget {
+    fn new(limit: u64) -> Self {
+        Self { limit, used: Mutex::new(0), released: Condvar::new() }
+    }
+
+    /// Blocks until `bytes` fit in the budget.  A part larger than the whole
+    /// budget is let through once nothing else is in flight, so it doesn't
+    /// block the application forever.
+    fn acquire(&self, bytes: u64) -> MemoryBudgetGuard<'_> {
+        let mut used = self.used.lock().unwrap();
+        while *used > 0 && *used + bytes > self.limit {
+            used = self.released.wait(used).unwrap();
+        }
+        *used += bytes;
+        MemoryBudgetGuard { budget: self, bytes }
+    }
+}
+
+struct MemoryBudgetGuard<'a> {
+    budget: &'a MemoryBudget,
+    bytes: u64,
+}
+
+impl Drop for MemoryBudgetGuard<'_> {
+    fn drop(&mut self) {
+        *self.budget.used.lock().unwrap() -= self.bytes;
+        self.budget.released.notify_all();
+    }
+}
+
+/// Validates and applies all parts of the request, using at most about
+/// `memory_limit` bytes for the parts in flight.  Stops scheduling parts after
+/// the first failure.
+pub fn apply_state_parts(request: &ApplyStatePartsRequest, memory_limit: u64) -> Result<(), Error> {
+    let _span = tracing::debug_span!(
+        target: "sync",
+        "apply_state_parts",
+        shard_id = request.shard_id,
+        num_parts = request.num_parts
+    )
+    .entered();
+    let store = request.runtime.store();
+    let budget = MemoryBudget::new(memory_limit);
+    let result = Mutex::new(Ok(()));
+    rayon::in_place_scope(|scope| {
+        for part_id in 0..request.num_parts {
+            if result.lock().unwrap().is_err() {
+                break;
+            }
+            let key = StatePartKey(request.sync_hash, request.shard_id, part_id).try_to_vec()?;
+            let part = store.get(DBCol::StateParts, &key)?.ok_or_else(|| {
+                Error::Other(format!(
+                    "state part {} of shard {} is missing",
+                    part_id, request.shard_id
+                ))
+            })?;
+            let guard = budget.acquire(part.len() as u64 * MEMORY_PER_PART_BYTE);
+            request.status.applying_parts.lock().unwrap().insert(part_id);
+            let result = &result;
+            scope.spawn(move |_| {
+                let _guard = guard;
+                let part_result = apply_state_part(request, part_id, &part);
+                request.status.applying_parts.lock().unwrap().remove(&part_id);
+                match part_result {
+                    Ok(()) => {
+                        request.status.applied_parts.fetch_add(1, Ordering::Relaxed);
+                    }
+                    Err(err) => {
+                        debug!(target: "sync", part_id, ?err, "Failed to apply state part");
+                        let mut result = result.lock().unwrap();
+                        if result.is_ok() {
+                            *result = Err(err);
+                        }
+                    }
+                }
+            });
+        }
+        Ok::<(), Error>(())
+    })?;
+    result.into_inner().unwrap()
+}
+
+fn apply_state_part(
+    request: &ApplyStatePartsRequest,
+    part_id: u64,
+    part: &[u8],
+) -> Result<(), Error> {
+    let part_id = PartId::new(part_id, request.num_parts);
+    if !request.runtime.validate_state_part(&request.state_root, part_id, part) {
+        return Err(Error::Other(format!(
+            "state part {} of shard {} failed validation",
+            part_id.idx, request.shard_id
+        )));
+    }
+    request.runtime.apply_state_part(
+        request.shard_id,
+        &request.state_root,
+        part_id,
+        part,
+        &request.epoch_id,
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::MemoryBudget;
+    use std::sync::atomic::{AtomicU64, Ordering};
+
+    #[test]
+    fn test_memory_budget() {
+        let budget = MemoryBudget::new(100);
+        let first = budget.acquire(60);
+        let second = budget.acquire(40);
+        assert_eq!(*budget.used.lock().unwrap(), 100);
+        drop((first, second));
+
+        // A part larger than the budget gets through alone.
+        let large = budget.acquire(300);
+        assert_eq!(*budget.used.lock().unwrap(), 300);
+        drop(large);
+
+        // Parts which don't fit wait until others are done.
+        let max_used = AtomicU64::new(0);
+        std::thread::scope(|scope| {
+            for _ in 0..8 {
+                scope.spawn(|| {
+                    let _guard = budget.acquire(30);
+                    max_used.fetch_max(*budget.used.lock().unwrap(), Ordering::SeqCst);
+                    std::thread::sleep(std::time::Duration::from_millis(5));
+                });
+            }
+        });
+        assert!(max_used.load(Ordering::SeqCst) <= 90);
+        assert_eq!(*budget.used.lock().unwrap(), 0);
+    }
+}
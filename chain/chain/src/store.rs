@@ -39,13 +39,13 @@ use near_primitives::utils::{
 use near_primitives::views::LightClientBlockView;
 use near_store::{
     DBCol, KeyForStateChanges, ShardTries, Store, StoreUpdate, WrappedTrieChanges, CHUNK_TAIL_KEY,
-    FINAL_HEAD_KEY, FORK_TAIL_KEY, HEADER_HEAD_KEY, HEAD_KEY, LARGEST_TARGET_HEIGHT_KEY,
-    LATEST_KNOWN_KEY, TAIL_KEY,
+    COLD_HEAD_KEY, FINAL_HEAD_KEY, FORK_TAIL_KEY, HEADER_HEAD_KEY, HEAD_KEY,
+    LARGEST_ENDORSED_HEIGHT_KEY, LARGEST_TARGET_HEIGHT_KEY, LATEST_KNOWN_KEY, TAIL_KEY,
 };
 
-use crate::chunks_store::ReadOnlyChunksStore;
+use crate::chunks_store::{verified_receipt_proof_key, ReadOnlyChunksStore};
 use crate::types::{Block, BlockHeader, LatestKnown};
-use crate::{byzantine_assert, RuntimeAdapter};
+use crate::{byzantine_assert, metrics, RuntimeAdapter};
 use near_store::db::StoreStatistics;
 use near_store::flat_state::{BlockInfo, ChainAccessForFlatStorage};
 use std::sync::Arc;
@@ -88,6 +88,8 @@ pub trait ChainStoreAccess {
     fn final_head(&self) -> Result<Tip, Error>;
     /// Largest approval target height sent by us
     fn largest_target_height(&self) -> Result<BlockHeight, Error>;
+    /// Height of the largest block endorsed by us
+    fn largest_endorsed_height(&self) -> Result<BlockHeight, Error>;
     /// Get full block.
     fn get_block(&self, h: &CryptoHash) -> Result<Block, Error>;
     /// Get full chunk.
@@ -605,6 +607,15 @@ impl ChainStore {
         )?)
     }
 
+    /// Returns the block on the canonical chain which produced the outcome
+    /// with given id, if the outcome compaction has recorded it.
+    pub fn get_canonical_outcome_block(
+        &self,
+        id: &CryptoHash,
+    ) -> Result<Option<CryptoHash>, Error> {
+        Ok(self.store.get_ser(DBCol::CanonicalOutcomeBlock, id.as_ref())?)
+    }
+
     /// Returns a vector of Outcome ids for given block and shard id
     pub fn get_outcomes_by_block_hash_and_shard_id(
         &self,
@@ -686,6 +697,12 @@ impl ChainStore {
         store_update.commit().map_err(|err| err.into())
     }
 
+    /// Returns height up to which blocks have been copied to cold storage and
+    /// verified there, or `None` if nothing has been migrated yet.
+    pub fn cold_head(&self) -> Result<Option<BlockHeight>, Error> {
+        self.store.get_ser(DBCol::BlockMisc, COLD_HEAD_KEY).map_err(|e| e.into())
+    }
+
     /// Retrieve the kinds of state changes occurred in a given block.
     ///
     /// We store different types of data, so we prefer to only expose minimal information about the
@@ -892,6 +909,15 @@ impl ChainStoreAccess for ChainStore {
         }
     }
 
+    /// Largest height of a block for which we created a doomslug endorsement
+    fn largest_endorsed_height(&self) -> Result<BlockHeight, Error> {
+        match self.store.get_ser(DBCol::BlockMisc, LARGEST_ENDORSED_HEIGHT_KEY) {
+            Ok(Some(o)) => Ok(o),
+            Ok(None) => Ok(0),
+            Err(e) => Err(e.into()),
+        }
+    }
+
     /// Head of the header chain (not the same thing as head_header).
     fn header_head(&self) -> Result<Tip, Error> {
         option_to_not_found(self.store.get_ser(DBCol::BlockMisc, HEADER_HEAD_KEY), "HEADER_HEAD")
@@ -1187,6 +1213,7 @@ pub struct ChainStoreUpdate<'a> {
     header_head: Option<Tip>,
     final_head: Option<Tip>,
     largest_target_height: Option<BlockHeight>,
+    largest_endorsed_height: Option<BlockHeight>,
     trie_changes: Vec<WrappedTrieChanges>,
     // All state changes made by a chunk, this is only used for splitting states
     add_state_changes_for_split_states: HashMap<(CryptoHash, ShardId), StateChangesForSplitStates>,
@@ -1199,6 +1226,9 @@ pub struct ChainStoreUpdate<'a> {
     add_state_dl_infos: Vec<StateSyncInfo>,
     remove_state_dl_infos: Vec<CryptoHash>,
     challenged_blocks: HashSet<CryptoHash>,
+    /// Call-site the writes of this update are attributed to, see
+    /// `near_store::write_attribution`.
+    tag: Option<&'static str>,
 }
 
 impl<'a> ChainStoreUpdate<'a> {
@@ -1214,6 +1244,7 @@ impl<'a> ChainStoreUpdate<'a> {
             header_head: None,
             final_head: None,
             largest_target_height: None,
+            largest_endorsed_height: None,
             trie_changes: vec![],
             add_state_changes_for_split_states: HashMap::new(),
             remove_state_changes_for_split_states: HashSet::new(),
@@ -1223,8 +1254,15 @@ impl<'a> ChainStoreUpdate<'a> {
             add_state_dl_infos: vec![],
             remove_state_dl_infos: vec![],
             challenged_blocks: HashSet::default(),
+            tag: None,
         }
     }
+
+    /// Attributes the writes of this update to the given call-site.  Writes
+    /// made by garbage collection are attributed to `gc` regardless.
+    pub fn set_tag(&mut self, tag: &'static str) {
+        self.tag = Some(tag);
+    }
 }
 
 impl<'a> ChainStoreAccess for ChainStoreUpdate<'a> {
@@ -1293,6 +1331,14 @@ impl<'a> ChainStoreAccess for ChainStoreUpdate<'a> {
         }
     }
 
+    fn largest_endorsed_height(&self) -> Result<BlockHeight, Error> {
+        if let Some(largest_endorsed_height) = &self.largest_endorsed_height {
+            Ok(*largest_endorsed_height)
+        } else {
+            self.chain_store.largest_endorsed_height()
+        }
+    }
+
     /// Header of the block at the head of the block chain (not the same thing as header_head).
     fn head_header(&self) -> Result<BlockHeader, Error> {
         self.get_block_header(&(self.head()?.last_block_hash))
@@ -1670,6 +1716,10 @@ impl<'a> ChainStoreUpdate<'a> {
         self.largest_target_height = Some(height);
     }
 
+    pub fn save_largest_endorsed_height(&mut self, height: BlockHeight) {
+        self.largest_endorsed_height = Some(height);
+    }
+
     /// Save new height if it's above currently latest known.
     pub fn try_save_latest_known(&mut self, height: BlockHeight) -> Result<(), Error> {
         let latest_known = self.chain_store.get_latest_known().ok();
@@ -1692,6 +1742,7 @@ impl<'a> ChainStoreUpdate<'a> {
 
     /// Save block.
     pub fn save_block(&mut self, block: Block) {
+        self.tag.get_or_insert("block_save");
         self.chain_store_cache_update.blocks.insert(*block.hash(), block);
     }
 
@@ -1960,6 +2011,7 @@ impl<'a> ChainStoreUpdate<'a> {
                 self.gc_col(DBCol::Chunks, chunk_hash);
                 self.gc_col(DBCol::PartialChunks, chunk_hash);
                 self.gc_col(DBCol::InvalidChunks, chunk_hash);
+                self.gc_verified_receipt_proofs(chunk_hash)?;
             }
 
             let header_hashes = self.chain_store.get_all_header_hashes_by_height(height)?;
@@ -2012,6 +2064,7 @@ impl<'a> ChainStoreUpdate<'a> {
                     // cannot be calculated from other data) but it is data we
                     // don’t need for anything so it can be deleted as well.
                     self.gc_col(DBCol::InvalidChunks, chunk_hash);
+                    self.gc_verified_receipt_proofs(chunk_hash)?;
                 }
             }
         }
@@ -2019,6 +2072,19 @@ impl<'a> ChainStoreUpdate<'a> {
         Ok(())
     }
 
+    fn gc_verified_receipt_proofs(&mut self, chunk_hash: &[u8]) -> Result<(), Error> {
+        let keys: Vec<Box<[u8]>> = self
+            .chain_store
+            .store()
+            .iter_prefix(DBCol::VerifiedReceiptProofs, chunk_hash)
+            .map(|item| item.map(|(key, _)| key))
+            .collect::<io::Result<Vec<_>>>()?;
+        for key in keys {
+            self.gc_col(DBCol::VerifiedReceiptProofs, &key);
+        }
+        Ok(())
+    }
+
     fn get_shard_uids_to_gc(
         &mut self,
         runtime_adapter: &dyn RuntimeAdapter,
@@ -2053,6 +2119,7 @@ impl<'a> ChainStoreUpdate<'a> {
         gc_mode: GCMode,
     ) -> Result<(), Error> {
         let mut store_update = self.store().store_update();
+        store_update.set_tag("gc");
 
         // 1. Apply revert insertions or deletions from DBCol::TrieChanges for Trie
         {
@@ -2193,6 +2260,7 @@ impl<'a> ChainStoreUpdate<'a> {
         epoch_id: &EpochId,
     ) -> Result<(), Error> {
         let mut store_update = self.store().store_update();
+        store_update.set_tag("gc");
         let epoch_to_hashes = self.chain_store.get_all_block_hashes_by_height(height)?;
         let mut epoch_to_hashes = HashMap::clone(&epoch_to_hashes);
         let hashes = epoch_to_hashes.get_mut(epoch_id).ok_or_else(|| {
@@ -2223,15 +2291,26 @@ impl<'a> ChainStoreUpdate<'a> {
         shard_id: ShardId,
         num_parts: u64,
     ) -> Result<(), Error> {
+        let (mut removed, mut removed_bytes) = (0, 0);
         for part_id in 0..num_parts {
             let key = StatePartKey(sync_hash, shard_id, part_id).try_to_vec()?;
+            if let Some(part) = self.store().get(DBCol::StateParts, &key)? {
+                removed += 1;
+                removed_bytes += part.len() as i64;
+            }
             self.gc_col(DBCol::StateParts, &key);
         }
+        // Parts stored before the node restarted were never counted.
+        let parts = &metrics::STATE_SYNC_PARTS_STORED;
+        parts.sub(removed.min(parts.get()));
+        let bytes = &metrics::STATE_SYNC_PARTS_STORED_BYTES;
+        bytes.sub(removed_bytes.min(bytes.get()));
         Ok(())
     }
 
     pub fn gc_outgoing_receipts(&mut self, block_hash: &CryptoHash, shard_id: ShardId) {
         let mut store_update = self.store().store_update();
+        store_update.set_tag("gc");
         match self
             .get_outgoing_receipts(block_hash, shard_id)
             .map(|receipts| receipts.iter().map(|receipt| receipt.receipt_id).collect::<Vec<_>>())
@@ -2276,6 +2355,9 @@ impl<'a> ChainStoreUpdate<'a> {
                     DBCol::TransactionResultForBlock,
                     &get_outcome_id_block_hash(&outcome_id, block_hash),
                 );
+                if self.chain_store.get_canonical_outcome_block(&outcome_id)? == Some(*block_hash) {
+                    self.gc_col(DBCol::CanonicalOutcomeBlock, outcome_id.as_ref());
+                }
             }
             self.gc_col(DBCol::OutcomeIds, &get_block_shard_id(block_hash, shard_id));
         }
@@ -2285,6 +2367,7 @@ impl<'a> ChainStoreUpdate<'a> {
 
     fn gc_col(&mut self, col: DBCol, key: &[u8]) {
         let mut store_update = self.store().store_update();
+        store_update.set_tag("gc");
         match col {
             DBCol::OutgoingReceipts => {
                 panic!("Must use gc_outgoing_receipts");
@@ -2355,6 +2438,9 @@ impl<'a> ChainStoreUpdate<'a> {
                 store_update.delete(col, key);
                 self.chain_store.invalid_chunks.pop(key);
             }
+            DBCol::VerifiedReceiptProofs => {
+                store_update.delete(col, key);
+            }
             DBCol::ChunkHashesByHeight => {
                 store_update.delete(col, key);
             }
@@ -2376,6 +2462,9 @@ impl<'a> ChainStoreUpdate<'a> {
             DBCol::OutcomeIds => {
                 store_update.delete(col, key);
             }
+            DBCol::CanonicalOutcomeBlock => {
+                store_update.delete(col, key);
+            }
             DBCol::StateDlInfos => {
                 store_update.delete(col, key);
             }
@@ -2411,6 +2500,15 @@ impl<'a> ChainStoreUpdate<'a> {
             | DBCol::_TransactionRefCount
             | DBCol::_TransactionResult
             | DBCol::StateChangesForSplitStates
+            | DBCol::StateSplitCheckpoints
+            | DBCol::PeerAuditLog
+            | DBCol::ColdMigrationProgress
+            | DBCol::RoutingEdges
+            | DBCol::ContractEvents
+            | DBCol::ContractCodeAccounts
+            | DBCol::DoomslugApprovals
+            | DBCol::Orphans
+            | DBCol::BlsKeys
             | DBCol::CachedContractCode => {
                 unreachable!();
             }
@@ -2555,6 +2653,9 @@ impl<'a> ChainStoreUpdate<'a> {
 
     fn finalize(&mut self) -> Result<StoreUpdate, Error> {
         let mut store_update = self.store().store_update();
+        if let Some(tag) = self.tag {
+            store_update.set_tag(tag);
+        }
         Self::write_col_misc(&mut store_update, HEAD_KEY, &mut self.head)?;
         Self::write_col_misc(&mut store_update, TAIL_KEY, &mut self.tail)?;
         Self::write_col_misc(&mut store_update, CHUNK_TAIL_KEY, &mut self.chunk_tail)?;
@@ -2566,6 +2667,11 @@ impl<'a> ChainStoreUpdate<'a> {
             LARGEST_TARGET_HEIGHT_KEY,
             &mut self.largest_target_height,
         )?;
+        Self::write_col_misc(
+            &mut store_update,
+            LARGEST_ENDORSED_HEIGHT_KEY,
+            &mut self.largest_endorsed_height,
+        )?;
         debug_assert!(self.chain_store_cache_update.blocks.len() <= 1);
         for (hash, block) in self.chain_store_cache_update.blocks.iter() {
             let mut map =
@@ -2672,6 +2778,12 @@ impl<'a> ChainStoreUpdate<'a> {
         }
         for (chunk_hash, partial_chunk) in self.chain_store_cache_update.partial_chunks.iter() {
             store_update.insert_ser(DBCol::PartialChunks, chunk_hash.as_ref(), partial_chunk)?;
+            // Receipt proofs of a saved partial chunk have either been verified
+            // or built by this node, mark them so they aren't verified again.
+            for receipt_proof in partial_chunk.receipts() {
+                let key = verified_receipt_proof_key(chunk_hash, receipt_proof);
+                store_update.set(DBCol::VerifiedReceiptProofs, &key, &[]);
+            }
         }
         for (height, hash) in self.chain_store_cache_update.height_to_hashes.iter() {
             if let Some(hash) = hash {
@@ -3005,8 +3117,14 @@ mod tests {
             .block_producers_per_epoch(vec![vec!["test1".parse().unwrap()]]);
         let runtime_adapter =
             Arc::new(KeyValueRuntime::new_with_validators(store, vs, epoch_length));
-        Chain::new(runtime_adapter, &chain_genesis, DoomslugThresholdMode::NoApprovals, true)
-            .unwrap()
+        Chain::new(
+            runtime_adapter,
+            &chain_genesis,
+            DoomslugThresholdMode::NoApprovals,
+            true,
+            &near_o11y::metrics::MetricsRegistry::global(),
+        )
+        .unwrap()
     }
 
     #[test]
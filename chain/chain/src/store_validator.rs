@@ -396,6 +396,7 @@ mod tests {
             &chain_genesis,
             DoomslugThresholdMode::NoApprovals,
             true,
+            &near_o11y::metrics::MetricsRegistry::global(),
         )
         .unwrap();
         (chain, StoreValidator::new(None, genesis, runtime_adapter, store, false))
@@ -3,6 +3,7 @@ mod validator_schedule;
 use std::cmp::Ordering;
 use std::collections::{HashMap, HashSet};
 use std::sync::{Arc, RwLock};
+use std::time::Instant;
 
 use borsh::{BorshDeserialize, BorshSerialize};
 
@@ -42,8 +43,9 @@ use near_primitives::types::{
 use near_primitives::validator_signer::InMemoryValidatorSigner;
 use near_primitives::version::{ProtocolVersion, PROTOCOL_VERSION};
 use near_primitives::views::{
-    AccessKeyInfoView, AccessKeyList, CallResult, ContractCodeView, EpochValidatorInfo,
-    QueryRequest, QueryResponse, QueryResponseKind, ViewStateResult,
+    AccessKeyInfoView, AccessKeyList, BlockProducerProofView, CallResult, ContractCodeView,
+    EpochRandomnessView, EpochValidatorInfo, QueryRequest, QueryResponse, QueryResponseKind,
+    ViewStateResult,
 };
 use near_store::test_utils::create_test_store;
 use near_store::{
@@ -577,6 +579,35 @@ impl EpochManagerAdapter for KeyValueRuntime {
         Ok(validators[(height as usize) % validators.len()].account_id().clone())
     }
 
+    fn get_block_producer_proof(
+        &self,
+        epoch_id: &EpochId,
+        height: BlockHeight,
+    ) -> Result<BlockProducerProofView, Error> {
+        let valset = self.get_valset_for_epoch(epoch_id)?;
+        let validators = self.get_block_producers(valset);
+        Ok(BlockProducerProofView {
+            height,
+            epoch_id: epoch_id.0,
+            epoch_height: valset as EpochHeight,
+            // Heights are assigned to the block producers in turn.
+            rng_seed: None,
+            block_producers_settlement: validators.iter().map(|v| v.clone().into()).collect(),
+            block_producer: validators[(height as usize) % validators.len()].account_id().clone(),
+        })
+    }
+
+    fn get_epoch_randomness(&self, block_hash: &CryptoHash) -> Result<EpochRandomnessView, Error> {
+        let epoch_id = self.get_epoch_id(block_hash)?;
+        Ok(EpochRandomnessView {
+            epoch_id: epoch_id.0,
+            epoch_height: self.get_valset_for_epoch(&epoch_id)? as EpochHeight,
+            // Heights are assigned to the block producers in turn.
+            rng_seed: None,
+            seed_block_hash: None,
+        })
+    }
+
     fn get_chunk_producer(
         &self,
         epoch_id: &EpochId,
@@ -731,6 +762,42 @@ impl EpochManagerAdapter for KeyValueRuntime {
             Ok(())
         }
     }
+
+    /// BLS keys are not tracked, so approvals are never aggregated.
+    #[cfg(feature = "protocol_feature_bls_approvals")]
+    fn verify_bls_key_registration(
+        &self,
+        _epoch_id: &EpochId,
+        _last_known_block_hash: &CryptoHash,
+        _registration: &near_primitives::block_header::BlsKeyRegistration,
+    ) -> Result<bool, Error> {
+        Ok(false)
+    }
+
+    #[cfg(feature = "protocol_feature_bls_approvals")]
+    fn register_bls_key(
+        &self,
+        _registration: &near_primitives::block_header::BlsKeyRegistration,
+    ) -> Result<(), Error> {
+        Ok(())
+    }
+
+    #[cfg(feature = "protocol_feature_bls_approvals")]
+    fn get_bls_key(&self, _account_id: &AccountId) -> Option<near_crypto::bls::PublicKey> {
+        None
+    }
+
+    #[cfg(feature = "protocol_feature_bls_approvals")]
+    fn verify_approvals_aggregate(
+        &self,
+        _prev_block_hash: &CryptoHash,
+        _prev_block_height: BlockHeight,
+        _block_height: BlockHeight,
+        _approvals: &[Option<Signature>],
+        _approvals_aggregate: &near_primitives::block_header::ApprovalsAggregate,
+    ) -> Result<bool, Error> {
+        Ok(true)
+    }
 }
 
 impl RuntimeAdapter for KeyValueRuntime {
@@ -842,6 +909,10 @@ impl RuntimeAdapter for KeyValueRuntime {
         false
     }
 
+    fn update_tracked_shards(&self, _shard_ids: Vec<ShardId>) -> Result<(), Error> {
+        Err(Error::Other("KeyValueRuntime doesn't support updating tracked shards".to_string()))
+    }
+
     fn validate_tx(
         &self,
         _gas_price: Balance,
@@ -858,6 +929,7 @@ impl RuntimeAdapter for KeyValueRuntime {
         &self,
         _gas_price: Balance,
         _gas_limit: Gas,
+        max_transactions: Option<u64>,
         _epoch_id: &EpochId,
         _shard_id: ShardId,
         _state_root: StateRoot,
@@ -865,9 +937,14 @@ impl RuntimeAdapter for KeyValueRuntime {
         transactions: &mut dyn PoolIterator,
         _chain_validate: &mut dyn FnMut(&SignedTransaction) -> bool,
         _current_protocol_version: ProtocolVersion,
+        _deadline: Option<Instant>,
     ) -> Result<Vec<SignedTransaction>, Error> {
         let mut res = vec![];
-        while let Some(iter) = transactions.next() {
+        while max_transactions.map_or(true, |max| (res.len() as u64) < max) {
+            let iter = match transactions.next() {
+                Some(iter) => iter,
+                None => break,
+            };
             res.push(iter.next().unwrap());
         }
         Ok(res)
@@ -1161,6 +1238,13 @@ impl RuntimeAdapter for KeyValueRuntime {
                 block_height,
                 block_hash: *block_hash,
             }),
+            QueryRequest::ViewReceiptProof { .. } => {
+                Err(near_chain_primitives::error::QueryError::InternalError {
+                    error_message: "receipt proofs aren't served from the state".to_string(),
+                    block_height,
+                    block_hash: *block_hash,
+                })
+            }
         }
     }
 
@@ -1396,6 +1480,7 @@ pub fn setup_with_tx_validity_period(
         },
         DoomslugThresholdMode::NoApprovals,
         true,
+        &near_o11y::metrics::MetricsRegistry::global(),
     )
     .unwrap();
     let test_account = "test".parse::<AccountId>().unwrap();
@@ -1436,6 +1521,7 @@ pub fn setup_with_validators(
         },
         DoomslugThresholdMode::NoApprovals,
         true,
+        &near_o11y::metrics::MetricsRegistry::global(),
     )
     .unwrap();
     (chain, runtime, signers)
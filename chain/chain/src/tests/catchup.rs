@@ -0,0 +1,63 @@
+use std::cell::RefCell;
+use std::sync::Arc;
+
+use crate::chain::BlocksCatchUpState;
+use crate::test_utils::setup;
+use crate::{Block, BlockProcessingArtifact, ChainStoreAccess};
+use near_o11y::testonly::init_test_logger;
+
+/// Blocks accepted while the state of a shard for the next epoch is being caught up are left to
+/// the catchup, including a block whose parent the catchup is already done with.  Such a block
+/// used to be dropped from the queue when the catchup was finished.
+#[test]
+fn test_blocks_accepted_during_catchup_are_caught_up() {
+    init_test_logger();
+    let (mut chain, _, signer) = setup();
+    let genesis = chain.get_block(&chain.genesis().hash().clone()).unwrap();
+    let b1 = Block::empty(&genesis, &*signer);
+    chain.process_block_test(&None, b1.clone()).unwrap();
+    let epoch_id = b1.header().epoch_id().clone();
+
+    // As if the state of shard 0 for the next epoch was downloaded at `b1`.
+    chain.shard_readiness.mark_pending(epoch_id.clone(), [0]);
+    let b2 = Block::empty(&b1, &*signer);
+    chain.process_block_test(&None, b2.clone()).unwrap();
+    assert_eq!(chain.store().get_blocks_to_catchup(b1.hash()).unwrap(), vec![*b2.hash()]);
+
+    // The catchup is done with `b2`, but not finished yet, when `b3` is accepted.
+    let mut state = BlocksCatchUpState::new(*b1.hash(), epoch_id.clone());
+    state.pending_blocks.clear();
+    state.done_blocks = vec![*b1.hash(), *b2.hash()];
+    let b3 = Block::empty(&b2, &*signer);
+    chain.process_block_test(&None, b3.clone()).unwrap();
+    assert_eq!(chain.store().get_blocks_to_catchup(b2.hash()).unwrap(), vec![*b3.hash()]);
+
+    // The next step picks `b3` up instead of letting the catchup finish.
+    let scheduled = RefCell::new(vec![]);
+    chain
+        .catchup_blocks_step(&None, b1.hash(), &mut state, &|request| {
+            scheduled.borrow_mut().push(request.block_hash)
+        })
+        .unwrap();
+    assert_eq!(scheduled.into_inner(), vec![*b3.hash()]);
+    assert!(!chain.is_catchup_finished(&state));
+
+    state.scheduled_blocks.clear();
+    state.done_blocks.push(*b3.hash());
+    assert!(chain.is_catchup_finished(&state));
+    chain
+        .finish_catchup_blocks(
+            &None,
+            b1.hash(),
+            &mut BlockProcessingArtifact::default(),
+            Arc::new(|_| {}),
+            &state.done_blocks,
+        )
+        .unwrap();
+    assert!(chain.shard_readiness.is_epoch_ready(&epoch_id));
+
+    // Blocks accepted after the catchup are applied right away.
+    let b4 = Block::empty(&b3, &*signer);
+    chain.process_block_test(&None, b4).unwrap();
+    assert!(chain.store().get_blocks_to_catchup(b3.hash()).unwrap().is_empty());
+}
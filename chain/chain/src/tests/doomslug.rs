@@ -60,6 +60,7 @@ fn one_iter(
         .iter()
         .map(|signer| {
             Doomslug::new(
+                0,
                 0,
                 Duration::from_millis(200),
                 Duration::from_millis(1000),
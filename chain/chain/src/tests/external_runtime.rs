@@ -0,0 +1,182 @@
+//! Conformance checks for `ExternalRuntimeAdapter`: every forwarded call must
+//! produce the same result as calling the wrapped runtime directly.
+use crate::external_runtime::{ExternalRuntimeAdapter, LocalExecutionEngine};
+use crate::test_utils::KeyValueRuntime;
+use crate::types::ApplyTransactionResult;
+use crate::RuntimeAdapter;
+use near_crypto::{InMemorySigner, KeyType};
+use near_pool::TransactionPool;
+use near_primitives::hash::CryptoHash;
+use near_primitives::sandbox::state_patch::SandboxStatePatch;
+use near_primitives::shard_layout::ShardUId;
+use near_primitives::transaction::SignedTransaction;
+use near_primitives::types::validator_stake::ValidatorStakeIter;
+use near_primitives::types::EpochId;
+use near_primitives::version::PROTOCOL_VERSION;
+use near_primitives::views::QueryRequest;
+use near_store::test_utils::create_test_store;
+use near_store::Trie;
+use std::sync::Arc;
+
+fn setup() -> (Arc<KeyValueRuntime>, ExternalRuntimeAdapter) {
+    let runtime = Arc::new(KeyValueRuntime::new(create_test_store(), 5));
+    let engine = Arc::new(LocalExecutionEngine::new(runtime.clone()));
+    let adapter = ExternalRuntimeAdapter::new(runtime.clone(), engine);
+    (runtime, adapter)
+}
+
+fn transfers(count: u64) -> Vec<SignedTransaction> {
+    let signer = InMemorySigner::from_seed("test".parse().unwrap(), KeyType::ED25519, "test");
+    (1..=count)
+        .map(|nonce| {
+            SignedTransaction::send_money(
+                nonce,
+                "test".parse().unwrap(),
+                "other".parse().unwrap(),
+                &signer,
+                10,
+                CryptoHash::default(),
+            )
+        })
+        .collect()
+}
+
+/// Prepares up to `max_transactions` of `transactions` and returns them along
+/// with the number of transactions left in the pool.
+fn prepare_with_limit(
+    runtime: &dyn RuntimeAdapter,
+    transactions: &[SignedTransaction],
+    max_transactions: Option<u64>,
+) -> (Vec<SignedTransaction>, usize) {
+    let mut pool = TransactionPool::new([0; 32]);
+    for transaction in transactions {
+        pool.insert_transaction(transaction.clone());
+    }
+    let prepared = runtime
+        .prepare_transactions(
+            0,
+            1_000_000,
+            max_transactions,
+            &EpochId::default(),
+            0,
+            Trie::EMPTY_ROOT,
+            1,
+            &mut pool.pool_iterator(),
+            &mut |_| true,
+            PROTOCOL_VERSION,
+            None,
+        )
+        .unwrap();
+    (prepared, pool.len())
+}
+
+fn prepare(
+    runtime: &dyn RuntimeAdapter,
+    transactions: &[SignedTransaction],
+) -> Vec<SignedTransaction> {
+    prepare_with_limit(runtime, transactions, None).0
+}
+
+fn apply(
+    runtime: &dyn RuntimeAdapter,
+    transactions: &[SignedTransaction],
+) -> ApplyTransactionResult {
+    runtime
+        .apply_transactions(
+            0,
+            &Trie::EMPTY_ROOT,
+            1,
+            0,
+            &CryptoHash::default(),
+            &CryptoHash::hash_bytes(b"block"),
+            &[],
+            transactions,
+            ValidatorStakeIter::empty(),
+            0,
+            1_000_000,
+            &vec![],
+            CryptoHash::default(),
+            true,
+            false,
+            SandboxStatePatch::default(),
+            false,
+        )
+        .unwrap()
+}
+
+#[test]
+fn test_external_runtime_validate_tx() {
+    let (runtime, adapter) = setup();
+    for transaction in transfers(3) {
+        let expected = runtime
+            .validate_tx(0, None, &transaction, true, &EpochId::default(), PROTOCOL_VERSION)
+            .unwrap();
+        let actual = adapter
+            .validate_tx(0, None, &transaction, true, &EpochId::default(), PROTOCOL_VERSION)
+            .unwrap();
+        assert_eq!(expected, actual);
+    }
+}
+
+#[test]
+fn test_external_runtime_prepare_transactions() {
+    let (runtime, adapter) = setup();
+    let transactions = transfers(5);
+    assert_eq!(prepare(runtime.as_ref(), &transactions), prepare(&adapter, &transactions));
+}
+
+#[test]
+fn test_external_runtime_prepare_transactions_in_batches() {
+    let (runtime, _) = setup();
+    let engine = Arc::new(LocalExecutionEngine::new(runtime.clone()));
+    let adapter = ExternalRuntimeAdapter::new(runtime.clone(), engine).with_prepare_batch_size(2);
+    let transactions = transfers(10);
+    assert_eq!(prepare(runtime.as_ref(), &transactions), prepare(&adapter, &transactions));
+    // The transactions which weren't considered once the limit was reached
+    // stay in the pool.
+    for max_transactions in [1, 3, 4] {
+        let expected = prepare_with_limit(runtime.as_ref(), &transactions, Some(max_transactions));
+        assert_eq!(expected.1, 10 - max_transactions as usize);
+        assert_eq!(prepare_with_limit(&adapter, &transactions, Some(max_transactions)), expected);
+    }
+}
+
+#[test]
+fn test_external_runtime_apply_transactions() {
+    let (runtime, adapter) = setup();
+    let transactions = transfers(2);
+    let expected = apply(runtime.as_ref(), &transactions);
+    let actual = apply(&adapter, &transactions);
+    assert_eq!(expected.new_root, actual.new_root);
+    assert_eq!(expected.outcomes, actual.outcomes);
+    assert_eq!(expected.outgoing_receipts, actual.outgoing_receipts);
+    assert_eq!(expected.validator_proposals, actual.validator_proposals);
+    assert_eq!(expected.total_gas_burnt, actual.total_gas_burnt);
+    assert_eq!(expected.total_balance_burnt, actual.total_balance_burnt);
+    assert_eq!(expected.processed_delayed_receipts, actual.processed_delayed_receipts);
+    assert_eq!(expected.trie_changes.shard_uid(), actual.trie_changes.shard_uid());
+    assert_eq!(expected.trie_changes.trie_changes(), actual.trie_changes.trie_changes());
+    assert_eq!(
+        expected.trie_changes.state_changes().len(),
+        actual.trie_changes.state_changes().len()
+    );
+}
+
+#[test]
+fn test_external_runtime_query() {
+    let (runtime, adapter) = setup();
+    let request = QueryRequest::ViewAccount { account_id: "test".parse().unwrap() };
+    let query = |runtime: &dyn RuntimeAdapter| {
+        runtime.query(
+            ShardUId::single_shard(),
+            &Trie::EMPTY_ROOT,
+            1,
+            0,
+            &CryptoHash::default(),
+            &CryptoHash::default(),
+            &EpochId::default(),
+            &request,
+        )
+    };
+    assert_eq!(query(runtime.as_ref()).unwrap(), query(&adapter).unwrap());
+}
@@ -1,9 +1,9 @@
 use std::sync::Arc;
 
-use crate::chain::Chain;
+use crate::chain::{ArchiveGCBlocker, Chain};
 use crate::test_utils::{KeyValueRuntime, ValidatorSchedule};
 use crate::types::{ChainGenesis, Tip};
-use crate::DoomslugThresholdMode;
+use crate::{ChainStoreAccess, DoomslugThresholdMode};
 
 use near_chain_configs::GCConfig;
 use near_crypto::KeyType;
@@ -13,7 +13,7 @@ use near_primitives::shard_layout::ShardUId;
 use near_primitives::types::{NumBlocks, NumShards, StateRoot};
 use near_primitives::validator_signer::InMemoryValidatorSigner;
 use near_store::test_utils::{create_test_store, gen_changes};
-use near_store::{ShardTries, Trie, WrappedTrieChanges};
+use near_store::{DBCol, ShardTries, Trie, WrappedTrieChanges, COLD_HEAD_KEY};
 use rand::Rng;
 
 fn get_chain(num_shards: NumShards) -> Chain {
@@ -30,7 +30,14 @@ fn get_chain_with_epoch_length_and_num_shards(
         .block_producers_per_epoch(vec![vec!["test1".parse().unwrap()]])
         .num_shards(num_shards);
     let runtime_adapter = Arc::new(KeyValueRuntime::new_with_validators(store, vs, epoch_length));
-    Chain::new(runtime_adapter, &chain_genesis, DoomslugThresholdMode::NoApprovals, true).unwrap()
+    Chain::new(
+        runtime_adapter,
+        &chain_genesis,
+        DoomslugThresholdMode::NoApprovals,
+        true,
+        &near_o11y::metrics::MetricsRegistry::global(),
+    )
+    .unwrap()
 }
 
 // Build a chain of num_blocks on top of prev_block
@@ -714,3 +721,35 @@ fn test_fork_far_away_from_epoch_end() {
         );
     }
 }
+
+#[test]
+fn test_clear_archive_data_cold_head_gate() {
+    let mut chain = get_chain(1);
+    let tries = chain.runtime_adapter.get_tries();
+    let genesis = chain.get_block_by_height(0).unwrap();
+    let mut states = vec![(genesis.clone(), vec![Trie::EMPTY_ROOT], vec![Vec::new()])];
+    do_fork(genesis, vec![Trie::EMPTY_ROOT], tries, &mut chain, 100, &mut states, 1, false);
+    let gc_stop_height =
+        chain.runtime_adapter.get_gc_stop_height(&chain.head().unwrap().last_block_hash);
+    assert!(gc_stop_height > 21);
+
+    // Nothing migrated yet: GC must not touch anything.
+    assert_eq!(
+        chain.clear_archive_data(1000, true).unwrap(),
+        Some(ArchiveGCBlocker::ColdHeadMissing)
+    );
+    assert_eq!(chain.store().chunk_tail().unwrap(), 0);
+
+    let mut store_update = chain.store().store().store_update();
+    store_update.set_ser(DBCol::BlockMisc, COLD_HEAD_KEY, &20u64).unwrap();
+    store_update.commit().unwrap();
+    assert_eq!(
+        chain.clear_archive_data(1000, true).unwrap(),
+        Some(ArchiveGCBlocker::ColdHeadBehind { cold_head_height: 20, gc_stop_height })
+    );
+    assert_eq!(chain.store().chunk_tail().unwrap(), 21);
+
+    // Without cold storage the whole range up to GC stop height is collected.
+    assert_eq!(chain.clear_archive_data(1000, false).unwrap(), None);
+    assert_eq!(chain.store().chunk_tail().unwrap(), gc_stop_height);
+}
@@ -1,5 +1,7 @@
+mod catchup;
 mod challenges;
 mod doomslug;
+mod external_runtime;
 mod gc;
 mod simple_chain;
 mod sync_chain;
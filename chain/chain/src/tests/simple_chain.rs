@@ -119,6 +119,12 @@ fn build_chain_with_orphans() {
         chain.process_block_test(&None, blocks.pop().unwrap()).unwrap_err(),
         Error::Orphan
     );
+    // All orphans wait for the block at height 1.
+    let orphans_info = chain.get_chain_processing_info().orphans_info;
+    assert_eq!(orphans_info.iter().map(|info| info.height).collect::<Vec<_>>(), vec![10, 3, 2]);
+    for info in &orphans_info {
+        assert_eq!(info.missing_ancestor, Some(*blocks[1].hash()));
+    }
     chain.process_block_test(&None, blocks.pop().unwrap()).unwrap();
     while wait_for_all_blocks_in_processing(&mut chain) {
         chain.postprocess_ready_blocks(
@@ -134,6 +140,57 @@ fn build_chain_with_orphans() {
     );
 }
 
+#[test]
+fn persisted_orphans() {
+    init_test_logger();
+    let (mut chain, _, signer) = setup();
+    let mut blocks = vec![chain.get_block(&chain.genesis().hash().clone()).unwrap()];
+    for i in 1..5 {
+        let block = Block::empty(&blocks[i - 1], &*signer);
+        blocks.push(block);
+    }
+    let persisted_heights = |chain: &crate::Chain| {
+        let mut heights = crate::persisted_orphans::load(chain.store().store())
+            .unwrap()
+            .iter()
+            .map(|block| block.header().height())
+            .collect::<Vec<_>>();
+        heights.sort();
+        heights
+    };
+
+    // Orphans saved before the restart are put back into the pool, except
+    // the ones whose previous block was processed, which are returned to be
+    // processed right away.
+    let mut store_update = chain.store().store().store_update();
+    crate::persisted_orphans::save(&mut store_update, &blocks[1]).unwrap();
+    crate::persisted_orphans::save(&mut store_update, &blocks[3]).unwrap();
+    store_update.commit().unwrap();
+    let ready = chain.enable_orphan_persistence().unwrap();
+    assert_eq!(
+        ready.iter().map(|block| *block.hash()).collect::<Vec<_>>(),
+        vec![*blocks[1].hash()]
+    );
+    assert_eq!(chain.orphans_len(), 1);
+    assert_matches!(chain.process_block_test(&None, blocks[4].clone()).unwrap_err(), Error::Orphan);
+    assert_eq!(persisted_heights(&chain), vec![3, 4]);
+
+    // Adopted orphans are removed from the store.
+    for block in ready.into_iter().chain(blocks[2..3].iter().cloned()) {
+        chain.process_block_test(&None, block).unwrap();
+        while wait_for_all_blocks_in_processing(&mut chain) {
+            chain.postprocess_ready_blocks(
+                &None,
+                &mut BlockProcessingArtifact::default(),
+                Arc::new(|_| {}),
+            );
+        }
+    }
+    assert_eq!(chain.head().unwrap().height, 4);
+    assert_eq!(chain.orphans_len(), 0);
+    assert!(persisted_heights(&chain).is_empty());
+}
+
 /// Checks that chain successfully processes blocks with skipped blocks and forks, but doesn't process block behind
 /// final head.
 #[test]
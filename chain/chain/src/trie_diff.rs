@@ -0,0 +1,380 @@
+//! Keys of the state of a shard changed between two blocks.
+//!
+//! Audit tools and consumers mirroring the state want the keys changed by a
+//! range of blocks without replaying their execution.  While the blocks are
+//! kept, the keys come from the state changes recorded when applying every
+//! block in the range.  For ranges longer than [`MAX_STATE_CHANGES_BLOCKS`],
+//! or once the blocks are garbage collected, the keys come from diffing the
+//! tries of the state of the shard after the first and the last block, which
+//! works as long as both states are kept, for example on archival nodes.
+//!
+//! Only keys of accounts, contract code, access keys and contract data are
+//! reported, the same kinds of keys state changes are recorded for.
+use std::collections::BTreeMap;
+
+use near_primitives::block::BlockHeader;
+use near_primitives::hash::CryptoHash;
+use near_primitives::serialize::{from_base64, to_base64};
+use near_primitives::shard_layout::{account_id_to_shard_id, ShardLayout, ShardUId};
+use near_primitives::trie_key::{col, trie_key_parsers};
+use near_primitives::types::{AccountId, BlockHeightDelta, ShardId};
+use near_primitives::views::{ChangedTrieKeyView, TrieDiffSourceView, TrieDiffView};
+use near_store::KeyForStateChanges;
+
+use crate::types::RuntimeAdapter;
+use crate::{ChainStore, ChainStoreAccess, Error};
+
+/// Maximum number of keys returned by a single query.
+pub const MAX_QUERY_LIMIT: usize = 1000;
+
+/// Longest range of blocks for which the changed keys are collected from the
+/// recorded state changes.  Longer ranges diff the tries instead.
+pub const MAX_STATE_CHANGES_BLOCKS: BlockHeightDelta = 100;
+
+#[derive(thiserror::Error, Debug)]
+pub enum QueryError {
+    #[error("Invalid query: {0}")]
+    InvalidQuery(String),
+    #[error("State is not available: {0}")]
+    StateUnavailable(String),
+    #[error(transparent)]
+    Chain(#[from] Error),
+}
+
+/// Parses a raw trie key, returning `None` for the kinds of keys which aren't
+/// reported.
+fn changed_key_view(raw_key: &[u8]) -> Result<Option<ChangedTrieKeyView>, std::io::Error> {
+    let view = match raw_key.first() {
+        Some(&col::ACCOUNT) => ChangedTrieKeyView::Account {
+            account_id: trie_key_parsers::parse_account_id_from_account_key(raw_key)?,
+        },
+        Some(&col::CONTRACT_CODE) => ChangedTrieKeyView::ContractCode {
+            account_id: trie_key_parsers::parse_account_id_from_contract_code_key(raw_key)?,
+        },
+        Some(&col::ACCESS_KEY) => {
+            let account_id = trie_key_parsers::parse_account_id_from_access_key_key(raw_key)?;
+            let public_key =
+                trie_key_parsers::parse_public_key_from_access_key_key(raw_key, &account_id)?;
+            ChangedTrieKeyView::AccessKey { account_id, public_key }
+        }
+        Some(&col::CONTRACT_DATA) => {
+            let account_id = trie_key_parsers::parse_account_id_from_contract_data_key(raw_key)?;
+            let key =
+                trie_key_parsers::parse_data_key_from_contract_data_key(raw_key, &account_id)?;
+            ChangedTrieKeyView::Data { account_id, key: key.to_vec().into() }
+        }
+        _ => return Ok(None),
+    };
+    Ok(Some(view))
+}
+
+fn changed_key_account_id(view: &ChangedTrieKeyView) -> &AccountId {
+    match view {
+        ChangedTrieKeyView::Account { account_id }
+        | ChangedTrieKeyView::ContractCode { account_id }
+        | ChangedTrieKeyView::AccessKey { account_id, .. }
+        | ChangedTrieKeyView::Data { account_id, .. } => account_id,
+    }
+}
+
+/// Returns the canonical blocks after `from` up to and including `to` if the
+/// state changes of the shard are recorded for all of them.
+fn blocks_with_state_changes(
+    chain_store: &ChainStore,
+    from: &BlockHeader,
+    to: &BlockHeader,
+    shard_uid: &ShardUId,
+) -> Result<Option<Vec<CryptoHash>>, Error> {
+    if to.height() - from.height() > MAX_STATE_CHANGES_BLOCKS
+        || from.height() + 1 < chain_store.tail()?
+    {
+        return Ok(None);
+    }
+    let mut blocks = vec![];
+    for height in from.height() + 1..=to.height() {
+        let block_hash = match chain_store.get_block_hash_by_height(height) {
+            Ok(block_hash) => block_hash,
+            Err(Error::DBNotFoundErr(_)) => continue,
+            Err(err) => return Err(err),
+        };
+        // State changes are saved along with the chunk extra when a chunk of
+        // a tracked shard is applied, and garbage collected along with it.
+        match chain_store.get_chunk_extra(&block_hash, shard_uid) {
+            Ok(_) => blocks.push(block_hash),
+            Err(Error::DBNotFoundErr(_)) => return Ok(None),
+            Err(err) => return Err(err),
+        }
+    }
+    Ok(Some(blocks))
+}
+
+/// Collects up to `limit` keys, starting at `start`, from the state changes
+/// recorded for the blocks.
+fn changed_keys_from_state_changes(
+    chain_store: &ChainStore,
+    blocks: &[CryptoHash],
+    shard_id: ShardId,
+    shard_layout: &ShardLayout,
+    start: &[u8],
+    limit: usize,
+) -> Result<Vec<(Vec<u8>, ChangedTrieKeyView)>, Error> {
+    let mut keys = BTreeMap::new();
+    for block_hash in blocks {
+        let storage_key = KeyForStateChanges::for_block(block_hash);
+        for changes in storage_key.find_iter(chain_store.store()) {
+            let raw_key = changes?.trie_key.to_vec();
+            if raw_key.as_slice() < start || keys.contains_key(&raw_key) {
+                continue;
+            }
+            // The changes of all the shards tracked by the node are recorded
+            // under the hash of the block.
+            if let Some(view) = changed_key_view(&raw_key)? {
+                if account_id_to_shard_id(changed_key_account_id(&view), shard_layout) == shard_id {
+                    keys.insert(raw_key, view);
+                }
+            }
+        }
+    }
+    Ok(keys.into_iter().take(limit).collect())
+}
+
+/// Collects up to `limit` keys, starting at `start`, by diffing the tries of
+/// the state of the shard after both blocks.
+fn changed_keys_from_tries(
+    chain_store: &ChainStore,
+    runtime_adapter: &dyn RuntimeAdapter,
+    from: &BlockHeader,
+    to: &BlockHeader,
+    shard_uid: ShardUId,
+    start: &[u8],
+    limit: usize,
+) -> Result<Vec<(Vec<u8>, ChangedTrieKeyView)>, QueryError> {
+    let trie = |header: &BlockHeader| match chain_store.get_chunk_extra(header.hash(), &shard_uid) {
+        Ok(chunk_extra) => Ok(runtime_adapter
+            .get_tries()
+            .get_view_trie_for_shard(shard_uid, *chunk_extra.state_root())),
+        Err(Error::DBNotFoundErr(_)) => Err(QueryError::StateUnavailable(format!(
+            "the node doesn't have the state of shard {} after block {}",
+            shard_uid.shard_id,
+            header.hash()
+        ))),
+        Err(err) => Err(QueryError::Chain(err)),
+    };
+    let from_trie = trie(from)?;
+    let to_trie = trie(to)?;
+    // Keys of other kinds, like those of delayed receipts, are skipped, so
+    // keep diffing until enough keys are found.
+    let mut keys = vec![];
+    let mut start = start.to_vec();
+    loop {
+        let raw_keys = from_trie.diff_keys(&to_trie, &start, limit).map_err(Error::from)?;
+        let done = raw_keys.len() < limit;
+        if let Some(last) = raw_keys.last() {
+            // The smallest key after the last one.
+            start = last.clone();
+            start.push(0);
+        }
+        for raw_key in raw_keys {
+            if let Some(view) = changed_key_view(&raw_key).map_err(Error::from)? {
+                keys.push((raw_key, view));
+            }
+        }
+        if done || keys.len() >= limit {
+            keys.truncate(limit);
+            return Ok(keys);
+        }
+    }
+}
+
+/// Returns up to `limit` keys of the state of the shard changed after the
+/// `from` block up to and including the `to` block, starting at the `cursor`
+/// returned by the previous query.  Both blocks have to be on the canonical
+/// chain.
+pub fn query_trie_diff(
+    chain_store: &ChainStore,
+    runtime_adapter: &dyn RuntimeAdapter,
+    shard_id: ShardId,
+    from_block_hash: &CryptoHash,
+    to_block_hash: &CryptoHash,
+    cursor: Option<&str>,
+    limit: usize,
+) -> Result<TrieDiffView, QueryError> {
+    let from = chain_store.get_block_header(from_block_hash)?;
+    let to = chain_store.get_block_header(to_block_hash)?;
+    for header in [&from, &to] {
+        match chain_store.get_block_hash_by_height(header.height()) {
+            Ok(block_hash) if block_hash == *header.hash() => {}
+            Ok(_) | Err(Error::DBNotFoundErr(_)) => {
+                return Err(QueryError::InvalidQuery(format!(
+                    "block {} is not on the canonical chain",
+                    header.hash()
+                )))
+            }
+            Err(err) => return Err(err.into()),
+        }
+    }
+    if from.height() > to.height() {
+        return Err(QueryError::InvalidQuery("from block is after the to block".to_string()));
+    }
+    let shard_layout = runtime_adapter.get_shard_layout(to.epoch_id())?;
+    if runtime_adapter.get_shard_layout(from.epoch_id())? != shard_layout {
+        return Err(QueryError::InvalidQuery(
+            "shard layout changed between the blocks".to_string(),
+        ));
+    }
+    if shard_id >= shard_layout.num_shards() {
+        return Err(QueryError::InvalidQuery(format!("shard {} does not exist", shard_id)));
+    }
+    let shard_uid = ShardUId::from_shard_id_and_layout(shard_id, &shard_layout);
+    let start = match cursor {
+        Some(cursor) => from_base64(cursor)
+            .map_err(|err| QueryError::InvalidQuery(format!("invalid cursor: {}", err)))?,
+        None => vec![],
+    };
+    let limit = limit.min(MAX_QUERY_LIMIT);
+
+    // One key more than requested tells where the next page starts.
+    let (source, mut changes) =
+        match blocks_with_state_changes(chain_store, &from, &to, &shard_uid)? {
+            Some(blocks) => (
+                TrieDiffSourceView::StateChanges,
+                changed_keys_from_state_changes(
+                    chain_store,
+                    &blocks,
+                    shard_id,
+                    &shard_layout,
+                    &start,
+                    limit + 1,
+                )?,
+            ),
+            None => (
+                TrieDiffSourceView::Trie,
+                changed_keys_from_tries(
+                    chain_store,
+                    runtime_adapter,
+                    &from,
+                    &to,
+                    shard_uid,
+                    &start,
+                    limit + 1,
+                )?,
+            ),
+        };
+    let next_cursor =
+        if changes.len() > limit { changes.pop().map(|(key, _)| to_base64(&key)) } else { None };
+    Ok(TrieDiffView {
+        from_block_hash: *from.hash(),
+        to_block_hash: *to.hash(),
+        shard_id,
+        source,
+        changes: changes.into_iter().map(|(_, view)| view).collect(),
+        next_cursor,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{changed_key_view, query_trie_diff, QueryError};
+    use crate::test_utils::setup;
+    use crate::{Block, ChainStoreAccess};
+    use assert_matches::assert_matches;
+    use borsh::BorshSerialize;
+    use near_crypto::{KeyType, PublicKey};
+    use near_primitives::trie_key::TrieKey;
+    use near_primitives::types::{
+        AccountId, RawStateChange, RawStateChangesWithTrieKey, StateChangeCause,
+    };
+    use near_primitives::views::{ChangedTrieKeyView, TrieDiffSourceView};
+    use near_store::{DBCol, KeyForStateChanges};
+
+    #[test]
+    fn test_changed_key_view() {
+        let account_id: AccountId = "alice.near".parse().unwrap();
+        let public_key = PublicKey::empty(KeyType::ED25519);
+        let view = |trie_key: TrieKey| changed_key_view(&trie_key.to_vec()).unwrap();
+        assert_eq!(
+            view(TrieKey::Account { account_id: account_id.clone() }),
+            Some(ChangedTrieKeyView::Account { account_id: account_id.clone() })
+        );
+        assert_eq!(
+            view(TrieKey::AccessKey {
+                account_id: account_id.clone(),
+                public_key: public_key.clone()
+            }),
+            Some(ChangedTrieKeyView::AccessKey { account_id: account_id.clone(), public_key })
+        );
+        assert_eq!(
+            view(TrieKey::ContractData { account_id: account_id.clone(), key: b"key".to_vec() }),
+            Some(ChangedTrieKeyView::Data { account_id, key: b"key".to_vec().into() })
+        );
+        assert_eq!(view(TrieKey::DelayedReceiptIndices), None);
+    }
+
+    #[test]
+    fn test_query_state_changes() {
+        let (mut chain, runtime, signer) = setup();
+        let genesis = chain.get_block(chain.genesis().hash()).unwrap();
+        let mut blocks = vec![genesis];
+        for _ in 1..4 {
+            let block = Block::empty(blocks.last().unwrap(), &*signer);
+            chain.process_block_test(&None, block.clone()).unwrap();
+            blocks.push(block);
+        }
+        let accounts: Vec<AccountId> =
+            ["alice.near", "bob.near", "carol.near"].iter().map(|id| id.parse().unwrap()).collect();
+        // Alice changes in every block, Bob and Carol in a single one each.
+        let mut store_update = chain.store().store().store_update();
+        for (block, account_id) in
+            blocks[1..].iter().zip([&accounts[1], &accounts[2], &accounts[1]])
+        {
+            for account_id in [&accounts[0], account_id] {
+                let trie_key = TrieKey::Account { account_id: account_id.clone() };
+                let changes = RawStateChangesWithTrieKey {
+                    trie_key: trie_key.clone(),
+                    changes: vec![RawStateChange {
+                        cause: StateChangeCause::InitialState,
+                        data: Some(vec![]),
+                    }],
+                };
+                store_update.set(
+                    DBCol::StateChanges,
+                    KeyForStateChanges::from_trie_key(block.hash(), &trie_key).as_ref(),
+                    &changes.try_to_vec().unwrap(),
+                );
+            }
+        }
+        store_update.commit().unwrap();
+
+        let query = |from: &Block, to: &Block, cursor: Option<&str>| {
+            query_trie_diff(chain.store(), &*runtime, 0, from.hash(), to.hash(), cursor, 2)
+        };
+        let first = query(&blocks[0], &blocks[3], None).unwrap();
+        assert_eq!(first.source, TrieDiffSourceView::StateChanges);
+        let second = query(&blocks[0], &blocks[3], first.next_cursor.as_deref()).unwrap();
+        assert_eq!(second.next_cursor, None);
+        let account_ids: Vec<_> = first
+            .changes
+            .into_iter()
+            .chain(second.changes)
+            .map(|view| match view {
+                ChangedTrieKeyView::Account { account_id } => account_id,
+                view => panic!("unexpected key {:?}", view),
+            })
+            .collect();
+        assert_eq!(account_ids, accounts);
+
+        // Only changes after the first block count.
+        let changes = query(&blocks[2], &blocks[3], None).unwrap().changes;
+        assert_eq!(
+            changes,
+            [&accounts[0], &accounts[1]]
+                .map(|account_id| ChangedTrieKeyView::Account { account_id: account_id.clone() })
+        );
+        assert_eq!(query(&blocks[3], &blocks[3], None).unwrap().changes, vec![]);
+
+        assert_matches!(query(&blocks[3], &blocks[2], None), Err(QueryError::InvalidQuery(_)));
+        let fork = Block::empty_with_height(&blocks[1], 5, &*signer);
+        chain.process_block_test(&None, fork.clone()).unwrap();
+        assert_matches!(query(&blocks[0], &blocks[3], None), Err(QueryError::InvalidQuery(_)));
+    }
+}
@@ -1,6 +1,7 @@
 use std::cmp::Ordering;
 use std::collections::HashMap;
 use std::sync::Arc;
+use std::time::Instant;
 
 use borsh::{BorshDeserialize, BorshSerialize};
 use chrono::DateTime;
@@ -324,12 +325,16 @@ pub trait RuntimeAdapter: EpochManagerAdapter + Send + Sync {
     /// against the given `chain_validate` closure and runtime's transaction verifier.
     /// If the transaction is valid for both, it's added to the result and the temporary state
     /// update is preserved for validation of next transactions.
+    /// At most `max_transactions` transactions are returned, if set.
+    /// Once the `deadline` passes no more transactions are pulled from the pool and the
+    /// transactions prepared so far are returned.
     /// Throws an `Error` with `ErrorKind::StorageError` in case the runtime throws
     /// `RuntimeError::StorageError`.
     fn prepare_transactions(
         &self,
         gas_price: Balance,
         gas_limit: Gas,
+        max_transactions: Option<u64>,
         epoch_id: &EpochId,
         shard_id: ShardId,
         state_root: StateRoot,
@@ -337,6 +342,7 @@ pub trait RuntimeAdapter: EpochManagerAdapter + Send + Sync {
         pool_iterator: &mut dyn PoolIterator,
         chain_validate: &mut dyn FnMut(&SignedTransaction) -> bool,
         current_protocol_version: ProtocolVersion,
+        deadline: Option<Instant>,
     ) -> Result<Vec<SignedTransaction>, Error>;
 
     /// Returns true if the shard layout will change in the next epoch
@@ -374,6 +380,11 @@ pub trait RuntimeAdapter: EpochManagerAdapter + Send + Sync {
         is_me: bool,
     ) -> bool;
 
+    /// Makes the client track the shards with the given ids instead of the
+    /// shards or accounts it was configured with, starting from the first
+    /// epoch whose tracked shards weren't determined yet.
+    fn update_tracked_shards(&self, shard_ids: Vec<ShardId>) -> Result<(), Error>;
+
     /// Get the block height for which garbage collection should not go over
     fn get_gc_stop_height(&self, block_hash: &CryptoHash) -> BlockHeight;
 
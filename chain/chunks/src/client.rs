@@ -1,14 +1,16 @@
-use std::collections::HashMap;
+use std::collections::{BTreeMap, HashMap};
 
 use actix::Message;
 use near_network::types::MsgRecipient;
 use near_o11y::{WithSpanContext, WithSpanContextExt};
+use near_pool::types::{PoolIterator, TransactionGroup};
 use near_pool::{PoolIteratorWrapper, TransactionPool};
 use near_primitives::{
     epoch_manager::RngSeed,
+    hash::CryptoHash,
     sharding::{EncodedShardChunk, PartialEncodedChunk, ShardChunk, ShardChunkHeader},
     transaction::SignedTransaction,
-    types::ShardId,
+    types::{AccountId, ShardId},
 };
 
 pub trait ClientAdapterForShardsManager {
@@ -50,35 +52,168 @@ impl<A: MsgRecipient<WithSpanContext<ShardsManagerResponse>>> ClientAdapterForSh
     }
 }
 
+/// Priority tier of a transaction in the pool.  Transactions of a higher tier
+/// are offered for inclusion in a chunk before any transaction of a lower one.
+pub type TxPriority = u8;
+
+/// Tier of transactions whose signer wasn't given a priority.
+pub const DEFAULT_TX_PRIORITY: TxPriority = 0;
+
 pub struct ShardedTransactionPool {
-    tx_pools: HashMap<ShardId, TransactionPool>,
+    /// Pool of every tier of every shard.
+    tx_pools: HashMap<ShardId, BTreeMap<TxPriority, TransactionPool>>,
 
     /// Useful to make tests deterministic and reproducible,
     /// while keeping the security of randomization of transactions in pool
     rng_seed: RngSeed,
+
+    /// Tiers of transactions by signer, as configured by the operator.
+    priority_signers: HashMap<AccountId, TxPriority>,
+
+    /// Maximum number of transactions of a single signer in the pool of a shard.  None is no
+    /// limit.
+    max_transactions_per_signer: Option<usize>,
 }
 
 impl ShardedTransactionPool {
     pub fn new(rng_seed: RngSeed) -> Self {
         TransactionPool::init_metrics();
-        Self { tx_pools: HashMap::new(), rng_seed }
+        Self {
+            tx_pools: HashMap::new(),
+            rng_seed,
+            priority_signers: HashMap::new(),
+            max_transactions_per_signer: None,
+        }
+    }
+
+    pub fn set_max_transactions_per_signer(&mut self, limit: Option<usize>) {
+        self.max_transactions_per_signer = limit;
+    }
+
+    /// Sets the tiers transactions of the given signers are inserted into.  It
+    /// affects only transactions inserted afterwards.
+    pub fn set_priority_signers(&mut self, priority_signers: HashMap<AccountId, TxPriority>) {
+        self.priority_signers = priority_signers;
     }
 
-    pub fn get_pool_iterator(&mut self, shard_id: ShardId) -> Option<PoolIteratorWrapper<'_>> {
-        self.tx_pools.get_mut(&shard_id).map(|pool| pool.pool_iterator())
+    /// Returns the tier the transaction is inserted into by `insert_transaction`.
+    pub fn priority(&self, tx: &SignedTransaction) -> TxPriority {
+        self.priority_signers.get(&tx.transaction.signer_id).copied().unwrap_or(DEFAULT_TX_PRIORITY)
+    }
+
+    /// Returns an iterator over the transactions of the shard which yields all
+    /// transactions of a tier before moving to a lower one.
+    pub fn get_pool_iterator(&mut self, shard_id: ShardId) -> Option<TieredPoolIterator<'_>> {
+        let Self { tx_pools, priority_signers, .. } = self;
+        tx_pools.get_mut(&shard_id).map(|tiers| {
+            let (priorities, tiers) = tiers
+                .iter_mut()
+                .rev()
+                .map(|(priority, pool)| (*priority, pool.pool_iterator()))
+                .unzip();
+            TieredPoolIterator { tiers, priorities, priority_signers, current: 0 }
+        })
     }
 
     /// Returns true if transaction is not in the pool before call
     pub fn insert_transaction(&mut self, shard_id: ShardId, tx: SignedTransaction) -> bool {
-        self.pool_for_shard(shard_id).insert_transaction(tx)
+        let priority = self.priority(&tx);
+        self.insert_transaction_with_priority(shard_id, tx, priority)
+    }
+
+    /// Inserts the transaction into the given tier.  Returns true if the
+    /// transaction wasn't in any tier before the call.
+    pub fn insert_transaction_with_priority(
+        &mut self,
+        shard_id: ShardId,
+        tx: SignedTransaction,
+        priority: TxPriority,
+    ) -> bool {
+        if self.contains_transaction(shard_id, &tx.get_hash()) {
+            return false;
+        }
+        self.pool_for_shard(shard_id, priority).insert_transaction(tx)
+    }
+
+    /// Returns true if the transaction with the given hash is in the pool of `shard_id`.
+    pub fn contains_transaction(&self, shard_id: ShardId, tx_hash: &CryptoHash) -> bool {
+        self.tx_pools
+            .get(&shard_id)
+            .map_or(false, |tiers| tiers.values().any(|pool| pool.contains(tx_hash)))
     }
 
     pub fn remove_transactions(&mut self, shard_id: ShardId, transactions: &[SignedTransaction]) {
-        if let Some(pool) = self.tx_pools.get_mut(&shard_id) {
-            pool.remove_transactions(transactions)
+        if let Some(tiers) = self.tx_pools.get_mut(&shard_id) {
+            for pool in tiers.values_mut() {
+                pool.remove_transactions(transactions)
+            }
         }
     }
 
+    /// Evicts the oldest transactions of the signer from the pool of the shard until the signer
+    /// is within `max_transactions_per_signer`.  Lower tiers are evicted from first and pinned
+    /// transactions are never evicted.  Returns hashes of the evicted transactions.
+    pub fn enforce_signer_quota(
+        &mut self,
+        shard_id: ShardId,
+        signer_id: &AccountId,
+    ) -> Vec<CryptoHash> {
+        let (limit, tiers) =
+            match (self.max_transactions_per_signer, self.tx_pools.get_mut(&shard_id)) {
+                (Some(limit), Some(tiers)) => (limit, tiers),
+                _ => return vec![],
+            };
+        let mut count: usize = tiers.values().map(|pool| pool.signer_len(signer_id)).sum();
+        let mut evicted = vec![];
+        for pool in tiers.values_mut() {
+            while count > limit {
+                match pool.evict_oldest(signer_id) {
+                    Some(hash) => evicted.push(hash),
+                    None => break,
+                }
+                count -= 1;
+            }
+        }
+        evicted
+    }
+
+    /// Iterates over the pools of all tiers of all shards which had any transactions.
+    pub fn pools(&self) -> impl Iterator<Item = (ShardId, TxPriority, &TransactionPool)> {
+        self.tx_pools.iter().flat_map(|(shard_id, tiers)| {
+            tiers.iter().map(move |(priority, pool)| (*shard_id, *priority, pool))
+        })
+    }
+
+    /// Removes transactions matching the predicate from the pools of all shards.  Returns hashes
+    /// of the removed transactions.
+    pub fn remove_transactions_if(
+        &mut self,
+        predicate: impl Fn(&SignedTransaction) -> bool,
+    ) -> Vec<CryptoHash> {
+        self.tx_pools
+            .values_mut()
+            .flat_map(|tiers| tiers.values_mut())
+            .flat_map(|pool| pool.remove_transactions_if(&predicate))
+            .collect()
+    }
+
+    /// Pins the transaction in the pool of whichever shard holds it.  Returns false if no pool
+    /// holds it.
+    pub fn pin_transaction(&mut self, tx_hash: &CryptoHash) -> bool {
+        self.tx_pools
+            .values_mut()
+            .flat_map(|tiers| tiers.values_mut())
+            .any(|pool| pool.pin_transaction(tx_hash))
+    }
+
+    /// Unpins the transaction.  Returns false if it wasn't pinned.
+    pub fn unpin_transaction(&mut self, tx_hash: &CryptoHash) -> bool {
+        self.tx_pools
+            .values_mut()
+            .flat_map(|tiers| tiers.values_mut())
+            .any(|pool| pool.unpin_transaction(tx_hash))
+    }
+
     /// Computes a deterministic random seed for given `shard_id`.
     /// This seed is used to randomize the transaction pool.
     /// For better security we want the seed to different in each shard.
@@ -90,24 +225,73 @@ impl ShardedTransactionPool {
         res
     }
 
-    fn pool_for_shard(&mut self, shard_id: ShardId) -> &mut TransactionPool {
+    fn pool_for_shard(&mut self, shard_id: ShardId, priority: TxPriority) -> &mut TransactionPool {
+        let seed = Self::random_seed(&self.rng_seed, shard_id);
         self.tx_pools
             .entry(shard_id)
-            .or_insert_with(|| TransactionPool::new(Self::random_seed(&self.rng_seed, shard_id)))
+            .or_default()
+            .entry(priority)
+            .or_insert_with(|| TransactionPool::new(seed))
     }
 
+    /// Puts the transactions back into the pool, into the tiers of their
+    /// signers.
     pub fn reintroduce_transactions(
         &mut self,
         shard_id: ShardId,
         transactions: &[SignedTransaction],
     ) {
-        self.pool_for_shard(shard_id).reintroduce_transactions(transactions.to_vec());
+        for tx in transactions {
+            self.insert_transaction(shard_id, tx.clone());
+        }
+    }
+}
+
+/// Iterates over the tiers of the pool of a shard, starting with the highest.
+/// A tier is left only once all its transactions were pulled.
+pub struct TieredPoolIterator<'a> {
+    tiers: Vec<PoolIteratorWrapper<'a>>,
+    /// Priority of each of the tiers.
+    priorities: Vec<TxPriority>,
+    priority_signers: &'a HashMap<AccountId, TxPriority>,
+    current: usize,
+}
+
+impl<'a> PoolIterator for TieredPoolIterator<'a> {
+    fn next(&mut self) -> Option<&mut TransactionGroup> {
+        while self.tiers.get(self.current).map_or(false, |tier| tier.is_exhausted()) {
+            self.current += 1;
+        }
+        self.tiers.get_mut(self.current)?.next()
+    }
+
+    /// Puts the transaction back into the tier of its signer, same as
+    /// `reintroduce_transactions`, or into the current tier if the iterator
+    /// has no such tier.
+    fn put_back(&mut self, transaction: SignedTransaction) {
+        let priority = self
+            .priority_signers
+            .get(&transaction.transaction.signer_id)
+            .copied()
+            .unwrap_or(DEFAULT_TX_PRIORITY);
+        let tier = self.priorities.iter().position(|p| *p == priority).unwrap_or(self.current);
+        // Once all tiers were pulled, the current one is past the last.
+        if let Some(tier) = self.tiers.get_mut(tier.min(self.tiers.len().saturating_sub(1))) {
+            tier.put_back(transaction);
+        }
     }
 }
 
 #[cfg(test)]
 mod tests {
+    use std::collections::HashMap;
+
+    use near_crypto::{InMemorySigner, KeyType};
+    use near_pool::types::PoolIterator;
     use near_primitives::epoch_manager::RngSeed;
+    use near_primitives::hash::CryptoHash;
+    use near_primitives::transaction::SignedTransaction;
+    use near_primitives::types::AccountId;
 
     use crate::client::ShardedTransactionPool;
 
@@ -131,4 +315,83 @@ mod tests {
         assert_ne!(seed256, seed1000000);
         assert_ne!(seed1000, seed1000000);
     }
+
+    fn transaction(signer_id: &str, nonce: u64) -> SignedTransaction {
+        let signer_id: AccountId = signer_id.parse().unwrap();
+        let signer =
+            InMemorySigner::from_seed(signer_id.clone(), KeyType::ED25519, signer_id.as_str());
+        SignedTransaction::send_money(
+            nonce,
+            signer_id,
+            "bob.near".parse().unwrap(),
+            &signer,
+            1,
+            CryptoHash::default(),
+        )
+    }
+
+    #[test]
+    fn test_priority_tiers() {
+        let mut pool = ShardedTransactionPool::new(TEST_SEED);
+        pool.set_priority_signers(HashMap::from([("vip.near".parse().unwrap(), 2)]));
+        for nonce in 1..=3 {
+            assert!(pool.insert_transaction(0, transaction("alice.near", nonce)));
+        }
+        assert!(pool.insert_transaction(0, transaction("vip.near", 1)));
+        assert!(pool.insert_transaction_with_priority(0, transaction("carol.near", 1), 1));
+        // A transaction already in one tier isn't inserted into another.
+        assert!(!pool.insert_transaction_with_priority(0, transaction("alice.near", 1), 3));
+
+        let mut iter = pool.get_pool_iterator(0).unwrap();
+        let mut signers = vec![];
+        while let Some(group) = iter.next() {
+            let tx = group.next().unwrap();
+            signers.push(tx.transaction.signer_id.to_string());
+        }
+        assert_eq!(
+            signers,
+            vec!["vip.near", "carol.near", "alice.near", "alice.near", "alice.near"]
+        );
+    }
+
+    #[test]
+    fn test_put_back_into_tier() {
+        let mut pool = ShardedTransactionPool::new(TEST_SEED);
+        pool.set_priority_signers(HashMap::from([("vip.near".parse().unwrap(), 2)]));
+        assert!(pool.insert_transaction(0, transaction("vip.near", 1)));
+        assert!(pool.insert_transaction(0, transaction("alice.near", 1)));
+
+        let mut iter = pool.get_pool_iterator(0).unwrap();
+        let vip = iter.next().unwrap().next().unwrap();
+        let alice = iter.next().unwrap().next().unwrap();
+        assert!(iter.next().is_none());
+        iter.put_back(alice);
+        iter.put_back(vip.clone());
+        drop(iter);
+
+        // The transaction of the priority signer is offered first again.
+        let mut iter = pool.get_pool_iterator(0).unwrap();
+        assert_eq!(iter.next().unwrap().next().unwrap(), vip);
+    }
+
+    #[test]
+    fn test_signer_quota() {
+        let alice: AccountId = "alice.near".parse().unwrap();
+        let mut pool = ShardedTransactionPool::new(TEST_SEED);
+        let transactions: Vec<_> = (1..=4).map(|nonce| transaction("alice.near", nonce)).collect();
+        for tx in &transactions {
+            pool.insert_transaction(0, tx.clone());
+        }
+        assert!(pool.enforce_signer_quota(0, &alice).is_empty());
+
+        pool.set_max_transactions_per_signer(Some(2));
+        pool.insert_transaction(1, transaction("alice.near", 5));
+        assert!(pool.enforce_signer_quota(1, &alice).is_empty());
+        assert_eq!(
+            pool.enforce_signer_quota(0, &alice),
+            vec![transactions[0].get_hash(), transactions[1].get_hash()]
+        );
+        assert!(!pool.contains_transaction(0, &transactions[0].get_hash()));
+        assert!(pool.contains_transaction(0, &transactions[3].get_hash()));
+    }
 }
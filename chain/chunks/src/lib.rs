@@ -51,6 +51,19 @@
 //! or partial chunk requests. Before that, they are temporarily stored in `chunk_forwards_cache`.
 //! After that, they are processed as a PartialEncodedChunk message only containing one part.
 //!
+//! ** Announcing available parts
+//! A part owner may not have received its parts, for example because it was offline, in which
+//! case requests for those parts time out.  Once a validator reconstructs a chunk, it announces to
+//! other validators that it has all the parts through a PartialEncodedChunkAvailability message.
+//! Announcements are kept in `chunk_part_availability`, and when requesting parts, a validator
+//! which announced the part is preferred over the part owner which hasn't.
+//!
+//! ** Availability of validators
+//! `validator_availability` keeps how reliably every validator delivered the parts requested from
+//! it.  Parts of owners which often fail to deliver them are more likely to be requested from the
+//! shard representative target instead, and among validators which announced a part, the most
+//! reliable one is preferred.
+//!
 //! ** Processing chunks
 //! Function `process_partial_encoded_chunk` processes a partial encoded chunk message.
 //! 1) validates the parts and receipts in the message
@@ -119,11 +132,13 @@ use near_primitives::version::ProtocolVersion;
 
 use crate::chunk_cache::{EncodedChunksCache, EncodedChunksCacheEntry};
 use crate::logic::cares_about_shard_this_or_next_epoch;
+use crate::persisted_requests::PersistedChunkRequest;
+use crate::validator_availability::ValidatorAvailability;
 use near_chain::near_chain_primitives::error::Error::DBNotFoundErr;
 pub use near_chunks_primitives::Error;
 use near_network::types::{
-    AccountIdOrPeerTrackingShard, PartialEncodedChunkForwardMsg, PartialEncodedChunkRequestMsg,
-    PartialEncodedChunkResponseMsg,
+    AccountIdOrPeerTrackingShard, PartialEncodedChunkAvailabilityMsg,
+    PartialEncodedChunkForwardMsg, PartialEncodedChunkRequestMsg, PartialEncodedChunkResponseMsg,
 };
 use near_o11y::WithSpanContextExt;
 use rand::Rng;
@@ -132,7 +147,9 @@ mod chunk_cache;
 pub mod client;
 pub mod logic;
 mod metrics;
+pub mod persisted_requests;
 pub mod test_utils;
+mod validator_availability;
 
 const CHUNK_PRODUCER_BLACKLIST_SIZE: usize = 100;
 pub const CHUNK_REQUEST_RETRY_MS: u64 = 100;
@@ -140,6 +157,11 @@ pub const CHUNK_REQUEST_SWITCH_TO_OTHERS_MS: u64 = 400;
 pub const CHUNK_REQUEST_SWITCH_TO_FULL_FETCH_MS: u64 = 3_000;
 const CHUNK_REQUEST_RETRY_MAX_MS: u64 = 1_000_000;
 const CHUNK_FORWARD_CACHE_SIZE: usize = 1000;
+// Number of forwarded chunk parts remembered to drop their copies, i.e. about
+// 25 heights worth of the parts of 4 shards with 100 parts each
+const CHUNK_FORWARD_DEDUP_CACHE_SIZE: usize = 10_000;
+const CHUNK_PART_AVAILABILITY_CACHE_SIZE: usize = 1000;
+const VERIFIED_RECEIPT_PROOFS_CACHE_SIZE: usize = 10_000;
 const ACCEPTING_SEAL_PERIOD_MS: i64 = 30_000;
 const NUM_PARTS_REQUESTED_IN_SEAL: usize = 3;
 // TODO(#3180): seals are disabled in single shard setting
@@ -179,14 +201,29 @@ struct ChunkRequestInfo {
     shard_id: ShardId,
     added: Instant,
     last_requested: Instant,
+    // when the first request was actually sent, as opposed to the chunk only
+    // being marked as requested while waiting for chunk forwarding
+    first_sent: Option<Instant>,
+    // whether a hedged request was sent, see `RequestPool::hedge_delay`
+    hedged: bool,
 }
 
 struct RequestPool {
     retry_duration: Duration,
+    /// How long after the first request for a chunk the parts which are still
+    /// missing are also requested from a second target, instead of waiting
+    /// for the retries to the same targets.  The part which arrives first is
+    /// used and the requests for the rest of the chunk stop once it's
+    /// complete.  None disables hedging.
+    hedge_delay: Option<Duration>,
     switch_to_others_duration: Duration,
     switch_to_full_fetch_duration: Duration,
     max_duration: Duration,
     requests: HashMap<ChunkHash, ChunkRequestInfo>,
+    /// Requests restored after a restart which are still outstanding.
+    restored: HashSet<ChunkHash>,
+    /// Whether requests were added or removed since they were last saved.
+    changed: bool,
 }
 
 impl RequestPool {
@@ -198,10 +235,13 @@ impl RequestPool {
     ) -> Self {
         Self {
             retry_duration,
+            hedge_delay: None,
             switch_to_others_duration,
             switch_to_full_fetch_duration,
             max_duration,
             requests: HashMap::default(),
+            restored: HashSet::default(),
+            changed: false,
         }
     }
     pub fn contains_key(&self, chunk_hash: &ChunkHash) -> bool {
@@ -213,14 +253,40 @@ impl RequestPool {
 
     pub fn insert(&mut self, chunk_hash: ChunkHash, chunk_request: ChunkRequestInfo) {
         self.requests.insert(chunk_hash, chunk_request);
+        self.changed = true;
+    }
+
+    fn insert_restored(&mut self, chunk_hash: ChunkHash, chunk_request: ChunkRequestInfo) {
+        self.restored.insert(chunk_hash.clone());
+        self.insert(chunk_hash, chunk_request);
     }
 
     pub fn get_request_info(&self, chunk_hash: &ChunkHash) -> Option<&ChunkRequestInfo> {
         self.requests.get(chunk_hash)
     }
 
+    /// Records that the first request for the chunk was sent.
+    fn mark_sent(&mut self, chunk_hash: &ChunkHash) {
+        if let Some(chunk_request) = self.requests.get_mut(chunk_hash) {
+            chunk_request.first_sent.get_or_insert_with(Clock::instant);
+        }
+    }
+
     pub fn remove(&mut self, chunk_hash: &ChunkHash) {
-        self.requests.remove(chunk_hash);
+        if let Some(chunk_request) = self.requests.remove(chunk_hash) {
+            self.changed = true;
+            if self.hedge_delay.is_some() {
+                if !chunk_request.hedged {
+                    metrics::CHUNK_REQUEST_HEDGES.with_label_values(&["cancelled"]).inc();
+                }
+                metrics::CHUNK_REQUEST_COMPLETION_TIME
+                    .with_label_values(&[if chunk_request.hedged { "true" } else { "false" }])
+                    .observe(chunk_request.added.elapsed().as_secs_f64());
+            }
+        }
+        if self.restored.remove(chunk_hash) {
+            metrics::RESTORED_CHUNK_REQUESTS.with_label_values(&["resolved"]).inc();
+        }
     }
 
     pub fn fetch(&mut self) -> Vec<(ChunkHash, ChunkRequestInfo)> {
@@ -234,14 +300,58 @@ impl RequestPool {
             }
             if chunk_request.last_requested.elapsed() > self.retry_duration {
                 chunk_request.last_requested = Clock::instant();
+                chunk_request.first_sent.get_or_insert(chunk_request.last_requested);
                 requests.push((chunk_hash.clone(), chunk_request.clone()));
             }
         }
         for chunk_hash in removed_requests {
             self.requests.remove(&chunk_hash);
+            self.changed = true;
+            if self.restored.remove(&chunk_hash) {
+                metrics::RESTORED_CHUNK_REQUESTS.with_label_values(&["evicted"]).inc();
+            }
+        }
+        requests
+    }
+
+    /// Returns the requests which are due a hedged request and marks them as
+    /// hedged.
+    fn fetch_to_hedge(&mut self) -> Vec<(ChunkHash, ChunkRequestInfo)> {
+        let hedge_delay = match self.hedge_delay {
+            Some(hedge_delay) => hedge_delay,
+            None => return vec![],
+        };
+        let mut requests = Vec::new();
+        for (chunk_hash, chunk_request) in self.requests.iter_mut() {
+            let due = chunk_request.first_sent.map_or(false, |sent| sent.elapsed() > hedge_delay);
+            if due && !chunk_request.hedged {
+                chunk_request.hedged = true;
+                requests.push((chunk_hash.clone(), chunk_request.clone()));
+            }
         }
         requests
     }
+
+    /// Returns the outstanding requests if they changed since the last call.
+    fn take_changed(&mut self) -> Option<Vec<PersistedChunkRequest>> {
+        if !std::mem::take(&mut self.changed) {
+            return None;
+        }
+        let now = near_primitives::utils::to_timestamp(Clock::utc());
+        let requests = self
+            .requests
+            .iter()
+            .map(|(chunk_hash, chunk_request)| PersistedChunkRequest {
+                chunk_hash: chunk_hash.clone(),
+                height: chunk_request.height,
+                ancestor_hash: chunk_request.ancestor_hash,
+                prev_block_hash: chunk_request.prev_block_hash,
+                shard_id: chunk_request.shard_id,
+                added: now.saturating_sub(chunk_request.added.elapsed().as_nanos() as u64),
+            })
+            .collect();
+        Some(requests)
+    }
 }
 
 #[derive(Debug, Eq, PartialEq)]
@@ -482,11 +592,27 @@ pub struct ShardsManager {
     encoded_chunks: EncodedChunksCache,
     requested_partial_encoded_chunks: RequestPool,
     chunk_forwards_cache: lru::LruCache<ChunkHash, HashMap<u64, PartialEncodedChunkPart>>,
+    /// Forwarded parts which were already received and validated.  Validators
+    /// receive the same parts from several forwarders, and the copies are
+    /// dropped before their merkle proofs are verified again.
+    forwarded_parts_seen: lru::LruCache<(ChunkHash, u64), ()>,
+    /// Parts of chunks which other validators announced they have.
+    chunk_part_availability: lru::LruCache<ChunkHash, Vec<PartialEncodedChunkAvailabilityMsg>>,
+    /// How reliably the validators parts are requested from deliver them.
+    validator_availability: ValidatorAvailability,
+    /// Receipt proofs, by chunk hash and hash of the proof, which were verified
+    /// against the outgoing receipts root of the chunk.
+    verified_receipt_proofs: lru::LruCache<(ChunkHash, CryptoHash), ()>,
 
     // This is a best-effort cache of the chain's head, not the source of truth. The source
     // of truth is in the chain store and written to by the Client.
     chain_head: Option<Tip>,
 
+    /// Chunks included in the blocks processed shortly before the node was
+    /// restarted.  They are complete, but not in `encoded_chunks`, and there's
+    /// no need to process or request them again.
+    completed_before_restart: HashSet<ChunkHash>,
+
     seals_mgr: SealsManager,
 }
 
@@ -517,11 +643,65 @@ impl ShardsManager {
                 Duration::from_millis(CHUNK_REQUEST_RETRY_MAX_MS),
             ),
             chunk_forwards_cache: lru::LruCache::new(CHUNK_FORWARD_CACHE_SIZE),
+            forwarded_parts_seen: lru::LruCache::new(CHUNK_FORWARD_DEDUP_CACHE_SIZE),
+            chunk_part_availability: lru::LruCache::new(CHUNK_PART_AVAILABILITY_CACHE_SIZE),
+            validator_availability: ValidatorAvailability::new(),
+            verified_receipt_proofs: lru::LruCache::new(VERIFIED_RECEIPT_PROOFS_CACHE_SIZE),
             chain_head: initial_chain_head,
+            completed_before_restart: HashSet::new(),
             seals_mgr: SealsManager::new(me, runtime_adapter),
         }
     }
 
+    /// Sets the chunks which were completed before the node was restarted.
+    pub fn set_completed_before_restart(&mut self, chunk_hashes: HashSet<ChunkHash>) {
+        self.completed_before_restart = chunk_hashes;
+    }
+
+    /// Restores the chunk requests saved before the node was restarted.
+    ///
+    /// Requests which would have been evicted by now, or for chunks completed
+    /// before the restart, are dropped.  The rest are re-issued on the next
+    /// call to `resend_chunk_requests`.
+    pub fn restore_chunk_requests(&mut self, requests: Vec<PersistedChunkRequest>) {
+        let now = Clock::instant();
+        let now_timestamp = near_primitives::utils::to_timestamp(Clock::utc());
+        let pool = &mut self.requested_partial_encoded_chunks;
+        for request in requests {
+            let age = Duration::from_nanos(now_timestamp.saturating_sub(request.added));
+            if age > pool.max_duration
+                || self.completed_before_restart.contains(&request.chunk_hash)
+            {
+                metrics::RESTORED_CHUNK_REQUESTS.with_label_values(&["expired"]).inc();
+                continue;
+            }
+            metrics::RESTORED_CHUNK_REQUESTS.with_label_values(&["restored"]).inc();
+            let chunk_request = ChunkRequestInfo {
+                height: request.height,
+                ancestor_hash: request.ancestor_hash,
+                prev_block_hash: request.prev_block_hash,
+                shard_id: request.shard_id,
+                added: now.checked_sub(age).unwrap_or(now),
+                last_requested: now.checked_sub(pool.retry_duration).unwrap_or(now),
+                first_sent: None,
+                hedged: false,
+            };
+            pool.insert_restored(request.chunk_hash, chunk_request);
+        }
+    }
+
+    /// Sets how long after the first request for a chunk its missing parts are
+    /// also requested from a second target.  None disables hedging.
+    pub fn set_chunk_request_hedge_delay(&mut self, hedge_delay: Option<Duration>) {
+        self.requested_partial_encoded_chunks.hedge_delay = hedge_delay;
+    }
+
+    /// Returns the outstanding chunk requests to save, if they changed since
+    /// they were last returned.
+    pub fn chunk_requests_to_save(&mut self) -> Option<Vec<PersistedChunkRequest>> {
+        self.requested_partial_encoded_chunks.take_changed()
+    }
+
     pub fn update_chain_head(&mut self, tip: Tip) {
         self.encoded_chunks.update_largest_seen_height(
             tip.height,
@@ -550,6 +730,8 @@ impl ShardsManager {
         .entered();
         let mut bp_to_parts = HashMap::<_, Vec<u64>>::new();
 
+        let now = Clock::instant();
+        self.validator_availability.expire(chunk_hash, now);
         let cache_entry = self.encoded_chunks.get(chunk_hash);
 
         let request_full = force_request_full
@@ -573,7 +755,9 @@ impl ShardsManager {
         // If request_from_archival is true (indicating we are requesting a chunk not from the current
         // or the last epoch), request all parts and receipts from the shard representative target
         // For each part, if we are the part owner, we request the part from the shard representative
-        // target, otherwise, the part owner
+        // target, otherwise, the part owner.  Parts of owners which often fail to deliver them are
+        // requested from the shard representative target instead, with the probability of the
+        // owner not delivering (see validator_availability.rs).
         // For receipts, request them from the shard representative target
         //
         // Also note that the target accounts decided is not necessarily the final destination
@@ -619,12 +803,25 @@ impl ShardsManager {
                 } else {
                     let part_owner = self.runtime_adapter.get_part_owner(&epoch_id, part_ord)?;
 
-                    if Some(&part_owner) == me {
+                    let fetch_from = if Some(&part_owner) == me {
                         // If missing own part, request it from the chunk producer / node tracking shard
                         shard_representative_target.clone()
+                    } else if shard_representative_target.is_some()
+                        && !rand::thread_rng()
+                            .gen_bool(self.validator_availability.pick_probability(&part_owner))
+                    {
+                        metrics::PARTIAL_ENCODED_CHUNK_PARTS_REQUESTED_FROM_OTHERS.inc();
+                        shard_representative_target.clone()
                     } else {
                         Some(part_owner)
-                    }
+                    };
+                    Self::choose_target_with_part(
+                        &self.chunk_part_availability,
+                        &self.validator_availability,
+                        chunk_hash,
+                        part_ord,
+                        fetch_from,
+                    )
                 };
 
                 bp_to_parts.entry(fetch_from).or_default().push(part_ord);
@@ -665,6 +862,16 @@ impl ShardsManager {
                     only_archival: request_from_archival,
                     min_height: height.saturating_sub(CHUNK_REQUEST_PEER_HORIZON),
                 };
+                // Only requests which are sent to the target account itself tell about its
+                // availability.
+                if let (false, Some(account_id)) = (target.prefer_peer, &target.account_id) {
+                    self.validator_availability.requested(
+                        chunk_hash,
+                        account_id,
+                        &request.part_ords,
+                        now,
+                    );
+                }
                 debug!(target: "chunks", "Requesting {} parts for shard {} from {:?} prefer {}", parts_count, shard_id, target.account_id, target.prefer_peer);
 
                 self.peer_manager_adapter.do_send(
@@ -687,6 +894,99 @@ impl ShardsManager {
         Ok(())
     }
 
+    /// Returns the target to request a part from.  The default target is kept
+    /// unless some other validator announced that it has the part and the
+    /// default target didn't, in which case the announcer which delivered
+    /// requested parts most reliably is chosen.
+    fn choose_target_with_part(
+        chunk_part_availability: &lru::LruCache<ChunkHash, Vec<PartialEncodedChunkAvailabilityMsg>>,
+        validator_availability: &ValidatorAvailability,
+        chunk_hash: &ChunkHash,
+        part_ord: u64,
+        default: Option<AccountId>,
+    ) -> Option<AccountId> {
+        let announcements = match chunk_part_availability.peek(chunk_hash) {
+            Some(announcements) => announcements,
+            None => return default,
+        };
+        let mut holders = announcements
+            .iter()
+            .filter(|announcement| announcement.has_part(part_ord))
+            .map(|announcement| &announcement.account_id);
+        if default.as_ref().map_or(false, |default| holders.clone().any(|holder| holder == default))
+        {
+            return default;
+        }
+        match holders.min_by(|a, b| {
+            validator_availability.score(b).total_cmp(&validator_availability.score(a))
+        }) {
+            Some(holder) => {
+                metrics::PARTIAL_ENCODED_CHUNK_PARTS_REQUESTED_FROM_HOLDER.inc();
+                Some(holder.clone())
+            }
+            None => default,
+        }
+    }
+
+    /// Records which parts of a chunk another validator has.
+    pub fn process_partial_encoded_chunk_availability(
+        &mut self,
+        availability: PartialEncodedChunkAvailabilityMsg,
+    ) {
+        if self.me.as_ref() == Some(&availability.account_id)
+            || availability.parts.len() > (self.rs.total_shard_count() + 7) / 8
+        {
+            debug!(target: "chunks", ?availability, "Ignoring chunk parts availability");
+            return;
+        }
+        let chunk_hash = availability.chunk_hash.clone();
+        match self.chunk_part_availability.get_mut(&chunk_hash) {
+            Some(announcements) => {
+                announcements
+                    .retain(|announcement| announcement.account_id != availability.account_id);
+                announcements.push(availability);
+            }
+            None => {
+                self.chunk_part_availability.put(chunk_hash, vec![availability]);
+            }
+        }
+    }
+
+    /// Announces to the owners of the parts of a chunk that we have all of
+    /// them.
+    fn announce_chunk_parts_availability(
+        &self,
+        chunk_hash: &ChunkHash,
+        epoch_id: &EpochId,
+    ) -> Result<(), Error> {
+        let me = match self.me.as_ref() {
+            Some(me) => me,
+            None => return Ok(()),
+        };
+        let availability = PartialEncodedChunkAvailabilityMsg::new(
+            chunk_hash.clone(),
+            me.clone(),
+            0..self.rs.total_shard_count() as u64,
+        );
+        let mut part_owners = HashSet::new();
+        for part_ord in 0..self.rs.total_shard_count() as u64 {
+            let part_owner = self.runtime_adapter.get_part_owner(epoch_id, part_ord)?;
+            if me == &part_owner || !part_owners.insert(part_owner.clone()) {
+                continue;
+            }
+            self.peer_manager_adapter.do_send(
+                PeerManagerMessageRequest::NetworkRequests(
+                    NetworkRequests::PartialEncodedChunkAvailability {
+                        account_id: part_owner,
+                        availability: availability.clone(),
+                    },
+                )
+                .with_span_context(),
+            );
+        }
+        Ok(())
+    }
+
     /// Get a random shard block producer that is not me.
     fn get_random_target_tracking_shard(
         &self,
@@ -791,7 +1091,9 @@ impl ShardsManager {
         let shard_id = chunk_header.shard_id();
         let chunk_hash = chunk_header.chunk_hash();
 
-        if self.requested_partial_encoded_chunks.contains_key(&chunk_hash) {
+        if self.requested_partial_encoded_chunks.contains_key(&chunk_hash)
+            || self.completed_before_restart.contains(&chunk_hash)
+        {
             return;
         }
 
@@ -819,6 +1121,8 @@ impl ShardsManager {
                 shard_id,
                 last_requested: Clock::instant(),
                 added: Clock::instant(),
+                first_sent: None,
+                hedged: false,
             },
         );
 
@@ -855,8 +1159,11 @@ impl ShardsManager {
                     old_block,
                     fetch_from_archival,
                 );
-                if let Err(err) = request_result {
-                    error!(target: "chunks", "Error during requesting partial encoded chunk: {}", err);
+                match request_result {
+                    Ok(()) => self.requested_partial_encoded_chunks.mark_sent(&chunk_hash),
+                    Err(err) => {
+                        error!(target: "chunks", "Error during requesting partial encoded chunk: {}", err);
+                    }
                 }
             } else {
                 debug!(target: "chunks",should_wait_for_chunk_forwarding, fetch_from_archival, old_block,  "Delaying the chunk request.");
@@ -949,6 +1256,82 @@ impl ShardsManager {
                 }
             }
         }
+
+        for (chunk_hash, chunk_request) in self.requested_partial_encoded_chunks.fetch_to_hedge() {
+            if let Err(err) = self.request_partial_encoded_chunk_hedged(&chunk_hash, &chunk_request)
+            {
+                error!(target: "chunks", "Error during sending hedged partial encoded chunk request: {}", err);
+            }
+        }
+    }
+
+    /// Requests the parts of the chunk which are still missing from a second
+    /// target, a random peer tracking the shard, on top of the targets the
+    /// regular requests go to.  See `RequestPool::hedge_delay`.
+    fn request_partial_encoded_chunk_hedged(
+        &self,
+        chunk_hash: &ChunkHash,
+        chunk_request: &ChunkRequestInfo,
+    ) -> Result<(), near_chain::Error> {
+        let cache_entry = match self.encoded_chunks.get(chunk_hash) {
+            Some(cache_entry) => cache_entry,
+            None => return Ok(()),
+        };
+        let ancestor_hash = &chunk_request.ancestor_hash;
+        let shard_id = chunk_request.shard_id;
+        let me = self.me.as_ref();
+        let request_full = cares_about_shard_this_or_next_epoch(
+            me,
+            ancestor_hash,
+            shard_id,
+            true,
+            self.runtime_adapter.as_ref(),
+        );
+        let epoch_id = self.runtime_adapter.get_epoch_id_from_prev_block(ancestor_hash)?;
+        let mut part_ords = vec![];
+        for part_ord in 0..self.rs.total_shard_count() as u64 {
+            if cache_entry.parts.contains_key(&part_ord) {
+                continue;
+            }
+            if request_full
+                || me.map_or(Ok(false), |me| {
+                    self.runtime_adapter
+                        .get_part_owner(&epoch_id, part_ord)
+                        .map(|owner| &owner == me)
+                })?
+            {
+                part_ords.push(part_ord);
+            }
+        }
+        if part_ords.is_empty() {
+            return Ok(());
+        }
+        metrics::CHUNK_REQUEST_HEDGES.with_label_values(&["sent"]).inc();
+        metrics::CHUNK_REQUEST_HEDGED_PARTS.inc_by(part_ords.len() as u64);
+        let target = AccountIdOrPeerTrackingShard {
+            account_id: self.get_random_target_tracking_shard(ancestor_hash, shard_id)?,
+            prefer_peer: true,
+            shard_id,
+            only_archival: false,
+            min_height: chunk_request.height.saturating_sub(CHUNK_REQUEST_PEER_HORIZON),
+        };
+        debug!(target: "chunks", ?chunk_hash, num_parts = part_ords.len(), shard_id, "Sending hedged chunk request");
+        let request = PartialEncodedChunkRequestMsg {
+            chunk_hash: chunk_hash.clone(),
+            part_ords,
+            tracking_shards: HashSet::new(),
+        };
+        self.peer_manager_adapter.do_send(
+            PeerManagerMessageRequest::NetworkRequests(
+                NetworkRequests::PartialEncodedChunkRequest {
+                    target,
+                    request,
+                    create_time: Clock::instant().into(),
+                },
+            )
+            .with_span_context(),
+        );
+        Ok(())
     }
 
     pub fn receipts_recipient_filter<T>(
@@ -1396,13 +1779,43 @@ impl ShardsManager {
         }
     }
 
+    /// Drops the forwarded parts which were received before, either forwarded
+    /// or otherwise.
+    fn drop_duplicate_forwarded_parts(&mut self, forward: &mut PartialEncodedChunkForwardMsg) {
+        let num_parts = forward.parts.len();
+        let cache_entry = self.encoded_chunks.get(&forward.chunk_hash);
+        let forwarded_parts_seen = &self.forwarded_parts_seen;
+        let chunk_hash = &forward.chunk_hash;
+        forward.parts.retain(|part| {
+            !cache_entry.map_or(false, |entry| entry.parts.contains_key(&part.part_ord))
+                && !forwarded_parts_seen.contains(&(chunk_hash.clone(), part.part_ord))
+        });
+        metrics::PARTIAL_ENCODED_CHUNK_FORWARDED_PARTS
+            .with_label_values(&["duplicate"])
+            .inc_by((num_parts - forward.parts.len()) as u64);
+        metrics::PARTIAL_ENCODED_CHUNK_FORWARDED_PARTS
+            .with_label_values(&["new"])
+            .inc_by(forward.parts.len() as u64);
+    }
+
     pub fn process_partial_encoded_chunk_forward(
         &mut self,
-        forward: PartialEncodedChunkForwardMsg,
+        mut forward: PartialEncodedChunkForwardMsg,
     ) -> Result<(), Error> {
-        let maybe_header = self
-            .validate_partial_encoded_chunk_forward(&forward)
-            .and_then(|_| self.get_partial_encoded_chunk_header(&forward.chunk_hash));
+        self.drop_duplicate_forwarded_parts(&mut forward);
+        if forward.parts.is_empty() {
+            return Ok(());
+        }
+        let validation = self.validate_partial_encoded_chunk_forward(&forward);
+        // Only parts whose merkle proofs were verified are remembered, so that
+        // invalid copies can't cause the valid ones to be dropped.
+        if validation.is_ok() {
+            for part in &forward.parts {
+                self.forwarded_parts_seen.put((forward.chunk_hash.clone(), part.part_ord), ());
+            }
+        }
+        let maybe_header =
+            validation.and_then(|_| self.get_partial_encoded_chunk_header(&forward.chunk_hash));
 
         let header = match maybe_header {
             Ok(header) => Ok(header),
@@ -1589,6 +2002,9 @@ impl ShardsManager {
                partial_encoded_chunk.get_inner().parts.len());
         // Verify the partial encoded chunk is valid and worth processing
         // 1.a Leave if we received known chunk
+        if self.completed_before_restart.contains(&chunk_hash) {
+            return Ok(ProcessPartialEncodedChunkResult::Known);
+        }
         if let Some(entry) = self.encoded_chunks.get(&chunk_hash) {
             if entry.complete {
                 return Ok(ProcessPartialEncodedChunkResult::Known);
@@ -1649,10 +2065,7 @@ impl ShardsManager {
             // https://github.com/near/nearcore/issues/5885
             // we can't simply use prev_block_hash to check if the node tracks this shard or not
             // because prev_block_hash may not be ready
-            let shard_id = proof.1.to_shard_id;
-            let ReceiptProof(shard_receipts, receipt_proof) = proof;
-            let receipt_hash = CryptoHash::hash_borsh(ReceiptList(shard_id, shard_receipts));
-            if !verify_path(header.outgoing_receipts_root(), &receipt_proof.proof, &receipt_hash) {
+            if !self.verify_receipt_proof(header, proof) {
                 byzantine_assert!(false);
                 return Err(Error::ChainError(near_chain::Error::InvalidReceiptsProof));
             }
@@ -1662,6 +2075,9 @@ impl ShardsManager {
         // into chunk cache
         let new_part_ords =
             self.encoded_chunks.merge_in_partial_encoded_chunk(partial_encoded_chunk);
+        for &part_ord in &new_part_ords {
+            self.validator_availability.received(&chunk_hash, part_ord);
+        }
 
         // 3. Forward my parts to others tracking this chunk's shard
         // It's possible that the previous block has not been processed yet. We will want to
@@ -1726,6 +2142,9 @@ impl ShardsManager {
         &mut self,
         header: &ShardChunkHeader,
     ) -> Result<(), Error> {
+        if self.completed_before_restart.contains(&header.chunk_hash()) {
+            return Ok(());
+        }
         if self.insert_header_if_not_exists_and_process_cached_chunk_forwards(header) {
             self.try_process_chunk_parts_and_receipts(header)?;
         }
@@ -1863,11 +2282,41 @@ impl ShardsManager {
             self.seals_mgr.approve_chunk(height, &chunk_hash);
 
             self.complete_chunk(partial_chunk, Some(shard_chunk));
+            if let Err(err) = self.announce_chunk_parts_availability(&chunk_hash, &epoch_id) {
+                warn!(target: "chunks", ?chunk_hash, ?err, "Failed to announce chunk parts");
+            }
             return Ok(ProcessPartialEncodedChunkResult::HaveAllPartsAndReceipts);
         }
         Ok(ProcessPartialEncodedChunkResult::NeedMorePartsOrReceipts)
     }
 
+    /// Verifies the receipt proof against the outgoing receipts root of the
+    /// chunk.  The same proofs are received many times, e.g. with every
+    /// partial chunk forwarded on forks or requested during catchup, so the
+    /// verified ones are remembered in memory and looked up among the markers
+    /// persisted together with the chunk before doing the verification.
+    fn verify_receipt_proof(&mut self, header: &ShardChunkHeader, proof: &ReceiptProof) -> bool {
+        let key = (header.chunk_hash(), CryptoHash::hash_borsh(proof));
+        if self.verified_receipt_proofs.contains(&key) {
+            metrics::RECEIPT_PROOF_CACHE_HITS.with_label_values(&["memory"]).inc();
+            return true;
+        }
+        if self.store.is_receipt_proof_verified(&key.0, proof) {
+            metrics::RECEIPT_PROOF_CACHE_HITS.with_label_values(&["store"]).inc();
+            self.verified_receipt_proofs.put(key, ());
+            return true;
+        }
+        metrics::RECEIPT_PROOF_CACHE_MISSES.inc();
+        let ReceiptProof(shard_receipts, receipt_proof) = proof;
+        let receipt_hash =
+            CryptoHash::hash_borsh(ReceiptList(receipt_proof.to_shard_id, shard_receipts));
+        if !verify_path(header.outgoing_receipts_root(), &receipt_proof.proof, &receipt_hash) {
+            return false;
+        }
+        self.verified_receipt_proofs.put(key, ());
+        true
+    }
+
     /// A helper function to be called after a chunk is considered complete
     fn complete_chunk(
         &mut self,
@@ -1878,6 +2327,7 @@ impl ShardsManager {
         self.encoded_chunks.mark_entry_complete(&chunk_hash);
         self.encoded_chunks.remove_from_cache_if_outside_horizon(&chunk_hash);
         self.requested_partial_encoded_chunks.remove(&chunk_hash);
+        self.validator_availability.remove(&chunk_hash, Clock::instant());
         debug!(target: "chunks", "Completed chunk {:?}", chunk_hash);
         self.client_adapter.did_complete_chunk(partial_chunk, shard_chunk);
     }
@@ -2186,6 +2636,7 @@ mod test {
     use near_primitives::types::EpochId;
     use near_primitives::validator_signer::InMemoryValidatorSigner;
     use near_primitives::version::PROTOCOL_VERSION;
+    use near_primitives::views::ReceiptProofView;
     use near_store::test_utils::create_test_store;
 
     use super::*;
@@ -2217,6 +2668,8 @@ mod test {
                 shard_id: 0,
                 added: added,
                 last_requested: added,
+                first_sent: None,
+                hedged: false,
             },
         );
         std::thread::sleep(Duration::from_millis(2 * CHUNK_REQUEST_RETRY_MS));
@@ -2297,6 +2750,8 @@ mod test {
                 shard_id: header.shard_id(),
                 last_requested: Clock::instant(),
                 added: Clock::instant(),
+                first_sent: None,
+                hedged: false,
             },
         );
         shards_manager
@@ -2477,6 +2932,98 @@ mod test {
         assert_eq!(requested_parts, HashSet::new());
     }
 
+    #[test]
+    fn test_hedged_chunk_requests() {
+        // Test that the missing parts are requested once more from a second target after the
+        // hedge delay, before the regular retry
+        let mut fixture = ChunkTestFixture::new(true);
+        let mut shards_manager = ShardsManager::new(
+            Some(fixture.mock_shard_tracker.clone()),
+            fixture.mock_runtime.clone(),
+            fixture.mock_network.clone(),
+            fixture.mock_client_adapter.clone(),
+            fixture.chain_store.new_read_only_chunks_store(),
+            Some(fixture.mock_chain_head.clone()),
+        );
+        let hedge_delay = Duration::from_millis(CHUNK_REQUEST_RETRY_MS / 2);
+        shards_manager.set_chunk_request_hedge_delay(Some(hedge_delay));
+        let partial_encoded_chunk = fixture.make_partial_encoded_chunk(&[0]);
+        shards_manager
+            .process_partial_encoded_chunk(MaybeValidated::from(partial_encoded_chunk))
+            .unwrap();
+        shards_manager.request_chunk_single(
+            &fixture.mock_chunk_header,
+            CryptoHash::default(),
+            Some(&fixture.mock_chain_head),
+        );
+        let collect_requests = |fixture: &mut ChunkTestFixture| {
+            let mut requests = vec![];
+            while let Some(r) = fixture.mock_network.pop() {
+                if let NetworkRequests::PartialEncodedChunkRequest { target, request, .. } =
+                    r.as_network_requests_ref()
+                {
+                    requests.push((target.clone(), request.part_ords.clone()));
+                }
+            }
+            requests
+        };
+        assert!(!collect_requests(&mut fixture).is_empty());
+        shards_manager.resend_chunk_requests(&fixture.mock_chain_head);
+        assert!(collect_requests(&mut fixture).is_empty());
+
+        std::thread::sleep(hedge_delay + Duration::from_millis(10));
+        shards_manager.resend_chunk_requests(&fixture.mock_chain_head);
+        let requests = collect_requests(&mut fixture);
+        assert_eq!(requests.len(), 1);
+        let (target, part_ords) = &requests[0];
+        assert!(target.prefer_peer);
+        assert_eq!(part_ords, &(1..fixture.mock_chunk_parts.len() as u64).collect::<Vec<_>>());
+
+        // The chunk is hedged only once.
+        shards_manager.resend_chunk_requests(&fixture.mock_chain_head);
+        assert!(collect_requests(&mut fixture).is_empty());
+    }
+
+    #[test]
+    fn test_request_parts_from_announced_holder() {
+        // Test that parts are requested from a validator which announced having them
+        let fixture = ChunkTestFixture::default();
+        let mut shards_manager = ShardsManager::new(
+            Some(fixture.mock_shard_tracker.clone()),
+            fixture.mock_runtime.clone(),
+            fixture.mock_network.clone(),
+            fixture.mock_client_adapter.clone(),
+            fixture.chain_store.new_read_only_chunks_store(),
+            Some(fixture.mock_chain_head.clone()),
+        );
+        let holder = fixture.mock_chunk_part_owner.clone();
+        shards_manager.process_partial_encoded_chunk_availability(
+            PartialEncodedChunkAvailabilityMsg::new(
+                fixture.mock_chunk_header.chunk_hash(),
+                holder.clone(),
+                fixture.all_part_ords.iter().copied(),
+            ),
+        );
+        shards_manager.request_chunk_single(
+            &fixture.mock_chunk_header,
+            CryptoHash::default(),
+            Some(&fixture.mock_chain_head),
+        );
+        let mut requested_parts = HashSet::new();
+        while let Some(r) = fixture.mock_network.pop() {
+            match r.as_network_requests_ref() {
+                NetworkRequests::PartialEncodedChunkRequest { target, request, .. }
+                    if !request.part_ords.is_empty() =>
+                {
+                    assert_eq!(target.account_id.as_ref(), Some(&holder));
+                    requested_parts.extend(request.part_ords.iter().copied());
+                }
+                _ => {}
+            }
+        }
+        assert_eq!(requested_parts, fixture.all_part_ords.iter().copied().collect());
+    }
+
     #[test]
     fn test_invalid_chunk() {
         // Test that process_partial_encoded_chunk will reject invalid chunk
@@ -2560,6 +3107,77 @@ mod test {
         assert!(requests_count > 0);
     }
 
+    #[test]
+    fn test_chunk_completed_before_restart() {
+        // Chunks completed before a restart are neither processed nor requested again.
+        let fixture = ChunkTestFixture::default();
+        let mut shards_manager = ShardsManager::new(
+            Some(fixture.mock_shard_tracker.clone()),
+            fixture.mock_runtime.clone(),
+            fixture.mock_network.clone(),
+            fixture.mock_client_adapter.clone(),
+            fixture.chain_store.new_read_only_chunks_store(),
+            None,
+        );
+        shards_manager
+            .set_completed_before_restart(HashSet::from([fixture.mock_chunk_header.chunk_hash()]));
+        let partial_encoded_chunk = fixture.make_partial_encoded_chunk(&fixture.mock_part_ords);
+        let result = shards_manager
+            .process_partial_encoded_chunk(MaybeValidated::from(partial_encoded_chunk))
+            .unwrap();
+        assert_matches!(result, ProcessPartialEncodedChunkResult::Known);
+        shards_manager.request_chunk_single(
+            &fixture.mock_chunk_header,
+            *fixture.mock_chunk_header.prev_block_hash(),
+            Some(&fixture.mock_chain_head),
+        );
+        assert_eq!(shards_manager.requested_partial_encoded_chunks.len(), 0);
+        assert!(fixture.mock_network.requests.read().unwrap().is_empty());
+    }
+
+    #[test]
+    fn test_chunk_requests_restored_after_restart() {
+        // Outstanding chunk requests are re-issued right after a restart, unless they're stale.
+        let fixture = ChunkTestFixture::default();
+        let new_shards_manager = || {
+            ShardsManager::new(
+                Some(fixture.mock_shard_tracker.clone()),
+                fixture.mock_runtime.clone(),
+                fixture.mock_network.clone(),
+                fixture.mock_client_adapter.clone(),
+                fixture.chain_store.new_read_only_chunks_store(),
+                None,
+            )
+        };
+        let mut shards_manager = new_shards_manager();
+        assert_eq!(shards_manager.chunk_requests_to_save(), None);
+        shards_manager.request_chunk_single(
+            &fixture.mock_chunk_header,
+            *fixture.mock_chunk_header.prev_block_hash(),
+            Some(&fixture.mock_chain_head),
+        );
+        let mut requests = shards_manager.chunk_requests_to_save().unwrap();
+        assert_eq!(requests.len(), 1);
+        assert_eq!(requests[0].chunk_hash, fixture.mock_chunk_header.chunk_hash());
+        assert_eq!(shards_manager.chunk_requests_to_save(), None);
+        while fixture.mock_network.pop().is_some() {}
+
+        let stale = PersistedChunkRequest {
+            chunk_hash: ChunkHash(hash(&[1])),
+            added: 0,
+            ..requests[0].clone()
+        };
+        requests.push(stale);
+        let mut shards_manager = new_shards_manager();
+        shards_manager.restore_chunk_requests(requests);
+        assert_eq!(shards_manager.requested_partial_encoded_chunks.len(), 1);
+        assert!(shards_manager
+            .requested_partial_encoded_chunks
+            .contains_key(&fixture.mock_chunk_header.chunk_hash()));
+        shards_manager.resend_chunk_requests(&fixture.mock_chain_head);
+        assert!(!fixture.mock_network.requests.read().unwrap().is_empty());
+    }
+
     #[test]
     fn test_chunk_forwarding_dedup() {
         // Tests that we only forward a chunk if it's the first time we receive it.
@@ -2864,6 +3482,39 @@ mod test {
             .is_none());
     }
 
+    #[test]
+    // Test that copies of forwarded parts received from other forwarders are dropped, but only
+    // once the parts were validated.
+    fn test_drop_duplicate_forwarded_parts() {
+        let fixture = ChunkTestFixture::new(true);
+        let mut shards_manager = ShardsManager::new(
+            Some(fixture.mock_shard_tracker.clone()),
+            fixture.mock_runtime.clone(),
+            fixture.mock_network.clone(),
+            fixture.mock_client_adapter.clone(),
+            fixture.chain_store.new_read_only_chunks_store(),
+            None,
+        );
+        let parts = fixture.mock_chunk_parts.clone();
+        let forward =
+            PartialEncodedChunkForwardMsg::from_header_and_parts(&fixture.mock_chunk_header, parts);
+        let mut invalid_forward = forward.clone();
+        invalid_forward.parts[0].merkle_proof = invalid_forward.parts[1].merkle_proof.clone();
+        assert_matches!(
+            shards_manager.process_partial_encoded_chunk_forward(invalid_forward),
+            Err(Error::InvalidMerkleProof)
+        );
+        assert_eq!(shards_manager.forwarded_parts_seen.len(), 0);
+
+        assert_matches!(
+            shards_manager.process_partial_encoded_chunk_forward(forward.clone()),
+            Err(Error::UnknownChunk)
+        );
+        assert_eq!(shards_manager.forwarded_parts_seen.len(), forward.parts.len());
+        // The copy has no new parts, so it isn't processed any further.
+        assert_matches!(shards_manager.process_partial_encoded_chunk_forward(forward), Ok(()));
+    }
+
     #[test]
     // Test that when a validator receives a chunk forward before the chunk header, and that the
     // chunk header first arrives as part of a block, it should store the the forward and use it
@@ -3067,4 +3718,59 @@ mod test {
         shards_manager.process_partial_encoded_chunk(part.into()).unwrap();
         assert_eq!(fixture.count_chunk_ready_for_inclusion_messages(), 0);
     }
+
+    #[test]
+    fn test_verified_receipt_proofs() {
+        let mut fixture = ChunkTestFixture::default();
+        let mut shards_manager = ShardsManager::new(
+            Some(fixture.mock_shard_tracker.clone()),
+            fixture.mock_runtime.clone(),
+            fixture.mock_network.clone(),
+            fixture.mock_client_adapter.clone(),
+            fixture.chain_store.new_read_only_chunks_store(),
+            None,
+        );
+        let header = fixture.mock_chunk_header.clone();
+        let receipt_proofs: Vec<_> = make_outgoing_receipts_proofs(
+            &header,
+            &fixture.mock_outgoing_receipts,
+            fixture.mock_runtime.as_ref(),
+        )
+        .unwrap()
+        .collect();
+        let mut invalid_proof = receipt_proofs[0].clone();
+        invalid_proof.1.to_shard_id += 1;
+
+        for proof in &receipt_proofs {
+            assert!(shards_manager.verify_receipt_proof(&header, proof));
+            assert!(shards_manager.verify_receipt_proof(&header, proof));
+        }
+        assert!(!shards_manager.verify_receipt_proof(&header, &invalid_proof));
+        assert!(!shards_manager.verify_receipt_proof(&header, &invalid_proof));
+
+        // The proofs served to light clients check out the same way.
+        let root = header.outgoing_receipts_root();
+        for proof in &receipt_proofs {
+            assert!(ReceiptProofView::from(proof.clone()).verify(&root));
+        }
+        assert!(!ReceiptProofView::from(invalid_proof).verify(&root));
+
+        // Proofs of a saved chunk are known to be verified after a restart.
+        persist_chunk(
+            PartialEncodedChunk::V2(PartialEncodedChunkV2 {
+                header: header.clone(),
+                parts: vec![],
+                receipts: receipt_proofs.clone(),
+            }),
+            None,
+            &mut fixture.chain_store,
+        )
+        .unwrap();
+        let store = fixture.chain_store.new_read_only_chunks_store();
+        let chunk_hash = header.chunk_hash();
+        for proof in &receipt_proofs {
+            assert!(store.is_receipt_proof_verified(&chunk_hash, proof));
+        }
+        assert!(!store.is_receipt_proof_verified(&chunk_hash, &invalid_proof));
+    }
 }
@@ -186,6 +186,7 @@ pub fn persist_chunk(
     store: &mut ChainStore,
 ) -> Result<(), Error> {
     let mut update = store.store_update();
+    update.set_tag("chunk_persist");
     update.save_partial_chunk(partial_chunk);
     if let Some(shard_chunk) = shard_chunk {
         update.save_chunk(shard_chunk);
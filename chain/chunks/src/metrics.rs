@@ -33,3 +33,127 @@ pub static DISTRIBUTE_ENCODED_CHUNK_TIME: Lazy<near_o11y::metrics::HistogramVec>
         )
         .unwrap()
     });
+
+pub static PARTIAL_ENCODED_CHUNK_PARTS_REQUESTED_FROM_HOLDER: Lazy<near_o11y::metrics::IntCounter> =
+    Lazy::new(|| {
+        near_o11y::metrics::try_create_int_counter(
+            "near_partial_encoded_chunk_parts_requested_from_holder_total",
+            concat!(
+                "Number of chunk parts requested from a validator which announced having them ",
+                "instead of the default target",
+            ),
+        )
+        .unwrap()
+    });
+
+pub static RECEIPT_PROOF_CACHE_HITS: Lazy<near_o11y::metrics::IntCounterVec> = Lazy::new(|| {
+    near_o11y::metrics::try_create_int_counter_vec(
+        "near_receipt_proof_cache_hits_total",
+        concat!(
+            "Number of incoming receipt proofs which weren't verified because they were ",
+            "verified before, by where the verification result was found",
+        ),
+        &["source"],
+    )
+    .unwrap()
+});
+
+pub static RECEIPT_PROOF_CACHE_MISSES: Lazy<near_o11y::metrics::IntCounter> = Lazy::new(|| {
+    near_o11y::metrics::try_create_int_counter(
+        "near_receipt_proof_cache_misses_total",
+        "Number of incoming receipt proofs which had to be verified",
+    )
+    .unwrap()
+});
+
+pub static RESTORED_CHUNK_REQUESTS: Lazy<near_o11y::metrics::IntCounterVec> = Lazy::new(|| {
+    near_o11y::metrics::try_create_int_counter_vec(
+        "near_restored_chunk_requests_total",
+        concat!(
+            "Number of chunk requests saved before a restart, by what became of them: ",
+            "restored, expired before the restart, resolved or evicted unfetched",
+        ),
+        &["status"],
+    )
+    .unwrap()
+});
+
+pub static CHUNK_REQUEST_HEDGES: Lazy<near_o11y::metrics::IntCounterVec> = Lazy::new(|| {
+    near_o11y::metrics::try_create_int_counter_vec(
+        "near_chunk_request_hedges_total",
+        concat!(
+            "Number of chunk requests by whether a hedged request was sent for them: sent, or ",
+            "cancelled because the chunk was completed within the hedge delay",
+        ),
+        &["outcome"],
+    )
+    .unwrap()
+});
+
+pub static CHUNK_REQUEST_HEDGED_PARTS: Lazy<near_o11y::metrics::IntCounter> = Lazy::new(|| {
+    near_o11y::metrics::try_create_int_counter(
+        "near_chunk_request_hedged_parts_total",
+        "Number of chunk parts requested again from a second target by hedged requests",
+    )
+    .unwrap()
+});
+
+pub static CHUNK_REQUEST_COMPLETION_TIME: Lazy<near_o11y::metrics::HistogramVec> =
+    Lazy::new(|| {
+        near_o11y::metrics::try_create_histogram_vec(
+            "near_chunk_request_completion_time",
+            concat!(
+                "Time from requesting a chunk to completing it, in seconds, by whether a hedged ",
+                "request was sent for it",
+            ),
+            &["hedged"],
+            Some(exponential_buckets(0.01, 1.5, 16).unwrap()),
+        )
+        .unwrap()
+    });
+
+pub static PARTIAL_ENCODED_CHUNK_FORWARDED_PARTS: Lazy<near_o11y::metrics::IntCounterVec> =
+    Lazy::new(|| {
+        near_o11y::metrics::try_create_int_counter_vec(
+            "near_partial_encoded_chunk_forwarded_parts_total",
+            concat!(
+                "Number of chunk parts received in forwards, by whether they were new or ",
+                "duplicates of parts received before, which are dropped without verification",
+            ),
+            &["status"],
+        )
+        .unwrap()
+    });
+
+pub static PARTIAL_ENCODED_CHUNK_REQUESTED_PARTS: Lazy<near_o11y::metrics::IntCounterVec> =
+    Lazy::new(|| {
+        near_o11y::metrics::try_create_int_counter_vec(
+            "near_partial_encoded_chunk_requested_parts_total",
+            concat!(
+                "Number of requested chunk parts, by whether they were delivered or the request ",
+                "timed out",
+            ),
+            &["outcome"],
+        )
+        .unwrap()
+    });
+
+pub static CHUNK_PART_VALIDATOR_AVAILABILITY: Lazy<near_o11y::metrics::GaugeVec> =
+    Lazy::new(|| {
+        near_o11y::metrics::try_create_gauge_vec(
+            "near_chunk_part_validator_availability",
+            "Moving average of the share of requested chunk parts delivered by the validator",
+            &["account_id"],
+        )
+        .unwrap()
+    });
+
+pub static PARTIAL_ENCODED_CHUNK_PARTS_REQUESTED_FROM_OTHERS: Lazy<near_o11y::metrics::IntCounter> =
+    Lazy::new(|| {
+        near_o11y::metrics::try_create_int_counter(
+        "near_partial_encoded_chunk_parts_requested_from_others_total",
+        "Number of chunk parts requested from the shard representative rather than their owner \
+         because of the low availability of the owner",
+    )
+    .unwrap()
+    });
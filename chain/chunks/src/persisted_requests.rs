@@ -0,0 +1,77 @@
+//! Chunk requests which survive restarts.
+//!
+//! Outstanding requests for parts of chunks are only kept in memory, so after
+//! a restart the chunks which were being fetched stall until the blocks
+//! including them are processed again and the chunks are found missing once
+//! more.  The client periodically saves the requests, with the context needed
+//! to send them, so that a restarted node re-issues them right away.
+use std::io;
+
+use borsh::{BorshDeserialize, BorshSerialize};
+use near_primitives::hash::CryptoHash;
+use near_primitives::sharding::ChunkHash;
+use near_primitives::types::{BlockHeight, ShardId};
+use near_store::{DBCol, Store, StoreUpdate, CHUNK_REQUESTS_KEY};
+
+#[derive(BorshSerialize, BorshDeserialize, Clone, Debug, PartialEq, Eq)]
+pub struct PersistedChunkRequest {
+    pub chunk_hash: ChunkHash,
+    pub height: BlockHeight,
+    pub ancestor_hash: CryptoHash,
+    pub prev_block_hash: CryptoHash,
+    pub shard_id: ShardId,
+    /// When the chunk was first requested, as a Unix timestamp in nanoseconds.
+    pub added: u64,
+}
+
+#[derive(BorshSerialize, BorshDeserialize)]
+struct Record {
+    requests: Vec<PersistedChunkRequest>,
+}
+
+/// Loads the requests saved before the node was restarted.
+pub fn load(store: &Store) -> io::Result<Vec<PersistedChunkRequest>> {
+    let record: Option<Record> = store.get_ser(DBCol::BlockMisc, CHUNK_REQUESTS_KEY)?;
+    Ok(record.map_or(vec![], |record| record.requests))
+}
+
+/// Saves the requests, replacing the ones saved previously.
+pub fn save(
+    store_update: &mut StoreUpdate,
+    requests: Vec<PersistedChunkRequest>,
+) -> io::Result<()> {
+    store_update.set_ser(DBCol::BlockMisc, CHUNK_REQUESTS_KEY, &Record { requests })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{load, save, PersistedChunkRequest};
+    use near_primitives::hash::hash;
+    use near_primitives::sharding::ChunkHash;
+    use near_store::test_utils::create_test_store;
+
+    fn request(height: u64) -> PersistedChunkRequest {
+        let block_hash = hash(&height.to_le_bytes());
+        PersistedChunkRequest {
+            chunk_hash: ChunkHash(hash(block_hash.as_ref())),
+            height,
+            ancestor_hash: block_hash,
+            prev_block_hash: block_hash,
+            shard_id: height % 4,
+            added: height,
+        }
+    }
+
+    #[test]
+    fn test_save_and_load() {
+        let store = create_test_store();
+        assert_eq!(load(&store).unwrap(), vec![]);
+
+        for requests in [(0..10).map(request).collect::<Vec<_>>(), vec![request(42)], vec![]] {
+            let mut store_update = store.store_update();
+            save(&mut store_update, requests.clone()).unwrap();
+            store_update.commit().unwrap();
+            assert_eq!(load(&store).unwrap(), requests);
+        }
+    }
+}
@@ -0,0 +1,175 @@
+//! Availability of the validators chunk parts are requested from.
+//!
+//! Parts are assigned to their owners uniformly, regardless of how reliably
+//! the owners serve them, so a flaky owner delays the reconstruction of every
+//! chunk it owns parts of until the requests for its parts time out.  To avoid
+//! that, every validator parts are requested from gets a score: the moving
+//! average of whether the requested parts were received within
+//! `PART_DELIVERY_TIMEOUT`.  When requesting a part, its owner is only picked
+//! with the probability of its score, and the part is requested from the
+//! shard representative otherwise.  Validators with a low score are still
+//! picked now and then, so that their score recovers once they do.
+use std::collections::HashMap;
+use std::time::{Duration, Instant};
+
+use near_primitives::sharding::ChunkHash;
+use near_primitives::types::AccountId;
+
+use crate::metrics;
+
+/// How long a requested part may take to be received before the request is
+/// considered failed.
+const PART_DELIVERY_TIMEOUT: Duration = Duration::from_millis(1_000);
+/// Weight of the latest outcome in the score of a validator.
+const SCORE_DECAY: f64 = 0.1;
+/// Lowest probability of a validator being picked.
+const MIN_PICK_PROBABILITY: f64 = 0.1;
+/// Number of chunks whose pending part requests are remembered.
+const PENDING_CHUNKS_CACHE_SIZE: usize = 1000;
+
+pub(crate) struct ValidatorAvailability {
+    /// Score of every validator parts were requested from, between 0 and 1.
+    /// Validators parts weren't requested from yet have a score of 1.
+    scores: HashMap<AccountId, f64>,
+    /// Parts which were requested and not received yet, by chunk, with the
+    /// validator they were requested from and the time of the first request.
+    pending: lru::LruCache<ChunkHash, HashMap<u64, (AccountId, Instant)>>,
+}
+
+impl ValidatorAvailability {
+    pub fn new() -> Self {
+        Self { scores: HashMap::new(), pending: lru::LruCache::new(PENDING_CHUNKS_CACHE_SIZE) }
+    }
+
+    pub fn score(&self, account_id: &AccountId) -> f64 {
+        self.scores.get(account_id).copied().unwrap_or(1.0)
+    }
+
+    /// Returns the probability with which parts are requested from the
+    /// validator rather than from another target.
+    pub fn pick_probability(&self, account_id: &AccountId) -> f64 {
+        self.score(account_id).max(MIN_PICK_PROBABILITY)
+    }
+
+    /// Records that the parts were requested from the validator.  Parts
+    /// requested again from the same validator keep the time of the first
+    /// request.
+    pub fn requested(
+        &mut self,
+        chunk_hash: &ChunkHash,
+        account_id: &AccountId,
+        part_ords: &[u64],
+        now: Instant,
+    ) {
+        if self.pending.peek(chunk_hash).is_none() {
+            self.pending.put(chunk_hash.clone(), HashMap::new());
+        }
+        let pending = self.pending.get_mut(chunk_hash).unwrap();
+        for &part_ord in part_ords {
+            match pending.get(&part_ord) {
+                Some((requested_from, _)) if requested_from == account_id => {}
+                _ => {
+                    pending.insert(part_ord, (account_id.clone(), now));
+                }
+            }
+        }
+    }
+
+    /// Records that the part was received, crediting the validator it was
+    /// requested from.
+    pub fn received(&mut self, chunk_hash: &ChunkHash, part_ord: u64) {
+        let requested_from =
+            match self.pending.get_mut(chunk_hash).and_then(|pending| pending.remove(&part_ord)) {
+                Some((requested_from, _)) => requested_from,
+                None => return,
+            };
+        self.record(requested_from, true);
+    }
+
+    /// Records the parts of the chunk requested longer than
+    /// `PART_DELIVERY_TIMEOUT` ago as failed.
+    pub fn expire(&mut self, chunk_hash: &ChunkHash, now: Instant) {
+        let pending = match self.pending.get_mut(chunk_hash) {
+            Some(pending) => pending,
+            None => return,
+        };
+        let mut failed = vec![];
+        pending.retain(|_, (requested_from, requested_at)| {
+            if now.saturating_duration_since(*requested_at) < PART_DELIVERY_TIMEOUT {
+                return true;
+            }
+            failed.push(requested_from.clone());
+            false
+        });
+        for account_id in failed {
+            self.record(account_id, false);
+        }
+    }
+
+    /// Forgets the part requests of the chunk, once it's complete.  Parts
+    /// which weren't received but aren't overdue yet don't count.
+    pub fn remove(&mut self, chunk_hash: &ChunkHash, now: Instant) {
+        self.expire(chunk_hash, now);
+        self.pending.pop(chunk_hash);
+    }
+
+    fn record(&mut self, account_id: AccountId, delivered: bool) {
+        let score = self.scores.entry(account_id.clone()).or_insert(1.0);
+        let outcome = if delivered { 1.0 } else { 0.0 };
+        *score = *score * (1.0 - SCORE_DECAY) + outcome * SCORE_DECAY;
+        metrics::CHUNK_PART_VALIDATOR_AVAILABILITY
+            .with_label_values(&[account_id.as_ref()])
+            .set(*score);
+        metrics::PARTIAL_ENCODED_CHUNK_REQUESTED_PARTS
+            .with_label_values(&[if delivered { "delivered" } else { "timed_out" }])
+            .inc();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{ValidatorAvailability, MIN_PICK_PROBABILITY, PART_DELIVERY_TIMEOUT};
+    use near_primitives::hash::hash;
+    use near_primitives::sharding::ChunkHash;
+    use std::time::Instant;
+
+    #[test]
+    fn test_validator_availability() {
+        let mut availability = ValidatorAvailability::new();
+        let now = Instant::now();
+        let chunk_hash = ChunkHash(hash(b"chunk"));
+        let (flaky, reliable) = ("flaky".parse().unwrap(), "reliable".parse().unwrap());
+        assert_eq!(availability.score(&flaky), 1.0);
+
+        availability.requested(&chunk_hash, &flaky, &[0, 1], now);
+        availability.requested(&chunk_hash, &reliable, &[2], now);
+        availability.received(&chunk_hash, 2);
+        // Requesting the part again doesn't postpone its timeout.
+        availability.requested(&chunk_hash, &flaky, &[0, 1], now + PART_DELIVERY_TIMEOUT / 2);
+        availability.expire(&chunk_hash, now + PART_DELIVERY_TIMEOUT / 2);
+        assert_eq!(availability.score(&flaky), 1.0);
+        availability.expire(&chunk_hash, now + PART_DELIVERY_TIMEOUT);
+        assert!(availability.score(&flaky) < 0.9);
+        assert!(availability.score(&reliable) > 0.99);
+        // Parts received after they were considered failed don't count.
+        let score = availability.score(&flaky);
+        availability.received(&chunk_hash, 0);
+        assert_eq!(availability.score(&flaky), score);
+
+        for _ in 0..100 {
+            availability.requested(&chunk_hash, &flaky, &[0], now);
+            availability.remove(&chunk_hash, now + PART_DELIVERY_TIMEOUT);
+        }
+        assert_eq!(availability.pick_probability(&flaky), MIN_PICK_PROBABILITY);
+
+        // The score recovers once the validator delivers.
+        availability.requested(&chunk_hash, &flaky, &[0], now);
+        availability.received(&chunk_hash, 0);
+        assert!(availability.score(&flaky) > MIN_PICK_PROBABILITY / 2.0);
+        // Requests of complete chunks which aren't overdue don't count.
+        let score = availability.score(&flaky);
+        availability.requested(&chunk_hash, &flaky, &[0], now);
+        availability.remove(&chunk_hash, now);
+        assert_eq!(availability.score(&flaky), score);
+    }
+}
@@ -6,14 +6,16 @@ use std::collections::HashMap;
 use crate::types::StatusError;
 use actix::Message;
 use chrono::DateTime;
+use near_crypto::PublicKey;
 use near_primitives::views::{
-    CatchupStatusView, ChainProcessingInfo, EpochValidatorInfo, SyncStatusView,
+    CatchupStatusView, ChainProcessingInfo, ChunkEndorsementStatusView, DebugDataView,
+    EpochValidatorInfo, HeaderSyncStatsView, SyncStatusView,
 };
 use near_primitives::{
     block_header::ApprovalInner,
     hash::CryptoHash,
     sharding::ChunkHash,
-    types::{AccountId, BlockHeight},
+    types::{AccountId, Balance, BlockHeight, Nonce, ShardId},
     views::ValidatorInfo,
 };
 use serde::{Deserialize, Serialize};
@@ -101,6 +103,24 @@ pub struct ChunkProduction {
     // How long did the chunk production take (reed solomon encoding, preparing fragments etc.)
     // Doesn't include network latency.
     pub chunk_production_duration_millis: Option<u64>,
+    // Number of transactions in the chunk and the limit of the node on it, if any.
+    pub num_transactions: Option<u64>,
+    pub max_transactions: Option<u64>,
+    // Blocks produced without the chunk because it reached their producers too late, as
+    // reported by the block producers.
+    pub missed_inclusions: Vec<MissedChunkInclusion>,
+}
+
+// Block produced without a chunk which reached its producer too late.
+// Times are in milliseconds since the timestamp of the previous block.
+// For debug purposes only.
+#[derive(Serialize, Debug, Clone)]
+pub struct MissedChunkInclusion {
+    pub block_height: BlockHeight,
+    // When the block was produced.
+    pub deadline_millis: u64,
+    // When the chunk became ready for inclusion at the block producer.
+    pub arrival_millis: u64,
 }
 // Information about the block produced by this node.
 // For debug purposes only.
@@ -166,6 +186,75 @@ pub struct ValidatorStatus {
     pub production: Vec<(BlockHeight, ProductionAtHeight)>,
 }
 
+/// Distribution of the delays of one finality stage, measured from the
+/// production timestamp of the block.
+#[derive(Serialize, Deserialize, Debug, Default, PartialEq, Eq)]
+pub struct FinalityStageStats {
+    pub num_blocks: usize,
+    pub p50_millis: u64,
+    pub p90_millis: u64,
+    pub p99_millis: u64,
+    pub max_millis: u64,
+}
+
+#[derive(Serialize, Deserialize, Debug)]
+pub struct FinalitySlaWindowView {
+    pub window_secs: u64,
+    /// Delay until the block was received (or produced) by this node.
+    pub received: FinalityStageStats,
+    /// Delay until the block got doomslug finality.
+    pub doomslug_final: FinalityStageStats,
+    /// Delay until the block got full finality.
+    pub full_final: FinalityStageStats,
+}
+
+/// Finality timeline of a single block.  Delays are in milliseconds since the
+/// production timestamp of the block.
+#[derive(Serialize, Deserialize, Debug)]
+pub struct BlockFinalityView {
+    pub block_hash: CryptoHash,
+    pub height: BlockHeight,
+    pub produced_timestamp: DateTime<chrono::Utc>,
+    pub received_millis: Option<u64>,
+    pub doomslug_final_millis: Option<u64>,
+    pub full_final_millis: Option<u64>,
+}
+
+#[derive(Serialize, Deserialize, Debug)]
+pub struct FinalitySlaView {
+    pub windows: Vec<FinalitySlaWindowView>,
+    /// Recent blocks, highest first.
+    pub recent_blocks: Vec<BlockFinalityView>,
+}
+
+#[derive(Serialize, Deserialize, Debug)]
+pub struct PooledTransactionView {
+    pub hash: CryptoHash,
+    pub signer_id: AccountId,
+    pub public_key: PublicKey,
+    pub nonce: Nonce,
+    pub receiver_id: AccountId,
+    pub pinned: bool,
+    /// Priority tier the transaction is in.
+    pub priority: u8,
+}
+
+#[derive(Serialize, Deserialize, Debug)]
+pub struct ShardTxPoolView {
+    pub shard_id: ShardId,
+    /// Sorted by priority, highest first, then by signer and nonce.
+    pub transactions: Vec<PooledTransactionView>,
+}
+
+#[derive(Serialize, Deserialize, Debug)]
+pub struct TxPoolView {
+    /// Transactions don't carry a gas price of their own, they pay the gas
+    /// price of the block they are included in.  This is the gas price of the
+    /// head block.
+    pub gas_price: Balance,
+    pub shards: Vec<ShardTxPoolView>,
+}
+
 // Different debug requests that can be sent by HTML pages, via GET.
 pub enum DebugStatus {
     // Request for the current sync status
@@ -182,6 +271,16 @@ pub enum DebugStatus {
     CatchupStatus,
     // Request for the current state of chain processing (blocks in progress etc).
     ChainProcessingStatus,
+    // Time to finality of recent blocks.
+    FinalitySla,
+    // Transactions in the pool.
+    TxPool,
+    // Inclusion of chunks of recent blocks per chunk producer.
+    ChunkEndorsementStatus,
+    // Header sync statistics of peers.
+    HeaderSyncStats,
+    // All of the data provided by `DebugApi`, versioned.
+    DebugData,
 }
 
 impl Message for DebugStatus {
@@ -201,4 +300,14 @@ pub enum DebugStatusResponse {
     ValidatorStatus(ValidatorStatus),
     // Detailed information about chain processing (blocks in progress etc).
     ChainProcessingStatus(ChainProcessingInfo),
+    // Time to finality of recent blocks.
+    FinalitySla(FinalitySlaView),
+    // Transactions in the pool.
+    TxPool(TxPoolView),
+    // Inclusion of chunks of recent blocks per chunk producer.
+    ChunkEndorsementStatus(ChunkEndorsementStatusView),
+    // Header sync statistics of peers.
+    HeaderSyncStats(HeaderSyncStatsView),
+    // All of the data provided by `DebugApi`, versioned.
+    DebugData(DebugDataView),
 }
@@ -1,27 +1,34 @@
 use once_cell::sync::OnceCell;
-use std::collections::HashMap;
+use std::collections::{BTreeMap, BTreeSet, HashMap};
 use std::sync::atomic::{AtomicBool, AtomicU64, Ordering};
-use std::sync::Arc;
+use std::sync::{Arc, Mutex};
+use std::time::Instant;
 
 use actix::Message;
 use chrono::DateTime;
-use near_primitives::time::Utc;
+use near_primitives::time::{Clock, Utc};
 
 use near_chain_configs::ProtocolConfigView;
 use near_primitives::hash::CryptoHash;
 use near_primitives::merkle::{MerklePath, PartialMerkleTree};
 use near_primitives::network::PeerId;
 use near_primitives::sharding::ChunkHash;
+use near_primitives::syncing::StateSubPart;
 use near_primitives::types::{
-    AccountId, BlockHeight, BlockReference, EpochId, EpochReference, MaybeBlockId, ShardId,
-    TransactionOrReceiptId,
+    AccountId, BlockHeight, BlockReference, EpochId, EpochReference, MaybeBlockId, NumBlocks,
+    NumShards, ShardId, TransactionOrReceiptId,
 };
+use near_primitives::version::ProtocolVersion;
 use near_primitives::views::validator_stake_view::ValidatorStakeView;
 use near_primitives::views::{
-    BlockView, ChunkView, DownloadStatusView, EpochValidatorInfo, ExecutionOutcomeWithIdView,
-    FinalExecutionOutcomeViewEnum, GasPriceView, LightClientBlockLiteView, LightClientBlockView,
-    QueryRequest, QueryResponse, ReceiptView, ShardSyncDownloadView, StateChangesKindsView,
-    StateChangesRequestView, StateChangesView, SyncStatusView,
+    BlockProducerProofView, BlockView, ChunkView, ContractAccountsView, ContractEventsView,
+    DownloadStatusView, DrainStatusView, EconomicsSeriesView, EpochValidatorInfo,
+    ExecutionOutcomeWithIdView, FinalExecutionOutcomeViewEnum, GasPriceView, HealthView,
+    LightClientBlockLiteView, LightClientBlockView, OutgoingReceiptProofsView,
+    ProductionScheduleView, QueryRequest, QueryResponse, RandomnessView, ReceiptExecutionProofView,
+    ReceiptView, RuntimeParametersDiffView, ShardSyncDownloadView, StateChangesKindsView,
+    StateChangesRequestView, StateChangesView, StatePartsApplyProgressView, StateSplitProgressView,
+    SyncStatusView, TrieDiffView, TxForkStatusView,
 };
 pub use near_primitives::views::{StatusResponse, StatusSyncInfo};
 use serde::Serialize;
@@ -57,6 +64,8 @@ pub struct DownloadStatus {
     pub done: bool,
     pub state_requests_count: u64,
     pub last_target: Option<AccountOrPeerIdOrHash>,
+    /// Sub-parts received so far if the part is downloaded in sub-parts.
+    pub sub_parts: SubPartsDownload,
 }
 
 impl Clone for DownloadStatus {
@@ -69,17 +78,105 @@ impl Clone for DownloadStatus {
             done: self.done,
             state_requests_count: self.state_requests_count,
             last_target: self.last_target.clone(),
+            sub_parts: self.sub_parts.clone(),
         }
     }
 }
 
+/// Partially received state part.
+///
+/// Received sub-parts survive timeouts and re-requests of the part, so that
+/// only the missing ranges need to be fetched again.  All sub-parts of a part
+/// have the same size, so a sub-part covers the range starting at its id
+/// times the size, whichever peer it came from.
+#[derive(Clone, Default, Serialize)]
+pub struct SubPartsDownload {
+    /// Size of the sub-parts being requested.  Unknown until they are first
+    /// requested.
+    pub sub_part_size: Option<u64>,
+    /// Total number of sub-parts.  Unknown until the first sub-part arrives.
+    pub num_sub_parts: Option<u64>,
+    #[serde(skip)]
+    received: BTreeMap<u64, Vec<u8>>,
+}
+
+impl std::fmt::Debug for SubPartsDownload {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("SubPartsDownload")
+            .field("sub_part_size", &self.sub_part_size)
+            .field("num_sub_parts", &self.num_sub_parts)
+            .field("received", &self.received.len())
+            .finish()
+    }
+}
+
+impl SubPartsDownload {
+    /// Returns the size of the sub-parts to request from a peer which serves
+    /// sub-parts of at most `limit` bytes and for which `preferred` is the
+    /// best size.  Once some sub-parts have been received the size stays the
+    /// same, unless the peer doesn't serve sub-parts that large, in which
+    /// case the part is downloaded again from scratch.
+    pub fn choose_sub_part_size(&mut self, preferred: u64, limit: u64) -> u64 {
+        match self.sub_part_size {
+            Some(size) if !self.received.is_empty() && size <= limit => size,
+            _ => {
+                self.clear();
+                self.sub_part_size = Some(preferred);
+                preferred
+            }
+        }
+    }
+
+    /// Ids of sub-parts that still need to be requested.  If the number of
+    /// sub-parts is not known yet, only the first one is requested.
+    pub fn missing(&self) -> Vec<u64> {
+        match self.num_sub_parts {
+            None => vec![0],
+            Some(num_sub_parts) => {
+                (0..num_sub_parts).filter(|id| !self.received.contains_key(id)).collect()
+            }
+        }
+    }
+
+    /// Records a received sub-part.  Returns false if the sub-part is corrupted
+    /// or inconsistent with sub-parts received before.  Sub-parts of another
+    /// size than the requested one must be filtered out by the caller.
+    pub fn insert(&mut self, sub_part: StateSubPart) -> bool {
+        if !sub_part.is_valid() || self.sub_part_size != Some(sub_part.sub_part_size) {
+            return false;
+        }
+        match self.num_sub_parts {
+            Some(num_sub_parts) if num_sub_parts != sub_part.num_sub_parts => return false,
+            _ => self.num_sub_parts = Some(sub_part.num_sub_parts),
+        }
+        self.received.insert(sub_part.sub_part_id, sub_part.data);
+        true
+    }
+
+    /// Returns the whole part once all sub-parts have been received.
+    pub fn assemble(&self) -> Option<Vec<u8>> {
+        if !self.missing().is_empty() {
+            return None;
+        }
+        Some(self.received.values().flatten().copied().collect())
+    }
+
+    /// Drops everything received so far, e.g. after the assembled part failed
+    /// validation.
+    pub fn clear(&mut self) {
+        self.sub_part_size = None;
+        self.num_sub_parts = None;
+        self.received.clear();
+    }
+}
+
 /// Various status of syncing a specific shard.
 #[derive(Clone, Debug)]
 pub enum ShardSyncStatus {
     StateDownloadHeader,
     StateDownloadParts,
     StateDownloadScheduling,
-    StateDownloadApplying,
+    StateDownloadApplying(Arc<StatePartsApplyingStatus>),
     StateDownloadComplete,
     StateSplitScheduling,
     StateSplitApplying(Arc<StateSplitApplyingStatus>),
@@ -101,15 +198,21 @@ impl ToString for ShardSyncStatus {
             ShardSyncStatus::StateDownloadHeader => "header".to_string(),
             ShardSyncStatus::StateDownloadParts => "parts".to_string(),
             ShardSyncStatus::StateDownloadScheduling => "scheduling".to_string(),
-            ShardSyncStatus::StateDownloadApplying => "applying".to_string(),
+            ShardSyncStatus::StateDownloadApplying(status) => {
+                let progress = status.progress_view();
+                format!("applying parts done {}/{}", progress.applied_parts, progress.total_parts)
+            }
             ShardSyncStatus::StateDownloadComplete => "download complete".to_string(),
             ShardSyncStatus::StateSplitScheduling => "split scheduling".to_string(),
             ShardSyncStatus::StateSplitApplying(state_split_status) => {
-                let str = if let Some(total_parts) = state_split_status.total_parts.get() {
+                let str = if let Some(progress) = state_split_status.progress_view() {
+                    let eta = match progress.estimated_remaining_secs {
+                        Some(secs) => format!(" eta {}s", secs),
+                        None => String::new(),
+                    };
                     format!(
-                        "total parts {} done {}",
-                        total_parts,
-                        state_split_status.done_parts.load(Ordering::Relaxed)
+                        "total parts {} done {}{}",
+                        progress.total_parts, progress.done_parts, eta
                     )
                 } else {
                     "not started".to_string()
@@ -129,9 +232,14 @@ impl From<&DownloadStatus> for DownloadStatusView {
 
 impl From<ShardSyncDownload> for ShardSyncDownloadView {
     fn from(download: ShardSyncDownload) -> Self {
+        let state_split_progress = match &download.status {
+            ShardSyncStatus::StateSplitApplying(status) => status.progress_view(),
+            _ => None,
+        };
         ShardSyncDownloadView {
             downloads: download.downloads.iter().map(|x| x.into()).collect(),
             status: download.status.to_string(),
+            state_split_progress,
         }
     }
 }
@@ -142,11 +250,73 @@ pub struct StateSplitApplyingStatus {
     pub total_parts: OnceCell<u64>,
     /// number of parts that are done
     pub done_parts: AtomicU64,
+    /// number of parts that were done before the split was resumed from a checkpoint
+    pub resumed_parts: AtomicU64,
+    /// time when applying of the parts started or resumed
+    pub started: OnceCell<Instant>,
 }
 
 impl StateSplitApplyingStatus {
     pub fn new() -> Self {
-        StateSplitApplyingStatus { total_parts: OnceCell::new(), done_parts: AtomicU64::new(0) }
+        StateSplitApplyingStatus {
+            total_parts: OnceCell::new(),
+            done_parts: AtomicU64::new(0),
+            resumed_parts: AtomicU64::new(0),
+            started: OnceCell::new(),
+        }
+    }
+
+    /// Returns the progress of the split, or `None` if it hasn't started yet.
+    /// Remaining time is extrapolated from the parts done since the start, so
+    /// parts done before a restart don't skew it.
+    pub fn progress_view(&self) -> Option<StateSplitProgressView> {
+        let total_parts = *self.total_parts.get()?;
+        let done_parts = self.done_parts.load(Ordering::Relaxed);
+        let resumed_parts = self.resumed_parts.load(Ordering::Relaxed);
+        let done_since_start = done_parts.saturating_sub(resumed_parts);
+        let estimated_remaining_secs = match self.started.get() {
+            Some(started) if done_since_start > 0 => {
+                let elapsed = (Clock::instant() - *started).as_secs_f64();
+                let remaining_parts = total_parts.saturating_sub(done_parts);
+                Some((elapsed * remaining_parts as f64 / done_since_start as f64) as u64)
+            }
+            _ => None,
+        };
+        Some(StateSplitProgressView {
+            total_parts,
+            done_parts,
+            resumed_parts,
+            estimated_remaining_secs,
+        })
+    }
+}
+
+/// Progress of applying the downloaded state parts of a shard.  Parts are
+/// applied concurrently, so any subset of them may be in progress at a time.
+#[derive(Debug)]
+pub struct StatePartsApplyingStatus {
+    pub total_parts: u64,
+    /// number of parts that are applied
+    pub applied_parts: AtomicU64,
+    /// parts that are being validated or applied
+    pub applying_parts: Mutex<BTreeSet<u64>>,
+}
+
+impl StatePartsApplyingStatus {
+    pub fn new(total_parts: u64) -> Self {
+        StatePartsApplyingStatus {
+            total_parts,
+            applied_parts: AtomicU64::new(0),
+            applying_parts: Mutex::new(BTreeSet::new()),
+        }
+    }
+
+    pub fn progress_view(&self) -> StatePartsApplyProgressView {
+        StatePartsApplyProgressView {
+            total_parts: self.total_parts,
+            applied_parts: self.applied_parts.load(Ordering::Relaxed),
+            applying_parts: self.applying_parts.lock().unwrap().iter().copied().collect(),
+        }
     }
 }
 
@@ -163,9 +333,8 @@ pub enum SyncStatus {
     AwaitingPeers,
     /// Not syncing / Done syncing.
     NoSync,
-    /// Syncing using light-client headers to a recent epoch
-    // TODO #3488
-    // Bowen: why do we use epoch ordinal instead of epoch id?
+    /// Verifying proofs of epochs to get to a recent epoch, `epoch_ord` is
+    /// the number of epochs verified so far.
     EpochSync { epoch_ord: u64 },
     /// Downloading block headers for fast sync.
     HeaderSync {
@@ -284,6 +453,17 @@ impl Message for GetBlock {
     type Result = Result<BlockView, GetBlockError>;
 }
 
+/// Randomness of a block and of its epoch, with `lineage_length` ancestors of
+/// the block, so that the random values can be verified externally.
+pub struct GetRandomness {
+    pub block_reference: BlockReference,
+    pub lineage_length: u64,
+}
+
+impl Message for GetRandomness {
+    type Result = Result<RandomnessView, GetBlockError>;
+}
+
 /// Get block with the block merkle tree. Used for testing
 pub struct GetBlockWithMerkleTree(pub BlockReference);
 
@@ -308,6 +488,16 @@ impl Message for GetChunk {
     type Result = Result<ChunkView, GetChunkError>;
 }
 
+/// Outgoing receipts of a chunk split by the receiving shard, each part with
+/// its merkle path to the outgoing receipts root of the chunk.
+pub struct GetOutgoingReceiptProofs {
+    pub chunk: GetChunk,
+}
+
+impl Message for GetOutgoingReceiptProofs {
+    type Result = Result<OutgoingReceiptProofsView, GetChunkError>;
+}
+
 #[derive(thiserror::Error, Debug)]
 pub enum GetChunkError {
     #[error("IO Error: {error_message}")]
@@ -409,6 +599,12 @@ pub enum QueryError {
         block_height: near_primitives::types::BlockHeight,
         block_hash: near_primitives::hash::CryptoHash,
     },
+    #[error("Receipt {receipt_id} has not been observed being sent by a chunk up to block #{block_height}")]
+    UnknownReceipt {
+        receipt_id: near_primitives::hash::CryptoHash,
+        block_height: near_primitives::types::BlockHeight,
+        block_hash: near_primitives::hash::CryptoHash,
+    },
     #[error("The node reached its limits. Try again later. More details: {error_message}")]
     InternalError { error_message: String },
     #[error(
@@ -474,6 +670,13 @@ impl Message for Status {
     type Result = Result<StatusResponse, StatusError>;
 }
 
+/// Readiness and liveness verdicts of the node, with the reasons they fail.
+pub struct GetHealth;
+
+impl Message for GetHealth {
+    type Result = Result<HealthView, StatusError>;
+}
+
 pub struct GetNextLightClientBlock {
     pub last_block_hash: CryptoHash,
 }
@@ -543,6 +746,82 @@ pub enum GetGasPriceError {
     Unreachable { error_message: String },
 }
 
+/// Economics of the canonical blocks between two heights.  Only every `step`th
+/// height starting from `from_height` is sampled and at most `limit` points
+/// are returned, the rest has to be requested again from the returned height.
+pub struct GetEconomicsSeries {
+    pub from_height: BlockHeight,
+    /// Inclusive.  None is the head of the chain.
+    pub to_height: Option<BlockHeight>,
+    pub step: NumBlocks,
+    pub limit: usize,
+}
+
+impl Message for GetEconomicsSeries {
+    type Result = Result<EconomicsSeriesView, GetEconomicsSeriesError>;
+}
+
+#[derive(thiserror::Error, Debug)]
+pub enum GetEconomicsSeriesError {
+    #[error("Invalid range: {error_message}")]
+    InvalidRange { error_message: String },
+    #[error("Internal error: {error_message}")]
+    InternalError { error_message: String },
+}
+
+impl From<near_chain_primitives::Error> for GetEconomicsSeriesError {
+    fn from(error: near_chain_primitives::Error) -> Self {
+        Self::InternalError { error_message: error.to_string() }
+    }
+}
+
+/// Events emitted by the contract of an account, optionally only of a single
+/// standard or event.  At most `limit` events are returned, the rest has to
+/// be requested again with the returned cursor.
+pub struct GetContractEvents {
+    pub account_id: AccountId,
+    pub standard: Option<String>,
+    /// Requires `standard` to be set.
+    pub event: Option<String>,
+    pub cursor: Option<String>,
+    pub limit: usize,
+}
+
+impl Message for GetContractEvents {
+    type Result = Result<ContractEventsView, GetContractIndexError>;
+}
+
+/// Accounts the code with the hash is currently deployed to, as of the final
+/// block.  At most `limit` accounts are returned, the rest has to be requested
+/// again with the returned cursor.
+pub struct GetContractAccounts {
+    pub code_hash: CryptoHash,
+    pub cursor: Option<AccountId>,
+    pub limit: usize,
+}
+
+impl Message for GetContractAccounts {
+    type Result = Result<ContractAccountsView, GetContractIndexError>;
+}
+
+/// Error of a query of one of the optional contract indices, see
+/// [`GetContractEvents`] and [`GetContractAccounts`].
+#[derive(thiserror::Error, Debug)]
+pub enum GetContractIndexError {
+    #[error("The queried index is not enabled on this node")]
+    IndexDisabled,
+    #[error("Invalid request: {error_message}")]
+    InvalidRequest { error_message: String },
+    #[error("Internal error: {error_message}")]
+    InternalError { error_message: String },
+}
+
+impl From<near_chain_primitives::Error> for GetContractIndexError {
+    fn from(error: near_chain_primitives::Error) -> Self {
+        Self::InternalError { error_message: error.to_string() }
+    }
+}
+
 impl From<near_chain_primitives::Error> for GetGasPriceError {
     fn from(error: near_chain_primitives::Error) -> Self {
         match error {
@@ -588,6 +867,9 @@ pub struct TxStatus {
     pub tx_hash: CryptoHash,
     pub signer_account_id: AccountId,
     pub fetch_receipt: bool,
+    /// Whether the proofs of the outcomes are included, along with the
+    /// receipts.  Only applies if `fetch_receipt` is set.
+    pub fetch_outcome_proofs: bool,
 }
 
 #[derive(Debug)]
@@ -608,6 +890,18 @@ impl Message for TxStatus {
     type Result = Result<Option<FinalExecutionOutcomeViewEnum>, TxStatusError>;
 }
 
+/// Reports in which known blocks, canonical or not, a transaction was
+/// executed and whether it is waiting in the pool for re-inclusion.
+#[derive(Debug)]
+pub struct TxForkStatus {
+    pub tx_hash: CryptoHash,
+    pub signer_account_id: AccountId,
+}
+
+impl Message for TxForkStatus {
+    type Result = Result<TxForkStatusView, TxStatusError>;
+}
+
 pub struct GetValidatorInfo {
     pub epoch_reference: EpochReference,
 }
@@ -651,6 +945,35 @@ impl Message for GetValidatorOrdered {
     type Result = Result<Vec<ValidatorStakeView>, GetValidatorInfoError>;
 }
 
+/// Block producer assigned to a height, with the inputs of the assignment.
+/// Heights after the head are assigned as in the epoch of the next block.
+pub struct GetBlockProducerProof {
+    pub height: BlockHeight,
+}
+
+impl Message for GetBlockProducerProof {
+    type Result = Result<BlockProducerProofView, GetValidatorInfoError>;
+}
+
+/// Puts the node into drain mode ahead of stopping it, see
+/// `Client::begin_drain`.  Returns the state of the drain.
+#[derive(Debug)]
+pub struct BeginDrain;
+
+impl Message for BeginDrain {
+    type Result = Result<DrainStatusView, StatusError>;
+}
+
+/// Expected block and chunk producers of the next `num_heights` heights.
+#[derive(Debug)]
+pub struct GetProductionSchedule {
+    pub num_heights: u64,
+}
+
+impl Message for GetProductionSchedule {
+    type Result = Result<ProductionScheduleView, GetValidatorInfoError>;
+}
+
 pub struct GetStateChanges {
     pub block_hash: CryptoHash,
     pub state_changes_request: StateChangesRequestView,
@@ -715,6 +1038,44 @@ impl Message for GetStateChangesWithCauseInBlockForTrackedShards {
     type Result = Result<HashMap<ShardId, StateChangesView>, GetStateChangesError>;
 }
 
+/// Keys of the state of a shard changed after `from_block_hash` up to and
+/// including `to_block_hash`.  At most `limit` keys are returned, the rest has
+/// to be requested again with the returned cursor.
+pub struct GetTrieDiff {
+    pub shard_id: ShardId,
+    pub from_block_hash: CryptoHash,
+    pub to_block_hash: CryptoHash,
+    pub cursor: Option<String>,
+    pub limit: usize,
+}
+
+impl Message for GetTrieDiff {
+    type Result = Result<TrieDiffView, GetTrieDiffError>;
+}
+
+#[derive(thiserror::Error, Debug)]
+pub enum GetTrieDiffError {
+    #[error("Block either has never been observed on the node or has been garbage collected: {error_message}")]
+    UnknownBlock { error_message: String },
+    #[error("Invalid request: {error_message}")]
+    InvalidRequest { error_message: String },
+    #[error("State is not available: {error_message}")]
+    StateUnavailable { error_message: String },
+    #[error("Internal error: {error_message}")]
+    InternalError { error_message: String },
+}
+
+impl From<near_chain_primitives::Error> for GetTrieDiffError {
+    fn from(error: near_chain_primitives::Error) -> Self {
+        match error {
+            near_chain_primitives::Error::DBNotFoundErr(error_message) => {
+                Self::UnknownBlock { error_message }
+            }
+            _ => Self::InternalError { error_message: error.to_string() },
+        }
+    }
+}
+
 pub struct GetExecutionOutcome {
     pub id: TransactionOrReceiptId,
 }
@@ -789,6 +1150,17 @@ impl Message for GetExecutionOutcomesForBlock {
     type Result = Result<HashMap<ShardId, Vec<ExecutionOutcomeWithIdView>>, String>;
 }
 
+/// Proof for light clients that a receipt was executed, which they verify
+/// against the block merkle root of `light_client_head`.
+pub struct GetReceiptExecutionProof {
+    pub receipt_id: CryptoHash,
+    pub light_client_head: CryptoHash,
+}
+
+impl Message for GetReceiptExecutionProof {
+    type Result = Result<ReceiptExecutionProofView, GetExecutionOutcomeError>;
+}
+
 pub struct GetBlockProof {
     pub block_hash: CryptoHash,
     pub head_block_hash: CryptoHash,
@@ -892,6 +1264,109 @@ impl From<near_chain_primitives::Error> for GetProtocolConfigError {
     }
 }
 
+/// Differences between runtime parameters of two protocol versions.
+pub struct GetRuntimeParametersDiff {
+    pub from_protocol_version: ProtocolVersion,
+    pub to_protocol_version: ProtocolVersion,
+}
+
+impl Message for GetRuntimeParametersDiff {
+    type Result = Result<RuntimeParametersDiffView, GetRuntimeParametersDiffError>;
+}
+
+#[derive(thiserror::Error, Debug)]
+pub enum GetRuntimeParametersDiffError {
+    #[error(
+        "Protocol version {requested} is newer than the latest version {latest} known to the node"
+    )]
+    UnknownProtocolVersion { requested: ProtocolVersion, latest: ProtocolVersion },
+}
+
+/// Operator actions on the transaction pool, meant for incident response when
+/// the pool gets clogged.
+#[derive(Debug)]
+pub enum TxPoolCommand {
+    /// Drops the transactions with the given hashes.
+    DropTransactions(Vec<CryptoHash>),
+    /// Drops all transactions signed by the account.
+    DropSignerTransactions(AccountId),
+    /// Keeps the transaction in the pool even if it's found invalid when
+    /// producing a chunk, until it's included in a block or dropped.
+    Pin(CryptoHash),
+    Unpin(CryptoHash),
+}
+
+impl Message for TxPoolCommand {
+    /// Hashes of the transactions affected by the command.
+    type Result = Vec<CryptoHash>;
+}
+
+/// Makes the node track the shards with the given ids instead of the shards or
+/// accounts it was configured with, see `Client::update_tracked_shards`.
+#[derive(Debug)]
+pub struct UpdateTrackedShards {
+    pub shard_ids: Vec<ShardId>,
+}
+
+impl Message for UpdateTrackedShards {
+    type Result = Result<(), String>;
+}
+
+/// Precondition of block production which isn't met, see
+/// `Client::simulate_block_production`.
+#[derive(Clone, Debug, PartialEq, Eq, Serialize)]
+pub enum BlockProductionFailure {
+    /// The node has no validator key.
+    NoValidatorSigner,
+    /// A block at the height, or a higher one, has been produced or approved
+    /// already.
+    HeightAlreadyKnown { latest_known_height: BlockHeight },
+    /// Another validator produces the block at the height.
+    NotBlockProducer { block_producer: AccountId },
+    /// The next block starts an epoch and the state of the shards the node
+    /// tracks in it isn't caught up yet.
+    PrevBlockNotCaughtUp,
+    /// The validator key of the node isn't the one the block producer staked
+    /// with.
+    ValidatorKeyMismatch { local: near_crypto::PublicKey, expected: near_crypto::PublicKey },
+    /// No chunks are ready for inclusion and empty blocks aren't produced.
+    NoNewChunks,
+    /// Validators with 2/3 of the stake haven't approved the height yet.
+    NotEnoughApprovals { largest_height_crossing_threshold: BlockHeight },
+    /// The protocol version of the epoch is newer than the one of the node.
+    UnsupportedProtocolVersion { protocol_version: ProtocolVersion },
+}
+
+/// Result of a dry run of block production.
+#[derive(Clone, Debug, Serialize)]
+pub struct BlockProductionReport {
+    pub next_height: BlockHeight,
+    pub prev_hash: CryptoHash,
+    pub block_producer: AccountId,
+    pub num_new_chunks: usize,
+    pub num_shards: NumShards,
+    /// Preconditions which aren't met, in the order block production checks
+    /// them.  The block would be produced if there are none.
+    pub failures: Vec<BlockProductionFailure>,
+}
+
+impl BlockProductionReport {
+    pub fn can_produce(&self) -> bool {
+        self.failures.is_empty()
+    }
+}
+
+/// Checks whether the node would produce the block at the height on top of its
+/// head, see `Client::simulate_block_production`.
+#[derive(Debug)]
+pub struct SimulateBlockProduction {
+    pub next_height: BlockHeight,
+}
+
+impl Message for SimulateBlockProduction {
+    type Result = Result<BlockProductionReport, String>;
+}
+
 #[cfg(feature = "sandbox")]
 #[derive(Debug)]
 pub enum SandboxMessage {
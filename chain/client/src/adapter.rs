@@ -1,10 +1,13 @@
 use crate::client_actor::ClientActor;
+use crate::message_dedup::SeenMessages;
 use crate::view_client::ViewClientActor;
 use near_network::time;
 use near_network::types::{
-    NetworkInfo, PartialEncodedChunkForwardMsg, PartialEncodedChunkRequestMsg,
-    PartialEncodedChunkResponseMsg, ReasonForBan, StateResponseInfo,
+    BlockHeaderAnnouncement, ChunkInclusionFeedbackMsg, NetworkInfo,
+    PartialEncodedChunkAvailabilityMsg, PartialEncodedChunkForwardMsg,
+    PartialEncodedChunkRequestMsg, PartialEncodedChunkResponseMsg, ReasonForBan, StateResponseInfo,
 };
+use near_o11y::metrics::MetricsRegistry;
 use near_o11y::WithSpanContextExt;
 use near_primitives::block::{Approval, Block, BlockHeader};
 use near_primitives::challenge::Challenge;
@@ -12,8 +15,9 @@ use near_primitives::errors::InvalidTxError;
 use near_primitives::hash::CryptoHash;
 use near_primitives::network::{AnnounceAccount, PeerId};
 use near_primitives::sharding::PartialEncodedChunk;
+use near_primitives::syncing::{EpochSyncDataResponse, EpochSyncProofsResponse};
 use near_primitives::transaction::SignedTransaction;
-use near_primitives::types::{AccountId, EpochId, ShardId};
+use near_primitives::types::{AccountId, BlockHeight, EpochId, ShardId};
 use near_primitives::views::FinalExecutionOutcomeView;
 
 /// Transaction status query
@@ -29,6 +33,16 @@ pub(crate) struct TxStatusRequest {
 #[rtype(result = "()")]
 pub(crate) struct TxStatusResponse(pub Box<FinalExecutionOutcomeView>);
 
+/// Subscription to the final outcome of a transaction
+#[derive(actix::Message)]
+#[rtype(result = "Option<Box<FinalExecutionOutcomeView>>")]
+pub(crate) struct TxStatusSubscribeRequest {
+    pub tx_hash: CryptoHash,
+    pub signer_account_id: AccountId,
+    pub route_back: CryptoHash,
+    pub subscriber: PeerId,
+}
+
 /// Request a block.
 #[derive(actix::Message)]
 #[rtype(result = "Option<Box<Block>>")]
@@ -43,6 +57,11 @@ pub struct BlockResponse {
     pub was_requested: bool,
 }
 
+/// Block announced by its header.
+#[derive(actix::Message, Debug)]
+#[rtype(result = "()")]
+pub(crate) struct RecvBlockHeaderAnnouncement(pub BlockHeaderAnnouncement, pub PeerId);
+
 #[derive(actix::Message, Debug)]
 #[rtype(result = "()")]
 pub struct BlockApproval(pub Approval, pub PeerId);
@@ -74,11 +93,37 @@ pub(crate) struct StateRequestPart {
     pub part_id: u64,
 }
 
+/// State request for a single sub-part of a state part.
+#[derive(actix::Message)]
+#[rtype(result = "Option<StateResponse>")]
+pub(crate) struct StateRequestSubPart {
+    pub shard_id: ShardId,
+    pub sync_hash: CryptoHash,
+    pub part_id: u64,
+    pub sub_part_id: u64,
+    pub sub_part_size: u64,
+}
+
 /// Response to state request.
 #[derive(actix::Message, Debug)]
 #[rtype(result = "()")]
 pub(crate) struct StateResponse(pub Box<StateResponseInfo>);
 
+/// Request for epoch sync data of the first block of an epoch.
+#[derive(actix::Message)]
+#[rtype(result = "Option<EpochSyncDataResponse>")]
+pub(crate) struct EpochSyncDataRequest(pub CryptoHash);
+
+/// Request for proofs of the epochs following the epoch of a block.
+#[derive(actix::Message)]
+#[rtype(result = "Option<EpochSyncProofsResponse>")]
+pub(crate) struct EpochSyncProofsRequest(pub CryptoHash);
+
+/// Epoch proofs received from a peer.
+#[derive(actix::Message, Debug)]
+#[rtype(result = "()")]
+pub(crate) struct RecvEpochSyncProofsResponse(pub EpochSyncProofsResponse, pub PeerId);
+
 /// Account announcements that needs to be validated before being processed.
 /// They are paired with last epoch id known to this announcement, in order to accept only
 /// newer announcements.
@@ -98,6 +143,14 @@ pub(crate) struct RecvChallenge(pub Challenge);
 #[rtype(result = "()")]
 pub(crate) struct RecvPartialEncodedChunkForward(pub PartialEncodedChunkForwardMsg);
 
+#[derive(actix::Message, Debug)]
+#[rtype(result = "()")]
+pub(crate) struct RecvPartialEncodedChunkAvailability(pub PartialEncodedChunkAvailabilityMsg);
+
+#[derive(actix::Message, Debug)]
+#[rtype(result = "()")]
+pub(crate) struct RecvChunkInclusionFeedback(pub ChunkInclusionFeedbackMsg);
+
 #[derive(actix::Message, Debug)]
 #[rtype(result = "()")]
 pub(crate) struct RecvPartialEncodedChunk(pub PartialEncodedChunk);
@@ -121,10 +174,18 @@ pub struct ProcessTxRequest {
     pub check_only: bool,
 }
 
+/// Transaction to be processed once the block it's anchored to is final, see
+/// `Client::defer_tx`.
+#[derive(actix::Message, Debug)]
+#[rtype(result = "ProcessTxResponse")]
+pub struct DeferTxRequest {
+    pub transaction: SignedTransaction,
+}
+
 #[derive(actix::MessageResponse, Debug, PartialEq, Eq)]
 pub enum ProcessTxResponse {
-    /// No response.
-    NoResponse,
+    /// The node didn't accept the transaction even though it isn't invalid.
+    Rejected(TxRejectionReason),
     /// Valid transaction inserted into mempool as response to Transaction.
     ValidTx,
     /// Invalid transaction inserted into mempool as response to Transaction.
@@ -136,11 +197,58 @@ pub enum ProcessTxResponse {
     DoesNotTrackShard,
 }
 
+/// Reason the node didn't accept a transaction which wasn't found invalid.
+#[derive(thiserror::Error, Debug, Clone, PartialEq, Eq)]
+pub enum TxRejectionReason {
+    /// The node doesn't have the state of the shard of the transaction yet, as
+    /// it's still catching up with it.
+    #[error("node has not caught up with the state of shard {shard_id} yet")]
+    NodeNotCaughtUp { shard_id: ShardId },
+    /// The transaction was forwarded to the node, but the node doesn't track
+    /// its shard.
+    #[error("node does not track shard {shard_id} of the forwarded transaction")]
+    ShardNotTracked { shard_id: ShardId },
+    /// The transaction was forwarded to the node, but the node isn't a
+    /// validator of its shard and doesn't forward it any further.
+    #[error("node is not a validator of shard {shard_id} of the forwarded transaction")]
+    NotValidator { shard_id: ShardId },
+    /// The signer already has as many transactions in the pool as it's allowed
+    /// to and none of them could be evicted to make room.
+    #[error("{signer_id} already has {limit} transactions in the pool")]
+    SignerQuotaExceeded { signer_id: AccountId, limit: usize },
+    /// The transaction relays a delegate action and the relayer, which signs
+    /// the transaction and whose quota it counts against, already has as many
+    /// transactions in the pool as it's allowed to.
+    #[error("relayer {relayer_id} already has {limit} transactions in the pool")]
+    RelayerQuotaExceeded { relayer_id: AccountId, limit: usize },
+    /// The transaction relays a delegate action which can no longer be
+    /// executed: the next block is higher than its `max_block_height`.
+    #[error("delegate action expired at height {max_block_height}, next block is {next_height}")]
+    DelegateActionExpired { max_block_height: BlockHeight, next_height: BlockHeight },
+    /// The node is draining before it's stopped and no longer accepts
+    /// transactions.
+    #[error("node is draining before shutdown")]
+    Draining,
+    /// The transaction was submitted deferred, but the node already holds as
+    /// many deferred transactions as it's allowed to.
+    #[error("node already holds {limit} deferred transactions")]
+    TooManyDeferred { limit: usize },
+    /// The transaction was submitted deferred, but the node already holds as
+    /// many deferred transactions of its signer as it's allowed to.
+    #[error("node already holds {limit} deferred transactions of {signer_id}")]
+    TooManyDeferredFromSigner { signer_id: AccountId, limit: usize },
+    /// Processing of the transaction failed.
+    #[error("{error_message}")]
+    InternalError { error_message: String },
+}
+
 pub struct Adapter {
     /// Address of the client actor.
     client_addr: actix::Addr<ClientActor>,
     /// Address of the view client actor.
     view_client_addr: actix::Addr<ViewClientActor>,
+    /// Recently received routed messages, to drop their copies.
+    seen_messages: SeenMessages,
 }
 
 impl Adapter {
@@ -148,7 +256,8 @@ impl Adapter {
         client_addr: actix::Addr<ClientActor>,
         view_client_addr: actix::Addr<ViewClientActor>,
     ) -> Self {
-        Self { client_addr, view_client_addr }
+        let seen_messages = SeenMessages::new(MetricsRegistry::global().get());
+        Self { client_addr, view_client_addr, seen_messages }
     }
 }
 
@@ -188,6 +297,34 @@ impl near_network::client::Client for Adapter {
         }
     }
 
+    async fn tx_status_subscribe(
+        &self,
+        account_id: AccountId,
+        tx_hash: CryptoHash,
+        route_back: CryptoHash,
+        subscriber: PeerId,
+    ) -> Option<Box<FinalExecutionOutcomeView>> {
+        match self
+            .client_addr
+            .send(
+                TxStatusSubscribeRequest {
+                    tx_hash,
+                    signer_account_id: account_id,
+                    route_back,
+                    subscriber,
+                }
+                .with_span_context(),
+            )
+            .await
+        {
+            Ok(res) => res,
+            Err(err) => {
+                tracing::error!("mailbox error: {err}");
+                None
+            }
+        }
+    }
+
     async fn state_request_header(
         &self,
         shard_id: ShardId,
@@ -232,6 +369,31 @@ impl near_network::client::Client for Adapter {
         }
     }
 
+    async fn state_request_sub_part(
+        &self,
+        shard_id: ShardId,
+        sync_hash: CryptoHash,
+        part_id: u64,
+        sub_part_id: u64,
+        sub_part_size: u64,
+    ) -> Result<Option<StateResponseInfo>, ReasonForBan> {
+        match self
+            .view_client_addr
+            .send(
+                StateRequestSubPart { shard_id, sync_hash, part_id, sub_part_id, sub_part_size }
+                    .with_span_context(),
+            )
+            .await
+        {
+            Ok(Some(StateResponse(resp))) => Ok(Some(*resp)),
+            Ok(None) => Ok(None),
+            Err(err) => {
+                tracing::error!("mailbox error: {err}");
+                Ok(None)
+            }
+        }
+    }
+
     async fn state_response(&self, info: StateResponseInfo) {
         match self.client_addr.send(StateResponse(Box::new(info)).with_span_context()).await {
             Ok(()) => {}
@@ -239,7 +401,52 @@ impl near_network::client::Client for Adapter {
         }
     }
 
+    async fn epoch_sync_data_request(
+        &self,
+        block_hash: CryptoHash,
+    ) -> Result<Option<EpochSyncDataResponse>, ReasonForBan> {
+        match self.view_client_addr.send(EpochSyncDataRequest(block_hash).with_span_context()).await
+        {
+            Ok(response) => Ok(response),
+            Err(err) => {
+                tracing::error!("mailbox error: {err}");
+                Ok(None)
+            }
+        }
+    }
+
+    async fn epoch_sync_proofs_request(
+        &self,
+        block_hash: CryptoHash,
+    ) -> Result<Option<EpochSyncProofsResponse>, ReasonForBan> {
+        match self
+            .view_client_addr
+            .send(EpochSyncProofsRequest(block_hash).with_span_context())
+            .await
+        {
+            Ok(response) => Ok(response),
+            Err(err) => {
+                tracing::error!("mailbox error: {err}");
+                Ok(None)
+            }
+        }
+    }
+
+    async fn epoch_sync_proofs_response(&self, response: EpochSyncProofsResponse, peer_id: PeerId) {
+        match self
+            .client_addr
+            .send(RecvEpochSyncProofsResponse(response, peer_id).with_span_context())
+            .await
+        {
+            Ok(()) => {}
+            Err(err) => tracing::error!("mailbox error: {err}"),
+        }
+    }
+
     async fn block_approval(&self, approval: Approval, peer_id: PeerId) {
+        if self.seen_messages.is_duplicate("approval", &approval) {
+            return;
+        }
         match self.client_addr.send(BlockApproval(approval, peer_id).with_span_context()).await {
             Ok(()) => {}
             Err(err) => tracing::error!("mailbox error: {err}"),
@@ -247,6 +454,9 @@ impl near_network::client::Client for Adapter {
     }
 
     async fn transaction(&self, transaction: SignedTransaction, is_forwarded: bool) {
+        if self.seen_messages.is_duplicate("transaction", &transaction) {
+            return;
+        }
         match self
             .client_addr
             .send(
@@ -297,6 +507,9 @@ impl near_network::client::Client for Adapter {
     }
 
     async fn partial_encoded_chunk(&self, chunk: PartialEncodedChunk) {
+        if self.seen_messages.is_duplicate("partial_encoded_chunk", &chunk) {
+            return;
+        }
         match self.client_addr.send(RecvPartialEncodedChunk(chunk).with_span_context()).await {
             Ok(()) => {}
             Err(err) => tracing::error!("mailbox error: {err}"),
@@ -304,12 +517,33 @@ impl near_network::client::Client for Adapter {
     }
 
     async fn partial_encoded_chunk_forward(&self, msg: PartialEncodedChunkForwardMsg) {
+        if self.seen_messages.is_duplicate("partial_encoded_chunk_forward", &msg) {
+            return;
+        }
         match self.client_addr.send(RecvPartialEncodedChunkForward(msg).with_span_context()).await {
             Ok(()) => {}
             Err(err) => tracing::error!("mailbox error: {err}"),
         }
     }
 
+    async fn partial_encoded_chunk_availability(&self, msg: PartialEncodedChunkAvailabilityMsg) {
+        match self
+            .client_addr
+            .send(RecvPartialEncodedChunkAvailability(msg).with_span_context())
+            .await
+        {
+            Ok(()) => {}
+            Err(err) => tracing::error!("mailbox error: {err}"),
+        }
+    }
+
+    async fn chunk_inclusion_feedback(&self, msg: ChunkInclusionFeedbackMsg) {
+        match self.client_addr.send(RecvChunkInclusionFeedback(msg).with_span_context()).await {
+            Ok(()) => {}
+            Err(err) => tracing::error!("mailbox error: {err}"),
+        }
+    }
+
     async fn block_request(&self, hash: CryptoHash) -> Option<Box<Block>> {
         match self.view_client_addr.send(BlockRequest(hash).with_span_context()).await {
             Ok(res) => res,
@@ -341,6 +575,21 @@ impl near_network::client::Client for Adapter {
         }
     }
 
+    async fn block_header_announcement(
+        &self,
+        announcement: BlockHeaderAnnouncement,
+        peer_id: PeerId,
+    ) {
+        match self
+            .client_addr
+            .send(RecvBlockHeaderAnnouncement(announcement, peer_id).with_span_context())
+            .await
+        {
+            Ok(()) => {}
+            Err(err) => tracing::error!("mailbox error: {err}"),
+        }
+    }
+
     async fn block_headers(
         &self,
         headers: Vec<BlockHeader>,
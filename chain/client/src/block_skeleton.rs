@@ -0,0 +1,48 @@
+//! Parts of the next block which only depend on its previous block.
+//!
+//! Producing a block involves work which only depends on the previous block:
+//! inserting the previous block into the block merkle tree, copying the chunk
+//! headers of the shards without new chunks and listing the approvers whose
+//! approvals the block collects.  When a block this node builds on next
+//! becomes the head, that work is done right away, so that `produce_block`
+//! only has to do it when it ends up building on another block.
+use near_chain::{Chain, ChainStoreAccess, Error, RuntimeAdapter};
+use near_primitives::hash::CryptoHash;
+use near_primitives::merkle::PartialMerkleTree;
+use near_primitives::sharding::ShardChunkHeader;
+use near_primitives::types::{ApprovalStake, NumBlocks};
+
+pub(crate) struct NextBlockSkeleton {
+    /// Hash of the block the skeleton is for the successor of.
+    pub prev_hash: CryptoHash,
+    pub block_merkle_root: CryptoHash,
+    pub block_ordinal: NumBlocks,
+    /// Chunk headers of the previous block, with the included height of new
+    /// chunks not updated yet.
+    pub prev_chunk_headers: Vec<ShardChunkHeader>,
+    /// Block approvers of the epoch in order, and whether they are slashed.
+    pub approvers: Vec<(ApprovalStake, bool)>,
+}
+
+impl NextBlockSkeleton {
+    pub fn compute(
+        chain: &Chain,
+        runtime_adapter: &dyn RuntimeAdapter,
+        prev_hash: &CryptoHash,
+    ) -> Result<Self, Error> {
+        let mut block_merkle_tree =
+            PartialMerkleTree::clone(&chain.store().get_block_merkle_tree(prev_hash)?);
+        block_merkle_tree.insert(*prev_hash);
+        let prev_block = chain.get_block(prev_hash)?;
+        Ok(Self {
+            prev_hash: *prev_hash,
+            block_merkle_root: block_merkle_tree.root(),
+            // The number of leaves in Block Merkle Tree is the amount of Blocks
+            // on the Canonical Chain by construction.  The ordinal of the next
+            // Block will be equal to this amount plus one.
+            block_ordinal: block_merkle_tree.size() + 1,
+            prev_chunk_headers: Chain::get_prev_chunk_headers(runtime_adapter, &prev_block)?,
+            approvers: runtime_adapter.get_epoch_block_approvers_ordered(prev_hash)?,
+        })
+    }
+}
@@ -0,0 +1,232 @@
+//! Tracks the blocks requested during block sync.
+//!
+//! Block sync used to request a handful of blocks ahead of the head from
+//! random peers, and requested the same blocks again whenever the head moved,
+//! so it advanced about one round trip per handful of blocks.  Instead, block
+//! sync now keeps many blocks in flight at the same time, spread over all
+//! peers with a limit on how many blocks a single peer is asked for.  A block
+//! is requested again only if the peer didn't deliver it in time, from another
+//! peer if there is one.  Peers which don't deliver in time are blamed and
+//! are asked for fewer blocks until they deliver again.
+use std::collections::HashMap;
+
+use chrono::{DateTime, Duration};
+use near_network::types::FullPeerInfo;
+use near_primitives::hash::CryptoHash;
+use near_primitives::network::PeerId;
+use near_primitives::time::Utc;
+use rand::seq::SliceRandom;
+use rand::thread_rng;
+
+/// Maximum number of blocks requested from a single peer and not delivered yet.
+pub(crate) const MAX_BLOCK_REQUESTS_PER_PEER: usize = 8;
+
+/// How long a peer has to deliver a requested block.
+pub(crate) const BLOCK_REQUEST_TIMEOUT: i64 = 2;
+
+/// Blame after which a peer is only asked for one block at a time.
+const MAX_BLAME: u32 = 3;
+
+struct BlockRequest {
+    peer_id: PeerId,
+    requested_at: DateTime<Utc>,
+    /// Whether the peer didn't deliver the block in time.
+    timed_out: bool,
+}
+
+#[derive(Default)]
+pub(crate) struct BlockSyncRequests {
+    requests: HashMap<CryptoHash, BlockRequest>,
+    /// Number of requests each peer didn't deliver in time since it last
+    /// delivered a block.
+    blame: HashMap<PeerId, u32>,
+}
+
+impl BlockSyncRequests {
+    /// Hashes of the blocks requested and not delivered yet.
+    pub fn requested(&self) -> Vec<CryptoHash> {
+        self.requests.keys().copied().collect()
+    }
+
+    /// Records that the block `hash` was delivered, clearing the blame of the
+    /// peer it was requested from.
+    pub fn on_block_received(&mut self, hash: &CryptoHash) {
+        if let Some(request) = self.requests.remove(hash) {
+            self.blame.remove(&request.peer_id);
+        }
+    }
+
+    /// Forgets requests of blocks which aren't needed anymore, e.g. because
+    /// the node switched to another fork.
+    pub fn retain(&mut self, mut needed: impl FnMut(&CryptoHash) -> bool) {
+        self.requests.retain(|hash, _| needed(hash));
+    }
+
+    pub fn clear(&mut self) {
+        self.requests.clear();
+    }
+
+    /// Blames peers for the requests they didn't deliver in time.  Returns
+    /// the number of such requests.
+    pub fn expire(&mut self, now: DateTime<Utc>) -> usize {
+        let mut expired = 0;
+        for request in self.requests.values_mut() {
+            if !request.timed_out
+                && now - request.requested_at > Duration::seconds(BLOCK_REQUEST_TIMEOUT)
+            {
+                request.timed_out = true;
+                *self.blame.entry(request.peer_id.clone()).or_default() += 1;
+                expired += 1;
+            }
+        }
+        expired
+    }
+
+    /// Whether the block `hash` needs to be requested, i.e. it wasn't
+    /// requested yet or the peer didn't deliver it in time.
+    pub fn needs_request(&self, hash: &CryptoHash) -> bool {
+        self.requests.get(hash).map_or(true, |request| request.timed_out)
+    }
+
+    /// Picks the peer to request the block `hash` from among `peers`, or None
+    /// if all of them have as many blocks in flight as they are allowed.
+    /// Peers with fewer blocks in flight and less blame go first, and a block
+    /// isn't requested again from the peer which didn't deliver it unless
+    /// there is no other peer.
+    pub fn choose_peer<'a>(
+        &self,
+        hash: &CryptoHash,
+        peers: &[&'a FullPeerInfo],
+    ) -> Option<&'a FullPeerInfo> {
+        let previous_peer = self.requests.get(hash).map(|request| &request.peer_id);
+        let mut in_flight: HashMap<&PeerId, usize> = HashMap::new();
+        for request in self.requests.values().filter(|request| !request.timed_out) {
+            *in_flight.entry(&request.peer_id).or_default() += 1;
+        }
+        let mut candidates: Vec<_> = peers
+            .iter()
+            .copied()
+            .filter_map(|peer| {
+                let peer_id = &peer.peer_info.id;
+                let in_flight = in_flight.get(peer_id).copied().unwrap_or(0);
+                let blame = self.blame.get(peer_id).copied().unwrap_or(0);
+                let limit = (MAX_BLOCK_REQUESTS_PER_PEER >> blame.min(MAX_BLAME)).max(1);
+                (in_flight < limit)
+                    .then(|| (peer, Some(peer_id) == previous_peer, in_flight, blame))
+            })
+            .collect();
+        // Shuffle so that ties are broken randomly.
+        candidates.shuffle(&mut thread_rng());
+        candidates
+            .into_iter()
+            .min_by_key(|(_, is_previous, in_flight, blame)| (*is_previous, *in_flight, *blame))
+            .map(|(peer, ..)| peer)
+    }
+
+    pub fn record_request(&mut self, hash: CryptoHash, peer_id: PeerId, now: DateTime<Utc>) {
+        self.requests.insert(hash, BlockRequest { peer_id, requested_at: now, timed_out: false });
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{BlockSyncRequests, MAX_BLOCK_REQUESTS_PER_PEER};
+    use chrono::Duration;
+    use near_network::test_utils::peer_id_from_seed;
+    use near_network::types::{FullPeerInfo, PartialEdgeInfo, PeerInfo};
+    use near_primitives::hash::hash;
+    use near_primitives::time::Utc;
+
+    fn peer(seed: &str) -> FullPeerInfo {
+        FullPeerInfo {
+            peer_info: PeerInfo { id: peer_id_from_seed(seed), addr: None, account_id: None },
+            chain_info: Default::default(),
+            partial_edge_info: PartialEdgeInfo::default(),
+            state_sub_part_size_limit: 0,
+        }
+    }
+
+    #[test]
+    fn test_spread_over_peers() {
+        let peers = [peer("a"), peer("b")];
+        let peers: Vec<_> = peers.iter().collect();
+        let mut requests = BlockSyncRequests::default();
+        let now = Utc::now();
+        let mut assigned = vec![];
+        for i in 0..3 * MAX_BLOCK_REQUESTS_PER_PEER as u64 {
+            let block_hash = hash(&i.to_le_bytes());
+            assert!(requests.needs_request(&block_hash));
+            match requests.choose_peer(&block_hash, &peers) {
+                Some(peer) => {
+                    requests.record_request(block_hash, peer.peer_info.id.clone(), now);
+                    assigned.push(peer.peer_info.id.clone());
+                }
+                None => break,
+            }
+            assert!(!requests.needs_request(&block_hash));
+        }
+        assert_eq!(assigned.len(), 2 * MAX_BLOCK_REQUESTS_PER_PEER);
+        for peer in &peers {
+            let count = assigned.iter().filter(|id| *id == &peer.peer_info.id).count();
+            assert_eq!(count, MAX_BLOCK_REQUESTS_PER_PEER);
+        }
+
+        // A delivered block frees a slot.
+        requests.on_block_received(&hash(&0u64.to_le_bytes()));
+        assert_eq!(requests.requested().len(), 2 * MAX_BLOCK_REQUESTS_PER_PEER - 1);
+        assert!(requests.choose_peer(&hash(&100u64.to_le_bytes()), &peers).is_some());
+    }
+
+    #[test]
+    fn test_retry_and_blame() {
+        let slow = peer("slow");
+        let fast = peer("fast");
+        let peers = vec![&slow, &fast];
+        let mut requests = BlockSyncRequests::default();
+        let now = Utc::now();
+        let block_hash = hash(b"block");
+        requests.record_request(block_hash, slow.peer_info.id.clone(), now);
+        assert_eq!(requests.expire(now + Duration::seconds(1)), 0);
+        assert!(!requests.needs_request(&block_hash));
+
+        // The block is requested again, from the other peer.
+        assert_eq!(requests.expire(now + Duration::seconds(3)), 1);
+        assert_eq!(requests.expire(now + Duration::seconds(4)), 0);
+        assert!(requests.needs_request(&block_hash));
+        let peer = requests.choose_peer(&block_hash, &peers).unwrap();
+        assert_eq!(peer.peer_info.id, fast.peer_info.id);
+        // Unless there is no other peer.
+        let peer = requests.choose_peer(&block_hash, &[&slow]).unwrap();
+        assert_eq!(peer.peer_info.id, slow.peer_info.id);
+
+        // The blamed peer is asked for fewer blocks.
+        requests.record_request(block_hash, fast.peer_info.id.clone(), now);
+        let mut assigned_to_slow = 0;
+        for i in 0..MAX_BLOCK_REQUESTS_PER_PEER as u64 {
+            let block_hash = hash(&i.to_le_bytes());
+            if let Some(peer) = requests.choose_peer(&block_hash, &[&slow]) {
+                requests.record_request(block_hash, peer.peer_info.id.clone(), now);
+                assigned_to_slow += 1;
+            }
+        }
+        assert_eq!(assigned_to_slow, MAX_BLOCK_REQUESTS_PER_PEER / 2);
+
+        // Delivering a block clears the blame.
+        requests.on_block_received(&hash(&0u64.to_le_bytes()));
+        let mut assigned_to_slow = 0;
+        for i in 0..MAX_BLOCK_REQUESTS_PER_PEER as u64 {
+            let block_hash = hash(&(100 + i).to_le_bytes());
+            if let Some(peer) = requests.choose_peer(&block_hash, &[&slow]) {
+                requests.record_request(block_hash, peer.peer_info.id.clone(), now);
+                assigned_to_slow += 1;
+            }
+        }
+        assert_eq!(assigned_to_slow, MAX_BLOCK_REQUESTS_PER_PEER / 2 + 1);
+
+        // Requests of blocks which aren't needed anymore are forgotten.
+        requests.retain(|hash| hash == &block_hash);
+        assert_eq!(requests.requested(), vec![block_hash]);
+        requests.clear();
+        assert!(requests.requested().is_empty());
+    }
+}
@@ -0,0 +1,114 @@
+//! Decides how long the block producer waits for chunks which usually arrive
+//! late.
+//!
+//! For every shard the node records, for the most recent chunks, how long after
+//! the production timestamp of the previous block the chunk became ready for
+//! inclusion.  When a block could be produced but chunks of some shards are
+//! still missing, the producer keeps waiting as long as, judging by these
+//! delays, a missing chunk is likely to arrive soon, but never longer than the
+//! configured bound after the previous block.
+//!
+//! Shards whose chunks are missing altogether have no recent arrivals and are
+//! never waited for, so a chunk producer being offline doesn't slow the chain
+//! down.
+use std::collections::{HashMap, VecDeque};
+use std::time::Duration;
+
+use near_primitives::types::ShardId;
+
+/// Number of most recent chunks per shard the delays are kept for.
+const WINDOW: usize = 100;
+/// Shards with fewer recorded delays are not waited for.
+const MIN_SAMPLES: usize = 10;
+/// Fraction of the recent chunks of a shard the producer waits for.
+const QUANTILE: f64 = 0.9;
+
+#[derive(Default)]
+pub(crate) struct ChunkArrivalStats {
+    delays: HashMap<ShardId, VecDeque<Duration>>,
+}
+
+impl ChunkArrivalStats {
+    /// Records that a chunk of the shard became ready for inclusion `delay`
+    /// after the production of the previous block.
+    pub fn record(&mut self, shard_id: ShardId, delay: Duration) {
+        let delays = self.delays.entry(shard_id).or_default();
+        if delays.len() == WINDOW {
+            delays.pop_front();
+        }
+        delays.push_back(delay);
+    }
+
+    /// Returns the delay after the previous block by which chunks of the shard
+    /// have usually arrived, or `None` if there were too few of them recently.
+    pub fn expected_delay(&self, shard_id: ShardId) -> Option<Duration> {
+        let delays = self.delays.get(&shard_id)?;
+        if delays.len() < MIN_SAMPLES {
+            return None;
+        }
+        let mut delays: Vec<_> = delays.iter().copied().collect();
+        delays.sort();
+        let index = ((delays.len() - 1) as f64 * QUANTILE).round() as usize;
+        Some(delays[index])
+    }
+
+    /// Returns how much longer to wait for the `missing` shards, `elapsed`
+    /// after the previous block, or `None` if none of them is worth waiting
+    /// for.  Chunks expected later than `max_wait` after the previous block
+    /// are not waited for.
+    pub fn wait_for_missing(
+        &self,
+        missing: impl Iterator<Item = ShardId>,
+        elapsed: Duration,
+        max_wait: Duration,
+    ) -> Option<Duration> {
+        missing
+            .filter_map(|shard_id| self.expected_delay(shard_id))
+            .filter(|expected| *expected <= max_wait && *expected > elapsed)
+            .map(|expected| expected - elapsed)
+            .max()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{ChunkArrivalStats, MIN_SAMPLES, WINDOW};
+    use std::time::Duration;
+
+    fn ms(millis: u64) -> Duration {
+        Duration::from_millis(millis)
+    }
+
+    #[test]
+    fn test_expected_delay() {
+        let mut stats = ChunkArrivalStats::default();
+        for i in 0..MIN_SAMPLES as u64 - 1 {
+            stats.record(0, ms(100 + i));
+        }
+        assert_eq!(stats.expected_delay(0), None);
+        stats.record(0, ms(1000));
+        assert_eq!(stats.expected_delay(0), Some(ms(108)));
+        assert_eq!(stats.expected_delay(1), None);
+
+        // Only the most recent delays count.
+        for _ in 0..WINDOW {
+            stats.record(0, ms(700));
+        }
+        assert_eq!(stats.expected_delay(0), Some(ms(700)));
+    }
+
+    #[test]
+    fn test_wait_for_missing() {
+        let mut stats = ChunkArrivalStats::default();
+        for _ in 0..MIN_SAMPLES {
+            stats.record(0, ms(300));
+            stats.record(1, ms(800));
+            stats.record(2, ms(3000));
+        }
+        let max_wait = ms(1200);
+        // Shard 2 is expected too late and shard 3 has no history.
+        assert_eq!(stats.wait_for_missing([2, 3].into_iter(), ms(100), max_wait), None);
+        assert_eq!(stats.wait_for_missing([0, 1, 2].into_iter(), ms(100), max_wait), Some(ms(700)));
+        assert_eq!(stats.wait_for_missing([0].into_iter(), ms(500), max_wait), None);
+    }
+}
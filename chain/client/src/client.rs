@@ -2,7 +2,8 @@
 //! This client works completely synchronously and must be operated by some async actor outside.
 
 use std::cmp::max;
-use std::collections::{HashMap, HashSet};
+use std::collections::{BTreeMap, HashMap, HashSet};
+use std::io;
 use std::sync::Arc;
 use std::time::{Duration, Instant};
 
@@ -11,10 +12,11 @@ use near_chunks::client::{ClientAdapterForShardsManager, ShardedTransactionPool}
 use near_chunks::logic::{
     cares_about_shard_this_or_next_epoch, decode_encoded_chunk, persist_chunk,
 };
-use near_client_primitives::debug::ChunkProduction;
+use near_client_primitives::debug::{ChunkProduction, MissedChunkInclusion};
 use near_primitives::time::Clock;
 use tracing::{debug, error, info, trace, warn};
 
+use near_chain::block_stages::BlockStage;
 use near_chain::chain::{
     ApplyStatePartsRequest, BlockCatchUpRequest, BlockMissingChunks, BlocksCatchUpState,
     OrphanMissingChunks, StateSplitRequest, TX_ROUTING_HEIGHT_HORIZON,
@@ -27,52 +29,85 @@ use near_chain::{
 };
 use near_chain_configs::ClientConfig;
 use near_chunks::ShardsManager;
-use near_network::types::{FullPeerInfo, NetworkRequests, PeerManagerAdapter, ReasonForBan};
+use near_crypto::PublicKey;
+use near_network::types::{
+    BlockHeaderAnnouncement, ChunkInclusionFeedbackMsg, FullPeerInfo, NetworkRequests,
+    PeerManagerAdapter, ReasonForBan,
+};
 use near_primitives::block::{Approval, ApprovalInner, ApprovalMessage, Block, BlockHeader, Tip};
 use near_primitives::challenge::{Challenge, ChallengeBody};
+use near_primitives::errors::{ActionsValidationError, InvalidTxError};
 use near_primitives::hash::CryptoHash;
-use near_primitives::merkle::{merklize, MerklePath, PartialMerkleTree};
+use near_primitives::merkle::{merklize, MerklePath};
 use near_primitives::receipt::Receipt;
 use near_primitives::sharding::{
     ChunkHash, EncodedShardChunk, PartialEncodedChunk, ReedSolomonWrapper, ShardChunk,
     ShardChunkHeader, ShardInfo,
 };
-use near_primitives::transaction::SignedTransaction;
+use near_primitives::transaction::{Action, SignedTransaction};
 use near_primitives::types::chunk_extra::ChunkExtra;
 use near_primitives::types::{AccountId, ApprovalStake, BlockHeight, EpochId, NumBlocks, ShardId};
 use near_primitives::unwrap_or_return;
 use near_primitives::utils::MaybeValidated;
-use near_primitives::validator_signer::ValidatorSigner;
+use near_primitives::validator_signer::{InMemoryValidatorSigner, ValidatorSigner};
+use near_store::StoreUpdate;
 
-use crate::adapter::ProcessTxResponse;
+use crate::adapter::{ProcessTxResponse, TxRejectionReason};
+use crate::block_skeleton::NextBlockSkeleton;
+use crate::chunk_arrival::ChunkArrivalStats;
 use crate::debug::BlockProductionTracker;
 use crate::debug::PRODUCTION_TIMES_CACHE_SIZE;
+use crate::deferred_txs::DeferredTransactions;
+use crate::finality_tracker::FinalityTracker;
+use crate::metrics::ClientMetrics;
+use crate::persisted_approvals;
 use crate::sync::{BlockSync, EpochSync, HeaderSync, StateSync, StateSyncResult};
-use crate::{metrics, SyncStatus};
-use near_client_primitives::types::{Error, ShardSyncDownload, ShardSyncStatus};
-use near_network::types::{AccountKeys, ChainInfo, PeerManagerMessageRequest, SetChainInfo};
+use crate::tx_status_subscriptions::TxStatusSubscriptions;
+use crate::SyncStatus;
+use near_client_primitives::types::{
+    BlockProductionFailure, BlockProductionReport, Error, ShardSyncDownload, ShardSyncStatus,
+    TxPoolCommand,
+};
+use near_network::types::{
+    AccountKeys, ChainInfo, MaintenanceWindow, PeerManagerMessageRequest, SetChainInfo,
+    ValidatorSigners,
+};
+use near_o11y::metrics::MetricsRegistry;
 use near_o11y::{log_assert, WithSpanContextExt};
 use near_primitives::block_header::ApprovalType;
 use near_primitives::epoch_manager::RngSeed;
 use near_primitives::network::PeerId;
 use near_primitives::version::PROTOCOL_VERSION;
-use near_primitives::views::{CatchupStatusView, DroppedReason};
+use near_primitives::views::{
+    CatchupStatusView, ChunkEndorsementStatusView, ChunkInclusionView, ChunkProducerInclusionView,
+    DrainStatusView, DroppedReason, FinalExecutionOutcomeView, ProductionScheduleEntryView,
+    ProductionScheduleView, TxForkStatusView, TxInclusionStatus, TxInclusionView,
+};
 
 const NUM_REBROADCAST_BLOCKS: usize = 30;
 const CHUNK_HEADERS_FOR_INCLUSION_CACHE_SIZE: usize = 2048;
 
-/// The time we wait for the response to a Epoch Sync request before retrying
-// TODO #3488 set 30_000
-pub const EPOCH_SYNC_REQUEST_TIMEOUT: Duration = Duration::from_millis(1_000);
-/// How frequently a Epoch Sync response can be sent to a particular peer
-// TODO #3488 set 60_000
-pub const EPOCH_SYNC_PEER_TIMEOUT: Duration = Duration::from_millis(10);
+/// The time we wait for the response to a Epoch Sync request before asking another peer
+pub const EPOCH_SYNC_REQUEST_TIMEOUT: Duration = Duration::from_millis(10_000);
 /// Drop blocks whose height are beyond head + horizon if it is not in the current epoch.
 const BLOCK_HORIZON: u64 = 500;
 
 /// number of blocks at the epoch start for which we will log more detailed info
 pub const EPOCH_START_INFO_BLOCKS: u64 = 500;
 
+/// Number of next block producers hinted to the peer manager as the most
+/// important validators.
+const NUM_PRIORITY_BLOCK_PRODUCERS: u64 = 10;
+
+/// Maximum number of heights returned by `Client::get_production_schedule`.
+pub const MAX_PRODUCTION_SCHEDULE_HEIGHTS: u64 = 1000;
+
+/// Number of recently produced blocks for which producers of chunks arriving
+/// too late are told so.
+const BLOCKS_WITHOUT_CHUNKS_CACHE_SIZE: usize = 32;
+/// Maximum number of missed inclusions kept for a produced chunk.
+const MAX_MISSED_INCLUSIONS_PER_CHUNK: usize = 10;
+
 pub struct Client {
     /// Adversarial controls
     #[cfg(feature = "test_features")]
@@ -95,12 +130,28 @@ pub struct Client {
     pub shards_mgr: ShardsManager,
     me: Option<AccountId>,
     pub sharded_tx_pool: ShardedTransactionPool,
-    prev_block_to_chunk_headers_ready_for_inclusion:
+    pub(crate) prev_block_to_chunk_headers_ready_for_inclusion:
         LruCache<CryptoHash, HashMap<ShardId, (ShardChunkHeader, chrono::DateTime<chrono::Utc>)>>,
     /// Network adapter.
     network_adapter: Arc<dyn PeerManagerAdapter>,
     /// Signer for block producer (if present).
     pub validator_signer: Option<Arc<dyn ValidatorSigner>>,
+    /// Other keys of the same validator, e.g. the key it rotates to.  Blocks,
+    /// chunks and approvals of an epoch are signed with whichever key is
+    /// staked in the epoch, so that the validator can rotate keys across an
+    /// epoch boundary without downtime.  See `set_other_validator_signers`.
+    other_validator_signers: Vec<Arc<dyn ValidatorSigner>>,
+    /// Whether the node is in its scheduled maintenance window.  The node then
+    /// doesn't produce blocks and chunks, and transactions submitted to it are
+    /// routed to validators instead of being kept in the local pool, which is
+    /// lost on shutdown.
+    pub in_maintenance: bool,
+    /// Maintenance window scheduled for the node, announced to the peers.
+    maintenance_window: Option<MaintenanceWindow>,
+    /// Drain entered with `begin_drain` before the node is stopped, if any.
+    drain: Option<Drain>,
+    /// Height of the last chunk produced by the node.
+    last_produced_chunk_height: Option<BlockHeight>,
     /// Approvals for which we do not have the block yet
     pub pending_approvals:
         lru::LruCache<ApprovalInner, HashMap<AccountId, (Approval, ApprovalType)>>,
@@ -122,6 +173,10 @@ pub struct Client {
     pub rs_for_chunk_production: ReedSolomonWrapper,
     /// Blocks that have been re-broadcast recently. They should not be broadcast again.
     rebroadcasted_blocks: lru::LruCache<CryptoHash, ()>,
+    /// Blocks announced by their header which couldn't be reconstructed and
+    /// were requested in full instead.  Those are processed as if they were
+    /// received unrequested.
+    announced_blocks: lru::LruCache<CryptoHash, ()>,
     /// Last time the head was updated, or our head was rebroadcasted. Used to re-broadcast the head
     /// again to prevent network from stalling if a large percentage of the network missed a block
     last_time_head_progress_made: Instant,
@@ -131,10 +186,56 @@ pub struct Client {
     pub block_production_info: BlockProductionTracker,
     /// Chunk production timing information. Used only for debug purposes.
     pub chunk_production_info: lru::LruCache<(BlockHeight, ShardId), ChunkProduction>,
+    /// Time to finality of recent blocks.  Used only for debug purposes.
+    pub(crate) finality_tracker: FinalityTracker,
+    /// Recent arrival delays of chunks, which determine how long block
+    /// production waits for missing ones.
+    chunk_arrival_stats: ChunkArrivalStats,
+    /// Blocks recently produced by this node without chunks of some shards,
+    /// by the hash of the previous block.
+    blocks_without_chunks: lru::LruCache<CryptoHash, BlockWithoutChunks>,
+    /// Parts of the next block precomputed when its previous block became the
+    /// head.  See `block_skeleton`.
+    pub(crate) next_block_skeleton: Option<NextBlockSkeleton>,
+    /// Whether the tracked shards were changed with `update_tracked_shards`
+    /// since the start, in which case `tracked_shards` of the config no longer
+    /// applies.
+    tracked_shards_updated: bool,
+    /// Subscriptions of peers to the final outcomes of transactions.
+    tx_status_subscriptions: TxStatusSubscriptions,
+    /// Transactions held until the block they're anchored to is final.
+    deferred_txs: DeferredTransactions,
+    /// Approvals passed to doomslug which weren't saved yet.  See
+    /// `persisted_approvals`.
+    unsaved_approvals: Vec<Approval>,
+    /// Orphans saved before the restart whose previous block was processed in
+    /// the meantime.  See `process_restored_orphans`.
+    restored_orphans: Vec<Block>,
 
     /// Cached precomputed set of TIER1 accounts.
     /// See send_network_chain_info().
     tier1_accounts_cache: Option<(EpochId, Arc<AccountKeys>)>,
+    pub(crate) metrics: Arc<ClientMetrics>,
+}
+
+/// Block produced by this node without chunks of some shards, whose
+/// producers are told if the chunks arrive later.
+struct BlockWithoutChunks {
+    height: BlockHeight,
+    /// Milliseconds between the timestamps of the previous block and this one.
+    deadline_millis: u64,
+    missing_shards: Vec<ShardId>,
+}
+
+/// Drain of a node which is about to be stopped, see `Client::begin_drain`.
+struct Drain {
+    started_at_height: BlockHeight,
+    /// Height of the last chunk the node produced before the drain started.
+    /// No chunks are produced while draining.
+    last_chunk_height: Option<BlockHeight>,
+    /// Whether nothing was in flight at the last check.  The store is flushed
+    /// whenever this becomes true.
+    safe_to_stop: bool,
 }
 
 // Debug information about the upcoming block.
@@ -177,14 +278,29 @@ impl Client {
         } else {
             DoomslugThresholdMode::NoApprovals
         };
-        let chain = Chain::new(
+        let metrics_registry = MetricsRegistry::for_instance(config.metrics_instance.as_deref());
+        let mut chain = Chain::new(
             runtime_adapter.clone(),
             &chain_genesis,
             doomslug_threshold_mode,
             !config.archive,
+            &metrics_registry,
         )?;
+        if config.contract_events_index {
+            chain.enable_contract_events_index();
+        }
+        if config.contract_code_index {
+            chain.enable_contract_code_index();
+        }
+        let restored_orphans =
+            if config.persist_orphans { chain.enable_orphan_persistence()? } else { vec![] };
+        if let Some(dir) = &config.state_mismatch_bundles_dir {
+            chain.enable_state_mismatch_bundles(dir.clone(), config.version.clone());
+        }
+        chain.set_block_stage_budgets(config.block_stage_budgets.clone());
+        chain.set_max_postprocessed_blocks_per_step(config.max_postprocessed_blocks_per_step);
         let me = validator_signer.as_ref().map(|x| x.validator_id().clone());
-        let shards_mgr = ShardsManager::new(
+        let mut shards_mgr = ShardsManager::new(
             me.clone(),
             runtime_adapter.clone(),
             network_adapter.clone(),
@@ -192,23 +308,31 @@ impl Client {
             chain.store().new_read_only_chunks_store(),
             chain.head().ok(),
         );
-        let sharded_tx_pool = ShardedTransactionPool::new(rng_seed);
+        shards_mgr.set_completed_before_restart(chain.recently_processed().chunk_hashes().clone());
+        shards_mgr.set_chunk_request_hedge_delay(config.chunk_request_hedge_delay);
+        match near_chunks::persisted_requests::load(chain.store().store()) {
+            Ok(requests) => shards_mgr.restore_chunk_requests(requests),
+            Err(err) => warn!(target: "client", ?err, "Failed to load saved chunk requests"),
+        }
+        let mut sharded_tx_pool = ShardedTransactionPool::new(rng_seed);
+        sharded_tx_pool.set_priority_signers(config.tx_priority_signers.clone());
+        sharded_tx_pool.set_max_transactions_per_signer(config.tx_pool_max_transactions_per_signer);
         let sync_status = SyncStatus::AwaitingPeers;
         let genesis_block = chain.genesis_block();
         let epoch_sync = EpochSync::new(
             network_adapter.clone(),
+            *genesis_block.hash(),
             genesis_block.header().epoch_id().clone(),
             genesis_block.header().next_epoch_id().clone(),
             runtime_adapter
                 .get_epoch_block_producers_ordered(
-                    genesis_block.header().epoch_id(),
+                    genesis_block.header().next_epoch_id(),
                     genesis_block.hash(),
                 )?
                 .iter()
                 .map(|x| x.0.clone())
                 .collect(),
             EPOCH_SYNC_REQUEST_TIMEOUT,
-            EPOCH_SYNC_PEER_TIMEOUT,
         );
         let header_sync = HeaderSync::new(
             network_adapter.clone(),
@@ -216,16 +340,24 @@ impl Client {
             config.header_sync_progress_timeout,
             config.header_sync_stall_ban_timeout,
             config.header_sync_expected_height_per_second,
+            config.header_sync_adaptive,
         );
         let block_sync =
             BlockSync::new(network_adapter.clone(), config.block_fetch_horizon, config.archive);
-        let state_sync = StateSync::new(network_adapter.clone(), config.state_sync_timeout);
+        let state_sync = StateSync::new(
+            network_adapter.clone(),
+            config.state_sync_timeout,
+            config.state_sync_sub_parts,
+            config.state_sync_source.clone(),
+        );
         let num_block_producer_seats = config.num_block_producer_seats as usize;
+        let finality_tracker = FinalityTracker::new(config.finality_sla_windows.clone());
         let data_parts = runtime_adapter.num_data_parts();
         let parity_parts = runtime_adapter.num_total_parts() - data_parts;
 
         let doomslug = Doomslug::new(
             chain.store().largest_target_height()?,
+            chain.store().largest_endorsed_height()?,
             config.min_block_production_delay,
             config.max_block_production_delay,
             config.max_block_production_delay / 10,
@@ -233,7 +365,7 @@ impl Client {
             validator_signer.clone(),
             doomslug_threshold_mode,
         );
-        Ok(Self {
+        let mut client = Self {
             #[cfg(feature = "test_features")]
             adv_produce_blocks: false,
             #[cfg(feature = "test_features")]
@@ -255,6 +387,11 @@ impl Client {
             ),
             network_adapter,
             validator_signer,
+            other_validator_signers: vec![],
+            in_maintenance: false,
+            maintenance_window: None,
+            drain: None,
+            last_produced_chunk_height: None,
             pending_approvals: lru::LruCache::new(num_block_producer_seats),
             catchup_state_syncs: HashMap::new(),
             epoch_sync,
@@ -264,11 +401,56 @@ impl Client {
             challenges: Default::default(),
             rs_for_chunk_production: ReedSolomonWrapper::new(data_parts, parity_parts),
             rebroadcasted_blocks: lru::LruCache::new(NUM_REBROADCAST_BLOCKS),
+            announced_blocks: lru::LruCache::new(NUM_REBROADCAST_BLOCKS),
             last_time_head_progress_made: Clock::instant(),
             block_production_info: BlockProductionTracker::new(),
             chunk_production_info: lru::LruCache::new(PRODUCTION_TIMES_CACHE_SIZE),
+            finality_tracker,
+            chunk_arrival_stats: ChunkArrivalStats::default(),
+            blocks_without_chunks: lru::LruCache::new(BLOCKS_WITHOUT_CHUNKS_CACHE_SIZE),
+            next_block_skeleton: None,
+            tracked_shards_updated: false,
+            tx_status_subscriptions: TxStatusSubscriptions::new(),
+            deferred_txs: DeferredTransactions::new(),
+            unsaved_approvals: vec![],
+            restored_orphans,
             tier1_accounts_cache: None,
-        })
+            metrics: metrics_registry.get(),
+        };
+        if let Some(path) = client.config.next_validator_key_file.clone() {
+            let signer = InMemoryValidatorSigner::from_file(&path).map_err(|err| {
+                Error::Other(format!(
+                    "Failed to read the next validator key from {}: {}",
+                    path.display(),
+                    err
+                ))
+            })?;
+            client.set_other_validator_signers(vec![Arc::new(signer)])?;
+        }
+        if let Err(err) = client.restore_approvals() {
+            warn!(target: "client", ?err, "Failed to restore saved approvals");
+        }
+        Ok(client)
+    }
+
+    /// Passes the approvals saved before a restart to doomslug again, so that
+    /// a restarted block producer doesn't miss its heights.
+    fn restore_approvals(&mut self) -> Result<(), Error> {
+        if self.validator_signer.is_none() {
+            return Ok(());
+        }
+        let head = self.chain.head()?;
+        let approvals = persisted_approvals::load(self.chain.store().store(), head.height)?;
+        if approvals.is_empty() {
+            return Ok(());
+        }
+        // Doomslug ignores approvals too far ahead of its tip.
+        self.check_and_update_doomslug_tip()?;
+        info!(target: "client", num_approvals = approvals.len(), "Restoring saved approvals");
+        for approval in &approvals {
+            self.collect_block_approval(approval, ApprovalType::SelfApproval);
+        }
+        Ok(())
     }
 
     // Checks if it's been at least `stall_timeout` since the last time the head was updated, or
@@ -287,6 +469,17 @@ impl Client {
         Ok(())
     }
 
+    /// Saves the outstanding chunk requests, if they changed, so that they're
+    /// re-issued after a restart.
+    pub fn save_chunk_requests(&mut self) -> Result<(), Error> {
+        if let Some(requests) = self.shards_mgr.chunk_requests_to_save() {
+            let mut store_update = self.chain.store().store().store_update();
+            near_chunks::persisted_requests::save(&mut store_update, requests)?;
+            store_update.commit()?;
+        }
+        Ok(())
+    }
+
     pub fn remove_transactions_for_block(&mut self, me: AccountId, block: &Block) {
         for (shard_id, chunk_header) in block.chunks().iter().enumerate() {
             let shard_id = shard_id as ShardId;
@@ -366,6 +559,88 @@ impl Client {
         account_id == next_block_proposer
     }
 
+    /// Checks the preconditions of block production at `next_height` on top of
+    /// the head, including the ones the client actor checks before calling
+    /// `produce_block`, without producing, signing or saving anything.  This
+    /// lets operators check the setup of a validator at any point of an epoch.
+    /// The only state updated is the tip of doomslug, which the client actor
+    /// keeps up to date with the head anyway.
+    pub fn simulate_block_production(
+        &mut self,
+        next_height: BlockHeight,
+    ) -> Result<BlockProductionReport, Error> {
+        self.check_and_update_doomslug_tip()?;
+        let head = self.chain.head()?;
+        let epoch_id = self.runtime_adapter.get_epoch_id_from_prev_block(&head.last_block_hash)?;
+        let block_producer = self.runtime_adapter.get_block_producer(&epoch_id, next_height)?;
+        let num_new_chunks = self.get_num_chunks_ready_for_inclusion(&head.last_block_hash);
+        let num_shards = self.runtime_adapter.num_shards(&epoch_id)?;
+        let mut failures = vec![];
+
+        let known_height = self.chain.store().get_latest_known()?.height;
+        if self.known_block_height(next_height, known_height) {
+            failures.push(BlockProductionFailure::HeightAlreadyKnown {
+                latest_known_height: known_height,
+            });
+        }
+        let signer = self.validator_signer.clone();
+        let is_block_producer = match &signer {
+            None => {
+                failures.push(BlockProductionFailure::NoValidatorSigner);
+                false
+            }
+            Some(signer) if !self.is_me_block_producer(signer.validator_id(), &block_producer) => {
+                failures.push(BlockProductionFailure::NotBlockProducer {
+                    block_producer: block_producer.clone(),
+                });
+                false
+            }
+            Some(_) => true,
+        };
+        if self.runtime_adapter.is_next_block_epoch_start(&head.last_block_hash)?
+            && !self.chain.prev_block_is_caught_up(&head.prev_block_hash, &head.last_block_hash)?
+        {
+            failures.push(BlockProductionFailure::PrevBlockNotCaughtUp);
+        }
+        if let (Some(signer), true) = (&signer, is_block_producer) {
+            let (validator_stake, _) = self.runtime_adapter.get_validator_by_account_id(
+                &epoch_id,
+                &head.last_block_hash,
+                &block_producer,
+            )?;
+            let expected = validator_stake.take_public_key();
+            if expected != signer.public_key() {
+                failures.push(BlockProductionFailure::ValidatorKeyMismatch {
+                    local: signer.public_key(),
+                    expected,
+                });
+            }
+        }
+        if !self.config.produce_empty_blocks && num_new_chunks == 0 {
+            failures.push(BlockProductionFailure::NoNewChunks);
+        }
+        let largest_height_crossing_threshold =
+            self.doomslug.get_largest_height_crossing_threshold();
+        if next_height > largest_height_crossing_threshold {
+            failures.push(BlockProductionFailure::NotEnoughApprovals {
+                largest_height_crossing_threshold,
+            });
+        }
+        let protocol_version = self.runtime_adapter.get_epoch_protocol_version(&epoch_id)?;
+        if protocol_version > PROTOCOL_VERSION {
+            failures.push(BlockProductionFailure::UnsupportedProtocolVersion { protocol_version });
+        }
+
+        Ok(BlockProductionReport {
+            next_height,
+            prev_hash: head.last_block_hash,
+            block_producer,
+            num_new_chunks,
+            num_shards,
+            failures,
+        })
+    }
+
     fn should_reschedule_block(
         &self,
         head: &Tip,
@@ -426,10 +701,62 @@ impl Client {
             .unwrap_or(0)
     }
 
+    /// Aggregates BLS signatures of the witness approvals and removes the aggregated approvals
+    /// from `approvals_map`.  Keys which are not registered yet are registered through the
+    /// aggregate.
+    #[cfg(feature = "protocol_feature_bls_approvals")]
+    fn aggregate_approvals(
+        &self,
+        prev_hash: &CryptoHash,
+        protocol_version: near_primitives::version::ProtocolVersion,
+        approvals_map: &mut HashMap<AccountId, (Approval, chrono::DateTime<chrono::Utc>)>,
+    ) -> Result<Option<near_primitives::block_header::ApprovalsAggregate>, Error> {
+        if !near_primitives::checked_feature!(
+            "protocol_feature_bls_approvals",
+            BlsApprovals,
+            protocol_version
+        ) {
+            return Ok(None);
+        }
+        let mut signers = vec![];
+        let mut signatures = vec![];
+        let mut registrations = vec![];
+        for (ApprovalStake { account_id, .. }, is_slashed) in
+            self.runtime_adapter.get_epoch_block_approvers_ordered(prev_hash)?
+        {
+            let bls_signature = match approvals_map.get(&account_id) {
+                Some((approval, _)) if !is_slashed => approval.bls_signature.as_ref(),
+                _ => None,
+            };
+            signers.push(bls_signature.is_some());
+            if let Some(bls_signature) = bls_signature {
+                signatures.push(bls_signature.signature);
+                let registration = &bls_signature.registration;
+                if self.runtime_adapter.get_bls_key(&account_id) != Some(registration.public_key) {
+                    registrations.push(registration.clone());
+                }
+            }
+        }
+        let signature = match near_crypto::bls::Signature::aggregate(&signatures) {
+            Some(signature) => signature,
+            None => return Ok(None),
+        };
+        approvals_map.retain(|_, (approval, _)| approval.bls_signature.is_none());
+        Ok(Some(near_primitives::block_header::ApprovalsAggregate {
+            signers,
+            signature,
+            registrations,
+        }))
+    }
+
     /// Produce block if we are block producer for given `next_height` block height.
     /// Either returns produced block (not applied) or error.
     pub fn produce_block(&mut self, next_height: BlockHeight) -> Result<Option<Block>, Error> {
         let _span = tracing::debug_span!(target: "client", "produce_block", next_height).entered();
+        if self.in_maintenance {
+            debug!(target: "client", next_height, "Not producing block: the node is in maintenance");
+            return Ok(None);
+        }
         let known_height = self.chain.store().get_latest_known()?.height;
 
         let validator_signer = self
@@ -478,15 +805,20 @@ impl Client {
         )?;
 
         let validator_pk = validator_stake.take_public_key();
-        if validator_pk != validator_signer.public_key() {
-            debug!(target: "client", "Local validator key {} does not match expected validator key {}, skipping block production", validator_signer.public_key(), validator_pk);
-            #[cfg(not(feature = "test_features"))]
-            return Ok(None);
-            #[cfg(feature = "test_features")]
-            if !self.adv_produce_blocks || self.adv_produce_blocks_only_valid {
-                return Ok(None);
+        let validator_signer = match self.validator_signer_with_key(&validator_pk) {
+            Some(signer) => signer,
+            None => {
+                debug!(target: "client", "Local validator key {} does not match expected validator key {}, skipping block production", validator_signer.public_key(), validator_pk);
+                #[cfg(not(feature = "test_features"))]
+                let produce_anyway = false;
+                #[cfg(feature = "test_features")]
+                let produce_anyway = self.adv_produce_blocks && !self.adv_produce_blocks_only_valid;
+                if !produce_anyway {
+                    return Ok(None);
+                }
+                validator_signer
             }
-        }
+        };
 
         let new_chunks = self.get_chunk_headers_ready_for_inclusion(&prev_hash);
         debug!(target: "client", "{:?} Producing block at height {}, parent {} @ {}, {} new chunks", validator_signer.validator_id(),
@@ -513,9 +845,19 @@ impl Client {
             panic!("The client protocol version is older than the protocol version of the network. Please update nearcore. Client protocol version:{}, network protocol version {}", PROTOCOL_VERSION, protocol_version);
         }
 
-        let approvals = self
-            .runtime_adapter
-            .get_epoch_block_approvers_ordered(&prev_hash)?
+        #[cfg(feature = "protocol_feature_bls_approvals")]
+        let approvals_aggregate =
+            self.aggregate_approvals(&prev_hash, protocol_version, &mut approvals_map)?;
+
+        let NextBlockSkeleton {
+            block_merkle_root,
+            block_ordinal,
+            prev_chunk_headers: mut chunks,
+            approvers,
+            ..
+        } = self.take_next_block_skeleton(&prev_hash)?;
+
+        let approvals = approvers
             .into_iter()
             .map(|(ApprovalStake { account_id, .. }, is_slashed)| {
                 if is_slashed {
@@ -556,16 +898,8 @@ impl Client {
         let timestamp_override = None;
 
         // Get block extra from previous block.
-        let block_merkle_tree = self.chain.store().get_block_merkle_tree(&prev_hash)?;
-        let mut block_merkle_tree = PartialMerkleTree::clone(&block_merkle_tree);
-        block_merkle_tree.insert(prev_hash);
-        let block_merkle_root = block_merkle_tree.root();
-        // The number of leaves in Block Merkle Tree is the amount of Blocks on the Canonical Chain by construction.
-        // The ordinal of the next Block will be equal to this amount plus one.
-        let block_ordinal: NumBlocks = block_merkle_tree.size() + 1;
         let prev_block_extra = self.chain.get_block_extra(&prev_hash)?;
         let prev_block = self.chain.get_block(&prev_hash)?;
-        let mut chunks = Chain::get_prev_chunk_headers(&*self.runtime_adapter, &prev_block)?;
 
         // Add debug information about the block production (and info on when did the chunks arrive).
         self.block_production_info.record_block_production(
@@ -579,6 +913,10 @@ impl Client {
             )?,
         );
 
+        let missing_shards: Vec<ShardId> = (0..chunks.len() as ShardId)
+            .filter(|shard_id| !new_chunks.contains_key(shard_id))
+            .collect();
+
         // Collect new chunks.
         for (shard_id, (mut chunk_header, _)) in new_chunks {
             *chunk_header.height_included_mut() = next_height;
@@ -638,6 +976,13 @@ impl Client {
             block_merkle_root,
             timestamp_override,
         );
+        #[cfg(feature = "protocol_feature_bls_approvals")]
+        let block = match approvals_aggregate {
+            Some(approvals_aggregate) => {
+                block.with_approvals_aggregate(approvals_aggregate, &*validator_signer)
+            }
+            None => block,
+        };
 
         // Update latest known even before returning block out, to prevent race conditions.
         self.chain.mut_store().save_latest_known(LatestKnown {
@@ -645,11 +990,71 @@ impl Client {
             seen: block.header().raw_timestamp(),
         })?;
 
-        metrics::BLOCK_PRODUCED_TOTAL.inc();
+        self.metrics.block_produced_total.inc();
+        if !missing_shards.is_empty() {
+            let deadline = block.header().timestamp() - prev.timestamp();
+            self.blocks_without_chunks.put(
+                prev_hash,
+                BlockWithoutChunks {
+                    height: next_height,
+                    deadline_millis: deadline.num_milliseconds().max(0) as u64,
+                    missing_shards,
+                },
+            );
+        }
 
         Ok(Some(block))
     }
 
+    /// Returns the precomputed parts of the block following `prev_hash`,
+    /// computing them if they weren't precomputed for that block.
+    fn take_next_block_skeleton(
+        &mut self,
+        prev_hash: &CryptoHash,
+    ) -> Result<NextBlockSkeleton, Error> {
+        match self.next_block_skeleton.take() {
+            Some(skeleton) if &skeleton.prev_hash == prev_hash => {
+                self.metrics.block_skeleton_hits.inc();
+                Ok(skeleton)
+            }
+            _ => {
+                self.metrics.block_skeleton_misses.inc();
+                Ok(NextBlockSkeleton::compute(&self.chain, &*self.runtime_adapter, prev_hash)?)
+            }
+        }
+    }
+
+    /// Precomputes the parts of the next block which only depend on the new
+    /// head, if this node produces the block at the next height.
+    fn precompute_next_block_skeleton(&mut self, head: &BlockHeader) {
+        self.next_block_skeleton = None;
+        let validator_signer = match &self.validator_signer {
+            Some(validator_signer) => validator_signer,
+            None => return,
+        };
+        let is_next_block_producer = self
+            .runtime_adapter
+            .get_epoch_id_from_prev_block(head.hash())
+            .and_then(|epoch_id| {
+                self.runtime_adapter.get_block_producer(&epoch_id, head.height() + 1)
+            })
+            .map_or(false, |producer| &producer == validator_signer.validator_id());
+        if !is_next_block_producer {
+            return;
+        }
+        let _span = tracing::debug_span!(
+            target: "client",
+            "precompute_next_block_skeleton",
+            prev_hash = ?head.hash())
+        .entered();
+        match NextBlockSkeleton::compute(&self.chain, &*self.runtime_adapter, head.hash()) {
+            Ok(skeleton) => self.next_block_skeleton = Some(skeleton),
+            Err(err) => {
+                debug!(target: "client", ?err, "Failed to precompute the next block skeleton")
+            }
+        }
+    }
+
     pub fn produce_chunk(
         &mut self,
         prev_block_hash: CryptoHash,
@@ -657,11 +1062,23 @@ impl Client {
         last_header: ShardChunkHeader,
         next_height: BlockHeight,
         shard_id: ShardId,
+        deadline: Option<Instant>,
     ) -> Result<Option<(EncodedShardChunk, Vec<MerklePath>, Vec<Receipt>)>, Error> {
         let timer = Instant::now();
-        let _timer =
-            metrics::PRODUCE_CHUNK_TIME.with_label_values(&[&shard_id.to_string()]).start_timer();
+        let _timer = self
+            .metrics
+            .produce_chunk_time
+            .with_label_values(&[&shard_id.to_string()])
+            .start_timer();
         let _span = tracing::debug_span!(target: "client", "produce_chunk", next_height, shard_id, ?epoch_id).entered();
+        if self.in_maintenance {
+            debug!(target: "client", next_height, shard_id, "Not producing chunk: the node is in maintenance");
+            return Ok(None);
+        }
+        if self.drain.is_some() {
+            debug!(target: "client", next_height, shard_id, "Not producing chunk: the node is draining");
+            return Ok(None);
+        }
         let validator_signer = self
             .validator_signer
             .as_ref()
@@ -674,6 +1091,8 @@ impl Client {
             debug!(target: "client", "Not producing chunk for shard {}: chain at {}, not block producer for next block. Me: {}, proposer: {}", shard_id, next_height, validator_signer.validator_id(), chunk_proposer);
             return Ok(None);
         }
+        let validator_signer =
+            self.validator_signer_for_epoch(epoch_id, &prev_block_hash).unwrap_or(validator_signer);
 
         if self.runtime_adapter.is_next_block_epoch_start(&prev_block_hash)? {
             let prev_prev_hash = *self.chain.get_block_header(&prev_block_hash)?.prev_hash();
@@ -702,8 +1121,29 @@ impl Client {
             .map_err(|err| Error::ChunkProducer(format!("No chunk extra available: {}", err)))?;
 
         let prev_block_header = self.chain.get_block_header(&prev_block_hash)?;
-        let transactions = self.prepare_transactions(shard_id, &chunk_extra, &prev_block_header)?;
+        let transactions =
+            self.prepare_transactions(shard_id, &chunk_extra, &prev_block_header, deadline)?;
+        if deadline.map_or(false, |deadline| Clock::instant() >= deadline) {
+            self.metrics
+                .produce_chunk_deadline_reached_total
+                .with_label_values(&[&shard_id.to_string()])
+                .inc();
+        }
         let num_filtered_transactions = transactions.len();
+        self.metrics
+            .produce_chunk_num_transactions
+            .with_label_values(&[&shard_id.to_string()])
+            .observe(num_filtered_transactions as f64);
+        if self
+            .config
+            .max_transactions_per_chunk
+            .map_or(false, |max| num_filtered_transactions as u64 >= max)
+        {
+            self.metrics
+                .produce_chunk_transactions_limit_reached_total
+                .with_label_values(&[&shard_id.to_string()])
+                .inc();
+        }
         let (tx_root, _) = merklize(&transactions);
         let outgoing_receipts = self.chain.get_outgoing_receipts_for_shard(
             prev_block_hash,
@@ -760,25 +1200,42 @@ impl Client {
             outgoing_receipts.len(),
         );
 
-        metrics::CHUNK_PRODUCED_TOTAL.inc();
+        self.metrics.chunk_produced_total.inc();
+        self.last_produced_chunk_height = Some(next_height);
         self.chunk_production_info.put(
             (next_height, shard_id),
             ChunkProduction {
                 chunk_production_time: Some(Clock::utc()),
                 chunk_production_duration_millis: Some(timer.elapsed().as_millis() as u64),
+                num_transactions: Some(num_filtered_transactions as u64),
+                max_transactions: self.config.max_transactions_per_chunk,
+                missed_inclusions: vec![],
             },
         );
         Ok(Some((encoded_chunk, merkle_paths, outgoing_receipts)))
     }
 
-    /// Prepares an ordered list of valid transactions from the pool up the limits.
+    /// Deadline for preparing the transactions of a chunk produced now.  The
+    /// chunk has to reach the producer of the next block before that block is
+    /// produced, which can happen as early as `min_block_production_delay`
+    /// from now, so transactions are never prepared for longer than that.
+    /// Without `produce_chunk_add_transactions_time_limit` there's no deadline.
+    pub fn chunk_production_deadline(&self) -> Option<Instant> {
+        let time_limit = self.config.produce_chunk_add_transactions_time_limit?;
+        Some(Clock::instant() + time_limit.min(self.config.min_block_production_delay))
+    }
+
+    /// Prepares an ordered list of valid transactions from the pool up the limits,
+    /// including `max_transactions_per_chunk`.
+    /// Once the deadline passes, only the transactions prepared so far are returned.
     fn prepare_transactions(
         &mut self,
         shard_id: ShardId,
         chunk_extra: &ChunkExtra,
         prev_block_header: &BlockHeader,
+        deadline: Option<Instant>,
     ) -> Result<Vec<SignedTransaction>, Error> {
-        let Self { chain, sharded_tx_pool, runtime_adapter, .. } = self;
+        let Self { chain, sharded_tx_pool, runtime_adapter, config, .. } = self;
 
         let next_epoch_id =
             runtime_adapter.get_epoch_id_from_prev_block(prev_block_header.hash())?;
@@ -789,6 +1246,7 @@ impl Client {
             runtime_adapter.prepare_transactions(
                 prev_block_header.gas_price(),
                 chunk_extra.gas_limit(),
+                config.max_transactions_per_chunk,
                 &next_epoch_id,
                 shard_id,
                 *chunk_extra.state_root(),
@@ -808,6 +1266,7 @@ impl Client {
                         .is_ok()
                 },
                 protocol_version,
+                deadline,
             )?
         } else {
             vec![]
@@ -884,6 +1343,82 @@ impl Client {
         }
     }
 
+    /// Processes a block announced by its header.  The block is reconstructed
+    /// from the chunk headers of its previous block and of the chunks this
+    /// node has, and requested in full from the peer if that fails.
+    pub fn receive_block_header_announcement(
+        &mut self,
+        announcement: BlockHeaderAnnouncement,
+        peer_id: PeerId,
+        apply_chunks_done_callback: DoneApplyChunkCallback,
+    ) {
+        let hash = *announcement.header.hash();
+        if self.chain.recently_processed().contains_block(&hash)
+            || self.announced_blocks.contains(&hash)
+        {
+            return;
+        }
+        match self.chain.process_block_header(&announcement.header, &mut vec![]) {
+            Ok(()) => {}
+            Err(near_chain::Error::BlockKnown(_)) => return,
+            Err(e) if e.is_bad_data() => {
+                warn!(target: "client", %hash, "Receive bad block header announcement: {}", e);
+                self.ban_peer(peer_id, ReasonForBan::BadBlockHeader);
+                return;
+            }
+            // The block is verified in full once reconstructed or received.
+            Err(_) => {}
+        }
+        let block = self
+            .announced_block_chunks(&announcement)
+            .map(|chunks| announcement.into_block(chunks))
+            .filter(|block| block.check_validity().is_ok());
+        match block {
+            Some(block) => {
+                self.metrics
+                    .block_header_announcements_total
+                    .with_label_values(&["reconstructed"])
+                    .inc();
+                self.receive_block(block, peer_id, false, apply_chunks_done_callback);
+            }
+            None => {
+                debug!(target: "client", %hash, "Requesting announced block which couldn't be reconstructed");
+                self.metrics
+                    .block_header_announcements_total
+                    .with_label_values(&["requested"])
+                    .inc();
+                self.announced_blocks.put(hash, ());
+                self.request_block(hash, peer_id);
+            }
+        }
+    }
+
+    /// Returns the chunk headers of the announced block, None if some of them
+    /// aren't known.  Chunks which weren't included in the block are taken
+    /// from its previous block, and new chunks from the ones this node has
+    /// received.
+    fn announced_block_chunks(
+        &self,
+        announcement: &BlockHeaderAnnouncement,
+    ) -> Option<Vec<ShardChunkHeader>> {
+        let prev_block = self.chain.get_block(announcement.header.prev_hash()).ok()?;
+        announcement
+            .chunk_hashes
+            .iter()
+            .map(|chunk_hash| {
+                if let Some(chunk) =
+                    prev_block.chunks().iter().find(|chunk| &chunk.chunk_hash() == chunk_hash)
+                {
+                    return Some(chunk.clone());
+                }
+                let mut chunk =
+                    self.chain.store().get_partial_chunk(chunk_hash).ok()?.cloned_header();
+                *chunk.height_included_mut() = announcement.header.height();
+                Some(chunk)
+            })
+            .collect()
+    }
+
     /// Processes received block.
     /// This function first does some pre-check based on block height to avoid processing
     /// blocks multiple times.
@@ -896,7 +1431,20 @@ impl Client {
         was_requested: bool,
         apply_chunks_done_callback: DoneApplyChunkCallback,
     ) -> Result<(), near_chain::Error> {
+        // Announced blocks are only requested because they couldn't be reconstructed, so they
+        // are rebroadcast like the blocks pushed to this node.
+        let was_requested = was_requested && self.announced_blocks.pop(block.hash()).is_none();
         self.chain.blocks_delay_tracker.mark_block_received(&block, Clock::instant(), Clock::utc());
+        self.finality_tracker.mark_block_received(block.header(), Clock::utc());
+        // Peers commonly send blocks the node has just processed, in particular right after a
+        // restart.  Those don't need to be verified or rebroadcast again.
+        if self.chain.recently_processed().contains_block(block.hash()) {
+            debug!(target: "client", hash = %block.hash(), "Dropping a recently processed block.");
+            self.chain
+                .blocks_delay_tracker
+                .mark_block_dropped(block.hash(), DroppedReason::BlockProcessed);
+            return Ok(());
+        }
         // To protect ourselves from spamming, we do some pre-check on block height before we do any
         // real processing.
         if !self.check_block_height(&block, was_requested)? {
@@ -971,6 +1519,7 @@ impl Client {
         let res = res.and_then(|_| self.chain.validate_block(block));
         match res {
             Ok(_) => {
+                self.reward_peer(peer_id.clone());
                 let head = self.chain.head()?;
                 // do not broadcast blocks that are too far back.
                 if (head.height < block.header().height()
@@ -1059,6 +1608,22 @@ impl Client {
         result
     }
 
+    /// Starts processing the orphans restored by `Chain::enable_orphan_persistence`
+    /// whose previous block is already processed, which the orphan pool
+    /// wouldn't adopt anymore.
+    pub fn process_restored_orphans(&mut self, apply_chunks_done_callback: DoneApplyChunkCallback) {
+        for block in std::mem::take(&mut self.restored_orphans) {
+            let hash = *block.hash();
+            if let Err(err) = self.start_process_block(
+                block.into(),
+                Provenance::NONE,
+                apply_chunks_done_callback.clone(),
+            ) {
+                debug!(target: "client", ?hash, ?err, "Failed to process restored orphan");
+            }
+        }
+    }
+
     /// Check if there are any blocks that has finished applying chunks, run post processing on these
     /// blocks.
     pub fn postprocess_ready_blocks(
@@ -1125,7 +1690,7 @@ impl Client {
     fn rebroadcast_block(&mut self, block: &Block) {
         if self.rebroadcasted_blocks.get(block.hash()).is_none() {
             self.network_adapter.do_send(
-                PeerManagerMessageRequest::NetworkRequests(NetworkRequests::Block {
+                PeerManagerMessageRequest::NetworkRequests(NetworkRequests::BlockHeaderFirst {
                     block: block.clone(),
                 })
                 .with_span_context(),
@@ -1162,13 +1727,159 @@ impl Client {
     }
 
     pub fn on_chunk_header_ready_for_inclusion(&mut self, chunk_header: ShardChunkHeader) {
-        let prev_block_hash = chunk_header.prev_block_hash();
+        let prev_block_hash = chunk_header.prev_block_hash().clone();
+        let shard_id = chunk_header.shard_id();
+        let chunk_hash = chunk_header.chunk_hash();
+        let height_created = chunk_header.height_created();
+        let now = Clock::utc();
         self.prev_block_to_chunk_headers_ready_for_inclusion
             .get_or_insert(prev_block_hash.clone(), || HashMap::new());
-        self.prev_block_to_chunk_headers_ready_for_inclusion
-            .get_mut(prev_block_hash)
+        let is_new = self
+            .prev_block_to_chunk_headers_ready_for_inclusion
+            .get_mut(&prev_block_hash)
             .unwrap()
-            .insert(chunk_header.shard_id(), (chunk_header, chrono::Utc::now()));
+            .insert(shard_id, (chunk_header, now))
+            .is_none();
+        if is_new {
+            if let Ok(prev_header) = self.chain.get_block_header(&prev_block_hash) {
+                let delay = (now - prev_header.timestamp()).to_std().unwrap_or_default();
+                self.chunk_arrival_stats.record(shard_id, delay);
+                self.send_chunk_inclusion_feedback(
+                    chunk_hash,
+                    &prev_block_hash,
+                    shard_id,
+                    height_created,
+                    delay,
+                );
+            }
+        }
+    }
+
+    /// Tells the producer of a chunk which became ready for inclusion `delay`
+    /// after the previous block, if this node has already produced a block on
+    /// top of the previous block without it.
+    fn send_chunk_inclusion_feedback(
+        &mut self,
+        chunk_hash: ChunkHash,
+        prev_block_hash: &CryptoHash,
+        shard_id: ShardId,
+        height_created: BlockHeight,
+        delay: Duration,
+    ) {
+        let block = match self.blocks_without_chunks.get(prev_block_hash) {
+            Some(block) if block.missing_shards.contains(&shard_id) => block,
+            _ => return,
+        };
+        let block_producer = match &self.me {
+            Some(me) => me.clone(),
+            None => return,
+        };
+        let feedback = ChunkInclusionFeedbackMsg {
+            chunk_hash,
+            shard_id,
+            height_created,
+            prev_block_hash: *prev_block_hash,
+            block_height: block.height,
+            block_producer,
+            deadline_millis: block.deadline_millis,
+            arrival_millis: delay.as_millis() as u64,
+        };
+        let chunk_producer = match self
+            .runtime_adapter
+            .get_epoch_id_from_prev_block(prev_block_hash)
+            .and_then(|epoch_id| {
+                self.runtime_adapter.get_chunk_producer(&epoch_id, height_created, shard_id)
+            }) {
+            Ok(chunk_producer) => chunk_producer,
+            Err(err) => {
+                debug!(target: "client", ?err, "Failed to find the producer of a late chunk");
+                return;
+            }
+        };
+        if self.me.as_ref() == Some(&chunk_producer) {
+            self.on_chunk_inclusion_feedback(feedback);
+            return;
+        }
+        self.network_adapter.do_send(
+            PeerManagerMessageRequest::NetworkRequests(NetworkRequests::ChunkInclusionFeedback {
+                account_id: chunk_producer,
+                feedback,
+            })
+            .with_span_context(),
+        );
+    }
+
+    /// Records that a block producer produced its block without a chunk this
+    /// node produced, because the chunk reached it too late.
+    pub fn on_chunk_inclusion_feedback(&mut self, feedback: ChunkInclusionFeedbackMsg) {
+        // The network checks that the feedback comes from `block_producer`, which has to be the
+        // producer of the block.
+        let is_block_producer = self
+            .runtime_adapter
+            .get_epoch_id_from_prev_block(&feedback.prev_block_hash)
+            .and_then(|epoch_id| {
+                self.runtime_adapter.get_block_producer(&epoch_id, feedback.block_height)
+            })
+            .map_or(false, |block_producer| block_producer == feedback.block_producer);
+        if !is_block_producer {
+            debug!(
+                target: "client",
+                block_producer = ?feedback.block_producer,
+                block_height = feedback.block_height,
+                "Ignoring chunk inclusion feedback of an account which didn't produce the block");
+            return;
+        }
+        // Feedback about chunks this node didn't produce recently is ignored.
+        let production =
+            match self.chunk_production_info.get_mut(&(feedback.height_created, feedback.shard_id))
+            {
+                Some(production) => production,
+                None => return,
+            };
+        if production.missed_inclusions.len() >= MAX_MISSED_INCLUSIONS_PER_CHUNK
+            || production
+                .missed_inclusions
+                .iter()
+                .any(|missed| missed.block_height == feedback.block_height)
+        {
+            return;
+        }
+        debug!(
+            target: "client",
+            chunk_hash = ?feedback.chunk_hash,
+            block_height = feedback.block_height,
+            deadline_millis = feedback.deadline_millis,
+            arrival_millis = feedback.arrival_millis,
+            "Produced chunk reached the block producer too late");
+        production.missed_inclusions.push(MissedChunkInclusion {
+            block_height: feedback.block_height,
+            deadline_millis: feedback.deadline_millis,
+            arrival_millis: feedback.arrival_millis,
+        });
+        self.metrics.chunk_missed_inclusion_total.inc();
+    }
+
+    /// Returns how much longer production of a block on top of
+    /// `prev_block_hash` should wait for missing chunks which, judging by the
+    /// recent ones, will likely arrive soon.  See `chunk_arrival`.
+    pub fn wait_for_late_chunks(
+        &self,
+        prev_block_hash: &CryptoHash,
+        epoch_id: &EpochId,
+    ) -> Result<Option<Duration>, Error> {
+        let max_wait = std::cmp::min(
+            self.config.max_adaptive_chunk_wait,
+            self.config.max_block_production_delay,
+        );
+        if max_wait.is_zero() {
+            return Ok(None);
+        }
+        let prev_header = self.chain.get_block_header(prev_block_hash)?;
+        let elapsed = (Clock::utc() - prev_header.timestamp()).to_std().unwrap_or_default();
+        let ready = self.prev_block_to_chunk_headers_ready_for_inclusion.peek(prev_block_hash);
+        let missing = (0..self.runtime_adapter.num_shards(epoch_id)?)
+            .filter(|shard_id| ready.map_or(true, |ready| !ready.contains_key(shard_id)));
+        Ok(self.chunk_arrival_stats.wait_for_missing(missing, elapsed, max_wait))
     }
 
     pub fn sync_block_headers(
@@ -1184,6 +1895,9 @@ impl Client {
     /// Checks if the latest hash known to Doomslug matches the current head, and updates it if not.
     pub fn check_and_update_doomslug_tip(&mut self) -> Result<(), Error> {
         let tip = self.chain.head()?;
+        let store = self.chain.store().store().clone();
+        let mut store_update = store.store_update();
+        let mut needs_commit = !self.unsaved_approvals.is_empty();
 
         if tip.last_block_hash != self.doomslug.get_tip().0 {
             // We need to update the doomslug tip
@@ -1200,6 +1914,19 @@ impl Client {
                 tip.height,
                 last_final_height,
             );
+            self.update_doomslug_signer()?;
+
+            // Approvals for heights below the head can't be used anymore.
+            self.unsaved_approvals.retain(|approval| approval.target_height >= tip.height);
+            needs_commit |= persisted_approvals::prune(&store, &mut store_update, tip.height)? > 0;
+        }
+
+        // This is called before each block production attempt, so the
+        // approvals collected for a height are saved together before the block
+        // for it is produced.
+        if needs_commit {
+            self.save_approvals(&mut store_update)?;
+            store_update.commit()?;
         }
 
         Ok(())
@@ -1280,6 +2007,7 @@ impl Client {
         };
 
         let _ = self.check_and_update_doomslug_tip();
+        self.finality_tracker.on_block_accepted(block.header(), Clock::utc());
 
         // If we produced the block, then it should have already been broadcasted.
         // If received the block from another node then broadcast "header first" to minimize network traffic.
@@ -1317,22 +2045,46 @@ impl Client {
                     block_hash = ?block.hash(),
                     height = block.header().height())
                 .entered();
-                let _gc_timer = metrics::GC_TIME.start_timer();
+                let _gc_timer = self.metrics.gc_time.start_timer();
+                let gc_start = Clock::instant();
 
                 let result = if self.config.archive {
-                    self.chain.clear_archive_data(self.config.gc.gc_blocks_limit)
+                    self.chain
+                        .clear_archive_data(self.config.gc.gc_blocks_limit, self.config.cold_store)
+                        .map(|blocker| {
+                            if let Some(blocker) = blocker {
+                                debug!(target: "client", %blocker, "Archival GC is blocked");
+                            }
+                        })
                 } else {
                     let tries = self.runtime_adapter.get_tries();
                     self.chain.clear_data(tries, &self.config.gc)
                 };
                 log_assert!(result.is_ok(), "Can't clear old data, {:?}", result);
+                self.chain.record_block_stage(
+                    BlockStage::GarbageCollection,
+                    &block_hash,
+                    block.header().height(),
+                    gc_start.elapsed(),
+                );
             }
 
             // send_network_chain_info should be called whenever the chain head changes.
             // See send_network_chain_info() for more details.
+            let network_info_start = Clock::instant();
             if let Err(err) = self.send_network_chain_info() {
                 error!(target:"client","Failed to update network chain info: {err}");
             }
+            self.chain.record_block_stage(
+                BlockStage::NetworkInfoUpdate,
+                &block_hash,
+                block.header().height(),
+                network_info_start.elapsed(),
+            );
+
+            self.precompute_next_block_skeleton(block.header());
+            self.push_tx_statuses();
+            self.release_deferred_txs();
         }
 
         if let Some(validator_signer) = self.validator_signer.clone() {
@@ -1427,7 +2179,9 @@ impl Client {
                             prev_block_hash = ?*block.hash(),
                             ?shard_id)
                         .entered();
-                        let _timer = metrics::PRODUCE_AND_DISTRIBUTE_CHUNK_TIME
+                        let _timer = self
+                            .metrics
+                            .produce_and_distribute_chunk_time
                             .with_label_values(&[&shard_id.to_string()])
                             .start_timer();
                         match self.produce_chunk(
@@ -1437,6 +2191,7 @@ impl Client {
                                 .unwrap(),
                             block.header().height() + 1,
                             shard_id,
+                            self.chunk_production_deadline(),
                         ) {
                             Ok(Some((encoded_chunk, merkle_paths, receipts))) => {
                                 self.persist_and_distribute_encoded_chunk(
@@ -1545,7 +2300,10 @@ impl Client {
                     .get_validator_by_account_id(epoch_id, block_hash, account_id)
                 {
                     Ok((validator_stake, is_slashed)) => {
-                        !is_slashed && validator_stake.take_public_key() == signer.public_key()
+                        !is_slashed
+                            && self
+                                .validator_signer_with_key(&validator_stake.take_public_key())
+                                .is_some()
                     }
                     Err(_) => false,
                 }
@@ -1553,6 +2311,73 @@ impl Client {
         }
     }
 
+    /// Sets other keys of this validator, besides `validator_signer`, e.g.
+    /// the key the validator rotates to once it's staked.  All of them have
+    /// to belong to the account of `validator_signer`.
+    pub fn set_other_validator_signers(
+        &mut self,
+        signers: Vec<Arc<dyn ValidatorSigner>>,
+    ) -> Result<(), Error> {
+        let account_id = match &self.validator_signer {
+            Some(validator_signer) => validator_signer.validator_id(),
+            None if signers.is_empty() => return Ok(()),
+            None => return Err(Error::Other("Other validator keys without a validator".into())),
+        };
+        if let Some(signer) = signers.iter().find(|signer| signer.validator_id() != account_id) {
+            return Err(Error::Other(format!(
+                "Validator key of {} doesn't belong to {}",
+                signer.validator_id(),
+                account_id
+            )));
+        }
+        self.other_validator_signers = signers;
+        self.update_doomslug_signer()?;
+        Ok(())
+    }
+
+    /// Returns the signer holding `public_key` among the keys of this validator.
+    fn validator_signer_with_key(
+        &self,
+        public_key: &PublicKey,
+    ) -> Option<Arc<dyn ValidatorSigner>> {
+        self.validator_signer
+            .iter()
+            .chain(self.other_validator_signers.iter())
+            .find(|signer| &signer.public_key() == public_key)
+            .cloned()
+    }
+
+    /// Returns the signer holding the key of this validator staked in
+    /// `epoch_id`, as of `block_hash`.  Returns None if this node isn't a
+    /// validator in the epoch or doesn't hold the staked key.
+    pub fn validator_signer_for_epoch(
+        &self,
+        epoch_id: &EpochId,
+        block_hash: &CryptoHash,
+    ) -> Option<Arc<dyn ValidatorSigner>> {
+        let account_id = self.validator_signer.as_ref()?.validator_id();
+        let (validator_stake, _) = self
+            .runtime_adapter
+            .get_validator_by_account_id(epoch_id, block_hash, account_id)
+            .ok()?;
+        self.validator_signer_with_key(&validator_stake.take_public_key())
+    }
+
+    /// Makes doomslug sign approvals on top of the head with the key staked
+    /// in the epoch of the next block.
+    fn update_doomslug_signer(&mut self) -> Result<(), Error> {
+        if self.other_validator_signers.is_empty() {
+            return Ok(());
+        }
+        let head = self.chain.head()?;
+        let epoch_id = self.runtime_adapter.get_epoch_id_from_prev_block(&head.last_block_hash)?;
+        let signer = self
+            .validator_signer_for_epoch(&epoch_id, &head.last_block_hash)
+            .or_else(|| self.validator_signer.clone());
+        self.doomslug.set_signer(signer);
+        Ok(())
+    }
+
     fn handle_process_approval_error(
         &mut self,
         approval: &Approval,
@@ -1605,7 +2430,7 @@ impl Client {
     /// * `approval_type`  - whether the approval was just produced by us (in which case skip validation,
     ///                      only check whether we are the next block producer and store in Doomslug)
     pub fn collect_block_approval(&mut self, approval: &Approval, approval_type: ApprovalType) {
-        let Approval { inner, account_id, target_height, signature } = approval;
+        let Approval { inner, account_id, target_height, signature, .. } = approval;
 
         let parent_hash = match inner {
             ApprovalInner::Endorsement(parent_hash) => *parent_hash,
@@ -1662,6 +2487,26 @@ impl Client {
                 Ok(true) => {}
                 _ => return,
             }
+            #[cfg(feature = "protocol_feature_bls_approvals")]
+            if let Some(bls_signature) = &approval.bls_signature {
+                let registration = &bls_signature.registration;
+                let is_valid = registration.account_id == *account_id
+                    && matches!(
+                        self.runtime_adapter.verify_bls_key_registration(
+                            &validator_epoch_id,
+                            &parent_hash,
+                            registration,
+                        ),
+                        Ok(true)
+                    )
+                    && registration.public_key.verify(
+                        &Approval::get_data_for_sig(inner, *target_height),
+                        &bls_signature.signature,
+                    );
+                if !is_valid {
+                    return;
+                }
+            }
         }
 
         let is_block_producer =
@@ -1697,13 +2542,287 @@ impl Client {
                     return;
                 }
             };
-        self.doomslug.on_approval_message(Clock::instant(), approval, &block_producer_stakes);
+        // Only the approvals doomslug keeps are saved, the others would never
+        // be pruned.  They are written in batches by `save_approvals`.
+        if self.doomslug.on_approval_message(Clock::instant(), approval, &block_producer_stakes) {
+            self.unsaved_approvals.push(approval.clone());
+        }
+    }
+
+    /// Saves the approvals collected since the last call in a single write.
+    fn save_approvals(&mut self, store_update: &mut StoreUpdate) -> io::Result<()> {
+        for approval in self.unsaved_approvals.drain(..) {
+            persisted_approvals::save(store_update, &approval)?;
+        }
+        Ok(())
+    }
+
+    /// Subscribes a peer to the final outcome of a transaction, see
+    /// `tx_status_subscriptions`.  Returns the outcome if it's already known.
+    /// Subscriptions to transactions of shards the node doesn't track, and of
+    /// peers which already have too many subscriptions, are ignored.
+    pub fn subscribe_tx_status(
+        &mut self,
+        signer_account_id: &AccountId,
+        tx_hash: CryptoHash,
+        route_back: CryptoHash,
+        subscriber: PeerId,
+    ) -> Result<Option<FinalExecutionOutcomeView>, Error> {
+        let head = self.chain.head()?;
+        let shard_id =
+            self.runtime_adapter.account_id_to_shard_id(signer_account_id, &head.epoch_id)?;
+        let me = self.validator_signer.as_ref().map(|signer| signer.validator_id());
+        if !self.runtime_adapter.cares_about_shard(me, &head.last_block_hash, shard_id, true) {
+            return Ok(None);
+        }
+        if let Some(outcome) = self.get_final_tx_outcome(&tx_hash)? {
+            return Ok(Some(outcome));
+        }
+        if !self.tx_status_subscriptions.subscribe(
+            tx_hash,
+            route_back,
+            subscriber.clone(),
+            Clock::instant(),
+        ) {
+            debug!(target: "client", ?tx_hash, ?subscriber, "Too many tx status subscriptions");
+        }
+        self.metrics.tx_status_subscriptions.set(self.tx_status_subscriptions.len() as i64);
+        Ok(None)
+    }
+
+    /// Returns the final outcome of a transaction, or `None` if some of its
+    /// receipts haven't been executed yet.
+    fn get_final_tx_outcome(
+        &self,
+        tx_hash: &CryptoHash,
+    ) -> Result<Option<FinalExecutionOutcomeView>, Error> {
+        match self.chain.get_final_transaction_result(tx_hash) {
+            Ok(mut outcome) => {
+                outcome
+                    .annotate_finality(|block_hash| self.chain.get_block_finality(block_hash))?;
+                Ok(Some(outcome))
+            }
+            Err(near_chain::Error::DBNotFoundErr(_)) => Ok(None),
+            Err(err) => Err(err.into()),
+        }
+    }
+
+    /// Pushes the final outcomes of the transactions subscribed to which have
+    /// become known to the subscribers.  Only a bounded number of the pending
+    /// subscriptions is checked after each block, see
+    /// `TxStatusSubscriptions::pending`.
+    fn push_tx_statuses(&mut self) {
+        for tx_hash in self.tx_status_subscriptions.pending(Clock::instant()) {
+            let outcome = match self.get_final_tx_outcome(&tx_hash) {
+                Ok(Some(outcome)) => outcome,
+                Ok(None) => continue,
+                Err(err) => {
+                    debug!(target: "client", ?tx_hash, ?err, "Failed to get transaction outcome");
+                    continue;
+                }
+            };
+            for route_back in self.tx_status_subscriptions.fulfil(&tx_hash) {
+                self.network_adapter.do_send(
+                    PeerManagerMessageRequest::NetworkRequests(NetworkRequests::TxStatusPush {
+                        route_back,
+                        response: Box::new(outcome.clone()),
+                    })
+                    .with_span_context(),
+                );
+                self.metrics.tx_status_pushes_total.inc();
+            }
+        }
+        self.metrics.tx_status_subscriptions.set(self.tx_status_subscriptions.len() as i64);
+    }
+
+    /// Holds the transaction until the block it's anchored to is final and
+    /// processes it then, see `deferred_txs`.  The transaction is processed
+    /// right away if the block is final already.
+    pub fn defer_tx(&mut self, tx: SignedTransaction) -> ProcessTxResponse {
+        let tx_hash = tx.get_hash();
+        match self.defer_tx_internal(tx) {
+            Ok(response) => response,
+            Err(err) => {
+                warn!(target: "client", ?err, ?tx_hash, "Dropping deferred tx");
+                ProcessTxResponse::Rejected(TxRejectionReason::InternalError {
+                    error_message: err.to_string(),
+                })
+            }
+        }
+    }
+
+    fn defer_tx_internal(&mut self, tx: SignedTransaction) -> Result<ProcessTxResponse, Error> {
+        if self.drain.is_some() {
+            return Ok(ProcessTxResponse::Rejected(TxRejectionReason::Draining));
+        }
+        // The transaction is validated like one submitted right away, against
+        // the state as of the head, without being added to the pool.  This
+        // rejects transactions anchored to blocks the node doesn't know, which
+        // are on another fork or too old, as they would never become valid.
+        // A transaction of a shard the node doesn't track only gets the basic
+        // validation, as it would when submitted right away.
+        match self.process_tx_internal(&tx, false, true)? {
+            ProcessTxResponse::ValidTx | ProcessTxResponse::DoesNotTrackShard => {}
+            response => return Ok(response),
+        }
+        let anchor_height = self.chain.get_block_header(&tx.transaction.block_hash)?.height();
+        if anchor_height <= self.chain.final_head()?.height {
+            return Ok(self.process_tx(tx, false, false));
+        }
+        let tx_hash = tx.get_hash();
+        if let Err(reason) = self.deferred_txs.insert(tx, Clock::instant()) {
+            return Ok(ProcessTxResponse::Rejected(reason));
+        }
+        debug!(target: "client", ?tx_hash, anchor_height, "Deferred a transaction until its anchor block is final");
+        self.metrics.deferred_transactions.set(self.deferred_txs.len() as i64);
+        Ok(ProcessTxResponse::ValidTx)
+    }
+
+    /// Processes the deferred transactions whose anchor block became final and
+    /// drops the ones whose anchor block was orphaned.
+    fn release_deferred_txs(&mut self) {
+        let final_height = match self.chain.final_head() {
+            Ok(final_head) => final_head.height,
+            Err(err) => {
+                debug!(target: "client", ?err, "Failed to get the final head");
+                return;
+            }
+        };
+        let held = self.deferred_txs.len();
+        let anchors = self.deferred_txs.pending(Clock::instant());
+        self.metrics
+            .deferred_transactions_dropped_total
+            .with_label_values(&["timeout"])
+            .inc_by((held - self.deferred_txs.len()) as u64);
+        for anchor in anchors {
+            let anchor_height = match self.chain.get_block_header(&anchor) {
+                Ok(header) => header.height(),
+                Err(_) => continue,
+            };
+            if anchor_height > final_height {
+                continue;
+            }
+            let txs = self.deferred_txs.take(&anchor);
+            let is_canonical = self
+                .chain
+                .get_block_hash_by_height(anchor_height)
+                .map_or(false, |hash| hash == anchor);
+            if !is_canonical {
+                debug!(target: "client", ?anchor, num_txs = txs.len(), "Anchor block of deferred transactions was orphaned, dropping them");
+                self.metrics
+                    .deferred_transactions_dropped_total
+                    .with_label_values(&["orphaned"])
+                    .inc_by(txs.len() as u64);
+                continue;
+            }
+            for tx in txs {
+                let tx_hash = tx.get_hash();
+                let response = self.process_tx(tx, false, false);
+                debug!(target: "client", ?tx_hash, ?response, "Released a deferred transaction");
+            }
+        }
+        self.metrics.deferred_transactions.set(self.deferred_txs.len() as i64);
+    }
+
+    /// Makes the node track the shards with the given ids, in addition to the
+    /// shards it tracks as a validator, instead of the shards or accounts it
+    /// was configured with, without a restart.
+    ///
+    /// The shards tracked in the current and the next epoch don't change, the
+    /// new shards are tracked from the epoch after.  Their state is caught up
+    /// before that epoch starts, like the state of shards a validator is
+    /// assigned to.  `ShardsManager` asks the runtime adapter which shards are
+    /// tracked, so it requests the chunks of the new shards from then on too.
+    pub fn update_tracked_shards(&mut self, shard_ids: Vec<ShardId>) -> Result<(), Error> {
+        let head = self.chain.head()?;
+        let num_shards = self.runtime_adapter.num_shards(&head.epoch_id)?;
+        if let Some(shard_id) = shard_ids.iter().find(|shard_id| **shard_id >= num_shards) {
+            return Err(Error::Other(format!(
+                "Invalid shard id {}, there are {} shards",
+                shard_id, num_shards
+            )));
+        }
+        info!(target: "client", ?shard_ids, "Updating tracked shards");
+        self.runtime_adapter.update_tracked_shards(shard_ids)?;
+        self.tracked_shards_updated = true;
+        self.send_network_chain_info()
+    }
+
+    /// Executes an operator command on the transaction pool.  Returns hashes of
+    /// the affected transactions.
+    pub fn handle_tx_pool_command(&mut self, command: TxPoolCommand) -> Vec<CryptoHash> {
+        info!(target: "client", ?command, "Transaction pool command");
+        match command {
+            TxPoolCommand::DropTransactions(hashes) => {
+                let hashes: HashSet<_> = hashes.into_iter().collect();
+                self.sharded_tx_pool.remove_transactions_if(|tx| hashes.contains(&tx.get_hash()))
+            }
+            TxPoolCommand::DropSignerTransactions(signer_id) => self
+                .sharded_tx_pool
+                .remove_transactions_if(|tx| tx.transaction.signer_id == signer_id),
+            TxPoolCommand::Pin(tx_hash) => {
+                if self.sharded_tx_pool.pin_transaction(&tx_hash) {
+                    vec![tx_hash]
+                } else {
+                    vec![]
+                }
+            }
+            TxPoolCommand::Unpin(tx_hash) => {
+                if self.sharded_tx_pool.unpin_transaction(&tx_hash) {
+                    vec![tx_hash]
+                } else {
+                    vec![]
+                }
+            }
+        }
+    }
+
+    /// Returns the status of the transaction across all forks known to this
+    /// node: every block that executed it, whether that block is still
+    /// canonical, and whether the transaction is back in the pool after a
+    /// reorg.
+    pub fn tx_fork_status(
+        &self,
+        tx_hash: &CryptoHash,
+        signer_account_id: &AccountId,
+    ) -> Result<TxForkStatusView, near_chain::Error> {
+        let mut inclusions = vec![];
+        for outcome in self.chain.store().get_outcomes_by_id(tx_hash)? {
+            let header = match self.chain.get_block_header(&outcome.block_hash) {
+                Ok(header) => header,
+                // The block may have been garbage collected.
+                Err(_) => continue,
+            };
+            let canonical = self.chain.is_on_current_chain(&header).unwrap_or(false);
+            inclusions.push(TxInclusionView {
+                block_hash: *header.hash(),
+                block_height: header.height(),
+                canonical,
+            });
+        }
+        inclusions.sort_by_key(|inclusion| (inclusion.block_height, inclusion.block_hash));
+
+        let head = self.chain.head()?;
+        let shard_id =
+            self.runtime_adapter.account_id_to_shard_id(signer_account_id, &head.epoch_id)?;
+        let in_pool = self.sharded_tx_pool.contains_transaction(shard_id, tx_hash);
+
+        let status = if inclusions.iter().any(|inclusion| inclusion.canonical) {
+            TxInclusionStatus::Included
+        } else if in_pool {
+            TxInclusionStatus::Pending
+        } else if !inclusions.is_empty() {
+            TxInclusionStatus::Orphaned
+        } else {
+            TxInclusionStatus::Unknown
+        };
+        Ok(TxForkStatusView { tx_hash: *tx_hash, status, in_pool, inclusions })
     }
 
     /// Forwards given transaction to upcoming validators.
     fn forward_tx(&self, epoch_id: &EpochId, tx: &SignedTransaction) -> Result<(), Error> {
         let shard_id =
-            self.runtime_adapter.account_id_to_shard_id(&tx.transaction.signer_id, epoch_id)?;
+            self.runtime_adapter.account_id_to_shard_id(tx.routing_account_id(), epoch_id)?;
         let head = self.chain.head()?;
         let maybe_next_epoch_id = self.get_next_epoch_id_if_at_boundary(&head)?;
 
@@ -1717,7 +2836,7 @@ impl Client {
             if let Some(next_epoch_id) = &maybe_next_epoch_id {
                 let next_shard_id = self
                     .runtime_adapter
-                    .account_id_to_shard_id(&tx.transaction.signer_id, next_epoch_id)?;
+                    .account_id_to_shard_id(tx.routing_account_id(), next_epoch_id)?;
                 let validator = self.chain.find_chunk_producer_for_forwarding(
                     next_epoch_id,
                     next_shard_id,
@@ -1758,13 +2877,100 @@ impl Client {
         is_forwarded: bool,
         check_only: bool,
     ) -> ProcessTxResponse {
-        unwrap_or_return!(self.process_tx_internal(&tx, is_forwarded, check_only), {
-            let me = self.validator_signer.as_ref().map(|vs| vs.validator_id());
-            warn!(target: "client", "I'm: {:?} Dropping tx: {:?}", me, tx);
-            ProcessTxResponse::NoResponse
+        match self.process_tx_internal(&tx, is_forwarded, check_only) {
+            Ok(response) => response,
+            Err(err) => {
+                let me = self.validator_signer.as_ref().map(|vs| vs.validator_id());
+                warn!(target: "client", ?err, "I'm: {:?} Dropping tx: {:?}", me, tx);
+                ProcessTxResponse::Rejected(TxRejectionReason::InternalError {
+                    error_message: err.to_string(),
+                })
+            }
+        }
+    }
+
+    /// Puts the node into drain mode ahead of stopping it.  The node keeps
+    /// producing the blocks it's responsible for, but stops producing chunks
+    /// and rejects new transactions.  `drain_status` reports when it's safe to
+    /// stop the node.
+    pub fn begin_drain(&mut self) -> Result<(), near_chain::Error> {
+        if self.drain.is_none() {
+            let head = self.chain.head()?;
+            info!(target: "client", height = head.height, "Draining the node before shutdown");
+            self.drain = Some(Drain {
+                started_at_height: head.height,
+                last_chunk_height: self.last_produced_chunk_height,
+                safe_to_stop: false,
+            });
+        }
+        self.check_drain()
+    }
+
+    /// Checks whether the draining node can be stopped: no blocks are being
+    /// applied and the head reached the height of the last chunk the node
+    /// produced before the drain started, so the chunk was distributed and
+    /// either included or skipped.  Flushes the store before reporting the
+    /// node safe to stop.
+    pub fn check_drain(&mut self) -> Result<(), near_chain::Error> {
+        let (was_safe_to_stop, last_chunk_height) = match &self.drain {
+            Some(drain) => (drain.safe_to_stop, drain.last_chunk_height),
+            None => return Ok(()),
+        };
+        let head = self.chain.head()?;
+        let chunks_distributed = last_chunk_height.map_or(true, |height| height <= head.height);
+        let safe_to_stop = self.chain.blocks_in_processing_len() == 0 && chunks_distributed;
+        if safe_to_stop && !was_safe_to_stop {
+            self.chain.store().store().flush()?;
+            info!(target: "client", height = head.height, "Drained, the node is safe to stop");
+        }
+        if let Some(drain) = &mut self.drain {
+            drain.safe_to_stop = safe_to_stop;
+        }
+        Ok(())
+    }
+
+    /// Returns the state of the drain, or None if the node isn't draining.
+    pub fn drain_status(&self) -> Option<DrainStatusView> {
+        self.drain.as_ref().map(|drain| DrainStatusView {
+            started_at_height: drain.started_at_height,
+            blocks_in_processing: self.chain.blocks_in_processing_len(),
+            last_produced_chunk_height: drain.last_chunk_height,
+            safe_to_stop: drain.safe_to_stop,
         })
     }
 
+    /// Applies the scheduled maintenance window: announces a changed window to
+    /// the peers and enters maintenance once the head reaches the start of the
+    /// window.  Returns whether the node should shut down now, i.e. the head
+    /// reached the shutdown height and no blocks are being applied, so that the
+    /// node restarts from a consistent head.
+    pub fn check_maintenance_window(
+        &mut self,
+        maintenance_window: Option<MaintenanceWindow>,
+    ) -> Result<bool, Error> {
+        if maintenance_window != self.maintenance_window {
+            info!(target: "client", ?maintenance_window, "Maintenance window changed");
+            self.maintenance_window = maintenance_window;
+            self.send_network_chain_info()?;
+        }
+        let head = self.chain.head()?;
+        let in_maintenance = maintenance_window.map_or(false, |w| w.is_active(head.height));
+        if in_maintenance != self.in_maintenance {
+            info!(target: "client", height = head.height, in_maintenance, "Maintenance state changed");
+            self.in_maintenance = in_maintenance;
+        }
+        let shutdown_height = match maintenance_window {
+            Some(window) if head.height >= window.shutdown_height => window.shutdown_height,
+            _ => return Ok(false),
+        };
+        let blocks_in_processing = self.chain.blocks_in_processing_len();
+        if blocks_in_processing > 0 {
+            debug!(target: "client", blocks_in_processing, shutdown_height, "Expected shutdown delayed until blocks are processed");
+            return Ok(false);
+        }
+        Ok(true)
+    }
+
     /// If we are close to epoch boundary, return next epoch id, otherwise return None.
     fn get_next_epoch_id_if_at_boundary(&self, head: &Tip) -> Result<Option<EpochId>, Error> {
         let next_epoch_started =
@@ -1797,6 +3003,49 @@ impl Client {
         Ok(())
     }
 
+    /// Checks the transaction against the configured admission limits.  These
+    /// checks only look at the already deserialized transaction, so they are
+    /// done before anything which needs the chain state.
+    fn check_tx_admission_limits(&self, tx: &SignedTransaction) -> Option<InvalidTxError> {
+        let limits = &self.config.tx_admission;
+        let (reason, err) = if tx.get_size() > limits.max_tx_size {
+            (
+                "tx_size",
+                InvalidTxError::TransactionSizeExceeded {
+                    size: tx.get_size(),
+                    limit: limits.max_tx_size,
+                },
+            )
+        } else if tx.transaction.actions.len() as u64 > limits.max_actions {
+            (
+                "num_actions",
+                InvalidTxError::ActionsValidation(
+                    ActionsValidationError::TotalNumberOfActionsExceeded {
+                        total_number_of_actions: tx.transaction.actions.len() as u64,
+                        limit: limits.max_actions,
+                    },
+                ),
+            )
+        } else {
+            let args_length = tx.transaction.actions.iter().find_map(|action| match action {
+                Action::FunctionCall(function_call) => Some(function_call.args.len() as u64)
+                    .filter(|length| *length > limits.max_args_size),
+                _ => None,
+            })?;
+            (
+                "args_size",
+                InvalidTxError::ActionsValidation(
+                    ActionsValidationError::FunctionCallArgumentsLengthExceeded {
+                        length: args_length,
+                        limit: limits.max_args_size,
+                    },
+                ),
+            )
+        };
+        self.metrics.transaction_admission_rejected_total.with_label_values(&[reason]).inc();
+        Some(err)
+    }
+
     /// Process transaction and either add it to the mempool or return to redirect to another validator.
     fn process_tx_internal(
         &mut self,
@@ -1804,6 +3053,14 @@ impl Client {
         is_forwarded: bool,
         check_only: bool,
     ) -> Result<ProcessTxResponse, Error> {
+        if self.drain.is_some() && !check_only {
+            debug!(target: "client", tx_hash = ?tx.get_hash(), "Node is draining, rejecting a transaction");
+            return Ok(ProcessTxResponse::Rejected(TxRejectionReason::Draining));
+        }
+        if let Some(err) = self.check_tx_admission_limits(tx) {
+            debug!(target: "client", "Invalid tx: exceeds admission limits -- {:?}", err);
+            return Ok(ProcessTxResponse::InvalidTx(err));
+        }
         let head = self.chain.head()?;
         let me = self.validator_signer.as_ref().map(|vs| vs.validator_id());
         let cur_block_header = self.chain.head_header()?;
@@ -1819,6 +3076,18 @@ impl Client {
             debug!(target: "client", "Invalid tx: expired or from a different fork -- {:?}", tx);
             return Ok(ProcessTxResponse::InvalidTx(e));
         }
+        // A relayed delegate action which expired would still be executed,
+        // at the relayer's expense, and fail.
+        if let Some(signed_delegate_action) = tx.delegate_action() {
+            let max_block_height = signed_delegate_action.delegate_action.max_block_height;
+            if max_block_height <= head.height {
+                debug!(target: "client", max_block_height, "Relayed delegate action expired");
+                return Ok(ProcessTxResponse::Rejected(TxRejectionReason::DelegateActionExpired {
+                    max_block_height,
+                    next_height: head.height + 1,
+                }));
+            }
+        }
         let gas_price = cur_block_header.gas_price();
         let epoch_id = self.runtime_adapter.get_epoch_id_from_prev_block(&head.last_block_hash)?;
 
@@ -1834,7 +3103,7 @@ impl Client {
         }
 
         let shard_id =
-            self.runtime_adapter.account_id_to_shard_id(&tx.transaction.signer_id, &epoch_id)?;
+            self.runtime_adapter.account_id_to_shard_id(tx.routing_account_id(), &epoch_id)?;
         if self.runtime_adapter.cares_about_shard(me, &head.last_block_hash, shard_id, true)
             || self.runtime_adapter.will_care_about_shard(me, &head.last_block_hash, shard_id, true)
         {
@@ -1845,7 +3114,14 @@ impl Client {
                     // Not being able to fetch a state root most likely implies that we haven't
                     //     caught up with the next epoch yet.
                     if is_forwarded {
-                        return Err(Error::Other("Node has not caught up yet".to_string()));
+                        debug!(
+                            target: "client",
+                            shard_id,
+                            "Received forwarded transaction but has not caught up yet"
+                        );
+                        return Ok(ProcessTxResponse::Rejected(
+                            TxRejectionReason::NodeNotCaughtUp { shard_id },
+                        ));
                     } else {
                         self.forward_tx(&epoch_id, tx)?;
                         return Ok(ProcessTxResponse::RequestRouted);
@@ -1861,6 +3137,10 @@ impl Client {
                 Ok(ProcessTxResponse::InvalidTx(err))
             } else if check_only {
                 Ok(ProcessTxResponse::ValidTx)
+            } else if self.in_maintenance && !is_forwarded {
+                trace!(target: "client", shard_id, "Node is in maintenance, forwarding a transaction.");
+                self.forward_tx(&epoch_id, tx)?;
+                Ok(ProcessTxResponse::RequestRouted)
             } else {
                 let active_validator = self.active_validator(shard_id)?;
 
@@ -1869,6 +3149,31 @@ impl Client {
                 // If I'm not an active validator I should forward tx to next validators.
                 self.sharded_tx_pool.insert_transaction(shard_id, tx.clone());
                 trace!(target: "client", shard_id, "Recorded a transaction.");
+                // Relayed transactions are signed by the relayer, so they count
+                // against its quota rather than the one of the delegate action's
+                // sender.
+                let evicted =
+                    self.sharded_tx_pool.enforce_signer_quota(shard_id, &tx.transaction.signer_id);
+                if evicted.contains(&tx.get_hash()) {
+                    let signer_id = tx.transaction.signer_id.clone();
+                    let limit = self.config.tx_pool_max_transactions_per_signer.unwrap_or(0);
+                    let reason = if tx.delegate_action().is_some() {
+                        TxRejectionReason::RelayerQuotaExceeded { relayer_id: signer_id, limit }
+                    } else {
+                        TxRejectionReason::SignerQuotaExceeded { signer_id, limit }
+                    };
+                    return Ok(ProcessTxResponse::Rejected(reason));
+                }
+                if !evicted.is_empty() {
+                    debug!(
+                        target: "client",
+                        shard_id,
+                        signer_id = %tx.transaction.signer_id,
+                        ?evicted,
+                        "Signer over the pool quota, evicted transactions"
+                    );
+                    self.metrics.transaction_pool_evicted_total.inc_by(evicted.len() as u64);
+                }
 
                 // Active validator:
                 //   possibly forward to next epoch validators
@@ -1877,7 +3182,7 @@ impl Client {
                 //   possibly forward to next epoch validators
                 if active_validator {
                     trace!(target: "client", account = ?me, shard_id, is_forwarded, "Recording a transaction.");
-                    metrics::TRANSACTION_RECEIVED_VALIDATOR.inc();
+                    self.metrics.transaction_received_validator.inc();
 
                     if !is_forwarded {
                         self.possibly_forward_tx_to_next_epoch(tx)?;
@@ -1885,13 +3190,13 @@ impl Client {
                     Ok(ProcessTxResponse::ValidTx)
                 } else if !is_forwarded {
                     trace!(target: "client", shard_id, "Forwarding a transaction.");
-                    metrics::TRANSACTION_RECEIVED_NON_VALIDATOR.inc();
+                    self.metrics.transaction_received_non_validator.inc();
                     self.forward_tx(&epoch_id, tx)?;
                     Ok(ProcessTxResponse::RequestRouted)
                 } else {
                     trace!(target: "client", shard_id, "Non-validator received a forwarded transaction, dropping it.");
-                    metrics::TRANSACTION_RECEIVED_NON_VALIDATOR_FORWARDED.inc();
-                    Ok(ProcessTxResponse::NoResponse)
+                    self.metrics.transaction_received_non_validator_forwarded.inc();
+                    Ok(ProcessTxResponse::Rejected(TxRejectionReason::NotValidator { shard_id }))
                 }
             }
         } else if check_only {
@@ -1900,7 +3205,9 @@ impl Client {
             if is_forwarded {
                 // received forwarded transaction but we are not tracking the shard
                 debug!(target: "client", "Received forwarded transaction but no tracking shard {}, I'm {:?}", shard_id, me);
-                return Ok(ProcessTxResponse::NoResponse);
+                return Ok(ProcessTxResponse::Rejected(TxRejectionReason::ShardNotTracked {
+                    shard_id,
+                }));
             }
             // We are not tracking this shard, so there is no way to validate this tx. Just rerouting.
 
@@ -1981,11 +3288,18 @@ impl Client {
                 }
             };
             let state_sync_timeout = self.config.state_sync_timeout;
+            let state_sync_sub_parts = self.config.state_sync_sub_parts;
+            let state_sync_source = self.config.state_sync_source.clone();
             let epoch_id = self.chain.get_block(&sync_hash)?.header().epoch_id().clone();
             let (state_sync, new_shard_sync, blocks_catch_up_state) =
                 self.catchup_state_syncs.entry(sync_hash).or_insert_with(|| {
                     (
-                        StateSync::new(network_adapter1, state_sync_timeout),
+                        StateSync::new(
+                            network_adapter1,
+                            state_sync_timeout,
+                            state_sync_sub_parts,
+                            state_sync_source,
+                        ),
                         new_shard_sync,
                         BlocksCatchUpState::new(sync_hash, epoch_id),
                     )
@@ -2021,7 +3335,7 @@ impl Client {
                         block_catch_up_task_scheduler,
                     )?;
 
-                    if blocks_catch_up_state.is_finished() {
+                    if self.chain.is_catchup_finished(blocks_catch_up_state) {
                         let mut block_processing_artifacts = BlockProcessingArtifact::default();
 
                         self.chain.finish_catchup_blocks(
@@ -2100,6 +3414,14 @@ impl Client {
             .with_span_context(),
         );
     }
+
+    /// Recovers the score of the peer, lowered by `ban_peer`.
+    pub fn reward_peer(&self, peer_id: PeerId) {
+        self.network_adapter.do_send(
+            PeerManagerMessageRequest::NetworkRequests(NetworkRequests::RewardPeer { peer_id })
+                .with_span_context(),
+        );
+    }
 }
 
 impl Client {
@@ -2117,7 +3439,10 @@ impl Client {
     /// if the current epoch didn't change since the last call. In particular SetChainInfo is being
     /// send after processing each block (order of seconds), while the epoch changes way less
     /// frequently (order of hours).
-    fn get_tier1_accounts(&mut self, tip: &Tip) -> Result<Arc<AccountKeys>, Error> {
+    ///
+    /// The accounts pinned with `ClientConfig::tier1_pinned_accounts` are added for both epochs,
+    /// unless they are staked in the epoch, in which case their staked keys are kept.
+    pub(crate) fn get_tier1_accounts(&mut self, tip: &Tip) -> Result<Arc<AccountKeys>, Error> {
         match &self.tier1_accounts_cache {
             Some(it) if it.0 == tip.epoch_id => return Ok(it.1.clone()),
             _ => {}
@@ -2160,12 +3485,92 @@ impl Client {
                         ((epoch_id.clone(), it.account_id().clone()), it.public_key().clone())
                     }),
             );
+            for (account_id, public_key) in &self.config.tier1_pinned_accounts {
+                accounts
+                    .entry((epoch_id.clone(), account_id.clone()))
+                    .or_insert_with(|| public_key.clone());
+            }
         }
         let accounts = Arc::new(accounts);
         self.tier1_accounts_cache = Some((tip.epoch_id.clone(), accounts.clone()));
         Ok(accounts)
     }
 
+    /// Returns the validators which matter the most to this node right now,
+    /// the most important first: the producers of the next
+    /// `NUM_PRIORITY_BLOCK_PRODUCERS` blocks, followed by the producers of the
+    /// next chunks of the shards this node tracks.  The heights past the end
+    /// of the epoch are assumed to be in the same epoch, which only makes the
+    /// hints less accurate for a few blocks.
+    pub(crate) fn get_priority_validators(&self, tip: &Tip) -> Result<Arc<Vec<AccountId>>, Error> {
+        let epoch_id = self.runtime_adapter.get_epoch_id_from_prev_block(&tip.last_block_hash)?;
+        let mut validators = vec![];
+        for height in tip.height + 1..=tip.height + NUM_PRIORITY_BLOCK_PRODUCERS {
+            let block_producer = self.runtime_adapter.get_block_producer(&epoch_id, height)?;
+            if !validators.contains(&block_producer) {
+                validators.push(block_producer);
+            }
+        }
+        let me = self.validator_signer.as_ref().map(|signer| signer.validator_id().clone());
+        for shard_id in 0..self.runtime_adapter.num_shards(&epoch_id)? {
+            if !self.runtime_adapter.cares_about_shard(
+                me.as_ref(),
+                &tip.last_block_hash,
+                shard_id,
+                true,
+            ) {
+                continue;
+            }
+            let chunk_producer =
+                self.runtime_adapter.get_chunk_producer(&epoch_id, tip.height + 1, shard_id)?;
+            if !validators.contains(&chunk_producer) {
+                validators.push(chunk_producer);
+            }
+        }
+        Ok(Arc::new(validators))
+    }
+
+    /// Returns the expected block producer and chunk producers of the next
+    /// `num_heights` heights, up to `MAX_PRODUCTION_SCHEDULE_HEIGHTS`.  Heights
+    /// from the estimated start of the next epoch are assigned as in the next
+    /// epoch, and the schedule stops at the estimated end of the next epoch,
+    /// after which the validators aren't known yet.
+    pub fn get_production_schedule(
+        &self,
+        num_heights: u64,
+    ) -> Result<ProductionScheduleView, near_chain::Error> {
+        let head = self.chain.head()?;
+        let prev_hash = &head.last_block_hash;
+        let epoch_id = self.runtime_adapter.get_epoch_id_from_prev_block(prev_hash)?;
+        let next_epoch_id = self.runtime_adapter.get_next_epoch_id_from_prev_block(prev_hash)?;
+        let epoch_start_height = if self.runtime_adapter.is_next_block_epoch_start(prev_hash)? {
+            head.height + 1
+        } else {
+            self.runtime_adapter.get_epoch_start_height(prev_hash)?
+        };
+        let next_epoch_start_height = epoch_start_height + self.config.epoch_length;
+        let last_height = std::cmp::min(
+            head.height + num_heights.min(MAX_PRODUCTION_SCHEDULE_HEIGHTS),
+            next_epoch_start_height + self.config.epoch_length - 1,
+        );
+        let mut heights = vec![];
+        for height in head.height + 1..=last_height {
+            let epoch_id =
+                if height < next_epoch_start_height { &epoch_id } else { &next_epoch_id };
+            let block_producer = self.runtime_adapter.get_block_producer(epoch_id, height)?;
+            let chunk_producers = (0..self.runtime_adapter.num_shards(epoch_id)?)
+                .map(|shard_id| self.runtime_adapter.get_chunk_producer(epoch_id, height, shard_id))
+                .collect::<Result<_, _>>()?;
+            heights.push(ProductionScheduleEntryView {
+                height,
+                epoch_id: epoch_id.0,
+                block_producer,
+                chunk_producers,
+            });
+        }
+        Ok(ProductionScheduleView { heights })
+    }
+
     /// send_network_chain_info sends ChainInfo to PeerManagerActor.
     /// ChainInfo contains chain information relevant to p2p networking.
     /// It is expected to be called every time the head of the chain changes (or more often).
@@ -2188,18 +3593,46 @@ impl Client {
         // convert config tracked shards
         // runtime will track all shards if config tracked shards is not empty
         // https://github.com/near/nearcore/issues/4930
-        let tracked_shards = if self.config.tracked_shards.is_empty() {
+        let tracked_shards = if self.tracked_shards_updated {
+            // Only the shards tracked already are advertised, not the ones
+            // which will be tracked once their state is caught up.
+            let num_shards = self.runtime_adapter.num_shards(&tip.epoch_id)?;
+            (0..num_shards)
+                .filter(|shard_id| {
+                    self.runtime_adapter.cares_about_shard(
+                        None,
+                        &tip.last_block_hash,
+                        *shard_id,
+                        true,
+                    )
+                })
+                .collect()
+        } else if self.config.tracked_shards.is_empty() {
             vec![]
         } else {
             let num_shards = self.runtime_adapter.num_shards(&tip.epoch_id)?;
             (0..num_shards).collect()
         };
         let tier1_accounts = self.get_tier1_accounts(&tip)?;
+        // The hints are best effort, so failing to compute them doesn't hold
+        // back the rest of the chain info.
+        let priority_validators = self.get_priority_validators(&tip).unwrap_or_else(|err| {
+            warn!(target: "client", ?err, "Failed to get the priority validators");
+            Default::default()
+        });
         let height = tip.height;
         #[cfg(feature = "test_features")]
         let height = self.adv_sync_height.unwrap_or(height);
         self.network_adapter.do_send(
-            SetChainInfo(ChainInfo { height, tracked_shards, tier1_accounts }).with_span_context(),
+            SetChainInfo(ChainInfo {
+                height,
+                tracked_shards,
+                tier1_accounts,
+                other_validator_signers: ValidatorSigners(self.other_validator_signers.clone()),
+                priority_validators,
+                maintenance_window: self.maintenance_window,
+            })
+            .with_span_context(),
         );
         Ok(())
     }
@@ -2216,13 +3649,90 @@ impl Client {
                 .iter()
                 .map(|(shard_id, state)| (*shard_id, state.status.to_string()))
                 .collect();
+            let state_parts_apply_progress = shard_sync_state
+                .iter()
+                .filter_map(|(shard_id, state)| match &state.status {
+                    ShardSyncStatus::StateDownloadApplying(status) => {
+                        Some((*shard_id, status.progress_view()))
+                    }
+                    _ => None,
+                })
+                .collect();
             ret.push(CatchupStatusView {
                 sync_block_hash: *sync_hash,
                 sync_block_height,
                 shard_sync_status,
+                state_parts_apply_progress,
                 blocks_to_catchup: self.chain.get_block_catchup_status(block_catchup_state),
             });
         }
         Ok(ret)
     }
+
+    /// Reports for the last `num_blocks` blocks of the canonical chain which
+    /// chunk producers got their chunks included and how long it took, so
+    /// that it's possible to tell why chunks of a producer keep being missed.
+    pub fn get_chunk_endorsement_status(
+        &self,
+        num_blocks: NumBlocks,
+    ) -> Result<ChunkEndorsementStatusView, near_chain::Error> {
+        let genesis_height = self.chain.genesis().height();
+        let mut chunks = vec![];
+        let mut chunk_producers: BTreeMap<AccountId, (u64, u64)> = BTreeMap::new();
+        let mut block_hash = self.chain.head()?.last_block_hash;
+        for _ in 0..num_blocks {
+            // Blocks before a state sync or garbage collected ones are gone.
+            let block = match self.chain.get_block(&block_hash) {
+                Ok(block) => block,
+                Err(near_chain::Error::DBNotFoundErr(_)) => break,
+                Err(err) => return Err(err),
+            };
+            let header = block.header();
+            if header.height() == genesis_height {
+                break;
+            }
+            let prev_header = self.chain.get_block_header(header.prev_hash())?;
+            let millis_since_prev = |time: chrono::DateTime<chrono::Utc>| {
+                (time - prev_header.timestamp()).num_milliseconds().max(0) as u64
+            };
+            let ready_chunks =
+                self.prev_block_to_chunk_headers_ready_for_inclusion.peek(header.prev_hash());
+            for chunk_header in block.chunks().iter() {
+                let shard_id = chunk_header.shard_id();
+                let chunk_producer = self.runtime_adapter.get_chunk_producer(
+                    header.epoch_id(),
+                    header.height(),
+                    shard_id,
+                )?;
+                let included = chunk_header.height_included() == header.height();
+                let counts = chunk_producers.entry(chunk_producer.clone()).or_default();
+                counts.0 += 1;
+                counts.1 += included as u64;
+                chunks.push(ChunkInclusionView {
+                    height: header.height(),
+                    shard_id,
+                    chunk_producer,
+                    included,
+                    inclusion_delay_millis: if included {
+                        Some(millis_since_prev(header.timestamp()))
+                    } else {
+                        None
+                    },
+                    ready_delay_millis: ready_chunks
+                        .and_then(|ready_chunks| ready_chunks.get(&shard_id))
+                        .map(|(_, ready_time)| millis_since_prev(*ready_time)),
+                });
+            }
+            block_hash = *header.prev_hash();
+        }
+        let chunk_producers = chunk_producers
+            .into_iter()
+            .map(|(account_id, (num_expected, num_included))| ChunkProducerInclusionView {
+                account_id,
+                num_expected,
+                num_included,
+            })
+            .collect();
+        Ok(ChunkEndorsementStatusView { chunks, chunk_producers })
+    }
 }
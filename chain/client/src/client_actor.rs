@@ -6,26 +6,29 @@
 //! https://github.com/near/nearcore/issues/7899
 
 use crate::adapter::{
-    BlockApproval, BlockHeadersResponse, BlockResponse, ProcessTxRequest, ProcessTxResponse,
-    RecvChallenge, RecvPartialEncodedChunk, RecvPartialEncodedChunkForward,
-    RecvPartialEncodedChunkRequest, RecvPartialEncodedChunkResponse, SetNetworkInfo, StateResponse,
+    BlockApproval, BlockHeadersResponse, BlockResponse, DeferTxRequest, ProcessTxRequest,
+    ProcessTxResponse, RecvBlockHeaderAnnouncement, RecvChallenge, RecvChunkInclusionFeedback,
+    RecvEpochSyncProofsResponse, RecvPartialEncodedChunk, RecvPartialEncodedChunkAvailability,
+    RecvPartialEncodedChunkForward, RecvPartialEncodedChunkRequest,
+    RecvPartialEncodedChunkResponse, SetNetworkInfo, StateResponse, TxStatusSubscribeRequest,
 };
 use crate::client::{Client, EPOCH_START_INFO_BLOCKS};
+use crate::head_lag::HeadLagDetector;
+use crate::health::{self, HealthInputs};
 use crate::info::{
     display_sync_status, get_validator_epoch_stats, InfoHelper, ValidatorInfoHelper,
 };
-use crate::metrics::PARTIAL_ENCODED_CHUNK_RESPONSE_DELAY;
 use crate::sync::{StateSync, StateSyncResult};
-use crate::{metrics, StatusResponse};
+use crate::StatusResponse;
 use actix::dev::SendError;
 use actix::{Actor, Addr, Arbiter, AsyncContext, Context, Handler, Message};
 use actix_rt::ArbiterHandle;
-use borsh::BorshSerialize;
 use chrono::DateTime;
 use near_chain::chain::{
     do_apply_chunks, ApplyStatePartsRequest, ApplyStatePartsResponse, BlockCatchUpRequest,
     BlockCatchUpResponse, StateSplitRequest, StateSplitResponse,
 };
+use near_chain::state_parts_apply::apply_state_parts;
 use near_chain::test_utils::format_hash;
 #[cfg(feature = "test_features")]
 use near_chain::ChainStoreAccess;
@@ -37,15 +40,16 @@ use near_chain_configs::ClientConfig;
 use near_chunks::client::ShardsManagerResponse;
 use near_chunks::logic::cares_about_shard_this_or_next_epoch;
 use near_client_primitives::types::{
-    Error, GetNetworkInfo, NetworkInfoResponse, ShardSyncDownload, ShardSyncStatus, Status,
-    StatusError, StatusSyncInfo, SyncStatus,
+    BeginDrain, BlockProductionReport, Error, GetHealth, GetNetworkInfo, GetProductionSchedule,
+    GetValidatorInfoError, NetworkInfoResponse, ShardSyncDownload, ShardSyncStatus,
+    SimulateBlockProduction, Status, StatusError, StatusSyncInfo, SyncStatus, TxForkStatus,
+    TxPoolCommand, TxStatusError, UpdateTrackedShards,
 };
-use near_dyn_configs::EXPECTED_SHUTDOWN_AT;
 #[cfg(feature = "test_features")]
 use near_network::types::NetworkAdversarialMessage;
 use near_network::types::ReasonForBan;
 use near_network::types::{
-    NetworkInfo, NetworkRequests, PeerManagerAdapter, PeerManagerMessageRequest,
+    MaintenanceWindow, NetworkInfo, NetworkRequests, PeerManagerAdapter, PeerManagerMessageRequest,
 };
 use near_o11y::{handler_debug_span, OpenTelemetrySpanExt, WithSpanContext, WithSpanContextExt};
 use near_performance_metrics;
@@ -55,14 +59,16 @@ use near_primitives::epoch_manager::RngSeed;
 use near_primitives::hash::CryptoHash;
 use near_primitives::network::{AnnounceAccount, PeerId};
 use near_primitives::state_part::PartId;
-use near_primitives::syncing::StatePartKey;
 use near_primitives::time::{Clock, Utc};
 use near_primitives::types::{BlockHeight, ValidatorInfoIdentifier};
 use near_primitives::unwrap_or_return;
 use near_primitives::utils::{from_timestamp, MaybeValidated};
 use near_primitives::validator_signer::ValidatorSigner;
 use near_primitives::version::PROTOCOL_VERSION;
-use near_primitives::views::{DetailedDebugStatus, ValidatorInfo};
+use near_primitives::views::{
+    DetailedDebugStatus, DrainStatusView, FinalExecutionOutcomeView, HealthView,
+    MaintenanceWindowView, ProductionScheduleView, TxForkStatusView, ValidatorInfo,
+};
 use near_store::DBCol;
 use near_telemetry::TelemetryActor;
 use rand::seq::SliceRandom;
@@ -107,6 +113,8 @@ pub struct ClientActor {
     doomslug_timer_next_attempt: DateTime<Utc>,
     chunk_request_retry_next_attempt: DateTime<Utc>,
     sync_started: bool,
+    /// Lag of the head behind the heights reported by the peers.
+    head_lag: HeadLagDetector,
     state_parts_task_scheduler: Box<dyn Fn(ApplyStatePartsRequest)>,
     block_catch_up_scheduler: Box<dyn Fn(BlockCatchUpRequest)>,
     state_split_scheduler: Box<dyn Fn(StateSplitRequest)>,
@@ -123,19 +131,18 @@ pub struct ClientActor {
 /// Blocks the program until given genesis time arrives.
 fn wait_until_genesis(genesis_time: &DateTime<Utc>) {
     loop {
-        // Get chrono::Duration::num_seconds() by deducting genesis_time from now.
         let duration = genesis_time.signed_duration_since(Clock::utc());
-        let chrono_seconds = duration.num_seconds();
-        // Check if number of seconds in chrono::Duration larger than zero.
-        if chrono_seconds <= 0 {
+        // Milliseconds rather than seconds, so that chains with sub-second
+        // blocks don't start producing before genesis time.
+        let millis = duration.num_milliseconds();
+        if millis <= 0 {
             break;
         }
         info!(target: "near", "Waiting until genesis: {}d {}h {}m {}s", duration.num_days(),
               (duration.num_hours() % 24),
               (duration.num_minutes() % 60),
               (duration.num_seconds() % 60));
-        let wait =
-            std::cmp::min(Duration::from_secs(10), Duration::from_secs(chrono_seconds as u64));
+        let wait = std::cmp::min(Duration::from_secs(10), Duration::from_millis(millis as u64));
         thread::sleep(wait);
     }
 }
@@ -159,11 +166,12 @@ impl ClientActor {
         let state_parts_arbiter = Arbiter::new();
         let self_addr = ctx.address();
         let self_addr_clone = self_addr.clone();
+        let state_parts_apply_memory_limit = config.state_parts_apply_memory_limit;
         let sync_jobs_actor_addr = SyncJobsActor::start_in_arbiter(
             &state_parts_arbiter.handle(),
             move |ctx: &mut Context<SyncJobsActor>| -> SyncJobsActor {
                 ctx.set_mailbox_capacity(SyncJobsActor::MAILBOX_CAPACITY);
-                SyncJobsActor { client_addr: self_addr_clone }
+                SyncJobsActor { client_addr: self_addr_clone, state_parts_apply_memory_limit }
             },
         );
         wait_until_genesis(&chain_genesis.time);
@@ -171,6 +179,7 @@ impl ClientActor {
             info!(target: "client", "Starting validator node: {}", vs.validator_id());
         }
         let info_helper = InfoHelper::new(Some(telemetry_actor), &config, validator_signer.clone());
+        let head_lag = HeadLagDetector::new(config.head_lag.clone());
         let client = Client::new(
             config,
             chain_genesis,
@@ -198,6 +207,7 @@ impl ClientActor {
                 sent_bytes_per_sec: 0,
                 known_producers: vec![],
                 tier1_accounts: vec![],
+                bandwidth_by_type: vec![],
             },
             last_validator_announce_time: None,
             info_helper,
@@ -207,6 +217,7 @@ impl ClientActor {
             doomslug_timer_next_attempt: now,
             chunk_request_retry_next_attempt: now,
             sync_started: false,
+            head_lag,
             state_parts_task_scheduler: create_sync_job_scheduler::<ApplyStatePartsRequest>(
                 sync_jobs_actor_addr.clone(),
             ),
@@ -263,6 +274,8 @@ impl Actor for ClientActor {
         // Start catchup job.
         self.catchup(ctx);
 
+        self.client.process_restored_orphans(self.get_apply_chunks_done_callback());
+
         self.client.send_network_chain_info().unwrap();
     }
 }
@@ -279,9 +292,13 @@ impl ClientActor {
         self.check_triggers(ctx);
         let _d =
             delay_detector::DelayDetector::new(|| format!("NetworkClientMessage {:?}", msg).into());
-        metrics::CLIENT_MESSAGES_COUNT.with_label_values(&[msg_type]).inc();
-        let timer =
-            metrics::CLIENT_MESSAGES_PROCESSING_TIME.with_label_values(&[msg_type]).start_timer();
+        self.client.metrics.client_messages_count.with_label_values(&[msg_type]).inc();
+        let timer = self
+            .client
+            .metrics
+            .client_messages_processing_time
+            .with_label_values(&[msg_type])
+            .start_timer();
         let res = f(self, msg);
         timer.observe_duration();
         res
@@ -414,6 +431,20 @@ impl Handler<WithSpanContext<ProcessTxRequest>> for ClientActor {
     }
 }
 
+impl Handler<WithSpanContext<DeferTxRequest>> for ClientActor {
+    type Result = ProcessTxResponse;
+
+    fn handle(
+        &mut self,
+        msg: WithSpanContext<DeferTxRequest>,
+        ctx: &mut Context<Self>,
+    ) -> Self::Result {
+        self.wrap(msg, ctx, "DeferTxRequest", |this: &mut Self, msg| {
+            this.client.defer_tx(msg.transaction)
+        })
+    }
+}
+
 impl Handler<WithSpanContext<BlockResponse>> for ClientActor {
     type Result = ();
 
@@ -468,6 +499,25 @@ impl Handler<WithSpanContext<BlockResponse>> for ClientActor {
     }
 }
 
+impl Handler<WithSpanContext<RecvBlockHeaderAnnouncement>> for ClientActor {
+    type Result = ();
+
+    fn handle(
+        &mut self,
+        msg: WithSpanContext<RecvBlockHeaderAnnouncement>,
+        ctx: &mut Context<Self>,
+    ) {
+        self.wrap(msg, ctx, "RecvBlockHeaderAnnouncement", |this, msg| {
+            let RecvBlockHeaderAnnouncement(announcement, peer_id) = msg;
+            this.client.receive_block_header_announcement(
+                announcement,
+                peer_id,
+                this.get_apply_chunks_done_callback(),
+            );
+        })
+    }
+}
+
 impl Handler<WithSpanContext<BlockHeadersResponse>> for ClientActor {
     type Result = Result<(), ReasonForBan>;
 
@@ -500,6 +550,28 @@ impl Handler<WithSpanContext<BlockApproval>> for ClientActor {
     }
 }
 
+impl Handler<WithSpanContext<RecvEpochSyncProofsResponse>> for ClientActor {
+    type Result = ();
+
+    fn handle(
+        &mut self,
+        msg: WithSpanContext<RecvEpochSyncProofsResponse>,
+        ctx: &mut Context<Self>,
+    ) {
+        self.wrap(msg, ctx, "RecvEpochSyncProofsResponse", |this, msg| {
+            let RecvEpochSyncProofsResponse(response, peer_id) = msg;
+            let block_hash = response.block_hash;
+            if let Err(err) = this.client.epoch_sync.on_epoch_sync_proofs(response, peer_id.clone())
+            {
+                warn!(target: "sync", ?block_hash, ?peer_id, ?err, "Rejected epoch sync proofs");
+                if err.is_bad_data() {
+                    this.client.ban_peer(peer_id, ReasonForBan::BadBlockHeader);
+                }
+            }
+        })
+    }
+}
+
 impl Handler<WithSpanContext<StateResponse>> for ClientActor {
     type Result = ();
 
@@ -508,7 +580,7 @@ impl Handler<WithSpanContext<StateResponse>> for ClientActor {
             let StateResponse(state_response_info) = msg;
             let shard_id = state_response_info.shard_id();
             let hash = state_response_info.sync_hash();
-            let state_response = state_response_info.take_state_response();
+            let mut state_response = state_response_info.take_state_response();
 
             trace!(target: "sync", "Received state response shard_id: {} sync_hash: {:?} part(id/size): {:?}",
                    shard_id,
@@ -589,7 +661,24 @@ impl Handler<WithSpanContext<StateResponse>> for ClientActor {
                         }
                     }
                     ShardSyncStatus::StateDownloadParts => {
-                        if let Some(part) = state_response.take_part() {
+                        let part = if let Some(sub_part) = state_response.take_sub_part() {
+                            let part_id = sub_part.part_id;
+                            match shard_sync_download.downloads.get_mut(part_id as usize) {
+                                Some(download) if !download.done => this
+                                    .client
+                                    .state_sync
+                                    .received_sub_part(download, shard_id, hash, sub_part)
+                                    .map(|data| (part_id, data)),
+                                Some(_) => None,
+                                None => {
+                                    error!(target: "sync", "State sync received incorrect part_id # {:?} for hash {:?}, potential malicious peer", part_id, hash);
+                                    return;
+                                }
+                            }
+                        } else {
+                            state_response.take_part()
+                        };
+                        if let Some(part) = part {
                             let num_parts = shard_sync_download.downloads.len() as u64;
                             let (part_id, data) = part;
                             if part_id >= num_parts {
@@ -611,6 +700,9 @@ impl Handler<WithSpanContext<StateResponse>> for ClientActor {
                                         error!(target: "sync", "State sync set_state_part error, shard = {}, part = {}, hash = {}: {:?}", shard_id, part_id, hash, err);
                                         shard_sync_download.downloads[part_id as usize].error =
                                             true;
+                                        shard_sync_download.downloads[part_id as usize]
+                                            .sub_parts
+                                            .clear();
                                     }
                                 }
                             }
@@ -653,7 +745,10 @@ impl Handler<WithSpanContext<RecvPartialEncodedChunkResponse>> for ClientActor {
     ) {
         self.wrap(msg, ctx, "RecvPartialEncodedChunkResponse", |this, msg| {
             let RecvPartialEncodedChunkResponse(response, time) = msg;
-            PARTIAL_ENCODED_CHUNK_RESPONSE_DELAY.observe(time.elapsed().as_secs_f64());
+            this.client
+                .metrics
+                .partial_encoded_chunk_response_delay
+                .observe(time.elapsed().as_secs_f64());
             let _ = this.client.shards_mgr.process_partial_encoded_chunk_response(response);
         });
     }
@@ -695,6 +790,36 @@ impl Handler<WithSpanContext<RecvPartialEncodedChunkForward>> for ClientActor {
     }
 }
 
+impl Handler<WithSpanContext<RecvPartialEncodedChunkAvailability>> for ClientActor {
+    type Result = ();
+
+    fn handle(
+        &mut self,
+        msg: WithSpanContext<RecvPartialEncodedChunkAvailability>,
+        ctx: &mut Context<Self>,
+    ) {
+        self.wrap(msg, ctx, "RecvPartialEncodedChunkAvailability", |this, msg| {
+            let RecvPartialEncodedChunkAvailability(availability) = msg;
+            this.client.shards_mgr.process_partial_encoded_chunk_availability(availability);
+        })
+    }
+}
+
+impl Handler<WithSpanContext<RecvChunkInclusionFeedback>> for ClientActor {
+    type Result = ();
+
+    fn handle(
+        &mut self,
+        msg: WithSpanContext<RecvChunkInclusionFeedback>,
+        ctx: &mut Context<Self>,
+    ) {
+        self.wrap(msg, ctx, "RecvChunkInclusionFeedback", |this, msg| {
+            let RecvChunkInclusionFeedback(feedback) = msg;
+            this.client.on_chunk_inclusion_feedback(feedback);
+        })
+    }
+}
+
 impl Handler<WithSpanContext<RecvChallenge>> for ClientActor {
     type Result = ();
 
@@ -876,6 +1001,16 @@ impl Handler<WithSpanContext<Status>> for ClientActor {
             node_key,
             uptime_sec,
             detailed_debug_status,
+            maintenance_window: near_dyn_configs::maintenance_window().map(
+                |(start_height, shutdown_height)| MaintenanceWindowView {
+                    start_height,
+                    shutdown_height,
+                    active: head.height >= start_height,
+                },
+            ),
+            storage_degraded: near_store::storage_health().is_degraded(),
+            drain: self.client.drain_status(),
+            head_lag: self.head_lag.view(),
         })
     }
 }
@@ -932,6 +1067,155 @@ impl Handler<WithSpanContext<GetNetworkInfo>> for ClientActor {
     }
 }
 
+impl Handler<WithSpanContext<TxForkStatus>> for ClientActor {
+    type Result = Result<TxForkStatusView, TxStatusError>;
+
+    #[perf]
+    fn handle(
+        &mut self,
+        msg: WithSpanContext<TxForkStatus>,
+        ctx: &mut Context<Self>,
+    ) -> Self::Result {
+        let (_span, msg) = handler_debug_span!(target: "client", msg);
+        let _d = delay_detector::DelayDetector::new(|| "client tx fork status".into());
+        self.check_triggers(ctx);
+
+        Ok(self.client.tx_fork_status(&msg.tx_hash, &msg.signer_account_id)?)
+    }
+}
+
+impl Handler<WithSpanContext<BeginDrain>> for ClientActor {
+    type Result = Result<DrainStatusView, StatusError>;
+
+    #[perf]
+    fn handle(
+        &mut self,
+        msg: WithSpanContext<BeginDrain>,
+        ctx: &mut Context<Self>,
+    ) -> Self::Result {
+        let (_span, _msg) = handler_debug_span!(target: "client", msg);
+        self.check_triggers(ctx);
+
+        self.client.begin_drain()?;
+        Ok(self.client.drain_status().expect("the node is draining"))
+    }
+}
+
+impl Handler<WithSpanContext<GetProductionSchedule>> for ClientActor {
+    type Result = Result<ProductionScheduleView, GetValidatorInfoError>;
+
+    #[perf]
+    fn handle(
+        &mut self,
+        msg: WithSpanContext<GetProductionSchedule>,
+        ctx: &mut Context<Self>,
+    ) -> Self::Result {
+        let (_span, msg) = handler_debug_span!(target: "client", msg);
+        let _d = delay_detector::DelayDetector::new(|| "client production schedule".into());
+        self.check_triggers(ctx);
+
+        Ok(self.client.get_production_schedule(msg.num_heights)?)
+    }
+}
+
+impl Handler<WithSpanContext<TxPoolCommand>> for ClientActor {
+    type Result = Vec<CryptoHash>;
+
+    #[perf]
+    fn handle(
+        &mut self,
+        msg: WithSpanContext<TxPoolCommand>,
+        _ctx: &mut Context<Self>,
+    ) -> Self::Result {
+        let (_span, msg) = handler_debug_span!(target: "client", msg);
+        self.client.handle_tx_pool_command(msg)
+    }
+}
+
+impl Handler<WithSpanContext<UpdateTrackedShards>> for ClientActor {
+    type Result = Result<(), String>;
+
+    #[perf]
+    fn handle(
+        &mut self,
+        msg: WithSpanContext<UpdateTrackedShards>,
+        _ctx: &mut Context<Self>,
+    ) -> Self::Result {
+        let (_span, msg) = handler_debug_span!(target: "client", msg);
+        self.client.update_tracked_shards(msg.shard_ids).map_err(|err| err.to_string())
+    }
+}
+
+impl Handler<WithSpanContext<GetHealth>> for ClientActor {
+    type Result = Result<HealthView, StatusError>;
+
+    #[perf]
+    fn handle(&mut self, msg: WithSpanContext<GetHealth>, ctx: &mut Context<Self>) -> Self::Result {
+        let (_span, _msg) = handler_debug_span!(target: "client", msg);
+        self.check_triggers(ctx);
+
+        let head = self.client.chain.head()?;
+        let head_header = self.client.chain.get_block_header(&head.last_block_hash)?;
+        let head_age = (Clock::utc() - from_timestamp(head_header.raw_timestamp()))
+            .to_std()
+            .unwrap_or_default();
+        let inputs = HealthInputs {
+            is_syncing: self.client.sync_status.is_syncing(),
+            head_age,
+            time_since_head_updated: Clock::instant()
+                .saturating_duration_since(self.client.chain.get_last_time_head_updated()),
+            head_height: head.height,
+            highest_peer_height: self
+                .network_info
+                .highest_height_peers
+                .iter()
+                .map(|peer| peer.chain_info.height)
+                .max(),
+            num_peers: self.network_info.num_connected_peers,
+            has_validator_signer: self.client.validator_signer.is_some(),
+            storage_degraded: near_store::storage_health().is_degraded(),
+            average_block_processing_time: self.client.chain.average_block_processing_time(),
+            draining: self.client.drain_status().is_some(),
+        };
+        Ok(health::evaluate(&self.client.config.health, &inputs))
+    }
+}
+
+impl Handler<WithSpanContext<SimulateBlockProduction>> for ClientActor {
+    type Result = Result<BlockProductionReport, String>;
+
+    #[perf]
+    fn handle(
+        &mut self,
+        msg: WithSpanContext<SimulateBlockProduction>,
+        _ctx: &mut Context<Self>,
+    ) -> Self::Result {
+        let (_span, msg) = handler_debug_span!(target: "client", msg);
+        self.client.simulate_block_production(msg.next_height).map_err(|err| err.to_string())
+    }
+}
+
+impl Handler<WithSpanContext<TxStatusSubscribeRequest>> for ClientActor {
+    type Result = Option<Box<FinalExecutionOutcomeView>>;
+
+    #[perf]
+    fn handle(
+        &mut self,
+        msg: WithSpanContext<TxStatusSubscribeRequest>,
+        _ctx: &mut Context<Self>,
+    ) -> Self::Result {
+        let (_span, msg) = handler_debug_span!(target: "client", msg);
+        let TxStatusSubscribeRequest { tx_hash, signer_account_id, route_back, subscriber } = msg;
+        match self.client.subscribe_tx_status(&signer_account_id, tx_hash, route_back, subscriber) {
+            Ok(outcome) => outcome.map(Box::new),
+            Err(err) => {
+                debug!(target: "client", ?tx_hash, ?err, "Failed to subscribe to tx status");
+                None
+            }
+        }
+    }
+}
+
 /// `ApplyChunksDoneMessage` is a message that signals the finishing of applying chunks of a block.
 /// Upon receiving this message, ClientActors knows that it's time to finish processing the blocks that
 /// just finished applying chunks.
@@ -991,6 +1275,12 @@ impl ClientActor {
             debug!(target: "client", "Sending announce account for {}", validator_signer.validator_id());
             self.last_validator_announce_time = Some(now);
 
+            // The announcement is verified against the key staked in the next
+            // epoch, which differs from the main key once the validator rotates.
+            let validator_signer = self
+                .client
+                .validator_signer_for_epoch(&next_epoch_id, &prev_block_hash)
+                .unwrap_or_else(|| validator_signer.clone());
             let signature = validator_signer.sign_account_announce(
                 validator_signer.validator_id(),
                 &self.node_id,
@@ -1168,6 +1458,14 @@ impl ClientActor {
                     have_all_chunks,
                     log_block_production_info,
                 ) {
+                    if !have_all_chunks {
+                        if let Some(wait) =
+                            self.client.wait_for_late_chunks(&head.last_block_hash, &epoch_id)?
+                        {
+                            debug!(target: "client", height, ?wait, "Waiting for late chunks");
+                            continue;
+                        }
+                    }
                     if let Err(err) = self.produce_block(height) {
                         // If there is an error, report it and let it retry on the next loop step.
                         error!(target: "client", height, "Block production failed: {}", err);
@@ -1194,26 +1492,37 @@ impl ClientActor {
         // will prioritize processing messages until mailbox is empty. Execution of any other task
         // scheduled with run_later will be delayed.
 
-        // Check block height to trigger expected shutdown
-        if let Ok(head) = self.client.chain.head() {
-            let block_height_to_shutdown =
-                EXPECTED_SHUTDOWN_AT.load(std::sync::atomic::Ordering::Relaxed);
-            if block_height_to_shutdown > 0 && head.height >= block_height_to_shutdown {
-                info!(target: "client", "Expected shutdown triggered: head block({}) >= ({})", head.height, block_height_to_shutdown);
+        // Check block height to enter the maintenance window and trigger expected shutdown
+        let maintenance_window =
+            near_dyn_configs::maintenance_window().map(|(start_height, shutdown_height)| {
+                MaintenanceWindow { start_height, shutdown_height }
+            });
+        match self.client.check_maintenance_window(maintenance_window) {
+            Ok(true) => {
                 if let Some(tx) = self.shutdown_signal.take() {
+                    info!(target: "client", ?maintenance_window, "Expected shutdown triggered");
+                    if let Err(err) = self.client.chain.store().store().flush() {
+                        warn!(target: "client", ?err, "Failed to flush the store before shutdown");
+                    }
                     let _ = tx.send(()); // Ignore send signal fail, it will send again in next trigger
                 }
             }
+            Ok(false) => {}
+            Err(err) => warn!(target: "client", ?err, "Failed to check the maintenance window"),
         }
 
         let _d = delay_detector::DelayDetector::new(|| "client triggers".into());
 
         self.try_process_unfinished_blocks();
 
+        if let Err(err) = self.client.check_drain() {
+            warn!(target: "client", ?err, "Failed to check the drain");
+        }
+
         let mut delay = Duration::from_secs(1);
         let now = Utc::now();
 
-        let timer = metrics::CHECK_TRIGGERS_TIME.start_timer();
+        let timer = self.client.metrics.check_triggers_time.start_timer();
         if self.sync_started {
             self.doomslug_timer_next_attempt = self.run_timer(
                 self.client.config.doosmslug_step_period,
@@ -1267,14 +1576,26 @@ impl ClientActor {
                 .unwrap_or(delay),
         );
 
+        // Hedged chunk requests are sent on the same timer, so it has to run at
+        // least as often as the hedge delay.
+        let chunk_request_retry_period = self
+            .client
+            .config
+            .chunk_request_hedge_delay
+            .map_or(self.client.config.chunk_request_retry_period, |hedge_delay| {
+                std::cmp::min(hedge_delay, self.client.config.chunk_request_retry_period)
+            });
         self.chunk_request_retry_next_attempt = self.run_timer(
-            self.client.config.chunk_request_retry_period,
+            chunk_request_retry_period,
             self.chunk_request_retry_next_attempt,
             ctx,
             |act, _ctx| {
                 if let Ok(header_head) = act.client.chain.header_head() {
                     act.client.shards_mgr.resend_chunk_requests(&header_head)
                 }
+                if let Err(err) = act.client.save_chunk_requests() {
+                    warn!(target: "client", ?err, "Failed to save chunk requests");
+                }
             },
             "resend_chunk_requests",
         );
@@ -1298,6 +1619,9 @@ impl ClientActor {
     /// calls this function to finish processing the unfinished blocks. ClientActor also calls
     /// this function in `check_triggers`, because the actix queue may be blocked by other messages
     /// and we want to prioritize block processing.
+    /// At most `max_postprocessed_blocks_per_step` blocks are postprocessed at once. Every block
+    /// finishing applying chunks sends its own ApplyChunkDoneMessage, so the remaining ones are
+    /// postprocessed when handling the following messages.
     fn try_process_unfinished_blocks(&mut self) {
         let (accepted_blocks, _errors) =
             self.client.postprocess_ready_blocks(self.get_apply_chunks_done_callback(), true);
@@ -1315,12 +1639,18 @@ impl ClientActor {
         let _span = tracing::debug_span!(target: "client", "try_doomslug_timer").entered();
         let _ = self.client.check_and_update_doomslug_tip();
         let approvals = self.client.doomslug.process_timer(Clock::instant());
+        self.client
+            .metrics
+            .approvals_refused_total
+            .inc_by(self.client.doomslug.take_num_refused_approvals());
 
-        // Important to save the largest approval target height before sending approvals, so
-        // that if the node crashes in the meantime, we cannot get slashed on recovery
+        // Important to save the largest approval target height and endorsed height before sending
+        // approvals, so that if the node crashes in the meantime, we cannot get slashed on recovery
         let mut chain_store_update = self.client.chain.mut_store().store_update();
         chain_store_update
             .save_largest_target_height(self.client.doomslug.get_largest_target_height());
+        chain_store_update
+            .save_largest_endorsed_height(self.client.doomslug.get_largest_endorsed_height());
 
         match chain_store_update.commit() {
             Ok(_) => {
@@ -1346,6 +1676,27 @@ impl ClientActor {
     fn produce_block(&mut self, next_height: BlockHeight) -> Result<(), Error> {
         let _span = tracing::debug_span!(target: "client", "produce_block", next_height).entered();
         if let Some(block) = self.client.produce_block(next_height)? {
+            if let Err(err) = self.client.chain.validate_produced_block(&block) {
+                self.client.metrics.produced_block_rejected_total.inc();
+                let chunks: Vec<_> = block
+                    .chunks()
+                    .iter()
+                    .map(|chunk| (chunk.chunk_hash(), chunk.height_included()))
+                    .collect();
+                error!(
+                    target: "client",
+                    ?err,
+                    height = block.header().height(),
+                    hash = ?block.hash(),
+                    prev_hash = ?block.header().prev_hash(),
+                    epoch_id = ?block.header().epoch_id(),
+                    approvals = ?block.header().approvals(),
+                    chunk_mask = ?block.header().chunk_mask(),
+                    ?chunks,
+                    "Produced block failed validation, not publishing it"
+                );
+                return Err(err.into());
+            }
             // If we produced the block, send it out before we apply the block.
             self.network_adapter.do_send(
                 PeerManagerMessageRequest::NetworkRequests(NetworkRequests::Block {
@@ -1451,6 +1802,7 @@ impl ClientActor {
             return true;
         }
         info!(target: "client", "Received block headers from height {} to {}", headers.first().unwrap().height(), headers.last().unwrap().height());
+        self.client.header_sync.on_headers_received(&peer_id, &headers);
         match self.client.sync_block_headers(headers) {
             Ok(_) => true,
             Err(err) => {
@@ -1493,6 +1845,7 @@ impl ClientActor {
         } else {
             if full_peer_info.chain_info.height
                 > head.height + self.client.config.sync_height_threshold
+                || (self.head_lag.is_lagging() && full_peer_info.chain_info.height > head.height)
             {
                 info!(
                     target: "client",
@@ -1542,6 +1895,9 @@ impl ClientActor {
     /// Usually `state_fetch_horizon` is much less than the expected number of produced blocks on an epoch,
     /// so this is only relevant on epoch boundaries.
     fn find_sync_hash(&mut self) -> Result<CryptoHash, near_chain::Error> {
+        if let Some(header) = self.verified_sync_header() {
+            return Ok(*header.hash());
+        }
         let header_head = self.client.chain.header_head()?;
         let mut sync_hash = header_head.prev_block_hash;
         for _ in 0..self.client.config.state_fetch_horizon {
@@ -1564,6 +1920,37 @@ impl ClientActor {
         Ok(epoch_start_sync_hash)
     }
 
+    /// Returns the header of the first block of the epoch proven by epoch
+    /// sync once header sync has downloaded it on the canonical chain.
+    fn verified_sync_header(&self) -> Option<BlockHeader> {
+        let verified_sync_hash = self.client.epoch_sync.verified_sync_hash()?;
+        let header = self.client.chain.get_block_header(&verified_sync_hash).ok()?;
+        match self.client.chain.get_block_header_by_height(header.height()) {
+            Ok(canonical) if canonical.hash() == &verified_sync_hash => Some(header),
+            _ => {
+                debug!(
+                    target: "sync",
+                    ?verified_sync_hash,
+                    "Downloaded headers conflict with epoch sync proofs"
+                );
+                None
+            }
+        }
+    }
+
+    /// Whether state should be synced to the epoch proven by epoch sync right
+    /// away, without downloading the headers of the rest of the chain first.
+    /// That's the case until the chain has reached the block state is synced
+    /// to, i.e. the one preceding the first block of the proven epoch.
+    fn should_sync_state_to_verified_epoch(&self) -> Result<bool, near_chain::Error> {
+        let header = match self.verified_sync_header() {
+            Some(header) => header,
+            None => return Ok(false),
+        };
+        let prev_header = self.client.chain.get_block_header(header.prev_hash())?;
+        Ok(self.client.chain.head()?.height < prev_header.height())
+    }
+
     /// Runs catchup on repeat, if this client is a validator.
     /// Schedules itself again if it was not ran as response to state parts job result
     fn catchup(&mut self, ctx: &mut Context<ClientActor>) {
@@ -1603,8 +1990,12 @@ impl ClientActor {
             return next_attempt;
         }
 
-        let timer =
-            metrics::CLIENT_TRIGGER_TIME_BY_TYPE.with_label_values(&[timer_label]).start_timer();
+        let timer = self
+            .client
+            .metrics
+            .client_trigger_time_by_type
+            .with_label_values(&[timer_label])
+            .start_timer();
         f(self, ctx);
         timer.observe_duration();
 
@@ -1636,6 +2027,14 @@ impl ClientActor {
         let mut wait_period = self.client.config.sync_step_period;
 
         let currently_syncing = self.client.sync_status.is_syncing();
+        let head = unwrap_or_run_later!(self.client.chain.head());
+        let peer_heights = self
+            .network_info
+            .connected_peers
+            .iter()
+            .map(|peer| peer.full_peer_info.chain_info.height)
+            .collect();
+        self.head_lag.update(head.height, peer_heights, Clock::instant(), &self.client.metrics);
         let (needs_syncing, highest_height) = unwrap_or_run_later!(self.syncing_info());
 
         if !self.needs_syncing(needs_syncing) {
@@ -1653,6 +2052,15 @@ impl ClientActor {
                 self.check_send_announce_account(head.prev_block_hash);
             }
             wait_period = self.client.config.sync_check_period;
+        } else if self.client.config.epoch_sync_enabled
+            && !unwrap_or_run_later!(self.client.epoch_sync.run(
+                &mut self.client.sync_status,
+                &self.client.chain,
+                highest_height,
+                &self.network_info.highest_height_peers
+            ))
+        {
+            // Epoch sync has to finish before headers are downloaded.
         } else {
             // Run each step of syncing separately.
             unwrap_or_run_later!(self.client.header_sync.run(
@@ -1664,9 +2072,12 @@ impl ClientActor {
             // Only body / state sync if header height is close to the latest.
             let header_head = unwrap_or_run_later!(self.client.chain.header_head());
 
-            // Sync state if already running sync state or if block sync is too far.
+            // Sync state if already running sync state, if epoch sync proved
+            // an epoch whose headers have been downloaded or if block sync is
+            // too far.
             let sync_state = match self.client.sync_status {
                 SyncStatus::StateSync(_, _) => true,
+                _ if unwrap_or_run_later!(self.should_sync_state_to_verified_epoch()) => true,
                 _ if header_head.height
                     >= highest_height
                         .saturating_sub(self.client.config.block_header_fetch_horizon) =>
@@ -1823,6 +2234,7 @@ impl ClientActor {
                 .map(get_validator_epoch_stats)
                 .unwrap_or_default()
         };
+        near_store::disk_budget().check(self.client.runtime_adapter.store());
         let statistics = if self.client.config.enable_statistics_export {
             self.client.chain.store().get_store_statistics()
         } else {
@@ -1857,6 +2269,8 @@ impl Drop for ClientActor {
 
 struct SyncJobsActor {
     client_addr: Addr<ClientActor>,
+    /// Memory available to the state parts being applied at once.
+    state_parts_apply_memory_limit: u64,
 }
 
 impl SyncJobsActor {
@@ -1867,22 +2281,7 @@ impl SyncJobsActor {
         msg: &ApplyStatePartsRequest,
     ) -> Result<(), near_chain_primitives::error::Error> {
         let _span = tracing::debug_span!(target: "client", "apply_parts").entered();
-        let store = msg.runtime.store();
-
-        for part_id in 0..msg.num_parts {
-            let key = StatePartKey(msg.sync_hash, msg.shard_id, part_id).try_to_vec()?;
-            let part = store.get(DBCol::StateParts, &key)?.unwrap();
-
-            msg.runtime.apply_state_part(
-                msg.shard_id,
-                &msg.state_root,
-                PartId::new(part_id, msg.num_parts),
-                &part,
-                &msg.epoch_id,
-            )?;
-        }
-
-        Ok(())
+        apply_state_parts(msg, self.state_parts_apply_memory_limit)
     }
 }
 
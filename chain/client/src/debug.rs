@@ -1,13 +1,14 @@
 //! Structs in this file are used for debug purposes, and might change at any time
 //! without backwards compatibility.
-use crate::ClientActor;
+use crate::{Client, ClientActor};
 use actix::{Context, Handler};
 use borsh::BorshSerialize;
 use near_chain::crypto_hash_timer::CryptoHashTimer;
 use near_chain::{near_chain_primitives, ChainStoreAccess, RuntimeAdapter};
 use near_client_primitives::debug::{
     ApprovalAtHeightStatus, BlockProduction, ChunkCollection, DebugBlockStatusData, DebugStatus,
-    DebugStatusResponse, MissedHeightInfo, ProductionAtHeight, ValidatorStatus,
+    DebugStatusResponse, FinalitySlaView, MissedHeightInfo, PooledTransactionView,
+    ProductionAtHeight, ShardTxPoolView, TxPoolView, ValidatorStatus,
 };
 use near_client_primitives::types::Error;
 use near_client_primitives::{
@@ -16,8 +17,14 @@ use near_client_primitives::{
 };
 use near_o11y::{handler_debug_span, log_assert, OpenTelemetrySpanExt, WithSpanContext};
 use near_performance_metrics_macros::perf;
+use near_primitives::block_header::ApprovalInner;
 use near_primitives::syncing::get_num_state_parts;
 use near_primitives::types::{AccountId, BlockHeight, ShardId, ValidatorInfoIdentifier};
+use near_primitives::views::{
+    ApprovalReceivedView, BlockProductionView, CatchupStatusView, ChainProcessingInfo,
+    ChunkCollectionView, ChunkEndorsementStatusView, ChunkProductionView, DebugDataView,
+    HeaderSyncStatsView, SyncStatusView, DEBUG_DATA_VERSION,
+};
 use near_primitives::{
     hash::CryptoHash,
     syncing::{ShardStateSyncResponseHeader, StateHeaderKey},
@@ -26,7 +33,7 @@ use near_primitives::{
 };
 use near_store::DBCol;
 use std::cmp::{max, min};
-use std::collections::{HashMap, HashSet};
+use std::collections::{BTreeMap, HashMap, HashSet};
 
 use near_client_primitives::debug::{DebugBlockStatus, DebugChunkStatus};
 use near_primitives::sharding::ShardChunkHeader;
@@ -52,8 +59,12 @@ impl BlockProductionTracker {
         Self(lru::LruCache::new(PRODUCTION_TIMES_CACHE_SIZE))
     }
 
-    pub(crate) fn get(&mut self, height: BlockHeight) -> BlockProduction {
-        self.0.get(&height).cloned().unwrap_or_default()
+    pub(crate) fn get(&self, height: BlockHeight) -> BlockProduction {
+        self.0.peek(&height).cloned().unwrap_or_default()
+    }
+
+    pub(crate) fn iter(&self) -> impl Iterator<Item = (&BlockHeight, &BlockProduction)> {
+        self.0.iter()
     }
 
     /// Record approvals received so far for this block. Must be called before block is produced.
@@ -143,232 +154,176 @@ impl BlockProductionTracker {
     }
 }
 
-impl Handler<WithSpanContext<DebugStatus>> for ClientActor {
-    type Result = Result<DebugStatusResponse, StatusError>;
+/// Typed access to the data behind the debug pages, so that dashboards other
+/// than the built-in pages can consume it.  `debug_data` returns all of it in
+/// the versioned `DebugDataView`.
+pub trait DebugApi {
+    fn sync_status_view(&self) -> SyncStatusView;
 
-    #[perf]
-    fn handle(
-        &mut self,
-        msg: WithSpanContext<DebugStatus>,
-        _ctx: &mut Context<Self>,
-    ) -> Self::Result {
-        let (_span, msg) = handler_debug_span!(target: "client", msg);
-        match msg {
-            DebugStatus::SyncStatus => {
-                Ok(DebugStatusResponse::SyncStatus(self.client.sync_status.clone().into()))
-            }
-            DebugStatus::TrackedShards => {
-                Ok(DebugStatusResponse::TrackedShards(self.get_tracked_shards_view()?))
-            }
-            DebugStatus::EpochInfo => {
-                Ok(DebugStatusResponse::EpochInfo(self.get_recent_epoch_info()?))
-            }
-            DebugStatus::BlockStatus(height) => {
-                Ok(DebugStatusResponse::BlockStatus(self.get_last_blocks_info(height)?))
-            }
-            DebugStatus::ValidatorStatus => {
-                Ok(DebugStatusResponse::ValidatorStatus(self.get_validator_status()?))
-            }
-            DebugStatus::CatchupStatus => {
-                Ok(DebugStatusResponse::CatchupStatus(self.client.get_catchup_status()?))
-            }
-            DebugStatus::ChainProcessingStatus => Ok(DebugStatusResponse::ChainProcessingStatus(
-                self.client.chain.get_chain_processing_info(),
-            )),
-        }
+    /// Header sync statistics of the peers headers were requested from.
+    fn header_sync_view(&self) -> HeaderSyncStatsView;
+
+    /// State syncs of the shards tracked in the next epoch.
+    fn catchup_view(&self) -> Result<Vec<CatchupStatusView>, near_chain_primitives::Error>;
+
+    /// Blocks and chunks in processing, as tracked by the blocks delay tracker.
+    fn chain_processing_view(&self) -> ChainProcessingInfo;
+
+    /// Blocks this node produced or is about to produce, the latest first.
+    fn block_production_view(&self) -> Vec<BlockProductionView>;
+
+    /// Chunks this node produced, the latest first.
+    fn chunk_production_view(&self) -> Vec<ChunkProductionView>;
+
+    /// Shards tracked in this and in the next epoch.
+    fn tracked_shards_view(&self) -> Result<TrackedShardsView, near_chain_primitives::Error>;
+
+    /// The next epoch and up to `DEBUG_EPOCHS_TO_FETCH` recent ones, the latest first.
+    fn epoch_info_view(&self) -> Result<Vec<EpochInfoView>, near_chain_primitives::Error>;
+
+    /// Blocks and missed heights below `starting_height`, or below the header
+    /// head if it's None.
+    fn block_status_view(
+        &self,
+        starting_height: Option<BlockHeight>,
+    ) -> Result<DebugBlockStatusData, near_chain_primitives::Error>;
+
+    /// Approvals received by this validator and what it produced or is about
+    /// to produce around the head, with timings.
+    fn validator_status_view(&self) -> Result<ValidatorStatus, near_chain_primitives::Error>;
+
+    fn finality_sla_view(&self) -> FinalitySlaView;
+
+    fn tx_pool_view(&self) -> Result<TxPoolView, near_chain_primitives::Error>;
+
+    /// Inclusion of chunks in the last `DEBUG_BLOCKS_TO_FETCH` blocks.
+    fn chunk_endorsement_view(
+        &self,
+    ) -> Result<ChunkEndorsementStatusView, near_chain_primitives::Error>;
+
+    fn debug_data(&self) -> Result<DebugDataView, near_chain_primitives::Error> {
+        Ok(DebugDataView {
+            version: DEBUG_DATA_VERSION,
+            sync_status: self.sync_status_view(),
+            header_sync: self.header_sync_view(),
+            catchup: self.catchup_view()?,
+            chain_processing: self.chain_processing_view(),
+            block_production: self.block_production_view(),
+            chunk_production: self.chunk_production_view(),
+        })
     }
 }
 
-impl ClientActor {
-    // Gets a list of block producers and chunk-only producers for a given epoch.
-    fn get_producers_for_epoch(
-        &self,
-        epoch_id: &EpochId,
-        last_known_block_hash: &CryptoHash,
-    ) -> Result<(Vec<ValidatorInfo>, Vec<String>), Error> {
-        let mut block_producers_set = HashSet::new();
-        let block_producers: Vec<ValidatorInfo> = self
-            .client
-            .runtime_adapter
-            .get_epoch_block_producers_ordered(&epoch_id, &last_known_block_hash)?
-            .into_iter()
-            .map(|(validator_stake, is_slashed)| {
-                block_producers_set.insert(validator_stake.account_id().as_str().to_owned());
-                ValidatorInfo { account_id: validator_stake.take_account_id(), is_slashed }
-            })
-            .collect();
-        let chunk_only_producers = self
-            .client
-            .runtime_adapter
-            .get_epoch_chunk_producers(&epoch_id)?
-            .iter()
-            .filter_map(|producer| {
-                if block_producers_set.contains(&producer.account_id().to_string()) {
-                    None
-                } else {
-                    Some(producer.account_id().to_string())
-                }
-            })
-            .collect::<Vec<_>>();
-        Ok((block_producers, chunk_only_producers))
+impl DebugApi for Client {
+    fn sync_status_view(&self) -> SyncStatusView {
+        self.sync_status.clone().into()
     }
 
-    /// Gets the information about the epoch that contains a given block.
-    /// Also returns the hash of the last block of the previous epoch.
-    fn get_epoch_info_view(
-        &mut self,
-        current_block: CryptoHash,
-        is_current_block_head: bool,
-    ) -> Result<(EpochInfoView, CryptoHash), Error> {
-        let epoch_start_height =
-            self.client.runtime_adapter.get_epoch_start_height(&current_block)?;
+    fn header_sync_view(&self) -> HeaderSyncStatsView {
+        self.header_sync.stats_view()
+    }
 
-        let block = self.client.chain.get_block_by_height(epoch_start_height)?.clone();
-        let epoch_id = block.header().epoch_id();
-        let (validators, chunk_only_producers) =
-            self.get_producers_for_epoch(&epoch_id, &current_block)?;
+    fn catchup_view(&self) -> Result<Vec<CatchupStatusView>, near_chain_primitives::Error> {
+        self.get_catchup_status()
+    }
 
-        let shards_size_and_parts: Vec<(u64, u64)> = block
-            .chunks()
-            .iter()
-            .enumerate()
-            .map(|(shard_id, chunk)| {
-                let state_root_node = self.client.runtime_adapter.get_state_root_node(
-                    shard_id as u64,
-                    block.hash(),
-                    &chunk.prev_state_root(),
-                );
-                if let Ok(state_root_node) = state_root_node {
-                    (
-                        state_root_node.memory_usage,
-                        get_num_state_parts(state_root_node.memory_usage),
-                    )
-                } else {
-                    (0, 0)
-                }
-            })
-            .collect();
+    fn chain_processing_view(&self) -> ChainProcessingInfo {
+        self.chain.get_chain_processing_info()
+    }
 
-        let state_header_exists: Vec<bool> = (0..block.chunks().len())
-            .map(|shard_id| {
-                let key = StateHeaderKey(shard_id as u64, *block.hash()).try_to_vec();
-                match key {
-                    Ok(key) => {
-                        if let Ok(Some(_)) =
-                            self.client
-                                .chain
-                                .store()
-                                .store()
-                                .get_ser::<ShardStateSyncResponseHeader>(DBCol::StateHeaders, &key)
-                        {
-                            true
-                        } else {
-                            false
-                        }
-                    }
-                    Err(_) => false,
+    fn block_production_view(&self) -> Vec<BlockProductionView> {
+        let mut views: Vec<_> = self
+            .block_production_info
+            .iter()
+            .map(|(height, production)| {
+                let mut approvals: Vec<_> = production
+                    .approvals
+                    .approvals
+                    .iter()
+                    .map(|(account_id, (inner, received_at))| ApprovalReceivedView {
+                        account_id: account_id.clone(),
+                        endorsement: matches!(inner, ApprovalInner::Endorsement(_)),
+                        received_at: *received_at,
+                    })
+                    .collect();
+                approvals.sort_by(|a, b| {
+                    a.received_at.cmp(&b.received_at).then_with(|| a.account_id.cmp(&b.account_id))
+                });
+                BlockProductionView {
+                    height: *height,
+                    approvals,
+                    approvals_ready_at: production.approvals.ready_at,
+                    chunks: production
+                        .chunks_collection_time
+                        .iter()
+                        .enumerate()
+                        .map(|(shard_id, collection)| ChunkCollectionView {
+                            shard_id: shard_id as ShardId,
+                            chunk_producer: collection.chunk_producer.clone(),
+                            received_at: collection.received_time,
+                            included: collection.chunk_included,
+                        })
+                        .collect(),
+                    produced_at: production.block_production_time,
                 }
             })
             .collect();
+        views.sort_by_key(|view| std::cmp::Reverse(view.height));
+        views
+    }
 
-        let shards_size_and_parts = shards_size_and_parts
+    fn chunk_production_view(&self) -> Vec<ChunkProductionView> {
+        let mut views: Vec<_> = self
+            .chunk_production_info
             .iter()
-            .zip(state_header_exists.iter())
-            .map(|((a, b), c)| (a.clone(), b.clone(), c.clone()))
+            .map(|((height, shard_id), production)| ChunkProductionView {
+                height_created: *height,
+                shard_id: *shard_id,
+                produced_at: production.chunk_production_time,
+                duration_millis: production.chunk_production_duration_millis,
+                num_transactions: production.num_transactions,
+                max_transactions: production.max_transactions,
+                missed_inclusion_heights: production
+                    .missed_inclusions
+                    .iter()
+                    .map(|missed| missed.block_height)
+                    .collect(),
+            })
             .collect();
-
-        let validator_info = if is_current_block_head {
-            self.client
-                .runtime_adapter
-                .get_validator_info(ValidatorInfoIdentifier::BlockHash(current_block))?
-        } else {
-            self.client
-                .runtime_adapter
-                .get_validator_info(ValidatorInfoIdentifier::EpochId(epoch_id.clone()))?
-        };
-        return Ok((
-            EpochInfoView {
-                epoch_id: epoch_id.0,
-                height: block.header().height(),
-                first_block: Some((block.header().hash().clone(), block.header().timestamp())),
-                block_producers: validators.to_vec(),
-                chunk_only_producers,
-                validator_info: Some(validator_info),
-                protocol_version: self
-                    .client
-                    .runtime_adapter
-                    .get_epoch_protocol_version(epoch_id)
-                    .unwrap_or(0),
-                shards_size_and_parts,
-            },
-            // Last block of the previous epoch.
-            *block.header().prev_hash(),
-        ));
-    }
-
-    fn get_next_epoch_view(&self) -> Result<EpochInfoView, Error> {
-        let head = self.client.chain.head()?;
-        let epoch_start_height =
-            self.client.runtime_adapter.get_epoch_start_height(&head.last_block_hash)?;
-        let (validators, chunk_only_producers) =
-            self.get_producers_for_epoch(&&head.next_epoch_id, &head.last_block_hash)?;
-
-        Ok(EpochInfoView {
-            epoch_id: head.next_epoch_id.0,
-            // Expected height of the next epoch.
-            height: epoch_start_height + self.client.config.epoch_length,
-            first_block: None,
-            block_producers: validators,
-            chunk_only_producers,
-            validator_info: None,
-            protocol_version: self
-                .client
-                .runtime_adapter
-                .get_epoch_protocol_version(&head.next_epoch_id)?,
-            shards_size_and_parts: vec![],
-        })
+        views.sort_by_key(|view| (std::cmp::Reverse(view.height_created), view.shard_id));
+        views
     }
 
-    fn get_tracked_shards_view(&self) -> Result<TrackedShardsView, near_chain_primitives::Error> {
-        let epoch_id = self.client.chain.header_head()?.epoch_id;
-        let fetch_hash = self.client.chain.header_head()?.last_block_hash;
-        let me = self.client.validator_signer.as_ref().map(|x| x.validator_id().clone());
+    fn tracked_shards_view(&self) -> Result<TrackedShardsView, near_chain_primitives::Error> {
+        let epoch_id = self.chain.header_head()?.epoch_id;
+        let fetch_hash = self.chain.header_head()?.last_block_hash;
+        let me = self.validator_signer.as_ref().map(|x| x.validator_id().clone());
 
-        let tracked_shards: Vec<(bool, bool)> =
-            (0..self.client.runtime_adapter.num_shards(&epoch_id).unwrap())
-                .map(|x| {
-                    (
-                        self.client.runtime_adapter.cares_about_shard(
-                            me.as_ref(),
-                            &fetch_hash,
-                            x,
-                            true,
-                        ),
-                        self.client.runtime_adapter.will_care_about_shard(
-                            me.as_ref(),
-                            &fetch_hash,
-                            x,
-                            true,
-                        ),
-                    )
-                })
-                .collect();
+        let tracked_shards: Vec<(bool, bool)> = (0..self
+            .runtime_adapter
+            .num_shards(&epoch_id)
+            .unwrap())
+            .map(|x| {
+                (
+                    self.runtime_adapter.cares_about_shard(me.as_ref(), &fetch_hash, x, true),
+                    self.runtime_adapter.will_care_about_shard(me.as_ref(), &fetch_hash, x, true),
+                )
+            })
+            .collect();
         Ok(TrackedShardsView {
             shards_tracked_this_epoch: tracked_shards.iter().map(|x| x.0).collect(),
             shards_tracked_next_epoch: tracked_shards.iter().map(|x| x.1).collect(),
         })
     }
 
-    fn get_recent_epoch_info(
-        &mut self,
-    ) -> Result<Vec<EpochInfoView>, near_chain_primitives::Error> {
+    fn epoch_info_view(&self) -> Result<Vec<EpochInfoView>, near_chain_primitives::Error> {
         // Next epoch id
         let mut epochs_info: Vec<EpochInfoView> = Vec::new();
 
         if let Ok(next_epoch) = self.get_next_epoch_view() {
             epochs_info.push(next_epoch);
         }
-        let head = self.client.chain.head()?;
+        let head = self.chain.head()?;
         let mut current_block = head.last_block_hash;
         for i in 0..DEBUG_EPOCHS_TO_FETCH {
             if let Ok((epoch_view, block_previous_epoch)) =
@@ -383,17 +338,17 @@ impl ClientActor {
         Ok(epochs_info)
     }
 
-    fn get_last_blocks_info(
-        &mut self,
+    fn block_status_view(
+        &self,
         starting_height: Option<BlockHeight>,
     ) -> Result<DebugBlockStatusData, near_chain_primitives::Error> {
-        let head = self.client.chain.head()?;
-        let header_head = self.client.chain.header_head()?;
+        let head = self.chain.head()?;
+        let header_head = self.chain.header_head()?;
 
         let mut blocks: HashMap<CryptoHash, DebugBlockStatus> = HashMap::new();
         let mut missed_heights: Vec<MissedHeightInfo> = Vec::new();
         let mut last_epoch_id = head.epoch_id.clone();
-        let initial_gas_price = self.client.chain.genesis_block().header().gas_price();
+        let initial_gas_price = self.chain.genesis_block().header().gas_price();
 
         let mut height_to_fetch = starting_height.unwrap_or(header_head.height);
         let min_height_to_fetch =
@@ -402,7 +357,6 @@ impl ClientActor {
         while height_to_fetch > min_height_to_fetch || !block_hashes_to_force_fetch.is_empty() {
             let block_hashes = if height_to_fetch > min_height_to_fetch {
                 let block_hashes: Vec<CryptoHash> = self
-                    .client
                     .chain
                     .store()
                     .get_all_header_hashes_by_height(height_to_fetch)?
@@ -412,7 +366,6 @@ impl ClientActor {
                     missed_heights.push(MissedHeightInfo {
                         block_height: height_to_fetch,
                         block_producer: self
-                            .client
                             .runtime_adapter
                             .get_block_producer(&last_epoch_id, height_to_fetch)
                             .ok(),
@@ -429,16 +382,15 @@ impl ClientActor {
                 if blocks.contains_key(&block_hash) {
                     continue;
                 }
-                let block_header = self.client.chain.get_block_header(&block_hash)?;
-                let block = self.client.chain.get_block(&block_hash).ok();
+                let block_header = self.chain.get_block_header(&block_hash)?;
+                let block = self.chain.get_block(&block_hash).ok();
                 let is_on_canonical_chain =
-                    match self.client.chain.get_block_by_height(block_header.height()) {
+                    match self.chain.get_block_by_height(block_header.height()) {
                         Ok(block) => block.hash() == &block_hash,
                         Err(_) => false,
                     };
 
                 let block_producer = self
-                    .client
                     .runtime_adapter
                     .get_block_producer(block_header.epoch_id(), block_header.height())
                     .ok();
@@ -451,7 +403,6 @@ impl ClientActor {
                             shard_id: chunk.shard_id(),
                             chunk_hash: chunk.chunk_hash(),
                             chunk_producer: self
-                                .client
                                 .runtime_adapter
                                 .get_chunk_producer(
                                     block_header.epoch_id(),
@@ -510,11 +461,11 @@ impl ClientActor {
 
     /// Returns debugging information about the validator - including things like which approvals were received, which blocks/chunks will be
     /// produced and some detailed timing information.
-    fn get_validator_status(&mut self) -> Result<ValidatorStatus, near_chain_primitives::Error> {
-        let head = self.client.chain.head()?;
+    fn validator_status_view(&self) -> Result<ValidatorStatus, near_chain_primitives::Error> {
+        let head = self.chain.head()?;
         let mut productions = vec![];
 
-        if let Some(signer) = &self.client.validator_signer {
+        if let Some(signer) = &self.validator_signer {
             let validator_id = signer.validator_id().to_string();
 
             // We want to show some older blocks (up to DEBUG_PRODUCTION_OLD_BLOCKS_TO_SHOW in the past)
@@ -522,10 +473,10 @@ impl ClientActor {
 
             let estimated_epoch_end = max(
                 head.height,
-                self.client.runtime_adapter.get_epoch_start_height(&head.last_block_hash)?
-                    + self.client.chain.epoch_length,
+                self.runtime_adapter.get_epoch_start_height(&head.last_block_hash)?
+                    + self.chain.epoch_length,
             );
-            let max_height = self.client.doomslug.get_largest_approval_height().clamp(
+            let max_height = self.doomslug.get_largest_approval_height().clamp(
                 head.height,
                 min(head.height + DEBUG_MAX_PRODUCTION_BLOCKS_TO_SHOW, estimated_epoch_end),
             );
@@ -538,32 +489,30 @@ impl ClientActor {
                 let mut production = ProductionAtHeight::default();
 
                 // The block may be in the last epoch from head, we need to account for that.
-                if let Ok(header) = self.client.chain.get_block_header_by_height(height) {
+                if let Ok(header) = self.chain.get_block_header_by_height(height) {
                     epoch_id = header.epoch_id().clone();
                 }
 
                 // And if we are the block (or chunk) producer for this height - collect some timing info.
                 let block_producer = self
-                    .client
                     .runtime_adapter
                     .get_block_producer(&epoch_id, height)
                     .map(|f| f.to_string())
                     .unwrap_or_default();
 
-                let num_chunks = self.client.runtime_adapter.num_shards(&epoch_id)?;
+                let num_chunks = self.runtime_adapter.num_shards(&epoch_id)?;
 
                 if block_producer == validator_id {
                     // For each height - we want to collect information about received approvals.
-                    let mut block_production = self.client.block_production_info.get(height);
+                    let mut block_production = self.block_production_info.get(height);
                     block_production.block_included =
-                        self.client.chain.get_block_hash_by_height(height).is_ok();
+                        self.chain.get_block_hash_by_height(height).is_ok();
                     production.block_production = Some(block_production);
                     has_block_or_chunks_to_produce = true;
                 }
 
                 for shard_id in 0..num_chunks {
                     let chunk_producer = self
-                        .client
                         .runtime_adapter
                         .get_chunk_producer(&epoch_id, height, shard_id)
                         .map(|f| f.to_string())
@@ -571,8 +520,7 @@ impl ClientActor {
                     if chunk_producer == validator_id {
                         production.chunk_production.insert(
                             shard_id,
-                            self.client
-                                .chunk_production_info
+                            self.chunk_production_info
                                 .get(&(height, shard_id))
                                 .cloned()
                                 .unwrap_or_default(),
@@ -589,14 +537,12 @@ impl ClientActor {
 
         Ok(ValidatorStatus {
             validator_name: self
-                .client
                 .validator_signer
                 .as_ref()
                 .map(|signer| signer.validator_id().clone()),
             // TODO: this might not work correctly when we're at the epoch boundary (as it will just return the validators for the current epoch).
             // We can fix it in the future, if we see that this debug page is useful.
             validators: self
-                .client
                 .runtime_adapter
                 .get_epoch_block_approvers_ordered(&head.last_block_hash)
                 .map(|validators| {
@@ -612,9 +558,237 @@ impl ClientActor {
                 })
                 .ok(),
             head_height: head.height,
-            shards: self.client.runtime_adapter.num_shards(&head.epoch_id).unwrap_or_default(),
-            approval_history: self.client.doomslug.get_approval_history(),
+            shards: self.runtime_adapter.num_shards(&head.epoch_id).unwrap_or_default(),
+            approval_history: self.doomslug.get_approval_history(),
             production: productions,
         })
     }
+
+    fn finality_sla_view(&self) -> FinalitySlaView {
+        self.finality_tracker.view(Clock::utc())
+    }
+
+    fn tx_pool_view(&self) -> Result<TxPoolView, near_chain_primitives::Error> {
+        let gas_price = self.chain.head_header()?.gas_price();
+        let mut shards: BTreeMap<ShardId, Vec<PooledTransactionView>> = BTreeMap::new();
+        for (shard_id, priority, pool) in self.sharded_tx_pool.pools() {
+            shards.entry(shard_id).or_default().extend(pool.transactions().map(|tx| {
+                PooledTransactionView {
+                    hash: tx.get_hash(),
+                    signer_id: tx.transaction.signer_id.clone(),
+                    public_key: tx.transaction.public_key.clone(),
+                    nonce: tx.transaction.nonce,
+                    receiver_id: tx.transaction.receiver_id.clone(),
+                    pinned: pool.is_pinned(&tx.get_hash()),
+                    priority,
+                }
+            }));
+        }
+        let shards = shards
+            .into_iter()
+            .map(|(shard_id, mut transactions)| {
+                transactions.sort_by(|a, b| {
+                    (b.priority, &a.signer_id, a.nonce).cmp(&(a.priority, &b.signer_id, b.nonce))
+                });
+                ShardTxPoolView { shard_id, transactions }
+            })
+            .collect();
+        Ok(TxPoolView { gas_price, shards })
+    }
+
+    fn chunk_endorsement_view(
+        &self,
+    ) -> Result<ChunkEndorsementStatusView, near_chain_primitives::Error> {
+        self.get_chunk_endorsement_status(DEBUG_BLOCKS_TO_FETCH as u64)
+    }
+}
+
+impl Handler<WithSpanContext<DebugStatus>> for ClientActor {
+    type Result = Result<DebugStatusResponse, StatusError>;
+
+    #[perf]
+    fn handle(
+        &mut self,
+        msg: WithSpanContext<DebugStatus>,
+        _ctx: &mut Context<Self>,
+    ) -> Self::Result {
+        let (_span, msg) = handler_debug_span!(target: "client", msg);
+        match msg {
+            DebugStatus::SyncStatus => {
+                Ok(DebugStatusResponse::SyncStatus(self.client.sync_status_view()))
+            }
+            DebugStatus::TrackedShards => {
+                Ok(DebugStatusResponse::TrackedShards(self.client.tracked_shards_view()?))
+            }
+            DebugStatus::EpochInfo => {
+                Ok(DebugStatusResponse::EpochInfo(self.client.epoch_info_view()?))
+            }
+            DebugStatus::BlockStatus(height) => {
+                Ok(DebugStatusResponse::BlockStatus(self.client.block_status_view(height)?))
+            }
+            DebugStatus::ValidatorStatus => {
+                Ok(DebugStatusResponse::ValidatorStatus(self.client.validator_status_view()?))
+            }
+            DebugStatus::CatchupStatus => {
+                Ok(DebugStatusResponse::CatchupStatus(self.client.catchup_view()?))
+            }
+            DebugStatus::ChainProcessingStatus => {
+                Ok(DebugStatusResponse::ChainProcessingStatus(self.client.chain_processing_view()))
+            }
+            DebugStatus::FinalitySla => {
+                Ok(DebugStatusResponse::FinalitySla(self.client.finality_sla_view()))
+            }
+            DebugStatus::TxPool => Ok(DebugStatusResponse::TxPool(self.client.tx_pool_view()?)),
+            DebugStatus::ChunkEndorsementStatus => Ok(DebugStatusResponse::ChunkEndorsementStatus(
+                self.client.chunk_endorsement_view()?,
+            )),
+            DebugStatus::HeaderSyncStats => {
+                Ok(DebugStatusResponse::HeaderSyncStats(self.client.header_sync_view()))
+            }
+            DebugStatus::DebugData => Ok(DebugStatusResponse::DebugData(self.client.debug_data()?)),
+        }
+    }
+}
+
+impl Client {
+    // Gets a list of block producers and chunk-only producers for a given epoch.
+    fn get_producers_for_epoch(
+        &self,
+        epoch_id: &EpochId,
+        last_known_block_hash: &CryptoHash,
+    ) -> Result<(Vec<ValidatorInfo>, Vec<String>), Error> {
+        let mut block_producers_set = HashSet::new();
+        let block_producers: Vec<ValidatorInfo> = self
+            .runtime_adapter
+            .get_epoch_block_producers_ordered(&epoch_id, &last_known_block_hash)?
+            .into_iter()
+            .map(|(validator_stake, is_slashed)| {
+                block_producers_set.insert(validator_stake.account_id().as_str().to_owned());
+                ValidatorInfo { account_id: validator_stake.take_account_id(), is_slashed }
+            })
+            .collect();
+        let chunk_only_producers = self
+            .runtime_adapter
+            .get_epoch_chunk_producers(&epoch_id)?
+            .iter()
+            .filter_map(|producer| {
+                if block_producers_set.contains(&producer.account_id().to_string()) {
+                    None
+                } else {
+                    Some(producer.account_id().to_string())
+                }
+            })
+            .collect::<Vec<_>>();
+        Ok((block_producers, chunk_only_producers))
+    }
+
+    /// Gets the information about the epoch that contains a given block.
+    /// Also returns the hash of the last block of the previous epoch.
+    fn get_epoch_info_view(
+        &self,
+        current_block: CryptoHash,
+        is_current_block_head: bool,
+    ) -> Result<(EpochInfoView, CryptoHash), Error> {
+        let epoch_start_height = self.runtime_adapter.get_epoch_start_height(&current_block)?;
+
+        let block = self.chain.get_block_by_height(epoch_start_height)?.clone();
+        let epoch_id = block.header().epoch_id();
+        let (validators, chunk_only_producers) =
+            self.get_producers_for_epoch(&epoch_id, &current_block)?;
+
+        let shards_size_and_parts: Vec<(u64, u64)> = block
+            .chunks()
+            .iter()
+            .enumerate()
+            .map(|(shard_id, chunk)| {
+                let state_root_node = self.runtime_adapter.get_state_root_node(
+                    shard_id as u64,
+                    block.hash(),
+                    &chunk.prev_state_root(),
+                );
+                if let Ok(state_root_node) = state_root_node {
+                    (
+                        state_root_node.memory_usage,
+                        get_num_state_parts(state_root_node.memory_usage),
+                    )
+                } else {
+                    (0, 0)
+                }
+            })
+            .collect();
+
+        let state_header_exists: Vec<bool> = (0..block.chunks().len())
+            .map(|shard_id| {
+                let key = StateHeaderKey(shard_id as u64, *block.hash()).try_to_vec();
+                match key {
+                    Ok(key) => {
+                        if let Ok(Some(_)) = self
+                            .chain
+                            .store()
+                            .store()
+                            .get_ser::<ShardStateSyncResponseHeader>(DBCol::StateHeaders, &key)
+                        {
+                            true
+                        } else {
+                            false
+                        }
+                    }
+                    Err(_) => false,
+                }
+            })
+            .collect();
+
+        let shards_size_and_parts = shards_size_and_parts
+            .iter()
+            .zip(state_header_exists.iter())
+            .map(|((a, b), c)| (a.clone(), b.clone(), c.clone()))
+            .collect();
+
+        let validator_info = if is_current_block_head {
+            self.runtime_adapter
+                .get_validator_info(ValidatorInfoIdentifier::BlockHash(current_block))?
+        } else {
+            self.runtime_adapter
+                .get_validator_info(ValidatorInfoIdentifier::EpochId(epoch_id.clone()))?
+        };
+        return Ok((
+            EpochInfoView {
+                epoch_id: epoch_id.0,
+                height: block.header().height(),
+                first_block: Some((block.header().hash().clone(), block.header().timestamp())),
+                block_producers: validators.to_vec(),
+                chunk_only_producers,
+                validator_info: Some(validator_info),
+                protocol_version: self
+                    .runtime_adapter
+                    .get_epoch_protocol_version(epoch_id)
+                    .unwrap_or(0),
+                shards_size_and_parts,
+            },
+            // Last block of the previous epoch.
+            *block.header().prev_hash(),
+        ));
+    }
+
+    fn get_next_epoch_view(&self) -> Result<EpochInfoView, Error> {
+        let head = self.chain.head()?;
+        let epoch_start_height =
+            self.runtime_adapter.get_epoch_start_height(&head.last_block_hash)?;
+        let (validators, chunk_only_producers) =
+            self.get_producers_for_epoch(&&head.next_epoch_id, &head.last_block_hash)?;
+
+        Ok(EpochInfoView {
+            epoch_id: head.next_epoch_id.0,
+            // Expected height of the next epoch.
+            height: epoch_start_height + self.config.epoch_length,
+            first_block: None,
+            block_producers: validators,
+            chunk_only_producers,
+            validator_info: None,
+            protocol_version: self
+                .runtime_adapter
+                .get_epoch_protocol_version(&head.next_epoch_id)?,
+            shards_size_and_parts: vec![],
+        })
+    }
 }
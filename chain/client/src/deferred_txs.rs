@@ -0,0 +1,211 @@
+//! Transactions held until the block they're anchored to is final.
+//!
+//! A transaction references the hash of a recent block and is only valid on
+//! the chain containing that block.  Clients which anchor transactions to the
+//! latest blocks, such as exchange withdrawal pipelines, risk the block being
+//! orphaned after the transaction was accepted, which silently invalidates
+//! it.  Instead, they can submit the transaction deferred: the node holds it
+//! and processes it like a newly submitted one only once its anchor block is
+//! final.  Transactions whose anchor block didn't make it to the canonical
+//! chain are dropped, as are the ones not released within `DEFER_TIMEOUT`.
+//!
+//! Deferred transactions are validated like the ones submitted right away, and
+//! a single signer may have at most `MAX_DEFERRED_TXS_PER_SIGNER` of them held,
+//! so that it can't take up all the room.
+use std::collections::HashMap;
+use std::time::{Duration, Instant};
+
+use near_primitives::hash::CryptoHash;
+use near_primitives::transaction::SignedTransaction;
+use near_primitives::types::AccountId;
+
+use crate::adapter::TxRejectionReason;
+
+/// Number of transactions held at most.
+pub(crate) const MAX_DEFERRED_TXS: usize = 10_000;
+/// Number of transactions of a single signer held at most.
+pub(crate) const MAX_DEFERRED_TXS_PER_SIGNER: usize = 100;
+/// How long a transaction is held at most.
+const DEFER_TIMEOUT: Duration = Duration::from_secs(600);
+
+pub(crate) struct DeferredTransactions {
+    /// Held transactions by the hash of their anchor block, with the time they
+    /// were deferred.
+    txs: HashMap<CryptoHash, Vec<(SignedTransaction, Instant)>>,
+    len: usize,
+    /// Number of held transactions of each signer.
+    num_txs_per_signer: HashMap<AccountId, usize>,
+}
+
+impl DeferredTransactions {
+    pub fn new() -> Self {
+        Self { txs: HashMap::new(), len: 0, num_txs_per_signer: HashMap::new() }
+    }
+
+    /// Holds the transaction until it's taken.  Fails if too many
+    /// transactions, or too many transactions of its signer, are held already.
+    pub fn insert(&mut self, tx: SignedTransaction, now: Instant) -> Result<(), TxRejectionReason> {
+        let anchor = tx.transaction.block_hash;
+        if let Some(txs) = self.txs.get(&anchor) {
+            if txs.iter().any(|(held, _)| held.get_hash() == tx.get_hash()) {
+                return Ok(());
+            }
+        }
+        if self.len == MAX_DEFERRED_TXS {
+            return Err(TxRejectionReason::TooManyDeferred { limit: MAX_DEFERRED_TXS });
+        }
+        let signer_id = &tx.transaction.signer_id;
+        let num_txs = self.num_txs_per_signer.get(signer_id).copied().unwrap_or(0);
+        if num_txs == MAX_DEFERRED_TXS_PER_SIGNER {
+            return Err(TxRejectionReason::TooManyDeferredFromSigner {
+                signer_id: signer_id.clone(),
+                limit: MAX_DEFERRED_TXS_PER_SIGNER,
+            });
+        }
+        self.num_txs_per_signer.insert(signer_id.clone(), num_txs + 1);
+        self.txs.entry(anchor).or_default().push((tx, now));
+        self.len += 1;
+        Ok(())
+    }
+
+    /// Drops the transactions held longer than `DEFER_TIMEOUT` and returns
+    /// the anchor blocks of the ones still held.
+    pub fn pending(&mut self, now: Instant) -> Vec<CryptoHash> {
+        let mut dropped = vec![];
+        self.txs.retain(|_, txs| {
+            let (kept, expired): (Vec<_>, Vec<_>) = std::mem::take(txs)
+                .into_iter()
+                .partition(|(_, time)| now.saturating_duration_since(*time) < DEFER_TIMEOUT);
+            *txs = kept;
+            dropped.extend(expired.into_iter().map(|(tx, _)| tx));
+            !txs.is_empty()
+        });
+        self.release(&dropped);
+        self.txs.keys().copied().collect()
+    }
+
+    /// Removes and returns the transactions anchored to the block.
+    pub fn take(&mut self, anchor: &CryptoHash) -> Vec<SignedTransaction> {
+        let txs: Vec<_> =
+            self.txs.remove(anchor).unwrap_or_default().into_iter().map(|(tx, _)| tx).collect();
+        self.release(&txs);
+        txs
+    }
+
+    pub fn len(&self) -> usize {
+        self.len
+    }
+
+    fn release(&mut self, txs: &[SignedTransaction]) {
+        self.len -= txs.len();
+        for tx in txs {
+            let signer_id = &tx.transaction.signer_id;
+            if let Some(num_txs) = self.num_txs_per_signer.get_mut(signer_id) {
+                *num_txs -= 1;
+                if *num_txs == 0 {
+                    self.num_txs_per_signer.remove(signer_id);
+                }
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{
+        DeferredTransactions, DEFER_TIMEOUT, MAX_DEFERRED_TXS, MAX_DEFERRED_TXS_PER_SIGNER,
+    };
+    use crate::adapter::TxRejectionReason;
+    use near_crypto::EmptySigner;
+    use near_primitives::hash::{hash, CryptoHash};
+    use near_primitives::transaction::SignedTransaction;
+    use std::time::{Duration, Instant};
+
+    fn make_signer_tx(signer: &str, nonce: u64, anchor: CryptoHash) -> SignedTransaction {
+        SignedTransaction::from_actions(
+            nonce,
+            signer.parse().unwrap(),
+            "test".parse().unwrap(),
+            &EmptySigner {},
+            vec![],
+            anchor,
+        )
+    }
+
+    fn make_tx(nonce: u64, anchor: CryptoHash) -> SignedTransaction {
+        make_signer_tx("test", nonce, anchor)
+    }
+
+    #[test]
+    fn test_deferred_txs() {
+        let mut deferred = DeferredTransactions::new();
+        let start = Instant::now();
+        let (anchor1, anchor2) = (hash(b"anchor1"), hash(b"anchor2"));
+        deferred.insert(make_tx(1, anchor1), start).unwrap();
+        deferred.insert(make_tx(2, anchor1), start + Duration::from_secs(60)).unwrap();
+        deferred.insert(make_tx(3, anchor2), start).unwrap();
+        // Submitting the same transaction again holds it once.
+        deferred.insert(make_tx(3, anchor2), start).unwrap();
+        assert_eq!(deferred.len(), 3);
+        let mut pending = deferred.pending(start);
+        pending.sort();
+        let mut expected = vec![anchor1, anchor2];
+        expected.sort();
+        assert_eq!(pending, expected);
+
+        // The first transactions anchored to both blocks time out.
+        assert_eq!(deferred.pending(start + DEFER_TIMEOUT), vec![anchor1]);
+        assert_eq!(deferred.len(), 1);
+        assert_eq!(deferred.take(&anchor1), vec![make_tx(2, anchor1)]);
+        assert!(deferred.take(&anchor1).is_empty());
+        assert_eq!(deferred.len(), 0);
+        assert!(deferred.num_txs_per_signer.is_empty());
+    }
+
+    #[test]
+    fn test_deferred_txs_limit() {
+        let mut deferred = DeferredTransactions::new();
+        let now = Instant::now();
+        let anchor = hash(b"anchor");
+        let num_signers = MAX_DEFERRED_TXS / MAX_DEFERRED_TXS_PER_SIGNER;
+        for signer in 0..num_signers {
+            for nonce in 0..MAX_DEFERRED_TXS_PER_SIGNER as u64 {
+                let tx = make_signer_tx(&format!("test{}", signer), nonce, anchor);
+                deferred.insert(tx, now).unwrap();
+            }
+        }
+        assert_eq!(
+            deferred.insert(make_signer_tx("other", 0, anchor), now),
+            Err(TxRejectionReason::TooManyDeferred { limit: MAX_DEFERRED_TXS })
+        );
+        assert_eq!(deferred.take(&anchor).len(), MAX_DEFERRED_TXS);
+        deferred.insert(make_signer_tx("other", 0, anchor), now).unwrap();
+    }
+
+    #[test]
+    fn test_deferred_txs_per_signer_limit() {
+        let mut deferred = DeferredTransactions::new();
+        let start = Instant::now();
+        let (anchor1, anchor2) = (hash(b"anchor1"), hash(b"anchor2"));
+        for nonce in 0..MAX_DEFERRED_TXS_PER_SIGNER as u64 {
+            deferred.insert(make_tx(nonce, anchor1), start).unwrap();
+        }
+        let nonce = MAX_DEFERRED_TXS_PER_SIGNER as u64;
+        assert_eq!(
+            deferred.insert(make_tx(nonce, anchor2), start),
+            Err(TxRejectionReason::TooManyDeferredFromSigner {
+                signer_id: "test".parse().unwrap(),
+                limit: MAX_DEFERRED_TXS_PER_SIGNER
+            })
+        );
+        deferred.insert(make_signer_tx("other", nonce, anchor2), start).unwrap();
+
+        // Once its transactions time out or are taken, the signer can defer
+        // transactions again.
+        deferred.pending(start + DEFER_TIMEOUT);
+        assert_eq!(deferred.len(), 0);
+        deferred.insert(make_tx(nonce, anchor2), start + DEFER_TIMEOUT).unwrap();
+        assert_eq!(deferred.take(&anchor2).len(), 1);
+        assert!(deferred.num_txs_per_signer.is_empty());
+    }
+}
@@ -0,0 +1,292 @@
+//! Tracks how long it takes recent blocks to become final.
+//!
+//! For every block the node learns about it records when the block was
+//! received, when it got doomslug finality and when it got full finality, all
+//! measured from the production timestamp in the block header.  Finality of a
+//! block implies finality of all its ancestors, so when a block becomes final
+//! the tracked ancestors which weren't marked yet are marked at the same time.
+//!
+//! The delays are aggregated into percentiles over a few sliding windows, which
+//! makes it easy to check whether the node keeps up with the expected time to
+//! finality and, if it doesn't, at which stage the time is lost.
+use std::collections::VecDeque;
+use std::time::Duration;
+
+use chrono::{DateTime, Utc};
+use near_client_primitives::debug::{
+    BlockFinalityView, FinalitySlaView, FinalitySlaWindowView, FinalityStageStats,
+};
+use near_primitives::block::BlockHeader;
+use near_primitives::hash::CryptoHash;
+use near_primitives::types::BlockHeight;
+
+/// Number of blocks for which the timeline is kept.
+const TRACKED_BLOCKS: usize = 1000;
+/// Number of blocks shown in the debug page.
+const RECENT_BLOCKS_TO_SHOW: usize = 50;
+
+#[derive(Clone, Copy)]
+enum Stage {
+    Received,
+    DoomslugFinal,
+    FullFinal,
+}
+
+struct BlockFinality {
+    prev_hash: CryptoHash,
+    height: BlockHeight,
+    produced: DateTime<Utc>,
+    received: Option<DateTime<Utc>>,
+    doomslug_final: Option<DateTime<Utc>>,
+    full_final: Option<DateTime<Utc>>,
+}
+
+impl BlockFinality {
+    fn stage_mut(&mut self, stage: Stage) -> &mut Option<DateTime<Utc>> {
+        match stage {
+            Stage::Received => &mut self.received,
+            Stage::DoomslugFinal => &mut self.doomslug_final,
+            Stage::FullFinal => &mut self.full_final,
+        }
+    }
+
+    fn delay_millis(&self, time: Option<DateTime<Utc>>) -> Option<u64> {
+        time.map(|time| (time - self.produced).num_milliseconds().max(0) as u64)
+    }
+}
+
+pub(crate) struct FinalityTracker {
+    /// Windows over which the delays are aggregated.
+    windows: Vec<Duration>,
+    blocks: lru::LruCache<CryptoHash, BlockFinality>,
+    /// Time when the sample was taken and the delay, per stage.
+    received: VecDeque<(DateTime<Utc>, u64)>,
+    doomslug_final: VecDeque<(DateTime<Utc>, u64)>,
+    full_final: VecDeque<(DateTime<Utc>, u64)>,
+}
+
+impl FinalityTracker {
+    pub fn new(windows: Vec<Duration>) -> Self {
+        Self {
+            windows,
+            blocks: lru::LruCache::new(TRACKED_BLOCKS),
+            received: VecDeque::new(),
+            doomslug_final: VecDeque::new(),
+            full_final: VecDeque::new(),
+        }
+    }
+
+    /// Records that the block was received from the network or produced by
+    /// this node.  Only the first call for a block has any effect.
+    pub fn mark_block_received(&mut self, header: &BlockHeader, now: DateTime<Utc>) {
+        self.track(*header.hash(), *header.prev_hash(), header.height(), header.timestamp(), now);
+    }
+
+    /// Updates finality of the ancestors of a block which was just accepted.
+    pub fn on_block_accepted(&mut self, header: &BlockHeader, now: DateTime<Utc>) {
+        self.mark_block_received(header, now);
+        self.mark_final(*header.last_ds_final_block(), Stage::DoomslugFinal, now);
+        self.mark_final(*header.last_final_block(), Stage::FullFinal, now);
+    }
+
+    fn track(
+        &mut self,
+        hash: CryptoHash,
+        prev_hash: CryptoHash,
+        height: BlockHeight,
+        produced: DateTime<Utc>,
+        now: DateTime<Utc>,
+    ) {
+        if self.blocks.contains(&hash) {
+            return;
+        }
+        self.blocks.put(
+            hash,
+            BlockFinality {
+                prev_hash,
+                height,
+                produced,
+                received: None,
+                doomslug_final: None,
+                full_final: None,
+            },
+        );
+        self.mark(hash, Stage::Received, now);
+    }
+
+    /// Marks the block and its tracked ancestors as having reached the stage.
+    fn mark_final(&mut self, mut hash: CryptoHash, stage: Stage, now: DateTime<Utc>) {
+        while self.mark(hash, stage, now) {
+            hash = self.blocks.peek(&hash).unwrap().prev_hash;
+        }
+    }
+
+    /// Marks a single block as having reached the stage.  Returns false if the
+    /// block isn't tracked or has reached the stage before.
+    fn mark(&mut self, hash: CryptoHash, stage: Stage, now: DateTime<Utc>) -> bool {
+        let block = match self.blocks.peek_mut(&hash) {
+            Some(block) => block,
+            None => return false,
+        };
+        let time = block.stage_mut(stage);
+        if time.is_some() {
+            return false;
+        }
+        *time = Some(now);
+        let delay = block.delay_millis(Some(now)).unwrap();
+        let max_window = to_chrono(self.windows.iter().max().copied().unwrap_or_default());
+        let samples = match stage {
+            Stage::Received => &mut self.received,
+            Stage::DoomslugFinal => &mut self.doomslug_final,
+            Stage::FullFinal => &mut self.full_final,
+        };
+        samples.push_back((now, delay));
+        while let Some((time, _)) = samples.front() {
+            if now - *time <= max_window {
+                break;
+            }
+            samples.pop_front();
+        }
+        true
+    }
+
+    pub fn view(&self, now: DateTime<Utc>) -> FinalitySlaView {
+        let windows = self
+            .windows
+            .iter()
+            .map(|window| FinalitySlaWindowView {
+                window_secs: window.as_secs(),
+                received: stage_stats(&self.received, now, *window),
+                doomslug_final: stage_stats(&self.doomslug_final, now, *window),
+                full_final: stage_stats(&self.full_final, now, *window),
+            })
+            .collect();
+        let mut recent_blocks: Vec<_> = self
+            .blocks
+            .iter()
+            .map(|(hash, block)| BlockFinalityView {
+                block_hash: *hash,
+                height: block.height,
+                produced_timestamp: block.produced,
+                received_millis: block.delay_millis(block.received),
+                doomslug_final_millis: block.delay_millis(block.doomslug_final),
+                full_final_millis: block.delay_millis(block.full_final),
+            })
+            .collect();
+        recent_blocks.sort_by(|a, b| b.height.cmp(&a.height));
+        recent_blocks.truncate(RECENT_BLOCKS_TO_SHOW);
+        FinalitySlaView { windows, recent_blocks }
+    }
+}
+
+fn to_chrono(duration: Duration) -> chrono::Duration {
+    chrono::Duration::from_std(duration).unwrap_or_else(|_| chrono::Duration::max_value())
+}
+
+fn stage_stats(
+    samples: &VecDeque<(DateTime<Utc>, u64)>,
+    now: DateTime<Utc>,
+    window: Duration,
+) -> FinalityStageStats {
+    let window = to_chrono(window);
+    let mut delays: Vec<u64> =
+        samples.iter().filter(|(time, _)| now - *time <= window).map(|(_, delay)| *delay).collect();
+    if delays.is_empty() {
+        return FinalityStageStats::default();
+    }
+    delays.sort_unstable();
+    let percentile = |p: usize| delays[(delays.len() - 1) * p / 100];
+    FinalityStageStats {
+        num_blocks: delays.len(),
+        p50_millis: percentile(50),
+        p90_millis: percentile(90),
+        p99_millis: percentile(99),
+        max_millis: *delays.last().unwrap(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{FinalityTracker, Stage};
+    use chrono::{DateTime, Utc};
+    use near_client_primitives::debug::FinalityStageStats;
+    use near_primitives::hash::CryptoHash;
+    use std::time::Duration;
+
+    fn at(millis: i64) -> DateTime<Utc> {
+        DateTime::<Utc>::from_utc(chrono::NaiveDateTime::from_timestamp(0, 0), Utc)
+            + chrono::Duration::milliseconds(millis)
+    }
+
+    fn block_hash(height: u64) -> CryptoHash {
+        CryptoHash::hash_borsh(&height)
+    }
+
+    /// Tracks a chain of blocks produced every second, each received 100ms
+    /// after production.
+    fn tracker_with_chain(num_blocks: u64) -> FinalityTracker {
+        let mut tracker =
+            FinalityTracker::new(vec![Duration::from_secs(2), Duration::from_secs(60)]);
+        for height in 1..=num_blocks {
+            let produced = at(height as i64 * 1000);
+            tracker.track(
+                block_hash(height),
+                block_hash(height - 1),
+                height,
+                produced,
+                produced + chrono::Duration::milliseconds(100),
+            );
+        }
+        tracker
+    }
+
+    #[test]
+    fn test_finality_marks_ancestors() {
+        let mut tracker = tracker_with_chain(5);
+        tracker.mark_final(block_hash(3), Stage::DoomslugFinal, at(4500));
+        tracker.mark_final(block_hash(4), Stage::DoomslugFinal, at(5500));
+        tracker.mark_final(block_hash(2), Stage::FullFinal, at(5500));
+
+        let view = tracker.view(at(5500));
+        let blocks: Vec<_> = view
+            .recent_blocks
+            .iter()
+            .map(|block| (block.height, block.doomslug_final_millis, block.full_final_millis))
+            .collect();
+        assert_eq!(
+            blocks,
+            vec![
+                (5, None, None),
+                (4, Some(1500), None),
+                (3, Some(1500), None),
+                (2, Some(2500), Some(3500)),
+                (1, Some(3500), Some(4500)),
+            ]
+        );
+        assert!(view.recent_blocks.iter().all(|block| block.received_millis == Some(100)));
+    }
+
+    #[test]
+    fn test_finality_windows() {
+        let mut tracker = tracker_with_chain(3);
+        tracker.mark_final(block_hash(1), Stage::FullFinal, at(2000));
+        tracker.mark_final(block_hash(3), Stage::FullFinal, at(5000));
+
+        let view = tracker.view(at(5000));
+        assert_eq!(view.windows[0].window_secs, 2);
+        assert_eq!(
+            view.windows[0].full_final,
+            FinalityStageStats {
+                num_blocks: 2,
+                p50_millis: 2000,
+                p90_millis: 2000,
+                p99_millis: 2000,
+                max_millis: 3000
+            }
+        );
+        assert_eq!(view.windows[1].full_final.num_blocks, 3);
+        assert_eq!(view.windows[1].full_final.p50_millis, 2000);
+        assert_eq!(view.windows[1].full_final.max_millis, 3000);
+        assert_eq!(view.windows[1].doomslug_final, FinalityStageStats::default());
+    }
+}
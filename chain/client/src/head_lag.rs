@@ -0,0 +1,163 @@
+//! Detection of a head lagging behind the heights reported by the peers.
+//!
+//! Every connected peer reports the height of its head.  The lag of the node
+//! is the number of heights its head is behind the median and the 95th
+//! percentile of these heights.  A single peer reporting a bogus height moves
+//! neither, and the lag shows up long before users notice stale data.
+//!
+//! The node starts lagging once the lag behind the median stays at
+//! `alert_lag` or above for `alert_after`, and stops lagging only once the lag
+//! drops below `clear_lag`.  While it's lagging, the node syncs even if the
+//! peer it checks against isn't far enough ahead to start syncing otherwise.
+use std::time::Instant;
+
+use near_chain_configs::HeadLagConfig;
+use near_primitives::types::BlockHeight;
+use near_primitives::views::HeadLagView;
+use tracing::{info, warn};
+
+use crate::metrics::ClientMetrics;
+
+pub(crate) struct HeadLagDetector {
+    config: HeadLagConfig,
+    /// Since when the lag has been at `alert_lag` or above.
+    lag_since: Option<Instant>,
+    lagging: bool,
+    last: Option<HeadLagView>,
+}
+
+/// Height at the percentile of sorted heights, rounding down.
+fn percentile(sorted: &[BlockHeight], percentile: usize) -> BlockHeight {
+    sorted[(sorted.len() - 1) * percentile / 100]
+}
+
+impl HeadLagDetector {
+    pub fn new(config: HeadLagConfig) -> Self {
+        Self { config, lag_since: None, lagging: false, last: None }
+    }
+
+    /// Whether the head persistently lags behind the peers.
+    pub fn is_lagging(&self) -> bool {
+        self.lagging
+    }
+
+    /// Lag computed by the last update, if any peers reported their heights.
+    pub fn view(&self) -> Option<HeadLagView> {
+        self.last.clone()
+    }
+
+    /// Updates the lag with the heights currently reported by the peers.
+    pub fn update(
+        &mut self,
+        head_height: BlockHeight,
+        mut peer_heights: Vec<BlockHeight>,
+        now: Instant,
+        metrics: &ClientMetrics,
+    ) {
+        if peer_heights.is_empty() {
+            // Without peers there is nothing to lag behind, nor to sync from.
+            self.lag_since = None;
+            self.set_lagging(false, 0);
+            self.last = None;
+            return;
+        }
+        peer_heights.sort_unstable();
+        let peer_height_p50 = percentile(&peer_heights, 50);
+        let peer_height_p95 = percentile(&peer_heights, 95);
+        let lag_p50 = peer_height_p50.saturating_sub(head_height);
+        let lag_p95 = peer_height_p95.saturating_sub(head_height);
+        metrics.head_lag.with_label_values(&["p50"]).set(lag_p50 as i64);
+        metrics.head_lag.with_label_values(&["p95"]).set(lag_p95 as i64);
+
+        if lag_p50 >= self.config.alert_lag {
+            let lag_since = *self.lag_since.get_or_insert(now);
+            if !self.lagging && now.saturating_duration_since(lag_since) >= self.config.alert_after
+            {
+                warn!(
+                    target: "client",
+                    head_height,
+                    peer_height_p50,
+                    peer_height_p95,
+                    "Head lags behind the peers, starting sync",
+                );
+                metrics.head_lag_alerts_total.inc();
+                self.set_lagging(true, lag_p50);
+            }
+        } else {
+            self.lag_since = None;
+            if lag_p50 < self.config.clear_lag {
+                self.set_lagging(false, lag_p50);
+            }
+        }
+        metrics.head_lagging.set(self.lagging as i64);
+
+        self.last = Some(HeadLagView {
+            head_height,
+            num_peers: peer_heights.len(),
+            peer_height_p50,
+            peer_height_p95,
+            lag_p50,
+            lag_p95,
+            lagging: self.lagging,
+        });
+    }
+
+    fn set_lagging(&mut self, lagging: bool, lag: u64) {
+        if self.lagging && !lagging {
+            info!(target: "client", lag, "Head caught up with the peers");
+        }
+        self.lagging = lagging;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::HeadLagDetector;
+    use crate::metrics::ClientMetrics;
+    use near_chain_configs::HeadLagConfig;
+    use near_o11y::metrics::MetricsRegistry;
+    use std::time::{Duration, Instant};
+
+    #[test]
+    fn test_hysteresis() {
+        let metrics = MetricsRegistry::instance("test_head_lag").get::<ClientMetrics>();
+        let config =
+            HeadLagConfig { alert_lag: 10, alert_after: Duration::from_secs(30), clear_lag: 3 };
+        let mut detector = HeadLagDetector::new(config);
+        let start = Instant::now();
+        let at = |secs| start + Duration::from_secs(secs);
+        // A single peer far ahead moves neither the median nor the lag.
+        let peers = |height| vec![height, height, height, height + 1000];
+
+        detector.update(100, peers(105), at(0), &metrics);
+        let view = detector.view().unwrap();
+        assert_eq!((view.peer_height_p50, view.lag_p50, view.num_peers), (105, 5, 4));
+        assert_eq!((view.peer_height_p95, view.lag_p95), (105, 5));
+
+        // The lag has to persist before the node is lagging.
+        detector.update(100, peers(110), at(10), &metrics);
+        detector.update(100, peers(115), at(30), &metrics);
+        assert!(!detector.is_lagging());
+        detector.update(100, peers(115), at(40), &metrics);
+        assert!(detector.is_lagging());
+        assert!(detector.view().unwrap().lagging);
+        assert_eq!(metrics.head_lagging.get(), 1);
+        assert_eq!(metrics.head_lag_alerts_total.get(), 1);
+
+        // Catching up part of the way doesn't clear it.
+        detector.update(110, peers(115), at(50), &metrics);
+        assert!(detector.is_lagging());
+        detector.update(113, peers(115), at(60), &metrics);
+        assert!(!detector.is_lagging());
+        assert_eq!(metrics.head_lagging.get(), 0);
+
+        // A short spike of lag isn't reported.
+        detector.update(113, peers(125), at(70), &metrics);
+        detector.update(120, peers(125), at(80), &metrics);
+        detector.update(120, peers(130), at(90), &metrics);
+        assert!(!detector.is_lagging());
+
+        detector.update(120, vec![], at(100), &metrics);
+        assert_eq!(detector.view(), None);
+    }
+}
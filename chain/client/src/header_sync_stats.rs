@@ -0,0 +1,259 @@
+//! Measures how fast peers deliver headers during header sync.
+//!
+//! With fixed timeouts and a fixed expected rate of progress, a node behind a
+//! slow link gives up on a peer before its response arrives, switches to
+//! another peer and then gives up on that one as well.  In the adaptive mode
+//! header sync instead waits for each response about as long as the peer
+//! took to deliver a batch of headers before, expects batches of the size the
+//! peer actually returns, and keeps syncing from the same peer for as long as
+//! it responds in time.  When it has to switch, peers which weren't asked yet
+//! are tried first, and then the fastest one is picked.
+use std::collections::HashMap;
+use std::time::{Duration, Instant};
+
+use near_network::types::FullPeerInfo;
+use near_primitives::network::PeerId;
+use near_primitives::views::HeaderSyncPeerStatsView;
+use rand::seq::SliceRandom;
+use rand::thread_rng;
+
+use crate::sync::MAX_BLOCK_HEADERS;
+
+/// Weight of the latest sample in the moving average of the throughput.
+const THROUGHPUT_SMOOTHING: f64 = 0.5;
+
+struct PeerStats {
+    num_requests: u64,
+    num_responses: u64,
+    num_headers: u64,
+    num_stalls: u64,
+    /// Moving average of the headers delivered per second.
+    headers_per_second: Option<f64>,
+    /// Number of headers the peer returns in a single response.
+    batch_size: u64,
+    /// When headers were requested, if the response is still awaited.
+    requested_at: Option<Instant>,
+    /// Whether the peer didn't respond in time to the latest request.
+    stalled: bool,
+}
+
+impl Default for PeerStats {
+    fn default() -> Self {
+        Self {
+            num_requests: 0,
+            num_responses: 0,
+            num_headers: 0,
+            num_stalls: 0,
+            headers_per_second: None,
+            batch_size: MAX_BLOCK_HEADERS,
+            requested_at: None,
+            stalled: false,
+        }
+    }
+}
+
+impl PeerStats {
+    /// Score used to pick the fastest peer.  Stalls reduce it.
+    fn score(&self) -> f64 {
+        self.headers_per_second.unwrap_or(0.0) / (1 + self.num_stalls) as f64
+    }
+}
+
+#[derive(Default)]
+pub(crate) struct HeaderSyncStats {
+    peers: HashMap<PeerId, PeerStats>,
+    /// Peer headers were requested from most recently.
+    syncing_peer: Option<PeerId>,
+}
+
+impl HeaderSyncStats {
+    pub fn record_request(&mut self, peer_id: &PeerId, now: Instant) {
+        let stats = self.peers.entry(peer_id.clone()).or_default();
+        stats.num_requests += 1;
+        stats.requested_at = Some(now);
+        self.syncing_peer = Some(peer_id.clone());
+    }
+
+    /// Records that `peer_id` sent `num_headers` headers.  `truncated` tells
+    /// whether the peer had more headers than it sent, in which case the
+    /// number of headers is the size of the batches it returns.
+    pub fn record_response(
+        &mut self,
+        peer_id: &PeerId,
+        num_headers: u64,
+        truncated: bool,
+        now: Instant,
+    ) {
+        let stats = match self.peers.get_mut(peer_id) {
+            Some(stats) => stats,
+            // Headers nobody asked for don't tell anything.
+            None => return,
+        };
+        stats.num_responses += 1;
+        stats.num_headers += num_headers;
+        stats.stalled = false;
+        if truncated && num_headers > 0 {
+            stats.batch_size = num_headers;
+        }
+        // The short response at the end of the chain says more about the
+        // round trip than about the throughput.
+        if let (Some(requested_at), true) = (stats.requested_at.take(), truncated) {
+            let elapsed = now.saturating_duration_since(requested_at).as_secs_f64().max(0.001);
+            let sample = num_headers as f64 / elapsed;
+            stats.headers_per_second = Some(match stats.headers_per_second {
+                Some(average) => {
+                    THROUGHPUT_SMOOTHING * sample + (1.0 - THROUGHPUT_SMOOTHING) * average
+                }
+                None => sample,
+            });
+        }
+    }
+
+    /// Records that `peer_id` didn't respond in time.
+    pub fn record_stall(&mut self, peer_id: &PeerId) {
+        if let Some(stats) = self.peers.get_mut(peer_id) {
+            stats.num_stalls += 1;
+            stats.stalled = true;
+            stats.requested_at = None;
+        }
+    }
+
+    /// Number of headers `peer_id` is expected to return in a response.
+    pub fn batch_size(&self, peer_id: &PeerId) -> u64 {
+        self.peers.get(peer_id).map_or(MAX_BLOCK_HEADERS, |stats| stats.batch_size)
+    }
+
+    /// How long it took `peer_id` to deliver a batch of headers.  None if it
+    /// hasn't delivered a full batch yet.
+    pub fn batch_time(&self, peer_id: &PeerId) -> Option<Duration> {
+        let stats = self.peers.get(peer_id)?;
+        let headers_per_second = stats.headers_per_second?;
+        Some(Duration::from_secs_f64(stats.batch_size as f64 / headers_per_second.max(0.001)))
+    }
+
+    /// Picks the peer to request headers from, out of `peers` at the highest
+    /// height.  The previous peer is kept unless it stalled.  Otherwise peers
+    /// which weren't asked yet go first, followed by the fastest one.
+    pub fn choose_peer(&self, peers: &[FullPeerInfo]) -> Option<FullPeerInfo> {
+        if let Some(syncing_peer) = &self.syncing_peer {
+            let stalled = self.peers.get(syncing_peer).map_or(false, |stats| stats.stalled);
+            if let Some(peer) = peers.iter().find(|peer| &peer.peer_info.id == syncing_peer) {
+                if !stalled {
+                    return Some(peer.clone());
+                }
+            }
+        }
+        let unknown: Vec<_> =
+            peers.iter().filter(|peer| !self.peers.contains_key(&peer.peer_info.id)).collect();
+        if let Some(peer) = unknown.choose(&mut thread_rng()) {
+            return Some((*peer).clone());
+        }
+        peers
+            .iter()
+            .max_by(|a, b| {
+                let score = |peer: &FullPeerInfo| self.peers[&peer.peer_info.id].score();
+                score(a).total_cmp(&score(b))
+            })
+            .cloned()
+    }
+
+    pub fn syncing_peer(&self) -> Option<&PeerId> {
+        self.syncing_peer.as_ref()
+    }
+
+    /// Statistics of all peers, the fastest first.
+    pub fn view(&self) -> Vec<HeaderSyncPeerStatsView> {
+        let mut peers: Vec<_> = self.peers.iter().collect();
+        peers.sort_by(|(_, a), (_, b)| b.score().total_cmp(&a.score()));
+        peers
+            .into_iter()
+            .map(|(peer_id, stats)| HeaderSyncPeerStatsView {
+                peer_id: peer_id.clone(),
+                num_requests: stats.num_requests,
+                num_responses: stats.num_responses,
+                num_headers: stats.num_headers,
+                num_stalls: stats.num_stalls,
+                headers_per_second: stats.headers_per_second.map(|value| value.round() as u64),
+                batch_size: stats.batch_size,
+            })
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::HeaderSyncStats;
+    use crate::sync::MAX_BLOCK_HEADERS;
+    use near_network::test_utils::peer_id_from_seed;
+    use near_network::types::{FullPeerInfo, PartialEdgeInfo, PeerInfo};
+    use near_primitives::network::PeerId;
+    use std::time::{Duration, Instant};
+
+    fn peer(peer_id: &PeerId) -> FullPeerInfo {
+        FullPeerInfo {
+            peer_info: PeerInfo { id: peer_id.clone(), addr: None, account_id: None },
+            chain_info: Default::default(),
+            partial_edge_info: PartialEdgeInfo::default(),
+            state_sub_part_size_limit: 0,
+        }
+    }
+
+    #[test]
+    fn test_measure_peers() {
+        let slow = peer_id_from_seed("slow");
+        let fast = peer_id_from_seed("fast");
+        let mut stats = HeaderSyncStats::default();
+        let start = Instant::now();
+
+        stats.record_request(&slow, start);
+        stats.record_response(&slow, 100, true, start + Duration::from_secs(10));
+        assert_eq!(stats.batch_size(&slow), 100);
+        assert_eq!(stats.batch_time(&slow), Some(Duration::from_secs(10)));
+
+        stats.record_request(&fast, start);
+        stats.record_response(&fast, MAX_BLOCK_HEADERS, true, start + Duration::from_secs(1));
+        // The last batch is shorter and doesn't change the measurements.
+        stats.record_request(&fast, start);
+        stats.record_response(&fast, 10, false, start + Duration::from_secs(1));
+        assert_eq!(stats.batch_size(&fast), MAX_BLOCK_HEADERS);
+        assert_eq!(stats.batch_time(&fast), Some(Duration::from_secs(1)));
+
+        let view = stats.view();
+        assert_eq!(view.len(), 2);
+        assert_eq!(view[0].peer_id, fast);
+        assert_eq!(view[0].num_requests, 2);
+        assert_eq!(view[0].num_headers, MAX_BLOCK_HEADERS + 10);
+        assert_eq!(view[0].headers_per_second, Some(MAX_BLOCK_HEADERS));
+        assert_eq!(view[1].headers_per_second, Some(10));
+    }
+
+    #[test]
+    fn test_choose_peer() {
+        let slow = peer_id_from_seed("slow");
+        let fast = peer_id_from_seed("fast");
+        let other = peer_id_from_seed("other");
+        let peers = vec![peer(&slow), peer(&fast)];
+        let mut stats = HeaderSyncStats::default();
+        let start = Instant::now();
+        stats.record_request(&fast, start);
+        stats.record_response(&fast, 100, true, start + Duration::from_secs(1));
+        assert_eq!(stats.choose_peer(&peers).unwrap().peer_info.id, fast);
+
+        // After a stall, peers which weren't asked yet are tried first.
+        stats.record_stall(&fast);
+        assert_eq!(stats.choose_peer(&peers).unwrap().peer_info.id, slow);
+        stats.record_request(&slow, start);
+        stats.record_response(&slow, 100, true, start + Duration::from_secs(10));
+
+        // The same peer is kept as long as it responds in time, even if it's slower.
+        assert_eq!(stats.choose_peer(&peers).unwrap().peer_info.id, slow);
+
+        // Then the fastest peer is picked.
+        stats.record_stall(&slow);
+        assert_eq!(stats.choose_peer(&peers).unwrap().peer_info.id, fast);
+
+        // Peers which don't have the highest height are never picked.
+        assert!(stats.choose_peer(&[]).is_none());
+        assert_eq!(stats.choose_peer(&[peer(&other)]).unwrap().peer_info.id, other);
+    }
+}
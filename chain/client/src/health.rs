@@ -0,0 +1,191 @@
+//! Readiness and liveness verdicts of the node.
+//!
+//! Orchestrators such as Kubernetes probe a node for two different things: a
+//! node which isn't ready shouldn't be sent requests, while a node which isn't
+//! live should be restarted.  The node is ready when it isn't syncing, its head
+//! is recent, it has enough peers, its storage isn't degraded, it processes
+//! blocks quickly enough and, if the configuration requires it, it has a
+//! validator key.  A node draining before it's stopped isn't ready either.
+//! The rest recover on their own, so liveness only fails when the head hasn't
+//! advanced for a long time while peers are ahead of it and the node doesn't
+//! even sync.  If no peer is ahead the whole network may be halted, and
+//! restarting every node at once wouldn't help.  A client actor too busy to
+//! answer fails both probes by timing out.
+//!
+//! Every verdict comes with the reasons it failed, so that operators don't
+//! have to work them out from the status of the node.
+use std::time::Duration;
+
+use near_chain_configs::HealthConfig;
+use near_primitives::types::BlockHeight;
+use near_primitives::views::{HealthReasonView, HealthView};
+
+/// State of the node the verdicts are based on.
+pub(crate) struct HealthInputs {
+    pub is_syncing: bool,
+    /// Time since the timestamp of the head block.
+    pub head_age: Duration,
+    /// Time since the head was last updated.
+    pub time_since_head_updated: Duration,
+    pub head_height: BlockHeight,
+    /// Highest head height among the connected peers, None without peers.
+    pub highest_peer_height: Option<BlockHeight>,
+    pub num_peers: usize,
+    pub has_validator_signer: bool,
+    pub storage_degraded: bool,
+    pub average_block_processing_time: Option<Duration>,
+    pub draining: bool,
+}
+
+fn millis(duration: Duration) -> u64 {
+    duration.as_millis() as u64
+}
+
+pub(crate) fn evaluate(config: &HealthConfig, inputs: &HealthInputs) -> HealthView {
+    let mut readiness_failures = vec![];
+    if inputs.is_syncing {
+        readiness_failures.push(HealthReasonView::Syncing);
+    }
+    if inputs.head_age > config.max_head_age {
+        readiness_failures.push(HealthReasonView::StaleHead {
+            head_age_ms: millis(inputs.head_age),
+            max_head_age_ms: millis(config.max_head_age),
+        });
+    }
+    if inputs.num_peers < config.min_peers {
+        readiness_failures.push(HealthReasonView::NotEnoughPeers {
+            num_peers: inputs.num_peers,
+            min_peers: config.min_peers,
+        });
+    }
+    if config.require_validator_signer && !inputs.has_validator_signer {
+        readiness_failures.push(HealthReasonView::NoValidatorSigner);
+    }
+    if inputs.storage_degraded {
+        readiness_failures.push(HealthReasonView::StorageDegraded);
+    }
+    if inputs.draining {
+        readiness_failures.push(HealthReasonView::Draining);
+    }
+    if let Some(time) = inputs.average_block_processing_time {
+        if time > config.max_block_processing_time {
+            readiness_failures.push(HealthReasonView::SlowBlockProcessing {
+                block_processing_time_ms: millis(time),
+                max_block_processing_time_ms: millis(config.max_block_processing_time),
+            });
+        }
+    }
+
+    let mut liveness_failures = vec![];
+    let peers_ahead = inputs.highest_peer_height.map_or(false, |h| h > inputs.head_height);
+    if !inputs.is_syncing && peers_ahead && inputs.time_since_head_updated > config.max_head_stall {
+        liveness_failures.push(HealthReasonView::HeadStalled {
+            stalled_for_ms: millis(inputs.time_since_head_updated),
+            max_head_stall_ms: millis(config.max_head_stall),
+        });
+    }
+
+    HealthView {
+        ready: readiness_failures.is_empty(),
+        live: liveness_failures.is_empty(),
+        readiness_failures,
+        liveness_failures,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{evaluate, HealthInputs};
+    use near_chain_configs::HealthConfig;
+    use near_primitives::views::HealthReasonView;
+    use std::time::Duration;
+
+    fn healthy() -> HealthInputs {
+        HealthInputs {
+            is_syncing: false,
+            head_age: Duration::from_secs(1),
+            time_since_head_updated: Duration::from_secs(1),
+            head_height: 100,
+            highest_peer_height: Some(100),
+            num_peers: 10,
+            has_validator_signer: false,
+            storage_degraded: false,
+            average_block_processing_time: Some(Duration::from_millis(300)),
+            draining: false,
+        }
+    }
+
+    #[test]
+    fn test_healthy() {
+        let health = evaluate(&HealthConfig::default(), &healthy());
+        assert!(health.ready && health.live, "{:?}", health);
+        let inputs = HealthInputs { average_block_processing_time: None, ..healthy() };
+        assert!(evaluate(&HealthConfig::default(), &inputs).ready);
+    }
+
+    #[test]
+    fn test_not_ready() {
+        let config = HealthConfig { require_validator_signer: true, ..HealthConfig::default() };
+        let inputs = HealthInputs {
+            is_syncing: true,
+            head_age: Duration::from_secs(3600),
+            time_since_head_updated: Duration::from_secs(3600),
+            num_peers: 0,
+            storage_degraded: true,
+            average_block_processing_time: Some(Duration::from_secs(5)),
+            draining: true,
+            ..healthy()
+        };
+        let health = evaluate(&config, &inputs);
+        assert!(!health.ready);
+        // Syncing nodes make progress even though their head doesn't advance.
+        assert!(health.live);
+        assert_eq!(
+            health.readiness_failures,
+            vec![
+                HealthReasonView::Syncing,
+                HealthReasonView::StaleHead { head_age_ms: 3_600_000, max_head_age_ms: 30_000 },
+                HealthReasonView::NotEnoughPeers { num_peers: 0, min_peers: 1 },
+                HealthReasonView::NoValidatorSigner,
+                HealthReasonView::StorageDegraded,
+                HealthReasonView::Draining,
+                HealthReasonView::SlowBlockProcessing {
+                    block_processing_time_ms: 5000,
+                    max_block_processing_time_ms: 2000,
+                },
+            ]
+        );
+    }
+
+    #[test]
+    fn test_not_live() {
+        let inputs = HealthInputs {
+            time_since_head_updated: Duration::from_secs(3600),
+            highest_peer_height: Some(200),
+            ..healthy()
+        };
+        let health = evaluate(&HealthConfig::default(), &inputs);
+        assert!(health.ready);
+        assert_eq!(
+            health.liveness_failures,
+            vec![HealthReasonView::HeadStalled {
+                stalled_for_ms: 3_600_000,
+                max_head_stall_ms: 600_000
+            }]
+        );
+    }
+
+    #[test]
+    fn test_network_halt_is_live() {
+        // Nothing to catch up with, restarting wouldn't help.
+        for highest_peer_height in [None, Some(100), Some(90)] {
+            let inputs = HealthInputs {
+                time_since_head_updated: Duration::from_secs(3600),
+                highest_peer_height,
+                ..healthy()
+            };
+            let health = evaluate(&HealthConfig::default(), &inputs);
+            assert!(health.live, "{:?}", health);
+        }
+    }
+}
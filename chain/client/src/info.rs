@@ -1,8 +1,10 @@
+use crate::metrics::ClientMetrics;
 use crate::{metrics, rocksdb_metrics, SyncStatus};
 use actix::Addr;
 use itertools::Itertools;
 use near_chain_configs::{ClientConfig, LogSummaryStyle};
 use near_network::types::NetworkInfo;
+use near_o11y::metrics::MetricsRegistry;
 use near_primitives::block::Tip;
 use near_primitives::network::PeerId;
 use near_primitives::telemetry::{
@@ -57,6 +59,7 @@ pub struct InfoHelper {
     log_summary_style: LogSummaryStyle,
     /// Timestamp of starting the client.
     pub boot_time_seconds: i64,
+    metrics: Arc<ClientMetrics>,
 }
 
 impl InfoHelper {
@@ -79,18 +82,20 @@ impl InfoHelper {
             validator_signer,
             log_summary_style: client_config.log_summary_style,
             boot_time_seconds: Clock::utc().timestamp(),
+            metrics: MetricsRegistry::for_instance(client_config.metrics_instance.as_deref()).get(),
         }
     }
 
     pub fn chunk_processed(&mut self, shard_id: ShardId, gas_used: Gas, balance_burnt: Balance) {
-        metrics::TGAS_USAGE_HIST
+        self.metrics
+            .tgas_usage_hist
             .with_label_values(&[&shard_id.to_string()])
             .observe(gas_used as f64 / TERAGAS);
-        metrics::BALANCE_BURNT.inc_by(balance_burnt as f64);
+        self.metrics.balance_burnt.inc_by(balance_burnt as f64);
     }
 
     pub fn chunk_skipped(&mut self, shard_id: ShardId) {
-        metrics::CHUNK_SKIPPED_TOTAL.with_label_values(&[&shard_id.to_string()]).inc();
+        self.metrics.chunk_skipped_total.with_label_values(&[&shard_id.to_string()]).inc();
     }
 
     pub fn block_processed(
@@ -106,14 +111,14 @@ impl InfoHelper {
         self.num_blocks_processed += 1;
         self.num_chunks_in_blocks_processed += num_chunks;
         self.gas_used += gas_used;
-        metrics::GAS_USED.inc_by(gas_used as f64);
-        metrics::BLOCKS_PROCESSED.inc();
-        metrics::CHUNKS_PROCESSED.inc_by(num_chunks);
-        metrics::GAS_PRICE.set(gas_price as f64);
-        metrics::TOTAL_SUPPLY.set(total_supply as f64);
-        metrics::FINAL_BLOCK_HEIGHT.set(last_final_block_height as i64);
-        metrics::FINAL_DOOMSLUG_BLOCK_HEIGHT.set(last_final_ds_block_height as i64);
-        metrics::EPOCH_HEIGHT.set(epoch_height as i64);
+        self.metrics.gas_used.inc_by(gas_used as f64);
+        self.metrics.blocks_processed.inc();
+        self.metrics.chunks_processed.inc_by(num_chunks);
+        self.metrics.gas_price.set(gas_price as f64);
+        self.metrics.total_supply.set(total_supply as f64);
+        self.metrics.final_block_height.set(last_final_block_height as i64);
+        self.metrics.final_doomslug_block_height.set(last_final_ds_block_height as i64);
+        self.metrics.epoch_height.set(epoch_height as i64);
     }
 
     pub fn info(
@@ -138,6 +143,7 @@ impl InfoHelper {
 
         let s = |num| if num == 1 { "" } else { "s" };
 
+        self.metrics.sync_status.set(sync_status.repr() as i64);
         let sync_status_log = Some(display_sync_status(sync_status, head));
 
         let catchup_status_log = display_catchup_status(catchup_status);
@@ -198,35 +204,43 @@ impl InfoHelper {
 
         let (cpu_usage, memory_usage) = proc_info.unwrap_or_default();
         let is_validator = validator_info.map(|v| v.is_validator).unwrap_or_default();
-        (metrics::IS_VALIDATOR.set(is_validator as i64));
-        (metrics::RECEIVED_BYTES_PER_SECOND.set(network_info.received_bytes_per_sec as i64));
-        (metrics::SENT_BYTES_PER_SECOND.set(network_info.sent_bytes_per_sec as i64));
+        (self.metrics.is_validator.set(is_validator as i64));
+        (self.metrics.received_bytes_per_second.set(network_info.received_bytes_per_sec as i64));
+        (self.metrics.sent_bytes_per_second.set(network_info.sent_bytes_per_sec as i64));
         (metrics::CPU_USAGE.set(cpu_usage as i64));
         (metrics::MEMORY_USAGE.set((memory_usage * 1024) as i64));
-        (metrics::PROTOCOL_UPGRADE_BLOCK_HEIGHT.set(protocol_upgrade_block_height as i64));
+        (self.metrics.protocol_upgrade_block_height.set(protocol_upgrade_block_height as i64));
 
         // TODO: Deprecated.
-        (metrics::BLOCKS_PER_MINUTE.set((avg_bls * (60 as f64)) as i64));
+        (self.metrics.blocks_per_minute.set((avg_bls * (60 as f64)) as i64));
         // TODO: Deprecated.
-        (metrics::CHUNKS_PER_BLOCK_MILLIS.set((1000. * chunks_per_block) as i64));
+        (self.metrics.chunks_per_block_millis.set((1000. * chunks_per_block) as i64));
         // TODO: Deprecated.
-        (metrics::AVG_TGAS_USAGE.set((avg_gas_used as f64 / TERAGAS).round() as i64));
+        (self.metrics.avg_tgas_usage.set((avg_gas_used as f64 / TERAGAS).round() as i64));
 
         // In case we can't get the list of validators for the current and the previous epoch,
         // skip updating the per-validator metrics.
         // Note that the metrics are set to 0 for previous epoch validators who are no longer
         // validators.
         for stats in validator_epoch_stats {
-            (metrics::VALIDATORS_BLOCKS_PRODUCED
+            (self
+                .metrics
+                .validators_blocks_produced
                 .with_label_values(&[stats.account_id.as_str()])
                 .set(stats.num_produced_blocks as i64));
-            (metrics::VALIDATORS_BLOCKS_EXPECTED
+            (self
+                .metrics
+                .validators_blocks_expected
                 .with_label_values(&[stats.account_id.as_str()])
                 .set(stats.num_expected_blocks as i64));
-            (metrics::VALIDATORS_CHUNKS_PRODUCED
+            (self
+                .metrics
+                .validators_chunks_produced
                 .with_label_values(&[stats.account_id.as_str()])
                 .set(stats.num_produced_chunks as i64));
-            (metrics::VALIDATORS_CHUNKS_EXPECTED
+            (self
+                .metrics
+                .validators_chunks_expected
                 .with_label_values(&[stats.account_id.as_str()])
                 .set(stats.num_expected_chunks as i64));
         }
@@ -344,7 +358,6 @@ pub fn display_catchup_status(catchup_status: Vec<CatchupStatusView>) -> String
 }
 
 pub fn display_sync_status(sync_status: &SyncStatus, head: &Tip) -> String {
-    metrics::SYNC_STATUS.set(sync_status.repr() as i64);
     match sync_status {
         SyncStatus::AwaitingPeers => format!("#{:>8} Waiting for peers", head.height),
         SyncStatus::NoSync => format!("#{:>8} {:>44}", head.height, head.last_block_hash),
@@ -539,8 +552,14 @@ mod tests {
             protocol_version: PROTOCOL_VERSION,
         };
         let doomslug_threshold_mode = DoomslugThresholdMode::TwoThirds;
-        let chain =
-            Chain::new(runtime.clone(), &chain_genesis, doomslug_threshold_mode, true).unwrap();
+        let chain = Chain::new(
+            runtime.clone(),
+            &chain_genesis,
+            doomslug_threshold_mode,
+            true,
+            &near_o11y::metrics::MetricsRegistry::global(),
+        )
+        .unwrap();
 
         let telemetry = info_helper.telemetry_info(
             &chain.head().unwrap(),
@@ -555,6 +574,7 @@ mod tests {
                 received_bytes_per_sec: 0,
                 known_producers: vec![],
                 tier1_accounts: vec![],
+                bandwidth_by_type: vec![],
             },
             &config,
             0.0,
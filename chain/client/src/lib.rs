@@ -1,16 +1,22 @@
 pub use near_client_primitives::types::{
-    Error, GetBlock, GetBlockProof, GetBlockProofResponse, GetBlockWithMerkleTree, GetChunk,
-    GetExecutionOutcome, GetExecutionOutcomeResponse, GetExecutionOutcomesForBlock, GetGasPrice,
-    GetNetworkInfo, GetNextLightClientBlock, GetProtocolConfig, GetReceipt, GetStateChanges,
-    GetStateChangesInBlock, GetStateChangesWithCauseInBlock,
-    GetStateChangesWithCauseInBlockForTrackedShards, GetValidatorInfo, GetValidatorOrdered, Query,
-    QueryError, Status, StatusResponse, SyncStatus, TxStatus, TxStatusError,
+    BeginDrain, BlockProductionFailure, BlockProductionReport, Error, GetBlock,
+    GetBlockProducerProof, GetBlockProof, GetBlockProofResponse, GetBlockWithMerkleTree, GetChunk,
+    GetContractAccounts, GetContractEvents, GetEconomicsSeries, GetExecutionOutcome,
+    GetExecutionOutcomeResponse, GetExecutionOutcomesForBlock, GetGasPrice, GetHealth,
+    GetNetworkInfo, GetNextLightClientBlock, GetOutgoingReceiptProofs, GetProductionSchedule,
+    GetProtocolConfig, GetRandomness, GetReceipt, GetReceiptExecutionProof,
+    GetRuntimeParametersDiff, GetStateChanges, GetStateChangesInBlock,
+    GetStateChangesWithCauseInBlock, GetStateChangesWithCauseInBlockForTrackedShards, GetTrieDiff,
+    GetValidatorInfo, GetValidatorOrdered, Query, QueryError, SimulateBlockProduction, Status,
+    StatusResponse, SyncStatus, TxForkStatus, TxPoolCommand, TxStatus, TxStatusError,
+    UpdateTrackedShards,
 };
 
 pub use near_client_primitives::debug::DebugStatus;
 
 pub use crate::adapter::{
-    BlockApproval, BlockResponse, ProcessTxRequest, ProcessTxResponse, SetNetworkInfo,
+    BlockApproval, BlockResponse, DeferTxRequest, ProcessTxRequest, ProcessTxResponse,
+    SetNetworkInfo, TxRejectionReason,
 };
 pub use crate::client::Client;
 pub use crate::client_actor::{start_client, ClientActor};
@@ -18,14 +24,29 @@ pub use crate::view_client::{start_view_client, ViewClientActor};
 
 pub mod adapter;
 pub mod adversarial;
+mod block_skeleton;
+mod block_sync_requests;
+mod chunk_arrival;
 mod client;
 mod client_actor;
 pub mod debug;
+mod deferred_txs;
+mod finality_tracker;
+mod head_lag;
+mod header_sync_stats;
+mod health;
 mod info;
+mod message_dedup;
 mod metrics;
+mod persisted_approvals;
 mod rocksdb_metrics;
+mod state_part_cache;
+pub mod state_parts_provider;
+mod state_sub_part_sizes;
 pub mod sync;
 pub mod test_utils;
 #[cfg(test)]
 mod tests;
+mod tx_status_subscriptions;
+mod view_cache;
 mod view_client;
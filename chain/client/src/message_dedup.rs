@@ -0,0 +1,87 @@
+//! Deduplication of routed messages at the boundary between the network and
+//! the client.
+//!
+//! Routed messages, like approvals and chunk parts, often reach the node a few
+//! times over different paths, and every copy would be processed in full,
+//! signature checks included.  Copies of a message seen shortly before are
+//! dropped before they reach the client actor instead.
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+
+use borsh::BorshSerialize;
+use near_primitives::hash::CryptoHash;
+use near_primitives::time::Clock;
+
+use crate::metrics::ClientMetrics;
+
+/// Number of recently seen messages remembered.
+const SEEN_MESSAGES_CAPACITY: usize = 10_000;
+/// For how long a copy of a seen message is considered a duplicate.
+const SEEN_MESSAGES_TTL: Duration = Duration::from_secs(5);
+
+pub(crate) struct SeenMessages {
+    /// Time a message was first seen, by the hash of its content.
+    seen: Mutex<lru::LruCache<CryptoHash, Instant>>,
+    ttl: Duration,
+    metrics: Arc<ClientMetrics>,
+}
+
+impl SeenMessages {
+    pub fn new(metrics: Arc<ClientMetrics>) -> Self {
+        Self::with_limits(SEEN_MESSAGES_CAPACITY, SEEN_MESSAGES_TTL, metrics)
+    }
+
+    fn with_limits(capacity: usize, ttl: Duration, metrics: Arc<ClientMetrics>) -> Self {
+        Self { seen: Mutex::new(lru::LruCache::new(capacity)), ttl, metrics }
+    }
+
+    /// Records a message of the given type.  Returns true if the same message
+    /// was seen within the TTL, in which case it should be dropped.
+    pub fn is_duplicate(&self, msg_type: &'static str, msg: &impl BorshSerialize) -> bool {
+        self.check(msg_type, CryptoHash::hash_borsh(msg), Clock::instant())
+    }
+
+    fn check(&self, msg_type: &'static str, key: CryptoHash, now: Instant) -> bool {
+        self.metrics.routed_messages_received_total.with_label_values(&[msg_type]).inc();
+        let mut seen = self.seen.lock().unwrap();
+        let duplicate = match seen.get(&key) {
+            Some(first_seen) => now.saturating_duration_since(*first_seen) <= self.ttl,
+            None => false,
+        };
+        if duplicate {
+            self.metrics.routed_messages_duplicate_total.with_label_values(&[msg_type]).inc();
+        } else {
+            seen.put(key, now);
+        }
+        duplicate
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::SeenMessages;
+    use near_o11y::metrics::MetricsRegistry;
+    use near_primitives::hash::CryptoHash;
+    use std::time::{Duration, Instant};
+
+    #[test]
+    fn test_seen_messages() {
+        let seen =
+            SeenMessages::with_limits(2, Duration::from_secs(5), MetricsRegistry::global().get());
+        let now = Instant::now();
+        let (a, b, c) = (
+            CryptoHash::hash_bytes(b"a"),
+            CryptoHash::hash_bytes(b"b"),
+            CryptoHash::hash_bytes(b"c"),
+        );
+        assert!(!seen.check("test", a, now));
+        assert!(seen.check("test", a, now + Duration::from_secs(1)));
+        // A copy arriving after the TTL is processed again.
+        assert!(!seen.check("test", a, now + Duration::from_secs(10)));
+        assert!(!seen.check("test", b, now + Duration::from_secs(10)));
+        // Only the most recent messages are remembered.
+        assert!(!seen.check("test", c, now + Duration::from_secs(10)));
+        assert!(!seen.check("test", a, now + Duration::from_secs(11)));
+        assert!(seen.check("test", c, now + Duration::from_secs(11)));
+    }
+}
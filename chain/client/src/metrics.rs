@@ -1,61 +1,11 @@
 use near_o11y::metrics::{
-    exponential_buckets, try_create_counter, try_create_gauge, try_create_histogram,
-    try_create_histogram_vec, try_create_int_counter, try_create_int_counter_vec,
-    try_create_int_gauge, try_create_int_gauge_vec, Counter, Gauge, Histogram, HistogramVec,
-    IntCounter, IntCounterVec, IntGauge, IntGaugeVec,
+    exponential_buckets, try_create_int_counter_vec, try_create_int_gauge, Counter, Gauge,
+    Histogram, HistogramVec, IntCounter, IntCounterVec, IntGauge, IntGaugeVec, MetricSet,
+    MetricsRegistry,
 };
 use once_cell::sync::Lazy;
 
-pub(crate) static BLOCK_PRODUCED_TOTAL: Lazy<IntCounter> = Lazy::new(|| {
-    try_create_int_counter(
-        "near_block_produced_total",
-        "Total number of blocks produced since starting this node",
-    )
-    .unwrap()
-});
-
-pub(crate) static CHUNK_PRODUCED_TOTAL: Lazy<IntCounter> = Lazy::new(|| {
-    try_create_int_counter(
-        "near_chunk_produced_total",
-        "Total number of chunks produced since starting this node",
-    )
-    .unwrap()
-});
-
-pub(crate) static IS_VALIDATOR: Lazy<IntGauge> = Lazy::new(|| {
-    try_create_int_gauge("near_is_validator", "Bool to denote if it is currently validating")
-        .unwrap()
-});
-
-pub(crate) static RECEIVED_BYTES_PER_SECOND: Lazy<IntGauge> = Lazy::new(|| {
-    try_create_int_gauge(
-        "near_received_bytes_per_second",
-        "Number of bytes per second received over the network overall",
-    )
-    .unwrap()
-});
-
-pub(crate) static SENT_BYTES_PER_SECOND: Lazy<IntGauge> = Lazy::new(|| {
-    try_create_int_gauge(
-        "near_sent_bytes_per_second",
-        "Number of bytes per second sent over the network overall",
-    )
-    .unwrap()
-});
-
-// Deprecated.
-pub(crate) static BLOCKS_PER_MINUTE: Lazy<IntGauge> = Lazy::new(|| {
-    try_create_int_gauge("near_blocks_per_minute", "Blocks produced per minute").unwrap()
-});
-
-// Deprecated.
-pub(crate) static CHUNKS_PER_BLOCK_MILLIS: Lazy<IntGauge> = Lazy::new(|| {
-    try_create_int_gauge(
-        "near_chunks_per_block_millis",
-        "Average number of chunks included in blocks",
-    )
-    .unwrap()
-});
+// Metrics describing the process rather than a client stay in the global registry.
 
 pub(crate) static CPU_USAGE: Lazy<IntGauge> =
     Lazy::new(|| try_create_int_gauge("near_cpu_usage_ratio", "Percent of CPU usage").unwrap());
@@ -64,176 +14,6 @@ pub(crate) static MEMORY_USAGE: Lazy<IntGauge> = Lazy::new(|| {
     try_create_int_gauge("near_memory_usage_bytes", "Amount of RAM memory usage").unwrap()
 });
 
-pub(crate) static GC_TIME: Lazy<Histogram> = Lazy::new(|| {
-    try_create_histogram("near_gc_time", "Time taken to do garbage collection").unwrap()
-});
-
-// Deprecated.
-pub(crate) static AVG_TGAS_USAGE: Lazy<IntGauge> = Lazy::new(|| {
-    try_create_int_gauge(
-        "near_chunk_tgas_used",
-        "Number of Tgas (10^12 of gas) used by the last processed chunks",
-    )
-    .unwrap()
-});
-
-pub(crate) static TGAS_USAGE_HIST: Lazy<HistogramVec> = Lazy::new(|| {
-    try_create_histogram_vec(
-        "near_chunk_tgas_used_hist",
-        "Number of Tgas (10^12 of gas) used by processed chunks, as a histogram",
-        &["shard"],
-        Some(vec![
-            50., 100., 300., 500., 700., 800., 900., 950., 1000., 1050., 1100., 1150., 1200.,
-            1250., 1300.,
-        ]),
-    )
-    .unwrap()
-});
-
-pub(crate) static VALIDATORS_CHUNKS_PRODUCED: Lazy<IntGaugeVec> = Lazy::new(|| {
-    try_create_int_gauge_vec(
-        "near_validators_chunks_produced",
-        "Number of chunks produced by a validator",
-        &["account_id"],
-    )
-    .unwrap()
-});
-
-pub(crate) static VALIDATORS_CHUNKS_EXPECTED: Lazy<IntGaugeVec> = Lazy::new(|| {
-    try_create_int_gauge_vec(
-        "near_validators_chunks_expected",
-        "Number of chunks expected to be produced by a validator",
-        &["account_id"],
-    )
-    .unwrap()
-});
-
-pub(crate) static VALIDATORS_BLOCKS_PRODUCED: Lazy<IntGaugeVec> = Lazy::new(|| {
-    try_create_int_gauge_vec(
-        "near_validators_blocks_produced",
-        "Number of blocks produced by a validator",
-        &["account_id"],
-    )
-    .unwrap()
-});
-
-pub(crate) static VALIDATORS_BLOCKS_EXPECTED: Lazy<IntGaugeVec> = Lazy::new(|| {
-    try_create_int_gauge_vec(
-        "near_validators_blocks_expected",
-        "Number of blocks expected to be produced by a validator",
-        &["account_id"],
-    )
-    .unwrap()
-});
-
-pub(crate) static SYNC_STATUS: Lazy<IntGauge> =
-    Lazy::new(|| try_create_int_gauge("near_sync_status", "Node sync status").unwrap());
-
-pub(crate) static EPOCH_HEIGHT: Lazy<IntGauge> = Lazy::new(|| {
-    try_create_int_gauge("near_epoch_height", "Height of the epoch at the head of the blockchain")
-        .unwrap()
-});
-
-pub(crate) static PROTOCOL_UPGRADE_BLOCK_HEIGHT: Lazy<IntGauge> = Lazy::new(|| {
-    try_create_int_gauge(
-        "near_protocol_upgrade_block_height",
-        "Estimated block height of the protocol upgrade",
-    )
-    .unwrap()
-});
-
-pub(crate) static CHUNK_SKIPPED_TOTAL: Lazy<IntCounterVec> = Lazy::new(|| {
-    try_create_int_counter_vec(
-        "near_chunk_skipped_total",
-        "Number of skipped chunks",
-        &["shard_id"],
-    )
-    .unwrap()
-});
-
-pub(crate) static PARTIAL_ENCODED_CHUNK_RESPONSE_DELAY: Lazy<Histogram> = Lazy::new(|| {
-    try_create_histogram(
-        "near_partial_encoded_chunk_response_delay",
-        "Delay between when a partial encoded chunk response is sent from PeerActor and when it is received by ClientActor",
-    )
-        .unwrap()
-});
-
-pub(crate) static CLIENT_MESSAGES_COUNT: Lazy<IntCounterVec> = Lazy::new(|| {
-    try_create_int_counter_vec(
-        "near_client_messages_count",
-        "Number of messages client actor received by message type",
-        &["type"],
-    )
-    .unwrap()
-});
-
-pub(crate) static CLIENT_MESSAGES_PROCESSING_TIME: Lazy<HistogramVec> = Lazy::new(|| {
-    try_create_histogram_vec(
-        "near_client_messages_processing_time",
-        "Processing time of messages that client actor received, sorted by message type",
-        &["type"],
-        Some(exponential_buckets(0.0001, 1.6, 20).unwrap()),
-    )
-    .unwrap()
-});
-
-pub(crate) static CHECK_TRIGGERS_TIME: Lazy<Histogram> = Lazy::new(|| {
-    try_create_histogram(
-        "near_client_triggers_time",
-        "Processing time of the check_triggers function in client",
-    )
-    .unwrap()
-});
-
-pub(crate) static CLIENT_TRIGGER_TIME_BY_TYPE: Lazy<HistogramVec> = Lazy::new(|| {
-    try_create_histogram_vec(
-        "near_client_triggers_time_by_type",
-        "Time spent on the different triggers in client",
-        &["trigger"],
-        Some(exponential_buckets(0.0001, 1.6, 20).unwrap()),
-    )
-    .unwrap()
-});
-
-pub(crate) static GAS_USED: Lazy<Counter> = Lazy::new(|| {
-    try_create_counter("near_gas_used", "Gas used by processed blocks, measured in gas").unwrap()
-});
-
-pub(crate) static BLOCKS_PROCESSED: Lazy<IntCounter> = Lazy::new(|| {
-    try_create_int_counter("near_blocks_processed", "Number of processed blocks").unwrap()
-});
-
-pub(crate) static CHUNKS_PROCESSED: Lazy<IntCounter> = Lazy::new(|| {
-    try_create_int_counter("near_chunks_processed", "Number of processed chunks").unwrap()
-});
-
-pub(crate) static GAS_PRICE: Lazy<Gauge> = Lazy::new(|| {
-    try_create_gauge("near_gas_price", "Gas price of the latest processed block").unwrap()
-});
-
-pub(crate) static BALANCE_BURNT: Lazy<Counter> = Lazy::new(|| {
-    try_create_counter("near_balance_burnt", "Balance burnt by processed blocks in NEAR tokens")
-        .unwrap()
-});
-
-pub(crate) static TOTAL_SUPPLY: Lazy<Gauge> = Lazy::new(|| {
-    try_create_gauge("near_total_supply", "Gas price of the latest processed block").unwrap()
-});
-
-pub(crate) static FINAL_BLOCK_HEIGHT: Lazy<IntGauge> = Lazy::new(|| {
-    try_create_int_gauge("near_final_block_height", "Last block that has full BFT finality")
-        .unwrap()
-});
-
-pub(crate) static FINAL_DOOMSLUG_BLOCK_HEIGHT: Lazy<IntGauge> = Lazy::new(|| {
-    try_create_int_gauge(
-        "near_final_doomslug_block_height",
-        "Last block that has Doomslug finality",
-    )
-    .unwrap()
-});
-
 static NODE_DB_VERSION: Lazy<IntGauge> = Lazy::new(|| {
     try_create_int_gauge("near_node_db_version", "DB version used by the node").unwrap()
 });
@@ -248,27 +28,6 @@ static NODE_BUILD_INFO: Lazy<IntCounterVec> = Lazy::new(|| {
     .unwrap()
 });
 
-pub(crate) static TRANSACTION_RECEIVED_VALIDATOR: Lazy<IntGauge> = Lazy::new(|| {
-    try_create_int_gauge("near_transaction_received_validator", "Validator received a transaction")
-        .unwrap()
-});
-
-pub(crate) static TRANSACTION_RECEIVED_NON_VALIDATOR: Lazy<IntGauge> = Lazy::new(|| {
-    try_create_int_gauge(
-        "near_transaction_received_non_validator",
-        "Non-validator received a transaction",
-    )
-    .unwrap()
-});
-
-pub(crate) static TRANSACTION_RECEIVED_NON_VALIDATOR_FORWARDED: Lazy<IntGauge> = Lazy::new(|| {
-    try_create_int_gauge(
-        "near_transaction_received_non_validator_forwarded",
-        "Non-validator received a forwarded transaction",
-    )
-    .unwrap()
-});
-
 pub(crate) static NODE_PROTOCOL_VERSION: Lazy<IntGauge> = Lazy::new(|| {
     try_create_int_gauge("near_node_protocol_version", "Max protocol version supported by the node")
         .unwrap()
@@ -281,36 +40,465 @@ pub(crate) static NODE_PROTOCOL_UPGRADE_VOTING_START: Lazy<IntGauge> = Lazy::new
         .unwrap()
 });
 
-pub static PRODUCE_CHUNK_TIME: Lazy<near_o11y::metrics::HistogramVec> = Lazy::new(|| {
-    try_create_histogram_vec(
-        "near_produce_chunk_time",
-        "Time taken to produce a chunk",
-        &["shard_id"],
-        Some(exponential_buckets(0.001, 2.0, 16).unwrap()),
-    )
-    .unwrap()
-});
+/// Metrics of a single client.
+pub(crate) struct ClientMetrics {
+    pub block_produced_total: IntCounter,
+    pub block_skeleton_hits: IntCounter,
+    pub block_skeleton_misses: IntCounter,
+    pub produced_block_rejected_total: IntCounter,
+    pub chunk_produced_total: IntCounter,
+    pub chunk_missed_inclusion_total: IntCounter,
+    pub is_validator: IntGauge,
+    pub received_bytes_per_second: IntGauge,
+    pub sent_bytes_per_second: IntGauge,
+    // Deprecated.
+    pub blocks_per_minute: IntGauge,
+    // Deprecated.
+    pub chunks_per_block_millis: IntGauge,
+    pub gc_time: Histogram,
+    // Deprecated.
+    pub avg_tgas_usage: IntGauge,
+    pub tgas_usage_hist: HistogramVec,
+    pub validators_chunks_produced: IntGaugeVec,
+    pub validators_chunks_expected: IntGaugeVec,
+    pub validators_blocks_produced: IntGaugeVec,
+    pub validators_blocks_expected: IntGaugeVec,
+    pub sync_status: IntGauge,
+    pub epoch_height: IntGauge,
+    pub protocol_upgrade_block_height: IntGauge,
+    pub chunk_skipped_total: IntCounterVec,
+    pub transaction_admission_rejected_total: IntCounterVec,
+    pub transaction_pool_evicted_total: IntCounter,
+    pub approvals_refused_total: IntCounter,
+    pub partial_encoded_chunk_response_delay: Histogram,
+    pub client_messages_count: IntCounterVec,
+    pub client_messages_processing_time: HistogramVec,
+    pub check_triggers_time: Histogram,
+    pub client_trigger_time_by_type: HistogramVec,
+    pub gas_used: Counter,
+    pub blocks_processed: IntCounter,
+    pub chunks_processed: IntCounter,
+    pub gas_price: Gauge,
+    pub balance_burnt: Counter,
+    pub total_supply: Gauge,
+    pub final_block_height: IntGauge,
+    pub final_doomslug_block_height: IntGauge,
+    pub transaction_received_validator: IntGauge,
+    pub transaction_received_non_validator: IntGauge,
+    pub transaction_received_non_validator_forwarded: IntGauge,
+    pub produce_chunk_time: HistogramVec,
+    pub produce_chunk_deadline_reached_total: IntCounterVec,
+    pub produce_chunk_num_transactions: HistogramVec,
+    pub produce_chunk_transactions_limit_reached_total: IntCounterVec,
+    pub view_client_message_time: HistogramVec,
+    pub view_client_cache_hits: IntCounterVec,
+    pub view_client_cache_misses: IntCounterVec,
+    pub produce_and_distribute_chunk_time: HistogramVec,
+    pub routed_messages_received_total: IntCounterVec,
+    pub routed_messages_duplicate_total: IntCounterVec,
+    pub tx_status_subscriptions: IntGauge,
+    pub tx_status_pushes_total: IntCounter,
+    pub deferred_transactions: IntGauge,
+    pub deferred_transactions_dropped_total: IntCounterVec,
+    pub head_lag: IntGaugeVec,
+    pub head_lagging: IntGauge,
+    pub head_lag_alerts_total: IntCounter,
+    pub state_part_cache_bytes: IntGauge,
+    pub block_header_announcements_total: IntCounterVec,
+}
 
-pub static VIEW_CLIENT_MESSAGE_TIME: Lazy<near_o11y::metrics::HistogramVec> = Lazy::new(|| {
-    try_create_histogram_vec(
-        "near_view_client_messages_processing_time",
-        "Time that view client takes to handle different messages",
-        &["message"],
-        Some(exponential_buckets(0.001, 2.0, 16).unwrap()),
-    )
-    .unwrap()
-});
+impl MetricSet for ClientMetrics {
+    fn new(registry: &MetricsRegistry) -> Self {
+        Self {
+            block_produced_total: registry
+                .try_create_int_counter(
+                    "near_block_produced_total",
+                    "Total number of blocks produced since starting this node",
+                )
+                .unwrap(),
+            block_skeleton_hits: registry
+                .try_create_int_counter(
+                    "near_block_skeleton_hits_total",
+                    "Number of produced blocks whose parts depending only on the previous block \
+                     had been precomputed",
+                )
+                .unwrap(),
+            block_skeleton_misses: registry
+                .try_create_int_counter(
+                    "near_block_skeleton_misses_total",
+                    "Number of produced blocks whose parts depending only on the previous block \
+                     had to be computed during block production",
+                )
+                .unwrap(),
+            produced_block_rejected_total: registry
+                .try_create_int_counter(
+                    "near_produced_block_rejected_total",
+                    "Number of blocks produced by this node which failed validation and weren't \
+                     published",
+                )
+                .unwrap(),
+            chunk_produced_total: registry
+                .try_create_int_counter(
+                    "near_chunk_produced_total",
+                    "Total number of chunks produced since starting this node",
+                )
+                .unwrap(),
+            chunk_missed_inclusion_total: registry
+                .try_create_int_counter(
+                    "near_chunk_missed_inclusion_total",
+                    "Number of blocks produced without a chunk of this node because the chunk reached the block producer too late",
+                )
+                .unwrap(),
+            is_validator: registry
+                .try_create_int_gauge(
+                    "near_is_validator",
+                    "Bool to denote if it is currently validating",
+                )
+                .unwrap(),
+            received_bytes_per_second: registry
+                .try_create_int_gauge(
+                    "near_received_bytes_per_second",
+                    "Number of bytes per second received over the network overall",
+                )
+                .unwrap(),
+            sent_bytes_per_second: registry
+                .try_create_int_gauge(
+                    "near_sent_bytes_per_second",
+                    "Number of bytes per second sent over the network overall",
+                )
+                .unwrap(),
+            blocks_per_minute: registry
+                .try_create_int_gauge("near_blocks_per_minute", "Blocks produced per minute")
+                .unwrap(),
+            chunks_per_block_millis: registry
+                .try_create_int_gauge(
+                    "near_chunks_per_block_millis",
+                    "Average number of chunks included in blocks",
+                )
+                .unwrap(),
+            gc_time: registry
+                .try_create_histogram("near_gc_time", "Time taken to do garbage collection")
+                .unwrap(),
+            avg_tgas_usage: registry
+                .try_create_int_gauge(
+                    "near_chunk_tgas_used",
+                    "Number of Tgas (10^12 of gas) used by the last processed chunks",
+                )
+                .unwrap(),
+            tgas_usage_hist: registry
+                .try_create_histogram_vec(
+                    "near_chunk_tgas_used_hist",
+                    "Number of Tgas (10^12 of gas) used by processed chunks, as a histogram",
+                    &["shard"],
+                    Some(vec![
+                        50., 100., 300., 500., 700., 800., 900., 950., 1000., 1050., 1100., 1150.,
+                        1200., 1250., 1300.,
+                    ]),
+                )
+                .unwrap(),
+            validators_chunks_produced: registry
+                .try_create_int_gauge_vec(
+                    "near_validators_chunks_produced",
+                    "Number of chunks produced by a validator",
+                    &["account_id"],
+                )
+                .unwrap(),
+            validators_chunks_expected: registry
+                .try_create_int_gauge_vec(
+                    "near_validators_chunks_expected",
+                    "Number of chunks expected to be produced by a validator",
+                    &["account_id"],
+                )
+                .unwrap(),
+            validators_blocks_produced: registry
+                .try_create_int_gauge_vec(
+                    "near_validators_blocks_produced",
+                    "Number of blocks produced by a validator",
+                    &["account_id"],
+                )
+                .unwrap(),
+            validators_blocks_expected: registry
+                .try_create_int_gauge_vec(
+                    "near_validators_blocks_expected",
+                    "Number of blocks expected to be produced by a validator",
+                    &["account_id"],
+                )
+                .unwrap(),
+            sync_status: registry
+                .try_create_int_gauge("near_sync_status", "Node sync status")
+                .unwrap(),
+            epoch_height: registry
+                .try_create_int_gauge(
+                    "near_epoch_height",
+                    "Height of the epoch at the head of the blockchain",
+                )
+                .unwrap(),
+            protocol_upgrade_block_height: registry
+                .try_create_int_gauge(
+                    "near_protocol_upgrade_block_height",
+                    "Estimated block height of the protocol upgrade",
+                )
+                .unwrap(),
+            chunk_skipped_total: registry
+                .try_create_int_counter_vec(
+                    "near_chunk_skipped_total",
+                    "Number of skipped chunks",
+                    &["shard_id"],
+                )
+                .unwrap(),
+            transaction_admission_rejected_total: registry
+                .try_create_int_counter_vec(
+                    "near_transaction_admission_rejected_total",
+                    "Number of submitted transactions rejected for exceeding the admission \
+                     limits",
+                    &["reason"],
+                )
+                .unwrap(),
+            transaction_pool_evicted_total: registry
+                .try_create_int_counter(
+                    "near_transaction_pool_evicted_total",
+                    "Number of transactions evicted from the pool because their signer had too \
+                     many transactions in it",
+                )
+                .unwrap(),
+            approvals_refused_total: registry
+                .try_create_int_counter(
+                    "near_approvals_refused_total",
+                    "Number of approvals not signed because they would conflict with approvals \
+                     signed before under the slashing rules",
+                )
+                .unwrap(),
+            partial_encoded_chunk_response_delay: registry
+                .try_create_histogram(
+                    "near_partial_encoded_chunk_response_delay",
+                    "Delay between when a partial encoded chunk response is sent from \
+                     PeerActor and when it is received by ClientActor",
+                )
+                .unwrap(),
+            client_messages_count: registry
+                .try_create_int_counter_vec(
+                    "near_client_messages_count",
+                    "Number of messages client actor received by message type",
+                    &["type"],
+                )
+                .unwrap(),
+            client_messages_processing_time: registry
+                .try_create_histogram_vec(
+                    "near_client_messages_processing_time",
+                    "Processing time of messages that client actor received, sorted by \
+                     message type",
+                    &["type"],
+                    Some(exponential_buckets(0.0001, 1.6, 20).unwrap()),
+                )
+                .unwrap(),
+            check_triggers_time: registry
+                .try_create_histogram(
+                    "near_client_triggers_time",
+                    "Processing time of the check_triggers function in client",
+                )
+                .unwrap(),
+            client_trigger_time_by_type: registry
+                .try_create_histogram_vec(
+                    "near_client_triggers_time_by_type",
+                    "Time spent on the different triggers in client",
+                    &["trigger"],
+                    Some(exponential_buckets(0.0001, 1.6, 20).unwrap()),
+                )
+                .unwrap(),
+            gas_used: registry
+                .try_create_counter(
+                    "near_gas_used",
+                    "Gas used by processed blocks, measured in gas",
+                )
+                .unwrap(),
+            blocks_processed: registry
+                .try_create_int_counter("near_blocks_processed", "Number of processed blocks")
+                .unwrap(),
+            chunks_processed: registry
+                .try_create_int_counter("near_chunks_processed", "Number of processed chunks")
+                .unwrap(),
+            gas_price: registry
+                .try_create_gauge("near_gas_price", "Gas price of the latest processed block")
+                .unwrap(),
+            balance_burnt: registry
+                .try_create_counter(
+                    "near_balance_burnt",
+                    "Balance burnt by processed blocks in NEAR tokens",
+                )
+                .unwrap(),
+            total_supply: registry
+                .try_create_gauge("near_total_supply", "Gas price of the latest processed block")
+                .unwrap(),
+            final_block_height: registry
+                .try_create_int_gauge(
+                    "near_final_block_height",
+                    "Last block that has full BFT finality",
+                )
+                .unwrap(),
+            final_doomslug_block_height: registry
+                .try_create_int_gauge(
+                    "near_final_doomslug_block_height",
+                    "Last block that has Doomslug finality",
+                )
+                .unwrap(),
+            transaction_received_validator: registry
+                .try_create_int_gauge(
+                    "near_transaction_received_validator",
+                    "Validator received a transaction",
+                )
+                .unwrap(),
+            transaction_received_non_validator: registry
+                .try_create_int_gauge(
+                    "near_transaction_received_non_validator",
+                    "Non-validator received a transaction",
+                )
+                .unwrap(),
+            transaction_received_non_validator_forwarded: registry
+                .try_create_int_gauge(
+                    "near_transaction_received_non_validator_forwarded",
+                    "Non-validator received a forwarded transaction",
+                )
+                .unwrap(),
+            produce_chunk_time: registry
+                .try_create_histogram_vec(
+                    "near_produce_chunk_time",
+                    "Time taken to produce a chunk",
+                    &["shard_id"],
+                    Some(exponential_buckets(0.001, 2.0, 16).unwrap()),
+                )
+                .unwrap(),
+            produce_chunk_deadline_reached_total: registry
+                .try_create_int_counter_vec(
+                    "near_produce_chunk_deadline_reached_total",
+                    "Number of produced chunks whose transactions were cut short by the chunk \
+                     production deadline",
+                    &["shard_id"],
+                )
+                .unwrap(),
+            produce_chunk_num_transactions: registry
+                .try_create_histogram_vec(
+                    "near_produce_chunk_num_transactions",
+                    "Number of transactions in produced chunks",
+                    &["shard_id"],
+                    Some(exponential_buckets(1.0, 2.0, 16).unwrap()),
+                )
+                .unwrap(),
+            produce_chunk_transactions_limit_reached_total: registry
+                .try_create_int_counter_vec(
+                    "near_produce_chunk_transactions_limit_reached_total",
+                    "Number of produced chunks which reached max_transactions_per_chunk",
+                    &["shard_id"],
+                )
+                .unwrap(),
+            view_client_message_time: registry
+                .try_create_histogram_vec(
+                    "near_view_client_messages_processing_time",
+                    "Time that view client takes to handle different messages",
+                    &["message"],
+                    Some(exponential_buckets(0.001, 2.0, 16).unwrap()),
+                )
+                .unwrap(),
+            view_client_cache_hits: registry
+                .try_create_int_counter_vec(
+                    "near_view_client_cache_hits_total",
+                    "Number of view client requests served from cache",
+                    &["cache"],
+                )
+                .unwrap(),
+            view_client_cache_misses: registry
+                .try_create_int_counter_vec(
+                    "near_view_client_cache_misses_total",
+                    "Number of view client requests which were not found in cache",
+                    &["cache"],
+                )
+                .unwrap(),
+            produce_and_distribute_chunk_time: registry
+                .try_create_histogram_vec(
+                    "near_produce_and_distribute_chunk_time",
+                    "Time to produce a chunk and distribute it to peers",
+                    &["shard_id"],
+                    Some(exponential_buckets(0.001, 2.0, 16).unwrap()),
+                )
+                .unwrap(),
+            routed_messages_received_total: registry
+                .try_create_int_counter_vec(
+                    "near_client_routed_messages_received_total",
+                    "Number of routed messages received by the client, by type",
+                    &["type"],
+                )
+                .unwrap(),
+            routed_messages_duplicate_total: registry
+                .try_create_int_counter_vec(
+                    "near_client_routed_messages_duplicate_total",
+                    "Number of routed messages dropped by the client as copies of messages it \
+                     received shortly before, by type",
+                    &["type"],
+                )
+                .unwrap(),
+            tx_status_subscriptions: registry
+                .try_create_int_gauge(
+                    "near_tx_status_subscriptions",
+                    "Number of transactions peers are subscribed to the final outcome of",
+                )
+                .unwrap(),
+            tx_status_pushes_total: registry
+                .try_create_int_counter(
+                    "near_tx_status_pushes_total",
+                    "Number of final transaction outcomes pushed to subscribed peers",
+                )
+                .unwrap(),
+            deferred_transactions: registry
+                .try_create_int_gauge(
+                    "near_deferred_transactions",
+                    "Number of transactions held until the block they're anchored to is final",
+                )
+                .unwrap(),
+            deferred_transactions_dropped_total: registry
+                .try_create_int_counter_vec(
+                    "near_deferred_transactions_dropped_total",
+                    "Number of deferred transactions dropped instead of being processed, by \
+                     reason",
+                    &["reason"],
+                )
+                .unwrap(),
+            head_lag: registry
+                .try_create_int_gauge_vec(
+                    "near_head_lag",
+                    "Number of heights the head is behind the heights reported by the connected \
+                     peers, by percentile of the peer heights",
+                    &["percentile"],
+                )
+                .unwrap(),
+            head_lagging: registry
+                .try_create_int_gauge(
+                    "near_head_lagging",
+                    "Whether the head persistently lags behind the connected peers",
+                )
+                .unwrap(),
+            head_lag_alerts_total: registry
+                .try_create_int_counter(
+                    "near_head_lag_alerts_total",
+                    "Number of times the head started lagging behind the connected peers",
+                )
+                .unwrap(),
+            state_part_cache_bytes: registry
+                .try_create_int_gauge(
+                    "near_state_part_cache_bytes",
+                    "Total size of the state parts cached in memory for serving to peers",
+                )
+                .unwrap(),
+            block_header_announcements_total: registry
+                .try_create_int_counter_vec(
+                    "near_block_header_announcements_total",
+                    "Number of blocks announced by their header, by whether the block was \
+                     reconstructed from known chunks or requested from the peer",
+                    &["outcome"],
+                )
+                .unwrap(),
+        }
+    }
+}
 
-pub static PRODUCE_AND_DISTRIBUTE_CHUNK_TIME: Lazy<near_o11y::metrics::HistogramVec> =
-    Lazy::new(|| {
-        try_create_histogram_vec(
-            "near_produce_and_distribute_chunk_time",
-            "Time to produce a chunk and distribute it to peers",
-            &["shard_id"],
-            Some(exponential_buckets(0.001, 2.0, 16).unwrap()),
-        )
-        .unwrap()
-    });
 /// Exports neard, protocol and database versions via Prometheus metrics.
 ///
 /// Sets metrics which export node’s max supported protocol version, used
@@ -0,0 +1,117 @@
+//! Approvals collected by doomslug which survive restarts.
+//!
+//! A block producer needs approvals from a threshold of the stake to produce
+//! its block, and the approvals it collected are only kept in memory.  A block
+//! producer restarted shortly before its turn therefore misses its height, or
+//! produces its block late, because the other validators don't send their
+//! approvals again.  The client saves the approvals doomslug keeps, in one
+//! write before each block production attempt, and passes the saved ones to
+//! doomslug again when it starts.  Approvals for heights below the head can no
+//! longer be used and are removed when the head moves.
+use std::io;
+
+use borsh::BorshDeserialize;
+use near_primitives::block::Approval;
+use near_primitives::types::{AccountId, BlockHeight};
+use near_store::{DBCol, Store, StoreUpdate};
+
+/// Keys start with the big endian target height, so that the approvals are
+/// ordered by height.
+fn encode_key(target_height: BlockHeight, account_id: &AccountId) -> Vec<u8> {
+    let mut key = target_height.to_be_bytes().to_vec();
+    key.extend_from_slice(account_id.as_bytes());
+    key
+}
+
+fn decode_height(key: &[u8]) -> io::Result<BlockHeight> {
+    let height = key.get(..8).ok_or_else(|| {
+        io::Error::new(io::ErrorKind::InvalidData, format!("invalid approval key {key:?}"))
+    })?;
+    Ok(BlockHeight::from_be_bytes(height.try_into().unwrap()))
+}
+
+/// Saves an approval, replacing the one saved previously for the same target
+/// height and account.
+pub fn save(store_update: &mut StoreUpdate, approval: &Approval) -> io::Result<()> {
+    let key = encode_key(approval.target_height, &approval.account_id);
+    store_update.set_ser(DBCol::DoomslugApprovals, &key, approval)
+}
+
+/// Loads the saved approvals with target height `min_height` or above.
+pub fn load(store: &Store, min_height: BlockHeight) -> io::Result<Vec<Approval>> {
+    let mut approvals = vec![];
+    for item in store.iter(DBCol::DoomslugApprovals) {
+        let (key, value) = item?;
+        if decode_height(&key)? >= min_height {
+            approvals.push(Approval::try_from_slice(&value)?);
+        }
+    }
+    Ok(approvals)
+}
+
+/// Removes the saved approvals with target height below `min_height`.
+/// Returns the number of approvals removed.
+pub fn prune(
+    store: &Store,
+    store_update: &mut StoreUpdate,
+    min_height: BlockHeight,
+) -> io::Result<usize> {
+    let mut num_removed = 0;
+    for item in store.iter(DBCol::DoomslugApprovals) {
+        let (key, _) = item?;
+        if decode_height(&key)? >= min_height {
+            break;
+        }
+        store_update.delete(DBCol::DoomslugApprovals, &key);
+        num_removed += 1;
+    }
+    Ok(num_removed)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{load, prune, save};
+    use near_crypto::KeyType;
+    use near_primitives::block::Approval;
+    use near_primitives::hash::hash;
+    use near_primitives::validator_signer::InMemoryValidatorSigner;
+    use near_store::test_utils::create_test_store;
+
+    #[test]
+    fn test_save_load_and_prune() {
+        let store = create_test_store();
+        let signers: Vec<_> = ["test0", "test1"]
+            .iter()
+            .map(|account_id| {
+                InMemoryValidatorSigner::from_seed(
+                    account_id.parse().unwrap(),
+                    KeyType::ED25519,
+                    account_id,
+                )
+            })
+            .collect();
+        // Heights around a power of 256, where little endian keys would be
+        // out of order.
+        let approvals: Vec<_> = [255, 256, 257]
+            .into_iter()
+            .flat_map(|target_height| {
+                signers.iter().map(move |signer| {
+                    Approval::new(hash(b"parent"), target_height - 1, target_height, signer)
+                })
+            })
+            .collect();
+        let mut store_update = store.store_update();
+        for approval in &approvals {
+            save(&mut store_update, approval).unwrap();
+        }
+        store_update.commit().unwrap();
+
+        assert_eq!(load(&store, 0).unwrap(), approvals);
+        assert_eq!(load(&store, 256).unwrap(), approvals[2..]);
+
+        let mut store_update = store.store_update();
+        assert_eq!(prune(&store, &mut store_update, 257).unwrap(), 4);
+        store_update.commit().unwrap();
+        assert_eq!(load(&store, 0).unwrap(), approvals[4..]);
+    }
+}
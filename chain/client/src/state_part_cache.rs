@@ -0,0 +1,100 @@
+//! In-memory cache of state parts served to peers.
+//!
+//! Peers syncing state at the same sync hash request the same parts from the
+//! nodes serving them.  Generated parts are persisted in `DBCol::StateParts`,
+//! but only while the disk budget allows it, and reading a part back still
+//! costs a database read per request, repeated for every sub-part.  The cache
+//! keeps recently served parts in memory within a byte budget, evicting the
+//! least recently used ones.  It's shared by all view client threads.
+use std::sync::Arc;
+
+use near_primitives::hash::CryptoHash;
+use near_primitives::types::ShardId;
+
+use crate::metrics::ClientMetrics;
+
+/// Sync hash, shard and part id.
+pub type StatePartKey = (CryptoHash, ShardId, u64);
+
+pub struct StatePartCache {
+    parts: lru::LruCache<StatePartKey, Arc<[u8]>>,
+    /// Total size of the cached parts.
+    size: u64,
+    /// Maximum total size of the cached parts.  Zero disables caching.
+    budget: u64,
+    metrics: Arc<ClientMetrics>,
+}
+
+impl StatePartCache {
+    pub fn new(budget: u64, metrics: Arc<ClientMetrics>) -> Self {
+        Self { parts: lru::LruCache::unbounded(), size: 0, budget, metrics }
+    }
+
+    pub fn get(&mut self, key: &StatePartKey) -> Option<Arc<[u8]>> {
+        if self.budget == 0 {
+            return None;
+        }
+        let part = self.parts.get(key).cloned();
+        if part.is_some() {
+            self.metrics.view_client_cache_hits.with_label_values(&["state_part"]).inc();
+        } else {
+            self.metrics.view_client_cache_misses.with_label_values(&["state_part"]).inc();
+        }
+        part
+    }
+
+    /// Caches the part, evicting the least recently used parts to stay
+    /// within the budget.  Parts larger than the whole budget aren't cached.
+    pub fn put(&mut self, key: StatePartKey, part: Arc<[u8]>) {
+        if part.len() as u64 > self.budget {
+            return;
+        }
+        self.size += part.len() as u64;
+        if let Some(old) = self.parts.put(key, part) {
+            self.size -= old.len() as u64;
+        }
+        while self.size > self.budget {
+            let (_, evicted) = self.parts.pop_lru().expect("size of an empty cache is zero");
+            self.size -= evicted.len() as u64;
+        }
+        self.metrics.state_part_cache_bytes.set(self.size as i64);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::StatePartCache;
+    use crate::metrics::ClientMetrics;
+    use near_o11y::metrics::MetricsRegistry;
+    use near_primitives::hash::CryptoHash;
+    use std::sync::Arc;
+
+    #[test]
+    fn test_byte_budget() {
+        let metrics = MetricsRegistry::instance("test_state_part_cache").get::<ClientMetrics>();
+        let mut cache = StatePartCache::new(10, metrics.clone());
+        let key = |part_id| (CryptoHash::default(), 0, part_id);
+        let part = |len| -> Arc<[u8]> { vec![0; len].into() };
+
+        cache.put(key(0), part(4));
+        cache.put(key(1), part(4));
+        assert_eq!(metrics.state_part_cache_bytes.get(), 8);
+        // Using the first part makes the second one the least recently used.
+        assert!(cache.get(&key(0)).is_some());
+        cache.put(key(2), part(4));
+        assert!(cache.get(&key(1)).is_none());
+        assert_eq!(cache.get(&key(0)).unwrap().len(), 4);
+        assert_eq!(cache.get(&key(2)).unwrap().len(), 4);
+        assert_eq!(metrics.state_part_cache_bytes.get(), 8);
+
+        // Parts which don't fit at all are ignored.
+        cache.put(key(3), part(11));
+        assert!(cache.get(&key(3)).is_none());
+        assert_eq!(metrics.view_client_cache_hits.with_label_values(&["state_part"]).get(), 3);
+        assert_eq!(metrics.view_client_cache_misses.with_label_values(&["state_part"]).get(), 2);
+
+        let mut disabled = StatePartCache::new(0, metrics);
+        disabled.put(key(0), part(0));
+        assert!(disabled.get(&key(0)).is_none());
+    }
+}
@@ -0,0 +1,109 @@
+//! Sources of state parts other than peers.
+//!
+//! When state sync has a [`StatePartsProvider`], every state part is first
+//! fetched from the provider.  Parts the provider doesn't have, can't fetch or
+//! which fail validation are then requested from peers as usual, so a provider
+//! can only speed state sync up.  State headers are always requested from
+//! peers, which means the parts are validated against a state root the node
+//! obtained independently of the provider.
+use std::time::Duration;
+
+use awc::{Client, Connector};
+use futures::future::LocalBoxFuture;
+use futures::FutureExt;
+use tracing::debug;
+
+use near_primitives::hash::CryptoHash;
+use near_primitives::types::ShardId;
+
+/// Timeout of a single request to fetch a state part over HTTP.
+const HTTP_REQUEST_TIMEOUT: Duration = Duration::from_secs(60);
+/// State parts are at most a few tens of megabytes.
+const MAX_PART_SIZE: usize = 256 * 1024 * 1024;
+
+thread_local! {
+    /// awc clients can't be shared between threads, while requests are sent
+    /// from whichever thread state sync runs on.
+    static HTTP_CLIENT: Client = Client::builder()
+        .timeout(HTTP_REQUEST_TIMEOUT)
+        .connector(Connector::new().max_http_version(awc::http::Version::HTTP_11))
+        .finish();
+}
+
+pub trait StatePartsProvider: Send + Sync {
+    /// Fetches a state part.  Resolves to `None` if the provider doesn't have
+    /// the part or failed to fetch it, in which case it's requested from peers.
+    fn fetch_part(
+        &self,
+        shard_id: ShardId,
+        sync_hash: CryptoHash,
+        part_id: u64,
+    ) -> LocalBoxFuture<'static, Option<Vec<u8>>>;
+}
+
+/// Fetches state parts from an HTTP endpoint, for example a CDN or a publicly
+/// readable bucket of an S3-compatible storage.  The part with id `part_id` of
+/// shard `shard_id` at `sync_hash` is fetched from
+/// `<url>/<sync_hash>/shard_<shard_id>/part_<part_id>`, which is the same
+/// layout as the one of `StateSyncSource::LocalDirectory`.
+pub struct HttpStatePartsProvider {
+    url: String,
+}
+
+impl HttpStatePartsProvider {
+    pub fn new(url: &str) -> Self {
+        Self { url: url.to_string() }
+    }
+}
+
+fn part_url(url: &str, shard_id: ShardId, sync_hash: &CryptoHash, part_id: u64) -> String {
+    format!("{}/{}/shard_{}/part_{}", url.trim_end_matches('/'), sync_hash, shard_id, part_id)
+}
+
+impl StatePartsProvider for HttpStatePartsProvider {
+    fn fetch_part(
+        &self,
+        shard_id: ShardId,
+        sync_hash: CryptoHash,
+        part_id: u64,
+    ) -> LocalBoxFuture<'static, Option<Vec<u8>>> {
+        let url = part_url(&self.url, shard_id, &sync_hash, part_id);
+        let request = HTTP_CLIENT.with(|client| client.get(&url).send());
+        async move {
+            let mut response = match request.await {
+                Ok(response) => response,
+                Err(err) => {
+                    debug!(target: "sync", %url, %err, "Failed to fetch state part");
+                    return None;
+                }
+            };
+            if !response.status().is_success() {
+                debug!(target: "sync", %url, status = %response.status(), "State part not found");
+                return None;
+            }
+            match response.body().limit(MAX_PART_SIZE).await {
+                Ok(body) => Some(body.to_vec()),
+                Err(err) => {
+                    debug!(target: "sync", %url, %err, "Failed to read state part");
+                    None
+                }
+            }
+        }
+        .boxed_local()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::part_url;
+    use near_primitives::hash::CryptoHash;
+
+    #[test]
+    fn test_part_url() {
+        let sync_hash = CryptoHash::default();
+        assert_eq!(
+            part_url("https://example.com/mainnet/", 2, &sync_hash, 7),
+            format!("https://example.com/mainnet/{}/shard_2/part_7", sync_hash)
+        );
+    }
+}
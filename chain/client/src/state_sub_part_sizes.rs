@@ -0,0 +1,135 @@
+//! Picks the size of the state sub-parts requested from each peer.
+//!
+//! Small sub-parts waste round trips on fast links while large ones time out
+//! on slow links, so the size is adjusted per peer, starting from the default
+//! size.  Whenever a sub-part arrives, the throughput observed for it tells how
+//! many bytes the peer delivers within `TARGET_LATENCY`: the size is doubled if
+//! that's at least twice the size of the sub-part and halved if it's less than
+//! the size of the sub-part.  A sub-part which doesn't arrive at all halves the
+//! size as well.  Sizes are powers of two within the bounds of the protocol and
+//! never exceed what the peer advertised in its handshake.
+use std::collections::HashMap;
+use std::time::Duration;
+
+use near_network::types::AccountOrPeerIdOrHash;
+use near_primitives::syncing::{
+    MAX_STATE_SUB_PART_SIZE, MIN_STATE_SUB_PART_SIZE, STATE_SUB_PART_SIZE,
+};
+
+/// How long the transfer of a single sub-part should take.
+const TARGET_LATENCY: Duration = Duration::from_secs(2);
+
+/// Returns the largest size of the sub-parts which may be requested from a
+/// peer which advertised `advertised` in its handshake.  Peers which didn't
+/// advertise anything are only asked for sub-parts of the default size.
+pub(crate) fn sub_part_size_limit(advertised: u64) -> u64 {
+    if advertised == 0 {
+        STATE_SUB_PART_SIZE.as_u64()
+    } else {
+        advertised.clamp(MIN_STATE_SUB_PART_SIZE.as_u64(), MAX_STATE_SUB_PART_SIZE.as_u64())
+    }
+}
+
+#[derive(Default)]
+pub(crate) struct StateSubPartSizes {
+    sizes: HashMap<AccountOrPeerIdOrHash, u64>,
+}
+
+impl StateSubPartSizes {
+    /// Size of the sub-parts to request from `target`, which serves sub-parts
+    /// of at most `limit` bytes.
+    pub fn size(&self, target: &AccountOrPeerIdOrHash, limit: u64) -> u64 {
+        let size = self.sizes.get(target).copied().unwrap_or(STATE_SUB_PART_SIZE.as_u64());
+        std::cmp::max(MIN_STATE_SUB_PART_SIZE.as_u64(), std::cmp::min(size, limit))
+    }
+
+    /// Records that `target` delivered `bytes` of a sub-part of
+    /// `sub_part_size` bytes within `elapsed`.  The last sub-part of a part is
+    /// usually shorter and its transfer time is dominated by the round trip,
+    /// so short sub-parts are ignored.
+    pub fn record_received(
+        &mut self,
+        target: &AccountOrPeerIdOrHash,
+        sub_part_size: u64,
+        bytes: u64,
+        elapsed: Duration,
+    ) {
+        if bytes < sub_part_size {
+            return;
+        }
+        let elapsed = elapsed.as_secs_f64().max(0.001);
+        let deliverable = bytes as f64 * TARGET_LATENCY.as_secs_f64() / elapsed;
+        let size = if deliverable >= 2.0 * sub_part_size as f64 {
+            sub_part_size * 2
+        } else if deliverable < sub_part_size as f64 {
+            sub_part_size / 2
+        } else {
+            sub_part_size
+        };
+        self.set(target, size);
+    }
+
+    /// Records that `target` didn't deliver a sub-part of `sub_part_size`
+    /// bytes before the timeout.
+    pub fn record_timeout(&mut self, target: &AccountOrPeerIdOrHash, sub_part_size: u64) {
+        self.set(target, sub_part_size / 2);
+    }
+
+    fn set(&mut self, target: &AccountOrPeerIdOrHash, size: u64) {
+        let size = size.clamp(MIN_STATE_SUB_PART_SIZE.as_u64(), MAX_STATE_SUB_PART_SIZE.as_u64());
+        self.sizes.insert(target.clone(), size);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{sub_part_size_limit, StateSubPartSizes};
+    use near_crypto::{KeyType, PublicKey};
+    use near_network::types::AccountOrPeerIdOrHash;
+    use near_primitives::network::PeerId;
+    use near_primitives::syncing::{
+        MAX_STATE_SUB_PART_SIZE, MIN_STATE_SUB_PART_SIZE, STATE_SUB_PART_SIZE,
+    };
+    use std::time::Duration;
+
+    #[test]
+    fn test_sub_part_size_limit() {
+        assert_eq!(sub_part_size_limit(0), STATE_SUB_PART_SIZE.as_u64());
+        assert_eq!(sub_part_size_limit(1), MIN_STATE_SUB_PART_SIZE.as_u64());
+        assert_eq!(sub_part_size_limit(u64::MAX), MAX_STATE_SUB_PART_SIZE.as_u64());
+        assert_eq!(sub_part_size_limit(1 << 20), 1 << 20);
+    }
+
+    #[test]
+    fn test_adjust_sizes() {
+        let fast = AccountOrPeerIdOrHash::AccountId("fast".parse().unwrap());
+        let slow = AccountOrPeerIdOrHash::PeerId(PeerId::new(PublicKey::empty(KeyType::ED25519)));
+        let default = STATE_SUB_PART_SIZE.as_u64();
+        let max = MAX_STATE_SUB_PART_SIZE.as_u64();
+        let mut sizes = StateSubPartSizes::default();
+        assert_eq!(sizes.size(&fast, max), default);
+
+        // Fast links get larger sub-parts, up to the limit of the peer.
+        for _ in 0..10 {
+            let size = sizes.size(&fast, 1 << 20);
+            sizes.record_received(&fast, size, size, Duration::from_millis(10));
+        }
+        assert_eq!(sizes.size(&fast, 1 << 20), 1 << 20);
+        assert_eq!(sizes.size(&fast, default), default);
+
+        // Short sub-parts don't tell anything about the throughput.
+        sizes.record_received(&fast, 1 << 20, 10, Duration::from_secs(10));
+        assert_eq!(sizes.size(&fast, 1 << 20), 1 << 20);
+
+        // Slow links and timeouts get smaller sub-parts.
+        sizes.record_received(&slow, default, default, Duration::from_secs(3));
+        assert_eq!(sizes.size(&slow, max), default / 2);
+        sizes.record_received(&slow, default / 2, default / 2, Duration::from_secs(3));
+        sizes.record_timeout(&slow, default / 4);
+        assert_eq!(sizes.size(&slow, max), MIN_STATE_SUB_PART_SIZE.as_u64());
+
+        // A throughput matching the size keeps it.
+        sizes.record_received(&fast, default, default, Duration::from_millis(1500));
+        assert_eq!(sizes.size(&fast, max), default);
+    }
+}
@@ -1,12 +1,14 @@
 use near_chain::{check_known, near_chain_primitives, ChainStoreAccess, Error};
-use std::cmp::min;
+use std::cmp::{max, min};
 use std::collections::{HashMap, HashSet};
 use std::ops::Add;
+use std::path::{Path, PathBuf};
 use std::sync::atomic::{AtomicBool, Ordering};
-use std::sync::Arc;
+use std::sync::{Arc, Mutex};
 use std::time::Duration as TimeDuration;
 
 use ansi_term::Color::{Purple, Yellow};
+use borsh::BorshDeserialize;
 use chrono::{DateTime, Duration};
 use futures::{future, FutureExt};
 use rand::seq::{IteratorRandom, SliceRandom};
@@ -15,25 +17,36 @@ use tracing::{debug, error, info, warn};
 
 use near_chain::{Chain, RuntimeAdapter};
 use near_network::types::{FullPeerInfo, NetworkRequests, NetworkResponses, PeerManagerAdapter};
-use near_primitives::block::Tip;
+use near_primitives::block::{BlockHeader, Tip};
 use near_primitives::hash::CryptoHash;
 use near_primitives::network::PeerId;
-use near_primitives::syncing::get_num_state_parts;
+use near_primitives::syncing::{
+    get_num_state_parts, EpochSyncProofsResponse, ShardStateSyncResponseHeader, StateSubPart,
+};
 use near_primitives::time::{Clock, Utc};
 use near_primitives::types::validator_stake::ValidatorStake;
 use near_primitives::types::{
     AccountId, BlockHeight, BlockHeightDelta, EpochId, ShardId, StateRoot,
 };
 use near_primitives::utils::to_timestamp;
+use near_primitives::views::HeaderSyncStatsView;
 
 use near_chain::chain::{ApplyStatePartsRequest, StateSplitRequest};
+use near_chain_configs::StateSyncSource;
 use near_client_primitives::types::{
-    DownloadStatus, ShardSyncDownload, ShardSyncStatus, StateSplitApplyingStatus, SyncStatus,
+    DownloadStatus, ShardSyncDownload, ShardSyncStatus, StatePartsApplyingStatus,
+    StateSplitApplyingStatus, SyncStatus,
 };
 use near_network::types::AccountOrPeerIdOrHash;
 use near_network::types::PeerManagerMessageRequest;
 use near_o11y::WithSpanContextExt;
 use near_primitives::shard_layout::ShardUId;
+use near_primitives::state_part::PartId;
+
+use crate::block_sync_requests::{BlockSyncRequests, BLOCK_REQUEST_TIMEOUT};
+use crate::header_sync_stats::HeaderSyncStats;
+use crate::state_parts_provider::{HttpStatePartsProvider, StatePartsProvider};
+use crate::state_sub_part_sizes::{sub_part_size_limit, StateSubPartSizes};
 
 /// Maximum number of block headers send over the network.
 pub const MAX_BLOCK_HEADERS: u64 = 512;
@@ -41,10 +54,8 @@ pub const MAX_BLOCK_HEADERS: u64 = 512;
 /// Maximum number of block header hashes to send as part of a locator.
 pub const MAX_BLOCK_HEADER_HASHES: usize = 20;
 
-/// Maximum number of block requested at once in BlockSync
-const MAX_BLOCK_REQUESTS: usize = 5;
-
-const BLOCK_REQUEST_TIMEOUT: i64 = 2;
+/// Maximum number of blocks ahead of the head BlockSync requests.
+const MAX_BLOCK_REQUESTS: usize = 64;
 
 /// Maximum number of state parts to request per peer on each round when node is trying to download the state.
 pub const MAX_STATE_PART_REQUEST: u64 = 16;
@@ -54,76 +65,215 @@ pub const MAX_PENDING_PART: u64 = MAX_STATE_PART_REQUEST * 10000;
 
 pub const NS_PER_SECOND: u128 = 1_000_000_000;
 
-/// Helper to keep track of the Epoch Sync
-// TODO #3488
-#[allow(dead_code)]
+/// Maximum number of state parts being fetched from the parts provider at the
+/// same time.
+const MAX_PENDING_PROVIDER_PARTS: usize = 64;
+
+/// Returns the directory with the state of the shard at `sync_hash` within
+/// the directory of a `StateSyncSource::LocalDirectory`.
+fn local_shard_dir(dir: &Path, sync_hash: &CryptoHash, shard_id: ShardId) -> PathBuf {
+    dir.join(sync_hash.to_string()).join(format!("shard_{}", shard_id))
+}
+
+/// A file of a `StateSyncSource::LocalDirectory` read in the background.
+struct LocalStateFile {
+    shard_id: ShardId,
+    sync_hash: CryptoHash,
+    /// None for the state header.
+    part_id: Option<u64>,
+    path: PathBuf,
+    data: std::io::Result<Vec<u8>>,
+}
+
+/// Helper to keep track of the Epoch Sync.
+///
+/// A node bootstrapping from genesis requests proofs of the following epochs
+/// from a peer, one batch at a time.  Each proof is the header of the first
+/// block of an epoch together with the epoch sync data it commits to, and is
+/// verified against the block producers committed to by the previous proof,
+/// starting with the block producers of genesis.  Once a peer reports there
+/// are no newer epochs, the first block of the last verified epoch is used as
+/// the target of state sync.
 pub struct EpochSync {
     network_adapter: Arc<dyn PeerManagerAdapter>,
-    /// Datastructure to keep track of when the last request to each peer was made.
-    /// Peers do not respond to Epoch Sync requests more frequently than once per a certain time
-    /// interval, thus there's no point in requesting more frequently.
-    peer_to_last_request_time: HashMap<PeerId, DateTime<Utc>>,
-    /// Tracks all the peers who have reported that we are already up to date
-    peers_reporting_up_to_date: HashSet<PeerId>,
+    /// Peers which didn't respond in time or couldn't serve the proofs.
+    peers_to_skip: HashSet<PeerId>,
     /// The last epoch we are synced to
     current_epoch_id: EpochId,
     /// The next epoch id we need to sync
     next_epoch_id: EpochId,
-    /// The block producers set to validate the light client block view for the next epoch
+    /// The block producers set to validate the proof of the next epoch
     next_block_producers: Vec<ValidatorStake>,
-    /// The last epoch id that we have requested
-    requested_epoch_id: EpochId,
+    /// Number of epochs verified so far.
+    epoch_ord: u64,
     /// When and to whom was the last request made
     last_request_time: DateTime<Utc>,
     last_request_peer_id: Option<PeerId>,
 
-    /// How long to wait for a response before re-requesting the same light client block view
+    /// How long to wait for a response before requesting the same proofs
+    /// from another peer
     request_timeout: Duration,
-    /// How frequently to send request to the same peer
-    peer_timeout: Duration,
 
-    /// True, if all peers agreed that we're at the last Epoch.
-    /// Only finalization is needed.
-    have_all_epochs: bool,
     /// Whether the Epoch Sync was performed to completion previously.
     /// Current state machine allows for only one Epoch Sync.
     pub done: bool,
 
+    /// Hash of the first block of the last verified epoch, or of genesis if
+    /// no epoch has been verified yet.
     pub sync_hash: CryptoHash,
-
-    received_epoch: bool,
-
-    is_just_started: bool,
 }
 
 impl EpochSync {
     pub fn new(
         network_adapter: Arc<dyn PeerManagerAdapter>,
+        genesis_hash: CryptoHash,
         genesis_epoch_id: EpochId,
         genesis_next_epoch_id: EpochId,
         first_epoch_block_producers: Vec<ValidatorStake>,
         request_timeout: TimeDuration,
-        peer_timeout: TimeDuration,
     ) -> Self {
         Self {
             network_adapter,
-            peer_to_last_request_time: HashMap::new(),
-            peers_reporting_up_to_date: HashSet::new(),
-            current_epoch_id: genesis_epoch_id.clone(),
+            peers_to_skip: HashSet::new(),
+            current_epoch_id: genesis_epoch_id,
             next_epoch_id: genesis_next_epoch_id,
             next_block_producers: first_epoch_block_producers,
-            requested_epoch_id: genesis_epoch_id,
+            epoch_ord: 0,
             last_request_time: Clock::utc(),
             last_request_peer_id: None,
             request_timeout: Duration::from_std(request_timeout).unwrap(),
-            peer_timeout: Duration::from_std(peer_timeout).unwrap(),
-            received_epoch: false,
-            have_all_epochs: false,
             done: false,
-            sync_hash: CryptoHash::default(),
-            is_just_started: true,
+            sync_hash: genesis_hash,
         }
     }
+
+    /// Advances epoch sync and returns whether it's done.  Only a node whose
+    /// head is still at genesis and whose peers are at least two epochs ahead
+    /// syncs epochs, others are done right away.  If none of the peers at the
+    /// highest height serves the proofs, epoch sync gives up and the node
+    /// falls back to downloading all headers.
+    pub fn run(
+        &mut self,
+        sync_status: &mut SyncStatus,
+        chain: &Chain,
+        highest_height: BlockHeight,
+        highest_height_peers: &[FullPeerInfo],
+    ) -> Result<bool, near_chain::Error> {
+        if self.done {
+            return Ok(true);
+        }
+        let genesis_height = chain.genesis().height();
+        if chain.head()?.height > genesis_height
+            || highest_height < genesis_height + 2 * chain.epoch_length
+        {
+            self.done = true;
+            return Ok(true);
+        }
+        *sync_status = SyncStatus::EpochSync { epoch_ord: self.epoch_ord };
+
+        let now = Clock::utc();
+        if let Some(peer_id) = self.last_request_peer_id.take() {
+            if now - self.last_request_time < self.request_timeout {
+                self.last_request_peer_id = Some(peer_id);
+                return Ok(false);
+            }
+            debug!(
+                target: "sync",
+                ?peer_id,
+                sync_hash = ?self.sync_hash,
+                "Epoch sync request timed out"
+            );
+            self.peers_to_skip.insert(peer_id);
+        }
+
+        match highest_height_peers
+            .iter()
+            .filter(|peer| !self.peers_to_skip.contains(&peer.peer_info.id))
+            .choose(&mut thread_rng())
+        {
+            Some(peer) => self.request_epoch_sync_proofs(peer.peer_info.id.clone()),
+            None => {
+                warn!(
+                    target: "sync",
+                    epoch_ord = self.epoch_ord,
+                    "Epoch sync: no peer serves epoch proofs, falling back to header sync"
+                );
+                self.done = true;
+            }
+        }
+        Ok(self.done)
+    }
+
+    /// Requests proofs of the epochs following the last verified one.
+    fn request_epoch_sync_proofs(&mut self, peer_id: PeerId) {
+        self.last_request_time = Clock::utc();
+        self.last_request_peer_id = Some(peer_id.clone());
+        self.network_adapter.do_send(
+            PeerManagerMessageRequest::NetworkRequests(NetworkRequests::EpochSyncProofsRequest {
+                block_hash: self.sync_hash,
+                target: AccountOrPeerIdOrHash::PeerId(peer_id),
+            })
+            .with_span_context(),
+        );
+    }
+
+    /// Verifies epoch proofs received from a peer in order, stopping at the
+    /// first invalid one.  Responses which weren't requested are ignored.
+    pub fn on_epoch_sync_proofs(
+        &mut self,
+        response: EpochSyncProofsResponse,
+        peer_id: PeerId,
+    ) -> Result<(), near_chain::Error> {
+        if self.done
+            || response.block_hash != self.sync_hash
+            || self.last_request_peer_id.as_ref() != Some(&peer_id)
+        {
+            return Ok(());
+        }
+        self.last_request_peer_id = None;
+        let proofs = match response.proofs {
+            Some(proofs) => proofs,
+            None => {
+                self.peers_to_skip.insert(peer_id);
+                return Ok(());
+            }
+        };
+        if proofs.is_empty() {
+            info!(
+                target: "sync",
+                epoch_ord = self.epoch_ord,
+                sync_hash = ?self.sync_hash,
+                "Epoch sync: reached the latest epoch"
+            );
+            self.done = true;
+            return Ok(());
+        }
+        for proof in proofs {
+            if proof.header.epoch_id() != &self.next_epoch_id {
+                return Err(near_chain::Error::InvalidEpochSyncProof);
+            }
+            self.next_block_producers =
+                Chain::verify_epoch_sync_proof(&proof, &self.next_block_producers)?;
+            self.current_epoch_id = proof.header.epoch_id().clone();
+            self.next_epoch_id = proof.header.next_epoch_id().clone();
+            self.sync_hash = *proof.header.hash();
+            self.epoch_ord += 1;
+        }
+        debug!(
+            target: "sync",
+            epoch_ord = self.epoch_ord,
+            epoch_id = ?self.current_epoch_id,
+            sync_hash = ?self.sync_hash,
+            "Epoch sync: verified epochs"
+        );
+        Ok(())
+    }
+
+    /// Returns the hash of the first block of the latest epoch proven by epoch
+    /// sync, if it has completed after verifying at least one epoch.
+    pub fn verified_sync_hash(&self) -> Option<CryptoHash> {
+        (self.done && self.epoch_ord > 0).then_some(self.sync_hash)
+    }
 }
 
 /// Helper to keep track of sync headers.
@@ -139,6 +289,13 @@ pub struct HeaderSync {
     progress_timeout: Duration,
     stall_ban_timeout: Duration,
     expected_height_per_second: u64,
+
+    /// Whether timeouts, the expected batch of headers and the peer to sync
+    /// from are adjusted to the throughput measured for peers.
+    adaptive: bool,
+    stats: HeaderSyncStats,
+    /// Number of headers expected in response to the latest request.
+    batch_size: BlockHeight,
 }
 
 impl HeaderSync {
@@ -148,6 +305,7 @@ impl HeaderSync {
         progress_timeout: TimeDuration,
         stall_ban_timeout: TimeDuration,
         expected_height_per_second: u64,
+        adaptive: bool,
     ) -> Self {
         HeaderSync {
             network_adapter,
@@ -159,6 +317,28 @@ impl HeaderSync {
             progress_timeout: Duration::from_std(progress_timeout).unwrap(),
             stall_ban_timeout: Duration::from_std(stall_ban_timeout).unwrap(),
             expected_height_per_second,
+            adaptive,
+            stats: HeaderSyncStats::default(),
+            batch_size: MAX_BLOCK_HEADERS,
+        }
+    }
+
+    /// Records headers received from a peer in response to a request.
+    pub fn on_headers_received(&mut self, peer_id: &PeerId, headers: &[BlockHeader]) {
+        // The peer had more headers than it sent unless it reached the height
+        // which was the highest when the headers were requested.
+        let prev_highest_height = self.prev_header_sync.3;
+        let truncated =
+            headers.last().map_or(false, |header| header.height() < prev_highest_height);
+        self.stats.record_response(peer_id, headers.len() as u64, truncated, Clock::instant());
+    }
+
+    /// Header sync statistics of the peers headers were requested from.
+    pub fn stats_view(&self) -> HeaderSyncStatsView {
+        HeaderSyncStatsView {
+            adaptive: self.adaptive,
+            syncing_peer: self.stats.syncing_peer().cloned(),
+            peers: self.stats.view(),
         }
     }
 
@@ -200,16 +380,41 @@ impl HeaderSync {
                 highest_height,
             };
             self.syncing_peer = None;
-            if let Some(peer) = highest_height_peers.choose(&mut thread_rng()).cloned() {
+            let peer = if self.adaptive {
+                self.stats.choose_peer(highest_height_peers)
+            } else {
+                highest_height_peers.choose(&mut thread_rng()).cloned()
+            };
+            if let Some(peer) = peer {
                 if peer.chain_info.height > header_head.height {
                     self.syncing_peer = self.request_headers(chain, peer);
                 }
             }
+            if self.adaptive {
+                let peer_id = self.syncing_peer.as_ref().map(|peer| peer.peer_info.id.clone());
+                if let Some(peer_id) = peer_id {
+                    self.adapt_to_peer(&peer_id, &header_head, highest_height);
+                }
+            }
         }
 
         Ok(())
     }
 
+    /// Sets the timeout and the expected batch of headers for a request sent
+    /// to `peer_id`, based on how fast it delivered headers before.  Headers
+    /// arrive in batches, so the request times out only if no headers at all
+    /// arrive before the timeout.
+    fn adapt_to_peer(&mut self, peer_id: &PeerId, header_head: &Tip, highest_height: BlockHeight) {
+        self.batch_size = self.stats.batch_size(peer_id);
+        let batch_time = self.stats.batch_time(peer_id).and_then(|t| Duration::from_std(t).ok());
+        // Allow for the throughput to vary.
+        let timeout = batch_time.map_or(self.initial_timeout, |t| max(self.initial_timeout, t * 2));
+        let timeout = min(timeout, self.stall_ban_timeout);
+        self.prev_header_sync =
+            (Clock::utc() + timeout, header_head.height, header_head.height, highest_height);
+    }
+
     fn compute_expected_height(
         &self,
         old_height: BlockHeight,
@@ -232,19 +437,29 @@ impl HeaderSync {
             self.prev_header_sync;
 
         // Received all necessary header, can request more.
-        let all_headers_received =
-            header_head.height >= min(prev_height + MAX_BLOCK_HEADERS - 4, prev_highest_height);
+        let all_headers_received = header_head.height
+            >= min(prev_height + max(self.batch_size, 5) - 4, prev_highest_height);
 
         // Did we receive as many headers as we expected from the peer? Request more or ban peer.
         let stalling = header_head.height <= old_expected_height && now > timeout;
 
+        // In the adaptive mode a request which timed out after some progress
+        // isn't stalling, but is done as well.
+        let timed_out = self.adaptive && now > timeout;
+
         // Always enable header sync on initial state transition from NoSync / NoSyncFewBlocksBehind / AwaitingPeers.
         let force_sync = match sync_status {
             SyncStatus::NoSync | SyncStatus::AwaitingPeers => true,
             _ => false,
         };
 
-        if force_sync || all_headers_received || stalling {
+        if stalling {
+            if let Some(peer) = &self.syncing_peer {
+                self.stats.record_stall(&peer.peer_info.id);
+            }
+        }
+
+        if force_sync || all_headers_received || stalling || timed_out {
             self.prev_header_sync = (
                 now + self.initial_timeout,
                 self.compute_expected_height(header_head.height, self.initial_timeout),
@@ -295,6 +510,9 @@ impl HeaderSync {
             }
             self.syncing_peer = None;
             true
+        } else if self.adaptive {
+            // The timeout already accounts for the throughput of the peer.
+            false
         } else {
             // Resetting the timeout as long as we make progress.
             let ns_time_till_timeout =
@@ -320,6 +538,7 @@ impl HeaderSync {
     fn request_headers(&mut self, chain: &Chain, peer: FullPeerInfo) -> Option<FullPeerInfo> {
         if let Ok(locator) = self.get_locator(chain) {
             debug!(target: "sync", "Sync: request headers: asking {} for headers, {:?}", peer.peer_info.id, locator);
+            self.stats.record_request(&peer.peer_info.id, Clock::instant());
             self.network_adapter.do_send(
                 PeerManagerMessageRequest::NetworkRequests(NetworkRequests::BlockHeadersRequest {
                     hashes: locator,
@@ -415,6 +634,8 @@ pub struct BlockSyncRequest {
 pub struct BlockSync {
     network_adapter: Arc<dyn PeerManagerAdapter>,
     last_request: Option<BlockSyncRequest>,
+    /// Blocks requested and not delivered yet.
+    requests: BlockSyncRequests,
     /// How far to fetch blocks vs fetch state.
     block_fetch_horizon: BlockHeightDelta,
     /// Whether to enforce block sync
@@ -427,7 +648,13 @@ impl BlockSync {
         block_fetch_horizon: BlockHeightDelta,
         archive: bool,
     ) -> Self {
-        BlockSync { network_adapter, last_request: None, block_fetch_horizon, archive }
+        BlockSync {
+            network_adapter,
+            last_request: None,
+            requests: BlockSyncRequests::default(),
+            block_fetch_horizon,
+            archive,
+        }
     }
 
     /// Runs check if block sync is needed, if it's needed and it's too far - sync state is started instead (returning true).
@@ -482,21 +709,29 @@ impl BlockSync {
     }
 
     /// Returns true if state download is required (last known block is too far).
-    /// Otherwise request recent blocks from peers round robin.
+    /// Otherwise requests the blocks ahead of the head from peers in parallel, up to
+    /// MAX_BLOCK_REQUESTS_PER_PEER blocks from each peer at a time.
     fn block_sync(
         &mut self,
         chain: &Chain,
         highest_height_peers: &[FullPeerInfo],
     ) -> Result<bool, near_chain::Error> {
         if self.check_state_needed(chain)? {
+            self.requests.clear();
             return Ok(true);
         }
 
         let chain_head = chain.head()?;
+        let now = Clock::utc();
         // update last request now because we want to update it whether or not the rest of the logic
         // succeeds
-        self.last_request =
-            Some(BlockSyncRequest { head: chain_head.last_block_hash, when: Clock::utc() });
+        self.last_request = Some(BlockSyncRequest { head: chain_head.last_block_hash, when: now });
+
+        for hash in self.requests.requested() {
+            if check_known(chain, &hash)?.is_err() {
+                self.requests.on_block_received(&hash);
+            }
+        }
 
         // reference_hash is the last block on the canonical chain that is in store (processed)
         let reference_hash = {
@@ -543,7 +778,7 @@ impl BlockSync {
         };
 
         // Look ahead for MAX_BLOCK_REQUESTS blocks and add the ones we don't have yet
-        let mut requests = vec![];
+        let mut needed = vec![];
         let mut next_hash = reference_hash;
         for _ in 0..MAX_BLOCK_REQUESTS {
             match chain.store().get_next_block_hash(&next_hash) {
@@ -555,27 +790,31 @@ impl BlockSync {
             }
             if let Ok(()) = check_known(chain, &next_hash)? {
                 let next_height = chain.get_block_header(&next_hash)?.height();
-                requests.push((next_height, next_hash));
+                needed.push((next_height, next_hash));
             }
         }
+        let needed_hashes: HashSet<_> = needed.iter().map(|(_, hash)| *hash).collect();
+        self.requests.retain(|hash| needed_hashes.contains(hash));
+        let expired = self.requests.expire(now);
+        if expired > 0 {
+            debug!(target: "sync", "Block sync: {} block requests timed out", expired);
+        }
 
         let header_head = chain.header_head()?;
 
         let gc_stop_height = chain.runtime_adapter.get_gc_stop_height(&header_head.last_block_hash);
 
-        for request in requests {
-            let (height, hash) = request;
+        let all_peers: Vec<_> = highest_height_peers.iter().collect();
+        let archival_peers: Vec<_> =
+            highest_height_peers.iter().filter(|p| p.chain_info.archival).collect();
+        for (height, hash) in needed {
+            if !self.requests.needs_request(&hash) {
+                continue;
+            }
             let request_from_archival = self.archive && height < gc_stop_height;
-            let peer = if request_from_archival {
-                let archival_peer_iter =
-                    highest_height_peers.iter().filter(|p| p.chain_info.archival);
-                archival_peer_iter.choose(&mut rand::thread_rng())
-            } else {
-                let peer_iter = highest_height_peers.iter();
-                peer_iter.choose(&mut rand::thread_rng())
-            };
+            let peers = if request_from_archival { &archival_peers } else { &all_peers };
 
-            if let Some(peer) = peer {
+            if let Some(peer) = self.requests.choose_peer(&hash, peers) {
                 debug!(target: "sync", "Block sync: {}/{} requesting block {} at height {} from {} (out of {} peers)",
                        chain_head.height, header_head.height, hash, height, peer.peer_info.id, highest_height_peers.len());
                 self.network_adapter.do_send(
@@ -585,7 +824,8 @@ impl BlockSync {
                     })
                     .with_span_context(),
                 );
-            } else {
+                self.requests.record_request(hash, peer.peer_info.id.clone(), now);
+            } else if peers.is_empty() {
                 warn!(target: "sync", "Block sync: {}/{} No available {}peers to request block {} from",
                       chain_head.height, header_head.height, if request_from_archival { "archival " } else { "" }, hash);
             }
@@ -661,10 +901,45 @@ pub struct StateSync {
 
     /// Maps shard_id to result of splitting state for resharding
     split_state_roots: HashMap<ShardId, Result<HashMap<ShardUId, StateRoot>, Error>>,
+
+    /// Whether state parts are requested in sub-parts rather than as a whole.
+    use_sub_parts: bool,
+    /// Size of the sub-parts requested from each peer.
+    sub_part_sizes: StateSubPartSizes,
+    /// Target, size and time of the sub-part requests which haven't been
+    /// answered yet, by shard, sync hash, part id and sub-part id.
+    sub_part_requests:
+        HashMap<(ShardId, CryptoHash, u64, u64), (AccountOrPeerIdOrHash, u64, DateTime<Utc>)>,
+
+    /// Where the state headers and parts are downloaded from.
+    source: StateSyncSource,
+
+    /// Where state parts are fetched from before requesting them from peers.
+    parts_provider: Option<Arc<dyn StatePartsProvider>>,
+    /// Parts fetched by the provider which haven't been saved yet, with
+    /// `None` for the parts the provider doesn't have.
+    fetched_parts: Arc<Mutex<Vec<(ShardId, CryptoHash, u64, Option<Vec<u8>>)>>>,
+    /// Number of parts being fetched by the provider.
+    pending_provider_parts: usize,
+    /// Parts the provider doesn't have, which are requested from peers.
+    parts_missing_from_provider: HashSet<(ShardId, CryptoHash, u64)>,
+    /// Files of the local directory which have been read but not saved yet.
+    local_files: Arc<Mutex<Vec<LocalStateFile>>>,
 }
 
 impl StateSync {
-    pub fn new(network_adapter: Arc<dyn PeerManagerAdapter>, timeout: TimeDuration) -> Self {
+    pub fn new(
+        network_adapter: Arc<dyn PeerManagerAdapter>,
+        timeout: TimeDuration,
+        use_sub_parts: bool,
+        source: StateSyncSource,
+    ) -> Self {
+        let parts_provider = match &source {
+            StateSyncSource::Http { url } => {
+                Some(Arc::new(HttpStatePartsProvider::new(url)) as Arc<dyn StatePartsProvider>)
+            }
+            StateSyncSource::Peers | StateSyncSource::LocalDirectory { .. } => None,
+        };
         StateSync {
             network_adapter,
             state_sync_time: Default::default(),
@@ -674,9 +949,24 @@ impl StateSync {
             timeout: Duration::from_std(timeout).unwrap(),
             state_parts_apply_results: HashMap::new(),
             split_state_roots: HashMap::new(),
+            use_sub_parts,
+            sub_part_sizes: Default::default(),
+            sub_part_requests: HashMap::new(),
+            source,
+            parts_provider,
+            fetched_parts: Default::default(),
+            pending_provider_parts: 0,
+            parts_missing_from_provider: HashSet::new(),
+            local_files: Default::default(),
         }
     }
 
+    /// Replaces the source state parts are fetched from before requesting
+    /// them from peers.
+    pub fn set_parts_provider(&mut self, parts_provider: Option<Arc<dyn StatePartsProvider>>) {
+        self.parts_provider = parts_provider;
+    }
+
     pub fn sync_block_status(
         &mut self,
         prev_hash: &CryptoHash,
@@ -735,6 +1025,7 @@ impl StateSync {
                     done: false,
                     state_requests_count: 0,
                     last_target: None,
+                    sub_parts: Default::default(),
                 };
                 1
             ],
@@ -751,6 +1042,8 @@ impl StateSync {
             panic!("cannot sync to the first epoch after sharding upgrade. Please wait for the next epoch or find peers that are more up to date");
         }
         let split_states = runtime_adapter.will_shard_layout_change_next_epoch(&prev_hash)?;
+        self.save_fetched_parts(sync_hash, new_shard_sync, chain);
+        self.save_local_files(sync_hash, new_shard_sync, chain);
 
         for shard_id in tracking_shards {
             let mut download_timeout = false;
@@ -778,6 +1071,7 @@ impl StateSync {
                                     done: false,
                                     state_requests_count: 0,
                                     last_target: None,
+                                    sub_parts: Default::default(),
                                 };
                                 state_num_parts as usize
                             ],
@@ -828,16 +1122,18 @@ impl StateSync {
                     let shard_state_header = chain.get_state_header(shard_id, sync_hash)?;
                     let state_num_parts =
                         get_num_state_parts(shard_state_header.state_root_node().memory_usage);
+                    let status = Arc::new(StatePartsApplyingStatus::new(state_num_parts));
                     match chain.schedule_apply_state_parts(
                         shard_id,
                         sync_hash,
                         state_num_parts,
                         state_parts_task_scheduler,
+                        status.clone(),
                     ) {
                         Ok(()) => {
                             *shard_sync_download = ShardSyncDownload {
                                 downloads: vec![],
-                                status: ShardSyncStatus::StateDownloadApplying,
+                                status: ShardSyncStatus::StateDownloadApplying(status),
                             }
                         }
                         Err(e) => {
@@ -849,7 +1145,7 @@ impl StateSync {
                         }
                     }
                 }
-                ShardSyncStatus::StateDownloadApplying => {
+                ShardSyncStatus::StateDownloadApplying(_) => {
                     let result = self.state_parts_apply_results.remove(&shard_id);
                     if let Some(result) = result {
                         match chain.set_state_finalize(shard_id, sync_hash, result) {
@@ -961,15 +1257,29 @@ impl StateSync {
             // Execute syncing for shard `shard_id`
             if need_shard {
                 update_sync_status = true;
-                *shard_sync_download = self.request_shard(
-                    me,
-                    shard_id,
-                    chain,
-                    runtime_adapter,
-                    sync_hash,
-                    shard_sync_download.clone(),
-                    highest_height_peers,
-                )?;
+                *shard_sync_download = match &self.source {
+                    StateSyncSource::Peers | StateSyncSource::Http { .. } => {
+                        self.fetch_parts_from_provider(shard_id, sync_hash, shard_sync_download);
+                        self.request_shard(
+                            me,
+                            shard_id,
+                            chain,
+                            runtime_adapter,
+                            sync_hash,
+                            shard_sync_download.clone(),
+                            highest_height_peers,
+                        )?
+                    }
+                    StateSyncSource::LocalDirectory { path } => {
+                        self.read_shard_from_directory(
+                            path,
+                            shard_id,
+                            sync_hash,
+                            shard_sync_download,
+                        );
+                        shard_sync_download.clone()
+                    }
+                };
             }
             update_sync_status |= shard_sync_download.status != old_status;
         }
@@ -977,6 +1287,189 @@ impl StateSync {
         Ok((update_sync_status, all_done))
     }
 
+    /// Starts fetching the parts which need to be downloaded from the parts
+    /// provider, unless the provider is known not to have them.
+    fn fetch_parts_from_provider(
+        &mut self,
+        shard_id: ShardId,
+        sync_hash: CryptoHash,
+        shard_sync_download: &mut ShardSyncDownload,
+    ) {
+        let parts_provider = match (&self.parts_provider, &shard_sync_download.status) {
+            (Some(parts_provider), ShardSyncStatus::StateDownloadParts) => parts_provider.clone(),
+            _ => return,
+        };
+        for (part_id, download) in shard_sync_download.downloads.iter_mut().enumerate() {
+            if self.pending_provider_parts >= MAX_PENDING_PROVIDER_PARTS {
+                break;
+            }
+            let part_id = part_id as u64;
+            if !download.run_me.load(Ordering::SeqCst)
+                || self.parts_missing_from_provider.contains(&(shard_id, sync_hash, part_id))
+            {
+                continue;
+            }
+            download.run_me.store(false, Ordering::SeqCst);
+            download.state_requests_count += 1;
+            download.last_target = None;
+            self.pending_provider_parts += 1;
+            let fetched_parts = self.fetched_parts.clone();
+            near_performance_metrics::actix::spawn(
+                std::any::type_name::<Self>(),
+                parts_provider.fetch_part(shard_id, sync_hash, part_id).map(move |data| {
+                    fetched_parts.lock().unwrap().push((shard_id, sync_hash, part_id, data));
+                }),
+            );
+        }
+    }
+
+    /// Saves the parts fetched by the parts provider.  Parts the provider
+    /// doesn't have or which don't pass validation are requested from peers.
+    fn save_fetched_parts(
+        &mut self,
+        sync_hash: CryptoHash,
+        new_shard_sync: &mut HashMap<u64, ShardSyncDownload>,
+        chain: &mut Chain,
+    ) {
+        self.parts_missing_from_provider
+            .retain(|(_, part_sync_hash, _)| *part_sync_hash == sync_hash);
+        let fetched_parts = std::mem::take(&mut *self.fetched_parts.lock().unwrap());
+        for (shard_id, part_sync_hash, part_id, data) in fetched_parts {
+            self.pending_provider_parts -= 1;
+            let shard_sync_download = match new_shard_sync.get_mut(&shard_id) {
+                Some(shard_sync_download) if part_sync_hash == sync_hash => shard_sync_download,
+                _ => continue,
+            };
+            if !matches!(shard_sync_download.status, ShardSyncStatus::StateDownloadParts) {
+                continue;
+            }
+            let num_parts = shard_sync_download.downloads.len() as u64;
+            let download = match shard_sync_download.downloads.get_mut(part_id as usize) {
+                Some(download) if !download.done => download,
+                _ => continue,
+            };
+            if let Some(data) = data {
+                let part = PartId::new(part_id, num_parts);
+                match chain.set_state_part(shard_id, sync_hash, part, &data) {
+                    Ok(()) => {
+                        download.done = true;
+                        continue;
+                    }
+                    Err(err) => {
+                        warn!(
+                            target: "sync",
+                            shard_id,
+                            part_id,
+                            ?err,
+                            "State part fetched from the parts provider is invalid"
+                        );
+                    }
+                }
+            }
+            self.parts_missing_from_provider.insert((shard_id, sync_hash, part_id));
+            download.run_me.store(true, Ordering::SeqCst);
+        }
+    }
+
+    /// Starts reading the state header or the state parts of the shard,
+    /// whichever is being downloaded, from a local directory instead of
+    /// requesting them from peers.  The files are read in the background and
+    /// saved by `save_local_files`.
+    ///
+    /// Files which can't be read or don't pass validation are read again once
+    /// the state sync timeout passes, the same as requests peers didn't
+    /// respond to.
+    fn read_shard_from_directory(
+        &self,
+        dir: &Path,
+        shard_id: ShardId,
+        sync_hash: CryptoHash,
+        shard_sync_download: &mut ShardSyncDownload,
+    ) {
+        let shard_dir = local_shard_dir(dir, &sync_hash, shard_id);
+        let is_header = match shard_sync_download.status {
+            ShardSyncStatus::StateDownloadHeader => true,
+            ShardSyncStatus::StateDownloadParts => false,
+            _ => return,
+        };
+        for (part_id, download) in shard_sync_download
+            .downloads
+            .iter_mut()
+            .enumerate()
+            .filter(|(_, download)| download.run_me.load(Ordering::SeqCst))
+        {
+            download.run_me.store(false, Ordering::SeqCst);
+            download.state_requests_count += 1;
+            let (part_id, path) = if is_header {
+                (None, shard_dir.join("header"))
+            } else {
+                (Some(part_id as u64), shard_dir.join(format!("part_{}", part_id)))
+            };
+            let local_files = self.local_files.clone();
+            near_performance_metrics::actix::spawn(std::any::type_name::<Self>(), async move {
+                let data = tokio::fs::read(&path).await;
+                local_files.lock().unwrap().push(LocalStateFile {
+                    shard_id,
+                    sync_hash,
+                    part_id,
+                    path,
+                    data,
+                });
+            });
+        }
+    }
+
+    /// Saves the state headers and parts read from the local directory.
+    fn save_local_files(
+        &mut self,
+        sync_hash: CryptoHash,
+        new_shard_sync: &mut HashMap<u64, ShardSyncDownload>,
+        chain: &mut Chain,
+    ) {
+        let local_files = std::mem::take(&mut *self.local_files.lock().unwrap());
+        for file in local_files {
+            let shard_sync_download = match new_shard_sync.get_mut(&file.shard_id) {
+                Some(shard_sync_download) if file.sync_hash == sync_hash => shard_sync_download,
+                _ => continue,
+            };
+            let num_parts = shard_sync_download.downloads.len() as u64;
+            let result = match (&shard_sync_download.status, file.part_id) {
+                (ShardSyncStatus::StateDownloadHeader, None) => file
+                    .data
+                    .and_then(|data| ShardStateSyncResponseHeader::try_from_slice(&data))
+                    .map_err(Error::from)
+                    .and_then(|header| chain.set_state_header(file.shard_id, sync_hash, header)),
+                (ShardSyncStatus::StateDownloadParts, Some(part_id)) => {
+                    file.data.map_err(Error::from).and_then(|data| {
+                        chain.set_state_part(
+                            file.shard_id,
+                            sync_hash,
+                            PartId::new(part_id, num_parts),
+                            &data,
+                        )
+                    })
+                }
+                _ => continue,
+            };
+            let download =
+                match shard_sync_download.downloads.get_mut(file.part_id.unwrap_or(0) as usize) {
+                    Some(download) if !download.done => download,
+                    _ => continue,
+                };
+            match result {
+                Ok(()) => download.done = true,
+                Err(err) => {
+                    warn!(
+                        target: "sync",
+                        path = ?file.path,
+                        ?err,
+                        "State sync failed to read local state file"
+                    );
+                }
+            }
+        }
+    }
+
     pub fn set_apply_result(&mut self, shard_id: ShardId, apply_result: Result<(), Error>) {
         self.state_parts_apply_results.insert(shard_id, apply_result);
     }
@@ -1120,6 +1613,7 @@ impl StateSync {
             sync_hash,
             highest_height_peers,
         )?;
+        self.expire_sub_part_requests();
 
         if possible_targets.is_empty() {
             return Ok(shard_sync_download);
@@ -1160,6 +1654,19 @@ impl StateSync {
             ShardSyncStatus::StateDownloadParts => {
                 let possible_targets_sampler =
                     SamplerLimited::new(possible_targets, MAX_STATE_PART_REQUEST);
+                // Parts left to the parts provider aren't requested from peers.
+                let provider_parts: HashSet<usize> = match self.parts_provider {
+                    Some(_) => (0..new_shard_sync_download.downloads.len())
+                        .filter(|part_id| {
+                            !self.parts_missing_from_provider.contains(&(
+                                shard_id,
+                                sync_hash,
+                                *part_id as u64,
+                            ))
+                        })
+                        .collect(),
+                    None => HashSet::new(),
+                };
 
                 // Iterate over all parts that needs to be requested (i.e. download.run_me is true).
                 // Parts are ordered such that its index match its part_id.
@@ -1169,7 +1676,9 @@ impl StateSync {
                     .downloads
                     .iter_mut()
                     .enumerate()
-                    .filter(|(_, download)| download.run_me.load(Ordering::SeqCst))
+                    .filter(|(part_id, download)| {
+                        download.run_me.load(Ordering::SeqCst) && !provider_parts.contains(part_id)
+                    })
                     .zip(possible_targets_sampler)
                 {
                     self.sent_request_part(target.clone(), part_id as u64, shard_id, sync_hash);
@@ -1178,6 +1687,32 @@ impl StateSync {
                     download.last_target = Some(make_account_or_peer_id_or_hash(target.clone()));
                     let run_me = download.run_me.clone();
 
+                    if self.use_sub_parts {
+                        let limit = match &target {
+                            AccountOrPeerIdOrHash::PeerId(peer_id) => highest_height_peers
+                                .iter()
+                                .find(|peer| &peer.peer_info.id == peer_id)
+                                .map_or(0, |peer| peer.state_sub_part_size_limit),
+                            _ => 0,
+                        };
+                        let limit = sub_part_size_limit(limit);
+                        let preferred = self.sub_part_sizes.size(&target, limit);
+                        let sub_part_size =
+                            download.sub_parts.choose_sub_part_size(preferred, limit);
+                        for sub_part_id in download.sub_parts.missing() {
+                            self.request_sub_part(
+                                shard_id,
+                                sync_hash,
+                                part_id as u64,
+                                sub_part_id,
+                                sub_part_size,
+                                target.clone(),
+                                run_me.clone(),
+                            );
+                        }
+                        continue;
+                    }
+
                     near_performance_metrics::actix::spawn(
                         std::any::type_name::<Self>(),
                         self.network_adapter
@@ -1210,6 +1745,107 @@ impl StateSync {
         Ok(new_shard_sync_download)
     }
 
+    fn request_sub_part(
+        &mut self,
+        shard_id: ShardId,
+        sync_hash: CryptoHash,
+        part_id: u64,
+        sub_part_id: u64,
+        sub_part_size: u64,
+        target: AccountOrPeerIdOrHash,
+        run_me: Arc<AtomicBool>,
+    ) {
+        self.sub_part_requests.insert(
+            (shard_id, sync_hash, part_id, sub_part_id),
+            (target.clone(), sub_part_size, Clock::utc()),
+        );
+        near_performance_metrics::actix::spawn(
+            std::any::type_name::<Self>(),
+            self.network_adapter
+                .send(
+                    PeerManagerMessageRequest::NetworkRequests(
+                        NetworkRequests::StateRequestSubPart {
+                            shard_id,
+                            sync_hash,
+                            part_id,
+                            sub_part_id,
+                            sub_part_size,
+                            target,
+                        },
+                    )
+                    .with_span_context(),
+                )
+                .then(move |result| {
+                    if let Ok(NetworkResponses::RouteNotFound) =
+                        result.map(|f| f.as_network_response())
+                    {
+                        // Request the missing sub-parts on the next iteration
+                        run_me.store(true, Ordering::SeqCst);
+                    }
+                    future::ready(())
+                }),
+        );
+    }
+
+    /// Treats the sub-part requests which weren't answered within the timeout
+    /// as a sign that the sub-parts requested from their targets are too
+    /// large.
+    fn expire_sub_part_requests(&mut self) {
+        let now = Clock::utc();
+        let timeout = self.timeout;
+        let sub_part_sizes = &mut self.sub_part_sizes;
+        self.sub_part_requests.retain(|_, (target, sub_part_size, sent)| {
+            if now - *sent <= timeout {
+                return true;
+            }
+            sub_part_sizes.record_timeout(target, *sub_part_size);
+            false
+        });
+    }
+
+    /// Records a sub-part received for a part being downloaded.  Returns the
+    /// whole part once all of its sub-parts have arrived.
+    pub fn received_sub_part(
+        &mut self,
+        download: &mut DownloadStatus,
+        shard_id: ShardId,
+        sync_hash: CryptoHash,
+        sub_part: StateSubPart,
+    ) -> Option<Vec<u8>> {
+        let part_id = sub_part.part_id;
+        let sub_part_id = sub_part.sub_part_id;
+        let sub_part_size = sub_part.sub_part_size;
+        let len = sub_part.data.len() as u64;
+        if download.sub_parts.sub_part_size != Some(sub_part_size) {
+            // An answer to a request made before the size of the sub-parts of
+            // this part changed.
+            debug!(target: "sync", part_id, sub_part_id, sub_part_size, "Ignoring stale state sub-part");
+            return None;
+        }
+        let knew_num_sub_parts = download.sub_parts.num_sub_parts.is_some();
+        if !download.sub_parts.insert(sub_part) {
+            error!(target: "sync", "State sync received invalid sub-part of part {} for hash {:?}, potential malicious peer", part_id, sync_hash);
+            download.error = true;
+            return None;
+        }
+        let key = (shard_id, sync_hash, part_id, sub_part_id);
+        if let Some((target, size, sent)) = self.sub_part_requests.remove(&key) {
+            if size == sub_part_size {
+                let elapsed = (Clock::utc() - sent).to_std().unwrap_or_default();
+                self.sub_part_sizes.record_received(&target, size, len, elapsed);
+            }
+        }
+        let part = download.sub_parts.assemble();
+        if part.is_some() {
+            self.received_requested_part(part_id, shard_id, sync_hash);
+        } else if !knew_num_sub_parts {
+            // Only the first sub-part was requested since the number of
+            // sub-parts was unknown.  Request the rest on the next iteration.
+            download.run_me.store(true, Ordering::SeqCst);
+        }
+        part
+    }
+
     pub fn run(
         &mut self,
         me: &Option<AccountId>,
@@ -1333,6 +1969,7 @@ mod test {
     use std::sync::Arc;
     use std::thread;
 
+    use near_actix_test_utils::run_actix;
     use near_chain::test_utils::{
         process_block_sync, setup, setup_with_validators, wait_for_all_blocks_in_processing,
         ValidatorSchedule,
@@ -1346,6 +1983,7 @@ mod test {
     use near_primitives::utils::MaybeValidated;
 
     use super::*;
+    use crate::block_sync_requests::MAX_BLOCK_REQUESTS_PER_PEER;
     use crate::test_utils::TestEnv;
     use near_network::types::{PartialEdgeInfo, PeerInfo};
     use near_primitives::merkle::PartialMerkleTree;
@@ -1355,6 +1993,156 @@ mod test {
     use num_rational::Ratio;
     use std::collections::HashSet;
 
+    #[test]
+    fn test_state_sync_sub_parts_resume() {
+        let network_adapter = Arc::new(MockPeerManagerAdapter::default());
+        let mut state_sync = StateSync::new(
+            network_adapter,
+            TimeDuration::from_secs(1),
+            /*use_sub_parts=*/ true,
+            StateSyncSource::Peers,
+        );
+        let now = Clock::utc();
+        let mut download = DownloadStatus {
+            start_time: now,
+            prev_update_time: now,
+            run_me: Arc::new(AtomicBool::new(false)),
+            error: false,
+            done: false,
+            state_requests_count: 0,
+            last_target: None,
+            sub_parts: Default::default(),
+        };
+        let sync_hash = CryptoHash::default();
+        let size = near_primitives::syncing::STATE_SUB_PART_SIZE.as_u64();
+        let part: Vec<u8> = (0..size * 2 + 1).map(|i| (i % 256) as u8).collect();
+        assert_eq!(download.sub_parts.choose_sub_part_size(size, size), size);
+        assert_eq!(download.sub_parts.missing(), vec![0]);
+
+        // Sub-parts of another size than the requested one are ignored.
+        let sub_part = StateSubPart::new(3, 0, size / 2, &part).unwrap();
+        assert!(state_sync.received_sub_part(&mut download, 0, sync_hash, sub_part).is_none());
+        assert!(!download.error);
+        assert_eq!(download.sub_parts.num_sub_parts, None);
+
+        // The first sub-part tells how many there are and schedules the rest.
+        let sub_part = StateSubPart::new(3, 0, size, &part).unwrap();
+        assert!(state_sync.received_sub_part(&mut download, 0, sync_hash, sub_part).is_none());
+        assert!(download.run_me.load(Ordering::SeqCst));
+        assert_eq!(download.sub_parts.missing(), vec![1, 2]);
+
+        // A corrupted sub-part is rejected and doesn't affect progress.
+        let mut corrupted = StateSubPart::new(3, 2, size, &part).unwrap();
+        corrupted.data[0] ^= 1;
+        assert!(state_sync.received_sub_part(&mut download, 0, sync_hash, corrupted).is_none());
+        assert!(download.error);
+        assert_eq!(download.sub_parts.missing(), vec![1, 2]);
+
+        // Re-requesting from a peer serving the same size keeps the progress,
+        // while the size of the sub-parts doesn't change midway.
+        assert_eq!(download.sub_parts.choose_sub_part_size(size * 2, size * 4), size);
+        assert_eq!(download.sub_parts.missing(), vec![1, 2]);
+
+        // After a re-request only missing sub-parts are needed.
+        for sub_part_id in [2, 1] {
+            let sub_part = StateSubPart::new(3, sub_part_id, size, &part).unwrap();
+            let result = state_sync.received_sub_part(&mut download, 0, sync_hash, sub_part);
+            assert_eq!(result.is_some(), sub_part_id == 1);
+            if let Some(assembled) = result {
+                assert_eq!(assembled, part);
+            }
+        }
+    }
+
+    fn parts_download(num_parts: usize, run_me: bool) -> ShardSyncDownload {
+        let now = Clock::utc();
+        ShardSyncDownload {
+            downloads: (0..num_parts)
+                .map(|_| DownloadStatus {
+                    start_time: now,
+                    prev_update_time: now,
+                    run_me: Arc::new(AtomicBool::new(run_me)),
+                    error: false,
+                    done: false,
+                    state_requests_count: 0,
+                    last_target: None,
+                    sub_parts: Default::default(),
+                })
+                .collect(),
+            status: ShardSyncStatus::StateDownloadParts,
+        }
+    }
+
+    #[test]
+    fn test_local_directory_missing_parts() {
+        run_actix(async {
+            let (mut chain, _, _) = setup();
+            let dir = std::env::temp_dir().join("test_local_directory_missing_parts");
+            let mut state_sync = StateSync::new(
+                Arc::new(MockPeerManagerAdapter::default()),
+                TimeDuration::from_secs(1),
+                false,
+                StateSyncSource::LocalDirectory { path: dir.clone() },
+            );
+            let sync_hash = CryptoHash::default();
+            let mut new_shard_sync = HashMap::from([(0, parts_download(2, true))]);
+            state_sync.read_shard_from_directory(
+                &dir,
+                0,
+                sync_hash,
+                new_shard_sync.get_mut(&0).unwrap(),
+            );
+            // The parts are read in the background.
+            while state_sync.local_files.lock().unwrap().len() < 2 {
+                tokio::time::sleep(std::time::Duration::from_millis(10)).await;
+            }
+            state_sync.save_local_files(sync_hash, &mut new_shard_sync, &mut chain);
+
+            // Missing parts are neither done nor retried before the timeout.
+            for part in &new_shard_sync[&0].downloads {
+                assert!(!part.done);
+                assert!(!part.error);
+                assert!(!part.run_me.load(Ordering::SeqCst));
+                assert_eq!(part.state_requests_count, 1);
+            }
+            actix::System::current().stop();
+        });
+    }
+
+    #[test]
+    fn test_parts_missing_from_provider() {
+        let (mut chain, _, _) = setup();
+        let mut state_sync = StateSync::new(
+            Arc::new(MockPeerManagerAdapter::default()),
+            TimeDuration::from_secs(1),
+            false,
+            StateSyncSource::Http { url: "http://localhost".to_string() },
+        );
+        let sync_hash = CryptoHash::default();
+        let mut new_shard_sync = HashMap::from([(0, parts_download(3, false))]);
+        // The provider doesn't have the first part and the second one fails
+        // validation since there's no state header to validate it against.
+        state_sync.pending_provider_parts = 2;
+        state_sync
+            .fetched_parts
+            .lock()
+            .unwrap()
+            .extend([(0, sync_hash, 0, None), (0, sync_hash, 1, Some(vec![1, 2, 3]))]);
+        state_sync.save_fetched_parts(sync_hash, &mut new_shard_sync, &mut chain);
+
+        assert_eq!(state_sync.pending_provider_parts, 0);
+        let downloads = &new_shard_sync[&0].downloads;
+        for part_id in 0..2 {
+            assert!(!downloads[part_id].done);
+            assert!(downloads[part_id].run_me.load(Ordering::SeqCst));
+        }
+        assert!(!downloads[2].run_me.load(Ordering::SeqCst));
+        assert_eq!(
+            state_sync.parts_missing_from_provider,
+            HashSet::from([(0, sync_hash, 0), (0, sync_hash, 1)])
+        );
+    }
+
     #[test]
     fn test_get_locator_heights() {
         assert_eq!(get_locator_heights(0), vec![0]);
@@ -1384,6 +2172,7 @@ mod test {
             TimeDuration::from_secs(2),
             TimeDuration::from_secs(120),
             1_000_000_000,
+            false,
         );
         let (mut chain, _, signer) = setup();
         for _ in 0..3 {
@@ -1424,6 +2213,7 @@ mod test {
                 archival: false,
             },
             partial_edge_info: PartialEdgeInfo::default(),
+            state_sub_part_size_limit: 0,
         };
         let head = chain.head().unwrap();
         assert!(header_sync
@@ -1463,6 +2253,7 @@ mod test {
             TimeDuration::from_secs(1),
             TimeDuration::from_secs(3),
             25,
+            false,
         );
 
         let set_syncing_peer = |header_sync: &mut HeaderSync| {
@@ -1474,6 +2265,7 @@ mod test {
                 },
                 chain_info: Default::default(),
                 partial_edge_info: Default::default(),
+                state_sub_part_size_limit: 0,
             });
             header_sync.syncing_peer.as_mut().unwrap().chain_info.height = highest_height;
         };
@@ -1632,13 +2424,10 @@ mod test {
     fn create_peer_infos(num_peers: usize) -> Vec<FullPeerInfo> {
         (0..num_peers)
             .map(|_| FullPeerInfo {
-                peer_info: PeerInfo {
-                    id: PeerId::new(PublicKey::empty(KeyType::ED25519)),
-                    addr: None,
-                    account_id: None,
-                },
+                peer_info: PeerInfo { id: PeerId::random(), addr: None, account_id: None },
                 chain_info: Default::default(),
                 partial_edge_info: Default::default(),
+                state_sub_part_size_limit: 0,
             })
             .collect()
     }
@@ -1652,8 +2441,10 @@ mod test {
         let mut chain_genesis = ChainGenesis::test();
         chain_genesis.epoch_length = 100;
         let mut env = TestEnv::builder(chain_genesis).clients_count(2).build();
+        // Two peers are asked for MAX_BLOCK_REQUESTS_PER_PEER blocks each.
+        let batch = 2 * MAX_BLOCK_REQUESTS_PER_PEER;
         let mut blocks = vec![];
-        for i in 1..5 * MAX_BLOCK_REQUESTS + 1 {
+        for i in 1..5 * batch + 1 {
             let block = env.clients[0].produce_block(i as u64).unwrap().unwrap();
             blocks.push(block.clone());
             env.process_block(0, block, Provenance::PRODUCED);
@@ -1664,13 +2455,12 @@ mod test {
         env.clients[1].chain.sync_block_headers(block_headers, &mut challenges).unwrap();
         assert!(challenges.is_empty());
 
-        // fetch three blocks at a time
+        // fetch a batch of blocks at a time
         for i in 0..3 {
             let is_state_sync = block_sync.block_sync(&env.clients[1].chain, &peer_infos).unwrap();
             assert!(!is_state_sync);
 
-            let expected_blocks: Vec<_> =
-                blocks[i * MAX_BLOCK_REQUESTS..(i + 1) * MAX_BLOCK_REQUESTS].to_vec();
+            let expected_blocks: Vec<_> = blocks[i * batch..(i + 1) * batch].to_vec();
             check_hashes_from_network_adapter(
                 &network_adapter,
                 expected_blocks.iter().map(|b| *b.hash()).collect(),
@@ -1682,32 +2472,28 @@ mod test {
         }
 
         // Now test when the node receives the block out of order
-        // fetch the next three blocks
+        // fetch the next batch of blocks
         let is_state_sync = block_sync.block_sync(&env.clients[1].chain, &peer_infos).unwrap();
         assert!(!is_state_sync);
         check_hashes_from_network_adapter(
             &network_adapter,
-            (3 * MAX_BLOCK_REQUESTS..4 * MAX_BLOCK_REQUESTS).map(|h| *blocks[h].hash()).collect(),
+            (3 * batch..4 * batch).map(|h| *blocks[h].hash()).collect(),
         );
-        // assumes that we only get block[4*MAX_BLOCK_REQUESTS-1]
+        // assumes that we only get block[4*batch-1]
         let _ = env.clients[1].process_block_test(
-            MaybeValidated::from(blocks[4 * MAX_BLOCK_REQUESTS - 1].clone()),
+            MaybeValidated::from(blocks[4 * batch - 1].clone()),
             Provenance::NONE,
         );
-        // the next block sync should not request block[4*MAX_BLOCK_REQUESTS-1] again
+        // the next block sync should neither request block[4*batch-1] again nor the blocks
+        // which are still in flight, and should use the freed slot for the next block
         let is_state_sync = block_sync.block_sync(&env.clients[1].chain, &peer_infos).unwrap();
         assert!(!is_state_sync);
-        check_hashes_from_network_adapter(
-            &network_adapter,
-            (3 * MAX_BLOCK_REQUESTS..4 * MAX_BLOCK_REQUESTS - 1)
-                .map(|h| *blocks[h].hash())
-                .collect(),
-        );
+        check_hashes_from_network_adapter(&network_adapter, vec![*blocks[4 * batch].hash()]);
 
         // Receive all blocks. Should not request more. As an extra
         // complication, pause the processing of one block.
-        env.pause_block_processing(&mut capture, blocks[4 * MAX_BLOCK_REQUESTS - 1].hash());
-        for i in 3 * MAX_BLOCK_REQUESTS..5 * MAX_BLOCK_REQUESTS {
+        env.pause_block_processing(&mut capture, blocks[4 * batch - 1].hash());
+        for i in 3 * batch..5 * batch {
             let _ = env.clients[1]
                 .process_block_test(MaybeValidated::from(blocks[i].clone()), Provenance::NONE);
         }
@@ -1717,7 +2503,7 @@ mod test {
 
         // Now finish paused processing processing and sanity check that we
         // still are fully synced.
-        env.resume_block_processing(blocks[4 * MAX_BLOCK_REQUESTS - 1].hash());
+        env.resume_block_processing(blocks[4 * batch - 1].hash());
         wait_for_all_blocks_in_processing(&mut env.clients[1].chain);
         let requested_block_hashes = collect_hashes_from_network_adapter(&network_adapter);
         assert!(requested_block_hashes.is_empty(), "{:?}", requested_block_hashes);
@@ -1757,7 +2543,11 @@ mod test {
         let requested_block_hashes = collect_hashes_from_network_adapter(&network_adapter);
         assert_eq!(
             requested_block_hashes,
-            blocks.iter().take(MAX_BLOCK_REQUESTS).map(|b| *b.hash()).collect::<HashSet<_>>()
+            blocks
+                .iter()
+                .take(2 * MAX_BLOCK_REQUESTS_PER_PEER)
+                .map(|b| *b.hash())
+                .collect::<HashSet<_>>()
         );
     }
 }
@@ -69,9 +69,10 @@ use near_telemetry::TelemetryActor;
 
 use crate::adapter::{
     AnnounceAccountRequest, BlockApproval, BlockHeadersRequest, BlockHeadersResponse, BlockRequest,
-    BlockResponse, ProcessTxResponse, RecvPartialEncodedChunk, RecvPartialEncodedChunkForward,
+    BlockResponse, ProcessTxResponse, RecvChunkInclusionFeedback, RecvPartialEncodedChunk,
+    RecvPartialEncodedChunkAvailability, RecvPartialEncodedChunkForward,
     RecvPartialEncodedChunkRequest, RecvPartialEncodedChunkResponse, SetNetworkInfo,
-    StateRequestHeader, StateRequestPart, StateResponse,
+    StateRequestHeader, StateRequestPart, StateRequestSubPart, StateResponse,
 };
 
 pub struct PeerManagerMock {
@@ -218,8 +219,14 @@ pub fn setup(
     } else {
         DoomslugThresholdMode::NoApprovals
     };
-    let chain =
-        Chain::new(runtime.clone(), &chain_genesis, doomslug_threshold_mode, !archive).unwrap();
+    let chain = Chain::new(
+        runtime.clone(),
+        &chain_genesis,
+        doomslug_threshold_mode,
+        !archive,
+        &near_o11y::metrics::MetricsRegistry::global(),
+    )
+    .unwrap();
     let genesis_block = chain.get_block(&chain.genesis().hash().clone()).unwrap();
 
     let signer = Arc::new(InMemoryValidatorSigner::from_seed(
@@ -243,6 +250,7 @@ pub fn setup(
         Some(signer.validator_id().clone()),
         chain_genesis.clone(),
         runtime.clone(),
+        runtime.store().clone(),
         network_adapter.clone(),
         config.clone(),
         adv.clone(),
@@ -303,7 +311,14 @@ pub fn setup_only_view(
     } else {
         DoomslugThresholdMode::NoApprovals
     };
-    Chain::new(runtime.clone(), &chain_genesis, doomslug_threshold_mode, !archive).unwrap();
+    Chain::new(
+        runtime.clone(),
+        &chain_genesis,
+        doomslug_threshold_mode,
+        !archive,
+        &near_o11y::metrics::MetricsRegistry::global(),
+    )
+    .unwrap();
 
     let signer = Arc::new(InMemoryValidatorSigner::from_seed(
         account_id.clone(),
@@ -325,7 +340,8 @@ pub fn setup_only_view(
     start_view_client(
         Some(signer.validator_id().clone()),
         chain_genesis,
-        runtime,
+        runtime.clone(),
+        runtime.store().clone(),
         network_adapter.clone(),
         config,
         adv,
@@ -660,6 +676,7 @@ pub fn setup_mock_all_validators(
                                         archival: true,
                                     },
                                     partial_edge_info: PartialEdgeInfo::default(),
+                                    state_sub_part_size_limit: 0,
                                 },
                                 received_bytes_per_sec: 0,
                                 sent_bytes_per_sec: 0,
@@ -667,6 +684,9 @@ pub fn setup_mock_all_validators(
                                 last_time_received_message: near_network::time::Instant::now(),
                                 connection_established_time: near_network::time::Instant::now(),
                                 peer_type: PeerType::Outbound,
+                                rtt: None,
+                                score: 0,
+                                bandwidth_by_type: vec![],
                             })
                             .collect();
                         let peers2 = peers.iter().map(|it| it.full_peer_info.clone()).collect();
@@ -679,12 +699,14 @@ pub fn setup_mock_all_validators(
                             received_bytes_per_sec: 0,
                             known_producers: vec![],
                             tier1_accounts: vec![],
+                            bandwidth_by_type: vec![],
                         };
                         client_addr.do_send(SetNetworkInfo(info).with_span_context());
                     }
 
                     match msg.as_network_requests_ref() {
-                        NetworkRequests::Block { block } => {
+                        NetworkRequests::Block { block }
+                        | NetworkRequests::BlockHeaderFirst { block } => {
                             if check_block_stats {
                                 let block_stats2 = &mut *block_stats1.write().unwrap();
                                 block_stats2.add_block(block);
@@ -767,6 +789,33 @@ pub fn setup_mock_all_validators(
                                 |c| c.do_send(create_msg()),
                             );
                         }
+                        NetworkRequests::PartialEncodedChunkAvailability {
+                            account_id,
+                            availability,
+                        } => {
+                            let create_msg = || {
+                                RecvPartialEncodedChunkAvailability(availability.clone())
+                                    .with_span_context()
+                            };
+                            send_chunks(
+                                connectors1,
+                                validators_clone2.iter().cloned().enumerate(),
+                                account_id.clone(),
+                                drop_chunks,
+                                |c| c.do_send(create_msg()),
+                            );
+                        }
+                        NetworkRequests::ChunkInclusionFeedback { account_id, feedback } => {
+                            let create_msg =
+                                || RecvChunkInclusionFeedback(feedback.clone()).with_span_context();
+                            send_chunks(
+                                connectors1,
+                                validators_clone2.iter().cloned().enumerate(),
+                                account_id.clone(),
+                                drop_chunks,
+                                |c| c.do_send(create_msg()),
+                            );
+                        }
                         NetworkRequests::BlockRequest { hash, peer_id } => {
                             for (i, peer_info) in key_pairs.iter().enumerate() {
                                 let peer_id = peer_id.clone();
@@ -900,6 +949,48 @@ pub fn setup_mock_all_validators(
                                 }
                             }
                         }
+                        NetworkRequests::StateRequestSubPart {
+                            shard_id,
+                            sync_hash,
+                            part_id,
+                            sub_part_id,
+                            sub_part_size,
+                            target: target_account_id,
+                        } => {
+                            let target_account_id = match target_account_id {
+                                AccountOrPeerIdOrHash::AccountId(x) => x,
+                                _ => panic!(),
+                            };
+                            for (i, name) in validators_clone2.iter().enumerate() {
+                                if name == target_account_id {
+                                    let me = connectors1[my_ord].0.clone();
+                                    actix::spawn(
+                                        connectors1[i]
+                                            .1
+                                            .send(
+                                                StateRequestSubPart {
+                                                    shard_id: *shard_id,
+                                                    sync_hash: *sync_hash,
+                                                    part_id: *part_id,
+                                                    sub_part_id: *sub_part_id,
+                                                    sub_part_size: *sub_part_size,
+                                                }
+                                                .with_span_context(),
+                                            )
+                                            .then(move |response| {
+                                                let response = response.unwrap();
+                                                match response {
+                                                    Some(response) => {
+                                                        me.do_send(response.with_span_context());
+                                                    }
+                                                    None => {}
+                                                }
+                                                future::ready(())
+                                            }),
+                                    );
+                                }
+                            }
+                        }
                         NetworkRequests::StateResponse { route_back, response } => {
                             for (i, address) in addresses.iter().enumerate() {
                                 if route_back == address {
@@ -993,7 +1084,12 @@ pub fn setup_mock_all_validators(
                         }
                         NetworkRequests::ForwardTx(_, _)
                         | NetworkRequests::BanPeer { .. }
+                        | NetworkRequests::RewardPeer { .. }
                         | NetworkRequests::TxStatus(_, _, _)
+                        | NetworkRequests::TxStatusSubscribe(_, _, _)
+                        | NetworkRequests::TxStatusPush { .. }
+                        | NetworkRequests::EpochSyncDataRequest { .. }
+                        | NetworkRequests::EpochSyncProofsRequest { .. }
                         | NetworkRequests::Challenge(_) => {}
                     };
                 }
@@ -1146,6 +1242,7 @@ pub struct TestEnvBuilder {
     validators: Vec<AccountId>,
     runtime_adapters: Option<Vec<Arc<dyn RuntimeAdapter>>>,
     network_adapters: Option<Vec<Arc<MockPeerManagerAdapter>>>,
+    stores: Option<Vec<Store>>,
     // random seed to be inject in each client according to AccountId
     // if not set, a default constant TEST_SEED will be injected
     seeds: HashMap<AccountId, RngSeed>,
@@ -1164,6 +1261,7 @@ impl TestEnvBuilder {
             validators,
             runtime_adapters: None,
             network_adapters: None,
+            stores: None,
             seeds,
         }
     }
@@ -1227,6 +1325,18 @@ impl TestEnvBuilder {
         self
     }
 
+    /// Specifies custom stores for each client, e.g. namespaces of a single
+    /// database (see [`Store::namespaced`]).  Can't be combined with custom
+    /// runtime adapters, which come with their own stores.
+    ///
+    /// The vector must have the same number of elements as they are clients
+    /// (one by default).  If that does not hold, [`Self::build`] method will
+    /// panic.
+    pub fn stores(mut self, stores: Vec<Store>) -> Self {
+        self.stores = Some(stores);
+        self
+    }
+
     /// Constructs new `TestEnv` structure.
     ///
     /// If no clients were configured (either through count or vector) one
@@ -1250,12 +1360,16 @@ impl TestEnvBuilder {
             .map(|_| Arc::new(MockClientAdapterForShardsManager::default()))
             .collect::<Vec<_>>();
         assert_eq!(clients.len(), network_adapters.len());
+        assert!(self.stores.is_none() || self.runtime_adapters.is_none());
+        let stores =
+            self.stores.unwrap_or_else(|| (0..num_clients).map(|_| create_test_store()).collect());
+        assert_eq!(clients.len(), stores.len());
         let clients = match self.runtime_adapters {
             None => clients
                 .into_iter()
                 .zip(network_adapters.iter())
-                .zip(client_adapters.iter())
-                .map(|((account_id, network_adapter), client_adapter)| {
+                .zip(client_adapters.iter().zip(stores))
+                .map(|((account_id, network_adapter), (client_adapter, store))| {
                     let rng_seed = match seeds.get(&account_id) {
                         Some(seed) => *seed,
                         None => TEST_SEED,
@@ -1263,7 +1377,7 @@ impl TestEnvBuilder {
                     let vs = ValidatorSchedule::new()
                         .block_producers_per_epoch(vec![validators.clone()]);
                     setup_client(
-                        create_test_store(),
+                        store,
                         vs,
                         Some(account_id),
                         false,
@@ -1322,6 +1436,36 @@ impl TestEnvBuilder {
     }
 }
 
+/// Independent chains running side by side in one process.  The clients of
+/// all chains keep their data in a single database, each under its own
+/// namespace, and every chain has its own mock network, so nothing produced on
+/// one chain reaches the others.
+pub struct MultiChainTestEnv {
+    pub store: Store,
+    pub chains: Vec<TestEnv>,
+}
+
+impl MultiChainTestEnv {
+    /// Builds a chain from each of the builders.  Stores configured in the
+    /// builders are replaced with namespaces of the shared database.
+    pub fn new(builders: Vec<TestEnvBuilder>) -> Self {
+        let store = create_test_store();
+        let chains = builders
+            .into_iter()
+            .enumerate()
+            .map(|(index, builder)| {
+                let stores = builder
+                    .clients
+                    .iter()
+                    .map(|account_id| store.namespaced(&format!("chain{index}/{account_id}")))
+                    .collect();
+                builder.stores(stores).build()
+            })
+            .collect();
+        Self { store, chains }
+    }
+}
+
 impl TestEnv {
     pub fn builder(chain_genesis: ChainGenesis) -> TestEnvBuilder {
         TestEnvBuilder::new(chain_genesis)
@@ -1688,6 +1832,7 @@ pub fn create_chunk_on_height_for_shard(
             Chain::get_prev_chunk_header(&*client.runtime_adapter, &last_block, shard_id).unwrap(),
             next_height,
             shard_id,
+            None,
         )
         .unwrap()
         .unwrap()
@@ -1723,6 +1868,7 @@ pub fn create_chunk(
             last_block.chunks()[0].clone(),
             next_height,
             0,
+            None,
         )
         .unwrap()
         .unwrap();
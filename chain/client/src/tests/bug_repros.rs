@@ -65,7 +65,9 @@ fn repro_1183() {
             vec![true; validators.len()],
             false,
             Box::new(move |_, _account_id: _, msg: &PeerManagerMessageRequest| {
-                if let NetworkRequests::Block { block } = msg.as_network_requests_ref() {
+                if let NetworkRequests::Block { block }
+                | NetworkRequests::BlockHeaderFirst { block } = msg.as_network_requests_ref()
+                {
                     let mut last_block = last_block.write().unwrap();
                     let mut delayed_one_parts = delayed_one_parts.write().unwrap();
 
@@ -198,7 +200,9 @@ fn test_sync_from_archival_node() {
                       msg: &PeerManagerMessageRequest|
                       -> (PeerManagerMessageResponse, bool) {
                     let msg = msg.as_network_requests_ref();
-                    if let NetworkRequests::Block { block } = msg {
+                    if let NetworkRequests::Block { block }
+                    | NetworkRequests::BlockHeaderFirst { block } = msg
+                    {
                         let mut largest_height = largest_height.write().unwrap();
                         *largest_height = max(block.header().height(), *largest_height);
                     }
@@ -207,7 +211,8 @@ fn test_sync_from_archival_node() {
                     }
                     if *largest_height.read().unwrap() <= 30 {
                         match msg {
-                            NetworkRequests::Block { block } => {
+                            NetworkRequests::Block { block }
+                            | NetworkRequests::BlockHeaderFirst { block } => {
                                 for (i, (client, _)) in conns.iter().enumerate() {
                                     if i != 3 {
                                         client.do_send(
@@ -256,7 +261,8 @@ fn test_sync_from_archival_node() {
                             );
                         }
                         match msg {
-                            NetworkRequests::Block { block } => {
+                            NetworkRequests::Block { block }
+                            | NetworkRequests::BlockHeaderFirst { block } => {
                                 if block.header().height() <= 10 {
                                     block_counter += 1;
                                 }
@@ -173,7 +173,9 @@ fn test_catchup_receipts_sync_common(wait_till: u64, send: u64, sync_hold: bool)
                 let mut seen_hashes_with_state = seen_hashes_with_state.write().unwrap();
                 match *phase {
                     ReceiptsSyncPhases::WaitingForFirstBlock => {
-                        if let NetworkRequests::Block { block } = msg {
+                        if let NetworkRequests::Block { block }
+                        | NetworkRequests::BlockHeaderFirst { block } = msg
+                        {
                             assert!(block.header().height() <= send);
                             // This tx is rather fragile, specifically it's important that
                             //   1. the `from` and `to` account are not in the same shard;
@@ -205,7 +207,9 @@ fn test_catchup_receipts_sync_common(wait_till: u64, send: u64, sync_hold: bool)
                     }
                     ReceiptsSyncPhases::WaitingForSecondBlock => {
                         // This block now contains a chunk with the transaction sent above.
-                        if let NetworkRequests::Block { block } = msg {
+                        if let NetworkRequests::Block { block }
+                        | NetworkRequests::BlockHeaderFirst { block } = msg
+                        {
                             assert!(block.header().height() <= send + 1);
                             if block.header().height() == send + 1 {
                                 *phase = ReceiptsSyncPhases::WaitingForDistantEpoch;
@@ -214,7 +218,9 @@ fn test_catchup_receipts_sync_common(wait_till: u64, send: u64, sync_hold: bool)
                     }
                     ReceiptsSyncPhases::WaitingForDistantEpoch => {
                         // This block now contains a chunk with the transaction sent above.
-                        if let NetworkRequests::Block { block } = msg {
+                        if let NetworkRequests::Block { block }
+                        | NetworkRequests::BlockHeaderFirst { block } = msg
+                        {
                             assert!(block.header().height() >= send + 1);
                             assert!(block.header().height() <= wait_till);
                             if block.header().height() == wait_till {
@@ -312,7 +318,9 @@ fn test_catchup_receipts_sync_common(wait_till: u64, send: u64, sync_hold: bool)
                     }
                     ReceiptsSyncPhases::WaitingForValidate => {
                         // This block now contains a chunk with the transaction sent above.
-                        if let NetworkRequests::Block { block } = msg {
+                        if let NetworkRequests::Block { block }
+                        | NetworkRequests::BlockHeaderFirst { block } = msg
+                        {
                             assert!(block.header().height() >= wait_till);
                             assert!(block.header().height() <= wait_till + 20);
                             if block.header().height() == wait_till + 20 {
@@ -457,13 +465,17 @@ fn test_catchup_random_single_part_sync_common(skip_15: bool, non_zero: bool, he
                 let mut phase = phase.write().unwrap();
                 match *phase {
                     RandomSinglePartPhases::WaitingForFirstBlock => {
-                        if let NetworkRequests::Block { block } = msg {
+                        if let NetworkRequests::Block { block }
+                        | NetworkRequests::BlockHeaderFirst { block } = msg
+                        {
                             assert_eq!(block.header().height(), 1);
                             *phase = RandomSinglePartPhases::WaitingForThirdEpoch;
                         }
                     }
                     RandomSinglePartPhases::WaitingForThirdEpoch => {
-                        if let NetworkRequests::Block { block } = msg {
+                        if let NetworkRequests::Block { block }
+                        | NetworkRequests::BlockHeaderFirst { block } = msg
+                        {
                             if block.header().height() == 1 {
                                 return (NetworkResponses::NoResponse.into(), false);
                             }
@@ -506,7 +518,9 @@ fn test_catchup_random_single_part_sync_common(skip_15: bool, non_zero: bool, he
                         }
                     }
                     RandomSinglePartPhases::WaitingForSixEpoch => {
-                        if let NetworkRequests::Block { block } = msg {
+                        if let NetworkRequests::Block { block }
+                        | NetworkRequests::BlockHeaderFirst { block } = msg
+                        {
                             assert!(block.header().height() >= height);
                             assert!(block.header().height() <= 32);
                             let check_height = if skip_15 { 28 } else { 26 };
@@ -641,7 +655,9 @@ fn test_catchup_sanity_blocks_produced() {
             false,
             Box::new(move |_, _account_id: _, msg: &PeerManagerMessageRequest| {
                 let msg = msg.as_network_requests_ref();
-                let propagate = if let NetworkRequests::Block { block } = msg {
+                let propagate = if let NetworkRequests::Block { block }
+                | NetworkRequests::BlockHeaderFirst { block } = msg
+                {
                     check_height(*block.hash(), block.header().height());
 
                     if block.header().height() % 10 == 5 {
@@ -740,7 +756,9 @@ fn test_chunk_grieving() {
                                 }
                             }
                         }
-                        if let NetworkRequests::Block { block } = msg {
+                        if let NetworkRequests::Block { block }
+                        | NetworkRequests::BlockHeaderFirst { block } = msg
+                        {
                             if block.header().height() == 12 {
                                 println!("BLOCK {:?}", block);
                                 *unaccepted_block_hash = *block.header().hash();
@@ -800,7 +818,9 @@ fn test_chunk_grieving() {
                                 );
                             }
                         }
-                        if let NetworkRequests::Block { block } = msg {
+                        if let NetworkRequests::Block { block }
+                        | NetworkRequests::BlockHeaderFirst { block } = msg
+                        {
                             if block.header().height() == 42 {
                                 println!("BLOCK {:?}", block,);
                                 // This is the main assert of the test
@@ -929,7 +949,9 @@ fn test_all_chunks_accepted_common(
                         ));
                     }
                 }
-                if let NetworkRequests::Block { block } = msg {
+                if let NetworkRequests::Block { block }
+                | NetworkRequests::BlockHeaderFirst { block } = msg
+                {
                     // There is no chunks at height 1
                     if block.header().height() > 1 {
                         if block.header().height() % epoch_length != 1 {
@@ -94,7 +94,8 @@ fn test_consensus_with_epoch_switches() {
                 let mut delayed_blocks = delayed_blocks.write().unwrap();
 
                 match msg.as_network_requests_ref() {
-                    NetworkRequests::Block { block } => {
+                    NetworkRequests::Block { block }
+                    | NetworkRequests::BlockHeaderFirst { block } => {
                         if !all_blocks.contains_key(&block.header().height()) {
                             println!(
                                 "BLOCK @{} EPOCH: {:?}, APPROVALS: {:?}",
@@ -127,7 +127,7 @@ fn send_tx(
             )
             .then(move |x| {
                 match x.unwrap() {
-                    ProcessTxResponse::NoResponse | ProcessTxResponse::RequestRouted => {
+                    ProcessTxResponse::Rejected(_) | ProcessTxResponse::RequestRouted => {
                         assert_eq!(num_validators, 24);
                         send_tx(
                             num_validators,
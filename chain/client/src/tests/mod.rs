@@ -3,5 +3,6 @@ mod catching_up;
 mod chunks_management;
 mod consensus;
 mod cross_shard_tx;
+mod multi_chain;
 mod process_blocks;
 mod query_client;
@@ -0,0 +1,45 @@
+use crate::test_utils::{MultiChainTestEnv, TestEnv};
+use near_chain::{ChainGenesis, Provenance};
+use near_primitives::utils::MaybeValidated;
+
+/// Runs two chains with different genesis side by side, producing blocks on
+/// each, and checks that neither sees the data of the other.
+#[test]
+fn test_two_chains_side_by_side() {
+    let builder = |chain_genesis: ChainGenesis| {
+        TestEnv::builder(chain_genesis).clients_count(2).validator_seats(1)
+    };
+    let mut other_genesis = ChainGenesis::test();
+    other_genesis.time = other_genesis.time + chrono::Duration::seconds(1);
+    let mut env =
+        MultiChainTestEnv::new(vec![builder(ChainGenesis::test()), builder(other_genesis)]);
+
+    let genesis_hashes: Vec<_> =
+        env.chains.iter().map(|chain| *chain.clients[0].chain.genesis().hash()).collect();
+    assert_ne!(genesis_hashes[0], genesis_hashes[1]);
+
+    // The chains advance at different paces.
+    let num_blocks = [5, 3];
+    for (chain, num_blocks) in env.chains.iter_mut().zip(num_blocks) {
+        for height in 1..=num_blocks {
+            let block = chain.clients[0].produce_block(height).unwrap().unwrap();
+            chain.process_block(0, block.clone(), Provenance::PRODUCED);
+            chain.process_block(1, block, Provenance::NONE);
+        }
+    }
+    for ((chain, num_blocks), genesis_hash) in
+        env.chains.iter().zip(num_blocks).zip(&genesis_hashes)
+    {
+        for client in &chain.clients {
+            assert_eq!(client.chain.genesis().hash(), genesis_hash);
+            assert_eq!(client.chain.head().unwrap().height, num_blocks);
+        }
+    }
+
+    // A block of one chain doesn't fit onto the other one.
+    let block = env.chains[0].clients[0].produce_block(6).unwrap().unwrap();
+    assert!(env.chains[1].clients[1]
+        .process_block_test(MaybeValidated::from(block), Provenance::NONE)
+        .is_err());
+    assert_eq!(env.chains[1].clients[1].chain.head().unwrap().height, 3);
+}
@@ -1,9 +1,27 @@
+use crate::adapter::{ProcessTxResponse, TxRejectionReason};
+use crate::block_skeleton::NextBlockSkeleton;
+use crate::debug::DebugApi;
 use crate::test_utils::TestEnv;
-use near_chain::{test_utils, ChainGenesis, Provenance};
-use near_crypto::{KeyType, PublicKey};
+use crate::Client;
+use near_chain::{test_utils, Chain, ChainGenesis, Provenance};
+use near_chain_configs::ClientConfig;
+use near_chunks::test_utils::MockClientAdapterForShardsManager;
+use near_crypto::{InMemorySigner, KeyType, PublicKey};
+use near_network::test_utils::MockPeerManagerAdapter;
+use near_network::types::{
+    BlockHeaderAnnouncement, ChunkInclusionFeedbackMsg, MaintenanceWindow, NetworkRequests,
+    PeerManagerMessageRequest,
+};
+use near_primitives::errors::{ActionsValidationError, InvalidTxError};
+use near_primitives::hash::{hash, CryptoHash};
 use near_primitives::network::PeerId;
+use near_primitives::sharding::ChunkHash;
+use near_primitives::transaction::{Action, FunctionCallAction, SignedTransaction};
 use near_primitives::types::validator_stake::ValidatorStake;
-use near_primitives::validator_signer::InMemoryValidatorSigner;
+use near_primitives::types::{AccountId, Finality};
+use near_primitives::validator_signer::{InMemoryValidatorSigner, ValidatorSigner};
+use near_primitives::views::{TxInclusionStatus, DEBUG_DATA_VERSION};
+use near_store::test_utils::create_test_store;
 use std::sync::Arc;
 
 /// Only process one block per height
@@ -37,3 +55,648 @@ fn test_not_process_height_twice() {
     // check that we didn't rebroadcast the second block
     assert!(env.network_adapters[0].pop().is_none());
 }
+
+/// Test that a produced block is validated the same way as a block received
+/// from the network before it's published.
+#[test]
+fn test_validate_produced_block() {
+    let mut env = TestEnv::builder(ChainGenesis::test()).build();
+    let block = env.clients[0].produce_block(1).unwrap().unwrap();
+    env.clients[0].chain.validate_produced_block(&block).unwrap();
+
+    // Proposals in the header which don't match the chunks.
+    let mut invalid_block = block.clone();
+    let validator_signer =
+        InMemoryValidatorSigner::from_seed("test0".parse().unwrap(), KeyType::ED25519, "test0");
+    let proposals =
+        vec![ValidatorStake::new("test1".parse().unwrap(), PublicKey::empty(KeyType::ED25519), 0)];
+    invalid_block.mut_header().get_mut().inner_rest.validator_proposals = proposals;
+    invalid_block.mut_header().resign(&validator_signer);
+    assert!(matches!(
+        env.clients[0].chain.validate_produced_block(&invalid_block),
+        Err(near_chain::Error::InvalidValidatorProposals)
+    ));
+
+    // The block is still processed normally.
+    env.process_block(0, block, Provenance::PRODUCED);
+}
+
+/// Test that chunk inclusion is reported for recent blocks, the latest first,
+/// and stops at genesis.
+#[test]
+fn test_chunk_endorsement_status() {
+    let mut env = TestEnv::builder(ChainGenesis::test()).build();
+    for height in 1..=5 {
+        env.produce_block(0, height);
+    }
+    let status = env.clients[0].get_chunk_endorsement_status(3).unwrap();
+    let heights: Vec<_> = status.chunks.iter().map(|chunk| chunk.height).collect();
+    assert_eq!(heights, vec![5, 4, 3]);
+    let test0: AccountId = "test0".parse().unwrap();
+    assert!(status.chunks.iter().all(|chunk| chunk.chunk_producer == test0));
+    let num_included = status.chunks.iter().filter(|chunk| chunk.included).count() as u64;
+    assert_eq!(status.chunk_producers.len(), 1);
+    assert_eq!(status.chunk_producers[0].num_expected, 3);
+    assert_eq!(status.chunk_producers[0].num_included, num_included);
+
+    let status = env.clients[0].get_chunk_endorsement_status(100).unwrap();
+    assert_eq!(status.chunks.len(), 5);
+}
+
+/// Test that the fork-aware transaction status follows a transaction from the
+/// pool onto the canonical chain.
+#[test]
+fn test_tx_fork_status() {
+    let mut env = TestEnv::builder(ChainGenesis::test()).build();
+    let signer_id: AccountId = "test0".parse().unwrap();
+    let signer = InMemorySigner::from_seed(signer_id.clone(), KeyType::ED25519, "test0");
+
+    let status = env.clients[0].tx_fork_status(&CryptoHash::default(), &signer_id).unwrap();
+    assert_eq!(status.status, TxInclusionStatus::Unknown);
+    assert!(!status.in_pool);
+    assert!(status.inclusions.is_empty());
+
+    let genesis_hash = *env.clients[0].chain.genesis().hash();
+    let tx = SignedTransaction::send_money(
+        1,
+        signer_id.clone(),
+        signer_id.clone(),
+        &signer,
+        100,
+        genesis_hash,
+    );
+    let tx_hash = tx.get_hash();
+    assert_eq!(env.clients[0].process_tx(tx, false, false), ProcessTxResponse::ValidTx);
+    let status = env.clients[0].tx_fork_status(&tx_hash, &signer_id).unwrap();
+    assert_eq!(status.status, TxInclusionStatus::Pending);
+    assert!(status.in_pool);
+    assert!(status.inclusions.is_empty());
+
+    for height in 1..5 {
+        env.produce_block(0, height);
+    }
+    let status = env.clients[0].tx_fork_status(&tx_hash, &signer_id).unwrap();
+    assert_eq!(status.status, TxInclusionStatus::Included);
+    assert!(!status.in_pool);
+    assert_eq!(status.inclusions.len(), 1);
+    assert!(status.inclusions[0].canonical);
+}
+
+/// Test that transactions exceeding the admission limits are rejected before
+/// any other validation.
+#[test]
+fn test_tx_admission_limits() {
+    let mut env = TestEnv::builder(ChainGenesis::test()).build();
+    env.clients[0].config.tx_admission.max_actions = 2;
+    env.clients[0].config.tx_admission.max_args_size = 10;
+    let signer_id: AccountId = "test0".parse().unwrap();
+    let signer = InMemorySigner::from_seed(signer_id.clone(), KeyType::ED25519, "test0");
+    let genesis_hash = *env.clients[0].chain.genesis().hash();
+    let function_call = |args_length: usize| {
+        Action::FunctionCall(FunctionCallAction {
+            method_name: "main".to_string(),
+            args: vec![0; args_length],
+            gas: 1_000_000,
+            deposit: 0,
+        })
+    };
+    let tx = |nonce, actions| {
+        SignedTransaction::from_actions(
+            nonce,
+            signer_id.clone(),
+            signer_id.clone(),
+            &signer,
+            actions,
+            genesis_hash,
+        )
+    };
+
+    assert_eq!(
+        env.clients[0].process_tx(tx(1, vec![function_call(1); 3]), false, false),
+        ProcessTxResponse::InvalidTx(InvalidTxError::ActionsValidation(
+            ActionsValidationError::TotalNumberOfActionsExceeded {
+                total_number_of_actions: 3,
+                limit: 2
+            }
+        ))
+    );
+    assert_eq!(
+        env.clients[0].process_tx(tx(2, vec![function_call(1), function_call(11)]), false, false),
+        ProcessTxResponse::InvalidTx(InvalidTxError::ActionsValidation(
+            ActionsValidationError::FunctionCallArgumentsLengthExceeded { length: 11, limit: 10 }
+        ))
+    );
+    let big_tx = tx(3, vec![function_call(10)]);
+    env.clients[0].config.tx_admission.max_tx_size = big_tx.get_size() - 1;
+    assert_eq!(
+        env.clients[0].process_tx(big_tx.clone(), false, false),
+        ProcessTxResponse::InvalidTx(InvalidTxError::TransactionSizeExceeded {
+            size: big_tx.get_size(),
+            limit: big_tx.get_size() - 1
+        })
+    );
+    env.clients[0].config.tx_admission.max_tx_size = big_tx.get_size();
+    assert_eq!(env.clients[0].process_tx(big_tx, false, false), ProcessTxResponse::ValidTx);
+}
+
+/// Test that block finality used to annotate execution outcomes follows the
+/// final and doomslug final heads.
+#[test]
+fn test_block_finality() {
+    let mut env = TestEnv::builder(ChainGenesis::test()).build();
+    for height in 1..10 {
+        env.produce_block(0, height);
+    }
+    let chain = &env.clients[0].chain;
+    let head_header = chain.head_header().unwrap();
+    let final_head = chain.final_head().unwrap();
+    assert!(final_head.height > 0);
+
+    assert_eq!(chain.get_block_finality(head_header.hash()).unwrap(), Finality::None);
+    assert_eq!(chain.get_block_finality(&final_head.last_block_hash).unwrap(), Finality::Final);
+    let genesis_hash = *chain.genesis().hash();
+    assert_eq!(chain.get_block_finality(&genesis_hash).unwrap(), Finality::Final);
+    let ds_final_header = chain.get_block_header(head_header.last_ds_final_block()).unwrap();
+    // With a single validator producing every block, the previous block is
+    // doomslug final and the one before it is final.
+    assert_eq!(ds_final_header.height(), head_header.height() - 1);
+    assert_eq!(final_head.height, head_header.height() - 2);
+    assert_eq!(chain.get_block_finality(ds_final_header.hash()).unwrap(), Finality::DoomSlug);
+    assert_eq!(chain.get_block_finality(&CryptoHash::default()).unwrap(), Finality::None);
+}
+
+/// Test that epoch sync data built for the first block of an epoch matches
+/// `epoch_sync_data_hash` of its header and that modified data is rejected.
+#[test]
+fn test_epoch_sync_data_hash_validation() {
+    let mut env = TestEnv::builder(ChainGenesis::test()).build();
+    let epoch_length = env.clients[0].chain.epoch_length;
+    for height in 1..=epoch_length * 2 + 1 {
+        env.produce_block(0, height);
+    }
+    let chain = &env.clients[0].chain;
+    let header = (1..=epoch_length * 2 + 1)
+        .filter_map(|height| chain.get_block_header_by_height(height).ok())
+        .find(|header| header.epoch_sync_data_hash().is_some())
+        .unwrap();
+
+    let data = chain.get_epoch_sync_data(header.hash()).unwrap();
+    Chain::validate_epoch_sync_data(&header, &data).unwrap();
+
+    let mut modified_data = data;
+    *modified_data.cur_epoch_info.epoch_height_mut() += 1;
+    assert!(matches!(
+        Chain::validate_epoch_sync_data(&header, &modified_data),
+        Err(near_chain::Error::InvalidEpochSyncData)
+    ));
+
+    let genesis_hash = *chain.genesis().hash();
+    assert!(chain.get_epoch_sync_data(&genesis_hash).is_err());
+}
+
+/// Test that the parts of the next block which only depend on the head are
+/// precomputed once the head is accepted, and that they are only used for
+/// blocks built on that head.
+#[test]
+fn test_next_block_skeleton() {
+    let mut env = TestEnv::builder(ChainGenesis::test()).build();
+    env.produce_block(0, 1);
+    let head = env.clients[0].chain.head().unwrap();
+    let skeleton = env.clients[0].next_block_skeleton.as_ref().unwrap();
+    assert_eq!(skeleton.prev_hash, head.last_block_hash);
+    let (block_merkle_root, block_ordinal) = (skeleton.block_merkle_root, skeleton.block_ordinal);
+
+    let block = env.clients[0].produce_block(2).unwrap().unwrap();
+    assert!(env.clients[0].next_block_skeleton.is_none());
+    assert_eq!(block.header().block_merkle_root(), &block_merkle_root);
+    assert_eq!(block.header().block_ordinal(), block_ordinal);
+    env.process_block(0, block, Provenance::PRODUCED);
+
+    // A skeleton precomputed for another block is ignored.
+    let client = &mut env.clients[0];
+    let genesis_hash = *client.chain.genesis().hash();
+    let stale =
+        NextBlockSkeleton::compute(&client.chain, &*client.runtime_adapter, &genesis_hash).unwrap();
+    let block_merkle_root = client.next_block_skeleton.as_ref().unwrap().block_merkle_root;
+    client.next_block_skeleton = Some(stale);
+    let block = client.produce_block(3).unwrap().unwrap();
+    assert_eq!(block.header().block_merkle_root(), &block_merkle_root);
+    assert_eq!(block.header().block_ordinal(), 3);
+    client.chain.validate_produced_block(&block).unwrap();
+}
+
+/// Test that a draining node rejects new transactions but keeps producing
+/// blocks, and is safe to stop once its chunks were distributed.
+#[test]
+fn test_drain() {
+    let mut env =
+        TestEnv::builder(ChainGenesis::test()).clients_count(2).validator_seats(1).build();
+    for height in 1..=2 {
+        let block = env.clients[0].produce_block(height).unwrap().unwrap();
+        env.process_block(0, block.clone(), Provenance::PRODUCED);
+        env.process_block(1, block, Provenance::NONE);
+    }
+    for client in &mut env.clients {
+        assert!(client.drain_status().is_none());
+        client.begin_drain().unwrap();
+    }
+
+    // The validator has the chunk of the next height in flight.
+    let status = env.clients[0].drain_status().unwrap();
+    assert_eq!(status.started_at_height, 2);
+    assert_eq!(status.last_produced_chunk_height, Some(3));
+    assert!(!status.safe_to_stop);
+    let status = env.clients[1].drain_status().unwrap();
+    assert_eq!(status.last_produced_chunk_height, None);
+    assert!(status.safe_to_stop);
+
+    // Transactions are rejected, but can still be checked.
+    let signer = InMemorySigner::from_seed("test0".parse().unwrap(), KeyType::ED25519, "test0");
+    let genesis_hash = *env.clients[0].chain.genesis().hash();
+    let tx = SignedTransaction::send_money(
+        1,
+        "test0".parse().unwrap(),
+        "test1".parse().unwrap(),
+        &signer,
+        100,
+        genesis_hash,
+    );
+    let rejected = ProcessTxResponse::Rejected(TxRejectionReason::Draining);
+    assert_eq!(env.clients[0].process_tx(tx.clone(), false, false), rejected);
+    assert_ne!(env.clients[0].process_tx(tx, false, true), rejected);
+
+    // The validator keeps producing its blocks, but no more chunks, so it's
+    // safe to stop once the block including its last chunk is produced.
+    env.produce_block(0, 3);
+    env.clients[0].check_drain().unwrap();
+    assert_eq!(env.clients[0].chain.head().unwrap().height, 3);
+    let status = env.clients[0].drain_status().unwrap();
+    assert_eq!(status.last_produced_chunk_height, Some(3));
+    assert!(status.safe_to_stop);
+    let block = env.clients[0].chain.get_block_by_height(3).unwrap();
+    assert_eq!(block.chunks()[0].height_included(), 3);
+}
+
+/// Test that a node in its maintenance window forwards transactions and
+/// doesn't produce blocks or chunks, and that its shutdown waits for the
+/// blocks being applied.
+#[test]
+fn test_maintenance_window() {
+    let mut env =
+        TestEnv::builder(ChainGenesis::test()).clients_count(2).validator_seats(1).build();
+    let mut blocks = vec![];
+    for height in 1..=3 {
+        let block = env.clients[0].produce_block(height).unwrap().unwrap();
+        env.process_block(0, block.clone(), Provenance::PRODUCED);
+        env.process_block(1, block, Provenance::NONE);
+    }
+    let signer = InMemorySigner::from_seed("test0".parse().unwrap(), KeyType::ED25519, "test0");
+    let genesis_hash = *env.clients[0].chain.genesis().hash();
+    let make_tx = |nonce| {
+        SignedTransaction::send_money(
+            nonce,
+            "test0".parse().unwrap(),
+            "test1".parse().unwrap(),
+            &signer,
+            100,
+            genesis_hash,
+        )
+    };
+
+    // The window doesn't affect the node before it starts.
+    let window = MaintenanceWindow { start_height: 4, shutdown_height: 5 };
+    let client = &mut env.clients[0];
+    assert!(!client.check_maintenance_window(Some(window)).unwrap());
+    assert!(!client.in_maintenance);
+    assert_eq!(client.process_tx(make_tx(1), false, false), ProcessTxResponse::ValidTx);
+    let block = client.produce_block(4).unwrap().unwrap();
+    env.process_block(0, block.clone(), Provenance::PRODUCED);
+    blocks.push(block);
+
+    // In the window, the validator forwards transactions and doesn't produce.
+    let client = &mut env.clients[0];
+    assert!(!client.check_maintenance_window(Some(window)).unwrap());
+    assert!(client.in_maintenance);
+    assert_eq!(client.process_tx(make_tx(2), false, false), ProcessTxResponse::RequestRouted);
+    assert!(client.produce_block(5).unwrap().is_none());
+    let head = client.chain.head().unwrap();
+    let epoch_id =
+        client.runtime_adapter.get_epoch_id_from_prev_block(&head.last_block_hash).unwrap();
+    let last_header = client.chain.get_block(&head.last_block_hash).unwrap().chunks()[0].clone();
+    assert!(client
+        .produce_chunk(head.last_block_hash, &epoch_id, last_header, 5, 0, None)
+        .unwrap()
+        .is_none());
+
+    // Production resumes once the window is cancelled.
+    assert!(!client.check_maintenance_window(None).unwrap());
+    assert!(!client.in_maintenance);
+    for height in 5..=6 {
+        let block = env.clients[0].produce_block(height).unwrap().unwrap();
+        env.process_block(0, block.clone(), Provenance::PRODUCED);
+        blocks.push(block);
+    }
+
+    // The shutdown waits for the block being applied.
+    let client = &mut env.clients[1];
+    for block in &blocks[..2] {
+        client.process_block_test(block.clone().into(), Provenance::NONE).unwrap();
+    }
+    client
+        .start_process_block(blocks[2].clone().into(), Provenance::NONE, Arc::new(|_| {}))
+        .unwrap();
+    assert!(!client.check_maintenance_window(Some(window)).unwrap());
+    assert!(client.in_maintenance);
+    client.finish_blocks_in_processing();
+    assert!(client.check_maintenance_window(Some(window)).unwrap());
+}
+
+/// Test that a chunk producer learns that its chunk became ready for inclusion
+/// only after the block at its height was produced without it.
+#[test]
+fn test_chunk_inclusion_feedback() {
+    let mut env = TestEnv::builder(ChainGenesis::test()).build();
+    for height in 1..=2 {
+        env.produce_block(0, height);
+    }
+    let client = &mut env.clients[0];
+    let head = client.chain.head().unwrap();
+    let (_, (chunk_header, _)) = client
+        .prev_block_to_chunk_headers_ready_for_inclusion
+        .pop(&head.last_block_hash)
+        .unwrap()
+        .into_iter()
+        .next()
+        .unwrap();
+    assert_eq!(chunk_header.height_created(), 3);
+    assert!(client.chunk_production_info.peek(&(3, 0)).unwrap().missed_inclusions.is_empty());
+
+    // The block is produced before the chunk is ready.
+    let block = client.produce_block(3).unwrap().unwrap();
+    assert_eq!(block.header().chunk_mask(), &[false]);
+    client.on_chunk_header_ready_for_inclusion(chunk_header);
+    let missed = &client.chunk_production_info.peek(&(3, 0)).unwrap().missed_inclusions;
+    assert_eq!(missed.len(), 1);
+    assert_eq!(missed[0].block_height, 3);
+    assert!(missed[0].arrival_millis >= missed[0].deadline_millis);
+
+    // Feedback from an account which didn't produce the block is ignored.
+    client.on_chunk_inclusion_feedback(ChunkInclusionFeedbackMsg {
+        chunk_hash: block.chunks()[0].chunk_hash(),
+        shard_id: 0,
+        height_created: 3,
+        prev_block_hash: head.last_block_hash,
+        block_height: 4,
+        block_producer: "other".parse().unwrap(),
+        deadline_millis: 0,
+        arrival_millis: 1,
+    });
+    assert_eq!(client.chunk_production_info.peek(&(3, 0)).unwrap().missed_inclusions.len(), 1);
+
+    // Chunks included in time don't trigger any feedback.
+    env.process_block(0, block, Provenance::PRODUCED);
+    env.produce_block(0, 4);
+    assert!(env.clients[0]
+        .chunk_production_info
+        .peek(&(4, 0))
+        .unwrap()
+        .missed_inclusions
+        .is_empty());
+}
+
+/// Test that a validator holding several keys produces blocks and chunks with
+/// the key staked in the epoch, which need not be its main key.
+#[test]
+fn test_other_validator_signers() {
+    let mut env = TestEnv::builder(ChainGenesis::test()).build();
+    let client = &mut env.clients[0];
+    let staked = client.validator_signer.clone().unwrap();
+    let unstaked: Arc<dyn ValidatorSigner> = Arc::new(InMemoryValidatorSigner::from_seed(
+        "test0".parse().unwrap(),
+        KeyType::ED25519,
+        "unstaked",
+    ));
+    client.validator_signer = Some(unstaked);
+    let head = client.chain.head().unwrap();
+    assert!(!client.is_validator(&head.epoch_id, &head.last_block_hash));
+    assert!(client.produce_block(1).unwrap().is_none());
+
+    // Keys of other accounts are refused.
+    let other_account: Arc<dyn ValidatorSigner> = Arc::new(InMemoryValidatorSigner::from_seed(
+        "test1".parse().unwrap(),
+        KeyType::ED25519,
+        "test1",
+    ));
+    assert!(client.set_other_validator_signers(vec![other_account]).is_err());
+
+    client.set_other_validator_signers(vec![staked.clone()]).unwrap();
+    assert!(client.is_validator(&head.epoch_id, &head.last_block_hash));
+    assert_eq!(
+        client
+            .validator_signer_for_epoch(&head.epoch_id, &head.last_block_hash)
+            .unwrap()
+            .public_key(),
+        staked.public_key()
+    );
+    for height in 1..=3 {
+        env.produce_block(0, height);
+        let block = env.clients[0].chain.get_block_by_height(height).unwrap();
+        assert!(block.header().verify_block_producer(&staked.public_key()));
+    }
+    // Chunks were produced and included as well.
+    let block = env.clients[0].chain.get_block_by_height(3).unwrap();
+    assert_eq!(block.header().chunk_mask(), &[true]);
+}
+
+/// Test that the key a validator rotates to is loaded from
+/// `next_validator_key_file` when the client starts.
+#[test]
+fn test_next_validator_key_file() {
+    let dir = tempfile::tempdir().unwrap();
+    let new_client = |next: &dyn ValidatorSigner| {
+        let path = dir.path().join("next_validator_key.json");
+        next.write_to_file(&path).unwrap();
+        let chain_genesis = ChainGenesis::test();
+        let vs = test_utils::ValidatorSchedule::new()
+            .block_producers_per_epoch(vec![vec!["test0".parse().unwrap()]]);
+        let runtime_adapter = Arc::new(test_utils::KeyValueRuntime::new_with_validators(
+            create_test_store(),
+            vs,
+            chain_genesis.epoch_length,
+        ));
+        let mut config = ClientConfig::test(true, 10, 20, 1, false, true);
+        config.next_validator_key_file = Some(path);
+        let unstaked = InMemoryValidatorSigner::from_seed(
+            "test0".parse().unwrap(),
+            KeyType::ED25519,
+            "unstaked",
+        );
+        Client::new(
+            config,
+            chain_genesis,
+            runtime_adapter,
+            Arc::new(MockPeerManagerAdapter::default()),
+            Arc::new(MockClientAdapterForShardsManager::default()),
+            Some(Arc::new(unstaked) as Arc<dyn ValidatorSigner>),
+            true,
+            [3; 32],
+        )
+    };
+
+    // Keys of other accounts are refused.
+    let other_account =
+        InMemoryValidatorSigner::from_seed("test1".parse().unwrap(), KeyType::ED25519, "test1");
+    assert!(new_client(&other_account).is_err());
+
+    let staked =
+        InMemoryValidatorSigner::from_seed("test0".parse().unwrap(), KeyType::ED25519, "test0");
+    let client = new_client(&staked).unwrap();
+    let head = client.chain.head().unwrap();
+    assert!(client.is_validator(&head.epoch_id, &head.last_block_hash));
+    assert_eq!(
+        client
+            .validator_signer_for_epoch(&head.epoch_id, &head.last_block_hash)
+            .unwrap()
+            .public_key(),
+        staked.public_key()
+    );
+}
+
+/// Pinned accounts are added to the TIER1 accounts of both epochs, without
+/// replacing the keys of staked accounts.
+#[test]
+fn test_tier1_pinned_accounts() {
+    let mut env = TestEnv::builder(ChainGenesis::test()).build();
+    let client = &mut env.clients[0];
+    let staked_key = client.validator_signer.as_ref().unwrap().public_key();
+    let relayer: AccountId = "relayer".parse().unwrap();
+    let relayer_key = PublicKey::from_seed(KeyType::ED25519, "relayer");
+    client.config.tier1_pinned_accounts = [
+        (relayer.clone(), relayer_key.clone()),
+        ("test0".parse().unwrap(), PublicKey::from_seed(KeyType::ED25519, "pinned")),
+    ]
+    .into_iter()
+    .collect();
+    let tip = client.chain.head().unwrap();
+    let accounts = client.get_tier1_accounts(&tip).unwrap();
+    for epoch_id in [&tip.epoch_id, &tip.next_epoch_id] {
+        assert_eq!(accounts.get(&(epoch_id.clone(), relayer.clone())), Some(&relayer_key));
+        assert_eq!(accounts.get(&(epoch_id.clone(), "test0".parse().unwrap())), Some(&staked_key));
+    }
+}
+
+/// Test that a block announced by its header is reconstructed from the chunks
+/// the node has, and requested otherwise, and that it's rebroadcast header-first
+/// in both cases.
+#[test]
+fn test_block_header_announcement() {
+    let mut env = TestEnv::builder(ChainGenesis::test()).build();
+    let peer_id = PeerId::new(PublicKey::empty(KeyType::ED25519));
+    let sent_requests = |env: &TestEnv| -> Vec<NetworkRequests> {
+        std::iter::from_fn(|| env.network_adapters[0].pop())
+            .filter_map(|request| match request {
+                PeerManagerMessageRequest::NetworkRequests(request) => Some(request),
+                _ => None,
+            })
+            .collect()
+    };
+
+    let block = env.clients[0].produce_block(1).unwrap().unwrap();
+    let announcement = BlockHeaderAnnouncement::from_block(&block).unwrap();
+    env.clients[0].receive_block_header_announcement(
+        announcement,
+        peer_id.clone(),
+        Arc::new(|_| {}),
+    );
+    env.clients[0].finish_blocks_in_processing();
+    assert_eq!(env.clients[0].chain.head().unwrap().last_block_hash, *block.hash());
+    assert!(sent_requests(&env).iter().any(|request| matches!(
+        request,
+        NetworkRequests::BlockHeaderFirst { block: sent } if sent.hash() == block.hash()
+    )));
+
+    // The announced chunk isn't known, so the block is requested.
+    let block = env.clients[0].produce_block(2).unwrap().unwrap();
+    let mut announcement = BlockHeaderAnnouncement::from_block(&block).unwrap();
+    announcement.chunk_hashes[0] = ChunkHash(hash(b"unknown"));
+    env.clients[0].receive_block_header_announcement(
+        announcement,
+        peer_id.clone(),
+        Arc::new(|_| {}),
+    );
+    assert!(sent_requests(&env).iter().any(|request| matches!(
+        request,
+        NetworkRequests::BlockRequest { hash, .. } if hash == block.hash()
+    )));
+    env.clients[0].receive_block_impl(block.clone(), peer_id, true, Arc::new(|_| {})).unwrap();
+    assert!(sent_requests(&env).iter().any(|request| matches!(
+        request,
+        NetworkRequests::BlockHeaderFirst { block: sent } if sent.hash() == block.hash()
+    )));
+}
+
+/// Test that the debug data reports the chunks produced by the node, the
+/// latest first, tagged with the version of the views.
+#[test]
+fn test_debug_data() {
+    let mut env = TestEnv::builder(ChainGenesis::test()).build();
+    for height in 1..=3 {
+        env.produce_block(0, height);
+    }
+    let data = env.clients[0].debug_data().unwrap();
+    assert_eq!(data.version, DEBUG_DATA_VERSION);
+    let heights: Vec<_> = data.chunk_production.iter().map(|chunk| chunk.height_created).collect();
+    assert!(!heights.is_empty());
+    assert!(heights.windows(2).all(|pair| pair[0] > pair[1]));
+    assert!(data.chunk_production.iter().all(|chunk| chunk.produced_at.is_some()));
+    assert!(data.catchup.is_empty());
+}
+
+/// Test that the validator and block status pages are served by the debug API.
+#[test]
+fn test_debug_api_status_views() {
+    let mut env = TestEnv::builder(ChainGenesis::test()).build();
+    for height in 1..=3 {
+        env.produce_block(0, height);
+    }
+    let client = &env.clients[0];
+    let head = client.chain.head().unwrap();
+
+    let status = client.validator_status_view().unwrap();
+    assert_eq!(status.head_height, head.height);
+    assert!(status.production.iter().any(|(height, production)| *height == head.height
+        && production.block_production.as_ref().map_or(false, |p| p.block_included)));
+
+    let blocks = client.block_status_view(None).unwrap();
+    assert_eq!(blocks.head, head.last_block_hash);
+    assert!(blocks
+        .blocks
+        .iter()
+        .any(|block| block.block_hash == head.last_block_hash && block.is_on_canonical_chain));
+}
+
+/// The priority validators are the producers of the next blocks, the next one
+/// first, followed by the producer of the next chunk of the tracked shard, each
+/// of them listed once.
+#[test]
+fn test_priority_validators() {
+    let env = TestEnv::builder(ChainGenesis::test()).clients_count(3).validator_seats(3).build();
+    let client = &env.clients[0];
+    let tip = client.chain.head().unwrap();
+    let epoch_id =
+        client.runtime_adapter.get_epoch_id_from_prev_block(&tip.last_block_hash).unwrap();
+    let validators = client.get_priority_validators(&tip).unwrap();
+    assert_eq!(
+        validators[0],
+        client.runtime_adapter.get_block_producer(&epoch_id, tip.height + 1).unwrap()
+    );
+    let chunk_producer =
+        client.runtime_adapter.get_chunk_producer(&epoch_id, tip.height + 1, 0).unwrap();
+    assert!(validators.contains(&chunk_producer));
+    // All validators produce some of the next blocks.
+    let mut validators = validators.to_vec();
+    validators.sort();
+    let mut want = env.validators.clone();
+    want.sort();
+    assert_eq!(validators, want);
+}
@@ -174,6 +174,7 @@ fn test_execution_outcome_for_chunk() {
                         tx_hash,
                         signer_account_id: "test".parse().unwrap(),
                         fetch_receipt: false,
+                        fetch_outcome_proofs: false,
                     }
                     .with_span_context(),
                 )
@@ -287,7 +288,9 @@ fn test_garbage_collection() {
                       _,
                       msg: &PeerManagerMessageRequest|
                       -> (PeerManagerMessageResponse, bool) {
-                    if let NetworkRequests::Block { block } = msg.as_network_requests_ref() {
+                    if let NetworkRequests::Block { block }
+                    | NetworkRequests::BlockHeaderFirst { block } = msg.as_network_requests_ref()
+                    {
                         if block.header().height() > target_height {
                             let view_client_non_archival = &conns[0].1;
                             let view_client_archival = &conns[1].1;
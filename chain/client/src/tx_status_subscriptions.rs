@@ -0,0 +1,242 @@
+//! Subscriptions of peers to the final outcomes of transactions.
+//!
+//! Nodes which don't track the shard of a transaction used to poll validators
+//! for its status.  Instead, they can subscribe to it: the subscription is
+//! answered right away if the outcome is already known and is kept otherwise,
+//! until the outcome becomes known after a block is applied and is pushed to
+//! the subscriber through the route back of its request.  Subscriptions which
+//! aren't fulfilled within `SUBSCRIPTION_TIMEOUT` are dropped; by then the
+//! route back has likely expired and the subscriber subscribes again.
+//!
+//! The subscriptions are controlled by peers, so a single peer may hold at
+//! most `MAX_SUBSCRIPTIONS_PER_PEER` of them, and at most
+//! `MAX_TXS_CHECKED_PER_BLOCK` transactions are looked up after each block,
+//! the ones checked least recently first.
+use std::collections::HashMap;
+use std::time::{Duration, Instant};
+
+use lru::LruCache;
+use near_primitives::hash::CryptoHash;
+use near_primitives::network::PeerId;
+
+/// Number of transactions subscribed to at most.
+const MAX_SUBSCRIBED_TXS: usize = 10_000;
+/// Number of subscriptions to a single transaction at most.
+const MAX_SUBSCRIPTIONS_PER_TX: usize = 16;
+/// Number of subscriptions of a single peer at most.
+const MAX_SUBSCRIPTIONS_PER_PEER: usize = 100;
+/// Number of transactions whose outcome is looked up after a block at most.
+const MAX_TXS_CHECKED_PER_BLOCK: usize = 500;
+/// How long a subscription is kept.
+const SUBSCRIPTION_TIMEOUT: Duration = Duration::from_secs(120);
+
+struct Subscription {
+    route_back: CryptoHash,
+    subscriber: PeerId,
+    time: Instant,
+}
+
+pub(crate) struct TxStatusSubscriptions {
+    /// Subscriptions to each transaction, the least recently checked
+    /// transactions first.
+    subscriptions: LruCache<CryptoHash, Vec<Subscription>>,
+    /// Number of subscriptions of each peer.
+    num_subscriptions: HashMap<PeerId, usize>,
+}
+
+impl TxStatusSubscriptions {
+    pub fn new() -> Self {
+        Self { subscriptions: LruCache::new(MAX_SUBSCRIBED_TXS), num_subscriptions: HashMap::new() }
+    }
+
+    /// Adds a subscription of `subscriber` to the transaction.  Once there are
+    /// too many subscriptions to it, the oldest one is dropped, and once there
+    /// are too many transactions subscribed to, the least recently checked one
+    /// is.  Returns false if the subscriber already has too many
+    /// subscriptions, in which case it isn't subscribed.
+    pub fn subscribe(
+        &mut self,
+        tx_hash: CryptoHash,
+        route_back: CryptoHash,
+        subscriber: PeerId,
+        now: Instant,
+    ) -> bool {
+        let count = self.num_subscriptions.get(&subscriber).copied().unwrap_or(0);
+        if count >= MAX_SUBSCRIPTIONS_PER_PEER {
+            return false;
+        }
+        if self.subscriptions.get(&tx_hash).is_none() {
+            if self.subscriptions.len() == self.subscriptions.cap() {
+                if let Some((_, evicted)) = self.subscriptions.pop_lru() {
+                    self.release(&evicted);
+                }
+            }
+            self.subscriptions.put(tx_hash, vec![]);
+        }
+        let subscriptions = self.subscriptions.get_mut(&tx_hash).unwrap();
+        let dropped = if subscriptions.len() == MAX_SUBSCRIPTIONS_PER_TX {
+            Some(subscriptions.remove(0))
+        } else {
+            None
+        };
+        subscriptions.push(Subscription { route_back, subscriber: subscriber.clone(), time: now });
+        *self.num_subscriptions.entry(subscriber).or_default() += 1;
+        if let Some(dropped) = dropped {
+            self.release(std::slice::from_ref(&dropped));
+        }
+        true
+    }
+
+    /// Drops the subscriptions older than `SUBSCRIPTION_TIMEOUT` and returns
+    /// at most `MAX_TXS_CHECKED_PER_BLOCK` of the transactions still
+    /// subscribed to, which are then considered checked.
+    pub fn pending(&mut self, now: Instant) -> Vec<CryptoHash> {
+        let mut expired_txs = vec![];
+        let mut expired = vec![];
+        for (tx_hash, subscriptions) in self.subscriptions.iter_mut() {
+            let (kept, dropped): (Vec<_>, Vec<_>) = std::mem::take(subscriptions)
+                .into_iter()
+                .partition(|s| now.saturating_duration_since(s.time) < SUBSCRIPTION_TIMEOUT);
+            *subscriptions = kept;
+            expired.extend(dropped);
+            if subscriptions.is_empty() {
+                expired_txs.push(*tx_hash);
+            }
+        }
+        for tx_hash in expired_txs {
+            self.subscriptions.pop(&tx_hash);
+        }
+        self.release(&expired);
+
+        let pending: Vec<CryptoHash> = self
+            .subscriptions
+            .iter()
+            .rev()
+            .take(MAX_TXS_CHECKED_PER_BLOCK)
+            .map(|(tx_hash, _)| *tx_hash)
+            .collect();
+        for tx_hash in &pending {
+            self.subscriptions.get(tx_hash);
+        }
+        pending
+    }
+
+    /// Removes the subscriptions to the transaction, whose outcome is known,
+    /// and returns their route backs.
+    pub fn fulfil(&mut self, tx_hash: &CryptoHash) -> Vec<CryptoHash> {
+        let subscriptions = self.subscriptions.pop(tx_hash).unwrap_or_default();
+        self.release(&subscriptions);
+        subscriptions.into_iter().map(|s| s.route_back).collect()
+    }
+
+    pub fn len(&self) -> usize {
+        self.subscriptions.len()
+    }
+
+    fn release(&mut self, subscriptions: &[Subscription]) {
+        for subscription in subscriptions {
+            if let Some(count) = self.num_subscriptions.get_mut(&subscription.subscriber) {
+                *count -= 1;
+                if *count == 0 {
+                    self.num_subscriptions.remove(&subscription.subscriber);
+                }
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{
+        TxStatusSubscriptions, MAX_SUBSCRIPTIONS_PER_PEER, MAX_SUBSCRIPTIONS_PER_TX,
+        MAX_TXS_CHECKED_PER_BLOCK, SUBSCRIPTION_TIMEOUT,
+    };
+    use near_network::test_utils::peer_id_from_seed;
+    use near_primitives::hash::hash;
+    use std::time::{Duration, Instant};
+
+    #[test]
+    fn test_subscriptions() {
+        let mut subscriptions = TxStatusSubscriptions::new();
+        let peer = peer_id_from_seed("peer");
+        let start = Instant::now();
+        let (tx1, tx2) = (hash(b"tx1"), hash(b"tx2"));
+        assert!(subscriptions.subscribe(tx1, hash(b"a"), peer.clone(), start));
+        assert!(subscriptions.subscribe(
+            tx1,
+            hash(b"b"),
+            peer.clone(),
+            start + Duration::from_secs(60)
+        ));
+        assert!(subscriptions.subscribe(tx2, hash(b"c"), peer.clone(), start));
+        let mut pending = subscriptions.pending(start + Duration::from_secs(1));
+        pending.sort();
+        let mut expected = vec![tx1, tx2];
+        expected.sort();
+        assert_eq!(pending, expected);
+
+        // The first subscriptions to both transactions expire.
+        let later = start + SUBSCRIPTION_TIMEOUT;
+        assert_eq!(subscriptions.pending(later), vec![tx1]);
+        assert_eq!(subscriptions.len(), 1);
+        assert_eq!(subscriptions.num_subscriptions[&peer], 1);
+        assert_eq!(subscriptions.fulfil(&tx1), vec![hash(b"b")]);
+        assert!(subscriptions.fulfil(&tx1).is_empty());
+        assert!(subscriptions.pending(later).is_empty());
+        assert!(subscriptions.num_subscriptions.is_empty());
+    }
+
+    #[test]
+    fn test_subscriptions_per_tx_limit() {
+        let mut subscriptions = TxStatusSubscriptions::new();
+        let now = Instant::now();
+        let tx = hash(b"tx");
+        for i in 0..=MAX_SUBSCRIPTIONS_PER_TX as u64 {
+            let peer = peer_id_from_seed(&i.to_string());
+            assert!(subscriptions.subscribe(tx, hash(&i.to_le_bytes()), peer, now));
+        }
+        // The subscription of the first peer was dropped.
+        assert!(!subscriptions.num_subscriptions.contains_key(&peer_id_from_seed("0")));
+        let route_backs = subscriptions.fulfil(&tx);
+        assert_eq!(route_backs.len(), MAX_SUBSCRIPTIONS_PER_TX);
+        assert_eq!(route_backs[0], hash(&1u64.to_le_bytes()));
+    }
+
+    #[test]
+    fn test_subscriptions_per_peer_limit() {
+        let mut subscriptions = TxStatusSubscriptions::new();
+        let now = Instant::now();
+        let (peer, other) = (peer_id_from_seed("peer"), peer_id_from_seed("other"));
+        for i in 0..MAX_SUBSCRIPTIONS_PER_PEER as u64 {
+            let tx = hash(&i.to_le_bytes());
+            assert!(subscriptions.subscribe(tx, tx, peer.clone(), now));
+        }
+        let tx = hash(b"tx");
+        assert!(!subscriptions.subscribe(tx, tx, peer.clone(), now));
+        assert!(subscriptions.subscribe(tx, tx, other, now));
+
+        // Once one of its subscriptions is fulfilled, the peer can subscribe again.
+        subscriptions.fulfil(&hash(&0u64.to_le_bytes()));
+        assert!(subscriptions.subscribe(tx, tx, peer, now));
+    }
+
+    #[test]
+    fn test_txs_checked_per_block_limit() {
+        let mut subscriptions = TxStatusSubscriptions::new();
+        let now = Instant::now();
+        let num_txs = MAX_TXS_CHECKED_PER_BLOCK + MAX_TXS_CHECKED_PER_BLOCK / 2;
+        for i in 0..num_txs as u64 {
+            let tx = hash(&i.to_le_bytes());
+            assert!(subscriptions.subscribe(tx, tx, peer_id_from_seed(&i.to_string()), now));
+        }
+        // The transactions are checked in the order of subscription and then
+        // round robin.
+        let first = subscriptions.pending(now);
+        assert_eq!(first.len(), MAX_TXS_CHECKED_PER_BLOCK);
+        assert_eq!(first[0], hash(&0u64.to_le_bytes()));
+        let second = subscriptions.pending(now);
+        assert_eq!(second.len(), MAX_TXS_CHECKED_PER_BLOCK);
+        assert_eq!(second[0], hash(&(MAX_TXS_CHECKED_PER_BLOCK as u64).to_le_bytes()));
+        assert_eq!(second[num_txs - MAX_TXS_CHECKED_PER_BLOCK], first[0]);
+    }
+}
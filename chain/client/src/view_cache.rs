@@ -0,0 +1,119 @@
+//! Caches for responses of frequently repeated view client requests.
+//!
+//! Responses are keyed by the hash of the block they were computed at, so a
+//! cached entry never becomes incorrect.  Entries are still dropped as soon as
+//! the chain head moves, since requests by finality then resolve to a new
+//! block and older entries are unlikely to be hit again, and after a TTL in
+//! case the head gets stuck.
+use std::hash::Hash;
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+
+use near_primitives::hash::CryptoHash;
+use near_primitives::time::Clock;
+
+use crate::metrics::ClientMetrics;
+
+pub(crate) struct ViewCache<K: Hash + Eq, V: Clone> {
+    /// Label used for metrics.
+    name: &'static str,
+    entries: Option<lru::LruCache<K, (Instant, V)>>,
+    ttl: Duration,
+    /// Head of the chain at the time the current entries were inserted.
+    head: CryptoHash,
+    metrics: Arc<ClientMetrics>,
+}
+
+impl<K: Hash + Eq, V: Clone> ViewCache<K, V> {
+    /// Creates a cache holding up to `capacity` entries.  Zero capacity
+    /// disables caching.
+    pub fn new(
+        name: &'static str,
+        capacity: usize,
+        ttl: Duration,
+        metrics: Arc<ClientMetrics>,
+    ) -> Self {
+        let entries = if capacity == 0 { None } else { Some(lru::LruCache::new(capacity)) };
+        Self { name, entries, ttl, head: CryptoHash::default(), metrics }
+    }
+
+    /// Drops all entries if the chain head is different from the one they
+    /// were inserted at.
+    pub fn on_head(&mut self, head: &CryptoHash) {
+        if self.head == *head {
+            return;
+        }
+        self.head = *head;
+        if let Some(entries) = &mut self.entries {
+            entries.clear();
+        }
+    }
+
+    pub fn get(&mut self, key: &K) -> Option<V> {
+        let entries = self.entries.as_mut()?;
+        let value = match entries.get(key) {
+            Some((inserted, value)) if Clock::instant() - *inserted <= self.ttl => {
+                Some(value.clone())
+            }
+            Some(_) => {
+                entries.pop(key);
+                None
+            }
+            None => None,
+        };
+        if value.is_some() {
+            self.metrics.view_client_cache_hits.with_label_values(&[self.name]).inc();
+        } else {
+            self.metrics.view_client_cache_misses.with_label_values(&[self.name]).inc();
+        }
+        value
+    }
+
+    pub fn put(&mut self, key: K, value: V) {
+        if let Some(entries) = &mut self.entries {
+            entries.put(key, (Clock::instant(), value));
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::ViewCache;
+    use crate::metrics::ClientMetrics;
+    use near_o11y::metrics::MetricsRegistry;
+    use near_primitives::hash::CryptoHash;
+    use std::sync::Arc;
+    use std::time::Duration;
+
+    fn metrics() -> Arc<ClientMetrics> {
+        MetricsRegistry::global().get()
+    }
+
+    #[test]
+    fn test_view_cache_invalidated_on_head_change() {
+        let mut cache = ViewCache::new("test", 10, Duration::from_secs(60), metrics());
+        let head = CryptoHash::hash_bytes(b"head");
+        cache.on_head(&head);
+        cache.put(1, "one");
+        assert_eq!(cache.get(&1), Some("one"));
+        cache.on_head(&head);
+        assert_eq!(cache.get(&1), Some("one"));
+        cache.on_head(&CryptoHash::hash_bytes(b"new head"));
+        assert_eq!(cache.get(&1), None);
+    }
+
+    #[test]
+    fn test_view_cache_ttl() {
+        let mut cache = ViewCache::new("test", 10, Duration::ZERO, metrics());
+        cache.put(1, "one");
+        std::thread::sleep(Duration::from_millis(1));
+        assert_eq!(cache.get(&1), None);
+    }
+
+    #[test]
+    fn test_view_cache_disabled() {
+        let mut cache = ViewCache::new("test", 0, Duration::from_secs(60), metrics());
+        cache.put(1, "one");
+        assert_eq!(cache.get(&1), None);
+    }
+}
@@ -12,6 +12,9 @@ use std::time::{Duration, Instant};
 
 use tracing::{debug, error, info, trace, warn};
 
+use near_chain::contract_code_index::query_contract_accounts;
+use near_chain::contract_events::{self, query_contract_events};
+use near_chain::trie_diff::{self, query_trie_diff};
 use near_chain::{
     get_epoch_block_producers_view, Chain, ChainGenesis, ChainStoreAccess, DoomslugThresholdMode,
     RuntimeAdapter,
@@ -19,10 +22,13 @@ use near_chain::{
 use near_chain_configs::{ClientConfig, ProtocolConfigView};
 use near_client_primitives::types::{
     Error, GetBlock, GetBlockError, GetBlockProof, GetBlockProofError, GetBlockProofResponse,
-    GetBlockWithMerkleTree, GetChunkError, GetExecutionOutcome, GetExecutionOutcomeError,
-    GetExecutionOutcomesForBlock, GetGasPrice, GetGasPriceError, GetNextLightClientBlockError,
-    GetProtocolConfig, GetProtocolConfigError, GetReceipt, GetReceiptError, GetStateChangesError,
-    GetStateChangesWithCauseInBlock, GetStateChangesWithCauseInBlockForTrackedShards,
+    GetBlockWithMerkleTree, GetChunkError, GetContractAccounts, GetContractEvents,
+    GetContractIndexError, GetEconomicsSeries, GetEconomicsSeriesError, GetExecutionOutcome,
+    GetExecutionOutcomeError, GetExecutionOutcomesForBlock, GetGasPrice, GetGasPriceError,
+    GetNextLightClientBlockError, GetProtocolConfig, GetProtocolConfigError, GetReceipt,
+    GetReceiptError, GetReceiptExecutionProof, GetRuntimeParametersDiff,
+    GetRuntimeParametersDiffError, GetStateChangesError, GetStateChangesWithCauseInBlock,
+    GetStateChangesWithCauseInBlockForTrackedShards, GetTrieDiff, GetTrieDiffError,
     GetValidatorInfoError, Query, QueryError, TxStatus, TxStatusError,
 };
 #[cfg(feature = "test_features")]
@@ -31,41 +37,61 @@ use near_network::types::{
     NetworkRequests, PeerManagerAdapter, PeerManagerMessageRequest, ReasonForBan,
     StateResponseInfo, StateResponseInfoV1, StateResponseInfoV2,
 };
+use near_o11y::metrics::MetricsRegistry;
 use near_o11y::{handler_debug_span, OpenTelemetrySpanExt, WithSpanContext, WithSpanContextExt};
 use near_performance_metrics_macros::perf;
 use near_primitives::block::{Block, BlockHeader};
 use near_primitives::hash::CryptoHash;
 use near_primitives::merkle::{merklize, PartialMerkleTree};
 use near_primitives::network::AnnounceAccount;
+use near_primitives::runtime::config_store::runtime_parameters_diff;
 use near_primitives::sharding::ShardChunk;
 use near_primitives::syncing::{
+    is_valid_state_sub_part_size, EpochSyncDataResponse, EpochSyncProofsResponse,
     ShardStateSyncResponse, ShardStateSyncResponseHeader, ShardStateSyncResponseV1,
-    ShardStateSyncResponseV2,
+    ShardStateSyncResponseV2, ShardStateSyncResponseV3, StateSubPart,
 };
 use near_primitives::types::{
     AccountId, BlockId, BlockReference, EpochReference, Finality, MaybeBlockId, ShardId,
     SyncCheckpoint, TransactionOrReceiptId, ValidatorInfoIdentifier,
 };
+use near_primitives::version::PROTOCOL_VERSION;
 use near_primitives::views::validator_stake_view::ValidatorStakeView;
 use near_primitives::views::{
-    BlockView, ChunkView, EpochValidatorInfo, ExecutionOutcomeWithIdView,
-    FinalExecutionOutcomeView, FinalExecutionOutcomeViewEnum, GasPriceView, LightClientBlockView,
-    QueryRequest, QueryResponse, ReceiptView, StateChangesKindsView, StateChangesView,
+    BlockProducerProofView, BlockRandomnessView, BlockView, ChunkView, ContractAccountsView,
+    ContractEventsView, EconomicsPointView, EconomicsSeriesView, EpochValidatorInfo,
+    ExecutionOutcomeWithIdView, FinalExecutionOutcomeView, FinalExecutionOutcomeViewEnum,
+    GasPriceView, LightClientBlockView, OutgoingReceiptProofsView, QueryRequest, QueryResponse,
+    QueryResponseKind, RandomnessView, ReceiptExecutionProofView, ReceiptProofView, ReceiptView,
+    RuntimeParametersDiffView, StateChangesKindsView, StateChangesView, TrieDiffView,
 };
+use near_store::Store;
 
 use crate::adapter::{
-    AnnounceAccountRequest, BlockHeadersRequest, BlockRequest, StateRequestHeader,
-    StateRequestPart, StateResponse, TxStatusRequest, TxStatusResponse,
+    AnnounceAccountRequest, BlockHeadersRequest, BlockRequest, EpochSyncDataRequest,
+    EpochSyncProofsRequest, StateRequestHeader, StateRequestPart, StateRequestSubPart,
+    StateResponse, TxStatusRequest, TxStatusResponse,
 };
+use crate::metrics::ClientMetrics;
+use crate::state_part_cache::StatePartCache;
+use crate::view_cache::ViewCache;
 use crate::{
-    metrics, sync, GetChunk, GetExecutionOutcomeResponse, GetNextLightClientBlock, GetStateChanges,
-    GetStateChangesInBlock, GetValidatorInfo, GetValidatorOrdered,
+    sync, GetBlockProducerProof, GetChunk, GetExecutionOutcomeResponse, GetNextLightClientBlock,
+    GetOutgoingReceiptProofs, GetRandomness, GetStateChanges, GetStateChangesInBlock,
+    GetValidatorInfo, GetValidatorOrdered,
 };
 
 /// Max number of queries that we keep.
 const QUERY_REQUEST_LIMIT: usize = 500;
 /// Waiting time between requests, in ms
 const REQUEST_WAIT_TIME: u64 = 1000;
+/// Time after which a subscription to the status of a transaction is renewed
+/// in case the push of its outcome got lost.
+const TX_STATUS_SUBSCRIPTION_RENEWAL_TIME: u64 = 10_000;
+/// Max number of points returned in a single economics series response.
+const MAX_ECONOMICS_SERIES_POINTS: usize = 1000;
+/// Max number of ancestors returned with the randomness of a block.
+const MAX_RANDOMNESS_LINEAGE_LENGTH: u64 = 100;
 
 const POISONED_LOCK_ERR: &str = "The lock was poisoned.";
 
@@ -95,6 +121,13 @@ pub struct ViewClientActor {
     pub config: ClientConfig,
     request_manager: Arc<RwLock<ViewClientRequestManager>>,
     state_request_cache: Arc<Mutex<VecDeque<Instant>>>,
+    /// State parts served to peers, shared by all view client threads.
+    state_part_cache: Arc<Mutex<StatePartCache>>,
+    /// Responses to queries, keyed by the hash of the block and the request.
+    query_cache: ViewCache<CryptoHash, QueryResponse>,
+    gas_price_cache: ViewCache<MaybeBlockId, GasPriceView>,
+    protocol_config_cache: ViewCache<BlockReference, ProtocolConfigView>,
+    metrics: Arc<ClientMetrics>,
 }
 
 impl ViewClientRequestManager {
@@ -117,17 +150,23 @@ impl ViewClientActor {
         validator_account_id: Option<AccountId>,
         chain_genesis: &ChainGenesis,
         runtime_adapter: Arc<dyn RuntimeAdapter>,
+        store: Store,
         network_adapter: Arc<dyn PeerManagerAdapter>,
         config: ClientConfig,
         request_manager: Arc<RwLock<ViewClientRequestManager>>,
+        state_part_cache: Arc<Mutex<StatePartCache>>,
         adv: crate::adversarial::Controls,
     ) -> Result<Self, Error> {
+        let metrics_registry = MetricsRegistry::for_instance(config.metrics_instance.as_deref());
+        let metrics = metrics_registry.get::<ClientMetrics>();
         // TODO: should we create shared ChainStore that is passed to both Client and ViewClient?
         let chain = Chain::new_for_view_client(
             runtime_adapter.clone(),
+            store,
             chain_genesis,
             DoomslugThresholdMode::TwoThirds,
             !config.archive,
+            &metrics_registry,
         )?;
         Ok(ViewClientActor {
             adv,
@@ -138,9 +177,38 @@ impl ViewClientActor {
             config,
             request_manager,
             state_request_cache: Arc::new(Mutex::new(VecDeque::default())),
+            state_part_cache,
+            query_cache: ViewCache::new(
+                "query",
+                config.view_client_cache_size,
+                config.view_client_cache_ttl,
+                metrics.clone(),
+            ),
+            gas_price_cache: ViewCache::new(
+                "gas_price",
+                config.view_client_cache_size,
+                config.view_client_cache_ttl,
+                metrics.clone(),
+            ),
+            protocol_config_cache: ViewCache::new(
+                "protocol_config",
+                config.view_client_cache_size,
+                config.view_client_cache_ttl,
+                metrics.clone(),
+            ),
+            metrics,
         })
     }
 
+    /// Drops cached responses if the head has moved since they were computed.
+    fn update_view_caches(&mut self) {
+        if let Ok(head) = self.chain.head() {
+            self.query_cache.on_head(&head.last_block_hash);
+            self.gas_price_cache.on_head(&head.last_block_hash);
+            self.protocol_config_cache.on_head(&head.last_block_hash);
+        }
+    }
+
     fn maybe_block_id_to_block_header(
         &self,
         block_id: MaybeBlockId,
@@ -155,10 +223,55 @@ impl ViewClientActor {
         }
     }
 
+    fn get_chunk(&self, chunk: GetChunk) -> Result<ShardChunk, near_chain::Error> {
+        let get_chunk_from_block = |block: Block,
+                                    shard_id: ShardId,
+                                    chain: &Chain|
+         -> Result<ShardChunk, near_chain::Error> {
+            let chunk_header = block
+                .chunks()
+                .get(shard_id as usize)
+                .ok_or_else(|| near_chain::Error::InvalidShardId(shard_id))?
+                .clone();
+            let chunk_hash = chunk_header.chunk_hash();
+            let chunk = chain.get_chunk(&chunk_hash)?;
+            let res = ShardChunk::with_header(ShardChunk::clone(&chunk), chunk_header).ok_or(
+                near_chain::Error::Other(format!(
+                    "Mismatched versions for chunk with hash {}",
+                    chunk_hash.0
+                )),
+            )?;
+            Ok(res)
+        };
+
+        match chunk {
+            GetChunk::ChunkHash(chunk_hash) => {
+                let chunk = self.chain.get_chunk(&chunk_hash)?;
+                Ok(ShardChunk::clone(&chunk))
+            }
+            GetChunk::BlockHash(block_hash, shard_id) => {
+                let block = self.chain.get_block(&block_hash)?;
+                get_chunk_from_block(block, shard_id, &self.chain)
+            }
+            GetChunk::Height(height, shard_id) => {
+                let block = self.chain.get_block_by_height(height)?;
+                get_chunk_from_block(block, shard_id, &self.chain)
+            }
+        }
+    }
+
     fn need_request<K: Hash + Eq + Clone>(key: K, cache: &mut lru::LruCache<K, Instant>) -> bool {
+        Self::need_request_after(key, cache, Duration::from_millis(REQUEST_WAIT_TIME))
+    }
+
+    fn need_request_after<K: Hash + Eq + Clone>(
+        key: K,
+        cache: &mut lru::LruCache<K, Instant>,
+        wait_time: Duration,
+    ) -> bool {
         let now = Clock::instant();
         let need_request = match cache.get(&key) {
-            Some(time) => now - *time > Duration::from_millis(REQUEST_WAIT_TIME),
+            Some(time) => now - *time > wait_time,
             None => true,
         };
         if need_request {
@@ -260,7 +373,18 @@ impl ViewClientActor {
             Err(err) => Err(QueryError::Unreachable { error_message: err.to_string() }),
         }?;
 
+        self.update_view_caches();
+        let cache_key = CryptoHash::hash_borsh((header.hash(), &msg.request));
+        if let Some(query_response) = self.query_cache.get(&cache_key) {
+            return Ok(query_response);
+        }
+
         let account_id = match &msg.request {
+            QueryRequest::ViewReceiptProof { receipt_id } => {
+                let query_response = self.query_receipt_proof(receipt_id, &header)?;
+                self.query_cache.put(cache_key, query_response.clone());
+                return Ok(query_response);
+            }
             QueryRequest::ViewAccount { account_id, .. } => account_id,
             QueryRequest::ViewState { account_id, .. } => account_id,
             QueryRequest::ViewAccessKey { account_id, .. } => account_id,
@@ -312,7 +436,10 @@ impl ViewClientActor {
             header.epoch_id(),
             &msg.request,
         ) {
-            Ok(query_response) => Ok(query_response),
+            Ok(query_response) => {
+                self.query_cache.put(cache_key, query_response.clone());
+                Ok(query_response)
+            }
             Err(query_error) => Err(match query_error {
                 near_chain::near_chain_primitives::error::QueryError::InternalError {
                     error_message,
@@ -360,16 +487,43 @@ impl ViewClientActor {
         }
     }
 
+    /// Proves that the receipt was sent by a chunk included in the block or
+    /// in one of its ancestors.
+    fn query_receipt_proof(
+        &self,
+        receipt_id: &CryptoHash,
+        header: &BlockHeader,
+    ) -> Result<QueryResponse, QueryError> {
+        let proof = self
+            .chain
+            .get_receipt_inclusion_proof(receipt_id)
+            .map_err(|err| QueryError::InternalError { error_message: err.to_string() })?;
+        match proof {
+            Some(proof) if proof.block_height <= header.height() => Ok(QueryResponse {
+                kind: QueryResponseKind::ReceiptProof(proof),
+                block_height: header.height(),
+                block_hash: *header.hash(),
+            }),
+            _ => Err(QueryError::UnknownReceipt {
+                receipt_id: *receipt_id,
+                block_height: header.height(),
+                block_hash: *header.hash(),
+            }),
+        }
+    }
+
     fn get_tx_status(
         &mut self,
         tx_hash: CryptoHash,
         signer_account_id: AccountId,
         fetch_receipt: bool,
+        fetch_outcome_proofs: bool,
     ) -> Result<Option<FinalExecutionOutcomeViewEnum>, TxStatusError> {
         {
             let mut request_manager = self.request_manager.write().expect(POISONED_LOCK_ERR);
-            if let Some(res) = request_manager.tx_status_response.pop(&tx_hash) {
+            if let Some(mut res) = request_manager.tx_status_response.pop(&tx_hash) {
                 request_manager.tx_status_requests.pop(&tx_hash);
+                res.annotate_finality(|block_hash| self.chain.get_block_finality(block_hash))?;
                 return Ok(Some(FinalExecutionOutcomeViewEnum::FinalExecutionOutcome(res)));
             }
         }
@@ -387,10 +541,19 @@ impl ViewClientActor {
             true,
         ) {
             match self.chain.get_final_transaction_result(&tx_hash) {
-                Ok(tx_result) => {
+                Ok(mut tx_result) => {
+                    tx_result.annotate_finality(|block_hash| {
+                        self.chain.get_block_finality(block_hash)
+                    })?;
                     let res = if fetch_receipt {
-                        let final_result =
+                        let mut final_result =
                             self.chain.get_final_transaction_result_with_receipt(tx_result)?;
+                        if fetch_outcome_proofs {
+                            final_result.outcome_proofs = Some(
+                                self.chain
+                                    .get_execution_outcome_proofs(&final_result.final_outcome)?,
+                            );
+                        }
                         FinalExecutionOutcomeViewEnum::FinalExecutionOutcomeWithReceipt(
                             final_result,
                         )
@@ -412,8 +575,16 @@ impl ViewClientActor {
                 }
             }
         } else {
+            // Rather than polling the validator, subscribe to the outcome.  It
+            // is pushed once known and put in the cache by TxStatusResponse.
+            // Validators on older protocol versions are polled instead, see
+            // `NetworkState::send_tx_status_subscribe`.
             let mut request_manager = self.request_manager.write().expect(POISONED_LOCK_ERR);
-            if Self::need_request(tx_hash, &mut request_manager.tx_status_requests) {
+            if Self::need_request_after(
+                tx_hash,
+                &mut request_manager.tx_status_requests,
+                Duration::from_millis(TX_STATUS_SUBSCRIPTION_RENEWAL_TIME),
+            ) {
                 let target_shard_id = self
                     .runtime_adapter
                     .account_id_to_shard_id(&signer_account_id, &head.epoch_id)
@@ -421,7 +592,7 @@ impl ViewClientActor {
                 let validator = self.chain.find_validator_for_forwarding(target_shard_id)?;
 
                 self.network_adapter.do_send(
-                    PeerManagerMessageRequest::NetworkRequests(NetworkRequests::TxStatus(
+                    PeerManagerMessageRequest::NetworkRequests(NetworkRequests::TxStatusSubscribe(
                         validator,
                         signer_account_id,
                         tx_hash,
@@ -473,6 +644,44 @@ impl ViewClientActor {
         cache.push_back(now);
         true
     }
+
+    /// Returns the state part from the in-memory cache, or gets it from the
+    /// chain, which computes it if it's not stored yet, and caches it.
+    fn get_state_part(
+        &self,
+        shard_id: ShardId,
+        part_id: u64,
+        sync_hash: CryptoHash,
+    ) -> Result<Arc<[u8]>, near_chain::Error> {
+        let key = (sync_hash, shard_id, part_id);
+        if let Some(part) = self.state_part_cache.lock().expect(POISONED_LOCK_ERR).get(&key) {
+            return Ok(part);
+        }
+        let part: Arc<[u8]> =
+            self.chain.get_state_response_part(shard_id, part_id, sync_hash)?.into();
+        self.state_part_cache.lock().expect(POISONED_LOCK_ERR).put(key, part.clone());
+        Ok(part)
+    }
+
+    /// Returns the state part if it's cached in memory or stored, without
+    /// computing it.
+    fn get_cached_state_part(
+        &self,
+        shard_id: ShardId,
+        part_id: u64,
+        sync_hash: CryptoHash,
+    ) -> Result<Option<Arc<[u8]>>, near_chain::Error> {
+        let key = (sync_hash, shard_id, part_id);
+        if let Some(part) = self.state_part_cache.lock().expect(POISONED_LOCK_ERR).get(&key) {
+            return Ok(Some(part));
+        }
+        let part = self.chain.get_cached_state_response_part(shard_id, part_id, sync_hash)?;
+        Ok(part.map(|part| {
+            let part: Arc<[u8]> = part.into();
+            self.state_part_cache.lock().expect(POISONED_LOCK_ERR).put(key, part.clone());
+            part
+        }))
+    }
 }
 
 impl Actor for ViewClientActor {
@@ -485,7 +694,16 @@ impl Handler<WithSpanContext<Query>> for ViewClientActor {
     #[perf]
     fn handle(&mut self, msg: WithSpanContext<Query>, _: &mut Self::Context) -> Self::Result {
         let (_span, msg) = handler_debug_span!(target: "client", msg);
-        let _timer = metrics::VIEW_CLIENT_MESSAGE_TIME.with_label_values(&["Query"]).start_timer();
+        let _timer =
+            self.metrics.view_client_message_time.with_label_values(&["Query"]).start_timer();
+        let _permit = match near_store::storage_health().try_start_view_query() {
+            Some(permit) => permit,
+            None => {
+                return Err(QueryError::InternalError {
+                    error_message: "storage latency is degraded".to_string(),
+                })
+            }
+        };
         self.handle_query(msg)
     }
 }
@@ -498,7 +716,7 @@ impl Handler<WithSpanContext<GetBlock>> for ViewClientActor {
     fn handle(&mut self, msg: WithSpanContext<GetBlock>, _: &mut Self::Context) -> Self::Result {
         let (_span, msg) = handler_debug_span!(target: "client", msg);
         let _timer =
-            metrics::VIEW_CLIENT_MESSAGE_TIME.with_label_values(&["GetBlock"]).start_timer();
+            self.metrics.view_client_message_time.with_label_values(&["GetBlock"]).start_timer();
         let block = match self.get_block_by_reference(&msg.0)? {
             None => return Err(GetBlockError::NotSyncedYet),
             Some(block) => block,
@@ -520,7 +738,9 @@ impl Handler<WithSpanContext<GetBlockWithMerkleTree>> for ViewClientActor {
         ctx: &mut Self::Context,
     ) -> Self::Result {
         let (_span, msg) = handler_debug_span!(target: "client", msg);
-        let _timer = metrics::VIEW_CLIENT_MESSAGE_TIME
+        let _timer = self
+            .metrics
+            .view_client_message_time
             .with_label_values(&["GetBlockWithMerkleTree"])
             .start_timer();
         let block_view = self.handle(GetBlock(msg.0).with_span_context(), ctx)?;
@@ -539,41 +759,8 @@ impl Handler<WithSpanContext<GetChunk>> for ViewClientActor {
     fn handle(&mut self, msg: WithSpanContext<GetChunk>, _: &mut Self::Context) -> Self::Result {
         let (_span, msg) = handler_debug_span!(target: "client", msg);
         let _timer =
-            metrics::VIEW_CLIENT_MESSAGE_TIME.with_label_values(&["GetChunk"]).start_timer();
-        let get_chunk_from_block = |block: Block,
-                                    shard_id: ShardId,
-                                    chain: &Chain|
-         -> Result<ShardChunk, near_chain::Error> {
-            let chunk_header = block
-                .chunks()
-                .get(shard_id as usize)
-                .ok_or_else(|| near_chain::Error::InvalidShardId(shard_id))?
-                .clone();
-            let chunk_hash = chunk_header.chunk_hash();
-            let chunk = chain.get_chunk(&chunk_hash)?;
-            let res = ShardChunk::with_header(ShardChunk::clone(&chunk), chunk_header).ok_or(
-                near_chain::Error::Other(format!(
-                    "Mismatched versions for chunk with hash {}",
-                    chunk_hash.0
-                )),
-            )?;
-            Ok(res)
-        };
-
-        let chunk = match msg {
-            GetChunk::ChunkHash(chunk_hash) => {
-                let chunk = self.chain.get_chunk(&chunk_hash)?;
-                ShardChunk::clone(&chunk)
-            }
-            GetChunk::BlockHash(block_hash, shard_id) => {
-                let block = self.chain.get_block(&block_hash)?;
-                get_chunk_from_block(block, shard_id, &self.chain)?
-            }
-            GetChunk::Height(height, shard_id) => {
-                let block = self.chain.get_block_by_height(height)?;
-                get_chunk_from_block(block, shard_id, &self.chain)?
-            }
-        };
+            self.metrics.view_client_message_time.with_label_values(&["GetChunk"]).start_timer();
+        let chunk = self.get_chunk(msg)?;
 
         let chunk_inner = chunk.cloned_header().take_inner();
         let epoch_id =
@@ -588,6 +775,50 @@ impl Handler<WithSpanContext<GetChunk>> for ViewClientActor {
     }
 }
 
+impl Handler<WithSpanContext<GetOutgoingReceiptProofs>> for ViewClientActor {
+    type Result = Result<OutgoingReceiptProofsView, GetChunkError>;
+
+    #[perf]
+    fn handle(
+        &mut self,
+        msg: WithSpanContext<GetOutgoingReceiptProofs>,
+        _: &mut Self::Context,
+    ) -> Self::Result {
+        let (_span, msg) = handler_debug_span!(target: "client", msg);
+        let _timer = self
+            .metrics
+            .view_client_message_time
+            .with_label_values(&["GetOutgoingReceiptProofs"])
+            .start_timer();
+        let chunk = self.get_chunk(msg.chunk)?;
+        let header = chunk.cloned_header();
+        // Genesis chunks send no receipts and aren't committed to any.
+        let proofs = if header.height_created() == self.chain.genesis().height() {
+            vec![]
+        } else {
+            // Same proofs as the ones sent to the validators of the other
+            // shards together with the parts of the chunk.
+            near_chunks::logic::make_outgoing_receipts_proofs(
+                &header,
+                chunk.receipts(),
+                &*self.runtime_adapter,
+            )
+            .map_err(|err| match err {
+                near_chunks::Error::ChainError(err) => GetChunkError::from(err),
+                err => GetChunkError::Unreachable { error_message: err.to_string() },
+            })?
+            .map(ReceiptProofView::from)
+            .collect()
+        };
+        Ok(OutgoingReceiptProofsView {
+            chunk_hash: header.chunk_hash().0,
+            shard_id: header.shard_id(),
+            outgoing_receipts_root: header.outgoing_receipts_root(),
+            proofs,
+        })
+    }
+}
+
 impl Handler<WithSpanContext<TxStatus>> for ViewClientActor {
     type Result = Result<Option<FinalExecutionOutcomeViewEnum>, TxStatusError>;
 
@@ -595,8 +826,13 @@ impl Handler<WithSpanContext<TxStatus>> for ViewClientActor {
     fn handle(&mut self, msg: WithSpanContext<TxStatus>, _: &mut Self::Context) -> Self::Result {
         let (_span, msg) = handler_debug_span!(target: "client", msg);
         let _timer =
-            metrics::VIEW_CLIENT_MESSAGE_TIME.with_label_values(&["TxStatus"]).start_timer();
-        self.get_tx_status(msg.tx_hash, msg.signer_account_id, msg.fetch_receipt)
+            self.metrics.view_client_message_time.with_label_values(&["TxStatus"]).start_timer();
+        self.get_tx_status(
+            msg.tx_hash,
+            msg.signer_account_id,
+            msg.fetch_receipt,
+            msg.fetch_outcome_proofs,
+        )
     }
 }
 
@@ -610,7 +846,9 @@ impl Handler<WithSpanContext<GetValidatorInfo>> for ViewClientActor {
         _: &mut Self::Context,
     ) -> Self::Result {
         let (_span, msg) = handler_debug_span!(target: "client", msg);
-        let _timer = metrics::VIEW_CLIENT_MESSAGE_TIME
+        let _timer = self
+            .metrics
+            .view_client_message_time
             .with_label_values(&["GetValidatorInfo"])
             .start_timer();
         let epoch_identifier = match msg.epoch_reference {
@@ -662,7 +900,9 @@ impl Handler<WithSpanContext<GetValidatorOrdered>> for ViewClientActor {
         _: &mut Self::Context,
     ) -> Self::Result {
         let (_span, msg) = handler_debug_span!(target: "client", msg);
-        let _timer = metrics::VIEW_CLIENT_MESSAGE_TIME
+        let _timer = self
+            .metrics
+            .view_client_message_time
             .with_label_values(&["GetValidatorOrdered"])
             .start_timer();
         Ok(self.maybe_block_id_to_block_header(msg.block_id).and_then(|header| {
@@ -674,6 +914,106 @@ impl Handler<WithSpanContext<GetValidatorOrdered>> for ViewClientActor {
         })?)
     }
 }
+
+impl Handler<WithSpanContext<GetBlockProducerProof>> for ViewClientActor {
+    type Result = Result<BlockProducerProofView, GetValidatorInfoError>;
+
+    #[perf]
+    fn handle(
+        &mut self,
+        msg: WithSpanContext<GetBlockProducerProof>,
+        _: &mut Self::Context,
+    ) -> Self::Result {
+        let (_span, msg) = handler_debug_span!(target: "client", msg);
+        let _timer = self
+            .metrics
+            .view_client_message_time
+            .with_label_values(&["GetBlockProducerProof"])
+            .start_timer();
+        // The epoch of a height follows from the last block before it.  Heights
+        // without blocks still had block producers assigned.  Blocks below the
+        // tail have been garbage collected, so the search stops there.
+        let head = self.chain.head()?;
+        let tail = std::cmp::max(self.chain.genesis().height(), self.chain.tail()?);
+        let prev_hash = if msg.height > head.height {
+            head.last_block_hash
+        } else {
+            let mut prev_height = msg.height;
+            loop {
+                if prev_height <= tail {
+                    return Err(GetValidatorInfoError::UnknownEpoch);
+                }
+                prev_height -= 1;
+                match self.chain.get_block_header_by_height(prev_height) {
+                    Ok(header) => break *header.hash(),
+                    Err(near_chain::Error::DBNotFoundErr(_)) => continue,
+                    Err(err) => return Err(err.into()),
+                }
+            }
+        };
+        let epoch_id = self.runtime_adapter.get_epoch_id_from_prev_block(&prev_hash)?;
+        Ok(self.runtime_adapter.get_block_producer_proof(&epoch_id, msg.height)?)
+    }
+}
+
+impl Handler<WithSpanContext<GetRandomness>> for ViewClientActor {
+    type Result = Result<RandomnessView, GetBlockError>;
+
+    #[perf]
+    fn handle(
+        &mut self,
+        msg: WithSpanContext<GetRandomness>,
+        _: &mut Self::Context,
+    ) -> Self::Result {
+        let (_span, msg) = handler_debug_span!(target: "client", msg);
+        let _timer = self
+            .metrics
+            .view_client_message_time
+            .with_label_values(&["GetRandomness"])
+            .start_timer();
+        let header = match self.get_block_header_by_reference(&msg.block_reference)? {
+            None => return Err(GetBlockError::NotSyncedYet),
+            Some(header) => header,
+        };
+        let epoch = self.runtime_adapter.get_epoch_randomness(header.hash())?;
+        let mut blocks = vec![];
+        let mut block_hash = *header.hash();
+        for _ in 0..=msg.lineage_length.min(MAX_RANDOMNESS_LINEAGE_LENGTH) {
+            let block = match self.chain.get_block(&block_hash) {
+                Ok(block) => block,
+                // Older ancestors might have been garbage collected already.
+                Err(near_chain::Error::DBNotFoundErr(_)) if !blocks.is_empty() => break,
+                Err(err) => return Err(err.into()),
+            };
+            let header = block.header();
+            if header.height() == self.chain.genesis().height() {
+                break;
+            }
+            let prev_header = self.chain.get_block_header(header.prev_hash())?;
+            let block_producer =
+                self.runtime_adapter.get_block_producer(header.epoch_id(), header.height())?;
+            let (validator, _) = self.runtime_adapter.get_validator_by_account_id(
+                header.epoch_id(),
+                &block_hash,
+                &block_producer,
+            )?;
+            blocks.push(BlockRandomnessView {
+                block_hash,
+                height: header.height(),
+                prev_block_hash: *header.prev_hash(),
+                block_producer,
+                block_producer_public_key: validator.take_public_key(),
+                prev_random_value: *prev_header.random_value(),
+                vrf_value: *block.vrf_value(),
+                vrf_proof: *block.vrf_proof(),
+                random_value: *header.random_value(),
+            });
+            block_hash = *header.prev_hash();
+        }
+        Ok(RandomnessView { epoch, blocks })
+    }
+}
+
 /// Returns a list of change kinds per account in a store for a given block.
 impl Handler<WithSpanContext<GetStateChangesInBlock>> for ViewClientActor {
     type Result = Result<StateChangesKindsView, GetStateChangesError>;
@@ -685,7 +1025,9 @@ impl Handler<WithSpanContext<GetStateChangesInBlock>> for ViewClientActor {
         _: &mut Self::Context,
     ) -> Self::Result {
         let (_span, msg) = handler_debug_span!(target: "client", msg);
-        let _timer = metrics::VIEW_CLIENT_MESSAGE_TIME
+        let _timer = self
+            .metrics
+            .view_client_message_time
             .with_label_values(&["GetStateChangesInBlock"])
             .start_timer();
         Ok(self
@@ -709,8 +1051,11 @@ impl Handler<WithSpanContext<GetStateChanges>> for ViewClientActor {
         _: &mut Self::Context,
     ) -> Self::Result {
         let (_span, msg) = handler_debug_span!(target: "client", msg);
-        let _timer =
-            metrics::VIEW_CLIENT_MESSAGE_TIME.with_label_values(&["GetStateChanges"]).start_timer();
+        let _timer = self
+            .metrics
+            .view_client_message_time
+            .with_label_values(&["GetStateChanges"])
+            .start_timer();
         Ok(self
             .chain
             .store()
@@ -732,7 +1077,9 @@ impl Handler<WithSpanContext<GetStateChangesWithCauseInBlock>> for ViewClientAct
         _: &mut Self::Context,
     ) -> Self::Result {
         let (_span, msg) = handler_debug_span!(target: "client", msg);
-        let _timer = metrics::VIEW_CLIENT_MESSAGE_TIME
+        let _timer = self
+            .metrics
+            .view_client_message_time
             .with_label_values(&["GetStateChangesWithCauseInBlock"])
             .start_timer();
         Ok(self
@@ -757,7 +1104,9 @@ impl Handler<WithSpanContext<GetStateChangesWithCauseInBlockForTrackedShards>> f
         _: &mut Self::Context,
     ) -> Self::Result {
         let (_span, msg) = handler_debug_span!(target: "client", msg);
-        let _timer = metrics::VIEW_CLIENT_MESSAGE_TIME
+        let _timer = self
+            .metrics
+            .view_client_message_time
             .with_label_values(&["GetStateChangesWithCauseInBlockForTrackedShards"])
             .start_timer();
         let state_changes_with_cause_in_block =
@@ -786,6 +1135,40 @@ impl Handler<WithSpanContext<GetStateChangesWithCauseInBlockForTrackedShards>> f
     }
 }
 
+impl Handler<WithSpanContext<GetTrieDiff>> for ViewClientActor {
+    type Result = Result<TrieDiffView, GetTrieDiffError>;
+
+    #[perf]
+    fn handle(&mut self, msg: WithSpanContext<GetTrieDiff>, _: &mut Self::Context) -> Self::Result {
+        let (_span, msg) = handler_debug_span!(target: "client", msg);
+        let _timer =
+            self.metrics.view_client_message_time.with_label_values(&["GetTrieDiff"]).start_timer();
+        if msg.limit == 0 {
+            return Err(GetTrieDiffError::InvalidRequest {
+                error_message: "limit must be positive".to_string(),
+            });
+        }
+        query_trie_diff(
+            self.chain.store(),
+            &*self.runtime_adapter,
+            msg.shard_id,
+            &msg.from_block_hash,
+            &msg.to_block_hash,
+            msg.cursor.as_deref(),
+            msg.limit,
+        )
+        .map_err(|err| match err {
+            trie_diff::QueryError::InvalidQuery(error_message) => {
+                GetTrieDiffError::InvalidRequest { error_message }
+            }
+            trie_diff::QueryError::StateUnavailable(error_message) => {
+                GetTrieDiffError::StateUnavailable { error_message }
+            }
+            trie_diff::QueryError::Chain(err) => err.into(),
+        })
+    }
+}
+
 /// Returns the next light client block, given the hash of the last block known to the light client.
 /// There are three cases:
 ///  1. The last block known to the light client is in the same epoch as the tip:
@@ -804,7 +1187,9 @@ impl Handler<WithSpanContext<GetNextLightClientBlock>> for ViewClientActor {
         _: &mut Self::Context,
     ) -> Self::Result {
         let (_span, msg) = handler_debug_span!(target: "client", msg);
-        let _timer = metrics::VIEW_CLIENT_MESSAGE_TIME
+        let _timer = self
+            .metrics
+            .view_client_message_time
             .with_label_values(&["GetNextLightClientBlock"])
             .start_timer();
         let last_block_header = self.chain.get_block_header(&msg.last_block_hash)?;
@@ -851,7 +1236,9 @@ impl Handler<WithSpanContext<GetExecutionOutcome>> for ViewClientActor {
         _: &mut Self::Context,
     ) -> Self::Result {
         let (_span, msg) = handler_debug_span!(target: "client", msg);
-        let _timer = metrics::VIEW_CLIENT_MESSAGE_TIME
+        let _timer = self
+            .metrics
+            .view_client_message_time
             .with_label_values(&["GetExecutionOutcome"])
             .start_timer();
         let (id, account_id) = match msg.id {
@@ -936,7 +1323,9 @@ impl Handler<WithSpanContext<GetExecutionOutcomesForBlock>> for ViewClientActor
         _: &mut Self::Context,
     ) -> Self::Result {
         let (_span, msg) = handler_debug_span!(target: "client", msg);
-        let _timer = metrics::VIEW_CLIENT_MESSAGE_TIME
+        let _timer = self
+            .metrics
+            .view_client_message_time
             .with_label_values(&["GetExecutionOutcomesForBlock"])
             .start_timer();
         Ok(self
@@ -956,7 +1345,7 @@ impl Handler<WithSpanContext<GetReceipt>> for ViewClientActor {
     fn handle(&mut self, msg: WithSpanContext<GetReceipt>, _: &mut Self::Context) -> Self::Result {
         let (_span, msg) = handler_debug_span!(target: "client", msg);
         let _timer =
-            metrics::VIEW_CLIENT_MESSAGE_TIME.with_label_values(&["GetReceipt"]).start_timer();
+            self.metrics.view_client_message_time.with_label_values(&["GetReceipt"]).start_timer();
         Ok(self
             .chain
             .store()
@@ -975,8 +1364,11 @@ impl Handler<WithSpanContext<GetBlockProof>> for ViewClientActor {
         _: &mut Self::Context,
     ) -> Self::Result {
         let (_span, msg) = handler_debug_span!(target: "client", msg);
-        let _timer =
-            metrics::VIEW_CLIENT_MESSAGE_TIME.with_label_values(&["GetBlockProof"]).start_timer();
+        let _timer = self
+            .metrics
+            .view_client_message_time
+            .with_label_values(&["GetBlockProof"])
+            .start_timer();
         let block_header = self.chain.get_block_header(&msg.block_hash)?;
         let head_block_header = self.chain.get_block_header(&msg.head_block_hash)?;
         self.chain.check_blocks_final_and_canonical(&[&block_header, &head_block_header])?;
@@ -986,6 +1378,38 @@ impl Handler<WithSpanContext<GetBlockProof>> for ViewClientActor {
     }
 }
 
+impl Handler<WithSpanContext<GetReceiptExecutionProof>> for ViewClientActor {
+    type Result = Result<ReceiptExecutionProofView, GetExecutionOutcomeError>;
+
+    #[perf]
+    fn handle(
+        &mut self,
+        msg: WithSpanContext<GetReceiptExecutionProof>,
+        _: &mut Self::Context,
+    ) -> Self::Result {
+        let (_span, msg) = handler_debug_span!(target: "client", msg);
+        let _timer = self
+            .metrics
+            .view_client_message_time
+            .with_label_values(&["GetReceiptExecutionProof"])
+            .start_timer();
+        // Fails with `UnknownBlock` if the light client head is unknown.
+        self.chain.get_block_header(&msg.light_client_head)?;
+        match self.chain.get_receipt_execution_proof(&msg.receipt_id, &msg.light_client_head) {
+            Ok(Some(proof)) => Ok(proof),
+            Ok(None) => Err(GetExecutionOutcomeError::NotConfirmed {
+                transaction_or_receipt_id: msg.receipt_id,
+            }),
+            Err(near_chain::Error::DBNotFoundErr(_)) => {
+                Err(GetExecutionOutcomeError::UnknownTransactionOrReceipt {
+                    transaction_or_receipt_id: msg.receipt_id,
+                })
+            }
+            Err(err) => Err(err.into()),
+        }
+    }
+}
+
 impl Handler<WithSpanContext<GetProtocolConfig>> for ViewClientActor {
     type Result = Result<ProtocolConfigView, GetProtocolConfigError>;
 
@@ -996,17 +1420,52 @@ impl Handler<WithSpanContext<GetProtocolConfig>> for ViewClientActor {
         _: &mut Self::Context,
     ) -> Self::Result {
         let (_span, msg) = handler_debug_span!(target: "client", msg);
-        let _timer = metrics::VIEW_CLIENT_MESSAGE_TIME
+        let _timer = self
+            .metrics
+            .view_client_message_time
             .with_label_values(&["GetProtocolConfig"])
             .start_timer();
+        self.update_view_caches();
+        if let Some(config) = self.protocol_config_cache.get(&msg.0) {
+            return Ok(config);
+        }
         let header = match self.get_block_header_by_reference(&msg.0)? {
             None => {
                 return Err(GetProtocolConfigError::UnknownBlock("EarliestAvailable".to_string()))
             }
             Some(header) => header,
         };
-        let config = self.runtime_adapter.get_protocol_config(header.epoch_id())?;
-        Ok(config.into())
+        let config: ProtocolConfigView =
+            self.runtime_adapter.get_protocol_config(header.epoch_id())?.into();
+        self.protocol_config_cache.put(msg.0, config.clone());
+        Ok(config)
+    }
+}
+
+impl Handler<WithSpanContext<GetRuntimeParametersDiff>> for ViewClientActor {
+    type Result = Result<RuntimeParametersDiffView, GetRuntimeParametersDiffError>;
+
+    #[perf]
+    fn handle(
+        &mut self,
+        msg: WithSpanContext<GetRuntimeParametersDiff>,
+        _: &mut Self::Context,
+    ) -> Self::Result {
+        let (_span, msg) = handler_debug_span!(target: "client", msg);
+        let _timer = self
+            .metrics
+            .view_client_message_time
+            .with_label_values(&["GetRuntimeParametersDiff"])
+            .start_timer();
+        for requested in [msg.from_protocol_version, msg.to_protocol_version] {
+            if requested > PROTOCOL_VERSION {
+                return Err(GetRuntimeParametersDiffError::UnknownProtocolVersion {
+                    requested,
+                    latest: PROTOCOL_VERSION,
+                });
+            }
+        }
+        Ok(runtime_parameters_diff(msg.from_protocol_version, msg.to_protocol_version))
     }
 }
 
@@ -1021,7 +1480,9 @@ impl Handler<WithSpanContext<NetworkAdversarialMessage>> for ViewClientActor {
         _ctx: &mut Self::Context,
     ) -> Self::Result {
         let (_span, msg) = handler_debug_span!(target: "client", msg);
-        let _timer = metrics::VIEW_CLIENT_MESSAGE_TIME
+        let _timer = self
+            .metrics
+            .view_client_message_time
             .with_label_values(&["NetworkAdversarialMessage"])
             .start_timer();
         match msg {
@@ -1058,10 +1519,13 @@ impl Handler<WithSpanContext<TxStatusRequest>> for ViewClientActor {
         _ctx: &mut Self::Context,
     ) -> Self::Result {
         let (_span, msg) = handler_debug_span!(target: "client", msg);
-        let _timer =
-            metrics::VIEW_CLIENT_MESSAGE_TIME.with_label_values(&["TxStatusRequest"]).start_timer();
+        let _timer = self
+            .metrics
+            .view_client_message_time
+            .with_label_values(&["TxStatusRequest"])
+            .start_timer();
         let TxStatusRequest { tx_hash, signer_account_id } = msg;
-        if let Ok(Some(result)) = self.get_tx_status(tx_hash, signer_account_id, false) {
+        if let Ok(Some(result)) = self.get_tx_status(tx_hash, signer_account_id, false, false) {
             Some(Box::new(result.into_outcome()))
         } else {
             None
@@ -1079,7 +1543,9 @@ impl Handler<WithSpanContext<TxStatusResponse>> for ViewClientActor {
         _ctx: &mut Self::Context,
     ) -> Self::Result {
         let (_span, msg) = handler_debug_span!(target: "client", msg);
-        let _timer = metrics::VIEW_CLIENT_MESSAGE_TIME
+        let _timer = self
+            .metrics
+            .view_client_message_time
             .with_label_values(&["TxStatusResponse"])
             .start_timer();
         let TxStatusResponse(tx_result) = msg;
@@ -1101,8 +1567,11 @@ impl Handler<WithSpanContext<BlockRequest>> for ViewClientActor {
         _ctx: &mut Self::Context,
     ) -> Self::Result {
         let (_span, msg) = handler_debug_span!(target: "client", msg);
-        let _timer =
-            metrics::VIEW_CLIENT_MESSAGE_TIME.with_label_values(&["BlockRequest"]).start_timer();
+        let _timer = self
+            .metrics
+            .view_client_message_time
+            .with_label_values(&["BlockRequest"])
+            .start_timer();
         let BlockRequest(hash) = msg;
         if let Ok(block) = self.chain.get_block(&hash) {
             Some(Box::new(block))
@@ -1122,7 +1591,9 @@ impl Handler<WithSpanContext<BlockHeadersRequest>> for ViewClientActor {
         _ctx: &mut Self::Context,
     ) -> Self::Result {
         let (_span, msg) = handler_debug_span!(target: "client", msg);
-        let _timer = metrics::VIEW_CLIENT_MESSAGE_TIME
+        let _timer = self
+            .metrics
+            .view_client_message_time
             .with_label_values(&["BlockHeadersRequest"])
             .start_timer();
         let BlockHeadersRequest(hashes) = msg;
@@ -1147,14 +1618,19 @@ impl Handler<WithSpanContext<StateRequestHeader>> for ViewClientActor {
         _ctx: &mut Self::Context,
     ) -> Self::Result {
         let (_span, msg) = handler_debug_span!(target: "client", msg);
-        let _timer = metrics::VIEW_CLIENT_MESSAGE_TIME
+        let _timer = self
+            .metrics
+            .view_client_message_time
             .with_label_values(&["StateRequestHeader"])
             .start_timer();
         let StateRequestHeader { shard_id, sync_hash } = msg;
         if !self.check_state_sync_request() {
             return None;
         }
-        let state_response = match self.chain.check_sync_hash_validity(&sync_hash) {
+        let state_response = match self
+            .chain
+            .check_sync_hash_validity(&sync_hash, self.config.state_sync_serve_epochs)
+        {
             Ok(true) => {
                 let header = match self.chain.get_state_response_header(shard_id, sync_hash) {
                     Ok(header) => Some(header),
@@ -1214,7 +1690,7 @@ impl Handler<WithSpanContext<StateRequestHeader>> for ViewClientActor {
                 });
                 Some(StateResponse(Box::new(info)))
             }
-            state_response @ ShardStateSyncResponse::V2(_) => {
+            state_response @ (ShardStateSyncResponse::V2(_) | ShardStateSyncResponse::V3(_)) => {
                 let info = StateResponseInfo::V2(StateResponseInfoV2 {
                     shard_id,
                     sync_hash,
@@ -1236,7 +1712,9 @@ impl Handler<WithSpanContext<StateRequestPart>> for ViewClientActor {
         _ctx: &mut Self::Context,
     ) -> Self::Result {
         let (_span, msg) = handler_debug_span!(target: "client", msg);
-        let _timer = metrics::VIEW_CLIENT_MESSAGE_TIME
+        let _timer = self
+            .metrics
+            .view_client_message_time
             .with_label_values(&["StateRequestPart"])
             .start_timer();
         let StateRequestPart { shard_id, sync_hash, part_id } = msg;
@@ -1244,10 +1722,13 @@ impl Handler<WithSpanContext<StateRequestPart>> for ViewClientActor {
             return None;
         }
         trace!(target: "sync", "Computing state request part {} {} {}", shard_id, sync_hash, part_id);
-        let state_response = match self.chain.check_sync_hash_validity(&sync_hash) {
+        let state_response = match self
+            .chain
+            .check_sync_hash_validity(&sync_hash, self.config.state_sync_serve_epochs)
+        {
             Ok(true) => {
-                let part = match self.chain.get_state_response_part(shard_id, part_id, sync_hash) {
-                    Ok(part) => Some((part_id, part)),
+                let part = match self.get_state_part(shard_id, part_id, sync_hash) {
+                    Ok(part) => Some((part_id, part.to_vec())),
                     Err(e) => {
                         error!(target: "sync", "Cannot build sync part #{:?} (get_state_response_part): {}", part_id, e);
                         None
@@ -1280,6 +1761,121 @@ impl Handler<WithSpanContext<StateRequestPart>> for ViewClientActor {
     }
 }
 
+impl Handler<WithSpanContext<StateRequestSubPart>> for ViewClientActor {
+    type Result = Option<StateResponse>;
+
+    #[perf]
+    fn handle(
+        &mut self,
+        msg: WithSpanContext<StateRequestSubPart>,
+        _ctx: &mut Self::Context,
+    ) -> Self::Result {
+        let (_span, msg) = handler_debug_span!(target: "client", msg);
+        let _timer = self
+            .metrics
+            .view_client_message_time
+            .with_label_values(&["StateRequestSubPart"])
+            .start_timer();
+        let StateRequestSubPart { shard_id, sync_hash, part_id, sub_part_id, sub_part_size } = msg;
+        if !is_valid_state_sub_part_size(sub_part_size) {
+            debug!(target: "sync", sub_part_size, "Requested state sub-part size out of range");
+            return None;
+        }
+        trace!(target: "sync", "Computing state request sub-part {} {} {} {}", shard_id, sync_hash, part_id, sub_part_id);
+        let part = match self
+            .chain
+            .check_sync_hash_validity(&sync_hash, self.config.state_sync_serve_epochs)
+        {
+            // Sub-parts of a part which is cached are served right away.  Any
+            // other sub-part computes the whole part, which is subject to
+            // throttling, and caches it for the remaining sub-parts.  This way
+            // a peer resuming a download started elsewhere can ask for any of
+            // them.
+            Ok(true) => match self.get_cached_state_part(shard_id, part_id, sync_hash) {
+                Ok(None) if !self.check_state_sync_request() => return None,
+                Ok(None) => self.get_state_part(shard_id, part_id, sync_hash).map(Some),
+                part => part,
+            },
+            Ok(false) => {
+                warn!(target: "sync", "sync_hash {:?} didn't pass validation, possible malicious behavior", sync_hash);
+                return None;
+            }
+            Err(e) => Err(e),
+        };
+        let sub_part = match part {
+            Ok(part) => {
+                part.and_then(|part| StateSubPart::new(part_id, sub_part_id, sub_part_size, &part))
+            }
+            Err(e) => {
+                error!(target: "sync", "Cannot build sync sub-part #{:?}/{:?}: {}", part_id, sub_part_id, e);
+                None
+            }
+        };
+        let state_response = ShardStateSyncResponse::V3(ShardStateSyncResponseV3 {
+            header: None,
+            part: None,
+            sub_part,
+        });
+        let info =
+            StateResponseInfo::V2(StateResponseInfoV2 { shard_id, sync_hash, state_response });
+        Some(StateResponse(Box::new(info)))
+    }
+}
+
+impl Handler<WithSpanContext<EpochSyncDataRequest>> for ViewClientActor {
+    type Result = Option<EpochSyncDataResponse>;
+
+    #[perf]
+    fn handle(
+        &mut self,
+        msg: WithSpanContext<EpochSyncDataRequest>,
+        _ctx: &mut Self::Context,
+    ) -> Self::Result {
+        let (_span, msg) = handler_debug_span!(target: "client", msg);
+        let _timer = self
+            .metrics
+            .view_client_message_time
+            .with_label_values(&["EpochSyncDataRequest"])
+            .start_timer();
+        let EpochSyncDataRequest(block_hash) = msg;
+        let data = match self.chain.get_epoch_sync_data(&block_hash) {
+            Ok(data) => Some(Box::new(data)),
+            Err(err) => {
+                debug!(target: "sync", ?block_hash, ?err, "Cannot build epoch sync data");
+                None
+            }
+        };
+        Some(EpochSyncDataResponse { block_hash, data })
+    }
+}
+
+impl Handler<WithSpanContext<EpochSyncProofsRequest>> for ViewClientActor {
+    type Result = Option<EpochSyncProofsResponse>;
+
+    #[perf]
+    fn handle(
+        &mut self,
+        msg: WithSpanContext<EpochSyncProofsRequest>,
+        _ctx: &mut Self::Context,
+    ) -> Self::Result {
+        let (_span, msg) = handler_debug_span!(target: "client", msg);
+        let _timer = self
+            .metrics
+            .view_client_message_time
+            .with_label_values(&["EpochSyncProofsRequest"])
+            .start_timer();
+        let EpochSyncProofsRequest(block_hash) = msg;
+        let proofs = match self.chain.get_epoch_sync_proofs(&block_hash) {
+            Ok(proofs) => Some(proofs),
+            Err(err) => {
+                debug!(target: "sync", ?block_hash, ?err, "Cannot build epoch sync proofs");
+                None
+            }
+        };
+        Some(EpochSyncProofsResponse { block_hash, proofs })
+    }
+}
+
 impl Handler<WithSpanContext<AnnounceAccountRequest>> for ViewClientActor {
     type Result = Result<Vec<AnnounceAccount>, ReasonForBan>;
 
@@ -1290,7 +1886,9 @@ impl Handler<WithSpanContext<AnnounceAccountRequest>> for ViewClientActor {
         _ctx: &mut Self::Context,
     ) -> Self::Result {
         let (_span, msg) = handler_debug_span!(target: "client", msg);
-        let _timer = metrics::VIEW_CLIENT_MESSAGE_TIME
+        let _timer = self
+            .metrics
+            .view_client_message_time
             .with_label_values(&["AnnounceAccountRequest"])
             .start_timer();
         let AnnounceAccountRequest(announce_accounts) = msg;
@@ -1350,9 +1948,178 @@ impl Handler<WithSpanContext<GetGasPrice>> for ViewClientActor {
     ) -> Self::Result {
         let (_span, msg) = handler_debug_span!(target: "client", msg);
         let _timer =
-            metrics::VIEW_CLIENT_MESSAGE_TIME.with_label_values(&["GetGasPrice"]).start_timer();
-        let header = self.maybe_block_id_to_block_header(msg.block_id);
-        Ok(GasPriceView { gas_price: header?.gas_price() })
+            self.metrics.view_client_message_time.with_label_values(&["GetGasPrice"]).start_timer();
+        self.update_view_caches();
+        if let Some(gas_price) = self.gas_price_cache.get(&msg.block_id) {
+            return Ok(gas_price);
+        }
+        let header = self.maybe_block_id_to_block_header(msg.block_id.clone())?;
+        let gas_price = GasPriceView { gas_price: header.gas_price() };
+        self.gas_price_cache.put(msg.block_id, gas_price.clone());
+        Ok(gas_price)
+    }
+}
+
+impl Handler<WithSpanContext<GetEconomicsSeries>> for ViewClientActor {
+    type Result = Result<EconomicsSeriesView, GetEconomicsSeriesError>;
+
+    #[perf]
+    fn handle(
+        &mut self,
+        msg: WithSpanContext<GetEconomicsSeries>,
+        _ctx: &mut Self::Context,
+    ) -> Self::Result {
+        let (_span, msg) = handler_debug_span!(target: "client", msg);
+        let _timer = self
+            .metrics
+            .view_client_message_time
+            .with_label_values(&["GetEconomicsSeries"])
+            .start_timer();
+        let head_height = self.chain.head()?.height;
+        let to_height = msg.to_height.unwrap_or(head_height).min(head_height);
+        if msg.step == 0 || msg.limit == 0 {
+            return Err(GetEconomicsSeriesError::InvalidRange {
+                error_message: "step and limit must be positive".to_string(),
+            });
+        }
+        if msg.from_height > to_height {
+            return Err(GetEconomicsSeriesError::InvalidRange {
+                error_message: format!(
+                    "from_height {} is above the end of the range {}",
+                    msg.from_height, to_height
+                ),
+            });
+        }
+        let limit = msg.limit.min(MAX_ECONOMICS_SERIES_POINTS);
+        let mut points = vec![];
+        // Garbage collected heights are skipped right away, keeping the
+        // sampled heights aligned with `from_height`.
+        let tail = self.chain.store().tail()?;
+        // Heights are caller-supplied, so a height which would overflow ends
+        // the series.
+        let mut next_height = Some(msg.from_height);
+        if msg.from_height < tail {
+            let steps = (tail - msg.from_height - 1) / msg.step + 1;
+            next_height = steps
+                .checked_mul(msg.step)
+                .and_then(|skipped| msg.from_height.checked_add(skipped));
+        }
+        while let Some(height) = next_height {
+            if height > to_height || points.len() >= limit {
+                break;
+            }
+            next_height = height.checked_add(msg.step);
+            let header = match self.chain.get_block_header_by_height(height) {
+                Ok(header) => header,
+                // No block at this height.
+                Err(near_chain::Error::DBNotFoundErr(_)) => continue,
+                Err(err) => return Err(err.into()),
+            };
+            let minted_amount = match self.chain.get_previous_header(&header) {
+                Ok(prev_header) if prev_header.epoch_id() != header.epoch_id() => {
+                    self.runtime_adapter.get_epoch_minted_amount(header.next_epoch_id())?
+                }
+                _ => 0,
+            };
+            points.push(EconomicsPointView {
+                height,
+                block_hash: *header.hash(),
+                timestamp_nanosec: header.raw_timestamp(),
+                gas_price: header.gas_price(),
+                total_supply: header.total_supply(),
+                minted_amount,
+            });
+        }
+        let next_from_height = next_height.filter(|height| *height <= to_height);
+        Ok(EconomicsSeriesView { points, next_from_height })
+    }
+}
+
+impl Handler<WithSpanContext<GetContractEvents>> for ViewClientActor {
+    type Result = Result<ContractEventsView, GetContractIndexError>;
+
+    #[perf]
+    fn handle(
+        &mut self,
+        msg: WithSpanContext<GetContractEvents>,
+        _ctx: &mut Self::Context,
+    ) -> Self::Result {
+        let (_span, msg) = handler_debug_span!(target: "client", msg);
+        let _timer = self
+            .metrics
+            .view_client_message_time
+            .with_label_values(&["GetContractEvents"])
+            .start_timer();
+        if !self.config.contract_events_index {
+            return Err(GetContractIndexError::IndexDisabled);
+        }
+        if msg.limit == 0 {
+            return Err(GetContractIndexError::InvalidRequest {
+                error_message: "limit must be positive".to_string(),
+            });
+        }
+        query_contract_events(
+            self.chain.store(),
+            &msg.account_id,
+            msg.standard.as_deref(),
+            msg.event.as_deref(),
+            msg.cursor.as_deref(),
+            msg.limit,
+        )
+        .map_err(|err| match err {
+            contract_events::QueryError::InvalidQuery(error_message) => {
+                GetContractIndexError::InvalidRequest { error_message }
+            }
+            contract_events::QueryError::Chain(err) => err.into(),
+        })
+    }
+}
+
+impl Handler<WithSpanContext<GetContractAccounts>> for ViewClientActor {
+    type Result = Result<ContractAccountsView, GetContractIndexError>;
+
+    #[perf]
+    fn handle(
+        &mut self,
+        msg: WithSpanContext<GetContractAccounts>,
+        _ctx: &mut Self::Context,
+    ) -> Self::Result {
+        let (_span, msg) = handler_debug_span!(target: "client", msg);
+        let _timer = self
+            .metrics
+            .view_client_message_time
+            .with_label_values(&["GetContractAccounts"])
+            .start_timer();
+        if !self.config.contract_code_index {
+            return Err(GetContractIndexError::IndexDisabled);
+        }
+        if msg.limit == 0 {
+            return Err(GetContractIndexError::InvalidRequest {
+                error_message: "limit must be positive".to_string(),
+            });
+        }
+        // The index may list accounts which have deployed other code since,
+        // so every candidate is checked against the state at the final block.
+        let final_hash = self.chain.final_head()?.last_block_hash;
+        let block_reference = BlockReference::BlockId(BlockId::Hash(final_hash));
+        let store = self.chain.store().store().clone();
+        query_contract_accounts(
+            &store,
+            &msg.code_hash,
+            msg.cursor.as_ref(),
+            msg.limit,
+            |account_id| {
+                let request = QueryRequest::ViewAccount { account_id: account_id.clone() };
+                match self.handle_query(Query::new(block_reference.clone(), request)) {
+                    Ok(QueryResponse { kind: QueryResponseKind::ViewAccount(account), .. }) => {
+                        Ok(account.code_hash == msg.code_hash)
+                    }
+                    Ok(_) | Err(QueryError::UnknownAccount { .. }) => Ok(false),
+                    Err(err) => Err(near_chain::Error::Other(err.to_string())),
+                }
+            },
+        )
+        .map_err(Into::into)
     }
 }
 
@@ -1361,11 +2128,16 @@ pub fn start_view_client(
     validator_account_id: Option<AccountId>,
     chain_genesis: ChainGenesis,
     runtime_adapter: Arc<dyn RuntimeAdapter>,
+    store: Store,
     network_adapter: Arc<dyn PeerManagerAdapter>,
     config: ClientConfig,
     adv: crate::adversarial::Controls,
 ) -> Addr<ViewClientActor> {
     let request_manager = Arc::new(RwLock::new(ViewClientRequestManager::new()));
+    let metrics =
+        MetricsRegistry::for_instance(config.metrics_instance.as_deref()).get::<ClientMetrics>();
+    let state_part_cache =
+        Arc::new(Mutex::new(StatePartCache::new(config.state_part_cache_size, metrics)));
     SyncArbiter::start(config.view_client_threads, move || {
         // ViewClientActor::start_in_arbiter(&Arbiter::current(), move |_ctx| {
         let validator_account_id1 = validator_account_id.clone();
@@ -1377,9 +2149,11 @@ pub fn start_view_client(
             validator_account_id1,
             &chain_genesis,
             runtime_adapter1,
+            store.clone(),
             network_adapter1,
             config1,
             request_manager1,
+            state_part_cache.clone(),
             adv.clone(),
         )
         .unwrap()
@@ -1,5 +1,7 @@
 use near_chain_primitives::Error;
 use near_crypto::Signature;
+#[cfg(feature = "protocol_feature_bls_approvals")]
+use near_primitives::block_header::{ApprovalsAggregate, BlsKeyRegistration};
 use near_primitives::{
     block_header::{Approval, ApprovalInner, BlockHeader},
     epoch_manager::ShardConfig,
@@ -11,7 +13,7 @@ use near_primitives::{
         validator_stake::ValidatorStake, AccountId, ApprovalStake, Balance, BlockHeight,
         EpochHeight, EpochId, NumShards, ShardId, ValidatorInfoIdentifier,
     },
-    views::EpochValidatorInfo,
+    views::{BlockProducerProofView, EpochRandomnessView, EpochValidatorInfo},
 };
 use near_store::ShardUId;
 
@@ -124,6 +126,18 @@ pub trait EpochManagerAdapter: Send + Sync {
         height: BlockHeight,
     ) -> Result<AccountId, Error>;
 
+    /// Block producer for given height together with the inputs of its
+    /// assignment, which let it be verified independently.
+    fn get_block_producer_proof(
+        &self,
+        epoch_id: &EpochId,
+        height: BlockHeight,
+    ) -> Result<BlockProducerProofView, Error>;
+
+    /// Randomness of the epoch of the block, with the block whose random value
+    /// seeded it.
+    fn get_epoch_randomness(&self, block_hash: &CryptoHash) -> Result<EpochRandomnessView, Error>;
+
     /// Chunk producer for given height for given shard. Return error if outside of known boundaries.
     fn get_chunk_producer(
         &self,
@@ -244,6 +258,36 @@ pub trait EpochManagerAdapter: Send + Sync {
         block_height: BlockHeight,
         approvals: &[Option<Signature>],
     ) -> Result<(), Error>;
+
+    /// Checks that BLS key registration is signed with the validator key in the given epoch and
+    /// has a valid proof of possession.
+    #[cfg(feature = "protocol_feature_bls_approvals")]
+    fn verify_bls_key_registration(
+        &self,
+        epoch_id: &EpochId,
+        last_known_block_hash: &CryptoHash,
+        registration: &BlsKeyRegistration,
+    ) -> Result<bool, Error>;
+
+    /// Stores BLS key of a validator.  The registration must have been verified.
+    #[cfg(feature = "protocol_feature_bls_approvals")]
+    fn register_bls_key(&self, registration: &BlsKeyRegistration) -> Result<(), Error>;
+
+    /// BLS key registered by the validator, if any.
+    #[cfg(feature = "protocol_feature_bls_approvals")]
+    fn get_bls_key(&self, account_id: &AccountId) -> Option<near_crypto::bls::PublicKey>;
+
+    /// Verify aggregated BLS signature of approvals.  Fails if a signer has no registered BLS
+    /// key or also has an individual approval.
+    #[cfg(feature = "protocol_feature_bls_approvals")]
+    fn verify_approvals_aggregate(
+        &self,
+        prev_block_hash: &CryptoHash,
+        prev_block_height: BlockHeight,
+        block_height: BlockHeight,
+        approvals: &[Option<Signature>],
+        approvals_aggregate: &ApprovalsAggregate,
+    ) -> Result<bool, Error>;
 }
 
 /// A technical plumbing trait to conveniently implement [`EpochManagerAdapter`]
@@ -433,6 +477,20 @@ impl<T: HasEpochMangerHandle + Send + Sync> EpochManagerAdapter for T {
         Ok(epoch_manager.get_block_producer_info(epoch_id, height)?.take_account_id())
     }
 
+    fn get_block_producer_proof(
+        &self,
+        epoch_id: &EpochId,
+        height: BlockHeight,
+    ) -> Result<BlockProducerProofView, Error> {
+        let epoch_manager = self.read();
+        Ok(epoch_manager.get_block_producer_proof(epoch_id, height)?)
+    }
+
+    fn get_epoch_randomness(&self, block_hash: &CryptoHash) -> Result<EpochRandomnessView, Error> {
+        let epoch_manager = self.read();
+        Ok(epoch_manager.get_epoch_randomness(block_hash)?)
+    }
+
     fn get_chunk_producer(
         &self,
         epoch_id: &EpochId,
@@ -653,4 +711,86 @@ impl<T: HasEpochMangerHandle + Send + Sync> EpochManagerAdapter for T {
             Ok(())
         }
     }
+
+    #[cfg(feature = "protocol_feature_bls_approvals")]
+    fn verify_bls_key_registration(
+        &self,
+        epoch_id: &EpochId,
+        last_known_block_hash: &CryptoHash,
+        registration: &BlsKeyRegistration,
+    ) -> Result<bool, Error> {
+        // Registered keys have been verified already, and checking the proof of possession
+        // is expensive.
+        if self.read().get_bls_key(&registration.account_id) == Some(&registration.public_key) {
+            return Ok(true);
+        }
+        if !registration.verify_proof_of_possession() {
+            return Ok(false);
+        }
+        self.verify_validator_signature(
+            epoch_id,
+            last_known_block_hash,
+            &registration.account_id,
+            &BlsKeyRegistration::get_data_for_sig(
+                &registration.account_id,
+                &registration.public_key,
+            ),
+            &registration.signature,
+        )
+    }
+
+    #[cfg(feature = "protocol_feature_bls_approvals")]
+    fn register_bls_key(&self, registration: &BlsKeyRegistration) -> Result<(), Error> {
+        self.write()
+            .register_bls_key(registration.account_id.clone(), registration.public_key)
+            .map_err(Error::from)
+    }
+
+    #[cfg(feature = "protocol_feature_bls_approvals")]
+    fn get_bls_key(&self, account_id: &AccountId) -> Option<near_crypto::bls::PublicKey> {
+        self.read().get_bls_key(account_id).copied()
+    }
+
+    #[cfg(feature = "protocol_feature_bls_approvals")]
+    fn verify_approvals_aggregate(
+        &self,
+        prev_block_hash: &CryptoHash,
+        prev_block_height: BlockHeight,
+        block_height: BlockHeight,
+        approvals: &[Option<Signature>],
+        approvals_aggregate: &ApprovalsAggregate,
+    ) -> Result<bool, Error> {
+        let epoch_manager = self.read();
+        let info =
+            epoch_manager.get_all_block_approvers_ordered(prev_block_hash).map_err(Error::from)?;
+        if approvals_aggregate.signers.len() > info.len() {
+            return Ok(false);
+        }
+
+        let mut public_keys = vec![];
+        for (i, ((validator, is_slashed), signed)) in
+            info.iter().zip(approvals_aggregate.signers.iter()).enumerate()
+        {
+            if !*signed {
+                continue;
+            }
+            if *is_slashed || approvals.get(i).map_or(false, Option::is_some) {
+                return Ok(false);
+            }
+            match epoch_manager.get_bls_key(&validator.account_id) {
+                Some(public_key) => public_keys.push(*public_key),
+                None => return Ok(false),
+            }
+        }
+
+        let message_to_sign = Approval::get_data_for_sig(
+            &if prev_block_height + 1 == block_height {
+                ApprovalInner::Endorsement(*prev_block_hash)
+            } else {
+                ApprovalInner::Skip(prev_block_height)
+            },
+            block_height,
+        );
+        Ok(approvals_aggregate.signature.verify_aggregate(&message_to_sign, public_keys.iter()))
+    }
 }
@@ -19,7 +19,8 @@ use near_primitives::types::{
 };
 use near_primitives::version::{ProtocolVersion, UPGRADABILITY_FIX_PROTOCOL_VERSION};
 use near_primitives::views::{
-    CurrentEpochValidatorInfo, EpochValidatorInfo, NextEpochValidatorInfo, ValidatorKickoutView,
+    BlockProducerProofView, CurrentEpochValidatorInfo, EpochRandomnessView, EpochValidatorInfo,
+    NextEpochValidatorInfo, ValidatorKickoutView,
 };
 use near_store::{DBCol, Store, StoreUpdate};
 use num_rational::Rational64;
@@ -139,6 +140,12 @@ pub struct EpochManager {
     /// Largest final height. Monotonically increasing.
     largest_final_height: BlockHeight,
 
+    /// BLS keys of validators, registered through the block headers that first used them.  The
+    /// keys are persisted, since the headers carrying the registrations aren't processed again
+    /// after a restart.
+    #[cfg(feature = "protocol_feature_bls_approvals")]
+    bls_keys: HashMap<AccountId, near_crypto::bls::PublicKey>,
+
     /// Counts loop iterations inside of aggregate_epoch_info_upto method.
     /// Used for tests as a bit of white-box testing.
     #[cfg(test)]
@@ -146,6 +153,44 @@ pub struct EpochManager {
 }
 
 impl EpochManager {
+    #[cfg(feature = "protocol_feature_bls_approvals")]
+    pub fn get_bls_key(&self, account_id: &AccountId) -> Option<&near_crypto::bls::PublicKey> {
+        self.bls_keys.get(account_id)
+    }
+
+    #[cfg(feature = "protocol_feature_bls_approvals")]
+    pub fn register_bls_key(
+        &mut self,
+        account_id: AccountId,
+        public_key: near_crypto::bls::PublicKey,
+    ) -> Result<(), EpochError> {
+        if self.bls_keys.get(&account_id) == Some(&public_key) {
+            return Ok(());
+        }
+        let mut store_update = self.store.store_update();
+        store_update.set_ser(DBCol::BlsKeys, account_id.as_ref().as_bytes(), &public_key)?;
+        store_update.commit()?;
+        self.bls_keys.insert(account_id, public_key);
+        Ok(())
+    }
+
+    #[cfg(feature = "protocol_feature_bls_approvals")]
+    fn load_bls_keys(
+        store: &Store,
+    ) -> Result<HashMap<AccountId, near_crypto::bls::PublicKey>, EpochError> {
+        let mut bls_keys = HashMap::new();
+        for item in store.iter(DBCol::BlsKeys) {
+            let (key, value) = item?;
+            let account_id = std::str::from_utf8(&key)
+                .ok()
+                .and_then(|account_id| account_id.parse().ok())
+                .ok_or_else(|| EpochError::IOErr(format!("Invalid BLS key row {:?}", key)))?;
+            let public_key = borsh::BorshDeserialize::try_from_slice(&value)?;
+            bls_keys.insert(account_id, public_key);
+        }
+        Ok(bls_keys)
+    }
+
     pub fn new_from_genesis_config(
         store: Store,
         genesis_config: &GenesisConfig,
@@ -176,6 +221,8 @@ impl EpochManager {
             .unwrap_or_default();
         let genesis_num_block_producer_seats =
             config.for_protocol_version(genesis_protocol_version).num_block_producer_seats;
+        #[cfg(feature = "protocol_feature_bls_approvals")]
+        let bls_keys = Self::load_bls_keys(&store)?;
         let mut epoch_manager = EpochManager {
             store,
             config,
@@ -192,6 +239,8 @@ impl EpochManager {
             #[cfg(test)]
             epoch_info_aggregator_loop_counter: Default::default(),
             largest_final_height: 0,
+            #[cfg(feature = "protocol_feature_bls_approvals")]
+            bls_keys,
         };
         let genesis_epoch_id = EpochId::default();
         if !epoch_manager.has_epoch_info(&genesis_epoch_id)? {
@@ -811,6 +860,61 @@ impl EpochManager {
         Ok(epoch_info.get_validator(validator_id))
     }
 
+    /// Returns the block producer of the height together with the inputs of
+    /// its assignment, which let it be verified independently.
+    pub fn get_block_producer_proof(
+        &self,
+        epoch_id: &EpochId,
+        height: BlockHeight,
+    ) -> Result<BlockProducerProofView, EpochError> {
+        let epoch_info = self.get_epoch_info(epoch_id)?;
+        let validator_id = Self::block_producer_from_info(&epoch_info, height);
+        Ok(BlockProducerProofView {
+            height,
+            epoch_id: epoch_id.0,
+            epoch_height: epoch_info.epoch_height(),
+            rng_seed: epoch_info.rng_seed().map(|rng_seed| CryptoHash(*rng_seed)),
+            block_producers_settlement: epoch_info
+                .block_producers_settlement()
+                .iter()
+                .map(|validator_id| epoch_info.get_validator(*validator_id).into())
+                .collect(),
+            block_producer: epoch_info.get_validator(validator_id).take_account_id(),
+        })
+    }
+
+    /// Returns the randomness of the epoch of the block together with the block
+    /// whose random value seeded it.
+    pub fn get_epoch_randomness(
+        &self,
+        block_hash: &CryptoHash,
+    ) -> Result<EpochRandomnessView, EpochError> {
+        let block_info = self.get_block_info(block_hash)?;
+        let epoch_info = self.get_epoch_info(block_info.epoch_id())?;
+        let rng_seed = epoch_info.rng_seed().map(|rng_seed| CryptoHash(*rng_seed));
+        // The last block of the epoch T seeds the epoch T + 2.  The epochs
+        // following the genesis block aren't seeded by any block, their seed
+        // is zero.
+        let prev_epoch_last_block =
+            *self.get_block_info(block_info.epoch_first_block())?.prev_hash();
+        let seed_block_hash = if rng_seed.map_or(true, |rng_seed| rng_seed == CryptoHash::default())
+            || prev_epoch_last_block == CryptoHash::default()
+        {
+            None
+        } else {
+            let prev_epoch_first_block =
+                *self.get_block_info(&prev_epoch_last_block)?.epoch_first_block();
+            Some(*self.get_block_info(&prev_epoch_first_block)?.prev_hash())
+                .filter(|hash| hash != &CryptoHash::default())
+        };
+        Ok(EpochRandomnessView {
+            epoch_id: block_info.epoch_id().0,
+            epoch_height: epoch_info.epoch_height(),
+            rng_seed,
+            seed_block_hash,
+        })
+    }
+
     /// Returns settlement of all block producers in current epoch, with indicator on whether they are slashed or not.
     pub fn get_all_block_producers_settlement(
         &self,
@@ -1233,6 +1337,8 @@ impl EpochManager {
                         let (account_id, public_key, stake) = info.destructure();
                         Ok(CurrentEpochValidatorInfo {
                             is_slashed: false, // currently there is no slashing
+                            #[cfg(feature = "protocol_feature_bls_approvals")]
+                            bls_public_key: self.bls_keys.get(&account_id).copied(),
                             account_id,
                             public_key,
                             stake,
@@ -1279,6 +1385,8 @@ impl EpochManager {
                         let (account_id, public_key, stake) = info.destructure();
                         Ok(CurrentEpochValidatorInfo {
                             is_slashed: false, // currently there is no slashing
+                            #[cfg(feature = "protocol_feature_bls_approvals")]
+                            bls_public_key: self.bls_keys.get(&account_id).copied(),
                             account_id,
                             public_key,
                             stake,
@@ -2421,6 +2421,94 @@ fn test_chunk_producers() {
     );
 }
 
+#[test]
+fn test_block_producer_proof() {
+    let amount_staked = 1_000_000;
+    let validators = vec![
+        ("test1".parse().unwrap(), amount_staked),
+        ("test2".parse().unwrap(), amount_staked / 2),
+        ("test3".parse().unwrap(), amount_staked / 4),
+    ];
+    let mut epoch_manager = setup_default_epoch_manager(validators, 5, 1, 3, 0, 90, 60);
+    let h = hash_range(10);
+    record_block(&mut epoch_manager, CryptoHash::default(), h[0], 0, vec![]);
+    for i in 1..10 {
+        record_block(&mut epoch_manager, h[i - 1], h[i], i as u64, vec![]);
+    }
+
+    for i in 1..10 {
+        let epoch_id = epoch_manager.get_epoch_id_from_prev_block(&h[i - 1]).unwrap();
+        for height in i as u64..i as u64 + 20 {
+            let proof = epoch_manager.get_block_producer_proof(&epoch_id, height).unwrap();
+            let block_producer = epoch_manager.get_block_producer_info(&epoch_id, height).unwrap();
+            assert_eq!(&proof.block_producer, block_producer.account_id());
+            assert!(proof.verify());
+
+            // A proof naming a different producer doesn't verify.
+            let mut forged = proof.clone();
+            forged.block_producer = if forged.block_producer.as_str() == "test1" {
+                "test2".parse().unwrap()
+            } else {
+                "test1".parse().unwrap()
+            };
+            assert!(!forged.verify());
+        }
+    }
+}
+
+#[test]
+fn test_epoch_randomness() {
+    let validators = vec![("test1".parse().unwrap(), 1_000_000)];
+    let epoch_length = 5;
+    let mut epoch_manager = setup_default_epoch_manager(validators, epoch_length, 1, 1, 0, 90, 60);
+    let h = hash_range(4 * epoch_length as usize);
+    // Every block has its hash as its random value.
+    let mut prev_h = CryptoHash::default();
+    for (height, cur_h) in h.iter().enumerate() {
+        let height = height as BlockHeight;
+        let block_info = BlockInfo::new(
+            *cur_h,
+            height,
+            height.saturating_sub(2),
+            prev_h,
+            prev_h,
+            vec![],
+            vec![],
+            vec![],
+            DEFAULT_TOTAL_SUPPLY,
+            PROTOCOL_VERSION,
+            height * NUM_NS_IN_SECOND,
+        );
+        epoch_manager.record_block_info(block_info, cur_h.0).unwrap().commit().unwrap();
+        prev_h = *cur_h;
+    }
+
+    let mut seed_blocks = vec![];
+    for cur_h in &h {
+        let randomness = epoch_manager.get_epoch_randomness(cur_h).unwrap();
+        let epoch_id = epoch_manager.get_epoch_id(cur_h).unwrap();
+        let epoch_info = epoch_manager.get_epoch_info(&epoch_id).unwrap();
+        assert_eq!(randomness.epoch_id, epoch_id.0);
+        assert_eq!(randomness.epoch_height, epoch_info.epoch_height());
+        let seed_block_hash = match randomness.seed_block_hash {
+            Some(seed_block_hash) => seed_block_hash,
+            None => {
+                assert!(randomness.rng_seed.map_or(true, |seed| seed == CryptoHash::default()));
+                continue;
+            }
+        };
+        // The seed is the random value of the last block two epochs back.
+        assert_eq!(randomness.rng_seed, Some(seed_block_hash));
+        let seed_block = epoch_manager.get_block_info(&seed_block_hash).unwrap();
+        assert!(epoch_manager.is_next_block_in_next_epoch(&seed_block).unwrap());
+        seed_blocks.push(seed_block_hash);
+    }
+    if epoch_manager.get_epoch_info(&EpochId::default()).unwrap().rng_seed().is_some() {
+        seed_blocks.dedup();
+        assert!(seed_blocks.len() >= 2);
+    }
+}
+
 /// A sanity test for the compute_kickout_info function, tests that
 /// the validators that don't meet the block/chunk producer kickout threshold is kicked out
 #[test]
@@ -2652,3 +2740,27 @@ fn test_max_kickout_stake_ratio() {
         ])
     );
 }
+
+#[test]
+#[cfg(feature = "protocol_feature_bls_approvals")]
+fn test_bls_keys_survive_restart() {
+    let validators = vec![("test1".parse().unwrap(), 1_000_000)];
+    let mut epoch_manager = setup_default_epoch_manager(validators.clone(), 2, 1, 1, 0, 90, 60);
+    let secret_key = near_crypto::SecretKey::from_seed(near_crypto::KeyType::ED25519, "test1");
+    let bls_key = *near_crypto::bls::SecretKey::derive_from(&secret_key).public_key();
+    let account_id: AccountId = "test1".parse().unwrap();
+    epoch_manager.register_bls_key(account_id.clone(), bls_key).unwrap();
+
+    let epoch_manager2 = EpochManager::new(
+        epoch_manager.store.clone(),
+        epoch_manager.config.clone(),
+        PROTOCOL_VERSION,
+        epoch_manager.reward_calculator,
+        validators
+            .iter()
+            .map(|(account_id, balance)| stake(account_id.clone(), *balance))
+            .collect(),
+    )
+    .unwrap();
+    assert_eq!(epoch_manager2.get_bls_key(&account_id), Some(&bls_key));
+}
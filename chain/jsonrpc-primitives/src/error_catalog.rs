@@ -0,0 +1,180 @@
+//! Machine-readable catalog of the errors returned by the JSON RPC methods.
+//!
+//! Every RPC error type lists all its variants with [`error_catalog!`] right
+//! next to its definition.  The macro also implements
+//! [`CatalogedError::is_retriable`] with an exhaustive match, so adding a
+//! variant without cataloguing it fails to compile.
+use serde::{Deserialize, Serialize};
+
+use crate::types;
+
+/// Description of a single error as returned by the RPC.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq)]
+pub struct ErrorCatalogEntry {
+    /// Rust type of the error, which identifies the methods returning it, for
+    /// example `RpcQueryError`.
+    pub error_type: String,
+    /// Name of the error as found in `cause.name` of the response.
+    pub name: String,
+    /// Kind of the error as found in `name` of the response, either
+    /// `HANDLER_ERROR` or `INTERNAL_ERROR`.
+    pub kind: String,
+    /// JSON RPC error code.
+    pub code: i64,
+    /// Whether retrying the request, possibly on another node, may succeed.
+    pub retriable: bool,
+    pub description: String,
+}
+
+impl ErrorCatalogEntry {
+    pub fn new(error_type: &str, variant: &str, retriable: bool, description: &str) -> Self {
+        let name = screaming_snake_case(variant);
+        let kind = if name == "INTERNAL_ERROR" { "INTERNAL_ERROR" } else { "HANDLER_ERROR" };
+        Self {
+            error_type: error_type.to_string(),
+            name,
+            kind: kind.to_string(),
+            code: -32_000,
+            retriable,
+            description: description.to_string(),
+        }
+    }
+}
+
+/// Implemented by the RPC error types with [`error_catalog!`].
+pub trait CatalogedError {
+    /// Returns entries for all variants of the error.
+    fn catalog() -> Vec<ErrorCatalogEntry>;
+
+    /// Whether retrying the request which failed with this error may succeed.
+    fn is_retriable(&self) -> bool;
+}
+
+/// Implements [`CatalogedError`] for an error enum.  Every variant has to be
+/// listed together with its retriability and description.
+macro_rules! error_catalog {
+    ($error:ident { $($variant:ident => ($retriable:expr, $description:literal),)* }) => {
+        impl $crate::error_catalog::CatalogedError for $error {
+            fn catalog() -> Vec<$crate::error_catalog::ErrorCatalogEntry> {
+                vec![$($crate::error_catalog::ErrorCatalogEntry::new(
+                    stringify!($error),
+                    stringify!($variant),
+                    $retriable,
+                    $description,
+                ),)*]
+            }
+
+            fn is_retriable(&self) -> bool {
+                match self {
+                    $(Self::$variant { .. } => $retriable,)*
+                }
+            }
+        }
+    };
+}
+
+/// Converts a variant name the same way serde’s `SCREAMING_SNAKE_CASE` does.
+fn screaming_snake_case(name: &str) -> String {
+    let mut result = String::with_capacity(name.len() + 4);
+    for (i, ch) in name.char_indices() {
+        if i > 0 && ch.is_uppercase() {
+            result.push('_');
+        }
+        result.push(ch.to_ascii_uppercase());
+    }
+    result
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq)]
+pub struct RpcErrorCatalogResponse {
+    pub errors: Vec<ErrorCatalogEntry>,
+}
+
+/// Returns the catalog of errors of all RPC methods.
+pub fn error_catalog() -> RpcErrorCatalogResponse {
+    let errors = [
+        types::auth::RpcAuthError::catalog(),
+        types::blocks::RpcBlockError::catalog(),
+        types::changes::RpcStateChangesError::catalog(),
+        types::changes::RpcTrieDiffError::catalog(),
+        types::chunks::RpcChunkError::catalog(),
+        types::config::RpcProtocolConfigError::catalog(),
+        types::config::RpcRuntimeParametersDiffError::catalog(),
+        types::contract_index::RpcContractIndexError::catalog(),
+        types::gas_price::RpcGasPriceError::catalog(),
+        types::gas_price::RpcEconomicsSeriesError::catalog(),
+        types::light_client::RpcLightClientProofError::catalog(),
+        types::light_client::RpcLightClientNextBlockError::catalog(),
+        types::network_info::RpcNetworkInfoError::catalog(),
+        types::query::RpcQueryError::catalog(),
+        types::rate_limit::RpcRateLimitError::catalog(),
+        types::receipts::RpcReceiptError::catalog(),
+        types::sandbox::RpcSandboxPatchStateError::catalog(),
+        types::sandbox::RpcSandboxFastForwardError::catalog(),
+        types::status::RpcStatusError::catalog(),
+        types::transactions::RpcTransactionError::catalog(),
+        types::tx_pool::RpcTxPoolError::catalog(),
+        types::validator::RpcValidatorError::catalog(),
+    ]
+    .concat();
+    RpcErrorCatalogResponse { errors }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{error_catalog, CatalogedError};
+    use crate::errors::{RpcError, RpcErrorKind};
+    use crate::types::query::RpcQueryError;
+    use crate::types::status::RpcStatusError;
+    use std::collections::HashSet;
+
+    /// Returns kind and name of the error as seen by RPC clients.
+    fn kind_and_name(error: RpcError) -> (String, String) {
+        let (kind, cause) = match error.error_struct.unwrap() {
+            RpcErrorKind::HandlerError(cause) => ("HANDLER_ERROR", cause),
+            RpcErrorKind::InternalError(cause) => ("INTERNAL_ERROR", cause),
+            RpcErrorKind::RequestValidationError(_) => unreachable!(),
+        };
+        (kind.to_string(), cause["name"].as_str().unwrap().to_string())
+    }
+
+    #[test]
+    fn test_catalog_matches_responses() {
+        let catalog = error_catalog().errors;
+        let errors = vec![
+            ("RpcStatusError", RpcError::from(RpcStatusError::NodeIsSyncing)),
+            (
+                "RpcStatusError",
+                RpcError::from(RpcStatusError::InternalError { error_message: "x".to_string() }),
+            ),
+            ("RpcQueryError", RpcError::from(RpcQueryError::NoSyncedBlocks)),
+            (
+                "RpcQueryError",
+                RpcError::from(RpcQueryError::UnavailableShard { requested_shard_id: 0 }),
+            ),
+        ];
+        for (error_type, error) in errors {
+            let (kind, name) = kind_and_name(error);
+            let entry = catalog
+                .iter()
+                .find(|entry| entry.error_type == error_type && entry.name == name)
+                .unwrap();
+            assert_eq!(entry.kind, kind);
+        }
+    }
+
+    #[test]
+    fn test_catalog_entries_unique() {
+        let catalog = error_catalog().errors;
+        let unique: HashSet<_> =
+            catalog.iter().map(|entry| (&entry.error_type, &entry.name)).collect();
+        assert_eq!(unique.len(), catalog.len());
+        assert!(catalog.iter().all(|entry| !entry.description.is_empty()));
+    }
+
+    #[test]
+    fn test_retriable() {
+        assert!(RpcStatusError::NodeIsSyncing.is_retriable());
+        assert!(!RpcQueryError::UnavailableShard { requested_shard_id: 0 }.is_retriable());
+    }
+}
@@ -1,3 +1,5 @@
+#[macro_use]
+pub mod error_catalog;
 pub mod errors;
 pub mod message;
 pub mod types;
@@ -0,0 +1,37 @@
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+
+/// Returned instead of the response of a privileged method when the node
+/// requires API keys for privileged calls and the request doesn't carry a key
+/// granting the permission the method requires.
+#[derive(thiserror::Error, Debug, Serialize, Deserialize)]
+#[serde(tag = "name", content = "info", rename_all = "SCREAMING_SNAKE_CASE")]
+pub enum RpcAuthError {
+    #[error("{method_name} requires an API key with the {permission} permission")]
+    Unauthorized { method_name: String, permission: String },
+    #[error("API key {key_name} lacks the {permission} permission required by {method_name}")]
+    Forbidden { key_name: String, method_name: String, permission: String },
+}
+
+error_catalog!(RpcAuthError {
+    Unauthorized => (false, "The method requires an API key, which is missing or unknown"),
+    Forbidden => (false, "The API key doesn't grant the permission the method requires"),
+});
+
+impl From<RpcAuthError> for crate::errors::RpcError {
+    fn from(error: RpcAuthError) -> Self {
+        let error_data = Some(Value::String(error.to_string()));
+
+        let error_data_value = match serde_json::to_value(error) {
+            Ok(value) => value,
+            Err(err) => {
+                return Self::new_internal_error(
+                    None,
+                    format!("Failed to serialize RpcAuthError: {:?}", err),
+                )
+            }
+        };
+
+        Self::new_internal_or_handler_error(error_data, error_data_value)
+    }
+}
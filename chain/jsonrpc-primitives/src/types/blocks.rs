@@ -18,6 +18,12 @@ pub enum RpcBlockError {
     InternalError { error_message: String },
 }
 
+error_catalog!(RpcBlockError {
+    UnknownBlock => (false, "Block is unknown to the node or garbage collected"),
+    NotSyncedYet => (true, "The node has no fully synchronized blocks yet"),
+    InternalError => (true, "The node reached its limits; retry later"),
+});
+
 #[derive(Debug, Serialize, Deserialize, arbitrary::Arbitrary)]
 pub struct RpcBlockRequest {
     #[serde(flatten)]
@@ -30,6 +36,22 @@ pub struct RpcBlockResponse {
     pub block_view: near_primitives::views::BlockView,
 }
 
+#[derive(Debug, Serialize, Deserialize)]
+pub struct RpcRandomnessRequest {
+    #[serde(flatten)]
+    pub block_reference: near_primitives::types::BlockReference,
+    /// Number of ancestors of the block to return, whose random values lead
+    /// to the random value of the block.
+    #[serde(default)]
+    pub lineage_length: u64,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct RpcRandomnessResponse {
+    #[serde(flatten)]
+    pub randomness: near_primitives::views::RandomnessView,
+}
+
 impl From<RpcBlockError> for crate::errors::RpcError {
     fn from(error: RpcBlockError) -> Self {
         let error_data = match &error {
@@ -26,6 +26,31 @@ pub struct RpcStateChangesInBlockByTypeResponse {
     pub changes: near_primitives::views::StateChangesKindsView,
 }
 
+fn default_trie_diff_limit() -> usize {
+    100
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct RpcTrieDiffRequest {
+    pub shard_id: near_primitives::types::ShardId,
+    /// Changes made after this block are returned.
+    pub from_block_id: near_primitives::types::BlockId,
+    /// Changes made up to and including this block are returned.
+    pub to_block_id: near_primitives::types::BlockId,
+    /// `next_cursor` of the previous response, to continue the query.
+    #[serde(default)]
+    pub cursor: Option<String>,
+    /// Maximum number of keys to return.  The node caps it at 1000.
+    #[serde(default = "default_trie_diff_limit")]
+    pub limit: usize,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct RpcTrieDiffResponse {
+    #[serde(flatten)]
+    pub trie_diff: near_primitives::views::TrieDiffView,
+}
+
 #[derive(thiserror::Error, Debug, Serialize, Deserialize)]
 #[serde(tag = "name", content = "info", rename_all = "SCREAMING_SNAKE_CASE")]
 pub enum RpcStateChangesError {
@@ -40,6 +65,12 @@ pub enum RpcStateChangesError {
     InternalError { error_message: String },
 }
 
+error_catalog!(RpcStateChangesError {
+    UnknownBlock => (false, "Block is unknown to the node or garbage collected"),
+    NotSyncedYet => (true, "The node has no fully synchronized blocks yet"),
+    InternalError => (true, "The node reached its limits; retry later"),
+});
+
 impl From<RpcStateChangesError> for crate::errors::RpcError {
     fn from(error: RpcStateChangesError) -> Self {
         let error_data = match serde_json::to_value(error) {
@@ -54,3 +85,44 @@ impl From<RpcStateChangesError> for crate::errors::RpcError {
         Self::new_internal_or_handler_error(Some(error_data.clone()), error_data)
     }
 }
+
+#[derive(thiserror::Error, Debug, Serialize, Deserialize)]
+#[serde(tag = "name", content = "info", rename_all = "SCREAMING_SNAKE_CASE")]
+pub enum RpcTrieDiffError {
+    #[error("Block not found: {error_message}")]
+    UnknownBlock {
+        #[serde(skip_serializing)]
+        error_message: String,
+    },
+    #[error("There are no fully synchronized blocks yet")]
+    NotSyncedYet,
+    #[error("Invalid request: {error_message}")]
+    InvalidRequest { error_message: String },
+    #[error("State is not available: {error_message}")]
+    StateUnavailable { error_message: String },
+    #[error("The node reached its limits. Try again later. More details: {error_message}")]
+    InternalError { error_message: String },
+}
+
+error_catalog!(RpcTrieDiffError {
+    UnknownBlock => (false, "Block is unknown to the node or garbage collected"),
+    NotSyncedYet => (true, "The node has no fully synchronized blocks yet"),
+    InvalidRequest => (false, "Requested blocks, shard, cursor or limit are invalid"),
+    StateUnavailable => (false, "The node doesn't keep the state of the shard at the blocks; query an archival node"),
+    InternalError => (true, "The node reached its limits; retry later"),
+});
+
+impl From<RpcTrieDiffError> for crate::errors::RpcError {
+    fn from(error: RpcTrieDiffError) -> Self {
+        let error_data = match serde_json::to_value(error) {
+            Ok(value) => value,
+            Err(err) => {
+                return Self::new_internal_error(
+                    None,
+                    format!("Failed to serialize RpcTrieDiffError: {:?}", err),
+                )
+            }
+        };
+        Self::new_internal_or_handler_error(Some(error_data.clone()), error_data)
+    }
+}
@@ -25,6 +25,18 @@ pub struct RpcChunkResponse {
     pub chunk_view: near_primitives::views::ChunkView,
 }
 
+#[derive(Serialize, Deserialize, Debug, arbitrary::Arbitrary)]
+pub struct RpcOutgoingReceiptProofsRequest {
+    #[serde(flatten)]
+    pub chunk_reference: ChunkReference,
+}
+
+#[derive(Serialize, Deserialize, Debug)]
+pub struct RpcOutgoingReceiptProofsResponse {
+    #[serde(flatten)]
+    pub receipt_proofs: near_primitives::views::OutgoingReceiptProofsView,
+}
+
 #[derive(thiserror::Error, Debug, Serialize, Deserialize)]
 #[serde(tag = "name", content = "info", rename_all = "SCREAMING_SNAKE_CASE")]
 pub enum RpcChunkError {
@@ -41,6 +53,13 @@ pub enum RpcChunkError {
     UnknownChunk { chunk_hash: near_primitives::sharding::ChunkHash },
 }
 
+error_catalog!(RpcChunkError {
+    InternalError => (true, "The node reached its limits; retry later"),
+    UnknownBlock => (false, "Block is unknown to the node or garbage collected"),
+    InvalidShardId => (false, "Shard with the requested id does not exist"),
+    UnknownChunk => (false, "Chunk has never been observed on the node"),
+});
+
 impl From<RpcChunkError> for crate::errors::RpcError {
     fn from(error: RpcChunkError) -> Self {
         let error_data = match &error {
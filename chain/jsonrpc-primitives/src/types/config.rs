@@ -13,6 +13,18 @@ pub struct RpcProtocolConfigResponse {
     pub config_view: near_chain_configs::ProtocolConfigView,
 }
 
+#[derive(Serialize, Deserialize, Debug)]
+pub struct RpcRuntimeParametersDiffRequest {
+    pub from_protocol_version: near_primitives::version::ProtocolVersion,
+    pub to_protocol_version: near_primitives::version::ProtocolVersion,
+}
+
+#[derive(Serialize, Deserialize, Debug)]
+pub struct RpcRuntimeParametersDiffResponse {
+    #[serde(flatten)]
+    pub diff_view: near_primitives::views::RuntimeParametersDiffView,
+}
+
 #[derive(thiserror::Error, Debug, Serialize, Deserialize)]
 #[serde(tag = "name", content = "info", rename_all = "SCREAMING_SNAKE_CASE")]
 pub enum RpcProtocolConfigError {
@@ -25,6 +37,11 @@ pub enum RpcProtocolConfigError {
     InternalError { error_message: String },
 }
 
+error_catalog!(RpcProtocolConfigError {
+    UnknownBlock => (false, "Block is unknown to the node or garbage collected"),
+    InternalError => (true, "The node reached its limits; retry later"),
+});
+
 impl From<RpcProtocolConfigError> for crate::errors::RpcError {
     fn from(error: RpcProtocolConfigError) -> Self {
         let error_data = match &error {
@@ -47,3 +64,40 @@ impl From<RpcProtocolConfigError> for crate::errors::RpcError {
         Self::new_internal_or_handler_error(error_data, error_data_value)
     }
 }
+
+#[derive(thiserror::Error, Debug, Serialize, Deserialize)]
+#[serde(tag = "name", content = "info", rename_all = "SCREAMING_SNAKE_CASE")]
+pub enum RpcRuntimeParametersDiffError {
+    #[error(
+        "Protocol version {requested} is newer than the latest version {latest} known to the node"
+    )]
+    UnknownProtocolVersion {
+        requested: near_primitives::version::ProtocolVersion,
+        latest: near_primitives::version::ProtocolVersion,
+    },
+    #[error("The node reached its limits. Try again later. More details: {error_message}")]
+    InternalError { error_message: String },
+}
+
+error_catalog!(RpcRuntimeParametersDiffError {
+    UnknownProtocolVersion => (false, "Protocol version is newer than the latest known one"),
+    InternalError => (true, "The node reached its limits; retry later"),
+});
+
+impl From<RpcRuntimeParametersDiffError> for crate::errors::RpcError {
+    fn from(error: RpcRuntimeParametersDiffError) -> Self {
+        let error_data = Some(Value::String(error.to_string()));
+
+        let error_data_value = match serde_json::to_value(error) {
+            Ok(value) => value,
+            Err(err) => {
+                return Self::new_internal_error(
+                    None,
+                    format!("Failed to serialize RpcRuntimeParametersDiffError: {:?}", err),
+                )
+            }
+        };
+
+        Self::new_internal_or_handler_error(error_data, error_data_value)
+    }
+}
@@ -0,0 +1,25 @@
+use near_primitives::hash::CryptoHash;
+use near_primitives::types::AccountId;
+use serde::{Deserialize, Serialize};
+
+fn default_contract_accounts_limit() -> usize {
+    100
+}
+
+#[derive(Serialize, Deserialize, Debug)]
+pub struct RpcContractAccountsRequest {
+    /// Hash of the contract code, as in the `code_hash` of an account.
+    pub code_hash: CryptoHash,
+    /// `next_cursor` of the previous response, to continue the query.
+    #[serde(default)]
+    pub cursor: Option<AccountId>,
+    /// Maximum number of accounts to return.  The node caps it at 1000.
+    #[serde(default = "default_contract_accounts_limit")]
+    pub limit: usize,
+}
+
+#[derive(Serialize, Deserialize, Debug)]
+pub struct RpcContractAccountsResponse {
+    #[serde(flatten)]
+    pub accounts_view: near_primitives::views::ContractAccountsView,
+}
@@ -0,0 +1,29 @@
+use near_primitives::types::AccountId;
+use serde::{Deserialize, Serialize};
+
+fn default_contract_events_limit() -> usize {
+    100
+}
+
+#[derive(Serialize, Deserialize, Debug)]
+pub struct RpcContractEventsRequest {
+    pub account_id: AccountId,
+    /// Only events of this standard, for example `nep171`.
+    #[serde(default)]
+    pub standard: Option<String>,
+    /// Only events with this name.  Requires `standard` to be set.
+    #[serde(default)]
+    pub event: Option<String>,
+    /// `next_cursor` of the previous response, to continue the query.
+    #[serde(default)]
+    pub cursor: Option<String>,
+    /// Maximum number of events to return.  The node caps it at 1000.
+    #[serde(default = "default_contract_events_limit")]
+    pub limit: usize,
+}
+
+#[derive(Serialize, Deserialize, Debug)]
+pub struct RpcContractEventsResponse {
+    #[serde(flatten)]
+    pub events_view: near_primitives::views::ContractEventsView,
+}
@@ -0,0 +1,39 @@
+//! Error of the queries of the optional contract indices, i.e. of
+//! `EXPERIMENTAL_contract_events` and `EXPERIMENTAL_contract_accounts`.
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+
+#[derive(thiserror::Error, Debug, Serialize, Deserialize)]
+#[serde(tag = "name", content = "info", rename_all = "SCREAMING_SNAKE_CASE")]
+pub enum RpcContractIndexError {
+    #[error("The queried index is not enabled on this node")]
+    IndexDisabled,
+    #[error("Invalid request: {error_message}")]
+    InvalidRequest { error_message: String },
+    #[error("Internal error: {error_message}")]
+    InternalError { error_message: String },
+}
+
+error_catalog!(RpcContractIndexError {
+    IndexDisabled => (false, "The node doesn't maintain the queried index; query another node"),
+    InvalidRequest => (false, "Requested filter, cursor or limit is invalid"),
+    InternalError => (true, "The node reached its limits; retry later"),
+});
+
+impl From<RpcContractIndexError> for crate::errors::RpcError {
+    fn from(error: RpcContractIndexError) -> Self {
+        let error_data = Some(Value::String(error.to_string()));
+
+        let error_data_value = match serde_json::to_value(error) {
+            Ok(value) => value,
+            Err(err) => {
+                return Self::new_internal_error(
+                    None,
+                    format!("Failed to serialize RpcContractIndexError: {:?}", err),
+                )
+            }
+        };
+
+        Self::new_internal_or_handler_error(error_data, error_data_value)
+    }
+}
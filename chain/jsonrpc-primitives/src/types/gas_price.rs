@@ -1,4 +1,4 @@
-use near_primitives::types::MaybeBlockId;
+use near_primitives::types::{BlockHeight, MaybeBlockId, NumBlocks};
 use serde::{Deserialize, Serialize};
 use serde_json::Value;
 
@@ -25,6 +25,11 @@ pub enum RpcGasPriceError {
     },
 }
 
+error_catalog!(RpcGasPriceError {
+    InternalError => (true, "The node reached its limits; retry later"),
+    UnknownBlock => (false, "Block is unknown to the node or garbage collected"),
+});
+
 impl From<RpcGasPriceError> for crate::errors::RpcError {
     fn from(error: RpcGasPriceError) -> Self {
         let error_data = match &error {
@@ -48,3 +53,63 @@ impl From<RpcGasPriceError> for crate::errors::RpcError {
         Self::new_internal_or_handler_error(error_data, error_data_value)
     }
 }
+
+fn default_economics_series_step() -> NumBlocks {
+    1
+}
+
+fn default_economics_series_limit() -> usize {
+    100
+}
+
+#[derive(Serialize, Deserialize, Debug)]
+pub struct RpcEconomicsSeriesRequest {
+    pub from_height: BlockHeight,
+    /// Inclusive, defaults to the head of the chain.
+    #[serde(default)]
+    pub to_height: Option<BlockHeight>,
+    /// Only every `step`th height is sampled.
+    #[serde(default = "default_economics_series_step")]
+    pub step: NumBlocks,
+    /// Maximum number of points to return.  The node caps it at 1000.
+    #[serde(default = "default_economics_series_limit")]
+    pub limit: usize,
+}
+
+#[derive(Serialize, Deserialize, Debug)]
+pub struct RpcEconomicsSeriesResponse {
+    #[serde(flatten)]
+    pub series_view: near_primitives::views::EconomicsSeriesView,
+}
+
+#[derive(thiserror::Error, Debug, Serialize, Deserialize)]
+#[serde(tag = "name", content = "info", rename_all = "SCREAMING_SNAKE_CASE")]
+pub enum RpcEconomicsSeriesError {
+    #[error("Invalid range: {error_message}")]
+    InvalidRange { error_message: String },
+    #[error("Internal error: {error_message}")]
+    InternalError { error_message: String },
+}
+
+error_catalog!(RpcEconomicsSeriesError {
+    InvalidRange => (false, "Requested range of heights, step or limit is invalid"),
+    InternalError => (true, "The node reached its limits; retry later"),
+});
+
+impl From<RpcEconomicsSeriesError> for crate::errors::RpcError {
+    fn from(error: RpcEconomicsSeriesError) -> Self {
+        let error_data = Some(Value::String(error.to_string()));
+
+        let error_data_value = match serde_json::to_value(error) {
+            Ok(value) => value,
+            Err(err) => {
+                return Self::new_internal_error(
+                    None,
+                    format!("Failed to serialize RpcEconomicsSeriesError: {:?}", err),
+                )
+            }
+        };
+
+        Self::new_internal_or_handler_error(error_data, error_data_value)
+    }
+}
@@ -54,6 +54,15 @@ pub enum RpcLightClientProofError {
     InternalError { error_message: String },
 }
 
+error_catalog!(RpcLightClientProofError {
+    UnknownBlock => (false, "Block is unknown to the node or garbage collected"),
+    InconsistentState => (true, "Outcome is in a shard unknown to the node"),
+    NotConfirmed => (true, "Transaction or receipt has not been confirmed yet"),
+    UnknownTransactionOrReceipt => (false, "Transaction or receipt does not exist"),
+    UnavailableShard => (false, "The node does not track the shard of the outcome"),
+    InternalError => (true, "The node reached its limits; retry later"),
+});
+
 #[derive(thiserror::Error, Debug, Serialize, Deserialize)]
 #[serde(tag = "name", content = "info", rename_all = "SCREAMING_SNAKE_CASE")]
 pub enum RpcLightClientNextBlockError {
@@ -68,6 +77,12 @@ pub enum RpcLightClientNextBlockError {
     EpochOutOfBounds { epoch_id: near_primitives::types::EpochId },
 }
 
+error_catalog!(RpcLightClientNextBlockError {
+    InternalError => (true, "The node reached its limits; retry later"),
+    UnknownBlock => (false, "Block is unknown to the node or garbage collected"),
+    EpochOutOfBounds => (false, "Epoch of the block is out of bounds of known epochs"),
+});
+
 impl From<RpcLightClientProofError> for crate::errors::RpcError {
     fn from(error: RpcLightClientProofError) -> Self {
         let error_data = match &error {
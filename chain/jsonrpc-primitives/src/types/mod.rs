@@ -1,13 +1,19 @@
+pub mod auth;
 pub mod blocks;
 pub mod changes;
 pub mod chunks;
 pub mod config;
+pub mod contract_accounts;
+pub mod contract_events;
+pub mod contract_index;
 pub mod gas_price;
 pub mod light_client;
 pub mod network_info;
 pub mod query;
+pub mod rate_limit;
 pub mod receipts;
 pub mod sandbox;
 pub mod status;
 pub mod transactions;
+pub mod tx_pool;
 pub mod validator;
@@ -35,6 +35,10 @@ pub enum RpcNetworkInfoError {
     InternalError { error_message: String },
 }
 
+error_catalog!(RpcNetworkInfoError {
+    InternalError => (true, "The node reached its limits; retry later"),
+});
+
 impl From<RpcNetworkInfoError> for crate::errors::RpcError {
     fn from(error: RpcNetworkInfoError) -> Self {
         let error_data = match serde_json::to_value(error) {
@@ -62,10 +62,31 @@ pub enum RpcQueryError {
         block_height: near_primitives::types::BlockHeight,
         block_hash: near_primitives::hash::CryptoHash,
     },
+    #[error("Receipt {receipt_id} has not been observed being sent by a chunk")]
+    UnknownReceipt {
+        receipt_id: near_primitives::hash::CryptoHash,
+        block_height: near_primitives::types::BlockHeight,
+        block_hash: near_primitives::hash::CryptoHash,
+    },
     #[error("The node reached its limits. Try again later. More details: {error_message}")]
     InternalError { error_message: String },
 }
 
+error_catalog!(RpcQueryError {
+    NoSyncedBlocks => (true, "The node has no fully synchronized blocks yet"),
+    UnavailableShard => (false, "The node does not track the shard of the account"),
+    GarbageCollectedBlock => (false, "Block is garbage collected; query an archival node"),
+    UnknownBlock => (false, "Block is unknown to the node or garbage collected"),
+    InvalidAccount => (false, "Account id is invalid"),
+    UnknownAccount => (false, "Account does not exist at the block"),
+    NoContractCode => (false, "Account has no contract deployed at the block"),
+    TooLargeContractState => (false, "Contract state is too large to be viewed"),
+    UnknownAccessKey => (false, "Access key does not exist at the block"),
+    ContractExecutionError => (false, "View function call returned an error"),
+    UnknownReceipt => (false, "Receipt was not sent by a chunk up to the block"),
+    InternalError => (true, "The node reached its limits; retry later"),
+});
+
 #[derive(Serialize, Deserialize, Debug)]
 pub struct RpcQueryResponse {
     #[serde(flatten)]
@@ -83,6 +104,7 @@ pub enum QueryResponseKind {
     CallResult(near_primitives::views::CallResult),
     AccessKey(near_primitives::views::AccessKeyView),
     AccessKeyList(near_primitives::views::AccessKeyList),
+    ReceiptProof(near_primitives::views::ReceiptInclusionProofView),
 }
 
 impl From<RpcQueryError> for crate::errors::RpcError {
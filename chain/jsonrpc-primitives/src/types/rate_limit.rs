@@ -0,0 +1,44 @@
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+
+/// Returned instead of the response of any method when the node serves RPC
+/// with rate limits and the request exceeds them.
+#[derive(thiserror::Error, Debug, Serialize, Deserialize)]
+#[serde(tag = "name", content = "info", rename_all = "SCREAMING_SNAKE_CASE")]
+pub enum RpcRateLimitError {
+    #[error(
+        "Rate limit of {client_class} clients exceeded by {method_name} costing {method_cost}, \
+         retry in {retry_after_ms} ms"
+    )]
+    RateLimitExceeded {
+        client_class: String,
+        method_name: String,
+        method_cost: u32,
+        retry_after_ms: u64,
+    },
+    #[error("Too many requests of heavy methods like {method_name} are processed, retry later")]
+    TooManyConcurrentRequests { method_name: String },
+}
+
+error_catalog!(RpcRateLimitError {
+    RateLimitExceeded => (true, "The client exceeded its rate limit; retry after the given delay"),
+    TooManyConcurrentRequests => (true, "The node processes too many heavy requests; retry later"),
+});
+
+impl From<RpcRateLimitError> for crate::errors::RpcError {
+    fn from(error: RpcRateLimitError) -> Self {
+        let error_data = Some(Value::String(error.to_string()));
+
+        let error_data_value = match serde_json::to_value(error) {
+            Ok(value) => value,
+            Err(err) => {
+                return Self::new_internal_error(
+                    None,
+                    format!("Failed to serialize RpcRateLimitError: {:?}", err),
+                )
+            }
+        };
+
+        Self::new_internal_or_handler_error(error_data, error_data_value)
+    }
+}
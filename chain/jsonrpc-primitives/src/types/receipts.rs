@@ -26,6 +26,11 @@ pub enum RpcReceiptError {
     UnknownReceipt { receipt_id: near_primitives::hash::CryptoHash },
 }
 
+error_catalog!(RpcReceiptError {
+    InternalError => (true, "The node reached its limits; retry later"),
+    UnknownReceipt => (false, "Receipt has never been observed on the node"),
+});
+
 impl From<RpcReceiptError> for crate::errors::RpcError {
     fn from(error: RpcReceiptError) -> Self {
         let error_data = match serde_json::to_value(error) {
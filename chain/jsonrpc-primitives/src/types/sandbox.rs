@@ -17,6 +17,10 @@ pub enum RpcSandboxPatchStateError {
     InternalError { error_message: String },
 }
 
+error_catalog!(RpcSandboxPatchStateError {
+    InternalError => (true, "The node reached its limits; retry later"),
+});
+
 impl From<RpcSandboxPatchStateError> for crate::errors::RpcError {
     fn from(error: RpcSandboxPatchStateError) -> Self {
         let error_data = match serde_json::to_value(error) {
@@ -47,6 +51,10 @@ pub enum RpcSandboxFastForwardError {
     InternalError { error_message: String },
 }
 
+error_catalog!(RpcSandboxFastForwardError {
+    InternalError => (true, "The node reached its limits; retry later"),
+});
+
 impl From<RpcSandboxFastForwardError> for crate::errors::RpcError {
     fn from(error: RpcSandboxFastForwardError) -> Self {
         let error_data = match serde_json::to_value(error) {
@@ -1,8 +1,10 @@
 use near_client_primitives::debug::{
-    DebugBlockStatusData, EpochInfoView, TrackedShardsView, ValidatorStatus,
+    DebugBlockStatusData, EpochInfoView, FinalitySlaView, TrackedShardsView, TxPoolView,
+    ValidatorStatus,
 };
 use near_primitives::views::{
-    CatchupStatusView, ChainProcessingInfo, PeerStoreView, SyncStatusView,
+    CatchupStatusView, ChainProcessingInfo, ChunkEndorsementStatusView, DebugDataView,
+    HeaderSyncStatsView, PeerAuditLogView, PeerStoreView, StoreWritesView, SyncStatusView,
 };
 use serde::{Deserialize, Serialize};
 
@@ -24,7 +26,14 @@ pub enum DebugStatusResponse {
     // Detailed information about the validator (approvals, block & chunk production etc.)
     ValidatorStatus(ValidatorStatus),
     PeerStore(PeerStoreView),
+    PeerAuditLog(PeerAuditLogView),
     ChainProcessingStatus(ChainProcessingInfo),
+    FinalitySla(FinalitySlaView),
+    TxPool(TxPoolView),
+    ChunkEndorsementStatus(ChunkEndorsementStatusView),
+    HeaderSyncStats(HeaderSyncStatsView),
+    StoreWrites(StoreWritesView),
+    DebugData(DebugDataView),
 }
 
 #[cfg(feature = "debug_types")]
@@ -36,6 +45,12 @@ pub struct RpcDebugStatusResponse {
 #[derive(Debug, Serialize, Deserialize)]
 pub struct RpcHealthResponse;
 
+#[derive(Debug, Serialize, Deserialize)]
+pub struct RpcDrainStatusResponse {
+    #[serde(flatten)]
+    pub drain_status: near_primitives::views::DrainStatusView,
+}
+
 #[derive(thiserror::Error, Debug, Serialize, Deserialize)]
 #[serde(tag = "name", content = "info", rename_all = "SCREAMING_SNAKE_CASE")]
 pub enum RpcStatusError {
@@ -49,6 +64,13 @@ pub enum RpcStatusError {
     InternalError { error_message: String },
 }
 
+error_catalog!(RpcStatusError {
+    NodeIsSyncing => (true, "The node is syncing"),
+    NoNewBlocks => (true, "The node has not received new blocks for a while"),
+    EpochOutOfBounds => (false, "Epoch is out of bounds of known epochs"),
+    InternalError => (true, "The node reached its limits; retry later"),
+});
+
 impl From<RpcStatusError> for crate::errors::RpcError {
     fn from(error: RpcStatusError) -> Self {
         let error_data = match serde_json::to_value(error) {
@@ -9,6 +9,15 @@ pub struct RpcBroadcastTransactionRequest {
 #[derive(Debug)]
 pub struct RpcTransactionStatusCommonRequest {
     pub transaction_info: TransactionInfo,
+    /// If set, the outcome is returned only once all of its parts reached
+    /// this finality.  Until then the request keeps waiting and eventually
+    /// times out.
+    pub min_finality: Option<near_primitives::types::Finality>,
+    /// Whether `EXPERIMENTAL_tx_status` includes, for every outcome, the proof
+    /// of its outcome root to the block including it along with the header of
+    /// that block, so that the outcome can be verified independently.  Only
+    /// accepted as a named parameter.
+    pub outcome_proofs: bool,
 }
 
 #[derive(Clone, Debug)]
@@ -30,6 +39,17 @@ pub enum RpcTransactionError {
     },
     #[error("Node doesn't track this shard. Cannot determine whether the transaction is valid")]
     DoesNotTrackShard,
+    #[error("Account {signer_id} already has {limit} transactions in the pool of the node")]
+    SignerQuotaExceeded { signer_id: near_primitives::types::AccountId, limit: usize },
+    #[error("Relayer {relayer_id} already has {limit} transactions in the pool of the node")]
+    RelayerQuotaExceeded { relayer_id: near_primitives::types::AccountId, limit: usize },
+    #[error("Delegate action expired at height {max_block_height}, next block is {next_height}")]
+    DelegateActionExpired {
+        max_block_height: near_primitives::types::BlockHeight,
+        next_height: near_primitives::types::BlockHeight,
+    },
+    #[error("The node is draining before shutdown and doesn't accept transactions")]
+    NodeDraining,
     #[error("Transaction with hash {transaction_hash} was routed")]
     RequestRouted { transaction_hash: near_primitives::hash::CryptoHash },
     #[error("Transaction {requested_transaction_hash} doesn't exist")]
@@ -40,12 +60,31 @@ pub enum RpcTransactionError {
     TimeoutError,
 }
 
+error_catalog!(RpcTransactionError {
+    InvalidTransaction => (false, "Transaction is invalid"),
+    DoesNotTrackShard => (false, "The node does not track the shard of the transaction"),
+    SignerQuotaExceeded => (true, "The signer has too many transactions in the pool; retry later"),
+    RelayerQuotaExceeded => (true, "Relayer has too many transactions in the pool; retry later"),
+    DelegateActionExpired => (false, "The relayed delegate action expired; sign a new one"),
+    NodeDraining => (true, "The node is about to stop; send the transaction to another node"),
+    RequestRouted => (true, "Transaction was routed to another node"),
+    UnknownTransaction => (true, "Transaction has not been observed on the node yet"),
+    InternalError => (true, "The node reached its limits; retry later"),
+    TimeoutError => (true, "Transaction was not executed in time; query it later"),
+});
+
 #[derive(Serialize, Deserialize, Debug)]
 pub struct RpcTransactionResponse {
     #[serde(flatten)]
     pub final_execution_outcome: near_primitives::views::FinalExecutionOutcomeViewEnum,
 }
 
+#[derive(Serialize, Deserialize, Debug)]
+pub struct RpcTransactionForkStatusResponse {
+    #[serde(flatten)]
+    pub tx_fork_status: near_primitives::views::TxForkStatusView,
+}
+
 #[derive(Serialize, Deserialize, Debug)]
 pub struct RpcBroadcastTxSyncResponse {
     pub transaction_hash: near_primitives::hash::CryptoHash,
@@ -0,0 +1,51 @@
+use near_primitives::hash::CryptoHash;
+use near_primitives::types::AccountId;
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+
+#[derive(Serialize, Deserialize, Debug)]
+#[serde(untagged)]
+pub enum RpcTxPoolDropRequest {
+    Transactions { tx_hashes: Vec<CryptoHash> },
+    Signer { signer_id: AccountId },
+}
+
+#[derive(Serialize, Deserialize, Debug)]
+pub struct RpcTxPoolPinRequest {
+    pub tx_hash: CryptoHash,
+}
+
+#[derive(Serialize, Deserialize, Debug)]
+pub struct RpcTxPoolResponse {
+    /// Hashes of the transactions affected by the request.
+    pub tx_hashes: Vec<CryptoHash>,
+}
+
+#[derive(thiserror::Error, Debug, Serialize, Deserialize)]
+#[serde(tag = "name", content = "info", rename_all = "SCREAMING_SNAKE_CASE")]
+pub enum RpcTxPoolError {
+    #[error("The node reached its limits. Try again later. More details: {error_message}")]
+    InternalError { error_message: String },
+}
+
+error_catalog!(RpcTxPoolError {
+    InternalError => (true, "The node reached its limits; retry later"),
+});
+
+impl From<RpcTxPoolError> for crate::errors::RpcError {
+    fn from(error: RpcTxPoolError) -> Self {
+        let error_data = Some(Value::String(error.to_string()));
+
+        let error_data_value = match serde_json::to_value(error) {
+            Ok(value) => value,
+            Err(err) => {
+                return Self::new_internal_error(
+                    None,
+                    format!("Failed to serialize RpcTxPoolError: {:?}", err),
+                )
+            }
+        };
+
+        Self::new_internal_or_handler_error(error_data, error_data_value)
+    }
+}
@@ -15,6 +15,12 @@ pub enum RpcValidatorError {
     InternalError { error_message: String },
 }
 
+error_catalog!(RpcValidatorError {
+    UnknownEpoch => (false, "Epoch is not known to the node"),
+    ValidatorInfoUnavailable => (true, "Validator information is not available yet"),
+    InternalError => (true, "The node reached its limits; retry later"),
+});
+
 #[derive(Serialize, Deserialize, Debug, arbitrary::Arbitrary)]
 pub struct RpcValidatorRequest {
     #[serde(flatten)]
@@ -32,6 +38,28 @@ pub struct RpcValidatorResponse {
     pub validator_info: near_primitives::views::EpochValidatorInfo,
 }
 
+#[derive(Serialize, Deserialize, Debug)]
+pub struct RpcBlockProducerProofRequest {
+    pub block_height: near_primitives::types::BlockHeight,
+}
+
+#[derive(Serialize, Deserialize, Debug)]
+pub struct RpcBlockProducerProofResponse {
+    #[serde(flatten)]
+    pub proof: near_primitives::views::BlockProducerProofView,
+}
+
+#[derive(Serialize, Deserialize, Debug)]
+pub struct RpcProductionScheduleRequest {
+    pub num_heights: u64,
+}
+
+#[derive(Serialize, Deserialize, Debug)]
+pub struct RpcProductionScheduleResponse {
+    #[serde(flatten)]
+    pub schedule: near_primitives::views::ProductionScheduleView,
+}
+
 impl From<RpcValidatorError> for crate::errors::RpcError {
     fn from(error: RpcValidatorError) -> Self {
         let error_data = match &error {
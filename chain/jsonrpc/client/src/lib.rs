@@ -5,6 +5,7 @@ use futures::{future, future::LocalBoxFuture, FutureExt, TryFutureExt};
 use serde::Deserialize;
 use serde::Serialize;
 
+use near_jsonrpc_primitives::error_catalog::RpcErrorCatalogResponse;
 use near_jsonrpc_primitives::errors::RpcError;
 use near_jsonrpc_primitives::message::{from_slice, Message};
 use near_jsonrpc_primitives::types::changes::{
@@ -15,8 +16,8 @@ use near_primitives::hash::CryptoHash;
 use near_primitives::types::{AccountId, BlockId, BlockReference, MaybeBlockId, ShardId};
 use near_primitives::views::validator_stake_view::ValidatorStakeView;
 use near_primitives::views::{
-    BlockView, ChunkView, EpochValidatorInfo, FinalExecutionOutcomeView, GasPriceView,
-    StatusResponse,
+    BlockView, ChunkView, DrainStatusView, EpochValidatorInfo, FinalExecutionOutcomeView,
+    GasPriceView, StatusResponse,
 };
 
 #[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
@@ -189,11 +190,17 @@ jsonrpc_client!(pub struct JsonRpcClient {
     #[allow(non_snake_case)]
     pub fn EXPERIMENTAL_check_tx(&self, tx: String) -> RpcRequest<serde_json::Value>;
     #[allow(non_snake_case)]
+    pub fn EXPERIMENTAL_error_catalog(&self) -> RpcRequest<RpcErrorCatalogResponse>;
+    #[allow(non_snake_case)]
     pub fn EXPERIMENTAL_genesis_config(&self) -> RpcRequest<serde_json::Value>;
     #[allow(non_snake_case)]
     pub fn EXPERIMENTAL_broadcast_tx_sync(&self, tx: String) -> RpcRequest<serde_json::Value>;
     #[allow(non_snake_case)]
+    pub fn EXPERIMENTAL_broadcast_tx_deferred(&self, tx: String) -> RpcRequest<serde_json::Value>;
+    #[allow(non_snake_case)]
     pub fn EXPERIMENTAL_tx_status(&self, tx: String) -> RpcRequest<serde_json::Value>;
+    #[allow(non_snake_case)]
+    pub fn EXPERIMENTAL_begin_drain(&self) -> RpcRequest<DrainStatusView>;
     pub fn health(&self) -> RpcRequest<()>;
     pub fn tx(&self, hash: String, account_id: AccountId) -> RpcRequest<FinalExecutionOutcomeView>;
     pub fn chunk(&self, id: ChunkId) -> RpcRequest<ChunkView>;
@@ -243,6 +250,43 @@ impl JsonRpcClient {
         call_method(&self.client, &self.server_addr, "EXPERIMENTAL_validators_ordered", request)
     }
 
+    #[allow(non_snake_case)]
+    pub fn EXPERIMENTAL_block_producer_proof(
+        &self,
+        request: near_jsonrpc_primitives::types::validator::RpcBlockProducerProofRequest,
+    ) -> RpcRequest<near_jsonrpc_primitives::types::validator::RpcBlockProducerProofResponse> {
+        call_method(&self.client, &self.server_addr, "EXPERIMENTAL_block_producer_proof", request)
+    }
+
+    #[allow(non_snake_case)]
+    pub fn EXPERIMENTAL_production_schedule(
+        &self,
+        request: near_jsonrpc_primitives::types::validator::RpcProductionScheduleRequest,
+    ) -> RpcRequest<near_jsonrpc_primitives::types::validator::RpcProductionScheduleResponse> {
+        call_method(&self.client, &self.server_addr, "EXPERIMENTAL_production_schedule", request)
+    }
+
+    #[allow(non_snake_case)]
+    pub fn EXPERIMENTAL_outgoing_receipt_proofs(
+        &self,
+        request: near_jsonrpc_primitives::types::chunks::RpcOutgoingReceiptProofsRequest,
+    ) -> RpcRequest<near_jsonrpc_primitives::types::chunks::RpcOutgoingReceiptProofsResponse> {
+        call_method(
+            &self.client,
+            &self.server_addr,
+            "EXPERIMENTAL_outgoing_receipt_proofs",
+            request,
+        )
+    }
+
+    #[allow(non_snake_case)]
+    pub fn EXPERIMENTAL_randomness(
+        &self,
+        request: near_jsonrpc_primitives::types::blocks::RpcRandomnessRequest,
+    ) -> RpcRequest<near_jsonrpc_primitives::types::blocks::RpcRandomnessResponse> {
+        call_method(&self.client, &self.server_addr, "EXPERIMENTAL_randomness", request)
+    }
+
     #[allow(non_snake_case)]
     pub fn EXPERIMENTAL_receipt(
         &self,
@@ -258,6 +302,77 @@ impl JsonRpcClient {
     ) -> RpcRequest<near_jsonrpc_primitives::types::config::RpcProtocolConfigResponse> {
         call_method(&self.client, &self.server_addr, "EXPERIMENTAL_protocol_config", request)
     }
+
+    #[allow(non_snake_case)]
+    pub fn EXPERIMENTAL_contract_accounts(
+        &self,
+        request: near_jsonrpc_primitives::types::contract_accounts::RpcContractAccountsRequest,
+    ) -> RpcRequest<near_jsonrpc_primitives::types::contract_accounts::RpcContractAccountsResponse>
+    {
+        call_method(&self.client, &self.server_addr, "EXPERIMENTAL_contract_accounts", request)
+    }
+
+    #[allow(non_snake_case)]
+    pub fn EXPERIMENTAL_contract_events(
+        &self,
+        request: near_jsonrpc_primitives::types::contract_events::RpcContractEventsRequest,
+    ) -> RpcRequest<near_jsonrpc_primitives::types::contract_events::RpcContractEventsResponse>
+    {
+        call_method(&self.client, &self.server_addr, "EXPERIMENTAL_contract_events", request)
+    }
+
+    #[allow(non_snake_case)]
+    pub fn EXPERIMENTAL_economics_series(
+        &self,
+        request: near_jsonrpc_primitives::types::gas_price::RpcEconomicsSeriesRequest,
+    ) -> RpcRequest<near_jsonrpc_primitives::types::gas_price::RpcEconomicsSeriesResponse> {
+        call_method(&self.client, &self.server_addr, "EXPERIMENTAL_economics_series", request)
+    }
+
+    #[allow(non_snake_case)]
+    pub fn EXPERIMENTAL_runtime_parameters_diff(
+        &self,
+        request: near_jsonrpc_primitives::types::config::RpcRuntimeParametersDiffRequest,
+    ) -> RpcRequest<near_jsonrpc_primitives::types::config::RpcRuntimeParametersDiffResponse> {
+        call_method(
+            &self.client,
+            &self.server_addr,
+            "EXPERIMENTAL_runtime_parameters_diff",
+            request,
+        )
+    }
+
+    #[allow(non_snake_case)]
+    pub fn EXPERIMENTAL_trie_diff(
+        &self,
+        request: near_jsonrpc_primitives::types::changes::RpcTrieDiffRequest,
+    ) -> RpcRequest<near_jsonrpc_primitives::types::changes::RpcTrieDiffResponse> {
+        call_method(&self.client, &self.server_addr, "EXPERIMENTAL_trie_diff", request)
+    }
+
+    #[allow(non_snake_case)]
+    pub fn EXPERIMENTAL_tx_pool_drop(
+        &self,
+        request: near_jsonrpc_primitives::types::tx_pool::RpcTxPoolDropRequest,
+    ) -> RpcRequest<near_jsonrpc_primitives::types::tx_pool::RpcTxPoolResponse> {
+        call_method(&self.client, &self.server_addr, "EXPERIMENTAL_tx_pool_drop", request)
+    }
+
+    #[allow(non_snake_case)]
+    pub fn EXPERIMENTAL_tx_pool_pin(
+        &self,
+        request: near_jsonrpc_primitives::types::tx_pool::RpcTxPoolPinRequest,
+    ) -> RpcRequest<near_jsonrpc_primitives::types::tx_pool::RpcTxPoolResponse> {
+        call_method(&self.client, &self.server_addr, "EXPERIMENTAL_tx_pool_pin", request)
+    }
+
+    #[allow(non_snake_case)]
+    pub fn EXPERIMENTAL_tx_pool_unpin(
+        &self,
+        request: near_jsonrpc_primitives::types::tx_pool::RpcTxPoolPinRequest,
+    ) -> RpcRequest<near_jsonrpc_primitives::types::tx_pool::RpcTxPoolResponse> {
+        call_method(&self.client, &self.server_addr, "EXPERIMENTAL_tx_pool_unpin", request)
+    }
 }
 
 fn create_client() -> Client {
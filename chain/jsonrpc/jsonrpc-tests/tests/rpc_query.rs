@@ -8,14 +8,22 @@ use serde_json::json;
 use near_actix_test_utils::run_actix;
 use near_crypto::{KeyType, PublicKey, Signature};
 use near_jsonrpc::client::{new_client, ChunkId};
+use near_jsonrpc_primitives::types::blocks::RpcRandomnessRequest;
+use near_jsonrpc_primitives::types::changes::RpcTrieDiffRequest;
+use near_jsonrpc_primitives::types::chunks::{ChunkReference, RpcOutgoingReceiptProofsRequest};
+use near_jsonrpc_primitives::types::contract_accounts::RpcContractAccountsRequest;
+use near_jsonrpc_primitives::types::contract_events::RpcContractEventsRequest;
+use near_jsonrpc_primitives::types::gas_price::RpcEconomicsSeriesRequest;
 use near_jsonrpc_primitives::types::query::QueryResponseKind;
-use near_jsonrpc_primitives::types::validator::RpcValidatorsOrderedRequest;
+use near_jsonrpc_primitives::types::validator::{
+    RpcBlockProducerProofRequest, RpcProductionScheduleRequest, RpcValidatorsOrderedRequest,
+};
 use near_network::test_utils::wait_or_timeout;
 use near_o11y::testonly::init_test_logger;
 use near_primitives::account::{AccessKey, AccessKeyPermission};
 use near_primitives::hash::CryptoHash;
 use near_primitives::types::{BlockId, BlockReference, EpochId, SyncCheckpoint};
-use near_primitives::views::QueryRequest;
+use near_primitives::views::{QueryRequest, TrieDiffSourceView};
 
 use near_jsonrpc_tests::{self as test_utils, test_with_client};
 
@@ -112,6 +120,37 @@ fn test_chunk_by_hash() {
     });
 }
 
+/// Retrieve the outgoing receipt proofs of a chunk via json rpc
+#[test]
+fn test_outgoing_receipt_proofs() {
+    test_with_client!(test_utils::NodeType::NonValidator, client, async move {
+        let chunk = client.chunk(ChunkId::BlockShardId(BlockId::Height(0), 0u64)).await.unwrap();
+        let response = client
+            .EXPERIMENTAL_outgoing_receipt_proofs(RpcOutgoingReceiptProofsRequest {
+                chunk_reference: ChunkReference::ChunkHash { chunk_id: chunk.header.chunk_hash },
+            })
+            .await
+            .unwrap();
+        let receipt_proofs = response.receipt_proofs;
+        assert_eq!(receipt_proofs.chunk_hash, chunk.header.chunk_hash);
+        assert_eq!(receipt_proofs.shard_id, 0);
+        assert_eq!(receipt_proofs.outgoing_receipts_root, chunk.header.outgoing_receipts_root);
+        // Genesis chunks send no receipts.
+        assert!(receipt_proofs.proofs.is_empty());
+
+        let response = client
+            .EXPERIMENTAL_outgoing_receipt_proofs(RpcOutgoingReceiptProofsRequest {
+                chunk_reference: ChunkReference::BlockShardId {
+                    block_id: BlockId::Height(0),
+                    shard_id: 100,
+                },
+            })
+            .await;
+        let s = serde_json::to_string(&response.unwrap_err().data.unwrap()).unwrap();
+        assert!(s.starts_with("\"Shard id 100 does not exist"));
+    });
+}
+
 /// Retrieve chunk via json rpc
 #[test]
 fn test_chunk_invalid_shard_id() {
@@ -196,6 +235,32 @@ fn test_query_account() {
     });
 }
 
+/// Receipts which weren't sent by any chunk can't be proven.
+#[test]
+fn test_query_unknown_receipt_proof() {
+    test_with_client!(test_utils::NodeType::NonValidator, client, async move {
+        let receipt_id = CryptoHash::hash_bytes(b"receipt");
+        let query_response = client
+            .query(near_jsonrpc_primitives::types::query::RpcQueryRequest {
+                block_reference: BlockReference::latest(),
+                request: QueryRequest::ViewReceiptProof { receipt_id },
+            })
+            .await;
+        match query_response {
+            Ok(_) => panic!("should result in an error"),
+            Err(e) => {
+                let s = serde_json::to_string(&e.data.unwrap()).unwrap();
+                assert!(
+                    s.starts_with(&format!(
+                        "\"Receipt {receipt_id} has not been observed being sent by a chunk"
+                    )),
+                    "{s}"
+                );
+            }
+        }
+    });
+}
+
 /// Connect to json rpc and query account info with soft-deprecated query API.
 #[test]
 fn test_query_by_path_access_keys() {
@@ -459,6 +524,59 @@ fn test_validators_ordered() {
     });
 }
 
+/// Retrieve the block producer of the next height along with the data needed to verify it.
+#[test]
+fn test_block_producer_proof() {
+    test_with_client!(test_utils::NodeType::Validator, client, async move {
+        let response = client
+            .EXPERIMENTAL_block_producer_proof(RpcBlockProducerProofRequest { block_height: 1 })
+            .await
+            .unwrap();
+        assert_eq!(response.proof.height, 1);
+        assert!(response.proof.verify());
+        assert!(["test1", "test2"].contains(&response.proof.block_producer.as_str()));
+    });
+}
+
+/// Retrieve the random values of blocks with the data needed to verify them.
+#[test]
+fn test_randomness() {
+    test_with_client!(test_utils::NodeType::Validator, client, async move {
+        let response = client
+            .EXPERIMENTAL_randomness(RpcRandomnessRequest {
+                block_reference: BlockReference::BlockId(BlockId::Height(1)),
+                lineage_length: 2,
+            })
+            .await
+            .unwrap();
+        let randomness = response.randomness;
+        // The lineage stops at the genesis block.
+        assert_eq!(randomness.blocks.len(), 1);
+        assert_eq!(randomness.blocks[0].height, 1);
+        assert!(randomness.verify());
+    });
+}
+
+/// Retrieve the expected producers of the next heights.
+#[test]
+fn test_production_schedule() {
+    test_with_client!(test_utils::NodeType::Validator, client, async move {
+        let response = client
+            .EXPERIMENTAL_production_schedule(RpcProductionScheduleRequest { num_heights: 3 })
+            .await
+            .unwrap();
+        let heights = response.schedule.heights;
+        assert_eq!(heights.len(), 3);
+        for (entry, next) in heights.iter().zip(heights.iter().skip(1)) {
+            assert_eq!(entry.height + 1, next.height);
+        }
+        for entry in &heights {
+            assert!(["test1", "test2"].contains(&entry.block_producer.as_str()));
+            assert_eq!(entry.chunk_producers.len(), 1);
+        }
+    });
+}
+
 /// Retrieve genesis config via JSON RPC.
 /// WARNING: Be mindful about changing genesis structure as it is part of the public protocol!
 #[test]
@@ -504,6 +622,78 @@ fn test_gas_price() {
     });
 }
 
+/// Retrieve economics of a range of blocks
+#[test]
+fn test_economics_series() {
+    test_with_client!(test_utils::NodeType::NonValidator, client, async move {
+        let request =
+            RpcEconomicsSeriesRequest { from_height: 0, to_height: Some(0), step: 1, limit: 10 };
+        let series = client.EXPERIMENTAL_economics_series(request).await.unwrap().series_view;
+        assert_eq!(series.points.len(), 1);
+        assert_eq!(series.points[0].height, 0);
+        assert!(series.points[0].gas_price > 0);
+        assert!(series.points[0].total_supply > 0);
+        assert_eq!(series.points[0].minted_amount, 0);
+        assert_eq!(series.next_from_height, None);
+
+        let request =
+            RpcEconomicsSeriesRequest { from_height: 0, to_height: None, step: 0, limit: 10 };
+        assert!(client.EXPERIMENTAL_economics_series(request).await.is_err());
+    });
+}
+
+/// Contract events can't be queried from a node which doesn't index them
+#[test]
+fn test_contract_events_index_disabled() {
+    test_with_client!(test_utils::NodeType::NonValidator, client, async move {
+        let request = RpcContractEventsRequest {
+            account_id: "test".parse().unwrap(),
+            standard: Some("nep171".to_string()),
+            event: None,
+            cursor: None,
+            limit: 10,
+        };
+        assert!(client.EXPERIMENTAL_contract_events(request).await.is_err());
+    });
+}
+
+/// Contract deployments can't be queried from a node which doesn't index them
+#[test]
+fn test_contract_accounts_index_disabled() {
+    test_with_client!(test_utils::NodeType::NonValidator, client, async move {
+        let request = RpcContractAccountsRequest {
+            code_hash: CryptoHash::default(),
+            cursor: None,
+            limit: 10,
+        };
+        assert!(client.EXPERIMENTAL_contract_accounts(request).await.is_err());
+    });
+}
+
+/// Retrieve the keys of the state changed between two blocks via json rpc
+#[test]
+fn test_trie_diff() {
+    test_with_client!(test_utils::NodeType::NonValidator, client, async move {
+        let request = |shard_id| RpcTrieDiffRequest {
+            shard_id,
+            from_block_id: BlockId::Height(0),
+            to_block_id: BlockId::Height(0),
+            cursor: None,
+            limit: 10,
+        };
+        let trie_diff = client.EXPERIMENTAL_trie_diff(request(0)).await.unwrap().trie_diff;
+        assert_eq!(trie_diff.from_block_hash, trie_diff.to_block_hash);
+        assert_eq!(trie_diff.source, TrieDiffSourceView::StateChanges);
+        assert!(trie_diff.changes.is_empty());
+        assert_eq!(trie_diff.next_cursor, None);
+
+        let error = client.EXPERIMENTAL_trie_diff(request(100)).await.unwrap_err();
+        let error = serde_json::to_value(error.error_struct.unwrap()).unwrap();
+        assert_eq!(error["name"], "HANDLER_ERROR");
+        assert_eq!(error["cause"]["name"], "INVALID_REQUEST");
+    });
+}
+
 #[test]
 fn test_invalid_methods() {
     test_with_client!(test_utils::NodeType::NonValidator, client, async move {
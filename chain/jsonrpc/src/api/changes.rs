@@ -1,9 +1,10 @@
 use serde_json::Value;
 
-use near_client_primitives::types::{GetBlockError, GetStateChangesError};
+use near_client_primitives::types::{GetBlockError, GetStateChangesError, GetTrieDiffError};
 use near_jsonrpc_primitives::errors::RpcParseError;
 use near_jsonrpc_primitives::types::changes::{
     RpcStateChangesError, RpcStateChangesInBlockByTypeRequest, RpcStateChangesInBlockRequest,
+    RpcTrieDiffError, RpcTrieDiffRequest,
 };
 
 use super::{parse_params, RpcFrom, RpcRequest};
@@ -20,6 +21,12 @@ impl RpcRequest for RpcStateChangesInBlockByTypeRequest {
     }
 }
 
+impl RpcRequest for RpcTrieDiffRequest {
+    fn parse(value: Option<Value>) -> Result<Self, RpcParseError> {
+        parse_params::<Self>(value)
+    }
+}
+
 impl RpcFrom<actix::MailboxError> for RpcStateChangesError {
     fn rpc_from(error: actix::MailboxError) -> Self {
         Self::InternalError { error_message: error.to_string() }
@@ -63,3 +70,45 @@ impl RpcFrom<GetStateChangesError> for RpcStateChangesError {
         }
     }
 }
+
+impl RpcFrom<actix::MailboxError> for RpcTrieDiffError {
+    fn rpc_from(error: actix::MailboxError) -> Self {
+        Self::InternalError { error_message: error.to_string() }
+    }
+}
+
+impl RpcFrom<GetBlockError> for RpcTrieDiffError {
+    fn rpc_from(error: GetBlockError) -> Self {
+        match error {
+            GetBlockError::UnknownBlock { error_message } => Self::UnknownBlock { error_message },
+            GetBlockError::NotSyncedYet => Self::NotSyncedYet,
+            GetBlockError::IOError { error_message } => Self::InternalError { error_message },
+            GetBlockError::Unreachable { ref error_message } => {
+                tracing::warn!(target: "jsonrpc", "Unreachable error occurred: {}", error_message);
+                crate::metrics::RPC_UNREACHABLE_ERROR_COUNT
+                    .with_label_values(&["RpcTrieDiffError"])
+                    .inc();
+                Self::InternalError { error_message: error.to_string() }
+            }
+        }
+    }
+}
+
+impl RpcFrom<GetTrieDiffError> for RpcTrieDiffError {
+    fn rpc_from(error: GetTrieDiffError) -> Self {
+        match error {
+            GetTrieDiffError::UnknownBlock { error_message } => {
+                Self::UnknownBlock { error_message }
+            }
+            GetTrieDiffError::InvalidRequest { error_message } => {
+                Self::InvalidRequest { error_message }
+            }
+            GetTrieDiffError::StateUnavailable { error_message } => {
+                Self::StateUnavailable { error_message }
+            }
+            GetTrieDiffError::InternalError { error_message } => {
+                Self::InternalError { error_message }
+            }
+        }
+    }
+}
@@ -1,8 +1,11 @@
 use serde_json::Value;
 
-use near_client_primitives::types::GetProtocolConfigError;
+use near_client_primitives::types::{GetProtocolConfigError, GetRuntimeParametersDiffError};
 use near_jsonrpc_primitives::errors::RpcParseError;
-use near_jsonrpc_primitives::types::config::{RpcProtocolConfigError, RpcProtocolConfigRequest};
+use near_jsonrpc_primitives::types::config::{
+    RpcProtocolConfigError, RpcProtocolConfigRequest, RpcRuntimeParametersDiffError,
+    RpcRuntimeParametersDiffRequest,
+};
 use near_primitives::types::BlockReference;
 
 use super::{parse_params, RpcFrom, RpcRequest};
@@ -36,3 +39,25 @@ impl RpcFrom<GetProtocolConfigError> for RpcProtocolConfigError {
         }
     }
 }
+
+impl RpcRequest for RpcRuntimeParametersDiffRequest {
+    fn parse(value: Option<Value>) -> Result<Self, RpcParseError> {
+        parse_params::<Self>(value)
+    }
+}
+
+impl RpcFrom<actix::MailboxError> for RpcRuntimeParametersDiffError {
+    fn rpc_from(error: actix::MailboxError) -> Self {
+        Self::InternalError { error_message: error.to_string() }
+    }
+}
+
+impl RpcFrom<GetRuntimeParametersDiffError> for RpcRuntimeParametersDiffError {
+    fn rpc_from(error: GetRuntimeParametersDiffError) -> Self {
+        match error {
+            GetRuntimeParametersDiffError::UnknownProtocolVersion { requested, latest } => {
+                Self::UnknownProtocolVersion { requested, latest }
+            }
+        }
+    }
+}
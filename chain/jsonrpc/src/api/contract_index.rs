@@ -0,0 +1,41 @@
+use serde_json::Value;
+
+use near_client_primitives::types::GetContractIndexError;
+use near_jsonrpc_primitives::errors::RpcParseError;
+use near_jsonrpc_primitives::types::contract_accounts::RpcContractAccountsRequest;
+use near_jsonrpc_primitives::types::contract_events::RpcContractEventsRequest;
+use near_jsonrpc_primitives::types::contract_index::RpcContractIndexError;
+
+use super::{parse_params, RpcFrom, RpcRequest};
+
+impl RpcRequest for RpcContractEventsRequest {
+    fn parse(value: Option<Value>) -> Result<Self, RpcParseError> {
+        parse_params::<Self>(value)
+    }
+}
+
+impl RpcRequest for RpcContractAccountsRequest {
+    fn parse(value: Option<Value>) -> Result<Self, RpcParseError> {
+        parse_params::<Self>(value)
+    }
+}
+
+impl RpcFrom<actix::MailboxError> for RpcContractIndexError {
+    fn rpc_from(error: actix::MailboxError) -> Self {
+        Self::InternalError { error_message: error.to_string() }
+    }
+}
+
+impl RpcFrom<GetContractIndexError> for RpcContractIndexError {
+    fn rpc_from(error: GetContractIndexError) -> Self {
+        match error {
+            GetContractIndexError::IndexDisabled => Self::IndexDisabled,
+            GetContractIndexError::InvalidRequest { error_message } => {
+                Self::InvalidRequest { error_message }
+            }
+            GetContractIndexError::InternalError { error_message } => {
+                Self::InternalError { error_message }
+            }
+        }
+    }
+}
@@ -1,8 +1,10 @@
 use serde_json::Value;
 
-use near_client_primitives::types::GetGasPriceError;
+use near_client_primitives::types::{GetEconomicsSeriesError, GetGasPriceError};
 use near_jsonrpc_primitives::errors::RpcParseError;
-use near_jsonrpc_primitives::types::gas_price::{RpcGasPriceError, RpcGasPriceRequest};
+use near_jsonrpc_primitives::types::gas_price::{
+    RpcEconomicsSeriesError, RpcEconomicsSeriesRequest, RpcGasPriceError, RpcGasPriceRequest,
+};
 use near_primitives::types::MaybeBlockId;
 
 use super::{parse_params, RpcFrom, RpcRequest};
@@ -38,3 +40,28 @@ impl RpcFrom<GetGasPriceError> for RpcGasPriceError {
         }
     }
 }
+
+impl RpcRequest for RpcEconomicsSeriesRequest {
+    fn parse(value: Option<Value>) -> Result<Self, RpcParseError> {
+        parse_params::<Self>(value)
+    }
+}
+
+impl RpcFrom<actix::MailboxError> for RpcEconomicsSeriesError {
+    fn rpc_from(error: actix::MailboxError) -> Self {
+        Self::InternalError { error_message: error.to_string() }
+    }
+}
+
+impl RpcFrom<GetEconomicsSeriesError> for RpcEconomicsSeriesError {
+    fn rpc_from(error: GetEconomicsSeriesError) -> Self {
+        match error {
+            GetEconomicsSeriesError::InvalidRange { error_message } => {
+                Self::InvalidRange { error_message }
+            }
+            GetEconomicsSeriesError::InternalError { error_message } => {
+                Self::InternalError { error_message }
+            }
+        }
+    }
+}
@@ -9,6 +9,7 @@ mod blocks;
 mod changes;
 mod chunks;
 mod config;
+mod contract_index;
 mod gas_price;
 mod light_client;
 mod network_info;
@@ -17,6 +18,7 @@ mod receipts;
 mod sandbox;
 mod status;
 mod transactions;
+mod tx_pool;
 mod validator;
 
 pub(crate) trait RpcRequest: Sized {
@@ -90,7 +92,13 @@ fn parse_signed_transaction(
     value: Option<Value>,
 ) -> Result<near_primitives::transaction::SignedTransaction, RpcParseError> {
     let (encoded,) = parse_params::<(String,)>(value)?;
-    let bytes = near_primitives::serialize::from_base64(&encoded)
+    decode_signed_transaction(&encoded)
+}
+
+fn decode_signed_transaction(
+    encoded: &str,
+) -> Result<near_primitives::transaction::SignedTransaction, RpcParseError> {
+    let bytes = near_primitives::serialize::from_base64(encoded)
         .map_err(|err| RpcParseError(err.to_string()))?;
     Ok(near_primitives::transaction::SignedTransaction::try_from_slice(&bytes)
         .map_err(|err| RpcParseError(format!("Failed to decode transaction: {}", err)))?)
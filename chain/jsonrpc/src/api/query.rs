@@ -121,6 +121,9 @@ impl RpcFrom<QueryError> for RpcQueryError {
             QueryError::ContractExecutionError { vm_error, block_height, block_hash } => {
                 Self::ContractExecutionError { vm_error, block_height, block_hash }
             }
+            QueryError::UnknownReceipt { receipt_id, block_height, block_hash } => {
+                Self::UnknownReceipt { receipt_id, block_height, block_hash }
+            }
             QueryError::Unreachable { ref error_message } => {
                 tracing::warn!(target: "jsonrpc", "Unreachable error occurred: {}", error_message);
                 crate::metrics::RPC_UNREACHABLE_ERROR_COUNT
@@ -168,6 +171,9 @@ impl RpcFrom<near_primitives::views::QueryResponseKind>
             near_primitives::views::QueryResponseKind::AccessKeyList(access_key_list) => {
                 Self::AccessKeyList(access_key_list)
             }
+            near_primitives::views::QueryResponseKind::ReceiptProof(receipt_proof) => {
+                Self::ReceiptProof(receipt_proof)
+            }
         }
     }
 }
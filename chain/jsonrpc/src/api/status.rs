@@ -46,6 +46,23 @@ impl RpcFrom<near_client_primitives::debug::DebugStatusResponse>
                     x,
                 )
             }
+            near_client_primitives::debug::DebugStatusResponse::FinalitySla(x) => {
+                near_jsonrpc_primitives::types::status::DebugStatusResponse::FinalitySla(x)
+            }
+            near_client_primitives::debug::DebugStatusResponse::TxPool(x) => {
+                near_jsonrpc_primitives::types::status::DebugStatusResponse::TxPool(x)
+            }
+            near_client_primitives::debug::DebugStatusResponse::ChunkEndorsementStatus(x) => {
+                near_jsonrpc_primitives::types::status::DebugStatusResponse::ChunkEndorsementStatus(
+                    x,
+                )
+            }
+            near_client_primitives::debug::DebugStatusResponse::HeaderSyncStats(x) => {
+                near_jsonrpc_primitives::types::status::DebugStatusResponse::HeaderSyncStats(x)
+            }
+            near_client_primitives::debug::DebugStatusResponse::DebugData(x) => {
+                near_jsonrpc_primitives::types::status::DebugStatusResponse::DebugData(x)
+            }
         }
     }
 }
@@ -58,6 +75,9 @@ impl RpcFrom<near_network::debug::DebugStatus>
             near_network::debug::DebugStatus::PeerStore(x) => {
                 near_jsonrpc_primitives::types::status::DebugStatusResponse::PeerStore(x)
             }
+            near_network::debug::DebugStatus::PeerAuditLog(x) => {
+                near_jsonrpc_primitives::types::status::DebugStatusResponse::PeerAuditLog(x)
+            }
         }
     }
 }
@@ -3,14 +3,16 @@ use serde_json::Value;
 use near_client_primitives::types::TxStatusError;
 use near_jsonrpc_primitives::errors::RpcParseError;
 use near_jsonrpc_primitives::types::transactions::{
-    RpcBroadcastTransactionRequest, RpcTransactionError, RpcTransactionResponse,
-    RpcTransactionStatusCommonRequest, TransactionInfo,
+    RpcBroadcastTransactionRequest, RpcTransactionError, RpcTransactionForkStatusResponse,
+    RpcTransactionResponse, RpcTransactionStatusCommonRequest, TransactionInfo,
 };
 use near_primitives::hash::CryptoHash;
-use near_primitives::types::AccountId;
-use near_primitives::views::FinalExecutionOutcomeViewEnum;
+use near_primitives::types::{AccountId, Finality};
+use near_primitives::views::{FinalExecutionOutcomeViewEnum, TxForkStatusView};
 
-use super::{parse_params, parse_signed_transaction, RpcFrom, RpcRequest};
+use super::{
+    decode_signed_transaction, parse_params, parse_signed_transaction, RpcFrom, RpcRequest,
+};
 
 impl RpcRequest for RpcBroadcastTransactionRequest {
     fn parse(value: Option<Value>) -> Result<Self, RpcParseError> {
@@ -19,15 +21,52 @@ impl RpcRequest for RpcBroadcastTransactionRequest {
     }
 }
 
+/// Named parameters of the transaction status methods.
+#[derive(serde::Deserialize)]
+struct TransactionStatusParams {
+    tx_hash: CryptoHash,
+    sender_account_id: AccountId,
+    #[serde(default)]
+    min_finality: Option<Finality>,
+    #[serde(default)]
+    outcome_proofs: bool,
+}
+
 impl RpcRequest for RpcTransactionStatusCommonRequest {
     fn parse(value: Option<Value>) -> Result<Self, RpcParseError> {
-        if let Ok((hash, account_id)) = parse_params::<(CryptoHash, AccountId)>(value.clone()) {
+        // The named form of the request accepts the minimum finality of the
+        // outcome and whether to bundle the outcome proofs, both positional
+        // forms accept the minimum finality as an optional last parameter.
+        if let Ok(params) = parse_params::<TransactionStatusParams>(value.clone()) {
+            let transaction_info = TransactionInfo::TransactionId {
+                hash: params.tx_hash,
+                account_id: params.sender_account_id,
+            };
+            Ok(Self {
+                transaction_info,
+                min_finality: params.min_finality,
+                outcome_proofs: params.outcome_proofs,
+            })
+        } else if let Ok((hash, account_id)) =
+            parse_params::<(CryptoHash, AccountId)>(value.clone())
+        {
             let transaction_info = TransactionInfo::TransactionId { hash, account_id };
-            Ok(Self { transaction_info })
+            Ok(Self { transaction_info, min_finality: None, outcome_proofs: false })
+        } else if let Ok((hash, account_id, min_finality)) =
+            parse_params::<(CryptoHash, AccountId, Finality)>(value.clone())
+        {
+            let transaction_info = TransactionInfo::TransactionId { hash, account_id };
+            Ok(Self { transaction_info, min_finality: Some(min_finality), outcome_proofs: false })
+        } else if let Ok((encoded, min_finality)) =
+            parse_params::<(String, Finality)>(value.clone())
+        {
+            let signed_transaction = decode_signed_transaction(&encoded)?;
+            let transaction_info = TransactionInfo::Transaction(signed_transaction);
+            Ok(Self { transaction_info, min_finality: Some(min_finality), outcome_proofs: false })
         } else {
             let signed_transaction = parse_signed_transaction(value)?;
             let transaction_info = TransactionInfo::Transaction(signed_transaction);
-            Ok(Self { transaction_info })
+            Ok(Self { transaction_info, min_finality: None, outcome_proofs: false })
         }
     }
 }
@@ -58,3 +97,55 @@ impl RpcFrom<FinalExecutionOutcomeViewEnum> for RpcTransactionResponse {
         Self { final_execution_outcome }
     }
 }
+
+impl RpcFrom<TxForkStatusView> for RpcTransactionForkStatusResponse {
+    fn rpc_from(tx_fork_status: TxForkStatusView) -> Self {
+        Self { tx_fork_status }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn parse(params: Value) -> RpcTransactionStatusCommonRequest {
+        RpcTransactionStatusCommonRequest::parse(Some(params)).unwrap()
+    }
+
+    fn transaction_id(request: &RpcTransactionStatusCommonRequest) -> (CryptoHash, AccountId) {
+        match &request.transaction_info {
+            TransactionInfo::TransactionId { hash, account_id } => (*hash, account_id.clone()),
+            TransactionInfo::Transaction(_) => panic!("expected a transaction id"),
+        }
+    }
+
+    #[test]
+    fn test_parse_tx_status() {
+        let hash = CryptoHash::hash_bytes(b"tx");
+        let account_id: AccountId = "test0".parse().unwrap();
+
+        let request = parse(serde_json::json!({
+            "tx_hash": hash,
+            "sender_account_id": account_id,
+            "min_finality": "near-final",
+            "outcome_proofs": true,
+        }));
+        assert_eq!(transaction_id(&request), (hash, account_id.clone()));
+        assert_eq!(request.min_finality, Some(Finality::DoomSlug));
+        assert!(request.outcome_proofs);
+
+        // The optional named parameters default to None and false.
+        let request = parse(serde_json::json!({
+            "tx_hash": hash,
+            "sender_account_id": account_id,
+        }));
+        assert_eq!(transaction_id(&request), (hash, account_id.clone()));
+        assert_eq!(request.min_finality, None);
+        assert!(!request.outcome_proofs);
+
+        let request = parse(serde_json::json!([hash, account_id, "final"]));
+        assert_eq!(transaction_id(&request), (hash, account_id));
+        assert_eq!(request.min_finality, Some(Finality::Final));
+        assert!(!request.outcome_proofs);
+    }
+}
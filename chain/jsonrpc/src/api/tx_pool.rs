@@ -0,0 +1,26 @@
+use serde_json::Value;
+
+use near_jsonrpc_primitives::errors::RpcParseError;
+use near_jsonrpc_primitives::types::tx_pool::{
+    RpcTxPoolDropRequest, RpcTxPoolError, RpcTxPoolPinRequest,
+};
+
+use super::{parse_params, RpcFrom, RpcRequest};
+
+impl RpcRequest for RpcTxPoolDropRequest {
+    fn parse(value: Option<Value>) -> Result<Self, RpcParseError> {
+        parse_params::<Self>(value)
+    }
+}
+
+impl RpcRequest for RpcTxPoolPinRequest {
+    fn parse(value: Option<Value>) -> Result<Self, RpcParseError> {
+        parse_params::<Self>(value)
+    }
+}
+
+impl RpcFrom<actix::MailboxError> for RpcTxPoolError {
+    fn rpc_from(error: actix::MailboxError) -> Self {
+        Self::InternalError { error_message: error.to_string() }
+    }
+}
@@ -3,7 +3,8 @@ use serde_json::Value;
 use near_client_primitives::types::GetValidatorInfoError;
 use near_jsonrpc_primitives::errors::RpcParseError;
 use near_jsonrpc_primitives::types::validator::{
-    RpcValidatorError, RpcValidatorRequest, RpcValidatorsOrderedRequest,
+    RpcBlockProducerProofRequest, RpcProductionScheduleRequest, RpcValidatorError,
+    RpcValidatorRequest, RpcValidatorsOrderedRequest,
 };
 use near_primitives::types::{EpochReference, MaybeBlockId};
 
@@ -30,6 +31,18 @@ impl RpcRequest for RpcValidatorsOrderedRequest {
     }
 }
 
+impl RpcRequest for RpcBlockProducerProofRequest {
+    fn parse(value: Option<Value>) -> Result<Self, RpcParseError> {
+        parse_params::<Self>(value)
+    }
+}
+
+impl RpcRequest for RpcProductionScheduleRequest {
+    fn parse(value: Option<Value>) -> Result<Self, RpcParseError> {
+        parse_params::<Self>(value)
+    }
+}
+
 impl RpcFrom<actix::MailboxError> for RpcValidatorError {
     fn rpc_from(error: actix::MailboxError) -> Self {
         Self::InternalError { error_message: error.to_string() }
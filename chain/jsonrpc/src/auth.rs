@@ -0,0 +1,218 @@
+//! Authorization of privileged RPC calls.
+//!
+//! Debug endpoints and methods controlling the node are meant for its
+//! operator.  Without API keys they're served to anyone who can reach the RPC
+//! port, as long as `enable_debug_rpc` is set.  Once the operator defines API
+//! keys, every privileged call has to carry a key granting the permission the
+//! call requires in the `Authorization: Bearer <key>` header.  Each key has a
+//! name, which identifies it in the logs without revealing it.
+//!
+//! Every privileged call is logged with the `rpc_audit` target, whether it's
+//! allowed or not.
+use std::collections::HashMap;
+
+use actix_web::http::header;
+use actix_web::HttpRequest;
+use near_jsonrpc_primitives::types::auth::RpcAuthError;
+use serde::{Deserialize, Serialize};
+
+use crate::metrics;
+
+/// Permissions of API keys, each covering a group of privileged calls.
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, PartialEq, Eq, Hash)]
+#[serde(rename_all = "snake_case")]
+pub enum RpcPermission {
+    /// Reading the debug endpoints, which are read-only.
+    Debug,
+    /// Controlling how the node syncs.
+    SyncControl,
+    /// Controlling what the node does as a validator, including its
+    /// transaction pool and draining it before a restart.
+    ValidatorControl,
+}
+
+impl RpcPermission {
+    fn as_str(self) -> &'static str {
+        match self {
+            RpcPermission::Debug => "debug",
+            RpcPermission::SyncControl => "sync_control",
+            RpcPermission::ValidatorControl => "validator_control",
+        }
+    }
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, Eq)]
+pub struct RpcApiKey {
+    /// Name of the key in the audit log.
+    pub name: String,
+    /// The key itself, as passed by clients.
+    pub key: String,
+    pub permissions: Vec<RpcPermission>,
+}
+
+/// Privileged JSON RPC methods with the permission each of them requires.
+///
+/// The dispatch serves these methods only with the debug RPC enabled (or, for
+/// the `adv_*` ones, with the `test_features` feature), so a method added here
+/// is both gated and authorized.
+pub(crate) const PRIVILEGED_METHODS: &[(&str, RpcPermission)] = &[
+    ("EXPERIMENTAL_begin_drain", RpcPermission::ValidatorControl),
+    ("EXPERIMENTAL_tx_pool_drop", RpcPermission::ValidatorControl),
+    ("EXPERIMENTAL_tx_pool_pin", RpcPermission::ValidatorControl),
+    ("EXPERIMENTAL_tx_pool_unpin", RpcPermission::ValidatorControl),
+    ("adv_check_store", RpcPermission::Debug),
+    ("adv_disable_doomslug", RpcPermission::ValidatorControl),
+    ("adv_disable_header_sync", RpcPermission::SyncControl),
+    ("adv_get_saved_blocks", RpcPermission::Debug),
+    ("adv_produce_blocks", RpcPermission::ValidatorControl),
+    ("adv_set_weight", RpcPermission::SyncControl),
+    ("adv_switch_to_height", RpcPermission::SyncControl),
+];
+
+/// Returns the permission required by the JSON RPC method, or None if the
+/// method isn't privileged.
+pub(crate) fn method_permission(method: &str) -> Option<RpcPermission> {
+    PRIVILEGED_METHODS.iter().find(|(name, _)| *name == method).map(|(_, permission)| *permission)
+}
+
+pub(crate) struct Authorizer {
+    /// API keys by the key.
+    keys: HashMap<String, RpcApiKey>,
+}
+
+impl Authorizer {
+    pub fn new(keys: Vec<RpcApiKey>) -> Self {
+        Self { keys: keys.into_iter().map(|key| (key.key.clone(), key)).collect() }
+    }
+
+    /// Checks that `request` may make the privileged `call`, which requires
+    /// `permission`, and logs the call.  Without API keys all calls are
+    /// allowed.
+    pub fn authorize(
+        &self,
+        request: &HttpRequest,
+        call: &str,
+        permission: RpcPermission,
+    ) -> Result<(), RpcAuthError> {
+        let result = if self.keys.is_empty() {
+            Ok(None)
+        } else {
+            match bearer_token(request).and_then(|key| self.keys.get(key)) {
+                None => Err(RpcAuthError::Unauthorized {
+                    method_name: call.to_string(),
+                    permission: permission.as_str().to_string(),
+                }),
+                Some(key) if !key.permissions.contains(&permission) => {
+                    Err(RpcAuthError::Forbidden {
+                        key_name: key.name.clone(),
+                        method_name: call.to_string(),
+                        permission: permission.as_str().to_string(),
+                    })
+                }
+                Some(key) => Ok(Some(key.name.as_str())),
+            }
+        };
+        let (key_name, outcome) = match &result {
+            Ok(key_name) => (*key_name, "allowed"),
+            Err(RpcAuthError::Unauthorized { .. }) => (None, "unauthorized"),
+            Err(RpcAuthError::Forbidden { key_name, .. }) => (Some(key_name.as_str()), "forbidden"),
+        };
+        tracing::info!(
+            target: "rpc_audit",
+            call,
+            permission = permission.as_str(),
+            key = ?key_name,
+            peer = ?request.peer_addr(),
+            outcome,
+            "Privileged RPC call");
+        metrics::RPC_PRIVILEGED_CALL_COUNT.with_label_values(&[permission.as_str(), outcome]).inc();
+        result.map(|_| ())
+    }
+}
+
+/// Returns the key passed in the `Authorization: Bearer <key>` header.
+fn bearer_token(request: &HttpRequest) -> Option<&str> {
+    let value = request.headers().get(header::AUTHORIZATION)?.to_str().ok()?;
+    value.strip_prefix("Bearer ").map(str::trim)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{method_permission, Authorizer, RpcApiKey, RpcPermission, PRIVILEGED_METHODS};
+    use actix_web::http::header;
+    use actix_web::test::TestRequest;
+    use near_jsonrpc_primitives::types::auth::RpcAuthError;
+
+    fn request(key: Option<&str>) -> actix_web::HttpRequest {
+        let mut request = TestRequest::default();
+        if let Some(key) = key {
+            request = request.insert_header((header::AUTHORIZATION, format!("Bearer {key}")));
+        }
+        request.to_http_request()
+    }
+
+    #[test]
+    fn test_authorize() {
+        let drain = "EXPERIMENTAL_begin_drain";
+        let permission = method_permission(drain).unwrap();
+        assert_eq!(permission, RpcPermission::ValidatorControl);
+        assert_eq!(method_permission("block"), None);
+
+        // Without keys everything is allowed.
+        let authorizer = Authorizer::new(vec![]);
+        authorizer.authorize(&request(None), drain, permission).unwrap();
+
+        let authorizer = Authorizer::new(vec![
+            RpcApiKey {
+                name: "monitoring".to_string(),
+                key: "secret1".to_string(),
+                permissions: vec![RpcPermission::Debug],
+            },
+            RpcApiKey {
+                name: "operator".to_string(),
+                key: "secret2".to_string(),
+                permissions: vec![RpcPermission::Debug, RpcPermission::ValidatorControl],
+            },
+        ]);
+        for key in [None, Some("secret3")] {
+            match authorizer.authorize(&request(key), drain, permission) {
+                Err(RpcAuthError::Unauthorized { method_name, permission }) => {
+                    assert_eq!(method_name, drain);
+                    assert_eq!(permission, "validator_control");
+                }
+                result => panic!("unexpected result {:?}", result),
+            }
+        }
+        match authorizer.authorize(&request(Some("secret1")), drain, permission) {
+            Err(RpcAuthError::Forbidden { key_name, .. }) => assert_eq!(key_name, "monitoring"),
+            result => panic!("unexpected result {:?}", result),
+        }
+        authorizer.authorize(&request(Some("secret2")), drain, permission).unwrap();
+        authorizer
+            .authorize(&request(Some("secret1")), "/debug/api/tx_pool", RpcPermission::Debug)
+            .unwrap();
+    }
+
+    #[test]
+    fn test_privileged_methods_require_key() {
+        let authorizer = Authorizer::new(vec![RpcApiKey {
+            name: "operator".to_string(),
+            key: "secret".to_string(),
+            permissions: vec![
+                RpcPermission::Debug,
+                RpcPermission::SyncControl,
+                RpcPermission::ValidatorControl,
+            ],
+        }]);
+        for &(method, permission) in PRIVILEGED_METHODS {
+            assert_eq!(method_permission(method), Some(permission));
+            match authorizer.authorize(&request(None), method, permission) {
+                Err(RpcAuthError::Unauthorized { method_name, .. }) => {
+                    assert_eq!(method_name, method)
+                }
+                result => panic!("unexpected result {:?} for {method}", result),
+            }
+            authorizer.authorize(&request(Some("secret")), method, permission).unwrap();
+        }
+    }
+}
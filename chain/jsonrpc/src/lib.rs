@@ -1,6 +1,7 @@
 #![doc = include_str!("../README.md")]
 
 use std::path::PathBuf;
+use std::sync::Arc;
 use std::time::{Duration, Instant};
 
 use actix::{Addr, MailboxError};
@@ -18,27 +19,41 @@ use tracing::info;
 
 use near_chain_configs::GenesisConfig;
 use near_client::{
-    ClientActor, DebugStatus, GetBlock, GetBlockProof, GetChunk, GetExecutionOutcome, GetGasPrice,
-    GetNetworkInfo, GetNextLightClientBlock, GetProtocolConfig, GetReceipt, GetStateChanges,
-    GetStateChangesInBlock, GetValidatorInfo, GetValidatorOrdered, ProcessTxRequest,
-    ProcessTxResponse, Query, Status, TxStatus, ViewClientActor,
+    BeginDrain, ClientActor, DebugStatus, DeferTxRequest, GetBlock, GetBlockProducerProof,
+    GetBlockProof, GetChunk, GetContractAccounts, GetContractEvents, GetEconomicsSeries,
+    GetExecutionOutcome, GetGasPrice, GetHealth, GetNetworkInfo, GetNextLightClientBlock,
+    GetOutgoingReceiptProofs, GetProductionSchedule, GetProtocolConfig, GetRandomness, GetReceipt,
+    GetRuntimeParametersDiff, GetStateChanges, GetStateChangesInBlock, GetTrieDiff,
+    GetValidatorInfo, GetValidatorOrdered, ProcessTxRequest, ProcessTxResponse, Query, Status,
+    TxForkStatus, TxPoolCommand, TxRejectionReason, TxStatus, ViewClientActor,
 };
 pub use near_jsonrpc_client as client;
 use near_jsonrpc_primitives::errors::RpcError;
 use near_jsonrpc_primitives::message::{Message, Request};
-use near_jsonrpc_primitives::types::config::RpcProtocolConfigResponse;
-use near_o11y::metrics::{prometheus, Encoder, TextEncoder};
+use near_jsonrpc_primitives::types::config::{
+    RpcProtocolConfigResponse, RpcRuntimeParametersDiffResponse,
+};
+use near_jsonrpc_primitives::types::tx_pool::{
+    RpcTxPoolDropRequest, RpcTxPoolError, RpcTxPoolPinRequest, RpcTxPoolResponse,
+};
+use near_o11y::metrics::{Encoder, TextEncoder};
 use near_primitives::hash::CryptoHash;
 use near_primitives::transaction::SignedTransaction;
-use near_primitives::types::{AccountId, BlockHeight};
+use near_primitives::types::{AccountId, BlockHeight, Finality};
 use near_primitives::views::FinalExecutionOutcomeViewEnum;
 
 mod api;
+mod auth;
 mod metrics;
+mod rate_limit;
 
 use api::RpcRequest;
 pub use api::{RpcFrom, RpcInto};
+use auth::Authorizer;
+pub use auth::{RpcApiKey, RpcPermission};
 use near_o11y::{WithSpanContext, WithSpanContextExt};
+use rate_limit::RateLimiter;
+pub use rate_limit::{RpcRateLimit, RpcRateLimitsConfig};
 
 #[derive(Serialize, Deserialize, Clone, Copy, Debug)]
 pub struct RpcPollingConfig {
@@ -88,6 +103,14 @@ pub struct RpcConfig {
     // be read from this directory, instead of the contents compiled into the binary. This allows
     // for quick iterative development.
     pub experimental_debug_pages_src_path: Option<String>,
+    // If provided, requests are rate limited per client, as suitable for nodes serving RPC to the
+    // public.
+    #[serde(default)]
+    pub rate_limits_config: Option<RpcRateLimitsConfig>,
+    // If not empty, debug endpoints and methods controlling the node require one of these keys
+    // granting the permission they need.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub api_keys: Vec<RpcApiKey>,
 }
 
 impl Default for RpcConfig {
@@ -100,6 +123,8 @@ impl Default for RpcConfig {
             limits_config: Default::default(),
             enable_debug_rpc: false,
             experimental_debug_pages_src_path: None,
+            rate_limits_config: None,
+            api_keys: vec![],
         }
     }
 }
@@ -141,7 +166,28 @@ impl near_jsonrpc_primitives::types::transactions::RpcTransactionError {
     pub fn from_network_client_responses(resp: ProcessTxResponse) -> Self {
         match resp {
             ProcessTxResponse::InvalidTx(context) => Self::InvalidTransaction { context },
-            ProcessTxResponse::NoResponse => Self::TimeoutError,
+            ProcessTxResponse::Rejected(reason) => match reason {
+                TxRejectionReason::SignerQuotaExceeded { signer_id, limit } => {
+                    Self::SignerQuotaExceeded { signer_id, limit }
+                }
+                TxRejectionReason::RelayerQuotaExceeded { relayer_id, limit } => {
+                    Self::RelayerQuotaExceeded { relayer_id, limit }
+                }
+                TxRejectionReason::DelegateActionExpired { max_block_height, next_height } => {
+                    Self::DelegateActionExpired { max_block_height, next_height }
+                }
+                TxRejectionReason::Draining => Self::NodeDraining,
+                reason @ (TxRejectionReason::TooManyDeferred { .. }
+                | TxRejectionReason::TooManyDeferredFromSigner { .. }) => {
+                    Self::InternalError { debug_info: reason.to_string() }
+                }
+                TxRejectionReason::InternalError { error_message } => {
+                    Self::InternalError { debug_info: error_message }
+                }
+                TxRejectionReason::NodeNotCaughtUp { .. }
+                | TxRejectionReason::ShardNotTracked { .. }
+                | TxRejectionReason::NotValidator { .. } => Self::DoesNotTrackShard,
+            },
             ProcessTxResponse::DoesNotTrackShard | ProcessTxResponse::RequestRouted => {
                 Self::DoesNotTrackShard
             }
@@ -225,6 +271,8 @@ struct JsonRpcHandler {
     genesis_config: GenesisConfig,
     enable_debug_rpc: bool,
     debug_pages_src_path: Option<PathBuf>,
+    rate_limiter: Option<Arc<RateLimiter>>,
+    authorizer: Arc<Authorizer>,
 }
 
 impl JsonRpcHandler {
@@ -273,6 +321,11 @@ impl JsonRpcHandler {
             Ok(response) => return response,
             Err(request) => request,
         };
+        // Privileged methods are meant for the node operator, so they are served only together
+        // with the debug RPC.
+        if !self.enable_debug_rpc && auth::method_permission(&request.method).is_some() {
+            return Err(RpcError::method_not_found(request.method));
+        }
 
         match request.method.as_ref() {
             // Handlers ordered alphabetically
@@ -307,12 +360,30 @@ impl JsonRpcHandler {
             }
             "status" => process_method_call(request, |_params: ()| self.status()).await,
             "tx" => {
-                process_method_call(request, |params| self.tx_status_common(params, false)).await
+                let params: near_jsonrpc_primitives::types::transactions::RpcTransactionStatusCommonRequest =
+                    RpcRequest::parse(request.params)?;
+                // Outcome proofs are bundled with the receipts, which only
+                // EXPERIMENTAL_tx_status returns.
+                if params.outcome_proofs {
+                    return Err(RpcError::invalid_params(
+                        "outcome_proofs is only supported by EXPERIMENTAL_tx_status",
+                    ));
+                }
+                serialize_response(self.tx_status_common(params, false).await?)
             }
             "validators" => process_method_call(request, |params| self.validators(params)).await,
+            "EXPERIMENTAL_broadcast_tx_deferred" => {
+                process_method_call(request, |params| self.send_tx_deferred(params)).await
+            }
             "EXPERIMENTAL_broadcast_tx_sync" => {
                 process_method_call(request, |params| self.send_tx_sync(params)).await
             }
+            "EXPERIMENTAL_block_producer_proof" => {
+                process_method_call(request, |params| self.block_producer_proof(params)).await
+            }
+            "EXPERIMENTAL_production_schedule" => {
+                process_method_call(request, |params| self.production_schedule(params)).await
+            }
             "EXPERIMENTAL_changes" => {
                 process_method_call(request, |params| self.changes_in_block_by_type(params)).await
             }
@@ -322,30 +393,80 @@ impl JsonRpcHandler {
             "EXPERIMENTAL_check_tx" => {
                 process_method_call(request, |params| self.check_tx(params)).await
             }
+            "EXPERIMENTAL_contract_accounts" => {
+                process_method_call(request, |params| self.contract_accounts(params)).await
+            }
+            "EXPERIMENTAL_contract_events" => {
+                process_method_call(request, |params| self.contract_events(params)).await
+            }
+            "EXPERIMENTAL_error_catalog" => {
+                process_method_call(request, |_params: ()| async {
+                    Result::<_, std::convert::Infallible>::Ok(
+                        near_jsonrpc_primitives::error_catalog::error_catalog(),
+                    )
+                })
+                .await
+            }
             "EXPERIMENTAL_genesis_config" => {
                 process_method_call(request, |_params: ()| async {
                     Result::<_, std::convert::Infallible>::Ok(&self.genesis_config)
                 })
                 .await
             }
+            "EXPERIMENTAL_economics_series" => {
+                process_method_call(request, |params| self.economics_series(params)).await
+            }
             "EXPERIMENTAL_light_client_proof" => {
                 process_method_call(request, |params| {
                     self.light_client_execution_outcome_proof(params)
                 })
                 .await
             }
+            "EXPERIMENTAL_outgoing_receipt_proofs" => {
+                process_method_call(request, |params| self.outgoing_receipt_proofs(params)).await
+            }
             "EXPERIMENTAL_protocol_config" => {
                 process_method_call(request, |params| self.protocol_config(params)).await
             }
+            "EXPERIMENTAL_randomness" => {
+                process_method_call(request, |params| self.randomness(params)).await
+            }
             "EXPERIMENTAL_receipt" => {
                 process_method_call(request, |params| self.receipt(params)).await
             }
+            "EXPERIMENTAL_runtime_parameters_diff" => {
+                process_method_call(request, |params| self.runtime_parameters_diff(params)).await
+            }
+            "EXPERIMENTAL_trie_diff" => {
+                process_method_call(request, |params| self.trie_diff(params)).await
+            }
+            "EXPERIMENTAL_tx_fork_status" => {
+                process_method_call(request, |params| self.tx_fork_status(params)).await
+            }
             "EXPERIMENTAL_tx_status" => {
                 process_method_call(request, |params| self.tx_status_common(params, true)).await
             }
             "EXPERIMENTAL_validators_ordered" => {
                 process_method_call(request, |params| self.validators_ordered(params)).await
             }
+            "EXPERIMENTAL_tx_pool_drop" => {
+                process_method_call(request, |params| self.tx_pool_drop(params)).await
+            }
+            "EXPERIMENTAL_tx_pool_pin" => {
+                process_method_call(request, |params: RpcTxPoolPinRequest| {
+                    self.tx_pool_command(TxPoolCommand::Pin(params.tx_hash))
+                })
+                .await
+            }
+            "EXPERIMENTAL_tx_pool_unpin" => {
+                process_method_call(request, |params: RpcTxPoolPinRequest| {
+                    self.tx_pool_command(TxPoolCommand::Unpin(params.tx_hash))
+                })
+                .await
+            }
+            "EXPERIMENTAL_begin_drain" => {
+                process_method_call(request, |_params: ()| self.begin_drain()).await
+            }
             #[cfg(feature = "sandbox")]
             "sandbox_patch_state" => {
                 process_method_call(request, |params| self.sandbox_patch_state(params)).await
@@ -464,6 +585,7 @@ impl JsonRpcHandler {
                         tx_hash,
                         signer_account_id: signer_account_id.clone(),
                         fetch_receipt: false,
+                        fetch_outcome_proofs: false,
                     })
                     .await
                 {
@@ -496,6 +618,8 @@ impl JsonRpcHandler {
         &self,
         tx_info: near_jsonrpc_primitives::types::transactions::TransactionInfo,
         fetch_receipt: bool,
+        fetch_outcome_proofs: bool,
+        min_finality: Option<Finality>,
     ) -> Result<
         FinalExecutionOutcomeViewEnum,
         near_jsonrpc_primitives::types::transactions::RpcTransactionError,
@@ -515,10 +639,19 @@ impl JsonRpcHandler {
                         tx_hash,
                         signer_account_id: account_id.clone(),
                         fetch_receipt,
+                        fetch_outcome_proofs,
                     })
                     .await;
                 match tx_status_result {
-                    Ok(Some(outcome)) => break Ok(outcome),
+                    Ok(Some(outcome)) => {
+                        let is_final_enough = min_finality.as_ref().map_or(true, |min_finality| {
+                            outcome.finality().map_or(false, |finality| finality >= min_finality)
+                        });
+                        if is_final_enough {
+                            break Ok(outcome);
+                        }
+                        // Outcome is known but not final enough yet, keep polling.
+                    }
                     Ok(None) => {} // No such transaction recorded on chain yet
                     Err(err @ near_jsonrpc_primitives::types::transactions::RpcTransactionError::UnknownTransaction {
                         ..
@@ -545,9 +678,10 @@ impl JsonRpcHandler {
         .map_err(|_| {
             metrics::RPC_TIMEOUT_TOTAL.inc();
             tracing::warn!(
-                target: "jsonrpc", "Timeout: tx_status_fetch method. tx_info {:?} fetch_receipt {:?}",
+                target: "jsonrpc", "Timeout: tx_status_fetch method. tx_info {:?} fetch_receipt {:?} min_finality {:?}",
                 tx_info,
                 fetch_receipt,
+                min_finality,
             );
             near_jsonrpc_primitives::types::transactions::RpcTransactionError::TimeoutError
         })?
@@ -562,7 +696,7 @@ impl JsonRpcHandler {
     > {
         timeout(self.polling_config.polling_timeout, async {
             loop {
-                match self.tx_status_fetch(tx_info.clone(), false).await {
+                match self.tx_status_fetch(tx_info.clone(), false, false, None).await {
                     Ok(tx_status) => {
                         break Ok(
                             near_jsonrpc_primitives::types::transactions::RpcTransactionResponse {
@@ -654,6 +788,37 @@ impl JsonRpcHandler {
         }
     }
 
+    /// Submits a transaction which the node holds until the block it's
+    /// anchored to is final, so that the transaction can't be invalidated by
+    /// the block getting orphaned.
+    async fn send_tx_deferred(
+        &self,
+        request_data: near_jsonrpc_primitives::types::transactions::RpcBroadcastTransactionRequest,
+    ) -> Result<
+        near_jsonrpc_primitives::types::transactions::RpcBroadcastTxSyncResponse,
+        near_jsonrpc_primitives::types::transactions::RpcTransactionError,
+    > {
+        let tx = request_data.signed_transaction;
+        let transaction_hash = tx.get_hash();
+        let response = self
+            .client_addr
+            .send(DeferTxRequest { transaction: tx }.with_span_context())
+            .await
+            .map_err(RpcFrom::rpc_from)?;
+        match response {
+            ProcessTxResponse::ValidTx | ProcessTxResponse::RequestRouted => {
+                Ok(near_jsonrpc_primitives::types::transactions::RpcBroadcastTxSyncResponse {
+                    transaction_hash,
+                })
+            }
+            resp => Err(
+                near_jsonrpc_primitives::types::transactions::RpcTransactionError::from_network_client_responses(
+                    resp
+                )
+            )
+        }
+    }
+
     async fn check_tx(
         &self,
         request_data: near_jsonrpc_primitives::types::transactions::RpcBroadcastTransactionRequest,
@@ -694,6 +859,8 @@ impl JsonRpcHandler {
                     tx.clone(),
                 ),
                 false,
+                false,
+                None,
             )
             .await
         {
@@ -733,6 +900,16 @@ impl JsonRpcHandler {
         Ok(status.rpc_into())
     }
 
+    /// Readiness and liveness verdicts of the node.
+    async fn health_verdicts(
+        &self,
+    ) -> Result<
+        near_primitives::views::HealthView,
+        near_jsonrpc_primitives::types::status::RpcStatusError,
+    > {
+        self.client_send(GetHealth).await
+    }
+
     pub async fn status(
         &self,
     ) -> Result<
@@ -766,6 +943,16 @@ impl JsonRpcHandler {
         near_jsonrpc_primitives::types::status::RpcStatusError,
     > {
         if self.enable_debug_rpc {
+            if near_store::storage_health().is_degraded() {
+                near_store::storage_health().record_shed("debug");
+                return Err(
+                    near_jsonrpc_primitives::types::status::RpcStatusError::InternalError {
+                        error_message:
+                            "debug endpoints are disabled while storage latency is degraded"
+                                .to_string(),
+                    },
+                );
+            }
             let debug_status: near_jsonrpc_primitives::types::status::DebugStatusResponse =
                 match path {
                     "/debug/api/tracked_shards" => {
@@ -789,10 +976,32 @@ impl JsonRpcHandler {
                     "/debug/api/chain_processing_status" => {
                         self.client_send(DebugStatus::ChainProcessingStatus).await?.rpc_into()
                     }
+                    "/debug/api/finality_sla" => {
+                        self.client_send(DebugStatus::FinalitySla).await?.rpc_into()
+                    }
+                    "/debug/api/tx_pool" => self.client_send(DebugStatus::TxPool).await?.rpc_into(),
+                    "/debug/api/chunk_endorsement_status" => {
+                        self.client_send(DebugStatus::ChunkEndorsementStatus).await?.rpc_into()
+                    }
+                    "/debug/api/header_sync" => {
+                        self.client_send(DebugStatus::HeaderSyncStats).await?.rpc_into()
+                    }
+                    "/debug/api/debug_data" => {
+                        self.client_send(DebugStatus::DebugData).await?.rpc_into()
+                    }
                     "/debug/api/peer_store" => self
                         .peer_manager_send(near_network::debug::GetDebugStatus::PeerStore)
                         .await?
                         .rpc_into(),
+                    "/debug/api/peer_audit_log" => self
+                        .peer_manager_send(near_network::debug::GetDebugStatus::PeerAuditLog)
+                        .await?
+                        .rpc_into(),
+                    "/debug/api/store_writes" => {
+                        near_jsonrpc_primitives::types::status::DebugStatusResponse::StoreWrites(
+                            near_store::write_attribution().view(),
+                        )
+                    }
                     _ => return Ok(None),
                 };
             return Ok(Some(near_jsonrpc_primitives::types::status::RpcDebugStatusResponse {
@@ -833,6 +1042,56 @@ impl JsonRpcHandler {
         Ok(RpcProtocolConfigResponse { config_view })
     }
 
+    pub async fn runtime_parameters_diff(
+        &self,
+        request_data: near_jsonrpc_primitives::types::config::RpcRuntimeParametersDiffRequest,
+    ) -> Result<
+        near_jsonrpc_primitives::types::config::RpcRuntimeParametersDiffResponse,
+        near_jsonrpc_primitives::types::config::RpcRuntimeParametersDiffError,
+    > {
+        let diff_view = self
+            .view_client_send(GetRuntimeParametersDiff {
+                from_protocol_version: request_data.from_protocol_version,
+                to_protocol_version: request_data.to_protocol_version,
+            })
+            .await?;
+        Ok(RpcRuntimeParametersDiffResponse { diff_view })
+    }
+
+    async fn tx_pool_drop(
+        &self,
+        request_data: RpcTxPoolDropRequest,
+    ) -> Result<RpcTxPoolResponse, RpcTxPoolError> {
+        let command = match request_data {
+            RpcTxPoolDropRequest::Transactions { tx_hashes } => {
+                TxPoolCommand::DropTransactions(tx_hashes)
+            }
+            RpcTxPoolDropRequest::Signer { signer_id } => {
+                TxPoolCommand::DropSignerTransactions(signer_id)
+            }
+        };
+        self.tx_pool_command(command).await
+    }
+
+    async fn begin_drain(
+        &self,
+    ) -> Result<
+        near_jsonrpc_primitives::types::status::RpcDrainStatusResponse,
+        near_jsonrpc_primitives::types::status::RpcStatusError,
+    > {
+        let drain_status = self.client_send(BeginDrain).await?;
+        Ok(near_jsonrpc_primitives::types::status::RpcDrainStatusResponse { drain_status })
+    }
+
+    async fn tx_pool_command(
+        &self,
+        command: TxPoolCommand,
+    ) -> Result<RpcTxPoolResponse, RpcTxPoolError> {
+        let tx_hashes =
+            self.client_addr.send(command.with_span_context()).await.map_err(RpcFrom::rpc_from)?;
+        Ok(RpcTxPoolResponse { tx_hashes })
+    }
+
     async fn query(
         &self,
         request_data: near_jsonrpc_primitives::types::query::RpcQueryRequest,
@@ -854,10 +1113,37 @@ impl JsonRpcHandler {
         near_jsonrpc_primitives::types::transactions::RpcTransactionResponse,
         near_jsonrpc_primitives::types::transactions::RpcTransactionError,
     > {
-        let tx_status = self.tx_status_fetch(request_data.transaction_info, fetch_receipt).await?;
+        let tx_status = self
+            .tx_status_fetch(
+                request_data.transaction_info,
+                fetch_receipt,
+                request_data.outcome_proofs,
+                request_data.min_finality,
+            )
+            .await?;
         Ok(tx_status.rpc_into())
     }
 
+    async fn tx_fork_status(
+        &self,
+        request_data: near_jsonrpc_primitives::types::transactions::RpcTransactionStatusCommonRequest,
+    ) -> Result<
+        near_jsonrpc_primitives::types::transactions::RpcTransactionForkStatusResponse,
+        near_jsonrpc_primitives::types::transactions::RpcTransactionError,
+    > {
+        let (tx_hash, signer_account_id) = match request_data.transaction_info {
+            near_jsonrpc_primitives::types::transactions::TransactionInfo::Transaction(tx) => {
+                (tx.get_hash(), tx.transaction.signer_id)
+            }
+            near_jsonrpc_primitives::types::transactions::TransactionInfo::TransactionId {
+                hash,
+                account_id,
+            } => (hash, account_id),
+        };
+        let tx_fork_status = self.client_send(TxForkStatus { tx_hash, signer_account_id }).await?;
+        Ok(tx_fork_status.rpc_into())
+    }
+
     async fn block(
         &self,
         request_data: near_jsonrpc_primitives::types::blocks::RpcBlockRequest,
@@ -881,6 +1167,23 @@ impl JsonRpcHandler {
         Ok(near_jsonrpc_primitives::types::chunks::RpcChunkResponse { chunk_view })
     }
 
+    async fn outgoing_receipt_proofs(
+        &self,
+        request_data: near_jsonrpc_primitives::types::chunks::RpcOutgoingReceiptProofsRequest,
+    ) -> Result<
+        near_jsonrpc_primitives::types::chunks::RpcOutgoingReceiptProofsResponse,
+        near_jsonrpc_primitives::types::chunks::RpcChunkError,
+    > {
+        let receipt_proofs = self
+            .view_client_send(GetOutgoingReceiptProofs {
+                chunk: GetChunk::rpc_from(request_data.chunk_reference),
+            })
+            .await?;
+        Ok(near_jsonrpc_primitives::types::chunks::RpcOutgoingReceiptProofsResponse {
+            receipt_proofs,
+        })
+    }
+
     async fn receipt(
         &self,
         request_data: near_jsonrpc_primitives::types::receipts::RpcReceiptRequest,
@@ -946,6 +1249,35 @@ impl JsonRpcHandler {
         })
     }
 
+    async fn trie_diff(
+        &self,
+        request: near_jsonrpc_primitives::types::changes::RpcTrieDiffRequest,
+    ) -> Result<
+        near_jsonrpc_primitives::types::changes::RpcTrieDiffResponse,
+        near_jsonrpc_primitives::types::changes::RpcTrieDiffError,
+    > {
+        let from_block: near_primitives::views::BlockView = self
+            .view_client_send(GetBlock(near_primitives::types::BlockReference::BlockId(
+                request.from_block_id,
+            )))
+            .await?;
+        let to_block: near_primitives::views::BlockView = self
+            .view_client_send(GetBlock(near_primitives::types::BlockReference::BlockId(
+                request.to_block_id,
+            )))
+            .await?;
+        let trie_diff = self
+            .view_client_send(GetTrieDiff {
+                shard_id: request.shard_id,
+                from_block_hash: from_block.header.hash,
+                to_block_hash: to_block.header.hash,
+                cursor: request.cursor,
+                limit: request.limit,
+            })
+            .await?;
+        Ok(near_jsonrpc_primitives::types::changes::RpcTrieDiffResponse { trie_diff })
+    }
+
     async fn next_light_client_block(
         &self,
         request: near_jsonrpc_primitives::types::light_client::RpcLightClientNextBlockRequest,
@@ -1011,6 +1343,64 @@ impl JsonRpcHandler {
         Ok(near_jsonrpc_primitives::types::gas_price::RpcGasPriceResponse { gas_price_view })
     }
 
+    async fn economics_series(
+        &self,
+        request_data: near_jsonrpc_primitives::types::gas_price::RpcEconomicsSeriesRequest,
+    ) -> Result<
+        near_jsonrpc_primitives::types::gas_price::RpcEconomicsSeriesResponse,
+        near_jsonrpc_primitives::types::gas_price::RpcEconomicsSeriesError,
+    > {
+        let series_view = self
+            .view_client_send(GetEconomicsSeries {
+                from_height: request_data.from_height,
+                to_height: request_data.to_height,
+                step: request_data.step,
+                limit: request_data.limit,
+            })
+            .await?;
+        Ok(near_jsonrpc_primitives::types::gas_price::RpcEconomicsSeriesResponse { series_view })
+    }
+
+    async fn contract_events(
+        &self,
+        request_data: near_jsonrpc_primitives::types::contract_events::RpcContractEventsRequest,
+    ) -> Result<
+        near_jsonrpc_primitives::types::contract_events::RpcContractEventsResponse,
+        near_jsonrpc_primitives::types::contract_index::RpcContractIndexError,
+    > {
+        let events_view = self
+            .view_client_send(GetContractEvents {
+                account_id: request_data.account_id,
+                standard: request_data.standard,
+                event: request_data.event,
+                cursor: request_data.cursor,
+                limit: request_data.limit,
+            })
+            .await?;
+        Ok(near_jsonrpc_primitives::types::contract_events::RpcContractEventsResponse {
+            events_view,
+        })
+    }
+
+    async fn contract_accounts(
+        &self,
+        request_data: near_jsonrpc_primitives::types::contract_accounts::RpcContractAccountsRequest,
+    ) -> Result<
+        near_jsonrpc_primitives::types::contract_accounts::RpcContractAccountsResponse,
+        near_jsonrpc_primitives::types::contract_index::RpcContractIndexError,
+    > {
+        let accounts_view = self
+            .view_client_send(GetContractAccounts {
+                code_hash: request_data.code_hash,
+                cursor: request_data.cursor,
+                limit: request_data.limit,
+            })
+            .await?;
+        Ok(near_jsonrpc_primitives::types::contract_accounts::RpcContractAccountsResponse {
+            accounts_view,
+        })
+    }
+
     async fn validators(
         &self,
         request_data: near_jsonrpc_primitives::types::validator::RpcValidatorRequest,
@@ -1040,6 +1430,52 @@ impl JsonRpcHandler {
         Ok(validators)
     }
 
+    /// Returns the block producer assigned to a height together with the data needed to
+    /// recompute the assignment.
+    async fn block_producer_proof(
+        &self,
+        request: near_jsonrpc_primitives::types::validator::RpcBlockProducerProofRequest,
+    ) -> Result<
+        near_jsonrpc_primitives::types::validator::RpcBlockProducerProofResponse,
+        near_jsonrpc_primitives::types::validator::RpcValidatorError,
+    > {
+        let proof =
+            self.view_client_send(GetBlockProducerProof { height: request.block_height }).await?;
+        Ok(near_jsonrpc_primitives::types::validator::RpcBlockProducerProofResponse { proof })
+    }
+
+    /// Returns the expected block producer and chunk producers of the heights
+    /// after the head.
+    async fn production_schedule(
+        &self,
+        request: near_jsonrpc_primitives::types::validator::RpcProductionScheduleRequest,
+    ) -> Result<
+        near_jsonrpc_primitives::types::validator::RpcProductionScheduleResponse,
+        near_jsonrpc_primitives::types::validator::RpcValidatorError,
+    > {
+        let schedule =
+            self.client_send(GetProductionSchedule { num_heights: request.num_heights }).await?;
+        Ok(near_jsonrpc_primitives::types::validator::RpcProductionScheduleResponse { schedule })
+    }
+
+    /// Returns the randomness of a block and of its epoch together with the data needed to
+    /// verify it.
+    async fn randomness(
+        &self,
+        request: near_jsonrpc_primitives::types::blocks::RpcRandomnessRequest,
+    ) -> Result<
+        near_jsonrpc_primitives::types::blocks::RpcRandomnessResponse,
+        near_jsonrpc_primitives::types::blocks::RpcBlockError,
+    > {
+        let randomness = self
+            .view_client_send(GetRandomness {
+                block_reference: request.block_reference,
+                lineage_length: request.lineage_length,
+            })
+            .await?;
+        Ok(near_jsonrpc_primitives::types::blocks::RpcRandomnessResponse { randomness })
+    }
+
     /// If experimental_debug_pages_src_path config is set, reads the html file from that
     /// directory. Otherwise, returns None.
     fn read_html_file_override(&self, html_file: &'static str) -> Option<String> {
@@ -1283,14 +1719,71 @@ impl JsonRpcHandler {
 fn rpc_handler(
     message: web::Json<Message>,
     handler: web::Data<JsonRpcHandler>,
+    http_request: HttpRequest,
 ) -> impl Future<Output = Result<HttpResponse, HttpError>> {
     let response = async move {
+        // Held until the request is processed.
+        let _permit = match (&handler.rate_limiter, &message.0) {
+            (Some(rate_limiter), Message::Request(request)) => {
+                let client = rate_limiter.client(&http_request);
+                match rate_limiter.acquire(&client, request, Instant::now()) {
+                    Ok(permit) => permit,
+                    Err(err) => return Ok(rate_limited_response(request, err)),
+                }
+            }
+            _ => None,
+        };
+        if let Message::Request(request) = &message.0 {
+            if let Some(permission) = auth::method_permission(&request.method) {
+                if let Err(err) =
+                    handler.authorizer.authorize(&http_request, &request.method, permission)
+                {
+                    return Ok(auth_error_response(Some(request), err));
+                }
+            }
+        }
         let message = handler.process(message.0).await?;
         Ok(HttpResponse::Ok().json(&message))
     };
     response.boxed()
 }
 
+/// Returns the error for a privileged call rejected by the authorization with the 401 or 403
+/// status code.
+fn auth_error_response(
+    request: Option<&Request>,
+    error: near_jsonrpc_primitives::types::auth::RpcAuthError,
+) -> HttpResponse {
+    let mut response = match error {
+        near_jsonrpc_primitives::types::auth::RpcAuthError::Unauthorized { .. } => {
+            HttpResponse::Unauthorized()
+        }
+        near_jsonrpc_primitives::types::auth::RpcAuthError::Forbidden { .. } => {
+            HttpResponse::Forbidden()
+        }
+    };
+    match request {
+        Some(request) => response.json(&request.error(error.into())),
+        None => response.json(&Message::error(error.into())),
+    }
+}
+
+/// Returns the error for a request rejected by the rate limits with the 429 status code.
+fn rate_limited_response(
+    request: &Request,
+    error: near_jsonrpc_primitives::types::rate_limit::RpcRateLimitError,
+) -> HttpResponse {
+    let mut response = HttpResponse::TooManyRequests();
+    if let near_jsonrpc_primitives::types::rate_limit::RpcRateLimitError::RateLimitExceeded {
+        retry_after_ms,
+        ..
+    } = &error
+    {
+        response.insert_header((header::RETRY_AFTER, (retry_after_ms + 999) / 1000));
+    }
+    response.json(&request.error(error.into()))
+}
+
 fn status_handler(
     handler: web::Data<JsonRpcHandler>,
 ) -> impl Future<Output = Result<HttpResponse, HttpError>> {
@@ -1309,6 +1802,9 @@ async fn debug_handler(
     req: HttpRequest,
     handler: web::Data<JsonRpcHandler>,
 ) -> Result<HttpResponse, HttpError> {
+    if let Err(err) = handler.authorizer.authorize(&req, req.path(), RpcPermission::Debug) {
+        return Ok(auth_error_response(None, err));
+    }
     if req.path() == "/debug/api/status" {
         // This is a temporary workaround - as we migrate the debug information to the separate class below.
         return match handler.old_debug().await {
@@ -1325,9 +1821,13 @@ async fn debug_handler(
 }
 
 async fn debug_block_status_handler(
+    req: HttpRequest,
     path: web::Path<u64>,
     handler: web::Data<JsonRpcHandler>,
 ) -> Result<HttpResponse, HttpError> {
+    if let Err(err) = handler.authorizer.authorize(&req, req.path(), RpcPermission::Debug) {
+        return Ok(auth_error_response(None, err));
+    }
     match handler.debug_block_status(Some(*path)).await {
         Ok(Some(value)) => Ok(HttpResponse::Ok().json(&value)),
         Ok(None) => Ok(HttpResponse::MethodNotAllowed().finish()),
@@ -1347,6 +1847,32 @@ fn health_handler(
     response.boxed()
 }
 
+/// Answers a readiness or liveness probe: 200 if the verdict holds and 503
+/// otherwise, with both verdicts and the reasons they fail in the body.  A
+/// client too busy to answer fails the probe as well.
+async fn health_verdict_response(
+    handler: web::Data<JsonRpcHandler>,
+    verdict: fn(&near_primitives::views::HealthView) -> bool,
+) -> Result<HttpResponse, HttpError> {
+    match handler.health_verdicts().await {
+        Ok(health) if verdict(&health) => Ok(HttpResponse::Ok().json(&health)),
+        Ok(health) => Ok(HttpResponse::ServiceUnavailable().json(&health)),
+        Err(_) => Ok(HttpResponse::ServiceUnavailable().finish()),
+    }
+}
+
+fn health_ready_handler(
+    handler: web::Data<JsonRpcHandler>,
+) -> impl Future<Output = Result<HttpResponse, HttpError>> {
+    health_verdict_response(handler, |health| health.ready).boxed()
+}
+
+fn health_live_handler(
+    handler: web::Data<JsonRpcHandler>,
+) -> impl Future<Output = Result<HttpResponse, HttpError>> {
+    health_verdict_response(handler, |health| health.live).boxed()
+}
+
 fn network_info_handler(
     handler: web::Data<JsonRpcHandler>,
 ) -> impl Future<Output = Result<HttpResponse, HttpError>> {
@@ -1364,7 +1890,7 @@ pub async fn prometheus_handler() -> Result<HttpResponse, HttpError> {
 
     let mut buffer = vec![];
     let encoder = TextEncoder::new();
-    encoder.encode(&prometheus::gather(), &mut buffer).unwrap();
+    encoder.encode(&near_o11y::metrics::gather(), &mut buffer).unwrap();
 
     match String::from_utf8(buffer) {
         Ok(text) => Ok(HttpResponse::Ok().body(text)),
@@ -1452,7 +1978,11 @@ pub fn start_http(
         limits_config,
         enable_debug_rpc,
         experimental_debug_pages_src_path: debug_pages_src_path,
+        rate_limits_config,
+        api_keys,
     } = config;
+    let rate_limiter = rate_limits_config.map(|config| Arc::new(RateLimiter::new(config)));
+    let authorizer = Arc::new(Authorizer::new(api_keys));
     let prometheus_addr = prometheus_addr.filter(|it| it != &addr);
     let cors_allowed_origins_clone = cors_allowed_origins.clone();
     info!(target:"network", "Starting http server at {}", addr);
@@ -1468,6 +1998,8 @@ pub fn start_http(
                 genesis_config: genesis_config.clone(),
                 enable_debug_rpc,
                 debug_pages_src_path: debug_pages_src_path.clone().map(Into::into),
+                rate_limiter: rate_limiter.clone(),
+                authorizer: authorizer.clone(),
             }))
             .app_data(web::JsonConfig::default().limit(limits_config.json_payload_max_size))
             .wrap(middleware::Logger::default())
@@ -1482,6 +2014,16 @@ pub fn start_http(
                     .route(web::get().to(health_handler))
                     .route(web::head().to(health_handler)),
             )
+            .service(
+                web::resource("/health/ready")
+                    .route(web::get().to(health_ready_handler))
+                    .route(web::head().to(health_ready_handler)),
+            )
+            .service(
+                web::resource("/health/live")
+                    .route(web::get().to(health_live_handler))
+                    .route(web::head().to(health_live_handler)),
+            )
             .service(web::resource("/network_info").route(web::get().to(network_info_handler)))
             .service(web::resource("/metrics").route(web::get().to(prometheus_handler)))
             .service(web::resource("/debug/api/{api}").route(web::get().to(debug_handler)))
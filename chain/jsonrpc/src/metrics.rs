@@ -55,3 +55,27 @@ pub static RPC_UNREACHABLE_ERROR_COUNT: Lazy<IntCounterVec> = Lazy::new(|| {
     )
     .unwrap()
 });
+pub static RPC_RATE_LIMIT_TOKENS_SPENT: Lazy<IntCounterVec> = Lazy::new(|| {
+    near_o11y::metrics::try_create_int_counter_vec(
+        "near_rpc_rate_limit_tokens_spent_total",
+        "Total number of rate limit tokens spent by requests, by method and client class",
+        &["method", "client_class"],
+    )
+    .unwrap()
+});
+pub static RPC_RATE_LIMITED_COUNT: Lazy<IntCounterVec> = Lazy::new(|| {
+    near_o11y::metrics::try_create_int_counter_vec(
+        "near_rpc_rate_limited_total",
+        "Total count of requests rejected by rate limits, by method, client class and reason",
+        &["method", "client_class", "reason"],
+    )
+    .unwrap()
+});
+pub static RPC_PRIVILEGED_CALL_COUNT: Lazy<IntCounterVec> = Lazy::new(|| {
+    near_o11y::metrics::try_create_int_counter_vec(
+        "near_rpc_privileged_calls_total",
+        "Total count of privileged calls, by the permission they require and whether they were allowed",
+        &["permission", "outcome"],
+    )
+    .unwrap()
+});
@@ -0,0 +1,364 @@
+//! Rate limits for nodes serving RPC to the public.
+//!
+//! Every client has a bucket of tokens which refills at a constant rate, and
+//! every request takes as many tokens as its method costs.  Requests of a
+//! client whose bucket doesn't hold enough tokens are rejected, telling when
+//! it will hold enough of them.  Clients are identified by an API key passed
+//! in the `x-api-key` header, which also selects the class of limits applying
+//! to the client, or else by their IP address.  On top of that, the number of
+//! requests of heavy methods processed at the same time is limited across all
+//! clients.
+use std::collections::HashMap;
+use std::net::{IpAddr, Ipv4Addr};
+use std::sync::{Arc, Mutex};
+use std::time::Instant;
+
+use actix_web::HttpRequest;
+use near_cache::{TokenBucketLimit, TokenBuckets};
+use near_jsonrpc_primitives::message::Request;
+use near_jsonrpc_primitives::types::rate_limit::RpcRateLimitError;
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use tokio::sync::{OwnedSemaphorePermit, Semaphore};
+
+use crate::metrics;
+
+/// Header with the API key of the client.
+const API_KEY_HEADER: &str = "x-api-key";
+
+/// Class of the clients identified by their IP address.
+const ANONYMOUS_CLASS: &str = "anonymous";
+
+/// Label of the methods without a cost in the metrics.
+const OTHER_METHOD: &str = "OTHER";
+
+/// Cost of `query` calling a contract's view function.
+const QUERY_CALL_FUNCTION: &str = "query_call_function";
+
+/// Number of clients whose buckets are kept.  The bucket of the client seen
+/// least recently is dropped to make room for a new one.
+const MAX_TRACKED_CLIENTS: usize = 65_536;
+
+/// Costs of methods not configured in `method_costs`.  Other methods cost 1.
+const DEFAULT_METHOD_COSTS: &[(&str, u32)] = &[
+    ("block", 2),
+    ("broadcast_tx_async", 2),
+    ("broadcast_tx_commit", 5),
+    ("chunk", 2),
+    ("light_client_proof", 5),
+    ("next_light_client_block", 2),
+    ("query", 2),
+    (QUERY_CALL_FUNCTION, 10),
+    ("tx", 2),
+    ("validators", 2),
+    ("EXPERIMENTAL_block_producer_proof", 2),
+    ("EXPERIMENTAL_broadcast_tx_deferred", 2),
+    ("EXPERIMENTAL_broadcast_tx_sync", 2),
+    ("EXPERIMENTAL_changes", 5),
+    ("EXPERIMENTAL_changes_in_block", 5),
+    ("EXPERIMENTAL_check_tx", 2),
+    ("EXPERIMENTAL_contract_accounts", 10),
+    ("EXPERIMENTAL_contract_events", 10),
+    ("EXPERIMENTAL_economics_series", 10),
+    ("EXPERIMENTAL_light_client_proof", 5),
+    ("EXPERIMENTAL_outgoing_receipt_proofs", 5),
+    ("EXPERIMENTAL_production_schedule", 5),
+    ("EXPERIMENTAL_randomness", 5),
+    ("EXPERIMENTAL_receipt", 2),
+    ("EXPERIMENTAL_runtime_parameters_diff", 2),
+    ("EXPERIMENTAL_trie_diff", 10),
+    ("EXPERIMENTAL_tx_fork_status", 2),
+    ("EXPERIMENTAL_tx_status", 2),
+    ("EXPERIMENTAL_validators_ordered", 2),
+];
+
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, PartialEq, Eq)]
+pub struct RpcRateLimit {
+    /// Number of tokens added to the bucket of a client every second.
+    pub tokens_per_second: u32,
+    /// Number of tokens the bucket of a client holds, i.e. how many tokens a
+    /// client can spend at once after being idle.
+    pub burst: u32,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug)]
+#[serde(default)]
+pub struct RpcRateLimitsConfig {
+    /// Limit of each client without an API key, by IP address.
+    pub anonymous: RpcRateLimit,
+    /// Limits of each client with an API key, by class.
+    pub classes: HashMap<String, RpcRateLimit>,
+    /// Known API keys with the classes of their clients.  Clients with other
+    /// keys are limited by IP address.
+    pub api_keys: HashMap<String, String>,
+    /// Costs of methods in tokens, overriding the default costs.  The cost of
+    /// `query` calling a view function is configured as `query_call_function`.
+    pub method_costs: HashMap<String, u32>,
+    /// Methods costing at least this many tokens are heavy.
+    pub heavy_method_cost: u32,
+    /// Maximum number of requests of heavy methods processed at the same time.
+    pub max_concurrent_heavy_requests: usize,
+}
+
+impl RpcRateLimitsConfig {
+    /// Checks that every client can call at least some methods.
+    pub fn validate(&self) -> Result<(), String> {
+        if self.anonymous.burst == 0 {
+            return Err("rate_limits_config.anonymous.burst must be positive".to_string());
+        }
+        for (class, limit) in &self.classes {
+            if limit.burst == 0 {
+                return Err(format!("rate_limits_config.classes.{class}.burst must be positive"));
+            }
+        }
+        Ok(())
+    }
+}
+
+impl From<RpcRateLimit> for TokenBucketLimit {
+    fn from(limit: RpcRateLimit) -> Self {
+        Self { tokens_per_second: limit.tokens_per_second as f64, burst: limit.burst as f64 }
+    }
+}
+
+impl Default for RpcRateLimitsConfig {
+    fn default() -> Self {
+        Self {
+            anonymous: RpcRateLimit { tokens_per_second: 20, burst: 100 },
+            classes: HashMap::new(),
+            api_keys: HashMap::new(),
+            method_costs: HashMap::new(),
+            heavy_method_cost: 10,
+            max_concurrent_heavy_requests: 32,
+        }
+    }
+}
+
+/// Identity of a client the limits apply to.
+#[derive(Clone, Debug, PartialEq, Eq, Hash)]
+pub(crate) enum RpcClient {
+    Ip(IpAddr),
+    ApiKey(String),
+}
+
+pub(crate) struct RateLimiter {
+    config: RpcRateLimitsConfig,
+    method_costs: HashMap<String, u32>,
+    buckets: Mutex<TokenBuckets<RpcClient>>,
+    heavy_requests: Arc<Semaphore>,
+}
+
+impl RateLimiter {
+    pub fn new(config: RpcRateLimitsConfig) -> Self {
+        let mut method_costs: HashMap<String, u32> =
+            DEFAULT_METHOD_COSTS.iter().map(|(method, cost)| (method.to_string(), *cost)).collect();
+        method_costs
+            .extend(config.method_costs.iter().map(|(method, cost)| (method.clone(), *cost)));
+        let heavy_requests = Arc::new(Semaphore::new(config.max_concurrent_heavy_requests));
+        Self {
+            config,
+            method_costs,
+            buckets: Mutex::new(TokenBuckets::new(MAX_TRACKED_CLIENTS)),
+            heavy_requests,
+        }
+    }
+
+    /// Identifies the client which sent `request`.
+    pub fn client(&self, request: &HttpRequest) -> RpcClient {
+        if let Some(api_key) = request.headers().get(API_KEY_HEADER) {
+            if let Ok(api_key) = api_key.to_str() {
+                if self.config.api_keys.contains_key(api_key) {
+                    return RpcClient::ApiKey(api_key.to_string());
+                }
+            }
+        }
+        let ip = request.peer_addr().map_or(IpAddr::V4(Ipv4Addr::UNSPECIFIED), |addr| addr.ip());
+        RpcClient::Ip(ip)
+    }
+
+    fn class(&self, client: &RpcClient) -> (&str, RpcRateLimit) {
+        if let RpcClient::ApiKey(api_key) = client {
+            if let Some(class) = self.config.api_keys.get(api_key) {
+                if let Some(limit) = self.config.classes.get(class) {
+                    return (class, *limit);
+                }
+            }
+        }
+        (ANONYMOUS_CLASS, self.config.anonymous)
+    }
+
+    /// Returns the name the method is known as in the costs, or None if it
+    /// doesn't have a cost, together with its cost.
+    fn method_cost(&self, request: &Request) -> (Option<&str>, u32) {
+        let mut method = request.method.as_str();
+        if method == "query" && is_call_function(&request.params) {
+            method = QUERY_CALL_FUNCTION;
+        }
+        match self.method_costs.get_key_value(method) {
+            Some((method, cost)) => (Some(method.as_str()), *cost),
+            None => (None, 1),
+        }
+    }
+
+    /// Takes the tokens for `request` from the bucket of `client`.  For heavy
+    /// methods returns a permit which has to be held while the request is
+    /// processed.
+    pub fn acquire(
+        &self,
+        client: &RpcClient,
+        request: &Request,
+        now: Instant,
+    ) -> Result<Option<OwnedSemaphorePermit>, RpcRateLimitError> {
+        let (class, limit) = self.class(client);
+        let (method, cost) = self.method_cost(request);
+        let method_label = method.unwrap_or(OTHER_METHOD);
+        let rejected = |reason: &str| {
+            metrics::RPC_RATE_LIMITED_COUNT.with_label_values(&[method_label, class, reason]).inc();
+        };
+
+        let mut buckets = self.buckets.lock().unwrap();
+        let bucket = buckets.get(client, limit.into(), now);
+        // A method costing more than the burst can still be called once the
+        // bucket is full.
+        let needed = cost.min(limit.burst) as f64;
+        if bucket.tokens() < needed {
+            rejected("rate_limit");
+            let retry_after =
+                TokenBucketLimit::from(limit).time_to_refill(needed - bucket.tokens());
+            return Err(RpcRateLimitError::RateLimitExceeded {
+                client_class: class.to_string(),
+                method_name: request.method.clone(),
+                method_cost: cost,
+                retry_after_ms: retry_after.as_millis().try_into().unwrap_or(u64::MAX),
+            });
+        }
+        let permit = if cost >= self.config.heavy_method_cost {
+            match self.heavy_requests.clone().try_acquire_owned() {
+                Ok(permit) => Some(permit),
+                Err(_) => {
+                    rejected("concurrency");
+                    return Err(RpcRateLimitError::TooManyConcurrentRequests {
+                        method_name: request.method.clone(),
+                    });
+                }
+            }
+        } else {
+            None
+        };
+        bucket.take(needed);
+        metrics::RPC_RATE_LIMIT_TOKENS_SPENT
+            .with_label_values(&[method_label, class])
+            .inc_by(cost.into());
+        Ok(permit)
+    }
+}
+
+/// Whether the params of `query` ask to call a view function, either in the
+/// structured or in the legacy form.
+fn is_call_function(params: &Option<Value>) -> bool {
+    match params {
+        Some(Value::Object(params)) => {
+            params.get("request_type").and_then(Value::as_str) == Some("call_function")
+        }
+        Some(Value::Array(params)) => {
+            params.first().and_then(Value::as_str).map_or(false, |path| path.starts_with("call/"))
+        }
+        _ => false,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{RateLimiter, RpcClient, RpcRateLimit, RpcRateLimitsConfig};
+    use near_jsonrpc_primitives::message::{Message, Request};
+    use near_jsonrpc_primitives::types::rate_limit::RpcRateLimitError;
+    use serde_json::json;
+    use std::net::{IpAddr, Ipv4Addr};
+    use std::time::{Duration, Instant};
+
+    fn request(method: &str, params: serde_json::Value) -> Request {
+        match Message::request(method.to_string(), Some(params)) {
+            Message::Request(request) => request,
+            _ => unreachable!(),
+        }
+    }
+
+    fn config() -> RpcRateLimitsConfig {
+        RpcRateLimitsConfig {
+            anonymous: RpcRateLimit { tokens_per_second: 10, burst: 20 },
+            classes: [("partner".to_string(), RpcRateLimit { tokens_per_second: 100, burst: 200 })]
+                .into_iter()
+                .collect(),
+            api_keys: [("secret".to_string(), "partner".to_string())].into_iter().collect(),
+            max_concurrent_heavy_requests: 1,
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn test_token_bucket() {
+        let limiter = RateLimiter::new(config());
+        let client = RpcClient::Ip(IpAddr::V4(Ipv4Addr::LOCALHOST));
+        let other = RpcClient::Ip(IpAddr::V4(Ipv4Addr::BROADCAST));
+        let gas_price = request("gas_price", json!([null]));
+        let view_account = request("query", json!({"request_type": "view_account"}));
+        let now = Instant::now();
+
+        for _ in 0..10 {
+            limiter.acquire(&client, &view_account, now).unwrap();
+        }
+        match limiter.acquire(&client, &gas_price, now) {
+            Err(RpcRateLimitError::RateLimitExceeded { client_class, retry_after_ms, .. }) => {
+                assert_eq!(client_class, "anonymous");
+                assert_eq!(retry_after_ms, 100);
+            }
+            result => panic!("unexpected result {:?}", result),
+        }
+        // Other clients have their own buckets.
+        limiter.acquire(&other, &gas_price, now).unwrap();
+        // The bucket refills over time.
+        limiter.acquire(&client, &gas_price, now + Duration::from_millis(100)).unwrap();
+        assert!(limiter.acquire(&client, &gas_price, now + Duration::from_millis(100)).is_err());
+
+        // Clients with an API key have the limits of their class.
+        let partner = RpcClient::ApiKey("secret".to_string());
+        for _ in 0..100 {
+            limiter.acquire(&partner, &view_account, now).unwrap();
+        }
+        assert!(limiter.acquire(&partner, &view_account, now).is_err());
+    }
+
+    #[test]
+    fn test_validate() {
+        config().validate().unwrap();
+        let mut zero_burst = config();
+        zero_burst.anonymous.burst = 0;
+        assert!(zero_burst.validate().is_err());
+        let mut zero_burst = config();
+        zero_burst.classes.get_mut("partner").unwrap().burst = 0;
+        assert!(zero_burst.validate().is_err());
+    }
+
+    #[test]
+    fn test_heavy_methods() {
+        let limiter = RateLimiter::new(config());
+        let client = RpcClient::ApiKey("secret".to_string());
+        let call_function = request("query", json!({"request_type": "call_function"}));
+        let legacy_call_function = request("query", json!(["call/contract/method", ""]));
+        let view_account = request("query", json!(["account/contract", ""]));
+        let now = Instant::now();
+
+        let permit = limiter.acquire(&client, &call_function, now).unwrap();
+        assert!(permit.is_some());
+        match limiter.acquire(&client, &legacy_call_function, now) {
+            Err(RpcRateLimitError::TooManyConcurrentRequests { method_name }) => {
+                assert_eq!(method_name, "query");
+            }
+            result => panic!("unexpected result {:?}", result),
+        }
+        // Light methods aren't limited by the concurrency.
+        assert!(limiter.acquire(&client, &view_account, now).unwrap().is_none());
+        drop(permit);
+        assert!(limiter.acquire(&client, &legacy_call_function, now).unwrap().is_some());
+    }
+}
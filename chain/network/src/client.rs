@@ -1,4 +1,5 @@
 use crate::network_protocol::{
+    BlockHeaderAnnouncement, ChunkInclusionFeedbackMsg, PartialEncodedChunkAvailabilityMsg,
     PartialEncodedChunkForwardMsg, PartialEncodedChunkRequestMsg, PartialEncodedChunkResponseMsg,
     StateResponseInfo,
 };
@@ -8,6 +9,7 @@ use near_primitives::challenge::Challenge;
 use near_primitives::hash::CryptoHash;
 use near_primitives::network::{AnnounceAccount, PeerId};
 use near_primitives::sharding::PartialEncodedChunk;
+use near_primitives::syncing::{EpochSyncDataResponse, EpochSyncProofsResponse};
 use near_primitives::transaction::SignedTransaction;
 use near_primitives::types::{AccountId, EpochId, ShardId};
 use near_primitives::views::FinalExecutionOutcomeView;
@@ -25,6 +27,17 @@ pub trait Client: Send + Sync + 'static {
 
     async fn tx_status_response(&self, tx_result: FinalExecutionOutcomeView);
 
+    /// Subscribes `subscriber` to the final outcome of a transaction.  Returns
+    /// the outcome if it's already known, otherwise it's pushed to `route_back`
+    /// once it is.
+    async fn tx_status_subscribe(
+        &self,
+        account_id: AccountId,
+        tx_hash: CryptoHash,
+        route_back: CryptoHash,
+        subscriber: PeerId,
+    ) -> Option<Box<FinalExecutionOutcomeView>>;
+
     async fn state_request_header(
         &self,
         shard_id: ShardId,
@@ -38,8 +51,29 @@ pub trait Client: Send + Sync + 'static {
         part_id: u64,
     ) -> Result<Option<StateResponseInfo>, ReasonForBan>;
 
+    async fn state_request_sub_part(
+        &self,
+        shard_id: ShardId,
+        sync_hash: CryptoHash,
+        part_id: u64,
+        sub_part_id: u64,
+        sub_part_size: u64,
+    ) -> Result<Option<StateResponseInfo>, ReasonForBan>;
+
     async fn state_response(&self, info: StateResponseInfo);
 
+    async fn epoch_sync_data_request(
+        &self,
+        block_hash: CryptoHash,
+    ) -> Result<Option<EpochSyncDataResponse>, ReasonForBan>;
+
+    async fn epoch_sync_proofs_request(
+        &self,
+        block_hash: CryptoHash,
+    ) -> Result<Option<EpochSyncProofsResponse>, ReasonForBan>;
+
+    async fn epoch_sync_proofs_response(&self, response: EpochSyncProofsResponse, peer_id: PeerId);
+
     async fn block_approval(&self, approval: Approval, peer_id: PeerId);
 
     async fn transaction(&self, transaction: SignedTransaction, is_forwarded: bool);
@@ -60,12 +94,24 @@ pub trait Client: Send + Sync + 'static {
 
     async fn partial_encoded_chunk_forward(&self, msg: PartialEncodedChunkForwardMsg);
 
+    async fn partial_encoded_chunk_availability(&self, msg: PartialEncodedChunkAvailabilityMsg);
+
+    async fn chunk_inclusion_feedback(&self, msg: ChunkInclusionFeedbackMsg);
+
     async fn block_request(&self, hash: CryptoHash) -> Option<Box<Block>>;
 
     async fn block_headers_request(&self, hashes: Vec<CryptoHash>) -> Option<Vec<BlockHeader>>;
 
     async fn block(&self, block: Block, peer_id: PeerId, was_requested: bool);
 
+    /// Processes a block announced by its header.  The client reconstructs
+    /// the block from the chunk headers it has, or requests it from the peer.
+    async fn block_header_announcement(
+        &self,
+        announcement: BlockHeaderAnnouncement,
+        peer_id: PeerId,
+    );
+
     async fn block_headers(
         &self,
         headers: Vec<BlockHeader>,
@@ -97,6 +143,16 @@ impl Client for Noop {
 
     async fn tx_status_response(&self, _tx_result: FinalExecutionOutcomeView) {}
 
+    async fn tx_status_subscribe(
+        &self,
+        _account_id: AccountId,
+        _tx_hash: CryptoHash,
+        _route_back: CryptoHash,
+        _subscriber: PeerId,
+    ) -> Option<Box<FinalExecutionOutcomeView>> {
+        None
+    }
+
     async fn state_request_header(
         &self,
         _shard_id: ShardId,
@@ -114,7 +170,40 @@ impl Client for Noop {
         Ok(None)
     }
 
+    async fn state_request_sub_part(
+        &self,
+        _shard_id: ShardId,
+        _sync_hash: CryptoHash,
+        _part_id: u64,
+        _sub_part_id: u64,
+        _sub_part_size: u64,
+    ) -> Result<Option<StateResponseInfo>, ReasonForBan> {
+        Ok(None)
+    }
+
     async fn state_response(&self, _info: StateResponseInfo) {}
+
+    async fn epoch_sync_data_request(
+        &self,
+        _block_hash: CryptoHash,
+    ) -> Result<Option<EpochSyncDataResponse>, ReasonForBan> {
+        Ok(None)
+    }
+
+    async fn epoch_sync_proofs_request(
+        &self,
+        _block_hash: CryptoHash,
+    ) -> Result<Option<EpochSyncProofsResponse>, ReasonForBan> {
+        Ok(None)
+    }
+
+    async fn epoch_sync_proofs_response(
+        &self,
+        _response: EpochSyncProofsResponse,
+        _peer_id: PeerId,
+    ) {
+    }
+
     async fn block_approval(&self, _approval: Approval, _peer_id: PeerId) {}
 
     async fn transaction(&self, _transaction: SignedTransaction, _is_forwarded: bool) {}
@@ -137,6 +226,10 @@ impl Client for Noop {
 
     async fn partial_encoded_chunk_forward(&self, _msg: PartialEncodedChunkForwardMsg) {}
 
+    async fn partial_encoded_chunk_availability(&self, _msg: PartialEncodedChunkAvailabilityMsg) {}
+
+    async fn chunk_inclusion_feedback(&self, _msg: ChunkInclusionFeedbackMsg) {}
+
     async fn block_request(&self, _hash: CryptoHash) -> Option<Box<Block>> {
         None
     }
@@ -147,6 +240,13 @@ impl Client for Noop {
 
     async fn block(&self, _block: Block, _peer_id: PeerId, _was_requested: bool) {}
 
+    async fn block_header_announcement(
+        &self,
+        _announcement: BlockHeaderAnnouncement,
+        _peer_id: PeerId,
+    ) {
+    }
+
     async fn block_headers(
         &self,
         _headers: Vec<BlockHeader>,
@@ -1,9 +1,16 @@
 use crate::blacklist;
 use crate::concurrency::demux;
+use crate::network_protocol::Compression;
 use crate::network_protocol::PeerAddr;
 use crate::network_protocol::PeerInfo;
+use crate::peer::liveness;
+use crate::peer_manager::audit_log;
+use crate::peer_manager::peer_affinity;
 use crate::peer_manager::peer_manager_actor::Event;
+use crate::peer_manager::peer_scores;
 use crate::peer_manager::peer_store;
+use crate::peer_manager::sync_requests;
+use crate::routing::tombstones;
 use crate::sink::Sink;
 use crate::time;
 use crate::types::ROUTED_MESSAGE_TTL;
@@ -90,6 +97,18 @@ pub struct NetworkConfig {
     pub max_send_peers: u32,
     /// Duration for checking on stats from the peers.
     pub peer_stats_period: time::Duration,
+    /// Idle time after which TCP keepalive probes are sent on a connection.
+    pub tcp_keepalive: Option<time::Duration>,
+    /// Algorithm compressing large messages sent to peers which support it.
+    pub compression: Option<Compression>,
+    /// Application-level liveness check of connections.
+    pub peer_liveness: liveness::Config,
+    /// Audit trail of the decisions to ban or disconnect peers.
+    pub peer_audit_log: audit_log::Config,
+    /// Scores deciding when misbehaving peers get banned.
+    pub peer_scores: peer_scores::Config,
+    /// Peers the node always tries to stay connected to.
+    pub peer_affinity: peer_affinity::Config,
     /// Time to persist Accounts Id in the router without removing them.
     pub ttl_account_id_router: time::Duration,
     /// Number of hops a message is allowed to travel before being dropped.
@@ -115,15 +134,21 @@ pub struct NetworkConfig {
     pub archive: bool,
     /// Maximal rate at which SyncAccountsData can be broadcasted.
     pub accounts_data_broadcast_rate_limit: demux::RateLimit,
+    /// Limits on serving block and header requests of peers.  None serves
+    /// them without limits.
+    pub sync_requests: Option<sync_requests::Config>,
+    /// Partial encoded chunk requests are relayed through a directly connected
+    /// validator if there's no route to the part owner, or if the round trip
+    /// time to the first hop of the route exceeds this.  None disables relaying.
+    pub chunk_request_relay_rtt_threshold: Option<time::Duration>,
     /// features
     pub features: Features,
-
-    // Whether to ignore tombstones some time after startup.
-    //
-    // Ignoring tombstones means:
-    //   * not broadcasting deleted edges
-    //   * ignoring received deleted edges as well
-    pub skip_tombstones: Option<time::Duration>,
+    /// Aging of edge tombstones.
+    pub tombstones: tombstones::Config,
+    /// Id of the metrics instance the network reports to, see
+    /// `near_o11y::metrics::MetricsRegistry::instance`.  None reports to the
+    /// global registry.
+    pub metrics_instance: Option<String>,
 
     /// TEST-ONLY
     /// TODO(gprusak): make it pub(crate), once all integration tests
@@ -208,6 +233,27 @@ impl NetworkConfig {
             archival_peer_connections_lower_bound: cfg.archival_peer_connections_lower_bound,
             max_send_peers: 512,
             peer_stats_period: cfg.peer_stats_period.try_into()?,
+            tcp_keepalive: cfg.tcp_keepalive.map(|d| d.try_into()).transpose()?,
+            compression: cfg.compression,
+            peer_liveness: liveness::Config {
+                min_interval: cfg.peer_liveness_min_interval.try_into()?,
+                max_interval: cfg.peer_liveness_max_interval.try_into()?,
+                timeout: cfg.peer_liveness_timeout.try_into()?,
+            },
+            peer_audit_log: audit_log::Config {
+                capacity: cfg.peer_audit_log_capacity,
+                export_path: cfg.peer_audit_log_export_path,
+            },
+            peer_scores: peer_scores::Config {
+                max: cfg.peer_score_max,
+                ban_threshold: cfg.peer_score_ban_threshold,
+                reward: cfg.peer_score_reward,
+            },
+            peer_affinity: peer_affinity::Config {
+                preferred_peers: cfg.peer_affinity.preferred_peers,
+                min_backoff: cfg.peer_affinity.min_reconnect_backoff.try_into()?,
+                max_backoff: cfg.peer_affinity.max_reconnect_backoff.try_into()?,
+            },
             ttl_account_id_router: cfg.ttl_account_id_router.try_into()?,
             routed_message_ttl: ROUTED_MESSAGE_TTL,
             max_routes_to_store: MAX_ROUTES_TO_STORE,
@@ -216,13 +262,28 @@ impl NetworkConfig {
             outbound_disabled: false,
             archive,
             accounts_data_broadcast_rate_limit: demux::RateLimit { qps: 0.1, burst: 1 },
+            sync_requests: cfg.experimental.sync_requests_limits.map(|limits| {
+                sync_requests::Config {
+                    rate_limit: demux::RateLimit {
+                        qps: limits.requests_per_second,
+                        burst: limits.burst,
+                    },
+                    max_concurrent: limits.max_concurrent,
+                    max_queued_per_peer: limits.max_queued_per_peer,
+                }
+            }),
+            chunk_request_relay_rtt_threshold: cfg
+                .experimental
+                .chunk_request_relay_rtt_threshold
+                .map(|d| d.try_into())
+                .transpose()?,
             features,
             inbound_disabled: cfg.experimental.inbound_disabled,
-            skip_tombstones: if cfg.experimental.skip_sending_tombstones_seconds > 0 {
-                Some(time::Duration::seconds(cfg.experimental.skip_sending_tombstones_seconds))
-            } else {
-                None
+            tombstones: tombstones::Config {
+                ttl: cfg.tombstone_ttl.try_into()?,
+                load_expired_on_restart: cfg.load_expired_tombstones_on_restart,
             },
+            metrics_instance: None,
             event_sink: Sink::null(),
         };
         Ok(this)
@@ -271,6 +332,20 @@ impl NetworkConfig {
             archival_peer_connections_lower_bound: 10,
             max_send_peers: 512,
             peer_stats_period: time::Duration::seconds(5),
+            tcp_keepalive: Some(time::Duration::seconds(60)),
+            compression: None,
+            peer_liveness: liveness::Config {
+                min_interval: time::Duration::seconds(5),
+                max_interval: time::Duration::seconds(30),
+                timeout: time::Duration::seconds(120),
+            },
+            peer_audit_log: audit_log::Config { capacity: 100, export_path: None },
+            peer_scores: peer_scores::Config { max: 100, ban_threshold: 0, reward: 1 },
+            peer_affinity: peer_affinity::Config {
+                preferred_peers: vec![],
+                min_backoff: time::Duration::seconds(1),
+                max_backoff: time::Duration::seconds(60),
+            },
             ttl_account_id_router: time::Duration::seconds(60 * 60),
             routed_message_ttl: ROUTED_MESSAGE_TTL,
             max_routes_to_store: 1,
@@ -280,8 +355,14 @@ impl NetworkConfig {
             inbound_disabled: false,
             archive: false,
             accounts_data_broadcast_rate_limit: demux::RateLimit { qps: 100., burst: 1000000 },
+            sync_requests: None,
+            chunk_request_relay_rtt_threshold: None,
             features: Features { enable_tier1: true },
-            skip_tombstones: None,
+            tombstones: tombstones::Config {
+                ttl: time::Duration::minutes(10),
+                load_expired_on_restart: false,
+            },
+            metrics_instance: None,
             event_sink: Sink::null(),
         }
     }
@@ -316,9 +397,50 @@ impl NetworkConfig {
                 self.peer_recent_time_window, UPDATE_INTERVAL_LAST_TIME_RECEIVED_MESSAGE
             );
         }
+        if !(self.peer_liveness.min_interval <= self.peer_liveness.max_interval
+            && self.peer_liveness.max_interval < self.peer_liveness.timeout)
+        {
+            anyhow::bail!(
+                "Invalid peer liveness values. Expected min_interval({}) <= max_interval({}) < timeout({}).",
+                self.peer_liveness.min_interval,
+                self.peer_liveness.max_interval,
+                self.peer_liveness.timeout
+            );
+        }
+        if !self.peer_liveness.min_interval.is_positive() {
+            anyhow::bail!("peer_liveness_min_interval must be positive.");
+        }
+        if self.peer_scores.ban_threshold >= self.peer_scores.max {
+            anyhow::bail!(
+                "peer_score_ban_threshold({}) has to be lower than peer_score_max({}).",
+                self.peer_scores.ban_threshold,
+                self.peer_scores.max
+            );
+        }
+        if self.peer_scores.reward < 0 {
+            anyhow::bail!("peer_score_reward must not be negative.");
+        }
+        if !(self.peer_affinity.min_backoff.is_positive()
+            && self.peer_affinity.min_backoff <= self.peer_affinity.max_backoff)
+        {
+            anyhow::bail!(
+                "Invalid peer_affinity backoff values. Expected 0 < min_reconnect_backoff({}) <= max_reconnect_backoff({}).",
+                self.peer_affinity.min_backoff,
+                self.peer_affinity.max_backoff
+            );
+        }
+        if self.peer_affinity.preferred_peers.iter().any(|peer| peer.peer_id == self.node_id()) {
+            anyhow::bail!("peer_affinity.preferred_peers must not contain this node.");
+        }
         self.accounts_data_broadcast_rate_limit
             .validate()
             .context("accounts_Data_broadcast_rate_limit")?;
+        if let Some(sync_requests) = &self.sync_requests {
+            sync_requests.rate_limit.validate().context("sync_requests_limits")?;
+            if sync_requests.max_concurrent == 0 {
+                anyhow::bail!("sync_requests_limits.max_concurrent has to be >0");
+            }
+        }
         Ok(VerifiedConfig { node_id: self.node_id(), inner: self })
     }
 }
@@ -1,5 +1,6 @@
-use crate::network_protocol::PeerAddr;
+use crate::network_protocol::{Compression, PeerAddr};
 use serde::{Deserialize, Serialize};
+use std::path::PathBuf;
 use std::time::Duration;
 
 /// Time to persist Accounts Id in the router without removing them in seconds.
@@ -48,20 +49,57 @@ fn default_monitor_peers_max_period() -> Duration {
     Duration::from_secs(60)
 }
 
+/// Idle time after which TCP keepalive probes are sent on a connection.
+fn default_tcp_keepalive() -> Option<Duration> {
+    Some(Duration::from_secs(60))
+}
+/// Shortest interval between liveness pings on an idle connection.
+fn default_peer_liveness_min_interval() -> Duration {
+    Duration::from_secs(5)
+}
+/// Interval between liveness pings on an idle connection to a responsive peer.
+fn default_peer_liveness_max_interval() -> Duration {
+    Duration::from_secs(30)
+}
+/// Close connections on which nothing has been received for this amount of time.
+fn default_peer_liveness_timeout() -> Duration {
+    Duration::from_secs(120)
+}
+
+/// Number of the most recent peer ban and disconnect decisions to keep.
+fn default_peer_audit_log_capacity() -> usize {
+    1000
+}
+
+/// Score of well-behaved peers, lowered by every misbehavior.
+fn default_peer_score_max() -> i32 {
+    100
+}
+/// Score at which misbehaving peers get banned.
+fn default_peer_score_ban_threshold() -> i32 {
+    0
+}
+/// Recovery of the score for every valid block or timely response.
+fn default_peer_score_reward() -> i32 {
+    1
+}
+
 /// Remove peers that we didn't hear about for this amount of time.
 fn default_peer_expiration_duration() -> Duration {
     Duration::from_secs(7 * 24 * 60 * 60)
 }
 
-// If non-zero - we'll skip sending tombstones during initial sync and for that many seconds after start.
-fn default_skip_tombstones() -> i64 {
-    // Enable by default in shardnet only.
-    if cfg!(feature = "shardnet") {
-        // Skip sending tombstones during sync and 240 seconds after start.
-        240
-    } else {
-        0
-    }
+/// Delay between the first two attempts to reconnect to a preferred peer.
+fn default_preferred_peer_min_backoff() -> Duration {
+    Duration::from_secs(1)
+}
+/// Longest delay between attempts to reconnect to a preferred peer.
+fn default_preferred_peer_max_backoff() -> Duration {
+    Duration::from_secs(60)
+}
+
+fn default_tombstone_ttl() -> Duration {
+    Duration::from_secs(10 * 60)
 }
 
 #[derive(Serialize, Deserialize, Clone, Debug)]
@@ -127,6 +165,53 @@ pub struct Config {
     // Remove peers that were not active for this amount of time.
     #[serde(default = "default_peer_expiration_duration")]
     pub peer_expiration_duration: Duration,
+    /// Idle time after which TCP keepalive probes are sent on a connection.
+    /// `null` disables TCP keepalive.
+    #[serde(default = "default_tcp_keepalive")]
+    pub tcp_keepalive: Option<Duration>,
+    /// Shortest interval between liveness pings on an idle connection, used
+    /// while pings stay unanswered.
+    #[serde(default = "default_peer_liveness_min_interval")]
+    pub peer_liveness_min_interval: Duration,
+    /// Interval between liveness pings on an idle connection to a responsive peer.
+    #[serde(default = "default_peer_liveness_max_interval")]
+    pub peer_liveness_max_interval: Duration,
+    /// Close connections on which nothing has been received for this amount of time.
+    #[serde(default = "default_peer_liveness_timeout")]
+    pub peer_liveness_timeout: Duration,
+    /// Number of the most recent peer ban and disconnect decisions kept in
+    /// the audit log.  Zero disables the audit log.
+    #[serde(default = "default_peer_audit_log_capacity")]
+    pub peer_audit_log_capacity: usize,
+    /// File to which entries of the audit log are appended as JSON lines.
+    #[serde(default)]
+    pub peer_audit_log_export_path: Option<PathBuf>,
+    /// Score of peers which didn't misbehave.  Misbehaving peers lose points
+    /// and recover them with good behavior, such as sending valid blocks.
+    #[serde(default = "default_peer_score_max")]
+    pub peer_score_max: i32,
+    /// Peers are banned once their score drops to this.
+    #[serde(default = "default_peer_score_ban_threshold")]
+    pub peer_score_ban_threshold: i32,
+    /// Points recovered for every valid block or timely response.
+    #[serde(default = "default_peer_score_reward")]
+    pub peer_score_reward: i32,
+    /// Tombstones of edges, which mark connections as removed, older than
+    /// this are neither sent to nor accepted from peers.
+    #[serde(default = "default_tombstone_ttl")]
+    pub tombstone_ttl: Duration,
+    /// Whether tombstones older than `tombstone_ttl`, persisted before a
+    /// restart, are loaded back into the routing table on startup.
+    #[serde(default)]
+    pub load_expired_tombstones_on_restart: bool,
+    /// Algorithm compressing large messages (blocks, chunks and state parts)
+    /// sent to peers which support it, either "gzip" or "zstd".  `null`
+    /// disables compression.
+    #[serde(default)]
+    pub compression: Option<Compression>,
+    /// Peers the node always tries to stay connected to.
+    #[serde(default)]
+    pub peer_affinity: PeerAffinityConfig,
 
     /// List of the public addresses (in the format "<node public key>@<IP>:<port>") of trusted nodes,
     /// which are willing to route messages to this node. Useful only if this node is a validator.
@@ -180,14 +265,15 @@ pub struct ExperimentalConfig {
     // If true - connect only to the boot nodes.
     #[serde(default)]
     pub connect_only_to_boot_nodes: bool,
-
-    // If greater than 0, then system will no longer send or receive tombstones
-    // during sync and during that many seconds after startup.
-    //
-    // The better name is `skip_tombstones_seconds`, but we keep send for
-    // compatibility.
-    #[serde(default = "default_skip_tombstones")]
-    pub skip_sending_tombstones_seconds: i64,
+    // Limits on serving the BlockRequest and BlockHeadersRequest messages of peers, meant for
+    // archival nodes overwhelmed by syncing peers. If not set, requests are served without limits.
+    #[serde(default)]
+    pub sync_requests_limits: Option<SyncRequestsLimitsConfig>,
+    // If set, requests for chunk parts are relayed through a directly connected validator when
+    // there's no route to the part owner or the round trip time to the first hop of the route
+    // exceeds this threshold.
+    #[serde(default)]
+    pub chunk_request_relay_rtt_threshold: Option<Duration>,
 }
 
 impl Default for ExperimentalConfig {
@@ -195,11 +281,55 @@ impl Default for ExperimentalConfig {
         ExperimentalConfig {
             inbound_disabled: false,
             connect_only_to_boot_nodes: false,
-            skip_sending_tombstones_seconds: default_skip_tombstones(),
+            sync_requests_limits: None,
+            chunk_request_relay_rtt_threshold: None,
         }
     }
 }
 
+#[derive(Serialize, Deserialize, Clone, Debug)]
+pub struct PeerAffinityConfig {
+    /// Peers, in the format "<node public key>@<IP>:<port>", which the node
+    /// dials whenever they aren't connected and never disconnects to make
+    /// room for other peers.  Meant for validator pairs and private meshes.
+    ///
+    /// Example:
+    ///   ["ed25519:86EtEy7epneKyrcJwSWP7zsisTkfDRH5CFVszt4qiQYw@31.192.22.209:24567"]
+    #[serde(default)]
+    pub preferred_peers: Vec<PeerAddr>,
+    /// Delay between the first two attempts to reconnect to a preferred peer.
+    /// It doubles with every failed attempt.
+    #[serde(default = "default_preferred_peer_min_backoff")]
+    pub min_reconnect_backoff: Duration,
+    /// Longest delay between attempts to reconnect to a preferred peer.
+    #[serde(default = "default_preferred_peer_max_backoff")]
+    pub max_reconnect_backoff: Duration,
+}
+
+impl Default for PeerAffinityConfig {
+    fn default() -> Self {
+        PeerAffinityConfig {
+            preferred_peers: vec![],
+            min_reconnect_backoff: default_preferred_peer_min_backoff(),
+            max_reconnect_backoff: default_preferred_peer_max_backoff(),
+        }
+    }
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug)]
+pub struct SyncRequestsLimitsConfig {
+    /// Number of requests of a single peer served per second.
+    pub requests_per_second: f64,
+    /// Number of requests a single peer can send at once after being idle.
+    pub burst: u64,
+    /// Maximum number of requests served at the same time.  The others wait
+    /// and the peers take turns.
+    pub max_concurrent: usize,
+    /// Maximum number of requests of a single peer waiting to be served.
+    /// Further requests are dropped.
+    pub max_queued_per_peer: usize,
+}
+
 impl Default for Config {
     fn default() -> Self {
         Config {
@@ -221,6 +351,19 @@ impl Default for Config {
             peer_stats_period: default_peer_stats_period(),
             monitor_peers_max_period: default_monitor_peers_max_period(),
             peer_expiration_duration: default_peer_expiration_duration(),
+            tcp_keepalive: default_tcp_keepalive(),
+            peer_liveness_min_interval: default_peer_liveness_min_interval(),
+            peer_liveness_max_interval: default_peer_liveness_max_interval(),
+            peer_liveness_timeout: default_peer_liveness_timeout(),
+            peer_audit_log_capacity: default_peer_audit_log_capacity(),
+            peer_audit_log_export_path: None,
+            peer_score_max: default_peer_score_max(),
+            peer_score_ban_threshold: default_peer_score_ban_threshold(),
+            peer_score_reward: default_peer_score_reward(),
+            tombstone_ttl: default_tombstone_ttl(),
+            load_expired_tombstones_on_restart: false,
+            compression: None,
+            peer_affinity: Default::default(),
             public_addrs: vec![],
             trusted_stun_servers: vec![],
             experimental: Default::default(),
@@ -1,14 +1,16 @@
 use ::actix::Message;
-use near_primitives::views::PeerStoreView;
+use near_primitives::views::{PeerAuditLogView, PeerStoreView};
 
 // Different debug requests that can be sent by HTML pages, via GET.
 pub enum GetDebugStatus {
     PeerStore,
+    PeerAuditLog,
 }
 
 #[derive(actix::MessageResponse, Debug)]
 pub enum DebugStatus {
     PeerStore(PeerStoreView),
+    PeerAuditLog(PeerAuditLogView),
 }
 
 impl Message for GetDebugStatus {
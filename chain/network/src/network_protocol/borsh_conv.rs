@@ -14,6 +14,10 @@ impl From<&net::Handshake> for mem::Handshake {
             sender_listen_port: x.sender_listen_port,
             sender_chain_info: x.sender_chain_info.clone(),
             partial_edge_info: x.partial_edge_info.clone(),
+            // Not supported by the borsh encoding.
+            state_sub_part_size_limit: 0,
+            compressions: vec![],
+            header_first_blocks: false,
         }
     }
 }
@@ -171,6 +175,16 @@ impl From<&mem::PeerMessage> for net::PeerMessage {
             mem::PeerMessage::BlockHeaders(bhs) => net::PeerMessage::BlockHeaders(bhs),
             mem::PeerMessage::BlockRequest(bh) => net::PeerMessage::BlockRequest(bh),
             mem::PeerMessage::Block(b) => net::PeerMessage::Block(b),
+            // This message is not supported, we translate it to an empty RoutingTableUpdate.
+            // It's never sent over borsh anyway, as borsh doesn't support
+            // `Handshake::header_first_blocks`.
+            mem::PeerMessage::BlockHeaderAnnouncement(_) => {
+                net::PeerMessage::SyncRoutingTable(net::RoutingTableUpdate::default())
+            }
+            // This message is not supported, we translate it to an empty RoutingTableUpdate.
+            mem::PeerMessage::MaintenanceWindow(_) => {
+                net::PeerMessage::SyncRoutingTable(net::RoutingTableUpdate::default())
+            }
             mem::PeerMessage::Transaction(t) => net::PeerMessage::Transaction(t),
             mem::PeerMessage::Routed(r) => net::PeerMessage::Routed(Box::new(r.msg.clone())),
             mem::PeerMessage::Disconnect => net::PeerMessage::Disconnect,
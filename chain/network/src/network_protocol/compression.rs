@@ -0,0 +1,133 @@
+//! Compression of large peer messages.
+//!
+//! Blocks, chunks and state parts are large and compress well, so peers may
+//! agree during the handshake to compress them: each peer advertises the
+//! algorithms it supports in `Handshake::compressions` and, if its own
+//! configured algorithm is among the ones advertised by the other peer, sends
+//! messages with `Encoding::CompressedProto` once the handshake is complete.
+//!
+//! A compressed message is the proto encoding of the message, prefixed with a
+//! byte telling how the rest of the message is compressed, if at all.  Small
+//! messages aren't worth compressing and are sent as they are.
+use bytesize::MIB;
+use serde::{Deserialize, Serialize};
+use std::borrow::Cow;
+use std::io::{self, Read as _, Write as _};
+
+/// Algorithm compressing large peer messages.
+#[derive(Copy, Clone, PartialEq, Eq, Debug, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Compression {
+    Gzip,
+    Zstd,
+}
+
+impl Compression {
+    /// Algorithms supported by this node, advertised during the handshake.
+    pub(crate) const ALL: [Compression; 2] = [Compression::Gzip, Compression::Zstd];
+
+    fn tag(self) -> u8 {
+        match self {
+            Compression::Gzip => GZIP_TAG,
+            Compression::Zstd => ZSTD_TAG,
+        }
+    }
+}
+
+/// Messages smaller than this are sent uncompressed.
+pub(crate) const MIN_COMPRESSED_SIZE: usize = 16 * 1024;
+/// Largest size of a decompressed message, the same as the largest size of a
+/// message on the wire.
+const MAX_DECOMPRESSED_SIZE: usize = 512 * MIB as usize;
+/// Largest ratio between the sizes of a decompressed message and of its
+/// compressed payload.  Real messages compress far less than that, while
+/// without a bound a message of a few hundred KiB could decompress to
+/// `MAX_DECOMPRESSED_SIZE`.
+const MAX_COMPRESSION_RATIO: usize = 100;
+
+const UNCOMPRESSED_TAG: u8 = 0;
+const GZIP_TAG: u8 = 1;
+const ZSTD_TAG: u8 = 2;
+
+/// Prefixes `data` with the tag of `compression` and compresses it with it.
+pub(crate) fn encode(compression: Option<Compression>, data: &[u8]) -> Vec<u8> {
+    let mut out = vec![compression.map_or(UNCOMPRESSED_TAG, Compression::tag)];
+    // Writing to a Vec doesn't fail.
+    match compression {
+        None => out.extend_from_slice(data),
+        Some(Compression::Gzip) => {
+            let mut encoder = flate2::write::GzEncoder::new(out, flate2::Compression::fast());
+            encoder.write_all(data).unwrap();
+            out = encoder.finish().unwrap();
+        }
+        Some(Compression::Zstd) => zstd::stream::copy_encode(data, &mut out, 0).unwrap(),
+    }
+    out
+}
+
+/// Decodes data produced by `encode`.  Fails if the data decompresses to more
+/// than `MAX_DECOMPRESSED_SIZE` bytes or to more than `MAX_COMPRESSION_RATIO`
+/// times its size, so that a peer can't exhaust our memory with a small
+/// message.
+pub(crate) fn decode(data: &[u8]) -> io::Result<Cow<'_, [u8]>> {
+    decode_with_limits(data, MAX_DECOMPRESSED_SIZE, MAX_COMPRESSION_RATIO)
+}
+
+fn decode_with_limits(data: &[u8], max_size: usize, max_ratio: usize) -> io::Result<Cow<'_, [u8]>> {
+    let invalid = |msg: String| io::Error::new(io::ErrorKind::InvalidData, msg);
+    let (tag, payload) = data.split_first().ok_or_else(|| invalid("empty message".into()))?;
+    let max_size = max_size.min(payload.len().saturating_mul(max_ratio));
+    let decoder: Box<dyn io::Read + '_> = match *tag {
+        UNCOMPRESSED_TAG => return Ok(Cow::Borrowed(payload)),
+        GZIP_TAG => Box::new(flate2::read::GzDecoder::new(payload)),
+        ZSTD_TAG => Box::new(zstd::stream::read::Decoder::new(payload)?),
+        tag => return Err(invalid(format!("unknown compression tag {tag}"))),
+    };
+    let mut out = vec![];
+    decoder.take(max_size as u64 + 1).read_to_end(&mut out)?;
+    if out.len() > max_size {
+        return Err(invalid(format!("message decompresses to more than {max_size} bytes")));
+    }
+    Ok(Cow::Owned(out))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{decode, decode_with_limits, encode, Compression};
+
+    #[test]
+    fn test_roundtrip() {
+        let data: String = (0..10_000).map(|i| format!("block {i} ")).collect();
+        let data = data.as_bytes();
+        for compression in [None, Some(Compression::Gzip), Some(Compression::Zstd)] {
+            let encoded = encode(compression, data);
+            if compression.is_some() {
+                assert!(encoded.len() < data.len() / 2);
+            }
+            assert_eq!(decode(&encoded).unwrap(), data);
+        }
+        assert!(decode(&[]).is_err());
+        assert!(decode(&[3, 1, 2]).is_err());
+    }
+
+    #[test]
+    fn test_decompression_limit() {
+        let data = vec![0; 1000];
+        for compression in Compression::ALL {
+            let encoded = encode(Some(compression), &data);
+            assert!(decode_with_limits(&encoded, 1000, 1000).is_ok());
+            assert!(decode_with_limits(&encoded, 999, 1000).is_err());
+        }
+    }
+
+    #[test]
+    fn test_compression_ratio_limit() {
+        // Zeros compress far better than real messages.
+        let data = vec![0; 1 << 20];
+        for compression in Compression::ALL {
+            let encoded = encode(Some(compression), &data);
+            assert!(decode_with_limits(&encoded, data.len(), data.len()).is_ok());
+            assert!(decode(&encoded).is_err());
+        }
+    }
+}
@@ -2,9 +2,11 @@
 #[path = "borsh.rs"]
 mod borsh_;
 mod borsh_conv;
+mod compression;
 mod edge;
 mod peer;
 mod proto_conv;
+pub use compression::Compression;
 pub use edge::*;
 pub use peer::*;
 
@@ -23,7 +25,7 @@ use crate::time;
 use borsh::{BorshDeserialize as _, BorshSerialize as _};
 use near_crypto::PublicKey;
 use near_crypto::Signature;
-use near_primitives::block::{Approval, Block, BlockHeader, GenesisId};
+use near_primitives::block::{Approval, Block, BlockHeader, BlockV2, GenesisId};
 use near_primitives::challenge::Challenge;
 use near_primitives::hash::CryptoHash;
 use near_primitives::merkle::combine_hash;
@@ -31,11 +33,15 @@ use near_primitives::network::{AnnounceAccount, PeerId};
 use near_primitives::sharding::{
     ChunkHash, PartialEncodedChunk, PartialEncodedChunkPart, ReceiptProof, ShardChunkHeader,
 };
-use near_primitives::syncing::{ShardStateSyncResponse, ShardStateSyncResponseV1};
+use near_primitives::syncing::{
+    EpochSyncDataResponse, EpochSyncProofsResponse, ShardStateSyncResponse,
+    ShardStateSyncResponseV1,
+};
 use near_primitives::transaction::SignedTransaction;
 use near_primitives::types::{AccountId, EpochId};
 use near_primitives::types::{BlockHeight, ShardId};
 use near_primitives::validator_signer::ValidatorSigner;
+use near_primitives::version::ProtocolVersion;
 use near_primitives::views::FinalExecutionOutcomeView;
 use protobuf::Message as _;
 use std::collections::HashSet;
@@ -209,6 +215,13 @@ pub struct Handshake {
     pub(crate) sender_chain_info: PeerChainInfoV2,
     /// Represents new `edge`. Contains only `none` and `Signature` from the sender.
     pub(crate) partial_edge_info: PartialEdgeInfo,
+    /// Largest size of the state sub-parts the sender serves, 0 if unknown.
+    pub(crate) state_sub_part_size_limit: u64,
+    /// Algorithms the sender can decompress messages with.
+    pub(crate) compressions: Vec<Compression>,
+    /// Whether the sender accepts blocks announced by their header, see
+    /// `BlockHeaderAnnouncement`.
+    pub(crate) header_first_blocks: bool,
 }
 
 #[derive(PartialEq, Eq, Clone, Debug, strum::IntoStaticStr)]
@@ -226,6 +239,62 @@ pub struct SyncAccountsData {
     pub incremental: bool,
 }
 
+/// Block broadcast without its chunk headers, which the receiver most likely
+/// has already, from the chunks it received or the previous block.  Receivers
+/// which can't reconstruct the block request it in full.
+#[derive(PartialEq, Eq, Clone, Debug, borsh::BorshSerialize, borsh::BorshDeserialize)]
+pub struct BlockHeaderAnnouncement {
+    pub header: BlockHeader,
+    /// Hashes of the chunks of the block, by shard.
+    pub chunk_hashes: Vec<ChunkHash>,
+    pub vrf_value: near_crypto::vrf::Value,
+    pub vrf_proof: near_crypto::vrf::Proof,
+}
+
+impl BlockHeaderAnnouncement {
+    /// Returns the announcement of the block, None if the block can't be
+    /// announced, because it has challenges or an old version.
+    pub fn from_block(block: &Block) -> Option<Self> {
+        match block {
+            Block::BlockV2(block) if block.challenges.is_empty() => Some(Self {
+                header: block.header.clone(),
+                chunk_hashes: block.chunks.iter().map(|chunk| chunk.chunk_hash()).collect(),
+                vrf_value: block.vrf_value,
+                vrf_proof: block.vrf_proof,
+            }),
+            _ => None,
+        }
+    }
+
+    /// Returns the announced block with the given chunk headers, which the
+    /// caller has to check against the block header.
+    pub fn into_block(self, chunks: Vec<ShardChunkHeader>) -> Block {
+        Block::BlockV2(Arc::new(BlockV2 {
+            header: self.header,
+            chunks,
+            challenges: vec![],
+            vrf_value: self.vrf_value,
+            vrf_proof: self.vrf_proof,
+        }))
+    }
+}
+
+/// Block heights of a planned restart of the node: from `start_height` on the
+/// node stops producing and forwards its transactions, at `shutdown_height`
+/// it shuts down.  Peers avoid syncing from a node in maintenance.
+#[derive(PartialEq, Eq, Clone, Copy, Debug, borsh::BorshSerialize, borsh::BorshDeserialize)]
+pub struct MaintenanceWindow {
+    pub start_height: BlockHeight,
+    pub shutdown_height: BlockHeight,
+}
+
+impl MaintenanceWindow {
+    /// Whether the window has started at the given height.
+    pub fn is_active(&self, height: BlockHeight) -> bool {
+        height >= self.start_height
+    }
+}
+
 #[derive(PartialEq, Eq, Clone, Debug, strum::IntoStaticStr, strum::EnumVariantNames)]
 #[allow(clippy::large_enum_variant)]
 pub enum PeerMessage {
@@ -248,6 +317,11 @@ pub enum PeerMessage {
 
     BlockRequest(CryptoHash),
     Block(Block),
+    /// Only sent to peers which advertised `Handshake::header_first_blocks`.
+    BlockHeaderAnnouncement(BlockHeaderAnnouncement),
+    /// Announces the maintenance window of the sender, None if it has been
+    /// cancelled.
+    MaintenanceWindow(Option<MaintenanceWindow>),
 
     Transaction(SignedTransaction),
     Routed(Box<RoutedMessageV2>),
@@ -267,6 +341,8 @@ impl fmt::Display for PeerMessage {
 pub enum Encoding {
     Borsh,
     Proto,
+    /// Proto, with large messages compressed, see `compression`.
+    CompressedProto(Compression),
 }
 
 #[derive(thiserror::Error, Debug)]
@@ -279,6 +355,8 @@ pub enum ParsePeerMessageError {
     ProtoDecode(#[source] protobuf::Error),
     #[error("ProtoConv")]
     ProtoConv(#[source] proto_conv::ParsePeerMessageError),
+    #[error("Decompress")]
+    Decompress(#[source] std::io::Error),
 }
 
 impl PeerMessage {
@@ -286,6 +364,12 @@ impl PeerMessage {
         match enc {
             Encoding::Borsh => borsh_::PeerMessage::from(self).try_to_vec().unwrap(),
             Encoding::Proto => proto::PeerMessage::from(self).write_to_bytes().unwrap(),
+            Encoding::CompressedProto(c) => {
+                let data = self.serialize(Encoding::Proto);
+                let compress =
+                    self.is_compressible() && data.len() >= compression::MIN_COMPRESSED_SIZE;
+                compression::encode(if compress { Some(c) } else { None }, &data)
+            }
         }
     }
 
@@ -302,9 +386,30 @@ impl PeerMessage {
                 .map_err(ParsePeerMessageError::ProtoDecode)?)
                 .try_into()
                 .map_err(ParsePeerMessageError::ProtoConv)?,
+            Encoding::CompressedProto(_) => {
+                let data = compression::decode(data).map_err(ParsePeerMessageError::Decompress)?;
+                Self::deserialize(Encoding::Proto, &data)?
+            }
         })
     }
 
+    /// Whether the message carries a block, a chunk or a state part, which are
+    /// worth compressing when large.
+    fn is_compressible(&self) -> bool {
+        match self {
+            PeerMessage::Block(_) => true,
+            PeerMessage::Routed(msg) => matches!(
+                msg.msg.body,
+                RoutedMessageBody::PartialEncodedChunkResponse(_)
+                    | RoutedMessageBody::VersionedPartialEncodedChunk(_)
+                    | RoutedMessageBody::PartialEncodedChunkForward(_)
+                    | RoutedMessageBody::StateResponse(_)
+                    | RoutedMessageBody::VersionedStateResponse(_)
+            ),
+            _ => false,
+        }
+    }
+
     pub(crate) fn msg_variant(&self) -> &'static str {
         match self {
             PeerMessage::Routed(routed_msg) => routed_msg.body_variant(),
@@ -313,6 +418,11 @@ impl PeerMessage {
     }
 }
 
+/// Protocol version starting from which peers know
+/// `RoutedMessageBody::TxStatusSubscribe`.  Older peers fail to decode it, so
+/// they are sent a `TxStatusRequest` instead.
+pub(crate) const TX_STATUS_SUBSCRIBE_PROTOCOL_VERSION: ProtocolVersion = 57;
+
 // TODO(#1313): Use Box
 #[derive(
     borsh::BorshSerialize, borsh::BorshDeserialize, PartialEq, Eq, Clone, strum::IntoStaticStr,
@@ -352,6 +462,25 @@ pub enum RoutedMessageBody {
     VersionedPartialEncodedChunk(PartialEncodedChunk),
     VersionedStateResponse(StateResponseInfo),
     PartialEncodedChunkForward(PartialEncodedChunkForwardMsg),
+    /// Request for a single sub-part of a state part:
+    /// (shard_id, sync_hash, part_id, sub_part_id, sub_part_size).
+    StateRequestSubPart(ShardId, CryptoHash, u64, u64, u64),
+    /// Request for the epoch sync data of the first block of an epoch.
+    EpochSyncDataRequest(CryptoHash),
+    EpochSyncDataResponse(EpochSyncDataResponse),
+    PartialEncodedChunkAvailability(PartialEncodedChunkAvailabilityMsg),
+    /// Subscription to the final outcome of a transaction: (signer_account_id,
+    /// tx_hash).  Answered right away with a TxStatusResponse if the outcome is
+    /// known, otherwise the response is pushed once it is.
+    TxStatusSubscribe(AccountId, CryptoHash),
+    /// Request for the proofs of the epochs following the epoch of the block.
+    EpochSyncProofsRequest(CryptoHash),
+    EpochSyncProofsResponse(EpochSyncProofsResponse),
+    ChunkInclusionFeedback(ChunkInclusionFeedbackMsg),
+    /// A `PartialEncodedChunkRequest` signed by the requester and addressed to the part owner,
+    /// which the recipient forwards on behalf of the requester.  Sent by requesters whose own
+    /// route to the part owner is missing or slow.  Relayed requests are never relayed again.
+    RelayedPartialEncodedChunkRequest(Box<RoutedMessage>),
 }
 
 impl RoutedMessageBody {
@@ -385,6 +514,9 @@ impl fmt::Debug for RoutedMessageBody {
             RoutedMessageBody::TxStatusResponse(response) => {
                 write!(f, "TxStatusResponse({})", response.transaction.hash)
             }
+            RoutedMessageBody::TxStatusSubscribe(account_id, hash) => {
+                write!(f, "TxStatusSubscribe({}, {})", account_id, hash)
+            }
             RoutedMessageBody::_UnusedQueryRequest => write!(f, "QueryRequest"),
             RoutedMessageBody::_UnusedQueryResponse => write!(f, "QueryResponse"),
             RoutedMessageBody::ReceiptOutcomeRequest(hash) => write!(f, "ReceiptRequest({})", hash),
@@ -395,9 +527,37 @@ impl fmt::Debug for RoutedMessageBody {
             RoutedMessageBody::StateRequestPart(shard_id, sync_hash, part_id) => {
                 write!(f, "StateRequestPart({}, {}, {})", shard_id, sync_hash, part_id)
             }
+            RoutedMessageBody::StateRequestSubPart(
+                shard_id,
+                sync_hash,
+                part_id,
+                sub_part_id,
+                sub_part_size,
+            ) => {
+                write!(
+                    f,
+                    "StateRequestSubPart({}, {}, {}, {}, {})",
+                    shard_id, sync_hash, part_id, sub_part_id, sub_part_size
+                )
+            }
             RoutedMessageBody::StateResponse(response) => {
                 write!(f, "StateResponse({}, {})", response.shard_id, response.sync_hash)
             }
+            RoutedMessageBody::EpochSyncDataRequest(block_hash) => {
+                write!(f, "EpochSyncDataRequest({})", block_hash)
+            }
+            RoutedMessageBody::EpochSyncDataResponse(response) => {
+                write!(f, "EpochSyncDataResponse({})", response.block_hash)
+            }
+            RoutedMessageBody::EpochSyncProofsRequest(block_hash) => {
+                write!(f, "EpochSyncProofsRequest({})", block_hash)
+            }
+            RoutedMessageBody::EpochSyncProofsResponse(response) => write!(
+                f,
+                "EpochSyncProofsResponse({}, {:?})",
+                response.block_hash,
+                response.proofs.as_ref().map(|proofs| proofs.len())
+            ),
             RoutedMessageBody::PartialEncodedChunkRequest(request) => {
                 write!(f, "PartialChunkRequest({:?}, {:?})", request.chunk_hash, request.part_ords)
             }
@@ -423,6 +583,19 @@ impl fmt::Debug for RoutedMessageBody {
                 forward.chunk_hash,
                 forward.parts.iter().map(|p| p.part_ord).collect::<Vec<_>>(),
             ),
+            RoutedMessageBody::PartialEncodedChunkAvailability(availability) => write!(
+                f,
+                "PartialChunkAvailability({:?}, {})",
+                availability.chunk_hash, availability.account_id,
+            ),
+            RoutedMessageBody::ChunkInclusionFeedback(feedback) => write!(
+                f,
+                "ChunkInclusionFeedback({:?}, {})",
+                feedback.chunk_hash, feedback.block_height,
+            ),
+            RoutedMessageBody::RelayedPartialEncodedChunkRequest(msg) => {
+                write!(f, "RelayedPartialChunkRequest({:?}, {:?})", msg.target, msg.body)
+            }
             RoutedMessageBody::Ping(_) => write!(f, "Ping"),
             RoutedMessageBody::Pong(_) => write!(f, "Pong"),
         }
@@ -504,8 +677,12 @@ impl RoutedMessage {
             self.body,
             RoutedMessageBody::Ping(_)
                 | RoutedMessageBody::TxStatusRequest(_, _)
+                | RoutedMessageBody::TxStatusSubscribe(_, _)
                 | RoutedMessageBody::StateRequestHeader(_, _)
                 | RoutedMessageBody::StateRequestPart(_, _, _)
+                | RoutedMessageBody::StateRequestSubPart(_, _, _, _, _)
+                | RoutedMessageBody::EpochSyncDataRequest(_)
+                | RoutedMessageBody::EpochSyncProofsRequest(_)
                 | RoutedMessageBody::PartialEncodedChunkRequest(_)
                 | RoutedMessageBody::ReceiptOutcomeRequest(_)
         )
@@ -543,6 +720,64 @@ pub struct PartialEncodedChunkForwardMsg {
     pub parts: Vec<PartialEncodedChunkPart>,
 }
 
+/// Message for validators tracking a shard to announce which parts of a chunk they have, so
+/// that other validators missing some parts can request them from a node which actually has
+/// them rather than from the assigned part owner, which may not have received them.
+#[derive(Clone, Debug, Eq, PartialEq, borsh::BorshSerialize, borsh::BorshDeserialize)]
+pub struct PartialEncodedChunkAvailabilityMsg {
+    pub chunk_hash: ChunkHash,
+    /// Account of the validator which has the parts.  Announcements are dropped on receipt
+    /// unless the routed message was signed by the peer the account is announced by.
+    pub account_id: AccountId,
+    /// Bitmap of the parts, bit `i % 8` of byte `i / 8` is set if part `i` is available.
+    pub parts: Vec<u8>,
+}
+
+impl PartialEncodedChunkAvailabilityMsg {
+    pub fn new(
+        chunk_hash: ChunkHash,
+        account_id: AccountId,
+        part_ords: impl IntoIterator<Item = u64>,
+    ) -> Self {
+        let mut parts = vec![];
+        for part_ord in part_ords {
+            let (byte, bit) = ((part_ord / 8) as usize, part_ord % 8);
+            if parts.len() <= byte {
+                parts.resize(byte + 1, 0);
+            }
+            parts[byte] |= 1 << bit;
+        }
+        Self { chunk_hash, account_id, parts }
+    }
+
+    pub fn has_part(&self, part_ord: u64) -> bool {
+        self.parts
+            .get((part_ord / 8) as usize)
+            .map_or(false, |byte| byte & (1 << (part_ord % 8)) != 0)
+    }
+}
+
+/// Message from a block producer to the producer of a chunk which became ready for inclusion
+/// only after the block producer had produced its block without the chunk.  Times are in
+/// milliseconds since the timestamp of the previous block, which both producers know.
+#[derive(Clone, Debug, Eq, PartialEq, borsh::BorshSerialize, borsh::BorshDeserialize)]
+pub struct ChunkInclusionFeedbackMsg {
+    pub chunk_hash: ChunkHash,
+    pub shard_id: ShardId,
+    pub height_created: BlockHeight,
+    /// Previous block of both the chunk and the block produced without it.
+    pub prev_block_hash: CryptoHash,
+    /// Height of the block produced without the chunk.
+    pub block_height: BlockHeight,
+    /// Producer of the block.  Feedback is dropped on receipt unless the routed message was
+    /// signed by the peer this account is announced by.
+    pub block_producer: AccountId,
+    /// When the block was produced, i.e. the deadline the chunk missed.
+    pub deadline_millis: u64,
+    /// When the chunk became ready for inclusion.
+    pub arrival_millis: u64,
+}
+
 /// Test code that someone become part of our protocol?
 #[derive(borsh::BorshSerialize, borsh::BorshDeserialize, PartialEq, Eq, Clone, Debug, Hash)]
 pub struct Ping {
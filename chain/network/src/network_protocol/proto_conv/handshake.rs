@@ -2,7 +2,7 @@
 use super::*;
 
 use crate::network_protocol::proto;
-use crate::network_protocol::{Handshake, HandshakeFailureReason};
+use crate::network_protocol::{Compression, Handshake, HandshakeFailureReason};
 use crate::network_protocol::{PeerChainInfoV2, PeerInfo};
 use near_primitives::block::GenesisId;
 use protobuf::MessageField as MF;
@@ -87,6 +87,19 @@ impl From<&Handshake> for proto::Handshake {
             sender_listen_port: x.sender_listen_port.unwrap_or(0).into(),
             sender_chain_info: MF::some((&x.sender_chain_info).into()),
             partial_edge_info: MF::some((&x.partial_edge_info).into()),
+            state_sub_part_size_limit: x.state_sub_part_size_limit,
+            compressions: x
+                .compressions
+                .iter()
+                .map(|c| {
+                    match c {
+                        Compression::Gzip => proto::handshake::Compression::GZIP,
+                        Compression::Zstd => proto::handshake::Compression::ZSTD,
+                    }
+                    .into()
+                })
+                .collect(),
+            header_first_blocks: x.header_first_blocks,
             ..Self::default()
         }
     }
@@ -115,6 +128,18 @@ impl TryFrom<&proto::Handshake> for Handshake {
                 .map_err(Self::Error::SenderChainInfo)?,
             partial_edge_info: try_from_required(&p.partial_edge_info)
                 .map_err(Self::Error::PartialEdgeInfo)?,
+            state_sub_part_size_limit: p.state_sub_part_size_limit,
+            // Algorithms unknown to this node are ignored.
+            compressions: p
+                .compressions
+                .iter()
+                .filter_map(|c| match c.enum_value() {
+                    Ok(proto::handshake::Compression::GZIP) => Some(Compression::Gzip),
+                    Ok(proto::handshake::Compression::ZSTD) => Some(Compression::Zstd),
+                    _ => None,
+                })
+                .collect(),
+            header_first_blocks: p.header_first_blocks,
         })
     }
 }
@@ -3,7 +3,9 @@ use super::*;
 
 use crate::network_protocol::proto;
 use crate::network_protocol::proto::peer_message::Message_type as ProtoMT;
-use crate::network_protocol::{PeerMessage, RoutingTableUpdate, SyncAccountsData};
+use crate::network_protocol::{
+    BlockHeaderAnnouncement, MaintenanceWindow, PeerMessage, RoutingTableUpdate, SyncAccountsData,
+};
 use crate::network_protocol::{RoutedMessage, RoutedMessageV2};
 use crate::time::error::ComponentRange;
 use borsh::{BorshDeserialize as _, BorshSerialize as _};
@@ -43,6 +45,24 @@ impl TryFrom<&proto::RoutingTableUpdate> for RoutingTableUpdate {
 
 //////////////////////////////////////////
 
+impl From<&MaintenanceWindow> for proto::MaintenanceWindow {
+    fn from(x: &MaintenanceWindow) -> Self {
+        Self {
+            start_height: x.start_height,
+            shutdown_height: x.shutdown_height,
+            ..Default::default()
+        }
+    }
+}
+
+impl From<&proto::MaintenanceWindow> for MaintenanceWindow {
+    fn from(x: &proto::MaintenanceWindow) -> Self {
+        Self { start_height: x.start_height, shutdown_height: x.shutdown_height }
+    }
+}
+
+//////////////////////////////////////////
+
 impl From<&BlockHeader> for proto::BlockHeader {
     fn from(x: &BlockHeader) -> Self {
         Self { borsh: x.try_to_vec().unwrap(), ..Default::default() }
@@ -139,6 +159,18 @@ impl From<&PeerMessage> for proto::PeerMessage {
                     block: MF::some(b.into()),
                     ..Default::default()
                 }),
+                PeerMessage::BlockHeaderAnnouncement(a) => {
+                    ProtoMT::BlockHeaderAnnouncement(proto::BlockHeaderAnnouncement {
+                        borsh: a.try_to_vec().unwrap(),
+                        ..Default::default()
+                    })
+                }
+                PeerMessage::MaintenanceWindow(w) => {
+                    ProtoMT::MaintenanceAnnouncement(proto::MaintenanceAnnouncement {
+                        window: MF::from_option(w.as_ref().map(Into::into)),
+                        ..Default::default()
+                    })
+                }
                 PeerMessage::Transaction(t) => ProtoMT::Transaction(proto::SignedTransaction {
                     borsh: t.try_to_vec().unwrap(),
                     ..Default::default()
@@ -159,6 +191,7 @@ impl From<&PeerMessage> for proto::PeerMessage {
     }
 }
 
+pub type ParseBlockHeaderAnnouncementError = borsh::maybestd::io::Error;
 pub type ParseTransactionError = borsh::maybestd::io::Error;
 pub type ParseRoutedError = borsh::maybestd::io::Error;
 pub type ParseChallengeError = borsh::maybestd::io::Error;
@@ -189,6 +222,8 @@ pub enum ParsePeerMessageError {
     BlockRequest(ParseRequiredError<ParseCryptoHashError>),
     #[error("block_response: {0}")]
     BlockResponse(ParseRequiredError<ParseBlockError>),
+    #[error("block_header_announcement: {0}")]
+    BlockHeaderAnnouncement(ParseBlockHeaderAnnouncementError),
     #[error("transaction: {0}")]
     Transaction(ParseTransactionError),
     #[error("routed: {0}")]
@@ -250,6 +285,13 @@ impl TryFrom<&proto::PeerMessage> for PeerMessage {
             ProtoMT::BlockResponse(br) => PeerMessage::Block(
                 try_from_required(&br.block).map_err(Self::Error::BlockResponse)?,
             ),
+            ProtoMT::BlockHeaderAnnouncement(a) => PeerMessage::BlockHeaderAnnouncement(
+                BlockHeaderAnnouncement::try_from_slice(&a.borsh)
+                    .map_err(Self::Error::BlockHeaderAnnouncement)?,
+            ),
+            ProtoMT::MaintenanceAnnouncement(a) => {
+                PeerMessage::MaintenanceWindow(a.window.as_ref().map(Into::into))
+            }
             ProtoMT::Transaction(t) => PeerMessage::Transaction(
                 SignedTransaction::try_from_slice(&t.borsh).map_err(Self::Error::Transaction)?,
             ),
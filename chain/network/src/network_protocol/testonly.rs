@@ -286,6 +286,9 @@ impl Chain {
             tracked_shards: Default::default(),
             height: self.height(),
             tier1_accounts: Arc::new(self.get_tier1_accounts()),
+            other_validator_signers: Default::default(),
+            priority_validators: Default::default(),
+            maintenance_window: None,
         }
     }
 
@@ -348,6 +351,11 @@ pub fn make_handshake<R: Rng>(rng: &mut R, chain: &Chain) -> Handshake {
         sender_listen_port: Some(rng.gen()),
         sender_chain_info: chain.get_peer_chain_info(),
         partial_edge_info: make_partial_edge(rng),
+        // Not supported by the borsh encoding, so these have to be empty for
+        // the message to survive a borsh round trip.
+        state_sub_part_size_limit: 0,
+        compressions: vec![],
+        header_first_blocks: false,
     }
 }
 
@@ -47,6 +47,60 @@ fn serialize_deserialize_protobuf_only() {
     }
 }
 
+#[test]
+fn serialize_deserialize_handshake_compressions() {
+    let mut rng = make_rng(71620393);
+    let mut clock = time::FakeClock::default();
+    let chain = data::Chain::make(&mut clock, &mut rng, 2);
+    let mut handshake = data::make_handshake(&mut rng, &chain);
+    handshake.compressions = Compression::ALL.to_vec();
+    let m = PeerMessage::Handshake(handshake);
+    assert_eq!(
+        m,
+        PeerMessage::deserialize(Encoding::Proto, &m.serialize(Encoding::Proto)).unwrap()
+    );
+}
+
+#[test]
+fn serialize_deserialize_block_header_announcement() {
+    let mut rng = make_rng(50918273);
+    let mut clock = time::FakeClock::default();
+    let chain = data::Chain::make(&mut clock, &mut rng, 2);
+    let mut handshake = data::make_handshake(&mut rng, &chain);
+    handshake.header_first_blocks = true;
+    let block = chain.blocks[1].clone();
+    let announcement = BlockHeaderAnnouncement::from_block(&block).unwrap();
+    for m in [
+        PeerMessage::Handshake(handshake),
+        PeerMessage::BlockHeaderAnnouncement(announcement.clone()),
+    ] {
+        assert_eq!(
+            m,
+            PeerMessage::deserialize(Encoding::Proto, &m.serialize(Encoding::Proto)).unwrap()
+        );
+    }
+    // The block is reconstructed from the announcement and its chunk headers.
+    let chunks: Vec<_> = block.chunks().iter().cloned().collect();
+    assert_eq!(
+        announcement.chunk_hashes,
+        chunks.iter().map(|chunk| chunk.chunk_hash()).collect::<Vec<_>>()
+    );
+    assert_eq!(announcement.into_block(chunks), block);
+}
+
+#[test]
+fn serialize_deserialize_maintenance_window() {
+    let window = MaintenanceWindow { start_height: 100, shutdown_height: 110 };
+    assert!(!window.is_active(99));
+    assert!(window.is_active(100));
+    for m in [PeerMessage::MaintenanceWindow(Some(window)), PeerMessage::MaintenanceWindow(None)] {
+        assert_eq!(
+            m,
+            PeerMessage::deserialize(Encoding::Proto, &m.serialize(Encoding::Proto)).unwrap()
+        );
+    }
+}
+
 #[test]
 fn serialize_deserialize() -> anyhow::Result<()> {
     let mut rng = make_rng(89028037453);
@@ -98,7 +152,12 @@ fn serialize_deserialize() -> anyhow::Result<()> {
     ];
 
     // Check that serialize;deserialize = 1
-    for enc in [Encoding::Proto, Encoding::Borsh] {
+    for enc in [
+        Encoding::Proto,
+        Encoding::Borsh,
+        Encoding::CompressedProto(Compression::Gzip),
+        Encoding::CompressedProto(Compression::Zstd),
+    ] {
         for m in &msgs {
             (|| {
                 let m2 = PeerMessage::deserialize(enc, &m.serialize(enc))
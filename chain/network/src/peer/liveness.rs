@@ -0,0 +1,162 @@
+//! Application-level liveness check of a connection.
+//!
+//! A connection to a peer which went away without closing it (crashed host,
+//! dropped NAT mapping) can stay open for a long time, holding a peer slot.
+//! Any message received from the peer proves that it is alive, so a ping is
+//! only sent once the connection has been idle for the current interval.
+//! While pings stay unanswered the interval is halved down to the configured
+//! minimum, so that a dead connection is probed more often, and it is reset to
+//! the maximum once the peer answers.  The connection is closed if nothing has
+//! been received from the peer for the configured timeout.
+//!
+//! Pongs are also used to estimate the round trip time to the peer.
+use crate::time;
+
+#[derive(Clone, Debug)]
+pub struct Config {
+    /// Shortest interval between pings, used while pings stay unanswered.
+    pub min_interval: time::Duration,
+    /// Interval between pings on an idle connection to a responsive peer.
+    pub max_interval: time::Duration,
+    /// The connection is closed if nothing has been received from the peer for
+    /// that long.
+    pub timeout: time::Duration,
+}
+
+#[derive(Debug, PartialEq, Eq)]
+pub(crate) enum Action {
+    None,
+    /// Send a ping with the given nonce.
+    Ping(u64),
+    /// Close the connection, the peer is considered dead.
+    Close,
+}
+
+pub(crate) struct Liveness {
+    config: Config,
+    /// Current interval between pings.
+    interval: time::Duration,
+    /// Nonce and send time of the last ping which wasn't answered yet.
+    pending: Option<(u64, time::Instant)>,
+    next_nonce: u64,
+    /// Smoothed round trip time.
+    rtt: Option<time::Duration>,
+}
+
+impl Liveness {
+    pub fn new(config: Config) -> Self {
+        Self { interval: config.max_interval, config, pending: None, next_nonce: 0, rtt: None }
+    }
+
+    pub fn rtt(&self) -> Option<time::Duration> {
+        self.rtt
+    }
+
+    /// Decides what to do with the connection, given the time when the last
+    /// message was received from the peer.  Should be called periodically,
+    /// at least every `min_interval`.
+    pub fn poll(&mut self, now: time::Instant, last_received: time::Instant) -> Action {
+        let idle = now - last_received;
+        if idle >= self.config.timeout {
+            return Action::Close;
+        }
+        match self.pending {
+            Some((_, sent)) if now - sent < self.interval => return Action::None,
+            Some(_) if idle >= self.interval => {
+                // The ping is unanswered and the peer is silent.
+                self.interval = std::cmp::max(self.interval / 2, self.config.min_interval);
+            }
+            Some(_) => {
+                // The peer is alive, the pong must have been lost on the way.
+                self.pending = None;
+                return Action::None;
+            }
+            None if idle < self.interval => return Action::None,
+            None => {}
+        }
+        let nonce = self.next_nonce;
+        self.next_nonce += 1;
+        self.pending = Some((nonce, now));
+        Action::Ping(nonce)
+    }
+
+    /// Handles a pong from the peer.  Returns whether it answers the last ping.
+    pub fn on_pong(&mut self, now: time::Instant, nonce: u64) -> bool {
+        let sent = match self.pending {
+            Some((pending, sent)) if pending == nonce => sent,
+            _ => return false,
+        };
+        let sample = now - sent;
+        self.rtt = Some(match self.rtt {
+            None => sample,
+            Some(rtt) => (rtt * 7 + sample) / 8,
+        });
+        self.pending = None;
+        self.interval = self.config.max_interval;
+        true
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{Action, Config, Liveness};
+    use crate::time;
+
+    fn config() -> Config {
+        Config {
+            min_interval: time::Duration::seconds(5),
+            max_interval: time::Duration::seconds(20),
+            timeout: time::Duration::seconds(60),
+        }
+    }
+
+    #[test]
+    fn test_no_ping_while_receiving() {
+        let clock = time::FakeClock::default();
+        let mut liveness = Liveness::new(config());
+        for _ in 0..10 {
+            clock.advance(time::Duration::seconds(10));
+            assert_eq!(liveness.poll(clock.now(), clock.now()), Action::None);
+        }
+    }
+
+    #[test]
+    fn test_ping_pong_rtt() {
+        let clock = time::FakeClock::default();
+        let mut liveness = Liveness::new(config());
+        let received = clock.now();
+        clock.advance(time::Duration::seconds(20));
+        assert_eq!(liveness.poll(clock.now(), received), Action::Ping(0));
+        clock.advance(time::Duration::milliseconds(100));
+        assert!(!liveness.on_pong(clock.now(), 1));
+        assert!(liveness.on_pong(clock.now(), 0));
+        assert_eq!(liveness.rtt(), Some(time::Duration::milliseconds(100)));
+        // Pong has been received, so the peer is idle again only after another interval.
+        let received = clock.now();
+        clock.advance(time::Duration::seconds(19));
+        assert_eq!(liveness.poll(clock.now(), received), Action::None);
+        clock.advance(time::Duration::seconds(1));
+        assert_eq!(liveness.poll(clock.now(), received), Action::Ping(1));
+        clock.advance(time::Duration::milliseconds(300));
+        assert!(liveness.on_pong(clock.now(), 1));
+        assert_eq!(liveness.rtt(), Some(time::Duration::milliseconds(125)));
+    }
+
+    #[test]
+    fn test_unanswered_pings_close_connection() {
+        let clock = time::FakeClock::default();
+        let mut liveness = Liveness::new(config());
+        let received = clock.now();
+        let mut pings = vec![];
+        while clock.now() - received < time::Duration::seconds(60) {
+            if let Action::Ping(nonce) = liveness.poll(clock.now(), received) {
+                pings.push((nonce, clock.now() - received));
+            }
+            clock.advance(time::Duration::seconds(1));
+        }
+        // Interval halves from 20s down to 5s while the peer stays silent.
+        let sent_at: Vec<_> = pings.iter().map(|(_, at)| at.whole_seconds()).collect();
+        assert_eq!(sent_at, vec![20, 40, 50, 55]);
+        assert_eq!(liveness.poll(clock.now(), received), Action::Close);
+    }
+}
@@ -1,3 +1,4 @@
+pub(crate) mod liveness;
 pub(crate) mod peer_actor;
 mod stream;
 mod tracker;
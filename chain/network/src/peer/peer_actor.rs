@@ -2,11 +2,13 @@ use crate::accounts_data;
 use crate::concurrency::atomic_cell::AtomicCell;
 use crate::concurrency::demux;
 use crate::network_protocol::{
-    Edge, EdgeState, Encoding, ParsePeerMessageError, PartialEdgeInfo, PeerChainInfoV2, PeerInfo,
+    Compression, Edge, Encoding, ParsePeerMessageError, PartialEdgeInfo, PeerChainInfoV2, PeerInfo,
     RawRoutedMessage, RoutedMessageBody, RoutingTableUpdate, SyncAccountsData,
 };
+use crate::peer::liveness;
 use crate::peer::stream;
 use crate::peer::tracker::Tracker;
+use crate::peer_manager::audit_log;
 use crate::peer_manager::connection;
 use crate::peer_manager::network_state::NetworkState;
 use crate::peer_manager::peer_manager_actor::Event;
@@ -32,6 +34,7 @@ use near_o11y::{
 use near_performance_metrics_macros::perf;
 use near_primitives::hash::CryptoHash;
 use near_primitives::network::{AnnounceAccount, PeerId};
+use near_primitives::syncing::MAX_STATE_SUB_PART_SIZE;
 use near_primitives::types::EpochId;
 use near_primitives::utils::DisplayOption;
 use near_primitives::version::{
@@ -86,6 +89,8 @@ pub(crate) enum ClosingReason {
 
     #[error("peer banned: {0:?}")]
     Ban(ReasonForBan),
+    #[error("peer misbehaved: {0:?}")]
+    Misbehavior(ReasonForBan),
     #[error("handshake failed")]
     HandshakeFailed,
     #[error("rejected by PeerManager: {0:?}")]
@@ -96,6 +101,25 @@ pub(crate) enum ClosingReason {
     PeerManager,
     #[error("Received DisconnectMessage from peer")]
     DisconnectMessage,
+    #[error("peer stopped responding")]
+    Unresponsive,
+}
+
+impl From<&ClosingReason> for audit_log::Reason {
+    fn from(reason: &ClosingReason) -> Self {
+        match reason {
+            ClosingReason::TooManyInbound => Self::TooManyInbound,
+            ClosingReason::OutboundNotAllowed(_) => Self::OutboundNotAllowed,
+            ClosingReason::Ban(reason) => Self::Ban(*reason),
+            ClosingReason::Misbehavior(reason) => Self::Misbehavior(*reason),
+            ClosingReason::HandshakeFailed => Self::HandshakeFailed,
+            ClosingReason::RejectedByPeerManager(_) => Self::RejectedByPeerManager,
+            ClosingReason::StreamError => Self::StreamError,
+            ClosingReason::PeerManager => Self::PeerManager,
+            ClosingReason::DisconnectMessage => Self::DisconnectMessage,
+            ClosingReason::Unresponsive => Self::Unresponsive,
+        }
+    }
 }
 
 pub(crate) struct PeerActor {
@@ -130,10 +154,14 @@ pub(crate) struct PeerActor {
     /// Whether the PeerActor should skip protobuf support detection and use
     /// a given encoding right away.
     force_encoding: Option<Encoding>,
+    /// Algorithm compressing large messages, agreed on during the handshake.
+    compression: Option<Compression>,
 
     /// Peer status.
     peer_status: PeerStatus,
     closing_reason: Option<ClosingReason>,
+    /// Type of the received message which caused the connection to be closed.
+    closing_trigger: Option<&'static str>,
     /// Peer id and info. Present when Ready,
     /// or (for outbound only) when Connecting.
     // TODO: move it to ConnectingStatus::Outbound.
@@ -206,15 +234,26 @@ impl PeerActor {
             addr: network_state.config.node_addr.clone(),
             account_id: network_state.config.validator.as_ref().map(|v| v.account_id()),
         };
+        if let Some(keepalive) = network_state.config.tcp_keepalive {
+            if let Err(err) = stream.set_keepalive(keepalive) {
+                tracing::warn!(target: "network", ?err, "Failed to enable TCP keepalive");
+            }
+        }
         // Start PeerActor on separate thread.
         Ok(Self::start_in_arbiter(&actix::Arbiter::new().handle(), move |ctx| {
             let stats = Arc::new(connection::Stats::default());
             let stream_id = stream.id();
             let peer_addr = stream.peer_addr;
             let stream_type = stream.type_.clone();
-            let framed = stream::FramedStream::spawn(ctx, stream, stats.clone());
+            let framed = stream::FramedStream::spawn(
+                ctx,
+                stream,
+                stats.clone(),
+                network_state.metrics.clone(),
+            );
             Self {
                 closing_reason: None,
+                closing_trigger: None,
                 clock,
                 my_node_info,
                 stream_id,
@@ -230,6 +269,7 @@ impl PeerActor {
                 routed_message_cache: LruCache::new(ROUTED_MESSAGE_CACHE_SIZE),
                 protocol_buffers_supported: false,
                 force_encoding,
+                compression: None,
                 peer_info: match &stream_type {
                     tcp::StreamType::Inbound => None,
                     tcp::StreamType::Outbound { peer_id } => Some(PeerInfo {
@@ -249,6 +289,9 @@ impl PeerActor {
     // In case it is None, both encodings are attempted for parsing, and each message
     // is sent twice.
     fn encoding(&self) -> Option<Encoding> {
+        if let Some(compression) = self.compression {
+            return Some(Encoding::CompressedProto(compression));
+        }
         if self.force_encoding.is_some() {
             return self.force_encoding;
         }
@@ -299,6 +342,11 @@ impl PeerActor {
         // Record block requests in tracker.
         match msg {
             PeerMessage::Block(b) if self.tracker.lock().has_received(b.hash()) => return,
+            PeerMessage::BlockHeaderAnnouncement(a)
+                if self.tracker.lock().has_received(a.header.hash()) =>
+            {
+                return
+            }
             PeerMessage::BlockRequest(h) => self.tracker.lock().push_request(*h),
             _ => (),
         };
@@ -308,11 +356,19 @@ impl PeerActor {
         let bytes_len = bytes.len();
         tracing::trace!(target: "network", msg_len = bytes_len);
         self.framed.send(stream::Frame(bytes));
-        metrics::PEER_DATA_SENT_BYTES.inc_by(bytes_len as u64);
-        metrics::PEER_MESSAGE_SENT_BY_TYPE_TOTAL.with_label_values(&[msg_type]).inc();
-        metrics::PEER_MESSAGE_SENT_BY_TYPE_BYTES
+        self.network_state.metrics.peer_data_sent_bytes.inc_by(bytes_len as u64);
+        self.network_state
+            .metrics
+            .peer_message_sent_by_type_total
+            .with_label_values(&[msg_type])
+            .inc();
+        self.network_state
+            .metrics
+            .peer_message_sent_by_type_bytes
             .with_label_values(&[msg_type])
             .inc_by(bytes_len as u64);
+        self.network_state.bandwidth_by_type.record_sent(msg_type, bytes_len);
+        self.stats.bandwidth_by_type.record_sent(msg_type, bytes_len);
     }
 
     fn send_handshake(&self, spec: HandshakeSpec) {
@@ -330,6 +386,12 @@ impl PeerActor {
                 archival: self.network_state.config.archive,
             },
             partial_edge_info: spec.partial_edge_info,
+            state_sub_part_size_limit: MAX_STATE_SUB_PART_SIZE.as_u64(),
+            compressions: match self.network_state.config.compression {
+                Some(_) => Compression::ALL.to_vec(),
+                None => vec![],
+            },
+            header_first_blocks: true,
         };
         let msg = PeerMessage::Handshake(handshake);
         self.send_message_or_log(&msg);
@@ -343,6 +405,76 @@ impl PeerActor {
         ctx.stop();
     }
 
+    /// Like `stop`, but also records the type of the received message which
+    /// caused the connection to be closed.
+    fn stop_on_message(
+        &mut self,
+        ctx: &mut Context<PeerActor>,
+        reason: ClosingReason,
+        msg_type: &'static str,
+    ) {
+        if self.closing_reason.is_none() {
+            self.closing_trigger = Some(msg_type);
+        }
+        self.stop(ctx, reason);
+    }
+
+    /// Penalizes the peer for the misbehavior and returns the reason to close
+    /// the connection with.  The peer is banned only if its score dropped to
+    /// the ban threshold.
+    fn misbehavior(&self, ban_reason: ReasonForBan) -> ClosingReason {
+        // The connection is being closed already, the peer has been penalized.
+        if self.closing_reason.is_some() {
+            return ClosingReason::Misbehavior(ban_reason);
+        }
+        let ban = match self.other_peer_id() {
+            Some(peer_id) => self.network_state.peer_scores.penalize(peer_id, ban_reason),
+            // Nothing is known about the peer yet.
+            None => true,
+        };
+        if ban {
+            ClosingReason::Ban(ban_reason)
+        } else {
+            ClosingReason::Misbehavior(ban_reason)
+        }
+    }
+
+    /// Closes the connection because of the misbehavior of the peer, caused by
+    /// a message of type `msg_type`.
+    fn misbehave(
+        &mut self,
+        ctx: &mut Context<PeerActor>,
+        ban_reason: ReasonForBan,
+        msg_type: &'static str,
+    ) {
+        let reason = self.misbehavior(ban_reason);
+        self.stop_on_message(ctx, reason, msg_type);
+    }
+
+    /// Records the decision to close the connection in the audit log.
+    fn record_closing(&self, reason: &ClosingReason) {
+        let peer_id = match self.other_peer_id() {
+            Some(peer_id) => peer_id.clone(),
+            // Nothing is known about the peer yet.
+            None => return,
+        };
+        let connected_at = match &self.peer_status {
+            PeerStatus::Ready(conn) => {
+                Some(self.clock.now_utc() - (self.clock.now() - conn.connection_established_time))
+            }
+            PeerStatus::Connecting(..) => None,
+        };
+        self.network_state.audit_log.record(audit_log::Entry {
+            peer_id,
+            addr: Some(self.peer_addr),
+            reason: reason.into(),
+            message_type: self.closing_trigger.map(str::to_string),
+            evidence: reason.to_string(),
+            connected_at,
+            time: self.clock.now_utc(),
+        });
+    }
+
     /// `PeerId` of the current node.
     fn my_node_id(&self) -> &PeerId {
         &self.my_node_info.id
@@ -355,8 +487,8 @@ impl PeerActor {
 
     /// Update stats when receiving msg
     fn update_stats_on_receiving_message(&mut self, msg_len: usize) {
-        metrics::PEER_DATA_RECEIVED_BYTES.inc_by(msg_len as u64);
-        metrics::PEER_MESSAGE_RECEIVED_TOTAL.inc();
+        self.network_state.metrics.peer_data_received_bytes.inc_by(msg_len as u64);
+        self.network_state.metrics.peer_message_received_total.inc();
         tracing::trace!(target: "network", msg_len);
         self.tracker.lock().increment_received(&self.clock, msg_len as u64);
     }
@@ -429,7 +561,11 @@ impl PeerActor {
                     return;
                 }
                 // Verify if nonce is sane.
-                if let Err(err) = verify_nonce(&self.clock, handshake.partial_edge_info.nonce) {
+                if let Err(err) = verify_nonce(
+                    &self.clock,
+                    &self.network_state.metrics,
+                    handshake.partial_edge_info.nonce,
+                ) {
                     debug!(target: "network", nonce=?handshake.partial_edge_info.nonce, my_node_id = ?self.my_node_id(), peer_id=?handshake.sender_peer_id, "bad nonce, disconnecting: {err}");
                     self.stop(ctx, ClosingReason::HandshakeFailed);
                     return;
@@ -456,7 +592,7 @@ impl PeerActor {
             &handshake.partial_edge_info,
         ) {
             warn!(target: "network", "partial edge with invalid signature, disconnecting");
-            self.stop(ctx, ClosingReason::Ban(ReasonForBan::InvalidSignature));
+            self.misbehave(ctx, ReasonForBan::InvalidSignature, "Handshake");
             return;
         }
 
@@ -498,15 +634,21 @@ impl PeerActor {
             peer_info: peer_info.clone(),
             initial_chain_info: handshake.sender_chain_info.clone(),
             chain_height: AtomicU64::new(handshake.sender_chain_info.height),
+            protocol_version: handshake.protocol_version,
+            state_sub_part_size_limit: handshake.state_sub_part_size_limit,
+            header_first_blocks: handshake.header_first_blocks,
+            maintenance_window: AtomicCell::new(None),
             edge,
             peer_type: self.peer_type,
             stats: self.stats.clone(),
-            _peer_connections_metric: metrics::PEER_CONNECTIONS.new_point(&metrics::Connection {
-                type_: self.peer_type,
-                encoding: self.encoding(),
-            }),
+            _peer_connections_metric: self.network_state.metrics.peer_connections.new_point(
+                &metrics::Connection { type_: self.peer_type, encoding: self.encoding() },
+            ),
             last_time_peer_requested: AtomicCell::new(None),
             last_time_received_message: AtomicCell::new(now),
+            liveness: Mutex::new(liveness::Liveness::new(
+                self.network_state.config.peer_liveness.clone(),
+            )),
             connection_established_time: now,
             send_accounts_data_demux: demux::Demux::new(
                 self.network_state.config.accounts_data_broadcast_rate_limit,
@@ -573,7 +715,12 @@ impl PeerActor {
                                 protocol_version: handshake.protocol_version,
                                 partial_edge_info: partial_edge_info,
                             });
-                        } else {
+                        }
+                        // Both peers have sent their handshakes, so the messages
+                        // following them may be compressed.
+                        act.compression = act.network_state.config.compression
+                            .filter(|c| handshake.compressions.contains(c));
+                        if act.peer_type == PeerType::Outbound {
                             // Outbound peer triggers the inital full accounts data sync.
                             // TODO(gprusak): implement triggering the periodic full sync.
                             act.send_message_or_log(&PeerMessage::SyncAccountsData(SyncAccountsData{
@@ -588,6 +735,10 @@ impl PeerActor {
                         }
                         // Sync the RoutingTable.
                         act.sync_routing_table();
+                        // Let the peer know that we are going to shut down soon.
+                        if let Some(window) = act.network_state.chain_info.load().maintenance_window {
+                            act.send_message_or_log(&PeerMessage::MaintenanceWindow(Some(window)));
+                        }
                         // Exchange peers periodically.
                         ctx.spawn(wrap_future({
                             let conn = conn.clone();
@@ -601,6 +752,34 @@ impl PeerActor {
                                 }
                             }
                         }));
+                        // Check that the peer is alive while the connection is idle.
+                        ctx.spawn(wrap_future({
+                            let conn = conn.clone();
+                            let network_state = act.network_state.clone();
+                            let clock = act.clock.clone();
+                            async move {
+                                let mut interval = tokio::time::interval(
+                                    network_state.config.peer_liveness.min_interval.try_into().unwrap(),
+                                );
+                                interval.set_missed_tick_behavior(tokio::time::MissedTickBehavior::Skip);
+                                loop {
+                                    interval.tick().await;
+                                    let action = conn.liveness.lock().poll(clock.now(), conn.last_time_received_message.load());
+                                    match action {
+                                        liveness::Action::None => {}
+                                        liveness::Action::Ping(nonce) => {
+                                            network_state.send_liveness_ping(&clock, &conn, nonce);
+                                        }
+                                        liveness::Action::Close => {
+                                            info!(target: "network", peer_id = ?conn.peer_info.id, "Closing connection to unresponsive peer");
+                                            network_state.metrics.peer_unresponsive_closed_total.inc();
+                                            conn.stop_unresponsive();
+                                            break;
+                                        }
+                                    }
+                                }
+                            }
+                        }));
                         act.network_state.config.event_sink.push(Event::HandshakeCompleted(HandshakeCompletedEvent{
                             stream_id: act.stream_id,
                             edge: conn.edge.clone(),
@@ -625,10 +804,12 @@ impl PeerActor {
     fn sync_routing_table(&self) {
         let mut known_edges: Vec<Edge> =
             self.network_state.graph.read().edges().values().cloned().collect();
-        if self.network_state.config.skip_tombstones.is_some() {
-            known_edges.retain(|edge| edge.removal_info().is_none());
-            metrics::EDGE_TOMBSTONE_SENDING_SKIPPED.inc();
-        }
+        let expired = self
+            .network_state
+            .config
+            .tombstones
+            .retain_unexpired(self.clock.now_utc(), &mut known_edges);
+        self.network_state.metrics.edge_tombstone_sending_skipped.inc_by(expired as u64);
         let known_accounts = self.network_state.routing_table_view.get_announce_accounts();
         self.send_message_or_log(&PeerMessage::SyncRoutingTable(RoutingTableUpdate::new(
             known_edges,
@@ -725,6 +906,7 @@ impl PeerActor {
         clock: &time::Clock,
         network_state: &NetworkState,
         peer_id: PeerId,
+        author: &PeerId,
         msg_hash: CryptoHash,
         body: RoutedMessageBody,
     ) -> Result<Option<RoutedMessageBody>, ReasonForBan> {
@@ -739,6 +921,11 @@ impl PeerActor {
                 network_state.client.tx_status_response(tx_result).await;
                 None
             }
+            RoutedMessageBody::TxStatusSubscribe(account_id, tx_hash) => network_state
+                .client
+                .tx_status_subscribe(account_id, tx_hash, msg_hash, author.clone())
+                .await
+                .map(|v| RoutedMessageBody::TxStatusResponse(*v)),
             RoutedMessageBody::StateRequestHeader(shard_id, sync_hash) => network_state
                 .client
                 .state_request_header(shard_id, sync_hash)
@@ -749,10 +936,37 @@ impl PeerActor {
                 .state_request_part(shard_id, sync_hash, part_id)
                 .await?
                 .map(RoutedMessageBody::VersionedStateResponse),
+            RoutedMessageBody::StateRequestSubPart(
+                shard_id,
+                sync_hash,
+                part_id,
+                sub_part_id,
+                sub_part_size,
+            ) => network_state
+                .client
+                .state_request_sub_part(shard_id, sync_hash, part_id, sub_part_id, sub_part_size)
+                .await?
+                .map(RoutedMessageBody::VersionedStateResponse),
             RoutedMessageBody::VersionedStateResponse(info) => {
                 network_state.client.state_response(info).await;
                 None
             }
+            RoutedMessageBody::EpochSyncDataRequest(block_hash) => network_state
+                .client
+                .epoch_sync_data_request(block_hash)
+                .await?
+                .map(RoutedMessageBody::EpochSyncDataResponse),
+            // Epoch sync data is only served, nodes don't request it yet.
+            RoutedMessageBody::EpochSyncDataResponse(_) => None,
+            RoutedMessageBody::EpochSyncProofsRequest(block_hash) => network_state
+                .client
+                .epoch_sync_proofs_request(block_hash)
+                .await?
+                .map(RoutedMessageBody::EpochSyncProofsResponse),
+            RoutedMessageBody::EpochSyncProofsResponse(response) => {
+                network_state.client.epoch_sync_proofs_response(response, peer_id).await;
+                None
+            }
             RoutedMessageBody::BlockApproval(approval) => {
                 network_state.client.block_approval(approval, peer_id).await;
                 None
@@ -777,6 +991,40 @@ impl PeerActor {
                 network_state.client.partial_encoded_chunk_forward(msg).await;
                 None
             }
+            RoutedMessageBody::PartialEncodedChunkAvailability(msg) => {
+                // Requests for parts are sent to the announced account, so it has to be the
+                // author of the message.
+                if network_state.routing_table_view.account_owner(&msg.account_id).as_ref()
+                    == Some(author)
+                {
+                    network_state.client.partial_encoded_chunk_availability(msg).await;
+                } else {
+                    debug!(
+                        target: "network",
+                        ?author,
+                        account_id = ?msg.account_id,
+                        "Dropping chunk parts availability of an account not owned by its author"
+                    );
+                }
+                None
+            }
+            RoutedMessageBody::ChunkInclusionFeedback(msg) => {
+                // Only the block producer knows its block missed the chunk, so it has to be the
+                // author of the message.
+                if network_state.routing_table_view.account_owner(&msg.block_producer).as_ref()
+                    == Some(author)
+                {
+                    network_state.client.chunk_inclusion_feedback(msg).await;
+                } else {
+                    debug!(
+                        target: "network",
+                        ?author,
+                        block_producer = ?msg.block_producer,
+                        "Dropping chunk inclusion feedback of a block producer other than its author"
+                    );
+                }
+                None
+            }
             RoutedMessageBody::ReceiptOutcomeRequest(_) => {
                 // Silently ignore for the time being.  We’ve been still
                 // sending those messages at protocol version 56 so we
@@ -813,16 +1061,22 @@ impl PeerActor {
                 tracker.push_received(hash);
                 tracker.has_request(&hash)
             }
+            PeerMessage::BlockHeaderAnnouncement(announcement) => {
+                conn.chain_height.fetch_max(announcement.header.height(), Ordering::Relaxed);
+                self.tracker.lock().push_received(*announcement.header.hash());
+                false
+            }
             _ => false,
         };
         let clock = self.clock.clone();
         let network_state = self.network_state.clone();
         let peer_id = conn.peer_info.id.clone();
+        let msg_type = msg.msg_variant();
         ctx.spawn(wrap_future(async move {
             Ok(match msg {
                 PeerMessage::Routed(msg) => {
                     let msg_hash = msg.hash();
-                    Self::receive_routed_message(&clock, &network_state, peer_id, msg_hash, msg.msg.body).await?.map(
+                    Self::receive_routed_message(&clock, &network_state, peer_id, &msg.msg.author, msg_hash, msg.msg.body).await?.map(
                         |body| {
                             PeerMessage::Routed(network_state.sign_message(
                                 &clock,
@@ -832,15 +1086,25 @@ impl PeerActor {
                     )
                 }
                 PeerMessage::BlockRequest(hash) => {
-                    network_state.client.block_request(hash).await.map(|b|PeerMessage::Block(*b))
+                    match network_state.sync_requests.acquire(&clock, &peer_id, msg_type).await {
+                        Some(_permit) => network_state.client.block_request(hash).await.map(|b|PeerMessage::Block(*b)),
+                        None => None,
+                    }
                 }
                 PeerMessage::BlockHeadersRequest(hashes) => {
-                    network_state.client.block_headers_request(hashes).await.map(PeerMessage::BlockHeaders)
+                    match network_state.sync_requests.acquire(&clock, &peer_id, msg_type).await {
+                        Some(_permit) => network_state.client.block_headers_request(hashes).await.map(PeerMessage::BlockHeaders),
+                        None => None,
+                    }
                 }
                 PeerMessage::Block(block) => {
                     network_state.client.block(block, peer_id, was_requested).await;
                     None
                 }
+                PeerMessage::BlockHeaderAnnouncement(announcement) => {
+                    network_state.client.block_header_announcement(announcement, peer_id).await;
+                    None
+                }
                 PeerMessage::Transaction(transaction) => {
                     network_state.client.transaction(transaction, /*is_forwarded=*/ false).await;
                     None
@@ -858,12 +1122,12 @@ impl PeerActor {
                     None
                 }
             })}.in_current_span())
-            .map(|res, act: &mut PeerActor, ctx| {
+            .map(move |res, act: &mut PeerActor, ctx| {
                 match res {
                     // TODO(gprusak): make sure that for routed messages we drop routeback info correctly.
                     Ok(Some(resp)) => act.send_message_or_log(&resp),
                     Ok(None) => {}
-                    Err(ban_reason) => act.stop(ctx, ClosingReason::Ban(ban_reason)),
+                    Err(ban_reason) => act.misbehave(ctx, ban_reason, msg_type),
                 }
                 message_processed_event();
             }),
@@ -884,7 +1148,7 @@ impl PeerActor {
         match peer_msg.clone() {
             PeerMessage::Disconnect => {
                 debug!(target: "network", "Disconnect signal. Me: {:?} Peer: {:?}", self.my_node_info.id, self.other_peer_id());
-                self.stop(ctx, ClosingReason::DisconnectMessage);
+                self.stop_on_message(ctx, ClosingReason::DisconnectMessage, peer_msg.msg_variant());
             }
             PeerMessage::Handshake(_) => {
                 // Received handshake after already have seen handshake from this peer.
@@ -931,7 +1195,7 @@ impl PeerActor {
                                 act.send_message_or_log(&PeerMessage::ResponseUpdateNonce(*edge));
                             }
                             Ok(PeerToManagerMsgResp::BanPeer(reason_for_ban)) => {
-                                act.stop(ctx, ClosingReason::Ban(reason_for_ban));
+                                act.misbehave(ctx, reason_for_ban, peer_msg.msg_variant());
                             }
                             _ => {}
                         }
@@ -951,7 +1215,7 @@ impl PeerActor {
                     .then(|res, act: &mut PeerActor, ctx| {
                         match res {
                             Ok(PeerToManagerMsgResp::BanPeer(reason_for_ban)) => {
-                                act.stop(ctx, ClosingReason::Ban(reason_for_ban))
+                                act.misbehave(ctx, reason_for_ban, peer_msg.msg_variant())
                             }
                             _ => {}
                         }
@@ -964,6 +1228,10 @@ impl PeerActor {
                 self.handle_sync_routing_table(ctx, conn, rtu);
                 self.network_state.config.event_sink.push(Event::MessageProcessed(peer_msg));
             }
+            PeerMessage::MaintenanceWindow(window) => {
+                conn.maintenance_window.store(window);
+                self.network_state.config.event_sink.push(Event::MessageProcessed(peer_msg));
+            }
             PeerMessage::SyncAccountsData(msg) => {
                 let peer_id = conn.peer_info.id.clone();
                 let pms = self.network_state.clone();
@@ -1015,7 +1283,7 @@ impl PeerActor {
                     )
                     .map(|ban_reason, act: &mut PeerActor, ctx| {
                         if let Some(ban_reason) = ban_reason {
-                            act.stop(ctx, ClosingReason::Ban(ban_reason));
+                            act.misbehave(ctx, ban_reason, peer_msg.msg_variant());
                         }
                         act.network_state.config.event_sink.push(Event::MessageProcessed(peer_msg));
                     }),
@@ -1029,7 +1297,7 @@ impl PeerActor {
                     msg.target);
                 if !msg.verify() {
                     // Received invalid routed message from peer.
-                    self.stop(ctx, ClosingReason::Ban(ReasonForBan::InvalidSignature));
+                    self.misbehave(ctx, ReasonForBan::InvalidSignature, peer_msg.msg_variant());
                     return;
                 }
                 let from = &conn.peer_info.id;
@@ -1042,7 +1310,11 @@ impl PeerActor {
                     );
                 }
                 if self.network_state.message_for_me(&msg.target) {
-                    metrics::record_routed_msg_latency(&self.clock, &msg);
+                    metrics::record_routed_msg_latency(
+                        &self.network_state.metrics,
+                        &self.clock,
+                        &msg,
+                    );
                     // Handle Ping and Pong message if they are for us without sending to client.
                     // i.e. Return false in case of Ping and Pong
                     match &msg.body {
@@ -1057,12 +1329,29 @@ impl PeerActor {
                                 .push(Event::MessageProcessed(PeerMessage::Routed(msg)));
                         }
                         RoutedMessageBody::Pong(pong) => {
+                            if pong.source == conn.peer_info.id
+                                && conn.liveness.lock().on_pong(self.clock.now(), pong.nonce)
+                            {
+                                self.network_state.peer_scores.reward(&conn.peer_info.id);
+                            }
                             self.network_state.config.event_sink.push(Event::Pong(pong.clone()));
                             self.network_state
                                 .config
                                 .event_sink
                                 .push(Event::MessageProcessed(PeerMessage::Routed(msg)));
                         }
+                        RoutedMessageBody::RelayedPartialEncodedChunkRequest(relayed) => {
+                            self.network_state.forward_relayed_chunk_request(
+                                &self.clock,
+                                from,
+                                &msg.author,
+                                (**relayed).clone(),
+                            );
+                            self.network_state
+                                .config
+                                .event_sink
+                                .push(Event::MessageProcessed(PeerMessage::Routed(msg)));
+                        }
                         _ => self.receive_message(ctx, conn, PeerMessage::Routed(msg.clone())),
                     }
                 } else {
@@ -1071,7 +1360,9 @@ impl PeerActor {
                     } else {
                         self.network_state.config.event_sink.push(Event::RoutedMessageDropped);
                         warn!(target: "network", ?msg, ?from, "Message dropped because TTL reached 0.");
-                        metrics::ROUTED_MESSAGE_DROPPED
+                        self.network_state
+                            .metrics
+                            .routed_message_dropped
                             .with_label_values(&[msg.body_variant()])
                             .inc();
                     }
@@ -1119,7 +1410,7 @@ impl PeerActor {
             )
             .then(move |res, act: &mut PeerActor, ctx| {
                 match res {
-                    Err(ban_reason) => act.stop(ctx, ClosingReason::Ban(ban_reason)),
+                    Err(ban_reason) => act.misbehave(ctx, ban_reason, "SyncRoutingTable"),
                     Ok(accounts) => act.network_state.broadcast_accounts(accounts),
                 }
                 wrap_future(async {})
@@ -1132,7 +1423,7 @@ impl Actor for PeerActor {
     type Context = Context<PeerActor>;
 
     fn started(&mut self, ctx: &mut Self::Context) {
-        metrics::PEER_CONNECTIONS_TOTAL.inc();
+        self.network_state.metrics.peer_connections_total.inc();
         debug!(target: "network", "{:?}: Peer {:?} {:?} started", self.my_node_info.id, self.peer_addr, self.peer_type);
         // Set Handshake timeout for stopping actor if peer is not ready after given period of time.
 
@@ -1161,8 +1452,11 @@ impl Actor for PeerActor {
     }
 
     fn stopping(&mut self, _: &mut Self::Context) -> Running {
-        metrics::PEER_CONNECTIONS_TOTAL.dec();
+        self.network_state.metrics.peer_connections_total.dec();
         debug!(target: "network", "{:?}: [status = {:?}] Peer {} disconnected.", self.my_node_info.id, self.peer_status, self.peer_info);
+        if let Some(reason) = &self.closing_reason {
+            self.record_closing(reason);
+        }
         match &self.peer_status {
             // If PeerActor is in Connecting state, then
             // it was not registered in the NewtorkState,
@@ -1198,7 +1492,8 @@ impl actix::Handler<stream::Error> for PeerActor {
     fn handle(&mut self, err: stream::Error, ctx: &mut Self::Context) {
         let expected = match &err {
             stream::Error::Recv(stream::RecvError::MessageTooLarge { .. }) => {
-                self.stop(ctx, ClosingReason::Ban(ReasonForBan::Abusive));
+                let reason = self.misbehavior(ReasonForBan::Abusive);
+                self.stop(ctx, reason);
                 true
             }
             // It is expected in a sense that the peer might be just slow.
@@ -1285,10 +1580,18 @@ impl actix::Handler<stream::Frame> for PeerActor {
 
         {
             let labels = [peer_msg.msg_variant()];
-            metrics::PEER_MESSAGE_RECEIVED_BY_TYPE_TOTAL.with_label_values(&labels).inc();
-            metrics::PEER_MESSAGE_RECEIVED_BY_TYPE_BYTES
+            self.network_state
+                .metrics
+                .peer_message_received_by_type_total
+                .with_label_values(&labels)
+                .inc();
+            self.network_state
+                .metrics
+                .peer_message_received_by_type_bytes
                 .with_label_values(&labels)
                 .inc_by(msg.len() as u64);
+            self.network_state.bandwidth_by_type.record_received(labels[0], msg.len());
+            self.stats.bandwidth_by_type.record_received(labels[0], msg.len());
         }
         match &self.peer_status {
             PeerStatus::Connecting { .. } => self.handle_msg_connecting(ctx, peer_msg),
@@ -1298,20 +1601,18 @@ impl actix::Handler<stream::Frame> for PeerActor {
                     return;
                 }
                 conn.last_time_received_message.store(self.clock.now());
-                // Optionally, ignore any received tombstones after startup. This is to
-                // prevent overload from too much accumulated deleted edges.
-                //
-                // We have similar code to skip sending tombstones, here we handle the
-                // case when our peer doesn't use that logic yet.
-                if let Some(skip_tombstones) = self.network_state.config.skip_tombstones {
-                    if let PeerMessage::SyncRoutingTable(routing_table) = &mut peer_msg {
-                        if conn.connection_established_time + skip_tombstones > self.clock.now() {
-                            routing_table
-                                .edges
-                                .retain(|edge| edge.edge_type() == EdgeState::Active);
-                            metrics::EDGE_TOMBSTONE_RECEIVING_SKIPPED.inc();
-                        }
-                    }
+                // Ignore received expired tombstones. We don't send them either, here we
+                // handle peers which still do.
+                if let PeerMessage::SyncRoutingTable(routing_table) = &mut peer_msg {
+                    let expired = self
+                        .network_state
+                        .config
+                        .tombstones
+                        .retain_unexpired(self.clock.now_utc(), &mut routing_table.edges);
+                    self.network_state
+                        .metrics
+                        .edge_tombstone_receiving_skipped
+                        .inc_by(expired as u64);
                 }
                 // Handle the message.
                 self.handle_msg_ready(ctx, &conn.clone(), peer_msg);
@@ -1335,7 +1636,7 @@ impl Handler<WithSpanContext<SendMessage>> for PeerActor {
 #[derive(actix::Message, Debug)]
 #[rtype(result = "()")]
 pub(crate) struct Stop {
-    pub ban_reason: Option<ReasonForBan>,
+    pub reason: ClosingReason,
 }
 
 impl actix::Handler<WithSpanContext<Stop>> for PeerActor {
@@ -1344,13 +1645,7 @@ impl actix::Handler<WithSpanContext<Stop>> for PeerActor {
     #[perf]
     fn handle(&mut self, msg: WithSpanContext<Stop>, ctx: &mut Self::Context) -> Self::Result {
         let (_span, msg) = handler_debug_span!(target: "network", msg);
-        self.stop(
-            ctx,
-            match msg.ban_reason {
-                Some(reason) => ClosingReason::Ban(reason),
-                None => ClosingReason::PeerManager,
-            },
-        );
+        self.stop(ctx, msg.reason);
     }
 }
 
@@ -1,5 +1,6 @@
 use crate::peer_manager::connection;
 use crate::stats::metrics;
+use crate::stats::metrics::NetworkMetrics;
 use crate::tcp;
 use actix::fut::future::wrap_future;
 use actix::AsyncContext as _;
@@ -60,6 +61,7 @@ pub(crate) struct FramedStream<Actor: actix::Actor> {
     queue_send: tokio::sync::mpsc::UnboundedSender<Frame>,
     stats: Arc<connection::Stats>,
     send_buf_size_metric: Arc<metrics::IntGaugeGuard>,
+    metrics: Arc<NetworkMetrics>,
     addr: actix::Addr<Actor>,
 }
 
@@ -73,19 +75,23 @@ where
         ctx: &mut actix::Context<Actor>,
         stream: tcp::Stream,
         stats: Arc<connection::Stats>,
+        metrics: Arc<NetworkMetrics>,
     ) -> Self {
         let (tcp_recv, tcp_send) = tokio::io::split(stream.stream);
         let (queue_send, queue_recv) = tokio::sync::mpsc::unbounded_channel();
         let send_buf_size_metric = Arc::new(metrics::MetricGuard::new(
-            &*metrics::PEER_DATA_WRITE_BUFFER_SIZE,
+            &metrics.peer_data_write_buffer_size,
             vec![stream.peer_addr.to_string()],
         ));
         ctx.spawn(wrap_future({
             let addr = ctx.address();
             let stats = stats.clone();
             let m = send_buf_size_metric.clone();
+            let metrics = metrics.clone();
             async move {
-                if let Err(err) = Self::run_send_loop(tcp_send, queue_recv, stats, m).await {
+                if let Err(err) =
+                    Self::run_send_loop(tcp_send, queue_recv, stats, m, &metrics).await
+                {
                     addr.do_send(Error::Send(SendError::IO(err)));
                 }
             }
@@ -93,15 +99,17 @@ where
         ctx.spawn(wrap_future({
             let addr = ctx.address();
             let stats = stats.clone();
+            let metrics = metrics.clone();
             async move {
                 if let Err(err) =
-                    Self::run_recv_loop(stream.peer_addr, tcp_recv, addr.clone(), stats).await
+                    Self::run_recv_loop(stream.peer_addr, tcp_recv, addr.clone(), stats, &metrics)
+                        .await
                 {
                     addr.do_send(Error::Recv(err));
                 }
             }
         }));
-        Self { queue_send, stats, send_buf_size_metric, addr: ctx.address() }
+        Self { queue_send, stats, send_buf_size_metric, metrics, addr: ctx.address() }
     }
 
     /// Pushes `msg` to the send queue.
@@ -119,7 +127,7 @@ where
         // when receiving one. It is not like we do any extra allocations, so we can affort
         // pushing the message to the queue anyway.
         if buf_size > MAX_WRITE_BUFFER_CAPACITY_BYTES {
-            metrics::MessageDropped::MaxCapacityExceeded.inc_unknown_msg();
+            metrics::MessageDropped::MaxCapacityExceeded.inc_unknown_msg(&self.metrics);
             self.addr.do_send(Error::Send(SendError::QueueOverflow {
                 got_bytes: buf_size,
                 want_max_bytes: MAX_WRITE_BUFFER_CAPACITY_BYTES,
@@ -141,14 +149,15 @@ where
         read: ReadHalf,
         addr: actix::Addr<Actor>,
         stats: Arc<connection::Stats>,
+        metrics: &NetworkMetrics,
     ) -> Result<(), RecvError> {
         const READ_BUFFER_CAPACITY: usize = 8 * 1024;
         let mut read = tokio::io::BufReader::with_capacity(READ_BUFFER_CAPACITY, read);
 
         let msg_size_metric =
-            metrics::MetricGuard::new(&metrics::PEER_MSG_SIZE_BYTES, vec![peer_addr.to_string()]);
+            metrics::MetricGuard::new(&metrics.peer_msg_size_bytes, vec![peer_addr.to_string()]);
         let buf_size_metric = metrics::MetricGuard::new(
-            &metrics::PEER_DATA_READ_BUFFER_SIZE,
+            &metrics.peer_data_read_buffer_size,
             vec![peer_addr.to_string()],
         );
         loop {
@@ -162,7 +171,7 @@ where
             msg_size_metric.observe(n as f64);
             buf_size_metric.set(n as i64);
             let mut buf = vec![0; n];
-            let t = metrics::PEER_MSG_READ_LATENCY.start_timer();
+            let t = metrics.peer_msg_read_latency.start_timer();
             read.read_exact(&mut buf[..]).await.map_err(RecvError::IO)?;
             t.observe_duration();
             buf_size_metric.set(0);
@@ -180,6 +189,7 @@ where
         mut queue_recv: tokio::sync::mpsc::UnboundedReceiver<Frame>,
         stats: Arc<connection::Stats>,
         buf_size_metric: Arc<metrics::IntGaugeGuard>,
+        metrics: &NetworkMetrics,
     ) -> io::Result<()> {
         const WRITE_BUFFER_CAPACITY: usize = 8 * 1024;
         let mut writer = tokio::io::BufWriter::with_capacity(WRITE_BUFFER_CAPACITY, tcp_send);
@@ -189,7 +199,7 @@ where
                 // TODO(gprusak): sending a too large message should probably be treated as a bug,
                 // since dropping messages may lead to hard-to-debug high-level issues.
                 if msg.len() > NETWORK_MESSAGE_MAX_SIZE_BYTES {
-                    metrics::MessageDropped::InputTooLong.inc_unknown_msg();
+                    metrics::MessageDropped::InputTooLong.inc_unknown_msg(metrics);
                 } else {
                     writer.write_u32_le(msg.len() as u32).await?;
                     writer.write_all(&msg[..]).await?;
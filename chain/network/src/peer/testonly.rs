@@ -6,6 +6,7 @@ use crate::network_protocol::{
     RoutedMessageV2,
 };
 use crate::peer::peer_actor::{ClosingReason, PeerActor};
+use crate::peer_manager::audit_log;
 use crate::peer_manager::network_state::NetworkState;
 use crate::peer_manager::peer_manager_actor;
 use crate::peer_manager::peer_store;
@@ -167,6 +168,8 @@ impl PeerHandle {
                 store.clone(),
                 peer_store::PeerStore::new(&clock, network_cfg.peer_store.clone(), store.clone())
                     .unwrap(),
+                audit_log::AuditLog::new(network_cfg.peer_audit_log.clone(), store.clone())
+                    .unwrap(),
                 Arc::new(network_cfg.verify().unwrap()),
                 cfg.chain.genesis_id.clone(),
                 fc,
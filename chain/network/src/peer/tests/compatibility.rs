@@ -0,0 +1,351 @@
+//! Interoperability of the current PeerActor with peers running older releases.
+//!
+//! Peers agree on a protocol version during the handshake, anywhere between
+//! `PEER_MIN_ALLOWED_PROTOCOL_VERSION` and `PROTOCOL_VERSION`.  An older release
+//! is emulated by `LegacyPeer`, which speaks the wire format directly: it supports
+//! protocol versions only up to a given one, uses a single encoding and doesn't
+//! negotiate compression.  The tests check that the handshake, block propagation
+//! and chunk part exchange keep working across the whole window, so that
+//! accidental wire format breaks are caught before a release.
+use crate::network_protocol::testonly as data;
+use crate::network_protocol::{
+    Edge, Encoding, Handshake, HandshakeFailureReason, PeerIdOrHash, PeerInfo, PeerMessage,
+    RawRoutedMessage, RoutedMessageBody, RoutedMessageV2,
+};
+use crate::peer::testonly::{Event, PeerConfig, PeerHandle};
+use crate::peer_manager::peer_manager_actor::Event as PME;
+use crate::tcp;
+use crate::test_utils::peer_id_from_seed;
+use crate::testonly::make_rng;
+use crate::testonly::stream::Stream;
+use crate::time;
+use crate::types::{PartialEncodedChunkRequestMsg, PartialEncodedChunkResponseMsg};
+use near_o11y::testonly::init_test_logger;
+use near_primitives::hash::hash;
+use near_primitives::network::PeerId;
+use near_primitives::version::{
+    ProtocolVersion, PEER_MIN_ALLOWED_PROTOCOL_VERSION, PROTOCOL_VERSION,
+};
+use rand::Rng;
+use std::sync::Arc;
+
+/// Peer running an older release, which supports protocol versions up to
+/// `protocol_version`.
+struct LegacyPeer {
+    cfg: PeerConfig,
+    protocol_version: ProtocolVersion,
+    listen_port: u16,
+    stream: Stream,
+}
+
+impl LegacyPeer {
+    fn new(cfg: PeerConfig, protocol_version: ProtocolVersion, stream: tcp::Stream) -> Self {
+        let listen_port = stream.local_addr.port();
+        let stream = Stream::new(cfg.force_encoding, stream);
+        Self { cfg, protocol_version, listen_port, stream }
+    }
+
+    fn handshake(&self, target: &PeerId, nonce: u64) -> PeerMessage {
+        PeerMessage::Handshake(Handshake {
+            protocol_version: self.protocol_version,
+            oldest_supported_version: PEER_MIN_ALLOWED_PROTOCOL_VERSION,
+            sender_peer_id: self.cfg.id(),
+            target_peer_id: target.clone(),
+            sender_listen_port: Some(self.listen_port),
+            sender_chain_info: self.cfg.chain.get_peer_chain_info(),
+            partial_edge_info: self.cfg.partial_edge_info(target, nonce),
+            state_sub_part_size_limit: 0,
+            compressions: vec![],
+            header_first_blocks: false,
+        })
+    }
+
+    fn routed_message(&self, body: RoutedMessageBody, target: &PeerId) -> RoutedMessageV2 {
+        RawRoutedMessage { target: PeerIdOrHash::PeerId(target.clone()), body }.sign(
+            &self.cfg.network.node_key,
+            1,    // ttl
+            None, // Older releases don't send the creation time.
+        )
+    }
+
+    async fn write(&mut self, msg: &PeerMessage) {
+        self.stream.write(msg).await
+    }
+
+    /// Reads messages until `pred` accepts one, ignoring the rest.
+    async fn read_until<T>(&mut self, mut pred: impl FnMut(PeerMessage) -> Option<T>) -> T {
+        loop {
+            if let Some(got) = pred(self.stream.read().await) {
+                return got;
+            }
+        }
+    }
+
+    async fn read_handshake(&mut self) -> Handshake {
+        self.read_until(|msg| match msg {
+            PeerMessage::Handshake(handshake) => Some(handshake),
+            _ => None,
+        })
+        .await
+    }
+}
+
+/// Protocol versions the legacy peers run at: the bounds of the supported
+/// window and the version preceding the current one.
+fn protocol_versions() -> Vec<ProtocolVersion> {
+    let mut versions =
+        vec![PEER_MIN_ALLOWED_PROTOCOL_VERSION, PROTOCOL_VERSION - 1, PROTOCOL_VERSION];
+    versions.retain(|v| *v >= PEER_MIN_ALLOWED_PROTOCOL_VERSION);
+    versions.dedup();
+    versions
+}
+
+/// Encodings older releases may be limited to.
+const ENCODINGS: [Encoding; 2] = [Encoding::Borsh, Encoding::Proto];
+
+/// Messages sent by a release at protocol version 56, kept as bytes rather than
+/// built from the current types, so that a change to how any of them is encoded
+/// fails the tests even if the current peer still understands itself.
+struct Fixtures {
+    /// Handshake from the `legacy` peer to the `current` one.
+    handshake: &'static [u8],
+    /// Block at height 1000 with a single chunk.
+    block: &'static [u8],
+    /// Part of the chunk of the block, routed from `legacy` to `current`.
+    partial_encoded_chunk: &'static [u8],
+}
+
+fn fixtures(encoding: Encoding) -> Fixtures {
+    match encoding {
+        Encoding::Borsh => Fixtures {
+            handshake: include_bytes!("fixtures/handshake.borsh"),
+            block: include_bytes!("fixtures/block.borsh"),
+            partial_encoded_chunk: include_bytes!("fixtures/partial_encoded_chunk.borsh"),
+        },
+        Encoding::Proto => Fixtures {
+            handshake: include_bytes!("fixtures/handshake.proto"),
+            block: include_bytes!("fixtures/block.proto"),
+            partial_encoded_chunk: include_bytes!("fixtures/partial_encoded_chunk.proto"),
+        },
+    }
+}
+
+/// Decodes a message sent by an older release and checks that encoding it again
+/// gives the same bytes, so that the current peer relays it unchanged.
+fn decode_fixture(encoding: Encoding, data: &[u8]) -> PeerMessage {
+    let msg = PeerMessage::deserialize(encoding, data).unwrap();
+    assert_eq!(msg.serialize(encoding), data, "{encoding:?} {msg:?}");
+    msg
+}
+
+fn make_configs(
+    rng: &mut impl Rng,
+    chain: &Arc<data::Chain>,
+    encoding: Encoding,
+) -> (PeerConfig, PeerConfig) {
+    let current = PeerConfig {
+        chain: chain.clone(),
+        network: chain.make_config(rng),
+        peers: (0..5).map(|_| data::make_peer_info(rng)).collect(),
+        force_encoding: None,
+        nonce: None,
+    };
+    let legacy = PeerConfig {
+        chain: chain.clone(),
+        network: chain.make_config(rng),
+        peers: (0..5).map(|_| data::make_peer_info(rng)).collect(),
+        force_encoding: Some(encoding),
+        nonce: None,
+    };
+    (current, legacy)
+}
+
+/// Propagates blocks and exchanges chunk parts in both directions over an
+/// established connection.
+async fn test_exchange(chain: &data::Chain, current: &PeerHandle, legacy: &mut LegacyPeer) {
+    let message_processed = |want| {
+        move |ev| match ev {
+            Event::Network(PME::MessageProcessed(got)) if got == want => Some(()),
+            _ => None,
+        }
+    };
+    let received =
+        |want: PeerMessage| move |got: PeerMessage| if got == want { Some(()) } else { None };
+
+    tracing::info!(target:"test","Block from the legacy peer");
+    let mut events = current.events.from_now();
+    let want = PeerMessage::Block(chain.blocks[5].clone());
+    legacy.write(&want).await;
+    events.recv_until(message_processed(want)).await;
+
+    tracing::info!(target:"test","Block to the legacy peer");
+    let want = PeerMessage::Block(chain.blocks[6].clone());
+    current.send(want.clone()).await;
+    legacy.read_until(received(want)).await;
+
+    tracing::info!(target:"test","PartialEncodedChunkRequest from the legacy peer");
+    let chunk_hash = chain.blocks[3].chunks()[0].chunk_hash();
+    let mut events = current.events.from_now();
+    let want = PeerMessage::Routed(Box::new(legacy.routed_message(
+        RoutedMessageBody::PartialEncodedChunkRequest(PartialEncodedChunkRequestMsg {
+            chunk_hash: chunk_hash.clone(),
+            part_ords: vec![],
+            tracking_shards: Default::default(),
+        }),
+        &current.cfg.id(),
+    )));
+    legacy.write(&want).await;
+    events.recv_until(message_processed(want)).await;
+
+    tracing::info!(target:"test","PartialEncodedChunkResponse to the legacy peer");
+    let want = PeerMessage::Routed(Box::new(current.routed_message(
+        RoutedMessageBody::PartialEncodedChunkResponse(PartialEncodedChunkResponseMsg {
+            chunk_hash: chunk_hash.clone(),
+            parts: data::make_chunk_parts(chain.chunks[&chunk_hash].clone()),
+            receipts: vec![],
+        }),
+        legacy.cfg.id(),
+        1,    // ttl
+        None, // Older releases don't understand the creation time.
+    )));
+    current.send(want.clone()).await;
+    legacy.read_until(received(want)).await;
+}
+
+/// The legacy peer connects to the current one, which accepts the protocol
+/// version the legacy peer proposes.
+async fn test_inbound_from_legacy(encoding: Encoding, protocol_version: ProtocolVersion) {
+    let mut rng = make_rng(89028037453);
+    let mut clock = time::FakeClock::default();
+    let chain = Arc::new(data::Chain::make(&mut clock, &mut rng, 12));
+    let (current_cfg, legacy_cfg) = make_configs(&mut rng, &chain, encoding);
+
+    let (legacy_stream, current_stream) = tcp::Stream::loopback(current_cfg.id()).await;
+    let mut current = PeerHandle::start_endpoint(clock.clock(), current_cfg, current_stream).await;
+    let mut legacy = LegacyPeer::new(legacy_cfg, protocol_version, legacy_stream);
+
+    let handshake = legacy.handshake(&current.cfg.id(), 1);
+    legacy.write(&handshake).await;
+    let handshake = legacy.read_handshake().await;
+    assert_eq!(handshake.protocol_version, protocol_version);
+    current.complete_handshake().await;
+
+    test_exchange(&chain, &current, &mut legacy).await;
+}
+
+/// The current peer connects to the legacy one, which rejects the current
+/// protocol version if it's too new, so that the current peer retries with
+/// the newest version both of them support.
+async fn test_outbound_to_legacy(encoding: Encoding, protocol_version: ProtocolVersion) {
+    let mut rng = make_rng(89028037453);
+    let mut clock = time::FakeClock::default();
+    let chain = Arc::new(data::Chain::make(&mut clock, &mut rng, 12));
+    let (current_cfg, legacy_cfg) = make_configs(&mut rng, &chain, encoding);
+
+    let (current_stream, legacy_stream) = tcp::Stream::loopback(legacy_cfg.id()).await;
+    let mut current = PeerHandle::start_endpoint(clock.clock(), current_cfg, current_stream).await;
+    let mut legacy = LegacyPeer::new(legacy_cfg, protocol_version, legacy_stream);
+
+    let mut handshake = legacy.read_handshake().await;
+    if handshake.protocol_version > protocol_version {
+        let peer_info = PeerInfo { id: legacy.cfg.id(), addr: None, account_id: None };
+        legacy
+            .write(&PeerMessage::HandshakeFailure(
+                peer_info,
+                HandshakeFailureReason::ProtocolVersionMismatch {
+                    version: protocol_version,
+                    oldest_supported_version: PEER_MIN_ALLOWED_PROTOCOL_VERSION,
+                },
+            ))
+            .await;
+        handshake = legacy.read_handshake().await;
+    }
+    assert_eq!(handshake.protocol_version, protocol_version);
+    let handshake = legacy.handshake(&handshake.sender_peer_id, handshake.partial_edge_info.nonce);
+    legacy.write(&handshake).await;
+    current.complete_handshake().await;
+
+    test_exchange(&chain, &current, &mut legacy).await;
+}
+
+#[tokio::test]
+// Verifies that peers running older releases can connect to the current one.
+async fn inbound_from_legacy() {
+    init_test_logger();
+    for encoding in ENCODINGS {
+        for protocol_version in protocol_versions() {
+            tracing::info!(target:"test", ?encoding, protocol_version, "Legacy peer");
+            test_inbound_from_legacy(encoding, protocol_version).await;
+        }
+    }
+}
+
+#[tokio::test]
+// Verifies that the current peer can connect to peers running older releases.
+async fn outbound_to_legacy() {
+    init_test_logger();
+    for encoding in ENCODINGS {
+        for protocol_version in protocol_versions() {
+            tracing::info!(target:"test", ?encoding, protocol_version, "Legacy peer");
+            test_outbound_to_legacy(encoding, protocol_version).await;
+        }
+    }
+}
+
+#[test]
+// Verifies that messages sent by an older release are decoded into the same
+// values and encoded back into the same bytes.
+fn legacy_fixtures() {
+    init_test_logger();
+    let (legacy, current) = (peer_id_from_seed("legacy"), peer_id_from_seed("current"));
+    for encoding in ENCODINGS {
+        tracing::info!(target:"test", ?encoding, "Legacy fixtures");
+        let fixtures = fixtures(encoding);
+
+        let handshake = match decode_fixture(encoding, fixtures.handshake) {
+            PeerMessage::Handshake(handshake) => handshake,
+            msg => panic!("unexpected message {msg:?}"),
+        };
+        assert_eq!(handshake.protocol_version, 56);
+        assert_eq!(handshake.oldest_supported_version, 55);
+        assert_eq!(handshake.sender_peer_id, legacy);
+        assert_eq!(handshake.target_peer_id, current);
+        assert_eq!(handshake.sender_listen_port, Some(24567));
+        assert_eq!(handshake.sender_chain_info.genesis_id.chain_id, "localnet");
+        assert_eq!(handshake.sender_chain_info.genesis_id.hash, hash(b"genesis"));
+        assert_eq!(handshake.sender_chain_info.height, 1000);
+        assert_eq!(handshake.partial_edge_info.nonce, 1);
+        assert!(Edge::partial_verify(&current, &legacy, &handshake.partial_edge_info));
+        assert!(handshake.compressions.is_empty());
+        assert!(!handshake.header_first_blocks);
+
+        let block = match decode_fixture(encoding, fixtures.block) {
+            PeerMessage::Block(block) => block,
+            msg => panic!("unexpected message {msg:?}"),
+        };
+        assert_eq!(block.header().height(), 1000);
+        assert_eq!(block.header().prev_hash(), &hash(b"prev"));
+        assert_eq!(block.header().latest_protocol_version(), 56);
+        assert_eq!(block.chunks().len(), 1);
+        let chunk_header = block.chunks()[0].clone();
+        assert_eq!(chunk_header.shard_id(), 0);
+        assert_eq!(chunk_header.height_included(), 1000);
+
+        let msg = match decode_fixture(encoding, fixtures.partial_encoded_chunk) {
+            PeerMessage::Routed(msg) => msg,
+            msg => panic!("unexpected message {msg:?}"),
+        };
+        assert_eq!(msg.msg.target, PeerIdOrHash::PeerId(current.clone()));
+        assert_eq!(msg.msg.author, legacy);
+        assert_eq!(msg.created_at, None);
+        let chunk = match &msg.msg.body {
+            RoutedMessageBody::VersionedPartialEncodedChunk(chunk) => chunk,
+            body => panic!("unexpected body {body:?}"),
+        };
+        assert_eq!(chunk.chunk_hash(), chunk_header.chunk_hash());
+        assert!(chunk.valid_for(56));
+        assert_eq!(chunk.parts().len(), 1);
+        assert_eq!(chunk.parts()[0].part_ord, 0);
+        assert_eq!(&chunk.parts()[0].part[..], b"part");
+    }
+}
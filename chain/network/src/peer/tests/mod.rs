@@ -1,2 +1,3 @@
 mod communication;
+mod compatibility;
 mod stream;
@@ -5,6 +5,7 @@ use crate::tcp;
 use crate::testonly::make_rng;
 use actix::Actor as _;
 use actix::ActorContext as _;
+use near_o11y::metrics::MetricsRegistry;
 use rand::Rng as _;
 use std::sync::Arc;
 use tokio::sync::mpsc;
@@ -55,7 +56,12 @@ impl Actor {
             queue_recv,
             system: ActixSystem::spawn(|| {
                 Actor::create(|ctx| {
-                    let stream = stream::FramedStream::spawn(ctx, s, Arc::default());
+                    let stream = stream::FramedStream::spawn(
+                        ctx,
+                        s,
+                        Arc::default(),
+                        MetricsRegistry::global().get(),
+                    );
                     Self { stream, queue_send }
                 })
             })
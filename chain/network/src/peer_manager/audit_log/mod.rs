@@ -0,0 +1,205 @@
+//! Audit trail of the decisions to ban or disconnect peers.
+//!
+//! Every closed connection to a known peer, and every ban of a peer which
+//! wasn't connected, is recorded together with the reason, the type of the
+//! received message which triggered the decision (if any) and a short summary
+//! of the evidence.  The most recent entries are persisted in the DB, so that
+//! they survive a restart of the node, and can be appended to a file as JSON
+//! lines for processing by external tools.  Both are written by a background
+//! thread, as the entries are recorded on the network threads.
+use crate::store;
+use crate::time;
+use crate::types::ReasonForBan;
+use near_primitives::network::PeerId;
+use near_primitives::views::PeerAuditEntryView;
+use parking_lot::Mutex;
+use std::collections::VecDeque;
+use std::io::Write as _;
+use std::net::SocketAddr;
+use std::path::PathBuf;
+use std::sync::mpsc;
+
+#[cfg(test)]
+mod tests;
+
+#[derive(Clone, Debug)]
+pub struct Config {
+    /// Number of the most recent entries to keep.  Zero disables the audit log.
+    pub capacity: usize,
+    /// File to which entries are appended as JSON lines.
+    pub export_path: Option<PathBuf>,
+}
+
+/// Why the connection to the peer was closed.
+#[derive(borsh::BorshSerialize, borsh::BorshDeserialize, Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Reason {
+    Ban(ReasonForBan),
+    TooManyInbound,
+    OutboundNotAllowed,
+    HandshakeFailed,
+    RejectedByPeerManager,
+    StreamError,
+    PeerManager,
+    Unresponsive,
+    DisconnectMessage,
+    /// The peer misbehaved, but its score is still above the ban threshold.
+    Misbehavior(ReasonForBan),
+}
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Entry {
+    pub peer_id: PeerId,
+    pub addr: Option<SocketAddr>,
+    pub reason: Reason,
+    /// Type of the received message which triggered the decision.
+    pub message_type: Option<String>,
+    /// Human readable summary of what led to the decision.
+    pub evidence: String,
+    /// When the connection was established, if the peer was connected.
+    pub connected_at: Option<time::Utc>,
+    pub time: time::Utc,
+}
+
+impl Entry {
+    pub fn view(&self) -> PeerAuditEntryView {
+        let (banned, reason) = match self.reason {
+            Reason::Ban(reason) => (true, format!("{:?}", reason)),
+            reason => (false, format!("{:?}", reason)),
+        };
+        PeerAuditEntryView {
+            peer_id: self.peer_id.clone(),
+            addr: self.addr.map(|addr| addr.to_string()),
+            banned,
+            reason,
+            message_type: self.message_type.clone(),
+            evidence: self.evidence.clone(),
+            connected_at: self.connected_at.map(|t| t.unix_timestamp()),
+            timestamp: self.time.unix_timestamp(),
+        }
+    }
+}
+
+/// Change of the persisted audit log.  The changes are written by a
+/// background thread, so that recording an entry doesn't block the caller on
+/// disk IO.
+enum AuditOp {
+    Push(u64, Entry),
+    Delete(Vec<u64>),
+}
+
+struct Writer {
+    export_path: Option<PathBuf>,
+    store: store::Store,
+}
+
+impl Writer {
+    fn run(mut self, writes: mpsc::Receiver<AuditOp>) {
+        for write in writes {
+            if let Err(err) = self.write(write) {
+                tracing::error!(target: "network", ?err, "Failed to write audit log entry");
+            }
+        }
+    }
+
+    fn write(&mut self, write: AuditOp) -> anyhow::Result<()> {
+        match write {
+            AuditOp::Push(index, entry) => {
+                if let Some(path) = &self.export_path {
+                    let mut file =
+                        std::fs::OpenOptions::new().create(true).append(true).open(path)?;
+                    writeln!(file, "{}", serde_json::to_string(&entry.view())?)?;
+                }
+                self.store.push_audit_entry(index, &entry)?;
+            }
+            AuditOp::Delete(indices) => self.store.delete_audit_entries(&indices)?,
+        }
+        Ok(())
+    }
+}
+
+struct Inner {
+    capacity: usize,
+    /// Entries by their index in the DB, oldest first.
+    entries: VecDeque<(u64, Entry)>,
+    next_index: u64,
+    /// Queue of the writes, None once the audit log is being dropped.
+    writes: Option<mpsc::Sender<AuditOp>>,
+}
+
+pub(crate) struct AuditLog {
+    inner: Mutex<Inner>,
+    writer: Option<std::thread::JoinHandle<()>>,
+}
+
+impl AuditLog {
+    pub fn new(config: Config, store: store::Store) -> anyhow::Result<Self> {
+        let entries: VecDeque<_> = store.list_audit_entries()?.into();
+        let next_index = entries.back().map_or(0, |(index, _)| index + 1);
+        let (writes, receiver) = mpsc::channel();
+        let writer = Writer { export_path: config.export_path, store };
+        let writer = std::thread::spawn(move || writer.run(receiver));
+        let mut inner =
+            Inner { capacity: config.capacity, entries, next_index, writes: Some(writes) };
+        // The capacity might have been lowered since the entries were stored.
+        inner.truncate(0);
+        Ok(Self { inner: Mutex::new(inner), writer: Some(writer) })
+    }
+
+    pub fn record(&self, entry: Entry) {
+        tracing::debug!(target: "network", ?entry, "Audit log entry");
+        self.inner.lock().record(entry);
+    }
+
+    /// Returns the stored entries, the most recent first.
+    pub fn entries(&self) -> Vec<Entry> {
+        self.inner.lock().entries.iter().rev().map(|(_, entry)| entry.clone()).collect()
+    }
+}
+
+impl Drop for AuditLog {
+    fn drop(&mut self) {
+        // Closing the queue stops the writer once it wrote the queued entries.
+        self.inner.lock().writes.take();
+        if let Some(writer) = self.writer.take() {
+            if writer.join().is_err() {
+                tracing::error!(target: "network", "Audit log writer panicked");
+            }
+        }
+    }
+}
+
+impl Inner {
+    fn record(&mut self, entry: Entry) {
+        if self.capacity == 0 {
+            return;
+        }
+        if near_store::disk_budget().is_disabling_recorders() {
+            near_store::disk_budget().record_skipped("peer_audit_log");
+            return;
+        }
+        self.truncate(1);
+        let index = self.next_index;
+        self.next_index += 1;
+        self.write(AuditOp::Push(index, entry.clone()));
+        self.entries.push_back((index, entry));
+    }
+
+    /// Removes the oldest entries, so that `extra` more entries can be added
+    /// without exceeding the capacity.
+    fn truncate(&mut self, extra: usize) {
+        let keep = self.capacity.saturating_sub(extra);
+        if self.entries.len() <= keep {
+            return;
+        }
+        let removed: Vec<_> =
+            self.entries.drain(..self.entries.len() - keep).map(|(index, _)| index).collect();
+        self.write(AuditOp::Delete(removed));
+    }
+
+    fn write(&self, write: AuditOp) {
+        if let Some(writes) = &self.writes {
+            // The writer runs until the queue is closed.
+            let _ = writes.send(write);
+        }
+    }
+}
@@ -0,0 +1,85 @@
+use super::*;
+use crate::network_protocol::testonly as data;
+use crate::testonly::make_rng;
+use std::net::{Ipv4Addr, SocketAddrV4};
+
+fn make_entry(peer_id: PeerId, clock: &time::FakeClock, reason: Reason) -> Entry {
+    clock.advance(time::Duration::seconds(1));
+    Entry {
+        peer_id,
+        addr: Some(SocketAddrV4::new(Ipv4Addr::LOCALHOST, 24567).into()),
+        reason,
+        message_type: Some("Routed".to_string()),
+        evidence: "peer banned: InvalidSignature".to_string(),
+        connected_at: None,
+        time: clock.now_utc(),
+    }
+}
+
+fn config(capacity: usize) -> Config {
+    Config { capacity, export_path: None }
+}
+
+#[test]
+fn test_audit_log_capacity_and_persistence() {
+    let mut rng = make_rng(921853233);
+    let rng = &mut rng;
+    let clock = time::FakeClock::default();
+    let store = store::Store::from(near_store::db::TestDB::new());
+
+    let log = AuditLog::new(config(2), store.clone()).unwrap();
+    let reasons =
+        [Reason::Ban(ReasonForBan::InvalidSignature), Reason::Unresponsive, Reason::StreamError];
+    let entries: Vec<_> = reasons
+        .into_iter()
+        .map(|reason| make_entry(data::make_peer_id(rng), &clock, reason))
+        .collect();
+    for entry in &entries {
+        log.record(entry.clone());
+    }
+    // Only the most recent entries are kept, the latest first.
+    let want = vec![entries[2].clone(), entries[1].clone()];
+    assert_eq!(want, log.entries());
+
+    // Entries survive a restart, dropping the log waits for the queued writes.
+    drop(log);
+    let log = AuditLog::new(config(2), store.clone()).unwrap();
+    assert_eq!(want, log.entries());
+    drop(log);
+
+    // Lowering the capacity drops the oldest entries.
+    let log = AuditLog::new(config(1), store.clone()).unwrap();
+    assert_eq!(vec![want[0].clone()], log.entries());
+    let entry = make_entry(data::make_peer_id(rng), &clock, Reason::PeerManager);
+    log.record(entry.clone());
+    assert_eq!(vec![entry], log.entries());
+    drop(log);
+    assert_eq!(1, store.list_audit_entries().unwrap().len());
+}
+
+#[test]
+fn test_audit_log_export() {
+    let mut rng = make_rng(921853233);
+    let rng = &mut rng;
+    let clock = time::FakeClock::default();
+    let dir = tempfile::tempdir().unwrap();
+    let path = dir.path().join("audit.jsonl");
+    let store = store::Store::from(near_store::db::TestDB::new());
+    let log =
+        AuditLog::new(Config { capacity: 10, export_path: Some(path.clone()) }, store).unwrap();
+
+    let entries: Vec<_> = (0..3)
+        .map(|_| make_entry(data::make_peer_id(rng), &clock, Reason::HandshakeFailed))
+        .collect();
+    for entry in &entries {
+        log.record(entry.clone());
+    }
+    drop(log);
+    let exported: Vec<PeerAuditEntryView> = std::fs::read_to_string(&path)
+        .unwrap()
+        .lines()
+        .map(|line| serde_json::from_str(line).unwrap())
+        .collect();
+    let want: Vec<_> = entries.iter().map(Entry::view).collect();
+    assert_eq!(want, exported);
+}
@@ -2,18 +2,23 @@ use crate::concurrency::arc_mutex::ArcMutex;
 use crate::concurrency::atomic_cell::AtomicCell;
 use crate::concurrency::demux;
 use crate::network_protocol::{
-    Edge, PartialEdgeInfo, PeerChainInfoV2, PeerInfo, PeerMessage, SignedAccountData,
-    SyncAccountsData,
+    Edge, MaintenanceWindow, PartialEdgeInfo, PeerChainInfoV2, PeerInfo, PeerMessage,
+    SignedAccountData, SyncAccountsData,
 };
+use crate::peer::liveness::Liveness;
 use crate::peer::peer_actor;
 use crate::peer::peer_actor::PeerActor;
 use crate::private_actix::SendMessage;
+use crate::stats::bandwidth::BandwidthByType;
 use crate::stats::metrics;
+use crate::stats::metrics::NetworkMetrics;
 use crate::time;
 use crate::types::{FullPeerInfo, PeerType, ReasonForBan};
 use near_o11y::WithSpanContextExt;
 use near_primitives::network::PeerId;
-use std::collections::{hash_map::Entry, HashMap};
+use near_primitives::version::ProtocolVersion;
+use parking_lot::Mutex;
+use std::collections::{hash_map::Entry, HashMap, HashSet};
 use std::fmt;
 use std::future::Future;
 use std::sync::atomic::{AtomicU64, Ordering};
@@ -37,6 +42,9 @@ pub(crate) struct Stats {
     pub messages_to_send: AtomicU64,
     /// Number of bytes (sum of message sizes) in the buffer to send.
     pub bytes_to_send: AtomicU64,
+
+    /// Bytes exchanged since the connection was established, by message type.
+    pub bandwidth_by_type: BandwidthByType,
 }
 
 /// Contains information relevant to a connected peer.
@@ -49,6 +57,14 @@ pub(crate) struct Connection {
     pub edge: Edge,
     pub initial_chain_info: PeerChainInfoV2,
     pub chain_height: AtomicU64,
+    /// Protocol version announced by the peer in its handshake.
+    pub protocol_version: ProtocolVersion,
+    /// Largest size of the state sub-parts the peer serves, 0 if unknown.
+    pub state_sub_part_size_limit: u64,
+    /// Whether the peer accepts blocks announced by their header.
+    pub header_first_blocks: bool,
+    /// Maintenance window last announced by the peer.
+    pub maintenance_window: AtomicCell<Option<MaintenanceWindow>>,
 
     /// Who started connection. Inbound (other) or Outbound (us).
     pub peer_type: PeerType,
@@ -59,6 +75,8 @@ pub(crate) struct Connection {
     pub last_time_peer_requested: AtomicCell<Option<time::Instant>>,
     /// Last time we received a message from this peer.
     pub last_time_received_message: AtomicCell<time::Instant>,
+    /// Liveness check of the connection, also tracks the round trip time.
+    pub liveness: Mutex<Liveness>,
     /// Connection stats
     pub stats: Arc<Stats>,
     /// prometheus gauge point guard.
@@ -94,11 +112,22 @@ impl Connection {
                     self.edge.signature1().clone()
                 },
             },
+            state_sub_part_size_limit: self.state_sub_part_size_limit,
         }
     }
 
     pub fn stop(&self, ban_reason: Option<ReasonForBan>) {
-        self.addr.do_send(peer_actor::Stop { ban_reason }.with_span_context());
+        let reason = match ban_reason {
+            Some(reason) => peer_actor::ClosingReason::Ban(reason),
+            None => peer_actor::ClosingReason::PeerManager,
+        };
+        self.addr.do_send(peer_actor::Stop { reason }.with_span_context());
+    }
+
+    /// Closes the connection because the peer stopped responding.
+    pub fn stop_unresponsive(&self) {
+        let reason = peer_actor::ClosingReason::Unresponsive;
+        self.addr.do_send(peer_actor::Stop { reason }.with_span_context());
     }
 
     // TODO(gprusak): embed Stream directly in Connection,
@@ -218,7 +247,10 @@ impl Drop for OutboundHandshakePermit {
 }
 
 #[derive(Clone)]
-pub(crate) struct Pool(Arc<ArcMutex<PoolSnapshot>>);
+pub(crate) struct Pool {
+    snapshot: Arc<ArcMutex<PoolSnapshot>>,
+    metrics: Arc<NetworkMetrics>,
+}
 
 #[derive(thiserror::Error, Clone, Copy, Debug, PartialEq, Eq)]
 pub(crate) enum PoolError {
@@ -231,20 +263,23 @@ pub(crate) enum PoolError {
 }
 
 impl Pool {
-    pub fn new(me: PeerId) -> Pool {
-        Self(Arc::new(ArcMutex::new(PoolSnapshot {
-            me,
-            ready: im::HashMap::new(),
-            outbound_handshakes: im::HashSet::new(),
-        })))
+    pub fn new(me: PeerId, metrics: Arc<NetworkMetrics>) -> Pool {
+        Self {
+            snapshot: Arc::new(ArcMutex::new(PoolSnapshot {
+                me,
+                ready: im::HashMap::new(),
+                outbound_handshakes: im::HashSet::new(),
+            })),
+            metrics,
+        }
     }
 
     pub fn load(&self) -> Arc<PoolSnapshot> {
-        self.0.load()
+        self.snapshot.load()
     }
 
     pub fn insert_ready(&self, peer: Arc<Connection>) -> Result<(), PoolError> {
-        self.0.update(move |pool| {
+        self.snapshot.update(move |pool| {
             let id = &peer.peer_info.id;
             if id == &pool.me {
                 return Err(PoolError::LoopConnection);
@@ -277,7 +312,7 @@ impl Pool {
     }
 
     pub fn start_outbound(&self, peer_id: PeerId) -> Result<OutboundHandshakePermit, PoolError> {
-        self.0.update(move |pool| {
+        self.snapshot.update(move |pool| {
             if peer_id == pool.me {
                 return Err(PoolError::LoopConnection);
             }
@@ -288,12 +323,12 @@ impl Pool {
                 return Err(PoolError::AlreadyStartedConnecting);
             }
             pool.outbound_handshakes.insert(peer_id.clone());
-            Ok(OutboundHandshakePermit(peer_id, Arc::downgrade(&self.0)))
+            Ok(OutboundHandshakePermit(peer_id, Arc::downgrade(&self.snapshot)))
         })
     }
 
     pub fn remove(&self, peer_id: &PeerId) {
-        self.0.update(|pool| {
+        self.snapshot.update(|pool| {
             pool.ready.remove(peer_id);
         });
     }
@@ -317,9 +352,47 @@ impl Pool {
 
     /// Broadcast message to all ready peers.
     pub fn broadcast_message(&self, msg: Arc<PeerMessage>) {
-        metrics::BROADCAST_MESSAGES.with_label_values(&[msg.msg_variant()]).inc();
+        self.metrics.broadcast_messages.with_label_values(&[msg.msg_variant()]).inc();
         for peer in self.load().ready.values() {
             peer.send_message(msg.clone());
         }
     }
+
+    /// Broadcast message to all ready peers, sending it to the `priority`
+    /// peers first.
+    pub fn broadcast_message_with_priority(
+        &self,
+        msg: Arc<PeerMessage>,
+        priority: &HashSet<PeerId>,
+    ) {
+        self.metrics.broadcast_messages.with_label_values(&[msg.msg_variant()]).inc();
+        let pool = self.load();
+        let (first, rest): (Vec<_>, Vec<_>) =
+            pool.ready.values().partition(|peer| priority.contains(&peer.peer_info.id));
+        for peer in first.into_iter().chain(rest) {
+            peer.send_message(msg.clone());
+        }
+    }
+
+    /// Broadcast the block to all ready peers, sending it to the `priority`
+    /// peers first.  Peers which accept it get only its announcement.
+    pub fn broadcast_block_header_first(
+        &self,
+        block: Arc<PeerMessage>,
+        announcement: Arc<PeerMessage>,
+        priority: &HashSet<PeerId>,
+    ) {
+        let pool = self.load();
+        let (first, rest): (Vec<_>, Vec<_>) =
+            pool.ready.values().partition(|peer| priority.contains(&peer.peer_info.id));
+        let mut sent = HashSet::new();
+        for peer in first.into_iter().chain(rest) {
+            let msg = if peer.header_first_blocks { &announcement } else { &block };
+            sent.insert(msg.msg_variant());
+            peer.send_message(msg.clone());
+        }
+        for msg_variant in sent {
+            self.metrics.broadcast_messages.with_label_values(&[msg_variant]).inc();
+        }
+    }
 }
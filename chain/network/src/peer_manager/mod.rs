@@ -1,7 +1,11 @@
+pub(crate) mod audit_log;
 pub(crate) mod connection;
 pub(crate) mod network_state;
+pub(crate) mod peer_affinity;
 pub(crate) mod peer_manager_actor;
+pub(crate) mod peer_scores;
 pub(crate) mod peer_store;
+pub(crate) mod sync_requests;
 
 #[cfg(test)]
 pub(crate) mod testonly;
@@ -3,27 +3,36 @@ use crate::client;
 use crate::config;
 use crate::network_protocol::{
     Edge, EdgeState, PartialEdgeInfo, PeerIdOrHash, PeerInfo, PeerMessage, Ping, Pong,
-    RawRoutedMessage, RoutedMessageBody, RoutedMessageV2, RoutingTableUpdate,
+    RawRoutedMessage, RoutedMessage, RoutedMessageBody, RoutedMessageV2, RoutingTableUpdate,
+    TX_STATUS_SUBSCRIBE_PROTOCOL_VERSION,
 };
+use crate::peer_manager::audit_log;
 use crate::peer_manager::connection;
+use crate::peer_manager::peer_affinity;
 use crate::peer_manager::peer_manager_actor::Event;
+use crate::peer_manager::peer_scores;
 use crate::peer_manager::peer_store;
+use crate::peer_manager::sync_requests;
 use crate::private_actix::{PeerToManagerMsg, ValidateEdgeList};
 use crate::routing;
 use crate::routing::edge_validator_actor::EdgeValidatorHelper;
 use crate::routing::routing_table_view::RoutingTableView;
+use crate::stats::bandwidth::BandwidthByType;
 use crate::stats::metrics;
+use crate::stats::metrics::NetworkMetrics;
 use crate::store;
 use crate::time;
-use crate::types::{ChainInfo, ReasonForBan};
+use crate::types::{ChainInfo, PartialEncodedChunkRequestMsg, ReasonForBan};
 use actix::Recipient;
 use arc_swap::ArcSwap;
+use near_o11y::metrics::MetricsRegistry;
 use near_o11y::{WithSpanContext, WithSpanContextExt};
 use near_primitives::block::GenesisId;
 use near_primitives::hash::CryptoHash;
 use near_primitives::network::{AnnounceAccount, PeerId};
 use near_primitives::types::AccountId;
 use parking_lot::RwLock;
+use std::collections::HashSet;
 use std::net::SocketAddr;
 use std::sync::atomic::{AtomicU32, AtomicUsize, Ordering};
 use std::sync::Arc;
@@ -107,8 +116,6 @@ pub(crate) struct NetworkState {
     runtime: Runtime,
     /// PeerManager config.
     pub config: Arc<config::VerifiedConfig>,
-    /// When network state has been constructed.
-    pub start_time: time::Instant,
     /// GenesisId of the chain.
     pub genesis_id: GenesisId,
     pub client: Arc<dyn client::Client>,
@@ -127,6 +134,16 @@ pub(crate) struct NetworkState {
     pub inbound_handshake_permits: Arc<tokio::sync::Semaphore>,
     /// Peer store that provides read/write access to peers.
     pub peer_store: peer_store::PeerStore,
+    /// Audit trail of the decisions to ban or disconnect peers.
+    pub audit_log: audit_log::AuditLog,
+    /// Scores deciding when misbehaving peers get banned.
+    pub peer_scores: peer_scores::PeerScores,
+    /// Peers the node always tries to stay connected to.
+    pub peer_affinity: peer_affinity::PeerAffinity,
+    /// Bytes exchanged with all peers, by message type.
+    pub bandwidth_by_type: BandwidthByType,
+    /// Limits on serving the block and header requests of peers.
+    pub sync_requests: Arc<sync_requests::Limiter>,
     /// A graph of the whole NEAR network.
     pub graph: Arc<RwLock<routing::GraphWithCache>>,
 
@@ -152,6 +169,7 @@ pub(crate) struct NetworkState {
     /// TODO(gprusak): determine why tests need to change that dynamically
     /// in the first place.
     pub max_num_peers: AtomicU32,
+    pub(crate) metrics: Arc<NetworkMetrics>,
 }
 
 impl NetworkState {
@@ -159,24 +177,42 @@ impl NetworkState {
         clock: &time::Clock,
         store: store::Store,
         peer_store: peer_store::PeerStore,
+        audit_log: audit_log::AuditLog,
         config: Arc<config::VerifiedConfig>,
         genesis_id: GenesisId,
         client: Arc<dyn client::Client>,
         peer_manager_addr: Recipient<WithSpanContext<PeerToManagerMsg>>,
         whitelist_nodes: Vec<WhitelistNode>,
     ) -> Self {
-        let graph = Arc::new(RwLock::new(routing::GraphWithCache::new(config.node_id())));
+        let metrics = MetricsRegistry::for_instance(config.metrics_instance.as_deref())
+            .get::<NetworkMetrics>();
+        let graph =
+            Arc::new(RwLock::new(routing::GraphWithCache::new(config.node_id(), metrics.clone())));
         Self {
             runtime: Runtime::new(),
-            routing_table_addr: routing::Actor::spawn(clock.clone(), store.clone(), graph.clone()),
+            routing_table_addr: routing::Actor::spawn(
+                clock.clone(),
+                store.clone(),
+                graph.clone(),
+                config.tombstones.clone(),
+                metrics.clone(),
+            ),
             graph,
             genesis_id,
             client,
             peer_manager_addr,
             chain_info: Default::default(),
-            tier2: connection::Pool::new(config.node_id()),
+            tier2: connection::Pool::new(config.node_id(), metrics.clone()),
             inbound_handshake_permits: Arc::new(tokio::sync::Semaphore::new(LIMIT_PENDING_PEERS)),
             peer_store,
+            audit_log,
+            peer_scores: peer_scores::PeerScores::new(config.peer_scores.clone()),
+            peer_affinity: peer_affinity::PeerAffinity::new(config.peer_affinity.clone()),
+            bandwidth_by_type: BandwidthByType::default(),
+            sync_requests: Arc::new(sync_requests::Limiter::new(
+                config.sync_requests.clone(),
+                metrics.clone(),
+            )),
             accounts_data: Arc::new(accounts_data::Cache::new()),
             routing_table_view: RoutingTableView::new(store, config.node_id()),
             routing_table_exchange_helper: Default::default(),
@@ -184,7 +220,7 @@ impl NetworkState {
             max_num_peers: AtomicU32::new(config.max_num_peers),
             config,
             txns_since_last_block: AtomicUsize::new(0),
-            start_time: clock.now(),
+            metrics,
         }
     }
 
@@ -204,6 +240,14 @@ impl NetworkState {
         PartialEdgeInfo::new(&self.config.node_id(), peer1, nonce, &self.config.node_key)
     }
 
+    /// Lowers the score of the peer for the misbehavior, and bans the peer if
+    /// the score dropped to the ban threshold.
+    pub fn penalize_peer(&self, clock: &time::Clock, peer_id: &PeerId, ban_reason: ReasonForBan) {
+        if self.peer_scores.penalize(peer_id, ban_reason) {
+            self.disconnect_and_ban(clock, peer_id, ban_reason);
+        }
+    }
+
     /// Stops peer instance if it is still connected,
     /// and then mark peer as banned in the peer store.
     pub fn disconnect_and_ban(
@@ -216,6 +260,16 @@ impl NetworkState {
         if let Some(peer) = tier2.ready.get(peer_id) {
             peer.stop(Some(ban_reason));
         } else {
+            // There is no connection to close, so the ban is recorded here.
+            self.audit_log.record(audit_log::Entry {
+                peer_id: peer_id.clone(),
+                addr: None,
+                reason: audit_log::Reason::Ban(ban_reason),
+                message_type: None,
+                evidence: "banned while not connected".to_string(),
+                connected_at: None,
+                time: clock.now_utc(),
+            });
             if let Err(err) = self.peer_store.peer_ban(clock, peer_id, ban_reason) {
                 tracing::error!(target: "network", ?err, "Failed to save peer data");
             }
@@ -243,9 +297,9 @@ impl NetworkState {
         {
             return true;
         }
-        // Whitelisted nodes are allowed to connect, even if the inbound connections limit has
-        // been reached.
-        if self.is_peer_whitelisted(peer_info) {
+        // Whitelisted nodes and preferred peers are allowed to connect, even if the inbound
+        // connections limit has been reached.
+        if self.is_peer_whitelisted(peer_info) || self.peer_affinity.is_preferred(&peer_info.id) {
             return true;
         }
         false
@@ -297,6 +351,21 @@ impl NetworkState {
         self.send_message_to_peer(clock, self.sign_message(clock, msg));
     }
 
+    /// Sends a ping directly over the given connection, to check that the peer is alive.
+    pub(crate) fn send_liveness_ping(
+        &self,
+        clock: &time::Clock,
+        conn: &connection::Connection,
+        nonce: u64,
+    ) {
+        let body = RoutedMessageBody::Ping(Ping { nonce, source: self.config.node_id() });
+        let target = PeerIdOrHash::PeerId(conn.peer_info.id.clone());
+        let msg = self.sign_message(clock, RawRoutedMessage { target, body });
+        // The pong is routed back by the hash of the ping.
+        self.routing_table_view.add_route_back(clock, msg.hash(), self.config.node_id());
+        conn.send_message(Arc::new(PeerMessage::Routed(msg)));
+    }
+
     pub fn send_pong(&self, clock: &time::Clock, nonce: u64, target: CryptoHash) {
         let body = RoutedMessageBody::Pong(Pong { nonce, source: self.config.node_id() });
         let msg = RawRoutedMessage { target: PeerIdOrHash::Hash(target), body };
@@ -320,7 +389,7 @@ impl NetworkState {
         if let PeerIdOrHash::PeerId(target) = &msg.target {
             if target == &my_peer_id {
                 debug!(target: "network", account_id = ?self.config.validator.as_ref().map(|v|v.account_id()), ?my_peer_id, ?msg, "Drop signed message to myself");
-                metrics::CONNECTED_TO_MYSELF.inc();
+                self.metrics.connected_to_myself.inc();
                 return false;
             }
         }
@@ -335,7 +404,7 @@ impl NetworkState {
             }
             Err(find_route_error) => {
                 // TODO(MarX, #1369): Message is dropped here. Define policy for this case.
-                metrics::MessageDropped::NoRouteFound.inc(&msg.body);
+                metrics::MessageDropped::NoRouteFound.inc(&self.metrics, &msg.body);
 
                 debug!(target: "network",
                       account_id = ?self.config.validator.as_ref().map(|v|v.account_id()),
@@ -350,6 +419,17 @@ impl NetworkState {
         }
     }
 
+    /// Peers of the validators which the client marked as the most important
+    /// right now, see `ChainInfo::priority_validators`.
+    pub fn priority_peers(&self) -> HashSet<PeerId> {
+        self.chain_info
+            .load()
+            .priority_validators
+            .iter()
+            .filter_map(|account_id| self.routing_table_view.account_owner(account_id))
+            .collect()
+    }
+
     /// Send message to specific account.
     /// Return whether the message is sent or not.
     pub fn send_message_to_account(
@@ -362,7 +442,7 @@ impl NetworkState {
             Some(peer_id) => peer_id,
             None => {
                 // TODO(MarX, #1369): Message is dropped here. Define policy for this case.
-                metrics::MessageDropped::UnknownAccount.inc(&msg);
+                metrics::MessageDropped::UnknownAccount.inc(&self.metrics, &msg);
                 debug!(target: "network",
                        account_id = ?self.config.validator.as_ref().map(|v|v.account_id()),
                        to = ?account_id,
@@ -386,6 +466,122 @@ impl NetworkState {
         }
     }
 
+    /// Subscribes to the final outcome of a transaction at the owner of the
+    /// account.  The subscription is only sent if the owner is connected
+    /// directly and knows it, see `TX_STATUS_SUBSCRIBE_PROTOCOL_VERSION`:
+    /// peers on older versions can neither handle nor route it.  Otherwise the
+    /// status is requested once with a `TxStatusRequest`.
+    pub fn send_tx_status_subscribe(
+        &self,
+        clock: &time::Clock,
+        account_id: &AccountId,
+        signer_account_id: AccountId,
+        tx_hash: CryptoHash,
+    ) -> bool {
+        let supported =
+            self.routing_table_view.account_owner(account_id).map_or(false, |peer_id| {
+                self.tier2.load().ready.get(&peer_id).map_or(false, |conn| {
+                    conn.protocol_version >= TX_STATUS_SUBSCRIBE_PROTOCOL_VERSION
+                })
+            });
+        let body = if supported {
+            RoutedMessageBody::TxStatusSubscribe(signer_account_id, tx_hash)
+        } else {
+            RoutedMessageBody::TxStatusRequest(signer_account_id, tx_hash)
+        };
+        self.send_message_to_account(clock, account_id, body)
+    }
+
+    /// Sends the chunk part request to the owner of the account.  If
+    /// `chunk_request_relay_rtt_threshold` is set and there's no route to the
+    /// owner, or the round trip times to all the first hops of the routes
+    /// exceed the threshold, the request is relayed through the directly
+    /// connected validator with the lowest round trip time instead.
+    pub fn send_partial_encoded_chunk_request(
+        &self,
+        clock: &time::Clock,
+        account_id: &AccountId,
+        request: PartialEncodedChunkRequestMsg,
+    ) -> bool {
+        let body = RoutedMessageBody::PartialEncodedChunkRequest(request);
+        let threshold = match self.config.chunk_request_relay_rtt_threshold {
+            Some(threshold) => threshold,
+            None => return self.send_message_to_account(clock, account_id, body),
+        };
+        let target = match self.routing_table_view.account_owner(account_id) {
+            Some(peer_id) => peer_id,
+            None => return self.send_message_to_account(clock, account_id, body),
+        };
+        let tier2 = self.tier2.load();
+        let rtt =
+            |peer_id: &PeerId| tier2.ready.get(peer_id).and_then(|conn| conn.liveness.lock().rtt());
+        // The direct route is used unless it's known to be slow.
+        let next_hops = self.routing_table_view.view_route(&target).unwrap_or_default();
+        if next_hops.iter().any(|peer_id| rtt(peer_id).map_or(true, |rtt| rtt <= threshold)) {
+            return self.send_message_to_account(clock, account_id, body);
+        }
+        let validators: HashSet<PeerId> = self
+            .routing_table_view
+            .get_announce_accounts()
+            .into_iter()
+            .map(|announce_account| announce_account.peer_id)
+            .collect();
+        let relay = tier2
+            .ready
+            .keys()
+            .filter(|peer_id| {
+                **peer_id != target
+                    && !next_hops.contains(*peer_id)
+                    && validators.contains(*peer_id)
+            })
+            .filter_map(|peer_id| Some((rtt(peer_id)?, peer_id)))
+            .filter(|(rtt, _)| *rtt <= threshold)
+            .min_by_key(|(rtt, _)| *rtt)
+            .map(|(_, peer_id)| peer_id.clone());
+        let relay = match relay {
+            Some(relay) => relay,
+            None => return self.send_message_to_account(clock, account_id, body),
+        };
+        debug!(target: "network", ?target, ?relay, "Relay chunk part request");
+        let msg = self
+            .sign_message(clock, RawRoutedMessage { target: PeerIdOrHash::PeerId(target), body });
+        // The relay routes the response back to us.
+        self.routing_table_view.add_route_back(clock, msg.hash(), self.config.node_id());
+        let body = RoutedMessageBody::RelayedPartialEncodedChunkRequest(Box::new(msg.msg));
+        let msg = self
+            .sign_message(clock, RawRoutedMessage { target: PeerIdOrHash::PeerId(relay), body });
+        self.metrics.partial_encoded_chunk_request_relayed.with_label_values(&["sent"]).inc();
+        self.send_message_to_peer(clock, msg)
+    }
+
+    /// Forwards the chunk part request `msg` which the peer `from` asked this
+    /// node to relay in a message authored by `author`.  Only requests signed
+    /// by the peer itself are forwarded, so that the response can be routed
+    /// back to it, and relayed requests are never relayed again, which keeps
+    /// them from looping.
+    pub fn forward_relayed_chunk_request(
+        &self,
+        clock: &time::Clock,
+        from: &PeerId,
+        author: &PeerId,
+        mut msg: RoutedMessage,
+    ) -> bool {
+        let relayed = &self.metrics.partial_encoded_chunk_request_relayed;
+        let valid = author == from
+            && &msg.author == author
+            && matches!(msg.body, RoutedMessageBody::PartialEncodedChunkRequest(_))
+            && !self.message_for_me(&msg.target)
+            && msg.verify();
+        if !valid || !msg.decrease_ttl() {
+            debug!(target: "network", ?from, ?msg, "Drop relayed chunk part request");
+            relayed.with_label_values(&["dropped"]).inc();
+            return false;
+        }
+        self.routing_table_view.add_route_back(clock, msg.hash(), from.clone());
+        relayed.with_label_values(&["forwarded"]).inc();
+        self.send_message_to_peer(clock, Box::new(RoutedMessageV2 { msg, created_at: None }))
+    }
+
     pub fn add_verified_edges_to_routing_table(
         self: &Arc<Self>,
         clock: &time::Clock,
@@ -405,16 +601,10 @@ impl NetworkState {
             {
                 Ok(routing::actor::Response::AddVerifiedEdgesResponse(mut edges)) => {
                     this.config.event_sink.push(Event::EdgesVerified(edges.clone()));
-                    // Don't send tombstones during the initial time.
-                    // Most of the network is created during this time, which results
-                    // in us sending a lot of tombstones to peers.
-                    // Later, the amount of new edges is a lot smaller.
-                    if let Some(skip_tombstones_duration) = this.config.skip_tombstones {
-                        if clock.now() < this.start_time + skip_tombstones_duration {
-                            edges.retain(|e| e.edge_type() == EdgeState::Active);
-                            metrics::EDGE_TOMBSTONE_SENDING_SKIPPED.inc();
-                        }
-                    }
+                    // Expired tombstones are new only to us, don't pass them on.
+                    let expired =
+                        this.config.tombstones.retain_unexpired(clock.now_utc(), &mut edges);
+                    this.metrics.edge_tombstone_sending_skipped.inc_by(expired as u64);
                     this.tier2.broadcast_message(Arc::new(PeerMessage::SyncRoutingTable(
                         RoutingTableUpdate::from_edges(edges),
                     )));
@@ -0,0 +1,78 @@
+//! Preferred peers, which the node always tries to stay connected to.
+//!
+//! Operators list the peers the node should be connected to regardless of
+//! the number of its other connections, such as the other node of a
+//! validator pair or the nodes of a private mesh.  The PeerManager dials the
+//! preferred peers which aren't connected and never disconnects them while
+//! pruning connections above `ideal_connections_hi`.  They're also let in
+//! when the node already has `max_num_peers` connections.
+//!
+//! Unlike boot nodes, which are only a starting point for discovering the
+//! network, preferred peers are redialed for as long as the node runs.  A
+//! peer which can't be reached is redialed with an exponential backoff,
+//! which starts over once the connection is established.
+use crate::network_protocol::PeerAddr;
+use crate::time;
+use near_primitives::network::PeerId;
+use parking_lot::Mutex;
+use std::collections::HashMap;
+
+#[cfg(test)]
+mod tests;
+
+#[derive(Clone, Debug)]
+pub struct Config {
+    pub preferred_peers: Vec<PeerAddr>,
+    /// Delay between the first two attempts to reconnect to a preferred peer.
+    pub min_backoff: time::Duration,
+    /// The delay doubles with every failed attempt up to this.
+    pub max_backoff: time::Duration,
+}
+
+struct Backoff {
+    next_attempt: time::Instant,
+    delay: time::Duration,
+}
+
+pub(crate) struct PeerAffinity {
+    config: Config,
+    /// Backoffs of the preferred peers which aren't connected.
+    backoffs: Mutex<HashMap<PeerId, Backoff>>,
+}
+
+impl PeerAffinity {
+    pub fn new(config: Config) -> Self {
+        Self { config, backoffs: Mutex::new(HashMap::new()) }
+    }
+
+    pub fn is_preferred(&self, peer_id: &PeerId) -> bool {
+        self.config.preferred_peers.iter().any(|peer| &peer.peer_id == peer_id)
+    }
+
+    /// Returns the preferred peers which should be dialed now, out of the ones
+    /// which aren't connected as told by `is_connected`.  The returned peers
+    /// are considered dialed, so their backoff grows.
+    pub fn peers_to_dial(
+        &self,
+        clock: &time::Clock,
+        is_connected: impl Fn(&PeerId) -> bool,
+    ) -> Vec<PeerAddr> {
+        let now = clock.now();
+        let mut backoffs = self.backoffs.lock();
+        let mut peers = vec![];
+        for peer in &self.config.preferred_peers {
+            if is_connected(&peer.peer_id) {
+                backoffs.remove(&peer.peer_id);
+                continue;
+            }
+            let delay = match backoffs.get(&peer.peer_id) {
+                None => self.config.min_backoff,
+                Some(backoff) if now < backoff.next_attempt => continue,
+                Some(backoff) => std::cmp::min(backoff.delay * 2, self.config.max_backoff),
+            };
+            backoffs.insert(peer.peer_id.clone(), Backoff { next_attempt: now + delay, delay });
+            peers.push(peer.clone());
+        }
+        peers
+    }
+}
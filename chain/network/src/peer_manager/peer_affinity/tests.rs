@@ -0,0 +1,43 @@
+use super::*;
+use crate::network_protocol::testonly as data;
+use crate::testonly::make_rng;
+use std::net::{IpAddr, Ipv4Addr};
+
+#[test]
+fn test_backoff() {
+    let mut rng = make_rng(6230141);
+    let rng = &mut rng;
+    let clock = time::FakeClock::default();
+    let ip = IpAddr::V4(Ipv4Addr::LOCALHOST);
+    let peer = data::make_peer_addr(rng, ip);
+    let other_peer = data::make_peer_addr(rng, ip);
+    let affinity = PeerAffinity::new(Config {
+        preferred_peers: vec![peer.clone(), other_peer.clone()],
+        min_backoff: time::Duration::seconds(1),
+        max_backoff: time::Duration::seconds(3),
+    });
+    assert!(affinity.is_preferred(&peer.peer_id));
+    assert!(!affinity.is_preferred(&data::make_peer_id(rng)));
+
+    let dial = |connected: &[&PeerAddr]| {
+        affinity.peers_to_dial(&clock.clock(), |peer_id| {
+            connected.iter().any(|peer| &peer.peer_id == peer_id)
+        })
+    };
+    // Peers which aren't connected are dialed right away.
+    assert_eq!(dial(&[&other_peer]), vec![peer.clone()]);
+    assert_eq!(dial(&[&other_peer]), vec![]);
+    // The delay between attempts doubles up to the maximum.
+    for delay in [1, 2, 3, 3] {
+        clock.advance(time::Duration::seconds(delay - 1));
+        assert_eq!(dial(&[&other_peer]), vec![]);
+        clock.advance(time::Duration::seconds(1));
+        assert_eq!(dial(&[&other_peer]), vec![peer.clone()]);
+    }
+
+    // The backoff starts over once the peer gets connected.
+    assert_eq!(dial(&[&peer, &other_peer]), vec![]);
+    assert_eq!(dial(&[]), vec![peer.clone(), other_peer.clone()]);
+    clock.advance(time::Duration::seconds(1));
+    assert_eq!(dial(&[]), vec![peer, other_peer]);
+}
@@ -2,10 +2,12 @@ use crate::client;
 use crate::config;
 use crate::debug::{DebugStatus, GetDebugStatus};
 use crate::network_protocol::{
-    AccountData, AccountOrPeerIdOrHash, Edge, EdgeState, PeerMessage, Ping, Pong, RawRoutedMessage,
-    RoutedMessageBody, StateResponseInfo, SyncAccountsData,
+    AccountData, AccountOrPeerIdOrHash, BlockHeaderAnnouncement, Edge, EdgeState, PeerInfo,
+    PeerMessage, Ping, Pong, RawRoutedMessage, RoutedMessageBody, StateResponseInfo,
+    SyncAccountsData,
 };
 use crate::peer::peer_actor::PeerActor;
+use crate::peer_manager::audit_log;
 use crate::peer_manager::connection;
 use crate::peer_manager::network_state::{NetworkState, WhitelistNode};
 use crate::peer_manager::peer_store;
@@ -14,7 +16,6 @@ use crate::private_actix::{
 };
 use crate::private_actix::{PeerToManagerMsg, PeerToManagerMsgResp, PeersResponse};
 use crate::routing;
-use crate::stats::metrics;
 use crate::store;
 use crate::tcp;
 use crate::time;
@@ -33,7 +34,7 @@ use near_o11y::{handler_trace_span, OpenTelemetrySpanExt, WithSpanContext, WithS
 use near_performance_metrics_macros::perf;
 use near_primitives::block::GenesisId;
 use near_primitives::network::{AnnounceAccount, PeerId};
-use near_primitives::views::{KnownPeerStateView, PeerStoreView};
+use near_primitives::views::{KnownPeerStateView, PeerAuditLogView, PeerStoreView};
 use rand::seq::IteratorRandom;
 use rand::thread_rng;
 use rand::Rng;
@@ -69,7 +70,7 @@ const REPORT_BANDWIDTH_THRESHOLD_COUNT: usize = 10_000;
 const PRUNE_UNREACHABLE_PEERS_AFTER: time::Duration = time::Duration::hours(1);
 
 /// Remove the edges that were created more that this duration ago.
-const PRUNE_EDGES_AFTER: time::Duration = time::Duration::minutes(30);
+pub(crate) const PRUNE_EDGES_AFTER: time::Duration = time::Duration::minutes(30);
 
 /// If a peer is more than these blocks behind (comparing to our current head) - don't route any messages through it.
 /// We are updating the list of unreliable peers every MONITOR_PEER_MAX_DURATION (60 seconds) - so the current
@@ -85,6 +86,8 @@ pub const MAX_NUM_PEERS: usize = 128;
 /// (a.k.a. ones that we've been connected to in the past) with these odds.
 /// Otherwise, we'd pick any peer that we've heard about.
 const PREFER_PREVIOUSLY_CONNECTED_PEER: f64 = 0.6;
+/// How often to check whether the preferred peers are connected.
+const CONNECT_PREFERRED_PEERS_INTERVAL: time::Duration = time::Duration::milliseconds(500);
 
 /// Actor that manages peers connections.
 pub struct PeerManagerActor {
@@ -194,6 +197,9 @@ impl Actor for PeerManagerActor {
             }
         }));
 
+        // Periodically dials the preferred peers which aren't connected.
+        self.connect_preferred_peers_trigger(ctx, CONNECT_PREFERRED_PEERS_INTERVAL);
+
         // Periodically reads valid edges from `EdgesVerifierActor` and broadcast.
         self.broadcast_validated_edges_trigger(ctx, BROADCAST_VALIDATED_EDGES_INTERVAL);
 
@@ -236,6 +242,8 @@ impl PeerManagerActor {
                banned = peer_store.count_banned(),
                "Found known peers");
         tracing::debug!(target: "network", blacklist = ?config.peer_store.blacklist, "Blacklist");
+        let audit_log = audit_log::AuditLog::new(config.peer_audit_log.clone(), store.clone())
+            .context("AuditLog::new")?;
 
         let my_peer_id = config.node_id();
         let whitelist_nodes = {
@@ -254,6 +262,7 @@ impl PeerManagerActor {
                 &clock,
                 store.clone(),
                 peer_store,
+                audit_log,
                 config.clone(),
                 genesis_id,
                 client,
@@ -288,7 +297,7 @@ impl PeerManagerActor {
                 }) => {
                     act.state.routing_table_view.update(&pruned_edges, next_hops.clone());
                     for peer in peers_to_ban {
-                        act.state.disconnect_and_ban(&act.clock, &peer, ReasonForBan::InvalidEdge);
+                        act.state.penalize_peer(&act.clock, &peer, ReasonForBan::InvalidEdge);
                     }
                     act.config
                         .event_sink
@@ -305,7 +314,10 @@ impl PeerManagerActor {
     ///   waiting to have their signatures checked.
     /// - edge pruning may be disabled for unit testing.
     fn update_routing_table_trigger(&self, ctx: &mut Context<Self>, interval: time::Duration) {
-        let _timer = metrics::PEER_MANAGER_TRIGGER_TIME
+        let _timer = self
+            .state
+            .metrics
+            .peer_manager_trigger_time
             .with_label_values(&["update_routing_table"])
             .start_timer();
         self.update_routing_table(
@@ -325,7 +337,10 @@ impl PeerManagerActor {
 
     /// Periodically prints bandwidth stats for each peer.
     fn report_bandwidth_stats_trigger(&mut self, ctx: &mut Context<Self>, every: time::Duration) {
-        let _timer = metrics::PEER_MANAGER_TRIGGER_TIME
+        let _timer = self
+            .state
+            .metrics
+            .peer_manager_trigger_time
             .with_label_values(&["report_bandwidth_stats"])
             .start_timer();
         let mut total_bandwidth_used_by_all_peers: usize = 0;
@@ -371,7 +386,10 @@ impl PeerManagerActor {
     ) {
         let _span =
             tracing::trace_span!(target: "network", "broadcast_validated_edges_trigger").entered();
-        let _timer = metrics::PEER_MANAGER_TRIGGER_TIME
+        let _timer = self
+            .state
+            .metrics
+            .peer_manager_trigger_time
             .with_label_values(&["broadcast_validated_edges"])
             .start_timer();
         let start = self.clock.now();
@@ -437,8 +455,20 @@ impl PeerManagerActor {
 
     /// Returns peers close to the highest height
     fn highest_height_peers(&self) -> Vec<FullPeerInfo> {
-        let infos: Vec<_> =
-            self.state.tier2.load().ready.values().map(|p| p.full_peer_info()).collect();
+        let infos: Vec<_> = self
+            .state
+            .tier2
+            .load()
+            .ready
+            .values()
+            // Don't sync from peers which are about to shut down.
+            .filter(|p| {
+                !p.maintenance_window
+                    .load()
+                    .map_or(false, |w| w.is_active(p.chain_height.load(Ordering::Relaxed)))
+            })
+            .map(|p| p.full_peer_info())
+            .collect();
 
         // This finds max height among peers, and returns one peer close to such height.
         let max_height = match infos.iter().map(|i| i.chain_info.height).max() {
@@ -472,18 +502,20 @@ impl PeerManagerActor {
             .collect()
     }
 
-    /// Check if the number of connections (excluding whitelisted ones) exceeds ideal_connections_hi.
+    /// Check if the number of connections (excluding whitelisted and preferred ones) exceeds ideal_connections_hi.
     /// If so, constructs a safe set of peers and selects one random peer outside of that set
     /// and sends signal to stop connection to it gracefully.
     ///
     /// Safe set contruction process:
-    /// 1. Add all whitelisted peers to the safe set.
+    /// 1. Add all whitelisted and preferred peers to the safe set.
     /// 2. If the number of outbound connections is less or equal than minimum_outbound_connections,
     ///    add all outbound connections to the safe set.
-    /// 3. Find all peers who sent us a message within the last peer_recent_time_window,
+    /// 3. Add the peers of the validators which the client marked as the most
+    ///    important right now.
+    /// 4. Find all peers who sent us a message within the last peer_recent_time_window,
     ///    and add them one by one to the safe_set (starting from earliest connection time)
     ///    until safe set has safe_set_size elements.
-    fn maybe_stop_active_connection(&self) {
+    pub(crate) fn maybe_stop_active_connection(&self) {
         let tier2 = self.state.tier2.load();
         let filter_peers = |predicate: &dyn Fn(&connection::Connection) -> bool| -> Vec<_> {
             tier2
@@ -497,8 +529,11 @@ impl PeerManagerActor {
         // Build safe set
         let mut safe_set = HashSet::new();
 
-        // Add whitelisted nodes to the safe set.
-        let whitelisted_peers = filter_peers(&|p| self.state.is_peer_whitelisted(&p.peer_info));
+        // Add whitelisted nodes and preferred peers to the safe set.
+        let whitelisted_peers = filter_peers(&|p| {
+            self.state.is_peer_whitelisted(&p.peer_info)
+                || self.state.peer_affinity.is_preferred(&p.peer_info.id)
+        });
         safe_set.extend(whitelisted_peers);
 
         // If there is not enough non-whitelisted peers, return without disconnecting anyone.
@@ -522,6 +557,10 @@ impl PeerManagerActor {
             }
         }
 
+        // Keep the peers of the validators which matter the most right now.
+        let priority_peers = self.state.priority_peers();
+        safe_set.extend(filter_peers(&|p| priority_peers.contains(&p.peer_info.id)));
+
         // Find all recently active peers.
         let now = self.clock.now();
         let mut active_peers: Vec<Arc<connection::Connection>> = tier2
@@ -575,8 +614,12 @@ impl PeerManagerActor {
         (default_interval, max_interval): (time::Duration, time::Duration),
     ) {
         let _span = tracing::trace_span!(target: "network", "monitor_peers_trigger").entered();
-        let _timer =
-            metrics::PEER_MANAGER_TRIGGER_TIME.with_label_values(&["monitor_peers"]).start_timer();
+        let _timer = self
+            .state
+            .metrics
+            .peer_manager_trigger_time
+            .with_label_values(&["monitor_peers"])
+            .start_timer();
 
         self.state.peer_store.unban(&self.clock);
         if let Err(err) = self.state.peer_store.update_connected_peers_last_seen(&self.clock) {
@@ -633,7 +676,7 @@ impl PeerManagerActor {
 
         // Find peers that are not reliable (too much behind) - and make sure that we're not routing messages through them.
         let unreliable_peers = self.unreliable_peers();
-        metrics::PEER_UNRELIABLE.set(unreliable_peers.len() as i64);
+        self.state.metrics.peer_unreliable.set(unreliable_peers.len() as i64);
         self.state.graph.write().set_unreliable_peers(unreliable_peers);
 
         let new_interval = min(max_interval, interval * EXPONENTIAL_BACKOFF_RATIO);
@@ -647,6 +690,52 @@ impl PeerManagerActor {
         );
     }
 
+    /// Dials the preferred peers which aren't connected, unless their backoff
+    /// hasn't expired yet.  Banned peers aren't dialed even if preferred.
+    fn connect_preferred_peers_trigger(&self, ctx: &mut Context<Self>, interval: time::Duration) {
+        let _timer = self
+            .state
+            .metrics
+            .peer_manager_trigger_time
+            .with_label_values(&["connect_preferred_peers"])
+            .start_timer();
+        if !self.config.outbound_disabled {
+            let tier2 = self.state.tier2.load();
+            let peers = self.state.peer_affinity.peers_to_dial(&self.clock, |peer_id| {
+                tier2.ready.contains_key(peer_id) || tier2.outbound_handshakes.contains(peer_id)
+            });
+            for peer in peers {
+                if self.state.peer_store.is_banned(&peer.peer_id) {
+                    continue;
+                }
+                let peer_info =
+                    PeerInfo { id: peer.peer_id, addr: Some(peer.addr), account_id: None };
+                ctx.spawn(wrap_future({
+                    let state = self.state.clone();
+                    let clock = self.clock.clone();
+                    async move {
+                        let result = async {
+                            let stream = tcp::Stream::connect(&peer_info).await.context("tcp::Stream::connect()")?;
+                            PeerActor::spawn(clock, stream, None, state).context("PeerActor::spawn()")?;
+                            anyhow::Ok(())
+                        }.await;
+                        if let Err(err) = result {
+                            tracing::info!(target: "network", ?err, "failed to connect to preferred peer {peer_info}");
+                        }
+                    }
+                }.instrument(tracing::trace_span!(target: "network", "connect_preferred_peer"))));
+            }
+        }
+
+        near_performance_metrics::actix::run_later(
+            ctx,
+            interval.try_into().unwrap(),
+            move |act, ctx| {
+                act.connect_preferred_peers_trigger(ctx, interval);
+            },
+        );
+    }
+
     /// Return whether the message is sent or not.
     fn send_message_to_account_or_peer_or_hash(
         &mut self,
@@ -684,6 +773,9 @@ impl PeerManagerActor {
                     last_time_received_message: cp.last_time_received_message.load(),
                     connection_established_time: cp.connection_established_time,
                     peer_type: cp.peer_type,
+                    rtt: cp.liveness.lock().rtt(),
+                    score: self.state.peer_scores.get(&cp.peer_info.id),
+                    bandwidth_by_type: cp.stats.bandwidth_by_type.get(),
                 })
                 .collect(),
             num_connected_peers: tier2.ready.len(),
@@ -713,13 +805,17 @@ impl PeerManagerActor {
                 })
                 .collect(),
             tier1_accounts: self.state.accounts_data.load().data.values().cloned().collect(),
+            bandwidth_by_type: self.state.bandwidth_by_type.get(),
         }
     }
 
     fn push_network_info_trigger(&self, ctx: &mut Context<Self>, interval: time::Duration) {
         let _span = tracing::trace_span!(target: "network", "push_network_info_trigger").entered();
         let network_info = self.get_network_info();
-        let _timer = metrics::PEER_MANAGER_TRIGGER_TIME
+        let _timer = self
+            .state
+            .metrics
+            .peer_manager_trigger_time
             .with_label_values(&["push_network_info"])
             .start_timer();
         // TODO(gprusak): just spawn a loop.
@@ -752,10 +848,27 @@ impl PeerManagerActor {
         let _d = delay_detector::DelayDetector::new(|| {
             format!("network request {}", msg.as_ref()).into()
         });
-        metrics::REQUEST_COUNT_BY_TYPE_TOTAL.with_label_values(&[msg.as_ref()]).inc();
+        self.state.metrics.request_count_by_type_total.with_label_values(&[msg.as_ref()]).inc();
         match msg {
             NetworkRequests::Block { block } => {
-                self.state.tier2.broadcast_message(Arc::new(PeerMessage::Block(block)));
+                self.state.tier2.broadcast_message_with_priority(
+                    Arc::new(PeerMessage::Block(block)),
+                    &self.state.priority_peers(),
+                );
+                NetworkResponses::NoResponse
+            }
+            NetworkRequests::BlockHeaderFirst { block } => {
+                match BlockHeaderAnnouncement::from_block(&block) {
+                    Some(announcement) => self.state.tier2.broadcast_block_header_first(
+                        Arc::new(PeerMessage::Block(block)),
+                        Arc::new(PeerMessage::BlockHeaderAnnouncement(announcement)),
+                        &self.state.priority_peers(),
+                    ),
+                    None => self.state.tier2.broadcast_message_with_priority(
+                        Arc::new(PeerMessage::Block(block)),
+                        &self.state.priority_peers(),
+                    ),
+                }
                 NetworkResponses::NoResponse
             }
             NetworkRequests::Approval { approval_message } => {
@@ -805,6 +918,49 @@ impl PeerManagerActor {
                     NetworkResponses::RouteNotFound
                 }
             }
+            NetworkRequests::StateRequestSubPart {
+                shard_id,
+                sync_hash,
+                part_id,
+                sub_part_id,
+                sub_part_size,
+                target,
+            } => {
+                if self.send_message_to_account_or_peer_or_hash(
+                    &target,
+                    RoutedMessageBody::StateRequestSubPart(
+                        shard_id,
+                        sync_hash,
+                        part_id,
+                        sub_part_id,
+                        sub_part_size,
+                    ),
+                ) {
+                    NetworkResponses::NoResponse
+                } else {
+                    NetworkResponses::RouteNotFound
+                }
+            }
+            NetworkRequests::EpochSyncDataRequest { block_hash, target } => {
+                if self.send_message_to_account_or_peer_or_hash(
+                    &target,
+                    RoutedMessageBody::EpochSyncDataRequest(block_hash),
+                ) {
+                    NetworkResponses::NoResponse
+                } else {
+                    NetworkResponses::RouteNotFound
+                }
+            }
+            NetworkRequests::EpochSyncProofsRequest { block_hash, target } => {
+                if self.send_message_to_account_or_peer_or_hash(
+                    &target,
+                    RoutedMessageBody::EpochSyncProofsRequest(block_hash),
+                ) {
+                    NetworkResponses::NoResponse
+                } else {
+                    NetworkResponses::RouteNotFound
+                }
+            }
             NetworkRequests::StateResponse { route_back, response } => {
                 let body = match response {
                     StateResponseInfo::V1(response) => RoutedMessageBody::StateResponse(response),
@@ -825,7 +981,11 @@ impl PeerManagerActor {
                 }
             }
             NetworkRequests::BanPeer { peer_id, ban_reason } => {
-                self.state.disconnect_and_ban(&self.clock, &peer_id, ban_reason);
+                self.state.penalize_peer(&self.clock, &peer_id, ban_reason);
+                NetworkResponses::NoResponse
+            }
+            NetworkRequests::RewardPeer { peer_id } => {
+                self.state.peer_scores.reward(&peer_id);
                 NetworkResponses::NoResponse
             }
             NetworkRequests::AnnounceAccount(announce_account) => {
@@ -833,7 +993,9 @@ impl PeerManagerActor {
                 NetworkResponses::NoResponse
             }
             NetworkRequests::PartialEncodedChunkRequest { target, request, create_time } => {
-                metrics::PARTIAL_ENCODED_CHUNK_REQUEST_DELAY
+                self.state
+                    .metrics
+                    .partial_encoded_chunk_request_delay
                     .observe((self.clock.now() - create_time.0).as_seconds_f64());
                 let mut success = false;
 
@@ -842,10 +1004,10 @@ impl PeerManagerActor {
                 for prefer_peer in &[target.prefer_peer, !target.prefer_peer] {
                     if !prefer_peer {
                         if let Some(account_id) = target.account_id.as_ref() {
-                            if self.state.send_message_to_account(
+                            if self.state.send_partial_encoded_chunk_request(
                                 &self.clock,
                                 account_id,
-                                RoutedMessageBody::PartialEncodedChunkRequest(request.clone()),
+                                request.clone(),
                             ) {
                                 success = true;
                                 break;
@@ -930,6 +1092,28 @@ impl PeerManagerActor {
                     NetworkResponses::RouteNotFound
                 }
             }
+            NetworkRequests::PartialEncodedChunkAvailability { account_id, availability } => {
+                if self.state.send_message_to_account(
+                    &self.clock,
+                    &account_id,
+                    RoutedMessageBody::PartialEncodedChunkAvailability(availability),
+                ) {
+                    NetworkResponses::NoResponse
+                } else {
+                    NetworkResponses::RouteNotFound
+                }
+            }
+            NetworkRequests::ChunkInclusionFeedback { account_id, feedback } => {
+                if self.state.send_message_to_account(
+                    &self.clock,
+                    &account_id,
+                    RoutedMessageBody::ChunkInclusionFeedback(feedback),
+                ) {
+                    NetworkResponses::NoResponse
+                } else {
+                    NetworkResponses::RouteNotFound
+                }
+            }
             NetworkRequests::ForwardTx(account_id, tx) => {
                 if self.state.send_message_to_account(
                     &self.clock,
@@ -952,6 +1136,34 @@ impl PeerManagerActor {
                     NetworkResponses::RouteNotFound
                 }
             }
+            NetworkRequests::TxStatusSubscribe(account_id, signer_account_id, tx_hash) => {
+                if self.state.send_tx_status_subscribe(
+                    &self.clock,
+                    &account_id,
+                    signer_account_id,
+                    tx_hash,
+                ) {
+                    NetworkResponses::NoResponse
+                } else {
+                    NetworkResponses::RouteNotFound
+                }
+            }
+            NetworkRequests::TxStatusPush { route_back, response } => {
+                if self.state.send_message_to_peer(
+                    &self.clock,
+                    self.state.sign_message(
+                        &self.clock,
+                        RawRoutedMessage {
+                            target: PeerIdOrHash::Hash(route_back),
+                            body: RoutedMessageBody::TxStatusResponse(*response),
+                        },
+                    ),
+                ) {
+                    NetworkResponses::NoResponse
+                } else {
+                    NetworkResponses::RouteNotFound
+                }
+            }
             NetworkRequests::Challenge(challenge) => {
                 // TODO(illia): smarter routing?
                 self.state.tier2.broadcast_message(Arc::new(PeerMessage::Challenge(challenge)));
@@ -1128,7 +1340,10 @@ impl Handler<WithSpanContext<GetNetworkInfo>> for PeerManagerActor {
         _ctx: &mut Self::Context,
     ) -> NetworkInfo {
         let (_span, _msg) = handler_trace_span!(target: "network", msg);
-        let _timer = metrics::PEER_MANAGER_MESSAGES_TIME
+        let _timer = self
+            .state
+            .metrics
+            .peer_manager_messages_time
             .with_label_values(&["GetNetworkInfo"])
             .start_timer();
         self.get_network_info()
@@ -1139,8 +1354,12 @@ impl Handler<WithSpanContext<SetChainInfo>> for PeerManagerActor {
     type Result = ();
     fn handle(&mut self, msg: WithSpanContext<SetChainInfo>, ctx: &mut Self::Context) {
         let (_span, info) = handler_trace_span!(target: "network", msg);
-        let _timer =
-            metrics::PEER_MANAGER_MESSAGES_TIME.with_label_values(&["SetChainInfo"]).start_timer();
+        let _timer = self
+            .state
+            .metrics
+            .peer_manager_messages_time
+            .with_label_values(&["SetChainInfo"])
+            .start_timer();
         let now = self.clock.now_utc();
         let SetChainInfo(info) = info;
         let state = self.state.clone();
@@ -1152,7 +1371,12 @@ impl Handler<WithSpanContext<SetChainInfo>> for PeerManagerActor {
         // just require the caller to await for completion before calling
         // SetChainInfo again. Alternatively we could have an async mutex
         // on the handler.
-        state.chain_info.store(Arc::new(info.clone()));
+        let old_info = state.chain_info.swap(Arc::new(info.clone()));
+        if old_info.maintenance_window != info.maintenance_window {
+            state.tier2.broadcast_message(Arc::new(PeerMessage::MaintenanceWindow(
+                info.maintenance_window,
+            )));
+        }
 
         // If enable_tier1 is false, we skip set_keys() call.
         // This way self.state.accounts_data is always empty, hence no data
@@ -1174,7 +1398,6 @@ impl Handler<WithSpanContext<SetChainInfo>> for PeerManagerActor {
             // will add new AccountData and trigger an incremental broadcast.
             if let Some(vc) = &state.config.validator {
                 let my_account_id = vc.signer.validator_id();
-                let my_public_key = vc.signer.public_key();
                 // TODO(gprusak): STUN servers should be queried periocally by a daemon
                 // so that the my_peers list is always resolved.
                 // Note that currently we will broadcast an empty list.
@@ -1190,10 +1413,20 @@ impl Handler<WithSpanContext<SetChainInfo>> for PeerManagerActor {
                     if account_id != my_account_id{
                         return None;
                     }
-                    if key != &my_public_key {
-                        warn!(target: "network", "node's account_id found in TIER1 accounts, but the public keys do not match");
-                        return None;
-                    }
+                    // The data of each epoch is signed with the key staked in it,
+                    // which is the configured key unless the validator rotates keys.
+                    let signer = std::iter::once(&vc.signer)
+                        .chain(info.other_validator_signers.0.iter())
+                        .find(|signer| {
+                            signer.validator_id() == my_account_id && &signer.public_key() == key
+                        });
+                    let signer = match signer {
+                        Some(signer) => signer,
+                        None => {
+                            warn!(target: "network", "node's account_id found in TIER1 accounts, but the public keys do not match");
+                            return None;
+                        }
+                    };
                     // This unwrap is safe, because we did signed a sample payload during
                     // config validation. See config::Config::new().
                     Some(Arc::new(AccountData {
@@ -1201,7 +1434,7 @@ impl Handler<WithSpanContext<SetChainInfo>> for PeerManagerActor {
                         account_id: my_account_id.clone(),
                         timestamp: now,
                         peers: my_peers.clone(),
-                    }.sign(vc.signer.as_ref()).unwrap()))
+                    }.sign(signer.as_ref()).unwrap()))
                 }).collect();
                 // Insert node's own AccountData should never fail.
                 // We ignore the new data, because we trigger a full sync anyway.
@@ -1234,8 +1467,12 @@ impl Handler<WithSpanContext<PeerToManagerMsg>> for PeerManagerActor {
     ) -> Self::Result {
         let msg_type: &str = (&msg.msg).into();
         let (_span, msg) = handler_trace_span!(target: "network", msg, msg_type);
-        let _timer =
-            metrics::PEER_MANAGER_MESSAGES_TIME.with_label_values(&[msg_type]).start_timer();
+        let _timer = self
+            .state
+            .metrics
+            .peer_manager_messages_time
+            .with_label_values(&[msg_type])
+            .start_timer();
         self.handle_peer_to_manager_msg(msg)
     }
 }
@@ -1249,8 +1486,12 @@ impl Handler<WithSpanContext<PeerManagerMessageRequest>> for PeerManagerActor {
     ) -> Self::Result {
         let msg_type: &str = (&msg.msg).into();
         let (_span, msg) = handler_trace_span!(target: "network", msg, msg_type);
-        let _timer =
-            metrics::PEER_MANAGER_MESSAGES_TIME.with_label_values(&[(&msg).into()]).start_timer();
+        let _timer = self
+            .state
+            .metrics
+            .peer_manager_messages_time
+            .with_label_values(&[(&msg).into()])
+            .start_timer();
         self.handle_peer_manager_message(msg, ctx)
     }
 }
@@ -1291,6 +1532,11 @@ impl Handler<GetDebugStatus> for PeerManagerActor {
                 });
                 DebugStatus::PeerStore(PeerStoreView { peer_states: peer_states_view })
             }
+            GetDebugStatus::PeerAuditLog => {
+                let entries =
+                    self.state.audit_log.entries().iter().map(audit_log::Entry::view).collect();
+                DebugStatus::PeerAuditLog(PeerAuditLogView { entries })
+            }
         }
     }
 }
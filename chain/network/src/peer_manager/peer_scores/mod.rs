@@ -0,0 +1,99 @@
+//! Scores of peers, which decide when a misbehaving peer gets banned.
+//!
+//! Every peer starts with the maximal score.  Misbehavior reported by the
+//! PeerActor or by the client lowers the score by a penalty depending on the
+//! kind of the misbehavior, and good behavior, such as sending valid blocks or
+//! answering liveness pings in time, recovers it up to the maximum.  A peer is
+//! banned only once its score drops to the ban threshold, so that a single
+//! invalid block, which might be caused by a bug or a fork, doesn't cut a peer
+//! off.  Misbehaviors which can't happen by accident, e.g. forged signatures,
+//! still ban the peer right away.
+//!
+//! Scores are kept in memory only.  The score of a banned peer is forgotten,
+//! so that the peer starts over once the ban expires.  PeerIds are free to
+//! mint, so only the scores of the `MAX_SCORES` most recently penalized or
+//! rewarded peers are kept; an evicted peer starts over as well.
+use crate::types::ReasonForBan;
+use lru::LruCache;
+use near_primitives::network::PeerId;
+use parking_lot::Mutex;
+
+#[cfg(test)]
+mod tests;
+
+/// Maximal number of peers with a score below the maximum kept in memory.
+const MAX_SCORES: usize = 10_000;
+
+#[derive(Clone, Debug)]
+pub struct Config {
+    /// Score of peers which didn't misbehave, and the maximal score.
+    pub max: i32,
+    /// Peers are banned once their score drops to this.
+    pub ban_threshold: i32,
+    /// Recovery of the score for every valid block or timely response.
+    pub reward: i32,
+}
+
+/// Penalty for the misbehavior, None if the peer should be banned right away.
+fn penalty(reason: ReasonForBan) -> Option<i32> {
+    match reason {
+        ReasonForBan::BadBlock | ReasonForBan::BadBlockHeader | ReasonForBan::Abusive => Some(50),
+        ReasonForBan::BadBlockApproval | ReasonForBan::InvalidHash => Some(20),
+        ReasonForBan::None
+        | ReasonForBan::HeightFraud
+        | ReasonForBan::BadHandshake
+        | ReasonForBan::InvalidSignature
+        | ReasonForBan::InvalidPeerId
+        | ReasonForBan::InvalidEdge
+        | ReasonForBan::Blacklisted => None,
+    }
+}
+
+pub(crate) struct PeerScores {
+    config: Config,
+    /// Scores of the peers which are below the maximum.
+    scores: Mutex<LruCache<PeerId, i32>>,
+}
+
+impl PeerScores {
+    pub fn new(config: Config) -> Self {
+        Self::with_capacity(config, MAX_SCORES)
+    }
+
+    fn with_capacity(config: Config, capacity: usize) -> Self {
+        Self { config, scores: Mutex::new(LruCache::new(capacity)) }
+    }
+
+    pub fn get(&self, peer_id: &PeerId) -> i32 {
+        self.scores.lock().peek(peer_id).copied().unwrap_or(self.config.max)
+    }
+
+    /// Lowers the score of the peer for the misbehavior.  Returns true if the
+    /// peer should be banned.
+    pub fn penalize(&self, peer_id: &PeerId, reason: ReasonForBan) -> bool {
+        let mut scores = self.scores.lock();
+        let score = scores.get(peer_id).copied().unwrap_or(self.config.max);
+        let score = match penalty(reason) {
+            Some(penalty) => score.saturating_sub(penalty),
+            None => self.config.ban_threshold,
+        };
+        tracing::debug!(target: "network", ?peer_id, ?reason, score, "Peer penalized");
+        if score <= self.config.ban_threshold {
+            scores.pop(peer_id);
+            return true;
+        }
+        scores.put(peer_id.clone(), score);
+        false
+    }
+
+    /// Recovers the score of the peer for good behavior.
+    pub fn reward(&self, peer_id: &PeerId) {
+        let mut scores = self.scores.lock();
+        if let Some(score) = scores.get_mut(peer_id) {
+            *score = score.saturating_add(self.config.reward);
+            if *score >= self.config.max {
+                scores.pop(peer_id);
+            }
+        }
+    }
+}
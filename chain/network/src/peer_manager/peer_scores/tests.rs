@@ -0,0 +1,66 @@
+use super::*;
+use crate::network_protocol::testonly as data;
+use crate::testonly::make_rng;
+
+fn scores() -> PeerScores {
+    PeerScores::new(Config { max: 100, ban_threshold: 0, reward: 10 })
+}
+
+#[test]
+fn test_penalize_and_reward() {
+    let mut rng = make_rng(8723414);
+    let rng = &mut rng;
+    let scores = scores();
+    let peer = data::make_peer_id(rng);
+    let other_peer = data::make_peer_id(rng);
+
+    assert_eq!(scores.get(&peer), 100);
+    assert!(!scores.penalize(&peer, ReasonForBan::BadBlock));
+    assert_eq!(scores.get(&peer), 50);
+    assert_eq!(scores.get(&other_peer), 100);
+
+    // Good behavior recovers the score, up to the maximum.
+    scores.reward(&peer);
+    assert_eq!(scores.get(&peer), 60);
+    for _ in 0..10 {
+        scores.reward(&peer);
+    }
+    assert_eq!(scores.get(&peer), 100);
+
+    // Only crossing the threshold bans the peer, which starts over afterwards.
+    assert!(!scores.penalize(&peer, ReasonForBan::BadBlockHeader));
+    assert!(!scores.penalize(&peer, ReasonForBan::InvalidHash));
+    assert!(scores.penalize(&peer, ReasonForBan::Abusive));
+    assert_eq!(scores.get(&peer), 100);
+}
+
+#[test]
+fn test_severe_misbehavior_bans() {
+    let mut rng = make_rng(8723414);
+    let scores = scores();
+    let peer = data::make_peer_id(&mut rng);
+    assert!(scores.penalize(&peer, ReasonForBan::InvalidSignature));
+    assert_eq!(scores.get(&peer), 100);
+}
+
+#[test]
+fn test_scores_are_capped() {
+    let mut rng = make_rng(8723414);
+    let rng = &mut rng;
+    let scores = PeerScores::with_capacity(Config { max: 100, ban_threshold: 0, reward: 10 }, 2);
+    let peers: Vec<_> = (0..3).map(|_| data::make_peer_id(rng)).collect();
+    for peer in &peers {
+        assert!(!scores.penalize(peer, ReasonForBan::BadBlock));
+    }
+    // The least recently penalized peer is forgotten.
+    assert_eq!(scores.get(&peers[0]), 100);
+    assert_eq!(scores.get(&peers[1]), 50);
+    assert_eq!(scores.get(&peers[2]), 50);
+
+    // Rewarding a peer makes it the most recently used one.
+    scores.reward(&peers[1]);
+    assert!(!scores.penalize(&peers[0], ReasonForBan::BadBlock));
+    assert_eq!(scores.get(&peers[0]), 50);
+    assert_eq!(scores.get(&peers[1]), 60);
+    assert_eq!(scores.get(&peers[2]), 100);
+}
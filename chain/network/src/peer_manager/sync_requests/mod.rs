@@ -0,0 +1,154 @@
+//! Limits on serving the block and header requests of syncing peers.
+//!
+//! Archival nodes are asked for blocks and headers by every peer syncing from
+//! them, and each request is served from the database.  Every peer has a
+//! token bucket, and the requests of a peer which exhausted its bucket are
+//! dropped, so that the peer asks somebody else.  On top of that, only
+//! `max_concurrent` requests are served at the same time.  The other requests
+//! wait in a queue of their peer, and the queues are served round robin, so
+//! that a peer sending many requests doesn't delay the requests of the others.
+//! Requests which don't fit into the queue of their peer are dropped.
+use crate::concurrency::demux;
+use crate::stats::metrics::NetworkMetrics;
+use crate::time;
+use near_cache::{TokenBucketLimit, TokenBuckets};
+use near_primitives::network::PeerId;
+use parking_lot::Mutex;
+use std::collections::{HashMap, VecDeque};
+use std::sync::Arc;
+use tokio::sync::oneshot;
+
+#[cfg(test)]
+mod tests;
+
+/// Number of peers whose buckets are kept.  The bucket of the peer seen least
+/// recently is dropped to make room for a new one.
+const MAX_TRACKED_PEERS: usize = 1024;
+
+#[derive(Clone, Debug)]
+pub struct Config {
+    /// Rate at which the requests of a single peer are served.
+    pub rate_limit: demux::RateLimit,
+    /// Maximum number of requests served at the same time.
+    pub max_concurrent: usize,
+    /// Maximum number of requests of a single peer waiting to be served.
+    pub max_queued_per_peer: usize,
+}
+
+struct Inner {
+    buckets: TokenBuckets<PeerId>,
+    /// Number of requests being served.
+    in_flight: usize,
+    /// Requests waiting to be served, by peer.
+    queues: HashMap<PeerId, VecDeque<oneshot::Sender<Permit>>>,
+    /// Peers with waiting requests, in the order they are served.
+    order: VecDeque<PeerId>,
+}
+
+pub(crate) struct Limiter {
+    /// None disables the limits.
+    config: Option<Config>,
+    inner: Mutex<Inner>,
+    metrics: Arc<NetworkMetrics>,
+}
+
+/// Allows serving a request.  The next waiting request is let through when
+/// the permit is dropped.
+pub(crate) struct Permit {
+    limiter: Option<Arc<Limiter>>,
+}
+
+impl Drop for Permit {
+    fn drop(&mut self) {
+        if let Some(limiter) = self.limiter.take() {
+            limiter.release();
+        }
+    }
+}
+
+impl Limiter {
+    pub fn new(config: Option<Config>, metrics: Arc<NetworkMetrics>) -> Self {
+        let inner = Inner {
+            buckets: TokenBuckets::new(MAX_TRACKED_PEERS),
+            in_flight: 0,
+            queues: HashMap::new(),
+            order: VecDeque::new(),
+        };
+        Self { config, inner: Mutex::new(inner), metrics }
+    }
+
+    /// Waits until the request of `peer_id` of type `msg_type` can be served.
+    /// Returns None if the request should be dropped instead.
+    pub async fn acquire(
+        self: &Arc<Self>,
+        clock: &time::Clock,
+        peer_id: &PeerId,
+        msg_type: &'static str,
+    ) -> Option<Permit> {
+        let config = match &self.config {
+            Some(config) => config,
+            None => return Some(Permit { limiter: None }),
+        };
+        let receiver = {
+            let mut inner = self.inner.lock();
+            let inner = &mut *inner;
+            let limit = TokenBucketLimit {
+                tokens_per_second: config.rate_limit.qps,
+                burst: config.rate_limit.burst as f64,
+            };
+            let bucket = inner.buckets.get(peer_id, limit, clock.now().into_inner());
+            if bucket.tokens() < 1. {
+                self.metrics
+                    .sync_requests_dropped
+                    .with_label_values(&[msg_type, "rate_limit"])
+                    .inc();
+                return None;
+            }
+            if inner.in_flight < config.max_concurrent {
+                bucket.take(1.);
+                inner.in_flight += 1;
+                return Some(Permit { limiter: Some(self.clone()) });
+            }
+            let queue = inner.queues.entry(peer_id.clone()).or_default();
+            if queue.len() >= config.max_queued_per_peer {
+                self.metrics
+                    .sync_requests_dropped
+                    .with_label_values(&[msg_type, "queue_full"])
+                    .inc();
+                return None;
+            }
+            bucket.take(1.);
+            if queue.is_empty() {
+                inner.order.push_back(peer_id.clone());
+            }
+            let (sender, receiver) = oneshot::channel();
+            queue.push_back(sender);
+            self.metrics.sync_requests_deferred.with_label_values(&[msg_type]).inc();
+            receiver
+        };
+        receiver.await.ok()
+    }
+
+    /// Hands the slot of a served request over to the next waiting request,
+    /// taking the peers in turns.
+    fn release(self: &Arc<Self>) {
+        let mut inner = self.inner.lock();
+        let inner = &mut *inner;
+        while let Some(peer_id) = inner.order.pop_front() {
+            let queue = inner.queues.get_mut(&peer_id).unwrap();
+            let sender = queue.pop_front().unwrap();
+            if queue.is_empty() {
+                inner.queues.remove(&peer_id);
+            } else {
+                inner.order.push_back(peer_id);
+            }
+            match sender.send(Permit { limiter: Some(self.clone()) }) {
+                Ok(()) => return,
+                // The request was abandoned, e.g. because the connection was
+                // closed.  The permit mustn't release the lock held here.
+                Err(mut permit) => permit.limiter = None,
+            }
+        }
+        inner.in_flight -= 1;
+    }
+}
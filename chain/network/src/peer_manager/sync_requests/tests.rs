@@ -0,0 +1,76 @@
+use super::*;
+use crate::network_protocol::testonly as data;
+use crate::testonly::make_rng;
+use futures::FutureExt;
+use near_o11y::metrics::MetricsRegistry;
+
+fn limiter(qps: f64, burst: u64, max_concurrent: usize) -> Arc<Limiter> {
+    let config = Config {
+        rate_limit: demux::RateLimit { qps, burst },
+        max_concurrent,
+        max_queued_per_peer: 2,
+    };
+    let metrics = MetricsRegistry::for_instance(None).get::<NetworkMetrics>();
+    Arc::new(Limiter::new(Some(config), metrics))
+}
+
+#[test]
+fn test_rate_limit() {
+    let mut rng = make_rng(2753081);
+    let rng = &mut rng;
+    let clock = time::FakeClock::default();
+    let limiter = limiter(1., 2, 10);
+    let peer = data::make_peer_id(rng);
+    let other_peer = data::make_peer_id(rng);
+
+    let acquire =
+        |peer_id| limiter.acquire(&clock.clock(), peer_id, "BlockRequest").now_or_never().unwrap();
+    assert!(acquire(&peer).is_some());
+    assert!(acquire(&peer).is_some());
+    assert!(acquire(&peer).is_none());
+    // Other peers have their own buckets.
+    assert!(acquire(&other_peer).is_some());
+    // The bucket refills over time.
+    clock.advance(time::Duration::seconds(1));
+    assert!(acquire(&peer).is_some());
+    assert!(acquire(&peer).is_none());
+}
+
+#[test]
+fn test_fairness() {
+    let mut rng = make_rng(9810347);
+    let rng = &mut rng;
+    let clock = time::FakeClock::default();
+    let clock = clock.clock();
+    let limiter = limiter(100., 100, 1);
+    let greedy = data::make_peer_id(rng);
+    let modest = data::make_peer_id(rng);
+
+    let permit = limiter.acquire(&clock, &greedy, "BlockRequest").now_or_never().unwrap();
+    assert!(permit.is_some());
+    let mut greedy1 = Box::pin(limiter.acquire(&clock, &greedy, "BlockRequest"));
+    assert!((&mut greedy1).now_or_never().is_none());
+    let mut greedy2 = Box::pin(limiter.acquire(&clock, &greedy, "BlockRequest"));
+    assert!((&mut greedy2).now_or_never().is_none());
+    // The queue of the peer is full.
+    let greedy3 = limiter.acquire(&clock, &greedy, "BlockRequest").now_or_never().unwrap();
+    assert!(greedy3.is_none());
+    let mut modest1 = Box::pin(limiter.acquire(&clock, &modest, "BlockHeadersRequest"));
+    assert!((&mut modest1).now_or_never().is_none());
+
+    // Peers take turns, even though the second request of the greedy peer
+    // came first.
+    drop(permit);
+    let permit = (&mut greedy1).now_or_never().unwrap().unwrap();
+    assert!((&mut greedy2).now_or_never().is_none());
+    assert!((&mut modest1).now_or_never().is_none());
+    drop(permit);
+    let permit = (&mut modest1).now_or_never().unwrap().unwrap();
+    assert!((&mut greedy2).now_or_never().is_none());
+
+    // Abandoned requests are skipped.
+    drop(greedy2);
+    drop(permit);
+    let permit = limiter.acquire(&clock, &modest, "BlockRequest").now_or_never().unwrap();
+    assert!(permit.is_some());
+}
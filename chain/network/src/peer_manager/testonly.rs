@@ -42,6 +42,17 @@ impl actix::Handler<WithNetworkState> for PeerManagerActor {
     }
 }
 
+#[derive(actix::Message)]
+#[rtype("()")]
+struct MaybeStopActiveConnection;
+
+impl actix::Handler<MaybeStopActiveConnection> for PeerManagerActor {
+    type Result = ();
+    fn handle(&mut self, _: MaybeStopActiveConnection, _: &mut Self::Context) -> Self::Result {
+        self.maybe_stop_active_connection()
+    }
+}
+
 #[derive(Debug, PartialEq, Eq, Clone)]
 pub enum Event {
     Client(fake_client::Event),
@@ -306,6 +317,12 @@ impl ActorHandler {
             .await;
     }
 
+    /// Stops a connection outside of the safe set if there are too many of
+    /// them, as the peer manager does periodically.
+    pub async fn maybe_stop_active_connection(&self) {
+        self.actix.addr.send(MaybeStopActiveConnection).await.unwrap();
+    }
+
     pub async fn announce_account(&self, aa: AnnounceAccount) {
         self.actix
             .addr
@@ -8,9 +8,11 @@ use crate::peer_manager::peer_manager_actor::Event as PME;
 use crate::peer_manager::testonly::NormalAccountData;
 use crate::testonly::{make_rng, AsSet as _};
 use crate::time;
-use crate::types::PeerMessage;
+use crate::types::{PeerMessage, ValidatorSigners};
 use itertools::Itertools;
+use near_crypto::KeyType;
 use near_o11y::testonly::init_test_logger;
+use near_primitives::validator_signer::{InMemoryValidatorSigner, ValidatorSigner};
 use pretty_assertions::assert_eq;
 use rand::seq::SliceRandom as _;
 use std::sync::Arc;
@@ -163,6 +165,57 @@ async fn accounts_data_gradual_epoch_change() {
     }
 }
 
+// A validator rotating its key signs the AccountData of each epoch with the
+// key staked in it, and doesn't sign data for a key it doesn't hold.
+#[tokio::test]
+async fn accounts_data_rotated_key() {
+    init_test_logger();
+    let mut rng = make_rng(921853233);
+    let rng = &mut rng;
+    let mut clock = time::FakeClock::default();
+    let chain = Arc::new(data::Chain::make(&mut clock, rng, 10));
+
+    let mut pm = peer_manager::testonly::start(
+        clock.clock(),
+        near_store::db::TestDB::new(),
+        chain.make_config(rng),
+        chain.clone(),
+    )
+    .await;
+    let vc = pm.cfg.validator.clone().unwrap();
+    let account_id = vc.signer.validator_id().clone();
+    let next_signer =
+        InMemoryValidatorSigner::from_seed(account_id.clone(), KeyType::ED25519, "next");
+    let unknown_signer =
+        InMemoryValidatorSigner::from_seed(account_id.clone(), KeyType::ED25519, "unknown");
+
+    let (e1, e2, e3) =
+        (data::make_epoch_id(rng), data::make_epoch_id(rng), data::make_epoch_id(rng));
+    let mut chain_info = chain.get_chain_info();
+    chain_info.tier1_accounts = Arc::new(
+        [
+            ((e1.clone(), account_id.clone()), vc.signer.public_key()),
+            ((e2.clone(), account_id.clone()), next_signer.public_key()),
+            ((e3, account_id.clone()), unknown_signer.public_key()),
+        ]
+        .into_iter()
+        .collect(),
+    );
+    chain_info.other_validator_signers = ValidatorSigners(vec![Arc::new(next_signer)]);
+    pm.set_chain_info(chain_info).await;
+
+    // Inserting the data verified it against the key of its epoch.
+    let want = [e1, e2]
+        .into_iter()
+        .map(|epoch_id| NormalAccountData {
+            epoch_id,
+            account_id: account_id.clone(),
+            peers: peer_addrs(&vc),
+        })
+        .collect();
+    pm.wait_for_accounts_data(&want).await;
+}
+
 // Test is expected to take ~5s.
 // Test with 20 peer managers connected in layers:
 // - 1st 5 and 2nd 5 are connected in full bipartite graph.
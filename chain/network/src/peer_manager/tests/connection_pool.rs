@@ -1,6 +1,7 @@
 use crate::network_protocol::testonly as data;
 use crate::network_protocol::PeerMessage;
 use crate::network_protocol::{Encoding, Handshake, PartialEdgeInfo};
+use crate::peer;
 use crate::peer::peer_actor::ClosingReason;
 use crate::peer_manager;
 use crate::peer_manager::connection;
@@ -12,7 +13,12 @@ use crate::tcp;
 use crate::testonly::make_rng;
 use crate::testonly::stream::Stream;
 use crate::time;
+use crate::types::{ChainInfo, NetworkRequests, PeerManagerMessageRequest, ReasonForBan};
 use near_o11y::testonly::init_test_logger;
+use near_o11y::WithSpanContextExt;
+use near_primitives::network::{AnnounceAccount, PeerId};
+use near_primitives::types::EpochId;
+use near_primitives::validator_signer::ValidatorSigner as _;
 use near_primitives::version::PROTOCOL_VERSION;
 use std::sync::Arc;
 
@@ -99,6 +105,9 @@ async fn loop_connection() {
                 1,
                 &pm.cfg.node_key,
             ),
+            state_sub_part_size_limit: 0,
+            compressions: vec![],
+            header_first_blocks: false,
         }))
         .await;
     let reason = events
@@ -119,3 +128,162 @@ async fn loop_connection() {
         reason
     );
 }
+
+// Verifies that the connections to the peers of the priority validators are
+// kept when there are too many connections and that blocks are broadcast to
+// all peers.
+#[tokio::test]
+async fn priority_validators() {
+    init_test_logger();
+    let mut rng = make_rng(921853233);
+    let rng = &mut rng;
+    let mut clock = time::FakeClock::default();
+    let chain = Arc::new(data::Chain::make(&mut clock, rng, 10));
+
+    let mut cfg = chain.make_config(rng);
+    // Every connection outside of the safe set is to be stopped.
+    cfg.ideal_connections_lo = 0;
+    cfg.ideal_connections_hi = 0;
+    cfg.minimum_outbound_peers = 0;
+    cfg.safe_set_size = 1;
+    let mut pm = peer_manager::testonly::start(
+        clock.clock(),
+        near_store::db::TestDB::new(),
+        cfg,
+        chain.clone(),
+    )
+    .await;
+    let mut peers = vec![];
+    for _ in 0..3 {
+        let conn = pm.start_inbound(chain.clone(), chain.make_config(rng)).await;
+        peers.push(conn.handshake(&clock.clock()).await);
+    }
+
+    tracing::info!(target:"test", "Make the validator of the first peer a priority one.");
+    let validator = peers[0].cfg.id();
+    let signer = data::make_validator_signer(rng);
+    let signature =
+        signer.sign_account_announce(signer.validator_id(), &validator, &EpochId::default());
+    let account_id = signer.validator_id().clone();
+    pm.announce_account(AnnounceAccount {
+        account_id: account_id.clone(),
+        peer_id: validator.clone(),
+        epoch_id: EpochId::default(),
+        signature,
+    })
+    .await;
+    pm.wait_for_account_owner(&account_id).await;
+    pm.set_chain_info(ChainInfo {
+        priority_validators: Arc::new(vec![account_id]),
+        ..chain.get_chain_info()
+    })
+    .await;
+
+    tracing::info!(target:"test", "Broadcast a block.");
+    let block = chain.blocks[5].clone();
+    pm.actix
+        .addr
+        .send(
+            PeerManagerMessageRequest::NetworkRequests(NetworkRequests::Block {
+                block: block.clone(),
+            })
+            .with_span_context(),
+        )
+        .await
+        .unwrap();
+    for peer in &mut peers {
+        peer.events
+            .recv_until(|ev| match ev {
+                peer::testonly::Event::Network(PME::MessageProcessed(PeerMessage::Block(b)))
+                    if b == block =>
+                {
+                    Some(())
+                }
+                _ => None,
+            })
+            .await;
+    }
+
+    tracing::info!(target:"test", "Stop connections until only the safe set is left.");
+    loop {
+        let ready: Vec<PeerId> =
+            pm.with_state(|s| async move { s.tier2.load().ready.keys().cloned().collect() }).await;
+        if ready.len() <= 1 {
+            assert_eq!(ready, vec![validator]);
+            break;
+        }
+        let mut events = pm.events.from_now();
+        pm.maybe_stop_active_connection().await;
+        events
+            .recv_until(|ev| match ev {
+                Event::PeerManager(PME::ConnectionClosed(_)) => Some(()),
+                _ => None,
+            })
+            .await;
+    }
+}
+
+// Verifies that a peer reported by the client for a bad block gets penalized instead of
+// being banned right away, and that it's banned once its score drops to the threshold.
+#[tokio::test]
+async fn ban_peer_penalizes() {
+    init_test_logger();
+    let mut rng = make_rng(921853233);
+    let rng = &mut rng;
+    let mut clock = time::FakeClock::default();
+    let chain = Arc::new(data::Chain::make(&mut clock, rng, 10));
+
+    let pm = peer_manager::testonly::start(
+        clock.clock(),
+        near_store::db::TestDB::new(),
+        chain.make_config(rng),
+        chain.clone(),
+    )
+    .await;
+    let conn = pm.start_inbound(chain.clone(), chain.make_config(rng)).await;
+    let peer = conn.handshake(&clock.clock()).await;
+    let peer_id = peer.cfg.id();
+    let ban_peer = || {
+        PeerManagerMessageRequest::NetworkRequests(NetworkRequests::BanPeer {
+            peer_id: peer_id.clone(),
+            ban_reason: ReasonForBan::BadBlock,
+        })
+        .with_span_context()
+    };
+
+    tracing::info!(target:"test", "A single bad block only lowers the score.");
+    pm.actix.addr.send(ban_peer()).await.unwrap();
+    let (score, connected, banned) = pm
+        .with_state({
+            let peer_id = peer_id.clone();
+            |s| async move {
+                (
+                    s.peer_scores.get(&peer_id),
+                    s.tier2.load().ready.contains_key(&peer_id),
+                    s.peer_store.is_banned(&peer_id),
+                )
+            }
+        })
+        .await;
+    assert_eq!(score, 50);
+    assert!(connected);
+    assert!(!banned);
+
+    tracing::info!(target:"test", "Another one drops the score to the threshold and bans.");
+    let mut events = pm.events.from_now();
+    pm.actix.addr.send(ban_peer()).await.unwrap();
+    let reason = events
+        .recv_until(|ev| match ev {
+            Event::PeerManager(PME::ConnectionClosed(ev)) => Some(ev.reason),
+            _ => None,
+        })
+        .await;
+    assert_eq!(ClosingReason::Ban(ReasonForBan::BadBlock), reason);
+    let banned = pm
+        .with_state({
+            let peer_id = peer_id.clone();
+            |s| async move { s.peer_store.is_banned(&peer_id) }
+        })
+        .await;
+    assert!(banned);
+}
@@ -1,15 +1,20 @@
 use crate::network_protocol::testonly as data;
 use crate::network_protocol::{Edge, Encoding, Ping, RoutedMessageBody, RoutingTableUpdate};
 use crate::peer;
+use crate::peer::liveness;
 use crate::peer_manager;
 use crate::peer_manager::peer_manager_actor::Event as PME;
 use crate::peer_manager::testonly::start as start_pm;
 use crate::peer_manager::testonly::Event;
 use crate::tcp;
+use crate::testonly::fake_client;
 use crate::testonly::make_rng;
 use crate::time;
-use crate::types::PeerMessage;
+use crate::types::{PartialEncodedChunkRequestMsg, PeerMessage};
 use near_o11y::testonly::init_test_logger;
+use near_primitives::network::{AnnounceAccount, PeerId};
+use near_primitives::types::EpochId;
+use near_primitives::validator_signer::ValidatorSigner as _;
 use near_store::db::TestDB;
 use pretty_assertions::assert_eq;
 use rand::Rng as _;
@@ -391,3 +396,204 @@ async fn do_not_block_announce_account_broadcast() {
     pm1.announce_account(aa.clone()).await;
     assert_eq!(&aa.peer_id, &pm2.wait_for_account_owner(&aa.account_id).await);
 }
+
+// Test that a relayed chunk part request is forwarded to its target, but only once.
+#[tokio::test]
+async fn relayed_partial_encoded_chunk_request() {
+    init_test_logger();
+    let mut rng = make_rng(921853233);
+    let rng = &mut rng;
+    let mut clock = time::FakeClock::default();
+    let chain = Arc::new(data::Chain::make(&mut clock, rng, 10));
+    let mut relay =
+        start_pm(clock.clock(), TestDB::new(), chain.make_config(rng), chain.clone()).await;
+    let connect = |network| {
+        let cfg = peer::testonly::PeerConfig {
+            network,
+            chain: chain.clone(),
+            peers: vec![],
+            force_encoding: Some(Encoding::Proto),
+            nonce: None,
+        };
+        let clock = clock.clock();
+        let peer_info = relay.peer_info();
+        async move {
+            let stream = tcp::Stream::connect(&peer_info).await.unwrap();
+            let mut peer = peer::testonly::PeerHandle::start_endpoint(clock, cfg, stream).await;
+            peer.complete_handshake().await;
+            peer
+        }
+    };
+    let requester = connect(chain.make_config(rng)).await;
+    let mut owner = connect(chain.make_config(rng)).await;
+    relay
+        .events
+        .recv_until(|ev| match ev {
+            Event::PeerManager(PME::RoutingTableUpdate { next_hops, .. }) => {
+                if next_hops.get(&owner.cfg.id()).map_or(false, |v| v.len() > 0) {
+                    Some(())
+                } else {
+                    None
+                }
+            }
+            _ => None,
+        })
+        .await;
+
+    let request = |i: usize| {
+        let chunk_hash = chain.blocks[i].chunks()[0].chunk_hash();
+        let body = RoutedMessageBody::PartialEncodedChunkRequest(PartialEncodedChunkRequestMsg {
+            chunk_hash,
+            part_ords: vec![0],
+            tracking_shards: Default::default(),
+        });
+        requester.routed_message(body, owner.cfg.id(), 3, Some(clock.now_utc())).msg
+    };
+    let relayed = |msg| {
+        let body = RoutedMessageBody::RelayedPartialEncodedChunkRequest(Box::new(msg));
+        let msg = requester.routed_message(body, relay.cfg.node_id(), 3, Some(clock.now_utc()));
+        PeerMessage::Routed(Box::new(msg))
+    };
+
+    tracing::info!(target:"test", "relayed requests are not relayed again");
+    let nested = requester.routed_message(
+        RoutedMessageBody::RelayedPartialEncodedChunkRequest(Box::new(request(1))),
+        owner.cfg.id(),
+        3,
+        Some(clock.now_utc()),
+    );
+    requester.send(relayed(nested.msg)).await;
+
+    tracing::info!(target:"test", "a request of the requester reaches the part owner");
+    requester.send(relayed(request(2))).await;
+    // The nested request would have reached the part owner first, since both go through the
+    // same connection.
+    let got = owner
+        .events
+        .recv_until(|ev| match ev {
+            peer::testonly::Event::Client(fake_client::Event::ChunkRequest(chunk_hash)) => {
+                Some(chunk_hash)
+            }
+            peer::testonly::Event::Network(PME::MessageProcessed(PeerMessage::Routed(msg)))
+                if matches!(
+                    msg.msg.body,
+                    RoutedMessageBody::RelayedPartialEncodedChunkRequest(_)
+                ) =>
+            {
+                panic!("relayed request was relayed again: {msg:?}")
+            }
+            _ => None,
+        })
+        .await;
+    assert_eq!(chain.blocks[2].chunks()[0].chunk_hash(), got);
+}
+
+/// Makes the round trip time to the connected peer `rtt` by answering a
+/// liveness ping after `rtt`.
+async fn set_rtt(
+    pm: &peer_manager::testonly::ActorHandler,
+    clock: &time::Clock,
+    peer_id: PeerId,
+    rtt: time::Duration,
+) {
+    let now = clock.now();
+    pm.with_state(move |s| async move {
+        let tier2 = s.tier2.load();
+        let mut liveness = tier2.ready.get(&peer_id).unwrap().liveness.lock();
+        // The peer has been silent for long enough to get pinged.
+        let nonce = match liveness.poll(now, now - time::Duration::minutes(1)) {
+            liveness::Action::Ping(nonce) => nonce,
+            action => panic!("unexpected {action:?}"),
+        };
+        assert!(liveness.on_pong(now + rtt, nonce));
+    })
+    .await
+}
+
+// Test that a chunk part request is sent directly unless the route to the part owner is known
+// to be slow, in which case it's relayed through the directly connected validator with the
+// lowest round trip time.
+#[tokio::test]
+async fn relay_partial_encoded_chunk_request_by_rtt() {
+    init_test_logger();
+    let mut rng = make_rng(921853233);
+    let rng = &mut rng;
+    let mut clock = time::FakeClock::default();
+    let chain = Arc::new(data::Chain::make(&mut clock, rng, 10));
+    let mut cfg = chain.make_config(rng);
+    cfg.chunk_request_relay_rtt_threshold = Some(time::Duration::milliseconds(100));
+    let pm = start_pm(clock.clock(), TestDB::new(), cfg, chain.clone()).await;
+    let mut peers = vec![];
+    for _ in 0..3 {
+        let conn = pm.start_inbound(chain.clone(), chain.make_config(rng)).await;
+        peers.push(conn.handshake(&clock.clock()).await);
+    }
+
+    tracing::info!(target:"test", "announce the accounts of all the peers");
+    let mut accounts = vec![];
+    for peer in &peers {
+        let signer = data::make_validator_signer(rng);
+        let account_id = signer.validator_id().clone();
+        let signature =
+            signer.sign_account_announce(&account_id, &peer.cfg.id(), &EpochId::default());
+        pm.announce_account(AnnounceAccount {
+            account_id: account_id.clone(),
+            peer_id: peer.cfg.id(),
+            epoch_id: EpochId::default(),
+            signature,
+        })
+        .await;
+        pm.wait_for_account_owner(&account_id).await;
+        accounts.push(account_id);
+    }
+    let want: Vec<_> = peers.iter().map(|peer| (peer.cfg.id(), vec![peer.cfg.id()])).collect();
+    pm.wait_for_routing_table(&want).await;
+    let (mut owner, mut fast_relay) = (peers.remove(0), peers.remove(1));
+
+    let request = |i: usize| PartialEncodedChunkRequestMsg {
+        chunk_hash: chain.blocks[i].chunks()[0].chunk_hash(),
+        part_ords: vec![0],
+        tracking_shards: Default::default(),
+    };
+    let send_request = |request| {
+        let clock = clock.clock();
+        let account_id = accounts[0].clone();
+        pm.with_state(move |s| async move {
+            s.send_partial_encoded_chunk_request(&clock, &account_id, request)
+        })
+    };
+
+    tracing::info!(target:"test", "without a known round trip time the request is sent directly");
+    assert!(send_request(request(1)).await);
+    let got = owner
+        .events
+        .recv_until(|ev| match ev {
+            peer::testonly::Event::Client(fake_client::Event::ChunkRequest(chunk_hash)) => {
+                Some(chunk_hash)
+            }
+            _ => None,
+        })
+        .await;
+    assert_eq!(chain.blocks[1].chunks()[0].chunk_hash(), got);
+
+    tracing::info!(target:"test", "a slow route is bypassed through the fastest validator");
+    set_rtt(&pm, &clock.clock(), owner.cfg.id(), time::Duration::milliseconds(500)).await;
+    set_rtt(&pm, &clock.clock(), peers[0].cfg.id(), time::Duration::milliseconds(80)).await;
+    set_rtt(&pm, &clock.clock(), fast_relay.cfg.id(), time::Duration::milliseconds(20)).await;
+    assert!(send_request(request(2)).await);
+    let got = fast_relay
+        .events
+        .recv_until(|ev| match ev {
+            peer::testonly::Event::Network(PME::MessageProcessed(PeerMessage::Routed(msg))) => {
+                match &msg.msg.body {
+                    RoutedMessageBody::RelayedPartialEncodedChunkRequest(relayed) => {
+                        Some(relayed.body.clone())
+                    }
+                    _ => None,
+                }
+            }
+            _ => None,
+        })
+        .await;
+    assert_eq!(RoutedMessageBody::PartialEncodedChunkRequest(request(2)), got);
+}
@@ -149,6 +149,9 @@ impl Connection {
                 1,
                 &self.secret_key,
             ),
+            state_sub_part_size_limit: 0,
+            compressions: vec![],
+            header_first_blocks: false,
         });
 
         self.write_message(&handshake).await.map_err(ConnectError::IO)?;
@@ -1,8 +1,10 @@
 use crate::network_protocol::Edge;
+use crate::peer_manager::peer_manager_actor::PRUNE_EDGES_AFTER;
 use crate::private_actix::{StopMsg, ValidateEdgeList};
 use crate::routing;
 use crate::routing::edge_validator_actor::EdgeValidatorActor;
-use crate::stats::metrics;
+use crate::routing::tombstones;
+use crate::stats::metrics::NetworkMetrics;
 use crate::store;
 use crate::time;
 use actix::{
@@ -18,7 +20,7 @@ use near_primitives::network::PeerId;
 use parking_lot::RwLock;
 use std::collections::{HashMap, HashSet};
 use std::sync::Arc;
-use tracing::warn;
+use tracing::{debug, warn};
 
 /// Actor that maintains routing table information.
 ///
@@ -27,7 +29,7 @@ use tracing::warn;
 ///
 /// We use store for following reasons:
 ///   - store removed edges to disk
-///   - we currently don't store active edges to disk
+///   - store the edges of the graph, so that they survive a restart
 pub(crate) struct Actor {
     clock: time::Clock,
     my_peer_id: PeerId,
@@ -48,6 +50,8 @@ pub(crate) struct Actor {
     /// Number of edge validations in progress; We will not update routing table as long as
     /// this number is non zero.
     edge_validator_requests_in_progress: u64,
+    tombstones: tombstones::Config,
+    metrics: Arc<NetworkMetrics>,
 }
 
 impl Actor {
@@ -55,9 +59,11 @@ impl Actor {
         clock: time::Clock,
         store: store::Store,
         graph: Arc<RwLock<routing::GraphWithCache>>,
+        tombstones: tombstones::Config,
+        metrics: Arc<NetworkMetrics>,
     ) -> Self {
         let my_peer_id = graph.read().my_peer_id();
-        Self {
+        let mut this = Self {
             clock,
             my_peer_id,
             graph,
@@ -66,16 +72,80 @@ impl Actor {
             peers_to_ban: Default::default(),
             edge_validator_requests_in_progress: 0,
             edge_validator_pool: actix::SyncArbiter::start(4, || EdgeValidatorActor {}),
-        }
+            tombstones,
+            metrics,
+        };
+        this.load_persisted_edges();
+        this
     }
 
     pub fn spawn(
         clock: time::Clock,
         store: store::Store,
         graph: Arc<RwLock<routing::GraphWithCache>>,
+        tombstones: tombstones::Config,
+        metrics: Arc<NetworkMetrics>,
     ) -> actix::Addr<Self> {
         let arbiter = actix::Arbiter::new();
-        Actor::start_in_arbiter(&arbiter.handle(), |_| Self::new(clock, store, graph))
+        Actor::start_in_arbiter(&arbiter.handle(), |_| {
+            Self::new(clock, store, graph, tombstones, metrics)
+        })
+    }
+
+    /// Loads the edges persisted before a restart into the graph, so that the
+    /// routing tables received from peers after the restart are not considered
+    /// new and broadcast again.
+    ///
+    /// Edges adjacent to this node are skipped, since they are established again
+    /// by the connections. So are edges which would be pruned as too old and,
+    /// unless configured otherwise, expired tombstones.
+    fn load_persisted_edges(&mut self) {
+        let edges = match self.store.list_routing_edges() {
+            Ok(edges) => edges,
+            Err(e) => {
+                warn!("self.store.list_routing_edges(): {}", e);
+                return;
+            }
+        };
+        let now = self.clock.now_utc();
+        let prune_edges_older_than = now - PRUNE_EDGES_AFTER;
+        let (edges, skipped): (Vec<_>, Vec<_>) = edges.into_iter().partition(|edge| {
+            !edge.contains_peer(&self.my_peer_id)
+                && !edge.is_edge_older_than(prune_edges_older_than)
+                && (self.tombstones.load_expired_on_restart
+                    || !self.tombstones.is_expired(now, edge))
+        });
+        let skipped: Vec<_> = skipped.iter().map(|edge| edge.key().clone()).collect();
+        if let Err(e) = self.store.delete_routing_edges(&skipped) {
+            warn!("self.store.delete_routing_edges(): {}", e);
+        }
+        debug!(
+            target: "network",
+            loaded = edges.len(),
+            skipped = skipped.len(),
+            "Loaded persisted routing edges"
+        );
+        self.graph.write().update_edges(edges);
+        self.metrics.edge_active.set(self.graph.read().total_active_edges() as i64);
+        self.metrics.edge_total.set(self.graph.read().edges().len() as i64);
+    }
+
+    fn persist_edges(&mut self, edges: &[Edge]) {
+        if edges.is_empty() {
+            return;
+        }
+        if let Err(e) = self.store.set_routing_edges(edges) {
+            warn!("self.store.set_routing_edges(): {}", e);
+        }
+    }
+
+    fn unpersist_edges(&mut self, keys: &[(PeerId, PeerId)]) {
+        if keys.is_empty() {
+            return;
+        }
+        if let Err(e) = self.store.delete_routing_edges(keys) {
+            warn!("self.store.delete_routing_edges(): {}", e);
+        }
     }
 
     /// Add several edges to the current view of the network.
@@ -98,10 +168,11 @@ impl Actor {
             self.load_component(&key.1);
         }
         let edges = self.graph.write().update_edges(edges);
+        self.persist_edges(&edges);
         // Update metrics after edge update
-        metrics::EDGE_UPDATES.inc_by(total as u64);
-        metrics::EDGE_ACTIVE.set(self.graph.read().total_active_edges() as i64);
-        metrics::EDGE_TOTAL.set(self.graph.read().edges().len() as i64);
+        self.metrics.edge_updates.inc_by(total as u64);
+        self.metrics.edge_active.set(self.graph.read().total_active_edges() as i64);
+        self.metrics.edge_total.set(self.graph.read().edges().len() as i64);
         edges
     }
 
@@ -146,7 +217,8 @@ impl Actor {
                 return;
             }
         };
-        self.graph.write().update_edges(edges);
+        let edges = self.graph.write().update_edges(edges);
+        self.persist_edges(&edges);
     }
 
     /// Prunes peers unreachable since <unreachable_since> (and their adjacent edges)
@@ -189,6 +261,7 @@ impl Actor {
         if let Err(e) = self.store.push_component(&peers, &edges) {
             warn!("self.store.push_component(): {}", e);
         }
+        self.unpersist_edges(&edges.iter().map(|edge| edge.key().clone()).collect::<Vec<_>>());
         edges
     }
 
@@ -204,7 +277,8 @@ impl Actor {
         prune_edges_older_than: Option<time::Utc>,
     ) -> (Arc<routing::NextHopTable>, Vec<Edge>) {
         if let Some(prune_edges_older_than) = prune_edges_older_than {
-            self.graph.write().prune_old_edges(prune_edges_older_than)
+            let pruned = self.graph.write().prune_old_edges(prune_edges_older_than);
+            self.unpersist_edges(&pruned);
         }
         let next_hops = self.graph.read().next_hops();
         // Update peer_reachable_at.
@@ -288,7 +362,7 @@ impl actix::Handler<WithSpanContext<Message>> for Actor {
         let msg_type: &str = (&msg.msg).into();
         let (_span, msg) = handler_trace_span!(target: "network", msg, msg_type);
         let _timer =
-            metrics::ROUTING_TABLE_MESSAGES_TIME.with_label_values(&[msg_type]).start_timer();
+            self.metrics.routing_table_messages_time.with_label_values(&[msg_type]).start_timer();
         match msg {
             // Schedules edges for validation.
             Message::ValidateEdgeList(mut msg) => {
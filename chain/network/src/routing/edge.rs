@@ -1,5 +1,5 @@
 use crate::network_protocol::{Edge, InvalidNonceError};
-use crate::stats::metrics;
+use crate::stats::metrics::NetworkMetrics;
 use crate::time;
 
 // Don't accept nonces (edges) that are more than this delta from current time.
@@ -16,7 +16,11 @@ pub(crate) enum VerifyNonceError {
     ZeroNonce,
 }
 
-pub(crate) fn verify_nonce(clock: &time::Clock, nonce: u64) -> Result<(), VerifyNonceError> {
+pub(crate) fn verify_nonce(
+    clock: &time::Clock,
+    metrics: &NetworkMetrics,
+    nonce: u64,
+) -> Result<(), VerifyNonceError> {
     if nonce == 0 {
         return Err(VerifyNonceError::ZeroNonce);
     }
@@ -25,15 +29,15 @@ pub(crate) fn verify_nonce(clock: &time::Clock, nonce: u64) -> Result<(), Verify
         Ok(Some(nonce)) => {
             let now = clock.now_utc();
             if (now - nonce).abs() >= EDGE_NONCE_MAX_TIME_DELTA {
-                metrics::EDGE_NONCE.with_label_values(&["error_timestamp_too_distant"]).inc();
+                metrics.edge_nonce.with_label_values(&["error_timestamp_too_distant"]).inc();
                 Err(VerifyNonceError::NonceTimestampTooDistant { got: nonce, now })
             } else {
-                metrics::EDGE_NONCE.with_label_values(&["new_style"]).inc();
+                metrics.edge_nonce.with_label_values(&["new_style"]).inc();
                 Ok(())
             }
         }
         Ok(None) => {
-            metrics::EDGE_NONCE.with_label_values(&["old_style"]).inc();
+            metrics.edge_nonce.with_label_values(&["old_style"]).inc();
             Ok(())
         }
     }
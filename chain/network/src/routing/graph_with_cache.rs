@@ -1,6 +1,6 @@
 use crate::network_protocol::{Edge, EdgeState};
 use crate::routing;
-use crate::stats::metrics;
+use crate::stats::metrics::NetworkMetrics;
 use crate::time;
 use near_primitives::network::PeerId;
 use parking_lot::Mutex;
@@ -25,15 +25,17 @@ pub struct GraphWithCache {
     cached_next_hops: Mutex<Option<Arc<NextHopTable>>>,
     // Don't allow edges that are before this time (if set)
     prune_edges_before: Option<time::Utc>,
+    metrics: Arc<NetworkMetrics>,
 }
 
 impl GraphWithCache {
-    pub fn new(my_peer_id: PeerId) -> Self {
+    pub fn new(my_peer_id: PeerId, metrics: Arc<NetworkMetrics>) -> Self {
         Self {
             graph: routing::Graph::new(my_peer_id),
             edges: Default::default(),
             cached_next_hops: Default::default(),
             prune_edges_before: None,
+            metrics,
         }
     }
 
@@ -114,11 +116,12 @@ impl GraphWithCache {
             return rt;
         }
         let _d = delay_detector::DelayDetector::new(|| "routing table update".into());
-        let _next_hops_recalculation = metrics::ROUTING_TABLE_RECALCULATION_HISTOGRAM.start_timer();
+        let _next_hops_recalculation =
+            self.metrics.routing_table_recalculation_histogram.start_timer();
         trace!(target: "network", "Update routing table.");
         let rt = Arc::new(self.graph.calculate_distance());
-        metrics::ROUTING_TABLE_RECALCULATIONS.inc();
-        metrics::PEER_REACHABLE.set(rt.len() as i64);
+        self.metrics.routing_table_recalculations.inc();
+        self.metrics.peer_reachable.set(rt.len() as i64);
         *self.cached_next_hops.lock() = Some(rt.clone());
         rt
     }
@@ -137,7 +140,9 @@ impl GraphWithCache {
         edges
     }
 
-    pub fn prune_old_edges(&mut self, prune_edges_older_than: time::Utc) {
+    /// Removes edges older than <prune_edges_older_than> and rejects them from
+    /// now on. Returns the keys of the removed edges.
+    pub fn prune_old_edges(&mut self, prune_edges_older_than: time::Utc) -> Vec<EdgeKey> {
         self.prune_edges_before = Some(prune_edges_older_than);
         let old_edges = self
             .edges()
@@ -152,5 +157,6 @@ impl GraphWithCache {
             .cloned()
             .collect::<Vec<_>>();
         self.remove_edges(&old_edges.iter().collect::<Vec<_>>());
+        old_edges
     }
 }
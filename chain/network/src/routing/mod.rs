@@ -6,6 +6,7 @@ pub mod actor;
 pub(crate) mod edge;
 mod graph;
 mod graph_with_cache;
+pub(crate) mod tombstones;
 pub(crate) use actor::Actor;
 pub(crate) use graph_with_cache::NextHopTable;
 // for benchmark only
@@ -2,11 +2,13 @@ use crate::network_protocol::testonly as data;
 use crate::network_protocol::Edge;
 use crate::network_protocol::EDGE_MIN_TIMESTAMP_NONCE;
 use crate::routing;
+use crate::routing::tombstones;
 use crate::store;
 use crate::store::testonly::Component;
 use crate::testonly::make_rng;
 use crate::time;
 use near_crypto::Signature;
+use near_o11y::metrics::MetricsRegistry;
 use near_primitives::network::PeerId;
 use parking_lot::RwLock;
 use std::collections::{HashMap, HashSet};
@@ -48,18 +50,32 @@ impl RoutingTableTest {
         let me = data::make_peer_id(&mut rng);
         let db = near_store::db::TestDB::new();
 
-        let graph = Arc::new(RwLock::new(routing::GraphWithCache::new(me.clone())));
+        let metrics = MetricsRegistry::global().get();
+        let graph = Arc::new(RwLock::new(routing::GraphWithCache::new(me.clone(), metrics)));
         Self { rng, clock, graph, db, _system: actix::System::new() }
     }
 
     fn new_actor(&self) -> routing::actor::Actor {
+        self.new_actor_with(false)
+    }
+
+    fn new_actor_with(&self, load_expired_on_restart: bool) -> routing::actor::Actor {
         routing::actor::Actor::new(
             self.clock.clock(),
             store::Store::from(self.db.clone()),
             self.graph.clone(),
+            tombstones::Config { ttl: time::Duration::minutes(10), load_expired_on_restart },
+            MetricsRegistry::global().get(),
         )
     }
 
+    // Simulates a restart of the node: the in-mem graph is lost, the DB is kept.
+    fn restart(&mut self, load_expired_on_restart: bool) -> routing::actor::Actor {
+        let metrics = MetricsRegistry::global().get();
+        self.graph = Arc::new(RwLock::new(routing::GraphWithCache::new(self.me(), metrics)));
+        self.new_actor_with(load_expired_on_restart)
+    }
+
     fn check(&mut self, want_mem: &[Edge], want_db: &[Component]) {
         let store = store::Store::from(self.db.clone());
         let got_mem = self.graph.read().edges().clone();
@@ -285,3 +301,43 @@ fn expired_edges() {
     );
     test.check(&[], &[]);
 }
+
+#[test]
+fn persisted_edges() {
+    let mut test = RoutingTableTest::new();
+    test.clock.set_utc(*EDGE_MIN_TIMESTAMP_NONCE + time::Duration::days(2));
+    let mut actor = test.new_actor();
+    let p1 = test.make_peer();
+    let p2 = test.make_peer();
+    let p3 = test.make_peer();
+    let p4 = test.make_peer();
+    let current_odd_nonce = to_active_nonce(test.clock.now_utc().unix_timestamp() as u64);
+
+    let local = edge(&test.me(), &p1, current_odd_nonce);
+    let active = edge(&p1, &p2, current_odd_nonce);
+    let tombstone = edge(&p2, &p3, current_odd_nonce + 1);
+    let expired_tombstone = edge(&p3, &p4, current_odd_nonce + 1 - 20 * 60);
+    actor.add_verified_edges(vec![
+        local.clone(),
+        active.clone(),
+        tombstone.clone(),
+        expired_tombstone.clone(),
+    ]);
+    drop(actor);
+
+    // Edges adjacent to me() are not loaded after a restart, expired tombstones
+    // only if configured.
+    let mut actor = test.restart(true);
+    test.check(&[active.clone(), tombstone.clone(), expired_tombstone.clone()], &[]);
+    // Loaded edges are not new.
+    assert_eq!(actor.add_verified_edges(vec![active.clone()]), vec![]);
+    drop(actor);
+
+    let actor = test.restart(false);
+    test.check(&[active.clone(), tombstone.clone()], &[]);
+    drop(actor);
+
+    // Skipped edges have been deleted from the DB.
+    let _actor = test.restart(true);
+    test.check(&[active, tombstone], &[]);
+}
@@ -0,0 +1,47 @@
+//! Aging of edge tombstones.
+//!
+//! Every version of an edge is identified by its nonce and a tombstone is the
+//! version which marks the connection as removed.  New-style nonces are the
+//! time at which the version was created, so every node knows the age of a
+//! tombstone.  A tombstone matters only until it reaches the nodes which know
+//! an older version of the edge, which happens within seconds after it's
+//! created.  Passing it on later is just churn: after a mass restart every node
+//! sends its full routing table to every peer and old tombstones used to flood
+//! the whole network again and again.
+//!
+//! Tombstones older than the TTL are therefore expired.  An expired tombstone
+//! stays in the graph until the edge is pruned, so that it still overrides
+//! older versions of the edge, but it's neither broadcast, nor sent in the full
+//! routing table, nor accepted from peers.
+//!
+//! The edges of the graph are persisted (see `routing::Actor`), so that after a
+//! restart the node doesn't treat the whole routing table received from its
+//! peers as new and broadcast it.  Expired tombstones are loaded back only if
+//! configured, since they no longer propagate anyway.
+use crate::network_protocol::{Edge, EdgeState};
+use crate::time;
+
+#[derive(Clone, Debug)]
+pub struct Config {
+    /// Age after which a tombstone expires.
+    pub ttl: time::Duration,
+    /// Whether expired tombstones persisted before a restart are loaded back
+    /// into the graph on startup.
+    pub load_expired_on_restart: bool,
+}
+
+impl Config {
+    /// Whether <edge> is a tombstone older than the TTL.
+    /// Tombstones with old-style nonces never expire.
+    pub fn is_expired(&self, now: time::Utc, edge: &Edge) -> bool {
+        edge.edge_type() == EdgeState::Removed && edge.is_edge_older_than(now - self.ttl)
+    }
+
+    /// Removes expired tombstones from <edges>.
+    /// Returns the number of removed tombstones.
+    pub fn retain_unexpired(&self, now: time::Utc, edges: &mut Vec<Edge>) -> usize {
+        let len = edges.len();
+        edges.retain(|edge| !self.is_expired(now, edge));
+        len - edges.len()
+    }
+}
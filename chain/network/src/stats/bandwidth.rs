@@ -0,0 +1,70 @@
+//! Bytes of messages exchanged with peers, by message type.
+//!
+//! Kept both per connection and for the whole node, so that operators can
+//! tell whether e.g. state sync, block broadcast or chunk parts dominate the
+//! traffic, and with which peers.  The totals of the whole node are also
+//! exported as the `near_peer_message_{sent,received}_by_type_bytes` metrics.
+use crate::types::MessageTypeBandwidth;
+use parking_lot::Mutex;
+use std::collections::HashMap;
+
+#[derive(Default)]
+pub(crate) struct BandwidthByType {
+    /// Sent and received bytes, by the message type.
+    bytes: Mutex<HashMap<&'static str, (u64, u64)>>,
+}
+
+impl BandwidthByType {
+    pub fn record_sent(&self, msg_type: &'static str, bytes: usize) {
+        self.bytes.lock().entry(msg_type).or_default().0 += bytes as u64;
+    }
+
+    pub fn record_received(&self, msg_type: &'static str, bytes: usize) {
+        self.bytes.lock().entry(msg_type).or_default().1 += bytes as u64;
+    }
+
+    /// Returns the bandwidth of all message types, the most demanding first.
+    pub fn get(&self) -> Vec<MessageTypeBandwidth> {
+        let mut bandwidth: Vec<_> = self
+            .bytes
+            .lock()
+            .iter()
+            .map(|(message_type, (sent_bytes, received_bytes))| MessageTypeBandwidth {
+                message_type,
+                sent_bytes: *sent_bytes,
+                received_bytes: *received_bytes,
+            })
+            .collect();
+        bandwidth.sort_by_key(|it| {
+            (std::cmp::Reverse(it.sent_bytes + it.received_bytes), it.message_type)
+        });
+        bandwidth
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::BandwidthByType;
+    use crate::types::MessageTypeBandwidth;
+
+    #[test]
+    fn test_bandwidth_by_type() {
+        let bandwidth = BandwidthByType::default();
+        assert_eq!(bandwidth.get(), vec![]);
+        bandwidth.record_sent("Block", 100);
+        bandwidth.record_received("StateResponse", 500);
+        bandwidth.record_received("Block", 50);
+        bandwidth.record_sent("Block", 100);
+        assert_eq!(
+            bandwidth.get(),
+            vec![
+                MessageTypeBandwidth {
+                    message_type: "StateResponse",
+                    sent_bytes: 0,
+                    received_bytes: 500
+                },
+                MessageTypeBandwidth { message_type: "Block", sent_bytes: 200, received_bytes: 50 },
+            ]
+        );
+    }
+}
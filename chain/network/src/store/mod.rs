@@ -2,10 +2,11 @@
 /// All transactions should be implemented within this module,
 /// in particular schema::StoreUpdate is not exported.
 use crate::network_protocol::Edge;
+use crate::peer_manager::audit_log;
 use crate::types::KnownPeerState;
 use near_primitives::network::{AnnounceAccount, PeerId};
 use near_primitives::types::AccountId;
-use std::collections::HashSet;
+use std::collections::{HashMap, HashSet};
 use std::sync::Arc;
 use tracing::debug;
 
@@ -146,6 +147,74 @@ impl Store {
     }
 }
 
+// Audit log storage.
+impl Store {
+    /// Inserts (index,entry) to the PeerAuditLog column.
+    pub fn push_audit_entry(&mut self, index: u64, entry: &audit_log::Entry) -> Result<(), Error> {
+        let mut update = self.0.new_update();
+        update.set::<schema::PeerAuditLog>(&index, entry);
+        self.0.commit(update).map_err(Error)
+    }
+
+    /// Deletes rows with keys in <indices> from the PeerAuditLog column.
+    pub fn delete_audit_entries(&mut self, indices: &[u64]) -> Result<(), Error> {
+        let mut update = self.0.new_update();
+        for index in indices {
+            update.delete::<schema::PeerAuditLog>(index);
+        }
+        self.0.commit(update).map_err(Error)
+    }
+
+    /// Reads the whole PeerAuditLog column, ordered by index.
+    pub fn list_audit_entries(&self) -> Result<Vec<(u64, audit_log::Entry)>, Error> {
+        let mut entries: Vec<_> =
+            self.0.iter::<schema::PeerAuditLog>().collect::<Result<_, _>>().map_err(Error)?;
+        // Keys are little endian, so the DB order is not the order of indices.
+        entries.sort_by_key(|(index, _)| *index);
+        Ok(entries)
+    }
+}
+
+// Routing edges storage.
+impl Store {
+    /// Inserts <edges> to the RoutingEdges column, replacing the previous
+    /// versions of the edges.
+    pub fn set_routing_edges(&mut self, edges: &[Edge]) -> Result<(), Error> {
+        // Store doesn't accept 2 mutations modifying the same row in a single
+        // transaction, so only the latest version of each edge is written.
+        let mut latest = HashMap::<_, &Edge>::new();
+        for edge in edges {
+            let prev = latest.entry(edge.key()).or_insert(edge);
+            if prev.nonce() < edge.nonce() {
+                *prev = edge;
+            }
+        }
+        let mut update = self.0.new_update();
+        for (key, edge) in latest {
+            update.set::<schema::RoutingEdges>(key, edge);
+        }
+        self.0.commit(update).map_err(Error)
+    }
+
+    /// Deletes rows with keys in <keys> from the RoutingEdges column.
+    pub fn delete_routing_edges(&mut self, keys: &[(PeerId, PeerId)]) -> Result<(), Error> {
+        let mut update = self.0.new_update();
+        for key in keys {
+            update.delete::<schema::RoutingEdges>(key);
+        }
+        self.0.commit(update).map_err(Error)
+    }
+
+    /// Reads the whole RoutingEdges column.
+    pub fn list_routing_edges(&self) -> Result<Vec<Edge>, Error> {
+        self.0
+            .iter::<schema::RoutingEdges>()
+            .map(|row| row.map(|(_, edge)| edge))
+            .collect::<Result<_, _>>()
+            .map_err(Error)
+    }
+}
+
 // TODO(mina86): Get rid of it.
 #[cfg(test)]
 impl From<near_store::NodeStorage> for Store {
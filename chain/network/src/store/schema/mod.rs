@@ -1,3 +1,4 @@
+use crate::peer_manager::audit_log;
 use crate::time;
 use crate::types as primitives;
 /// Schema module defines a type-safe access to the DB.
@@ -128,6 +129,50 @@ impl BorshRepr for EdgeRepr {
     }
 }
 
+#[derive(BorshSerialize, BorshDeserialize)]
+pub struct AuditEntryRepr {
+    peer_id: PeerId,
+    addr: Option<String>,
+    reason: audit_log::Reason,
+    message_type: Option<String>,
+    evidence: String,
+    /// UNIX timestamps in nanos.
+    connected_at: Option<u64>,
+    time: u64,
+}
+
+impl BorshRepr for AuditEntryRepr {
+    type T = audit_log::Entry;
+
+    fn to_repr(e: &Self::T) -> Self {
+        Self {
+            peer_id: e.peer_id.clone(),
+            addr: e.addr.map(|addr| addr.to_string()),
+            reason: e.reason,
+            message_type: e.message_type.clone(),
+            evidence: e.evidence.clone(),
+            connected_at: e.connected_at.map(|t| t.unix_timestamp_nanos() as u64),
+            time: e.time.unix_timestamp_nanos() as u64,
+        }
+    }
+
+    fn from_repr(e: Self) -> Result<Self::T, Error> {
+        Ok(audit_log::Entry {
+            peer_id: e.peer_id,
+            addr: e.addr.map(|addr| addr.parse()).transpose().map_err(invalid_data)?,
+            reason: e.reason,
+            message_type: e.message_type,
+            evidence: e.evidence,
+            connected_at: e
+                .connected_at
+                .map(|t| time::Utc::from_unix_timestamp_nanos(t as i128))
+                .transpose()
+                .map_err(invalid_data)?,
+            time: time::Utc::from_unix_timestamp_nanos(e.time as i128).map_err(invalid_data)?,
+        })
+    }
+}
+
 /////////////////////////////////////////////
 // Columns
 
@@ -166,6 +211,20 @@ impl Column for LastComponentNonce {
     type Value = Borsh<u64>;
 }
 
+pub struct PeerAuditLog;
+impl Column for PeerAuditLog {
+    const COL: DBCol = DBCol::PeerAuditLog;
+    type Key = U64LE;
+    type Value = AuditEntryRepr;
+}
+
+pub struct RoutingEdges;
+impl Column for RoutingEdges {
+    const COL: DBCol = DBCol::RoutingEdges;
+    type Key = Borsh<(PeerId, PeerId)>;
+    type Value = EdgeRepr;
+}
+
 ////////////////////////////////////////////////////
 // Storage
 
@@ -57,6 +57,18 @@ impl Stream {
         Ok(Self { peer_addr: stream.peer_addr()?, local_addr: stream.local_addr()?, stream, type_ })
     }
 
+    /// Enables TCP keepalive probes after the connection has been idle for
+    /// `time`, so that connections to hosts which went away are detected by
+    /// the OS even if nothing is being sent.
+    pub(crate) fn set_keepalive(&self, time: crate::time::Duration) -> anyhow::Result<()> {
+        let time: std::time::Duration = time.try_into()?;
+        let keepalive = socket2::TcpKeepalive::new().with_time(time);
+        #[cfg(target_os = "linux")]
+        let keepalive = keepalive.with_interval(time / 4);
+        socket2::SockRef::from(&self.stream).set_tcp_keepalive(&keepalive)?;
+        Ok(())
+    }
+
     pub async fn connect(peer_info: &PeerInfo) -> anyhow::Result<Stream> {
         let addr =
             peer_info.addr.ok_or(anyhow!("Trying to connect to peer with no public address"))?;
@@ -1,5 +1,6 @@
 use crate::client;
 use crate::network_protocol::{
+    BlockHeaderAnnouncement, ChunkInclusionFeedbackMsg, PartialEncodedChunkAvailabilityMsg,
     PartialEncodedChunkForwardMsg, PartialEncodedChunkRequestMsg, PartialEncodedChunkResponseMsg,
     StateResponseInfo,
 };
@@ -10,6 +11,7 @@ use near_primitives::challenge::Challenge;
 use near_primitives::hash::CryptoHash;
 use near_primitives::network::{AnnounceAccount, PeerId};
 use near_primitives::sharding::{ChunkHash, PartialEncodedChunk, PartialEncodedChunkPart};
+use near_primitives::syncing::{EpochSyncDataResponse, EpochSyncProofsResponse};
 use near_primitives::transaction::SignedTransaction;
 use near_primitives::types::{AccountId, EpochId, ShardId};
 use near_primitives::views::FinalExecutionOutcomeView;
@@ -18,6 +20,7 @@ use near_primitives::views::FinalExecutionOutcomeView;
 pub enum Event {
     BlockRequest(CryptoHash),
     Block(Block),
+    BlockHeaderAnnouncement(BlockHeaderAnnouncement),
     BlockHeadersRequest(Vec<CryptoHash>),
     BlockHeaders(Vec<BlockHeader>),
     Chunk(Vec<PartialEncodedChunkPart>),
@@ -43,6 +46,16 @@ impl client::Client for Fake {
 
     async fn tx_status_response(&self, _tx_result: FinalExecutionOutcomeView) {}
 
+    async fn tx_status_subscribe(
+        &self,
+        _account_id: AccountId,
+        _tx_hash: CryptoHash,
+        _route_back: CryptoHash,
+        _subscriber: PeerId,
+    ) -> Option<Box<FinalExecutionOutcomeView>> {
+        unimplemented!();
+    }
+
     async fn state_request_header(
         &self,
         _shard_id: ShardId,
@@ -60,10 +73,43 @@ impl client::Client for Fake {
         unimplemented!();
     }
 
+    async fn state_request_sub_part(
+        &self,
+        _shard_id: ShardId,
+        _sync_hash: CryptoHash,
+        _part_id: u64,
+        _sub_part_id: u64,
+        _sub_part_size: u64,
+    ) -> Result<Option<StateResponseInfo>, ReasonForBan> {
+        unimplemented!();
+    }
+
     async fn state_response(&self, _info: StateResponseInfo) {
         unimplemented!();
     }
 
+    async fn epoch_sync_data_request(
+        &self,
+        _block_hash: CryptoHash,
+    ) -> Result<Option<EpochSyncDataResponse>, ReasonForBan> {
+        unimplemented!();
+    }
+
+    async fn epoch_sync_proofs_request(
+        &self,
+        _block_hash: CryptoHash,
+    ) -> Result<Option<EpochSyncProofsResponse>, ReasonForBan> {
+        unimplemented!();
+    }
+
+    async fn epoch_sync_proofs_response(
+        &self,
+        _response: EpochSyncProofsResponse,
+        _peer_id: PeerId,
+    ) {
+        unimplemented!();
+    }
+
     async fn block_approval(&self, _approval: Approval, _peer_id: PeerId) {
         unimplemented!();
     }
@@ -96,6 +142,14 @@ impl client::Client for Fake {
         unimplemented!();
     }
 
+    async fn partial_encoded_chunk_availability(&self, _msg: PartialEncodedChunkAvailabilityMsg) {
+        unimplemented!();
+    }
+
+    async fn chunk_inclusion_feedback(&self, _msg: ChunkInclusionFeedbackMsg) {
+        unimplemented!();
+    }
+
     async fn block_request(&self, hash: CryptoHash) -> Option<Box<Block>> {
         self.event_sink.push(Event::BlockRequest(hash));
         None
@@ -110,6 +164,14 @@ impl client::Client for Fake {
         self.event_sink.push(Event::Block(block));
     }
 
+    async fn block_header_announcement(
+        &self,
+        announcement: BlockHeaderAnnouncement,
+        _peer_id: PeerId,
+    ) {
+        self.event_sink.push(Event::BlockHeaderAnnouncement(announcement));
+    }
+
     async fn block_headers(
         &self,
         headers: Vec<BlockHeader>,
@@ -1,6 +1,6 @@
 /// Type that belong to the network protocol.
 pub use crate::network_protocol::{
-    AccountOrPeerIdOrHash, Encoding, Handshake, HandshakeFailureReason, PeerMessage,
+    AccountOrPeerIdOrHash, Compression, Encoding, Handshake, HandshakeFailureReason, PeerMessage,
     RoutingTableUpdate, SignedAccountData,
 };
 use crate::routing::routing_table_view::RoutingTableInfo;
@@ -17,7 +17,11 @@ use near_primitives::sharding::PartialEncodedChunkWithArcReceipts;
 use near_primitives::transaction::SignedTransaction;
 use near_primitives::types::BlockHeight;
 use near_primitives::types::{AccountId, EpochId, ShardId};
-use near_primitives::views::{KnownProducerView, NetworkInfoView, PeerInfoView};
+use near_primitives::validator_signer::ValidatorSigner;
+use near_primitives::views::{
+    FinalExecutionOutcomeView, KnownProducerView, MessageTypeBandwidthView, NetworkInfoView,
+    PeerInfoView,
+};
 use once_cell::sync::OnceCell;
 use std::collections::HashMap;
 use std::fmt::Debug;
@@ -26,9 +30,11 @@ use std::sync::Arc;
 
 /// Exported types, which are part of network protocol.
 pub use crate::network_protocol::{
-    Edge, PartialEdgeInfo, PartialEncodedChunkForwardMsg, PartialEncodedChunkRequestMsg,
-    PartialEncodedChunkResponseMsg, PeerChainInfo, PeerChainInfoV2, PeerIdOrHash, PeerInfo, Ping,
-    Pong, StateResponseInfo, StateResponseInfoV1, StateResponseInfoV2,
+    BlockHeaderAnnouncement, ChunkInclusionFeedbackMsg, Edge, MaintenanceWindow, PartialEdgeInfo,
+    PartialEncodedChunkAvailabilityMsg, PartialEncodedChunkForwardMsg,
+    PartialEncodedChunkRequestMsg, PartialEncodedChunkResponseMsg, PeerChainInfo, PeerChainInfoV2,
+    PeerIdOrHash, PeerInfo, Ping, Pong, StateResponseInfo, StateResponseInfoV1,
+    StateResponseInfoV2,
 };
 
 /// Number of hops a message is allowed to travel before being dropped.
@@ -129,6 +135,18 @@ impl KnownPeerStatus {
 /// See ChainInfo.
 pub type AccountKeys = HashMap<(EpochId, AccountId), PublicKey>;
 
+/// Keys of the validator of this node besides the one in the network config,
+/// e.g. the key the validator rotates to.  The AccountData of an epoch is
+/// signed with whichever key is staked in the epoch.  See ChainInfo.
+#[derive(Clone, Default)]
+pub struct ValidatorSigners(pub Vec<Arc<dyn ValidatorSigner>>);
+
+impl Debug for ValidatorSigners {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_list().entries(self.0.iter().map(|signer| signer.public_key())).finish()
+    }
+}
+
 /// Network-relevant data about the chain.
 // TODO(gprusak): it is more like node info, or sth.
 #[derive(Debug, Clone, Default)]
@@ -143,6 +161,16 @@ pub struct ChainInfo {
     // Peers acting on behalf of these accounts have a higher
     // priority on the NEAR network than other peers.
     pub tier1_accounts: Arc<AccountKeys>,
+    // Other keys of the validator of this node, used to sign its AccountData
+    // in the epochs they are staked in.
+    pub other_validator_signers: ValidatorSigners,
+    // Validators which matter the most to this node right now, the most
+    // important first: the producers of the next blocks and the producers of
+    // the next chunks of the tracked shards.  Connections to the peers of
+    // these validators are kept and messages to them are sent first.
+    pub priority_validators: Arc<Vec<AccountId>>,
+    // Maintenance window of the node, announced to the peers when it changes.
+    pub maintenance_window: Option<MaintenanceWindow>,
 }
 
 #[derive(Debug, actix::Message)]
@@ -225,6 +253,9 @@ impl From<NetworkResponses> for PeerManagerMessageResponse {
 pub enum NetworkRequests {
     /// Sends block, either when block was just produced or when requested.
     Block { block: Block },
+    /// Rebroadcasts a block received from a peer.  Peers which accept it get
+    /// only the announcement of the block, see `BlockHeaderAnnouncement`.
+    BlockHeaderFirst { block: Block },
     /// Sends approval.
     Approval { approval_message: ApprovalMessage },
     /// Request block with given hash from given peer.
@@ -240,10 +271,27 @@ pub enum NetworkRequests {
         part_id: u64,
         target: AccountOrPeerIdOrHash,
     },
+    /// Request a single sub-part of a state part for given shard at given state root.
+    StateRequestSubPart {
+        shard_id: ShardId,
+        sync_hash: CryptoHash,
+        part_id: u64,
+        sub_part_id: u64,
+        sub_part_size: u64,
+        target: AccountOrPeerIdOrHash,
+    },
+    /// Request epoch sync data of the first block of an epoch.
+    EpochSyncDataRequest { block_hash: CryptoHash, target: AccountOrPeerIdOrHash },
+    /// Request proofs of the epochs following the epoch of given block.
+    EpochSyncProofsRequest { block_hash: CryptoHash, target: AccountOrPeerIdOrHash },
     /// Response to state request.
     StateResponse { route_back: CryptoHash, response: StateResponseInfo },
-    /// Ban given peer.
+    /// Report misbehavior of the given peer, which lowers its score.  The peer
+    /// is banned once its score drops to the ban threshold.
     BanPeer { peer_id: PeerId, ban_reason: ReasonForBan },
+    /// Report good behavior of the given peer, e.g. a valid block, which
+    /// recovers its score.
+    RewardPeer { peer_id: PeerId },
     /// Announce account
     AnnounceAccount(AnnounceAccount),
 
@@ -262,11 +310,23 @@ pub enum NetworkRequests {
     },
     /// Forwarding a chunk part to a validator tracking the shard
     PartialEncodedChunkForward { account_id: AccountId, forward: PartialEncodedChunkForwardMsg },
+    /// Announcing the chunk parts we have to a validator tracking the shard
+    PartialEncodedChunkAvailability {
+        account_id: AccountId,
+        availability: PartialEncodedChunkAvailabilityMsg,
+    },
+
+    /// Telling the producer of a chunk that a block was produced without it because it arrived late
+    ChunkInclusionFeedback { account_id: AccountId, feedback: ChunkInclusionFeedbackMsg },
 
     /// Valid transaction but since we are not validators we send this transaction to current validators.
     ForwardTx(AccountId, SignedTransaction),
     /// Query transaction status
     TxStatus(AccountId, AccountId, CryptoHash),
+    /// Subscribe to the final outcome of a transaction
+    TxStatusSubscribe(AccountId, AccountId, CryptoHash),
+    /// Final outcome of a transaction pushed to a subscriber
+    TxStatusPush { route_back: CryptoHash, response: Box<FinalExecutionOutcomeView> },
     /// A challenge to invalidate a block.
     Challenge(Challenge),
 }
@@ -277,6 +337,8 @@ pub struct FullPeerInfo {
     pub peer_info: PeerInfo,
     pub chain_info: PeerChainInfoV2,
     pub partial_edge_info: PartialEdgeInfo,
+    /// Largest size of the state sub-parts the peer serves, 0 if unknown.
+    pub state_sub_part_size_limit: u64,
 }
 
 impl From<&FullPeerInfo> for ConnectedPeerInfo {
@@ -289,6 +351,9 @@ impl From<&FullPeerInfo> for ConnectedPeerInfo {
             last_time_received_message: time::Instant::now(),
             connection_established_time: time::Instant::now(),
             peer_type: PeerType::Outbound,
+            rtt: None,
+            score: 0,
+            bandwidth_by_type: vec![],
         }
     }
 }
@@ -321,6 +386,13 @@ impl From<&ConnectedPeerInfo> for PeerInfoView {
                 .elapsed()
                 .whole_milliseconds() as u64,
             is_outbound_peer: connected_peer_info.peer_type == PeerType::Outbound,
+            rtt_millis: connected_peer_info.rtt.map(|rtt| rtt.whole_milliseconds() as u64),
+            score: connected_peer_info.score,
+            bandwidth_by_type: connected_peer_info
+                .bandwidth_by_type
+                .iter()
+                .map(MessageTypeBandwidthView::from)
+                .collect(),
         }
     }
 }
@@ -341,6 +413,30 @@ pub struct ConnectedPeerInfo {
     pub connection_established_time: time::Instant,
     /// Who started connection. Inbound (other) or Outbound (us).
     pub peer_type: PeerType,
+    /// Estimated round trip time to the peer, if it has been measured.
+    pub rtt: Option<time::Duration>,
+    /// Score of the peer, lowered by misbehavior.
+    pub score: i32,
+    /// Bytes exchanged with the peer since the connection was established.
+    pub bandwidth_by_type: Vec<MessageTypeBandwidth>,
+}
+
+/// Bytes of messages of a type exchanged with peers.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct MessageTypeBandwidth {
+    pub message_type: &'static str,
+    pub sent_bytes: u64,
+    pub received_bytes: u64,
+}
+
+impl From<&MessageTypeBandwidth> for MessageTypeBandwidthView {
+    fn from(bandwidth: &MessageTypeBandwidth) -> Self {
+        MessageTypeBandwidthView {
+            message_type: bandwidth.message_type.to_string(),
+            sent_bytes: bandwidth.sent_bytes,
+            received_bytes: bandwidth.received_bytes,
+        }
+    }
 }
 
 #[derive(Debug, Clone, actix::MessageResponse)]
@@ -354,6 +450,8 @@ pub struct NetworkInfo {
     /// Accounts of known block and chunk producers from routing table.
     pub known_producers: Vec<KnownProducer>,
     pub tier1_accounts: Vec<Arc<SignedAccountData>>,
+    /// Bytes exchanged with all peers since the node started.
+    pub bandwidth_by_type: Vec<MessageTypeBandwidth>,
 }
 
 impl From<NetworkInfo> for NetworkInfoView {
@@ -378,6 +476,11 @@ impl From<NetworkInfo> for NetworkInfoView {
                         .map(|it| it.iter().map(|peer_id| peer_id.public_key().clone()).collect()),
                 })
                 .collect(),
+            bandwidth_by_type: network_info
+                .bandwidth_by_type
+                .iter()
+                .map(MessageTypeBandwidthView::from)
+                .collect(),
         }
     }
 }
@@ -541,6 +644,15 @@ mod tests {
             ],
         );
 
+        check(
+            RoutedMessageBody::TxStatusSubscribe("test_x".parse().unwrap(), CryptoHash([42; 32])),
+            &[
+                23, 6, 0, 0, 0, 116, 101, 115, 116, 95, 120, 42, 42, 42, 42, 42, 42, 42, 42, 42,
+                42, 42, 42, 42, 42, 42, 42, 42, 42, 42, 42, 42, 42, 42, 42, 42, 42, 42, 42, 42, 42,
+                42, 42,
+            ],
+        );
+
         check(
             RoutedMessageBody::VersionedStateResponse(StateResponseInfo::V1(StateResponseInfoV1 {
                 shard_id: 62,
@@ -1,4 +1,4 @@
-use std::collections::{BTreeMap, HashMap, HashSet, VecDeque};
+use std::collections::{btree_map, hash_map, BTreeMap, HashMap, HashSet, VecDeque};
 
 use crate::types::{PoolIterator, PoolKey, TransactionGroup};
 use borsh::BorshSerialize;
@@ -18,12 +18,28 @@ pub struct TransactionPool {
     /// NOTE: It's more efficient on average to keep transactions unsorted and with potentially
     /// conflicting nonce than to create a BTreeMap for every transaction.
     transactions: BTreeMap<PoolKey, Vec<SignedTransaction>>,
-    /// Set of all hashes to quickly check if the given transaction is in the pool.
-    unique_transactions: HashSet<CryptoHash>,
+    /// All transactions in the pool by hash, to quickly check if the given transaction is in the
+    /// pool.
+    unique_transactions: HashMap<CryptoHash, PoolEntry>,
+    /// Hashes of the transactions in the pool by signer, in the order they were inserted.
+    signer_transactions: HashMap<AccountId, BTreeMap<u64, CryptoHash>>,
+    /// Sequence number of the next inserted transaction.
+    next_seq: u64,
     /// A uniquely generated key seed to randomize PoolKey order.
     key_seed: RngSeed,
     /// The key after which the pool iterator starts. Doesn't have to be present in the pool.
     last_used_key: PoolKey,
+    /// Transactions pinned by the operator.  They stay in the pool when pulled by the pool
+    /// iterator, even if they are found invalid, until they are removed explicitly or included
+    /// in a block.  A copy is kept so that they can be put back.
+    pinned: HashMap<CryptoHash, SignedTransaction>,
+}
+
+struct PoolEntry {
+    signer_id: AccountId,
+    key: PoolKey,
+    /// Sequence number of the insertion of the transaction.
+    seq: u64,
 }
 
 impl TransactionPool {
@@ -31,8 +47,11 @@ impl TransactionPool {
         Self {
             key_seed,
             transactions: BTreeMap::new(),
-            unique_transactions: HashSet::new(),
+            unique_transactions: HashMap::new(),
+            signer_transactions: HashMap::new(),
+            next_seq: 0,
             last_used_key: CryptoHash::default(),
+            pinned: HashMap::new(),
         }
     }
 
@@ -50,21 +69,41 @@ impl TransactionPool {
 
     /// Insert a signed transaction into the pool that passed validation.
     pub fn insert_transaction(&mut self, signed_transaction: SignedTransaction) -> bool {
-        if !self.unique_transactions.insert(signed_transaction.get_hash()) {
+        let hash = signed_transaction.get_hash();
+        let signer_id = &signed_transaction.transaction.signer_id;
+        let key = self.key(signer_id, &signed_transaction.transaction.public_key);
+        let seq = self.next_seq;
+        match self.unique_transactions.entry(hash) {
             // The hash of this transaction was already seen, skip it.
-            return false;
-        }
+            hash_map::Entry::Occupied(_) => return false,
+            hash_map::Entry::Vacant(entry) => {
+                entry.insert(PoolEntry { signer_id: signer_id.clone(), key, seq })
+            }
+        };
         metrics::TRANSACTION_POOL_TOTAL.inc();
-
-        let signer_id = &signed_transaction.transaction.signer_id;
-        let signer_public_key = &signed_transaction.transaction.public_key;
-        self.transactions
-            .entry(self.key(signer_id, signer_public_key))
-            .or_insert_with(Vec::new)
-            .push(signed_transaction);
+        self.next_seq += 1;
+        self.signer_transactions.entry(signer_id.clone()).or_default().insert(seq, hash);
+        self.transactions.entry(key).or_insert_with(Vec::new).push(signed_transaction);
         true
     }
 
+    /// Forgets a transaction which has been taken out of its group.
+    fn forget_transaction(&mut self, hash: &CryptoHash) {
+        let entry = match self.unique_transactions.remove(hash) {
+            Some(entry) => entry,
+            None => return,
+        };
+        metrics::TRANSACTION_POOL_TOTAL.dec();
+        if let hash_map::Entry::Occupied(mut signer) =
+            self.signer_transactions.entry(entry.signer_id)
+        {
+            signer.get_mut().remove(&entry.seq);
+            if signer.get().is_empty() {
+                signer.remove();
+            }
+        }
+    }
+
     /// Returns a pool iterator wrapper that implements an iterator like trait to iterate over
     /// transaction groups in the proper order defined by the protocol.
     /// When the iterator is dropped, all remaining groups are inserted back into the pool.
@@ -77,32 +116,48 @@ impl TransactionPool {
     pub fn remove_transactions(&mut self, transactions: &[SignedTransaction]) {
         let mut grouped_transactions = HashMap::new();
         for tx in transactions {
-            if self.unique_transactions.contains(&tx.get_hash()) {
-                let signer_id = &tx.transaction.signer_id;
-                let signer_public_key = &tx.transaction.public_key;
-                grouped_transactions
-                    .entry(self.key(signer_id, signer_public_key))
-                    .or_insert_with(HashSet::new)
-                    .insert(tx.get_hash());
+            let hash = tx.get_hash();
+            if let Some(entry) = self.unique_transactions.get(&hash) {
+                grouped_transactions.entry(entry.key).or_insert_with(HashSet::new).insert(hash);
             }
         }
         for (key, hashes) in grouped_transactions {
-            let mut remove_entry = false;
-            if let Some(v) = self.transactions.get_mut(&key) {
-                v.retain(|tx| !hashes.contains(&tx.get_hash()));
-                remove_entry = v.is_empty();
-            }
-            if remove_entry {
-                self.transactions.remove(&key);
-            }
+            self.remove_from_group(key, &hashes);
             for hash in &hashes {
-                if self.unique_transactions.remove(&hash) {
-                    metrics::TRANSACTION_POOL_TOTAL.dec();
-                }
+                self.pinned.remove(hash);
+                self.forget_transaction(hash);
             }
         }
     }
 
+    fn remove_from_group(&mut self, key: PoolKey, hashes: &HashSet<CryptoHash>) {
+        if let btree_map::Entry::Occupied(mut group) = self.transactions.entry(key) {
+            group.get_mut().retain(|tx| !hashes.contains(&tx.get_hash()));
+            if group.get().is_empty() {
+                group.remove();
+            }
+        }
+    }
+
+    /// Returns the number of transactions of the signer in the pool.
+    pub fn signer_len(&self, signer_id: &AccountId) -> usize {
+        self.signer_transactions.get(signer_id).map_or(0, |hashes| hashes.len())
+    }
+
+    /// Removes the oldest transaction of the signer which isn't pinned.  Returns its hash, or
+    /// None if the signer has no such transaction in the pool.
+    pub fn evict_oldest(&mut self, signer_id: &AccountId) -> Option<CryptoHash> {
+        let hash = *self
+            .signer_transactions
+            .get(signer_id)?
+            .values()
+            .find(|hash| !self.pinned.contains_key(hash))?;
+        let key = self.unique_transactions[&hash].key;
+        self.remove_from_group(key, &HashSet::from([hash]));
+        self.forget_transaction(&hash);
+        Some(hash)
+    }
+
     /// Reintroduce transactions back during the chain reorg
     pub fn reintroduce_transactions(&mut self, transactions: Vec<SignedTransaction>) {
         for tx in transactions {
@@ -113,6 +168,49 @@ impl TransactionPool {
     pub fn len(&self) -> usize {
         self.unique_transactions.len()
     }
+
+    /// Returns true if the transaction with the given hash is in the pool.
+    pub fn contains(&self, tx_hash: &CryptoHash) -> bool {
+        self.unique_transactions.contains_key(tx_hash)
+    }
+
+    /// Iterates over all transactions in the pool, in no particular order.
+    pub fn transactions(&self) -> impl Iterator<Item = &SignedTransaction> {
+        self.transactions.values().flatten()
+    }
+
+    /// Removes all transactions matching the predicate.  Returns hashes of the removed
+    /// transactions.
+    pub fn remove_transactions_if(
+        &mut self,
+        predicate: impl Fn(&SignedTransaction) -> bool,
+    ) -> Vec<CryptoHash> {
+        let transactions: Vec<_> =
+            self.transactions().filter(|tx| predicate(tx)).cloned().collect();
+        self.remove_transactions(&transactions);
+        transactions.iter().map(|tx| tx.get_hash()).collect()
+    }
+
+    /// Pins the transaction with the given hash, so that it stays in the pool until it's included
+    /// in a block or removed explicitly.  Returns false if the transaction isn't in the pool.
+    pub fn pin_transaction(&mut self, tx_hash: &CryptoHash) -> bool {
+        match self.transactions().find(|tx| tx.get_hash() == *tx_hash).cloned() {
+            Some(tx) => {
+                self.pinned.insert(*tx_hash, tx);
+                true
+            }
+            None => false,
+        }
+    }
+
+    /// Unpins the transaction with the given hash.  Returns false if it wasn't pinned.
+    pub fn unpin_transaction(&mut self, tx_hash: &CryptoHash) -> bool {
+        self.pinned.remove(tx_hash).is_some()
+    }
+
+    pub fn is_pinned(&self, tx_hash: &CryptoHash) -> bool {
+        self.pinned.contains_key(tx_hash)
+    }
 }
 
 /// PoolIterator is a structure to pull transactions from the pool.
@@ -124,11 +222,38 @@ pub struct PoolIteratorWrapper<'a> {
 
     /// Queue of transaction groups. Each group there is sorted by nonce.
     sorted_groups: VecDeque<TransactionGroup>,
+
+    /// Pinned transactions pulled from the groups, to be put back into the pool when the
+    /// iterator is dropped.  Putting them back earlier would make the iterator return them
+    /// again.
+    pulled_pinned: Vec<(PoolKey, CryptoHash)>,
+
+    /// Pulled transactions which weren't used, to be inserted back into the pool when the
+    /// iterator is dropped, for the same reason.
+    put_back: Vec<SignedTransaction>,
 }
 
 impl<'a> PoolIteratorWrapper<'a> {
     pub fn new(pool: &'a mut TransactionPool) -> Self {
-        Self { pool, sorted_groups: Default::default() }
+        Self { pool, sorted_groups: Default::default(), pulled_pinned: vec![], put_back: vec![] }
+    }
+
+    /// Returns true if all transactions were pulled, i.e. `next()` will
+    /// return None.
+    pub fn is_exhausted(&self) -> bool {
+        self.pool.transactions.is_empty()
+            && self.sorted_groups.iter().all(|group| group.transactions.is_empty())
+    }
+
+    /// Removes transactions pulled from a group from the pool, except for the pinned ones.
+    fn forget_pulled(&mut self, key: PoolKey, hashes: Vec<CryptoHash>) {
+        for hash in hashes {
+            if self.pool.pinned.contains_key(&hash) {
+                self.pulled_pinned.push((key, hash));
+            } else {
+                self.pool.forget_transaction(&hash);
+            }
+        }
     }
 }
 
@@ -178,11 +303,7 @@ impl<'a> PoolIterator for PoolIteratorWrapper<'a> {
         } else {
             while let Some(sorted_group) = self.sorted_groups.pop_front() {
                 if sorted_group.transactions.is_empty() {
-                    for hash in sorted_group.removed_transaction_hashes {
-                        if self.pool.unique_transactions.remove(&hash) {
-                            metrics::TRANSACTION_POOL_TOTAL.dec();
-                        }
-                    }
+                    self.forget_pulled(sorted_group.key, sorted_group.removed_transaction_hashes);
                 } else {
                     self.sorted_groups.push_back(sorted_group);
                     return Some(self.sorted_groups.back_mut().expect("just pushed"));
@@ -191,23 +312,32 @@ impl<'a> PoolIterator for PoolIteratorWrapper<'a> {
             None
         }
     }
+
+    fn put_back(&mut self, transaction: SignedTransaction) {
+        self.put_back.push(transaction);
+    }
 }
 
 /// When a pool iterator is dropped, all remaining non empty transaction groups from the sorted
 /// groups queue are inserted back into the pool. And removed transactions hashes from groups are
-/// removed from the pool's unique_transactions.
+/// removed from the pool's unique_transactions, unless the transactions are pinned, in which
+/// case they are inserted back as well, same as the transactions which were put back.
 impl<'a> Drop for PoolIteratorWrapper<'a> {
     fn drop(&mut self) {
-        for group in self.sorted_groups.drain(..) {
-            for hash in group.removed_transaction_hashes {
-                if self.pool.unique_transactions.remove(&hash) {
-                    metrics::TRANSACTION_POOL_TOTAL.dec();
-                }
-            }
+        while let Some(group) = self.sorted_groups.pop_front() {
+            self.forget_pulled(group.key, group.removed_transaction_hashes);
             if !group.transactions.is_empty() {
                 self.pool.transactions.insert(group.key, group.transactions);
             }
         }
+        for (key, hash) in std::mem::take(&mut self.pulled_pinned) {
+            let tx = self.pool.pinned[&hash].clone();
+            self.pool.transactions.entry(key).or_insert_with(Vec::new).push(tx);
+        }
+        // Pinned transactions were never forgotten, so they aren't inserted twice.
+        for tx in std::mem::take(&mut self.put_back) {
+            self.pool.insert_transaction(tx);
+        }
     }
 }
 
@@ -465,4 +595,102 @@ mod tests {
         new_nonces.sort();
         assert_ne!(nonces, new_nonces);
     }
+
+    /// Pinned transactions stay in the pool when pulled by the pool iterator.
+    #[test]
+    fn test_pinned_transactions_stay_in_pool() {
+        let transactions = generate_transactions("alice.near", "alice.near", 1, 10);
+        let mut pool = TransactionPool::new(TEST_SEED);
+        for tx in transactions.clone() {
+            pool.insert_transaction(tx);
+        }
+        assert!(pool.pin_transaction(&transactions[2].get_hash()));
+        assert!(!pool.pin_transaction(&CryptoHash::default()));
+
+        // Pull and drop all transactions, as if all of them were invalid.
+        assert_eq!(prepare_transactions(&mut pool, 10).len(), 10);
+        assert_eq!(pool.len(), 1);
+        assert!(pool.contains(&transactions[2].get_hash()));
+        assert_eq!(prepare_transactions(&mut pool, 10), vec![transactions[2].clone()]);
+        assert_eq!(pool.len(), 1);
+
+        assert!(pool.unpin_transaction(&transactions[2].get_hash()));
+        assert!(!pool.unpin_transaction(&transactions[2].get_hash()));
+        assert_eq!(prepare_transactions(&mut pool, 10).len(), 1);
+        assert_eq!(pool.len(), 0);
+    }
+
+    /// Transactions put back are returned to the pool once the iterator is dropped, not earlier.
+    #[test]
+    fn test_put_back_transactions() {
+        let transactions = generate_transactions("alice.near", "alice.near", 1, 10);
+        let mut pool = TransactionPool::new(TEST_SEED);
+        for tx in transactions.clone() {
+            pool.insert_transaction(tx);
+        }
+        {
+            let mut pool_iter = pool.pool_iterator();
+            let mut pulled = vec![];
+            while let Some(iter) = pool_iter.next() {
+                pulled.push(iter.next().unwrap());
+            }
+            assert_eq!(pulled.len(), 10);
+            for tx in pulled.drain(5..) {
+                pool_iter.put_back(tx);
+            }
+            assert!(pool_iter.next().is_none());
+        }
+        assert_eq!(pool.len(), 5);
+        let nonces: Vec<_> =
+            prepare_transactions(&mut pool, 10).iter().map(|tx| tx.transaction.nonce).collect();
+        assert_eq!(nonces, vec![6, 7, 8, 9, 10]);
+    }
+
+    /// Removing a pinned transaction, e.g. once it's included in a block, unpins it.
+    #[test]
+    fn test_remove_transactions_if() {
+        let mut transactions = generate_transactions("alice.near", "alice.near", 1, 5);
+        transactions.extend(generate_transactions("bob.near", "bob.near", 1, 5));
+        let mut pool = TransactionPool::new(TEST_SEED);
+        for tx in transactions.clone() {
+            pool.insert_transaction(tx);
+        }
+        assert!(pool.pin_transaction(&transactions[0].get_hash()));
+
+        let mut removed =
+            pool.remove_transactions_if(|tx| tx.transaction.signer_id.as_ref() == "alice.near");
+        removed.sort();
+        let mut expected: Vec<_> = transactions[..5].iter().map(|tx| tx.get_hash()).collect();
+        expected.sort();
+        assert_eq!(removed, expected);
+        assert_eq!(pool.len(), 5);
+        assert!(!pool.is_pinned(&transactions[0].get_hash()));
+        assert!(pool.transactions().all(|tx| tx.transaction.signer_id.as_ref() == "bob.near"));
+    }
+
+    /// Eviction takes the oldest transactions of the signer, skipping the pinned ones, and
+    /// counts of signers are kept up to date when transactions are pulled.
+    #[test]
+    fn test_evict_oldest() {
+        let alice: AccountId = "alice.near".parse().unwrap();
+        let transactions = generate_transactions("alice.near", "alice.near", 1, 4);
+        let mut pool = TransactionPool::new(TEST_SEED);
+        for tx in transactions.iter().rev().cloned() {
+            pool.insert_transaction(tx);
+        }
+        pool.insert_transaction(generate_transactions("bob.near", "bob.near", 1, 1).remove(0));
+        assert_eq!(pool.signer_len(&alice), 4);
+        assert!(pool.pin_transaction(&transactions[3].get_hash()));
+
+        assert_eq!(pool.evict_oldest(&alice), Some(transactions[2].get_hash()));
+        assert_eq!(pool.evict_oldest(&alice), Some(transactions[1].get_hash()));
+        assert_eq!(pool.signer_len(&alice), 2);
+        assert_eq!(pool.len(), 3);
+        assert!(!pool.contains(&transactions[2].get_hash()));
+
+        assert_eq!(prepare_transactions(&mut pool, 10).len(), 3);
+        assert_eq!(pool.signer_len(&alice), 1);
+        assert_eq!(pool.evict_oldest(&alice), None);
+        assert_eq!(pool.signer_len(&"bob.near".parse().unwrap()), 0);
+    }
 }
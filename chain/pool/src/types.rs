@@ -7,6 +7,11 @@ use near_primitives::transaction::SignedTransaction;
 /// When this iterator is dropped the remaining transactions are returned back to the pool.
 pub trait PoolIterator {
     fn next(&mut self) -> Option<&mut TransactionGroup>;
+
+    /// Returns a transaction pulled from a group which ended up unused, e.g. because the limits
+    /// were reached before it was considered.  It goes back to the pool once the iterator is
+    /// dropped and isn't returned by this iterator again.
+    fn put_back(&mut self, transaction: SignedTransaction);
 }
 
 /// A hash of (an AccountId, a PublicKey and a seed).
@@ -24,6 +29,15 @@ pub struct TransactionGroup {
 }
 
 impl TransactionGroup {
+    /// Creates a group holding a single transaction, keyed by its hash.
+    pub fn from_transaction(transaction: SignedTransaction) -> Self {
+        Self {
+            key: transaction.get_hash(),
+            transactions: vec![transaction],
+            removed_transaction_hashes: vec![],
+        }
+    }
+
     /// Returns the next transaction with the smallest nonce and removes it from the group.
     /// It also stores all hashes of returned transactions.
     pub fn next(&mut self) -> Option<SignedTransaction> {
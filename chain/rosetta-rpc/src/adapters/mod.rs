@@ -419,6 +419,10 @@ impl From<NearActions> for Vec<crate::models::Operation> {
                     );
                     operations.push(deploy_contract_operation);
                 }
+                // Delegated actions have no Rosetta operations.  Their
+                // transfers show up as balance changes of the accounts
+                // once the receipt carrying them is executed.
+                near_primitives::transaction::Action::Delegate(_) => {}
             }
         }
         operations
@@ -1,10 +1,13 @@
 //! Chain Client Configuration
 use std::cmp::max;
 use std::cmp::min;
+use std::collections::HashMap;
+use std::path::PathBuf;
 use std::time::Duration;
 
 use serde::{Deserialize, Serialize};
 
+use near_crypto::PublicKey;
 use near_primitives::types::{AccountId, BlockHeightDelta, Gas, NumBlocks, NumSeats, ShardId};
 use near_primitives::version::Version;
 
@@ -70,6 +73,156 @@ impl GCConfig {
     }
 }
 
+/// Limits checked when a transaction is submitted to the node, before any
+/// expensive validation.  They protect the node from transactions which would
+/// be rejected by the runtime anyway, but only after a lot of work.  Setting
+/// them above the protocol limits has no effect.
+#[derive(Clone, Debug, Serialize, Deserialize, PartialEq)]
+pub struct TxAdmissionConfig {
+    /// Maximum size of a serialized transaction in bytes.
+    #[serde(default = "default_max_tx_size")]
+    pub max_tx_size: u64,
+    /// Maximum number of actions in a transaction.
+    #[serde(default = "default_max_tx_actions")]
+    pub max_actions: u64,
+    /// Maximum length of the arguments of a function call action in bytes.
+    #[serde(default = "default_max_tx_args_size")]
+    pub max_args_size: u64,
+}
+
+impl Default for TxAdmissionConfig {
+    fn default() -> Self {
+        Self { max_tx_size: 4 * 1024 * 1024, max_actions: 100, max_args_size: 4 * 1024 * 1024 }
+    }
+}
+
+fn default_max_tx_size() -> u64 {
+    TxAdmissionConfig::default().max_tx_size
+}
+
+fn default_max_tx_actions() -> u64 {
+    TxAdmissionConfig::default().max_actions
+}
+
+fn default_max_tx_args_size() -> u64 {
+    TxAdmissionConfig::default().max_args_size
+}
+
+/// Soft budgets of the stages of block processing.  A stage taking longer
+/// than its budget logs a warning, but doesn't affect the processing.  None
+/// disables the warning of the stage.
+#[derive(Clone, Debug, Serialize, Deserialize, PartialEq)]
+#[serde(default)]
+pub struct BlockStageBudgets {
+    /// Checks of the block header and of the block itself.
+    pub header_verification: Option<Duration>,
+    /// Checks that the chunks of the block are known and valid.
+    pub chunks_check: Option<Duration>,
+    /// Preparation of applying the chunks and waiting for a thread to apply
+    /// them.
+    pub apply_scheduling: Option<Duration>,
+    /// Applying the chunks of all shards of the block.
+    pub apply: Option<Duration>,
+    /// Storing the results of applying the chunks and updating the head.
+    pub postprocess: Option<Duration>,
+    /// Garbage collection run after the block is accepted.
+    pub garbage_collection: Option<Duration>,
+    /// Sending the new chain info to the network.
+    pub network_info_update: Option<Duration>,
+}
+
+impl Default for BlockStageBudgets {
+    fn default() -> Self {
+        Self {
+            header_verification: Some(Duration::from_millis(100)),
+            chunks_check: Some(Duration::from_millis(100)),
+            apply_scheduling: Some(Duration::from_millis(100)),
+            apply: Some(Duration::from_secs(1)),
+            postprocess: Some(Duration::from_millis(300)),
+            garbage_collection: Some(Duration::from_millis(300)),
+            network_info_update: Some(Duration::from_millis(50)),
+        }
+    }
+}
+
+/// Thresholds of the readiness and liveness verdicts of the node, served at
+/// `/health/ready` and `/health/live`.
+#[derive(Clone, Debug, Serialize, Deserialize, PartialEq)]
+#[serde(default)]
+pub struct HealthConfig {
+    /// Fewest connected peers with which the node is ready.
+    pub min_peers: usize,
+    /// Oldest the head block may be for the node to be ready.
+    pub max_head_age: Duration,
+    /// Longest average time of processing a block with which the node is
+    /// ready.
+    pub max_block_processing_time: Duration,
+    /// Whether the node is only ready if it has a validator key.
+    pub require_validator_signer: bool,
+    /// Longest time the head may not advance, while peers are ahead of it
+    /// and the node isn't syncing, for the node to be live.
+    pub max_head_stall: Duration,
+}
+
+impl Default for HealthConfig {
+    fn default() -> Self {
+        Self {
+            min_peers: 1,
+            max_head_age: Duration::from_secs(30),
+            max_block_processing_time: Duration::from_secs(2),
+            require_validator_signer: false,
+            max_head_stall: Duration::from_secs(600),
+        }
+    }
+}
+
+/// Thresholds of the detection of a head lagging behind the heights reported
+/// by the connected peers.
+#[derive(Clone, Debug, Serialize, Deserialize, PartialEq, Eq)]
+#[serde(default)]
+pub struct HeadLagConfig {
+    /// Lag behind the median height of the peers at or above which the node
+    /// starts lagging.
+    pub alert_lag: BlockHeightDelta,
+    /// How long the lag has to stay at `alert_lag` or above for the node to
+    /// start lagging.
+    pub alert_after: Duration,
+    /// Lag below which a lagging node stops lagging.  Lower than `alert_lag`,
+    /// so that the alert doesn't flap while the lag hovers around it.
+    pub clear_lag: BlockHeightDelta,
+}
+
+impl Default for HeadLagConfig {
+    fn default() -> Self {
+        Self { alert_lag: 10, alert_after: Duration::from_secs(30), clear_lag: 3 }
+    }
+}
+
+/// Where state sync gets the state of shards from.
+#[derive(Clone, Debug, Serialize, Deserialize, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum StateSyncSource {
+    /// State headers and parts are requested from peers.
+    Peers,
+    /// State headers and parts are read from a local directory, for example a
+    /// mounted snapshot, instead.  The directory is laid out as
+    /// `<path>/<sync_hash>/shard_<shard_id>/header` for borsh serialized
+    /// `ShardStateSyncResponseHeader` and
+    /// `<path>/<sync_hash>/shard_<shard_id>/part_<part_id>` for the parts.
+    LocalDirectory { path: PathBuf },
+    /// State parts are fetched over HTTP, for example from a CDN or a
+    /// publicly readable bucket of an S3-compatible storage, using the same
+    /// layout as for `LocalDirectory` under `url`.  Parts which can't be
+    /// fetched are requested from peers, as are state headers.
+    Http { url: String },
+}
+
+impl Default for StateSyncSource {
+    fn default() -> Self {
+        Self::Peers
+    }
+}
+
 #[derive(Clone, Serialize, Deserialize)]
 pub struct ClientConfig {
     /// Version of the binary.
@@ -88,6 +241,9 @@ pub struct ClientConfig {
     pub max_block_wait_delay: Duration,
     /// Duration to reduce the wait for each missed block by validator.
     pub reduce_wait_for_missing_block: Duration,
+    /// Longest time after the previous block the producer waits for missing
+    /// chunks which recently arrived that late.  Zero disables the wait.
+    pub max_adaptive_chunk_wait: Duration,
     /// Skip waiting for sync (for testing or single node testnet).
     pub skip_sync_wait: bool,
     /// How often to check that we are not out of sync.
@@ -104,8 +260,17 @@ pub struct ClientConfig {
     pub header_sync_stall_ban_timeout: Duration,
     /// Expected increase of header head weight per second during header sync
     pub header_sync_expected_height_per_second: u64,
+    /// Adjust header sync timeouts, the expected batch of headers and the
+    /// choice of the peer to sync from to the throughput measured for peers,
+    /// instead of using the fixed timeouts and expected height per second.
+    pub header_sync_adaptive: bool,
     /// How long to wait for a response during state sync
     pub state_sync_timeout: Duration,
+    /// Request state parts in checksummed sub-parts so that an interrupted
+    /// transfer only needs to re-fetch the missing ranges.
+    pub state_sync_sub_parts: bool,
+    /// Where the state of shards is downloaded from during state sync.
+    pub state_sync_source: StateSyncSource,
     /// Minimum number of peers to start syncing.
     pub min_num_peers: usize,
     /// Period between logging summary information.
@@ -130,6 +295,9 @@ pub struct ClientConfig {
     pub catchup_step_period: Duration,
     /// Time between checking to re-request chunks.
     pub chunk_request_retry_period: Duration,
+    /// How long after requesting a chunk its missing parts are also requested
+    /// from a second peer.  None disables hedged requests.
+    pub chunk_request_hedge_delay: Option<Duration>,
     /// Time between running doomslug timer.
     pub doosmslug_step_period: Duration,
     /// Behind this horizon header fetch kicks in.
@@ -142,12 +310,80 @@ pub struct ClientConfig {
     pub tracked_shards: Vec<ShardId>,
     /// Not clear old data, set `true` for archive nodes.
     pub archive: bool,
+    /// Archive is kept in cold storage.  Garbage collection of the hot store
+    /// then only touches heights already migrated to cold storage.
+    pub cold_store: bool,
     /// Number of threads for ViewClientActor pool.
     pub view_client_threads: usize,
     /// Run Epoch Sync on the start.
     pub epoch_sync_enabled: bool,
     /// Number of seconds between state requests for view client.
     pub view_client_throttle_period: Duration,
+    /// Number of most recent epochs whose first blocks the node serves state
+    /// sync headers and parts for.
+    pub state_sync_serve_epochs: u64,
+    /// Memory in bytes available to the state parts of a shard being applied
+    /// at once.  State parts are applied concurrently within this limit.
+    pub state_parts_apply_memory_limit: u64,
+    /// Total size in bytes of the state parts served to peers which are kept
+    /// in memory.  Zero disables caching.
+    pub state_part_cache_size: u64,
+    /// Whether events emitted by contracts are indexed, which makes them
+    /// available to queries and subscriptions.
+    pub contract_events_index: bool,
+    /// Whether the accounts contracts are deployed to are indexed by the hash
+    /// of the code.
+    pub contract_code_index: bool,
+    /// Whether orphans close to the head are saved to the store, so that they
+    /// survive restarts.
+    pub persist_orphans: bool,
+    /// Number of responses each view client thread caches per request kind.
+    /// Zero disables caching.
+    pub view_client_cache_size: usize,
+    /// How long a cached view client response stays valid even if the head
+    /// doesn't move.
+    pub view_client_cache_ttl: Duration,
+    /// Windows over which the time to finality of recent blocks is aggregated
+    /// in the debug page.
+    pub finality_sla_windows: Vec<Duration>,
+    /// Limits on transactions submitted to the node.
+    pub tx_admission: TxAdmissionConfig,
+    /// Soft budgets of the stages of block processing.
+    pub block_stage_budgets: BlockStageBudgets,
+    /// Maximum number of blocks which finished applying chunks postprocessed
+    /// at once, before other messages of the client get handled.
+    pub max_postprocessed_blocks_per_step: usize,
+    /// Thresholds of the readiness and liveness verdicts of the node.
+    pub health: HealthConfig,
+    /// Thresholds of the detection of the head lagging behind the peers.
+    pub head_lag: HeadLagConfig,
+    /// Time budget for pulling transactions from the pool into a produced
+    /// chunk, never more than `min_block_production_delay`.  Transactions not
+    /// reached in time stay in the pool for the next chunk.  None is no limit.
+    pub produce_chunk_add_transactions_time_limit: Option<Duration>,
+    /// Priority tiers of transactions of the given signers in the pool.
+    /// Transactions of a higher tier are included in chunks first, the
+    /// signers not listed are in tier 0.
+    pub tx_priority_signers: HashMap<AccountId, u8>,
+    /// Maximum number of transactions of a single signer in the pool of a
+    /// shard.  When a signer goes over it, its oldest transactions are
+    /// evicted.  None is no limit.
+    pub tx_pool_max_transactions_per_signer: Option<usize>,
+    /// Maximum number of transactions in a produced chunk, on top of the
+    /// limits by gas and size.  None is no limit.
+    pub max_transactions_per_chunk: Option<u64>,
+    /// Accounts added to the TIER1 accounts of every epoch, with the keys
+    /// they sign their TIER1 data with, for example a trusted relayer.  The
+    /// keys of accounts staked in the epoch take precedence, so pinning a
+    /// staked account has no effect.
+    pub tier1_pinned_accounts: HashMap<AccountId, PublicKey>,
+    /// Directory forensic bundles are saved to when a chunk disagrees with the
+    /// state root computed for its shard.  None disables them.
+    pub state_mismatch_bundles_dir: Option<PathBuf>,
+    /// Key file of the key the validator rotates to, signing the blocks,
+    /// chunks and approvals of the epochs it's staked in.  None if the
+    /// validator doesn't rotate its key.
+    pub next_validator_key_file: Option<PathBuf>,
     /// Upper bound of the byte size of contract state that is still viewable. None is no limit
     pub trie_viewer_state_size_limit: Option<u64>,
     /// Max burnt gas per view method.  If present, overrides value stored in
@@ -156,6 +392,10 @@ pub struct ClientConfig {
     pub max_gas_burnt_view: Option<Gas>,
     /// Re-export storage layer statistics as prometheus metrics.
     pub enable_statistics_export: bool,
+    /// Id of the metrics instance the client reports to.  Metrics of an
+    /// instance carry an `instance` label, which allows running several
+    /// clients in one process.  None reports to the global registry.
+    pub metrics_instance: Option<String>,
 }
 
 impl ClientConfig {
@@ -179,6 +419,7 @@ impl ClientConfig {
             max_block_production_delay: Duration::from_millis(max_block_prod_time),
             max_block_wait_delay: Duration::from_millis(3 * min_block_prod_time),
             reduce_wait_for_missing_block: Duration::from_millis(0),
+            max_adaptive_chunk_wait: Duration::ZERO,
             skip_sync_wait,
             sync_check_period: Duration::from_millis(100),
             sync_step_period: Duration::from_millis(10),
@@ -187,7 +428,10 @@ impl ClientConfig {
             header_sync_progress_timeout: Duration::from_secs(2),
             header_sync_stall_ban_timeout: Duration::from_secs(30),
             state_sync_timeout: Duration::from_secs(TEST_STATE_SYNC_TIMEOUT),
+            state_sync_sub_parts: false,
+            state_sync_source: StateSyncSource::Peers,
             header_sync_expected_height_per_second: 1,
+            header_sync_adaptive: false,
             min_num_peers: 1,
             log_summary_period: Duration::from_secs(10),
             produce_empty_blocks: true,
@@ -202,19 +446,45 @@ impl ClientConfig {
                 Duration::from_millis(100),
                 Duration::from_millis(min_block_prod_time / 5),
             ),
+            chunk_request_hedge_delay: None,
             doosmslug_step_period: Duration::from_millis(100),
             block_header_fetch_horizon: 50,
             gc: GCConfig { gc_blocks_limit: 100, ..GCConfig::default() },
             tracked_accounts: vec![],
             tracked_shards: vec![],
             archive,
+            cold_store: false,
             log_summary_style: LogSummaryStyle::Colored,
             view_client_threads: 1,
             epoch_sync_enabled,
             view_client_throttle_period: Duration::from_secs(1),
+            state_sync_serve_epochs: 2,
+            state_parts_apply_memory_limit: 1024 * 1024 * 1024,
+            state_part_cache_size: 64 * 1024 * 1024,
+            contract_events_index: false,
+            contract_code_index: false,
+            persist_orphans: false,
+            view_client_cache_size: 100,
+            view_client_cache_ttl: Duration::from_secs(1),
+            finality_sla_windows: vec![Duration::from_secs(60), Duration::from_secs(600)],
+            tx_admission: TxAdmissionConfig::default(),
+            block_stage_budgets: BlockStageBudgets::default(),
+            // Tests postprocess blocks synchronously and expect all ready
+            // blocks to be postprocessed at once.
+            max_postprocessed_blocks_per_step: usize::MAX,
+            health: HealthConfig::default(),
+            head_lag: HeadLagConfig::default(),
+            produce_chunk_add_transactions_time_limit: None,
+            tx_priority_signers: HashMap::new(),
+            tx_pool_max_transactions_per_signer: None,
+            max_transactions_per_chunk: None,
+            tier1_pinned_accounts: HashMap::new(),
+            state_mismatch_bundles_dir: None,
+            next_validator_key_file: None,
             trie_viewer_state_size_limit: None,
             max_gas_burnt_view: None,
             enable_statistics_export: true,
+            metrics_instance: None,
         }
     }
 }
@@ -0,0 +1,155 @@
+//! BLS12-381 signatures used by the aggregated block approvals prototype.
+//!
+//! Keys and signatures are kept as raw compressed bytes and only decoded when
+//! signing or verifying.  Aggregates are verified with the fast aggregate
+//! verification, which is only sound if every public key comes with a proof of
+//! possession, see [`SecretKey::proof_of_possession`].
+use blst::min_pk;
+use blst::BLST_ERROR;
+
+/// Domain separation tag for signatures over protocol messages.
+const SIGNATURE_DST: &[u8] = b"BLS_SIG_BLS12381G2_XMD:SHA-256_SSWU_RO_POP_";
+/// Domain separation tag for proofs of possession.
+const POP_DST: &[u8] = b"BLS_POP_BLS12381G2_XMD:SHA-256_SSWU_RO_POP_";
+/// Key info of the keys derived from validator keys, so that the derived key
+/// differs from any other key derived from the same secret.
+const DERIVATION_KEY_INFO: &[u8] = b"near-bls-approvals-v1";
+
+value_type!(pub, PublicKey, 48, "BLS public key");
+value_type!(pub, Signature, 96, "BLS signature");
+
+#[derive(Clone)]
+pub struct SecretKey(min_pk::SecretKey, PublicKey);
+
+impl SecretKey {
+    /// Derives a key from the secret key of a validator with the HKDF based
+    /// key generation of the BLS signature standard.  The secret scalar of
+    /// the validator key is the input key material.
+    pub fn derive_from(secret_key: &crate::SecretKey) -> Self {
+        let ikm: &[u8] = match secret_key {
+            crate::SecretKey::ED25519(secret_key) => {
+                &secret_key.0[..ed25519_dalek::SECRET_KEY_LENGTH]
+            }
+            crate::SecretKey::SECP256K1(secret_key) => &secret_key[..],
+        };
+        let sk = min_pk::SecretKey::key_gen(ikm, DERIVATION_KEY_INFO)
+            .expect("secret keys are 32 bytes long");
+        let pk = PublicKey(sk.sk_to_pk().compress());
+        SecretKey(sk, pk)
+    }
+
+    pub fn public_key(&self) -> &PublicKey {
+        &self.1
+    }
+
+    pub fn sign(&self, data: &[u8]) -> Signature {
+        Signature(self.0.sign(data, SIGNATURE_DST, &[]).compress())
+    }
+
+    /// Signs own public key to prove that the key holder knows the secret key.
+    pub fn proof_of_possession(&self) -> Signature {
+        Signature(self.0.sign(&(self.1).0, POP_DST, &[]).compress())
+    }
+}
+
+impl PublicKey {
+    fn decode(&self) -> Option<min_pk::PublicKey> {
+        min_pk::PublicKey::key_validate(&self.0).ok()
+    }
+
+    pub fn verify(&self, data: &[u8], signature: &Signature) -> bool {
+        let (pk, sig) = match (self.decode(), signature.decode()) {
+            (Some(pk), Some(sig)) => (pk, sig),
+            _ => return false,
+        };
+        sig.verify(true, data, SIGNATURE_DST, &[], &pk, false) == BLST_ERROR::BLST_SUCCESS
+    }
+
+    pub fn verify_proof_of_possession(&self, proof: &Signature) -> bool {
+        let (pk, sig) = match (self.decode(), proof.decode()) {
+            (Some(pk), Some(sig)) => (pk, sig),
+            _ => return false,
+        };
+        sig.verify(true, &self.0, POP_DST, &[], &pk, false) == BLST_ERROR::BLST_SUCCESS
+    }
+}
+
+impl Signature {
+    fn decode(&self) -> Option<min_pk::Signature> {
+        min_pk::Signature::from_bytes(&self.0).ok()
+    }
+
+    /// Aggregates signatures into one.  Returns `None` if there are no
+    /// signatures or one of them is malformed.
+    pub fn aggregate<'a>(signatures: impl IntoIterator<Item = &'a Signature>) -> Option<Signature> {
+        let signatures =
+            signatures.into_iter().map(Signature::decode).collect::<Option<Vec<_>>>()?;
+        let signatures = signatures.iter().collect::<Vec<_>>();
+        let aggregate = min_pk::AggregateSignature::aggregate(&signatures, true).ok()?;
+        Some(Signature(aggregate.to_signature().compress()))
+    }
+
+    /// Verifies an aggregate of signatures of the same message by all given
+    /// keys.  Keys must have been checked with
+    /// [`PublicKey::verify_proof_of_possession`] beforehand.
+    pub fn verify_aggregate<'a>(
+        &self,
+        data: &[u8],
+        public_keys: impl IntoIterator<Item = &'a PublicKey>,
+    ) -> bool {
+        let public_keys =
+            match public_keys.into_iter().map(PublicKey::decode).collect::<Option<Vec<_>>>() {
+                Some(public_keys) if !public_keys.is_empty() => public_keys,
+                _ => return false,
+            };
+        let sig = match self.decode() {
+            Some(sig) => sig,
+            None => return false,
+        };
+        let public_keys = public_keys.iter().collect::<Vec<_>>();
+        sig.fast_aggregate_verify(true, data, SIGNATURE_DST, &public_keys)
+            == BLST_ERROR::BLST_SUCCESS
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{SecretKey, Signature};
+    use crate::KeyType;
+
+    fn derive(seed: &str, key_type: KeyType) -> SecretKey {
+        SecretKey::derive_from(&crate::SecretKey::from_seed(key_type, seed))
+    }
+
+    #[test]
+    fn test_bls_sign_verify() {
+        let sk = derive("test", KeyType::ED25519);
+        let sig = sk.sign(b"message");
+        assert!(sk.public_key().verify(b"message", &sig));
+        assert!(!sk.public_key().verify(b"other message", &sig));
+        assert!(sk.public_key().verify_proof_of_possession(&sk.proof_of_possession()));
+        assert!(!sk.public_key().verify_proof_of_possession(&sig));
+    }
+
+    #[test]
+    fn test_bls_aggregate() {
+        let keys = ["test0", "test1", "test2"].map(|seed| derive(seed, KeyType::ED25519));
+        let signatures = keys.iter().map(|sk| sk.sign(b"message")).collect::<Vec<_>>();
+        let aggregate = Signature::aggregate(&signatures).unwrap();
+        assert!(aggregate.verify_aggregate(b"message", keys.iter().map(|sk| sk.public_key())));
+        assert!(!aggregate.verify_aggregate(b"other", keys.iter().map(|sk| sk.public_key())));
+        assert!(!aggregate.verify_aggregate(b"message", keys[..2].iter().map(|sk| sk.public_key())));
+        assert!(Signature::aggregate(&[]).is_none());
+    }
+
+    #[test]
+    fn test_bls_derive() {
+        let ed25519 = derive("test", KeyType::ED25519);
+        assert_eq!(ed25519.public_key(), derive("test", KeyType::ED25519).public_key());
+        assert_ne!(ed25519.public_key(), derive("other", KeyType::ED25519).public_key());
+        let secp256k1 = derive("test", KeyType::SECP256K1);
+        assert_ne!(ed25519.public_key(), secp256k1.public_key());
+        let sig = secp256k1.sign(b"message");
+        assert!(secp256k1.public_key().verify(b"message", &sig));
+    }
+}
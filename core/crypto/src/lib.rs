@@ -13,6 +13,8 @@ mod traits;
 #[macro_use]
 mod util;
 
+#[cfg(feature = "bls")]
+pub mod bls;
 mod errors;
 pub mod key_conversion;
 mod key_file;
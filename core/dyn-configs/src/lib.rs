@@ -6,10 +6,49 @@ use std::sync::atomic::{AtomicU64, Ordering};
 // shutdown
 pub static EXPECTED_SHUTDOWN_AT: AtomicU64 = AtomicU64::new(0);
 
-pub fn reload(expected_shutdown: Option<u64>) {
+// NOTE: Same unit as BlockHeight, stores the height from which the node drains its work ahead of
+// the expected shutdown.  Only meaningful together with `EXPECTED_SHUTDOWN_AT`.
+pub static MAINTENANCE_STARTS_AT: AtomicU64 = AtomicU64::new(0);
+
+pub fn reload(expected_shutdown: Option<u64>, maintenance_start: Option<u64>) {
     if let Some(expected_shutdown) = expected_shutdown {
         EXPECTED_SHUTDOWN_AT.store(expected_shutdown, Ordering::Relaxed);
     } else {
         EXPECTED_SHUTDOWN_AT.store(0, Ordering::Relaxed);
     }
+    MAINTENANCE_STARTS_AT.store(maintenance_start.unwrap_or(0), Ordering::Relaxed);
+}
+
+/// Returns the scheduled maintenance window as `(start, shutdown)` block heights, if a shutdown
+/// is scheduled.  Without an explicit start the window begins at the shutdown height.
+pub fn maintenance_window() -> Option<(u64, u64)> {
+    let shutdown = EXPECTED_SHUTDOWN_AT.load(Ordering::Relaxed);
+    if shutdown == 0 {
+        return None;
+    }
+    let start = match MAINTENANCE_STARTS_AT.load(Ordering::Relaxed) {
+        0 => shutdown,
+        start => start.min(shutdown),
+    };
+    Some((start, shutdown))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_maintenance_window() {
+        reload(None, Some(10));
+        assert_eq!(maintenance_window(), None);
+        reload(Some(20), None);
+        assert_eq!(maintenance_window(), Some((20, 20)));
+        reload(Some(20), Some(10));
+        assert_eq!(maintenance_window(), Some((10, 20)));
+        // The window can't start after the shutdown.
+        reload(Some(20), Some(30));
+        assert_eq!(maintenance_window(), Some((20, 20)));
+        reload(None, None);
+        assert_eq!(maintenance_window(), None);
+    }
 }
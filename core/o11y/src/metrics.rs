@@ -16,6 +16,14 @@
 //! Metrics will fail if two items have the same `name`. All metrics must have a unique `name`.
 //! Because we use a global registry there is no namespace per crate, it's one big global space.
 //!
+//! ## Instances
+//!
+//! Metrics which describe a single node rather than the whole process can be grouped into a
+//! [`MetricSet`] and created in a [`MetricsRegistry`] instead of the global registry.  Every
+//! registry obtained with [`MetricsRegistry::instance`] attaches an `instance` label to its
+//! metrics, so that several nodes running in the same process (tests, simulators) don't clash and
+//! their metrics can be told apart.  [`gather`] collects the metrics of all registries.
+//!
 //! See the [Prometheus naming best practices](https://prometheus.io/docs/practices/naming/) when
 //! choosing metric names.
 //!
@@ -67,24 +75,264 @@
 //! }
 //! ```
 
+use std::any::{Any, TypeId};
+use std::cell::RefCell;
+use std::collections::HashMap;
+use std::sync::{Arc, Mutex, PoisonError};
+
+use once_cell::sync::Lazy;
 pub use prometheus::{
     self, core::MetricVec, core::MetricVecBuilder, exponential_buckets, linear_buckets, Counter,
     Encoder, Gauge, GaugeVec, Histogram, HistogramOpts, HistogramVec, IntCounter, IntCounterVec,
     IntGauge, IntGaugeVec, Opts, Result, TextEncoder,
 };
 
-/// Collect all the metrics for reporting.
+/// Name of the label which identifies the instance a metric belongs to.
+pub const INSTANCE_LABEL: &str = "instance";
+
+/// A set of metrics created together in one registry, e.g. all the metrics of a crate.
+pub trait MetricSet: Send + Sync + 'static {
+    /// Creates and registers the metrics.  Panics if the registry doesn't accept them.
+    fn new(registry: &MetricsRegistry) -> Self;
+}
+
+struct RegistryInner {
+    registry: prometheus::Registry,
+    /// Metric sets created in this registry, keyed by the type of the set.
+    sets: Mutex<HashMap<TypeId, Arc<dyn Any + Send + Sync>>>,
+    /// Held while creating a set, so that threads asking for the same set at
+    /// once don't both register its metrics.
+    creation: Mutex<()>,
+}
+
+thread_local! {
+    /// Registries in which the current thread is creating a metric set.
+    static CREATING_IN: RefCell<Vec<*const RegistryInner>> = RefCell::new(vec![]);
+}
+
+/// Marks the current thread as creating a set in a registry until dropped, so
+/// that the mark is removed even if the constructor of the set panics.
+struct CreatingIn;
+
+impl CreatingIn {
+    fn enter(inner: *const RegistryInner) -> Self {
+        CREATING_IN.with(|creating_in| creating_in.borrow_mut().push(inner));
+        Self
+    }
+}
+
+impl Drop for CreatingIn {
+    fn drop(&mut self) {
+        CREATING_IN.with(|creating_in| creating_in.borrow_mut().pop());
+    }
+}
+
+/// A registry metrics are created in.
+///
+/// Cloning is cheap and the clones refer to the same registry.
+#[derive(Clone)]
+pub struct MetricsRegistry(Arc<RegistryInner>);
+
+static GLOBAL_REGISTRY: Lazy<MetricsRegistry> =
+    Lazy::new(|| MetricsRegistry::from_prometheus(prometheus::default_registry().clone()));
+
+static INSTANCE_REGISTRIES: Lazy<Mutex<HashMap<String, MetricsRegistry>>> =
+    Lazy::new(|| Mutex::new(HashMap::new()));
+
+impl MetricsRegistry {
+    fn from_prometheus(registry: prometheus::Registry) -> Self {
+        Self(Arc::new(RegistryInner {
+            registry,
+            sets: Mutex::new(HashMap::new()),
+            creation: Mutex::new(()),
+        }))
+    }
+
+    /// Returns the process-wide registry, which is the default prometheus registry.
+    pub fn global() -> Self {
+        GLOBAL_REGISTRY.clone()
+    }
+
+    /// Returns the registry of the given instance, creating it on first use.  Metrics created in
+    /// it carry the `instance` label with the id as the value.
+    pub fn instance(id: &str) -> Self {
+        INSTANCE_REGISTRIES
+            .lock()
+            .unwrap()
+            .entry(id.to_string())
+            .or_insert_with(|| {
+                let labels = HashMap::from([(INSTANCE_LABEL.to_string(), id.to_string())]);
+                Self::from_prometheus(
+                    prometheus::Registry::new_custom(None, Some(labels))
+                        .expect("instance label is valid"),
+                )
+            })
+            .clone()
+    }
+
+    /// Returns the registry of the given instance, or the global one if there is no instance.
+    pub fn for_instance(id: Option<&str>) -> Self {
+        match id {
+            Some(id) => Self::instance(id),
+            None => Self::global(),
+        }
+    }
+
+    /// Returns the metric set of the given type, creating it in this registry on first use.
+    pub fn get<T: MetricSet>(&self) -> Arc<T> {
+        if let Some(set) = self.lookup() {
+            return set;
+        }
+        // Creating a set may need other sets of the registry, in which case
+        // this thread holds the creation lock already.
+        let inner = Arc::as_ptr(&self.0);
+        let nested = CREATING_IN.with(|creating_in| creating_in.borrow().contains(&inner));
+        // The lock guards no data, so a constructor which panicked while
+        // holding it doesn't leave anything to recover.
+        let _guard = if nested {
+            None
+        } else {
+            Some(self.0.creation.lock().unwrap_or_else(PoisonError::into_inner))
+        };
+        if let Some(set) = self.lookup() {
+            return set;
+        }
+        let set = {
+            let _creating_in = CreatingIn::enter(inner);
+            Arc::new(T::new(self))
+        };
+        self.0.sets.lock().unwrap().insert(TypeId::of::<T>(), set.clone());
+        set
+    }
+
+    fn lookup<T: MetricSet>(&self) -> Option<Arc<T>> {
+        let sets = self.0.sets.lock().unwrap();
+        sets.get(&TypeId::of::<T>()).map(|set| set.clone().downcast().unwrap())
+    }
+
+    /// Collects the metrics of this registry.
+    pub fn gather(&self) -> Vec<prometheus::proto::MetricFamily> {
+        self.0.registry.gather()
+    }
+
+    fn register<M: prometheus::core::Collector + Clone + 'static>(&self, metric: M) -> Result<M> {
+        self.0.registry.register(Box::new(metric.clone()))?;
+        Ok(metric)
+    }
+
+    /// Attempts to crate an `IntCounter`, returning `Err` if the registry does not accept the
+    /// counter (potentially due to naming conflict).
+    pub fn try_create_int_counter(&self, name: &str, help: &str) -> Result<IntCounter> {
+        self.register(IntCounter::with_opts(Opts::new(name, help))?)
+    }
+
+    /// Attempts to crate an `IntCounterVec`, returning `Err` if the registry does not accept the
+    /// counter (potentially due to naming conflict).
+    pub fn try_create_int_counter_vec(
+        &self,
+        name: &str,
+        help: &str,
+        labels: &[&str],
+    ) -> Result<IntCounterVec> {
+        self.register(IntCounterVec::new(Opts::new(name, help), labels)?)
+    }
+
+    /// Attempts to crate an `Counter`, returning `Err` if the registry does not accept the
+    /// counter (potentially due to naming conflict).
+    pub fn try_create_counter(&self, name: &str, help: &str) -> Result<Counter> {
+        self.register(Counter::with_opts(Opts::new(name, help))?)
+    }
+
+    /// Attempts to crate an `IntGauge`, returning `Err` if the registry does not accept the gauge
+    /// (potentially due to naming conflict).
+    pub fn try_create_int_gauge(&self, name: &str, help: &str) -> Result<IntGauge> {
+        self.register(IntGauge::with_opts(Opts::new(name, help))?)
+    }
+
+    /// Attempts to crate an `IntGaugeVec`, returning `Err` if the registry does not accept the
+    /// gauge (potentially due to naming conflict).
+    pub fn try_create_int_gauge_vec(
+        &self,
+        name: &str,
+        help: &str,
+        labels: &[&str],
+    ) -> Result<IntGaugeVec> {
+        self.register(IntGaugeVec::new(Opts::new(name, help), labels)?)
+    }
+
+    /// Attempts to crate an `Gauge`, returning `Err` if the registry does not accept the gauge
+    /// (potentially due to naming conflict).
+    pub fn try_create_gauge(&self, name: &str, help: &str) -> Result<Gauge> {
+        self.register(Gauge::with_opts(Opts::new(name, help))?)
+    }
+
+    /// Attempts to crate an `GaugeVec`, returning `Err` if the registry does not accept the gauge
+    /// (potentially due to naming conflict).
+    pub fn try_create_gauge_vec(
+        &self,
+        name: &str,
+        help: &str,
+        labels: &[&str],
+    ) -> Result<GaugeVec> {
+        self.register(GaugeVec::new(Opts::new(name, help), labels)?)
+    }
+
+    /// Attempts to crate a `Histogram`, returning `Err` if the registry does not accept the
+    /// histogram (potentially due to naming conflict).
+    pub fn try_create_histogram(&self, name: &str, help: &str) -> Result<Histogram> {
+        self.register(Histogram::with_opts(HistogramOpts::new(name, help))?)
+    }
+
+    /// Attempts to crate a `Histogram` with the given buckets, returning `Err` if the registry
+    /// does not accept the histogram (potentially due to naming conflict).
+    pub fn try_create_histogram_with_buckets(
+        &self,
+        name: &str,
+        help: &str,
+        buckets: Vec<f64>,
+    ) -> Result<Histogram> {
+        self.register(Histogram::with_opts(HistogramOpts::new(name, help).buckets(buckets))?)
+    }
+
+    /// Attempts to create a `HistogramVector`, returning `Err` if the registry does not accept
+    /// the histogram (potentially due to naming conflict).
+    pub fn try_create_histogram_vec(
+        &self,
+        name: &str,
+        help: &str,
+        labels: &[&str],
+        buckets: Option<Vec<f64>>,
+    ) -> Result<HistogramVec> {
+        let mut opts = HistogramOpts::new(name, help);
+        if let Some(buckets) = buckets {
+            opts = opts.buckets(buckets);
+        }
+        self.register(HistogramVec::new(opts, labels)?)
+    }
+}
+
+/// Collect all the metrics for reporting: the global ones and the ones of all instances.
 pub fn gather() -> Vec<prometheus::proto::MetricFamily> {
-    prometheus::gather()
+    let mut families = MetricsRegistry::global().gather();
+    let instances: Vec<MetricsRegistry> =
+        INSTANCE_REGISTRIES.lock().unwrap().values().cloned().collect();
+    for instance in instances {
+        for family in instance.gather() {
+            // Families of the same name coming from different instances are merged, since
+            // a family may be present only once in the exposition format.
+            match families.iter_mut().find(|f| f.get_name() == family.get_name()) {
+                Some(existing) => existing.mut_metric().extend(family.get_metric().iter().cloned()),
+                None => families.push(family),
+            }
+        }
+    }
+    families
 }
 
 /// Attempts to crate an `IntCounter`, returning `Err` if the registry does not accept the counter
 /// (potentially due to naming conflict).
 pub fn try_create_int_counter(name: &str, help: &str) -> Result<IntCounter> {
-    let opts = Opts::new(name, help);
-    let counter = IntCounter::with_opts(opts)?;
-    prometheus::register(Box::new(counter.clone()))?;
-    Ok(counter)
+    GLOBAL_REGISTRY.try_create_int_counter(name, help)
 }
 
 /// Attempts to crate an `IntCounterVec`, returning `Err` if the registry does not accept the counter
@@ -94,64 +342,43 @@ pub fn try_create_int_counter_vec(
     help: &str,
     labels: &[&str],
 ) -> Result<IntCounterVec> {
-    let opts = Opts::new(name, help);
-    let counter = IntCounterVec::new(opts, labels)?;
-    prometheus::register(Box::new(counter.clone()))?;
-    Ok(counter)
+    GLOBAL_REGISTRY.try_create_int_counter_vec(name, help, labels)
 }
 
 /// Attempts to crate an `Counter`, returning `Err` if the registry does not accept the counter
 /// (potentially due to naming conflict).
 pub fn try_create_counter(name: &str, help: &str) -> Result<Counter> {
-    let opts = Opts::new(name, help);
-    let counter = Counter::with_opts(opts)?;
-    prometheus::register(Box::new(counter.clone()))?;
-    Ok(counter)
+    GLOBAL_REGISTRY.try_create_counter(name, help)
 }
 
 /// Attempts to crate an `IntGauge`, returning `Err` if the registry does not accept the gauge
 /// (potentially due to naming conflict).
 pub fn try_create_int_gauge(name: &str, help: &str) -> Result<IntGauge> {
-    let opts = Opts::new(name, help);
-    let gauge = IntGauge::with_opts(opts)?;
-    prometheus::register(Box::new(gauge.clone()))?;
-    Ok(gauge)
+    GLOBAL_REGISTRY.try_create_int_gauge(name, help)
 }
 
 /// Attempts to crate an `IntGaugeVec`, returning `Err` if the registry does not accept the gauge
 /// (potentially due to naming conflict).
 pub fn try_create_int_gauge_vec(name: &str, help: &str, labels: &[&str]) -> Result<IntGaugeVec> {
-    let opts = Opts::new(name, help);
-    let gauge = IntGaugeVec::new(opts, labels)?;
-    prometheus::register(Box::new(gauge.clone()))?;
-    Ok(gauge)
+    GLOBAL_REGISTRY.try_create_int_gauge_vec(name, help, labels)
 }
 
 /// Attempts to crate an `Gauge`, returning `Err` if the registry does not accept the gauge
 /// (potentially due to naming conflict).
 pub fn try_create_gauge(name: &str, help: &str) -> Result<Gauge> {
-    let opts = Opts::new(name, help);
-    let gauge = Gauge::with_opts(opts)?;
-    prometheus::register(Box::new(gauge.clone()))?;
-    Ok(gauge)
+    GLOBAL_REGISTRY.try_create_gauge(name, help)
 }
 
 /// Attempts to crate an `GaugeVec`, returning `Err` if the registry does not accept the gauge
 /// (potentially due to naming conflict).
 pub fn try_create_gauge_vec(name: &str, help: &str, labels: &[&str]) -> Result<GaugeVec> {
-    let opts = Opts::new(name, help);
-    let gauge = GaugeVec::new(opts, labels)?;
-    prometheus::register(Box::new(gauge.clone()))?;
-    Ok(gauge)
+    GLOBAL_REGISTRY.try_create_gauge_vec(name, help, labels)
 }
 
 /// Attempts to crate a `Histogram`, returning `Err` if the registry does not accept the counter
 /// (potentially due to naming conflict).
 pub fn try_create_histogram(name: &str, help: &str) -> Result<Histogram> {
-    let opts = HistogramOpts::new(name, help);
-    let histogram = Histogram::with_opts(opts)?;
-    prometheus::register(Box::new(histogram.clone()))?;
-    Ok(histogram)
+    GLOBAL_REGISTRY.try_create_histogram(name, help)
 }
 
 /// Attempts to crate a `Histogram`, returning `Err` if the registry does not accept the counter
@@ -161,10 +388,7 @@ pub fn try_create_histogram_with_buckets(
     help: &str,
     buckets: Vec<f64>,
 ) -> Result<Histogram> {
-    let opts = HistogramOpts::new(name, help).buckets(buckets);
-    let histogram = Histogram::with_opts(opts)?;
-    prometheus::register(Box::new(histogram.clone()))?;
-    Ok(histogram)
+    GLOBAL_REGISTRY.try_create_histogram_with_buckets(name, help, buckets)
 }
 
 /// Attempts to create a `HistogramVector`, returning `Err` if the registry does not accept the counter
@@ -175,11 +399,116 @@ pub fn try_create_histogram_vec(
     labels: &[&str],
     buckets: Option<Vec<f64>>,
 ) -> Result<HistogramVec> {
-    let mut opts = HistogramOpts::new(name, help);
-    if let Some(buckets) = buckets {
-        opts = opts.buckets(buckets);
+    GLOBAL_REGISTRY.try_create_histogram_vec(name, help, labels, buckets)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{IntCounter, MetricSet, MetricsRegistry};
+
+    struct TestMetrics {
+        counter: IntCounter,
+    }
+
+    impl MetricSet for TestMetrics {
+        fn new(registry: &MetricsRegistry) -> Self {
+            Self {
+                counter: registry
+                    .try_create_int_counter("near_test_instance_counter", "Test counter")
+                    .unwrap(),
+            }
+        }
+    }
+
+    #[test]
+    fn test_instance_registries() {
+        let first = MetricsRegistry::instance("test-first");
+        let second = MetricsRegistry::instance("test-second");
+        first.get::<TestMetrics>().counter.inc();
+        // The set is created once per registry.
+        first.get::<TestMetrics>().counter.inc();
+        second.get::<TestMetrics>().counter.inc();
+        assert_eq!(first.get::<TestMetrics>().counter.get(), 2);
+        assert_eq!(MetricsRegistry::instance("test-second").get::<TestMetrics>().counter.get(), 1);
+
+        let family = super::gather()
+            .into_iter()
+            .find(|family| family.get_name() == "near_test_instance_counter")
+            .unwrap();
+        let mut values: Vec<_> = family
+            .get_metric()
+            .iter()
+            .map(|metric| {
+                let label = &metric.get_label()[0];
+                assert_eq!(label.get_name(), super::INSTANCE_LABEL);
+                (label.get_value().to_string(), metric.get_counter().get_value())
+            })
+            .collect();
+        values.sort_by(|a, b| a.0.cmp(&b.0));
+        assert_eq!(values, vec![("test-first".to_string(), 2.0), ("test-second".to_string(), 1.0)]);
+    }
+
+    struct ConcurrentTestMetrics {
+        counter: IntCounter,
+    }
+
+    impl MetricSet for ConcurrentTestMetrics {
+        fn new(registry: &MetricsRegistry) -> Self {
+            Self {
+                counter: registry
+                    .try_create_int_counter("near_test_concurrent_counter", "Test counter")
+                    .unwrap(),
+            }
+        }
+    }
+
+    /// Needs another set of the registry to be created.
+    struct NestedTestMetrics {
+        inner: std::sync::Arc<ConcurrentTestMetrics>,
+    }
+
+    impl MetricSet for NestedTestMetrics {
+        fn new(registry: &MetricsRegistry) -> Self {
+            // Give concurrent callers time to race into the creation.
+            std::thread::sleep(std::time::Duration::from_millis(10));
+            Self { inner: registry.get::<ConcurrentTestMetrics>() }
+        }
+    }
+
+    struct PanickingTestMetrics;
+
+    impl MetricSet for PanickingTestMetrics {
+        fn new(_registry: &MetricsRegistry) -> Self {
+            panic!("failed to create metrics");
+        }
+    }
+
+    /// A constructor which panics leaves the registry usable by all threads.
+    #[test]
+    fn test_get_after_panic() {
+        let registry = MetricsRegistry::instance("test-panic");
+        let get = std::panic::AssertUnwindSafe(|| registry.get::<PanickingTestMetrics>());
+        assert!(std::panic::catch_unwind(get).is_err());
+        super::CREATING_IN.with(|creating_in| assert!(creating_in.borrow().is_empty()));
+        let other = registry.clone();
+        std::thread::spawn(move || other.get::<ConcurrentTestMetrics>().counter.inc())
+            .join()
+            .unwrap();
+        assert_eq!(registry.get::<ConcurrentTestMetrics>().counter.get(), 1);
+    }
+
+    #[test]
+    fn test_concurrent_get() {
+        let registry = MetricsRegistry::instance("test-concurrent");
+        let threads = (0..8)
+            .map(|_| {
+                let registry = registry.clone();
+                std::thread::spawn(move || registry.get::<NestedTestMetrics>().inner.counter.inc())
+            })
+            .collect::<Vec<_>>();
+        for thread in threads {
+            thread.join().unwrap();
+        }
+        assert_eq!(registry.get::<ConcurrentTestMetrics>().counter.get(), 8);
     }
-    let histogram = HistogramVec::new(opts, labels)?;
-    prometheus::register(Box::new(histogram.clone()))?;
-    Ok(histogram)
 }
@@ -262,6 +262,10 @@ impl Block {
             BlockHeader::BlockHeaderV3(_) => {
                 debug_assert_eq!(prev.block_ordinal() + 1, block_ordinal)
             }
+            #[cfg(feature = "protocol_feature_bls_approvals")]
+            BlockHeader::BlockHeaderV4(_) => {
+                debug_assert_eq!(prev.block_ordinal() + 1, block_ordinal)
+            }
         };
 
         let header = BlockHeader::new(
@@ -305,6 +309,22 @@ impl Block {
         )
     }
 
+    /// Sets the approvals aggregate of a freshly produced block, see
+    /// [`BlockHeader::with_approvals_aggregate`].
+    #[cfg(feature = "protocol_feature_bls_approvals")]
+    pub fn with_approvals_aggregate(
+        mut self,
+        approvals_aggregate: ApprovalsAggregate,
+        signer: &dyn ValidatorSigner,
+    ) -> Self {
+        let header = match &mut self {
+            Block::BlockV1(block) => &mut Arc::make_mut(block).header,
+            Block::BlockV2(block) => &mut Arc::make_mut(block).header,
+        };
+        *header = header.clone().with_approvals_aggregate(approvals_aggregate, signer);
+        self
+    }
+
     pub fn verify_gas_price(
         &self,
         prev_gas_price: Balance,
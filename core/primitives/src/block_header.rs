@@ -156,6 +156,105 @@ pub struct BlockHeaderInnerRestV3 {
     pub latest_protocol_version: ProtocolVersion,
 }
 
+/// Add `approvals_aggregate`
+#[cfg(feature = "protocol_feature_bls_approvals")]
+#[derive(BorshSerialize, BorshDeserialize, Serialize, Debug, Clone, Eq, PartialEq)]
+pub struct BlockHeaderInnerRestV4 {
+    /// Root hash of the chunk receipts in the given block.
+    pub chunk_receipts_root: MerkleHash,
+    /// Root hash of the chunk headers in the given block.
+    pub chunk_headers_root: MerkleHash,
+    /// Root hash of the chunk transactions in the given block.
+    pub chunk_tx_root: MerkleHash,
+    /// Root hash of the challenges in the given block.
+    pub challenges_root: MerkleHash,
+    /// The output of the randomness beacon
+    pub random_value: CryptoHash,
+    /// Validator proposals.
+    pub validator_proposals: Vec<ValidatorStake>,
+    /// Mask for new chunks included in the block
+    pub chunk_mask: Vec<bool>,
+    /// Gas price. Same for all chunks
+    pub gas_price: Balance,
+    /// Total supply of tokens in the system
+    pub total_supply: Balance,
+    /// List of challenges result from previous block.
+    pub challenges_result: ChallengesResult,
+
+    /// Last block that has full BFT finality
+    pub last_final_block: CryptoHash,
+    /// Last block that has doomslug finality
+    pub last_ds_final_block: CryptoHash,
+
+    /// The ordinal of the Block on the Canonical Chain
+    pub block_ordinal: NumBlocks,
+
+    pub prev_height: BlockHeight,
+
+    pub epoch_sync_data_hash: Option<CryptoHash>,
+
+    /// All the approvals included in this block, except for the ones aggregated in
+    /// `approvals_aggregate`.
+    pub approvals: Vec<Option<Signature>>,
+
+    /// Aggregated BLS signature of the approvals of validators that registered a BLS key.
+    pub approvals_aggregate: Option<ApprovalsAggregate>,
+
+    /// Latest protocol version that this block producer has.
+    pub latest_protocol_version: ProtocolVersion,
+}
+
+/// Aggregated BLS signature of block approvals.
+#[cfg(feature = "protocol_feature_bls_approvals")]
+#[derive(
+    BorshSerialize, BorshDeserialize, Serialize, serde::Deserialize, Debug, Clone, Eq, PartialEq,
+)]
+pub struct ApprovalsAggregate {
+    /// Which of the block approvers, in the same order as `approvals`, signed the aggregate.
+    pub signers: Vec<bool>,
+    pub signature: near_crypto::bls::Signature,
+    /// Registrations of signer keys which were not registered on chain yet.  Every node registers
+    /// them when processing the header.
+    pub registrations: Vec<BlsKeyRegistration>,
+}
+
+/// Registration of a validator BLS key, signed with the validator key.  The proof of possession
+/// is required for aggregated signatures to be safe against rogue key attacks.
+#[cfg(feature = "protocol_feature_bls_approvals")]
+#[derive(
+    BorshSerialize, BorshDeserialize, Serialize, serde::Deserialize, Debug, Clone, PartialEq, Eq,
+)]
+pub struct BlsKeyRegistration {
+    pub account_id: AccountId,
+    pub public_key: near_crypto::bls::PublicKey,
+    pub proof_of_possession: near_crypto::bls::Signature,
+    pub signature: Signature,
+}
+
+#[cfg(feature = "protocol_feature_bls_approvals")]
+impl BlsKeyRegistration {
+    pub fn get_data_for_sig(
+        account_id: &AccountId,
+        public_key: &near_crypto::bls::PublicKey,
+    ) -> Vec<u8> {
+        (account_id, public_key).try_to_vec().expect("Failed to serialize")
+    }
+
+    /// Checks the proof of possession.  The signature must be checked against the validator key
+    /// separately.
+    pub fn verify_proof_of_possession(&self) -> bool {
+        self.public_key.verify_proof_of_possession(&self.proof_of_possession)
+    }
+}
+
+/// BLS signature of an approval, together with the registration of the key that made it.
+#[cfg(feature = "protocol_feature_bls_approvals")]
+#[derive(BorshSerialize, BorshDeserialize, Serialize, Debug, Clone, PartialEq, Eq)]
+pub struct ApprovalBlsSignature {
+    pub signature: near_crypto::bls::Signature,
+    pub registration: BlsKeyRegistration,
+}
+
 /// The part of the block approval that is different for endorsements and skips
 #[derive(BorshSerialize, BorshDeserialize, Serialize, Debug, Clone, PartialEq, Eq, Hash)]
 pub enum ApprovalInner {
@@ -170,6 +269,10 @@ pub struct Approval {
     pub target_height: BlockHeight,
     pub signature: Signature,
     pub account_id: AccountId,
+    /// Signature of the same data with the validator BLS key, if it has one.  Only present with
+    /// the BLS approvals prototype, which changes the wire format of approvals.
+    #[cfg(feature = "protocol_feature_bls_approvals")]
+    pub bls_signature: Option<ApprovalBlsSignature>,
 }
 
 /// The type of approvals. It is either approval from self or from a peer
@@ -209,7 +312,14 @@ impl Approval {
     ) -> Self {
         let inner = ApprovalInner::new(&parent_hash, parent_height, target_height);
         let signature = signer.sign_approval(&inner, target_height);
-        Approval { inner, target_height, signature, account_id: signer.validator_id().clone() }
+        Approval {
+            #[cfg(feature = "protocol_feature_bls_approvals")]
+            bls_signature: signer.sign_approval_bls(&inner, target_height),
+            inner,
+            target_height,
+            signature,
+            account_id: signer.validator_id().clone(),
+        }
     }
 
     pub fn get_data_for_sig(inner: &ApprovalInner, target_height: BlockHeight) -> Vec<u8> {
@@ -310,6 +420,63 @@ impl BlockHeaderV3 {
     }
 }
 
+/// V3 -> V4: Add `approvals_aggregate` to `inner_rest`
+#[cfg(feature = "protocol_feature_bls_approvals")]
+#[derive(BorshSerialize, BorshDeserialize, Serialize, Debug, Clone, Eq, PartialEq)]
+#[borsh_init(init)]
+pub struct BlockHeaderV4 {
+    pub prev_hash: CryptoHash,
+
+    /// Inner part of the block header that gets hashed, split into two parts, one that is sent
+    ///    to light clients, and the rest
+    pub inner_lite: BlockHeaderInnerLite,
+    pub inner_rest: BlockHeaderInnerRestV4,
+
+    /// Signature of the block producer.
+    pub signature: Signature,
+
+    /// Cached value of hash for this block.
+    #[borsh_skip]
+    pub hash: CryptoHash,
+}
+
+#[cfg(feature = "protocol_feature_bls_approvals")]
+impl BlockHeaderV4 {
+    pub fn init(&mut self) {
+        self.hash = BlockHeader::compute_hash(
+            self.prev_hash,
+            &self.inner_lite.try_to_vec().expect("Failed to serialize"),
+            &self.inner_rest.try_to_vec().expect("Failed to serialize"),
+        );
+    }
+}
+
+#[cfg(feature = "protocol_feature_bls_approvals")]
+impl From<BlockHeaderInnerRestV3> for BlockHeaderInnerRestV4 {
+    fn from(inner_rest: BlockHeaderInnerRestV3) -> Self {
+        Self {
+            chunk_receipts_root: inner_rest.chunk_receipts_root,
+            chunk_headers_root: inner_rest.chunk_headers_root,
+            chunk_tx_root: inner_rest.chunk_tx_root,
+            challenges_root: inner_rest.challenges_root,
+            random_value: inner_rest.random_value,
+            validator_proposals: inner_rest.validator_proposals,
+            chunk_mask: inner_rest.chunk_mask,
+            gas_price: inner_rest.gas_price,
+            total_supply: inner_rest.total_supply,
+            challenges_result: inner_rest.challenges_result,
+            last_final_block: inner_rest.last_final_block,
+            last_ds_final_block: inner_rest.last_ds_final_block,
+            block_ordinal: inner_rest.block_ordinal,
+            prev_height: inner_rest.prev_height,
+            epoch_sync_data_hash: inner_rest.epoch_sync_data_hash,
+            approvals: inner_rest.approvals,
+            approvals_aggregate: None,
+            latest_protocol_version: inner_rest.latest_protocol_version,
+        }
+    }
+}
+
 /// Versioned BlockHeader data structure.
 /// For each next version, document what are the changes between versions.
 #[derive(BorshSerialize, BorshDeserialize, Serialize, Debug, Clone, Eq, PartialEq)]
@@ -317,6 +484,8 @@ pub enum BlockHeader {
     BlockHeaderV1(Arc<BlockHeaderV1>),
     BlockHeaderV2(Arc<BlockHeaderV2>),
     BlockHeaderV3(Arc<BlockHeaderV3>),
+    #[cfg(feature = "protocol_feature_bls_approvals")]
+    BlockHeaderV4(Arc<BlockHeaderV4>),
 }
 
 impl BlockHeader {
@@ -456,6 +625,14 @@ impl BlockHeader {
                 approvals,
                 latest_protocol_version: get_protocol_version(next_epoch_protocol_version),
             };
+            #[cfg(feature = "protocol_feature_bls_approvals")]
+            if crate::checked_feature!(
+                "protocol_feature_bls_approvals",
+                BlsApprovals,
+                this_epoch_protocol_version
+            ) {
+                return Self::new_v4(prev_hash, inner_lite, inner_rest.into(), signer);
+            }
             let (hash, signature) = signer.sign_block_header_parts(
                 prev_hash,
                 &inner_lite.try_to_vec().expect("Failed to serialize"),
@@ -577,6 +754,26 @@ impl BlockHeader {
                 approvals: vec![],
                 latest_protocol_version: genesis_protocol_version,
             };
+            #[cfg(feature = "protocol_feature_bls_approvals")]
+            if crate::checked_feature!(
+                "protocol_feature_bls_approvals",
+                BlsApprovals,
+                genesis_protocol_version
+            ) {
+                let inner_rest = BlockHeaderInnerRestV4::from(inner_rest);
+                let hash = BlockHeader::compute_hash(
+                    CryptoHash::default(),
+                    &inner_lite.try_to_vec().expect("Failed to serialize"),
+                    &inner_rest.try_to_vec().expect("Failed to serialize"),
+                );
+                return Self::BlockHeaderV4(Arc::new(BlockHeaderV4 {
+                    prev_hash: CryptoHash::default(),
+                    inner_lite,
+                    inner_rest,
+                    signature: Signature::empty(KeyType::ED25519),
+                    hash,
+                }));
+            }
             let hash = BlockHeader::compute_hash(
                 CryptoHash::default(),
                 &inner_lite.try_to_vec().expect("Failed to serialize"),
@@ -592,12 +789,56 @@ impl BlockHeader {
         }
     }
 
+    #[cfg(feature = "protocol_feature_bls_approvals")]
+    fn new_v4(
+        prev_hash: CryptoHash,
+        inner_lite: BlockHeaderInnerLite,
+        inner_rest: BlockHeaderInnerRestV4,
+        signer: &dyn ValidatorSigner,
+    ) -> Self {
+        let (hash, signature) = signer.sign_block_header_parts(
+            prev_hash,
+            &inner_lite.try_to_vec().expect("Failed to serialize"),
+            &inner_rest.try_to_vec().expect("Failed to serialize"),
+        );
+        Self::BlockHeaderV4(Arc::new(BlockHeaderV4 {
+            prev_hash,
+            inner_lite,
+            inner_rest,
+            signature,
+            hash,
+        }))
+    }
+
+    /// Sets the approvals aggregate of a freshly produced header and signs it again.  Headers of
+    /// older versions are returned unchanged.
+    #[cfg(feature = "protocol_feature_bls_approvals")]
+    pub fn with_approvals_aggregate(
+        self,
+        approvals_aggregate: ApprovalsAggregate,
+        signer: &dyn ValidatorSigner,
+    ) -> Self {
+        match self {
+            BlockHeader::BlockHeaderV4(header) => {
+                let header = Arc::try_unwrap(header).unwrap_or_else(|header| (*header).clone());
+                let inner_rest = BlockHeaderInnerRestV4 {
+                    approvals_aggregate: Some(approvals_aggregate),
+                    ..header.inner_rest
+                };
+                Self::new_v4(header.prev_hash, header.inner_lite, inner_rest, signer)
+            }
+            header => header,
+        }
+    }
+
     #[inline]
     pub fn hash(&self) -> &CryptoHash {
         match self {
             BlockHeader::BlockHeaderV1(header) => &header.hash,
             BlockHeader::BlockHeaderV2(header) => &header.hash,
             BlockHeader::BlockHeaderV3(header) => &header.hash,
+            #[cfg(feature = "protocol_feature_bls_approvals")]
+            BlockHeader::BlockHeaderV4(header) => &header.hash,
         }
     }
 
@@ -607,6 +848,8 @@ impl BlockHeader {
             BlockHeader::BlockHeaderV1(header) => &header.prev_hash,
             BlockHeader::BlockHeaderV2(header) => &header.prev_hash,
             BlockHeader::BlockHeaderV3(header) => &header.prev_hash,
+            #[cfg(feature = "protocol_feature_bls_approvals")]
+            BlockHeader::BlockHeaderV4(header) => &header.prev_hash,
         }
     }
 
@@ -616,6 +859,8 @@ impl BlockHeader {
             BlockHeader::BlockHeaderV1(header) => &header.signature,
             BlockHeader::BlockHeaderV2(header) => &header.signature,
             BlockHeader::BlockHeaderV3(header) => &header.signature,
+            #[cfg(feature = "protocol_feature_bls_approvals")]
+            BlockHeader::BlockHeaderV4(header) => &header.signature,
         }
     }
 
@@ -625,6 +870,8 @@ impl BlockHeader {
             BlockHeader::BlockHeaderV1(header) => header.inner_lite.height,
             BlockHeader::BlockHeaderV2(header) => header.inner_lite.height,
             BlockHeader::BlockHeaderV3(header) => header.inner_lite.height,
+            #[cfg(feature = "protocol_feature_bls_approvals")]
+            BlockHeader::BlockHeaderV4(header) => header.inner_lite.height,
         }
     }
 
@@ -634,6 +881,8 @@ impl BlockHeader {
             BlockHeader::BlockHeaderV1(_) => None,
             BlockHeader::BlockHeaderV2(_) => None,
             BlockHeader::BlockHeaderV3(header) => Some(header.inner_rest.prev_height),
+            #[cfg(feature = "protocol_feature_bls_approvals")]
+            BlockHeader::BlockHeaderV4(header) => Some(header.inner_rest.prev_height),
         }
     }
 
@@ -643,6 +892,8 @@ impl BlockHeader {
             BlockHeader::BlockHeaderV1(header) => &header.inner_lite.epoch_id,
             BlockHeader::BlockHeaderV2(header) => &header.inner_lite.epoch_id,
             BlockHeader::BlockHeaderV3(header) => &header.inner_lite.epoch_id,
+            #[cfg(feature = "protocol_feature_bls_approvals")]
+            BlockHeader::BlockHeaderV4(header) => &header.inner_lite.epoch_id,
         }
     }
 
@@ -652,6 +903,8 @@ impl BlockHeader {
             BlockHeader::BlockHeaderV1(header) => &header.inner_lite.next_epoch_id,
             BlockHeader::BlockHeaderV2(header) => &header.inner_lite.next_epoch_id,
             BlockHeader::BlockHeaderV3(header) => &header.inner_lite.next_epoch_id,
+            #[cfg(feature = "protocol_feature_bls_approvals")]
+            BlockHeader::BlockHeaderV4(header) => &header.inner_lite.next_epoch_id,
         }
     }
 
@@ -661,6 +914,8 @@ impl BlockHeader {
             BlockHeader::BlockHeaderV1(header) => &header.inner_lite.prev_state_root,
             BlockHeader::BlockHeaderV2(header) => &header.inner_lite.prev_state_root,
             BlockHeader::BlockHeaderV3(header) => &header.inner_lite.prev_state_root,
+            #[cfg(feature = "protocol_feature_bls_approvals")]
+            BlockHeader::BlockHeaderV4(header) => &header.inner_lite.prev_state_root,
         }
     }
 
@@ -670,6 +925,8 @@ impl BlockHeader {
             BlockHeader::BlockHeaderV1(header) => &header.inner_rest.chunk_receipts_root,
             BlockHeader::BlockHeaderV2(header) => &header.inner_rest.chunk_receipts_root,
             BlockHeader::BlockHeaderV3(header) => &header.inner_rest.chunk_receipts_root,
+            #[cfg(feature = "protocol_feature_bls_approvals")]
+            BlockHeader::BlockHeaderV4(header) => &header.inner_rest.chunk_receipts_root,
         }
     }
 
@@ -679,6 +936,8 @@ impl BlockHeader {
             BlockHeader::BlockHeaderV1(header) => &header.inner_rest.chunk_headers_root,
             BlockHeader::BlockHeaderV2(header) => &header.inner_rest.chunk_headers_root,
             BlockHeader::BlockHeaderV3(header) => &header.inner_rest.chunk_headers_root,
+            #[cfg(feature = "protocol_feature_bls_approvals")]
+            BlockHeader::BlockHeaderV4(header) => &header.inner_rest.chunk_headers_root,
         }
     }
 
@@ -688,6 +947,8 @@ impl BlockHeader {
             BlockHeader::BlockHeaderV1(header) => &header.inner_rest.chunk_tx_root,
             BlockHeader::BlockHeaderV2(header) => &header.inner_rest.chunk_tx_root,
             BlockHeader::BlockHeaderV3(header) => &header.inner_rest.chunk_tx_root,
+            #[cfg(feature = "protocol_feature_bls_approvals")]
+            BlockHeader::BlockHeaderV4(header) => &header.inner_rest.chunk_tx_root,
         }
     }
 
@@ -700,6 +961,10 @@ impl BlockHeader {
             BlockHeader::BlockHeaderV3(header) => {
                 header.inner_rest.chunk_mask.iter().map(|&x| u64::from(x)).sum::<u64>()
             }
+            #[cfg(feature = "protocol_feature_bls_approvals")]
+            BlockHeader::BlockHeaderV4(header) => {
+                header.inner_rest.chunk_mask.iter().map(|&x| u64::from(x)).sum::<u64>()
+            }
         }
     }
 
@@ -709,6 +974,8 @@ impl BlockHeader {
             BlockHeader::BlockHeaderV1(header) => &header.inner_rest.challenges_root,
             BlockHeader::BlockHeaderV2(header) => &header.inner_rest.challenges_root,
             BlockHeader::BlockHeaderV3(header) => &header.inner_rest.challenges_root,
+            #[cfg(feature = "protocol_feature_bls_approvals")]
+            BlockHeader::BlockHeaderV4(header) => &header.inner_rest.challenges_root,
         }
     }
 
@@ -718,6 +985,8 @@ impl BlockHeader {
             BlockHeader::BlockHeaderV1(header) => &header.inner_lite.outcome_root,
             BlockHeader::BlockHeaderV2(header) => &header.inner_lite.outcome_root,
             BlockHeader::BlockHeaderV3(header) => &header.inner_lite.outcome_root,
+            #[cfg(feature = "protocol_feature_bls_approvals")]
+            BlockHeader::BlockHeaderV4(header) => &header.inner_lite.outcome_root,
         }
     }
 
@@ -727,6 +996,8 @@ impl BlockHeader {
             BlockHeader::BlockHeaderV1(header) => header.inner_lite.timestamp,
             BlockHeader::BlockHeaderV2(header) => header.inner_lite.timestamp,
             BlockHeader::BlockHeaderV3(header) => header.inner_lite.timestamp,
+            #[cfg(feature = "protocol_feature_bls_approvals")]
+            BlockHeader::BlockHeaderV4(header) => header.inner_lite.timestamp,
         }
     }
 
@@ -742,6 +1013,10 @@ impl BlockHeader {
             BlockHeader::BlockHeaderV3(header) => {
                 ValidatorStakeIter::new(&header.inner_rest.validator_proposals)
             }
+            #[cfg(feature = "protocol_feature_bls_approvals")]
+            BlockHeader::BlockHeaderV4(header) => {
+                ValidatorStakeIter::new(&header.inner_rest.validator_proposals)
+            }
         }
     }
 
@@ -751,6 +1026,8 @@ impl BlockHeader {
             BlockHeader::BlockHeaderV1(header) => &header.inner_rest.chunk_mask,
             BlockHeader::BlockHeaderV2(header) => &header.inner_rest.chunk_mask,
             BlockHeader::BlockHeaderV3(header) => &header.inner_rest.chunk_mask,
+            #[cfg(feature = "protocol_feature_bls_approvals")]
+            BlockHeader::BlockHeaderV4(header) => &header.inner_rest.chunk_mask,
         }
     }
 
@@ -760,6 +1037,8 @@ impl BlockHeader {
             BlockHeader::BlockHeaderV1(_) => 0, // not applicable
             BlockHeader::BlockHeaderV2(_) => 0, // not applicable
             BlockHeader::BlockHeaderV3(header) => header.inner_rest.block_ordinal,
+            #[cfg(feature = "protocol_feature_bls_approvals")]
+            BlockHeader::BlockHeaderV4(header) => header.inner_rest.block_ordinal,
         }
     }
 
@@ -769,6 +1048,8 @@ impl BlockHeader {
             BlockHeader::BlockHeaderV1(header) => header.inner_rest.gas_price,
             BlockHeader::BlockHeaderV2(header) => header.inner_rest.gas_price,
             BlockHeader::BlockHeaderV3(header) => header.inner_rest.gas_price,
+            #[cfg(feature = "protocol_feature_bls_approvals")]
+            BlockHeader::BlockHeaderV4(header) => header.inner_rest.gas_price,
         }
     }
 
@@ -778,6 +1059,8 @@ impl BlockHeader {
             BlockHeader::BlockHeaderV1(header) => header.inner_rest.total_supply,
             BlockHeader::BlockHeaderV2(header) => header.inner_rest.total_supply,
             BlockHeader::BlockHeaderV3(header) => header.inner_rest.total_supply,
+            #[cfg(feature = "protocol_feature_bls_approvals")]
+            BlockHeader::BlockHeaderV4(header) => header.inner_rest.total_supply,
         }
     }
 
@@ -787,6 +1070,8 @@ impl BlockHeader {
             BlockHeader::BlockHeaderV1(header) => &header.inner_rest.random_value,
             BlockHeader::BlockHeaderV2(header) => &header.inner_rest.random_value,
             BlockHeader::BlockHeaderV3(header) => &header.inner_rest.random_value,
+            #[cfg(feature = "protocol_feature_bls_approvals")]
+            BlockHeader::BlockHeaderV4(header) => &header.inner_rest.random_value,
         }
     }
 
@@ -796,6 +1081,8 @@ impl BlockHeader {
             BlockHeader::BlockHeaderV1(header) => &header.inner_rest.last_final_block,
             BlockHeader::BlockHeaderV2(header) => &header.inner_rest.last_final_block,
             BlockHeader::BlockHeaderV3(header) => &header.inner_rest.last_final_block,
+            #[cfg(feature = "protocol_feature_bls_approvals")]
+            BlockHeader::BlockHeaderV4(header) => &header.inner_rest.last_final_block,
         }
     }
 
@@ -805,6 +1092,8 @@ impl BlockHeader {
             BlockHeader::BlockHeaderV1(header) => &header.inner_rest.last_ds_final_block,
             BlockHeader::BlockHeaderV2(header) => &header.inner_rest.last_ds_final_block,
             BlockHeader::BlockHeaderV3(header) => &header.inner_rest.last_ds_final_block,
+            #[cfg(feature = "protocol_feature_bls_approvals")]
+            BlockHeader::BlockHeaderV4(header) => &header.inner_rest.last_ds_final_block,
         }
     }
 
@@ -814,6 +1103,8 @@ impl BlockHeader {
             BlockHeader::BlockHeaderV1(header) => &header.inner_rest.challenges_result,
             BlockHeader::BlockHeaderV2(header) => &header.inner_rest.challenges_result,
             BlockHeader::BlockHeaderV3(header) => &header.inner_rest.challenges_result,
+            #[cfg(feature = "protocol_feature_bls_approvals")]
+            BlockHeader::BlockHeaderV4(header) => &header.inner_rest.challenges_result,
         }
     }
 
@@ -823,6 +1114,8 @@ impl BlockHeader {
             BlockHeader::BlockHeaderV1(header) => &header.inner_lite.next_bp_hash,
             BlockHeader::BlockHeaderV2(header) => &header.inner_lite.next_bp_hash,
             BlockHeader::BlockHeaderV3(header) => &header.inner_lite.next_bp_hash,
+            #[cfg(feature = "protocol_feature_bls_approvals")]
+            BlockHeader::BlockHeaderV4(header) => &header.inner_lite.next_bp_hash,
         }
     }
 
@@ -832,6 +1125,8 @@ impl BlockHeader {
             BlockHeader::BlockHeaderV1(header) => &header.inner_lite.block_merkle_root,
             BlockHeader::BlockHeaderV2(header) => &header.inner_lite.block_merkle_root,
             BlockHeader::BlockHeaderV3(header) => &header.inner_lite.block_merkle_root,
+            #[cfg(feature = "protocol_feature_bls_approvals")]
+            BlockHeader::BlockHeaderV4(header) => &header.inner_lite.block_merkle_root,
         }
     }
 
@@ -841,6 +1136,8 @@ impl BlockHeader {
             BlockHeader::BlockHeaderV1(_) => None,
             BlockHeader::BlockHeaderV2(_) => None,
             BlockHeader::BlockHeaderV3(header) => header.inner_rest.epoch_sync_data_hash,
+            #[cfg(feature = "protocol_feature_bls_approvals")]
+            BlockHeader::BlockHeaderV4(header) => header.inner_rest.epoch_sync_data_hash,
         }
     }
 
@@ -850,9 +1147,37 @@ impl BlockHeader {
             BlockHeader::BlockHeaderV1(header) => &header.inner_rest.approvals,
             BlockHeader::BlockHeaderV2(header) => &header.inner_rest.approvals,
             BlockHeader::BlockHeaderV3(header) => &header.inner_rest.approvals,
+            #[cfg(feature = "protocol_feature_bls_approvals")]
+            BlockHeader::BlockHeaderV4(header) => &header.inner_rest.approvals,
         }
     }
 
+    #[cfg(feature = "protocol_feature_bls_approvals")]
+    #[inline]
+    pub fn approvals_aggregate(&self) -> Option<&ApprovalsAggregate> {
+        match self {
+            BlockHeader::BlockHeaderV4(header) => header.inner_rest.approvals_aggregate.as_ref(),
+            _ => None,
+        }
+    }
+
+    /// Which of the block approvers approved this block, either with their own signature or as
+    /// part of the aggregate.
+    pub fn approvers_mask(&self) -> Vec<bool> {
+        #[allow(unused_mut)]
+        let mut mask = self.approvals().iter().map(Option::is_some).collect::<Vec<_>>();
+        #[cfg(feature = "protocol_feature_bls_approvals")]
+        if let Some(aggregate) = self.approvals_aggregate() {
+            if mask.len() < aggregate.signers.len() {
+                mask.resize(aggregate.signers.len(), false);
+            }
+            for (approved, signed) in mask.iter_mut().zip(aggregate.signers.iter()) {
+                *approved |= *signed;
+            }
+        }
+        mask
+    }
+
     /// Verifies that given public key produced the block.
     pub fn verify_block_producer(&self, public_key: &PublicKey) -> bool {
         self.signature().verify(self.hash().as_ref(), public_key)
@@ -863,7 +1188,7 @@ impl BlockHeader {
     }
 
     pub fn num_approvals(&self) -> u64 {
-        self.approvals().iter().filter(|x| x.is_some()).count() as u64
+        self.approvers_mask().into_iter().filter(|x| *x).count() as u64
     }
 
     pub fn verify_chunks_included(&self) -> bool {
@@ -874,6 +1199,8 @@ impl BlockHeader {
             }
             BlockHeader::BlockHeaderV2(_header) => true,
             BlockHeader::BlockHeaderV3(_header) => true,
+            #[cfg(feature = "protocol_feature_bls_approvals")]
+            BlockHeader::BlockHeaderV4(_header) => true,
         }
     }
 
@@ -883,6 +1210,8 @@ impl BlockHeader {
             BlockHeader::BlockHeaderV1(header) => header.inner_rest.latest_protocol_version,
             BlockHeader::BlockHeaderV2(header) => header.inner_rest.latest_protocol_version,
             BlockHeader::BlockHeaderV3(header) => header.inner_rest.latest_protocol_version,
+            #[cfg(feature = "protocol_feature_bls_approvals")]
+            BlockHeader::BlockHeaderV4(header) => header.inner_rest.latest_protocol_version,
         }
     }
 
@@ -897,6 +1226,10 @@ impl BlockHeader {
             BlockHeader::BlockHeaderV3(header) => {
                 header.inner_lite.try_to_vec().expect("Failed to serialize")
             }
+            #[cfg(feature = "protocol_feature_bls_approvals")]
+            BlockHeader::BlockHeaderV4(header) => {
+                header.inner_lite.try_to_vec().expect("Failed to serialize")
+            }
         }
     }
 
@@ -911,6 +1244,10 @@ impl BlockHeader {
             BlockHeader::BlockHeaderV3(header) => {
                 header.inner_rest.try_to_vec().expect("Failed to serialize")
             }
+            #[cfg(feature = "protocol_feature_bls_approvals")]
+            BlockHeader::BlockHeaderV4(header) => {
+                header.inner_rest.try_to_vec().expect("Failed to serialize")
+            }
         }
     }
 }
@@ -667,6 +667,16 @@ pub mod epoch_info {
             }
         }
 
+        /// Randomness block and chunk producers are sampled with.  None for
+        /// versions which assign heights to producers in turn.
+        #[inline]
+        pub fn rng_seed(&self) -> Option<&RngSeed> {
+            match self {
+                Self::V1(_) | Self::V2(_) => None,
+                Self::V3(v3) => Some(&v3.rng_seed),
+            }
+        }
+
         #[inline]
         pub fn stake_change(&self) -> &BTreeMap<AccountId, Balance> {
             match self {
@@ -862,7 +872,7 @@ pub mod epoch_info {
         }
 
         /// 32 bytes from epoch_seed, 8 bytes from height
-        fn block_produce_seed(height: BlockHeight, seed: &RngSeed) -> [u8; 32] {
+        pub fn block_produce_seed(height: BlockHeight, seed: &RngSeed) -> [u8; 32] {
             let mut buffer = [0u8; 40];
             buffer[0..32].copy_from_slice(seed);
             buffer[32..40].copy_from_slice(&height.to_le_bytes());
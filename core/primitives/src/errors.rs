@@ -1,5 +1,5 @@
 use crate::serialize::dec_format;
-use crate::types::{AccountId, Balance, EpochId, Gas, Nonce};
+use crate::types::{AccountId, Balance, BlockHeight, EpochId, Gas, Nonce, ProtocolVersion};
 use borsh::{BorshDeserialize, BorshSerialize};
 use near_crypto::PublicKey;
 use serde::{Deserialize, Serialize};
@@ -71,7 +71,7 @@ impl std::fmt::Display for RuntimeError {
 impl std::error::Error for RuntimeError {}
 
 /// Internal
-#[derive(Debug, Clone, PartialEq, Eq)]
+#[derive(BorshSerialize, BorshDeserialize, Debug, Clone, PartialEq, Eq)]
 pub enum StorageError {
     /// Key-value db internal failure
     StorageInternalError,
@@ -198,6 +198,18 @@ pub enum ActionsValidationError {
     UnsuitableStakingKey { public_key: PublicKey },
     /// The attached amount of gas in a FunctionCall action has to be a positive number.
     FunctionCallZeroAttachedGas,
+    /// A delegate action must be the only action of a transaction.
+    DelegateActionMustBeOnlyOne,
+    /// A delegate action can't carry other delegate actions.
+    DelegateActionCantContainNestedOne,
+    /// The signature of a delegate action doesn't match its sender's key.
+    DelegateActionInvalidSignature,
+    /// A delegate action must be relayed by a transaction to its sender.
+    DelegateActionSenderDoesNotMatchTxReceiver { sender_id: AccountId, receiver_id: AccountId },
+    /// A delegate action must be relayed by an account on the shard of its sender.
+    DelegateActionRelayerOnDifferentShard { relayer_id: AccountId, sender_id: AccountId },
+    /// The action isn't supported by the current protocol version yet.
+    UnsupportedProtocolFeature { protocol_feature: String, version: ProtocolVersion },
 }
 
 /// Describes the error for validating a receipt.
@@ -314,6 +326,33 @@ impl Display for ActionsValidationError {
                 f,
                 "The attached amount of gas in a FunctionCall action has to be a positive number",
             ),
+            ActionsValidationError::DelegateActionMustBeOnlyOne => write!(
+                f,
+                "The delegate action must be the only action in transaction",
+            ),
+            ActionsValidationError::DelegateActionCantContainNestedOne => write!(
+                f,
+                "The delegate action can't contain another delegate action",
+            ),
+            ActionsValidationError::DelegateActionInvalidSignature => write!(
+                f,
+                "The signature of the delegate action is invalid",
+            ),
+            ActionsValidationError::DelegateActionSenderDoesNotMatchTxReceiver { sender_id, receiver_id } => write!(
+                f,
+                "The sender {} of the delegate action doesn't match the receiver {} of the transaction",
+                sender_id, receiver_id,
+            ),
+            ActionsValidationError::DelegateActionRelayerOnDifferentShard { relayer_id, sender_id } => write!(
+                f,
+                "The relayer {} of the delegate action isn't on the shard of its sender {}",
+                relayer_id, sender_id,
+            ),
+            ActionsValidationError::UnsupportedProtocolFeature { protocol_feature, version } => write!(
+                f,
+                "Protocol feature {} is unsupported in version {}",
+                protocol_feature, version,
+            ),
         }
     }
 }
@@ -397,6 +436,15 @@ pub enum ActionErrorKind {
     OnlyImplicitAccountCreationAllowed { account_id: AccountId },
     /// Delete account whose state is large is temporarily banned.
     DeleteAccountWithLargeState { account_id: AccountId },
+    /// The delegate action is executed in a block higher than its `max_block_height`.
+    DelegateActionExpired { max_block_height: BlockHeight, block_height: BlockHeight },
+    /// The key the delegate action is signed with can't be used for it.
+    DelegateActionAccessKeyError(InvalidAccessKeyError),
+    /// The nonce of the delegate action isn't larger than the nonce of its access key.
+    DelegateActionInvalidNonce { delegate_nonce: Nonce, ak_nonce: Nonce },
+    /// The nonce of the delegate action is too large, which would let it use up
+    /// the nonces of its access key.
+    DelegateActionNonceTooLarge { delegate_nonce: Nonce, upper_bound: Nonce },
 }
 
 impl From<ActionErrorKind> for ActionError {
@@ -707,6 +755,10 @@ impl Display for ActionErrorKind {
             ActionErrorKind::InsufficientStake { account_id, stake, minimum_stake } => write!(f, "Account {} tries to stake {} but minimum required stake is {}", account_id, stake, minimum_stake),
             ActionErrorKind::OnlyImplicitAccountCreationAllowed { account_id } => write!(f, "CreateAccount action is called on hex-characters account of length 64 {}", account_id),
             ActionErrorKind::DeleteAccountWithLargeState { account_id } => write!(f, "The state of account {} is too large and therefore cannot be deleted", account_id),
+            ActionErrorKind::DelegateActionExpired { max_block_height, block_height } => write!(f, "The delegate action expired at height {} and is executed at height {}", max_block_height, block_height),
+            ActionErrorKind::DelegateActionAccessKeyError(access_key_error) => Display::fmt(&access_key_error, f),
+            ActionErrorKind::DelegateActionInvalidNonce { delegate_nonce, ak_nonce } => write!(f, "Delegate action nonce {} must be larger than nonce of the used access key {}", delegate_nonce, ak_nonce),
+            ActionErrorKind::DelegateActionNonceTooLarge { delegate_nonce, upper_bound } => write!(f, "Delegate action nonce {} must be smaller than the access key nonce upper bound {}", delegate_nonce, upper_bound),
         }
     }
 }
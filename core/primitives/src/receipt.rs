@@ -86,6 +86,40 @@ impl Receipt {
             }),
         }
     }
+
+    /// Returns kind and amount of the refund if this is a refund receipt
+    /// generated by the runtime, see [`Receipt::new_balance_refund`] and
+    /// [`Receipt::new_gas_refund`].
+    pub fn as_refund(&self) -> Option<(RefundKind, Balance)> {
+        if !self.predecessor_id.is_system() {
+            return None;
+        }
+        let action_receipt = match &self.receipt {
+            ReceiptEnum::Action(action_receipt) => action_receipt,
+            ReceiptEnum::Data(_) => return None,
+        };
+        let amount = match action_receipt.actions.as_slice() {
+            [Action::Transfer(TransferAction { deposit })] => *deposit,
+            _ => return None,
+        };
+        let kind = if action_receipt.signer_id.is_system() {
+            RefundKind::Deposit
+        } else {
+            RefundKind::Gas
+        };
+        Some((kind, amount))
+    }
+}
+
+/// Kind of a refund receipt.
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum RefundKind {
+    /// Refund of prepaid gas which wasn't used.  It also refunds the allowance
+    /// of the access key which signed the transaction.
+    Gas,
+    /// Refund of a deposit attached to a receipt which failed.
+    Deposit,
 }
 
 /// Receipt could be either ActionReceipt or DataReceipt
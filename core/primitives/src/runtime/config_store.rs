@@ -1,6 +1,7 @@
 use crate::runtime::config::RuntimeConfig;
 use crate::runtime::parameter_table::{ParameterTable, ParameterTableDiff};
 use crate::types::ProtocolVersion;
+use crate::views::RuntimeParametersDiffView;
 use std::collections::BTreeMap;
 use std::ops::Bound;
 use std::sync::Arc;
@@ -103,6 +104,42 @@ impl RuntimeConfigStore {
     }
 }
 
+/// Returns the parameter table compiled into the node for the protocol version.
+fn parameter_table(protocol_version: ProtocolVersion) -> ParameterTable {
+    let mut params: ParameterTable =
+        BASE_CONFIG.parse().expect("Failed parsing base parameter file.");
+    for (version, diff_bytes) in CONFIG_DIFFS {
+        if *version > protocol_version {
+            break;
+        }
+        let diff: ParameterTableDiff = diff_bytes.parse().unwrap_or_else(|err| {
+            panic!("Failed parsing runtime parameters diff for version {version}. Error: {err}")
+        });
+        params.apply_diff(diff).unwrap_or_else(|err| {
+            panic!("Failed applying diff to `RuntimeConfig` for version {version}. Error: {err}")
+        });
+    }
+    params
+}
+
+/// Returns the differences between runtime parameters of two protocol
+/// versions, as compiled into the node.  Overrides of the runtime config in
+/// genesis are not taken into account.
+pub fn runtime_parameters_diff(
+    from_protocol_version: ProtocolVersion,
+    to_protocol_version: ProtocolVersion,
+) -> RuntimeParametersDiffView {
+    let (added, removed, changed) =
+        parameter_table(from_protocol_version).diff(&parameter_table(to_protocol_version));
+    RuntimeParametersDiffView {
+        from_protocol_version,
+        to_protocol_version,
+        added,
+        removed,
+        changed,
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -233,6 +270,34 @@ mod tests {
         );
     }
 
+    #[test]
+    fn test_runtime_parameters_diff() {
+        let parameters = |changes: &[crate::views::RuntimeParameterChangeView]| -> Vec<String> {
+            changes.iter().map(|change| change.parameter.clone()).collect()
+        };
+        let diff = runtime_parameters_diff(52, 53);
+        assert_eq!(
+            parameters(&diff.added),
+            vec!["wasm_read_cached_trie_node", "wasmer2_stack_limit", "max_locals_per_contract"]
+        );
+        assert!(diff.removed.is_empty());
+        assert_eq!(
+            parameters(&diff.changed),
+            vec!["action_deploy_contract_per_byte_execution", "max_length_storage_key"]
+        );
+        let storage_key = &diff.changed[1];
+        assert_eq!(storage_key.old_value, Some(serde_json::json!(4_194_304)));
+        assert_eq!(storage_key.new_value, Some(serde_json::json!(2_048)));
+
+        let reverse = runtime_parameters_diff(53, 52);
+        assert!(reverse.added.is_empty());
+        assert_eq!(reverse.removed.len(), 3);
+        assert_eq!(reverse.changed[1].old_value, Some(serde_json::json!(2_048)));
+
+        let diff = runtime_parameters_diff(53, 53);
+        assert!(diff.added.is_empty() && diff.removed.is_empty() && diff.changed.is_empty());
+    }
+
     /// Use snapshot testing to check that the JSON representation of the
     /// configurations of each version is unchanged.
     /// If tests fail after an intended change, run `cargo insta review` accept
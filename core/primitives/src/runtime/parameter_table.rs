@@ -1,3 +1,4 @@
+use crate::views::RuntimeParameterChangeView;
 use near_primitives_core::parameter::{FeeParameter, Parameter};
 use serde_json::json;
 use std::collections::BTreeMap;
@@ -103,6 +104,37 @@ impl ParameterTable {
         Ok(())
     }
 
+    /// Compares parameters with the ones in a newer table.  Returns the added,
+    /// removed and changed parameters, each sorted by parameter.
+    pub(crate) fn diff(
+        &self,
+        new: &ParameterTable,
+    ) -> (
+        Vec<RuntimeParameterChangeView>,
+        Vec<RuntimeParameterChangeView>,
+        Vec<RuntimeParameterChangeView>,
+    ) {
+        let (mut added, mut removed, mut changed) = (vec![], vec![], vec![]);
+        let keys: std::collections::BTreeSet<_> =
+            self.parameters.keys().chain(new.parameters.keys()).collect();
+        for key in keys {
+            let old_value = self.get(*key).filter(|value| !value.is_null());
+            let new_value = new.get(*key).filter(|value| !value.is_null());
+            let list = match (old_value, new_value) {
+                (None, Some(_)) => &mut added,
+                (Some(_), None) => &mut removed,
+                (Some(old), Some(new)) if old != new => &mut changed,
+                _ => continue,
+            };
+            list.push(RuntimeParameterChangeView {
+                parameter: key.to_string(),
+                old_value: old_value.cloned(),
+                new_value: new_value.cloned(),
+            });
+        }
+        (added, removed, changed)
+    }
+
     fn transaction_costs_json(&self) -> serde_json::Value {
         json!( {
             "action_receipt_creation_config": self.fee_json(FeeParameter::ActionReceiptCreation),
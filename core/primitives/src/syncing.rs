@@ -147,10 +147,20 @@ pub struct ShardStateSyncResponseV2 {
     pub part: Option<(u64, Vec<u8>)>,
 }
 
+/// Response carrying a single sub-part of a state part.  Unlike V1 and V2,
+/// `header` and `part` are expected to be `None` whenever `sub_part` is set.
+#[derive(Debug, Clone, PartialEq, Eq, BorshSerialize, BorshDeserialize)]
+pub struct ShardStateSyncResponseV3 {
+    pub header: Option<ShardStateSyncResponseHeaderV2>,
+    pub part: Option<(u64, Vec<u8>)>,
+    pub sub_part: Option<StateSubPart>,
+}
+
 #[derive(Debug, Clone, PartialEq, Eq, BorshSerialize, BorshDeserialize)]
 pub enum ShardStateSyncResponse {
     V1(ShardStateSyncResponseV1),
     V2(ShardStateSyncResponseV2),
+    V3(ShardStateSyncResponseV3),
 }
 
 impl ShardStateSyncResponse {
@@ -158,6 +168,7 @@ impl ShardStateSyncResponse {
         match self {
             Self::V1(response) => response.part_id(),
             Self::V2(response) => response.part.as_ref().map(|(part_id, _)| *part_id),
+            Self::V3(response) => response.part.as_ref().map(|(part_id, _)| *part_id),
         }
     }
 
@@ -165,6 +176,7 @@ impl ShardStateSyncResponse {
         match self {
             Self::V1(response) => response.header.map(ShardStateSyncResponseHeader::V1),
             Self::V2(response) => response.header.map(ShardStateSyncResponseHeader::V2),
+            Self::V3(response) => response.header.map(ShardStateSyncResponseHeader::V2),
         }
     }
 
@@ -172,6 +184,7 @@ impl ShardStateSyncResponse {
         match self {
             Self::V1(response) => &response.part,
             Self::V2(response) => &response.part,
+            Self::V3(response) => &response.part,
         }
     }
 
@@ -179,6 +192,22 @@ impl ShardStateSyncResponse {
         match self {
             Self::V1(response) => response.part,
             Self::V2(response) => response.part,
+            Self::V3(response) => response.part,
+        }
+    }
+
+    pub fn sub_part(&self) -> Option<&StateSubPart> {
+        match self {
+            Self::V1(_) | Self::V2(_) => None,
+            Self::V3(response) => response.sub_part.as_ref(),
+        }
+    }
+
+    /// Takes the sub-part out of the response, leaving the rest untouched.
+    pub fn take_sub_part(&mut self) -> Option<StateSubPart> {
+        match self {
+            Self::V1(_) | Self::V2(_) => None,
+            Self::V3(response) => response.sub_part.take(),
         }
     }
 }
@@ -211,6 +240,55 @@ pub struct EpochSyncFinalizationResponse {
     pub next_epoch_info: EpochInfo,
 }
 
+/// Data committed to by `epoch_sync_data_hash` in the header of the first
+/// block of an epoch.
+#[derive(BorshSerialize, BorshDeserialize, Eq, PartialEq, Debug, Clone)]
+pub struct EpochSyncData {
+    pub prev_epoch_first_block_info: BlockInfo,
+    pub prev_epoch_prev_last_block_info: BlockInfo,
+    pub prev_epoch_last_block_info: BlockInfo,
+    pub prev_epoch_info: EpochInfo,
+    pub cur_epoch_info: EpochInfo,
+    pub next_epoch_info: EpochInfo,
+}
+
+impl EpochSyncData {
+    /// Hash which is stored in the block header as `epoch_sync_data_hash`.
+    pub fn hash(&self) -> CryptoHash {
+        CryptoHash::hash_borsh(self)
+    }
+}
+
+/// Response to a request for epoch sync data of the block with hash
+/// `block_hash`.  `data` is `None` if the peer doesn't have it or the block
+/// doesn't start an epoch.
+#[derive(BorshSerialize, BorshDeserialize, Eq, PartialEq, Debug, Clone)]
+pub struct EpochSyncDataResponse {
+    pub block_hash: CryptoHash,
+    pub data: Option<Box<EpochSyncData>>,
+}
+
+/// Proof of an epoch: the header of its first block together with the epoch
+/// sync data committed to by the header.  The data tells who the block
+/// producers of the epoch are, so that the signature of the header can be
+/// checked, and who the block producers of the next epoch are, so that the
+/// proof of the next epoch can be checked in turn.
+#[derive(BorshSerialize, BorshDeserialize, Eq, PartialEq, Debug, Clone)]
+pub struct EpochSyncProof {
+    pub header: BlockHeader,
+    pub data: EpochSyncData,
+}
+
+/// Response to a request for the proofs of the epochs following the epoch
+/// started by the block with hash `block_hash`, in order.  `proofs` is `None`
+/// if that block isn't the first block of an epoch on the canonical chain of
+/// the peer, and is empty if the peer doesn't know of any later final epoch.
+#[derive(BorshSerialize, BorshDeserialize, Eq, PartialEq, Debug, Clone)]
+pub struct EpochSyncProofsResponse {
+    pub block_hash: CryptoHash,
+    pub proofs: Option<Vec<EpochSyncProof>>,
+}
+
 #[derive(BorshSerialize, BorshDeserialize, Eq, PartialEq, Debug, Clone)]
 pub enum EpochSyncResponse {
     UpToDate,
@@ -226,3 +304,120 @@ pub fn get_num_state_parts(memory_usage: u64) -> u64 {
     // TODO #1708
     memory_usage / STATE_PART_MEMORY_LIMIT.as_u64() + 3
 }
+
+/// Size of the sub-parts requested from peers which didn't advertise the
+/// sizes they serve.
+pub const STATE_SUB_PART_SIZE: bytesize::ByteSize = bytesize::ByteSize(256 * bytesize::KIB);
+/// Smallest size of a sub-part a node serves.
+pub const MIN_STATE_SUB_PART_SIZE: bytesize::ByteSize = bytesize::ByteSize(64 * bytesize::KIB);
+/// Largest size of a sub-part a node serves.
+pub const MAX_STATE_SUB_PART_SIZE: bytesize::ByteSize = bytesize::ByteSize(4 * bytesize::MIB);
+
+/// Whether sub-parts of `sub_part_size` bytes may be requested.
+pub fn is_valid_state_sub_part_size(sub_part_size: u64) -> bool {
+    (MIN_STATE_SUB_PART_SIZE.as_u64()..=MAX_STATE_SUB_PART_SIZE.as_u64()).contains(&sub_part_size)
+}
+
+/// Number of sub-parts of `sub_part_size` bytes a state part of `part_size`
+/// bytes is split into.  Even an empty part is sent as a single (empty)
+/// sub-part.
+pub fn get_num_state_sub_parts(part_size: u64, sub_part_size: u64) -> u64 {
+    std::cmp::max(1, (part_size + sub_part_size - 1) / sub_part_size)
+}
+
+/// A contiguous range of a state part together with a checksum of its data.
+///
+/// Large state parts are transferred as a sequence of sub-parts so that a lost
+/// or corrupted message only requires the affected range to be requested
+/// again rather than the whole part.  The requester picks the size of the
+/// sub-parts, so the sub-part with a given id covers the range starting at
+/// `sub_part_id * sub_part_size` of the part.
+#[derive(Debug, Clone, PartialEq, Eq, BorshSerialize, BorshDeserialize)]
+pub struct StateSubPart {
+    pub part_id: u64,
+    pub sub_part_id: u64,
+    pub sub_part_size: u64,
+    pub num_sub_parts: u64,
+    /// Hash of `data`.
+    pub checksum: CryptoHash,
+    pub data: Vec<u8>,
+}
+
+impl StateSubPart {
+    /// Cuts sub-part `sub_part_id` of `sub_part_size` bytes out of state part
+    /// `part`.  Returns `None` if the size isn't valid or `sub_part_id` is out
+    /// of range.
+    pub fn new(part_id: u64, sub_part_id: u64, sub_part_size: u64, part: &[u8]) -> Option<Self> {
+        if !is_valid_state_sub_part_size(sub_part_size) {
+            return None;
+        }
+        let num_sub_parts = get_num_state_sub_parts(part.len() as u64, sub_part_size);
+        if sub_part_id >= num_sub_parts {
+            return None;
+        }
+        let size = sub_part_size as usize;
+        let start = sub_part_id as usize * size;
+        let end = std::cmp::min(start + size, part.len());
+        let data = part[start..end].to_vec();
+        let checksum = CryptoHash::hash_bytes(&data);
+        Some(Self { part_id, sub_part_id, sub_part_size, num_sub_parts, checksum, data })
+    }
+
+    /// Checks that the sub-part is internally consistent, i.e. that its index
+    /// is in range, that only the last sub-part is shorter than the sub-part
+    /// size and that the data matches the checksum.
+    pub fn is_valid(&self) -> bool {
+        let len = self.data.len() as u64;
+        let is_last = self.sub_part_id + 1 == self.num_sub_parts;
+        is_valid_state_sub_part_size(self.sub_part_size)
+            && self.sub_part_id < self.num_sub_parts
+            && (len == self.sub_part_size || is_last && len < self.sub_part_size)
+            && CryptoHash::hash_bytes(&self.data) == self.checksum
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_state_sub_parts_roundtrip() {
+        let size = STATE_SUB_PART_SIZE.as_u64();
+        let part: Vec<u8> = (0..size * 2 + 17).map(|i| (i % 251) as u8).collect();
+        for (sub_part_size, expected_num_sub_parts) in [
+            (size, 3),
+            (MIN_STATE_SUB_PART_SIZE.as_u64(), 9),
+            (MAX_STATE_SUB_PART_SIZE.as_u64(), 1),
+        ] {
+            let num_sub_parts = get_num_state_sub_parts(part.len() as u64, sub_part_size);
+            assert_eq!(num_sub_parts, expected_num_sub_parts);
+
+            let mut assembled = vec![];
+            for sub_part_id in 0..num_sub_parts {
+                let sub_part = StateSubPart::new(7, sub_part_id, sub_part_size, &part).unwrap();
+                assert!(sub_part.is_valid());
+                assert_eq!(sub_part.num_sub_parts, num_sub_parts);
+                assembled.extend_from_slice(&sub_part.data);
+            }
+            assert_eq!(assembled, part);
+            assert!(StateSubPart::new(7, num_sub_parts, sub_part_size, &part).is_none());
+        }
+        assert!(StateSubPart::new(7, 0, MIN_STATE_SUB_PART_SIZE.as_u64() - 1, &part).is_none());
+        assert!(StateSubPart::new(7, 0, MAX_STATE_SUB_PART_SIZE.as_u64() + 1, &part).is_none());
+    }
+
+    #[test]
+    fn test_state_sub_part_corrupted() {
+        let mut sub_part =
+            StateSubPart::new(0, 0, STATE_SUB_PART_SIZE.as_u64(), &[1, 2, 3]).unwrap();
+        assert_eq!(sub_part.num_sub_parts, 1);
+        sub_part.data[0] = 42;
+        assert!(!sub_part.is_valid());
+
+        // Only the last sub-part may be shorter than the sub-part size.
+        let mut sub_part =
+            StateSubPart::new(0, 0, STATE_SUB_PART_SIZE.as_u64(), &[1, 2, 3]).unwrap();
+        sub_part.num_sub_parts = 2;
+        assert!(!sub_part.is_valid());
+    }
+}
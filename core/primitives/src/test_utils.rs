@@ -260,6 +260,8 @@ impl BlockHeader {
                 panic!("old header should not appear in tests")
             }
             BlockHeader::BlockHeaderV3(header) => Arc::make_mut(header),
+            #[cfg(feature = "protocol_feature_bls_approvals")]
+            BlockHeader::BlockHeaderV4(_) => panic!("BLS approvals header is not supported"),
         }
     }
 
@@ -277,6 +279,11 @@ impl BlockHeader {
                 let header = Arc::make_mut(header);
                 header.inner_rest.latest_protocol_version = latest_protocol_version;
             }
+            #[cfg(feature = "protocol_feature_bls_approvals")]
+            BlockHeader::BlockHeaderV4(header) => {
+                let header = Arc::make_mut(header);
+                header.inner_rest.latest_protocol_version = latest_protocol_version;
+            }
         }
     }
 
@@ -302,6 +309,12 @@ impl BlockHeader {
                 header.hash = hash;
                 header.signature = signature;
             }
+            #[cfg(feature = "protocol_feature_bls_approvals")]
+            BlockHeader::BlockHeaderV4(header) => {
+                let header = Arc::make_mut(header);
+                header.hash = hash;
+                header.signature = signature;
+            }
         }
     }
 }
@@ -14,7 +14,7 @@ use crate::errors::TxExecutionError;
 use crate::hash::{hash, CryptoHash};
 use crate::merkle::MerklePath;
 use crate::serialize::{base64_format, dec_format};
-use crate::types::{AccountId, Balance, Gas, Nonce};
+use crate::types::{AccountId, Balance, BlockHeight, Gas, Nonce};
 
 pub type LogEntry = String;
 
@@ -68,19 +68,40 @@ pub enum Action {
     AddKey(AddKeyAction),
     DeleteKey(DeleteKeyAction),
     DeleteAccount(DeleteAccountAction),
+    /// Actions another account authorised to be submitted on its behalf, see
+    /// [`DelegateAction`].  Supported from [`ProtocolFeature::DelegateAction`].
+    ///
+    /// [`ProtocolFeature::DelegateAction`]: crate::version::ProtocolFeature
+    Delegate(SignedDelegateAction),
 }
 
 impl Action {
+    /// Gas attached to the action.  The relayer of a delegate action attaches
+    /// the gas of the actions it carries.  Saturates rather than overflows, so
+    /// that the sum is rejected by the limits on gas.
     pub fn get_prepaid_gas(&self) -> Gas {
         match self {
             Action::FunctionCall(a) => a.gas,
+            Action::Delegate(a) => a
+                .delegate_action
+                .actions
+                .iter()
+                .fold(0, |gas: Gas, action| gas.saturating_add(action.get_prepaid_gas())),
             _ => 0,
         }
     }
+    /// Tokens attached to the action.  The relayer of a delegate action
+    /// attaches the deposits of the actions it carries.  Saturates rather than
+    /// overflows, so that the sum exceeds any balance.
     pub fn get_deposit_balance(&self) -> Balance {
         match self {
             Action::FunctionCall(a) => a.deposit,
             Action::Transfer(a) => a.deposit,
+            Action::Delegate(a) => {
+                a.delegate_action.actions.iter().fold(0, |deposit: Balance, action| {
+                    deposit.saturating_add(action.get_deposit_balance())
+                })
+            }
             _ => 0,
         }
     }
@@ -210,6 +231,63 @@ impl From<DeleteAccountAction> for Action {
     }
 }
 
+/// Prefix of the message signed in a [`SignedDelegateAction`].  It's larger
+/// than the length of any account id, which a serialized transaction starts
+/// with, so that a signature of a delegate action can't be passed off as the
+/// signature of a transaction.
+const DELEGATE_ACTION_PREFIX: u32 = (1 << 30) + 366;
+
+/// Actions which `sender_id` signs so that another account, the relayer, can
+/// submit them on its behalf and pay for them.  The relayer wraps the signed
+/// actions in a transaction to `sender_id`; once the resulting receipt arrives
+/// at the shard of `sender_id`, the actions are checked against the access key
+/// of the sender and sent on to `receiver_id` as if `sender_id` sent them.
+#[derive(BorshSerialize, BorshDeserialize, Serialize, Deserialize, PartialEq, Eq, Clone, Debug)]
+pub struct DelegateAction {
+    /// Account which signs the actions and on whose behalf they are sent.
+    pub sender_id: AccountId,
+    /// Receiver of the actions.
+    pub receiver_id: AccountId,
+    /// Actions to send, which can't include delegate actions.
+    pub actions: Vec<Action>,
+    /// Nonce of the access key the actions are signed with.  Must be larger
+    /// than the current nonce of the key, which is then set to it.
+    pub nonce: Nonce,
+    /// Largest height of a block in which the actions are executed.
+    pub max_block_height: BlockHeight,
+    /// Key of the sender the actions are signed with.
+    pub public_key: PublicKey,
+}
+
+impl DelegateAction {
+    /// Hash of the message the sender signs.
+    pub fn get_hash(&self) -> CryptoHash {
+        let mut bytes = DELEGATE_ACTION_PREFIX.try_to_vec().expect("Failed to serialize");
+        self.serialize(&mut bytes).expect("Failed to serialize");
+        hash(&bytes)
+    }
+}
+
+#[derive(BorshSerialize, BorshDeserialize, Serialize, Deserialize, PartialEq, Eq, Clone, Debug)]
+pub struct SignedDelegateAction {
+    pub delegate_action: DelegateAction,
+    pub signature: Signature,
+}
+
+impl SignedDelegateAction {
+    /// Whether the signature is the one of the sender's key.
+    pub fn verify(&self) -> bool {
+        let delegate_action = &self.delegate_action;
+        self.signature.verify(delegate_action.get_hash().as_ref(), &delegate_action.public_key)
+    }
+}
+
+impl From<SignedDelegateAction> for Action {
+    fn from(signed_delegate_action: SignedDelegateAction) -> Self {
+        Self::Delegate(signed_delegate_action)
+    }
+}
+
 #[derive(BorshSerialize, BorshDeserialize, Serialize, Deserialize, Eq, Debug, Clone)]
 #[borsh_init(init)]
 pub struct SignedTransaction {
@@ -242,6 +320,25 @@ impl SignedTransaction {
     pub fn get_size(&self) -> u64 {
         self.size
     }
+
+    /// The delegate action the transaction relays, if any.  Only valid
+    /// transactions are expected to relay at most one.
+    pub fn delegate_action(&self) -> Option<&SignedDelegateAction> {
+        self.transaction.actions.iter().find_map(|action| match action {
+            Action::Delegate(signed_delegate_action) => Some(signed_delegate_action),
+            _ => None,
+        })
+    }
+
+    /// Account whose shard the transaction is routed to.  Relayed
+    /// transactions go to the shard of the delegate action's sender, where its
+    /// access key is checked, rather than to the shard of the relayer.
+    pub fn routing_account_id(&self) -> &AccountId {
+        match self.delegate_action() {
+            Some(signed_delegate_action) => &signed_delegate_action.delegate_action.sender_id,
+            None => &self.transaction.signer_id,
+        }
+    }
 }
 
 impl Hash for SignedTransaction {
@@ -526,6 +623,27 @@ mod tests {
         );
     }
 
+    #[test]
+    fn test_verify_delegate_action() {
+        let signer = InMemorySigner::from_seed("alice".parse().unwrap(), KeyType::ED25519, "alice");
+        let delegate_action = DelegateAction {
+            sender_id: "alice".parse().unwrap(),
+            receiver_id: "bob".parse().unwrap(),
+            actions: vec![Action::Transfer(TransferAction { deposit: 1 })],
+            nonce: 1,
+            max_block_height: 100,
+            public_key: signer.public_key(),
+        };
+        let signature = signer.sign(delegate_action.get_hash().as_ref());
+        let mut signed = SignedDelegateAction { delegate_action, signature };
+        assert!(signed.verify());
+        let bytes = Action::Delegate(signed.clone()).try_to_vec().unwrap();
+        assert_eq!(Action::try_from_slice(&bytes).unwrap(), Action::Delegate(signed.clone()));
+
+        signed.delegate_action.nonce = 2;
+        assert!(!signed.verify());
+    }
+
     #[test]
     fn test_outcome_to_hashes() {
         let outcome = ExecutionOutcome {
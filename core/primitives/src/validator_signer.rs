@@ -4,6 +4,8 @@ use std::sync::Arc;
 use near_crypto::{InMemorySigner, KeyType, PublicKey, Signature, Signer};
 
 use crate::block::{Approval, ApprovalInner, BlockHeader};
+#[cfg(feature = "protocol_feature_bls_approvals")]
+use crate::block_header::{ApprovalBlsSignature, BlsKeyRegistration};
 use crate::challenge::ChallengeBody;
 use crate::hash::CryptoHash;
 use crate::network::{AnnounceAccount, PeerId};
@@ -36,6 +38,14 @@ pub trait ValidatorSigner: Sync + Send {
     /// Signs approval of given parent hash and reference hash.
     fn sign_approval(&self, inner: &ApprovalInner, target_height: BlockHeight) -> Signature;
 
+    /// Signs approval with the BLS key of the validator, if it has one.
+    #[cfg(feature = "protocol_feature_bls_approvals")]
+    fn sign_approval_bls(
+        &self,
+        inner: &ApprovalInner,
+        target_height: BlockHeight,
+    ) -> Option<ApprovalBlsSignature>;
+
     /// Signs challenge body.
     fn sign_challenge(&self, challenge_body: &ChallengeBody) -> (CryptoHash, Signature);
 
@@ -108,6 +118,15 @@ impl ValidatorSigner for EmptyValidatorSigner {
         Signature::default()
     }
 
+    #[cfg(feature = "protocol_feature_bls_approvals")]
+    fn sign_approval_bls(
+        &self,
+        _inner: &ApprovalInner,
+        _target_height: BlockHeight,
+    ) -> Option<ApprovalBlsSignature> {
+        None
+    }
+
     fn sign_challenge(&self, challenge_body: &ChallengeBody) -> (CryptoHash, Signature) {
         (CryptoHash::hash_borsh(challenge_body), Signature::default())
     }
@@ -142,17 +161,43 @@ impl ValidatorSigner for EmptyValidatorSigner {
 pub struct InMemoryValidatorSigner {
     account_id: AccountId,
     signer: Arc<dyn Signer>,
+    /// BLS key derived from the validator key, so that no separate key file is needed.
+    #[cfg(feature = "protocol_feature_bls_approvals")]
+    bls_secret_key: near_crypto::bls::SecretKey,
+    #[cfg(feature = "protocol_feature_bls_approvals")]
+    bls_registration: BlsKeyRegistration,
 }
 
 impl InMemoryValidatorSigner {
+    fn new(signer: InMemorySigner) -> Self {
+        #[cfg(feature = "protocol_feature_bls_approvals")]
+        let bls_secret_key = near_crypto::bls::SecretKey::derive_from(&signer.secret_key);
+        #[cfg(feature = "protocol_feature_bls_approvals")]
+        let bls_registration = BlsKeyRegistration {
+            account_id: signer.account_id.clone(),
+            public_key: *bls_secret_key.public_key(),
+            proof_of_possession: bls_secret_key.proof_of_possession(),
+            signature: signer.sign(&BlsKeyRegistration::get_data_for_sig(
+                &signer.account_id,
+                bls_secret_key.public_key(),
+            )),
+        };
+        Self {
+            account_id: signer.account_id.clone(),
+            signer: Arc::new(signer),
+            #[cfg(feature = "protocol_feature_bls_approvals")]
+            bls_secret_key,
+            #[cfg(feature = "protocol_feature_bls_approvals")]
+            bls_registration,
+        }
+    }
+
     pub fn from_random(account_id: AccountId, key_type: KeyType) -> Self {
-        let signer = Arc::new(InMemorySigner::from_random(account_id.clone(), key_type));
-        Self { account_id, signer }
+        Self::new(InMemorySigner::from_random(account_id, key_type))
     }
 
     pub fn from_seed(account_id: AccountId, key_type: KeyType, seed: &str) -> Self {
-        let signer = Arc::new(InMemorySigner::from_seed(account_id.clone(), key_type, seed));
-        Self { account_id, signer }
+        Self::new(InMemorySigner::from_seed(account_id, key_type, seed))
     }
 
     pub fn public_key(&self) -> PublicKey {
@@ -161,7 +206,7 @@ impl InMemoryValidatorSigner {
 
     pub fn from_file(path: &Path) -> std::io::Result<Self> {
         let signer = InMemorySigner::from_file(path)?;
-        Ok(Self { account_id: signer.account_id.clone(), signer: Arc::new(signer) })
+        Ok(Self::new(signer))
     }
 }
 
@@ -199,6 +244,18 @@ impl ValidatorSigner for InMemoryValidatorSigner {
         self.signer.sign(&Approval::get_data_for_sig(inner, target_height))
     }
 
+    #[cfg(feature = "protocol_feature_bls_approvals")]
+    fn sign_approval_bls(
+        &self,
+        inner: &ApprovalInner,
+        target_height: BlockHeight,
+    ) -> Option<ApprovalBlsSignature> {
+        Some(ApprovalBlsSignature {
+            signature: self.bls_secret_key.sign(&Approval::get_data_for_sig(inner, target_height)),
+            registration: self.bls_registration.clone(),
+        })
+    }
+
     fn sign_challenge(&self, challenge_body: &ChallengeBody) -> (CryptoHash, Signature) {
         let hash = CryptoHash::hash_borsh(challenge_body);
         let signature = self.signer.sign(hash.as_ref());
@@ -151,6 +151,15 @@ pub enum ProtocolFeature {
     RejectBlocksWithOutdatedProtocolVersions,
     #[cfg(feature = "shardnet")]
     ShardnetShardLayoutUpgrade,
+    /// Approvals carry BLS signatures which block producers aggregate into
+    /// a single signature in the block header.
+    #[cfg(feature = "protocol_feature_bls_approvals")]
+    BlsApprovals,
+    /// Transactions can relay actions signed by another account, which are
+    /// then executed on behalf of that account while the relayer pays for
+    /// them.
+    #[cfg(feature = "protocol_feature_delegate_action")]
+    DelegateAction,
 }
 
 /// Both, outgoing and incoming tcp connections to peers, will be rejected if `peer's`
@@ -166,7 +175,7 @@ const STABLE_PROTOCOL_VERSION: ProtocolVersion = 57;
 /// Largest protocol version supported by the current binary.
 pub const PROTOCOL_VERSION: ProtocolVersion = if cfg!(feature = "nightly_protocol") {
     // On nightly, pick big enough version to support all features.
-    132
+    134
 } else if cfg!(feature = "shardnet") {
     102
 } else {
@@ -254,6 +263,10 @@ impl ProtocolFeature {
             }
             #[cfg(feature = "shardnet")]
             ProtocolFeature::ShardnetShardLayoutUpgrade => 102,
+            #[cfg(feature = "protocol_feature_bls_approvals")]
+            ProtocolFeature::BlsApprovals => 133,
+            #[cfg(feature = "protocol_feature_delegate_action")]
+            ProtocolFeature::DelegateAction => 134,
         }
     }
 }
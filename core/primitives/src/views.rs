@@ -27,29 +27,29 @@ use crate::hash::{hash, CryptoHash};
 use crate::merkle::{combine_hash, MerklePath};
 use crate::network::PeerId;
 use crate::profile::Cost;
-use crate::receipt::{ActionReceipt, DataReceipt, DataReceiver, Receipt, ReceiptEnum};
+use crate::receipt::{ActionReceipt, DataReceipt, DataReceiver, Receipt, ReceiptEnum, RefundKind};
 use crate::serialize::{base64_format, dec_format, option_base64_format};
 use crate::sharding::{
-    ChunkHash, ShardChunk, ShardChunkHeader, ShardChunkHeaderInner, ShardChunkHeaderInnerV2,
-    ShardChunkHeaderV3,
+    ChunkHash, ReceiptProof, ShardChunk, ShardChunkHeader, ShardChunkHeaderInner,
+    ShardChunkHeaderInnerV2, ShardChunkHeaderV3,
 };
 use crate::transaction::{
-    Action, AddKeyAction, CreateAccountAction, DeleteAccountAction, DeleteKeyAction,
-    DeployContractAction, ExecutionMetadata, ExecutionOutcome, ExecutionOutcomeWithIdAndProof,
-    ExecutionStatus, FunctionCallAction, PartialExecutionOutcome, PartialExecutionStatus,
-    SignedTransaction, StakeAction, TransferAction,
+    Action, AddKeyAction, CreateAccountAction, DelegateAction, DeleteAccountAction,
+    DeleteKeyAction, DeployContractAction, ExecutionMetadata, ExecutionOutcome,
+    ExecutionOutcomeWithIdAndProof, ExecutionStatus, FunctionCallAction, PartialExecutionOutcome,
+    PartialExecutionStatus, SignedDelegateAction, SignedTransaction, StakeAction, TransferAction,
 };
 use crate::types::{
-    AccountId, AccountWithPublicKey, Balance, BlockHeight, CompiledContractCache, EpochHeight,
-    EpochId, FunctionArgs, Gas, Nonce, NumBlocks, ShardId, StateChangeCause, StateChangeKind,
-    StateChangeValue, StateChangeWithCause, StateChangesRequest, StateRoot, StorageUsage, StoreKey,
-    StoreValue, ValidatorKickoutReason,
+    AccountId, AccountWithPublicKey, Balance, BlockHeight, BlockHeightDelta, CompiledContractCache,
+    EpochHeight, EpochId, Finality, FunctionArgs, Gas, Nonce, NumBlocks, ShardId, StateChangeCause,
+    StateChangeKind, StateChangeValue, StateChangeWithCause, StateChangesRequest, StateRoot,
+    StorageUsage, StoreKey, StoreValue, ValidatorKickoutReason,
 };
 use crate::version::{ProtocolVersion, Version};
 use validator_stake_view::ValidatorStakeView;
 
 /// A view of the account
-#[derive(Serialize, Deserialize, Debug, Eq, PartialEq, Clone)]
+#[derive(BorshSerialize, BorshDeserialize, Serialize, Deserialize, Debug, Eq, PartialEq, Clone)]
 pub struct AccountView {
     #[serde(with = "dec_format")]
     pub amount: Balance,
@@ -63,7 +63,7 @@ pub struct AccountView {
 }
 
 /// A view of the contract code.
-#[derive(Serialize, Deserialize, PartialEq, Eq, Debug, Clone)]
+#[derive(BorshSerialize, BorshDeserialize, Serialize, Deserialize, PartialEq, Eq, Debug, Clone)]
 pub struct ContractCodeView {
     #[serde(rename = "code_base64", with = "base64_format")]
     pub code: Vec<u8>,
@@ -193,7 +193,7 @@ impl From<AccessKeyView> for AccessKey {
 }
 
 /// Item of the state, key and value are serialized in base64 and proof for inclusion of given state item.
-#[derive(Serialize, Deserialize, Debug, PartialEq, Eq, Clone)]
+#[derive(BorshSerialize, BorshDeserialize, Serialize, Deserialize, Debug, PartialEq, Eq, Clone)]
 pub struct StateItem {
     #[serde(with = "base64_format")]
     pub key: Vec<u8>,
@@ -203,10 +203,11 @@ pub struct StateItem {
     // TODO(mina86): This was deprecated in 1.30.  Get rid of the field
     // altogether at 1.33 or something.
     #[serde(default)]
+    #[borsh_skip]
     pub proof: Vec<()>,
 }
 
-#[derive(Serialize, Deserialize, Debug, PartialEq, Eq, Clone)]
+#[derive(BorshSerialize, BorshDeserialize, Serialize, Deserialize, Debug, PartialEq, Eq, Clone)]
 pub struct ViewStateResult {
     pub values: Vec<StateItem>,
     // TODO(mina86): Empty proof (i.e. sending proof when include_proof is not
@@ -215,7 +216,9 @@ pub struct ViewStateResult {
     pub proof: Vec<Arc<[u8]>>,
 }
 
-#[derive(Serialize, Deserialize, Debug, PartialEq, Eq, Clone, Default)]
+#[derive(
+    BorshSerialize, BorshDeserialize, Serialize, Deserialize, Debug, PartialEq, Eq, Clone, Default,
+)]
 pub struct CallResult {
     pub result: Vec<u8>,
     pub logs: Vec<String>,
@@ -227,13 +230,13 @@ pub struct QueryError {
     pub logs: Vec<String>,
 }
 
-#[derive(Serialize, Deserialize, Debug, PartialEq, Eq, Clone)]
+#[derive(BorshSerialize, BorshDeserialize, Serialize, Deserialize, Debug, PartialEq, Eq, Clone)]
 pub struct AccessKeyInfoView {
     pub public_key: PublicKey,
     pub access_key: AccessKeyView,
 }
 
-#[derive(Serialize, Deserialize, Debug, PartialEq, Eq, Clone)]
+#[derive(BorshSerialize, BorshDeserialize, Serialize, Deserialize, Debug, PartialEq, Eq, Clone)]
 pub struct AccessKeyList {
     pub keys: Vec<AccessKeyInfoView>,
 }
@@ -256,7 +259,7 @@ pub struct KnownPeerStateView {
 }
 
 #[cfg_attr(feature = "deepsize_feature", derive(deepsize::DeepSizeOf))]
-#[derive(Debug, PartialEq, Eq, Clone)]
+#[derive(BorshSerialize, BorshDeserialize, Debug, PartialEq, Eq, Clone)]
 pub enum QueryResponseKind {
     ViewAccount(AccountView),
     ViewCode(ContractCodeView),
@@ -264,9 +267,10 @@ pub enum QueryResponseKind {
     CallResult(CallResult),
     AccessKey(AccessKeyView),
     AccessKeyList(AccessKeyList),
+    ReceiptProof(ReceiptInclusionProofView),
 }
 
-#[derive(Serialize, Deserialize, Debug, PartialEq, Eq, Clone)]
+#[derive(BorshSerialize, BorshDeserialize, Serialize, Deserialize, Debug, PartialEq, Eq, Clone)]
 #[serde(tag = "request_type", rename_all = "snake_case")]
 pub enum QueryRequest {
     ViewAccount {
@@ -295,13 +299,16 @@ pub enum QueryRequest {
         #[serde(rename = "args_base64", with = "base64_format")]
         args: FunctionArgs,
     },
+    ViewReceiptProof {
+        receipt_id: CryptoHash,
+    },
 }
 
 fn is_false(v: &bool) -> bool {
     !*v
 }
 
-#[derive(Debug, PartialEq, Eq, Clone)]
+#[derive(BorshSerialize, BorshDeserialize, Debug, PartialEq, Eq, Clone)]
 pub struct QueryResponse {
     pub kind: QueryResponseKind,
     pub block_height: BlockHeight,
@@ -343,6 +350,25 @@ pub struct PeerInfoView {
     pub last_time_received_message_millis: u64,
     pub connection_established_time_millis: u64,
     pub is_outbound_peer: bool,
+    /// Estimated round trip time to the peer, measured with liveness pings.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub rtt_millis: Option<u64>,
+    /// Score of the peer, which gets banned once the score drops to the
+    /// configured threshold.
+    #[serde(default)]
+    pub score: i32,
+    /// Bytes exchanged with the peer since the connection was established,
+    /// by message type.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub bandwidth_by_type: Vec<MessageTypeBandwidthView>,
+}
+
+/// Bytes of messages of a type exchanged with peers.
+#[derive(Serialize, Deserialize, Debug, PartialEq, Eq, Clone)]
+pub struct MessageTypeBandwidthView {
+    pub message_type: String,
+    pub sent_bytes: u64,
+    pub received_bytes: u64,
 }
 
 /// Information about a Producer: its account name, peer_id and a list of connected peers that
@@ -360,6 +386,10 @@ pub struct NetworkInfoView {
     pub num_connected_peers: usize,
     pub connected_peers: Vec<PeerInfoView>,
     pub known_producers: Vec<KnownProducerView>,
+    /// Bytes exchanged with all peers since the node started, by message
+    /// type, the most demanding first.
+    #[serde(default)]
+    pub bandwidth_by_type: Vec<MessageTypeBandwidthView>,
 }
 
 #[derive(Serialize, Deserialize, Debug, PartialEq, Eq)]
@@ -386,15 +416,101 @@ pub enum SyncStatusView {
     BodySync { start_height: BlockHeight, current_height: BlockHeight, highest_height: BlockHeight },
 }
 
+/// Header sync statistics of a peer.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq)]
+pub struct HeaderSyncPeerStatsView {
+    pub peer_id: PeerId,
+    /// Number of times headers were requested from the peer.
+    pub num_requests: u64,
+    /// Number of responses received from the peer.
+    pub num_responses: u64,
+    /// Number of headers received from the peer.
+    pub num_headers: u64,
+    /// Number of requests which the peer didn't respond to in time.
+    pub num_stalls: u64,
+    /// Headers the peer delivers per second, measured from request to
+    /// response.  None if it hasn't responded yet.
+    pub headers_per_second: Option<u64>,
+    /// Number of headers the peer returns in a single response.
+    pub batch_size: u64,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq)]
+pub struct HeaderSyncStatsView {
+    /// Whether header sync adapts to the measured throughput of peers.
+    pub adaptive: bool,
+    /// Peer headers were requested from most recently.
+    pub syncing_peer: Option<PeerId>,
+    /// Peers headers were requested from, the fastest first.
+    pub peers: Vec<HeaderSyncPeerStatsView>,
+}
+
 #[derive(Serialize, Deserialize, Debug, PartialEq, Eq)]
 pub struct PeerStoreView {
     pub peer_states: Vec<KnownPeerStateView>,
 }
 
+#[derive(Serialize, Deserialize, Debug, PartialEq, Eq, Clone)]
+pub struct PeerAuditEntryView {
+    pub peer_id: PeerId,
+    pub addr: Option<String>,
+    /// Whether the peer was banned, rather than just disconnected.
+    pub banned: bool,
+    pub reason: String,
+    /// Type of the received message which triggered the decision.
+    pub message_type: Option<String>,
+    pub evidence: String,
+    pub connected_at: Option<i64>,
+    pub timestamp: i64,
+}
+
+#[derive(Serialize, Deserialize, Debug, PartialEq, Eq)]
+pub struct PeerAuditLogView {
+    pub entries: Vec<PeerAuditEntryView>,
+}
+
+/// Volume of store writes made by a call-site since the node started.
+#[derive(Serialize, Deserialize, Debug, PartialEq, Eq, Clone)]
+pub struct StoreWriteVolumeView {
+    pub tag: String,
+    pub ops: u64,
+    /// Bytes of the keys and values written.
+    pub bytes: u64,
+}
+
+#[derive(Serialize, Deserialize, Debug, PartialEq, Eq, Clone)]
+pub struct StoreWritesView {
+    /// The call-sites which wrote the most come first.
+    pub by_tag: Vec<StoreWriteVolumeView>,
+}
+
 #[derive(Serialize, Deserialize, Debug, PartialEq, Eq)]
 pub struct ShardSyncDownloadView {
     pub downloads: Vec<DownloadStatusView>,
     pub status: String,
+    /// Progress of building the states of the child shards, set while the
+    /// shard is being split.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub state_split_progress: Option<StateSplitProgressView>,
+}
+
+#[derive(Serialize, Deserialize, Debug, PartialEq, Eq)]
+pub struct StateSplitProgressView {
+    pub total_parts: u64,
+    pub done_parts: u64,
+    /// Parts which had been done before the node restarted and the split
+    /// resumed from the checkpoint.
+    pub resumed_parts: u64,
+    /// Estimated time until all parts are done, based on the speed so far.
+    pub estimated_remaining_secs: Option<u64>,
+}
+
+#[derive(Serialize, Deserialize, Debug, PartialEq, Eq)]
+pub struct StatePartsApplyProgressView {
+    pub total_parts: u64,
+    pub applied_parts: u64,
+    /// Ids of the parts being validated or applied at the moment.
+    pub applying_parts: Vec<u64>,
 }
 
 #[derive(Serialize, Deserialize, Debug, PartialEq, Eq)]
@@ -410,6 +526,9 @@ pub struct CatchupStatusView {
     pub sync_block_height: BlockHeight,
     // Status of all shards that need to sync
     pub shard_sync_status: HashMap<ShardId, String>,
+    // Progress of applying state parts of the shards whose parts are being applied
+    #[serde(default, skip_serializing_if = "HashMap::is_empty")]
+    pub state_parts_apply_progress: HashMap<ShardId, StatePartsApplyProgressView>,
     // Blocks that we need to catchup, if it is empty, it means catching up is done
     pub blocks_to_catchup: Vec<BlockStatusView>,
 }
@@ -432,6 +551,40 @@ impl From<Tip> for BlockStatusView {
     }
 }
 
+/// Whether the chunk of a shard made it into the canonical block at a height.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq)]
+pub struct ChunkInclusionView {
+    pub height: BlockHeight,
+    pub shard_id: ShardId,
+    /// Chunk producer responsible for the chunk at this height.
+    pub chunk_producer: AccountId,
+    pub included: bool,
+    /// Time between the production of the previous block and of the block
+    /// which included the chunk.  None if the chunk was missed.
+    pub inclusion_delay_millis: Option<u64>,
+    /// Time between the production of the previous block and the moment this
+    /// node had the chunk ready for inclusion.  None if the node didn't get
+    /// the chunk or doesn't remember it anymore.
+    pub ready_delay_millis: Option<u64>,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq)]
+pub struct ChunkProducerInclusionView {
+    pub account_id: AccountId,
+    /// Number of chunks the producer was responsible for.
+    pub num_expected: u64,
+    /// Number of those chunks which were included.
+    pub num_included: u64,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq)]
+pub struct ChunkEndorsementStatusView {
+    /// Chunks of recent blocks, the latest first.
+    pub chunks: Vec<ChunkInclusionView>,
+    /// Per chunk producer summary of `chunks`, sorted by account id.
+    pub chunk_producers: Vec<ChunkProducerInclusionView>,
+}
+
 #[derive(Serialize, Deserialize, Debug)]
 pub struct BlockByChunksView {
     pub height: BlockHeight,
@@ -449,6 +602,9 @@ pub struct ChainProcessingInfo {
     pub blocks_info: Vec<BlockProcessingInfo>,
     /// contains processing info of chunks that we don't know which block it belongs to yet
     pub floating_chunks_info: Vec<ChunkProcessingInfo>,
+    /// contains processing info of blocks in the orphan pool, ordered by height high to low
+    #[serde(default)]
+    pub orphans_info: Vec<BlockProcessingInfo>,
 }
 
 #[derive(Serialize, Deserialize, Debug)]
@@ -472,6 +628,10 @@ pub struct BlockProcessingInfo {
     /// Only contains new chunks that belong to this block, if the block doesn't produce a new chunk
     /// for a shard, the corresponding item will be None.
     pub chunks_info: Vec<Option<ChunkProcessingInfo>>,
+    /// For orphans, the hash of the block they are waiting for, that is the parent of the oldest
+    /// of their ancestors in the orphan pool.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub missing_ancestor: Option<CryptoHash>,
 }
 
 #[derive(BorshSerialize, BorshDeserialize, Serialize, Deserialize, Clone, Debug, PartialEq, Eq)]
@@ -491,6 +651,8 @@ pub enum DroppedReason {
     HeightProcessed,
     // If the block processing pool is full
     TooManyProcessingBlocks,
+    // If the node has already processed the block, possibly before a restart
+    BlockProcessed,
 }
 
 #[derive(Serialize, Deserialize, Debug)]
@@ -530,6 +692,73 @@ pub enum ChunkProcessingStatus {
     Completed,
 }
 
+/// Version of `DebugDataView`.  Bumped whenever the layout of the view or of
+/// any view it contains changes incompatibly, so that dashboards consuming it
+/// can tell which layout they're reading.
+pub const DEBUG_DATA_VERSION: u32 = 1;
+
+/// Data behind the debug pages, for dashboards other than the built-in ones.
+#[derive(Serialize, Deserialize, Debug)]
+pub struct DebugDataView {
+    /// `DEBUG_DATA_VERSION` of the node which served the data.
+    pub version: u32,
+    pub sync_status: SyncStatusView,
+    pub header_sync: HeaderSyncStatsView,
+    pub catchup: Vec<CatchupStatusView>,
+    pub chain_processing: ChainProcessingInfo,
+    /// Blocks this node produced or is about to produce, the latest first.
+    pub block_production: Vec<BlockProductionView>,
+    /// Chunks this node produced, the latest first.
+    pub chunk_production: Vec<ChunkProductionView>,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq)]
+pub struct BlockProductionView {
+    pub height: BlockHeight,
+    /// Approvals received for the block, by the time they were received.
+    pub approvals: Vec<ApprovalReceivedView>,
+    /// When enough approvals were received to produce the block.
+    pub approvals_ready_at: Option<DateTime<chrono::Utc>>,
+    /// Chunks collected for the block, by shard.  Empty until the block is
+    /// produced.
+    pub chunks: Vec<ChunkCollectionView>,
+    /// None if the block wasn't produced yet.
+    pub produced_at: Option<DateTime<chrono::Utc>>,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq)]
+pub struct ApprovalReceivedView {
+    pub account_id: AccountId,
+    /// Whether the approval endorses the previous block, rather than skips it.
+    pub endorsement: bool,
+    pub received_at: DateTime<chrono::Utc>,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq)]
+pub struct ChunkCollectionView {
+    pub shard_id: ShardId,
+    pub chunk_producer: AccountId,
+    /// When the chunk was received.  Also set for chunks received after the
+    /// block was produced without them.
+    pub received_at: Option<DateTime<chrono::Utc>>,
+    pub included: bool,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq)]
+pub struct ChunkProductionView {
+    pub height_created: BlockHeight,
+    pub shard_id: ShardId,
+    pub produced_at: Option<DateTime<chrono::Utc>>,
+    /// Time spent producing the chunk, excluding its distribution.
+    pub duration_millis: Option<u64>,
+    pub num_transactions: Option<u64>,
+    /// Limit of the node on the number of transactions in a chunk, if any.
+    pub max_transactions: Option<u64>,
+    /// Heights of the blocks produced without the chunk because it reached
+    /// their producers too late.
+    pub missed_inclusion_heights: Vec<BlockHeight>,
+}
+
 #[derive(Serialize, Deserialize, Debug)]
 pub struct DetailedDebugStatus {
     pub network_info: NetworkInfoView,
@@ -571,6 +800,65 @@ pub struct StatusResponse {
     /// Information about last blocks, network, epoch and chain & chunk info.
     #[serde(skip_serializing_if = "Option::is_none")]
     pub detailed_debug_status: Option<DetailedDebugStatus>,
+    /// Scheduled maintenance window of the node, if any.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub maintenance_window: Option<MaintenanceWindowView>,
+    /// Whether storage latency is degraded, in which case the node sheds
+    /// optional load.
+    #[serde(default, skip_serializing_if = "is_false")]
+    pub storage_degraded: bool,
+    /// Drain of the node before it's stopped, if one began.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub drain: Option<DrainStatusView>,
+    /// Lag of the head behind the heights reported by the connected peers.
+    /// None without peers reporting their heights.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub head_lag: Option<HeadLagView>,
+}
+
+/// Lag of the head of a node behind the heights reported by its peers.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq)]
+pub struct HeadLagView {
+    pub head_height: BlockHeight,
+    /// Number of connected peers the heights are taken from.
+    pub num_peers: usize,
+    /// Median and 95th percentile of the heights reported by the peers.
+    pub peer_height_p50: BlockHeight,
+    pub peer_height_p95: BlockHeight,
+    /// Number of heights the head is behind the median and the 95th
+    /// percentile, zero if it isn't behind.
+    pub lag_p50: BlockHeightDelta,
+    pub lag_p95: BlockHeightDelta,
+    /// Whether the head persistently lags behind the median, in which case
+    /// the node syncs.
+    pub lagging: bool,
+}
+
+/// Drain of a node which is about to be stopped.  The node keeps producing the
+/// blocks and chunks it's responsible for but no longer accepts transactions.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq)]
+pub struct DrainStatusView {
+    /// Height of the head when the drain began.
+    pub started_at_height: BlockHeight,
+    /// Number of blocks being applied.
+    pub blocks_in_processing: usize,
+    /// Height of the last chunk produced by the node.  It's still being
+    /// distributed while the head is below it.
+    pub last_produced_chunk_height: Option<BlockHeight>,
+    /// Whether nothing the node is responsible for is in flight and the store
+    /// was flushed, so the node can be stopped.
+    pub safe_to_stop: bool,
+}
+
+/// Maintenance window scheduled through the dynamic config of the node.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq)]
+pub struct MaintenanceWindowView {
+    /// Height from which the node stops taking on new work.
+    pub start_height: BlockHeight,
+    /// Height at which the node shuts down once in-flight work is done.
+    pub shutdown_height: BlockHeight,
+    /// Whether the head of the node reached the start of the window.
+    pub active: bool,
 }
 
 #[derive(Serialize, Deserialize, Debug, Clone)]
@@ -624,6 +912,9 @@ pub struct BlockHeaderView {
     pub block_merkle_root: CryptoHash,
     pub epoch_sync_data_hash: Option<CryptoHash>,
     pub approvals: Vec<Option<Signature>>,
+    #[cfg(feature = "protocol_feature_bls_approvals")]
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub approvals_aggregate: Option<crate::block_header::ApprovalsAggregate>,
     pub signature: Signature,
     pub latest_protocol_version: ProtocolVersion,
 }
@@ -665,6 +956,8 @@ impl From<BlockHeader> for BlockHeaderView {
             block_merkle_root: *header.block_merkle_root(),
             epoch_sync_data_hash: header.epoch_sync_data_hash(),
             approvals: header.approvals().to_vec(),
+            #[cfg(feature = "protocol_feature_bls_approvals")]
+            approvals_aggregate: header.approvals_aggregate().cloned(),
             signature: header.signature().clone(),
             latest_protocol_version: header.latest_protocol_version(),
         }
@@ -749,6 +1042,45 @@ impl From<BlockHeaderView> for BlockHeader {
             header.init();
             BlockHeader::BlockHeaderV2(Arc::new(header))
         } else {
+            #[cfg(feature = "protocol_feature_bls_approvals")]
+            if crate::checked_feature!(
+                "protocol_feature_bls_approvals",
+                BlsApprovals,
+                view.latest_protocol_version
+            ) {
+                let mut header = crate::block_header::BlockHeaderV4 {
+                    prev_hash: view.prev_hash,
+                    inner_lite,
+                    inner_rest: crate::block_header::BlockHeaderInnerRestV4 {
+                        chunk_receipts_root: view.chunk_receipts_root,
+                        chunk_headers_root: view.chunk_headers_root,
+                        chunk_tx_root: view.chunk_tx_root,
+                        challenges_root: view.challenges_root,
+                        random_value: view.random_value,
+                        validator_proposals: view
+                            .validator_proposals
+                            .into_iter()
+                            .map(Into::into)
+                            .collect(),
+                        chunk_mask: view.chunk_mask,
+                        gas_price: view.gas_price,
+                        block_ordinal: view.block_ordinal.unwrap_or(0),
+                        total_supply: view.total_supply,
+                        challenges_result: view.challenges_result,
+                        last_final_block: view.last_final_block,
+                        last_ds_final_block: view.last_ds_final_block,
+                        prev_height: view.prev_height.unwrap_or_default(),
+                        epoch_sync_data_hash: view.epoch_sync_data_hash,
+                        approvals: view.approvals.clone(),
+                        approvals_aggregate: view.approvals_aggregate,
+                        latest_protocol_version: view.latest_protocol_version,
+                    },
+                    signature: view.signature,
+                    hash: CryptoHash::default(),
+                };
+                header.init();
+                return BlockHeader::BlockHeaderV4(Arc::new(header));
+            }
             let mut header = BlockHeaderV3 {
                 prev_hash: view.prev_hash,
                 inner_lite,
@@ -835,6 +1167,18 @@ impl From<BlockHeader> for BlockHeaderInnerLiteView {
                 next_bp_hash: header.inner_lite.next_bp_hash,
                 block_merkle_root: header.inner_lite.block_merkle_root,
             },
+            #[cfg(feature = "protocol_feature_bls_approvals")]
+            BlockHeader::BlockHeaderV4(header) => BlockHeaderInnerLiteView {
+                height: header.inner_lite.height,
+                epoch_id: header.inner_lite.epoch_id.0,
+                next_epoch_id: header.inner_lite.next_epoch_id.0,
+                prev_state_root: header.inner_lite.prev_state_root,
+                outcome_root: header.inner_lite.outcome_root,
+                timestamp: header.inner_lite.timestamp,
+                timestamp_nanosec: header.inner_lite.timestamp,
+                next_bp_hash: header.inner_lite.next_bp_hash,
+                block_merkle_root: header.inner_lite.block_merkle_root,
+            },
         }
     }
 }
@@ -1015,6 +1359,10 @@ pub enum ActionView {
     DeleteAccount {
         beneficiary_id: AccountId,
     },
+    Delegate {
+        delegate_action: DelegateAction,
+        signature: Signature,
+    },
 }
 
 impl From<Action> for ActionView {
@@ -1043,6 +1391,10 @@ impl From<Action> for ActionView {
             Action::DeleteAccount(action) => {
                 ActionView::DeleteAccount { beneficiary_id: action.beneficiary_id }
             }
+            Action::Delegate(action) => ActionView::Delegate {
+                delegate_action: action.delegate_action,
+                signature: action.signature,
+            },
         }
     }
 }
@@ -1072,6 +1424,9 @@ impl TryFrom<ActionView> for Action {
             ActionView::DeleteAccount { beneficiary_id } => {
                 Action::DeleteAccount(DeleteAccountAction { beneficiary_id })
             }
+            ActionView::Delegate { delegate_action, signature } => {
+                Action::Delegate(SignedDelegateAction { delegate_action, signature })
+            }
         })
     }
 }
@@ -1330,6 +1685,12 @@ pub struct ExecutionOutcomeWithIdView {
     pub block_hash: CryptoHash,
     pub id: CryptoHash,
     pub outcome: ExecutionOutcomeView,
+    /// Finality of `block_hash` as seen by the node serving the request,
+    /// computed at response time.  Blocks which are not on the canonical chain
+    /// are always tentative (`optimistic`), since they may never become final.
+    #[borsh_skip]
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub finality: Option<Finality>,
 }
 
 impl From<ExecutionOutcomeWithIdAndProof> for ExecutionOutcomeWithIdView {
@@ -1339,6 +1700,7 @@ impl From<ExecutionOutcomeWithIdAndProof> for ExecutionOutcomeWithIdView {
             block_hash: outcome_with_id_and_proof.block_hash,
             id: outcome_with_id_and_proof.outcome_with_id.id,
             outcome: outcome_with_id_and_proof.outcome_with_id.outcome.into(),
+            finality: None,
         }
     }
 }
@@ -1363,6 +1725,15 @@ impl FinalExecutionOutcomeViewEnum {
             Self::FinalExecutionOutcomeWithReceipt(outcome) => outcome.final_outcome,
         }
     }
+
+    pub fn finality(&self) -> Option<&Finality> {
+        match self {
+            Self::FinalExecutionOutcome(outcome) => outcome.finality.as_ref(),
+            Self::FinalExecutionOutcomeWithReceipt(outcome) => {
+                outcome.final_outcome.finality.as_ref()
+            }
+        }
+    }
 }
 
 /// Final execution outcome of the transaction and all of subsequent the receipts.
@@ -1376,6 +1747,46 @@ pub struct FinalExecutionOutcomeView {
     pub transaction_outcome: ExecutionOutcomeWithIdView,
     /// The execution outcome of receipts.
     pub receipts_outcome: Vec<ExecutionOutcomeWithIdView>,
+    /// The weakest finality of the outcomes above, i.e. the finality of the
+    /// result as a whole.  Computed at response time, see
+    /// [`ExecutionOutcomeWithIdView::finality`].
+    #[borsh_skip]
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub finality: Option<Finality>,
+    /// Refunds generated while executing the transaction.  Computed at
+    /// response time from the stored receipts.
+    #[borsh_skip]
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub refunds: Option<TransactionRefundsView>,
+}
+
+impl FinalExecutionOutcomeView {
+    /// Computes refunds of the transaction, see [`TransactionRefundsView::new`].
+    pub fn annotate_refunds<E>(
+        &mut self,
+        get_receipt: impl FnMut(&CryptoHash) -> Result<Option<Arc<Receipt>>, E>,
+    ) -> Result<(), E> {
+        self.refunds = Some(TransactionRefundsView::new(self, get_receipt)?);
+        Ok(())
+    }
+
+    /// Annotates all outcomes with finality of their blocks and sets the
+    /// finality of the whole result.
+    pub fn annotate_finality<E>(
+        &mut self,
+        mut block_finality: impl FnMut(&CryptoHash) -> Result<Finality, E>,
+    ) -> Result<(), E> {
+        let mut finality = Finality::Final;
+        for outcome in
+            std::iter::once(&mut self.transaction_outcome).chain(self.receipts_outcome.iter_mut())
+        {
+            let outcome_finality = block_finality(&outcome.block_hash)?;
+            finality = std::cmp::min(finality, outcome_finality.clone());
+            outcome.finality = Some(outcome_finality);
+        }
+        self.finality = Some(finality);
+        Ok(())
+    }
 }
 
 impl fmt::Debug for FinalExecutionOutcomeView {
@@ -1385,10 +1796,105 @@ impl fmt::Debug for FinalExecutionOutcomeView {
             .field("transaction", &self.transaction)
             .field("transaction_outcome", &self.transaction_outcome)
             .field("receipts_outcome", &pretty::Slice(&self.receipts_outcome))
+            .field("finality", &self.finality)
+            .field("refunds", &self.refunds)
             .finish()
     }
 }
 
+/// Refund receipt generated while executing a transaction.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq)]
+pub struct RefundView {
+    /// Id of the refund receipt.
+    pub receipt_id: CryptoHash,
+    /// Id of the transaction or receipt whose execution generated the refund.
+    pub refunded_for: CryptoHash,
+    pub receiver_id: AccountId,
+    pub kind: RefundKind,
+    #[serde(with = "dec_format")]
+    pub amount: Balance,
+}
+
+/// Refunds of a transaction, which let clients compute the effective fee
+/// without reconstructing the refund receipts themselves.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq, Default)]
+pub struct TransactionRefundsView {
+    pub refunds: Vec<RefundView>,
+    /// Price at which the gas of the transaction was purchased, i.e. the gas
+    /// price of the receipt the transaction was converted to.
+    #[serde(with = "dec_format")]
+    pub gas_price: Balance,
+    /// Unused prepaid gas which was refunded.  Gas is refunded at the gas
+    /// price of the receipt it was unused by.
+    pub gas_refunded: Gas,
+    /// Tokens refunded for unused prepaid gas.
+    #[serde(with = "dec_format")]
+    pub gas_tokens_refunded: Balance,
+    /// Deposits refunded because the receipts they were attached to failed.
+    #[serde(with = "dec_format")]
+    pub deposit_refunded: Balance,
+}
+
+impl TransactionRefundsView {
+    /// Collects refunds among the receipts generated by the outcomes.
+    /// Receipts which can no longer be found, e.g. because they were garbage
+    /// collected, are skipped.
+    pub fn new<E>(
+        outcome: &FinalExecutionOutcomeView,
+        mut get_receipt: impl FnMut(&CryptoHash) -> Result<Option<Arc<Receipt>>, E>,
+    ) -> Result<Self, E> {
+        let gas_price_of = |receipt: Option<Arc<Receipt>>| match receipt.as_deref() {
+            Some(Receipt { receipt: ReceiptEnum::Action(action_receipt), .. }) => {
+                action_receipt.gas_price
+            }
+            _ => 0,
+        };
+        let gas_price = match outcome.transaction_outcome.outcome.receipt_ids.first() {
+            Some(receipt_id) => gas_price_of(get_receipt(receipt_id)?),
+            None => 0,
+        };
+        let mut refunds = Self { gas_price, ..Self::default() };
+        for parent in std::iter::once(&outcome.transaction_outcome).chain(&outcome.receipts_outcome)
+        {
+            // Gas price of the receipt the refunds were generated for.
+            let mut parent_gas_price = None;
+            for receipt_id in &parent.outcome.receipt_ids {
+                let receipt = match get_receipt(receipt_id)? {
+                    Some(receipt) => receipt,
+                    None => continue,
+                };
+                let (kind, amount) = match receipt.as_refund() {
+                    Some(refund) => refund,
+                    None => continue,
+                };
+                match kind {
+                    RefundKind::Gas => {
+                        refunds.gas_tokens_refunded += amount;
+                        let parent_gas_price = match parent_gas_price {
+                            Some(gas_price) => gas_price,
+                            None => {
+                                *parent_gas_price.insert(gas_price_of(get_receipt(&parent.id)?))
+                            }
+                        };
+                        if parent_gas_price > 0 {
+                            refunds.gas_refunded += (amount / parent_gas_price) as Gas;
+                        }
+                    }
+                    RefundKind::Deposit => refunds.deposit_refunded += amount,
+                }
+                refunds.refunds.push(RefundView {
+                    receipt_id: *receipt_id,
+                    refunded_for: parent.id,
+                    receiver_id: receipt.receiver_id.clone(),
+                    kind,
+                    amount,
+                });
+            }
+        }
+        Ok(refunds)
+    }
+}
+
 /// Final execution outcome of the transaction and all of subsequent the receipts. Also includes
 /// the generated receipt.
 #[derive(BorshSerialize, BorshDeserialize, Serialize, Deserialize, PartialEq, Eq, Clone, Debug)]
@@ -1398,6 +1904,46 @@ pub struct FinalExecutionOutcomeWithReceiptView {
     pub final_outcome: FinalExecutionOutcomeView,
     /// Receipts generated from the transaction
     pub receipts: Vec<ReceiptView>,
+    /// Proofs of the outcomes of the transaction and its receipts, if
+    /// requested.  Outcomes whose outcome root isn't included in a block yet
+    /// have no proof.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub outcome_proofs: Option<Vec<ExecutionOutcomeProofView>>,
+}
+
+/// A block known to this node in which the transaction was executed.
+#[derive(BorshSerialize, BorshDeserialize, Serialize, Deserialize, PartialEq, Eq, Clone, Debug)]
+pub struct TxInclusionView {
+    pub block_hash: CryptoHash,
+    pub block_height: BlockHeight,
+    /// Whether the block is on the current canonical chain.
+    pub canonical: bool,
+}
+
+/// Status of the transaction with respect to the forks known to this node.
+#[derive(BorshSerialize, BorshDeserialize, Serialize, Deserialize, PartialEq, Eq, Clone, Debug)]
+pub enum TxInclusionStatus {
+    /// The transaction was executed in a block on the canonical chain.
+    Included,
+    /// The transaction was only executed in blocks that are no longer on the
+    /// canonical chain and it has not been included again.
+    Orphaned,
+    /// The transaction is not on the canonical chain but is waiting in the
+    /// transaction pool to be (re-)included.
+    Pending,
+    /// The node has no record of the transaction.
+    Unknown,
+}
+
+/// Fork-aware status of a transaction.
+#[derive(BorshSerialize, BorshDeserialize, Serialize, Deserialize, PartialEq, Eq, Clone, Debug)]
+pub struct TxForkStatusView {
+    pub tx_hash: CryptoHash,
+    pub status: TxInclusionStatus,
+    /// Whether the transaction is currently in the transaction pool.
+    pub in_pool: bool,
+    /// All known blocks that executed the transaction, ordered by height.
+    pub inclusions: Vec<TxInclusionView>,
 }
 
 pub mod validator_stake_view {
@@ -1438,6 +1984,13 @@ pub mod validator_stake_view {
                 Self::V1(v1) => &v1.account_id,
             }
         }
+
+        #[inline]
+        pub fn stake(&self) -> Balance {
+            match self {
+                Self::V1(v1) => v1.stake,
+            }
+        }
     }
 
     #[derive(
@@ -1588,6 +2141,307 @@ impl TryFrom<ReceiptView> for Receipt {
     }
 }
 
+/// Block producer assigned to a height together with the inputs of the
+/// assignment, so that it can be verified without trusting the node.
+#[derive(Serialize, Deserialize, Debug, PartialEq, Eq, Clone)]
+pub struct BlockProducerProofView {
+    pub height: BlockHeight,
+    pub epoch_id: CryptoHash,
+    pub epoch_height: EpochHeight,
+    /// Randomness of the epoch the block producers are sampled with, weighted
+    /// by stake.  None for epochs which assign heights to the seats in turn.
+    pub rng_seed: Option<CryptoHash>,
+    /// Block producer seats of the epoch, in order, with the stakes they're
+    /// weighted by.
+    pub block_producers_settlement: Vec<ValidatorStakeView>,
+    pub block_producer: AccountId,
+}
+
+impl BlockProducerProofView {
+    /// Computes the block producer of the height from the inputs of the
+    /// assignment.  None if the inputs can't produce any assignment.
+    pub fn compute_block_producer(&self) -> Option<&AccountId> {
+        let seats = &self.block_producers_settlement;
+        if seats.is_empty() {
+            return None;
+        }
+        let seat = match &self.rng_seed {
+            None => (self.height % seats.len() as u64) as usize,
+            Some(rng_seed) => {
+                let stakes: Vec<Balance> = seats.iter().map(|seat| seat.stake()).collect();
+                // The sampler can't handle no stake at all or a total stake
+                // which overflows when multiplied by the number of seats.
+                let total_stake = stakes
+                    .iter()
+                    .try_fold(0 as Balance, |total, stake| total.checked_add(*stake))?;
+                if total_stake == 0 || total_stake.checked_mul(seats.len() as Balance).is_none() {
+                    return None;
+                }
+                let seed = crate::epoch_manager::epoch_info::EpochInfo::block_produce_seed(
+                    self.height,
+                    &rng_seed.0,
+                );
+                crate::rand::WeightedIndex::new(stakes).sample(seed)
+            }
+        };
+        Some(seats[seat].account_id())
+    }
+
+    /// Whether the block producer follows from the inputs of the assignment.
+    pub fn verify(&self) -> bool {
+        self.compute_block_producer() == Some(&self.block_producer)
+    }
+}
+
+/// Randomness of a block: the VRF output of its producer on the random value
+/// of the previous block, with the proof needed to verify it externally.
+#[derive(Serialize, Deserialize, Debug, PartialEq, Eq, Clone)]
+pub struct BlockRandomnessView {
+    pub block_hash: CryptoHash,
+    pub height: BlockHeight,
+    pub prev_block_hash: CryptoHash,
+    pub block_producer: AccountId,
+    /// Key the block producer computed the VRF output with.
+    pub block_producer_public_key: PublicKey,
+    /// Random value of the previous block, which is the input of the VRF.
+    pub prev_random_value: CryptoHash,
+    pub vrf_value: near_crypto::vrf::Value,
+    pub vrf_proof: near_crypto::vrf::Proof,
+    /// Hash of `vrf_value`.
+    pub random_value: CryptoHash,
+}
+
+impl BlockRandomnessView {
+    /// Whether the random value is the hash of the VRF output of the block
+    /// producer on the random value of the previous block.
+    pub fn verify(&self) -> bool {
+        let public_key = match &self.block_producer_public_key {
+            PublicKey::ED25519(public_key) => public_key,
+            PublicKey::SECP256K1(_) => return false,
+        };
+        let public_key = match near_crypto::key_conversion::convert_public_key(public_key) {
+            Some(public_key) => public_key,
+            None => return false,
+        };
+        self.random_value == hash(self.vrf_value.0.as_ref())
+            && public_key.is_vrf_valid(
+                &self.prev_random_value.as_ref(),
+                &self.vrf_value,
+                &self.vrf_proof,
+            )
+    }
+}
+
+/// Randomness of an epoch, which seeds the sampling of its block and chunk
+/// producers.  The last block of the epoch T seeds the epoch T + 2 with its
+/// random value.
+#[derive(Serialize, Deserialize, Debug, PartialEq, Eq, Clone)]
+pub struct EpochRandomnessView {
+    pub epoch_id: CryptoHash,
+    pub epoch_height: EpochHeight,
+    /// None for epochs which assign heights to the seats in turn.
+    pub rng_seed: Option<CryptoHash>,
+    /// Block whose random value is the seed.  None for the first two epochs,
+    /// which are seeded with zeros.
+    pub seed_block_hash: Option<CryptoHash>,
+}
+
+impl EpochRandomnessView {
+    /// Whether the seed is the random value of `seed_block`, which should be
+    /// verified on its own.
+    pub fn verify_seed(&self, seed_block: &BlockRandomnessView) -> bool {
+        self.seed_block_hash == Some(seed_block.block_hash)
+            && self.rng_seed == Some(seed_block.random_value)
+    }
+}
+
+#[derive(Serialize, Deserialize, Debug, PartialEq, Eq, Clone)]
+pub struct RandomnessView {
+    /// Randomness of the epoch of the first block.
+    pub epoch: EpochRandomnessView,
+    /// The requested block followed by its ancestors, each of which provides
+    /// the input of the VRF of the preceding one.  The genesis block, which
+    /// has no producer, isn't included.
+    pub blocks: Vec<BlockRandomnessView>,
+}
+
+impl RandomnessView {
+    /// Whether the random values of all blocks verify and follow from each
+    /// other.
+    pub fn verify(&self) -> bool {
+        self.blocks.iter().all(BlockRandomnessView::verify)
+            && self.blocks.windows(2).all(|pair| {
+                pair[0].prev_block_hash == pair[1].block_hash
+                    && pair[0].prev_random_value == pair[1].random_value
+            })
+    }
+}
+
+/// Expected block producer and chunk producers of the heights after the head.
+#[derive(Serialize, Deserialize, Debug, PartialEq, Eq, Clone)]
+pub struct ProductionScheduleView {
+    pub heights: Vec<ProductionScheduleEntryView>,
+}
+
+#[derive(Serialize, Deserialize, Debug, PartialEq, Eq, Clone)]
+pub struct ProductionScheduleEntryView {
+    pub height: BlockHeight,
+    /// Epoch the producers are assigned in.  Heights close to the end of the
+    /// epoch may end up in the next one.
+    pub epoch_id: CryptoHash,
+    pub block_producer: AccountId,
+    /// Chunk producers, indexed by shard id.
+    pub chunk_producers: Vec<AccountId>,
+}
+
+/// Outgoing receipts of a chunk, split by the shard receiving them, with the
+/// merkle paths the validators of the receiving shards check them with.
+#[derive(Serialize, Deserialize, Debug, PartialEq, Eq, Clone)]
+pub struct OutgoingReceiptProofsView {
+    pub chunk_hash: CryptoHash,
+    pub shard_id: ShardId,
+    pub outgoing_receipts_root: CryptoHash,
+    /// Proofs indexed by the id of the receiving shard, including the shards
+    /// which receive no receipts from the chunk.
+    pub proofs: Vec<ReceiptProofView>,
+}
+
+#[derive(BorshSerialize, BorshDeserialize, Serialize, Deserialize, Debug, PartialEq, Eq, Clone)]
+pub struct ReceiptProofView {
+    pub to_shard_id: ShardId,
+    pub receipts: Vec<ReceiptView>,
+    pub proof: MerklePath,
+}
+
+impl From<ReceiptProof> for ReceiptProofView {
+    fn from(ReceiptProof(receipts, shard_proof): ReceiptProof) -> Self {
+        Self {
+            to_shard_id: shard_proof.to_shard_id,
+            receipts: receipts.into_iter().map(Into::into).collect(),
+            proof: shard_proof.proof,
+        }
+    }
+}
+
+impl ReceiptProofView {
+    /// Checks that the receipts are exactly the ones the chunk with the given
+    /// outgoing receipts root sent to the shard.
+    pub fn verify(&self, outgoing_receipts_root: &CryptoHash) -> bool {
+        let receipts: Result<Vec<Receipt>, _> =
+            self.receipts.iter().cloned().map(Receipt::try_from).collect();
+        let receipts = match receipts {
+            Ok(receipts) => receipts,
+            Err(_) => return false,
+        };
+        let receipts_hash = hash(&(self.to_shard_id, receipts).try_to_vec().unwrap());
+        crate::merkle::verify_path(*outgoing_receipts_root, &self.proof, receipts_hash)
+    }
+}
+
+/// Proof that a receipt was sent by a chunk.  The receipt is among the
+/// receipts the chunk sent to the receiving shard, which the outgoing
+/// receipts root of the chunk commits to, which in turn the chunk receipts
+/// root of the block including the chunk commits to.
+#[derive(BorshSerialize, BorshDeserialize, Serialize, Deserialize, Debug, PartialEq, Eq, Clone)]
+pub struct ReceiptInclusionProofView {
+    pub receipt_id: CryptoHash,
+    /// Block including the chunk which sent the receipt.
+    pub block_hash: CryptoHash,
+    pub block_height: BlockHeight,
+    pub chunk_hash: CryptoHash,
+    pub from_shard_id: ShardId,
+    pub outgoing_receipts_root: CryptoHash,
+    /// Receipts the chunk sent to the receiving shard, including this one.
+    pub receipt_proof: ReceiptProofView,
+    /// Path from the outgoing receipts root of the chunk to the chunk
+    /// receipts root of the block.
+    pub chunk_proof: MerklePath,
+}
+
+impl ReceiptInclusionProofView {
+    /// Checks the proof against the chunk receipts root of the block header
+    /// with the hash `block_hash`.
+    pub fn verify(&self, chunk_receipts_root: &CryptoHash) -> bool {
+        self.receipt_proof.receipts.iter().any(|receipt| receipt.receipt_id == self.receipt_id)
+            && self.receipt_proof.verify(&self.outgoing_receipts_root)
+            && crate::merkle::verify_path(
+                *chunk_receipts_root,
+                &self.chunk_proof,
+                self.outgoing_receipts_root,
+            )
+    }
+}
+
+/// Proof that a receipt was executed with the given outcome, which a light
+/// client verifies knowing only the block merkle root of its head.  The
+/// outcome root of the shard commits to the outcome, the outcome root of the
+/// block commits to the outcome root of the shard and the block merkle root of
+/// the light client head commits to the block.
+#[derive(BorshSerialize, BorshDeserialize, Serialize, Deserialize, Debug, Clone)]
+pub struct ReceiptExecutionProofView {
+    /// Outcome of the receipt and the path from it to the outcome root of the
+    /// shard.  Its `block_hash` is the hash of `block_header_lite`.
+    pub outcome_proof: ExecutionOutcomeWithIdView,
+    /// Path from the outcome root of the shard to the outcome root of the
+    /// block.
+    pub outcome_root_proof: MerklePath,
+    /// Block including the outcome root of the chunk which executed the
+    /// receipt.
+    pub block_header_lite: LightClientBlockLiteView,
+    /// Path from the hash of the block to the block merkle root of the light
+    /// client head.
+    pub block_proof: MerklePath,
+}
+
+/// Proof that an outcome is part of a block, which a client verifies knowing
+/// the header of the block.  The path from the outcome to the outcome root of
+/// the shard is the `proof` of the outcome itself.
+#[derive(BorshSerialize, BorshDeserialize, Serialize, Deserialize, PartialEq, Eq, Clone, Debug)]
+pub struct ExecutionOutcomeProofView {
+    /// Id of the transaction or receipt the outcome is of.
+    pub id: CryptoHash,
+    /// Path from the outcome root of the shard to the outcome root of the
+    /// block.
+    pub outcome_root_proof: MerklePath,
+    /// Block including the outcome root of the chunk which produced the
+    /// outcome.
+    pub block_header_lite: LightClientBlockLiteView,
+}
+
+impl ExecutionOutcomeProofView {
+    /// Checks that the outcome is part of the block of the proof.  The block
+    /// itself is to be checked by the caller, e.g. against the hash it knows.
+    pub fn verify(&self, outcome: &ExecutionOutcomeWithIdView) -> bool {
+        let shard_outcome_root =
+            crate::merkle::compute_root_from_path_and_item(&outcome.proof, outcome.to_hashes());
+        outcome.id == self.id
+            && crate::merkle::verify_path(
+                self.block_header_lite.inner_lite.outcome_root,
+                &self.outcome_root_proof,
+                shard_outcome_root,
+            )
+    }
+}
+
+impl ReceiptExecutionProofView {
+    /// Checks the proof against the block merkle root of the light client
+    /// head.
+    pub fn verify(&self, block_merkle_root: &CryptoHash) -> bool {
+        let shard_outcome_root = crate::merkle::compute_root_from_path_and_item(
+            &self.outcome_proof.proof,
+            self.outcome_proof.to_hashes(),
+        );
+        let block_hash = self.block_header_lite.hash();
+        block_hash == self.outcome_proof.block_hash
+            && crate::merkle::verify_path(
+                self.block_header_lite.inner_lite.outcome_root,
+                &self.outcome_root_proof,
+                shard_outcome_root,
+            )
+            && crate::merkle::verify_hash(*block_merkle_root, &self.block_proof, block_hash)
+    }
+}
+
 /// Information about this epoch validators and next epoch validators
 #[derive(Serialize, Deserialize, Debug, PartialEq, Eq, Clone)]
 pub struct EpochValidatorInfo {
@@ -1629,6 +2483,10 @@ pub struct CurrentEpochValidatorInfo {
     pub num_produced_chunks: NumBlocks,
     #[serde(default)]
     pub num_expected_chunks: NumBlocks,
+    /// BLS key registered by the validator for aggregated approvals.
+    #[cfg(feature = "protocol_feature_bls_approvals")]
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub bls_public_key: Option<near_crypto::bls::PublicKey>,
 }
 
 #[derive(BorshSerialize, BorshDeserialize, Serialize, Deserialize, Debug, PartialEq, Eq, Clone)]
@@ -1650,7 +2508,7 @@ pub struct LightClientBlockView {
     pub approvals_after_next: Vec<Option<Signature>>,
 }
 
-#[derive(Serialize, Deserialize, Debug, Clone, BorshDeserialize, BorshSerialize)]
+#[derive(Serialize, Deserialize, PartialEq, Eq, Debug, Clone, BorshDeserialize, BorshSerialize)]
 pub struct LightClientBlockLiteView {
     pub prev_block_hash: CryptoHash,
     pub inner_rest_hash: CryptoHash,
@@ -1679,12 +2537,125 @@ impl LightClientBlockLiteView {
     }
 }
 
-#[derive(Serialize, Deserialize, Debug)]
+#[derive(Serialize, Deserialize, Debug, Clone)]
 pub struct GasPriceView {
     #[serde(with = "dec_format")]
     pub gas_price: Balance,
 }
 
+/// Economics of a single block, see [`EconomicsSeriesView`].
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq)]
+pub struct EconomicsPointView {
+    pub height: BlockHeight,
+    pub block_hash: CryptoHash,
+    #[serde(with = "dec_format")]
+    pub timestamp_nanosec: u64,
+    #[serde(with = "dec_format")]
+    pub gas_price: Balance,
+    #[serde(with = "dec_format")]
+    pub total_supply: Balance,
+    /// Tokens minted by the block.  Tokens are minted only by the first block
+    /// of an epoch, for all other blocks this is zero.
+    #[serde(with = "dec_format")]
+    pub minted_amount: Balance,
+}
+
+/// Gas price, total supply and minted tokens of blocks in a range of heights.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq)]
+pub struct EconomicsSeriesView {
+    /// Points in increasing order of height.  Heights without a block are
+    /// skipped.
+    pub points: Vec<EconomicsPointView>,
+    /// Height to request next to continue the series, None if the end of the
+    /// range was reached.
+    pub next_from_height: Option<BlockHeight>,
+}
+
+/// Event emitted by a contract as a log following the event standard
+/// (NEP-297).
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq)]
+pub struct ContractEventView {
+    /// Account of the contract which emitted the event.
+    pub account_id: AccountId,
+    pub standard: String,
+    pub version: String,
+    pub event: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub data: Option<serde_json::Value>,
+    pub block_height: BlockHeight,
+    pub block_hash: CryptoHash,
+    /// Id of the receipt or transaction whose execution emitted the event.
+    pub outcome_id: CryptoHash,
+    /// Index of the event's log among the logs of the execution.
+    pub log_index: u32,
+}
+
+/// A page of events of a single contract.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq)]
+pub struct ContractEventsView {
+    /// Events ordered by standard, event name and then block height.
+    pub events: Vec<ContractEventView>,
+    /// Cursor to request next to continue the query, None if there are no
+    /// more events.
+    pub next_cursor: Option<String>,
+}
+
+/// Account a contract is deployed to.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq)]
+pub struct ContractAccountView {
+    pub account_id: AccountId,
+    /// Block in which the code was last seen deployed to the account.
+    pub block_height: BlockHeight,
+    pub block_hash: CryptoHash,
+}
+
+/// A page of the accounts a contract is deployed to.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq)]
+pub struct ContractAccountsView {
+    /// Accounts ordered by account id.
+    pub accounts: Vec<ContractAccountView>,
+    /// Cursor to request next to continue the query, None if there are no
+    /// more accounts.
+    pub next_cursor: Option<AccountId>,
+}
+
+/// Reason the node isn't ready or isn't live.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq)]
+#[serde(tag = "reason", rename_all = "snake_case")]
+pub enum HealthReasonView {
+    /// The node is syncing.
+    Syncing,
+    /// The head block is older than the configured maximum.
+    StaleHead { head_age_ms: u64, max_head_age_ms: u64 },
+    /// The node has fewer connected peers than the configured minimum.
+    NotEnoughPeers { num_peers: usize, min_peers: usize },
+    /// The node has no validator key, which the configuration requires.
+    NoValidatorSigner,
+    /// Latency of the storage is degraded.
+    StorageDegraded,
+    /// The node is draining before it's stopped.
+    Draining,
+    /// Blocks take longer to process, on average, than the configured
+    /// maximum.
+    SlowBlockProcessing { block_processing_time_ms: u64, max_block_processing_time_ms: u64 },
+    /// The head hasn't advanced for longer than the configured maximum while
+    /// peers are ahead of it and the node isn't syncing.
+    HeadStalled { stalled_for_ms: u64, max_head_stall_ms: u64 },
+}
+
+/// Readiness and liveness verdicts of the node.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq)]
+pub struct HealthView {
+    /// Whether the node is ready to serve requests.
+    pub ready: bool,
+    /// Whether the node makes progress and doesn't need a restart.
+    pub live: bool,
+    /// Why the node isn't ready, empty if it is.
+    pub readiness_failures: Vec<HealthReasonView>,
+    /// Why the node isn't live, empty if it is.
+    pub liveness_failures: Vec<HealthReasonView>,
+}
+
 /// It is a [serializable view] of [`StateChangesRequest`].
 ///
 /// [serializable view]: ./index.html
@@ -1763,6 +2734,42 @@ impl From<StateChangeKind> for StateChangeKindView {
 
 pub type StateChangesKindsView = Vec<StateChangeKindView>;
 
+/// Key of the state changed between two blocks.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq)]
+#[serde(rename_all = "snake_case", tag = "type")]
+pub enum ChangedTrieKeyView {
+    Account { account_id: AccountId },
+    ContractCode { account_id: AccountId },
+    AccessKey { account_id: AccountId, public_key: PublicKey },
+    Data { account_id: AccountId, key: StoreKey },
+}
+
+/// Where the changed keys of a [`TrieDiffView`] come from.
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum TrieDiffSourceView {
+    /// State changes recorded for every block in between.  Keys written in
+    /// any of the blocks are included, even if their value ended up the same.
+    StateChanges,
+    /// Diff of the tries of the state after both blocks.
+    Trie,
+}
+
+/// A page of the keys of the state of a shard changed after `from_block_hash`
+/// up to and including `to_block_hash`.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq)]
+pub struct TrieDiffView {
+    pub from_block_hash: CryptoHash,
+    pub to_block_hash: CryptoHash,
+    pub shard_id: ShardId,
+    pub source: TrieDiffSourceView,
+    /// Changed keys ordered by their raw trie keys.
+    pub changes: Vec<ChangedTrieKeyView>,
+    /// Cursor to request next to continue the query, None if there are no
+    /// more changes.
+    pub next_cursor: Option<String>,
+}
+
 /// See crate::types::StateChangeCause for details.
 #[derive(Debug, Serialize, Deserialize)]
 #[serde(rename_all = "snake_case", tag = "type")]
@@ -1896,3 +2903,24 @@ impl From<StateChangeWithCause> for StateChangeWithCauseView {
 }
 
 pub type StateChangesView = Vec<StateChangeWithCauseView>;
+
+/// Value of a runtime parameter before and after a protocol upgrade.  A missing
+/// value means that the parameter isn't defined in that protocol version.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq)]
+pub struct RuntimeParameterChangeView {
+    pub parameter: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub old_value: Option<serde_json::Value>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub new_value: Option<serde_json::Value>,
+}
+
+/// Differences between runtime parameters of two protocol versions.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq)]
+pub struct RuntimeParametersDiffView {
+    pub from_protocol_version: ProtocolVersion,
+    pub to_protocol_version: ProtocolVersion,
+    pub added: Vec<RuntimeParameterChangeView>,
+    pub removed: Vec<RuntimeParameterChangeView>,
+    pub changed: Vec<RuntimeParameterChangeView>,
+}
@@ -0,0 +1,250 @@
+//! Continuous migration of data from hot to cold storage.
+//!
+//! A node with cold storage copies every final block, together with all data
+//! written while processing it, from the hot database to the cold one (see
+//! [`update_cold_db`]).  Garbage collection of the hot database never goes
+//! past the cold head, so the migration has to keep up with the chain for the
+//! hot database not to grow without bound.
+//!
+//! The migration runs in a background thread.  It copies heights one by one,
+//! from the height after the last copied one up to the final head, records
+//! its progress in [`DBCol::ColdMigrationProgress`] and advances the cold
+//! head.  An interrupted migration resumes from the recorded progress.  Since
+//! cold storage only ever gets values set, copying a height again is harmless.
+//!
+//! When there’s no progress recorded yet, everything in cold columns of the
+//! hot database is copied first, which covers genesis state and anything
+//! written before the migration was enabled.
+use std::io;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Arc;
+use std::time::Duration;
+
+use borsh::{BorshDeserialize, BorshSerialize};
+use near_primitives::block::Tip;
+use near_primitives::block_header::BlockHeader;
+use near_primitives::shard_layout::ShardLayout;
+use near_primitives::types::{BlockHeight, EpochId};
+
+use crate::cold_storage::{copy_all_to_cold_db, update_cold_db, update_cold_head};
+use crate::{metrics, DBCol, Database, Store, FINAL_HEAD_KEY};
+
+const PROGRESS_KEY: &[u8] = b"PROGRESS";
+
+#[derive(Clone, Debug, PartialEq, Eq, serde::Serialize, serde::Deserialize)]
+#[serde(default)]
+pub struct ColdMigrationConfig {
+    /// Whether data is migrated to cold storage by the node itself.  Disable
+    /// if cold storage is populated by an external tool.
+    pub enabled: bool,
+    /// How long to wait before checking for new final heights once the
+    /// migration has caught up with the final head.
+    pub poll_interval: Duration,
+    /// Maximum number of heights copied in a single step, after which the
+    /// thread checks whether it should stop.
+    pub max_heights_per_step: u64,
+}
+
+impl Default for ColdMigrationConfig {
+    fn default() -> Self {
+        Self { enabled: true, poll_interval: Duration::from_secs(1), max_heights_per_step: 100 }
+    }
+}
+
+#[derive(BorshSerialize, BorshDeserialize, Clone, Debug, PartialEq, Eq)]
+pub struct ColdMigrationProgress {
+    /// All heights up to this one, whether there’s a block at them or not,
+    /// have been copied to cold storage.
+    pub copied_height: BlockHeight,
+}
+
+/// Returns the shard layout of an epoch.  The store doesn’t know the layouts,
+/// so the node provides them.
+pub type ShardLayoutFn = Box<dyn Fn(&EpochId) -> io::Result<ShardLayout> + Send>;
+
+pub struct ColdMigration {
+    hot_store: Store,
+    cold_db: Arc<dyn Database>,
+    shard_layout: ShardLayoutFn,
+    config: ColdMigrationConfig,
+}
+
+impl ColdMigration {
+    pub fn new(
+        hot_store: Store,
+        cold_db: Arc<dyn Database>,
+        shard_layout: ShardLayoutFn,
+        config: ColdMigrationConfig,
+    ) -> Self {
+        Self { hot_store, cold_db, shard_layout, config }
+    }
+
+    /// Returns the recorded progress of the migration.
+    pub fn progress(&self) -> io::Result<Option<ColdMigrationProgress>> {
+        self.hot_store.get_ser(DBCol::ColdMigrationProgress, PROGRESS_KEY)
+    }
+
+    fn save_progress(&self, progress: &ColdMigrationProgress) -> io::Result<()> {
+        let mut store_update = self.hot_store.store_update();
+        store_update.set_ser(DBCol::ColdMigrationProgress, PROGRESS_KEY, progress)?;
+        store_update.commit()
+    }
+
+    /// Copies up to `max_heights_per_step` final heights which haven’t been
+    /// copied yet.  Returns the number of heights copied.
+    pub fn run_step(&self) -> io::Result<u64> {
+        let final_head: Tip = match self.hot_store.get_ser(DBCol::BlockMisc, FINAL_HEAD_KEY)? {
+            Some(final_head) => final_head,
+            None => return Ok(0),
+        };
+        let copied_height = match self.progress()? {
+            Some(progress) => progress.copied_height,
+            None => {
+                tracing::info!(
+                    target: "store",
+                    final_height = final_head.height,
+                    "Copying hot storage to cold storage"
+                );
+                copy_all_to_cold_db(&*self.cold_db, &self.hot_store)?;
+                self.finish_height(final_head.height)?;
+                return Ok(1);
+            }
+        };
+        let last_height =
+            std::cmp::min(final_head.height, copied_height + self.config.max_heights_per_step);
+        for height in copied_height + 1..=last_height {
+            self.copy_height(height)?;
+            self.finish_height(height)?;
+        }
+        metrics::COLD_MIGRATION_LAG.set(final_head.height.saturating_sub(last_height) as i64);
+        Ok(last_height.saturating_sub(copied_height))
+    }
+
+    fn copy_height(&self, height: BlockHeight) -> io::Result<()> {
+        let block_hash = match self.hot_store.get(DBCol::BlockHeight, &height.to_le_bytes())? {
+            Some(block_hash) => block_hash,
+            // No block at the height on the canonical chain, nothing to copy.
+            None => return Ok(()),
+        };
+        let header: BlockHeader =
+            self.hot_store.get_ser(DBCol::BlockHeader, &block_hash)?.ok_or_else(|| {
+                io::Error::new(io::ErrorKind::NotFound, format!("header at height {height}"))
+            })?;
+        let shard_layout = (self.shard_layout)(header.epoch_id())?;
+        update_cold_db(&*self.cold_db, &self.hot_store, &shard_layout, &height)
+    }
+
+    /// Records that everything up to `height` has been copied and moves the
+    /// cold head, which lets garbage collection of the hot store proceed.
+    fn finish_height(&self, height: BlockHeight) -> io::Result<()> {
+        self.save_progress(&ColdMigrationProgress { copied_height: height })?;
+        update_cold_head(&*self.cold_db, &self.hot_store, &height)?;
+        metrics::COLD_MIGRATION_COPIED_HEIGHT.set(height as i64);
+        Ok(())
+    }
+
+    /// Starts the migration in a background thread, unless it’s disabled in
+    /// the config.  The thread stops once the returned handle is dropped.
+    pub fn spawn(self) -> io::Result<Option<ColdMigrationHandle>> {
+        if !self.config.enabled {
+            return Ok(None);
+        }
+        let stop = Arc::new(AtomicBool::new(false));
+        let thread_stop = stop.clone();
+        std::thread::Builder::new().name("cold_migration".to_string()).spawn(move || {
+            while !thread_stop.load(Ordering::Relaxed) {
+                match self.run_step() {
+                    Ok(0) => std::thread::sleep(self.config.poll_interval),
+                    Ok(_) => {}
+                    Err(err) => {
+                        tracing::error!(target: "store", ?err, "Cold storage migration failed");
+                        std::thread::sleep(self.config.poll_interval);
+                    }
+                }
+            }
+        })?;
+        Ok(Some(ColdMigrationHandle { stop }))
+    }
+}
+
+/// Stops the migration thread when dropped.  The thread finishes the step
+/// it’s in the middle of first.
+pub struct ColdMigrationHandle {
+    stop: Arc<AtomicBool>,
+}
+
+impl Drop for ColdMigrationHandle {
+    fn drop(&mut self) {
+        self.stop.store(true, Ordering::Relaxed);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{ColdMigration, ColdMigrationConfig, ColdMigrationProgress};
+    use crate::db::{ColdDB, TestDB};
+    use crate::{DBCol, NodeStorage, Store, Temperature, COLD_HEAD_KEY, FINAL_HEAD_KEY};
+    use near_primitives::block::Tip;
+    use near_primitives::hash::CryptoHash;
+    use near_primitives::shard_layout::ShardLayout;
+    use near_primitives::types::{BlockHeight, EpochId};
+    use std::sync::Arc;
+
+    fn set_final_height(store: &Store, height: BlockHeight) {
+        let tip = Tip {
+            height,
+            last_block_hash: CryptoHash::default(),
+            prev_block_hash: CryptoHash::default(),
+            epoch_id: EpochId::default(),
+            next_epoch_id: EpochId::default(),
+        };
+        let mut store_update = store.store_update();
+        store_update.set_ser(DBCol::BlockMisc, FINAL_HEAD_KEY, &tip).unwrap();
+        store_update.commit().unwrap();
+    }
+
+    fn migration(hot_store: &Store, cold_db: &Arc<ColdDB<TestDB>>) -> ColdMigration {
+        ColdMigration::new(
+            hot_store.clone(),
+            cold_db.clone(),
+            Box::new(|_| Ok(ShardLayout::v0_single_shard())),
+            ColdMigrationConfig { max_heights_per_step: 2, ..ColdMigrationConfig::default() },
+        )
+    }
+
+    fn progress(migration: &ColdMigration) -> Option<BlockHeight> {
+        migration.progress().unwrap().map(|progress| progress.copied_height)
+    }
+
+    #[test]
+    fn test_migration_progress() {
+        let hot = TestDB::new();
+        let hot_store = NodeStorage::new(hot.clone()).get_store(Temperature::Hot);
+        let cold_db = Arc::new(ColdDB::new(hot, TestDB::default()));
+        let migration = migration(&hot_store, &cold_db);
+
+        // Nothing to do without final head.
+        assert_eq!(migration.run_step().unwrap(), 0);
+        assert_eq!(progress(&migration), None);
+
+        // The first step copies everything there is.
+        set_final_height(&hot_store, 5);
+        assert_eq!(migration.run_step().unwrap(), 1);
+        assert_eq!(progress(&migration), Some(5));
+
+        // Then at most `max_heights_per_step` heights at a time.
+        set_final_height(&hot_store, 8);
+        assert_eq!(migration.run_step().unwrap(), 2);
+        assert_eq!(progress(&migration), Some(7));
+        let cold_head: Option<BlockHeight> =
+            hot_store.get_ser(DBCol::BlockMisc, COLD_HEAD_KEY).unwrap();
+        assert_eq!(cold_head, Some(7));
+
+        // A restarted migration resumes where the previous one stopped.
+        let migration = self::migration(&hot_store, &cold_db);
+        assert_eq!(migration.progress().unwrap(), Some(ColdMigrationProgress { copied_height: 7 }));
+        assert_eq!(migration.run_step().unwrap(), 1);
+        assert_eq!(migration.run_step().unwrap(), 0);
+        assert_eq!(progress(&migration), Some(8));
+    }
+}
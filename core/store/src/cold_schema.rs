@@ -0,0 +1,227 @@
+//! Versioning of the format of the cold database.
+//!
+//! Cold storage keeps some columns in a format different from the one they
+//! have in hot storage (see [`ColdColumnFormat`]) and archives are kept
+//! forever, so a change to that format which isn’t accompanied by a migration
+//! would silently corrupt them.  The format is therefore versioned separately
+//! from the database version, which tracks the format of hot storage: the cold
+//! database records its [`ColdSchemaVersion`] in [`DBCol::DbVersion`] under
+//! [`COLD_SCHEMA_VERSION_KEY`].  Archives written before the version was
+//! recorded don’t have the key and are at version 0.
+//!
+//! When the storage is opened, a cold database with a version newer than
+//! [`COLD_SCHEMA_VERSION`] is refused and one with an older version is
+//! migrated, one version at a time, with the migrations listed in
+//! [`MIGRATIONS`].  The version is recorded after every migration so that an
+//! interrupted migration resumes with the first one which hasn’t completed.
+//! Migrations therefore must cope with their data being partially converted
+//! already.
+//!
+//! Changing the format of a column, for example once DBCol::BlockHeader moves
+//! to cold storage, involves updating [`ColdColumnFormat::of`], bumping
+//! [`COLD_SCHEMA_VERSION`] and adding a migration converting existing data.
+//!
+//! [`ColdColumnFormat`]: crate::db::ColdColumnFormat
+//! [`ColdColumnFormat::of`]: crate::db::ColdColumnFormat::of
+use std::io;
+
+use anyhow::Context;
+
+use crate::db::{DBTransaction, Database};
+use crate::DBCol;
+
+/// Version of the format of the cold database.
+pub type ColdSchemaVersion = u32;
+
+/// Current version of the format of the cold database.
+pub const COLD_SCHEMA_VERSION: ColdSchemaVersion = 1;
+
+/// Key for the cold schema version entry in DBCol::DbVersion of the cold
+/// database.
+///
+/// The key holds [`ColdSchemaVersion`] value serialised to a string.
+pub const COLD_SCHEMA_VERSION_KEY: &[u8; 19] = b"COLD_SCHEMA_VERSION";
+
+/// Migrates the raw cold database, i.e. without the key and value adjustments
+/// done by [`crate::db::ColdDB`], from a version to the next one.
+type Migration = fn(&dyn Database) -> anyhow::Result<()>;
+
+/// Migrations of the cold database.  The migration at index `i` migrates the
+/// database from version `i` to version `i + 1`.
+const MIGRATIONS: [Migration; COLD_SCHEMA_VERSION as usize] = [migrate_0_to_1];
+
+/// Archives at version 0 have the same format as at version 1, which is the
+/// first version recorded in the database.
+fn migrate_0_to_1(_db: &dyn Database) -> anyhow::Result<()> {
+    Ok(())
+}
+
+/// Reads the schema version of a cold database.
+///
+/// Returns 0 if the version isn’t recorded, which is the case for archives
+/// written before the schema was versioned.
+pub fn read_version(db: &dyn Database) -> io::Result<ColdSchemaVersion> {
+    let bytes = match db.get_raw_bytes(DBCol::DbVersion, COLD_SCHEMA_VERSION_KEY)? {
+        Some(bytes) => bytes,
+        None => return Ok(0),
+    };
+    std::str::from_utf8(&bytes).ok().and_then(|value| value.parse().ok()).ok_or_else(|| {
+        let msg = format!("invalid cold schema version: {bytes:?}; cold database is corrupted");
+        io::Error::new(io::ErrorKind::Other, msg)
+    })
+}
+
+/// Records the schema version of a cold database.
+pub(crate) fn write_version(db: &dyn Database, version: ColdSchemaVersion) -> io::Result<()> {
+    let mut transaction = DBTransaction::new();
+    let version = version.to_string().into_bytes();
+    transaction.set(DBCol::DbVersion, COLD_SCHEMA_VERSION_KEY.to_vec(), version);
+    db.write(transaction)
+}
+
+/// Migrates the raw cold database to [`COLD_SCHEMA_VERSION`].
+///
+/// The caller is expected to have checked that the database isn’t newer than
+/// that.  Does nothing if the database is at the current version already.
+pub(crate) fn migrate(db: &dyn Database) -> anyhow::Result<()> {
+    migrate_with(db, &MIGRATIONS)
+}
+
+fn migrate_with(db: &dyn Database, migrations: &[Migration]) -> anyhow::Result<()> {
+    let latest = migrations.len() as ColdSchemaVersion;
+    let version = read_version(db)?;
+    anyhow::ensure!(
+        version <= latest,
+        "cold schema version {version} is newer than supported version {latest}"
+    );
+    for version in version..latest {
+        tracing::info!(target: "store",
+                       "Migrating the cold database schema from version {version} to {}",
+                       version + 1);
+        migrations[version as usize](db)
+            .with_context(|| format!("migrating cold schema from version {version}"))?;
+        write_version(db, version + 1)?;
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::db::{ColdDB, TestDB};
+    use crate::{Mode, NodeStorage, StoreConfig, StoreOpenerError, Temperature};
+
+    const HEIGHT_LE: &[u8] = &42u64.to_le_bytes();
+    const HEIGHT_BE: &[u8] = &42u64.to_be_bytes();
+    const HASH: &[u8] = &[1; 32];
+    const BROKEN_KEY: &[u8] = b"BROKEN";
+
+    fn set(db: &dyn Database, col: DBCol, key: &[u8], value: &[u8]) {
+        let mut transaction = DBTransaction::new();
+        transaction.set(col, key.to_vec(), value.to_vec());
+        db.write(transaction).unwrap();
+    }
+
+    /// Writes data the way ColdDB did before the schema was versioned.
+    fn unversioned_archive() -> TestDB {
+        let db = TestDB::default();
+        set(&db, DBCol::BlockHeight, HEIGHT_BE, HASH);
+        set(&db, DBCol::State, HASH, b"node");
+        db
+    }
+
+    #[test]
+    fn test_migrate_unversioned_archive() {
+        let db = unversioned_archive();
+        assert_eq!(read_version(&db).unwrap(), 0);
+        migrate(&db).unwrap();
+        assert_eq!(read_version(&db).unwrap(), COLD_SCHEMA_VERSION);
+        // Migrating again is a no-op.
+        migrate(&db).unwrap();
+        assert_eq!(read_version(&db).unwrap(), COLD_SCHEMA_VERSION);
+
+        let cold = ColdDB::new(TestDB::new(), db);
+        let block_hash = cold.get_raw_bytes(DBCol::BlockHeight, HEIGHT_LE).unwrap();
+        assert_eq!(block_hash.as_deref(), Some(HASH));
+        let key = [&[0; 8], HASH].concat();
+        let node = cold.get_with_rc_stripped(DBCol::State, &key).unwrap();
+        assert_eq!(node.as_deref(), Some(&b"node"[..]));
+    }
+
+    /// Converts keys of DBCol::BlockHeight to little-endian.
+    fn height_to_le(db: &dyn Database) -> anyhow::Result<()> {
+        for item in db.iter_raw_bytes(DBCol::BlockHeight).collect::<Vec<_>>() {
+            let (key, value) = item?;
+            let height = u64::from_be_bytes(key[..].try_into()?);
+            let mut transaction = DBTransaction::new();
+            transaction.delete(DBCol::BlockHeight, key.into_vec());
+            transaction.set(DBCol::BlockHeight, height.to_le_bytes().to_vec(), value.into_vec());
+            db.write(transaction)?;
+        }
+        Ok(())
+    }
+
+    fn fail_if_broken(db: &dyn Database) -> anyhow::Result<()> {
+        let broken = db.get_raw_bytes(DBCol::DbVersion, BROKEN_KEY)?.is_some();
+        anyhow::ensure!(!broken, "broken database");
+        Ok(())
+    }
+
+    #[test]
+    fn test_interrupted_migration() {
+        let migrations: [Migration; 3] = [migrate_0_to_1, height_to_le, fail_if_broken];
+        let db = unversioned_archive();
+        set(&db, DBCol::DbVersion, BROKEN_KEY, b"");
+        migrate_with(&db, &migrations).unwrap_err();
+        assert_eq!(read_version(&db).unwrap(), 2);
+        assert_eq!(db.get_raw_bytes(DBCol::BlockHeight, HEIGHT_LE).unwrap().as_deref(), Some(HASH));
+
+        // The migration resumes from the failed step.
+        let mut transaction = DBTransaction::new();
+        transaction.delete(DBCol::DbVersion, BROKEN_KEY.to_vec());
+        db.write(transaction).unwrap();
+        migrate_with(&db, &migrations).unwrap();
+        assert_eq!(read_version(&db).unwrap(), 3);
+        assert_eq!(db.get_raw_bytes(DBCol::BlockHeight, HEIGHT_LE).unwrap().as_deref(), Some(HASH));
+
+        // Databases newer than the migrations are refused.
+        migrate_with(&db, &migrations[..2]).unwrap_err();
+    }
+
+    #[test]
+    fn test_open_older_archive() {
+        let tmp_dir = tempfile::tempdir().unwrap();
+        let config = StoreConfig::test_config();
+        let opener = NodeStorage::opener(tmp_dir.path(), &config, Some(&config));
+        let cold_path = tmp_dir.path().join("cold-data");
+
+        let storage = opener.open().unwrap();
+        let cold = storage.cold_db().unwrap();
+        assert_eq!(read_version(&*cold).unwrap(), COLD_SCHEMA_VERSION);
+        drop((cold, storage));
+
+        // Make the archive look like one written before the schema was
+        // versioned.
+        let db = crate::db::RocksDB::open(&cold_path, &config, Mode::ReadWrite, Temperature::Cold)
+            .unwrap();
+        let mut transaction = DBTransaction::new();
+        transaction.delete(DBCol::DbVersion, COLD_SCHEMA_VERSION_KEY.to_vec());
+        db.write(transaction).unwrap();
+        drop(db);
+
+        assert!(matches!(
+            opener.open_in_mode(Mode::ReadOnly),
+            Err(StoreOpenerError::ColdSchemaMismatchOnRead { got: 0, .. })
+        ));
+        let storage = opener.open().unwrap();
+        assert_eq!(read_version(&*storage.cold_db().unwrap()).unwrap(), COLD_SCHEMA_VERSION);
+        drop(storage);
+
+        // Archives written by newer versions are refused.
+        let db = crate::db::RocksDB::open(&cold_path, &config, Mode::ReadWrite, Temperature::Cold)
+            .unwrap();
+        write_version(&db, COLD_SCHEMA_VERSION + 1).unwrap();
+        drop(db);
+        assert!(matches!(opener.open(), Err(StoreOpenerError::ColdSchemaTooNew { .. })));
+    }
+}
@@ -1,7 +1,7 @@
 use crate::columns::DBKeyType;
 use crate::refcount::add_positive_refcount;
 use crate::trie::TrieRefcountChange;
-use crate::{DBCol, DBTransaction, Database, Store, TrieChanges};
+use crate::{DBCol, DBTransaction, Database, Store, TrieChanges, COLD_HEAD_KEY};
 
 use borsh::BorshDeserialize;
 use near_primitives::block::Block;
@@ -65,6 +65,33 @@ pub fn update_cold_db(
     Ok(())
 }
 
+/// Advances cold head stored in hot store to `height`.
+///
+/// Hot store garbage collection of archival nodes never goes past the cold
+/// head, so before moving it we verify that the block at `height` (if there is
+/// one) is actually present in cold db.  Should be called after
+/// `update_cold_db` for the same height has succeeded.
+pub fn update_cold_head(
+    cold_db: &dyn Database,
+    hot_store: &Store,
+    height: &BlockHeight,
+) -> io::Result<()> {
+    let _span = tracing::debug_span!(target: "store", "update cold head", height = height);
+
+    if let Some(block_hash) = hot_store.get(DBCol::BlockHeight, &height.to_le_bytes())? {
+        if cold_db.get_raw_bytes(DBCol::Block, &block_hash)?.is_none() {
+            return Err(io::Error::new(
+                io::ErrorKind::NotFound,
+                format!("block at height {} is missing in cold storage", height),
+            ));
+        }
+    }
+
+    let mut store_update = hot_store.store_update();
+    store_update.set_ser(DBCol::BlockMisc, COLD_HEAD_KEY, height)?;
+    store_update.commit()
+}
+
 /// Gets values for given keys in a column from provided hot_store.
 /// Creates a transaction based on that values with set DBOp s.
 /// Writes that transaction to cold_db.
@@ -84,29 +111,58 @@ fn copy_from_store(
         // added.
         let data = hot_store.get(col, &key)?;
         if let Some(value) = data {
-            // Database checks col.is_rc() on read and write
-            // And in every way expects rc columns to be written with rc
-            //
-            // TODO: As an optimisation, we might consider breaking the
-            // abstraction layer.  Since we’re always writing to cold database,
-            // rather than using `cold_db: &dyn Database` argument we cloud have
-            // `cold_db: &ColdDB` and then some custom function which lets us
-            // write raw bytes.
-            if col.is_rc() {
-                transaction.update_refcount(
-                    col,
-                    key,
-                    add_positive_refcount(&value, std::num::NonZeroU32::new(1).unwrap()),
-                );
-            } else {
-                transaction.set(col, key, value);
-            }
+            add_to_transaction(&mut transaction, col, key, value);
         }
     }
     cold_db.write(transaction)?;
     return Ok(());
 }
 
+/// Adds an operation writing a value of a column to a transaction for cold db.
+fn add_to_transaction(transaction: &mut DBTransaction, col: DBCol, key: StoreKey, value: Vec<u8>) {
+    // Database checks col.is_rc() on read and write
+    // And in every way expects rc columns to be written with rc
+    //
+    // TODO: As an optimisation, we might consider breaking the
+    // abstraction layer.  Since we’re always writing to cold database,
+    // rather than using `cold_db: &dyn Database` argument we cloud have
+    // `cold_db: &ColdDB` and then some custom function which lets us
+    // write raw bytes.
+    if col.is_rc() {
+        transaction.update_refcount(
+            col,
+            key,
+            add_positive_refcount(&value, std::num::NonZeroU32::new(1).unwrap()),
+        );
+    } else {
+        transaction.set(col, key, value);
+    }
+}
+
+/// Copies everything in cold columns of provided hot store to cold db.
+///
+/// Used to populate an empty cold db, which per-height updates wouldn’t do for
+/// data not produced by any block, e.g. genesis state.
+pub fn copy_all_to_cold_db(cold_db: &dyn Database, hot_store: &Store) -> io::Result<()> {
+    const BATCH_SIZE: usize = 10_000;
+    for col in DBCol::iter() {
+        if !col.is_cold() {
+            continue;
+        }
+        let _span = tracing::debug_span!(target: "store", "copy column to cold db", col = %col);
+        let mut transaction = DBTransaction::new();
+        for item in hot_store.iter(col) {
+            let (key, value) = item?;
+            add_to_transaction(&mut transaction, col, key.into(), value.into());
+            if transaction.ops.len() >= BATCH_SIZE {
+                cold_db.write(std::mem::take(&mut transaction))?;
+            }
+        }
+        cold_db.write(transaction)?;
+    }
+    Ok(())
+}
+
 pub fn test_cold_genesis_update(cold_db: &dyn Database, hot_store: &Store) -> io::Result<()> {
     let mut store_with_cache = StoreWithCache { store: hot_store, cache: StoreCache::new() };
     for col in DBCol::iter() {
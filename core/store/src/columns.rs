@@ -242,6 +242,65 @@ pub enum DBCol {
     /// *Rows*: OutcomeId (CryptoHash) || BlockHash (CryptoHash)
     /// *Column type*: ExecutionOutcomeWithProof
     TransactionResultForBlock,
+    /// Progress of building the states of the shards which a shard is split
+    /// into, used to resume an interrupted split.
+    /// - *Rows*: ShardUId of the parent shard
+    /// - *Column type*: StateSplitCheckpoint
+    StateSplitCheckpoints,
+    /// Markers of incoming receipt proofs which were verified against the
+    /// outgoing receipts root of the chunk they come from.
+    /// - *Rows*: ChunkHash || ReceiptProofHash (hash of the ReceiptProof)
+    /// - *Column type*: empty
+    VerifiedReceiptProofs,
+    /// Most recent decisions to ban or disconnect peers, see
+    /// `near_network::peer_manager::audit_log`.
+    /// - *Rows*: index of the entry (u64, little endian)
+    /// - *Column type*: `near_network::peer_manager::audit_log::Entry`
+    PeerAuditLog,
+    /// Progress of the migration of data from hot to cold storage, see
+    /// `near_store::cold_migration`.
+    /// - *Rows*: single row with key `PROGRESS`
+    /// - *Column type*: `near_store::cold_migration::ColdMigrationProgress`
+    ColdMigrationProgress,
+    /// Edges of the routing graph, persisted so that they survive a restart,
+    /// see `near_network::routing::tombstones`.
+    /// - *Rows*: pair of peer_ids of the edge
+    /// - *Column type*: `near_network::routing::Edge`
+    RoutingEdges,
+    /// Index of events emitted by contracts, see
+    /// `near_chain::contract_events`.
+    /// - *Rows*: AccountId || standard || event name || BlockHeight (big
+    ///   endian) || BlockHash || OutcomeId || index of the log, with the
+    ///   variable length parts terminated by a zero byte
+    /// - *Column type*: `near_chain::contract_events::Record`
+    ContractEvents,
+    /// Index of the accounts contracts are deployed to, see
+    /// `near_chain::contract_code_index`.
+    /// - *Rows*: hash of the code || AccountId
+    /// - *Column type*: `near_chain::contract_code_index::Record`
+    ContractCodeAccounts,
+    /// Block on the canonical chain which produced an outcome, written by
+    /// `near_chain::outcome_compaction` once the block is final, so that
+    /// lookups don't have to go through the outcomes on all forks.
+    /// - *Rows*: OutcomeId (CryptoHash)
+    /// - *Column type*: BlockHash (CryptoHash)
+    CanonicalOutcomeBlock,
+    /// Approvals collected by doomslug, reloaded after a restart, see
+    /// `near_client::persisted_approvals`.
+    /// - *Rows*: target BlockHeight (big endian) || AccountId
+    /// - *Column type*: Approval
+    DoomslugApprovals,
+    /// Orphan blocks close to the head, reloaded after a restart, see
+    /// `near_chain::persisted_orphans`.
+    /// - *Rows*: BlockHash (CryptoHash)
+    /// - *Column type*: Block
+    Orphans,
+    /// BLS keys of validators registered through block headers, see
+    /// `near_epoch_manager::EpochManager::register_bls_key`.  Only written
+    /// with the `protocol_feature_bls_approvals` feature.
+    /// - *Rows*: AccountId
+    /// - *Column type*: `near_crypto::bls::PublicKey`
+    BlsKeys,
     /// Flat state contents. Used to get `ValueRef` by trie key faster than doing a trie lookup.
     /// - *Rows*: trie key (Vec<u8>)
     /// - *Column type*: ValueRef
@@ -286,11 +345,17 @@ pub enum DBKeyType {
     TrieNodeOrValueHash,
     TrieKey,
     ReceiptHash,
+    ReceiptProofHash,
+    AuditLogIndex,
     TransactionHash,
     OutcomeId,
     ContractCacheKey,
     PartId,
     ColumnId,
+    /// Standard and name of an event emitted by a contract.
+    ContractEventName,
+    /// Hash of the code of a contract.
+    ContractCodeHash,
 }
 
 impl DBCol {
@@ -455,6 +520,23 @@ impl DBCol {
             DBCol::HeaderHashesByHeight => &[DBKeyType::BlockHeight],
             DBCol::StateChangesForSplitStates => &[DBKeyType::BlockHash, DBKeyType::ShardId],
             DBCol::TransactionResultForBlock => &[DBKeyType::OutcomeId, DBKeyType::BlockHash],
+            DBCol::StateSplitCheckpoints => &[DBKeyType::ShardUId],
+            DBCol::VerifiedReceiptProofs => &[DBKeyType::ChunkHash, DBKeyType::ReceiptProofHash],
+            DBCol::PeerAuditLog => &[DBKeyType::AuditLogIndex],
+            DBCol::ColdMigrationProgress => &[DBKeyType::StringLiteral],
+            DBCol::RoutingEdges => &[DBKeyType::PeerId, DBKeyType::PeerId],
+            DBCol::ContractEvents => &[
+                DBKeyType::AccountId,
+                DBKeyType::ContractEventName,
+                DBKeyType::BlockHeight,
+                DBKeyType::BlockHash,
+                DBKeyType::OutcomeId,
+            ],
+            DBCol::ContractCodeAccounts => &[DBKeyType::ContractCodeHash, DBKeyType::AccountId],
+            DBCol::CanonicalOutcomeBlock => &[DBKeyType::OutcomeId],
+            DBCol::DoomslugApprovals => &[DBKeyType::BlockHeight, DBKeyType::AccountId],
+            DBCol::Orphans => &[DBKeyType::BlockHash],
+            DBCol::BlsKeys => &[DBKeyType::AccountId],
             #[cfg(feature = "protocol_feature_flat_state")]
             DBCol::FlatState => &[DBKeyType::TrieKey],
             #[cfg(feature = "protocol_feature_flat_state")]
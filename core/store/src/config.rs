@@ -59,6 +59,15 @@ pub struct StoreConfig {
     /// This config option is temporary and will be removed once flat storage is implemented.
     pub sweat_prefetch_senders: Vec<String>,
 
+    /// Detection of degraded storage latency, during which the node sheds
+    /// optional load such as prefetching, view queries and debug endpoints.
+    pub health: crate::StorageHealthConfig,
+
+    /// Maximum size of the database directory, approaching which the node
+    /// shortens garbage collection retention, stops optional recorders and
+    /// eventually raises an alert.
+    pub disk_budget: crate::DiskBudgetConfig,
+
     /// Path where to create RocksDB checkpoints during database migrations or
     /// `false` to disable that feature.
     ///
@@ -203,6 +212,9 @@ impl Default for StoreConfig {
                 "sweat_the_oracle.testnet".to_owned(),
             ],
 
+            health: Default::default(),
+            disk_budget: Default::default(),
+
             migration_snapshot: Default::default(),
         }
     }
@@ -4,15 +4,21 @@ use crate::DBCol;
 
 #[cfg(feature = "cold_store")]
 mod colddb;
+mod namespaced;
 pub mod refcount;
 pub(crate) mod rocksdb;
 mod slice;
+#[cfg(feature = "cold_store")]
+mod splitdb;
 mod testdb;
 
 #[cfg(feature = "cold_store")]
-pub use self::colddb::ColdDB;
+pub use self::colddb::{ColdColumnFormat, ColdDB, ColdKeyFormat};
+pub use self::namespaced::NamespacedDB;
 pub use self::rocksdb::RocksDB;
 pub use self::slice::DBSlice;
+#[cfg(feature = "cold_store")]
+pub use self::splitdb::SplitDB;
 pub use self::testdb::TestDB;
 
 pub const HEAD_KEY: &[u8; 4] = b"HEAD";
@@ -21,8 +27,20 @@ pub const CHUNK_TAIL_KEY: &[u8; 10] = b"CHUNK_TAIL";
 pub const FORK_TAIL_KEY: &[u8; 9] = b"FORK_TAIL";
 pub const HEADER_HEAD_KEY: &[u8; 11] = b"HEADER_HEAD";
 pub const FINAL_HEAD_KEY: &[u8; 10] = b"FINAL_HEAD";
+/// Height up to which all blocks have been copied to cold storage and verified
+/// there.  Kept in the hot store.
+pub const COLD_HEAD_KEY: &[u8; 9] = b"COLD_HEAD";
 pub const LATEST_KNOWN_KEY: &[u8; 12] = b"LATEST_KNOWN";
+/// Hashes of the blocks processed most recently and of their chunks.
+pub const RECENTLY_PROCESSED_KEY: &[u8; 18] = b"RECENTLY_PROCESSED";
+/// Chunks whose parts were being requested, to request them again after a
+/// restart.
+pub const CHUNK_REQUESTS_KEY: &[u8; 14] = b"CHUNK_REQUESTS";
+/// Height up to which the outcomes of blocks off the canonical chain have been
+/// removed, see `near_chain::outcome_compaction`.
+pub const OUTCOME_COMPACTION_KEY: &[u8; 18] = b"OUTCOME_COMPACTION";
 pub const LARGEST_TARGET_HEIGHT_KEY: &[u8; 21] = b"LARGEST_TARGET_HEIGHT";
+pub const LARGEST_ENDORSED_HEIGHT_KEY: &[u8; 23] = b"LARGEST_ENDORSED_HEIGHT";
 pub const GENESIS_JSON_HASH_KEY: &[u8; 17] = b"GENESIS_JSON_HASH";
 pub const GENESIS_STATE_ROOTS_KEY: &[u8; 19] = b"GENESIS_STATE_ROOTS";
 
@@ -117,6 +135,24 @@ pub trait Database: Sync + Send {
     /// are returned in lexicographical order sorted by the key.
     fn iter_prefix<'a>(&'a self, col: DBCol, key_prefix: &'a [u8]) -> DBIterator<'a>;
 
+    /// Iterate over items in given column whose keys start with given prefix,
+    /// starting with the first key which isn't lower than `start`.
+    ///
+    /// This is equivalent to [`Self::iter_prefix`] skipping keys lower than
+    /// `start`, which is what the default implementation does.  Databases
+    /// which can seek override it so that the skipped keys aren't read.
+    fn iter_prefix_from<'a>(
+        &'a self,
+        col: DBCol,
+        key_prefix: &'a [u8],
+        start: &'a [u8],
+    ) -> DBIterator<'a> {
+        Box::new(self.iter_prefix(col, key_prefix).filter(move |item| match item {
+            Ok((key, _)) => **key >= *start,
+            Err(_) => true,
+        }))
+    }
+
     /// Iterate over items in given column bypassing reference count decoding if
     /// any.
     ///
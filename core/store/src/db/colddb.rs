@@ -1,6 +1,48 @@
 use crate::db::{DBIterator, DBOp, DBSlice, DBTransaction, Database};
 use crate::DBCol;
 
+/// Encoding of the keys of a column in cold storage.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum ColdKeyFormat {
+    /// Keys are the same as in hot storage.
+    Plain,
+    /// Keys are block heights, little-endian in hot storage and big-endian in
+    /// cold storage.
+    BigEndianHeight,
+    /// Keys are `ShardUId || CryptoHash` in hot storage and the ShardUId prefix
+    /// is stripped in cold storage.
+    WithoutShardUId,
+}
+
+/// Describes how a column is stored in cold storage.
+///
+/// This is the format of the current cold schema version (see
+/// [`crate::cold_schema`]).  Changing the format of a column requires bumping
+/// the version and adding a migration of the existing archives.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct ColdColumnFormat {
+    pub key: ColdKeyFormat,
+    /// Whether values are stored without reference count.  This is the case for
+    /// all reference counted columns.
+    pub strips_refcount: bool,
+}
+
+impl ColdColumnFormat {
+    /// Returns the format the column is stored in in cold storage.
+    pub fn of(col: DBCol) -> Self {
+        let key = match col {
+            DBCol::BlockHeight
+            | DBCol::BlockPerHeight
+            | DBCol::ChunkHashesByHeight
+            | DBCol::ProcessedBlockHeights
+            | DBCol::HeaderHashesByHeight => ColdKeyFormat::BigEndianHeight,
+            DBCol::State => ColdKeyFormat::WithoutShardUId,
+            _ => ColdKeyFormat::Plain,
+        };
+        Self { key, strips_refcount: col.is_rc() }
+    }
+}
+
 /// A database which provides access to the cold storage.
 ///
 /// Some of the data we’re storing in cold storage is saved in slightly
@@ -28,9 +70,9 @@ use crate::DBCol;
 /// differently.
 ///
 /// Note however that this also means that some operations, specifically
-/// iterations, are limited and not implemented for all columns.  When trying to
-/// implement over unsupported column the code will panic.  Refer to particular
-/// iteration method in Database trait implementation for more details.
+/// iterations, are limited.  Iterating by prefix is implemented only for some
+/// columns and will panic for the others.  Refer to particular iteration method
+/// in Database trait implementation for more details.
 ///
 /// Lastly, since no data is ever deleted from cold storage, trying to decrease
 /// reference of a value count or delete data is ignored and if debug assertions
@@ -96,7 +138,7 @@ impl<D: Database> super::Database for ColdDB<D> {
             return self.hot.get_raw_bytes(col, key);
         }
         match self.get_cold_impl(col, key) {
-            Ok(Some(value)) if col.is_rc() => {
+            Ok(Some(value)) if ColdColumnFormat::of(col).strips_refcount => {
                 // Since we’ve stripped the reference count from the data stored
                 // in the database, we need to reintroduce it.  In practice this
                 // should be never called in production since reading of rc
@@ -120,32 +162,26 @@ impl<D: Database> super::Database for ColdDB<D> {
 
     /// Iterates over all values in a column.
     ///
-    /// This is implemented only for a few columns.  Specifically for Block,
-    /// BlockHeader, ChunkHashesByHeight and EpochInfo.  It’ll panic if used for
-    /// any other column.
+    /// As with reads, BlockHeader is iterated over in the hot database and all
+    /// other columns in the cold database (see [`Self::is_hot_column`]).
     ///
-    /// Furthermore, because key of ChunkHashesByHeight is modified in cold
-    /// storage, the order of iteration of that column is different than if it
-    /// would be in hot storage.
+    /// Keys are converted back to the format used in hot storage (see
+    /// [`get_hot_key`]) and values of reference counted columns are returned
+    /// without reference count, as with any other database.  Furthermore,
+    /// because keys of columns indexed by height are big-endian in cold
+    /// storage, the order of iteration of those columns is different than if
+    /// they would be in hot storage.
     fn iter<'a>(&'a self, column: DBCol) -> DBIterator<'a> {
-        match column {
-            DBCol::BlockHeader => return self.hot.iter(column),
-            // Those are the only columns we’re ever iterating over.
-            DBCol::Block | DBCol::ChunkHashesByHeight | DBCol::EpochInfo => (),
-            _ => panic!("iter on cold storage is not supported for {column}"),
+        if Self::is_hot_column(column) {
+            return self.hot.iter(column);
         }
+        // Values are stored without reference count so iter_raw_bytes already
+        // returns what iter is supposed to.
         let it = self.cold.iter_raw_bytes(column);
-        if column == DBCol::ChunkHashesByHeight {
-            // For the column we need to swap bytes in the key.
-            Box::new(it.map(|result| {
-                let (mut key, value) = result?;
-                let num = u64::from_le_bytes(key.as_ref().try_into().unwrap());
-                key.as_mut().copy_from_slice(&num.to_be_bytes());
-                Ok((key, value))
-            }))
-        } else {
-            it
-        }
+        Box::new(it.map(move |result| {
+            let (key, value) = result?;
+            Ok((get_hot_key(column, &key).unwrap_or(key), value))
+        }))
     }
 
     /// Iterates over values in a given column whose key has given prefix.
@@ -213,8 +249,9 @@ impl<D: Database> super::Database for ColdDB<D> {
 /// Returns key as used in cold database for given column in hot database.
 ///
 /// Some columns use different keys in cold storage compared to what they use in
-/// hot storage.  Most column use the same key and for those this function
-/// returns None.  However, there are two adjustments that the method performs:
+/// hot storage (see [`ColdColumnFormat`]).  Most column use the same key and for
+/// those this function returns None.  However, there are two adjustments that
+/// the method performs:
 ///
 /// 1. Due to historical reasons, when integers are keys they are stored as
 ///    little-endian.  In cold storage we’re swapping the bytes for those
@@ -229,24 +266,42 @@ impl<D: Database> super::Database for ColdDB<D> {
 /// When doing the transformations of the key, the new value is stored in the
 /// provided `buffer` and the function returns a slice pointing at it.
 fn get_cold_key<'a>(col: DBCol, key: &[u8], buffer: &'a mut [u8; 32]) -> Option<&'a [u8]> {
-    match col {
-        DBCol::BlockHeight
-        | DBCol::BlockPerHeight
-        | DBCol::ChunkHashesByHeight
-        | DBCol::ProcessedBlockHeights
-        | DBCol::HeaderHashesByHeight => {
+    match ColdColumnFormat::of(col).key {
+        ColdKeyFormat::BigEndianHeight => {
             // Key is `little_endian(height)`
             let num = u64::from_le_bytes(key.try_into().unwrap());
             buffer[..8].copy_from_slice(&num.to_be_bytes());
             Some(&buffer[..8])
         }
-        DBCol::State => {
+        ColdKeyFormat::WithoutShardUId => {
             // Key is `ShardUId || CryptoHash(node_or_value)`.  We’re stripping
             // the ShardUId.
             buffer[..32].copy_from_slice(&key[8..]);
             Some(&buffer[..32])
         }
-        _ => None,
+        ColdKeyFormat::Plain => None,
+    }
+}
+
+/// Returns key as used in hot database for given column in cold database.
+///
+/// This is the inverse of [`get_cold_key`] and as with that function returns
+/// None for columns whose keys are the same in both databases.  Keys of
+/// columns indexed by height are converted back to little-endian.
+///
+/// Since the ShardUId prefix of DBCol::State keys isn’t stored in cold
+/// storage, it can’t be restored.  Instead, keys are prefixed by a synthetic
+/// all-zero ShardUId.  ColdDB strips the prefix whatever it is, so such a key
+/// can still be used to read the value from cold storage.
+fn get_hot_key(col: DBCol, key: &[u8]) -> Option<Box<[u8]>> {
+    match ColdColumnFormat::of(col).key {
+        ColdKeyFormat::BigEndianHeight => {
+            // Key is `big_endian(height)`
+            let num = u64::from_be_bytes(key.try_into().unwrap());
+            Some(num.to_le_bytes().into())
+        }
+        ColdKeyFormat::WithoutShardUId => Some([&[0; 8], key].concat().into_boxed_slice()),
+        ColdKeyFormat::Plain => None,
     }
 }
 
@@ -271,7 +326,7 @@ fn adjust_op(op: &mut DBOp) -> bool {
             true
         }
         DBOp::UpdateRefcount { col, key, value } => {
-            assert!(col.is_rc());
+            assert!(ColdColumnFormat::of(*col).strips_refcount);
             let value = core::mem::take(value);
             if let Some(value) = crate::db::refcount::strip_refcount(value) {
                 *op = DBOp::Set { col: *col, key: core::mem::take(key), value };
@@ -460,6 +515,8 @@ mod test {
             .map(|col| set(*col, HASH))
             .collect();
         ops.push(set(DBCol::ChunkHashesByHeight, HEIGHT_LE));
+        ops.push(set(DBCol::BlockHeight, HEIGHT_LE));
+        ops.push(set(DBCol::State, &[SHARD, HASH].concat()));
         db.write(DBTransaction { ops }).unwrap();
 
         // BlockHeader is special since it’s read from hot database.  Note that
@@ -476,13 +533,18 @@ mod test {
             .unwrap();
 
         let mut result = Vec::<String>::new();
-        for col in [DBCol::BlockHeader, DBCol::Block, DBCol::EpochInfo, DBCol::ChunkHashesByHeight]
-        {
-            let dbs: [(bool, &dyn Database); 2] = [(false, &db), (true, &db.cold)];
+        let columns = [
+            DBCol::BlockHeader,
+            DBCol::Block,
+            DBCol::EpochInfo,
+            DBCol::ChunkHashesByHeight,
+            DBCol::BlockHeight,
+            DBCol::State,
+        ];
+        for col in columns {
             result.push(col.to_string());
-            for (is_raw, db) in dbs {
-                let name = if is_raw { "raw " } else { "cold" };
-                for item in db.iter(col) {
+            for (name, iter) in [("cold", db.iter(col)), ("raw ", db.cold.iter_raw_bytes(col))] {
+                for item in iter {
                     let (key, value) = item.unwrap();
                     let value = pretty_value(Some(value.as_ref()), false);
                     let key = pretty_key(&key);
@@ -507,7 +569,20 @@ mod test {
         ChunkHashesByHeight
         [cold] (le(42), FooBar)
         [raw ] (be(42), FooBar)
+        BlockHeight
+        [cold] (le(42), FooBar)
+        [raw ] (be(42), FooBar)
+        State
+        [cold] (`le(0) || 11111111111111111111111111111111`, FooBar)
+        [raw ] (11111111111111111111111111111111, FooBar)
         "###);
+
+        // Keys of State returned by the iterator can be used to read values.
+        for item in db.iter(DBCol::State) {
+            let (key, _) = item.unwrap();
+            let got = db.get_with_rc_stripped(DBCol::State, &key).unwrap();
+            assert_eq!(Some(VALUE), got.as_deref());
+        }
     }
 
     /// Tests that stripping and adding refcount works correctly.
@@ -0,0 +1,152 @@
+use std::io;
+use std::sync::Arc;
+
+use crate::db::{DBIterator, DBOp, DBSlice, DBTransaction, Database};
+use crate::{DBCol, StoreStatistics};
+
+/// A database storing its data in a namespace of another database.
+///
+/// Keys are prefixed with the namespace before they reach the underlying
+/// database, so that several independent chains can share a single database,
+/// e.g. to run them side by side in one process in tests.  The prefix is the
+/// length of the namespace followed by the namespace itself, which means that
+/// the keys of different namespaces never overlap, even if one namespace is a
+/// prefix of another one.
+///
+/// Iterating over a prefix of the keys goes through the keys of the namespace
+/// from the start rather than seeking to the prefix.  That’s good enough for
+/// tests but not for production use with large databases.
+pub struct NamespacedDB {
+    db: Arc<dyn Database>,
+    prefix: Vec<u8>,
+}
+
+impl NamespacedDB {
+    /// Wraps `db` so that all keys are kept in `namespace`.  Panics if the
+    /// namespace is longer than 255 bytes.
+    pub fn new(db: Arc<dyn Database>, namespace: &str) -> Arc<dyn Database> {
+        let len = u8::try_from(namespace.len()).expect("namespace longer than 255 bytes");
+        let mut prefix = vec![len];
+        prefix.extend_from_slice(namespace.as_bytes());
+        Arc::new(Self { db, prefix })
+    }
+
+    fn key(&self, key: &[u8]) -> Vec<u8> {
+        [self.prefix.as_slice(), key].concat()
+    }
+
+    /// Strips the namespace from the keys returned by an iterator over the
+    /// underlying database.
+    fn strip_prefix<'a>(&'a self, iter: DBIterator<'a>) -> DBIterator<'a> {
+        Box::new(iter.filter_map(move |item| match item {
+            Ok((key, value)) => {
+                key.strip_prefix(self.prefix.as_slice()).map(|key| Ok((key.into(), value)))
+            }
+            Err(err) => Some(Err(err)),
+        }))
+    }
+}
+
+impl Database for NamespacedDB {
+    fn get_raw_bytes(&self, col: DBCol, key: &[u8]) -> io::Result<Option<DBSlice<'_>>> {
+        self.db.get_raw_bytes(col, &self.key(key))
+    }
+
+    fn get_with_rc_stripped(&self, col: DBCol, key: &[u8]) -> io::Result<Option<DBSlice<'_>>> {
+        self.db.get_with_rc_stripped(col, &self.key(key))
+    }
+
+    fn iter<'a>(&'a self, col: DBCol) -> DBIterator<'a> {
+        self.strip_prefix(self.db.iter_prefix(col, &self.prefix))
+    }
+
+    fn iter_prefix<'a>(&'a self, col: DBCol, key_prefix: &'a [u8]) -> DBIterator<'a> {
+        Box::new(self.iter(col).filter(move |item| match item {
+            Ok((key, _)) => key.starts_with(key_prefix),
+            Err(_) => true,
+        }))
+    }
+
+    fn iter_raw_bytes<'a>(&'a self, col: DBCol) -> DBIterator<'a> {
+        self.strip_prefix(self.db.iter_raw_bytes(col))
+    }
+
+    /// Atomically applies operations in given transaction, with the keys moved
+    /// to the namespace.  Deleting all data from a column deletes only the
+    /// keys of the namespace.
+    fn write(&self, transaction: DBTransaction) -> io::Result<()> {
+        let mut namespaced = DBTransaction::new();
+        for op in transaction.ops {
+            match op {
+                DBOp::Set { col, key, value } => namespaced.set(col, self.key(&key), value),
+                DBOp::Insert { col, key, value } => namespaced.insert(col, self.key(&key), value),
+                DBOp::UpdateRefcount { col, key, value } => {
+                    namespaced.update_refcount(col, self.key(&key), value)
+                }
+                DBOp::Delete { col, key } => namespaced.delete(col, self.key(&key)),
+                DBOp::DeleteAll { col } => {
+                    for item in self.iter_raw_bytes(col) {
+                        let (key, _) = item?;
+                        namespaced.delete(col, self.key(&key));
+                    }
+                }
+            }
+        }
+        self.db.write(namespaced)
+    }
+
+    fn flush(&self) -> io::Result<()> {
+        self.db.flush()
+    }
+
+    fn compact(&self) -> io::Result<()> {
+        self.db.compact()
+    }
+
+    fn get_store_statistics(&self) -> Option<StoreStatistics> {
+        self.db.get_store_statistics()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::NamespacedDB;
+    use crate::db::{DBTransaction, Database, TestDB};
+    use crate::DBCol;
+
+    fn keys(db: &dyn Database, col: DBCol) -> Vec<Vec<u8>> {
+        db.iter(col).map(|item| item.unwrap().0.to_vec()).collect()
+    }
+
+    #[test]
+    fn test_namespaces_are_isolated() {
+        let db = TestDB::new();
+        let a = NamespacedDB::new(db.clone(), "a");
+        // Keys of a namespace which is a prefix of another one don't mix.
+        let ab = NamespacedDB::new(db.clone(), "ab");
+
+        for (db, value) in [(&a, b"a"), (&ab, b"x")] {
+            let mut transaction = DBTransaction::new();
+            transaction.set(DBCol::BlockMisc, b"key".to_vec(), value.to_vec());
+            transaction.set(DBCol::BlockMisc, b"other".to_vec(), value.to_vec());
+            db.write(transaction).unwrap();
+        }
+        assert_eq!(a.get_raw_bytes(DBCol::BlockMisc, b"key").unwrap().unwrap().as_slice(), b"a");
+        assert_eq!(ab.get_raw_bytes(DBCol::BlockMisc, b"key").unwrap().unwrap().as_slice(), b"x");
+        assert_eq!(keys(&*ab, DBCol::BlockMisc), vec![b"key".to_vec(), b"other".to_vec()]);
+        assert_eq!(
+            ab.iter_prefix(DBCol::BlockMisc, b"o")
+                .map(|item| item.unwrap().0.to_vec())
+                .collect::<Vec<_>>(),
+            vec![b"other".to_vec()]
+        );
+
+        // Deleting a column only deletes the keys of the namespace.
+        let mut transaction = DBTransaction::new();
+        transaction.delete_all(DBCol::BlockMisc);
+        ab.write(transaction).unwrap();
+        assert!(keys(&*ab, DBCol::BlockMisc).is_empty());
+        assert_eq!(a.get_raw_bytes(DBCol::BlockMisc, b"key").unwrap().unwrap().as_slice(), b"a");
+        assert_eq!(keys(&*db, DBCol::BlockMisc).len(), 2);
+    }
+}
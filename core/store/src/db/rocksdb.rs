@@ -2,13 +2,15 @@ use std::io;
 use std::path::Path;
 
 use ::rocksdb::{
-    BlockBasedOptions, Cache, ColumnFamily, Env, IteratorMode, Options, ReadOptions, WriteBatch, DB,
+    BlockBasedOptions, Cache, ColumnFamily, Direction, Env, IteratorMode, Options, ReadOptions,
+    WriteBatch, DB,
 };
 use strum::IntoEnumIterator;
 use tracing::warn;
 
 use crate::config::Mode;
 use crate::db::{refcount, DBIterator, DBOp, DBSlice, DBTransaction, Database, StatsValue};
+use crate::health::Op;
 use crate::{metadata, metrics, DBCol, StoreConfig, StoreStatistics, Temperature};
 
 mod instance_tracker;
@@ -206,7 +208,14 @@ impl RocksDB {
         })
     }
 
-    fn iter_raw_bytes_prefix<'a>(&'a self, col: DBCol, prefix: &'a [u8]) -> RocksDBIterator<'a> {
+    /// Iterates over keys starting with the prefix, from the first key which
+    /// isn't lower than `start`.
+    fn iter_raw_bytes_prefix<'a>(
+        &'a self,
+        col: DBCol,
+        prefix: &'a [u8],
+        start: &[u8],
+    ) -> RocksDBIterator<'a> {
         let cf_handle = self.cf_handle(col).unwrap();
         let mut read_options = rocksdb_read_options();
         if !prefix.is_empty() {
@@ -217,7 +226,12 @@ impl RocksDB {
             // is therefore pointless.
             //     read_options.set_prefix_same_as_start(true);
         }
-        let iter = self.db.iterator_cf_opt(cf_handle, read_options, IteratorMode::Start);
+        let mode = if start > prefix {
+            IteratorMode::From(start, Direction::Forward)
+        } else {
+            IteratorMode::Start
+        };
+        let iter = self.db.iterator_cf_opt(cf_handle, read_options, mode);
         RocksDBIterator(iter)
     }
 }
@@ -268,20 +282,31 @@ impl Database for RocksDB {
             .get_pinned_cf_opt(self.cf_handle(col)?, key, &read_options)
             .map_err(into_other)?
             .map(DBSlice::from_rocksdb_slice);
-        timer.observe_duration();
+        let elapsed = std::time::Duration::from_secs_f64(timer.stop_and_record());
+        crate::health::storage_health().record(Op::Read, elapsed);
         Ok(result)
     }
 
     fn iter_raw_bytes<'a>(&'a self, col: DBCol) -> DBIterator<'a> {
-        Box::new(self.iter_raw_bytes_prefix(col, &[]))
+        Box::new(self.iter_raw_bytes_prefix(col, &[], &[]))
     }
 
     fn iter<'a>(&'a self, col: DBCol) -> DBIterator<'a> {
-        refcount::iter_with_rc_logic(col, self.iter_raw_bytes_prefix(col, &[]))
+        refcount::iter_with_rc_logic(col, self.iter_raw_bytes_prefix(col, &[], &[]))
     }
 
     fn iter_prefix<'a>(&'a self, col: DBCol, key_prefix: &'a [u8]) -> DBIterator<'a> {
-        let iter = self.iter_raw_bytes_prefix(col, key_prefix);
+        let iter = self.iter_raw_bytes_prefix(col, key_prefix, &[]);
+        refcount::iter_with_rc_logic(col, iter)
+    }
+
+    fn iter_prefix_from<'a>(
+        &'a self,
+        col: DBCol,
+        key_prefix: &'a [u8],
+        start: &'a [u8],
+    ) -> DBIterator<'a> {
+        let iter = self.iter_raw_bytes_prefix(col, key_prefix, start);
         refcount::iter_with_rc_logic(col, iter)
     }
 
@@ -317,7 +342,10 @@ impl Database for RocksDB {
                 }
             }
         }
-        self.db.write(batch).map_err(into_other)
+        let start = std::time::Instant::now();
+        let result = self.db.write(batch).map_err(into_other);
+        crate::health::storage_health().record(Op::Write, start.elapsed());
+        result
     }
 
     fn compact(&self) -> io::Result<()> {
@@ -497,6 +525,20 @@ impl RocksDB {
         Some(metadata::DbMetadata::read(&db)).transpose()
     }
 
+    /// Returns the schema version of a cold database.
+    ///
+    /// As with [`Self::get_metadata`], the database is opened in read-only mode
+    /// so that nothing is modified before the version is checked.
+    #[cfg(feature = "cold_store")]
+    pub(crate) fn get_cold_schema_version(
+        path: &Path,
+        config: &StoreConfig,
+    ) -> io::Result<crate::cold_schema::ColdSchemaVersion> {
+        let cols = [DBCol::DbVersion];
+        let db = Self::open_with_columns(path, config, Mode::ReadOnly, Temperature::Cold, &cols)?;
+        crate::cold_schema::read_version(&db)
+    }
+
     /// Gets every int property in CF_PROPERTY_NAMES for every column in DBCol.
     fn get_cf_statistics(&self, result: &mut StoreStatistics) {
         for prop_name in CF_PROPERTY_NAMES {
@@ -0,0 +1,146 @@
+use std::io;
+use std::sync::Arc;
+
+use crate::db::{DBIterator, DBSlice, DBTransaction, Database};
+use crate::{DBCol, StoreStatistics};
+
+/// A database reading from hot storage and, for data which has been garbage
+/// collected there, from cold storage.
+///
+/// Reads of columns copied to cold storage (see [`DBCol::is_cold`]) which find
+/// nothing in the hot database are repeated in the cold database, so that
+/// archival nodes with split storage serve old blocks, chunks, outcomes and
+/// state the same way nodes keeping everything in a single database do.  Data
+/// only ever kept in hot storage, like the head of the chain, is read from the
+/// hot database only.
+///
+/// Iterating over a prefix of keys of [`DBCol::StateChanges`] falls back to
+/// cold storage if the hot database has no keys with the prefix.  Since the
+/// keys start with the hash of the block, that's the case when the changes of
+/// the block were garbage collected.  Iterating over whole columns goes
+/// through the hot database only.
+///
+/// Writes go to the hot database.  The cold database is filled by the
+/// migration of final blocks to cold storage.
+pub struct SplitDB {
+    hot: Arc<dyn Database>,
+    cold: Arc<dyn Database>,
+}
+
+impl SplitDB {
+    /// Creates a database reading from `hot` and falling back to `cold`, which
+    /// is expected to be a [`crate::db::ColdDB`].
+    pub fn new(hot: Arc<dyn Database>, cold: Arc<dyn Database>) -> Arc<dyn Database> {
+        Arc::new(Self { hot, cold })
+    }
+}
+
+impl Database for SplitDB {
+    fn get_raw_bytes(&self, col: DBCol, key: &[u8]) -> io::Result<Option<DBSlice<'_>>> {
+        match self.hot.get_raw_bytes(col, key)? {
+            None if col.is_cold() => self.cold.get_raw_bytes(col, key),
+            value => Ok(value),
+        }
+    }
+
+    fn get_with_rc_stripped(&self, col: DBCol, key: &[u8]) -> io::Result<Option<DBSlice<'_>>> {
+        match self.hot.get_with_rc_stripped(col, key)? {
+            None if col.is_cold() => self.cold.get_with_rc_stripped(col, key),
+            value => Ok(value),
+        }
+    }
+
+    fn iter<'a>(&'a self, col: DBCol) -> DBIterator<'a> {
+        self.hot.iter(col)
+    }
+
+    fn iter_prefix<'a>(&'a self, col: DBCol, key_prefix: &'a [u8]) -> DBIterator<'a> {
+        let mut hot = self.hot.iter_prefix(col, key_prefix).peekable();
+        if col != DBCol::StateChanges || hot.peek().is_some() {
+            return Box::new(hot);
+        }
+        self.cold.iter_prefix(col, key_prefix)
+    }
+
+    fn iter_raw_bytes<'a>(&'a self, col: DBCol) -> DBIterator<'a> {
+        self.hot.iter_raw_bytes(col)
+    }
+
+    fn write(&self, transaction: DBTransaction) -> io::Result<()> {
+        self.hot.write(transaction)
+    }
+
+    fn flush(&self) -> io::Result<()> {
+        self.hot.flush()
+    }
+
+    fn compact(&self) -> io::Result<()> {
+        self.hot.compact()
+    }
+
+    fn get_store_statistics(&self) -> Option<StoreStatistics> {
+        self.hot.get_store_statistics()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::SplitDB;
+    use crate::db::{ColdDB, DBTransaction, Database, TestDB};
+    use crate::DBCol;
+    use std::sync::Arc;
+
+    fn write(db: &dyn Database, col: DBCol, key: &[u8], value: &[u8]) {
+        let mut transaction = DBTransaction::new();
+        transaction.set(col, key.to_vec(), value.to_vec());
+        db.write(transaction).unwrap();
+    }
+
+    fn get(db: &dyn Database, col: DBCol, key: &[u8]) -> Option<Vec<u8>> {
+        db.get_raw_bytes(col, key).unwrap().map(|value| value.to_vec())
+    }
+
+    #[test]
+    fn test_fallback_reads() {
+        let hot = TestDB::new();
+        let cold: Arc<dyn Database> = Arc::new(ColdDB::new(hot.clone(), TestDB::default()));
+        let split = SplitDB::new(hot.clone(), cold.clone());
+
+        // Hot data is read from the hot database, even if it's in cold one too.
+        write(&*hot, DBCol::Block, b"new", b"hot");
+        write(&*cold, DBCol::Block, b"new", b"cold");
+        assert_eq!(get(&*split, DBCol::Block, b"new"), Some(b"hot".to_vec()));
+
+        // Garbage collected data is read from the cold database.
+        write(&*cold, DBCol::Block, b"old", b"cold");
+        assert_eq!(get(&*split, DBCol::Block, b"old"), Some(b"cold".to_vec()));
+        assert_eq!(get(&*split, DBCol::Block, b"missing"), None);
+
+        // Data only kept in hot storage doesn't fall back.
+        write(&*cold, DBCol::BlockMisc, b"HEAD", b"cold");
+        assert_eq!(get(&*split, DBCol::BlockMisc, b"HEAD"), None);
+
+        // Writes go to the hot database.
+        write(&*split, DBCol::Block, b"written", b"split");
+        assert_eq!(get(&*hot, DBCol::Block, b"written"), Some(b"split".to_vec()));
+    }
+
+    #[test]
+    fn test_fallback_state_changes() {
+        let hot = TestDB::new();
+        let cold: Arc<dyn Database> = Arc::new(ColdDB::new(hot.clone(), TestDB::default()));
+        let split = SplitDB::new(hot.clone(), cold.clone());
+        write(&*hot, DBCol::StateChanges, b"new/a", b"hot");
+        write(&*cold, DBCol::StateChanges, b"new/b", b"cold");
+        write(&*cold, DBCol::StateChanges, b"old/a", b"cold");
+
+        let keys = |prefix: &'static [u8]| -> Vec<Vec<u8>> {
+            split
+                .iter_prefix(DBCol::StateChanges, prefix)
+                .map(|item| item.unwrap().0.to_vec())
+                .collect()
+        };
+        assert_eq!(keys(b"new/"), vec![b"new/a".to_vec()]);
+        assert_eq!(keys(b"old/"), vec![b"old/a".to_vec()]);
+    }
+}
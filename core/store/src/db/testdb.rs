@@ -39,8 +39,17 @@ impl Database for TestDB {
     }
 
     fn iter_prefix<'a>(&'a self, col: DBCol, key_prefix: &'a [u8]) -> DBIterator<'a> {
+        self.iter_prefix_from(col, key_prefix, key_prefix)
+    }
+
+    fn iter_prefix_from<'a>(
+        &'a self,
+        col: DBCol,
+        key_prefix: &'a [u8],
+        start: &'a [u8],
+    ) -> DBIterator<'a> {
         let iterator = self.db.read().unwrap()[col]
-            .range(key_prefix.to_vec()..)
+            .range(start.max(key_prefix).to_vec()..)
             .take_while(move |(k, _)| k.starts_with(&key_prefix))
             .map(|(k, v)| Ok((k.clone().into_boxed_slice(), v.clone().into_boxed_slice())))
             .collect::<Vec<io::Result<_>>>();
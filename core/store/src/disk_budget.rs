@@ -0,0 +1,346 @@
+//! Enforcement of a limit on the size of the database directory.
+//!
+//! The size of the hot database directory is checked periodically.  As it
+//! approaches the configured maximum the node reacts in stages, each one
+//! including the previous ones:
+//!
+//! 1. garbage collection retention is shortened, the more the closer the
+//!    usage is to the maximum, though never below the minimum number of epochs
+//!    the protocol needs;
+//! 2. optional recorders, such as the peer audit log and the cache of state
+//!    parts computed for state sync, stop writing to the database;
+//! 3. an alert is raised so that the operator can act before the disk fills
+//!    up and the node crashes in the middle of an epoch.
+//!
+//! The stages are left again as soon as the usage drops below their
+//! thresholds, which happens once garbage collection catches up.
+use std::collections::BTreeMap;
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicU64, AtomicU8, Ordering};
+use std::sync::Mutex;
+use std::time::{Duration, Instant};
+
+use once_cell::sync::Lazy;
+use tracing::{error, info, warn};
+
+use crate::db::StatsValue;
+use crate::{metrics, DBCol, Store};
+
+#[derive(Clone, Debug, PartialEq, serde::Serialize, serde::Deserialize)]
+#[serde(default)]
+pub struct DiskBudgetConfig {
+    /// Maximum size of the database directory in bytes.  `None` disables the
+    /// budget.
+    pub max_bytes: Option<u64>,
+    /// How often the size of the database directory is checked.
+    pub check_period: Duration,
+    /// Fraction of the budget from which garbage collection retention is
+    /// shortened.
+    pub tighten_gc_threshold: f64,
+    /// Fraction of the budget from which optional recorders are disabled.
+    pub disable_recorders_threshold: f64,
+    /// Fraction of the budget from which an alert is raised.  Garbage
+    /// collection retention is at its minimum from this point on.
+    pub alert_threshold: f64,
+}
+
+impl Default for DiskBudgetConfig {
+    fn default() -> Self {
+        Self {
+            max_bytes: None,
+            check_period: Duration::from_secs(60),
+            tighten_gc_threshold: 0.8,
+            disable_recorders_threshold: 0.9,
+            alert_threshold: 0.95,
+        }
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord)]
+pub enum DiskPressure {
+    Normal = 0,
+    TightenGc = 1,
+    DisableRecorders = 2,
+    Critical = 3,
+}
+
+impl DiskPressure {
+    fn from_u8(value: u8) -> Self {
+        match value {
+            0 => Self::Normal,
+            1 => Self::TightenGc,
+            2 => Self::DisableRecorders,
+            _ => Self::Critical,
+        }
+    }
+}
+
+struct Inner {
+    config: DiskBudgetConfig,
+    path: Option<PathBuf>,
+    next_check: Option<Instant>,
+}
+
+pub struct DiskBudget {
+    inner: Mutex<Inner>,
+    pressure: AtomicU8,
+    /// Used fraction of the budget, as bits of an `f64`.
+    usage: AtomicU64,
+}
+
+static DISK_BUDGET: Lazy<DiskBudget> = Lazy::new(DiskBudget::new);
+
+/// Returns the disk budget of the database of this process.
+pub fn disk_budget() -> &'static DiskBudget {
+    &DISK_BUDGET
+}
+
+/// Name of the group of columns the usage of a column is reported under.
+fn column_group(col: DBCol) -> &'static str {
+    match col {
+        DBCol::State => "state",
+        #[cfg(feature = "protocol_feature_flat_state")]
+        DBCol::FlatState | DBCol::FlatStateDeltas | DBCol::FlatStateMisc => "state",
+        DBCol::StateParts | DBCol::StateHeaders | DBCol::StateDlInfos => "state_sync",
+        DBCol::Block
+        | DBCol::BlockHeader
+        | DBCol::BlockInfo
+        | DBCol::BlockExtra
+        | DBCol::BlockMerkleTree
+        | DBCol::BlockHeight
+        | DBCol::BlockPerHeight
+        | DBCol::HeaderHashesByHeight => "blocks",
+        DBCol::Chunks
+        | DBCol::PartialChunks
+        | DBCol::InvalidChunks
+        | DBCol::ChunkExtra
+        | DBCol::ChunkHashesByHeight => "chunks",
+        DBCol::Transactions
+        | DBCol::Receipts
+        | DBCol::OutgoingReceipts
+        | DBCol::IncomingReceipts
+        | DBCol::TransactionResultForBlock
+        | DBCol::OutcomeIds
+        | DBCol::StateChanges
+        | DBCol::TrieChanges => "execution",
+        _ => "other",
+    }
+}
+
+/// Returns the total size of the files in the directory and its
+/// subdirectories.  Files which disappear while the directory is walked, as
+/// RocksDB files do after compaction, are skipped.
+fn directory_size(path: &Path) -> std::io::Result<u64> {
+    let mut size = 0;
+    for entry in std::fs::read_dir(path)? {
+        let entry = match entry {
+            Ok(entry) => entry,
+            Err(_) => continue,
+        };
+        let metadata = match entry.metadata() {
+            Ok(metadata) => metadata,
+            Err(_) => continue,
+        };
+        if metadata.is_dir() {
+            size += directory_size(&entry.path()).unwrap_or(0);
+        } else {
+            size += metadata.len();
+        }
+    }
+    Ok(size)
+}
+
+impl DiskBudget {
+    fn new() -> Self {
+        Self {
+            inner: Mutex::new(Inner {
+                config: DiskBudgetConfig::default(),
+                path: None,
+                next_check: None,
+            }),
+            pressure: AtomicU8::new(DiskPressure::Normal as u8),
+            usage: AtomicU64::new(0f64.to_bits()),
+        }
+    }
+
+    /// Sets the configuration and the directory of the database the budget
+    /// applies to.
+    pub fn configure(&self, config: &DiskBudgetConfig, path: &Path) {
+        let mut inner = self.inner.lock().unwrap();
+        inner.config = config.clone();
+        inner.path = Some(path.to_path_buf());
+        inner.next_check = None;
+        if config.max_bytes.is_none() {
+            self.set_usage(&inner.config, 0.0);
+        }
+    }
+
+    pub fn pressure(&self) -> DiskPressure {
+        DiskPressure::from_u8(self.pressure.load(Ordering::Relaxed))
+    }
+
+    /// Whether optional recorders should skip writing to the database.
+    pub fn is_disabling_recorders(&self) -> bool {
+        self.pressure() >= DiskPressure::DisableRecorders
+    }
+
+    /// Records that an optional write was skipped because of the budget.
+    pub fn record_skipped(&self, what: &str) {
+        metrics::DISK_BUDGET_SKIPPED_WRITES.with_label_values(&[what]).inc();
+    }
+
+    /// Returns the number of epochs garbage collection should keep, given the
+    /// configured and the minimum number of epochs.  Below the threshold it's
+    /// the configured number, from there it shrinks linearly and reaches the
+    /// minimum at the alert threshold.
+    pub fn gc_num_epochs_to_keep(&self, configured: u64, minimum: u64) -> u64 {
+        let config = self.inner.lock().unwrap().config.clone();
+        let usage = f64::from_bits(self.usage.load(Ordering::Relaxed));
+        Self::retention(&config, usage, configured, minimum)
+    }
+
+    fn retention(config: &DiskBudgetConfig, usage: f64, configured: u64, minimum: u64) -> u64 {
+        if config.max_bytes.is_none() || configured <= minimum {
+            return configured;
+        }
+        if usage < config.tighten_gc_threshold {
+            return configured;
+        }
+        let range = config.alert_threshold - config.tighten_gc_threshold;
+        let progress = if range > 0.0 {
+            ((usage - config.tighten_gc_threshold) / range).min(1.0)
+        } else {
+            1.0
+        };
+        let reduction = ((configured - minimum) as f64 * progress).ceil() as u64;
+        configured - reduction.min(configured - minimum)
+    }
+
+    /// Measures the usage of the budget, unless it was measured less than
+    /// `check_period` ago.  The store is used to report the usage by groups of
+    /// columns.
+    pub fn check(&self, store: &Store) {
+        let (config, path) = {
+            let mut inner = self.inner.lock().unwrap();
+            let path = match (&inner.config.max_bytes, &inner.path) {
+                (Some(_), Some(path)) => path.clone(),
+                _ => return,
+            };
+            let now = Instant::now();
+            if inner.next_check.map_or(false, |next_check| now < next_check) {
+                return;
+            }
+            inner.next_check = Some(now + inner.config.check_period);
+            (inner.config.clone(), path)
+        };
+        let used_bytes = match directory_size(&path) {
+            Ok(used_bytes) => used_bytes,
+            Err(err) => {
+                warn!(target: "store", ?err, ?path, "Failed to measure database size");
+                return;
+            }
+        };
+        metrics::DISK_BUDGET_USED_BYTES.set(used_bytes as i64);
+        self.export_column_groups(store);
+        let max_bytes = config.max_bytes.unwrap_or(u64::MAX).max(1);
+        self.set_usage(&config, used_bytes as f64 / max_bytes as f64);
+    }
+
+    fn export_column_groups(&self, store: &Store) {
+        let stats = match store.get_store_statistics() {
+            Some(stats) => stats,
+            None => return,
+        };
+        let mut groups = BTreeMap::<&str, i64>::new();
+        for (name, values) in &stats.data {
+            if name != "rocksdb.live-sst-files-size" {
+                continue;
+            }
+            for value in values {
+                if let StatsValue::ColumnValue(col, size) = value {
+                    *groups.entry(column_group(*col)).or_default() += size;
+                }
+            }
+        }
+        for (group, size) in groups {
+            metrics::DISK_BUDGET_GROUP_BYTES.with_label_values(&[group]).set(size);
+        }
+    }
+
+    fn set_usage(&self, config: &DiskBudgetConfig, usage: f64) {
+        self.usage.store(usage.to_bits(), Ordering::Relaxed);
+        let pressure = if config.max_bytes.is_none() {
+            DiskPressure::Normal
+        } else if usage >= config.alert_threshold {
+            DiskPressure::Critical
+        } else if usage >= config.disable_recorders_threshold {
+            DiskPressure::DisableRecorders
+        } else if usage >= config.tighten_gc_threshold {
+            DiskPressure::TightenGc
+        } else {
+            DiskPressure::Normal
+        };
+        metrics::DISK_BUDGET_PRESSURE.set(pressure as i64);
+        let previous = DiskPressure::from_u8(self.pressure.swap(pressure as u8, Ordering::Relaxed));
+        if previous == pressure {
+            return;
+        }
+        match pressure {
+            DiskPressure::Critical => error!(
+                target: "store",
+                usage,
+                max_bytes = ?config.max_bytes,
+                "Database is about to exceed its disk budget, free up space or raise the budget"
+            ),
+            _ if pressure > previous => warn!(
+                target: "store",
+                usage,
+                ?pressure,
+                "Database is approaching its disk budget"
+            ),
+            _ => info!(target: "store", usage, ?pressure, "Database disk usage went down"),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{DiskBudget, DiskBudgetConfig, DiskPressure};
+
+    fn config() -> DiskBudgetConfig {
+        DiskBudgetConfig { max_bytes: Some(1000), ..DiskBudgetConfig::default() }
+    }
+
+    #[test]
+    fn test_retention() {
+        let config = config();
+        assert_eq!(DiskBudget::retention(&config, 0.5, 10, 3), 10);
+        assert_eq!(DiskBudget::retention(&config, 0.8, 10, 3), 10);
+        assert_eq!(DiskBudget::retention(&config, 0.85, 10, 3), 7);
+        assert_eq!(DiskBudget::retention(&config, 0.95, 10, 3), 3);
+        assert_eq!(DiskBudget::retention(&config, 1.5, 10, 3), 3);
+        // Retention never goes below the minimum, nor is it extended.
+        assert_eq!(DiskBudget::retention(&config, 0.9, 2, 3), 2);
+        let disabled = DiskBudgetConfig::default();
+        assert_eq!(DiskBudget::retention(&disabled, 0.99, 10, 3), 10);
+    }
+
+    #[test]
+    fn test_pressure() {
+        let budget = DiskBudget::new();
+        let config = config();
+        for (usage, pressure) in [
+            (0.5, DiskPressure::Normal),
+            (0.85, DiskPressure::TightenGc),
+            (0.9, DiskPressure::DisableRecorders),
+            (0.97, DiskPressure::Critical),
+            (0.7, DiskPressure::Normal),
+        ] {
+            budget.set_usage(&config, usage);
+            assert_eq!(budget.pressure(), pressure);
+        }
+        assert!(!budget.is_disabling_recorders());
+        budget.set_usage(&config, 0.92);
+        assert!(budget.is_disabling_recorders());
+    }
+}
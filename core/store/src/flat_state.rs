@@ -786,6 +786,7 @@ impl FlatStorageState {
             return Err(guard.create_block_not_supported_error(block_hash));
         }
         let mut store_update = StoreUpdate::new(guard.store.storage.clone());
+        store_update.set_tag("flat_state_delta");
         store_helper::set_delta(&mut store_update, guard.shard_id, block_hash.clone(), &delta)?;
         guard.deltas.insert(*block_hash, Arc::new(delta));
         guard.blocks.insert(*block_hash, block);
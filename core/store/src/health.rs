@@ -0,0 +1,307 @@
+//! Detection of degraded storage latency.
+//!
+//! Latencies of RocksDB reads and writes are averaged over fixed windows.  When
+//! the average latency of either operation exceeds its threshold, the storage
+//! is considered degraded.  It is considered healthy again only once both
+//! averages drop below half of their thresholds, so that the status doesn’t
+//! flap while the latency hovers around a threshold.
+//!
+//! While the storage is degraded the node sheds optional load which competes
+//! with block processing for disk access: receipt prefetching is skipped, the
+//! number of concurrent view queries is capped and debug endpoints are
+//! disabled.
+use std::sync::atomic::{AtomicBool, AtomicU64, AtomicUsize, Ordering};
+use std::sync::Mutex;
+use std::time::{Duration, Instant};
+
+use once_cell::sync::Lazy;
+use tracing::{error, info};
+
+use crate::metrics;
+
+#[derive(Clone, Debug, PartialEq, Eq, serde::Serialize, serde::Deserialize)]
+#[serde(default)]
+pub struct StorageHealthConfig {
+    /// Whether storage latencies are monitored at all.
+    pub enabled: bool,
+    /// Length of the window over which latencies are averaged.
+    pub window: Duration,
+    /// Windows with fewer samples of an operation aren’t used to judge the
+    /// latency of that operation.
+    pub min_samples: u64,
+    /// Average latency of a read above which the storage is degraded.
+    pub read_latency_threshold: Duration,
+    /// Average latency of a write batch above which the storage is degraded.
+    pub write_latency_threshold: Duration,
+    /// Maximum number of view queries processed concurrently while the
+    /// storage is degraded.
+    pub degraded_max_concurrent_view_queries: usize,
+}
+
+impl Default for StorageHealthConfig {
+    fn default() -> Self {
+        Self {
+            enabled: true,
+            window: Duration::from_secs(10),
+            min_samples: 10,
+            // On a healthy SSD average read takes well under a millisecond
+            // and a write batch a few milliseconds.
+            read_latency_threshold: Duration::from_millis(20),
+            write_latency_threshold: Duration::from_millis(500),
+            degraded_max_concurrent_view_queries: 1,
+        }
+    }
+}
+
+#[derive(Clone, Copy, Debug)]
+pub(crate) enum Op {
+    Read,
+    Write,
+}
+
+#[derive(Default)]
+struct OpStats {
+    total_micros: AtomicU64,
+    count: AtomicU64,
+}
+
+impl OpStats {
+    fn add(&self, latency: Duration) {
+        self.total_micros.fetch_add(latency.as_micros() as u64, Ordering::Relaxed);
+        self.count.fetch_add(1, Ordering::Relaxed);
+    }
+
+    /// Returns the average latency since the previous call, or `None` if
+    /// there were fewer than `min_samples` samples.
+    fn take_average(&self, min_samples: u64) -> Option<Duration> {
+        let total = self.total_micros.swap(0, Ordering::Relaxed);
+        let count = self.count.swap(0, Ordering::Relaxed);
+        if count == 0 || count < min_samples {
+            return None;
+        }
+        Some(Duration::from_micros(total / count))
+    }
+}
+
+/// Holds one of the limited slots for a view query, see
+/// [`StorageHealth::try_start_view_query`].
+pub struct ViewQueryPermit<'a>(&'a AtomicUsize);
+
+impl Drop for ViewQueryPermit<'_> {
+    fn drop(&mut self) {
+        self.0.fetch_sub(1, Ordering::Relaxed);
+    }
+}
+
+pub struct StorageHealth {
+    config: Mutex<StorageHealthConfig>,
+    enabled: AtomicBool,
+    max_view_queries: AtomicUsize,
+    started: Instant,
+    /// End of the current window, in microseconds since `started`.
+    window_end: AtomicU64,
+    reads: OpStats,
+    writes: OpStats,
+    degraded: AtomicBool,
+    view_queries: AtomicUsize,
+}
+
+static STORAGE_HEALTH: Lazy<StorageHealth> =
+    Lazy::new(|| StorageHealth::new(StorageHealthConfig::default(), Instant::now()));
+
+/// Returns the health monitor of the storage of this process.
+pub fn storage_health() -> &'static StorageHealth {
+    &STORAGE_HEALTH
+}
+
+impl StorageHealth {
+    fn new(config: StorageHealthConfig, started: Instant) -> Self {
+        let this = Self {
+            config: Mutex::new(StorageHealthConfig::default()),
+            enabled: AtomicBool::new(false),
+            max_view_queries: AtomicUsize::new(0),
+            started,
+            window_end: AtomicU64::new(0),
+            reads: OpStats::default(),
+            writes: OpStats::default(),
+            degraded: AtomicBool::new(false),
+            view_queries: AtomicUsize::new(0),
+        };
+        this.configure(&config);
+        this
+    }
+
+    /// Replaces the configuration of the monitor.
+    pub fn configure(&self, config: &StorageHealthConfig) {
+        let mut current = self.config.lock().unwrap();
+        *current = config.clone();
+        self.enabled.store(config.enabled, Ordering::Relaxed);
+        self.max_view_queries.store(config.degraded_max_concurrent_view_queries, Ordering::Relaxed);
+        if !config.enabled {
+            self.set_degraded(false, None, None);
+        }
+    }
+
+    /// Whether the storage is currently considered degraded.
+    pub fn is_degraded(&self) -> bool {
+        self.degraded.load(Ordering::Relaxed)
+    }
+
+    /// Records that a piece of optional work was skipped because the storage
+    /// is degraded.
+    pub fn record_shed(&self, what: &str) {
+        metrics::STORAGE_SHED_LOAD.with_label_values(&[what]).inc();
+    }
+
+    /// Takes a slot for a view query.  Returns `None` if the storage is
+    /// degraded and the limit of concurrent view queries has been reached, in
+    /// which case the query should be rejected.
+    pub fn try_start_view_query(&self) -> Option<ViewQueryPermit<'_>> {
+        let running = self.view_queries.fetch_add(1, Ordering::Relaxed);
+        let permit = ViewQueryPermit(&self.view_queries);
+        if self.is_degraded() && running >= self.max_view_queries.load(Ordering::Relaxed) {
+            self.record_shed("view_query");
+            return None;
+        }
+        Some(permit)
+    }
+
+    pub(crate) fn record(&self, op: Op, latency: Duration) {
+        if self.enabled.load(Ordering::Relaxed) {
+            self.record_at(op, latency, Instant::now());
+        }
+    }
+
+    fn record_at(&self, op: Op, latency: Duration, now: Instant) {
+        match op {
+            Op::Read => self.reads.add(latency),
+            Op::Write => self.writes.add(latency),
+        }
+        let elapsed = now.saturating_duration_since(self.started).as_micros() as u64;
+        if elapsed < self.window_end.load(Ordering::Relaxed) {
+            return;
+        }
+        // Only one thread evaluates a window, the others carry on.
+        let config = match self.config.try_lock() {
+            Ok(config) => config,
+            Err(_) => return,
+        };
+        if elapsed < self.window_end.load(Ordering::Relaxed) {
+            return;
+        }
+        self.window_end.store(elapsed + config.window.as_micros() as u64, Ordering::Relaxed);
+        self.evaluate(&config);
+    }
+
+    /// Judges the latencies of the window which just ended.
+    fn evaluate(&self, config: &StorageHealthConfig) {
+        let read = self.reads.take_average(config.min_samples);
+        let write = self.writes.take_average(config.min_samples);
+        for (op, latency) in [("read", read), ("write", write)] {
+            if let Some(latency) = latency {
+                metrics::STORAGE_AVERAGE_LATENCY
+                    .with_label_values(&[op])
+                    .set(latency.as_secs_f64());
+            }
+        }
+        let exceeds = |latency: Option<Duration>, threshold: Duration| match latency {
+            Some(latency) => latency > threshold,
+            None => false,
+        };
+        if !self.is_degraded() {
+            if exceeds(read, config.read_latency_threshold)
+                || exceeds(write, config.write_latency_threshold)
+            {
+                self.set_degraded(true, read, write);
+            }
+        } else if (read.is_some() || write.is_some())
+            && !exceeds(read, config.read_latency_threshold / 2)
+            && !exceeds(write, config.write_latency_threshold / 2)
+        {
+            self.set_degraded(false, read, write);
+        }
+    }
+
+    fn set_degraded(&self, degraded: bool, read: Option<Duration>, write: Option<Duration>) {
+        if self.degraded.swap(degraded, Ordering::Relaxed) == degraded {
+            return;
+        }
+        metrics::STORAGE_DEGRADED.set(degraded as i64);
+        if degraded {
+            error!(target: "store", ?read, ?write, "Storage latency degraded, shedding load");
+        } else {
+            info!(target: "store", ?read, ?write, "Storage latency recovered");
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{Op, StorageHealth, StorageHealthConfig};
+    use std::time::{Duration, Instant};
+
+    fn config() -> StorageHealthConfig {
+        StorageHealthConfig {
+            enabled: true,
+            window: Duration::from_secs(10),
+            min_samples: 2,
+            read_latency_threshold: Duration::from_millis(10),
+            write_latency_threshold: Duration::from_millis(100),
+            degraded_max_concurrent_view_queries: 1,
+        }
+    }
+
+    /// Records the same latency a few times within a window and then a sample
+    /// which ends the window.
+    fn window(health: &StorageHealth, op: Op, latency: Duration, start: Instant, index: u64) {
+        let window_start = start + Duration::from_secs(10 * index);
+        for i in 0..3 {
+            health.record_at(op, latency, window_start + Duration::from_secs(i));
+        }
+        health.record_at(op, latency, window_start + Duration::from_secs(10));
+    }
+
+    #[test]
+    fn test_degraded_with_hysteresis() {
+        let start = Instant::now();
+        let health = StorageHealth::new(config(), start);
+        window(&health, Op::Read, Duration::from_millis(5), start, 0);
+        assert!(!health.is_degraded());
+        window(&health, Op::Write, Duration::from_millis(200), start, 1);
+        assert!(health.is_degraded());
+        // Below the threshold but not below half of it, still degraded.
+        window(&health, Op::Write, Duration::from_millis(80), start, 2);
+        assert!(health.is_degraded());
+        window(&health, Op::Write, Duration::from_millis(40), start, 3);
+        assert!(!health.is_degraded());
+    }
+
+    #[test]
+    fn test_too_few_samples_ignored() {
+        let start = Instant::now();
+        let health = StorageHealth::new(config(), start);
+        health.record_at(Op::Read, Duration::from_secs(1), start + Duration::from_secs(10));
+        assert!(!health.is_degraded());
+    }
+
+    #[test]
+    fn test_view_queries_capped_while_degraded() {
+        let start = Instant::now();
+        let health = StorageHealth::new(config(), start);
+        let first = health.try_start_view_query();
+        assert!(first.is_some());
+        assert!(health.try_start_view_query().is_some());
+
+        window(&health, Op::Read, Duration::from_millis(50), start, 0);
+        assert!(health.is_degraded());
+        assert!(health.try_start_view_query().is_none());
+        drop(first);
+        let second = health.try_start_view_query();
+        assert!(second.is_some());
+        assert!(health.try_start_view_query().is_none());
+        drop(second);
+
+        health.configure(&StorageHealthConfig { enabled: false, ..config() });
+        assert!(!health.is_degraded());
+    }
+}
@@ -10,8 +10,9 @@ use once_cell::sync::Lazy;
 
 pub use columns::DBCol;
 pub use db::{
-    CHUNK_TAIL_KEY, FINAL_HEAD_KEY, FORK_TAIL_KEY, HEADER_HEAD_KEY, HEAD_KEY,
-    LARGEST_TARGET_HEIGHT_KEY, LATEST_KNOWN_KEY, TAIL_KEY,
+    CHUNK_REQUESTS_KEY, CHUNK_TAIL_KEY, COLD_HEAD_KEY, FINAL_HEAD_KEY, FORK_TAIL_KEY,
+    HEADER_HEAD_KEY, HEAD_KEY, LARGEST_ENDORSED_HEIGHT_KEY, LARGEST_TARGET_HEIGHT_KEY,
+    LATEST_KNOWN_KEY, OUTCOME_COMPACTION_KEY, RECENTLY_PROCESSED_KEY, TAIL_KEY,
 };
 use near_crypto::PublicKey;
 use near_o11y::pretty;
@@ -37,21 +38,31 @@ pub use crate::trie::{
 };
 pub use flat_state::FlatStateDelta;
 
+#[cfg(feature = "cold_store")]
+pub mod cold_migration;
+#[cfg(feature = "cold_store")]
+pub mod cold_schema;
 #[cfg(feature = "cold_store")]
 pub mod cold_storage;
 mod columns;
 pub mod config;
 pub mod db;
+pub mod disk_budget;
 pub mod flat_state;
+mod health;
 pub mod metadata;
 mod metrics;
 pub mod migrations;
 mod opener;
 pub mod test_utils;
 mod trie;
+mod write_attribution;
 
 pub use crate::config::{Mode, StoreConfig};
+pub use crate::disk_budget::{disk_budget, DiskBudget, DiskBudgetConfig, DiskPressure};
+pub use crate::health::{storage_health, StorageHealth, StorageHealthConfig, ViewQueryPermit};
 pub use crate::opener::{StoreMigrator, StoreOpener, StoreOpenerError};
+pub use crate::write_attribution::{write_attribution, WriteAttribution, WriteVolume};
 
 /// Specifies temperature of a storage.
 ///
@@ -219,6 +230,24 @@ impl NodeStorage {
         }
     }
 
+    /// Returns the cold database if the storage has one.
+    #[cfg(feature = "cold_store")]
+    pub fn cold_db(&self) -> Option<Arc<dyn Database>> {
+        self.cold_storage.clone().map(|db| db as Arc<dyn Database>)
+    }
+
+    /// Returns store reading from hot storage and falling back to cold storage
+    /// for data garbage collected in hot storage, if the storage has a cold
+    /// database.
+    ///
+    /// This is meant for serving archival data, e.g. through the view client.
+    /// See [`crate::db::SplitDB`] for details.
+    #[cfg(feature = "cold_store")]
+    pub fn get_split_store(&self) -> Option<Store> {
+        self.cold_db()
+            .map(|cold| Store { storage: crate::db::SplitDB::new(self.hot_storage.clone(), cold) })
+    }
+
     /// Returns whether the storage has a cold database.
     pub fn has_cold(&self) -> bool {
         self.cold_storage.is_some()
@@ -239,6 +268,13 @@ impl NodeStorage {
 }
 
 impl Store {
+    /// Returns a store keeping its data in `namespace` of this store, separate
+    /// from the data of any other namespace.  This lets several independent
+    /// chains share a single database, see [`crate::db::NamespacedDB`].
+    pub fn namespaced(&self, namespace: &str) -> Store {
+        Store { storage: crate::db::NamespacedDB::new(self.storage.clone(), namespace) }
+    }
+
     /// Fetches value from given column.
     ///
     /// If the key does not exist in the column returns `None`.  Otherwise
@@ -291,6 +327,17 @@ impl Store {
         self.storage.iter_prefix(column, key_prefix)
     }
 
+    /// Iterates over the keys with the prefix from the first key which isn't
+    /// lower than `start`, see [`Database::iter_prefix_from`].
+    pub fn iter_prefix_from<'a>(
+        &'a self,
+        column: DBCol,
+        key_prefix: &'a [u8],
+        start: &'a [u8],
+    ) -> DBIterator<'a> {
+        self.storage.iter_prefix_from(column, key_prefix, start)
+    }
+
     pub fn iter_prefix_ser<'a, T: BorshDeserialize>(
         &'a self,
         column: DBCol,
@@ -355,6 +402,11 @@ impl Store {
 pub struct StoreUpdate {
     transaction: DBTransaction,
     storage: StoreUpdateStorage,
+    /// Call-site the writes of the update are attributed to, see
+    /// [`write_attribution`].
+    tag: Option<&'static str>,
+    /// Volumes of the tagged updates merged into this one.
+    merged: Vec<(&'static str, WriteVolume)>,
 }
 
 enum StoreUpdateStorage {
@@ -369,11 +421,31 @@ impl StoreUpdate {
     };
 
     pub(crate) fn new(db: Arc<dyn Database>) -> Self {
-        StoreUpdate { transaction: DBTransaction::new(), storage: StoreUpdateStorage::DB(db) }
+        Self::with_storage(StoreUpdateStorage::DB(db))
     }
 
     pub fn new_with_tries(tries: ShardTries) -> Self {
-        StoreUpdate { transaction: DBTransaction::new(), storage: StoreUpdateStorage::Tries(tries) }
+        Self::with_storage(StoreUpdateStorage::Tries(tries))
+    }
+
+    fn with_storage(storage: StoreUpdateStorage) -> Self {
+        StoreUpdate { transaction: DBTransaction::new(), storage, tag: None, merged: vec![] }
+    }
+
+    /// Attributes the writes of the update to the call-site `tag` in the
+    /// store write metrics.
+    pub fn set_tag(&mut self, tag: &'static str) {
+        self.tag = Some(tag);
+    }
+
+    /// Volume of the writes of the update attributed to its own tag, i.e.
+    /// excluding the tagged updates merged into it.
+    fn own_volume(&self) -> WriteVolume {
+        let mut merged = WriteVolume::default();
+        for (_, volume) in &self.merged {
+            merged.add(*volume);
+        }
+        WriteVolume::of(&self.transaction.ops).saturating_sub(merged)
     }
 
     /// Inserts a new value into the database.
@@ -510,6 +582,9 @@ impl StoreUpdate {
     ///
     /// Panics if `self`’s and `other`’s storage are incompatible.
     pub fn merge(&mut self, other: StoreUpdate) {
+        if let Some(tag) = other.tag {
+            self.merged.push((tag, other.own_volume()));
+        }
         match other.storage {
             StoreUpdateStorage::Tries(tries) => {
                 assert!(self.check_compatible_shard_tries(&tries));
@@ -523,6 +598,7 @@ impl StoreUpdate {
                 assert!(same_db(self_db, &other_db));
             }
         }
+        self.merged.extend(other.merged);
         self.transaction.merge(other.transaction)
     }
 
@@ -586,6 +662,11 @@ impl StoreUpdate {
                 }
             }
         }
+        let attribution = write_attribution();
+        attribution.record(self.tag.unwrap_or(write_attribution::UNTAGGED), self.own_volume());
+        for (tag, volume) in &self.merged {
+            attribution.record(tag, *volume);
+        }
         let storage = match &self.storage {
             StoreUpdateStorage::Tries(tries) => {
                 tries.update_cache(&self.transaction)?;
@@ -958,7 +1039,16 @@ mod tests {
                     "Expected {prefix:?} prefix but got {key:?} key at {pos}"
                 );
             }
-            assert_sorted(COUNT, keys);
+            assert_sorted(COUNT, keys.clone());
+
+            // Starting from a key in the middle skips exactly the keys before it.
+            let start = keys[COUNT / 2].clone();
+            let from = collect(store.iter_prefix_from(COLUMN, prefix, &start));
+            assert_eq!(from, keys[COUNT / 2..]);
+            let mut after = start.to_vec();
+            after.push(0);
+            let from = collect(store.iter_prefix_from(COLUMN, prefix, &after));
+            assert_eq!(from, keys[COUNT / 2 + 1..]);
         }
     }
 
@@ -1,6 +1,7 @@
 use near_o11y::metrics::{
-    try_create_histogram_vec, try_create_int_counter_vec, try_create_int_gauge_vec, HistogramVec,
-    IntCounterVec, IntGaugeVec,
+    try_create_gauge_vec, try_create_histogram_vec, try_create_int_counter_vec,
+    try_create_int_gauge, try_create_int_gauge_vec, GaugeVec, HistogramVec, IntCounterVec,
+    IntGauge, IntGaugeVec,
 };
 use once_cell::sync::Lazy;
 
@@ -205,6 +206,92 @@ pub static PREFETCH_STAGED_SLOTS: Lazy<IntGaugeVec> = Lazy::new(|| {
     )
     .unwrap()
 });
+pub(crate) static STORAGE_DEGRADED: Lazy<IntGauge> = Lazy::new(|| {
+    try_create_int_gauge(
+        "near_storage_degraded",
+        "Whether storage latency is degraded and the node sheds optional load",
+    )
+    .unwrap()
+});
+pub(crate) static STORAGE_AVERAGE_LATENCY: Lazy<GaugeVec> = Lazy::new(|| {
+    try_create_gauge_vec(
+        "near_storage_average_latency_seconds",
+        "Average latency of storage operations over the last health monitoring window",
+        &["op"],
+    )
+    .unwrap()
+});
+pub(crate) static STORAGE_SHED_LOAD: Lazy<IntCounterVec> = Lazy::new(|| {
+    try_create_int_counter_vec(
+        "near_storage_shed_load_total",
+        "Number of optional operations skipped because storage latency is degraded",
+        &["kind"],
+    )
+    .unwrap()
+});
+pub(crate) static DISK_BUDGET_USED_BYTES: Lazy<IntGauge> = Lazy::new(|| {
+    try_create_int_gauge(
+        "near_disk_budget_used_bytes",
+        "Size of the database directory counted against the disk budget",
+    )
+    .unwrap()
+});
+pub(crate) static DISK_BUDGET_GROUP_BYTES: Lazy<IntGaugeVec> = Lazy::new(|| {
+    try_create_int_gauge_vec(
+        "near_disk_budget_group_bytes",
+        "Size of the live SST files of groups of columns",
+        &["group"],
+    )
+    .unwrap()
+});
+pub(crate) static DISK_BUDGET_PRESSURE: Lazy<IntGauge> = Lazy::new(|| {
+    try_create_int_gauge(
+        "near_disk_budget_pressure",
+        "Stage of disk budget enforcement: 0 normal, 1 shortened GC retention, \
+         2 optional recorders disabled, 3 alert",
+    )
+    .unwrap()
+});
+pub(crate) static DISK_BUDGET_SKIPPED_WRITES: Lazy<IntCounterVec> = Lazy::new(|| {
+    try_create_int_counter_vec(
+        "near_disk_budget_skipped_writes_total",
+        "Number of optional writes skipped because the database is close to its disk budget",
+        &["kind"],
+    )
+    .unwrap()
+});
+pub(crate) static STORE_WRITE_OPS_BY_TAG: Lazy<IntCounterVec> = Lazy::new(|| {
+    try_create_int_counter_vec(
+        "near_store_write_ops_by_tag",
+        "Number of committed store operations by the call-site which made them",
+        &["tag"],
+    )
+    .unwrap()
+});
+pub(crate) static STORE_WRITE_BYTES_BY_TAG: Lazy<IntCounterVec> = Lazy::new(|| {
+    try_create_int_counter_vec(
+        "near_store_write_bytes_by_tag",
+        "Bytes of keys and values of committed store operations by the call-site which made them",
+        &["tag"],
+    )
+    .unwrap()
+});
+#[cfg(feature = "cold_store")]
+pub(crate) static COLD_MIGRATION_COPIED_HEIGHT: Lazy<IntGauge> = Lazy::new(|| {
+    try_create_int_gauge(
+        "near_cold_migration_copied_height",
+        "Height up to which data has been copied to cold storage",
+    )
+    .unwrap()
+});
+#[cfg(feature = "cold_store")]
+pub(crate) static COLD_MIGRATION_LAG: Lazy<IntGauge> = Lazy::new(|| {
+    try_create_int_gauge(
+        "near_cold_migration_lag",
+        "Number of final heights not yet copied to cold storage",
+    )
+    .unwrap()
+});
 #[cfg(feature = "cold_store")]
 pub static COLD_MIGRATION_READS: Lazy<IntCounterVec> = Lazy::new(|| {
     try_create_int_counter_vec(
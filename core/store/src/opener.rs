@@ -5,6 +5,9 @@ use crate::metadata::{
 };
 use crate::{Mode, NodeStorage, StoreConfig, Temperature};
 
+#[cfg(feature = "cold_store")]
+use crate::cold_schema::{ColdSchemaVersion, COLD_SCHEMA_VERSION};
+
 #[derive(Debug, thiserror::Error)]
 pub enum StoreOpenerError {
     /// I/O or RocksDB-level error while opening or accessing the database.
@@ -100,6 +103,23 @@ pub enum StoreOpenerError {
     /// Error while performing migration.
     #[error("{0}")]
     MigrationError(#[source] anyhow::Error),
+
+    /// The cold database was opened for reading but its schema version wasn’t
+    /// what we expect.
+    #[cfg(feature = "cold_store")]
+    #[error(
+        "Cold database schema version {got} incompatible with expected {want}; \
+         open in read-write mode (to run a migration) or use older neard"
+    )]
+    ColdSchemaMismatchOnRead { got: ColdSchemaVersion, want: ColdSchemaVersion },
+
+    /// The cold database has schema version newer than what we support.
+    #[cfg(feature = "cold_store")]
+    #[error(
+        "Cold database schema version {got} incompatible with expected {want}; \
+         update neard release"
+    )]
+    ColdSchemaTooNew { got: ColdSchemaVersion, want: ColdSchemaVersion },
 }
 
 impl From<SnapshotError> for StoreOpenerError {
@@ -227,6 +247,8 @@ impl<'a> StoreOpener<'a> {
     /// other hand, if mode is [`Mode::Create`], fails if the database already
     /// exists.
     pub fn open_in_mode(&self, mode: Mode) -> Result<crate::NodeStorage, StoreOpenerError> {
+        crate::health::storage_health().configure(&self.hot.config.health);
+        crate::disk_budget::disk_budget().configure(&self.hot.config.disk_budget, &self.hot.path);
         let hot_meta = self.hot.get_metadata()?;
         let cold_meta = self.cold.as_ref().map(|db| db.get_metadata()).transpose()?;
 
@@ -257,6 +279,8 @@ impl<'a> StoreOpener<'a> {
                         want: DbKind::Cold,
                     });
                 }
+                #[cfg(feature = "cold_store")]
+                self.check_cold_schema(mode)?;
             } else if cold_meta.is_some() {
                 // If cold database is configured and hot database exists,
                 // cold database must exist as well.
@@ -288,6 +312,11 @@ impl<'a> StoreOpener<'a> {
         metadata: DbMetadata,
     ) -> Result<crate::NodeStorage, StoreOpenerError> {
         let snapshots = self.apply_migrations(mode, metadata)?;
+        #[cfg(feature = "cold_store")]
+        if let Some(opener) = self.cold.as_ref().filter(|_| !mode.read_only()) {
+            let db = opener.open(Mode::ReadWriteExisting, DB_VERSION)?.0;
+            crate::cold_schema::migrate(&db).map_err(StoreOpenerError::MigrationError)?;
+        }
         tracing::info!(target: "near", path=%self.path().display(),
                        "Opening an existing RocksDB database");
         let (storage, hot_meta, cold_meta) = self.open_storage(mode, DB_VERSION)?;
@@ -306,6 +335,29 @@ impl<'a> StoreOpener<'a> {
         Ok(storage)
     }
 
+    /// Checks that the cold database schema is supported.
+    ///
+    /// Fails if the cold database has a schema version newer than
+    /// [`COLD_SCHEMA_VERSION`], or an older one and the storage is opened for
+    /// reading, in which case it can’t be migrated.  Older schemas are migrated
+    /// once the database version is up to date (see [`Self::open_existing`]).
+    #[cfg(feature = "cold_store")]
+    fn check_cold_schema(&self, mode: Mode) -> Result<(), StoreOpenerError> {
+        let opener = match self.cold.as_ref() {
+            Some(opener) => opener,
+            None => return Ok(()),
+        };
+        let got = crate::db::RocksDB::get_cold_schema_version(&opener.path, opener.config)?;
+        let want = COLD_SCHEMA_VERSION;
+        if got > want {
+            Err(StoreOpenerError::ColdSchemaTooNew { got, want })
+        } else if got < want && mode.read_only() {
+            Err(StoreOpenerError::ColdSchemaMismatchOnRead { got, want })
+        } else {
+            Ok(())
+        }
+    }
+
     /// Makes sure that database’s kind is correct.
     fn ensure_kind(
         &self,
@@ -346,6 +398,10 @@ impl<'a> StoreOpener<'a> {
             &storage,
             DbMetadata { version: DB_VERSION, kind: self.expected_kind.or(Some(DbKind::RPC)) },
         )?;
+        #[cfg(feature = "cold_store")]
+        if let Some(cold) = storage.cold_db() {
+            crate::cold_schema::write_version(&*cold, COLD_SCHEMA_VERSION)?;
+        }
         Ok(storage)
     }
 
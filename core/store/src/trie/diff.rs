@@ -0,0 +1,192 @@
+//! Keys whose values differ between two tries.
+//!
+//! Both tries are traversed side by side in the order of keys.  Nodes found at
+//! the same position in both tries and having the same hash have equal
+//! subtrees, so those are skipped without being read.  Diffing the states of
+//! two nearby blocks thus reads only the nodes on the paths to the changed
+//! keys rather than the whole state.
+use std::cmp::Ordering;
+
+use near_primitives::hash::CryptoHash;
+
+use crate::trie::iterator::{IterStep, TrieIterator};
+use crate::trie::nibble_slice::NibbleSlice;
+use crate::{StorageError, Trie};
+
+/// Node or value a traversal stopped at.
+enum Item {
+    Node(CryptoHash),
+    /// Key and hash of the value.
+    Value(Vec<u8>, CryptoHash),
+}
+
+impl Item {
+    /// Nodes come before values at the same position, since a node at a
+    /// position may hold a value at the same position.
+    fn rank(&self) -> u8 {
+        match self {
+            Item::Node(_) => 0,
+            Item::Value(..) => 1,
+        }
+    }
+}
+
+/// Traversal of a trie which descends into nodes only when asked to.
+struct Traversal<'a> {
+    iter: TrieIterator<'a>,
+    /// Position, in nibbles, and the node or value the traversal stopped at,
+    /// or `None` once the traversal is over.
+    current: Option<(Vec<u8>, Item)>,
+}
+
+impl<'a> Traversal<'a> {
+    fn new(trie: &'a Trie, start: &[u8]) -> Result<Self, StorageError> {
+        let mut iter = trie.iter()?;
+        iter.seek_nibble_slice(NibbleSlice::new(start), false)?;
+        let mut traversal = Self { iter, current: None };
+        traversal.skip();
+        Ok(traversal)
+    }
+
+    /// Moves past the current value or node, skipping its whole subtree.
+    fn skip(&mut self) {
+        self.current = self.iter.next_node_or_value().map(|step| {
+            let item = match step {
+                IterStep::Descend(hash) => Item::Node(hash),
+                IterStep::Value(hash) => Item::Value(self.iter.key(), hash),
+                IterStep::PopTrail | IterStep::Continue => unreachable!(),
+            };
+            (self.iter.key_nibbles.clone(), item)
+        });
+    }
+
+    /// Descends into the node and stops at the first node or value in it.
+    fn descend(&mut self, hash: &CryptoHash) -> Result<(), StorageError> {
+        self.iter.descend_into_node(hash)?;
+        self.skip();
+        Ok(())
+    }
+
+    /// Moves forward a traversal which is ahead of the other one.  Values it
+    /// passes aren't in the other trie, so their keys are added to `keys`.
+    fn advance_alone(&mut self, keys: &mut Vec<Vec<u8>>) -> Result<(), StorageError> {
+        match self.current.take() {
+            Some((_, Item::Node(hash))) => self.descend(&hash)?,
+            Some((_, Item::Value(key, _))) => {
+                keys.push(key);
+                self.skip();
+            }
+            None => {}
+        }
+        Ok(())
+    }
+}
+
+impl Trie {
+    /// Returns up to `limit` keys, starting at `start`, whose values differ
+    /// between this trie and the `other` one, including keys present in only
+    /// one of them.  The keys are sorted.
+    pub fn diff_keys(
+        &self,
+        other: &Trie,
+        start: &[u8],
+        limit: usize,
+    ) -> Result<Vec<Vec<u8>>, StorageError> {
+        let mut this = Traversal::new(self, start)?;
+        let mut other = Traversal::new(other, start)?;
+        let mut keys = vec![];
+        while keys.len() < limit {
+            let order = match (&this.current, &other.current) {
+                (None, None) => break,
+                (Some(_), None) => Ordering::Less,
+                (None, Some(_)) => Ordering::Greater,
+                (Some((this_pos, this_item)), Some((other_pos, other_item))) => {
+                    this_pos.cmp(other_pos).then(this_item.rank().cmp(&other_item.rank()))
+                }
+            };
+            match order {
+                Ordering::Less => this.advance_alone(&mut keys)?,
+                Ordering::Greater => other.advance_alone(&mut keys)?,
+                Ordering::Equal => match (this.current.take(), other.current.take()) {
+                    (Some((_, Item::Node(this_hash))), Some((_, Item::Node(other_hash)))) => {
+                        if this_hash == other_hash {
+                            this.skip();
+                            other.skip();
+                        } else {
+                            this.descend(&this_hash)?;
+                            other.descend(&other_hash)?;
+                        }
+                    }
+                    (
+                        Some((_, Item::Value(key, this_hash))),
+                        Some((_, Item::Value(_, other_hash))),
+                    ) => {
+                        if this_hash != other_hash {
+                            keys.push(key);
+                        }
+                        this.skip();
+                        other.skip();
+                    }
+                    _ => unreachable!("items at equal positions have equal ranks"),
+                },
+            }
+        }
+        Ok(keys)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::{BTreeMap, BTreeSet};
+
+    use near_primitives::shard_layout::ShardUId;
+    use rand::Rng;
+
+    use crate::test_utils::{create_tries, gen_changes, simplify_changes, test_populate_trie};
+    use crate::Trie;
+
+    #[test]
+    fn test_diff_keys() {
+        let mut rng = rand::thread_rng();
+        for _ in 0..100 {
+            let tries = create_tries();
+            let shard_uid = ShardUId::single_shard();
+            let changes = simplify_changes(&gen_changes(&mut rng, 20));
+            let old_root = test_populate_trie(&tries, &Trie::EMPTY_ROOT, shard_uid, changes);
+            let changes = simplify_changes(&gen_changes(&mut rng, 20));
+            let new_root = test_populate_trie(&tries, &old_root, shard_uid, changes);
+            let old = tries.get_trie_for_shard(shard_uid, old_root);
+            let new = tries.get_trie_for_shard(shard_uid, new_root);
+
+            let old_items: BTreeMap<_, _> = old.iter().unwrap().map(Result::unwrap).collect();
+            let new_items: BTreeMap<_, _> = new.iter().unwrap().map(Result::unwrap).collect();
+            let expected: Vec<Vec<u8>> = old_items
+                .keys()
+                .chain(new_items.keys())
+                .collect::<BTreeSet<_>>()
+                .into_iter()
+                .filter(|key| old_items.get(*key) != new_items.get(*key))
+                .cloned()
+                .collect();
+            assert_eq!(old.diff_keys(&new, &[], usize::MAX).unwrap(), expected);
+            assert_eq!(new.diff_keys(&old, &[], usize::MAX).unwrap(), expected);
+            assert!(new.diff_keys(&new, &[], usize::MAX).unwrap().is_empty());
+
+            // Page through the keys, each page starting at the key after the
+            // last one of the previous page.
+            let limit = rng.gen_range(1..4);
+            let mut keys = vec![];
+            let mut start = vec![];
+            loop {
+                let mut page = old.diff_keys(&new, &start, limit + 1).unwrap();
+                if page.len() <= limit {
+                    keys.extend(page);
+                    break;
+                }
+                start = page.pop().unwrap();
+                keys.extend(page);
+            }
+            assert_eq!(keys, expected);
+        }
+    }
+}
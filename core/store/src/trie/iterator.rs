@@ -174,7 +174,7 @@ impl<'a> TrieIterator<'a> {
     /// configured to remember all the nodes its visiting (which can be enabled
     /// with [`Self::remember_visited_nodes`]), the node will be added to the
     /// list.
-    fn descend_into_node(&mut self, hash: &CryptoHash) -> Result<(), StorageError> {
+    pub(super) fn descend_into_node(&mut self, hash: &CryptoHash) -> Result<(), StorageError> {
         let (bytes, node) = self.trie.retrieve_node(hash)?;
         if let Some(ref mut visited) = self.visited_nodes {
             visited.push(bytes.ok_or(StorageError::TrieNodeMissing)?);
@@ -183,7 +183,7 @@ impl<'a> TrieIterator<'a> {
         Ok(())
     }
 
-    fn key(&self) -> Vec<u8> {
+    pub(super) fn key(&self) -> Vec<u8> {
         let mut result = <Vec<u8>>::with_capacity(self.key_nibbles.len() / 2);
         for i in (1..self.key_nibbles.len()).step_by(2) {
             result.push(self.key_nibbles[i - 1] * 16 + self.key_nibbles[i]);
@@ -261,6 +261,24 @@ impl<'a> TrieIterator<'a> {
         }
     }
 
+    /// Advances to the next node to descend into or the next value.
+    ///
+    /// Unlike [`Iterator::next`], this doesn’t descend into nodes on its own.
+    /// The caller either descends into the returned node with
+    /// [`Self::descend_into_node`] or skips its whole subtree by advancing
+    /// further.  The position of the node or value is in `key_nibbles`.
+    pub(super) fn next_node_or_value(&mut self) -> Option<IterStep> {
+        loop {
+            match self.iter_step()? {
+                IterStep::PopTrail => {
+                    self.trail.pop();
+                }
+                IterStep::Continue => {}
+                step => return Some(step),
+            }
+        }
+    }
+
     fn common_prefix(str1: &[u8], str2: &[u8]) -> usize {
         let mut prefix = 0;
         while prefix < str1.len() && prefix < str2.len() && str1[prefix] == str2[prefix] {
@@ -350,7 +368,7 @@ impl<'a> TrieIterator<'a> {
     }
 }
 
-enum IterStep {
+pub(super) enum IterStep {
     Continue,
     PopTrail,
     Descend(CryptoHash),
@@ -30,6 +30,7 @@ pub use near_primitives::types::TrieNodesCount;
 use std::fmt::Write;
 
 mod config;
+mod diff;
 mod insert_delete;
 pub mod iterator;
 mod nibble_slice;
@@ -349,6 +349,14 @@ impl WrappedTrieChanges {
         WrappedTrieChanges { tries, shard_uid, trie_changes, state_changes, block_hash }
     }
 
+    pub fn shard_uid(&self) -> ShardUId {
+        self.shard_uid
+    }
+
+    pub fn trie_changes(&self) -> &TrieChanges {
+        &self.trie_changes
+    }
+
     pub fn state_changes(&self) -> &[RawStateChangesWithTrieKey] {
         &self.state_changes
     }
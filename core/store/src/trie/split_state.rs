@@ -1,8 +1,9 @@
 use crate::trie::iterator::TrieItem;
 use crate::{
-    get, get_delayed_receipt_indices, set, ShardTries, StoreUpdate, Trie, TrieChanges, TrieUpdate,
+    get, get_delayed_receipt_indices, set, DBCol, ShardTries, StoreUpdate, Trie, TrieChanges,
+    TrieUpdate,
 };
-use borsh::BorshDeserialize;
+use borsh::{BorshDeserialize, BorshSerialize};
 use bytesize::ByteSize;
 use near_primitives::account::id::AccountId;
 use near_primitives::errors::StorageError;
@@ -16,6 +17,22 @@ use near_primitives::types::{
 };
 use std::collections::HashMap;
 
+/// Progress of building the states of the shards a shard is split into.
+///
+/// Parts of the parent state are added to the child states in batches and the
+/// checkpoint is committed together with the trie nodes of every batch, so an
+/// interrupted split resumes from the first part which hasn't been applied.
+#[derive(BorshSerialize, BorshDeserialize, Clone, Debug, PartialEq, Eq)]
+pub struct StateSplitCheckpoint {
+    /// State root of the parent shard which is being split.
+    pub state_root: StateRoot,
+    pub num_parts: u64,
+    /// First part which hasn't been added to the child states yet.
+    pub next_part: u64,
+    /// State roots of the child shards with all parts before `next_part` added.
+    pub state_roots: Vec<(ShardUId, StateRoot)>,
+}
+
 impl Trie {
     /// Computes the set of trie items (nodes with keys and values) for a state part.
     ///
@@ -35,6 +52,29 @@ impl Trie {
 }
 
 impl ShardTries {
+    pub fn get_state_split_checkpoint(
+        &self,
+        shard_uid: ShardUId,
+    ) -> Result<Option<StateSplitCheckpoint>, StorageError> {
+        self.get_store()
+            .get_ser(DBCol::StateSplitCheckpoints, &shard_uid.to_bytes())
+            .map_err(|_| StorageError::StorageInternalError)
+    }
+
+    pub fn set_state_split_checkpoint(
+        store_update: &mut StoreUpdate,
+        shard_uid: ShardUId,
+        checkpoint: &StateSplitCheckpoint,
+    ) -> Result<(), StorageError> {
+        store_update
+            .set_ser(DBCol::StateSplitCheckpoints, &shard_uid.to_bytes(), checkpoint)
+            .map_err(|_| StorageError::StorageInternalError)
+    }
+
+    pub fn remove_state_split_checkpoint(store_update: &mut StoreUpdate, shard_uid: ShardUId) {
+        store_update.delete(DBCol::StateSplitCheckpoints, &shard_uid.to_bytes());
+    }
+
     /// applies `changes` to split states
     /// and returns the generated TrieChanges for all split states
     /// Note that this function is different from the function `add_values_to_split_states`
@@ -320,7 +360,9 @@ pub fn get_delayed_receipts(
 
 #[cfg(test)]
 mod tests {
-    use crate::split_state::{apply_delayed_receipts_to_split_states_impl, get_delayed_receipts};
+    use crate::split_state::{
+        apply_delayed_receipts_to_split_states_impl, get_delayed_receipts, StateSplitCheckpoint,
+    };
     use crate::test_utils::{
         create_tries, gen_changes, gen_larger_changes, gen_receipts, gen_unique_accounts,
         simplify_changes, test_populate_trie,
@@ -457,6 +499,34 @@ mod tests {
         assert_eq!(expected_trie_items, combined_trie_items);
     }
 
+    #[test]
+    fn test_state_split_checkpoint() {
+        let tries = create_tries();
+        let shard_uid = ShardUId::single_shard();
+        assert_eq!(tries.get_state_split_checkpoint(shard_uid).unwrap(), None);
+
+        let checkpoint = StateSplitCheckpoint {
+            state_root: hash(b"parent"),
+            num_parts: 10,
+            next_part: 4,
+            state_roots: vec![
+                (ShardUId { version: 1, shard_id: 0 }, hash(b"child0")),
+                (ShardUId { version: 1, shard_id: 1 }, hash(b"child1")),
+            ],
+        };
+        let mut store_update = tries.store_update();
+        ShardTries::set_state_split_checkpoint(&mut store_update, shard_uid, &checkpoint).unwrap();
+        store_update.commit().unwrap();
+        assert_eq!(tries.get_state_split_checkpoint(shard_uid).unwrap(), Some(checkpoint));
+        let other_shard_uid = ShardUId { version: 0, shard_id: 1 };
+        assert_eq!(tries.get_state_split_checkpoint(other_shard_uid).unwrap(), None);
+
+        let mut store_update = tries.store_update();
+        ShardTries::remove_state_split_checkpoint(&mut store_update, shard_uid);
+        store_update.commit().unwrap();
+        assert_eq!(tries.get_state_split_checkpoint(shard_uid).unwrap(), None);
+    }
+
     #[test]
     fn test_add_values_to_split_states() {
         let mut rng = rand::thread_rng();
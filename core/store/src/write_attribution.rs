@@ -0,0 +1,132 @@
+//! Attribution of the volume of store writes to the call-sites making them.
+//!
+//! Store updates can be tagged with the logical call-site they belong to,
+//! e.g. saving a block or garbage collection.  When an update is committed,
+//! the number of its operations and the bytes of their keys and values are
+//! added to the totals of its tag, so that unexpected growth of the storage
+//! can be traced to the subsystem responsible for it.  Tags survive merging
+//! of updates: the operations of a tagged update merged into another one
+//! stay attributed to the tag of the merged update.  Operations of untagged
+//! updates are attributed to the update they're merged into, and to
+//! `untagged` if they're committed without a tag.
+//!
+//! The totals are exported as metrics and served by the `store_writes` debug
+//! endpoint.
+use std::collections::HashMap;
+use std::sync::Mutex;
+
+use near_primitives::views::{StoreWriteVolumeView, StoreWritesView};
+use once_cell::sync::Lazy;
+
+use crate::db::DBOp;
+use crate::metrics;
+
+/// Tag of operations committed without one.
+pub(crate) const UNTAGGED: &str = "untagged";
+
+/// Volume of store writes.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct WriteVolume {
+    /// Number of operations.
+    pub ops: u64,
+    /// Bytes of the keys and values written.
+    pub bytes: u64,
+}
+
+impl WriteVolume {
+    pub(crate) fn of(ops: &[DBOp]) -> Self {
+        let bytes = ops
+            .iter()
+            .map(|op| match op {
+                DBOp::Set { key, value, .. }
+                | DBOp::Insert { key, value, .. }
+                | DBOp::UpdateRefcount { key, value, .. } => key.len() + value.len(),
+                DBOp::Delete { key, .. } => key.len(),
+                DBOp::DeleteAll { .. } => 0,
+            })
+            .sum::<usize>();
+        Self { ops: ops.len() as u64, bytes: bytes as u64 }
+    }
+
+    pub(crate) fn add(&mut self, other: WriteVolume) {
+        self.ops += other.ops;
+        self.bytes += other.bytes;
+    }
+
+    pub(crate) fn saturating_sub(self, other: WriteVolume) -> Self {
+        Self {
+            ops: self.ops.saturating_sub(other.ops),
+            bytes: self.bytes.saturating_sub(other.bytes),
+        }
+    }
+}
+
+pub struct WriteAttribution {
+    /// Volume written since the start of the process, by tag.
+    volumes: Mutex<HashMap<&'static str, WriteVolume>>,
+}
+
+static WRITE_ATTRIBUTION: Lazy<WriteAttribution> =
+    Lazy::new(|| WriteAttribution { volumes: Mutex::new(HashMap::new()) });
+
+/// Returns the attribution of the store writes of this process.
+pub fn write_attribution() -> &'static WriteAttribution {
+    &WRITE_ATTRIBUTION
+}
+
+impl WriteAttribution {
+    pub(crate) fn record(&self, tag: &'static str, volume: WriteVolume) {
+        if volume.ops == 0 {
+            return;
+        }
+        metrics::STORE_WRITE_OPS_BY_TAG.with_label_values(&[tag]).inc_by(volume.ops);
+        metrics::STORE_WRITE_BYTES_BY_TAG.with_label_values(&[tag]).inc_by(volume.bytes);
+        self.volumes.lock().unwrap().entry(tag).or_default().add(volume);
+    }
+
+    /// Returns the volume written by every tag, the largest first.
+    pub fn view(&self) -> StoreWritesView {
+        let mut by_tag: Vec<_> = self
+            .volumes
+            .lock()
+            .unwrap()
+            .iter()
+            .map(|(tag, volume)| StoreWriteVolumeView {
+                tag: tag.to_string(),
+                ops: volume.ops,
+                bytes: volume.bytes,
+            })
+            .collect();
+        by_tag.sort_by(|a, b| b.bytes.cmp(&a.bytes).then_with(|| a.tag.cmp(&b.tag)));
+        StoreWritesView { by_tag }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{write_attribution, WriteVolume};
+    use crate::DBCol;
+
+    fn volume_of(tag: &'static str) -> WriteVolume {
+        write_attribution().volumes.lock().unwrap().get(tag).copied().unwrap_or_default()
+    }
+
+    #[test]
+    fn test_merged_updates_keep_their_tags() {
+        let store = crate::test_utils::create_test_store();
+        let mut inner = store.store_update();
+        inner.set_tag("test_inner");
+        inner.set(DBCol::BlockMisc, b"a", b"12");
+        inner.set(DBCol::BlockMisc, b"b", b"34");
+        let mut untagged = store.store_update();
+        untagged.set(DBCol::BlockMisc, b"c", b"5");
+        let mut outer = store.store_update();
+        outer.set_tag("test_outer");
+        outer.set(DBCol::BlockMisc, b"d", b"678");
+        outer.merge(inner);
+        outer.merge(untagged);
+        outer.commit().unwrap();
+        assert_eq!(volume_of("test_inner"), WriteVolume { ops: 2, bytes: 6 });
+        assert_eq!(volume_of("test_outer"), WriteVolume { ops: 2, bytes: 6 });
+    }
+}
@@ -20,8 +20,14 @@ pub fn genesis_header(genesis: &Genesis) -> BlockHeader {
     let store = create_test_store();
     let chain_genesis = ChainGenesis::new(genesis);
     let runtime = Arc::new(NightshadeRuntime::test(dir.path(), store, genesis));
-    let chain =
-        Chain::new(runtime, &chain_genesis, DoomslugThresholdMode::TwoThirds, true).unwrap();
+    let chain = Chain::new(
+        runtime,
+        &chain_genesis,
+        DoomslugThresholdMode::TwoThirds,
+        true,
+        &near_o11y::metrics::MetricsRegistry::global(),
+    )
+    .unwrap();
     chain.genesis().clone()
 }
 
@@ -31,7 +37,13 @@ pub fn genesis_block(genesis: &Genesis) -> Block {
     let store = create_test_store();
     let chain_genesis = ChainGenesis::new(genesis);
     let runtime = Arc::new(NightshadeRuntime::test(dir.path(), store, genesis));
-    let chain =
-        Chain::new(runtime, &chain_genesis, DoomslugThresholdMode::TwoThirds, true).unwrap();
+    let chain = Chain::new(
+        runtime,
+        &chain_genesis,
+        DoomslugThresholdMode::TwoThirds,
+        true,
+        &near_o11y::metrics::MetricsRegistry::global(),
+    )
+    .unwrap();
     chain.get_block(&chain.genesis().hash().clone()).unwrap()
 }
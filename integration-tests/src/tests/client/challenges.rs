@@ -2,6 +2,7 @@ use assert_matches::assert_matches;
 use borsh::BorshSerialize;
 
 use crate::tests::client::process_blocks::create_nightshade_runtimes;
+use near_chain::state_mismatch::StateMismatchBundle;
 use near_chain::validate::validate_challenge;
 use near_chain::{Block, Chain, ChainGenesis, ChainStoreAccess, Error, Provenance};
 use near_chain_configs::Genesis;
@@ -63,20 +64,26 @@ fn test_block_with_challenges() {
     assert_matches!(result.unwrap_err(), Error::InvalidChallengeRoot);
 }
 
-/// Check that attempt to process block on top of incorrect state root leads to InvalidChunkState error.
+/// Check that attempt to process block on top of incorrect state root leads to InvalidChunkState error
+/// and saves a forensic bundle which replays the previous block.
 #[test]
 fn test_invalid_chunk_state() {
     let genesis = Genesis::test(vec!["test0".parse().unwrap()], 1);
     let mut env = TestEnv::builder(ChainGenesis::test())
         .runtime_adapters(create_nightshade_runtimes(&genesis, 1))
         .build();
+    let bundles_dir = tempfile::tempdir().unwrap();
+    let version = env.clients[0].config.version.clone();
+    env.clients[0].chain.enable_state_mismatch_bundles(bundles_dir.path().to_path_buf(), version);
     env.produce_block(0, 1);
     let block_hash = env.clients[0].chain.get_block_hash_by_height(1).unwrap();
+    let state_root;
 
     {
         let mut chunk_extra = ChunkExtra::clone(
             &env.clients[0].chain.get_chunk_extra(&block_hash, &ShardUId::single_shard()).unwrap(),
         );
+        state_root = *chunk_extra.state_root();
         let store = env.clients[0].chain.mut_store();
         let mut store_update = store.store_update();
         assert_ne!(chunk_extra.state_root(), &Trie::EMPTY_ROOT);
@@ -86,8 +93,21 @@ fn test_invalid_chunk_state() {
     }
 
     let block = env.clients[0].produce_block(2).unwrap().unwrap();
+    let bundle_path = StateMismatchBundle::path(bundles_dir.path(), block.hash(), 0);
     let result = env.clients[0].process_block_test(block.into(), Provenance::NONE);
     assert_matches!(result.unwrap_err(), Error::InvalidChunkState(_));
+
+    // The bundle is captured in the background.
+    let started = std::time::Instant::now();
+    while !bundle_path.exists() {
+        assert!(started.elapsed() < std::time::Duration::from_secs(60), "bundle wasn't saved");
+        std::thread::sleep(std::time::Duration::from_millis(10));
+    }
+    let bundle = StateMismatchBundle::load(&bundle_path).unwrap();
+    assert_eq!(bundle.prev_block_header.hash(), &block_hash);
+    assert_eq!(bundle.prev_chunk_extra.state_root(), &Trie::EMPTY_ROOT);
+    assert_eq!(bundle.new_state_root, state_root);
+    assert!(!bundle.partial_state.0.is_empty());
 }
 
 #[test]
@@ -115,7 +115,8 @@ impl Test {
             Box::new(move |_, from_whom: AccountId, msg: &PeerManagerMessageRequest| {
                 let msg = msg.as_network_requests_ref();
                 match msg {
-                    NetworkRequests::Block { block } => {
+                    NetworkRequests::Block { block }
+                    | NetworkRequests::BlockHeaderFirst { block } => {
                         let h = block.header().height();
                         check_heights(block.header().prev_hash(), block.hash(), h);
 
@@ -9,7 +9,9 @@ use near_primitives::sharding::ShardChunk;
 use near_primitives::transaction::{
     Action, DeployContractAction, FunctionCallAction, SignedTransaction,
 };
-use near_store::cold_storage::{test_cold_genesis_update, test_get_store_reads, update_cold_db};
+use near_store::cold_storage::{
+    test_cold_genesis_update, test_get_store_reads, update_cold_db, update_cold_head,
+};
 use near_store::db::TestDB;
 use near_store::{DBCol, NodeStorage, Store, Temperature};
 use nearcore::config::GenesisExt;
@@ -128,6 +130,10 @@ fn test_storage_after_commit_of_cold_update() {
         let block = env.clients[0].produce_block(h).unwrap().unwrap();
         env.process_block(0, block.clone(), Provenance::PRODUCED);
 
+        // The cold head can't move past a block that isn't in cold storage yet.
+        let hot_store = env.clients[0].runtime_adapter.store();
+        assert!(update_cold_head(&*cold_db, &hot_store, &h).is_err());
+
         update_cold_db(
             &*cold_db,
             &env.clients[0].runtime_adapter.store(),
@@ -143,6 +149,8 @@ fn test_storage_after_commit_of_cold_update() {
             &h,
         )
         .unwrap();
+        update_cold_head(&*cold_db, &hot_store, &h).unwrap();
+        assert_eq!(env.clients[0].chain.store().cold_head().unwrap(), Some(h));
 
         last_hash = block.hash().clone();
     }
@@ -0,0 +1,173 @@
+//! Relaying of delegate actions, which a relayer submits and pays for on
+//! behalf of their sender.
+use crate::tests::client::process_blocks::create_nightshade_runtimes;
+use assert_matches::assert_matches;
+use near_chain::ChainGenesis;
+use near_chain_configs::Genesis;
+use near_client::test_utils::TestEnv;
+use near_client::{ProcessTxResponse, TxRejectionReason};
+use near_crypto::{InMemorySigner, KeyType, Signer};
+use near_primitives::account::AccessKey;
+use near_primitives::errors::{ActionError, ActionErrorKind, TxExecutionError};
+use near_primitives::hash::CryptoHash;
+use near_primitives::transaction::{
+    Action, DelegateAction, SignedDelegateAction, SignedTransaction, TransferAction,
+};
+use near_primitives::types::{AccountId, Balance, BlockHeight, Nonce};
+use near_primitives::views::{ExecutionStatusView, FinalExecutionStatus};
+use nearcore::config::GenesisExt;
+use nearcore::NEAR_BASE;
+
+fn signer(account_id: &str) -> InMemorySigner {
+    InMemorySigner::from_seed(account_id.parse().unwrap(), KeyType::ED25519, account_id)
+}
+
+/// Transfer of `NEAR_BASE` from `test0` to `receiver_id`, signed by `test0`.
+fn delegate_transfer(
+    receiver_id: &str,
+    nonce: Nonce,
+    max_block_height: BlockHeight,
+) -> SignedDelegateAction {
+    let sender = signer("test0");
+    let delegate_action = DelegateAction {
+        sender_id: sender.account_id.clone(),
+        receiver_id: receiver_id.parse().unwrap(),
+        actions: vec![Action::Transfer(TransferAction { deposit: NEAR_BASE })],
+        nonce,
+        max_block_height,
+        public_key: sender.public_key(),
+    };
+    let signature = sender.sign(delegate_action.get_hash().as_ref());
+    SignedDelegateAction { delegate_action, signature }
+}
+
+/// Transaction of the relayer `test2` relaying the delegate action.
+fn relay(
+    nonce: Nonce,
+    signed_delegate_action: SignedDelegateAction,
+    block_hash: CryptoHash,
+) -> SignedTransaction {
+    SignedTransaction::from_actions(
+        nonce,
+        "test2".parse().unwrap(),
+        "test0".parse().unwrap(),
+        &signer("test2"),
+        vec![Action::Delegate(signed_delegate_action)],
+        block_hash,
+    )
+}
+
+fn setup_env() -> TestEnv {
+    let accounts: Vec<AccountId> =
+        vec!["test0".parse().unwrap(), "test1".parse().unwrap(), "test2".parse().unwrap()];
+    let genesis = Genesis::test(accounts, 1);
+    TestEnv::builder(ChainGenesis::test())
+        .runtime_adapters(create_nightshade_runtimes(&genesis, 1))
+        .build()
+}
+
+#[test]
+fn test_relay_delegate_action() {
+    let mut env = setup_env();
+    let genesis_hash = *env.clients[0].chain.get_block_by_height(0).unwrap().hash();
+    let sender_balance = env.query_balance("test0".parse().unwrap());
+    let receiver_balance = env.query_balance("test1".parse().unwrap());
+    let relayer_balance = env.query_balance("test2".parse().unwrap());
+
+    let tx = relay(1, delegate_transfer("test1", 1, 100), genesis_hash);
+    let tx_hash = tx.get_hash();
+    assert_eq!(env.clients[0].process_tx(tx, false, false), ProcessTxResponse::ValidTx);
+    for height in 1..10 {
+        env.produce_block(0, height);
+    }
+    let outcome = env.clients[0].chain.get_final_transaction_result(&tx_hash).unwrap();
+    assert_matches!(outcome.status, FinalExecutionStatus::SuccessValue(_));
+    // The relayer pays for the transfer of the sender.
+    assert_eq!(env.query_balance("test0".parse().unwrap()), sender_balance);
+    assert_eq!(env.query_balance("test1".parse().unwrap()), receiver_balance + NEAR_BASE);
+    assert!(env.query_balance("test2".parse().unwrap()) < relayer_balance - NEAR_BASE);
+
+    // The delegate action can't be replayed.
+    let tx = relay(2, delegate_transfer("test1", 1, 100), genesis_hash);
+    let tx_hash = tx.get_hash();
+    assert_eq!(env.clients[0].process_tx(tx, false, false), ProcessTxResponse::ValidTx);
+    for height in 10..15 {
+        env.produce_block(0, height);
+    }
+    let outcome = env.clients[0].chain.get_final_transaction_result(&tx_hash).unwrap();
+    assert_eq!(
+        outcome.status,
+        FinalExecutionStatus::Failure(TxExecutionError::ActionError(ActionError {
+            index: Some(0),
+            kind: ActionErrorKind::DelegateActionInvalidNonce { delegate_nonce: 1, ak_nonce: 1 },
+        }))
+    );
+    assert_eq!(env.query_balance("test1".parse().unwrap()), receiver_balance + NEAR_BASE);
+
+    // Expired delegate actions aren't accepted into the pool.
+    let head = env.clients[0].chain.head().unwrap();
+    let tx = relay(3, delegate_transfer("test1", 2, head.height), head.last_block_hash);
+    assert_eq!(
+        env.clients[0].process_tx(tx, false, false),
+        ProcessTxResponse::Rejected(TxRejectionReason::DelegateActionExpired {
+            max_block_height: head.height,
+            next_height: head.height + 1,
+        })
+    );
+}
+
+#[test]
+// Verifies that once the relayed actions fail, their deposits are refunded to
+// the sender and the unused gas to the relayer.
+fn test_relay_delegate_action_refunds() {
+    let mut env = setup_env();
+    let genesis_hash = *env.clients[0].chain.get_block_by_height(0).unwrap().hash();
+    let sender_balance = env.query_balance("test0".parse().unwrap());
+    let relayer_balance = env.query_balance("test2".parse().unwrap());
+
+    let tx = relay(1, delegate_transfer("test3", 1, 100), genesis_hash);
+    let tx_hash = tx.get_hash();
+    assert_eq!(env.clients[0].process_tx(tx, false, false), ProcessTxResponse::ValidTx);
+    for height in 1..10 {
+        env.produce_block(0, height);
+    }
+    let outcome = env.clients[0].chain.get_final_transaction_result(&tx_hash).unwrap();
+    assert!(outcome.receipts_outcome.iter().any(|outcome| outcome.outcome.status
+        == ExecutionStatusView::Failure(TxExecutionError::ActionError(ActionError {
+            index: Some(0),
+            kind: ActionErrorKind::AccountDoesNotExist { account_id: "test3".parse().unwrap() },
+        }))));
+    assert_eq!(env.query_balance("test0".parse().unwrap()), sender_balance + NEAR_BASE);
+    let tokens_burnt: Balance = std::iter::once(&outcome.transaction_outcome)
+        .chain(&outcome.receipts_outcome)
+        .map(|outcome| outcome.outcome.tokens_burnt)
+        .sum();
+    assert_eq!(
+        env.query_balance("test2".parse().unwrap()),
+        relayer_balance - NEAR_BASE - tokens_burnt
+    );
+}
+
+#[test]
+// Verifies that the nonce of a delegate action is bounded by the height, as the
+// nonce of a transaction is, so that it can't use up the nonces of its key.
+fn test_relay_delegate_action_nonce_too_large() {
+    let mut env = setup_env();
+    let genesis_hash = *env.clients[0].chain.get_block_by_height(0).unwrap().hash();
+
+    let delegate_nonce = 100 * AccessKey::ACCESS_KEY_NONCE_RANGE_MULTIPLIER;
+    let tx = relay(1, delegate_transfer("test1", delegate_nonce, 100), genesis_hash);
+    let tx_hash = tx.get_hash();
+    assert_eq!(env.clients[0].process_tx(tx, false, false), ProcessTxResponse::ValidTx);
+    for height in 1..10 {
+        env.produce_block(0, height);
+    }
+    let outcome = env.clients[0].chain.get_final_transaction_result(&tx_hash).unwrap();
+    assert_matches!(
+        outcome.status,
+        FinalExecutionStatus::Failure(TxExecutionError::ActionError(ActionError {
+            index: Some(0),
+            kind: ActionErrorKind::DelegateActionNonceTooLarge { delegate_nonce: 100_000_000, .. },
+        }))
+    );
+}
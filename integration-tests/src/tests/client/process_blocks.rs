@@ -13,11 +13,12 @@ use near_primitives::num_rational::{Ratio, Rational32};
 
 use near_actix_test_utils::run_actix;
 use near_chain::chain::ApplyStatePartsRequest;
+use near_chain::state_parts_apply::apply_state_parts;
 use near_chain::types::LatestKnown;
 use near_chain::validate::validate_chunk_with_chunk_extra;
 use near_chain::{
-    Block, BlockProcessingArtifact, ChainGenesis, ChainStore, ChainStoreAccess, Error, Provenance,
-    RuntimeAdapter,
+    Block, BlockProcessingArtifact, Chain, ChainGenesis, ChainStore, ChainStoreAccess, Error,
+    Provenance, RuntimeAdapter,
 };
 use near_chain_configs::{ClientConfig, Genesis, DEFAULT_GC_NUM_EPOCHS_TO_KEEP};
 use near_chunks::{ChunkStatus, ShardsManager};
@@ -25,11 +26,12 @@ use near_client::test_utils::{
     create_chunk_on_height, setup_client, setup_mock, setup_mock_all_validators, TestEnv,
 };
 use near_client::{
-    BlockApproval, BlockResponse, Client, GetBlock, GetBlockWithMerkleTree, ProcessTxRequest,
-    ProcessTxResponse, SetNetworkInfo,
+    BlockApproval, BlockProductionFailure, BlockResponse, Client, GetBlock, GetBlockWithMerkleTree,
+    ProcessTxRequest, ProcessTxResponse, SetNetworkInfo,
 };
+use near_client_primitives::types::StatePartsApplyingStatus;
 use near_crypto::{InMemorySigner, KeyType, PublicKey, Signature, Signer};
-use near_network::test_utils::{wait_or_panic, MockPeerManagerAdapter};
+use near_network::test_utils::{peer_id_from_seed, wait_or_panic, MockPeerManagerAdapter};
 use near_network::types::{
     ConnectedPeerInfo, NetworkInfo, PeerManagerMessageRequest, PeerManagerMessageResponse,
 };
@@ -53,7 +55,7 @@ use near_primitives::sharding::{
     ShardChunkHeaderV3,
 };
 use near_primitives::state_part::PartId;
-use near_primitives::syncing::{get_num_state_parts, ShardStateSyncResponseHeader, StatePartKey};
+use near_primitives::syncing::{get_num_state_parts, ShardStateSyncResponseHeader};
 use near_primitives::transaction::{
     Action, DeployContractAction, ExecutionStatus, FunctionCallAction, SignedTransaction,
     Transaction,
@@ -65,10 +67,10 @@ use near_primitives::utils::to_timestamp;
 use near_primitives::validator_signer::{InMemoryValidatorSigner, ValidatorSigner};
 use near_primitives::version::PROTOCOL_VERSION;
 use near_primitives::views::{
-    BlockHeaderView, FinalExecutionStatus, QueryRequest, QueryResponseKind,
+    BlockHeaderView, FinalExecutionStatus, QueryRequest, QueryResponseKind, TxInclusionStatus,
 };
+use near_store::get;
 use near_store::test_utils::create_test_store;
-use near_store::{get, DBCol};
 use nearcore::config::{GenesisExt, TESTING_INIT_BALANCE, TESTING_INIT_STAKE};
 use nearcore::NEAR_BASE;
 use rand::prelude::StdRng;
@@ -255,7 +257,9 @@ fn produce_two_blocks() {
             true,
             false,
             Box::new(move |msg, _ctx, _| {
-                if let NetworkRequests::Block { .. } = msg.as_network_requests_ref() {
+                if let NetworkRequests::Block { .. } | NetworkRequests::BlockHeaderFirst { .. } =
+                    msg.as_network_requests_ref()
+                {
                     count.fetch_add(1, Ordering::Relaxed);
                     if count.load(Ordering::Relaxed) >= 2 {
                         System::current().stop();
@@ -427,7 +431,9 @@ fn produce_block_with_approvals() {
             true,
             false,
             Box::new(move |msg, _ctx, _| {
-                if let NetworkRequests::Block { block } = msg.as_network_requests_ref() {
+                if let NetworkRequests::Block { block }
+                | NetworkRequests::BlockHeaderFirst { block } = msg.as_network_requests_ref()
+                {
                     // Below we send approvals from all the block producers except for test1 and test2
                     // test1 will only create their approval for height 10 after their doomslug timer
                     // runs 10 iterations, which is way further in the future than them producing the
@@ -556,7 +562,8 @@ fn produce_block_with_approvals_arrived_early() {
                       -> (PeerManagerMessageResponse, bool) {
                     let msg = msg.as_network_requests_ref();
                     match msg {
-                        NetworkRequests::Block { block } => {
+                        NetworkRequests::Block { block }
+                        | NetworkRequests::BlockHeaderFirst { block } => {
                             if block.header().height() == 3 {
                                 for (i, (client, _)) in conns.iter().enumerate() {
                                     if i > 0 {
@@ -619,7 +626,8 @@ fn invalid_blocks_common(is_requested: bool) {
             false,
             Box::new(move |msg, _ctx, _client_actor| {
                 match msg.as_network_requests_ref() {
-                    NetworkRequests::Block { block } => {
+                    NetworkRequests::Block { block }
+                    | NetworkRequests::BlockHeaderFirst { block } => {
                         if is_requested {
                             panic!("rebroadcasting requested block");
                         } else {
@@ -837,7 +845,8 @@ fn ban_peer_for_invalid_block_common(mode: InvalidBlockMode) {
                       msg: &PeerManagerMessageRequest|
                       -> (PeerManagerMessageResponse, bool) {
                     match msg.as_network_requests_ref() {
-                        NetworkRequests::Block { block } => {
+                        NetworkRequests::Block { block }
+                        | NetworkRequests::BlockHeaderFirst { block } => {
                             if block.header().height() >= 4 && !sent_bad_blocks {
                                 let block_producer_idx =
                                     block.header().height() as usize % validators.len();
@@ -986,7 +995,8 @@ fn skip_block_production() {
             false,
             Box::new(move |msg, _ctx, _client_actor| {
                 match msg.as_network_requests_ref() {
-                    NetworkRequests::Block { block } => {
+                    NetworkRequests::Block { block }
+                    | NetworkRequests::BlockHeaderFirst { block } => {
                         if block.header().height() > 3 {
                             System::current().stop();
                         }
@@ -1035,6 +1045,7 @@ fn client_sync_headers() {
                         archival: false,
                     },
                     partial_edge_info: near_network::types::PartialEdgeInfo::default(),
+                    state_sub_part_size_limit: 0,
                 })],
                 num_connected_peers: 1,
                 peer_max_count: 1,
@@ -1047,11 +1058,13 @@ fn client_sync_headers() {
                         archival: false,
                     },
                     partial_edge_info: near_network::types::PartialEdgeInfo::default(),
+                    state_sub_part_size_limit: 0,
                 }],
                 sent_bytes_per_sec: 0,
                 received_bytes_per_sec: 0,
                 known_producers: vec![],
                 tier1_accounts: vec![],
+                bandwidth_by_type: vec![],
             })
             .with_span_context(),
         );
@@ -1190,6 +1203,47 @@ fn test_no_double_sign() {
     assert_eq!(env.clients[0].produce_block(1).unwrap(), None);
 }
 
+#[test]
+fn test_simulate_block_production() {
+    init_test_logger();
+    let mut env =
+        TestEnv::builder(ChainGenesis::test()).clients_count(2).validator_seats(2).build();
+    let genesis_hash = *env.clients[0].chain.genesis().hash();
+    let runtime_adapter = env.clients[0].runtime_adapter.clone();
+    let epoch_id = runtime_adapter.get_epoch_id_from_prev_block(&genesis_hash).unwrap();
+    let block_producer = runtime_adapter.get_block_producer(&epoch_id, 1).unwrap();
+    let producer = (0..2).find(|&i| env.get_client_id(i) == &block_producer).unwrap();
+    let other = 1 - producer;
+
+    // No approvals have been received yet.
+    let not_enough_approvals =
+        BlockProductionFailure::NotEnoughApprovals { largest_height_crossing_threshold: 0 };
+    let report = env.clients[producer].simulate_block_production(1).unwrap();
+    assert_eq!(report.prev_hash, genesis_hash);
+    assert_eq!(report.block_producer, block_producer);
+    assert_eq!(report.failures, vec![not_enough_approvals.clone()]);
+    assert!(!report.can_produce());
+    let report = env.clients[other].simulate_block_production(1).unwrap();
+    assert_eq!(
+        report.failures,
+        vec![
+            BlockProductionFailure::NotBlockProducer { block_producer },
+            not_enough_approvals.clone()
+        ]
+    );
+
+    // The dry run doesn't prevent producing the block.
+    env.clients[producer].produce_block(1).unwrap().unwrap();
+    let report = env.clients[producer].simulate_block_production(1).unwrap();
+    assert_eq!(
+        report.failures,
+        vec![
+            BlockProductionFailure::HeightAlreadyKnown { latest_known_height: 1 },
+            not_enough_approvals
+        ]
+    );
+}
+
 #[test]
 fn test_invalid_gas_price() {
     init_test_logger();
@@ -1574,6 +1628,139 @@ fn test_gc_execution_outcome() {
     assert!(env.clients[0].chain.get_final_transaction_result(&tx_hash).is_err());
 }
 
+/// Test that a subscription to the final outcome of a transaction is answered
+/// right away once the outcome is known and is pushed to the subscriber once
+/// it becomes known otherwise.
+#[test]
+fn test_tx_status_subscription() {
+    let mut genesis = Genesis::test(vec!["test0".parse().unwrap(), "test1".parse().unwrap()], 1);
+    genesis.config.epoch_length = 10;
+    let mut chain_genesis = ChainGenesis::test();
+    chain_genesis.epoch_length = 10;
+    let mut env = TestEnv::builder(chain_genesis)
+        .runtime_adapters(create_nightshade_runtimes(&genesis, 1))
+        .build();
+    let genesis_hash = *env.clients[0].chain.genesis().hash();
+    let signer = InMemorySigner::from_seed("test0".parse().unwrap(), KeyType::ED25519, "test0");
+    let tx = SignedTransaction::send_money(
+        1,
+        "test0".parse().unwrap(),
+        "test1".parse().unwrap(),
+        &signer,
+        100,
+        genesis_hash,
+    );
+    let tx_hash = tx.get_hash();
+    let subscriber = peer_id_from_seed("subscriber");
+    let route_back = hash(b"route_back");
+
+    let outcome = env.clients[0]
+        .subscribe_tx_status(&"test0".parse().unwrap(), tx_hash, route_back, subscriber.clone())
+        .unwrap();
+    assert!(outcome.is_none());
+    env.clients[0].process_tx(tx, false, false);
+    let mut pushed = vec![];
+    for height in 1..10 {
+        env.produce_block(0, height);
+        while let Some(request) = env.network_adapters[0].pop() {
+            if let NetworkRequests::TxStatusPush { route_back, response } =
+                request.as_network_requests_ref()
+            {
+                pushed.push((*route_back, response.transaction.hash));
+            }
+        }
+    }
+    assert_eq!(pushed, vec![(route_back, tx_hash)]);
+
+    // Once the outcome is known, it's returned right away and isn't pushed.
+    let outcome = env.clients[0]
+        .subscribe_tx_status(&"test0".parse().unwrap(), tx_hash, route_back, subscriber)
+        .unwrap();
+    assert_eq!(outcome.unwrap().transaction.hash, tx_hash);
+    env.produce_block(0, 10);
+    while let Some(request) = env.network_adapters[0].pop() {
+        assert!(!matches!(request.as_network_requests_ref(), NetworkRequests::TxStatusPush { .. }));
+    }
+}
+
+/// Test that a deferred transaction is validated when it's submitted, is held
+/// out of the pool until its anchor block is final and is dropped if its
+/// anchor block is orphaned.
+#[test]
+fn test_deferred_tx() {
+    let mut genesis = Genesis::test(vec!["test0".parse().unwrap(), "test1".parse().unwrap()], 1);
+    genesis.config.epoch_length = 100;
+    let chain_genesis = ChainGenesis::new(&genesis);
+    let mut env = TestEnv::builder(chain_genesis)
+        .runtime_adapters(create_nightshade_runtimes(&genesis, 1))
+        .build();
+    let mut blocks = vec![];
+    for height in 1..5 {
+        let block = env.clients[0].produce_block(height).unwrap().unwrap();
+        blocks.push(block.clone());
+        env.process_block(0, block, Provenance::PRODUCED);
+    }
+    let signer_id: AccountId = "test0".parse().unwrap();
+    let signer = InMemorySigner::from_seed(signer_id.clone(), KeyType::ED25519, "test0");
+    let make_tx = |nonce, amount, signer: &InMemorySigner, anchor| {
+        SignedTransaction::send_money(
+            nonce,
+            signer_id.clone(),
+            "test1".parse().unwrap(),
+            signer,
+            amount,
+            anchor,
+        )
+    };
+    let anchor = *blocks.last().unwrap().hash();
+
+    // Deferred transactions are validated like the ones submitted right away.
+    let wrong_signer = InMemorySigner::from_seed(signer_id.clone(), KeyType::ED25519, "wrong");
+    assert_matches!(
+        env.clients[0].defer_tx(make_tx(1, 100, &wrong_signer, anchor)),
+        ProcessTxResponse::InvalidTx(InvalidTxError::InvalidSignature)
+    );
+    assert_matches!(
+        env.clients[0].defer_tx(make_tx(1, TESTING_INIT_BALANCE, &signer, anchor)),
+        ProcessTxResponse::InvalidTx(InvalidTxError::NotEnoughBalance { .. })
+    );
+
+    // The block at height 4 isn't final yet, so the transaction is held.
+    let tx = make_tx(1, 100, &signer, anchor);
+    assert_eq!(env.clients[0].defer_tx(tx.clone()), ProcessTxResponse::ValidTx);
+    let status = env.clients[0].tx_fork_status(&tx.get_hash(), &signer_id).unwrap();
+    assert!(!status.in_pool);
+
+    // Make a fork: the block at height 5 is orphaned by the one at height 6.
+    let fork1_block = env.clients[0].produce_block(5).unwrap().unwrap();
+    env.clients[0]
+        .chain
+        .mut_store()
+        .save_latest_known(LatestKnown {
+            height: blocks.last().unwrap().header().height(),
+            seen: blocks.last().unwrap().header().raw_timestamp(),
+        })
+        .unwrap();
+    let fork2_block = env.clients[0].produce_block(6).unwrap().unwrap();
+    env.process_block(0, fork1_block.clone(), Provenance::NONE);
+    let orphaned_tx = make_tx(2, 100, &signer, *fork1_block.hash());
+    assert_eq!(env.clients[0].defer_tx(orphaned_tx.clone()), ProcessTxResponse::ValidTx);
+    env.process_block(0, fork2_block, Provenance::NONE);
+    assert_eq!(env.clients[0].chain.head().unwrap().height, 6);
+
+    // Once the block at height 4 is final the transaction is released and
+    // included, while the one anchored to the orphaned block is dropped.
+    for height in 7..12 {
+        env.produce_block(0, height);
+    }
+    assert!(env.clients[0].chain.final_head().unwrap().height >= 5);
+    let status = env.clients[0].tx_fork_status(&tx.get_hash(), &signer_id).unwrap();
+    assert_eq!(status.status, TxInclusionStatus::Included);
+    let status = env.clients[0].tx_fork_status(&orphaned_tx.get_hash(), &signer_id).unwrap();
+    assert_eq!(status.status, TxInclusionStatus::Unknown);
+    assert!(!status.in_pool);
+}
+
 #[test]
 #[cfg_attr(not(feature = "expensive_tests"), ignore)]
 fn test_gc_after_state_sync() {
@@ -2122,16 +2309,20 @@ fn test_sync_hash_validity() {
     }
     for i in 0..19 {
         let block_hash = *env.clients[0].chain.get_block_header_by_height(i).unwrap().hash();
-        let res = env.clients[0].chain.check_sync_hash_validity(&block_hash);
+        let res = env.clients[0].chain.check_sync_hash_validity(&block_hash, 2);
         println!("height {:?} -> {:?}", i, res);
         if i == 11 || i == 16 {
             assert!(res.unwrap())
         } else {
             assert!(!res.unwrap())
         }
+        let chain = &env.clients[0].chain;
+        assert_eq!(chain.check_sync_hash_validity(&block_hash, 1).unwrap(), i == 16);
+        let valid = i == 6 || i == 11 || i == 16;
+        assert_eq!(chain.check_sync_hash_validity(&block_hash, 3).unwrap(), valid);
     }
     let bad_hash = CryptoHash::from_str("7tkzFg8RHBmMw1ncRJZCCZAizgq4rwCftTKYLce8RU8t").unwrap();
-    let res = env.clients[0].chain.check_sync_hash_validity(&bad_hash);
+    let res = env.clients[0].chain.check_sync_hash_validity(&bad_hash, 2);
     println!("bad hash -> {:?}", res.is_ok());
     match res {
         Ok(_) => assert!(false),
@@ -2362,7 +2553,7 @@ fn test_catchup_gas_price_change() {
 
     // Simulate state sync
     let sync_hash = *blocks[5].hash();
-    assert!(env.clients[0].chain.check_sync_hash_validity(&sync_hash).unwrap());
+    assert!(env.clients[0].chain.check_sync_hash_validity(&sync_hash, 2).unwrap());
     let state_sync_header = env.clients[0].chain.get_state_response_header(0, sync_hash).unwrap();
     let state_root = match &state_sync_header {
         ShardStateSyncResponseHeader::V1(header) => header.chunk.header.inner.prev_state_root,
@@ -2385,26 +2576,15 @@ fn test_catchup_gas_price_change() {
             .set_state_part(0, sync_hash, PartId::new(i, num_parts), &state_sync_parts[i as usize])
             .unwrap();
     }
-    let rt = Arc::clone(&env.clients[1].runtime_adapter);
-    let f = move |msg: ApplyStatePartsRequest| {
-        use borsh::BorshSerialize;
-        let store = rt.store();
-
-        for part_id in 0..msg.num_parts {
-            let key = StatePartKey(msg.sync_hash, msg.shard_id, part_id).try_to_vec().unwrap();
-            let part = store.get(DBCol::StateParts, &key).unwrap().unwrap();
-
-            rt.apply_state_part(
-                msg.shard_id,
-                &msg.state_root,
-                PartId::new(part_id, msg.num_parts),
-                &part,
-                &msg.epoch_id,
-            )
-            .unwrap();
-        }
-    };
-    env.clients[1].chain.schedule_apply_state_parts(0, sync_hash, num_parts, &f).unwrap();
+    // A budget for a single part makes the parts applied one by one.
+    let f = move |msg: ApplyStatePartsRequest| apply_state_parts(&msg, 1).unwrap();
+    let status = Arc::new(StatePartsApplyingStatus::new(num_parts));
+    env.clients[1]
+        .chain
+        .schedule_apply_state_parts(0, sync_hash, num_parts, &f, status.clone())
+        .unwrap();
+    assert_eq!(status.progress_view().applied_parts, num_parts);
+    assert!(status.progress_view().applying_parts.is_empty());
     env.clients[1].chain.set_state_finalize(0, sync_hash, Ok(())).unwrap();
     let chunk_extra_after_sync =
         env.clients[1].chain.get_chunk_extra(blocks[4].hash(), &ShardUId::single_shard()).unwrap();
@@ -2495,6 +2675,127 @@ fn test_block_execution_outcomes() {
     assert!(execution_outcomes_from_block[0].outcome_with_id.id == delayed_receipt_id[0]);
 }
 
+#[test]
+fn test_receipt_inclusion_proof() {
+    init_test_logger();
+
+    let genesis = Genesis::test(vec!["test0".parse().unwrap(), "test1".parse().unwrap()], 1);
+    let chain_genesis = ChainGenesis::new(&genesis);
+    let mut env = TestEnv::builder(chain_genesis)
+        .runtime_adapters(create_nightshade_runtimes(&genesis, 1))
+        .build();
+    let genesis_block = env.clients[0].chain.get_block_by_height(0).unwrap();
+    let signer = InMemorySigner::from_seed("test0".parse().unwrap(), KeyType::ED25519, "test0");
+    // A transfer to another account sends a receipt from the chunk after
+    // the one including the transaction.
+    let tx = SignedTransaction::send_money(
+        1,
+        "test0".parse().unwrap(),
+        "test1".parse().unwrap(),
+        &signer,
+        1,
+        *genesis_block.hash(),
+    );
+    let tx_hash = tx.get_hash();
+    env.clients[0].process_tx(tx, false, false);
+    for i in 1..6 {
+        env.produce_block(0, i);
+    }
+
+    let chain = &env.clients[0].chain;
+    let tx_outcome = chain.get_execution_outcome(&tx_hash).unwrap();
+    let receipt_id = tx_outcome.outcome_with_id.outcome.receipt_ids[0];
+    let proof = chain.get_receipt_inclusion_proof(&receipt_id).unwrap().unwrap();
+    let block = chain.get_block(&proof.block_hash).unwrap();
+    assert_eq!(proof.block_height, block.header().height());
+    assert_eq!(proof.receipt_proof.to_shard_id, 0);
+    assert!(proof.verify(block.header().chunk_receipts_root()));
+    assert!(!proof.verify(genesis_block.header().chunk_receipts_root()));
+    // No chunk sends transactions.
+    assert_eq!(chain.get_receipt_inclusion_proof(&tx_hash).unwrap(), None);
+}
+
+#[test]
+fn test_receipt_execution_proof() {
+    init_test_logger();
+
+    let genesis = Genesis::test(vec!["test0".parse().unwrap(), "test1".parse().unwrap()], 1);
+    let chain_genesis = ChainGenesis::new(&genesis);
+    let mut env = TestEnv::builder(chain_genesis)
+        .runtime_adapters(create_nightshade_runtimes(&genesis, 1))
+        .build();
+    let genesis_block = env.clients[0].chain.get_block_by_height(0).unwrap();
+    let signer = InMemorySigner::from_seed("test0".parse().unwrap(), KeyType::ED25519, "test0");
+    let tx = SignedTransaction::send_money(
+        1,
+        "test0".parse().unwrap(),
+        "test1".parse().unwrap(),
+        &signer,
+        1,
+        *genesis_block.hash(),
+    );
+    let tx_hash = tx.get_hash();
+    env.clients[0].process_tx(tx, false, false);
+    for i in 1..10 {
+        env.produce_block(0, i);
+    }
+
+    let chain = &env.clients[0].chain;
+    let tx_outcome = chain.get_execution_outcome(&tx_hash).unwrap();
+    let receipt_id = tx_outcome.outcome_with_id.outcome.receipt_ids[0];
+    let head = chain.head_header().unwrap();
+    let light_client_head = chain.get_block_header(head.last_final_block()).unwrap();
+    let proof =
+        chain.get_receipt_execution_proof(&receipt_id, light_client_head.hash()).unwrap().unwrap();
+    assert_eq!(proof.outcome_proof.id, receipt_id);
+    assert!(proof.verify(light_client_head.block_merkle_root()));
+    assert!(!proof.verify(genesis_block.header().block_merkle_root()));
+    // The light client head has to be final.
+    assert!(chain.get_receipt_execution_proof(&receipt_id, head.hash()).unwrap().is_none());
+}
+
+#[test]
+fn test_execution_outcome_proofs() {
+    init_test_logger();
+
+    let genesis = Genesis::test(vec!["test0".parse().unwrap(), "test1".parse().unwrap()], 1);
+    let chain_genesis = ChainGenesis::new(&genesis);
+    let mut env = TestEnv::builder(chain_genesis)
+        .runtime_adapters(create_nightshade_runtimes(&genesis, 1))
+        .build();
+    let genesis_block = env.clients[0].chain.get_block_by_height(0).unwrap();
+    let signer = InMemorySigner::from_seed("test0".parse().unwrap(), KeyType::ED25519, "test0");
+    let tx = SignedTransaction::send_money(
+        1,
+        "test0".parse().unwrap(),
+        "test1".parse().unwrap(),
+        &signer,
+        1,
+        *genesis_block.hash(),
+    );
+    let tx_hash = tx.get_hash();
+    env.clients[0].process_tx(tx, false, false);
+    for i in 1..10 {
+        env.produce_block(0, i);
+    }
+
+    let chain = &env.clients[0].chain;
+    let outcome = chain.get_final_transaction_result(&tx_hash).unwrap();
+    let proofs = chain.get_execution_outcome_proofs(&outcome).unwrap();
+    let outcomes = std::iter::once(&outcome.transaction_outcome)
+        .chain(outcome.receipts_outcome.iter())
+        .collect::<Vec<_>>();
+    assert_eq!(proofs.len(), outcomes.len());
+    for (proof, outcome) in proofs.iter().zip(outcomes.iter()) {
+        assert!(proof.verify(outcome));
+        // The outcome root is included in a later block than the outcome.
+        let block = chain.get_block(&proof.block_header_lite.hash()).unwrap();
+        let outcome_block = chain.get_block_header(&outcome.block_hash).unwrap();
+        assert!(block.header().height() > outcome_block.height());
+    }
+    assert!(!proofs[0].verify(outcomes[1]));
+}
+
 #[test]
 fn test_refund_receipts_processing() {
     init_test_logger();
@@ -3031,6 +3332,71 @@ fn prepare_env_with_transaction() -> (TestEnv, CryptoHash) {
     (env, tx_hash)
 }
 
+/// A chunk produced past its deadline has no transactions, which stay in the
+/// pool for the next chunk.
+#[test]
+fn test_produce_chunk_past_deadline() {
+    let (mut env, _) = prepare_env_with_transaction();
+    let client = &mut env.clients[0];
+    let head = client.chain.head().unwrap();
+    let last_block = client.chain.get_block(&head.last_block_hash).unwrap();
+    let epoch_id =
+        client.runtime_adapter.get_epoch_id_from_prev_block(&head.last_block_hash).unwrap();
+    let mut produce_chunk = |deadline| {
+        let prev_chunk_header =
+            Chain::get_prev_chunk_header(&*client.runtime_adapter, &last_block, 0).unwrap();
+        let (chunk, _, _) = client
+            .produce_chunk(
+                head.last_block_hash,
+                &epoch_id,
+                prev_chunk_header,
+                head.height + 1,
+                0,
+                deadline,
+            )
+            .unwrap()
+            .unwrap();
+        chunk.cloned_header().tx_root()
+    };
+    assert_eq!(produce_chunk(Some(std::time::Instant::now())), CryptoHash::default());
+    assert_ne!(produce_chunk(None), CryptoHash::default());
+}
+
+/// A chunk has at most `max_transactions_per_chunk` transactions, even if more
+/// of them fit in its gas limit.
+#[test]
+fn test_produce_chunk_max_transactions() {
+    let (mut env, _) = prepare_env_with_transaction();
+    let client = &mut env.clients[0];
+    let head = client.chain.head().unwrap();
+    let signer = InMemorySigner::from_seed("test0".parse().unwrap(), KeyType::ED25519, "test0");
+    for nonce in 2..=3 {
+        let tx = SignedTransaction::send_money(
+            nonce,
+            "test0".parse().unwrap(),
+            "test1".parse().unwrap(),
+            &signer,
+            100,
+            head.last_block_hash,
+        );
+        client.process_tx(tx, false, false);
+    }
+    client.config.max_transactions_per_chunk = Some(2);
+
+    let last_block = client.chain.get_block(&head.last_block_hash).unwrap();
+    let epoch_id =
+        client.runtime_adapter.get_epoch_id_from_prev_block(&head.last_block_hash).unwrap();
+    let prev_chunk_header =
+        Chain::get_prev_chunk_header(&*client.runtime_adapter, &last_block, 0).unwrap();
+    client
+        .produce_chunk(head.last_block_hash, &epoch_id, prev_chunk_header, head.height + 1, 0, None)
+        .unwrap()
+        .unwrap();
+    let production = client.chunk_production_info.peek(&(head.height + 1, 0)).unwrap();
+    assert_eq!(production.num_transactions, Some(2));
+    assert_eq!(production.max_transactions, Some(2));
+}
+
 #[test]
 fn test_not_broadcast_block_on_accept() {
     let epoch_length = 5;
@@ -3185,6 +3551,69 @@ fn test_block_ordinal() {
     assert_eq!(fork_ordinal_block_hash, *fork1_block.hash());
 }
 
+/// Test that proofs of the epochs following genesis verify in order starting
+/// from the genesis block producers, that the last proven epoch is the latest
+/// one, and that a header signed by a block producer but lacking approvals is
+/// rejected.
+#[test]
+fn test_epoch_sync_proofs() {
+    let epoch_length = 5;
+    let mut genesis = Genesis::test(vec!["test0".parse().unwrap(), "test1".parse().unwrap()], 1);
+    genesis.config.epoch_length = epoch_length;
+    let mut chain_genesis = ChainGenesis::test();
+    chain_genesis.epoch_length = epoch_length;
+    let mut env = TestEnv::builder(chain_genesis)
+        .runtime_adapters(create_nightshade_runtimes(&genesis, 1))
+        .build();
+    for height in 1..=epoch_length * 4 {
+        let block = env.clients[0].produce_block(height).unwrap().unwrap();
+        env.process_block(0, block, Provenance::PRODUCED);
+    }
+
+    let chain = &env.clients[0].chain;
+    let genesis_header = chain.genesis().clone();
+    let proofs = chain.get_epoch_sync_proofs(genesis_header.hash()).unwrap();
+    assert!(proofs.len() >= 3);
+
+    let mut next_epoch_id = genesis_header.next_epoch_id().clone();
+    let mut block_producers: Vec<ValidatorStake> = env.clients[0]
+        .runtime_adapter
+        .get_epoch_block_producers_ordered(&next_epoch_id, genesis_header.hash())
+        .unwrap()
+        .into_iter()
+        .map(|(bp, _)| bp)
+        .collect();
+    for proof in &proofs {
+        assert_eq!(proof.header.epoch_id(), &next_epoch_id);
+        block_producers = Chain::verify_epoch_sync_proof(proof, &block_producers).unwrap();
+        next_epoch_id = proof.header.next_epoch_id().clone();
+    }
+    let last_hash = *proofs.last().unwrap().header.hash();
+    assert!(chain.get_epoch_sync_proofs(&last_hash).unwrap().is_empty());
+
+    assert_matches!(
+        Chain::verify_epoch_sync_proof(&proofs[1], &[]),
+        Err(Error::InvalidEpochSyncProof)
+    );
+
+    let block_producers = Chain::epoch_info_block_producers(&proofs[1].data.cur_epoch_info);
+    let signer = env.clients[0].validator_signer.as_ref().unwrap().clone();
+    let mut forged = proofs[1].clone();
+    let some_signature = Signature::from_parts(KeyType::ED25519, &[1; 64]).unwrap();
+    forged.header.get_mut().inner_rest.approvals = vec![Some(some_signature)];
+    forged.header.resign(&*signer);
+    assert_matches!(
+        Chain::verify_epoch_sync_proof(&forged, &block_producers),
+        Err(Error::NotEnoughApprovals)
+    );
+    forged.header.get_mut().inner_rest.approvals = vec![];
+    forged.header.resign(&*signer);
+    assert_matches!(
+        Chain::verify_epoch_sync_proof(&forged, &block_producers),
+        Err(Error::NotEnoughApprovals)
+    );
+}
+
 #[test]
 fn test_congestion_receipt_execution() {
     let (mut env, tx_hashes) = prepare_env_with_congestion(PROTOCOL_VERSION, None, 3);
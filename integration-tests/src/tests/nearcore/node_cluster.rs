@@ -9,6 +9,7 @@ use near_network::test_utils::{convert_boot_nodes, open_port};
 use near_o11y::testonly::init_integration_logger;
 use near_primitives::types::{BlockHeight, BlockHeightDelta, NumSeats, NumShards};
 use nearcore::{config::GenesisExt, load_test_config, start_with_config};
+use std::time::Duration;
 
 fn start_nodes(
     temp_dir: &std::path::Path,
@@ -18,6 +19,7 @@ fn start_nodes(
     num_lightclient: NumSeats,
     epoch_length: BlockHeightDelta,
     genesis_height: BlockHeight,
+    block_production_delay: Option<(Duration, Duration)>,
 ) -> (Genesis, Vec<String>, Vec<(Addr<ClientActor>, Addr<ViewClientActor>, Vec<ArbiterHandle>)>) {
     init_integration_logger();
 
@@ -52,6 +54,17 @@ fn start_nodes(
             near_config.client_config.tracked_shards = vec![0];
         }
         near_config.client_config.epoch_sync_enabled = false;
+        if let Some((min, max)) = block_production_delay {
+            let consensus = &mut near_config.config.consensus;
+            consensus.set_block_production_delay(min, max);
+            consensus.validate().unwrap();
+            let client_config = &mut near_config.client_config;
+            client_config.min_block_production_delay = consensus.min_block_production_delay;
+            client_config.max_block_production_delay = consensus.max_block_production_delay;
+            client_config.block_production_tracking_delay =
+                consensus.block_production_tracking_delay;
+            client_config.doosmslug_step_period = consensus.doomslug_step_period;
+        }
         near_configs.push(near_config);
     }
 
@@ -74,6 +87,7 @@ pub struct NodeCluster {
     num_lightclient: Option<NumSeats>,
     epoch_length: Option<BlockHeightDelta>,
     genesis_height: Option<BlockHeight>,
+    block_production_delay: Option<(Duration, Duration)>,
 }
 
 impl NodeCluster {
@@ -107,6 +121,12 @@ impl NodeCluster {
         self
     }
 
+    /// Sets minimum and maximum block production delays of all nodes.
+    pub fn set_block_production_delay(mut self, min: Duration, max: Duration) -> Self {
+        self.block_production_delay = Some((min, max));
+        self
+    }
+
     pub fn exec_until_stop<F, R>(self, f: F)
     where
         R: future::Future<Output = ()> + 'static,
@@ -144,6 +164,7 @@ impl NodeCluster {
                     num_lightclient,
                     epoch_length,
                     genesis_height,
+                    self.block_production_delay,
                 );
                 spawn_interruptible(f(genesis, rpc_addrs, clients));
             });
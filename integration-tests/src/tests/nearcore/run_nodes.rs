@@ -6,6 +6,7 @@ use near_o11y::WithSpanContextExt;
 use near_primitives::types::{BlockHeightDelta, NumSeats, NumShards};
 use rand::{thread_rng, Rng};
 use std::ops::ControlFlow;
+use std::time::{Duration, Instant};
 
 fn run_heavy_nodes(
     num_shards: NumShards,
@@ -53,6 +54,42 @@ fn run_heavy_nodes(
     std::thread::sleep(std::time::Duration::from_millis(250));
 }
 
+/// Runs two validators producing a block every 250ms and checks that the
+/// chain keeps up with that pace.
+#[test]
+#[cfg_attr(not(feature = "expensive_tests"), ignore)]
+fn run_nodes_subsecond_blocks() {
+    let num_blocks = 40;
+    let cluster = NodeCluster::default()
+        .set_num_shards(1)
+        .set_num_validator_seats(2)
+        .set_num_lightclients(0)
+        .set_epoch_length(20)
+        .set_genesis_height(0)
+        .set_block_production_delay(Duration::from_millis(250), Duration::from_millis(750));
+
+    cluster.exec_until_stop(|_, _, clients| async move {
+        let view_client = clients.last().unwrap().1.clone();
+        let started = Instant::now();
+        wait_or_timeout(50, 60000, || async {
+            let res = view_client.send(GetBlock::latest().with_span_context()).await;
+            match &res {
+                Ok(Ok(b)) if b.header.height > num_blocks => return ControlFlow::Break(()),
+                _ => {}
+            };
+            ControlFlow::Continue(())
+        })
+        .await
+        .unwrap();
+        // Allow for twice the configured block time to account for the time
+        // it takes the nodes to connect.
+        let elapsed = started.elapsed();
+        assert!(elapsed < Duration::from_millis(500) * num_blocks as u32, "took {elapsed:?}");
+        System::current().stop()
+    });
+    std::thread::sleep(Duration::from_millis(250));
+}
+
 /// Runs two nodes that should produce blocks one after another.
 #[test]
 #[cfg_attr(not(feature = "expensive_tests"), ignore)]
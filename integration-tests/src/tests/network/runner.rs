@@ -2,7 +2,7 @@ use crate::tests::network::multiset::MultiSet;
 use actix::{Actor, Addr};
 use anyhow::{anyhow, bail, Context};
 use near_chain::test_utils::{KeyValueRuntime, ValidatorSchedule};
-use near_chain::{Chain, ChainGenesis};
+use near_chain::{Chain, ChainGenesis, RuntimeAdapter};
 use near_chain_configs::ClientConfig;
 use near_client::{start_client, start_view_client};
 use near_crypto::KeyType;
@@ -92,6 +92,7 @@ fn setup_network_node(
         config.validator.as_ref().map(|v| v.account_id()),
         chain_genesis.clone(),
         runtime.clone(),
+        runtime.store().clone(),
         network_adapter.clone(),
         client_config,
         adv,
@@ -21,7 +21,7 @@ use nearcore::config::{NEAR_BASE, TESTING_INIT_BALANCE, TESTING_INIT_STAKE};
 
 use crate::node::Node;
 use crate::user::User;
-use near_primitives::receipt::{ActionReceipt, Receipt, ReceiptEnum};
+use near_primitives::receipt::{ActionReceipt, Receipt, ReceiptEnum, RefundKind};
 use near_primitives::runtime::config::RuntimeConfig;
 use near_primitives::test_utils;
 use near_primitives::transaction::{Action, DeployContractAction, FunctionCallAction};
@@ -506,6 +506,30 @@ pub fn test_refund_on_send_money_to_non_existent_account(node: impl Node) {
         )
     );
     assert_eq!(transaction_result.receipts_outcome.len(), 3);
+    // Both the deposit and the unused gas are refunded by the failed receipt.
+    let refunds = transaction_result.refunds.as_ref().unwrap();
+    let failed_receipt_id = transaction_result.receipts_outcome[0].id;
+    assert_eq!(refunds.deposit_refunded, money_used);
+    assert_eq!(
+        refunds.refunds.iter().map(|refund| (refund.kind, refund.refunded_for)).collect::<Vec<_>>(),
+        vec![(RefundKind::Deposit, failed_receipt_id), (RefundKind::Gas, failed_receipt_id)]
+    );
+    assert_eq!(
+        refunds.gas_tokens_refunded,
+        refunds
+            .refunds
+            .iter()
+            .filter(|r| r.kind == RefundKind::Gas)
+            .map(|r| r.amount)
+            .sum::<Balance>()
+    );
+    // The failed receipt is the one the transaction was converted to, so the
+    // unused gas is refunded at the price the gas was purchased at.
+    assert!(refunds.gas_price > 0);
+    assert_eq!(
+        Balance::from(refunds.gas_refunded),
+        refunds.gas_tokens_refunded / refunds.gas_price
+    );
     let new_root = node_user.get_state_root();
     assert_ne!(root, new_root);
     let result1 = node_user.view_account(account_id).unwrap();
@@ -168,6 +168,7 @@ impl RuntimeUser {
             outcome,
             proof: vec![],
             block_hash: Default::default(),
+            finality: None,
         }];
         for hash in &receipt_ids {
             transactions.extend(self.get_recursive_transaction_results(hash).into_iter());
@@ -206,12 +207,21 @@ impl RuntimeUser {
             .expect("results should resolve to a final outcome");
         let receipts = outcomes.split_off(1);
         let transaction = self.transactions.borrow().get(hash).unwrap().clone().into();
-        FinalExecutionOutcomeView {
+        let mut outcome = FinalExecutionOutcomeView {
             status,
             transaction,
             transaction_outcome: outcomes.pop().unwrap(),
             receipts_outcome: receipts,
-        }
+            finality: None,
+            refunds: None,
+        };
+        let receipts = self.receipts.borrow();
+        outcome
+            .annotate_refunds(|receipt_id| {
+                Ok::<_, std::convert::Infallible>(receipts.get(receipt_id).cloned().map(Arc::new))
+            })
+            .unwrap();
+        outcome
     }
 }
 
@@ -1,3 +1,4 @@
+use std::collections::HashMap;
 use std::fs;
 use std::fs::File;
 use std::io::{Read, Write};
@@ -15,8 +16,9 @@ use tempfile::tempdir;
 use tracing::{info, warn};
 
 use near_chain_configs::{
-    get_initial_supply, ClientConfig, GCConfig, Genesis, GenesisConfig, GenesisValidationMode,
-    LogSummaryStyle,
+    get_initial_supply, BlockStageBudgets, ClientConfig, GCConfig, Genesis, GenesisConfig,
+    GenesisValidationMode, HeadLagConfig, HealthConfig, LogSummaryStyle, StateSyncSource,
+    TxAdmissionConfig,
 };
 use near_crypto::{InMemorySigner, KeyFile, KeyType, PublicKey, Signer};
 #[cfg(feature = "json_rpc")]
@@ -69,6 +71,12 @@ pub const MAX_BLOCK_PRODUCTION_DELAY: u64 = 2_000;
 /// Maximum time until skipping the previous block is ms.
 pub const MAX_BLOCK_WAIT_DELAY: u64 = 6_000;
 
+/// Smallest supported block production time in ms.
+pub const MIN_SUPPORTED_BLOCK_PRODUCTION_DELAY: u64 = 100;
+
+/// Smallest period of the block production and doomslug timers in ms.
+const MIN_TICK_PERIOD: u64 = 10;
+
 /// Reduce wait time for every missing block in ms.
 const REDUCE_DELAY_FOR_MISSING_BLOCKS: u64 = 100;
 
@@ -155,6 +163,10 @@ fn default_reduce_wait_for_missing_block() -> Duration {
     Duration::from_millis(REDUCE_DELAY_FOR_MISSING_BLOCKS)
 }
 
+fn default_max_adaptive_chunk_wait() -> Duration {
+    Duration::from_millis(1_200)
+}
+
 fn default_header_sync_initial_timeout() -> Duration {
     Duration::from_secs(10)
 }
@@ -199,6 +211,42 @@ fn default_view_client_throttle_period() -> Duration {
     Duration::from_secs(30)
 }
 
+fn default_state_sync_serve_epochs() -> u64 {
+    2
+}
+
+fn default_state_parts_apply_memory_limit() -> u64 {
+    1024 * 1024 * 1024
+}
+
+fn default_state_part_cache_size() -> u64 {
+    256 * 1024 * 1024
+}
+
+fn default_max_postprocessed_blocks_per_step() -> usize {
+    1
+}
+
+fn default_view_client_cache_size() -> usize {
+    1000
+}
+
+fn default_view_client_cache_ttl() -> Duration {
+    Duration::from_secs(1)
+}
+
+fn default_finality_sla_windows() -> Vec<Duration> {
+    vec![Duration::from_secs(60), Duration::from_secs(600), Duration::from_secs(3600)]
+}
+
+fn default_produce_chunk_add_transactions_time_limit() -> Option<Duration> {
+    Some(Duration::from_millis(200))
+}
+
+fn default_tx_pool_max_transactions_per_signer() -> Option<usize> {
+    Some(1000)
+}
+
 fn default_trie_viewer_state_size_limit() -> Option<u64> {
     Some(50_000)
 }
@@ -218,6 +266,11 @@ pub struct Consensus {
     /// Duration to reduce the wait for each missed block by validator.
     #[serde(default = "default_reduce_wait_for_missing_block")]
     pub reduce_wait_for_missing_block: Duration,
+    /// Longest time after the previous block the producer waits for a missing
+    /// chunk of a shard whose chunks recently arrived that late.  Capped by
+    /// `max_block_production_delay`; zero disables the wait.
+    #[serde(default = "default_max_adaptive_chunk_wait")]
+    pub max_adaptive_chunk_wait: Duration,
     /// Produce empty blocks, use `false` for testing.
     pub produce_empty_blocks: bool,
     /// Horizon at which instead of fetching block, fetch full state.
@@ -230,6 +283,12 @@ pub struct Consensus {
     pub catchup_step_period: Duration,
     /// Time between checking to re-request chunks.
     pub chunk_request_retry_period: Duration,
+    /// How long after requesting the parts of a chunk the ones which are
+    /// still missing are also requested from a second peer, so that a single
+    /// slow peer doesn't hold up the chunk until the request is retried.
+    /// Unset disables hedged requests.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub chunk_request_hedge_delay: Option<Duration>,
     /// How much time to wait after initial header sync
     #[serde(default = "default_header_sync_initial_timeout")]
     pub header_sync_initial_timeout: Duration,
@@ -242,9 +301,23 @@ pub struct Consensus {
     /// How much to wait for a state sync response before re-requesting
     #[serde(default = "default_state_sync_timeout")]
     pub state_sync_timeout: Duration,
+    /// Whether to download state parts in sub-parts.  Peers must support
+    /// `StateRequestSubPart` for this to work.  The size of the sub-parts is
+    /// adjusted per peer to the throughput observed for it.
+    #[serde(default)]
+    pub state_sync_sub_parts: bool,
+    /// Where state sync gets the state of shards from: `"peers"` or
+    /// `{"local_directory": {"path": ...}}` to read state parts from a local
+    /// directory or a mounted snapshot instead of downloading them.
+    #[serde(default)]
+    pub state_sync_source: StateSyncSource,
     /// Expected increase of header head weight per second during header sync
     #[serde(default = "default_header_sync_expected_height_per_second")]
     pub header_sync_expected_height_per_second: u64,
+    /// Adjust header sync timeouts, the expected batch of headers and the
+    /// choice of the peer to sync from to the throughput measured for peers.
+    #[serde(default)]
+    pub header_sync_adaptive: bool,
     /// How frequently we check whether we need to sync
     #[serde(default = "default_sync_check_period")]
     pub sync_check_period: Duration,
@@ -267,18 +340,23 @@ impl Default for Consensus {
             max_block_production_delay: Duration::from_millis(MAX_BLOCK_PRODUCTION_DELAY),
             max_block_wait_delay: Duration::from_millis(MAX_BLOCK_WAIT_DELAY),
             reduce_wait_for_missing_block: default_reduce_wait_for_missing_block(),
+            max_adaptive_chunk_wait: default_max_adaptive_chunk_wait(),
             produce_empty_blocks: true,
             block_fetch_horizon: BLOCK_FETCH_HORIZON,
             state_fetch_horizon: STATE_FETCH_HORIZON,
             block_header_fetch_horizon: BLOCK_HEADER_FETCH_HORIZON,
             catchup_step_period: Duration::from_millis(CATCHUP_STEP_PERIOD),
             chunk_request_retry_period: Duration::from_millis(CHUNK_REQUEST_RETRY_PERIOD),
+            chunk_request_hedge_delay: None,
             header_sync_initial_timeout: default_header_sync_initial_timeout(),
             header_sync_progress_timeout: default_header_sync_progress_timeout(),
             header_sync_stall_ban_timeout: default_header_sync_stall_ban_timeout(),
             state_sync_timeout: default_state_sync_timeout(),
+            state_sync_sub_parts: false,
+            state_sync_source: StateSyncSource::Peers,
             header_sync_expected_height_per_second: default_header_sync_expected_height_per_second(
             ),
+            header_sync_adaptive: false,
             sync_check_period: default_sync_check_period(),
             sync_step_period: default_sync_step_period(),
             doomslug_step_period: default_doomslug_step_period(),
@@ -287,12 +365,73 @@ impl Default for Consensus {
     }
 }
 
+impl Consensus {
+    /// Sets the block production delays and shortens the periods of the
+    /// timers driving block production, so that they tick at least five times
+    /// per block.
+    pub fn set_block_production_delay(&mut self, min: Duration, max: Duration) {
+        self.min_block_production_delay = min;
+        self.max_block_production_delay = max;
+        let tick = std::cmp::max(min / 5, Duration::from_millis(MIN_TICK_PERIOD));
+        self.block_production_tracking_delay =
+            std::cmp::min(self.block_production_tracking_delay, tick);
+        self.doomslug_step_period = std::cmp::min(self.doomslug_step_period, tick);
+    }
+
+    /// Checks that the timing parameters are consistent with each other.
+    ///
+    /// Block production is driven by timers ticking every
+    /// `block_production_tracking_delay` and `doomslug_step_period`, so a block
+    /// can be late by up to a tick; the ticks have to be considerably shorter
+    /// than a block for the delays to be honoured.
+    pub fn validate(&self) -> anyhow::Result<()> {
+        let min_delay = self.min_block_production_delay;
+        anyhow::ensure!(
+            min_delay >= Duration::from_millis(MIN_SUPPORTED_BLOCK_PRODUCTION_DELAY),
+            "consensus.min_block_production_delay must be at least \
+             {MIN_SUPPORTED_BLOCK_PRODUCTION_DELAY}ms, got {min_delay:?}"
+        );
+        // Doomslug expects the skip delay to be at least twice the endorsement
+        // delay.
+        anyhow::ensure!(
+            self.max_block_production_delay >= 2 * min_delay,
+            "consensus.max_block_production_delay ({:?}) must be at least twice \
+             consensus.min_block_production_delay ({min_delay:?})",
+            self.max_block_production_delay
+        );
+        anyhow::ensure!(
+            self.max_block_wait_delay >= self.max_block_production_delay,
+            "consensus.max_block_wait_delay ({:?}) must be at least \
+             consensus.max_block_production_delay ({:?})",
+            self.max_block_wait_delay,
+            self.max_block_production_delay
+        );
+        for (name, tick) in [
+            ("block_production_tracking_delay", self.block_production_tracking_delay),
+            ("doomslug_step_period", self.doomslug_step_period),
+        ] {
+            anyhow::ensure!(
+                tick >= Duration::from_millis(MIN_TICK_PERIOD) && tick <= min_delay / 2,
+                "consensus.{name} must be between {MIN_TICK_PERIOD}ms and half of \
+                 consensus.min_block_production_delay ({min_delay:?}), got {tick:?}"
+            );
+        }
+        Ok(())
+    }
+}
+
 #[derive(Serialize, Deserialize, Clone, Debug)]
 #[serde(default)]
 pub struct Config {
     pub genesis_file: String,
     pub genesis_records_file: Option<String>,
     pub validator_key_file: String,
+    /// Key file, relative to the home directory, of the key the validator
+    /// rotates to.  Once the key is staked, blocks, chunks and approvals of
+    /// its epochs are signed with it instead of the one in
+    /// `validator_key_file`.  Both keys must belong to the same account.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub next_validator_key_file: Option<String>,
     pub node_key_file: String,
     #[cfg(feature = "json_rpc")]
     #[serde(skip_serializing_if = "Option::is_none")]
@@ -316,6 +455,112 @@ pub struct Config {
     pub epoch_sync_enabled: bool,
     #[serde(default = "default_view_client_throttle_period")]
     pub view_client_throttle_period: Duration,
+    /// Number of most recent epochs whose first blocks the node serves state
+    /// sync headers and parts for.  Parts are computed on first request and
+    /// kept until garbage collected, so this has to be less than
+    /// `gc_num_epochs_to_keep`.
+    #[serde(default = "default_state_sync_serve_epochs")]
+    pub state_sync_serve_epochs: u64,
+    /// Memory in bytes available to the state parts of a shard being applied
+    /// at once after state sync.  Parts are applied concurrently as long as
+    /// they fit, a part larger than the limit is applied alone.
+    #[serde(default = "default_state_parts_apply_memory_limit")]
+    pub state_parts_apply_memory_limit: u64,
+    /// Total size in bytes of the state parts served to peers which are kept
+    /// in memory, so that parts requested by many syncing peers are read or
+    /// generated once.  Zero disables caching.
+    #[serde(default = "default_state_part_cache_size")]
+    pub state_part_cache_size: u64,
+    /// Whether events emitted by contracts following the event standard
+    /// (NEP-297) are indexed.  The index can be queried with the
+    /// `EXPERIMENTAL_contract_events` method.  Only blocks processed after
+    /// the index is enabled are indexed.
+    #[serde(default)]
+    pub contract_events_index: bool,
+    /// Whether the accounts contracts are deployed to are indexed by the hash
+    /// of the code.  The index can be queried with the
+    /// `EXPERIMENTAL_contract_accounts` method.  Only contracts deployed in
+    /// blocks processed after the index is enabled are indexed.
+    #[serde(default)]
+    pub contract_code_index: bool,
+    /// Whether orphans within 64 heights of the head are saved to the store,
+    /// so that a node restarted while catching up with a fork doesn't
+    /// download them again.
+    #[serde(default)]
+    pub persist_orphans: bool,
+    #[serde(default = "default_view_client_cache_size")]
+    pub view_client_cache_size: usize,
+    #[serde(default = "default_view_client_cache_ttl")]
+    pub view_client_cache_ttl: Duration,
+    /// Windows over which the time to finality of recent blocks is aggregated
+    /// in the `/debug/api/finality_sla` debug page.
+    #[serde(default = "default_finality_sla_windows")]
+    pub finality_sla_windows: Vec<Duration>,
+    /// Limits on transactions submitted to the node.
+    #[serde(default)]
+    pub tx_admission: TxAdmissionConfig,
+    /// Soft budgets of the stages of block processing: header verification,
+    /// chunks check, apply scheduling, apply, postprocess, garbage collection
+    /// and network info update.  A stage taking longer than its budget logs a
+    /// warning with the block.  Durations of all stages are exported as
+    /// `near_block_processing_stage_time`.
+    #[serde(default)]
+    pub block_stage_budgets: BlockStageBudgets,
+    /// Maximum number of blocks which finished applying chunks postprocessed
+    /// at once.  Blocks extending the head are postprocessed first.  Lower
+    /// values let the client handle other messages in between blocks whose
+    /// chunks finished being applied at the same time.  Time between
+    /// applying chunks finishing and postprocessing is exported as
+    /// `near_apply_chunks_completion_delay`.
+    #[serde(default = "default_max_postprocessed_blocks_per_step")]
+    pub max_postprocessed_blocks_per_step: usize,
+    /// Thresholds of the verdicts served at `/health/ready` and
+    /// `/health/live`, meant for readiness and liveness probes.  The node is
+    /// ready when it isn't syncing, its head is recent, it has enough peers,
+    /// its storage isn't degraded, blocks are processed quickly enough and,
+    /// if required, it has a validator key.  It is live unless its head
+    /// hasn't advanced for `max_head_stall` while peers are ahead of it and
+    /// it isn't syncing.
+    #[serde(default)]
+    pub health: HealthConfig,
+    /// When the head lags `alert_lag` heights or more behind the median
+    /// height reported by the connected peers for `alert_after`, the node
+    /// logs a warning, reports it in `/status` and starts syncing.  It stops
+    /// lagging once the lag drops below `clear_lag`.
+    #[serde(default)]
+    pub head_lag: HeadLagConfig,
+    /// Time budget for pulling transactions from the pool into a produced
+    /// chunk.  It's capped at `consensus.min_block_production_delay`, so that
+    /// the chunk is out before the next block can be produced.  Transactions
+    /// not reached in time stay in the pool.
+    #[serde(default = "default_produce_chunk_add_transactions_time_limit")]
+    pub produce_chunk_add_transactions_time_limit: Option<Duration>,
+    /// Priority tiers of transactions by signer.  Transactions of a higher
+    /// tier are included in produced chunks first.
+    #[serde(default, skip_serializing_if = "HashMap::is_empty")]
+    pub tx_priority_signers: HashMap<AccountId, u8>,
+    /// Maximum number of transactions of a single signer in the pool of a
+    /// shard.  The oldest transactions of a signer over it are evicted.
+    #[serde(default = "default_tx_pool_max_transactions_per_signer")]
+    pub tx_pool_max_transactions_per_signer: Option<usize>,
+    /// Maximum number of transactions in a chunk produced by this node,
+    /// regardless of their gas.  Meant for private chains which bound the
+    /// transactions per chunk for fairness or latency.  It has to be at least
+    /// one, so that the shard keeps making progress.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub max_transactions_per_chunk: Option<u64>,
+    /// Accounts pinned into the TIER1 accounts, with the public keys they
+    /// sign their TIER1 data with.  Meant for accounts which aren't staked,
+    /// such as a trusted relayer, which then get the same priority
+    /// connections as the validators.
+    #[serde(default, skip_serializing_if = "HashMap::is_empty")]
+    pub tier1_pinned_accounts: HashMap<AccountId, PublicKey>,
+    /// Directory, relative to the home directory, forensic bundles are saved
+    /// to when a chunk disagrees with the state root this node computed for
+    /// its shard.  Each bundle holds what's needed to replay the application
+    /// of the shard offline.  Unset by default, which disables them.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub state_mismatch_bundles_dir: Option<PathBuf>,
     #[serde(default = "default_trie_viewer_state_size_limit")]
     pub trie_viewer_state_size_limit: Option<u64>,
     /// If set, overrides value in genesis configuration.
@@ -327,6 +572,15 @@ pub struct Config {
     #[cfg(feature = "cold_store")]
     #[serde(default, skip_serializing_if = "Option::is_none")]
     pub cold_store: Option<near_store::StoreConfig>,
+    /// Migration of data from hot to cold storage, used if `cold_store` is
+    /// set.
+    #[cfg(feature = "cold_store")]
+    #[serde(default)]
+    pub cold_migration: near_store::cold_migration::ColdMigrationConfig,
+    /// Removal of the outcomes of blocks off the canonical chain once they
+    /// are final, together with an index of the canonical ones.
+    #[serde(default)]
+    pub outcome_compaction: near_chain::outcome_compaction::OutcomeCompactionConfig,
 
     // TODO(mina86): Remove those two altogether at some point.  We need to be
     // somewhat careful though and make sure that we don’t start silently
@@ -351,6 +605,7 @@ impl Default for Config {
             genesis_file: GENESIS_CONFIG_FILENAME.to_string(),
             genesis_records_file: None,
             validator_key_file: VALIDATOR_KEY_FILE.to_string(),
+            next_validator_key_file: None,
             node_key_file: NODE_KEY_FILE.to_string(),
             #[cfg(feature = "json_rpc")]
             rpc: Some(RpcConfig::default()),
@@ -367,6 +622,27 @@ impl Default for Config {
             epoch_sync_enabled: true,
             view_client_threads: default_view_client_threads(),
             view_client_throttle_period: default_view_client_throttle_period(),
+            state_sync_serve_epochs: default_state_sync_serve_epochs(),
+            state_parts_apply_memory_limit: default_state_parts_apply_memory_limit(),
+            state_part_cache_size: default_state_part_cache_size(),
+            contract_events_index: false,
+            contract_code_index: false,
+            persist_orphans: false,
+            view_client_cache_size: default_view_client_cache_size(),
+            view_client_cache_ttl: default_view_client_cache_ttl(),
+            finality_sla_windows: default_finality_sla_windows(),
+            tx_admission: TxAdmissionConfig::default(),
+            block_stage_budgets: BlockStageBudgets::default(),
+            max_postprocessed_blocks_per_step: default_max_postprocessed_blocks_per_step(),
+            health: HealthConfig::default(),
+            head_lag: HeadLagConfig::default(),
+            produce_chunk_add_transactions_time_limit:
+                default_produce_chunk_add_transactions_time_limit(),
+            tx_priority_signers: HashMap::new(),
+            tx_pool_max_transactions_per_signer: default_tx_pool_max_transactions_per_signer(),
+            max_transactions_per_chunk: None,
+            tier1_pinned_accounts: HashMap::new(),
+            state_mismatch_bundles_dir: None,
             trie_viewer_state_size_limit: default_trie_viewer_state_size_limit(),
             max_gas_burnt_view: None,
             db_migration_snapshot_path: None,
@@ -374,6 +650,9 @@ impl Default for Config {
             store: near_store::StoreConfig::default(),
             #[cfg(feature = "cold_store")]
             cold_store: None,
+            #[cfg(feature = "cold_store")]
+            cold_migration: Default::default(),
+            outcome_compaction: Default::default(),
         }
     }
 }
@@ -427,6 +706,15 @@ impl Config {
         None
     }
 
+    /// Whether the node keeps its archive in a separate cold storage.
+    pub fn has_cold_store(&self) -> bool {
+        #[cfg(feature = "cold_store")]
+        if self.cold_store.is_some() {
+            return true;
+        }
+        false
+    }
+
     #[allow(unused_variables)]
     pub fn set_rpc_addr(&mut self, addr: String) {
         #[cfg(feature = "json_rpc")]
@@ -569,6 +857,7 @@ impl NearConfig {
                 max_block_production_delay: config.consensus.max_block_production_delay,
                 max_block_wait_delay: config.consensus.max_block_wait_delay,
                 reduce_wait_for_missing_block: config.consensus.reduce_wait_for_missing_block,
+                max_adaptive_chunk_wait: config.consensus.max_adaptive_chunk_wait,
                 skip_sync_wait: config.network.skip_sync_wait,
                 sync_check_period: config.consensus.sync_check_period,
                 sync_step_period: config.consensus.sync_step_period,
@@ -579,7 +868,10 @@ impl NearConfig {
                 header_sync_expected_height_per_second: config
                     .consensus
                     .header_sync_expected_height_per_second,
+                header_sync_adaptive: config.consensus.header_sync_adaptive,
                 state_sync_timeout: config.consensus.state_sync_timeout,
+                state_sync_sub_parts: config.consensus.state_sync_sub_parts,
+                state_sync_source: config.consensus.state_sync_source,
                 min_num_peers: config.consensus.min_num_peers,
                 log_summary_period: Duration::from_secs(10),
                 produce_empty_blocks: config.consensus.produce_empty_blocks,
@@ -593,18 +885,43 @@ impl NearConfig {
                 block_header_fetch_horizon: config.consensus.block_header_fetch_horizon,
                 catchup_step_period: config.consensus.catchup_step_period,
                 chunk_request_retry_period: config.consensus.chunk_request_retry_period,
+                chunk_request_hedge_delay: config.consensus.chunk_request_hedge_delay,
                 doosmslug_step_period: config.consensus.doomslug_step_period,
                 tracked_accounts: config.tracked_accounts,
                 tracked_shards: config.tracked_shards,
                 archive: config.archive,
+                cold_store: config.has_cold_store(),
                 log_summary_style: config.log_summary_style,
                 gc: config.gc,
                 view_client_threads: config.view_client_threads,
                 epoch_sync_enabled: config.epoch_sync_enabled,
                 view_client_throttle_period: config.view_client_throttle_period,
+                state_sync_serve_epochs: config.state_sync_serve_epochs,
+                state_parts_apply_memory_limit: config.state_parts_apply_memory_limit,
+                state_part_cache_size: config.state_part_cache_size,
+                contract_events_index: config.contract_events_index,
+                contract_code_index: config.contract_code_index,
+                persist_orphans: config.persist_orphans,
+                view_client_cache_size: config.view_client_cache_size,
+                view_client_cache_ttl: config.view_client_cache_ttl,
+                finality_sla_windows: config.finality_sla_windows,
+                tx_admission: config.tx_admission,
+                block_stage_budgets: config.block_stage_budgets,
+                max_postprocessed_blocks_per_step: config.max_postprocessed_blocks_per_step,
+                health: config.health,
+                head_lag: config.head_lag,
+                produce_chunk_add_transactions_time_limit: config
+                    .produce_chunk_add_transactions_time_limit,
+                tx_priority_signers: config.tx_priority_signers,
+                tx_pool_max_transactions_per_signer: config.tx_pool_max_transactions_per_signer,
+                max_transactions_per_chunk: config.max_transactions_per_chunk,
+                tier1_pinned_accounts: config.tier1_pinned_accounts,
+                state_mismatch_bundles_dir: config.state_mismatch_bundles_dir,
+                next_validator_key_file: config.next_validator_key_file.map(PathBuf::from),
                 trie_viewer_state_size_limit: config.trie_viewer_state_size_limit,
                 max_gas_burnt_view: config.max_gas_burnt_view,
                 enable_statistics_export: config.store.enable_statistics_export,
+                metrics_instance: None,
             },
             network_config: NetworkConfig::new(
                 config.network,
@@ -963,10 +1280,10 @@ pub fn init_configs(
             // Create new configuration, key files and genesis for one validator.
             config.network.skip_sync_wait = true;
             if fast {
-                config.consensus.min_block_production_delay =
-                    Duration::from_millis(FAST_MIN_BLOCK_PRODUCTION_DELAY);
-                config.consensus.max_block_production_delay =
-                    Duration::from_millis(FAST_MAX_BLOCK_PRODUCTION_DELAY);
+                config.consensus.set_block_production_delay(
+                    Duration::from_millis(FAST_MIN_BLOCK_PRODUCTION_DELAY),
+                    Duration::from_millis(FAST_MAX_BLOCK_PRODUCTION_DELAY),
+                );
             }
             config.write_to_file(&dir.join(CONFIG_FILENAME)).with_context(|| {
                 format!("Error writing config to {}", dir.join(CONFIG_FILENAME).display())
@@ -1285,6 +1602,33 @@ pub fn load_config(
     genesis_validation: GenesisValidationMode,
 ) -> anyhow::Result<NearConfig> {
     let config = Config::from_file(&dir.join(CONFIG_FILENAME))?;
+    // Configs written before the timing parameters were validated, e.g. by
+    // `neard init --fast`, can be inconsistent and still work well enough, so
+    // they don't prevent the node from starting.
+    if let Err(err) = config.consensus.validate() {
+        warn!(
+            target: "neard",
+            "Inconsistent consensus config, blocks may be produced later than configured: {err}"
+        );
+    }
+    #[cfg(feature = "json_rpc")]
+    if let Some(rate_limits_config) =
+        config.rpc.as_ref().and_then(|rpc| rpc.rate_limits_config.as_ref())
+    {
+        rate_limits_config.validate().map_err(|err| anyhow::anyhow!(err))?;
+    }
+    anyhow::ensure!(
+        config.state_sync_serve_epochs >= 1
+            && config.state_sync_serve_epochs < config.gc.gc_num_epochs_to_keep(),
+        "state_sync_serve_epochs must be at least 1 and less than gc_num_epochs_to_keep ({})",
+        config.gc.gc_num_epochs_to_keep()
+    );
+    // Chunks without transactions would keep transactions from ever landing
+    // in the shards this node produces chunks for.
+    anyhow::ensure!(
+        config.max_transactions_per_chunk != Some(0),
+        "max_transactions_per_chunk must be at least 1"
+    );
     let genesis_file = dir.join(&config.genesis_file);
     let validator_file = dir.join(&config.validator_key_file);
     let validator_signer = if validator_file.exists() {
@@ -1314,17 +1658,25 @@ pub fn load_config(
                         "Validator must track all shards. Please change `tracked_shards` field in config.json to be any non-empty vector");
     }
 
-    NearConfig::new(config, genesis, network_signer.into(), validator_signer)
+    let state_mismatch_bundles_dir =
+        config.state_mismatch_bundles_dir.as_ref().map(|path| dir.join(path));
+    let next_validator_key_file =
+        config.next_validator_key_file.as_ref().map(|path| dir.join(path));
+    let mut near_config =
+        NearConfig::new(config, genesis, network_signer.into(), validator_signer)?;
+    near_config.client_config.state_mismatch_bundles_dir = state_mismatch_bundles_dir;
+    near_config.client_config.next_validator_key_file = next_validator_key_file;
+    Ok(near_config)
 }
 
 pub fn load_test_config(seed: &str, port: u16, genesis: Genesis) -> NearConfig {
     let mut config = Config::default();
     config.network.addr = format!("0.0.0.0:{}", port);
     config.set_rpc_addr(format!("0.0.0.0:{}", open_port()));
-    config.consensus.min_block_production_delay =
-        Duration::from_millis(FAST_MIN_BLOCK_PRODUCTION_DELAY);
-    config.consensus.max_block_production_delay =
-        Duration::from_millis(FAST_MAX_BLOCK_PRODUCTION_DELAY);
+    config.consensus.set_block_production_delay(
+        Duration::from_millis(FAST_MIN_BLOCK_PRODUCTION_DELAY),
+        Duration::from_millis(FAST_MAX_BLOCK_PRODUCTION_DELAY),
+    );
     let (signer, validator_signer) = if seed.is_empty() {
         let signer =
             Arc::new(InMemorySigner::from_random("node".parse().unwrap(), KeyType::ED25519));
@@ -1390,6 +1742,33 @@ fn test_init_config_localnet() {
     );
 }
 
+#[test]
+fn test_consensus_config_validation() {
+    let mut consensus = Consensus::default();
+    consensus.validate().unwrap();
+
+    // Sub-second blocks need the timers to tick faster.
+    consensus.min_block_production_delay = Duration::from_millis(250);
+    consensus.max_block_production_delay = Duration::from_millis(750);
+    assert!(consensus.validate().is_err());
+    consensus.set_block_production_delay(Duration::from_millis(250), Duration::from_millis(750));
+    assert_eq!(consensus.block_production_tracking_delay, Duration::from_millis(50));
+    assert_eq!(consensus.doomslug_step_period, Duration::from_millis(50));
+    consensus.validate().unwrap();
+
+    consensus.set_block_production_delay(Duration::from_millis(100), Duration::from_millis(300));
+    assert_eq!(consensus.block_production_tracking_delay, Duration::from_millis(20));
+    consensus.validate().unwrap();
+
+    consensus.set_block_production_delay(Duration::from_millis(50), Duration::from_millis(300));
+    assert!(consensus.validate().is_err());
+    consensus.set_block_production_delay(Duration::from_millis(250), Duration::from_millis(400));
+    assert!(consensus.validate().is_err());
+    consensus.set_block_production_delay(Duration::from_millis(250), Duration::from_millis(750));
+    consensus.max_block_wait_delay = Duration::from_millis(500);
+    assert!(consensus.validate().is_err());
+}
+
 /// Tests that loading a config.json file works and results in values being
 /// correctly parsed and defaults being applied correctly applied.
 #[test]
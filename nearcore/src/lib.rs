@@ -152,6 +152,12 @@ pub struct NearNode {
     pub view_client: Addr<ViewClientActor>,
     pub arbiters: Vec<ArbiterHandle>,
     pub rpc_servers: Vec<(&'static str, actix_web::dev::ServerHandle)>,
+    /// Migration of data to cold storage, which stops once this is dropped.
+    #[cfg(feature = "cold_store")]
+    pub cold_migration: Option<near_store::cold_migration::ColdMigrationHandle>,
+    /// Compaction of the outcomes of fork blocks, which stops once this is
+    /// dropped.
+    pub outcome_compaction: Option<near_chain::outcome_compaction::OutcomeCompactionHandle>,
 }
 
 pub fn start_with_config(home_dir: &Path, config: NearConfig) -> anyhow::Result<NearNode> {
@@ -173,6 +179,35 @@ pub fn start_with_config_and_synchronization(
         &config,
     ));
 
+    #[cfg(feature = "cold_store")]
+    let cold_migration = match store.cold_db() {
+        Some(cold_db) => {
+            let epoch_runtime = runtime.clone();
+            near_store::cold_migration::ColdMigration::new(
+                store.get_store(Temperature::Hot),
+                cold_db,
+                Box::new(move |epoch_id| {
+                    near_epoch_manager::EpochManagerAdapter::get_shard_layout(
+                        &*epoch_runtime,
+                        epoch_id,
+                    )
+                    .map_err(|err| std::io::Error::new(std::io::ErrorKind::Other, err.to_string()))
+                }),
+                config.config.cold_migration.clone(),
+            )
+            .spawn()
+            .context("ColdMigration::spawn()")?
+        }
+        None => None,
+    };
+
+    let outcome_compaction = near_chain::outcome_compaction::OutcomeCompaction::new(
+        store.get_store(Temperature::Hot),
+        config.config.outcome_compaction.clone(),
+    )
+    .spawn()
+    .context("OutcomeCompaction::spawn()")?;
+
     let telemetry = TelemetryActor::new(config.telemetry_config.clone()).start();
     let chain_genesis = ChainGenesis::new(&config.genesis);
     let genesis_block = Chain::make_genesis_block(&*runtime, &chain_genesis)?;
@@ -185,10 +220,18 @@ pub fn start_with_config_and_synchronization(
     let network_adapter = Arc::new(NetworkRecipient::default());
     let adv = near_client::adversarial::Controls::new(config.client_config.archive);
 
+    // With split storage, the view client reads data garbage collected in hot
+    // storage from cold storage, so that archival nodes serve all of history.
+    #[cfg(feature = "cold_store")]
+    let view_store = store.get_split_store().unwrap_or_else(|| store.get_store(Temperature::Hot));
+    #[cfg(not(feature = "cold_store"))]
+    let view_store = store.get_store(Temperature::Hot);
+
     let view_client = start_view_client(
         config.validator_signer.as_ref().map(|signer| signer.validator_id().clone()),
         chain_genesis.clone(),
         runtime.clone(),
+        view_store,
         network_adapter.clone(),
         config.client_config.clone(),
         adv.clone(),
@@ -255,6 +298,9 @@ pub fn start_with_config_and_synchronization(
         view_client,
         rpc_servers,
         arbiters: vec![client_arbiter_handle],
+        #[cfg(feature = "cold_store")]
+        cold_migration,
+        outcome_compaction,
     })
 }
 
@@ -1,4 +1,7 @@
-use near_o11y::metrics::{linear_buckets, try_create_histogram_vec, HistogramVec};
+use near_o11y::metrics::{
+    linear_buckets, try_create_histogram_vec, try_create_int_counter, try_create_int_counter_vec,
+    HistogramVec, IntCounter, IntCounterVec,
+};
 use once_cell::sync::Lazy;
 
 pub static APPLY_CHUNK_DELAY: Lazy<HistogramVec> = Lazy::new(|| {
@@ -24,3 +27,28 @@ pub static SECONDS_PER_PETAGAS: Lazy<HistogramVec> = Lazy::new(|| {
     )
     .unwrap()
 });
+
+pub static APPLY_CACHE_HITS: Lazy<IntCounter> = Lazy::new(|| {
+    try_create_int_counter(
+        "near_apply_chunk_cache_hits_total",
+        "Number of chunk applications served from the cache of recent results",
+    )
+    .unwrap()
+});
+
+pub static APPLY_CACHE_MISSES: Lazy<IntCounter> = Lazy::new(|| {
+    try_create_int_counter(
+        "near_apply_chunk_cache_misses_total",
+        "Number of chunk applications not found in the cache of recent results",
+    )
+    .unwrap()
+});
+
+pub static PREPARE_TRANSACTIONS_DROPPED: Lazy<IntCounterVec> = Lazy::new(|| {
+    try_create_int_counter_vec(
+        "near_prepare_transactions_dropped_total",
+        "Number of transactions pulled from the pool and dropped instead of being included in a produced chunk",
+        &["reason"],
+    )
+    .unwrap()
+});
@@ -0,0 +1,205 @@
+//! Cache of recent chunk application results.
+//!
+//! The same receipts can be applied more than once on top of the same state,
+//! e.g. when processing of a block is retried after a failure or when a block
+//! is applied again while catching up.  Receipts of a chunk are executed one
+//! after another against the state left by the previous ones, so outcomes are
+//! cached for the whole chunk rather than for individual receipts.
+//!
+//! An entry is keyed by the chunk, i.e. the prev state root, the receipts and
+//! the transactions, together with every other input of the application.  The
+//! block the chunk is applied in is an input only as far as the runtime reads
+//! it, so the hash of the block is a part of the key only in the protocol
+//! versions which derive receipt ids from it.  A hit therefore returns exactly
+//! what recomputing would, and there is nothing to invalidate explicitly: any
+//! change of the inputs changes the key.  Applications which patch the state
+//! are never cached.
+use std::sync::Mutex;
+
+use borsh::BorshSerialize;
+use near_chain::types::ApplyTransactionResult;
+use near_primitives::challenge::ChallengesResult;
+use near_primitives::hash::{hash, CryptoHash};
+use near_primitives::receipt::Receipt;
+use near_primitives::shard_layout::ShardUId;
+use near_primitives::transaction::{ExecutionOutcomeWithId, SignedTransaction};
+use near_primitives::types::validator_stake::ValidatorStake;
+use near_primitives::types::{
+    Balance, BlockHeight, Gas, RawStateChangesWithTrieKey, ShardId, StateRoot,
+};
+use near_store::{PartialStorage, ShardTries, TrieChanges, WrappedTrieChanges};
+
+use crate::metrics;
+
+/// Number of chunk applications to keep.  Each entry holds the trie changes of
+/// a chunk, so this is kept small.
+const APPLY_CACHE_SIZE: usize = 32;
+
+/// Inputs of a chunk application, other than the chunk itself.
+#[derive(BorshSerialize)]
+pub(crate) struct ApplyCacheInputs<'a> {
+    pub block_height: BlockHeight,
+    /// None if receipt ids are derived from the previous block in the
+    /// protocol version of the application.
+    pub block_hash: Option<&'a CryptoHash>,
+    pub block_timestamp: u64,
+    pub prev_block_hash: &'a CryptoHash,
+    pub last_validator_proposals: &'a [ValidatorStake],
+    pub gas_price: Balance,
+    pub gas_limit: Gas,
+    pub challenges_result: &'a ChallengesResult,
+    pub random_seed: &'a CryptoHash,
+    pub is_new_chunk: bool,
+    pub is_first_block_with_chunk_of_version: bool,
+}
+
+impl<'a> ApplyCacheInputs<'a> {
+    /// Returns the key of applying the chunk with these inputs.  The chunk is
+    /// covered by whole receipts and transactions rather than their ids, so
+    /// that nothing which is not covered by an id can slip through.
+    pub fn key(
+        &self,
+        shard_id: ShardId,
+        state_root: &StateRoot,
+        receipts: &[Receipt],
+        transactions: &[SignedTransaction],
+    ) -> ApplyCacheKey {
+        ApplyCacheKey {
+            shard_id,
+            state_root: *state_root,
+            chunk: hash(
+                &(receipts, transactions).try_to_vec().expect("borsh serialization can't fail"),
+            ),
+            inputs: hash(&self.try_to_vec().expect("borsh serialization can't fail")),
+        }
+    }
+}
+
+#[derive(Clone, PartialEq, Eq, Hash, Debug)]
+pub(crate) struct ApplyCacheKey {
+    shard_id: ShardId,
+    state_root: StateRoot,
+    /// Hash of the receipts and the transactions.
+    chunk: CryptoHash,
+    /// Hash of all [`ApplyCacheInputs`].
+    inputs: CryptoHash,
+}
+
+/// Result of a chunk application, without the parts which are specific to
+/// the instance of the runtime.
+#[derive(Clone)]
+pub(crate) struct CachedApplyResult {
+    pub new_root: StateRoot,
+    pub trie_changes: TrieChanges,
+    pub state_changes: Vec<RawStateChangesWithTrieKey>,
+    pub outcomes: Vec<ExecutionOutcomeWithId>,
+    pub outgoing_receipts: Vec<Receipt>,
+    pub validator_proposals: Vec<ValidatorStake>,
+    pub total_gas_burnt: Gas,
+    pub total_balance_burnt: Balance,
+    pub processed_delayed_receipts: Vec<Receipt>,
+    pub proof: Option<PartialStorage>,
+}
+
+impl CachedApplyResult {
+    pub fn into_apply_result(
+        self,
+        tries: ShardTries,
+        shard_uid: ShardUId,
+        block_hash: CryptoHash,
+    ) -> ApplyTransactionResult {
+        ApplyTransactionResult {
+            trie_changes: WrappedTrieChanges::new(
+                tries,
+                shard_uid,
+                self.trie_changes,
+                self.state_changes,
+                block_hash,
+            ),
+            new_root: self.new_root,
+            outcomes: self.outcomes,
+            outgoing_receipts: self.outgoing_receipts,
+            validator_proposals: self.validator_proposals,
+            total_gas_burnt: self.total_gas_burnt,
+            total_balance_burnt: self.total_balance_burnt,
+            proof: self.proof,
+            processed_delayed_receipts: self.processed_delayed_receipts,
+        }
+    }
+}
+
+pub(crate) struct ApplyCache(Mutex<lru::LruCache<ApplyCacheKey, CachedApplyResult>>);
+
+impl ApplyCache {
+    pub fn new() -> Self {
+        Self(Mutex::new(lru::LruCache::new(APPLY_CACHE_SIZE)))
+    }
+
+    pub fn get(&self, key: &ApplyCacheKey) -> Option<CachedApplyResult> {
+        let result = self.0.lock().unwrap().get(key).cloned();
+        if result.is_some() {
+            metrics::APPLY_CACHE_HITS.inc();
+        } else {
+            metrics::APPLY_CACHE_MISSES.inc();
+        }
+        result
+    }
+
+    pub fn put(&self, key: ApplyCacheKey, result: CachedApplyResult) {
+        self.0.lock().unwrap().put(key, result);
+    }
+
+    #[cfg(test)]
+    pub fn len(&self) -> usize {
+        self.0.lock().unwrap().len()
+    }
+
+    #[cfg(test)]
+    pub fn clear(&self) {
+        self.0.lock().unwrap().clear();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::ApplyCacheInputs;
+    use near_primitives::challenge::ChallengesResult;
+    use near_primitives::hash::{hash, CryptoHash};
+    use near_primitives::receipt::Receipt;
+    use near_primitives::types::StateRoot;
+
+    /// The key covers the chunk and the inputs, and the block hash only when
+    /// receipt ids are derived from it.
+    #[test]
+    fn test_apply_cache_key() {
+        let prev_block_hash = hash(b"prev block");
+        let challenges_result = ChallengesResult::default();
+        let random_seed = CryptoHash::default();
+        let inputs = |block_hash| ApplyCacheInputs {
+            block_height: 1,
+            block_hash,
+            block_timestamp: 0,
+            prev_block_hash: &prev_block_hash,
+            last_validator_proposals: &[],
+            gas_price: 1,
+            gas_limit: 1,
+            challenges_result: &challenges_result,
+            random_seed: &random_seed,
+            is_new_chunk: true,
+            is_first_block_with_chunk_of_version: false,
+        };
+        let state_root = StateRoot::default();
+        let receipts = [Receipt::new_balance_refund(&"alice.near".parse().unwrap(), 1)];
+        let (block, other_block) = (hash(b"block"), hash(b"other block"));
+
+        let key = inputs(None).key(0, &state_root, &receipts, &[]);
+        assert_eq!(key, inputs(None).key(0, &state_root, &receipts, &[]));
+        assert_ne!(key, inputs(None).key(1, &state_root, &receipts, &[]));
+        assert_ne!(key, inputs(None).key(0, &state_root, &[], &[]));
+        assert_ne!(key, inputs(Some(&block)).key(0, &state_root, &receipts, &[]));
+        assert_ne!(
+            inputs(Some(&block)).key(0, &state_root, &receipts, &[]),
+            inputs(Some(&other_block)).key(0, &state_root, &receipts, &[])
+        );
+    }
+}
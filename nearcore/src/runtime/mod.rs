@@ -1,5 +1,6 @@
 use crate::metrics;
 use crate::migrations::load_migration_data;
+use crate::runtime::apply_cache::{ApplyCache, ApplyCacheInputs, CachedApplyResult};
 use crate::shard_tracker::{ShardTracker, TrackedConfig};
 use crate::NearConfig;
 use borsh::ser::BorshSerialize;
@@ -18,11 +19,14 @@ use near_o11y::log_assert;
 use near_pool::types::PoolIterator;
 use near_primitives::account::{AccessKey, Account};
 use near_primitives::challenge::ChallengesResult;
+use near_primitives::checked_feature;
 use near_primitives::contract::ContractCode;
 use near_primitives::epoch_manager::block_info::BlockInfo;
 use near_primitives::epoch_manager::epoch_info::EpochInfo;
 use near_primitives::epoch_manager::EpochConfig;
-use near_primitives::errors::{EpochError, InvalidTxError, RuntimeError, StorageError};
+use near_primitives::errors::{
+    ActionsValidationError, EpochError, InvalidTxError, RuntimeError, StorageError,
+};
 use near_primitives::hash::{hash, CryptoHash};
 use near_primitives::receipt::Receipt;
 use near_primitives::runtime::config_store::RuntimeConfigStore;
@@ -34,6 +38,7 @@ use near_primitives::shard_layout::{
 use near_primitives::state_part::PartId;
 use near_primitives::state_record::{state_record_to_account_id, StateRecord};
 use near_primitives::syncing::{get_num_state_parts, STATE_PART_MEMORY_LIMIT};
+use near_primitives::time::Clock;
 use near_primitives::transaction::SignedTransaction;
 use near_primitives::types::validator_stake::ValidatorStakeIter;
 use near_primitives::types::{
@@ -41,7 +46,9 @@ use near_primitives::types::{
     EpochInfoProvider, Gas, MerkleHash, NumShards, ShardId, StateChangeCause,
     StateChangesForSplitStates, StateRoot, StateRootNode,
 };
-use near_primitives::version::ProtocolVersion;
+use near_primitives::version::{
+    ProtocolVersion, CREATE_RECEIPT_ID_SWITCH_TO_CURRENT_BLOCK_VERSION,
+};
 use near_primitives::views::{
     AccessKeyInfoView, CallResult, QueryRequest, QueryResponse, QueryResponseKind, ViewApplyState,
     ViewStateResult,
@@ -50,7 +57,7 @@ use near_store::flat_state::ChainAccessForFlatStorage;
 use near_store::flat_state::{
     store_helper, FlatStateFactory, FlatStorageState, FlatStorageStateStatus,
 };
-use near_store::split_state::get_delayed_receipts;
+use near_store::split_state::{get_delayed_receipts, StateSplitCheckpoint};
 use near_store::{
     get_genesis_hash, get_genesis_state_roots, set_genesis_hash, set_genesis_state_roots,
     ApplyStatePartResult, DBCol, PartialStorage, ShardTries, Store, StoreCompiledContractCache,
@@ -61,9 +68,10 @@ use node_runtime::adapter::ViewRuntimeAdapter;
 use node_runtime::config::RuntimeConfig;
 use node_runtime::state_viewer::TrieViewer;
 use node_runtime::{
-    validate_transaction, verify_and_charge_transaction, ApplyState, Runtime,
-    ValidatorAccountsUpdate,
+    check_transaction_freshness, validate_transaction, verify_and_charge_transaction, ApplyState,
+    Runtime, ValidatorAccountsUpdate,
 };
+use rayon::iter::{IntoParallelIterator, ParallelIterator};
 use std::cmp::Ordering;
 use std::collections::{HashMap, HashSet};
 use std::fs;
@@ -72,10 +80,14 @@ use std::sync::{Arc, RwLockReadGuard, RwLockWriteGuard};
 use std::time::Instant;
 use tracing::{debug, error, info, warn};
 
+mod apply_cache;
 pub mod errors;
 
 const STATE_DUMP_FILE: &str = "state_dump";
 const GENESIS_ROOTS_FILE: &str = "genesis_roots";
+/// Number of state parts added to the child shards of a split shard between
+/// checkpoints.  Parts of a batch are read in parallel.
+const STATE_SPLIT_BATCH_SIZE: u64 = 16;
 
 /// Defines Nightshade state transition and validator rotation.
 /// TODO: this possibly should be merged with the runtime cargo or at least reconciled on the interfaces.
@@ -93,6 +105,7 @@ pub struct NightshadeRuntime {
     genesis_state_roots: Vec<StateRoot>,
     migration_data: Arc<MigrationData>,
     gc_num_epochs_to_keep: u64,
+    apply_cache: ApplyCache,
 }
 
 impl NightshadeRuntime {
@@ -162,6 +175,7 @@ impl NightshadeRuntime {
             genesis_state_roots: state_roots,
             migration_data: Arc::new(load_migration_data(&genesis.config.chain_id)),
             gc_num_epochs_to_keep: gc_num_epochs_to_keep.max(MIN_GC_NUM_EPOCHS_TO_KEEP),
+            apply_cache: ApplyCache::new(),
         }
     }
 
@@ -372,6 +386,11 @@ impl NightshadeRuntime {
     }
 
     /// Processes state update.
+    ///
+    /// The result is reused from the apply cache if `use_apply_cache` is set.
+    /// The cache is keyed on the inputs of the application, not on where the
+    /// trie reads its nodes from, so it must not be used when the trie is
+    /// backed by storage which still has to be checked, e.g. a storage proof.
     fn process_state_update(
         &self,
         trie: Trie,
@@ -390,9 +409,43 @@ impl NightshadeRuntime {
         is_new_chunk: bool,
         is_first_block_with_chunk_of_version: bool,
         state_patch: SandboxStatePatch,
+        use_apply_cache: bool,
     ) -> Result<ApplyTransactionResult, Error> {
         let _span = tracing::debug_span!(target: "runtime", "process_state_update").entered();
+        let last_validator_proposals = last_validator_proposals.collect::<Vec<_>>();
+        let shard_uid = self.get_shard_uid_from_prev_hash(shard_id, prev_block_hash)?;
         let epoch_id = self.get_epoch_id_from_prev_block(prev_block_hash)?;
+        let current_protocol_version = self.get_epoch_protocol_version(&epoch_id)?;
+        // Patching the state is not part of the key, so such applications are never cached.
+        let cache_key = if use_apply_cache && state_patch.is_empty() {
+            Some(
+                ApplyCacheInputs {
+                    block_height,
+                    // Nothing but receipt ids depends on the hash of the block the chunk is
+                    // applied in, and older protocol versions derive them from the previous one.
+                    block_hash: (current_protocol_version
+                        >= CREATE_RECEIPT_ID_SWITCH_TO_CURRENT_BLOCK_VERSION)
+                        .then_some(block_hash),
+                    block_timestamp,
+                    prev_block_hash,
+                    last_validator_proposals: &last_validator_proposals,
+                    gas_price,
+                    gas_limit,
+                    challenges_result,
+                    random_seed: &random_seed,
+                    is_new_chunk,
+                    is_first_block_with_chunk_of_version,
+                }
+                .key(shard_id, trie.get_root(), receipts, transactions),
+            )
+        } else {
+            None
+        };
+        if let Some(cached) = cache_key.as_ref().and_then(|key| self.apply_cache.get(key)) {
+            debug!(target: "runtime", shard_id, block_height, "Reusing result of a previous chunk application");
+            return Ok(cached.into_apply_result(self.get_tries(), shard_uid, *block_hash));
+        }
+
         let validator_accounts_update = {
             let epoch_manager = self.epoch_manager.read();
             let shard_layout = epoch_manager.get_shard_layout(&epoch_id)?.clone();
@@ -431,6 +484,7 @@ impl NightshadeRuntime {
                     })
                     .collect();
                 let last_proposals = last_validator_proposals
+                    .into_iter()
                     .filter(|v| account_id_to_shard_id(v.account_id(), &shard_layout) == shard_id)
                     .fold(HashMap::new(), |mut acc, v| {
                         let (account_id, stake) = v.account_and_stake();
@@ -472,7 +526,6 @@ impl NightshadeRuntime {
 
         let epoch_height = self.get_epoch_height_from_prev_block(prev_block_hash)?;
         let prev_block_epoch_id = self.get_epoch_id(prev_block_hash)?;
-        let current_protocol_version = self.get_epoch_protocol_version(&epoch_id)?;
         let prev_block_protocol_version = self.get_epoch_protocol_version(&prev_block_epoch_id)?;
         let is_first_block_of_version = current_protocol_version != prev_block_protocol_version;
 
@@ -548,27 +601,23 @@ impl NightshadeRuntime {
                 Error::Other("Integer overflow during burnt balance summation".to_string())
             })?;
 
-        let shard_uid = self.get_shard_uid_from_prev_hash(shard_id, prev_block_hash)?;
-
-        let result = ApplyTransactionResult {
-            trie_changes: WrappedTrieChanges::new(
-                self.get_tries(),
-                shard_uid,
-                apply_result.trie_changes,
-                apply_result.state_changes,
-                *block_hash,
-            ),
+        let result = CachedApplyResult {
             new_root: apply_result.state_root,
+            trie_changes: apply_result.trie_changes,
+            state_changes: apply_result.state_changes,
             outcomes: apply_result.outcomes,
             outgoing_receipts: apply_result.outgoing_receipts,
             validator_proposals: apply_result.validator_proposals,
             total_gas_burnt,
             total_balance_burnt,
-            proof: apply_result.proof,
             processed_delayed_receipts: apply_result.processed_delayed_receipts,
+            proof: apply_result.proof,
         };
+        if let Some(cache_key) = cache_key {
+            self.apply_cache.put(cache_key, result.clone());
+        }
 
-        Ok(result)
+        Ok(result.into_apply_result(self.get_tries(), shard_uid, *block_hash))
     }
 
     fn precompile_contracts(
@@ -755,9 +804,33 @@ impl RuntimeAdapter for NightshadeRuntime {
     ) -> Result<Option<InvalidTxError>, Error> {
         let runtime_config = self.runtime_config_store.get_config(current_protocol_version);
 
+        // Relayed transactions are routed to the shard of the delegate action's
+        // sender but charged to the relayer, so both accounts must share it.
+        let delegate_action_enabled = checked_feature!(
+            "protocol_feature_delegate_action",
+            DelegateAction,
+            current_protocol_version
+        );
+        if let Some(signed_delegate_action) =
+            transaction.delegate_action().filter(|_| delegate_action_enabled)
+        {
+            let relayer_id = &transaction.transaction.signer_id;
+            let sender_id = &signed_delegate_action.delegate_action.sender_id;
+            if self.account_id_to_shard_uid(relayer_id, epoch_id)?
+                != self.account_id_to_shard_uid(sender_id, epoch_id)?
+            {
+                return Ok(Some(InvalidTxError::ActionsValidation(
+                    ActionsValidationError::DelegateActionRelayerOnDifferentShard {
+                        relayer_id: relayer_id.clone(),
+                        sender_id: sender_id.clone(),
+                    },
+                )));
+            }
+        }
+
         if let Some(state_root) = state_root {
             let shard_uid =
-                self.account_id_to_shard_uid(&transaction.transaction.signer_id, epoch_id)?;
+                self.account_id_to_shard_uid(transaction.routing_account_id(), epoch_id)?;
             let mut state_update = self.tries.new_trie_update(shard_uid, state_root);
 
             match verify_and_charge_transaction(
@@ -803,6 +876,7 @@ impl RuntimeAdapter for NightshadeRuntime {
         &self,
         gas_price: Balance,
         gas_limit: Gas,
+        max_transactions: Option<u64>,
         epoch_id: &EpochId,
         shard_id: ShardId,
         state_root: StateRoot,
@@ -810,7 +884,12 @@ impl RuntimeAdapter for NightshadeRuntime {
         pool_iterator: &mut dyn PoolIterator,
         chain_validate: &mut dyn FnMut(&SignedTransaction) -> bool,
         current_protocol_version: ProtocolVersion,
+        deadline: Option<Instant>,
     ) -> Result<Vec<SignedTransaction>, Error> {
+        let out_of_time = || match deadline {
+            Some(deadline) => Clock::instant() >= deadline,
+            None => false,
+        };
         let shard_uid = self.get_shard_uid_from_epoch_id(shard_id, epoch_id)?;
         let mut state_update = self.tries.new_trie_update(shard_uid, state_root);
 
@@ -835,41 +914,73 @@ impl RuntimeAdapter for NightshadeRuntime {
             / (runtime_config.wasm_config.ext_costs.storage_write_value_byte
                 + runtime_config.wasm_config.ext_costs.storage_read_value_byte);
 
-        while total_gas_burnt < transactions_gas_limit && total_size < size_limit {
-            if let Some(iter) = pool_iterator.next() {
-                while let Some(tx) = iter.next() {
-                    num_checked_transactions += 1;
-                    // Verifying the transaction is on the same chain and hasn't expired yet.
-                    if chain_validate(&tx) {
-                        // Verifying the validity of the transaction based on the current state.
-                        match verify_and_charge_transaction(
-                            runtime_config,
-                            &mut state_update,
-                            gas_price,
-                            &tx,
-                            false,
-                            Some(next_block_height),
-                            current_protocol_version,
-                        ) {
-                            Ok(verification_result) => {
-                                state_update.commit(StateChangeCause::NotWritableToDisk);
-                                total_gas_burnt += verification_result.gas_burnt;
-                                total_size += tx.get_size();
-                                transactions.push(tx);
-                                break;
-                            }
-                            Err(RuntimeError::InvalidTxError(_err)) => {
-                                state_update.rollback();
-                            }
-                            Err(RuntimeError::StorageError(err)) => {
-                                return Err(Error::StorageError(err))
-                            }
-                            Err(err) => unreachable!("Unexpected RuntimeError error {:?}", err),
-                        }
+        // The node may further limit the number of transactions regardless of
+        // their gas, see `ClientConfig::max_transactions_per_chunk`.
+        'pool: while total_gas_burnt < transactions_gas_limit
+            && total_size < size_limit
+            && max_transactions.map_or(true, |max| (transactions.len() as u64) < max)
+        {
+            let iter = match pool_iterator.next() {
+                Some(iter) => iter,
+                None => break,
+            };
+            loop {
+                // Transactions which weren't pulled yet stay in the pool for
+                // the next chunk.
+                if out_of_time() {
+                    break 'pool;
+                }
+                let tx = match iter.next() {
+                    Some(tx) => tx,
+                    None => break,
+                };
+                num_checked_transactions += 1;
+                // Verifying the transaction is on the same chain and hasn't expired yet.
+                if !chain_validate(&tx) {
+                    metrics::PREPARE_TRANSACTIONS_DROPPED.with_label_values(&["expired"]).inc();
+                    continue;
+                }
+                // The transaction was valid when it entered the pool, but its
+                // nonce may have been used since, e.g. by a transaction
+                // included on another fork.  Check that cheaply first.
+                match check_transaction_freshness(&state_update, &tx) {
+                    Ok(()) => {}
+                    Err(RuntimeError::InvalidTxError(err)) => {
+                        let reason = match err {
+                            InvalidTxError::InvalidNonce { .. } => "stale_nonce",
+                            InvalidTxError::NotEnoughBalance { .. } => "insufficient_balance",
+                            _ => "invalid",
+                        };
+                        metrics::PREPARE_TRANSACTIONS_DROPPED.with_label_values(&[reason]).inc();
+                        continue;
                     }
+                    Err(RuntimeError::StorageError(err)) => return Err(Error::StorageError(err)),
+                    Err(err) => unreachable!("Unexpected RuntimeError error {:?}", err),
+                }
+                // Verifying the validity of the transaction based on the current state.
+                match verify_and_charge_transaction(
+                    runtime_config,
+                    &mut state_update,
+                    gas_price,
+                    &tx,
+                    false,
+                    Some(next_block_height),
+                    current_protocol_version,
+                ) {
+                    Ok(verification_result) => {
+                        state_update.commit(StateChangeCause::NotWritableToDisk);
+                        total_gas_burnt += verification_result.gas_burnt;
+                        total_size += tx.get_size();
+                        transactions.push(tx);
+                        break;
+                    }
+                    Err(RuntimeError::InvalidTxError(_err)) => {
+                        state_update.rollback();
+                        metrics::PREPARE_TRANSACTIONS_DROPPED.with_label_values(&["invalid"]).inc();
+                    }
+                    Err(RuntimeError::StorageError(err)) => return Err(Error::StorageError(err)),
+                    Err(err) => unreachable!("Unexpected RuntimeError error {:?}", err),
                 }
-            } else {
-                break;
             }
         }
         debug!(target: "runtime", "Transaction filtering results {} valid out of {} pulled from the pool", transactions.len(), num_checked_transactions);
@@ -896,6 +1007,11 @@ impl RuntimeAdapter for NightshadeRuntime {
         self.shard_tracker.will_care_about_shard(account_id, parent_hash, shard_id, is_me)
     }
 
+    fn update_tracked_shards(&self, shard_ids: Vec<ShardId>) -> Result<(), Error> {
+        self.shard_tracker.update_tracked_config(TrackedConfig::Shards(shard_ids));
+        Ok(())
+    }
+
     fn get_gc_stop_height(&self, block_hash: &CryptoHash) -> BlockHeight {
         (|| -> Result<BlockHeight, Error> {
             let epoch_manager = self.epoch_manager.read();
@@ -1069,6 +1185,7 @@ impl RuntimeAdapter for NightshadeRuntime {
             is_new_chunk,
             is_first_block_with_chunk_of_version,
             states_to_patch,
+            true,
         ) {
             Ok(result) => Ok(result),
             Err(e) => match e {
@@ -1101,6 +1218,8 @@ impl RuntimeAdapter for NightshadeRuntime {
         is_first_block_with_chunk_of_version: bool,
     ) -> Result<ApplyTransactionResult, Error> {
         let trie = Trie::from_recorded_storage(partial_storage, state_root.clone());
+        // The partial storage is what is being checked, so the transition is
+        // always recomputed from it.
         self.process_state_update(
             trie,
             shard_id,
@@ -1118,6 +1237,7 @@ impl RuntimeAdapter for NightshadeRuntime {
             is_new_chunk,
             is_first_block_with_chunk_of_version,
             Default::default(),
+            false,
         )
     }
 
@@ -1261,6 +1381,15 @@ impl RuntimeAdapter for NightshadeRuntime {
                     block_hash: *block_hash,
                 })
             }
+            QueryRequest::ViewReceiptProof { .. } => {
+                // Receipt proofs come from the chain rather than the state and
+                // are answered by the view client.
+                Err(near_chain::near_chain_primitives::error::QueryError::InternalError {
+                    error_message: "receipt proofs aren't served from the state".to_string(),
+                    block_height,
+                    block_hash: *block_hash,
+                })
+            }
         }
     }
 
@@ -1369,17 +1498,64 @@ impl RuntimeAdapter for NightshadeRuntime {
         if state_split_status.total_parts.set(num_parts).is_err() {
             log_assert!(false, "splitting state was done twice for shard {}", shard_id);
         }
+        // Resume an interrupted split of the same state into the same shards.
+        let mut next_part = 0;
+        match self.tries.get_state_split_checkpoint(shard_uid)? {
+            Some(checkpoint)
+                if checkpoint.state_root == *state_root
+                    && checkpoint.num_parts == num_parts
+                    && checkpoint.state_roots.len() == state_roots.len()
+                    && checkpoint
+                        .state_roots
+                        .iter()
+                        .all(|(shard_uid, _)| state_roots.contains_key(shard_uid)) =>
+            {
+                debug!(target: "runtime", "resuming splitting state for shard {} from part {}", shard_id, checkpoint.next_part);
+                next_part = checkpoint.next_part;
+                state_roots = checkpoint.state_roots.into_iter().collect();
+            }
+            Some(_) => {
+                debug!(target: "runtime", "ignoring state split checkpoint of a different state for shard {}", shard_id);
+            }
+            None => {}
+        }
+        state_split_status.resumed_parts.store(next_part, core::sync::atomic::Ordering::Relaxed);
+        state_split_status.done_parts.store(next_part, core::sync::atomic::Ordering::Relaxed);
+        let _ = state_split_status.started.set(Clock::instant());
         debug!(target: "runtime", "splitting state for shard {} to {} parts to build new states", shard_id, num_parts);
-        for part_id in 0..num_parts {
-            let trie_items = trie.get_trie_items_for_part(PartId::new(part_id, num_parts))?;
-            let (store_update, new_state_roots) = self.tries.add_values_to_split_states(
+        while next_part < num_parts {
+            let batch_end = std::cmp::min(next_part + STATE_SPLIT_BATCH_SIZE, num_parts);
+            // Reading parts from the trie dominates the cost, so the parts of
+            // a batch are read in parallel and then added in order.
+            let trie_items = (next_part..batch_end)
+                .into_par_iter()
+                .map(|part_id| {
+                    self.tries
+                        .get_view_trie_for_shard(shard_uid, *state_root)
+                        .get_trie_items_for_part(PartId::new(part_id, num_parts))
+                })
+                .collect::<Result<Vec<_>, _>>()?;
+            let (mut store_update, new_state_roots) = self.tries.add_values_to_split_states(
                 &state_roots,
-                trie_items.into_iter().map(|(key, value)| (key, Some(value))).collect(),
+                trie_items.into_iter().flatten().map(|(key, value)| (key, Some(value))).collect(),
                 &checked_account_id_to_shard_id,
             )?;
             state_roots = new_state_roots;
+            next_part = batch_end;
+            // The checkpoint is committed atomically with the trie nodes it
+            // refers to.
+            ShardTries::set_state_split_checkpoint(
+                &mut store_update,
+                shard_uid,
+                &StateSplitCheckpoint {
+                    state_root: *state_root,
+                    num_parts,
+                    next_part,
+                    state_roots: state_roots.iter().map(|(k, v)| (*k, *v)).collect(),
+                },
+            )?;
             store_update.commit()?;
-            state_split_status.done_parts.fetch_add(1, core::sync::atomic::Ordering::Relaxed);
+            state_split_status.done_parts.store(next_part, core::sync::atomic::Ordering::Relaxed);
         }
         state_roots = apply_delayed_receipts(
             &self.tries,
@@ -1388,6 +1564,9 @@ impl RuntimeAdapter for NightshadeRuntime {
             state_roots,
             &checked_account_id_to_shard_id,
         )?;
+        let mut store_update = self.tries.store_update();
+        ShardTries::remove_state_split_checkpoint(&mut store_update, shard_uid);
+        store_update.commit()?;
         Ok(state_roots)
     }
 
@@ -1628,8 +1807,11 @@ mod test {
     use near_epoch_manager::EpochManagerAdapter;
     use near_o11y::testonly::init_test_logger;
     use near_primitives::block::Tip;
-    use near_primitives::challenge::SlashedValidator;
-    use near_primitives::transaction::{Action, DeleteAccountAction, StakeAction, TransferAction};
+    use near_primitives::challenge::{PartialState, SlashedValidator};
+    use near_primitives::transaction::{
+        Action, DelegateAction, DeleteAccountAction, SignedDelegateAction, StakeAction,
+        TransferAction,
+    };
     use near_primitives::types::{
         BlockHeightDelta, Nonce, ValidatorId, ValidatorInfoIdentifier, ValidatorKickoutReason,
     };
@@ -2513,6 +2695,8 @@ mod test {
                 num_expected_blocks: expected_blocks[0],
                 num_produced_chunks: expected_chunks[0],
                 num_expected_chunks: expected_chunks[0],
+                #[cfg(feature = "protocol_feature_bls_approvals")]
+                bls_public_key: None,
             },
             CurrentEpochValidatorInfo {
                 account_id: "test2".parse().unwrap(),
@@ -2524,6 +2708,8 @@ mod test {
                 num_expected_blocks: expected_blocks[1],
                 num_produced_chunks: expected_chunks[1],
                 num_expected_chunks: expected_chunks[1],
+                #[cfg(feature = "protocol_feature_bls_approvals")]
+                bls_public_key: None,
             },
         ];
         let next_epoch_validator_info = vec![
@@ -3170,6 +3356,83 @@ mod test {
         assert_eq!(env.last_proposals[0].stake(), 0);
     }
 
+    /// Relayed transactions are validated on the shard of the delegate action's
+    /// sender and rejected if the relayer lives on another shard.
+    #[test]
+    fn test_validate_tx_delegate_action_shard() {
+        let validators: Vec<AccountId> = vec!["test1".parse().unwrap(), "test2".parse().unwrap()];
+        let env = TestEnv::new(vec![validators.clone(), validators], 4, false);
+        let epoch_id = env.head.epoch_id.clone();
+        let shard_id = |account_id: &AccountId| {
+            env.runtime.account_id_to_shard_id(account_id, &epoch_id).unwrap()
+        };
+        let accounts: Vec<AccountId> =
+            (0..32).map(|i| format!("account{}", i).parse().unwrap()).collect();
+        let sender_id = accounts[0].clone();
+        let same_shard_relayer =
+            accounts[1..].iter().find(|id| shard_id(id) == shard_id(&sender_id)).unwrap().clone();
+        let other_shard_relayer =
+            accounts[1..].iter().find(|id| shard_id(id) != shard_id(&sender_id)).unwrap().clone();
+
+        let validate_relayed = |relayer_id: AccountId| {
+            let signer = InMemorySigner::from_seed(
+                relayer_id.clone(),
+                KeyType::ED25519,
+                relayer_id.as_ref(),
+            );
+            let delegate_action = DelegateAction {
+                sender_id: sender_id.clone(),
+                receiver_id: "test1".parse().unwrap(),
+                actions: vec![Action::Transfer(TransferAction { deposit: 1 })],
+                nonce: 1,
+                max_block_height: 100,
+                public_key: signer.public_key(),
+            };
+            let signature = signer.sign(delegate_action.get_hash().as_ref());
+            let transaction = SignedTransaction::from_actions(
+                1,
+                relayer_id,
+                sender_id.clone(),
+                &signer,
+                vec![Action::Delegate(SignedDelegateAction { delegate_action, signature })],
+                CryptoHash::default(),
+            );
+            assert_eq!(transaction.routing_account_id(), &sender_id);
+            env.runtime
+                .validate_tx(
+                    0,
+                    None,
+                    &transaction,
+                    false,
+                    &epoch_id,
+                    near_primitives::version::PROTOCOL_VERSION,
+                )
+                .unwrap()
+        };
+
+        if cfg!(feature = "protocol_feature_delegate_action") {
+            assert_eq!(validate_relayed(same_shard_relayer), None);
+            assert_eq!(
+                validate_relayed(other_shard_relayer.clone()),
+                Some(InvalidTxError::ActionsValidation(
+                    ActionsValidationError::DelegateActionRelayerOnDifferentShard {
+                        relayer_id: other_shard_relayer,
+                        sender_id,
+                    }
+                ))
+            );
+        } else {
+            for relayer_id in [same_shard_relayer, other_shard_relayer] {
+                assert!(matches!(
+                    validate_relayed(relayer_id),
+                    Some(InvalidTxError::ActionsValidation(
+                        ActionsValidationError::UnsupportedProtocolFeature { .. }
+                    ))
+                ));
+            }
+        }
+    }
+
     /// Check that flat state is included into trie and is not included into view trie, because we can't apply flat
     /// state optimization to view calls.
     #[test]
@@ -3235,6 +3498,114 @@ mod test {
         assert_eq!(state_value, view_state_value);
     }
 
+    /// Check that results served from the cache of recent chunk applications are the same as
+    /// recomputed ones, that changing an input of the application misses the cache and that
+    /// checking a state transition against a storage proof doesn't use the cache.
+    #[test]
+    fn test_apply_cache_matches_recomputed_result() {
+        let validators: Vec<AccountId> = vec!["test1".parse().unwrap(), "test2".parse().unwrap()];
+        let mut env = TestEnv::new(vec![validators.clone()], 4, false);
+        let signer = InMemorySigner::from_seed(
+            validators[0].clone(),
+            KeyType::ED25519,
+            validators[0].as_ref(),
+        );
+        let transfer_tx = |nonce| {
+            SignedTransaction::from_actions(
+                nonce,
+                validators[0].clone(),
+                validators[1].clone(),
+                &signer as &dyn Signer,
+                vec![Action::Transfer(TransferAction { deposit: 10 })],
+                // runtime does not validate block history
+                CryptoHash::default(),
+            )
+        };
+        env.step_default(vec![transfer_tx(4)]);
+        let receipts = env.last_receipts.get(&0).cloned().unwrap_or_default();
+        let transactions = vec![transfer_tx(5)];
+        assert!(!receipts.is_empty());
+
+        let apply = |block_hash: &CryptoHash| {
+            env.runtime
+                .apply_transactions(
+                    0,
+                    &env.state_roots[0],
+                    env.head.height + 1,
+                    0,
+                    &env.head.last_block_hash,
+                    block_hash,
+                    &receipts,
+                    &transactions,
+                    ValidatorStakeIter::empty(),
+                    env.runtime.genesis_config.min_gas_price,
+                    u64::max_value(),
+                    &ChallengesResult::default(),
+                    CryptoHash::default(),
+                    true,
+                    false,
+                    Default::default(),
+                    true,
+                )
+                .unwrap()
+        };
+        let summary = |result: &ApplyTransactionResult| {
+            (
+                result.new_root,
+                result.outcomes.clone(),
+                result.outgoing_receipts.clone(),
+                result.validator_proposals.clone(),
+                result.trie_changes.trie_changes().clone(),
+                result.trie_changes.state_changes().to_vec(),
+                result.total_gas_burnt,
+                result.total_balance_burnt,
+                result.processed_delayed_receipts.clone(),
+            )
+                .try_to_vec()
+                .unwrap()
+        };
+
+        env.runtime.apply_cache.clear();
+        let block_hash = hash(&[env.head.height as u8 + 1]);
+        let computed = apply(&block_hash);
+        assert_eq!(env.runtime.apply_cache.len(), 1);
+        let cached = apply(&block_hash);
+        assert_eq!(env.runtime.apply_cache.len(), 1);
+        env.runtime.apply_cache.clear();
+        let recomputed = apply(&block_hash);
+        assert_eq!(computed.outcomes.len(), receipts.len() + transactions.len());
+        assert_eq!(summary(&cached), summary(&computed));
+        assert_eq!(summary(&cached), summary(&recomputed));
+
+        // Receipt ids depend on the block hash, so a different block must not reuse the result.
+        let other = apply(&hash(b"other block"));
+        assert_eq!(env.runtime.apply_cache.len(), 2);
+        assert_ne!(summary(&other), summary(&cached));
+
+        // An empty proof lacks the nodes of the state, so the transition can't be checked even
+        // though the result of the same application is cached.
+        let result = env.runtime.check_state_transition(
+            PartialStorage { nodes: PartialState(vec![]) },
+            0,
+            &env.state_roots[0],
+            env.head.height + 1,
+            0,
+            &env.head.last_block_hash,
+            &block_hash,
+            &receipts,
+            &transactions,
+            ValidatorStakeIter::empty(),
+            env.runtime.genesis_config.min_gas_price,
+            u64::max_value(),
+            &ChallengesResult::default(),
+            CryptoHash::default(),
+            true,
+            false,
+        );
+        assert!(result.is_err());
+        assert_eq!(env.runtime.apply_cache.len(), 2);
+    }
+
     /// Check that mainnet genesis hash still matches, to make sure that we're still backwards compatible.
     #[test]
     fn test_genesis_hash() {
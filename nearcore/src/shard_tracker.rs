@@ -1,3 +1,5 @@
+use std::sync::RwLock;
+
 use crate::append_only_map::AppendOnlyMap;
 use near_chain_configs::ClientConfig;
 use near_epoch_manager::EpochManagerHandle;
@@ -8,6 +10,8 @@ use near_primitives::types::{AccountId, EpochId, ShardId};
 
 pub enum TrackedConfig {
     Accounts(Vec<AccountId>),
+    /// Shards with the given ids in the shard layout of each epoch.
+    Shards(Vec<ShardId>),
     AllShards,
 }
 
@@ -28,12 +32,20 @@ impl TrackedConfig {
 // bit mask for which shard to track
 type BitMask = Vec<bool>;
 
-/// Tracker that tracks shard ids and accounts. Right now, it only supports two modes
+/// Tracker that tracks shard ids and accounts. It supports three modes
 /// TrackedConfig::Accounts(accounts): track the shards where `accounts` belong to
+/// TrackedConfig::Shards(shard_ids): track the shards with the given ids
 /// TrackedConfig::AllShards: track all shards
+///
+/// The config can be replaced at runtime.  The shards tracked in an epoch are
+/// fixed the first time they are looked up, which happens at the latest when
+/// the epoch before it starts and decides which shards to catch up.  A new
+/// config therefore only applies to epochs whose shards weren't looked up yet,
+/// so the shards tracked in the current and the next epoch never change and
+/// newly tracked shards are caught up like for any other epoch change.
 pub struct ShardTracker {
-    tracked_config: TrackedConfig,
-    /// Stores shard tracking information by epoch, only useful if TrackedState == Accounts
+    tracked_config: RwLock<TrackedConfig>,
+    /// Stores shard tracking information by epoch
     tracking_shards: AppendOnlyMap<EpochId, BitMask>,
     /// Epoch manager that for given block hash computes the epoch id.
     epoch_manager: EpochManagerHandle,
@@ -41,7 +53,21 @@ pub struct ShardTracker {
 
 impl ShardTracker {
     pub fn new(tracked_config: TrackedConfig, epoch_manager: EpochManagerHandle) -> Self {
-        ShardTracker { tracked_config, tracking_shards: AppendOnlyMap::new(), epoch_manager }
+        ShardTracker {
+            tracked_config: RwLock::new(tracked_config),
+            tracking_shards: AppendOnlyMap::new(),
+            epoch_manager,
+        }
+    }
+
+    /// Replaces the config of the tracker, for the epochs whose tracked
+    /// shards weren't looked up yet.
+    pub fn update_tracked_config(&self, tracked_config: TrackedConfig) {
+        *self.tracked_config.write().unwrap() = tracked_config;
+    }
+
+    fn tracks_all_shards(&self) -> bool {
+        matches!(*self.tracked_config.read().unwrap(), TrackedConfig::AllShards)
     }
 
     fn tracks_shard_at_epoch(
@@ -49,22 +75,28 @@ impl ShardTracker {
         shard_id: ShardId,
         epoch_id: &EpochId,
     ) -> Result<bool, EpochError> {
-        match &self.tracked_config {
-            TrackedConfig::Accounts(tracked_accounts) => {
-                let epoch_manager = self.epoch_manager.read();
-                let shard_layout = epoch_manager.get_shard_layout(epoch_id)?;
-                let tracking_mask = self.tracking_shards.get_or_insert(epoch_id, || {
-                    let mut tracking_mask = vec![false; shard_layout.num_shards() as usize];
+        let epoch_manager = self.epoch_manager.read();
+        let shard_layout = epoch_manager.get_shard_layout(epoch_id)?;
+        let tracking_mask = self.tracking_shards.get_or_insert(epoch_id, || {
+            let num_shards = shard_layout.num_shards();
+            let mut tracking_mask = vec![false; num_shards as usize];
+            match &*self.tracked_config.read().unwrap() {
+                TrackedConfig::Accounts(tracked_accounts) => {
                     for account_id in tracked_accounts {
                         let shard_id = account_id_to_shard_id(account_id, &shard_layout);
                         *tracking_mask.get_mut(shard_id as usize).unwrap() = true;
                     }
-                    tracking_mask
-                });
-                Ok(tracking_mask.get(shard_id as usize).copied().unwrap_or(false))
+                }
+                TrackedConfig::Shards(shard_ids) => {
+                    for shard_id in shard_ids.iter().filter(|shard_id| **shard_id < num_shards) {
+                        tracking_mask[*shard_id as usize] = true;
+                    }
+                }
+                TrackedConfig::AllShards => tracking_mask.fill(true),
             }
-            TrackedConfig::AllShards => Ok(true),
-        }
+            tracking_mask
+        });
+        Ok(tracking_mask.get(shard_id as usize).copied().unwrap_or(false))
     }
 
     fn tracks_shard(&self, shard_id: ShardId, prev_hash: &CryptoHash) -> Result<bool, EpochError> {
@@ -75,6 +107,34 @@ impl ShardTracker {
         self.tracks_shard_at_epoch(shard_id, &epoch_id)
     }
 
+    /// Whether the shard will be tracked in the next epoch.  If the shard
+    /// layout changes, whether any of the shards split from it will be.
+    fn will_track_shard(
+        &self,
+        shard_id: ShardId,
+        prev_hash: &CryptoHash,
+    ) -> Result<bool, EpochError> {
+        let (next_epoch_id, split_shard_ids) = {
+            let epoch_manager = self.epoch_manager.read();
+            let epoch_id = epoch_manager.get_epoch_id_from_prev_block(prev_hash)?;
+            let next_epoch_id = epoch_manager.get_next_epoch_id_from_prev_block(prev_hash)?;
+            let shard_layout = epoch_manager.get_shard_layout(&epoch_id)?;
+            let next_shard_layout = epoch_manager.get_shard_layout(&next_epoch_id)?;
+            let split_shard_ids = if next_shard_layout == shard_layout {
+                None
+            } else {
+                next_shard_layout.get_split_shard_ids(shard_id)
+            };
+            (next_epoch_id, split_shard_ids.unwrap_or_else(|| vec![shard_id]))
+        };
+        for shard_id in split_shard_ids {
+            if self.tracks_shard_at_epoch(shard_id, &next_epoch_id)? {
+                return Ok(true);
+            }
+        }
+        Ok(false)
+    }
+
     pub fn care_about_shard(
         &self,
         account_id: Option<&AccountId>,
@@ -97,8 +157,7 @@ impl ShardTracker {
                 return true;
             }
         }
-        matches!(self.tracked_config, TrackedConfig::AllShards)
-            || self.tracks_shard(shard_id, parent_hash).unwrap_or(false)
+        self.tracks_shard(shard_id, parent_hash).unwrap_or_else(|_| self.tracks_all_shards())
     }
 
     // `shard_id` always refers to a shard in the current epoch that the next block from `parent_hash` belongs
@@ -124,8 +183,7 @@ impl ShardTracker {
                 return true;
             }
         }
-        matches!(self.tracked_config, TrackedConfig::AllShards)
-            || self.tracks_shard(shard_id, parent_hash).unwrap_or(false)
+        self.will_track_shard(shard_id, parent_hash).unwrap_or_else(|_| self.tracks_all_shards())
     }
 }
 
@@ -290,6 +348,47 @@ mod tests {
         );
     }
 
+    #[test]
+    fn test_update_tracked_shards() {
+        let num_shards = 4;
+        let epoch_manager = get_epoch_manager(PROTOCOL_VERSION, num_shards, false);
+        let tracker = ShardTracker::new(TrackedConfig::Shards(vec![0]), epoch_manager.clone());
+        let h = hash_range(8);
+        {
+            let mut epoch_manager = epoch_manager.write();
+            record_block(
+                &mut epoch_manager,
+                CryptoHash::default(),
+                h[0],
+                0,
+                vec![],
+                PROTOCOL_VERSION,
+            );
+            for i in 1..8 {
+                record_block(
+                    &mut epoch_manager,
+                    h[i - 1],
+                    h[i],
+                    i as u64,
+                    vec![],
+                    PROTOCOL_VERSION,
+                );
+            }
+        }
+        let initial_shards = HashSet::from([0]);
+        assert_eq!(get_all_shards_care_about(&tracker, num_shards, &h[0]), initial_shards);
+        assert_eq!(get_all_shards_will_care_about(&tracker, num_shards, &h[0]), initial_shards);
+
+        // The shards of the epochs which were already looked up don't change,
+        // later epochs track the new shards.
+        tracker.update_tracked_config(TrackedConfig::Shards(vec![1, 2, 7]));
+        assert_eq!(get_all_shards_care_about(&tracker, num_shards, &h[0]), initial_shards);
+        assert_eq!(get_all_shards_will_care_about(&tracker, num_shards, &h[0]), initial_shards);
+        let updated_shards = HashSet::from([1, 2]);
+        assert_eq!(get_all_shards_care_about(&tracker, num_shards, &h[5]), updated_shards);
+        assert_eq!(get_all_shards_will_care_about(&tracker, num_shards, &h[5]), updated_shards);
+    }
+
     #[test]
     fn test_track_shards_shard_layout_change() {
         let simple_nightshade_version = SimpleNightshade.protocol_version();
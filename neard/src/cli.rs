@@ -457,16 +457,19 @@ impl RunCmd {
             .await
             .global();
 
-            let nearcore::NearNode { rpc_servers, .. } =
+            // Keep the node around, its background threads stop once it's dropped.
+            let near_node =
                 nearcore::start_with_config_and_synchronization(home_dir, near_config, Some(tx))
                     .expect("start_with_config");
 
             let sig = wait_for_interrupt_signal(home_dir, rx).await;
             warn!(target: "neard", "{}, stopping... this may take a few minutes.", sig);
-            futures::future::join_all(rpc_servers.iter().map(|(name, server)| async move {
-                server.stop(true).await;
-                debug!(target: "neard", "{} server stopped", name);
-            }))
+            futures::future::join_all(near_node.rpc_servers.iter().map(
+                |(name, server)| async move {
+                    server.stop(true).await;
+                    debug!(target: "neard", "{} server stopped", name);
+                },
+            ))
             .await;
             actix::System::current().stop();
 
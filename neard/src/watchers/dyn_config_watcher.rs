@@ -7,15 +7,19 @@ use serde::{Deserialize, Serialize};
 pub(crate) struct DynConfig {
     /// Graceful shutdown at expected blockheight
     pub expected_shutdown: Option<u64>,
+    /// Start of the maintenance window before the expected shutdown.  From this
+    /// blockheight on the node stops taking on new work and drains in-flight work.
+    #[serde(default)]
+    pub maintenance_start: Option<u64>,
 }
 
 impl Watcher for DynConfig {
     fn reload(config: Option<Self>) -> Result<(), WatchConfigError> {
         if let Some(config) = config {
-            reload(config.expected_shutdown);
+            reload(config.expected_shutdown, config.maintenance_start);
             Ok(())
         } else {
-            reload(None);
+            reload(None, None);
             Ok(())
         }
     }
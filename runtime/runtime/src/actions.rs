@@ -1,4 +1,4 @@
-use crate::config::{safe_add_gas, RuntimeConfig};
+use crate::config::{safe_add_gas, total_prepaid_exec_fees, total_prepaid_gas, RuntimeConfig};
 use crate::ext::{ExternalError, RuntimeExt};
 use crate::{metrics, ActionResult, ApplyState};
 use borsh::{BorshDeserialize, BorshSerialize};
@@ -7,14 +7,14 @@ use near_primitives::account::{AccessKey, AccessKeyPermission, Account};
 use near_primitives::checked_feature;
 use near_primitives::config::ViewConfig;
 use near_primitives::contract::ContractCode;
-use near_primitives::errors::{ActionError, ActionErrorKind, RuntimeError};
+use near_primitives::errors::{ActionError, ActionErrorKind, InvalidAccessKeyError, RuntimeError};
 use near_primitives::hash::CryptoHash;
 use near_primitives::receipt::{ActionReceipt, Receipt, ReceiptEnum};
 use near_primitives::runtime::config::AccountCreationConfig;
 use near_primitives::runtime::fees::RuntimeFeesConfig;
 use near_primitives::transaction::{
     Action, AddKeyAction, DeleteAccountAction, DeleteKeyAction, DeployContractAction,
-    FunctionCallAction, StakeAction, TransferAction,
+    FunctionCallAction, SignedDelegateAction, StakeAction, TransferAction,
 };
 use near_primitives::types::validator_stake::ValidatorStake;
 use near_primitives::types::{AccountId, BlockHeight, EpochInfoProvider, TrieCacheMode};
@@ -624,6 +624,136 @@ pub(crate) fn action_add_key(
     Ok(())
 }
 
+/// Checks a delegate action against the access key of its sender and sends the
+/// actions it carries on to their receiver on behalf of the sender.  The
+/// signature was checked when the transaction relaying it was validated.
+///
+/// The relayer, who signed that transaction, keeps paying for the new receipt
+/// and gets the refunds of its gas.  The deposits it attached are handed to the
+/// sender along with the actions, so if these fail, the deposits are refunded
+/// to the sender as the predecessor of the new receipt, as proposed in NEP-366.
+/// A relayer which doesn't want to fund the sender shouldn't relay deposits.
+pub(crate) fn apply_delegate_action(
+    state_update: &mut TrieUpdate,
+    apply_state: &ApplyState,
+    action_receipt: &ActionReceipt,
+    signed_delegate_action: &SignedDelegateAction,
+    result: &mut ActionResult,
+) -> Result<(), RuntimeError> {
+    let delegate_action = &signed_delegate_action.delegate_action;
+    if apply_state.block_height > delegate_action.max_block_height {
+        result.result = Err(ActionErrorKind::DelegateActionExpired {
+            max_block_height: delegate_action.max_block_height,
+            block_height: apply_state.block_height,
+        }
+        .into());
+        return Ok(());
+    }
+
+    let sender_id = &delegate_action.sender_id;
+    let public_key = &delegate_action.public_key;
+    let mut access_key = match get_access_key(state_update, sender_id, public_key)? {
+        Some(access_key) => access_key,
+        None => {
+            result.result = Err(ActionErrorKind::DelegateActionAccessKeyError(
+                InvalidAccessKeyError::AccessKeyNotFound {
+                    account_id: sender_id.clone(),
+                    public_key: public_key.clone(),
+                },
+            )
+            .into());
+            return Ok(());
+        }
+    };
+    if delegate_action.nonce <= access_key.nonce {
+        result.result = Err(ActionErrorKind::DelegateActionInvalidNonce {
+            delegate_nonce: delegate_action.nonce,
+            ak_nonce: access_key.nonce,
+        }
+        .into());
+        return Ok(());
+    }
+    // As for transactions, the nonce is bounded by the height, so that a single
+    // delegate action can't make its access key unusable by setting its nonce
+    // to the maximum.
+    let upper_bound = apply_state.block_height
+        * near_primitives::account::AccessKey::ACCESS_KEY_NONCE_RANGE_MULTIPLIER;
+    if delegate_action.nonce >= upper_bound {
+        result.result = Err(ActionErrorKind::DelegateActionNonceTooLarge {
+            delegate_nonce: delegate_action.nonce,
+            upper_bound,
+        }
+        .into());
+        return Ok(());
+    }
+    if let AccessKeyPermission::FunctionCall(ref permission) = access_key.permission {
+        let access_key_error = match delegate_action.actions.as_slice() {
+            [Action::FunctionCall(function_call)] => {
+                if function_call.deposit > 0 {
+                    Some(InvalidAccessKeyError::DepositWithFunctionCall)
+                } else if delegate_action.receiver_id.as_ref() != permission.receiver_id {
+                    Some(InvalidAccessKeyError::ReceiverMismatch {
+                        tx_receiver: delegate_action.receiver_id.clone(),
+                        ak_receiver: permission.receiver_id.clone(),
+                    })
+                } else if !permission.method_names.is_empty()
+                    && permission
+                        .method_names
+                        .iter()
+                        .all(|method_name| &function_call.method_name != method_name)
+                {
+                    Some(InvalidAccessKeyError::MethodNameMismatch {
+                        method_name: function_call.method_name.clone(),
+                    })
+                } else {
+                    None
+                }
+            }
+            _ => Some(InvalidAccessKeyError::RequiresFullAccess),
+        };
+        if let Some(access_key_error) = access_key_error {
+            result.result =
+                Err(ActionErrorKind::DelegateActionAccessKeyError(access_key_error).into());
+            return Ok(());
+        }
+    }
+    access_key.nonce = delegate_action.nonce;
+    set_access_key(state_update, sender_id.clone(), public_key.clone(), &access_key);
+
+    // The send fees of the new receipt were burnt when the relaying
+    // transaction was converted, its execution is paid for with the prepaid
+    // gas of the delegate action.
+    let fee_config = &apply_state.config.transaction_costs;
+    let mut new_receipt_gas = fee_config.action_receipt_creation_config.exec_fee();
+    new_receipt_gas = safe_add_gas(
+        new_receipt_gas,
+        total_prepaid_exec_fees(
+            fee_config,
+            &delegate_action.actions,
+            &delegate_action.receiver_id,
+            apply_state.current_protocol_version,
+        )?,
+    )?;
+    new_receipt_gas = safe_add_gas(new_receipt_gas, total_prepaid_gas(&delegate_action.actions)?)?;
+    result.gas_used = safe_add_gas(result.gas_used, new_receipt_gas)?;
+    result.new_receipts.push(Receipt {
+        predecessor_id: sender_id.clone(),
+        receiver_id: delegate_action.receiver_id.clone(),
+        // Actual receipt ID is set in the Runtime.apply_action_receipt(...) in the
+        // "Generating receipt IDs" section
+        receipt_id: CryptoHash::default(),
+        receipt: ReceiptEnum::Action(ActionReceipt {
+            signer_id: action_receipt.signer_id.clone(),
+            signer_public_key: action_receipt.signer_public_key.clone(),
+            gas_price: action_receipt.gas_price,
+            output_data_receivers: vec![],
+            input_data_ids: vec![],
+            actions: delegate_action.actions.clone(),
+        }),
+    });
+    Ok(())
+}
+
 pub(crate) fn check_actor_permissions(
     action: &Action,
     account: &Option<Account>,
@@ -656,7 +786,11 @@ pub(crate) fn check_actor_permissions(
                 .into());
             }
         }
-        Action::CreateAccount(_) | Action::FunctionCall(_) | Action::Transfer(_) => (),
+        // The delegate action is authorised by the signature of its sender.
+        Action::CreateAccount(_)
+        | Action::FunctionCall(_)
+        | Action::Transfer(_)
+        | Action::Delegate(_) => (),
     };
     Ok(())
 }
@@ -723,7 +857,8 @@ pub(crate) fn check_account_existence(
         | Action::Stake(_)
         | Action::AddKey(_)
         | Action::DeleteKey(_)
-        | Action::DeleteAccount(_) => {
+        | Action::DeleteAccount(_)
+        | Action::Delegate(_) => {
             if account.is_none() {
                 return Err(ActionErrorKind::AccountDoesNotExist {
                     account_id: account_id.clone(),
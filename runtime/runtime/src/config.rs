@@ -120,6 +120,23 @@ pub fn total_send_fees(
             },
             DeleteKey(_) => cfg.delete_key_cost.send_fee(sender_is_receiver),
             DeleteAccount(_) => cfg.delete_account_cost.send_fee(sender_is_receiver),
+            // The relayer pays upfront for sending the receipt with the
+            // delegated actions, which is sent on behalf of the sender.
+            Delegate(signed_delegate_action) => {
+                let delegate_action = &signed_delegate_action.delegate_action;
+                let delegate_sender_is_receiver =
+                    delegate_action.sender_id == delegate_action.receiver_id;
+                safe_add_gas(
+                    config.action_receipt_creation_config.send_fee(delegate_sender_is_receiver),
+                    total_send_fees(
+                        config,
+                        delegate_sender_is_receiver,
+                        &delegate_action.actions,
+                        &delegate_action.receiver_id,
+                        current_protocol_version,
+                    )?,
+                )?
+            }
         };
         result = safe_add_gas(result, delta)?;
     }
@@ -170,6 +187,10 @@ pub fn exec_fee(
         },
         DeleteKey(_) => cfg.delete_key_cost.exec_fee(),
         DeleteAccount(_) => cfg.delete_account_cost.exec_fee(),
+        // Checking the delegate action costs as much as executing an action
+        // receipt.  The execution of the actions it carries is paid for
+        // separately, see `total_prepaid_exec_fees`.
+        Delegate(_) => config.action_receipt_creation_config.exec_fee(),
     }
 }
 
@@ -195,7 +216,12 @@ pub fn tx_cost(
     let prepaid_gas = total_prepaid_gas(&transaction.actions)?;
     // If signer is equals to receiver the receipt will be processed at the same block as this
     // transaction. Otherwise it will processed in the next block and the gas might be inflated.
-    let initial_receipt_hop = if transaction.signer_id == transaction.receiver_id { 0 } else { 1 };
+    let mut initial_receipt_hop =
+        if transaction.signer_id == transaction.receiver_id { 0 } else { 1 };
+    // Delegated actions are sent on in another receipt.
+    if transaction.actions.iter().any(|action| matches!(action, Action::Delegate(_))) {
+        initial_receipt_hop += 1;
+    }
     let minimum_new_receipt_gas = config.min_receipt_with_function_call_gas();
     // In case the config is free, we don't care about the maximum depth.
     let receipt_gas_price = if gas_price == 0 {
@@ -239,7 +265,22 @@ pub fn total_prepaid_exec_fees(
 ) -> Result<Gas, IntegerOverflowError> {
     let mut result = 0;
     for action in actions {
-        let delta = exec_fee(config, action, receiver_id, current_protocol_version);
+        let mut delta = exec_fee(config, action, receiver_id, current_protocol_version);
+        // The receipt with the delegated actions is executed on the prepaid
+        // gas of the delegate action.
+        if let Action::Delegate(signed_delegate_action) = action {
+            let delegate_action = &signed_delegate_action.delegate_action;
+            delta = safe_add_gas(delta, config.action_receipt_creation_config.exec_fee())?;
+            delta = safe_add_gas(
+                delta,
+                total_prepaid_exec_fees(
+                    config,
+                    &delegate_action.actions,
+                    &delegate_action.receiver_id,
+                    current_protocol_version,
+                )?,
+            )?;
+        }
         result = safe_add_gas(result, delta)?;
     }
     Ok(result)
@@ -61,7 +61,9 @@ use crate::config::{
 use crate::genesis::{GenesisStateApplier, StorageComputer};
 use crate::prefetch::TriePrefetcher;
 use crate::verifier::validate_receipt;
-pub use crate::verifier::{validate_transaction, verify_and_charge_transaction};
+pub use crate::verifier::{
+    check_transaction_freshness, validate_transaction, verify_and_charge_transaction,
+};
 
 mod actions;
 pub mod adapter;
@@ -443,6 +445,15 @@ impl Runtime {
                     apply_state.current_protocol_version,
                 )?;
             }
+            Action::Delegate(signed_delegate_action) => {
+                apply_delegate_action(
+                    state_update,
+                    apply_state,
+                    action_receipt,
+                    signed_delegate_action,
+                    &mut result,
+                )?;
+            }
         };
         Ok(result)
     }
@@ -64,6 +64,11 @@ pub(crate) struct TriePrefetcher {
 
 impl TriePrefetcher {
     pub(crate) fn new_if_enabled(trie: Rc<Trie>) -> Option<Self> {
+        if near_store::storage_health().is_degraded() {
+            // Prefetching competes with chunk application for disk access.
+            near_store::storage_health().record_shed("prefetch");
+            return None;
+        }
         if let Some(caching_storage) = trie.storage.as_caching_storage() {
             if let Some(prefetch_api) = caching_storage.prefetch_api().clone() {
                 let trie_root = *trie.get_root();
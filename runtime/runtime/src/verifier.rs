@@ -9,8 +9,8 @@ use near_primitives::{
     },
     receipt::{ActionReceipt, DataReceipt, Receipt, ReceiptEnum},
     transaction::{
-        Action, AddKeyAction, DeployContractAction, FunctionCallAction, SignedTransaction,
-        StakeAction,
+        Action, AddKeyAction, DeployContractAction, FunctionCallAction, SignedDelegateAction,
+        SignedTransaction, StakeAction,
     },
     types::{AccountId, Balance},
     version::ProtocolVersion,
@@ -19,7 +19,7 @@ use near_store::{
     get_access_key, get_account, set_access_key, set_account, StorageError, TrieUpdate,
 };
 
-use crate::config::{total_prepaid_gas, tx_cost, TransactionCost};
+use crate::config::{total_deposit, total_prepaid_gas, tx_cost, TransactionCost};
 use crate::VerificationResult;
 use near_primitives::checked_feature;
 use near_primitives::runtime::config::RuntimeConfig;
@@ -55,6 +55,38 @@ pub fn validate_transaction(
         .into());
     }
 
+    if let Some(signed_delegate_action) = signed_transaction.delegate_action() {
+        if !checked_feature!(
+            "protocol_feature_delegate_action",
+            DelegateAction,
+            current_protocol_version
+        ) {
+            return Err(InvalidTxError::ActionsValidation(
+                ActionsValidationError::UnsupportedProtocolFeature {
+                    protocol_feature: "DelegateAction".to_string(),
+                    version: current_protocol_version,
+                },
+            )
+            .into());
+        }
+        // The transaction is routed to the shard of the sender of the delegate
+        // action, see `SignedTransaction::routing_account_id`, and its receipt
+        // is sent back to the sender so that the actions are checked against
+        // the sender's access key.  The relayer, which signs and pays for the
+        // transaction, must therefore live on the same shard, which is checked
+        // by the callers that know the shard layout.
+        let sender_id = &signed_delegate_action.delegate_action.sender_id;
+        if sender_id != &transaction.receiver_id {
+            return Err(InvalidTxError::ActionsValidation(
+                ActionsValidationError::DelegateActionSenderDoesNotMatchTxReceiver {
+                    sender_id: sender_id.clone(),
+                    receiver_id: transaction.receiver_id.clone(),
+                },
+            )
+            .into());
+        }
+    }
+
     validate_actions(&config.wasm_config.limit_config, &transaction.actions)
         .map_err(InvalidTxError::ActionsValidation)?;
 
@@ -70,6 +102,48 @@ pub fn validate_transaction(
     .map_err(|_| InvalidTxError::CostOverflow.into())
 }
 
+/// Checks on top of given state whether a transaction which was valid when it
+/// entered the pool can still be included: its nonce mustn't have been used
+/// since and the signer must still be able to cover the attached deposit.
+///
+/// Only the signer's access key and account are read and nothing is computed,
+/// so stale transactions are dropped before paying for the full
+/// [`verify_and_charge_transaction`].  Passing the check doesn't mean the
+/// transaction is valid.
+pub fn check_transaction_freshness(
+    state_update: &TrieUpdate,
+    signed_transaction: &SignedTransaction,
+) -> Result<(), RuntimeError> {
+    let transaction = &signed_transaction.transaction;
+    let signer_id = &transaction.signer_id;
+    // Missing account or access key is reported by the full verification.
+    let access_key = match get_access_key(state_update, signer_id, &transaction.public_key)? {
+        Some(access_key) => access_key,
+        None => return Ok(()),
+    };
+    if transaction.nonce <= access_key.nonce {
+        return Err(InvalidTxError::InvalidNonce {
+            tx_nonce: transaction.nonce,
+            ak_nonce: access_key.nonce,
+        }
+        .into());
+    }
+    let signer = match get_account(state_update, signer_id)? {
+        Some(signer) => signer,
+        None => return Ok(()),
+    };
+    let deposit = total_deposit(&transaction.actions).map_err(|_| InvalidTxError::CostOverflow)?;
+    if signer.amount() < deposit {
+        return Err(InvalidTxError::NotEnoughBalance {
+            signer_id: signer_id.clone(),
+            balance: signer.amount(),
+            cost: deposit,
+        }
+        .into());
+    }
+    Ok(())
+}
+
 /// Verifies the signed transaction on top of given state, charges transaction fees
 /// and balances, and updates the state for the used account and access keys.
 pub fn verify_and_charge_transaction(
@@ -280,6 +354,7 @@ fn validate_data_receipt(
 ///
 /// - Checks limits if applicable.
 /// - Checks that the total number of actions doesn't exceed the limit.
+/// - Checks that a delegate action is the only action.
 /// - Validates each individual action.
 /// - Checks that the total prepaid gas doesn't exceed the limit.
 pub(crate) fn validate_actions(
@@ -293,6 +368,10 @@ pub(crate) fn validate_actions(
         });
     }
 
+    if actions.len() > 1 && actions.iter().any(|action| matches!(action, Action::Delegate(_))) {
+        return Err(ActionsValidationError::DelegateActionMustBeOnlyOne);
+    }
+
     let mut iter = actions.iter().peekable();
     while let Some(action) = iter.next() {
         if let Action::DeleteAccount(_) = action {
@@ -329,9 +408,27 @@ pub fn validate_action(
         Action::AddKey(a) => validate_add_key_action(limit_config, a),
         Action::DeleteKey(_) => Ok(()),
         Action::DeleteAccount(_) => Ok(()),
+        Action::Delegate(a) => validate_delegate_action(limit_config, a),
     }
 }
 
+/// Validates `SignedDelegateAction`.  Checks the signature of the sender and
+/// validates the actions it carries, which can't be delegate actions.
+fn validate_delegate_action(
+    limit_config: &VMLimitConfig,
+    signed_delegate_action: &SignedDelegateAction,
+) -> Result<(), ActionsValidationError> {
+    let actions = &signed_delegate_action.delegate_action.actions;
+    if actions.iter().any(|action| matches!(action, Action::Delegate(_))) {
+        return Err(ActionsValidationError::DelegateActionCantContainNestedOne);
+    }
+    validate_actions(limit_config, actions)?;
+    if !signed_delegate_action.verify() {
+        return Err(ActionsValidationError::DelegateActionInvalidSignature);
+    }
+    Ok(())
+}
+
 /// Validates `DeployContractAction`. Checks that the given contract size doesn't exceed the limit.
 fn validate_deploy_contract_action(
     limit_config: &VMLimitConfig,
@@ -464,7 +561,8 @@ mod tests {
     use near_primitives::hash::{hash, CryptoHash};
     use near_primitives::test_utils::account_new;
     use near_primitives::transaction::{
-        CreateAccountAction, DeleteAccountAction, DeleteKeyAction, StakeAction, TransferAction,
+        CreateAccountAction, DelegateAction, DeleteAccountAction, DeleteKeyAction, StakeAction,
+        TransferAction,
     };
     use near_primitives::types::{AccountId, Balance, MerkleHash, StateChangeCause};
     use near_primitives::version::PROTOCOL_VERSION;
@@ -756,6 +854,41 @@ mod tests {
         );
     }
 
+    #[test]
+    fn test_check_transaction_freshness() {
+        let (signer, state_update, _) = setup_common(
+            TESTING_INIT_BALANCE,
+            0,
+            Some(AccessKey { nonce: 2, permission: AccessKeyPermission::FullAccess }),
+        );
+        let send_money = |nonce, deposit| {
+            SignedTransaction::send_money(
+                nonce,
+                alice_account(),
+                bob_account(),
+                &*signer,
+                deposit,
+                CryptoHash::default(),
+            )
+        };
+
+        check_transaction_freshness(&state_update, &send_money(3, 100)).expect("fresh");
+        assert_eq!(
+            check_transaction_freshness(&state_update, &send_money(2, 100))
+                .expect_err("expected an error"),
+            RuntimeError::InvalidTxError(InvalidTxError::InvalidNonce { tx_nonce: 2, ak_nonce: 2 }),
+        );
+        assert_eq!(
+            check_transaction_freshness(&state_update, &send_money(3, TESTING_INIT_BALANCE + 1))
+                .expect_err("expected an error"),
+            RuntimeError::InvalidTxError(InvalidTxError::NotEnoughBalance {
+                signer_id: alice_account(),
+                balance: TESTING_INIT_BALANCE,
+                cost: TESTING_INIT_BALANCE + 1,
+            }),
+        );
+    }
+
     #[test]
     fn test_validate_transaction_invalid_balance_overflow() {
         let config = RuntimeConfig::test();
@@ -1388,6 +1521,100 @@ mod tests {
         );
     }
 
+    fn signed_delegate_action(signer: &InMemorySigner, actions: Vec<Action>) -> Action {
+        let delegate_action = DelegateAction {
+            sender_id: alice_account(),
+            receiver_id: bob_account(),
+            actions,
+            nonce: 1,
+            max_block_height: 100,
+            public_key: signer.public_key(),
+        };
+        let signature = signer.sign(delegate_action.get_hash().as_ref());
+        Action::Delegate(SignedDelegateAction { delegate_action, signature })
+    }
+
+    #[test]
+    fn test_validate_delegate_action() {
+        let limit_config = VMLimitConfig::test();
+        let signer =
+            InMemorySigner::from_seed(alice_account(), KeyType::ED25519, alice_account().as_ref());
+        let transfer = Action::Transfer(TransferAction { deposit: 10 });
+        let delegate = signed_delegate_action(&signer, vec![transfer.clone()]);
+        assert_eq!(validate_actions(&limit_config, &[delegate.clone()]), Ok(()));
+
+        assert_eq!(
+            validate_actions(&limit_config, &[delegate.clone(), transfer.clone()]),
+            Err(ActionsValidationError::DelegateActionMustBeOnlyOne),
+        );
+        let nested = signed_delegate_action(&signer, vec![delegate.clone()]);
+        assert_eq!(
+            validate_actions(&limit_config, &[nested]),
+            Err(ActionsValidationError::DelegateActionCantContainNestedOne),
+        );
+        let mut tampered = delegate;
+        if let Action::Delegate(signed_delegate_action) = &mut tampered {
+            signed_delegate_action.delegate_action.actions = vec![transfer.clone(), transfer];
+        }
+        assert_eq!(
+            validate_actions(&limit_config, &[tampered]),
+            Err(ActionsValidationError::DelegateActionInvalidSignature),
+        );
+    }
+
+    #[test]
+    fn test_validate_transaction_delegate_action() {
+        let config = RuntimeConfig::test();
+        let (signer, _, _) = setup_common(TESTING_INIT_BALANCE, 0, Some(AccessKey::full_access()));
+        let delegate =
+            signed_delegate_action(&signer, vec![Action::Transfer(TransferAction { deposit: 10 })]);
+        // Relayed transactions are routed by the sender of the delegate action
+        // rather than by the relayer signing the transaction.
+        let transaction = SignedTransaction::from_actions(
+            1,
+            bob_account(),
+            alice_account(),
+            &*signer,
+            vec![delegate.clone()],
+            CryptoHash::default(),
+        );
+        assert_eq!(transaction.routing_account_id(), &alice_account());
+        // The relayer must send the delegate action to its sender.
+        let transaction = SignedTransaction::from_actions(
+            1,
+            bob_account(),
+            bob_account(),
+            &*signer,
+            vec![delegate],
+            CryptoHash::default(),
+        );
+        let err = validate_transaction(&config, 100, &transaction, false, PROTOCOL_VERSION)
+            .expect_err("expected an error");
+        if cfg!(feature = "protocol_feature_delegate_action") {
+            assert_eq!(
+                err,
+                InvalidTxError::ActionsValidation(
+                    ActionsValidationError::DelegateActionSenderDoesNotMatchTxReceiver {
+                        sender_id: alice_account(),
+                        receiver_id: bob_account(),
+                    }
+                )
+                .into()
+            );
+        } else {
+            assert_eq!(
+                err,
+                InvalidTxError::ActionsValidation(
+                    ActionsValidationError::UnsupportedProtocolFeature {
+                        protocol_feature: "DelegateAction".to_string(),
+                        version: PROTOCOL_VERSION,
+                    }
+                )
+                .into()
+            );
+        }
+    }
+
     // Individual actions
 
     #[test]
@@ -2,8 +2,9 @@ use crate::concurrency::{Ctx, Once, RateLimiter, Scope, WeakMap};
 use log::info;
 use near_network::time;
 use near_network::types::{
-    AccountIdOrPeerTrackingShard, PartialEncodedChunkForwardMsg, PartialEncodedChunkRequestMsg,
-    PartialEncodedChunkResponseMsg, ReasonForBan, StateResponseInfo,
+    AccountIdOrPeerTrackingShard, BlockHeaderAnnouncement, ChunkInclusionFeedbackMsg,
+    PartialEncodedChunkAvailabilityMsg, PartialEncodedChunkForwardMsg,
+    PartialEncodedChunkRequestMsg, PartialEncodedChunkResponseMsg, ReasonForBan, StateResponseInfo,
 };
 use near_network::types::{
     FullPeerInfo, NetworkInfo, NetworkRequests, PeerManagerAdapter, PeerManagerMessageRequest,
@@ -15,6 +16,7 @@ use near_primitives::hash::CryptoHash;
 use near_primitives::network::{AnnounceAccount, PeerId};
 use near_primitives::sharding::ShardChunkHeader;
 use near_primitives::sharding::{ChunkHash, PartialEncodedChunk};
+use near_primitives::syncing::{EpochSyncDataResponse, EpochSyncProofsResponse};
 use near_primitives::time::Clock;
 use near_primitives::transaction::SignedTransaction;
 use near_primitives::types::{AccountId, EpochId, ShardId};
@@ -87,6 +89,7 @@ impl Network {
                     received_bytes_per_sec: 0,
                     known_producers: vec![],
                     tier1_accounts: vec![],
+                    bandwidth_by_type: vec![],
                 }),
                 info_futures: Default::default(),
             }),
@@ -263,6 +266,16 @@ impl near_network::client::Client for Network {
 
     async fn tx_status_response(&self, _tx_result: FinalExecutionOutcomeView) {}
 
+    async fn tx_status_subscribe(
+        &self,
+        _account_id: AccountId,
+        _tx_hash: CryptoHash,
+        _route_back: CryptoHash,
+        _subscriber: PeerId,
+    ) -> Option<Box<FinalExecutionOutcomeView>> {
+        None
+    }
+
     async fn state_request_header(
         &self,
         _shard_id: ShardId,
@@ -280,8 +293,40 @@ impl near_network::client::Client for Network {
         Ok(None)
     }
 
+    async fn state_request_sub_part(
+        &self,
+        _shard_id: ShardId,
+        _sync_hash: CryptoHash,
+        _part_id: u64,
+        _sub_part_id: u64,
+        _sub_part_size: u64,
+    ) -> Result<Option<StateResponseInfo>, ReasonForBan> {
+        Ok(None)
+    }
+
     async fn state_response(&self, _info: StateResponseInfo) {}
 
+    async fn epoch_sync_data_request(
+        &self,
+        _block_hash: CryptoHash,
+    ) -> Result<Option<EpochSyncDataResponse>, ReasonForBan> {
+        Ok(None)
+    }
+
+    async fn epoch_sync_proofs_request(
+        &self,
+        _block_hash: CryptoHash,
+    ) -> Result<Option<EpochSyncProofsResponse>, ReasonForBan> {
+        Ok(None)
+    }
+
+    async fn epoch_sync_proofs_response(
+        &self,
+        _response: EpochSyncProofsResponse,
+        _peer_id: PeerId,
+    ) {
+    }
+
     async fn block_approval(&self, _approval: Approval, _peer_id: PeerId) {}
 
     async fn transaction(&self, _transaction: SignedTransaction, _is_forwarded: bool) {}
@@ -305,6 +350,10 @@ impl near_network::client::Client for Network {
 
     async fn partial_encoded_chunk_forward(&self, _msg: PartialEncodedChunkForwardMsg) {}
 
+    async fn partial_encoded_chunk_availability(&self, _msg: PartialEncodedChunkAvailabilityMsg) {}
+
+    async fn chunk_inclusion_feedback(&self, _msg: ChunkInclusionFeedbackMsg) {}
+
     async fn block_request(&self, _hash: CryptoHash) -> Option<Box<Block>> {
         None
     }
@@ -317,6 +366,13 @@ impl near_network::client::Client for Network {
         self.blocks.get(&block.hash().clone()).map(|p| p.set(block));
     }
 
+    async fn block_header_announcement(
+        &self,
+        _announcement: BlockHeaderAnnouncement,
+        _peer_id: PeerId,
+    ) {
+    }
+
     async fn block_headers(
         &self,
         headers: Vec<BlockHeader>,
@@ -233,6 +233,7 @@ impl MockPeerManagerActor {
                 archival: false,
             },
             partial_edge_info: PartialEdgeInfo::default(),
+            state_sub_part_size_limit: 0,
         };
         let network_info = NetworkInfo {
             connected_peers: vec![(&peer).into()],
@@ -243,6 +244,7 @@ impl MockPeerManagerActor {
             received_bytes_per_sec: 0,
             known_producers: vec![],
             tier1_accounts: vec![],
+            bandwidth_by_type: vec![],
         };
         let incoming_requests = IncomingRequests::new(
             &network_config.incoming_requests,
@@ -411,7 +413,7 @@ impl Handler<WithSpanContext<PeerManagerMessageRequest>> for MockPeerManagerActo
                     });
                 }
                 NetworkRequests::PartialEncodedChunkResponse { .. } => {}
-                NetworkRequests::Block { .. } => {}
+                NetworkRequests::Block { .. } | NetworkRequests::BlockHeaderFirst { .. } => {}
                 NetworkRequests::StateRequestHeader { .. } => {
                     panic!(
                         "MockPeerManagerActor receives state sync request. \
@@ -528,9 +530,11 @@ mod test {
         // Create view client chain to retrieve and check chain data in the test.
         let chain = Chain::new_for_view_client(
             runtimes[0].clone(),
+            runtimes[0].store().clone(),
             &chain_genesis,
             env.clients[0].chain.doomslug_threshold_mode,
             true,
+            &near_o11y::metrics::MetricsRegistry::global(),
         )
         .unwrap();
         (ChainHistoryAccess { chain, target_height: 21 }, env)
@@ -251,7 +251,8 @@ pub fn setup_mock_node(
     let view_client = start_view_client(
         None,
         chain_genesis.clone(),
-        client_runtime,
+        client_runtime.clone(),
+        client_runtime.store().clone(),
         network_adapter.clone(),
         config.client_config.clone(),
         adv,
@@ -265,10 +266,12 @@ pub fn setup_mock_node(
     let network_config = network_config.clone();
 
     let chain = Chain::new_for_view_client(
-        mock_network_runtime,
+        mock_network_runtime.clone(),
+        mock_network_runtime.store().clone(),
         &chain_genesis,
         DoomslugThresholdMode::NoApprovals,
         !archival,
+        &near_o11y::metrics::MetricsRegistry::global(),
     )
     .unwrap();
     let chain_height = chain.head().unwrap().height;
@@ -238,6 +238,7 @@ fn load_snapshot(load_cmd: LoadCmd) {
         &chain_genesis,
         DoomslugThresholdMode::TwoThirds,
         !config.client_config.archive,
+        &near_o11y::metrics::MetricsRegistry::global(),
     )
     .unwrap();
 
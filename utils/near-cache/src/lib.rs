@@ -1,4 +1,9 @@
 mod cell;
 mod sync;
+mod token_buckets;
 
-pub use crate::{cell::CellLruCache, sync::SyncLruCache};
+pub use crate::{
+    cell::CellLruCache,
+    sync::SyncLruCache,
+    token_buckets::{TokenBucket, TokenBucketLimit, TokenBuckets},
+};
@@ -0,0 +1,114 @@
+use lru::LruCache;
+use std::hash::Hash;
+use std::time::{Duration, Instant};
+
+/// Rate at which a token bucket refills and the number of tokens it holds.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct TokenBucketLimit {
+    /// Number of tokens added to the bucket every second.
+    pub tokens_per_second: f64,
+    /// Number of tokens the bucket holds, i.e. how many tokens can be spent at
+    /// once after being idle.
+    pub burst: f64,
+}
+
+impl TokenBucketLimit {
+    /// Returns how long it takes to add `tokens` to a bucket.
+    pub fn time_to_refill(&self, tokens: f64) -> Duration {
+        if self.tokens_per_second <= 0. {
+            return Duration::MAX;
+        }
+        Duration::try_from_secs_f64(tokens / self.tokens_per_second).unwrap_or(Duration::MAX)
+    }
+}
+
+pub struct TokenBucket {
+    tokens: f64,
+    updated: Instant,
+}
+
+impl TokenBucket {
+    fn refill(&mut self, limit: TokenBucketLimit, now: Instant) {
+        let elapsed = now.saturating_duration_since(self.updated).as_secs_f64();
+        self.tokens = (self.tokens + elapsed * limit.tokens_per_second).min(limit.burst);
+        self.updated = now;
+    }
+
+    /// Number of tokens in the bucket.
+    pub fn tokens(&self) -> f64 {
+        self.tokens
+    }
+
+    /// Takes `tokens` from the bucket, which has to hold them.
+    pub fn take(&mut self, tokens: f64) {
+        debug_assert!(tokens <= self.tokens);
+        self.tokens -= tokens;
+    }
+}
+
+/// Token buckets of the clients of a service, used to limit the rate of their
+/// requests.  A client without a bucket is given a full one.  At most `cap`
+/// buckets are kept: the bucket of the client seen least recently is dropped
+/// to make room for a new one, so the memory and the time spent per request
+/// are bounded no matter how many clients there are.
+pub struct TokenBuckets<K> {
+    buckets: LruCache<K, TokenBucket>,
+}
+
+impl<K> TokenBuckets<K>
+where
+    K: Hash + Eq + Clone,
+{
+    /// Creates token buckets for at most `cap` clients.
+    pub fn new(cap: usize) -> Self {
+        Self { buckets: LruCache::new(cap) }
+    }
+
+    /// Returns the number of buckets kept.
+    pub fn len(&self) -> usize {
+        self.buckets.len()
+    }
+
+    /// Returns the bucket of `key`, refilled up to `now`.
+    pub fn get(&mut self, key: &K, limit: TokenBucketLimit, now: Instant) -> &mut TokenBucket {
+        if self.buckets.get(key).is_none() {
+            self.buckets.put(key.clone(), TokenBucket { tokens: limit.burst, updated: now });
+        }
+        let bucket = self.buckets.get_mut(key).unwrap();
+        bucket.refill(limit, now);
+        bucket
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const LIMIT: TokenBucketLimit = TokenBucketLimit { tokens_per_second: 10., burst: 20. };
+
+    #[test]
+    fn test_refill() {
+        let mut buckets = TokenBuckets::new(10);
+        let now = Instant::now();
+        let bucket = buckets.get(&0, LIMIT, now);
+        assert_eq!(bucket.tokens(), 20.);
+        bucket.take(15.);
+        assert_eq!(buckets.get(&0, LIMIT, now + Duration::from_millis(100)).tokens(), 6.);
+        assert_eq!(buckets.get(&0, LIMIT, now + Duration::from_secs(10)).tokens(), 20.);
+        assert_eq!(LIMIT.time_to_refill(5.), Duration::from_millis(500));
+    }
+
+    #[test]
+    fn test_cap() {
+        let mut buckets = TokenBuckets::new(2);
+        let now = Instant::now();
+        for key in 0..3 {
+            buckets.get(&key, LIMIT, now).take(20.);
+        }
+        assert_eq!(buckets.len(), 2);
+        // The least recently seen bucket was dropped, the others are kept.
+        assert_eq!(buckets.get(&2, LIMIT, now).tokens(), 0.);
+        assert_eq!(buckets.get(&0, LIMIT, now).tokens(), 20.);
+        assert_eq!(buckets.get(&2, LIMIT, now).tokens(), 0.);
+    }
+}